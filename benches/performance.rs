@@ -0,0 +1,237 @@
+//! Performance benchmarks for the audit trail, CAPA metrics and search.
+//!
+//! Run with `cargo bench --bench performance`. After the run, every benchmark's
+//! mean time is compared against its budget in [`BUDGETS`]. A budget that is
+//! exceeded fails the run, so a redesign meant to improve performance can be
+//! measured against a fixed bar. Budgets are per iteration and deliberately
+//! generous; tighten them when a redesign lands.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use chrono::{TimeZone, Utc};
+use criterion::{black_box, criterion_group, BatchSize, Criterion, Throughput};
+use rusqlite::params;
+use uuid::Uuid;
+
+use qmsrs::audit::AuditManager;
+use qmsrs::capa::{CapaPriority, CapaRecord, CapaService, CapaStatus, CapaType};
+use qmsrs::config::DatabaseConfig;
+use qmsrs::database::Database;
+use qmsrs::logging::{AuditLogEntry, AuditOutcome};
+use qmsrs::search::SearchService;
+
+/// Audit entries present before the query benchmarks run
+const AUDIT_ROWS: usize = 20_000;
+/// Entries written per batch in the batch insert benchmark
+const AUDIT_BATCH: usize = 1_000;
+/// Page size used by the audit trail API
+const AUDIT_PAGE: i64 = 50;
+/// CAPAs aggregated by the metrics benchmark
+const CAPA_COUNT: usize = 100_000;
+/// Documents searched by the search benchmark
+const DOCUMENT_COUNT: usize = 5_000;
+
+/// Maximum mean time per iteration for each benchmark, by criterion ID
+const BUDGETS: &[(&str, Duration)] = &[
+    ("audit/insert_single", Duration::from_millis(2)),
+    ("audit/insert_batch_1000", Duration::from_millis(250)),
+    ("audit/page_first", Duration::from_millis(10)),
+    ("audit/page_deep", Duration::from_millis(50)),
+    ("audit/page_by_user", Duration::from_millis(20)),
+    ("capa/metrics_100k", Duration::from_millis(100)),
+    ("documents/search_title", Duration::from_millis(50)),
+    ("documents/search_number", Duration::from_millis(50)),
+];
+
+fn database() -> Database {
+    Database::new(DatabaseConfig {
+        url: ":memory:".to_string(),
+        max_connections: 10,
+        wal_mode: false,
+        backup_interval_hours: 24,
+        backup_retention_days: 90,
+    })
+    .expect("in-memory database")
+}
+
+fn audit_entry(i: usize) -> AuditLogEntry {
+    AuditLogEntry::new(
+        format!("user{}", i % 25),
+        "document_updated".to_string(),
+        format!("document:{}", i % 500),
+        AuditOutcome::Success,
+        Uuid::new_v4().to_string(),
+    )
+}
+
+fn bench_audit(c: &mut Criterion) {
+    let db = database();
+    let audit = AuditManager::new(db.clone());
+    let entries: Vec<AuditLogEntry> = (0..AUDIT_ROWS).map(audit_entry).collect();
+    for chunk in entries.chunks(AUDIT_BATCH) {
+        audit.log_events(chunk).expect("seed audit trail");
+    }
+
+    let mut group = c.benchmark_group("audit");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("insert_single", |b| {
+        b.iter(|| audit.log_action("bench", "record_viewed", "capa:bench", "Success", None).unwrap())
+    });
+    group.throughput(Throughput::Elements(AUDIT_BATCH as u64));
+    group.bench_function("insert_batch_1000", |b| {
+        b.iter_batched(
+            || (0..AUDIT_BATCH).map(audit_entry).collect::<Vec<_>>(),
+            |batch| audit.log_events(&batch).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.throughput(Throughput::Elements(AUDIT_PAGE as u64));
+    group.bench_function("page_first", |b| {
+        b.iter(|| db.get_audit_entries(AUDIT_PAGE, 0, None).unwrap())
+    });
+    group.bench_function("page_deep", |b| {
+        b.iter(|| db.get_audit_entries(AUDIT_PAGE, AUDIT_ROWS as i64 - AUDIT_PAGE, None).unwrap())
+    });
+    group.bench_function("page_by_user", |b| {
+        b.iter(|| db.get_audit_entries(AUDIT_PAGE, AUDIT_PAGE, Some("user7")).unwrap())
+    });
+    group.finish();
+}
+
+fn capa_records(count: usize) -> Vec<CapaRecord> {
+    let created = Utc.with_ymd_and_hms(2025, 1, 1, 8, 0, 0).unwrap();
+    let statuses = [
+        CapaStatus::Identified,
+        CapaStatus::InvestigationInProgress,
+        CapaStatus::RootCauseAnalysis,
+        CapaStatus::CorrectiveActionInProgress,
+        CapaStatus::EffectivenessVerification,
+        CapaStatus::Closed,
+    ];
+    let priorities = [CapaPriority::Critical, CapaPriority::High, CapaPriority::Medium, CapaPriority::Low];
+    (0..count)
+        .map(|i| {
+            let status = statuses[i % statuses.len()].clone();
+            let due_date = created + chrono::Duration::days((i % 720) as i64);
+            CapaRecord {
+                id: Uuid::new_v4().to_string(),
+                capa_number: format!("CAPA-2025-{:06}", i),
+                title: format!("Benchmark CAPA {}", i),
+                description: "Generated for benchmarking".to_string(),
+                capa_type: CapaType::Corrective,
+                priority: priorities[i % priorities.len()].clone(),
+                closed_date: (status == CapaStatus::Closed).then_some(due_date),
+                status,
+                initiator_id: "bench".to_string(),
+                assigned_to: format!("engineer{}", i % 40),
+                created_at: created,
+                updated_at: created,
+                due_date: Some(due_date),
+                source_document: None,
+                related_risk_id: None,
+                investigation_summary: None,
+                root_cause: None,
+                corrective_actions: Vec::new(),
+                preventive_actions: Vec::new(),
+                effectiveness_verification: None,
+                metadata: HashMap::new(),
+            }
+        })
+        .collect()
+}
+
+fn bench_capa_metrics(c: &mut Criterion) {
+    let service = CapaService::new(AuditManager::new(database()));
+    let capas = capa_records(CAPA_COUNT);
+
+    let mut group = c.benchmark_group("capa");
+    group.throughput(Throughput::Elements(CAPA_COUNT as u64));
+    group.bench_function("metrics_100k", |b| b.iter(|| service.get_capa_metrics(black_box(&capas))));
+    group.finish();
+}
+
+fn bench_document_search(c: &mut Criterion) {
+    let db = database();
+    db.with_connection(|conn| {
+        conn.execute(
+            "INSERT INTO users (id, username, email, password_hash, salt, role)
+             VALUES ('bench', 'bench', 'bench@bench.invalid', '!', '!', 'QualityEngineer')",
+            [],
+        )?;
+        let mut stmt = conn.prepare(
+            "INSERT INTO documents (id, document_number, title, version, status, document_type, content_hash, created_by)
+             VALUES (?1, ?2, ?3, '1.0', 'Effective', 'SOP', ?4, 'bench')",
+        )?;
+        for i in 0..DOCUMENT_COUNT {
+            let topic = ["Sterilization", "Cleanroom Gowning", "Calibration", "Complaint Handling"][i % 4];
+            stmt.execute(params![
+                Uuid::new_v4().to_string(),
+                format!("SOP-{:05}", i),
+                format!("{} Procedure {}", topic, i),
+                format!("{:064x}", i)
+            ])?;
+        }
+        Ok(())
+    })
+    .expect("seed documents");
+    let search = SearchService::new(&db);
+
+    let mut group = c.benchmark_group("documents");
+    group.bench_function("search_title", |b| b.iter(|| search.search(black_box("calibration"), 20).unwrap()));
+    group.bench_function("search_number", |b| b.iter(|| search.search(black_box("SOP-04999"), 20).unwrap()));
+    group.finish();
+}
+
+/// Directory criterion writes its estimates to
+fn criterion_home() -> PathBuf {
+    if let Some(home) = std::env::var_os("CRITERION_HOME") {
+        return PathBuf::from(home);
+    }
+    std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("target"))
+        .join("criterion")
+}
+
+/// Mean time per iteration of benchmark `id`, if it was measured since `started`
+fn measured_mean(id: &str, started: SystemTime) -> Option<Duration> {
+    let path = criterion_home().join(id).join("new").join("estimates.json");
+    if std::fs::metadata(&path).and_then(|meta| meta.modified()).ok()? < started {
+        return None;
+    }
+    let estimates = std::fs::read_to_string(path).ok()?;
+    let estimates: serde_json::Value = serde_json::from_str(&estimates).ok()?;
+    let nanos = estimates["mean"]["point_estimate"].as_f64()?;
+    Some(Duration::from_nanos(nanos as u64))
+}
+
+/// Compare each benchmark's mean against its budget; benchmarks that were
+/// not measured in this run (filtered out, or `--test` mode) are skipped.
+fn check_budgets(started: SystemTime) -> Vec<String> {
+    let mut exceeded = Vec::new();
+    for (id, budget) in BUDGETS {
+        let Some(mean) = measured_mean(id, started) else { continue };
+        let verdict = if mean > *budget { "EXCEEDED" } else { "ok" };
+        println!("{:<28} mean {:>12?}  budget {:>10?}  {}", id, mean, budget, verdict);
+        if mean > *budget {
+            exceeded.push(format!("{} took {:?} (budget {:?})", id, mean, budget));
+        }
+    }
+    exceeded
+}
+
+criterion_group!(benches, bench_audit, bench_capa_metrics, bench_document_search);
+
+fn main() {
+    let started = SystemTime::now();
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+
+    let exceeded = check_budgets(started);
+    if !exceeded.is_empty() {
+        eprintln!("performance regression:\n  {}", exceeded.join("\n  "));
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,9 @@
+// Generates the gRPC server and client from the shipped proto definitions.
+// A vendored `protoc` is used so builds do not depend on a system install.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    if std::env::var_os("PROTOC").is_none() {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    }
+    tonic_build::compile_protos("proto/qms.proto")?;
+    Ok(())
+}
@@ -0,0 +1,306 @@
+//! # Access Audit - Read/Access Events (CFR Part 11)
+//!
+//! The regular audit trail records mutations. When `cfr_part_11_mode` is on,
+//! access to sensitive records is recorded too, under the distinct `access.*`
+//! action class so it can be filtered apart from changes. The
+//! `access_audit_scope` setting limits which categories are logged to keep
+//! the trail volume manageable.
+
+use std::io::{BufWriter, Write};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::config::ComplianceConfig;
+use crate::database::{AuditTrailEntry, Database};
+use crate::display_time::{audit_csv_row, audit_trail_csv, DisplayTimezone, AUDIT_CSV_HEADER};
+use crate::error::{QmsError, Result};
+use crate::post_market::{AdverseEvent, AdverseEventRepo};
+
+/// Rows fetched per query by streaming exports.
+pub const AUDIT_EXPORT_CHUNK_SIZE: usize = 1000;
+
+/// Prefix shared by every read/access audit action.
+pub const ACCESS_ACTION_PREFIX: &str = "access.";
+
+/// Category of sensitive record access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessCategory {
+    /// Viewing a complaint / adverse event record
+    Complaint,
+    /// Exporting audit trail data
+    AuditExport,
+    /// Downloading a controlled document
+    ControlledDocument,
+    /// Downloading a generated report
+    Report,
+}
+
+impl AccessCategory {
+    pub const ALL: [AccessCategory; 4] = [
+        AccessCategory::Complaint,
+        AccessCategory::AuditExport,
+        AccessCategory::ControlledDocument,
+        AccessCategory::Report,
+    ];
+
+    /// Audit action recorded for this category, e.g. `access.view_complaint`.
+    pub fn action(&self) -> &'static str {
+        match self {
+            AccessCategory::Complaint => "access.view_complaint",
+            AccessCategory::AuditExport => "access.export_audit_trail",
+            AccessCategory::ControlledDocument => "access.download_document",
+            AccessCategory::Report => "access.download_report",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccessCategory::Complaint => "complaint",
+            AccessCategory::AuditExport => "audit_export",
+            AccessCategory::ControlledDocument => "controlled_document",
+            AccessCategory::Report => "report",
+        }
+    }
+}
+
+/// Whether an audit action belongs to the read/access class.
+pub fn is_access_action(action: &str) -> bool {
+    action.starts_with(ACCESS_ACTION_PREFIX)
+}
+
+/// Records read/access events according to the compliance configuration.
+pub struct AccessAuditor {
+    database: Database,
+    audit: AuditManager,
+    enabled: bool,
+    scope: Vec<AccessCategory>,
+}
+
+impl AccessAuditor {
+    pub fn new(database: Database, config: &ComplianceConfig) -> Self {
+        Self {
+            audit: AuditManager::new(database.clone()),
+            database,
+            enabled: config.cfr_part_11_mode,
+            scope: config.access_audit_scope.clone(),
+        }
+    }
+
+    /// Whether access in `category` is currently audited.
+    pub fn is_audited(&self, category: AccessCategory) -> bool {
+        self.enabled && self.scope.contains(&category)
+    }
+
+    /// Record that `user_id` accessed `resource`. Returns `false` when the
+    /// category is out of scope and nothing was logged.
+    pub fn record(&self, user_id: &str, category: AccessCategory, resource: &str, detail: Option<&str>) -> Result<bool> {
+        if !self.is_audited(category) {
+            return Ok(false);
+        }
+        let metadata = serde_json::json!({
+            "action_class": "access",
+            "category": category.as_str(),
+            "detail": detail,
+        });
+        self.audit
+            .log_action(user_id, category.action(), resource, "Success", Some(metadata.to_string()))?;
+        Ok(true)
+    }
+
+    /// View a complaint (adverse event), recording the access.
+    pub fn view_complaint(&self, user_id: &str, id: Uuid) -> Result<AdverseEvent> {
+        let event = AdverseEventRepo::new(&self.database).get(id)?;
+        self.record(user_id, AccessCategory::Complaint, &format!("adverse_event:{}", id), None)?;
+        Ok(event)
+    }
+
+    /// Export a page of the audit trail, recording the export and its size.
+    pub fn export_audit_trail(
+        &self,
+        user_id: &str,
+        limit: i64,
+        offset: i64,
+        user_filter: Option<&str>,
+    ) -> Result<Vec<AuditTrailEntry>> {
+        let entries = self.database.get_audit_entries(limit, offset, user_filter)?;
+        let detail = format!(
+            "{} entries exported (offset {}, user filter {})",
+            entries.len(),
+            offset,
+            user_filter.unwrap_or("none")
+        );
+        self.record(user_id, AccessCategory::AuditExport, "audit_trail", Some(&detail))?;
+        Ok(entries)
+    }
+
+    /// Export a page of the audit trail as CSV with timestamps shown in `tz`.
+    pub fn export_audit_trail_csv(
+        &self,
+        user_id: &str,
+        limit: i64,
+        offset: i64,
+        user_filter: Option<&str>,
+        tz: DisplayTimezone,
+    ) -> Result<String> {
+        audit_trail_csv(&self.export_audit_trail(user_id, limit, offset, user_filter)?, tz)
+    }
+
+    /// Start a full audit trail CSV export without loading it into memory.
+    /// Returns the number of entries to be exported and the lines (header
+    /// first); the export itself is recorded before any line is produced.
+    pub fn stream_audit_trail_csv(
+        &self,
+        user_id: &str,
+        user_filter: Option<&str>,
+        tz: DisplayTimezone,
+    ) -> Result<(u64, impl Iterator<Item = Result<String>>)> {
+        let detail = format!("streaming export (user filter {})", user_filter.unwrap_or("none"));
+        self.record(user_id, AccessCategory::AuditExport, "audit_trail", Some(&detail))?;
+        let total = self.database.count_audit_entries(user_filter)?;
+        let rows = self
+            .database
+            .stream_audit_entries(user_filter, AUDIT_EXPORT_CHUNK_SIZE)
+            .map(move |entry| entry.and_then(|entry| audit_csv_row(&entry, tz)));
+        Ok((total, std::iter::once(Ok(AUDIT_CSV_HEADER.to_string())).chain(rows)))
+    }
+
+    /// Write a full audit trail CSV export to `out`, calling
+    /// `progress(exported, total)` after every chunk. Returns the number of
+    /// entries written.
+    pub fn write_audit_trail_csv<W: Write>(
+        &self,
+        user_id: &str,
+        user_filter: Option<&str>,
+        tz: DisplayTimezone,
+        out: W,
+        progress: &mut dyn FnMut(u64, u64),
+    ) -> Result<u64> {
+        let io_error = |e: std::io::Error| QmsError::FileSystem {
+            path: "audit export".to_string(),
+            message: e.to_string(),
+        };
+        let mut out = BufWriter::new(out);
+        let (total, lines) = self.stream_audit_trail_csv(user_id, user_filter, tz)?;
+        let mut exported = 0u64;
+        for (index, line) in lines.enumerate() {
+            out.write_all(line?.as_bytes()).map_err(io_error)?;
+            if index > 0 {
+                exported += 1;
+                if exported.is_multiple_of(AUDIT_EXPORT_CHUNK_SIZE as u64) {
+                    progress(exported, total);
+                }
+            }
+        }
+        out.flush().map_err(io_error)?;
+        progress(exported, total);
+        Ok(exported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::post_market::Severity;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_view_complaint_is_audited_in_part_11_mode() {
+        let db = test_db();
+        let event = AdverseEvent::new("tester", "device overheated", Severity::Major);
+        AdverseEventRepo::new(&db).insert(&event).unwrap();
+
+        let auditor = AccessAuditor::new(db.clone(), &ComplianceConfig::default());
+        let viewed = auditor.view_complaint("reviewer", event.id).unwrap();
+        assert_eq!(viewed.id, event.id);
+
+        let entries = db.get_audit_entries_for_resource(&format!("adverse_event:{}", event.id)).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].action, "access.view_complaint");
+        assert_eq!(entries[0].user_id, "reviewer");
+        assert!(is_access_action(&entries[0].action));
+        assert!(entries[0].metadata.as_deref().unwrap_or_default().contains("\"action_class\":\"access\""));
+    }
+
+    #[test]
+    fn test_export_records_entry_count() {
+        let db = test_db();
+        let audit = AuditManager::new(db.clone());
+        audit.log_action("qa", "capa_created", "capa:1", "Success", None).unwrap();
+        audit.log_action("qa", "capa_closed", "capa:1", "Success", None).unwrap();
+
+        let auditor = AccessAuditor::new(db.clone(), &ComplianceConfig::default());
+        let exported = auditor.export_audit_trail("auditor", 100, 0, None).unwrap();
+        assert_eq!(exported.len(), 2);
+
+        let log = db.get_audit_entries_for_resource("audit_trail").unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].action, "access.export_audit_trail");
+        assert!(log[0].metadata.as_deref().unwrap_or_default().contains("2 entries exported"));
+    }
+
+    #[test]
+    fn test_csv_export_is_audited() {
+        let db = test_db();
+        AuditManager::new(db.clone()).log_action("qa", "capa_created", "capa:1", "Success", None).unwrap();
+
+        let auditor = AccessAuditor::new(db.clone(), &ComplianceConfig::default());
+        let csv = auditor
+            .export_audit_trail_csv("auditor", 100, 0, Some("qa"), "-05:00".parse().unwrap())
+            .unwrap();
+        assert_eq!(csv.lines().count(), 2);
+        assert!(csv.lines().nth(1).unwrap().contains("-05:00,"));
+        assert_eq!(db.get_audit_entries_for_resource("audit_trail").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_streamed_csv_export_reports_progress() {
+        let db = test_db();
+        let audit = AuditManager::new(db.clone());
+        for n in 0..3 {
+            audit.log_action("qa", "capa_created", &format!("capa:{}", n), "Success", None).unwrap();
+        }
+
+        let auditor = AccessAuditor::new(db.clone(), &ComplianceConfig::default());
+        let mut out = Vec::new();
+        let mut reported = Vec::new();
+        let written = auditor
+            .write_audit_trail_csv("auditor", Some("qa"), DisplayTimezone::default(), &mut out, &mut |done, total| {
+                reported.push((done, total))
+            })
+            .unwrap();
+        assert_eq!(written, 3);
+        assert_eq!(reported.last(), Some(&(3, 3)));
+        let csv = String::from_utf8(out).unwrap();
+        assert!(csv.starts_with(AUDIT_CSV_HEADER));
+        assert_eq!(csv.lines().count(), 4);
+        assert_eq!(db.get_audit_entries_for_resource("audit_trail").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_scope_and_mode_limit_logging() {
+        let db = test_db();
+        let mut config = ComplianceConfig { access_audit_scope: vec![AccessCategory::AuditExport], ..Default::default() };
+        let auditor = AccessAuditor::new(db.clone(), &config);
+        assert!(!auditor.record("u", AccessCategory::Complaint, "adverse_event:x", None).unwrap());
+        assert!(auditor.record("u", AccessCategory::AuditExport, "audit_trail", None).unwrap());
+
+        config.cfr_part_11_mode = false;
+        let auditor = AccessAuditor::new(db.clone(), &config);
+        assert!(!auditor.record("u", AccessCategory::AuditExport, "audit_trail", None).unwrap());
+        assert_eq!(db.get_audit_entries(100, 0, Some("u")).unwrap().len(), 1);
+    }
+}
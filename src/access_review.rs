@@ -0,0 +1,573 @@
+//! # Access Review - Periodic User Access Review Campaigns
+//!
+//! 21 CFR 11.10(d) and ISO 13485 4.2.5 expect system access to be limited to
+//! authorised individuals, which in practice means re-confirming who holds
+//! which role at a fixed interval. Each quarter the scheduler opens a
+//! campaign listing every active user's organisation role and site roles.
+//! A manager attests or revokes each item; nobody decides on their own
+//! access. A revocation takes effect immediately: revoking a site role
+//! removes it, revoking the organisation role deactivates the account, ends
+//! its sessions and revokes the rest of the user's items. Campaigns and
+//! items are never deleted and are kept as the review evidence.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::authorization::UserRole;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+
+/// Days a campaign stays open for decisions before it is overdue.
+pub const REVIEW_WINDOW_DAYS: i64 = 30;
+
+/// Quarter label of `date`, e.g. "2026-Q4".
+pub fn quarter_of(date: NaiveDate) -> String {
+    format!("{}-Q{}", date.year(), date.month0() / 3 + 1)
+}
+
+/// Manager's decision on one access item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccessDecision {
+    /// The access is still needed
+    Attest,
+    /// The access is removed at once; a comment is required
+    Revoke,
+}
+
+impl AccessDecision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccessDecision::Attest => "attest",
+            AccessDecision::Revoke => "revoke",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [AccessDecision::Attest, AccessDecision::Revoke].into_iter().find(|item| item.as_str() == value)
+    }
+}
+
+/// One quarterly access review campaign.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessCampaign {
+    pub id: Uuid,
+    /// Quarter under review, e.g. "2026-Q4"
+    pub quarter: String,
+    pub opened_by: String,
+    pub opened_at: DateTime<Utc>,
+    pub due_date: NaiveDate,
+    pub closed_by: Option<String>,
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+impl AccessCampaign {
+    pub fn is_closed(&self) -> bool {
+        self.closed_at.is_some()
+    }
+}
+
+/// A role held by a user when the campaign opened.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessItem {
+    pub id: Uuid,
+    pub campaign_id: Uuid,
+    pub user_id: String,
+    pub username: String,
+    /// `None` for the organisation-wide role in `users.role`
+    pub site_id: Option<String>,
+    pub role: String,
+    pub decision: Option<AccessDecision>,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub comment: Option<String>,
+}
+
+impl AccessItem {
+    pub fn is_decided(&self) -> bool {
+        self.decision.is_some()
+    }
+
+    /// "QualityEngineer" or "Viewer @ plant-a".
+    pub fn scope(&self) -> String {
+        match &self.site_id {
+            Some(site) => format!("{} @ {}", self.role, site),
+            None => self.role.clone(),
+        }
+    }
+}
+
+/// Persistence and workflow of access review campaigns.
+pub struct AccessReviewRepo<'a> {
+    db: &'a Database,
+    audit: AuditManager,
+}
+
+impl<'a> AccessReviewRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, audit: AuditManager::new(db.clone()) }
+    }
+
+    /// Open the campaign of the quarter containing `date`, listing every
+    /// active user's organisation and site roles.
+    pub fn open_campaign(&self, date: NaiveDate, opened_by: &str) -> Result<AccessCampaign> {
+        let campaign = AccessCampaign {
+            id: Uuid::new_v4(),
+            quarter: quarter_of(date),
+            opened_by: opened_by.to_string(),
+            opened_at: Utc::now(),
+            due_date: date + Duration::days(REVIEW_WINDOW_DAYS),
+            closed_by: None,
+            closed_at: None,
+        };
+        self.db.unit_of_work(|uow| {
+            let conn = uow.connection();
+            let existing: Option<String> = conn
+                .query_row(
+                    "SELECT id FROM access_review_campaigns WHERE quarter = ?1",
+                    params![campaign.quarter],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(existing) = existing {
+                return Err(QmsError::Validation {
+                    field: "quarter".to_string(),
+                    message: format!("{} is already covered by campaign {}", campaign.quarter, existing),
+                });
+            }
+            conn.execute(
+                "INSERT INTO access_review_campaigns (id, quarter, opened_by, opened_at, due_date)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    campaign.id.to_string(),
+                    campaign.quarter,
+                    opened_by,
+                    campaign.opened_at.to_rfc3339(),
+                    campaign.due_date.to_string(),
+                ],
+            )?;
+            let mut stmt = conn.prepare(
+                "SELECT id, username, NULL, role FROM users WHERE is_active = 1
+                 UNION ALL
+                 SELECT u.id, u.username, r.site_id, r.role
+                 FROM site_roles r JOIN users u ON u.id = r.user_id WHERE u.is_active = 1",
+            )?;
+            let access = stmt
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, String>(3)?,
+                    ))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            for (user_id, username, site_id, role) in &access {
+                conn.execute(
+                    "INSERT INTO access_review_items (id, campaign_id, user_id, username, site_id, role)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![Uuid::new_v4().to_string(), campaign.id.to_string(), user_id, username, site_id, role],
+                )?;
+            }
+            let items = access.len();
+            self.audit.log_action_in(
+                uow,
+                opened_by,
+                "access_review_campaign_opened",
+                &resource(campaign.id),
+                "Success",
+                Some(format!("{}: {} access item(s), due {}", campaign.quarter, items, campaign.due_date)),
+            )
+        })?;
+        Ok(campaign)
+    }
+
+    /// Open the campaign of the quarter containing `date` unless it exists.
+    pub fn open_due(&self, date: NaiveDate, opened_by: &str) -> Result<Option<AccessCampaign>> {
+        if self.find_by_quarter(&quarter_of(date))?.is_some() {
+            return Ok(None);
+        }
+        self.open_campaign(date, opened_by).map(Some)
+    }
+
+    pub fn get(&self, campaign_id: Uuid) -> Result<AccessCampaign> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM access_review_campaigns WHERE id = ?1", COLUMNS),
+                        params![campaign_id.to_string()],
+                        row_to_campaign,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound {
+                resource: "access_review_campaign".to_string(),
+                id: campaign_id.to_string(),
+            })
+    }
+
+    pub fn find_by_quarter(&self, quarter: &str) -> Result<Option<AccessCampaign>> {
+        self.db.with_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    &format!("SELECT {} FROM access_review_campaigns WHERE quarter = ?1", COLUMNS),
+                    params![quarter],
+                    row_to_campaign,
+                )
+                .optional()?)
+        })
+    }
+
+    /// All campaigns, most recent first.
+    pub fn list(&self) -> Result<Vec<AccessCampaign>> {
+        self.db.with_connection(|conn| {
+            let mut stmt =
+                conn.prepare(&format!("SELECT {} FROM access_review_campaigns ORDER BY opened_at DESC", COLUMNS))?;
+            let campaigns = stmt.query_map([], row_to_campaign)?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(campaigns)
+        })
+    }
+
+    /// Items of a campaign by username, organisation role first.
+    pub fn items(&self, campaign_id: Uuid) -> Result<Vec<AccessItem>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM access_review_items WHERE campaign_id = ?1
+                 ORDER BY username, site_id IS NOT NULL, site_id, role",
+                ITEM_COLUMNS
+            ))?;
+            let items =
+                stmt.query_map(params![campaign_id.to_string()], row_to_item)?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(items)
+        })
+    }
+
+    /// Record a manager's decision on an item; a revocation is carried out
+    /// in the same transaction. Decisions are final.
+    pub fn decide(
+        &self,
+        item_id: Uuid,
+        decision: AccessDecision,
+        comment: Option<&str>,
+        reviewer: &str,
+    ) -> Result<AccessItem> {
+        let mut item = self.get_item(item_id)?;
+        let campaign = self.get(item.campaign_id)?;
+        if campaign.is_closed() {
+            return Err(QmsError::Validation {
+                field: "campaign_id".to_string(),
+                message: format!("Access review campaign {} is closed", campaign.quarter),
+            });
+        }
+        if item.is_decided() {
+            return Err(QmsError::Validation {
+                field: "item_id".to_string(),
+                message: format!("Access item {} was already decided", item_id),
+            });
+        }
+        self.authorize_reviewer(reviewer)?;
+        if item.user_id == reviewer {
+            return Err(QmsError::Validation {
+                field: "reviewer".to_string(),
+                message: "Reviewers cannot decide on their own access".to_string(),
+            });
+        }
+        let comment = comment.map(str::trim).filter(|c| !c.is_empty());
+        if decision == AccessDecision::Revoke && comment.is_none() {
+            return Err(QmsError::Validation {
+                field: "comment".to_string(),
+                message: "Revocations need a comment".to_string(),
+            });
+        }
+        let now = Utc::now();
+        self.db.unit_of_work(|uow| {
+            let conn = uow.connection();
+            conn.execute(
+                "UPDATE access_review_items SET decision = ?2, decided_by = ?3, decided_at = ?4, comment = ?5
+                 WHERE id = ?1",
+                params![item_id.to_string(), decision.as_str(), reviewer, now.to_rfc3339(), comment],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                reviewer,
+                "access_review_item_decided",
+                &resource(campaign.id),
+                "Success",
+                Some(match comment {
+                    Some(comment) => format!("{} {}: {} ({})", item.username, item.scope(), decision.as_str(), comment),
+                    None => format!("{} {}: {}", item.username, item.scope(), decision.as_str()),
+                }),
+            )?;
+            if decision == AccessDecision::Attest {
+                return Ok(());
+            }
+            let user_resource = format!("user:{}", item.user_id);
+            match &item.site_id {
+                Some(site_id) => {
+                    conn.execute(
+                        "DELETE FROM site_roles WHERE user_id = ?1 AND site_id = ?2 AND role = ?3",
+                        params![item.user_id, site_id, item.role],
+                    )?;
+                    self.audit.log_action_in(
+                        uow,
+                        reviewer,
+                        "access_revoked",
+                        &user_resource,
+                        "Success",
+                        Some(format!("{} revoked in access review {}", item.scope(), campaign.quarter)),
+                    )
+                }
+                None => {
+                    conn.execute(
+                        "UPDATE users SET is_active = 0, updated_at = ?2 WHERE id = ?1",
+                        params![item.user_id, now.to_rfc3339()],
+                    )?;
+                    conn.execute("UPDATE sessions SET is_active = 0 WHERE user_id = ?1", params![item.user_id])?;
+                    conn.execute("DELETE FROM site_roles WHERE user_id = ?1", params![item.user_id])?;
+                    let cascaded = conn.execute(
+                        "UPDATE access_review_items SET decision = 'revoke', decided_by = ?3, decided_at = ?4,
+                                comment = 'Account deactivated'
+                         WHERE campaign_id = ?1 AND user_id = ?2 AND decision IS NULL",
+                        params![campaign.id.to_string(), item.user_id, reviewer, now.to_rfc3339()],
+                    )?;
+                    self.audit.log_action_in(
+                        uow,
+                        reviewer,
+                        "user_deactivated",
+                        &user_resource,
+                        "Success",
+                        Some(format!(
+                            "Account deactivated in access review {}; {} site role item(s) revoked with it",
+                            campaign.quarter, cascaded
+                        )),
+                    )
+                }
+            }
+        })?;
+        item.decision = Some(decision);
+        item.decided_by = Some(reviewer.to_string());
+        item.decided_at = Some(now);
+        item.comment = comment.map(str::to_string);
+        Ok(item)
+    }
+
+    /// Close a campaign once every item is decided.
+    pub fn close(&self, campaign_id: Uuid, reviewer: &str) -> Result<AccessCampaign> {
+        let mut campaign = self.get(campaign_id)?;
+        if campaign.is_closed() {
+            return Err(QmsError::Validation {
+                field: "campaign_id".to_string(),
+                message: format!("Access review campaign {} is already closed", campaign.quarter),
+            });
+        }
+        self.authorize_reviewer(reviewer)?;
+        let items = self.items(campaign_id)?;
+        let pending = items.iter().filter(|item| !item.is_decided()).count();
+        if pending > 0 {
+            return Err(QmsError::Validation {
+                field: "decision".to_string(),
+                message: format!("{} access item(s) are not yet decided", pending),
+            });
+        }
+        let revoked = items.iter().filter(|item| item.decision == Some(AccessDecision::Revoke)).count();
+        let now = Utc::now();
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE access_review_campaigns SET closed_by = ?2, closed_at = ?3 WHERE id = ?1",
+                params![campaign_id.to_string(), reviewer, now.to_rfc3339()],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                reviewer,
+                "access_review_campaign_closed",
+                &resource(campaign_id),
+                "Success",
+                Some(format!("{}: {} attested, {} revoked", campaign.quarter, items.len() - revoked, revoked)),
+            )
+        })?;
+        campaign.closed_by = Some(reviewer.to_string());
+        campaign.closed_at = Some(now);
+        Ok(campaign)
+    }
+
+    fn get_item(&self, item_id: Uuid) -> Result<AccessItem> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM access_review_items WHERE id = ?1", ITEM_COLUMNS),
+                        params![item_id.to_string()],
+                        row_to_item,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "access_review_item".to_string(), id: item_id.to_string() })
+    }
+
+    /// Only active quality managers and administrators review access.
+    fn authorize_reviewer(&self, reviewer: &str) -> Result<()> {
+        match UserRole::of_user(self.db, reviewer)? {
+            Some(role) if role >= UserRole::QualityManager => Ok(()),
+            _ => Err(QmsError::Security { message: format!("User {} may not review access", reviewer) }),
+        }
+    }
+}
+
+fn resource(campaign_id: Uuid) -> String {
+    format!("access_review:{}", campaign_id)
+}
+
+const COLUMNS: &str = "id, quarter, opened_by, opened_at, due_date, closed_by, closed_at";
+
+const ITEM_COLUMNS: &str =
+    "id, campaign_id, user_id, username, site_id, role, decision, decided_by, decided_at, comment";
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn parse_time(row: &Row, index: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| conversion_error(index, e.to_string()))
+        })
+        .transpose()
+}
+
+fn parse_uuid(row: &Row, index: usize) -> rusqlite::Result<Uuid> {
+    let value: String = row.get(index)?;
+    Uuid::parse_str(&value).map_err(|e| conversion_error(index, e.to_string()))
+}
+
+fn row_to_campaign(row: &Row) -> rusqlite::Result<AccessCampaign> {
+    let due_date: String = row.get(4)?;
+    Ok(AccessCampaign {
+        id: parse_uuid(row, 0)?,
+        quarter: row.get(1)?,
+        opened_by: row.get(2)?,
+        opened_at: parse_time(row, 3)?.ok_or_else(|| conversion_error(3, "opened_at is missing".to_string()))?,
+        due_date: NaiveDate::parse_from_str(&due_date, "%Y-%m-%d").map_err(|e| conversion_error(4, e.to_string()))?,
+        closed_by: row.get(5)?,
+        closed_at: parse_time(row, 6)?,
+    })
+}
+
+fn row_to_item(row: &Row) -> rusqlite::Result<AccessItem> {
+    let decision: Option<String> = row.get(6)?;
+    Ok(AccessItem {
+        id: parse_uuid(row, 0)?,
+        campaign_id: parse_uuid(row, 1)?,
+        user_id: row.get(2)?,
+        username: row.get(3)?,
+        site_id: row.get(4)?,
+        role: row.get(5)?,
+        decision: decision
+            .map(|d| AccessDecision::parse(&d).ok_or_else(|| conversion_error(6, format!("unknown decision {}", d))))
+            .transpose()?,
+        decided_by: row.get(7)?,
+        decided_at: parse_time(row, 8)?,
+        comment: row.get(9)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+
+    #[test]
+    fn test_campaign_workflow() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["manager", "alice", "bob", "former"]);
+        db.with_connection(|conn| {
+            conn.execute("UPDATE users SET role = 'QualityManager' WHERE id = 'manager'", [])?;
+            conn.execute("UPDATE users SET is_active = 0 WHERE id = 'former'", [])?;
+            for (user, site, role) in [("alice", "default", "Viewer"), ("bob", "default", "QualityEngineer")] {
+                conn.execute(
+                    "INSERT INTO site_roles (user_id, site_id, role, granted_by, granted_at)
+                     VALUES (?1, ?2, ?3, 'admin', '2026-01-01T00:00:00Z')",
+                    params![user, site, role],
+                )?;
+            }
+            conn.execute(
+                "INSERT INTO sessions (id, user_id, expires_at) VALUES ('s1', 'bob', '2099-01-01T00:00:00Z')",
+                [],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        let repo = AccessReviewRepo::new(&db);
+        let date = NaiveDate::from_ymd_opt(2026, 10, 1).unwrap();
+        assert_eq!(quarter_of(date), "2026-Q4");
+
+        let campaign = repo.open_due(date, "scheduler").unwrap().unwrap();
+        assert_eq!(campaign.due_date, NaiveDate::from_ymd_opt(2026, 10, 31).unwrap());
+        assert!(repo.open_due(NaiveDate::from_ymd_opt(2026, 12, 31).unwrap(), "scheduler").unwrap().is_none());
+        let items = repo.items(campaign.id).unwrap();
+        let scopes: Vec<(&str, String)> = items.iter().map(|i| (i.username.as_str(), i.scope())).collect();
+        assert_eq!(
+            scopes,
+            vec![
+                ("alice", "QualityEngineer".to_string()),
+                ("alice", "Viewer @ default".to_string()),
+                ("bob", "QualityEngineer".to_string()),
+                ("bob", "QualityEngineer @ default".to_string()),
+                ("manager", "QualityManager".to_string()),
+            ],
+            "deactivated users are not reviewed"
+        );
+
+        let (alice_site, bob_org, manager_org) = (items[1].id, items[2].id, items[4].id);
+        assert!(repo.decide(alice_site, AccessDecision::Attest, None, "alice").is_err(), "not a manager");
+        assert!(repo.decide(manager_org, AccessDecision::Attest, None, "manager").is_err(), "own access");
+        assert!(repo.decide(bob_org, AccessDecision::Revoke, None, "manager").is_err(), "comment required");
+
+        repo.decide(alice_site, AccessDecision::Revoke, Some("Left the plant"), "manager").unwrap();
+        assert!(repo.decide(alice_site, AccessDecision::Attest, None, "manager").is_err(), "decisions are final");
+        repo.decide(bob_org, AccessDecision::Revoke, Some("Contract ended"), "manager").unwrap();
+        assert_eq!(UserRole::of_user(&db, "bob").unwrap(), None, "account deactivated");
+        let (site_roles, sessions): (i64, i64) = db
+            .with_connection(|conn| {
+                Ok(conn.query_row(
+                    "SELECT (SELECT COUNT(*) FROM site_roles),
+                            (SELECT COUNT(*) FROM sessions WHERE is_active = 1)",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )?)
+            })
+            .unwrap();
+        assert_eq!((site_roles, sessions), (0, 0));
+        assert_eq!(repo.items(campaign.id).unwrap()[3].decision, Some(AccessDecision::Revoke), "cascaded");
+
+        assert!(repo.close(campaign.id, "manager").is_err(), "items pending");
+        repo.decide(items[0].id, AccessDecision::Attest, None, "manager").unwrap();
+        db.with_connection(|conn| {
+            conn.execute("UPDATE users SET role = 'Administrator' WHERE id = 'alice'", [])?;
+            Ok(())
+        })
+        .unwrap();
+        repo.decide(manager_org, AccessDecision::Attest, None, "alice").unwrap();
+        let closed = repo.close(campaign.id, "manager").unwrap();
+        assert!(closed.is_closed());
+        assert_eq!(repo.list().unwrap(), vec![closed]);
+
+        let evidence = db.get_audit_entries_for_resource(&format!("access_review:{}", campaign.id)).unwrap();
+        assert_eq!(evidence.first().unwrap().action, "access_review_campaign_opened");
+        assert_eq!(evidence.last().unwrap().action, "access_review_campaign_closed");
+        let bob = db.get_audit_entries_for_resource("user:bob").unwrap();
+        assert!(bob.iter().any(|e| e.action == "user_deactivated"));
+    }
+}
@@ -0,0 +1,304 @@
+//! # Analytics Export - Fact Snapshots for BI Tools
+//!
+//! BI tools should not query the live quality database. `qmsrs analytics
+//! export <dir>` (and the `analytics_export` background job) writes a
+//! read-only snapshot of denormalized fact tables instead: one CSV per fact
+//! table, read in a single transaction so the files are consistent with each
+//! other, plus `manifest.json` (row counts, columns, checksums) and a
+//! `MANIFEST.sha256` listing that `sha256sum -c` can verify. Each snapshot
+//! goes to its own timestamped directory and is recorded in the audit trail.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::keystore::sha256_hex;
+use crate::vigilance_export::csv_field;
+
+/// Machine-readable description of the snapshot.
+pub const MANIFEST_JSON: &str = "manifest.json";
+/// `<sha256>  <file>` for every other file of the snapshot.
+pub const MANIFEST_SHA256: &str = "MANIFEST.sha256";
+
+/// A denormalized fact table and the query producing it. `?1` is the
+/// snapshot date, used for ages and overdue flags.
+struct FactTable {
+    file: &'static str,
+    columns: &'static [&'static str],
+    sql: &'static str,
+}
+
+const FACT_TABLES: [FactTable; 3] = [
+    FactTable {
+        file: "capa_facts.csv",
+        columns: &[
+            "capa_id",
+            "site_id",
+            "capa_type",
+            "priority",
+            "status",
+            "opened_on",
+            "due_on",
+            "closed_on",
+            "days_open",
+            "is_overdue",
+            "action_count",
+            "actions_completed",
+        ],
+        sql: "SELECT c.id, c.site_id, c.capa_type, c.priority, c.status,
+                     substr(c.created_at, 1, 10), substr(c.due_date, 1, 10), substr(c.closed_date, 1, 10),
+                     CAST(julianday(COALESCE(substr(c.closed_date, 1, 10), ?1))
+                          - julianday(substr(c.created_at, 1, 10)) AS INTEGER),
+                     COALESCE(c.closed_date IS NULL AND substr(c.due_date, 1, 10) < ?1, 0),
+                     (SELECT COUNT(*) FROM capa_actions a WHERE a.capa_id = c.id AND a.deleted_at IS NULL),
+                     (SELECT COUNT(*) FROM capa_actions a
+                      WHERE a.capa_id = c.id AND a.deleted_at IS NULL AND a.status IN ('Completed', 'Verified'))
+              FROM capa_records c
+              WHERE c.deleted_at IS NULL
+              ORDER BY c.created_at, c.id",
+    },
+    FactTable {
+        file: "complaint_facts.csv",
+        columns: &[
+            "complaint_id",
+            "reference",
+            "reported_on",
+            "reported_month",
+            "triaged_on",
+            "routed_to",
+            "routed_record_id",
+            "days_to_triage",
+        ],
+        sql: "SELECT id, COALESCE(record_number, id), substr(reported_at, 1, 10), substr(reported_at, 1, 7),
+                     substr(triaged_at, 1, 10), routed_to, routed_record_id,
+                     CAST(julianday(COALESCE(substr(triaged_at, 1, 10), ?1))
+                          - julianday(substr(reported_at, 1, 10)) AS INTEGER)
+              FROM quality_intake
+              WHERE source = 'complaint'
+              ORDER BY reported_at, id",
+    },
+    FactTable {
+        file: "training_facts.csv",
+        columns: &[
+            "training_id",
+            "site_id",
+            "employee_id",
+            "training_item",
+            "mandatory",
+            "status",
+            "due_on",
+            "completed_on",
+            "is_overdue",
+            "days_late",
+        ],
+        sql: "SELECT id, site_id, employee_id, training_item, mandatory, status, due_date, completion_date,
+                     status != 'Completed' AND due_date < ?1,
+                     CASE WHEN completion_date IS NOT NULL
+                          THEN MAX(0, CAST(julianday(substr(completion_date, 1, 10)) - julianday(due_date) AS INTEGER))
+                     END
+              FROM training_records
+              WHERE deleted_at IS NULL
+              ORDER BY due_date, id",
+    },
+];
+
+/// One file of a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FactFile {
+    pub file: String,
+    pub columns: Vec<String>,
+    pub rows: usize,
+    pub sha256: String,
+}
+
+/// Contents of `manifest.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub generated_at: DateTime<Utc>,
+    pub generated_by: String,
+    /// Date ages and overdue flags are computed for
+    pub as_of: NaiveDate,
+    pub schema_version: u32,
+    pub files: Vec<FactFile>,
+}
+
+/// A written snapshot.
+#[derive(Debug, Clone)]
+pub struct AnalyticsSnapshot {
+    pub directory: PathBuf,
+    pub manifest: SnapshotManifest,
+}
+
+/// Write a snapshot of the fact tables as of `now` into a new directory
+/// below `output_root`.
+pub fn export_snapshot(
+    db: &Database,
+    output_root: &Path,
+    generated_by: &str,
+    now: DateTime<Utc>,
+) -> Result<AnalyticsSnapshot> {
+    let io_error = |path: &Path, e: std::io::Error| QmsError::FileSystem {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    };
+    let directory = output_root.join(format!("analytics-{}", now.format("%Y%m%d-%H%M%S")));
+    if directory.exists() {
+        return Err(QmsError::Validation {
+            field: "output".to_string(),
+            message: format!("Snapshot directory {} already exists", directory.display()),
+        });
+    }
+    fs::create_dir_all(&directory).map_err(|e| io_error(&directory, e))?;
+
+    let as_of = now.date_naive();
+    let tables = db.with_connection(|conn| {
+        // One read transaction, so all fact tables show the same state
+        let tx = conn.unchecked_transaction()?;
+        let tables = FACT_TABLES.iter().map(|table| fact_csv(&tx, table, as_of)).collect::<Result<Vec<_>>>()?;
+        tx.commit()?;
+        Ok(tables)
+    })?;
+
+    let mut written = Vec::new();
+    let mut files = Vec::new();
+    for (table, (csv, rows)) in FACT_TABLES.iter().zip(tables) {
+        let path = directory.join(table.file);
+        fs::write(&path, &csv).map_err(|e| io_error(&path, e))?;
+        files.push(FactFile {
+            file: table.file.to_string(),
+            columns: table.columns.iter().map(|c| c.to_string()).collect(),
+            rows,
+            sha256: sha256_hex(csv.as_bytes()),
+        });
+        written.push(path);
+    }
+    let manifest = SnapshotManifest {
+        generated_at: now,
+        generated_by: generated_by.to_string(),
+        as_of,
+        schema_version: db.schema_version()?,
+        files,
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+    let manifest_path = directory.join(MANIFEST_JSON);
+    fs::write(&manifest_path, &manifest_json).map_err(|e| io_error(&manifest_path, e))?;
+    written.push(manifest_path);
+
+    let mut listing: String = manifest.files.iter().map(|f| format!("{}  {}\n", f.sha256, f.file)).collect();
+    listing.push_str(&format!("{}  {}\n", sha256_hex(&manifest_json), MANIFEST_JSON));
+    let listing_path = directory.join(MANIFEST_SHA256);
+    fs::write(&listing_path, listing).map_err(|e| io_error(&listing_path, e))?;
+    written.push(listing_path);
+
+    for path in &written {
+        let mut permissions = fs::metadata(path).map_err(|e| io_error(path, e))?.permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(path, permissions).map_err(|e| io_error(path, e))?;
+    }
+
+    let rows: serde_json::Map<String, serde_json::Value> =
+        manifest.files.iter().map(|f| (f.file.clone(), f.rows.into())).collect();
+    let metadata = serde_json::json!({ "directory": directory.display().to_string(), "rows": rows });
+    AuditManager::new(db.clone()).log_action(
+        generated_by,
+        "analytics_exported",
+        "analytics_export",
+        "Success",
+        Some(metadata.to_string()),
+    )?;
+    Ok(AnalyticsSnapshot { directory, manifest })
+}
+
+/// CSV of `table` (header first) and its row count.
+fn fact_csv(conn: &Connection, table: &FactTable, as_of: NaiveDate) -> Result<(String, usize)> {
+    let mut csv = table.columns.join(",");
+    csv.push('\n');
+    let mut stmt = conn.prepare(table.sql)?;
+    let mut rows = stmt.query(params![as_of.to_string()])?;
+    let mut count = 0;
+    while let Some(row) = rows.next()? {
+        let values = (0..table.columns.len())
+            .map(|i| {
+                Ok(match row.get_ref(i)? {
+                    ValueRef::Null | ValueRef::Blob(_) => String::new(),
+                    ValueRef::Integer(value) => value.to_string(),
+                    ValueRef::Real(value) => value.to_string(),
+                    ValueRef::Text(text) => csv_field(&String::from_utf8_lossy(text)),
+                })
+            })
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        csv.push_str(&values.join(","));
+        csv.push('\n');
+        count += 1;
+    }
+    Ok((csv, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_snapshot_writes_facts_manifest_and_checksums() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["qa", "emp"]);
+        db.with_connection(|conn| {
+            conn.execute_batch(
+                "INSERT INTO capa_records (id, title, description, capa_type, priority, status, initiator_id,
+                                           assigned_to, created_at, updated_at, due_date)
+                 VALUES ('c1', 'Seal leak', 'Leaking, intermittently', 'Corrective', 'High', 'Identified', 'qa', 'qa',
+                         '2026-03-01T08:00:00Z', '2026-03-01T08:00:00Z', '2026-03-20T00:00:00Z');
+                 INSERT INTO capa_actions (id, capa_id, action_type, description, assigned_to, due_date,
+                                           verification_method, status)
+                 VALUES ('a1', 'c1', 'Corrective', 'Replace seal', 'qa', '2026-03-15', 'Inspection', 'Completed');
+                 INSERT INTO quality_intake (id, source, title, description, reported_by, reported_at)
+                 VALUES ('q1', 'complaint', 'Cracked housing', 'Customer report', 'qa', '2026-03-10T09:00:00Z');
+                 INSERT INTO training_records (id, employee_id, training_item, mandatory, assigned_by, due_date,
+                                               completion_date, status)
+                 VALUES ('t1', 'emp', 'GMP basics', 1, 'qa', '2026-03-05', '2026-03-08', 'Completed');",
+            )?;
+            Ok(())
+        })
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let now = Utc.with_ymd_and_hms(2026, 3, 31, 12, 0, 0).unwrap();
+        let snapshot = export_snapshot(&db, dir.path(), "analyst", now).unwrap();
+        assert!(snapshot.directory.ends_with("analytics-20260331-120000"));
+
+        let capa = fs::read_to_string(snapshot.directory.join("capa_facts.csv")).unwrap();
+        assert_eq!(capa.lines().nth(1), Some("c1,default,Corrective,High,Identified,2026-03-01,2026-03-20,,30,1,1,1"));
+        let complaints = fs::read_to_string(snapshot.directory.join("complaint_facts.csv")).unwrap();
+        assert_eq!(complaints.lines().nth(1), Some("q1,q1,2026-03-10,2026-03,,,,21"));
+        let training = fs::read_to_string(snapshot.directory.join("training_facts.csv")).unwrap();
+        assert_eq!(training.lines().nth(1), Some("t1,default,emp,GMP basics,1,Completed,2026-03-05,2026-03-08,0,3"));
+
+        let manifest: SnapshotManifest =
+            serde_json::from_slice(&fs::read(snapshot.directory.join(MANIFEST_JSON)).unwrap()).unwrap();
+        assert_eq!(manifest, snapshot.manifest);
+        assert_eq!(manifest.files.iter().map(|f| f.rows).collect::<Vec<_>>(), vec![1, 1, 1]);
+        assert_eq!(manifest.files[0].sha256, sha256_hex(capa.as_bytes()));
+        let listing = fs::read_to_string(snapshot.directory.join(MANIFEST_SHA256)).unwrap();
+        assert_eq!(listing.lines().count(), 4);
+        assert!(fs::metadata(snapshot.directory.join("capa_facts.csv")).unwrap().permissions().readonly());
+
+        assert!(export_snapshot(&db, dir.path(), "analyst", now).is_err(), "snapshots are never overwritten");
+        assert_eq!(db.get_audit_entries_for_resource("analytics_export").unwrap().len(), 1);
+    }
+}
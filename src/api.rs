@@ -11,23 +11,68 @@
 //! - INVEST: Self-contained feature deployable independently.
 
 use std::sync::{Arc, RwLock};
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use hyper::Error as HyperError;
 use std::collections::HashMap;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use axum::middleware::{self, Next};
-use axum::http::{Request, header::AUTHORIZATION};
+use axum::http::{Method, Request, HeaderMap, HeaderValue, header::{AUTHORIZATION, CONTENT_TYPE, COOKIE, ETAG, IF_MATCH, LOCATION, SET_COOKIE}};
+use tracing::Instrument;
 use uuid::Uuid;
 
-use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::get, Json, Router};
-use serde::Serialize;
+use axum::{body::{Body, Bytes, HttpBody}, extract::{ConnectInfo, MatchedPath, Path, Query, State}, http::StatusCode, response::{IntoResponse, Response}, routing::{get, post}, Extension, Json, Router};
+use serde::{Deserialize, Serialize};
 
-use crate::capa::{CapaMetrics, CapaRecord, CapaService};
-use crate::risk::{RiskAssessment, RiskManagementReport, RiskManagementService};
-use crate::audit::{AuditLogger, AuditManager};
-use crate::config::DatabaseConfig;
+use crate::access_audit::AccessAuditor;
+use crate::app_context::AppContext;
+use crate::audit::AuditManager;
+use crate::audit_findings::FindingRepo;
+use crate::authorization::{Permission, UserRole};
+use crate::capa::{CapaMetrics, CapaRecord, CapaService, CapaStatus};
+use crate::risk::{RiskAssessment, RiskHeatMap, RiskManagementReport, RiskManagementService};
+use crate::change_history::{ChangeHistoryRepo, ChangeReason};
+use crate::clinical::ClinicalRepo;
+use crate::record_history::RecordHistoryRepo;
+use crate::complaint_rate::{ComplaintRates, DEFAULT_TREND_MONTHS};
+use crate::concurrency;
+use crate::cost_of_quality::{QualityCostRepo, REVIEW_QUARTERS};
+use crate::kpi::{self, KpiRepo, KpiStatus, KpiTarget};
+use crate::links::{LinkRepo, RecordRef, DEFAULT_GRAPH_DEPTH};
+use crate::reporting_views::SummaryRepo;
+use crate::search::{SearchKind, SearchService, DEFAULT_SEARCH_LIMIT};
+use crate::site::DEFAULT_SITE_ID;
+use crate::error::{ErrorSeverity, QmsError, ValidationErrors};
+use crate::events::EventLog;
+use crate::feature_flags::{Feature, FeatureFlags};
+use crate::i18n::{error_message, tr, Locale};
+use crate::config::{ComplaintRateConfig, ComplianceConfig, Config, DatabaseConfig, RequestAuditConfig};
 use crate::database::Database;
+use crate::delegation::DelegationRepo;
+use crate::display_time::DisplayTimezone;
+use crate::file_store::FileStore;
+use crate::graphql::{self, QmsSchema};
+use crate::jobs::{JobKind, JobScheduler, JobTrigger};
+use crate::keystore::{Keystore, SYSTEM_KEY_FILE};
+use crate::legal_hold::{LegalHold, LegalHoldService};
+use crate::lims::{EvidenceTarget, ResultFormat, TestResultIngest};
+use crate::network_policy::{self, NetworkPolicy};
+use crate::notification::OutboxNotifier;
+use crate::oidc::{self, OidcClient};
+use crate::pareto::{ParetoAnalysis, DEFAULT_PERIOD_DAYS, DEFAULT_SOURCES};
+use crate::quality_intake::IssueSource;
+use tokio_rustls::TlsAcceptor;
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use crate::report_scheduler::{ReportData, ReportScheduler, RunStatus};
+use crate::request_audit::{self, RouteClass};
+use crate::security::{SecurityManager, Session};
+use crate::self_test::{self, SelfTestSettings};
+use crate::controlled_prints::{ControlledPrintRepo, PrintDisposition};
+use crate::document::{DocumentApprovals, DocumentEffectivityRepo};
 use crate::supplier::{Supplier, SupplierService, SupplierMetrics};
+use crate::supplier_documents::{SupplierDocumentKind, SupplierDocumentRepo};
+use crate::tasks::{TaskInbox, TaskKind, TaskRepo};
+use crate::time_integrity::SignatureGuard;
 use crate::training::{TrainingMetrics, TrainingRecord, TrainingService};
 use chrono::Duration as ChronoDuration;
 
@@ -40,6 +85,8 @@ pub struct ApiToken {
     pub expires_at: DateTime<Utc>,
     /// Allowed scopes (e.g., "metrics:read")
     pub scopes: Vec<String>,
+    /// User the token acts for; `None` for service tokens
+    pub user_id: Option<String>,
 }
 
 impl ApiToken {
@@ -49,6 +96,9 @@ impl ApiToken {
     }
 }
 
+/// Scope every API token needs; also checked by the gRPC interface.
+pub(crate) const REQUIRED_SCOPE: &str = "metrics:read";
+
 /// Simple in-memory token manager – suitable for embedded API use cases.
 #[derive(Clone, Debug, Default)]
 pub struct TokenManager {
@@ -64,17 +114,41 @@ impl TokenManager {
     /// Insert a new token with TTL (minutes) and scopes.
     pub fn insert_token(&self, token: String, ttl_minutes: i64, scopes: Vec<String>) {
         let expires_at = Utc::now() + Duration::minutes(ttl_minutes);
-        let api_token = ApiToken { token: token.clone(), expires_at, scopes };
+        let api_token = ApiToken { token: token.clone(), expires_at, scopes, user_id: None };
+        self.tokens.write().unwrap().insert(token, api_token);
+    }
+
+    /// Insert a token acting for `user_id`; requests are authorized with the
+    /// user's role.
+    pub fn insert_user_token(&self, token: String, ttl_minutes: i64, scopes: Vec<String>, user_id: &str) {
+        let expires_at = Utc::now() + Duration::minutes(ttl_minutes);
+        let api_token = ApiToken { token: token.clone(), expires_at, scopes, user_id: Some(user_id.to_string()) };
         self.tokens.write().unwrap().insert(token, api_token);
     }
 
     /// Validate incoming token string for required scope.
     pub fn validate(&self, token: &str, scope: &str) -> bool {
-        if let Some(stored) = self.tokens.read().unwrap().get(token) {
-            stored.is_valid(scope)
-        } else {
-            false
-        }
+        self.lookup(token, scope).is_some()
+    }
+
+    /// The stored token, if it is valid for `scope`.
+    pub fn lookup(&self, token: &str, scope: &str) -> Option<ApiToken> {
+        self.tokens.read().unwrap().get(token).filter(|stored| stored.is_valid(scope)).cloned()
+    }
+}
+
+/// Caller of an authenticated request, available to handlers as an
+/// `Extension`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ApiPrincipal {
+    pub user_id: String,
+    pub role: UserRole,
+}
+
+impl ApiPrincipal {
+    /// Service tokens are not tied to a user and may only read.
+    pub(crate) fn service() -> Self {
+        Self { user_id: "api_user".to_string(), role: UserRole::Viewer }
     }
 }
 
@@ -101,6 +175,34 @@ pub struct ApiState {
     pub token_manager: TokenManager,
     /// Cached metrics response with expiry (performance optimization)
     pub metrics_cache: Arc<RwLock<Option<(MetricsResponse, DateTime<Utc>)>>>,
+    /// Backing database (change history & record version queries)
+    pub database: Database,
+    /// Language of error messages returned to clients
+    pub locale: Locale,
+    /// Configured KPI targets (database overrides are applied per request)
+    pub kpi_targets: Vec<KpiTarget>,
+    /// Thresholds flagging monthly complaint and adverse event rates
+    pub complaint_rates: ComplaintRateConfig,
+    /// Write-once store of supplier documents uploaded through the API
+    pub file_store: FileStore,
+    /// Password login and cookie sessions for browser clients
+    pub security: SecurityManager,
+    /// SSO provider, when OIDC login is enabled and discovery succeeded
+    pub oidc: Option<Arc<OidcClient>>,
+    /// Which API mutations are audited and how payloads are redacted
+    pub request_audit: RequestAuditConfig,
+    /// Background jobs shared with the TUI
+    pub jobs: JobScheduler,
+    /// Client address allowlist and denylist
+    pub network_policy: Arc<NetworkPolicy>,
+    /// Thresholds for the `/health/compliance` self-test
+    pub self_test: SelfTestSettings,
+    /// Latest NTP comparison, when the clock monitor runs
+    pub clock_guard: Option<SignatureGuard>,
+    /// Routes of disabled modules answer 404
+    pub features: FeatureFlags,
+    /// Mark session cookies `Secure`, as the API is served over HTTPS
+    pub secure_cookies: bool,
 }
 
 impl ApiState {
@@ -115,46 +217,88 @@ impl ApiState {
             backup_interval_hours: 24,
             backup_retention_days: 90,
         };
-        let database = Database::new(db_config).expect("failed to init in-memory DB");
-        let audit_manager = AuditManager::new(database.clone());
-        let capa_service = CapaService::new(audit_manager);
-
-        // Risk service relies only on a lightweight audit logger
-        let risk_logger = AuditLogger::new_test();
-        let risk_service = RiskManagementService::new(risk_logger);
-
-        // Supplier service (separate logger for better isolation)
-        let supplier_logger = AuditLogger::new_test();
-        let supplier_repository = crate::supplier_repo::SupplierRepository::new(database.clone());
-        let supplier_service = SupplierService::new(supplier_logger, supplier_repository);
+        Self::with_database(Database::new(db_config).expect("failed to init in-memory DB"))
+    }
 
-        // Training service setup
-        let training_logger = AuditLogger::new_test();
-        let training_repo = crate::training_repo::TrainingRepository::new(database.clone());
-        let training_service = TrainingService::new(training_logger, training_repo);
+    /// Build API state on top of an existing database with default settings.
+    pub fn with_database(database: Database) -> Self {
+        Self::from_context(&AppContext::with_database(Config::default(), database))
+    }
 
-        Self {
-            capa_service,
-            risk_service,
-            supplier_service,
-            training_service,
+    /// Build API state over the application's shared context, so requests
+    /// go through the same services and database as the TUI.
+    pub fn from_context(context: &AppContext) -> Self {
+        let state = Self {
+            capa_service: context.capa_service.clone(),
+            risk_service: context.risk_service.clone(),
+            supplier_service: context.supplier_service.clone(),
+            training_service: context.training_service.clone(),
             capa_records: Arc::new(RwLock::new(Vec::new())),
             risk_assessments: Arc::new(RwLock::new(Vec::new())),
             suppliers: Arc::new(RwLock::new(Vec::new())),
             training_records: Arc::new(RwLock::new(Vec::new())),
             token_manager: TokenManager::new(),
             metrics_cache: Arc::new(RwLock::new(None)),
-        }
+            database: context.database.clone(),
+            locale: context.config.application.locale,
+            kpi_targets: context.config.kpis.targets.clone(),
+            complaint_rates: context.config.complaint_rates,
+            file_store: FileStore::for_data_dir(std::path::Path::new(&context.config.application.data_directory)),
+            security: context.security.clone(),
+            oidc: None,
+            request_audit: context.config.api.request_audit.clone(),
+            jobs: context.jobs.clone(),
+            network_policy: Arc::new(
+                NetworkPolicy::from_config(&context.config.api.network).expect("invalid api.network configuration"),
+            ),
+            self_test: SelfTestSettings::from_config(&context.config),
+            clock_guard: None,
+            features: context.features.clone(),
+            secure_cookies: context.config.api.tls.enabled,
+        };
+        // Report generation reads the API's records; the job's copy of the
+        // state gets its own scheduler so the registry does not own itself.
+        let job_state = Self { jobs: JobScheduler::new(context.database.clone()), ..state.clone() };
+        context.jobs.register(
+            JobKind::ReportGeneration,
+            Duration::minutes(i64::from(context.config.jobs.report_check_interval_minutes)),
+            report_generation_job(job_state, &context.config),
+        );
+        state
+    }
+
+    /// Offer SSO login through `client`.
+    pub fn with_oidc(mut self, client: OidcClient) -> Self {
+        self.oidc = Some(Arc::new(client));
+        self
+    }
+
+    /// Return client-facing messages in `locale`.
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Report the clock monitor's latest check in the compliance self-test.
+    pub fn with_clock_guard(mut self, guard: SignatureGuard) -> Self {
+        self.clock_guard = Some(guard);
+        self
     }
 }
 
 /// API response payload containing aggregated metrics.
-#[derive(Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MetricsResponse {
     /// Aggregated CAPA statistics
     pub capa_metrics: CapaMetrics,
     /// Aggregated Risk-management statistics
     pub risk_report: RiskManagementReport,
+    /// Supplier qualification mix (defaults to zero counts for older clients)
+    #[serde(default)]
+    pub supplier_metrics: SupplierMetrics,
+    /// KPIs rated against their targets, with monthly trend
+    #[serde(default)]
+    pub kpis: Vec<KpiStatus>,
 }
 
 /// Handler for `GET /metrics`.
@@ -168,30 +312,71 @@ async fn get_metrics(State(state): State<ApiState>) -> impl IntoResponse {
         }
     }
 
+    let response = match compute_metrics(&state, now).await {
+        Ok(response) => response,
+        Err(e) => return error_response(state.locale, e),
+    };
+
+    // Store in cache
+    *state.metrics_cache.write().unwrap() = Some((response.clone(), now + ChronoDuration::seconds(TTL_SEC)));
+
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+/// Metrics over a consistent snapshot of the in-memory records, with KPIs
+/// rated against the configured targets.
+pub(crate) async fn compute_metrics(state: &ApiState, now: DateTime<Utc>) -> crate::Result<MetricsResponse> {
     // Gather a snapshot of data under read locks to ensure consistency.
     let capa_records = state.capa_records.read().unwrap().clone();
     let risk_assessments = state.risk_assessments.read().unwrap().clone();
+    let suppliers = state.suppliers.read().unwrap().clone();
+    let training_records = state.training_records.read().unwrap().clone();
 
     // Compute metrics via domain services (SOLID adherence)
     let capa_metrics = state.capa_service.get_capa_metrics(&capa_records);
-    let risk_report = match state
-        .risk_service
-        .generate_risk_report(&risk_assessments, "api_user".to_string())
-        .await
-    {
-        Ok(report) => report,
-        Err(e) => {
-            tracing::error!("risk report generation failed: {e}");
-            return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
-        }
-    };
+    let risk_report = state.risk_service.generate_risk_report(&risk_assessments, "api_user".to_string()).await?;
 
-    let response = MetricsResponse { capa_metrics, risk_report };
+    let supplier_metrics = SupplierMetrics::from_suppliers(&suppliers);
+    let training_metrics = state.training_service.calculate_metrics(&training_records);
+    let values = kpi::measure(&capa_records, &training_metrics, &supplier_metrics, now);
+    let repo = KpiRepo::new(&state.database);
+    let kpis = repo.evaluate(&repo.targets(&state.kpi_targets)?, &values, now)?;
+    Ok(MetricsResponse { capa_metrics, risk_report, supplier_metrics, kpis })
+}
 
-    // Store in cache
-    *state.metrics_cache.write().unwrap() = Some((response.clone(), now + ChronoDuration::seconds(TTL_SEC)));
+/// Handler for `GET /metrics/prometheus` (database pool statistics).
+async fn get_prometheus_metrics(State(state): State<ApiState>) -> impl IntoResponse {
+    (
+        StatusCode::OK,
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.database.pool_stats().to_prometheus(),
+    )
+        .into_response()
+}
 
-    (StatusCode::OK, Json(response)).into_response()
+/// Dashboard aggregates read from the materialized summary tables.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SummaryResponse {
+    pub capa_metrics: CapaMetrics,
+    pub supplier_metrics: SupplierMetrics,
+    pub training_metrics: TrainingMetrics,
+}
+
+/// Handler for `GET /summaries` (default site).
+async fn get_summaries(State(state): State<ApiState>) -> impl IntoResponse {
+    let repo = SummaryRepo::new(&state.database);
+    let now = Utc::now();
+    let summary = repo.capa_metrics(DEFAULT_SITE_ID, now).and_then(|capa_metrics| {
+        Ok(SummaryResponse {
+            capa_metrics,
+            supplier_metrics: repo.supplier_metrics(DEFAULT_SITE_ID, now)?,
+            training_metrics: repo.training_metrics(DEFAULT_SITE_ID, now)?,
+        })
+    });
+    match summary {
+        Ok(summary) => (StatusCode::OK, Json(summary)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
 }
 
 /// Handler for `GET /supplier_metrics`.
@@ -201,6 +386,79 @@ async fn get_supplier_metrics(State(state): State<ApiState>) -> impl IntoRespons
     (StatusCode::OK, Json(metrics)).into_response()
 }
 
+/// Query parameters for `GET /risk_heat_map`.
+#[derive(Debug, Deserialize)]
+pub struct RiskHeatMapQuery {
+    /// Place assessments by their residual instead of their initial estimate
+    #[serde(default)]
+    pub residual: bool,
+}
+
+/// Handler for `GET /risk_heat_map?residual=..` – the severity × probability
+/// matrix with the current assessments on each cell.
+async fn get_risk_heat_map(State(state): State<ApiState>, Query(query): Query<RiskHeatMapQuery>) -> impl IntoResponse {
+    let risk_assessments = state.risk_assessments.read().unwrap().clone();
+    let heat_map = RiskHeatMap::from_assessments(&risk_assessments, query.residual);
+    (StatusCode::OK, Json(heat_map)).into_response()
+}
+
+/// Query parameters for `GET /analytics/pareto`.
+#[derive(Debug, Deserialize)]
+pub struct ParetoQuery {
+    /// Comma-separated issue sources; complaints and NCRs by default
+    pub sources: Option<String>,
+    /// First day of the period; 90 days before its end by default
+    pub from: Option<NaiveDate>,
+    /// Last day of the period, included; today by default
+    pub to: Option<NaiveDate>,
+}
+
+/// Handler for `GET /analytics/pareto?sources=..&from=..&to=..` – cause
+/// categories of the selected issues ranked by frequency.
+async fn get_pareto(State(state): State<ApiState>, Query(query): Query<ParetoQuery>) -> impl IntoResponse {
+    let start_of = |date: NaiveDate| date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    let to = query.to.map_or_else(Utc::now, |date| start_of(date) + Duration::days(1));
+    let from = query.from.map_or(to - Duration::days(DEFAULT_PERIOD_DAYS), start_of);
+    // Sources of a disabled module are left out, and unknown when asked for
+    let enabled = |source: &IssueSource| state.features.allows_record_type(source.as_str());
+    let sources = match &query.sources {
+        None => Ok(DEFAULT_SOURCES.into_iter().filter(enabled).collect()),
+        Some(list) => list
+            .split(',')
+            .map(|value| {
+                IssueSource::parse(value.trim()).filter(enabled).ok_or_else(|| QmsError::Validation {
+                    field: "sources".to_string(),
+                    message: format!("Unknown issue source: {}", value.trim()),
+                })
+            })
+            .collect::<crate::Result<Vec<_>>>(),
+    };
+    match sources.and_then(|sources| ParetoAnalysis::new(&state.database).rank(&sources, from, to)) {
+        Ok(ranking) => (StatusCode::OK, Json(ranking)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Query parameters for `GET /analytics/complaint_rates`.
+#[derive(Debug, Deserialize)]
+pub struct ComplaintRateQuery {
+    /// Months up to and including the current one; 12 by default
+    pub months: Option<u32>,
+}
+
+/// Handler for `GET /analytics/complaint_rates?months=..` – complaints and
+/// adverse events per 10,000 units shipped, per month.
+async fn get_complaint_rates(
+    State(state): State<ApiState>,
+    Query(query): Query<ComplaintRateQuery>,
+) -> impl IntoResponse {
+    let months = query.months.unwrap_or(DEFAULT_TREND_MONTHS);
+    match ComplaintRates::new(&state.database).trend(months, Utc::now().date_naive(), &state.complaint_rates) {
+        Ok(trend) => (StatusCode::OK, Json(trend)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
 /// Handler for `GET /training_metrics`.
 async fn get_training_metrics(State(state): State<ApiState>) -> impl IntoResponse {
     let training_records = state.training_records.read().unwrap().clone();
@@ -208,209 +466,1851 @@ async fn get_training_metrics(State(state): State<ApiState>) -> impl IntoRespons
     (StatusCode::OK, Json(metrics)).into_response()
 }
 
-/// Middleware: Enforces Bearer token authentication and scope validation.
-async fn token_auth<B>(
-    State(state): State<ApiState>,
-    req: Request<B>,
-    next: Next<B>,
-) -> impl IntoResponse {
-    const REQUIRED_SCOPE: &str = "metrics:read";
+/// Row of `GET /capas`: the readable number alongside the internal key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapaListItem {
+    pub id: String,
+    pub capa_number: String,
+    pub title: String,
+    pub status: CapaStatus,
+}
 
-    // Extract token from `Authorization: Bearer <token>` header
-    let unauthorized = || (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
-    let Some(header_val) = req.headers().get(AUTHORIZATION) else {
-        return unauthorized();
-    };
-    let Ok(auth_str) = header_val.to_str() else {
-        return unauthorized();
-    };
-    let token = auth_str.strip_prefix("Bearer ").unwrap_or("");
+/// Handler for `GET /capas`.
+async fn get_capas(State(state): State<ApiState>) -> impl IntoResponse {
+    let items: Vec<CapaListItem> = state
+        .capa_records
+        .read()
+        .unwrap()
+        .iter()
+        .map(|capa| CapaListItem {
+            id: capa.id.clone(),
+            capa_number: capa.display_id().to_string(),
+            title: capa.title.clone(),
+            status: capa.status.clone(),
+        })
+        .collect();
+    (StatusCode::OK, Json(items)).into_response()
+}
 
-    if state.token_manager.validate(token, REQUIRED_SCOPE) {
-        next.run(req).await
-    } else {
-        unauthorized()
+/// Handler for `GET /capas/:reference` – `reference` is either the UUID or
+/// the CAPA number. The row version is served as `ETag`.
+async fn get_capa(State(state): State<ApiState>, Path(reference): Path<String>) -> impl IntoResponse {
+    let found = state
+        .capa_records
+        .read()
+        .unwrap()
+        .iter()
+        .find(|capa| capa.id == reference || capa.capa_number.eq_ignore_ascii_case(&reference))
+        .cloned();
+    let Some(capa) = found else {
+        return error_response(state.locale, QmsError::NotFound { resource: "CAPA".to_string(), id: reference });
+    };
+    let capa_id = capa.id.clone();
+    match RecordDetail::load(&state.database, "capa_records", &capa_id, capa) {
+        Ok(detail) => with_etag(detail.record.row_version, Json(detail)),
+        Err(e) => error_response(state.locale, e),
     }
 }
 
-/// Build an Axum router with all API routes registered.
-pub fn router() -> Router {
-    let state = ApiState::new();
+/// Body of `POST /capas/:reference/status`.
+#[derive(Debug, Deserialize)]
+pub struct CapaStatusRequest {
+    /// Next status; closing needs an electronic signature and is refused here
+    pub status: CapaStatus,
+    pub reason: String,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
 
-    // For demonstration, generate a default token valid for 24 hours with metrics scope.
-    let default_token = Uuid::new_v4().to_string();
-    state.token_manager.insert_token(default_token.clone(), 60 * 24, vec!["metrics:read".to_string()]);
-    tracing::info!("API authentication token generated", %default_token);
+/// Body of `POST /capas/:reference/reassign`.
+#[derive(Debug, Deserialize)]
+pub struct CapaReassignRequest {
+    pub assigned_to: String,
+    pub reason: String,
+}
 
-    Router::new()
-        .route("/metrics", get(get_metrics))
-        .route("/supplier_metrics", get(get_supplier_metrics))
-        .route("/training_metrics", get(get_training_metrics))
-        .layer(middleware::from_fn_with_state(state.clone(), token_auth))
-        .with_state(state)
+/// Handler for `POST /capas/:reference/status`.
+async fn update_capa_status(
+    State(state): State<ApiState>,
+    Extension(principal): Extension<ApiPrincipal>,
+    Path(reference): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<CapaStatusRequest>,
+) -> impl IntoResponse {
+    let CapaStatusRequest { status, reason, comment } = request;
+    change_capa(state, principal, reference, headers, reason, |service, capa, user_id, reason| {
+        service.update_status(capa, status, user_id, reason, comment)
+    })
 }
 
-pub use MetricsResponse;
+/// Handler for `POST /capas/:reference/reassign`.
+async fn reassign_capa(
+    State(state): State<ApiState>,
+    Extension(principal): Extension<ApiPrincipal>,
+    Path(reference): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<CapaReassignRequest>,
+) -> impl IntoResponse {
+    let CapaReassignRequest { assigned_to, reason } = request;
+    change_capa(state, principal, reference, headers, reason, |service, capa, user_id, reason| {
+        service.reassign(capa, &assigned_to, user_id, reason)
+    })
+}
 
-/// Start the API server on the provided address (e.g., "127.0.0.1:3000").
-/// This is intended to run in a background Tokio task.
-pub async fn serve(addr: &str) -> Result<(), HyperError> {
-    let socket: SocketAddr = addr.parse().expect("invalid socket address");
-    let router = router();
-    axum::Server::bind(&socket)
-        .serve(router.into_make_service())
-        .await
+/// Apply `change` to the CAPA version named by `If-Match`; a CAPA changed in
+/// the meantime (e.g. from the TUI) yields 412. The change is made to the
+/// latest version snapshot, which also replaces the listed copy.
+fn change_capa<F>(
+    state: ApiState,
+    principal: ApiPrincipal,
+    reference: String,
+    headers: HeaderMap,
+    reason: String,
+    change: F,
+) -> Response
+where
+    F: FnOnce(&CapaService, &mut CapaRecord, &str, &ChangeReason) -> crate::Result<()>,
+{
+    if let Err(denied) = authorize(&state, &principal, Permission::WriteRecords, &format!("capa:{}", reference)) {
+        return denied;
+    }
+    let expected = match expected_version(state.locale, &headers) {
+        Ok(version) => version,
+        Err(response) => return response,
+    };
+    // Held across the update so that two requests cannot both pass the check
+    let mut capas = state.capa_records.write().unwrap();
+    let result = ChangeReason::new(reason).and_then(|reason| {
+        let listed = capas
+            .iter_mut()
+            .find(|capa| capa.id == reference || capa.capa_number.eq_ignore_ascii_case(&reference))
+            .ok_or_else(|| QmsError::NotFound { resource: "CAPA".to_string(), id: reference.clone() })?;
+        let latest = RecordHistoryRepo::new(&state.database).record_as_of::<CapaRecord>(&listed.id, Utc::now())?;
+        let mut capa = latest.unwrap_or_else(|| listed.clone());
+        concurrency::check_version("capa", &capa.id, expected, capa.row_version)?;
+        change(&state.capa_service, &mut capa, &principal.user_id, &reason)?;
+        *listed = capa.clone();
+        Ok(capa)
+    });
+    drop(capas);
+    match result {
+        Ok(capa) => with_etag(capa.row_version, Json(capa)),
+        Err(e) => error_response(state.locale, e),
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use axum::http::{Method, Request};
-    use hyper::Body;
-    use tower::ServiceExt; // for `oneshot`
-    use chrono::Utc;
-    use crate::capa::{CapaPriority, CapaStatus, CapaType};
-    use crate::risk::{RiskSeverity, RiskProbability};
-    use axum::http::header::{AUTHORIZATION, HeaderValue};
-    use crate::supplier::{Supplier, SupplierStatus, SupplierMetrics};
-    use crate::training::{TrainingRecord, TrainingStatus, TrainingMetrics};
+/// A record as shown in a detail view, with the active legal holds covering
+/// it so that clients can display a hold banner. `legal_holds` is omitted
+/// when there are none.
+#[derive(Debug, Serialize)]
+pub struct RecordDetail<T> {
+    #[serde(flatten)]
+    pub record: T,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub legal_holds: Vec<LegalHold>,
+}
 
-    /// Build a router and underlying state for test purposes (FIRST compliant).
-    async fn setup_test_router() -> (Router, ApiState) {
-        let state = ApiState::new();
-        let router = Router::new()
-            .route("/metrics", get(super::get_metrics))
-            .route("/supplier_metrics", get(super::get_supplier_metrics))
-            .route("/training_metrics", get(super::get_training_metrics))
-            .layer(middleware::from_fn_with_state(state.clone(), super::token_auth))
-            .with_state(state.clone());
-        (router, state)
+impl<T> RecordDetail<T> {
+    fn load(db: &Database, table: &str, record_id: &str, record: T) -> crate::Result<Self> {
+        Ok(Self { record, legal_holds: LegalHoldService::new(db).holds_on(table, record_id)? })
     }
+}
 
-    /// Helper: obtain valid token from state after setup.
-    async fn setup_test_router_with_token() -> (Router, String) {
-        let (router, state) = setup_test_router().await;
-        // Insert token valid for tests
-        let token = "test-token".to_string();
-        state.token_manager.insert_token(token.clone(), 60, vec!["metrics:read".to_string()]);
-        (router, token)
+/// Handler for `POST /graphql` – dashboard queries over the read models in
+/// [`crate::graphql`].
+async fn graphql_query(
+    State(state): State<ApiState>,
+    Extension(schema): Extension<QmsSchema>,
+    request: GraphQLRequest,
+) -> GraphQLResponse {
+    graphql::execute(&schema, state, request.into_inner()).await.into()
+}
+
+/// Handler for `GET /search?q=..&limit=..` – ranked hits across modules.
+async fn search_records(State(state): State<ApiState>, Query(query): Query<SearchQuery>) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let kinds = SearchKind::ALL.into_iter().filter(|kind| state.features.allows_record_type(kind.as_str())).collect();
+    match SearchService::new(&state.database).with_kinds(kinds).search(&query.q, limit) {
+        Ok(results) => (StatusCode::OK, Json(results)).into_response(),
+        Err(e) => error_response(state.locale, e),
     }
+}
 
-    #[tokio::test]
-    async fn test_metrics_endpoint() {
-        // Arrange
-        let (router, state) = setup_test_router().await;
+/// Handler for `GET /records/:record_type/:record_id/history`.
+async fn get_record_history(
+    State(state): State<ApiState>,
+    Path((record_type, record_id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    match ChangeHistoryRepo::new(&state.database).history(&record_type, &record_id) {
+        Ok(history) => (StatusCode::OK, Json(history)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
 
-        // Insert valid token for this test
-        let token = "metrics-token".to_string();
-        state.token_manager.insert_token(token.clone(), 60, vec!["metrics:read".to_string()]);
+/// Query parameters for `GET /records/:record_type/:record_id/as_of`.
+#[derive(Debug, Deserialize)]
+pub struct AsOfQuery {
+    /// RFC 3339 timestamp
+    pub at: DateTime<Utc>,
+}
 
-        // Create sample CAPA record
-        let mut capa = state
-            .capa_service
-            .create_capa(
-                "Test CAPA".to_string(),
-                "Test description".to_string(),
-                CapaType::Preventive,
-                CapaPriority::Medium,
-                "initiator1".to_string(),
-                "assignee1".to_string(),
-                None,
-            )
-            .expect("create_capa failed");
-        // Transition status to Closed for metrics diversity
-        state
-            .capa_service
-            .update_status(&mut capa, CapaStatus::Closed, "initiator1", None)
-            .expect("status update failed");
-        state.capa_records.write().unwrap().push(capa);
+/// Query parameters for `GET /records/:record_type/:record_id/diff`.
+#[derive(Debug, Deserialize)]
+pub struct DiffQuery {
+    pub from: u32,
+    pub to: u32,
+}
 
-        // Create sample Risk assessment
-        let assessment = state
-            .risk_service
-            .create_risk_assessment(
-                "Device X".to_string(),
-                "Hazard description".to_string(),
-                "Situation".to_string(),
-                "Sequence".to_string(),
-                "Harm description".to_string(),
-                RiskSeverity::Minor,
-                RiskProbability::Possible,
-                "creator".to_string(),
-            )
-            .await
-            .expect("risk assessment creation failed");
-        state.risk_assessments.write().unwrap().push(assessment);
+/// Query parameters for `GET /records/:record_type/:record_id/links`.
+#[derive(Debug, Deserialize)]
+pub struct GraphQuery {
+    /// Hops to follow from the record
+    pub depth: Option<usize>,
+    /// `json` (default) or `dot`
+    pub format: Option<String>,
+}
 
-        // Act
-        let response = router
-            .oneshot(
-                Request::builder()
-                    .method(Method::GET)
-                    .uri("/metrics")
-                    .header(
-                        AUTHORIZATION,
-                        HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
-                    )
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
+/// Query parameters for `GET /search`.
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    /// Keyword or record identifier
+    pub q: String,
+    pub limit: Option<usize>,
+}
 
-        // Assert
-        assert_eq!(response.status(), StatusCode::OK);
-        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
-        let parsed: MetricsResponse = serde_json::from_slice(&body).expect("valid JSON");
-        assert_eq!(parsed.capa_metrics.total_count, 1);
-        assert_eq!(parsed.risk_report.total_assessments, 1);
-    }
+/// Header carrying the correlation id of a request and its response.
+pub const CORRELATION_ID_HEADER: &str = "x-correlation-id";
 
-    #[tokio::test]
-    async fn test_metrics_endpoint_requires_auth() {
-        let (router, _token) = setup_test_router_with_token().await;
+tokio::task_local! {
+    static CORRELATION_ID: String;
+}
 
-        // Request without token should be 401
-        let response = router
-            .oneshot(
-                Request::builder()
-                    .method(Method::GET)
-                    .uri("/metrics")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
-        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+/// Correlation id of the API request being handled on this task, if any.
+pub fn current_correlation_id() -> Option<String> {
+    CORRELATION_ID.try_with(|id| id.clone()).ok()
+}
+
+/// JSON body of every API error response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiError {
+    /// Stable machine-readable code, e.g. `NOT_FOUND`
+    pub code: String,
+    /// `LOW` to `CRITICAL`, see [`ErrorSeverity`]
+    pub severity: String,
+    /// Localized human readable message
+    pub message: String,
+    /// Matches the `x-correlation-id` response header and the request's log lines
+    pub correlation_id: Option<String>,
+    /// Field → message map for validation errors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields: Option<ValidationErrors>,
+}
+
+impl ApiError {
+    pub fn new(code: &str, severity: ErrorSeverity, message: String) -> Self {
+        Self {
+            code: code.to_string(),
+            severity: severity.as_str().to_string(),
+            message,
+            correlation_id: current_correlation_id(),
+            fields: None,
+        }
     }
 
-    #[tokio::test]
-    async fn test_metrics_endpoint_with_valid_token() {
-        let (router, token) = setup_test_router_with_token().await;
+    pub fn from_error(locale: Locale, e: &QmsError) -> Self {
+        let mut error = Self::new(e.error_code(), e.severity(), error_message(locale, e));
+        if let QmsError::Validation { field, message }
+        | QmsError::ValidationError { field, message } = e
+        {
+            let mut fields = ValidationErrors::new();
+            fields.add(field.as_str(), message.as_str());
+            error.fields = Some(fields);
+        } else if let QmsError::ValidationErrors { errors } = e {
+            error.fields = Some(errors.clone());
+        }
+        error
+    }
 
-        let auth_header = format!("Bearer {}", token);
-        let response = router
-            .oneshot(
-                Request::builder()
-                    .method(Method::GET)
-                    .uri("/metrics")
-                    .header(AUTHORIZATION, HeaderValue::from_str(&auth_header).unwrap())
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
+    /// Respond with this body and `status`.
+    pub fn into_response_with(self, status: StatusCode) -> Response {
+        (status, Json(self)).into_response()
+    }
+}
 
-        assert_eq!(response.status(), StatusCode::OK);
+/// HTTP status for a domain error.
+fn status_for(e: &QmsError) -> StatusCode {
+    match e {
+        QmsError::NotFound { .. } => StatusCode::NOT_FOUND,
+        QmsError::Validation { .. } | QmsError::ValidationError { .. } | QmsError::ValidationErrors { .. } => {
+            StatusCode::BAD_REQUEST
+        }
+        QmsError::Security { .. } => StatusCode::FORBIDDEN,
+        QmsError::Conflict { .. } => StatusCode::PRECONDITION_FAILED,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
     }
+}
 
-    #[tokio::test]
-    async fn test_supplier_metrics_endpoint() {
-        let (router, state) = setup_test_router().await;
-        let token = "supplier-token".to_string();
-        state.token_manager.insert_token(token.clone(), 60, vec!["metrics:read".to_string()]);
+/// Map a domain error onto an HTTP response.
+fn error_response(locale: Locale, e: QmsError) -> Response {
+    error_with_status(status_for(&e), locale, &e)
+}
 
-        // Add sample suppliers
+fn error_with_status(status: StatusCode, locale: Locale, e: &QmsError) -> Response {
+    if status.is_server_error() {
+        tracing::error!(code = e.error_code(), "API request failed: {e}");
+    } else {
+        tracing::warn!(code = e.error_code(), "API request rejected: {e}");
+    }
+    ApiError::from_error(locale, e).into_response_with(status)
+}
+
+impl IntoResponse for QmsError {
+    fn into_response(self) -> Response {
+        error_response(Locale::default(), self)
+    }
+}
+
+/// Handler for `GET /records/:record_type/:record_id/versions`.
+async fn get_record_versions(
+    State(state): State<ApiState>,
+    Path((record_type, record_id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    match RecordHistoryRepo::new(&state.database).versions(&record_type, &record_id) {
+        Ok(versions) => (StatusCode::OK, Json(versions)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Handler for `GET /records/:record_type/:record_id/as_of?at=...` – read-only
+/// reconstruction of the record as it stood at `at`.
+async fn get_record_as_of(
+    State(state): State<ApiState>,
+    Path((record_type, record_id)): Path<(String, String)>,
+    Query(query): Query<AsOfQuery>,
+) -> impl IntoResponse {
+    match RecordHistoryRepo::new(&state.database).as_of(&record_type, &record_id, query.at) {
+        Ok(Some(version)) => (StatusCode::OK, Json(version)).into_response(),
+        Ok(None) => ApiError::new("NOT_FOUND", ErrorSeverity::Medium, tr(state.locale, "api.record_not_yet_created").to_string())
+            .into_response_with(StatusCode::NOT_FOUND),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Handler for `GET /records/:record_type/:record_id/diff?from=..&to=..`.
+async fn get_record_diff(
+    State(state): State<ApiState>,
+    Path((record_type, record_id)): Path<(String, String)>,
+    Query(query): Query<DiffQuery>,
+) -> impl IntoResponse {
+    match RecordHistoryRepo::new(&state.database).diff(&record_type, &record_id, query.from, query.to) {
+        Ok(diff) => (StatusCode::OK, Json(diff)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Handler for `GET /records/:record_type/:record_id/links` – the record's
+/// traceability graph as JSON or Graphviz DOT.
+async fn get_record_links(
+    State(state): State<ApiState>,
+    Path((record_type, record_id)): Path<(String, String)>,
+    Query(query): Query<GraphQuery>,
+) -> impl IntoResponse {
+    let root = RecordRef::new(record_type, record_id);
+    let depth = query.depth.unwrap_or(DEFAULT_GRAPH_DEPTH);
+    let visible = |record: &RecordRef| state.features.allows_record_type(&record.record_type);
+    let graph = match LinkRepo::new(&state.database).graph_where(&root, depth, visible) {
+        Ok(graph) => graph,
+        Err(e) => return error_response(state.locale, e),
+    };
+    match query.format.as_deref().unwrap_or("json") {
+        "json" => (StatusCode::OK, Json(graph)).into_response(),
+        "dot" => (StatusCode::OK, [(CONTENT_TYPE, "text/vnd.graphviz")], graph.to_dot()).into_response(),
+        other => error_response(
+            state.locale,
+            QmsError::Validation { field: "format".to_string(), message: format!("Unsupported graph format '{}'", other) },
+        ),
+    }
+}
+
+/// Query parameters for `GET /audit_trail/export`.
+#[derive(Debug, Deserialize)]
+pub struct AuditExportQuery {
+    /// Only export entries by this user
+    pub user: Option<String>,
+    /// Display zone for the local timestamp column (e.g. `+02:00`)
+    pub tz: Option<String>,
+}
+
+/// Bytes buffered before a chunk of the CSV export is sent.
+const EXPORT_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Handler for `GET /audit_trail/export` – the full audit trail as CSV, sent
+/// as a chunked body so memory stays bounded regardless of trail size.
+async fn export_audit_trail(
+    State(state): State<ApiState>,
+    Extension(principal): Extension<ApiPrincipal>,
+    Query(query): Query<AuditExportQuery>,
+) -> impl IntoResponse {
+    if let Err(denied) = authorize(&state, &principal, Permission::ExportAuditTrail, "audit_trail") {
+        return denied;
+    }
+    let tz = match query.tz.as_deref().map(str::parse::<DisplayTimezone>).transpose() {
+        Ok(tz) => tz.unwrap_or_default(),
+        Err(e) => return error_with_status(StatusCode::BAD_REQUEST, state.locale, &e),
+    };
+    let auditor = AccessAuditor::new(state.database.clone(), &ComplianceConfig::default());
+    let lines = match auditor.stream_audit_trail_csv(&principal.user_id, query.user.as_deref(), tz) {
+        Ok((_, lines)) => lines,
+        Err(e) => return error_response(state.locale, e),
+    };
+
+    let (mut sender, body) = hyper::Body::channel();
+    let runtime = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || {
+        let mut chunk = String::with_capacity(EXPORT_CHUNK_BYTES);
+        for line in lines {
+            match line {
+                Ok(line) => chunk.push_str(&line),
+                Err(e) => {
+                    tracing::error!("audit trail export aborted: {e}");
+                    sender.abort();
+                    return;
+                }
+            }
+            if chunk.len() >= EXPORT_CHUNK_BYTES {
+                let full = std::mem::replace(&mut chunk, String::with_capacity(EXPORT_CHUNK_BYTES));
+                if runtime.block_on(sender.send_data(full.into())).is_err() {
+                    return; // client went away
+                }
+            }
+        }
+        if !chunk.is_empty() {
+            let _ = runtime.block_on(sender.send_data(chunk.into()));
+        }
+    });
+
+    (StatusCode::OK, [(CONTENT_TYPE, "text/csv")], axum::body::boxed(body)).into_response()
+}
+
+/// Handler for `GET /documents/:document_id` – approval state, with the
+/// row version as `ETag`.
+async fn get_document(State(state): State<ApiState>, Path(document_id): Path<String>) -> impl IntoResponse {
+    let found = DocumentApprovals::new(&state.database)
+        .fetch(&document_id)
+        .and_then(|document| document.map(|d| RecordDetail::load(&state.database, "documents", &document_id, d)).transpose());
+    match found {
+        Ok(Some(detail)) => with_etag(detail.record.row_version, Json(detail)),
+        Ok(None) => error_response(state.locale, QmsError::NotFound { resource: "document".to_string(), id: document_id }),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Body of `POST /documents/:document_id/approve`.
+#[derive(Debug, Deserialize)]
+pub struct DocumentApprovalRequest {
+    pub reason: String,
+}
+
+/// Handler for `POST /documents/:document_id/approve` – QualityManager, or a
+/// delegate of one on behalf of the delegator. `If-Match` must carry the ETag
+/// of the version that was reviewed.
+async fn approve_document(
+    State(state): State<ApiState>,
+    Extension(principal): Extension<ApiPrincipal>,
+    Path(document_id): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<DocumentApprovalRequest>,
+) -> impl IntoResponse {
+    let resource = format!("document:{}", document_id);
+    let delegator = match principal.role.has_permission(Permission::ApproveDocuments) {
+        true => None,
+        false => match DelegationRepo::new(&state.database).in_force_for(&principal.user_id, Utc::now().date_naive()) {
+            Ok(delegations) => delegations.into_iter().next().map(|delegation| delegation.delegator),
+            Err(e) => return error_response(state.locale, e),
+        },
+    };
+    if delegator.is_none() {
+        if let Err(denied) = authorize(&state, &principal, Permission::ApproveDocuments, &resource) {
+            return denied;
+        }
+    }
+    let expected = match expected_version(state.locale, &headers) {
+        Ok(version) => version,
+        Err(response) => return response,
+    };
+    let approvals = DocumentApprovals::new(&state.database);
+    let approved = ChangeReason::new(request.reason).and_then(|reason| match &delegator {
+        Some(delegator) => approvals.approve_on_behalf(&document_id, &principal.user_id, delegator, expected, &reason),
+        None => approvals.approve(&document_id, &principal.user_id, expected, &reason),
+    });
+    match approved {
+        Ok(version) => with_etag(version, StatusCode::NO_CONTENT),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Handler for `GET /documents/:document_id/effectivity` – the sites and
+/// products each revision applies to.
+async fn list_document_effectivity(
+    State(state): State<ApiState>,
+    Path(document_id): Path<String>,
+) -> impl IntoResponse {
+    match DocumentEffectivityRepo::new(&state.database).scopes(&document_id) {
+        Ok(scopes) => (StatusCode::OK, Json(scopes)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Body of `POST /documents/:document_id/effectivity`.
+#[derive(Debug, Deserialize)]
+pub struct DocumentEffectivityRequest {
+    pub version: String,
+    /// Site id, or `*` for every site
+    pub site_id: String,
+    /// Product the revision is limited to; every product when absent
+    #[serde(default)]
+    pub product: Option<String>,
+    pub effective_from: NaiveDate,
+}
+
+/// Handler for `POST /documents/:document_id/effectivity` – make a revision
+/// effective at a site (and product) from a date. Requires document approval
+/// rights.
+async fn set_document_effectivity(
+    State(state): State<ApiState>,
+    Extension(principal): Extension<ApiPrincipal>,
+    Path(document_id): Path<String>,
+    Json(request): Json<DocumentEffectivityRequest>,
+) -> impl IntoResponse {
+    let resource = format!("document:{}", document_id);
+    if let Err(denied) = authorize(&state, &principal, Permission::ApproveDocuments, &resource) {
+        return denied;
+    }
+    let set = DocumentEffectivityRepo::new(&state.database).set(
+        &document_id,
+        &request.version,
+        &request.site_id,
+        request.product.as_deref(),
+        request.effective_from,
+        &principal.user_id,
+    );
+    match set {
+        Ok(effectivity) => (StatusCode::CREATED, Json(effectivity)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Handler for `GET /documents/:document_id/prints` – every controlled print
+/// of the document, open and reconciled.
+async fn list_controlled_prints(State(state): State<ApiState>, Path(document_id): Path<String>) -> impl IntoResponse {
+    match ControlledPrintRepo::new(&state.database).prints(&document_id) {
+        Ok(prints) => (StatusCode::OK, Json(prints)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Body of `POST /documents/:document_id/prints`.
+#[derive(Debug, Deserialize)]
+pub struct ControlledPrintRequest {
+    pub recipient: String,
+    /// Today when absent
+    #[serde(default)]
+    pub issued_on: Option<NaiveDate>,
+}
+
+/// Handler for `POST /documents/:document_id/prints` – issue the next
+/// numbered print of the current revision.
+async fn issue_controlled_print(
+    State(state): State<ApiState>,
+    Extension(principal): Extension<ApiPrincipal>,
+    Path(document_id): Path<String>,
+    Json(request): Json<ControlledPrintRequest>,
+) -> impl IntoResponse {
+    if let Err(denied) = authorize(&state, &principal, Permission::WriteRecords, &format!("document:{}", document_id)) {
+        return denied;
+    }
+    let issued_on = request.issued_on.unwrap_or_else(|| Utc::now().date_naive());
+    let issued = ControlledPrintRepo::new(&state.database).issue(
+        &document_id,
+        &request.recipient,
+        issued_on,
+        &principal.user_id,
+    );
+    match issued {
+        Ok(print) => (StatusCode::CREATED, Json(print)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Handler for `GET /controlled_prints` – unreconciled prints per document,
+/// with those of superseded revisions flagged for recall.
+async fn get_open_prints(State(state): State<ApiState>) -> impl IntoResponse {
+    match ControlledPrintRepo::new(&state.database).open_prints_report() {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Body of `POST /controlled_prints/:print_id/reconcile`.
+#[derive(Debug, Deserialize)]
+pub struct PrintReconciliationRequest {
+    /// `returned` or `destroyed`
+    pub disposition: PrintDisposition,
+    /// Today when absent
+    #[serde(default)]
+    pub reconciled_on: Option<NaiveDate>,
+}
+
+/// Handler for `POST /controlled_prints/:print_id/reconcile` – record the
+/// return or destruction of a print.
+async fn reconcile_controlled_print(
+    State(state): State<ApiState>,
+    Extension(principal): Extension<ApiPrincipal>,
+    Path(print_id): Path<Uuid>,
+    Json(request): Json<PrintReconciliationRequest>,
+) -> impl IntoResponse {
+    let resource = format!("controlled_print:{}", print_id);
+    if let Err(denied) = authorize(&state, &principal, Permission::WriteRecords, &resource) {
+        return denied;
+    }
+    let reconciled_on = request.reconciled_on.unwrap_or_else(|| Utc::now().date_naive());
+    let result = ControlledPrintRepo::new(&state.database).reconcile(
+        print_id,
+        request.disposition,
+        reconciled_on,
+        &principal.user_id,
+    );
+    match result {
+        Ok(print) => (StatusCode::OK, Json(print)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Body of `POST /tasks`.
+#[derive(Debug, Deserialize)]
+pub struct TaskRequest {
+    pub title: String,
+    /// `investigation`, `review`, `verification`, `follow_up` or `other`
+    pub kind: TaskKind,
+    pub due_date: NaiveDate,
+    pub assignee: String,
+    /// Record the task concerns, as `type:id`
+    #[serde(default)]
+    pub linked_record: Option<String>,
+}
+
+/// Handler for `POST /tasks` – assign an ad hoc quality task.
+async fn create_task(
+    State(state): State<ApiState>,
+    Extension(principal): Extension<ApiPrincipal>,
+    Json(request): Json<TaskRequest>,
+) -> impl IntoResponse {
+    if let Err(denied) = authorize(&state, &principal, Permission::WriteRecords, "tasks") {
+        return denied;
+    }
+    let linked_record = request.linked_record.as_deref().and_then(|linked| RecordRef::parse(linked).ok());
+    if let Some(hidden) = linked_record.and_then(|linked| hidden_record(&state, &linked.record_type)) {
+        return hidden;
+    }
+    let created = request.linked_record.as_deref().map(RecordRef::parse).transpose().and_then(|linked| {
+        TaskRepo::new(&state.database).create(
+            &request.title,
+            request.kind,
+            request.due_date,
+            linked.as_ref(),
+            &request.assignee,
+            &principal.user_id,
+        )
+    });
+    match created {
+        Ok(task) => (StatusCode::CREATED, Json(task)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Handler for `POST /tasks/:task_id/complete`.
+async fn complete_task(
+    State(state): State<ApiState>,
+    Extension(principal): Extension<ApiPrincipal>,
+    Path(task_id): Path<Uuid>,
+) -> impl IntoResponse {
+    if let Err(denied) = authorize(&state, &principal, Permission::WriteRecords, &format!("task:{}", task_id)) {
+        return denied;
+    }
+    let tasks = TaskRepo::new(&state.database);
+    let linked = tasks.get(task_id).ok().and_then(|task| task.linked_record);
+    if let Some(hidden) = linked.and_then(|linked| hidden_record(&state, &linked.record_type)) {
+        return hidden;
+    }
+    match tasks.complete(task_id, &principal.user_id) {
+        Ok(task) => (StatusCode::OK, Json(task)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Handler for `GET /inbox` – open work assigned to the calling user.
+async fn get_inbox(State(state): State<ApiState>, Extension(principal): Extension<ApiPrincipal>) -> impl IntoResponse {
+    match TaskInbox::new(&state.database).for_user(&principal.user_id, Utc::now().date_naive()) {
+        Ok(mut items) => {
+            items.retain(|item| state.features.allows_record_type(&item.record.record_type));
+            (StatusCode::OK, Json(items)).into_response()
+        }
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Query parameters for `GET /events`.
+#[derive(Debug, Deserialize)]
+pub struct EventLogQuery {
+    /// Only events after this sequence number; from the start when absent
+    pub after: Option<i64>,
+    /// Only events with this name, e.g. `capa.closed`
+    pub name: Option<String>,
+    /// Maximum number of events (default 100, at most 1000)
+    pub limit: Option<usize>,
+}
+
+/// Handler for `GET /events?after=..&name=..&limit=..` – the persisted
+/// domain event log in sequence order, for consumers catching up.
+async fn list_events(State(state): State<ApiState>, Query(query): Query<EventLogQuery>) -> impl IntoResponse {
+    let limit = query.limit.unwrap_or(100).min(1000);
+    let log = EventLog::new(state.database.clone());
+    // Events of disabled modules are skipped; read on so a page is not cut short by them
+    let mut after = query.after.unwrap_or(0);
+    let mut events = Vec::new();
+    loop {
+        let chunk = match log.after(after, query.name.as_deref(), limit) {
+            Ok(chunk) => chunk,
+            Err(e) => return error_response(state.locale, e),
+        };
+        let exhausted = chunk.len() < limit;
+        after = chunk.last().map_or(after, |stored| stored.sequence);
+        events.extend(chunk.into_iter().filter(|stored| state.features.allows_event(&stored.event)));
+        if exhausted || events.len() >= limit {
+            break;
+        }
+    }
+    events.truncate(limit);
+    (StatusCode::OK, Json(events)).into_response()
+}
+
+/// Query parameters for `GET /sites/:site_id/effective_documents`.
+#[derive(Debug, Deserialize)]
+pub struct EffectiveDocumentsQuery {
+    /// Only revisions applying to this product
+    pub product: Option<String>,
+    /// Day of effectivity; today when absent
+    pub on: Option<NaiveDate>,
+}
+
+/// Handler for `GET /sites/:site_id/effective_documents?product=..&on=..` –
+/// the document revisions in effect at a site.
+async fn list_effective_documents(
+    State(state): State<ApiState>,
+    Path(site_id): Path<String>,
+    Query(query): Query<EffectiveDocumentsQuery>,
+) -> impl IntoResponse {
+    let on = query.on.unwrap_or_else(|| Utc::now().date_naive());
+    match DocumentEffectivityRepo::new(&state.database).effective_at(&site_id, query.product.as_deref(), on) {
+        Ok(documents) => (StatusCode::OK, Json(documents)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Body of `POST /suppliers/:supplier_id/qualify` and `/disqualify`.
+#[derive(Debug, Deserialize)]
+pub struct SupplierDecisionRequest {
+    pub reason: String,
+    /// Qualification expiry; ignored when disqualifying
+    #[serde(default)]
+    pub expiry_date: Option<NaiveDate>,
+}
+
+/// Handler for `GET /suppliers/:supplier_id` – the supplier with its row
+/// version as `ETag`.
+async fn get_supplier(State(state): State<ApiState>, Path(supplier_id): Path<Uuid>) -> impl IntoResponse {
+    let found = state.supplier_service.get_supplier(&supplier_id).and_then(|supplier| {
+        supplier.map(|s| RecordDetail::load(&state.database, "suppliers", &supplier_id.to_string(), s)).transpose()
+    });
+    match found {
+        Ok(Some(detail)) => with_etag(detail.record.row_version, Json(detail)),
+        Ok(None) => {
+            error_response(state.locale, QmsError::NotFound { resource: "supplier".to_string(), id: supplier_id.to_string() })
+        }
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Handler for `GET /suppliers/:supplier_id/documents` – the current version
+/// of each document the supplier provided.
+async fn list_supplier_documents(State(state): State<ApiState>, Path(supplier_id): Path<Uuid>) -> impl IntoResponse {
+    match SupplierDocumentRepo::new(&state.database, &state.file_store).documents(supplier_id) {
+        Ok(documents) => (StatusCode::OK, Json(documents)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Query parameters for `POST /suppliers/:supplier_id/documents`.
+#[derive(Debug, Deserialize)]
+pub struct SupplierDocumentQuery {
+    /// `iso_certificate`, `quality_agreement`, `data_processing_agreement` or `other`
+    pub kind: SupplierDocumentKind,
+    /// Uploads with the same kind and title are versions of one document
+    pub title: String,
+    pub file_name: String,
+    pub expires_on: Option<NaiveDate>,
+}
+
+/// Handler for `POST /suppliers/:supplier_id/documents?kind=..&title=..&file_name=..&expires_on=..`
+/// – the raw file as body, stored as the next version of the document.
+async fn upload_supplier_document(
+    State(state): State<ApiState>,
+    Extension(principal): Extension<ApiPrincipal>,
+    Path(supplier_id): Path<Uuid>,
+    Query(query): Query<SupplierDocumentQuery>,
+    body: Bytes,
+) -> impl IntoResponse {
+    if let Err(denied) = authorize(&state, &principal, Permission::WriteRecords, &format!("supplier:{}", supplier_id)) {
+        return denied;
+    }
+    let result = SupplierDocumentRepo::new(&state.database, &state.file_store).upload(
+        supplier_id,
+        query.kind,
+        &query.title,
+        &query.file_name,
+        &body,
+        query.expires_on,
+        &principal.user_id,
+        Utc::now(),
+    );
+    match result {
+        Ok(document) => (StatusCode::CREATED, Json(document)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Handler for `POST /suppliers/:supplier_id/qualify`.
+async fn qualify_supplier(
+    State(state): State<ApiState>,
+    Extension(principal): Extension<ApiPrincipal>,
+    Path(supplier_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(request): Json<SupplierDecisionRequest>,
+) -> impl IntoResponse {
+    decide_supplier(state, principal, supplier_id, headers, request, true)
+}
+
+/// Handler for `POST /suppliers/:supplier_id/disqualify`.
+async fn disqualify_supplier(
+    State(state): State<ApiState>,
+    Extension(principal): Extension<ApiPrincipal>,
+    Path(supplier_id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(request): Json<SupplierDecisionRequest>,
+) -> impl IntoResponse {
+    decide_supplier(state, principal, supplier_id, headers, request, false)
+}
+
+/// Qualify or disqualify the supplier version named by `If-Match`; a
+/// supplier changed in the meantime (e.g. from the TUI) yields 412.
+fn decide_supplier(
+    state: ApiState,
+    principal: ApiPrincipal,
+    supplier_id: Uuid,
+    headers: HeaderMap,
+    request: SupplierDecisionRequest,
+    qualify: bool,
+) -> Response {
+    if let Err(denied) = authorize(&state, &principal, Permission::WriteRecords, &format!("supplier:{}", supplier_id)) {
+        return denied;
+    }
+    let expected = match expected_version(state.locale, &headers) {
+        Ok(version) => version,
+        Err(response) => return response,
+    };
+    let result = ChangeReason::new(request.reason).and_then(|reason| {
+        let mut supplier = state.supplier_service.get_supplier(&supplier_id)?.ok_or_else(|| QmsError::NotFound {
+            resource: "supplier".to_string(),
+            id: supplier_id.to_string(),
+        })?;
+        supplier.row_version = expected;
+        let service = &state.supplier_service;
+        if qualify {
+            service.qualify_supplier(&mut supplier, principal.user_id.clone(), request.expiry_date, &reason)?;
+        } else {
+            service.disqualify_supplier(&mut supplier, principal.user_id.clone(), &reason)?;
+        }
+        Ok(supplier)
+    });
+    match result {
+        Ok(supplier) => with_etag(supplier.row_version, Json(supplier)),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Version a mutation was based on, from the `If-Match` header. Requests
+/// without one are refused with 428 so that no update can skip the check.
+fn expected_version(locale: Locale, headers: &HeaderMap) -> Result<i64, Response> {
+    headers
+        .get(IF_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(concurrency::parse_if_match)
+        .ok_or_else(|| {
+            ApiError::new("PRECONDITION_REQUIRED", ErrorSeverity::Medium, tr(locale, "api.precondition_required").to_string())
+                .into_response_with(StatusCode::PRECONDITION_REQUIRED)
+        })
+}
+
+fn with_etag(version: i64, response: impl IntoResponse) -> Response {
+    let mut response = response.into_response();
+    if let Ok(value) = HeaderValue::from_str(&concurrency::etag(version)) {
+        response.headers_mut().insert(ETAG, value);
+    }
+    response
+}
+
+/// Handler for `GET /jobs` (status of every background job).
+async fn list_jobs(State(state): State<ApiState>) -> impl IntoResponse {
+    match state.jobs.statuses(Utc::now()) {
+        Ok(statuses) => (StatusCode::OK, Json(statuses)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Handler for `GET /health/compliance`: the startup self-test checklist,
+/// run now. Answers 503 while any check fails so monitors can alert.
+async fn get_compliance_health(State(state): State<ApiState>) -> impl IntoResponse {
+    let clock = state.clock_guard.as_ref().and_then(SignatureGuard::last_check);
+    let checklist = self_test::run_self_test(&state.database, &state.self_test, clock.as_ref(), Utc::now());
+    let status = if checklist.all_passed() { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(checklist)).into_response()
+}
+
+/// Handler for `POST /jobs/:job/run`; runs the job now and returns the run.
+async fn run_job(
+    State(state): State<ApiState>,
+    Extension(principal): Extension<ApiPrincipal>,
+    Path(job): Path<String>,
+) -> impl IntoResponse {
+    if let Err(denied) = authorize(&state, &principal, Permission::RunJobs, &format!("job:{}", job)) {
+        return denied;
+    }
+    let kind = match job.parse::<JobKind>() {
+        Ok(kind) => kind,
+        Err(e) => return error_response(state.locale, e),
+    };
+    let jobs = state.jobs.clone();
+    let user_id = principal.user_id.clone();
+    match tokio::task::spawn_blocking(move || jobs.run(kind, JobTrigger::Manual, &user_id)).await {
+        Ok(Ok(run)) => (StatusCode::OK, Json(run)).into_response(),
+        Ok(Err(e)) => error_response(state.locale, e),
+        Err(e) => error_response(state.locale, QmsError::Application { message: e.to_string() }),
+    }
+}
+
+/// Body of `POST /legal_holds`.
+#[derive(Debug, Deserialize)]
+pub struct LegalHoldRequest {
+    pub record_table: String,
+    /// Omit to hold every record of `record_table`
+    #[serde(default)]
+    pub record_id: Option<String>,
+    pub reason: String,
+}
+
+/// Body of `POST /legal_holds/:hold_id/release`.
+#[derive(Debug, Deserialize)]
+pub struct LegalHoldReleaseRequest {
+    pub reason: String,
+}
+
+/// Handler for `GET /legal_holds` (active holds).
+async fn list_legal_holds(State(state): State<ApiState>) -> impl IntoResponse {
+    match LegalHoldService::new(&state.database).active() {
+        Ok(holds) => (StatusCode::OK, Json(holds)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Handler for `POST /legal_holds` – administrators only.
+async fn place_legal_hold(
+    State(state): State<ApiState>,
+    Extension(principal): Extension<ApiPrincipal>,
+    Json(request): Json<LegalHoldRequest>,
+) -> impl IntoResponse {
+    if let Err(denied) = authorize(&state, &principal, Permission::ManageLegalHolds, "legal_holds") {
+        return denied;
+    }
+    let result = ChangeReason::new(request.reason).and_then(|reason| {
+        LegalHoldService::new(&state.database).place(
+            &request.record_table,
+            request.record_id.as_deref(),
+            &principal.user_id,
+            &reason,
+        )
+    });
+    match result {
+        Ok(hold) => (StatusCode::CREATED, Json(hold)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Handler for `POST /legal_holds/:hold_id/release` – administrators only.
+async fn release_legal_hold(
+    State(state): State<ApiState>,
+    Extension(principal): Extension<ApiPrincipal>,
+    Path(hold_id): Path<Uuid>,
+    Json(request): Json<LegalHoldReleaseRequest>,
+) -> impl IntoResponse {
+    if let Err(denied) = authorize(&state, &principal, Permission::ManageLegalHolds, &format!("legal_hold:{}", hold_id)) {
+        return denied;
+    }
+    let result = ChangeReason::new(request.reason)
+        .and_then(|reason| LegalHoldService::new(&state.database).release(hold_id, &principal.user_id, &reason));
+    match result {
+        Ok(hold) => (StatusCode::OK, Json(hold)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Query parameters for `POST /test_results`.
+#[derive(Debug, Deserialize)]
+pub struct TestResultQuery {
+    /// `capa_verification`, `validation` or `incoming_inspection`
+    pub target: EvidenceTarget,
+    pub record_id: String,
+    /// Sending lab system or test station
+    pub source: String,
+}
+
+/// Handler for `POST /test_results?target=..&record_id=..&source=..` – a CSV
+/// or JSON export from a lab system, attached as objective evidence.
+async fn ingest_test_results(
+    State(state): State<ApiState>,
+    Extension(principal): Extension<ApiPrincipal>,
+    Query(query): Query<TestResultQuery>,
+    headers: HeaderMap,
+    body: String,
+) -> impl IntoResponse {
+    if let Some(hidden) = hidden_record(&state, &query.target.record(&query.record_id).record_type) {
+        return hidden;
+    }
+    let resource = format!("{}:{}", query.target.as_str(), query.record_id);
+    if let Err(denied) = authorize(&state, &principal, Permission::WriteRecords, &resource) {
+        return denied;
+    }
+    let content_type = headers.get(CONTENT_TYPE).and_then(|value| value.to_str().ok());
+    let format = ResultFormat::detect(content_type, &body);
+    let result = TestResultIngest::new(&state.database).ingest(
+        &body,
+        format,
+        &query.source,
+        query.target,
+        &query.record_id,
+        &principal.user_id,
+        Utc::now(),
+    );
+    match result {
+        Ok(batch) => (StatusCode::CREATED, Json(batch)).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Generate and distribute the scheduled reports due at the time of the run,
+/// from the records held by `state`.
+fn report_generation_job(
+    state: ApiState,
+    config: &Config,
+) -> impl Fn(DateTime<Utc>) -> crate::Result<String> + Send + Sync {
+    let data_directory = PathBuf::from(&config.application.data_directory);
+    let notifications = config.notifications.clone();
+    move |now| {
+        // Jobs run on blocking threads, outside the API's runtime
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| QmsError::Application { message: e.to_string() })?;
+        let metrics = runtime.block_on(compute_metrics(&state, now))?;
+        let capa_records = state.capa_records.read().unwrap().clone();
+        let audit_findings = FindingRepo::new(&state.database).open_findings()?;
+        let clinical_evaluations = ClinicalRepo::new(&state.database).summaries()?;
+        let cause_pareto = ParetoAnalysis::new(&state.database).management_review(now)?;
+        let cost_of_quality = QualityCostRepo::new(&state.database).quarterly(REVIEW_QUARTERS, now.date_naive())?;
+
+        let store = FileStore::for_data_dir(&data_directory);
+        let key_path = Keystore::keys_dir(&data_directory).join(SYSTEM_KEY_FILE);
+        let keystore = if key_path.exists() { Some(Keystore::open(&key_path)?) } else { None };
+        let notifier = OutboxNotifier::from_config(&notifications);
+        let mut scheduler = ReportScheduler::new(&state.database, &store);
+        if let Some(notifier) = &notifier {
+            scheduler = scheduler.with_notifier(notifier);
+        }
+        if let Some(keystore) = &keystore {
+            scheduler = scheduler.with_keystore(keystore);
+        }
+        let data = ReportData {
+            capa_records: &capa_records,
+            metrics: &metrics,
+            audit_findings: &audit_findings,
+            clinical_evaluations: &clinical_evaluations,
+            cause_pareto: Some(&cause_pareto),
+            cost_of_quality: Some(&cost_of_quality),
+        };
+        let runs = scheduler.run_due(now, &data)?;
+        let failed = runs.iter().filter(|run| run.status == RunStatus::Failed).count();
+        Ok(format!("{} report(s) generated; {} failed", runs.len() - failed, failed))
+    }
+}
+
+/// Check that the caller's role grants `permission`. Denials are recorded in
+/// the audit trail against `resource` and answered with 403.
+fn authorize(state: &ApiState, principal: &ApiPrincipal, permission: Permission, resource: &str) -> Result<(), Response> {
+    if principal.role.has_permission(permission) {
+        return Ok(());
+    }
+    let metadata = serde_json::json!({ "permission": permission.as_str(), "role": principal.role.as_str() });
+    if let Err(e) = AuditManager::new(state.database.clone()).log_action(
+        &principal.user_id,
+        "api_access_denied",
+        resource,
+        "Failure",
+        Some(metadata.to_string()),
+    ) {
+        return Err(error_response(state.locale, e));
+    }
+    tracing::warn!(user = %principal.user_id, permission = permission.as_str(), "API request denied");
+    Err(ApiError::new("FORBIDDEN", ErrorSeverity::High, tr(state.locale, "api.forbidden").to_string())
+        .into_response_with(StatusCode::FORBIDDEN))
+}
+
+/// Cookie carrying the session id of a browser client.
+pub const SESSION_COOKIE: &str = "qms_session";
+
+/// Header in which cookie-authenticated clients echo their session's CSRF
+/// token on state-changing requests.
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
+/// Body of `POST /login`.
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+/// Response of `POST /login`; the session id itself is only sent as an
+/// HttpOnly cookie.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginResponse {
+    pub user_id: String,
+    pub role: UserRole,
+    /// Echo in the `x-csrf-token` header on POST/PUT/PATCH/DELETE
+    pub csrf_token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Session id from the request's `Cookie` header.
+fn session_cookie(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|pair| pair.trim().strip_prefix(SESSION_COOKIE)?.strip_prefix('=').map(str::to_string))
+        .filter(|id| !id.is_empty())
+}
+
+/// `Set-Cookie` value for the session; `secure` keeps browsers from sending
+/// it over plain HTTP.
+fn set_session_cookie(session_id: &str, max_age_seconds: i64, secure: bool) -> String {
+    let secure = if secure { "; Secure" } else { "" };
+    format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Strict; Max-Age={}{}",
+        SESSION_COOKIE,
+        session_id,
+        max_age_seconds.max(0),
+        secure
+    )
+}
+
+/// Whether a cookie-authenticated request carries its session's CSRF token.
+/// Safe methods need none; everything else must echo it (synchronizer token
+/// pattern), on top of the `SameSite=Strict` cookie. The token is compared
+/// in constant time.
+fn csrf_satisfied(method: &Method, headers: &HeaderMap, csrf_token: &str) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+        || headers.get(CSRF_HEADER).is_some_and(|value| {
+            ring::constant_time::verify_slices_are_equal(value.as_bytes(), csrf_token.as_bytes()).is_ok()
+        })
+}
+
+fn unauthorized_response(locale: Locale) -> Response {
+    ApiError::new("UNAUTHORIZED", ErrorSeverity::Medium, tr(locale, "api.unauthorized").to_string())
+        .into_response_with(StatusCode::UNAUTHORIZED)
+}
+
+/// Audit and reject a cookie request without a valid CSRF token.
+fn csrf_rejected(state: &ApiState, user_id: &str) -> Response {
+    if let Err(e) = AuditManager::new(state.database.clone()).log_action(
+        user_id,
+        "csrf_rejected",
+        &format!("user:{}", user_id),
+        "Failure",
+        None,
+    ) {
+        return error_response(state.locale, e);
+    }
+    ApiError::new("FORBIDDEN", ErrorSeverity::High, tr(state.locale, "api.csrf_rejected").to_string())
+        .into_response_with(StatusCode::FORBIDDEN)
+}
+
+/// Handler for `POST /login` – password login for browser clients. Sets the
+/// session cookie and returns the CSRF token.
+async fn login(State(state): State<ApiState>, Json(request): Json<LoginRequest>) -> impl IntoResponse {
+    let session = state.security.login(&state.database, &request.username, &request.password, None);
+    let session = match session {
+        Ok(session) => session,
+        Err(QmsError::Security { .. }) => return unauthorized_response(state.locale),
+        Err(e) => return error_response(state.locale, e),
+    };
+    session_response(&state, session)
+}
+
+/// Login response for a new session: cookie plus CSRF token.
+fn session_response(state: &ApiState, session: Session) -> Response {
+    let role = match UserRole::of_user(&state.database, &session.user_id) {
+        Ok(Some(role)) => role,
+        Ok(None) => return unauthorized_response(state.locale),
+        Err(e) => return error_response(state.locale, e),
+    };
+    let max_age = (session.expires_at - Utc::now()).num_seconds();
+    let cookie = set_session_cookie(&session.id, max_age, state.secure_cookies);
+    let principal = ApiPrincipal { user_id: session.user_id.clone(), role };
+    let body = LoginResponse { user_id: session.user_id, role, csrf_token: session.csrf_token, expires_at: session.expires_at };
+    (StatusCode::OK, [(SET_COOKIE, cookie)], Extension(principal), Json(body)).into_response()
+}
+
+/// Query parameters of the SSO provider's redirect to `GET /auth/oidc/callback`.
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackQuery {
+    pub state: String,
+    pub code: Option<String>,
+    /// Set by the provider when the user could not be signed in
+    pub error: Option<String>,
+}
+
+fn sso_unavailable(locale: Locale) -> Response {
+    error_response(locale, QmsError::NotFound { resource: "sso_provider".to_string(), id: "oidc".to_string() })
+}
+
+/// Handler for `GET /auth/oidc/login` – redirects the browser to the SSO provider.
+async fn oidc_login(State(state): State<ApiState>) -> impl IntoResponse {
+    let Some(client) = state.oidc.clone() else {
+        return sso_unavailable(state.locale);
+    };
+    match client.authorization_url(Utc::now()) {
+        Ok(url) => (StatusCode::FOUND, [(LOCATION, url)]).into_response(),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Handler for `GET /auth/oidc/callback` – completes the SSO login, maps the
+/// identity to a local user and opens a cookie session.
+async fn oidc_callback(State(state): State<ApiState>, Query(query): Query<OidcCallbackQuery>) -> impl IntoResponse {
+    let Some(client) = state.oidc.clone() else {
+        return sso_unavailable(state.locale);
+    };
+    let identity = match (query.error, query.code) {
+        (None, Some(code)) => client.complete(&code, &query.state).await,
+        (error, _) => Err(QmsError::Security {
+            message: format!("SSO provider returned {}", error.as_deref().unwrap_or("no authorization code")),
+        }),
+    };
+    let user_id = identity.and_then(|identity| oidc::provision_user(&state.database, client.config(), &identity));
+    let user_id = match user_id {
+        Ok(user_id) => user_id,
+        Err(QmsError::Security { message }) => {
+            tracing::warn!("SSO login rejected: {message}");
+            let audit = AuditManager::new(state.database.clone());
+            if let Err(e) = audit.log_action("sso", "sso_login", "authentication", "Failure", Some(message)) {
+                return error_response(state.locale, e);
+            }
+            return unauthorized_response(state.locale);
+        }
+        Err(e) => return error_response(state.locale, e),
+    };
+    let session = state.security.open_session(&state.database, &user_id, None, "oidc");
+    match session {
+        Ok(session) => session_response(&state, session),
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Handler for `POST /logout` – ends the cookie session and clears the cookie.
+async fn logout(State(state): State<ApiState>, headers: HeaderMap) -> impl IntoResponse {
+    let Some(session_id) = session_cookie(&headers) else {
+        return unauthorized_response(state.locale);
+    };
+    let security = &state.security;
+    let session = match security.resume_session(&state.database, &session_id) {
+        Ok(Some(session)) => session,
+        Ok(None) => return unauthorized_response(state.locale),
+        Err(e) => return error_response(state.locale, e),
+    };
+    if !csrf_satisfied(&Method::POST, &headers, &session.csrf_token) {
+        return csrf_rejected(&state, &session.user_id);
+    }
+    match security.logout(&state.database, &session_id) {
+        Ok(()) => {
+            (StatusCode::NO_CONTENT, [(SET_COOKIE, set_session_cookie("", 0, state.secure_cookies))]).into_response()
+        }
+        Err(e) => error_response(state.locale, e),
+    }
+}
+
+/// Resolve a cookie session into its principal.
+fn session_principal<B>(state: &ApiState, req: &Request<B>, session_id: &str) -> Result<ApiPrincipal, Response> {
+    let session = state.security.resume_session(&state.database, session_id);
+    let session = match session {
+        Ok(Some(session)) => session,
+        Ok(None) => return Err(unauthorized_response(state.locale)),
+        Err(e) => return Err(error_response(state.locale, e)),
+    };
+    if !csrf_satisfied(req.method(), req.headers(), &session.csrf_token) {
+        return Err(csrf_rejected(state, &session.user_id));
+    }
+    match UserRole::of_user(&state.database, &session.user_id) {
+        Ok(Some(role)) => Ok(ApiPrincipal { user_id: session.user_id, role }),
+        Ok(None) => Err(unauthorized_response(state.locale)),
+        Err(e) => Err(error_response(state.locale, e)),
+    }
+}
+
+/// Middleware: Enforces Bearer token or session cookie authentication and
+/// scope validation, and attaches the caller's [`ApiPrincipal`] to the
+/// request.
+async fn token_auth<B>(
+    State(state): State<ApiState>,
+    mut req: Request<B>,
+    next: Next<B>,
+) -> impl IntoResponse {
+    // Extract token from `Authorization: Bearer <token>` header
+    let locale = state.locale;
+    let unauthorized = || unauthorized_response(locale);
+    let principal = match req.headers().get(AUTHORIZATION) {
+        // Browser clients authenticate with the session cookie instead
+        None => match session_cookie(req.headers()) {
+            Some(session_id) => match session_principal(&state, &req, &session_id) {
+                Ok(principal) => principal,
+                Err(rejected) => return rejected,
+            },
+            None => return unauthorized(),
+        },
+        Some(header_val) => {
+            let Ok(auth_str) = header_val.to_str() else {
+                return unauthorized();
+            };
+            let token = auth_str.strip_prefix("Bearer ").unwrap_or("");
+            let Some(stored) = state.token_manager.lookup(token, REQUIRED_SCOPE) else {
+                return unauthorized();
+            };
+            match stored.user_id {
+                None => ApiPrincipal::service(),
+                // Tokens of unknown or deactivated users are rejected
+                Some(user_id) => match UserRole::of_user(&state.database, &user_id) {
+                    Ok(Some(role)) => ApiPrincipal { user_id, role },
+                    Ok(None) => return unauthorized(),
+                    Err(e) => return error_response(locale, e),
+                },
+            }
+        }
+    };
+    req.extensions_mut().insert(principal.clone());
+    let mut response = next.run(req).await;
+    // Lets outer middleware (request audit) see who made the request
+    response.extensions_mut().insert(principal);
+    response
+}
+
+/// Middleware: Records API mutations in the audit trail with actor, route,
+/// outcome, latency and a redacted payload summary (see [`request_audit`]).
+async fn audit_mutations(State(state): State<ApiState>, req: Request<Body>, next: Next<Body>) -> Response {
+    let config = &state.request_audit;
+    let class = RouteClass::of(req.uri().path());
+    let read_only = request_audit::READ_ONLY_ROUTES.contains(&req.uri().path());
+    if !request_audit::is_mutation(req.method()) || read_only || !config.audits(class) {
+        return next.run(req).await;
+    }
+    let method = req.method().clone();
+    let path = req.uri().path().to_string();
+    let route = req.extensions().get::<MatchedPath>().map(|m| m.as_str().to_string()).unwrap_or_else(|| path.clone());
+    let content_type = req.headers().get(CONTENT_TYPE).and_then(|v| v.to_str().ok()).map(str::to_string);
+    let captured = req.body().size_hint().exact().filter(|len| *len <= request_audit::MAX_CAPTURED_BODY_BYTES);
+
+    let (req, payload) = if captured.is_some() {
+        let (parts, body) = req.into_parts();
+        let bytes = match hyper::body::to_bytes(body).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                return ApiError::new("BAD_REQUEST", ErrorSeverity::Low, e.to_string()).into_response_with(StatusCode::BAD_REQUEST)
+            }
+        };
+        let payload = request_audit::summarize_payload(&bytes, content_type.as_deref(), &config.redacted_fields, config.max_payload_chars);
+        (Request::from_parts(parts, Body::from(bytes)), payload)
+    } else {
+        (req, "<body not captured>".to_string())
+    };
+
+    let started = std::time::Instant::now();
+    let response = next.run(req).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let actor = response.extensions().get::<ApiPrincipal>().map(|p| p.user_id.clone()).unwrap_or_else(|| "anonymous".to_string());
+    let status = response.status();
+    let metadata = serde_json::json!({
+        "method": method.as_str(),
+        "route": route,
+        "class": class.as_str(),
+        "status": status.as_u16(),
+        "latency_ms": latency_ms,
+        "payload": payload,
+        "correlation_id": current_correlation_id(),
+    });
+    let outcome = if status.is_client_error() || status.is_server_error() { "Failure" } else { "Success" };
+    if let Err(e) = AuditManager::new(state.database.clone()).log_action(
+        &actor,
+        "api_request",
+        &format!("api:{}", path),
+        outcome,
+        Some(metadata.to_string()),
+    ) {
+        // The mutation already happened; a missing entry must be visible
+        tracing::error!(%path, "API request audit entry could not be written: {e}");
+    }
+    response
+}
+
+/// Middleware: Refuses requests from addresses outside `[api.network]`
+/// before authentication, and audits each refusal.
+async fn network_guard<B>(State(state): State<ApiState>, req: Request<B>, next: Next<B>) -> Response {
+    let peer = req.extensions().get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| addr.ip());
+    match state.network_policy.check(peer) {
+        Ok(()) => next.run(req).await,
+        Err(reason) => {
+            log_network_rejection(&state.database, peer, req.uri().path(), &reason);
+            ApiError::new("FORBIDDEN", ErrorSeverity::High, tr(state.locale, "api.network_rejected").to_string())
+                .into_response_with(StatusCode::FORBIDDEN)
+        }
+    }
+}
+
+/// Middleware: Answers 404 for the routes of a module switched off by a
+/// feature flag, as if the route did not exist.
+async fn feature_gate<B>(State(state): State<ApiState>, req: Request<B>, next: Next<B>) -> Response {
+    match Feature::for_api_path(req.uri().path()) {
+        Some(feature) if !state.features.is_enabled(feature) => StatusCode::NOT_FOUND.into_response(),
+        _ => next.run(req).await,
+    }
+}
+
+/// 404 for a request about a record of a disabled module, as [`feature_gate`]
+/// answers for the module's own routes.
+fn hidden_record(state: &ApiState, record_type: &str) -> Option<Response> {
+    (!state.features.allows_record_type(record_type)).then(|| StatusCode::NOT_FOUND.into_response())
+}
+
+/// Security audit entry for a request or TLS handshake the network policy refused.
+pub(crate) fn log_network_rejection(database: &Database, peer: Option<IpAddr>, path: &str, reason: &str) {
+    let peer = peer.map_or_else(|| "unknown".to_string(), |ip| ip.to_string());
+    tracing::warn!(%peer, %path, "API connection refused: {reason}");
+    let metadata = serde_json::json!({ "peer": peer, "reason": reason, "correlation_id": current_correlation_id() });
+    if let Err(e) = AuditManager::new(database.clone()).log_action(
+        "anonymous",
+        "api_connection_rejected",
+        &format!("api:{}", path),
+        "Failure",
+        Some(metadata.to_string()),
+    ) {
+        tracing::error!(%path, "API rejection audit entry could not be written: {e}");
+    }
+}
+
+/// Middleware: Tags each request with a correlation id – the caller's
+/// `x-correlation-id` or a fresh UUID – that is echoed on the response,
+/// included in error bodies and attached to every log line of the request.
+async fn correlation_id<B>(req: Request<B>, next: Next<B>) -> Response {
+    let id = req
+        .headers()
+        .get(CORRELATION_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty() && value.len() <= 128)
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let span = tracing::info_span!("api_request", correlation_id = %id, method = %req.method(), path = %req.uri().path());
+    let mut response = CORRELATION_ID.scope(id.clone(), next.run(req).instrument(span)).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(CORRELATION_ID_HEADER, value);
+    }
+    response
+}
+
+/// Build an Axum router with all API routes registered.
+pub fn router() -> Router {
+    let state = ApiState::new();
+
+    // For demonstration, generate a default token valid for 24 hours with metrics scope.
+    let default_token = Uuid::new_v4().to_string();
+    state.token_manager.insert_token(default_token.clone(), 60 * 24, vec!["metrics:read".to_string()]);
+    tracing::info!(%default_token, "API authentication token generated");
+
+    router_with_state(state)
+}
+
+/// Build the router over caller-provided state.
+pub fn router_with_state(state: ApiState) -> Router {
+    Router::new()
+        .route("/metrics", get(get_metrics))
+        .route("/metrics/prometheus", get(get_prometheus_metrics))
+        .route("/summaries", get(get_summaries))
+        .route("/audit_trail/export", get(export_audit_trail))
+        .route("/supplier_metrics", get(get_supplier_metrics))
+        .route("/training_metrics", get(get_training_metrics))
+        .route("/risk_heat_map", get(get_risk_heat_map))
+        .route("/analytics/pareto", get(get_pareto))
+        .route("/analytics/complaint_rates", get(get_complaint_rates))
+        .route("/capas", get(get_capas))
+        .route("/capas/:reference", get(get_capa))
+        .route("/capas/:reference/status", post(update_capa_status))
+        .route("/capas/:reference/reassign", post(reassign_capa))
+        .route("/search", get(search_records))
+        .route("/records/:record_type/:record_id/history", get(get_record_history))
+        .route("/records/:record_type/:record_id/versions", get(get_record_versions))
+        .route("/records/:record_type/:record_id/as_of", get(get_record_as_of))
+        .route("/records/:record_type/:record_id/diff", get(get_record_diff))
+        .route("/records/:record_type/:record_id/links", get(get_record_links))
+        .route("/documents/:document_id", get(get_document))
+        .route("/documents/:document_id/approve", post(approve_document))
+        .route("/documents/:document_id/effectivity", get(list_document_effectivity).post(set_document_effectivity))
+        .route("/sites/:site_id/effective_documents", get(list_effective_documents))
+        .route("/documents/:document_id/prints", get(list_controlled_prints).post(issue_controlled_print))
+        .route("/controlled_prints", get(get_open_prints))
+        .route("/controlled_prints/:print_id/reconcile", post(reconcile_controlled_print))
+        .route("/tasks", post(create_task))
+        .route("/tasks/:task_id/complete", post(complete_task))
+        .route("/inbox", get(get_inbox))
+        .route("/events", get(list_events))
+        .route("/suppliers/:supplier_id", get(get_supplier))
+        .route("/suppliers/:supplier_id/documents", get(list_supplier_documents).post(upload_supplier_document))
+        .route("/suppliers/:supplier_id/qualify", post(qualify_supplier))
+        .route("/suppliers/:supplier_id/disqualify", post(disqualify_supplier))
+        .route("/jobs", get(list_jobs))
+        .route("/jobs/:job/run", post(run_job))
+        .route("/legal_holds", get(list_legal_holds).post(place_legal_hold))
+        .route("/legal_holds/:hold_id/release", post(release_legal_hold))
+        .route("/test_results", post(ingest_test_results))
+        .route("/health/compliance", get(get_compliance_health))
+        .route("/graphql", post(graphql_query))
+        .layer(middleware::from_fn_with_state(state.clone(), token_auth))
+        .route("/login", post(login))
+        .route("/logout", post(logout))
+        .route("/auth/oidc/login", get(oidc_login))
+        .route("/auth/oidc/callback", get(oidc_callback))
+        .layer(middleware::from_fn_with_state(state.clone(), audit_mutations))
+        .layer(middleware::from_fn_with_state(state.clone(), feature_gate))
+        .layer(middleware::from_fn_with_state(state.clone(), network_guard))
+        .layer(middleware::from_fn(correlation_id))
+        .layer(Extension(graphql::schema()))
+        .with_state(state)
+}
+
+/// Start the API server on the provided address (e.g., "127.0.0.1:3000").
+/// This is intended to run in a background Tokio task.
+pub async fn serve(addr: &str) -> Result<(), HyperError> {
+    let socket: SocketAddr = addr.parse().expect("invalid socket address");
+    let router = router();
+    axum::Server::bind(&socket)
+        .serve(router.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+}
+
+/// Serve `router` over TLS on `listener`; the peer address is attached to
+/// every request for the network policy. Failed handshakes, including
+/// missing or untrusted client certificates, are audited.
+async fn serve_tls(listener: tokio::net::TcpListener, acceptor: TlsAcceptor, router: Router, database: Database) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                tracing::warn!("API connection could not be accepted: {e}");
+                continue;
+            }
+        };
+        let (acceptor, router, database) = (acceptor.clone(), router.clone(), database.clone());
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(stream).await {
+                Ok(stream) => stream,
+                Err(e) => {
+                    log_network_rejection(&database, Some(peer.ip()), "/", &format!("TLS handshake failed: {e}"));
+                    return;
+                }
+            };
+            let service = router.layer(Extension(ConnectInfo(peer)));
+            if let Err(e) = hyper::server::conn::Http::new().serve_connection(stream, service).await {
+                tracing::debug!(%peer, "API connection closed: {e}");
+            }
+        });
+    }
+}
+
+/// Lifetime of the token handed to the in-process TUI (one year).
+const EMBEDDED_TOKEN_TTL_MINUTES: i64 = 60 * 24 * 365;
+
+/// API server running inside the application process on the application's
+/// own database, so the TUI and API clients see the same records.
+pub struct EmbeddedApi {
+    state: ApiState,
+    bind: SocketAddr,
+    token: String,
+    oidc: Option<crate::config::OidcConfig>,
+    tls: Option<TlsAcceptor>,
+}
+
+impl EmbeddedApi {
+    /// Prepare a server for the context's `api.bind` and issue a token for
+    /// the TUI.
+    pub fn new(context: &AppContext) -> crate::Result<Self> {
+        let config = &context.config.api;
+        let bind = config.bind.parse().map_err(|_| QmsError::Configuration {
+            message: format!("invalid api.bind address '{}'", config.bind),
+        })?;
+        let state = ApiState::from_context(context);
+        let token = Uuid::new_v4().to_string();
+        state.token_manager.insert_token(token.clone(), EMBEDDED_TOKEN_TTL_MINUTES, vec!["metrics:read".to_string()]);
+        let oidc = context.config.oidc.enabled.then(|| context.config.oidc.clone());
+        let tls = match config.tls.enabled {
+            true => Some(network_policy::tls_acceptor(&config.tls)?),
+            false => None,
+        };
+        Ok(Self { state, bind, token, oidc, tls })
+    }
+
+    /// Include the clock monitor's checks in `/health/compliance`.
+    pub fn with_clock_guard(mut self, guard: SignatureGuard) -> Self {
+        self.state = self.state.with_clock_guard(guard);
+        self
+    }
+
+    /// Base URL for in-process clients, e.g. `http://127.0.0.1:3000`.
+    pub fn base_url(&self) -> String {
+        let scheme = if self.tls.is_some() { "https" } else { "http" };
+        format!("{}://{}", scheme, self.bind)
+    }
+
+    /// Bearer token accepted by this server.
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Shared state, for serving the gRPC interface with the same tokens.
+    pub fn state(&self) -> &ApiState {
+        &self.state
+    }
+
+    /// Serve in a background task until the handle is aborted.
+    pub fn spawn(self) -> tokio::task::JoinHandle<()> {
+        let Self { mut state, bind, oidc, tls, .. } = self;
+        tokio::spawn(async move {
+            if let Some(config) = oidc {
+                match OidcClient::discover(config).await {
+                    Ok(client) => state = state.with_oidc(client),
+                    Err(e) => tracing::error!("OIDC discovery failed, SSO login unavailable: {e}"),
+                }
+            }
+            tracing::info!(%bind, tls = tls.is_some(), "Embedded API server listening");
+            if let Some(acceptor) = tls {
+                match tokio::net::TcpListener::bind(bind).await {
+                    Ok(listener) => {
+                        let database = state.database.clone();
+                        serve_tls(listener, acceptor, router_with_state(state), database).await
+                    }
+                    Err(e) => tracing::error!(%bind, "embedded API server could not bind: {e}"),
+                }
+                return;
+            }
+            let server = axum::Server::try_bind(&bind).map(|builder| {
+                builder.serve(router_with_state(state).into_make_service_with_connect_info::<SocketAddr>())
+            });
+            match server {
+                Ok(server) => {
+                    if let Err(e) = server.await {
+                        tracing::error!("embedded API server stopped: {e}");
+                    }
+                }
+                Err(e) => tracing::error!(%bind, "embedded API server could not bind: {e}"),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{Method, Request};
+    use hyper::Body;
+    use tower::ServiceExt; // for `oneshot`
+    use chrono::Utc;
+    use crate::capa::{CapaPriority, CapaStatus, CapaType, CAPA_CLOSURE_MEANING};
+    use crate::risk::{RiskSeverity, RiskProbability};
+    use axum::http::header::{AUTHORIZATION, HeaderValue};
+    use crate::supplier::{Supplier, SupplierStatus, SupplierMetrics};
+    use crate::training::{TrainingRecord, TrainingStatus, TrainingMetrics};
+    use crate::kpi::{KpiMetric, RagStatus};
+
+    /// Build a router and underlying state for test purposes (FIRST compliant).
+    async fn setup_test_router() -> (Router, ApiState) {
+        let state = ApiState::new();
+        (router_with_state(state.clone()), state)
+    }
+
+    /// Helper: obtain valid token from state after setup.
+    async fn setup_test_router_with_token() -> (Router, String) {
+        let (router, state) = setup_test_router().await;
+        // Insert token valid for tests
+        let token = "test-token".to_string();
+        state.token_manager.insert_token(token.clone(), 60, vec!["metrics:read".to_string()]);
+        (router, token)
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint() {
+        // Arrange
+        let (router, state) = setup_test_router().await;
+
+        // Insert valid token for this test
+        let token = "metrics-token".to_string();
+        state.token_manager.insert_token(token.clone(), 60, vec!["metrics:read".to_string()]);
+
+        // Create sample CAPA record
+        let mut capa = state
+            .capa_service
+            .create_capa(
+                "Test CAPA".to_string(),
+                "Test description".to_string(),
+                CapaType::Preventive,
+                CapaPriority::Medium,
+                "initiator1".to_string(),
+                "assignee1".to_string(),
+                None,
+            )
+            .expect("create_capa failed");
+        // Walk the workflow to a signed closure for metrics diversity
+        let reason = ChangeReason::new("Preventive action verified").unwrap();
+        for status in [
+            CapaStatus::InvestigationInProgress,
+            CapaStatus::RootCauseAnalysis,
+            CapaStatus::PreventiveActionInProgress,
+            CapaStatus::EffectivenessVerification,
+        ] {
+            state
+                .capa_service
+                .update_status(&mut capa, status, "initiator1", &reason, None)
+                .expect("status update failed");
+        }
+        state
+            .capa_service
+            .close_with_signature(&mut capa, "initiator1", CAPA_CLOSURE_MEANING, &reason, None)
+            .expect("closure failed");
+        state.capa_records.write().unwrap().push(capa);
+
+        // Create sample Risk assessment
+        let assessment = state
+            .risk_service
+            .create_risk_assessment(
+                "Device X".to_string(),
+                "Hazard description".to_string(),
+                "Situation".to_string(),
+                "Sequence".to_string(),
+                "Harm description".to_string(),
+                RiskSeverity::Minor,
+                RiskProbability::Possible,
+                "creator".to_string(),
+            )
+            .await
+            .expect("risk assessment creation failed");
+        state.risk_assessments.write().unwrap().push(assessment);
+
+        // Act
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::GET)
+                    .uri("/metrics")
+                    .header(
+                        AUTHORIZATION,
+                        HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
+                    )
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // Assert
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let parsed: MetricsResponse = serde_json::from_slice(&body).expect("valid JSON");
+        assert_eq!(parsed.capa_metrics.total_count, 1);
+        assert_eq!(parsed.risk_report.total_assessments, 1);
+        // Only the CAPA KPI has data; it is rated and snapshotted for the trend.
+        assert_eq!(parsed.kpis.len(), 1);
+        assert_eq!(parsed.kpis[0].metric, KpiMetric::CapaOnTimeClosure);
+        assert_eq!(parsed.kpis[0].status, RagStatus::Green);
+        assert_eq!(parsed.kpis[0].trend.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_requires_auth() {
+        let (router, _token) = setup_test_router_with_token().await;
+
+        // Request without token should be 401
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::GET)
+                    .uri("/metrics")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_with_valid_token() {
+        let (router, token) = setup_test_router_with_token().await;
+
+        let auth_header = format!("Bearer {}", token);
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::GET)
+                    .uri("/metrics")
+                    .header(AUTHORIZATION, HeaderValue::from_str(&auth_header).unwrap())
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_supplier_metrics_endpoint() {
+        let (router, state) = setup_test_router().await;
+        let token = "supplier-token".to_string();
+        state.token_manager.insert_token(token.clone(), 60, vec!["metrics:read".to_string()]);
+
+        // Add sample suppliers
         let mut suppliers_guard = state.suppliers.write().unwrap();
         use uuid::Uuid;
         suppliers_guard.extend(vec![
@@ -424,6 +2324,7 @@ mod tests {
                 approved_by: None,
                 created_at: chrono::Utc::now(),
                 updated_at: chrono::Utc::now(),
+                row_version: 1,
             },
             Supplier {
                 id: Uuid::new_v4(),
@@ -435,82 +2336,1416 @@ mod tests {
                 approved_by: None,
                 created_at: chrono::Utc::now(),
                 updated_at: chrono::Utc::now(),
+                row_version: 1,
             },
         ]);
         drop(suppliers_guard);
 
-        // Perform request
+        // Perform request
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::GET)
+                    .uri("/supplier_metrics")
+                    .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap())
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let parsed: SupplierMetrics = serde_json::from_slice(&body).expect("valid JSON");
+        assert_eq!(parsed.total_count, 2);
+        assert_eq!(parsed.qualified_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_risk_heat_map_endpoint() {
+        let (router, state) = setup_test_router().await;
+        let token = "risk-token".to_string();
+        state.token_manager.insert_token(token.clone(), 60, vec!["metrics:read".to_string()]);
+        let assessment = state
+            .risk_service
+            .create_risk_assessment(
+                "Device X".to_string(),
+                "Hazard description".to_string(),
+                "Situation".to_string(),
+                "Sequence".to_string(),
+                "Harm description".to_string(),
+                RiskSeverity::Catastrophic,
+                RiskProbability::Remote,
+                "creator".to_string(),
+            )
+            .await
+            .expect("risk assessment creation failed");
+        let id = assessment.id;
+        state.risk_assessments.write().unwrap().push(assessment);
+
+        for (uri, placed) in [("/risk_heat_map", 1), ("/risk_heat_map?residual=true", 0)] {
+            let response = router
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method(Method::GET)
+                        .uri(uri)
+                        .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap())
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            let heat_map: RiskHeatMap = serde_json::from_slice(&body).expect("valid JSON");
+            assert_eq!(heat_map.total(), placed);
+            if placed == 1 {
+                let cell = heat_map.cell(0, 0).unwrap();
+                assert_eq!((cell.severity, cell.risk_level), (RiskSeverity::Catastrophic, 5));
+                assert_eq!(cell.assessments[0].id, id);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pareto_endpoint() {
+        let (router, state) = setup_test_router().await;
+        let token = "pareto-token".to_string();
+        state.token_manager.insert_token(token.clone(), 60, vec!["metrics:read".to_string()]);
+        let intake = crate::quality_intake::QualityIntake::new(&state.database);
+        for category in ["Seal leak", "Seal leak", "Label misprint"] {
+            let item = intake.submit(IssueSource::Complaint, "Complaint", "Description", "support").unwrap();
+            intake.categorize(item.id, category, "qe1").unwrap();
+        }
+
+        let today = Utc::now().date_naive();
+        let request = |uri: String| {
+            Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap())
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = router
+            .clone()
+            .oneshot(request(format!("/analytics/pareto?sources=complaint&from={}&to={}", today, today)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let ranking: crate::pareto::ParetoRanking = serde_json::from_slice(&body).expect("valid JSON");
+        assert_eq!(ranking.total, 3);
+        assert_eq!((ranking.entries[0].category.as_str(), ranking.entries[0].count), ("Seal leak", 2));
+
+        let response = router.oneshot(request("/analytics/pareto?sources=complaint,rumour".to_string())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_complaint_rates_endpoint() {
+        let (router, state) = setup_test_router().await;
+        let token = "rates-token".to_string();
+        state.token_manager.insert_token(token.clone(), 60, vec!["metrics:read".to_string()]);
+        let month = Utc::now().format("%Y-%m").to_string();
+        ComplaintRates::new(&state.database).record_units(&month, 2_000, "sales_ops").unwrap();
+        crate::quality_intake::QualityIntake::new(&state.database)
+            .submit(IssueSource::Complaint, "Complaint", "Description", "support")
+            .unwrap();
+
+        let request = |uri: &str| {
+            Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap())
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = router.clone().oneshot(request("/analytics/complaint_rates?months=2")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let trend: crate::complaint_rate::ComplaintRateTrend = serde_json::from_slice(&body).expect("valid JSON");
+        assert_eq!(trend.months.len(), 2);
+        assert_eq!((trend.months[1].units, trend.months[1].complaint_rate), (Some(2_000), Some(5.0)));
+        assert!(!trend.months[1].complaint_alert);
+
+        let response = router.oneshot(request("/analytics/complaint_rates?months=0")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_training_metrics_endpoint() {
+        let (router, state) = setup_test_router().await;
+
+        // Add one sample training record to state
+        let mut records = state.training_records.write().unwrap();
+        records.push(TrainingRecord {
+            id: Uuid::new_v4(),
+            employee_id: "emp1".to_string(),
+            training_item: "QMS Overview".to_string(),
+            mandatory: true,
+            assigned_by: "manager".to_string(),
+            due_date: chrono::Utc::now().date_naive(),
+            completion_date: None,
+            status: TrainingStatus::Pending,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            row_version: 1,
+        });
+        drop(records);
+
+        // Obtain valid token
+        let default_token = state.token_manager.tokens.read().unwrap().keys().next().unwrap().clone();
+        let req = Request::builder()
+            .method(Method::GET)
+            .uri("/training_metrics")
+            .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", default_token)).unwrap())
+            .body(Body::empty())
+            .unwrap();
+        let resp = router.oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let bytes = hyper::body::to_bytes(resp.into_body()).await.unwrap();
+        let metrics: TrainingMetrics = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(metrics.total_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_cached() {
+        use axum::http::header::{AUTHORIZATION, HeaderValue};
+        let (router, state) = setup_test_router().await;
+        // Obtain token
+        let default_token = state.token_manager.tokens.read().unwrap().keys().next().unwrap().clone();
+        let req = |uri: &str| Request::builder()
+            .method(Method::GET)
+            .uri(uri)
+            .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", default_token)).unwrap())
+            .body(Body::empty())
+            .unwrap();
+        // First request – populates cache
+        let resp1 = router.clone().oneshot(req("/metrics")).await.unwrap();
+        assert_eq!(resp1.status(), StatusCode::OK);
+        // Second request – should hit cache
+        let resp2 = router.oneshot(req("/metrics")).await.unwrap();
+        assert_eq!(resp2.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_record_history_endpoint() {
+        use crate::change_history::{record_changes, ChangeReason, ChangeRecord, FieldChange};
+        let (router, state) = setup_test_router().await;
+        let token = "history-token".to_string();
+        state.token_manager.insert_token(token.clone(), 60, vec!["metrics:read".to_string()]);
+
+        let reason = ChangeReason::new("Requalified after audit").unwrap();
+        let change = FieldChange {
+            field: "status".to_string(),
+            old_value: Some("Pending".to_string()),
+            new_value: Some("Qualified".to_string()),
+        };
+        state
+            .database
+            .with_connection(|conn| record_changes(conn, "supplier", "s-1", "qa_manager", &reason, &[change.clone()]))
+            .unwrap();
+
+        let response = router
+            .oneshot(
+                Request::builder()
+                    .method(Method::GET)
+                    .uri("/records/supplier/s-1/history")
+                    .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap())
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let parsed: Vec<ChangeRecord> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].reason, reason);
+        assert_eq!(parsed[0].changes, vec![change]);
+    }
+
+    #[tokio::test]
+    async fn test_capa_lookup_by_number_or_id() {
+        let (router, state) = setup_test_router().await;
+        let token = "capa-token".to_string();
+        state.token_manager.insert_token(token.clone(), 60, vec!["metrics:read".to_string()]);
+        let capa = state
+            .capa_service
+            .create_capa(
+                "Seal failure".to_string(),
+                "Lot 42 failed peel test".to_string(),
+                CapaType::Corrective,
+                CapaPriority::High,
+                "qe1".to_string(),
+                "eng1".to_string(),
+                None,
+            )
+            .unwrap();
+        state.capa_records.write().unwrap().push(capa.clone());
+        let get = |uri: String| {
+            router.clone().oneshot(
+                Request::builder()
+                    .method(Method::GET)
+                    .uri(uri)
+                    .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap())
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let response = get("/capas".to_string()).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let list: Vec<CapaListItem> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].id, capa.id);
+        assert_eq!(list[0].capa_number, format!("CAPA-{}-0001", Utc::now().format("%Y")));
+
+        for reference in [capa.capa_number.clone(), capa.id.clone()] {
+            let response = get(format!("/capas/{}", reference)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let found: CapaRecord = serde_json::from_slice(&hyper::body::to_bytes(response.into_body()).await.unwrap()).unwrap();
+            assert_eq!(found.id, capa.id);
+        }
+        assert_eq!(get("/capas/CAPA-1999-0001".to_string()).await.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_capa_updates_require_current_etag() {
+        let (router, state) = setup_test_router().await;
+        state.database.seed_test_users(&["engineer"]);
+        let scopes = vec!["metrics:read".to_string()];
+        state.token_manager.insert_user_token("engineer-token".to_string(), 60, scopes, "engineer");
+        let capa = state
+            .capa_service
+            .create_capa(
+                "Seal failure".to_string(),
+                "Lot 42 failed peel test".to_string(),
+                CapaType::Corrective,
+                CapaPriority::High,
+                "qe1".to_string(),
+                "eng1".to_string(),
+                None,
+            )
+            .unwrap();
+        state.capa_records.write().unwrap().push(capa.clone());
+        let request = |method: Method, path: &str, etag: Option<&str>, body: &'static str| {
+            let mut builder = Request::builder()
+                .method(method)
+                .uri(format!("/capas/{}{}", capa.capa_number, path))
+                .header(AUTHORIZATION, "Bearer engineer-token")
+                .header(CONTENT_TYPE, "application/json");
+            if let Some(etag) = etag {
+                builder = builder.header(IF_MATCH, etag);
+            }
+            builder.body(Body::from(body)).unwrap()
+        };
+        let investigate = r#"{"status":"InvestigationInProgress","reason":"Complaint trend confirmed"}"#;
+
+        let response = router.clone().oneshot(request(Method::GET, "", None, "")).await.unwrap();
+        assert_eq!(response.headers()[ETAG], "\"1\"");
+        let response = router.clone().oneshot(request(Method::POST, "/status", None, investigate)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
+
+        // A TUI user reassigns the CAPA while the API client still holds version 1
+        let mut tui_copy = capa.clone();
+        let reason = ChangeReason::new("Workload").unwrap();
+        state.capa_service.reassign(&mut tui_copy, "lead", "engineer", &reason).unwrap();
+
+        let investigate_at = |etag| request(Method::POST, "/status", Some(etag), investigate);
+        let response = router.clone().oneshot(investigate_at("\"1\"")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+        let response = router.clone().oneshot(investigate_at("\"2\"")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[ETAG], "\"3\"");
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let updated: CapaRecord = serde_json::from_slice(&body).unwrap();
+        assert_eq!((updated.status, updated.assigned_to.as_str()), (CapaStatus::InvestigationInProgress, "lead"));
+
+        let close = r#"{"status":"Closed","reason":"Verified"}"#;
+        let response = router.clone().oneshot(request(Method::POST, "/status", Some("\"3\""), close)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "closure needs an electronic signature");
+        let reassign = r#"{"assigned_to":"eng1","reason":"Back from leave"}"#;
+        let response = router.oneshot(request(Method::POST, "/reassign", Some("\"3\""), reassign)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[ETAG], "\"4\"");
+        assert_eq!(state.capa_records.read().unwrap()[0].row_version, 4);
+    }
+
+    #[tokio::test]
+    async fn test_search_endpoint() {
+        use crate::search::{SearchKind, SearchResult};
+        let (router, state) = setup_test_router().await;
+        let token = "search-token".to_string();
+        state.token_manager.insert_token(token.clone(), 60, vec!["metrics:read".to_string()]);
+        state
+            .database
+            .with_connection(|conn| {
+                conn.execute("INSERT INTO suppliers (id, name, qualification_status) VALUES ('s-1', 'Acme Seals', 'Qualified')", [])?;
+                Ok(())
+            })
+            .unwrap();
+        let get = |uri: &str| {
+            router.clone().oneshot(
+                Request::builder()
+                    .method(Method::GET)
+                    .uri(uri)
+                    .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap())
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let response = get("/search?q=acme%20seals&limit=5").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let results: Vec<SearchResult> = serde_json::from_slice(&hyper::body::to_bytes(response.into_body()).await.unwrap()).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].kind, SearchKind::Supplier);
+        assert_eq!(results[0].id, "s-1");
+
+        assert_eq!(get("/search?q=").await.unwrap().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_record_links_graph_endpoint() {
+        use crate::links::{LinkType, TraceabilityGraph};
+        let (router, state) = setup_test_router().await;
+        let token = "links-token".to_string();
+        state.token_manager.insert_token(token.clone(), 60, vec!["metrics:read".to_string()]);
+        LinkRepo::new(&state.database)
+            .link(&RecordRef::new("capa", "c-1"), &RecordRef::new("complaint", "cmp-1"), LinkType::Addresses, "qa")
+            .unwrap();
+        let get = |uri: &str| {
+            router.clone().oneshot(
+                Request::builder()
+                    .method(Method::GET)
+                    .uri(uri)
+                    .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap())
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+        };
+
+        let response = get("/records/complaint/cmp-1/links").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let graph: TraceabilityGraph = serde_json::from_slice(&hyper::body::to_bytes(response.into_body()).await.unwrap()).unwrap();
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.links[0].link_type, LinkType::Addresses);
+
+        let response = get("/records/complaint/cmp-1/links?format=dot&depth=1").await.unwrap();
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/vnd.graphviz");
+        let dot = String::from_utf8(hyper::body::to_bytes(response.into_body()).await.unwrap().to_vec()).unwrap();
+        assert!(dot.contains("\"capa:c-1\" -> \"complaint:cmp-1\" [label=\"addresses\"];"));
+
+        assert_eq!(get("/records/complaint/cmp-1/links?format=svg").await.unwrap().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_error_messages_are_localized() {
+        let state = ApiState::new().with_locale(Locale::De);
+        let token = "locale-token".to_string();
+        state.token_manager.insert_token(token.clone(), 60, vec!["metrics:read".to_string()]);
+        let get = |uri: &str, auth: Option<&str>| {
+            let mut request = Request::builder().method(Method::GET).uri(uri);
+            if let Some(token) = auth {
+                request = request.header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap());
+            }
+            router_with_state(state.clone()).oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        let response = get("/records/supplier/s-9/diff?from=1&to=2", Some(&token)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let error: ApiError = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.message, "Ressource 'supplier version' mit ID 's-9@1' nicht gefunden");
+
+        let response = get("/records/supplier/s-9/history", None).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let error: ApiError = serde_json::from_slice(&hyper::body::to_bytes(response.into_body()).await.unwrap()).unwrap();
+        assert_eq!(error.message, "Nicht autorisiert");
+    }
+
+    #[tokio::test]
+    async fn test_error_body_carries_correlation_id() {
+        let (router, token) = setup_test_router_with_token().await;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("/records/supplier/s-9/diff?from=1&to=2")
+            .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap())
+            .header(CORRELATION_ID_HEADER, "req-42")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.headers()[CORRELATION_ID_HEADER], "req-42");
+        let error: ApiError = serde_json::from_slice(&hyper::body::to_bytes(response.into_body()).await.unwrap()).unwrap();
+        assert_eq!(error.code, "NOT_FOUND");
+        assert_eq!(error.severity, "MEDIUM");
+        assert_eq!(error.correlation_id.as_deref(), Some("req-42"));
+
+        // Without a caller-supplied id a fresh one is generated
+        let response = router
+            .oneshot(Request::builder().uri("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let generated = response.headers()[CORRELATION_ID_HEADER].to_str().unwrap().to_string();
+        let error: ApiError = serde_json::from_slice(&hyper::body::to_bytes(response.into_body()).await.unwrap()).unwrap();
+        assert_eq!(error.correlation_id, Some(generated));
+        assert!(current_correlation_id().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_validation_errors_map_to_field_messages() {
+        let mut errors = ValidationErrors::new();
+        errors.add("title", "must not be empty");
+        errors.add("assigned_to", "must not be empty");
+        let response = QmsError::ValidationErrors { errors }.into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let body: serde_json::Value =
+            serde_json::from_slice(&hyper::body::to_bytes(response.into_body()).await.unwrap()).unwrap();
+        assert_eq!(body["code"], "VAL_ERROR");
+        assert_eq!(body["fields"], serde_json::json!({ "assigned_to": "must not be empty", "title": "must not be empty" }));
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_pool_metrics_endpoint() {
+        let (router, token) = setup_test_router_with_token().await;
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("/metrics/prometheus")
+            .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap())
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/plain; version=0.0.4");
+        let body = String::from_utf8(hyper::body::to_bytes(response.into_body()).await.unwrap().to_vec()).unwrap();
+        assert!(body.contains("qmsrs_db_pool_max_connections 10\n"));
+        assert!(body.contains("# TYPE qmsrs_db_slow_queries_total counter"));
+    }
+
+    #[tokio::test]
+    async fn test_audit_trail_export_streams_csv() {
+        let (router, state) = setup_test_router().await;
+        let token = "export-token".to_string();
+        state.database.seed_test_users(&["manager"]);
+        state.database.with_connection(|conn| {
+            conn.execute("UPDATE users SET role = 'QualityManager' WHERE id = 'manager'", [])?;
+            Ok(())
+        }).unwrap();
+        state.token_manager.insert_user_token(token.clone(), 60, vec!["metrics:read".to_string()], "manager");
+        let audit = AuditManager::new(state.database.clone());
+        for n in 0..50 {
+            audit.log_action("qa", "capa_created", &format!("capa:{}", n), "Success", None).unwrap();
+        }
+
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("/audit_trail/export?user=qa&tz=%2B02:00")
+            .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap())
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/csv");
+        let body = String::from_utf8(hyper::body::to_bytes(response.into_body()).await.unwrap().to_vec()).unwrap();
+        assert_eq!(body.lines().count(), 51);
+        assert!(body.lines().nth(1).unwrap().contains("+02:00,"));
+        assert_eq!(state.database.get_audit_entries_for_resource("audit_trail").unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_embedded_api_serves_shared_database() {
+        let state = ApiState::new();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut config = Config::default();
+        config.api.bind = listener.local_addr().unwrap().to_string();
+        drop(listener);
+        let context = AppContext::with_database(config, state.database.clone());
+        context.supplier_service.register_supplier("Acme".to_string(), None).unwrap();
+
+        let api = EmbeddedApi::new(&context).unwrap();
+        let (url, token) = (format!("{}/summaries", api.base_url()), api.token().to_string());
+        let server = api.spawn();
+        let mut response = None;
+        for _ in 0..50 {
+            match reqwest::Client::new().get(&url).bearer_auth(&token).send().await {
+                Ok(resp) => {
+                    response = Some(resp);
+                    break;
+                }
+                Err(_) => tokio::time::sleep(std::time::Duration::from_millis(20)).await,
+            }
+        }
+        let summary: SummaryResponse = response.expect("server reachable").json().await.unwrap();
+        assert_eq!(summary.supplier_metrics.total_count, 1);
+        server.abort();
+
+        let mut invalid = Config::default();
+        invalid.api.bind = "nowhere".to_string();
+        assert!(EmbeddedApi::new(&AppContext::with_database(invalid, state.database.clone())).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_summaries_endpoint_reads_materialized_counts() {
+        let (router, state) = setup_test_router().await;
+        let token = "summary-token".to_string();
+        state.token_manager.insert_token(token.clone(), 60, vec!["metrics:read".to_string()]);
+        state.supplier_service.register_supplier("Acme".to_string(), None).unwrap();
+
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri("/summaries")
+            .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap())
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let summary: SummaryResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(summary.supplier_metrics.pending_count, 1);
+        assert_eq!(summary.capa_metrics.total_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_record_as_of_and_diff_endpoints() {
+        let (router, state) = setup_test_router().await;
+        let token = "versions-token".to_string();
+        state.token_manager.insert_token(token.clone(), 60, vec!["metrics:read".to_string()]);
+
+        let mut capa = state.capa_service.create_capa(
+            "Versioned".to_string(),
+            "As-of inspection view".to_string(),
+            CapaType::Corrective,
+            CapaPriority::Low,
+            "initiator1".to_string(),
+            "assignee1".to_string(),
+            None,
+        ).unwrap();
+        let created = Utc::now();
+        let reason = ChangeReason::new("Investigation opened").unwrap();
+        let investigating = CapaStatus::InvestigationInProgress;
+        state.capa_service.update_status(&mut capa, investigating, "assignee1", &reason, None).unwrap();
+
+        let get = |uri: String| {
+            Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap())
+                .body(Body::empty())
+                .unwrap()
+        };
+        let at = created.to_rfc3339_opts(chrono::SecondsFormat::Micros, true);
+        let response = router.clone().oneshot(get(format!("/records/capa/{}/as_of?at={}", capa.id, at))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let version: crate::record_history::RecordVersion = serde_json::from_slice(&body).unwrap();
+        assert_eq!(version.version, 1);
+        assert_eq!(version.restore::<CapaRecord>().unwrap().status, CapaStatus::Identified);
+
+        let response = router.clone().oneshot(get(format!("/records/capa/{}/diff?from=1&to=2", capa.id))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = router.oneshot(get(format!("/records/capa/{}/diff?from=1&to=9", capa.id))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_document_approval_requires_quality_manager_role() {
+        let (router, state) = setup_test_router().await;
+        state.database.seed_test_users(&["engineer", "manager"]);
+        state.database.with_connection(|conn| {
+            conn.execute("UPDATE users SET role = 'QualityManager' WHERE id = 'manager'", [])?;
+            conn.execute(
+                "INSERT INTO documents (id, document_number, title, version, status, document_type, content_hash, created_by)
+                 VALUES ('d1', 'SOP-001', 'Cleaning', '1.0', 'UnderReview', 'SOP', 'h', 'engineer')",
+                [],
+            )?;
+            Ok(())
+        }).unwrap();
+        let scopes = vec!["metrics:read".to_string()];
+        state.token_manager.insert_user_token("engineer-token".to_string(), 60, scopes.clone(), "engineer");
+        state.token_manager.insert_user_token("manager-token".to_string(), 60, scopes.clone(), "manager");
+        state.token_manager.insert_token("service-token".to_string(), 60, scopes);
+
+        let approve = |token: &str, etag: &str| {
+            Request::builder()
+                .method(Method::POST)
+                .uri("/documents/d1/approve")
+                .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap())
+                .header(IF_MATCH, etag)
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"reason":"Procedure reviewed"}"#))
+                .unwrap()
+        };
+        for token in ["engineer-token", "service-token"] {
+            let response = router.clone().oneshot(approve(token, "\"1\"")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            let error: ApiError = serde_json::from_slice(&body).unwrap();
+            assert_eq!(error.code, "FORBIDDEN");
+        }
+        let denied = state.database.get_audit_entries_for_resource("document:d1").unwrap();
+        assert_eq!(denied.len(), 2);
+        assert!(denied.iter().all(|e| e.action == "api_access_denied" && e.outcome == "FAILURE"));
+        assert_eq!(denied[0].user_id, "engineer");
+
+        let response = router.clone().oneshot(approve("manager-token", "\"1\"")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(response.headers()[ETAG], "\"2\"");
+        let approved_by: String = state.database.with_connection(|conn| {
+            Ok(conn.query_row("SELECT approved_by FROM documents WHERE id = 'd1'", [], |row| row.get(0))?)
+        }).unwrap();
+        assert_eq!(approved_by, "manager");
+        let history = ChangeHistoryRepo::new(&state.database).history("document", "d1").unwrap();
+        assert_eq!(history[0].reason.as_str(), "Procedure reviewed");
+        let response = router.clone().oneshot(approve("manager-token", "\"1\"")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED, "stale version");
+        let response = router.clone().oneshot(approve("manager-token", "\"2\"")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "already approved");
+
+        state.database.with_connection(|conn| {
+            conn.execute("UPDATE users SET is_active = 0 WHERE id = 'manager'", [])?;
+            Ok(())
+        }).unwrap();
+        let response = router.oneshot(approve("manager-token", "\"2\"")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_document_effectivity_scopes_effective_documents_by_site() {
+        let (router, state) = setup_test_router().await;
+        state.database.seed_test_users(&["engineer", "manager"]);
+        state.database.with_connection(|conn| {
+            conn.execute("UPDATE users SET role = 'QualityManager' WHERE id = 'manager'", [])?;
+            conn.execute("INSERT INTO sites (id, name) VALUES ('site-b', 'Site B')", [])?;
+            conn.execute(
+                "INSERT INTO documents (id, document_number, title, version, status, document_type, content_hash, created_by)
+                 VALUES ('d1', 'SOP-001', 'Cleaning', '2.0', 'Approved', 'SOP', 'h', 'engineer')",
+                [],
+            )?;
+            Ok(())
+        }).unwrap();
+        let scopes = vec!["metrics:read".to_string()];
+        state.token_manager.insert_user_token("engineer-token".to_string(), 60, scopes.clone(), "engineer");
+        state.token_manager.insert_user_token("manager-token".to_string(), 60, scopes, "manager");
+
+        let set = |token: &str| {
+            Request::builder()
+                .method(Method::POST)
+                .uri("/documents/d1/effectivity")
+                .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap())
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"version":"2.0","site_id":"site-b","effective_from":"2026-03-01"}"#))
+                .unwrap()
+        };
+        let response = router.clone().oneshot(set("engineer-token")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = router.clone().oneshot(set("manager-token")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let get = |uri: &str| {
+            Request::builder()
+                .uri(uri)
+                .header(AUTHORIZATION, "Bearer manager-token")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = router.clone().oneshot(get("/documents/d1/effectivity")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let scopes: Vec<crate::document::DocumentEffectivity> = serde_json::from_slice(&body).unwrap();
+        assert_eq!((scopes.len(), scopes[0].set_by.as_str()), (1, "manager"));
+
+        for (uri, expected) in [
+            ("/sites/site-b/effective_documents?on=2026-03-01", 1),
+            ("/sites/site-b/effective_documents?on=2026-02-28", 0),
+            ("/sites/default/effective_documents?on=2026-03-01", 0),
+        ] {
+            let response = router.clone().oneshot(get(uri)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            let documents: Vec<crate::document::EffectiveDocument> = serde_json::from_slice(&body).unwrap();
+            assert_eq!(documents.len(), expected, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_controlled_print_issue_and_reconcile() {
+        let (router, state) = setup_test_router().await;
+        state.database.seed_test_users(&["engineer"]);
+        state.database.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO documents (id, document_number, title, version, status, document_type, content_hash, created_by)
+                 VALUES ('d1', 'SOP-001', 'Cleaning', '1.0', 'Effective', 'SOP', 'h', 'engineer')",
+                [],
+            )?;
+            Ok(())
+        }).unwrap();
+        let scopes = vec!["metrics:read".to_string()];
+        state.token_manager.insert_user_token("engineer-token".to_string(), 60, scopes, "engineer");
+        let request = |method: Method, uri: &str, body: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(AUTHORIZATION, "Bearer engineer-token")
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
         let response = router
-            .oneshot(
-                Request::builder()
-                    .method(Method::GET)
-                    .uri("/supplier_metrics")
-                    .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap())
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+            .clone()
+            .oneshot(request(Method::POST, "/documents/d1/prints", r#"{"recipient":"Line 1"}"#))
             .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let print: crate::controlled_prints::ControlledPrint = serde_json::from_slice(&body).unwrap();
+        assert_eq!((print.copy_number, print.issued_by.as_str()), (1, "engineer"));
+
+        state.database.with_connection(|conn| {
+            conn.execute("UPDATE documents SET version = '2.0' WHERE id = 'd1'", [])?;
+            Ok(())
+        }).unwrap();
+        let response = router.clone().oneshot(request(Method::GET, "/controlled_prints", "")).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let report: Vec<crate::controlled_prints::OpenPrints> = serde_json::from_slice(&body).unwrap();
+        assert_eq!((report.len(), report[0].awaiting_recall()), (1, 1));
 
+        let reconcile = format!("/controlled_prints/{}/reconcile", print.id);
+        let response = router
+            .clone()
+            .oneshot(request(Method::POST, &reconcile, r#"{"disposition":"destroyed"}"#))
+            .await
+            .unwrap();
         assert_eq!(response.status(), StatusCode::OK);
+        let response = router
+            .clone()
+            .oneshot(request(Method::POST, &reconcile, r#"{"disposition":"returned"}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = router.oneshot(request(Method::GET, "/controlled_prints", "")).await.unwrap();
         let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
-        let parsed: SupplierMetrics = serde_json::from_slice(&body).expect("valid JSON");
-        assert_eq!(parsed.total_count, 2);
-        assert_eq!(parsed.qualified_count, 1);
+        let report: Vec<crate::controlled_prints::OpenPrints> = serde_json::from_slice(&body).unwrap();
+        assert!(report.is_empty());
     }
 
     #[tokio::test]
-    async fn test_training_metrics_endpoint() {
+    async fn test_task_assignment_and_inbox() {
         let (router, state) = setup_test_router().await;
+        state.database.seed_test_users(&["engineer", "manager"]);
+        let scopes = vec!["metrics:read".to_string()];
+        state.token_manager.insert_user_token("engineer-token".to_string(), 60, scopes.clone(), "engineer");
+        state.token_manager.insert_user_token("manager-token".to_string(), 60, scopes, "manager");
+        let request = |method: Method, uri: &str, token: &str, body: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token)).unwrap())
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
 
-        // Add one sample training record to state
-        let mut records = state.training_records.write().unwrap();
-        records.push(TrainingRecord {
-            id: Uuid::new_v4(),
-            employee_id: "emp1".to_string(),
-            training_item: "QMS Overview".to_string(),
-            mandatory: true,
-            assigned_by: "manager".to_string(),
-            due_date: chrono::Utc::now().date_naive(),
-            completion_date: None,
-            status: TrainingStatus::Pending,
-            created_at: chrono::Utc::now(),
-            updated_at: chrono::Utc::now(),
-        });
-        drop(records);
+        let body = r#"{"title":"Check label proof","kind":"verification","due_date":"2026-11-02",
+            "assignee":"engineer","linked_record":"lot:L-2026-042"}"#;
+        let response = router.clone().oneshot(request(Method::POST, "/tasks", "manager-token", body)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let task: crate::tasks::Task = serde_json::from_slice(&body).unwrap();
+        assert_eq!((task.assignee.as_str(), task.created_by.as_str()), ("engineer", "manager"));
 
-        // Obtain valid token
-        let default_token = state.token_manager.tokens.read().unwrap().keys().next().unwrap().clone();
-        let req = Request::builder()
-            .method(Method::GET)
-            .uri("/training_metrics")
-            .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", default_token)).unwrap())
+        let response = router.clone().oneshot(request(Method::GET, "/inbox", "engineer-token", "")).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let inbox: Vec<crate::tasks::InboxItem> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(inbox.len(), 1);
+        assert_eq!(inbox[0].record, RecordRef::new("task", task.id.to_string()));
+
+        let complete = format!("/tasks/{}/complete", task.id);
+        let response = router.clone().oneshot(request(Method::POST, &complete, "engineer-token", "")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = router.oneshot(request(Method::GET, "/inbox", "engineer-token", "")).await.unwrap();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let inbox: Vec<crate::tasks::InboxItem> = serde_json::from_slice(&body).unwrap();
+        assert!(inbox.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_event_log_lists_published_events() {
+        let (router, state) = setup_test_router().await;
+        state.database.seed_test_users(&["reader"]);
+        state.token_manager.insert_user_token("reader-token".to_string(), 60, vec!["metrics:read".to_string()], "reader");
+        let log = EventLog::new(state.database.clone());
+        for capa_id in ["capa-1", "capa-2"] {
+            log.append(&crate::events::QmsEvent::CapaClosed {
+                capa_id: capa_id.to_string(),
+                closed_by: "qa".to_string(),
+                closed_at: Utc::now(),
+            })
+            .unwrap();
+        }
+        let first = log.after(0, None, 1).unwrap().remove(0);
+
+        let request = Request::builder()
+            .uri(format!("/events?after={}&name=capa.closed", first.sequence))
+            .header(AUTHORIZATION, "Bearer reader-token")
             .body(Body::empty())
             .unwrap();
-        let resp = router.oneshot(req).await.unwrap();
-        assert_eq!(resp.status(), StatusCode::OK);
-        let bytes = hyper::body::to_bytes(resp.into_body()).await.unwrap();
-        let metrics: TrainingMetrics = serde_json::from_slice(&bytes).unwrap();
-        assert_eq!(metrics.total_count, 1);
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let events: Vec<crate::events::StoredEvent> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(&events[0].event, crate::events::QmsEvent::CapaClosed { capa_id, .. } if capa_id == "capa-2"));
     }
 
     #[tokio::test]
-    async fn test_metrics_endpoint_cached() {
-        use axum::http::header::{AUTHORIZATION, HeaderValue};
+    async fn test_disabled_module_routes_answer_not_found() {
+        let mut config = Config::default();
+        config.features.disabled = vec![Feature::Capa];
+        let state = ApiState::from_context(&AppContext::with_database(config, ApiState::new().database));
+        let router = router_with_state(state.clone());
+        state.database.seed_test_users(&["reader"]);
+        let scopes = vec!["metrics:read".to_string()];
+        state.token_manager.insert_user_token("reader-token".to_string(), 60, scopes, "reader");
+
+        for (uri, status) in [("/capas", StatusCode::NOT_FOUND), ("/supplier_metrics", StatusCode::OK)] {
+            let request = Request::builder()
+                .uri(uri)
+                .header(AUTHORIZATION, "Bearer reader-token")
+                .body(Body::empty())
+                .unwrap();
+            assert_eq!(router.clone().oneshot(request).await.unwrap().status(), status, "{}", uri);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_disabled_module_records_are_hidden() {
+        use crate::events::QmsEvent;
+        use crate::links::LinkType;
+
+        let mut config = Config::default();
+        config.features.disabled = vec![Feature::Capa, Feature::PostMarket];
+        let state = ApiState::from_context(&AppContext::with_database(config, ApiState::new().database));
+        let router = router_with_state(state.clone());
+        state.database.seed_test_users(&["qa"]);
+        state.token_manager.insert_user_token("qa-token".to_string(), 60, vec!["metrics:read".to_string()], "qa");
+        let capa = state
+            .capa_service
+            .create_capa(
+                "Seal failures".into(),
+                "Pouch seals fail peel test".into(),
+                CapaType::Corrective,
+                CapaPriority::High,
+                "qa".into(),
+                "qa".into(),
+                None,
+            )
+            .unwrap();
+        let supplier = state.supplier_service.register_supplier("Seal Pouches".into(), None).unwrap();
+        let supplier_ref = RecordRef::new("supplier", supplier.id.to_string());
+        LinkRepo::new(&state.database)
+            .link(&RecordRef::new("capa", capa.id.clone()), &supplier_ref, LinkType::Addresses, "qa")
+            .unwrap();
+        let log = EventLog::new(state.database.clone());
+        log.append(&QmsEvent::CapaClosed { capa_id: capa.id.clone(), closed_by: "qa".into(), closed_at: Utc::now() })
+            .unwrap();
+        log.append(&QmsEvent::DocumentEffective {
+            document_id: "d1".into(),
+            document_number: "SOP-001".into(),
+            version: "1.0".into(),
+            effective_date: Utc::now(),
+        })
+        .unwrap();
+        let send = |method: Method, uri: String, body: &str| {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(AUTHORIZATION, "Bearer qa-token")
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            router.clone().oneshot(request)
+        };
+        let json = |response: Response| async move {
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap()
+        };
+
+        let task = serde_json::json!({
+            "title": "Check seal",
+            "kind": "review",
+            "due_date": "2030-01-31",
+            "assignee": "qa",
+            "linked_record": format!("capa:{}", capa.id),
+        });
+        let hidden = [
+            (Method::GET, format!("/records/capa/{}/history", capa.id), String::new()),
+            (Method::GET, "/analytics/pareto?sources=complaint".to_string(), String::new()),
+            (Method::POST, "/tasks".to_string(), task.to_string()),
+            (
+                Method::POST,
+                format!("/test_results?target=capa_verification&record_id={}&source=lab", capa.id),
+                "{}".into(),
+            ),
+        ];
+        for (method, uri, body) in hidden {
+            let status = send(method, uri.clone(), &body).await.unwrap().status();
+            assert!(matches!(status, StatusCode::NOT_FOUND | StatusCode::BAD_REQUEST), "{}: {}", uri, status);
+        }
+
+        let response = send(Method::GET, "/analytics/pareto".into(), "").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let events = json(send(Method::GET, "/events".into(), "").await.unwrap()).await;
+        let types: Vec<_> = events.as_array().unwrap().iter().map(|stored| stored["event"]["type"].clone()).collect();
+        assert_eq!(types, vec![serde_json::json!("document_effective")]);
+
+        let hits = json(send(Method::GET, "/search?q=seal".into(), "").await.unwrap()).await;
+        let kinds: Vec<_> =
+            hits.as_array().unwrap().iter().map(|hit| hit["kind"].as_str().unwrap().to_string()).collect();
+        assert!(kinds.iter().all(|kind| kind != "capa"), "{:?}", kinds);
+        assert!(kinds.iter().any(|kind| kind == "supplier"), "{:?}", kinds);
+
+        let graph =
+            json(send(Method::GET, format!("/records/supplier/{}/links", supplier.id), "").await.unwrap()).await;
+        assert_eq!(graph["nodes"].as_array().unwrap().len(), 1, "{}", graph);
+        assert!(graph["links"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_supplier_updates_require_current_etag() {
         let (router, state) = setup_test_router().await;
-        // Obtain token
-        let default_token = state.token_manager.tokens.read().unwrap().keys().next().unwrap().clone();
-        let req = |uri: &str| Request::builder()
-            .method(Method::GET)
-            .uri(uri)
-            .header(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", default_token)).unwrap())
-            .body(Body::empty())
+        state.database.seed_test_users(&["engineer"]);
+        state.token_manager.insert_user_token("engineer-token".to_string(), 60, vec!["metrics:read".to_string()], "engineer");
+        let supplier = state.supplier_service.register_supplier("Acme".to_string(), None).unwrap();
+        let request = |method: Method, path: &str, etag: Option<&str>| {
+            let mut builder = Request::builder()
+                .method(method)
+                .uri(format!("/suppliers/{}{}", supplier.id, path))
+                .header(AUTHORIZATION, "Bearer engineer-token")
+                .header(CONTENT_TYPE, "application/json");
+            if let Some(etag) = etag {
+                builder = builder.header(IF_MATCH, etag);
+            }
+            builder.body(Body::from(r#"{"reason":"Audit result"}"#)).unwrap()
+        };
+
+        let response = router.clone().oneshot(request(Method::GET, "", None)).await.unwrap();
+        assert_eq!(response.headers()[ETAG], "\"1\"");
+
+        let response = router.clone().oneshot(request(Method::POST, "/qualify", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
+
+        // A TUI user qualifies the supplier while the API client still holds version 1
+        let mut tui_copy = supplier.clone();
+        let reason = ChangeReason::new("Desk review").unwrap();
+        state.supplier_service.qualify_supplier(&mut tui_copy, "engineer".to_string(), None, &reason).unwrap();
+
+        let response = router.clone().oneshot(request(Method::POST, "/disqualify", Some("\"1\""))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let error: ApiError = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error.code, "CONFLICT");
+        let stored = state.supplier_service.get_supplier(&supplier.id).unwrap().unwrap();
+        assert_eq!((stored.status, stored.row_version), (SupplierStatus::Qualified, 2));
+
+        let response = router.oneshot(request(Method::POST, "/disqualify", Some("\"2\""))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[ETAG], "\"3\"");
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let updated: Supplier = serde_json::from_slice(&body).unwrap();
+        assert_eq!(updated.status, SupplierStatus::Disqualified);
+    }
+
+    #[tokio::test]
+    async fn test_supplier_document_upload_and_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = ApiState::new();
+        state.file_store = FileStore::new(dir.path());
+        let router = router_with_state(state.clone());
+        state.database.seed_test_users(&["engineer"]);
+        state.token_manager.insert_user_token("engineer-token".to_string(), 60, vec!["metrics:read".to_string()], "engineer");
+        let supplier = state.supplier_service.register_supplier("Acme".to_string(), None).unwrap();
+        let request = |method: Method, query: &str, body: &'static [u8]| {
+            Request::builder()
+                .method(method)
+                .uri(format!("/suppliers/{}/documents{}", supplier.id, query))
+                .header(AUTHORIZATION, "Bearer engineer-token")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let upload = "?kind=iso_certificate&title=ISO%2013485&file_name=cert.pdf&expires_on=2030-01-31";
+        for _ in 0..2 {
+            let response = router.clone().oneshot(request(Method::POST, upload, b"%PDF-1.4")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+        let response = router.clone().oneshot(request(Method::POST, upload, b"")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = router.oneshot(request(Method::GET, "", b"")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let documents: Vec<crate::supplier_documents::SupplierDocument> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!((documents[0].title.as_str(), documents[0].version), ("ISO 13485", 2));
+    }
+
+    #[tokio::test]
+    async fn test_job_status_and_manual_run() {
+        use crate::jobs::{JobOutcome, JobRun, JobStatus};
+
+        let (router, state) = setup_test_router().await;
+        state.database.seed_test_users(&["engineer", "manager"]);
+        state.database.with_connection(|conn| {
+            conn.execute("UPDATE users SET role = 'QualityManager' WHERE id = 'manager'", [])?;
+            Ok(())
+        }).unwrap();
+        state.token_manager.insert_user_token("engineer-token".to_string(), 60, vec!["metrics:read".to_string()], "engineer");
+        state.token_manager.insert_user_token("manager-token".to_string(), 60, vec!["metrics:read".to_string()], "manager");
+        let request = |method: Method, uri: &str, token: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(AUTHORIZATION, format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = router.clone().oneshot(request(Method::GET, "/jobs", "engineer-token")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let statuses: Vec<JobStatus> = serde_json::from_slice(&body).unwrap();
+        let kinds: Vec<JobKind> = statuses.iter().map(|s| s.job).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                JobKind::Backup,
+                JobKind::OverdueScan,
+                JobKind::DatabaseMaintenance,
+                JobKind::AnalyticsExport,
+                JobKind::AccessReview,
+                JobKind::StorageMonitor,
+                JobKind::ReportGeneration
+            ]
+        );
+        assert!(statuses.iter().all(|s| s.last_run.is_none()));
+
+        let response =
+            router.clone().oneshot(request(Method::POST, "/jobs/overdue_scan/run", "engineer-token")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = router.clone().oneshot(request(Method::POST, "/jobs/defrag/run", "manager-token")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response =
+            router.clone().oneshot(request(Method::POST, "/jobs/overdue_scan/run", "manager-token")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let run: JobRun = serde_json::from_slice(&body).unwrap();
+        assert_eq!((run.outcome, run.trigger, run.triggered_by.as_str()), (JobOutcome::Succeeded, JobTrigger::Manual, "manager"));
+
+        let statuses = state.jobs.statuses(Utc::now()).unwrap();
+        assert_eq!(statuses[1].last_run.as_ref(), Some(&run));
+        assert_eq!(statuses[1].next_run_at, run.started_at + Duration::minutes(60));
+    }
+
+    #[tokio::test]
+    async fn test_legal_holds_require_admin_and_show_in_detail_views() {
+        let (router, state) = setup_test_router().await;
+        state.database.seed_test_users(&["admin", "manager"]);
+        state.database.with_connection(|conn| {
+            conn.execute("UPDATE users SET role = 'Administrator' WHERE id = 'admin'", [])?;
+            conn.execute("UPDATE users SET role = 'QualityManager' WHERE id = 'manager'", [])?;
+            Ok(())
+        }).unwrap();
+        state.token_manager.insert_user_token("admin-token".to_string(), 60, vec!["metrics:read".to_string()], "admin");
+        state.token_manager.insert_user_token("manager-token".to_string(), 60, vec!["metrics:read".to_string()], "manager");
+        let supplier = state.supplier_service.register_supplier("Acme".to_string(), None).unwrap();
+        let request = |method: Method, uri: String, token: &str, body: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(AUTHORIZATION, format!("Bearer {}", token))
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let place = format!(r#"{{"record_table":"suppliers","record_id":"{}","reason":"Litigation 2026-CV-7"}}"#, supplier.id);
+
+        let response = router.clone().oneshot(request(Method::POST, "/legal_holds".to_string(), "manager-token", &place)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let blank = r#"{"record_table":"suppliers","reason":" "}"#;
+        let response = router.clone().oneshot(request(Method::POST, "/legal_holds".to_string(), "admin-token", blank)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "a reason is required");
+        let response = router.clone().oneshot(request(Method::POST, "/legal_holds".to_string(), "admin-token", &place)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let hold: LegalHold = serde_json::from_slice(&hyper::body::to_bytes(response.into_body()).await.unwrap()).unwrap();
+
+        let detail_uri = format!("/suppliers/{}", supplier.id);
+        let response = router.clone().oneshot(request(Method::GET, detail_uri.clone(), "manager-token", "")).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&hyper::body::to_bytes(response.into_body()).await.unwrap()).unwrap();
+        assert_eq!(body["name"], "Acme");
+        assert_eq!(body["legal_holds"][0]["reason"], "Litigation 2026-CV-7");
+
+        let release = r#"{"reason":"Case dismissed"}"#;
+        let uri = format!("/legal_holds/{}/release", hold.id);
+        let response = router.clone().oneshot(request(Method::POST, uri.clone(), "manager-token", release)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = router.clone().oneshot(request(Method::POST, uri, "admin-token", release)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = router.oneshot(request(Method::GET, detail_uri, "manager-token", "")).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&hyper::body::to_bytes(response.into_body()).await.unwrap()).unwrap();
+        assert!(body.get("legal_holds").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cookie_session_login_csrf_and_logout() {
+        let (router, state) = setup_test_router().await;
+        state.database.seed_test_users(&["manager"]);
+        state.database.with_connection(|conn| {
+            conn.execute(
+                "UPDATE users SET role = 'QualityManager', password_hash = ?1, salt = 's1' WHERE id = 'manager'",
+                rusqlite::params![crate::security::hash_password("correct horse", "s1")],
+            )?;
+            conn.execute(
+                "INSERT INTO documents (id, document_number, title, version, status, document_type, content_hash, created_by)
+                 VALUES ('d1', 'SOP-001', 'Cleaning', '1.0', 'UnderReview', 'SOP', 'h', 'manager')",
+                [],
+            )?;
+            Ok(())
+        }).unwrap();
+        let login = |password: &str| {
+            Request::builder()
+                .method(Method::POST)
+                .uri("/login")
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::json!({ "username": "manager", "password": password }).to_string()))
+                .unwrap()
+        };
+        let response = router.clone().oneshot(login("wrong")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let response = router.clone().oneshot(login("correct horse")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let cookie = response.headers()[SET_COOKIE].to_str().unwrap().to_string();
+        assert!(cookie.contains("HttpOnly") && cookie.contains("SameSite=Strict"));
+        assert!(!cookie.contains("Secure"), "plain HTTP without [api.tls]");
+        let session = cookie.split(';').next().unwrap().to_string();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let login: LoginResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(login.role, UserRole::QualityManager);
+
+        let request = |method: Method, uri: &str, csrf: Option<&str>| {
+            let mut builder = Request::builder().method(method).uri(uri).header(COOKIE, session.as_str());
+            if let Some(csrf) = csrf {
+                builder = builder.header(CSRF_HEADER, csrf);
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+        let response = router.clone().oneshot(request(Method::GET, "/capas", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "safe methods need no CSRF token");
+        let response = router.clone().oneshot(request(Method::POST, "/documents/d1/approve", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(state.database.get_audit_entries_for_resource("user:manager").unwrap().last().unwrap().action, "csrf_rejected");
+        let mut forged = login.csrf_token.clone();
+        let last = forged.pop().unwrap();
+        forged.push(if last == '0' { '1' } else { '0' });
+        let forged = request(Method::POST, "/documents/d1/approve", Some(&forged));
+        assert_eq!(router.clone().oneshot(forged).await.unwrap().status(), StatusCode::FORBIDDEN);
+        let approve = Request::builder()
+            .method(Method::POST)
+            .uri("/documents/d1/approve")
+            .header(COOKIE, session.as_str())
+            .header(CSRF_HEADER, login.csrf_token.as_str())
+            .header(IF_MATCH, "\"1\"")
+            .header(CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"reason":"Procedure reviewed"}"#))
             .unwrap();
-        // First request – populates cache
-        let resp1 = router.clone().oneshot(req("/metrics")).await.unwrap();
-        assert_eq!(resp1.status(), StatusCode::OK);
-        // Second request – should hit cache
-        let resp2 = router.oneshot(req("/metrics")).await.unwrap();
-        assert_eq!(resp2.status(), StatusCode::OK);
+        let response = router.clone().oneshot(approve).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        let response = router.clone().oneshot(request(Method::POST, "/logout", Some(&login.csrf_token))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(response.headers()[SET_COOKIE].to_str().unwrap().contains("Max-Age=0"));
+        let response = router.oneshot(request(Method::GET, "/capas", None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_session_cookie_is_secure_under_tls() {
+        let mut config = Config::default();
+        config.api.tls.enabled = true;
+        let state = ApiState::from_context(&AppContext::with_database(config, ApiState::new().database));
+        assert!(state.secure_cookies);
+        assert!(set_session_cookie("s-1", 60, state.secure_cookies).ends_with("; Secure"));
+        assert!(set_session_cookie("", 0, state.secure_cookies).contains("Max-Age=0; Secure"));
+    }
+
+    #[tokio::test]
+    async fn test_oidc_routes_without_provider() {
+        let (router, _state) = setup_test_router().await;
+        let request = Request::builder().uri("/auth/oidc/login").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let state = ApiState::new().with_oidc(OidcClient::new(
+            crate::config::OidcConfig {
+                enabled: true,
+                issuer: "https://login.example.com".to_string(),
+                client_id: "qms".to_string(),
+                redirect_uri: "https://qms.example.com/auth/oidc/callback".to_string(),
+                ..Default::default()
+            },
+            crate::oidc::ProviderMetadata {
+                issuer: "https://login.example.com".to_string(),
+                authorization_endpoint: "https://login.example.com/authorize".to_string(),
+                token_endpoint: "https://login.example.com/token".to_string(),
+                jwks_uri: "https://login.example.com/keys".to_string(),
+            },
+            Default::default(),
+        ));
+        let router = router_with_state(state.clone());
+        let request = Request::builder().uri("/auth/oidc/login").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert!(response.headers()[LOCATION].to_str().unwrap().starts_with("https://login.example.com/authorize?"));
+
+        let request = Request::builder().uri("/auth/oidc/callback?state=forged&code=abc").body(Body::empty()).unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let audit = state.database.get_audit_entries_for_resource("authentication").unwrap();
+        assert_eq!(audit[0].action, "sso_login");
+    }
+
+    #[tokio::test]
+    async fn test_mutations_are_audited_with_redacted_payload() {
+        let (router, state) = setup_test_router().await;
+        state.database.seed_test_users(&["qa"]);
+        state.database.with_connection(|conn| {
+            conn.execute(
+                "UPDATE users SET password_hash = ?1, salt = 's1' WHERE id = 'qa'",
+                rusqlite::params![crate::security::hash_password("correct horse", "s1")],
+            )?;
+            Ok(())
+        }).unwrap();
+        let login = |password: &str| {
+            Request::builder()
+                .method(Method::POST)
+                .uri("/login")
+                .header(CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::json!({ "username": "qa", "password": password }).to_string()))
+                .unwrap()
+        };
+        let response = router.clone().oneshot(login("hunter2")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = router.clone().oneshot(login("correct horse")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = router.clone().oneshot(Request::builder().uri("/capas").body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let entries = state.database.get_audit_entries_for_resource("api:/login").unwrap();
+        assert_eq!(entries.len(), 2, "reads are not audited as mutations");
+        assert_eq!((entries[0].user_id.as_str(), entries[0].outcome.as_str()), ("anonymous", "FAILURE"));
+        assert_eq!((entries[1].user_id.as_str(), entries[1].outcome.as_str()), ("qa", "SUCCESS"));
+        let metadata: serde_json::Value = serde_json::from_str(entries[1].metadata.as_deref().unwrap()).unwrap();
+        assert_eq!(metadata["route"], "/login");
+        assert_eq!(metadata["class"], "authentication");
+        assert_eq!(metadata["status"], 200);
+        let payload = metadata["payload"].as_str().unwrap();
+        assert!(payload.contains("[redacted]") && !payload.contains("correct horse"));
+        assert!(!entries[0].metadata.as_deref().unwrap().contains("hunter2"));
+
+        let mut quiet = ApiState::with_database(state.database.clone());
+        quiet.request_audit.route_classes = vec![RouteClass::Records];
+        let response = router_with_state(quiet.clone()).oneshot(login("correct horse")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(state.database.get_audit_entries_for_resource("api:/login").unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_network_policy_refuses_and_audits() {
+        let mut state = ApiState::new();
+        state.network_policy = Arc::new(
+            NetworkPolicy::from_config(&crate::config::NetworkPolicyConfig {
+                allowlist: vec!["10.0.0.0/8".to_string()],
+                denylist: vec!["10.66.0.0/16".to_string()],
+            })
+            .unwrap(),
+        );
+        state.token_manager.insert_token("test-token".to_string(), 60, vec!["metrics:read".to_string()]);
+        let router = router_with_state(state.clone());
+        let request = |peer: Option<&str>| {
+            let mut request = Request::builder()
+                .uri("/metrics")
+                .header(AUTHORIZATION, "Bearer test-token")
+                .body(Body::empty())
+                .unwrap();
+            if let Some(peer) = peer {
+                request.extensions_mut().insert(ConnectInfo(peer.parse::<SocketAddr>().unwrap()));
+            }
+            request
+        };
+
+        let response = router.clone().oneshot(request(Some("10.1.2.3:50000"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        for peer in [Some("10.66.0.5:50000"), Some("192.0.2.10:50000"), None] {
+            let response = router.clone().oneshot(request(peer)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{:?}", peer);
+        }
+
+        let entries = state.database.get_audit_entries_for_resource("api:/metrics").unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(entries.iter().all(|e| e.action == "api_connection_rejected" && e.outcome == "FAILURE"));
+        let metadata: serde_json::Value = serde_json::from_str(entries[0].metadata.as_deref().unwrap()).unwrap();
+        assert_eq!(metadata["peer"], "10.66.0.5");
+        assert!(metadata["reason"].as_str().unwrap().contains("denied range 10.66.0.0/16"));
+    }
+
+    #[tokio::test]
+    async fn test_compliance_health_checklist() {
+        use crate::self_test::ComplianceChecklist;
+
+        let mut state = ApiState::new();
+        let dir = tempfile::TempDir::new().unwrap();
+        state.self_test.data_directory = dir.path().to_path_buf();
+        state.self_test.min_free_disk_mb = 0;
+        state.token_manager.insert_token("test-token".to_string(), 60, vec!["metrics:read".to_string()]);
+        let router = router_with_state(state.clone());
+        let request = || {
+            Request::builder()
+                .uri("/health/compliance")
+                .header(AUTHORIZATION, "Bearer test-token")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let checklist = |response: Response| async move {
+            let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+            serde_json::from_slice::<ComplianceChecklist>(&body).unwrap()
+        };
+
+        let response = router.clone().oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let failed: Vec<String> = checklist(response).await.failures().map(|c| c.name.clone()).collect();
+        assert_eq!(failed, vec!["backup_recency".to_string()]);
+
+        state.database.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO job_runs
+                     (id, job, run_trigger, triggered_by, started_at, completed_at, duration_ms, outcome)
+                 VALUES ('run-1', 'backup', 'manual', 'manager', ?1, ?1, 12, 'succeeded')",
+                [Utc::now().to_rfc3339()],
+            )?;
+            Ok(())
+        }).unwrap();
+        let response = router.clone().oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(checklist(response).await.all_passed());
+
+        let unauthenticated = Request::builder().uri("/health/compliance").body(Body::empty()).unwrap();
+        assert_eq!(router.oneshot(unauthenticated).await.unwrap().status(), StatusCode::UNAUTHORIZED);
     }
-}
\ No newline at end of file
+}
@@ -1,64 +1,67 @@
 use crate::{
+    api::EmbeddedApi,
+    app_context::{AppContext, SessionContext},
     config::{Config, DatabaseConfig},
-    database::Database,
-    security::SecurityManager,
-    audit::AuditManager,
+    crash_guard::{self, TerminalGuard},
     document::DocumentManager,
+    site::DEFAULT_SITE_ID,
     ui::TuiApp,
     logging::{AuditLogEntry, AuditOutcome},
-    Result, QmsError,
+    self_test::ComplianceChecklist,
+    shutdown::ShutdownCoordinator,
+    time_integrity::{ClockCheck, TimeIntegrityMonitor},
+    Result,
 };
 use ratatui::{
     backend::CrosstermBackend,
     Terminal,
 };
-use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
 use std::io;
 use chrono::Utc;
 
 /// Main QMS application
 pub struct App {
-    config: Config,
-    database: Database,
-    security_manager: SecurityManager,
-    audit_manager: AuditManager,
+    context: AppContext,
     document_manager: DocumentManager,
     tui_app: TuiApp,
-    current_user: Option<String>,
-    current_session: Option<String>,
+    clock_monitor: Option<TimeIntegrityMonitor>,
+    api_server: Option<EmbeddedApi>,
 }
 
 impl App {
     /// Create new QMS application
     pub async fn new(config: Config) -> Result<Self> {
-        // Initialize database
-        let database = Database::new(config.database.clone())?;
-        
-        // Initialize security manager
-        let security_manager = SecurityManager::new(config.security.clone())?;
-        
-        // Initialize audit manager
-        let audit_manager = AuditManager::new(database.clone());
-        
+        // Shared database, services and session for the TUI and API
+        let mut context = AppContext::new(config)?;
+        let clock_monitor = context.start()?.clock_monitor;
+        let config = &context.config;
+
         // Initialize document manager
-        let document_manager = DocumentManager::new();
-        
+        let document_manager = DocumentManager::new()
+            .with_event_bus(context.events.clone())
+            .with_numbering(context.database.clone(), DEFAULT_SITE_ID);
+
         // Initialize TUI application
-        let tui_app = TuiApp::new();
+        let mut tui_app = TuiApp::new().with_context(&context);
+
+        // Serve the API from this process on the same context
+        let api_server = if config.api.enabled {
+            let mut server = EmbeddedApi::new(&context)?;
+            if let Some(monitor) = &clock_monitor {
+                server = server.with_clock_guard(monitor.guard());
+            }
+            tui_app = tui_app.with_api(server.base_url(), server.token());
+            Some(server)
+        } else {
+            None
+        };
 
         let mut app = Self {
-            config,
-            database,
-            security_manager,
-            audit_manager,
+            context,
             document_manager,
             tui_app,
-            current_user: None,
-            current_session: None,
+            clock_monitor,
+            api_server,
         };
 
         // Log application startup
@@ -69,11 +72,23 @@ impl App {
 
     /// Run the QMS application
     pub async fn run(&mut self) -> Result<()> {
-        // Setup terminal
-        enable_raw_mode()?;
-        let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-        let backend = CrosstermBackend::new(stdout);
+        // Restore the terminal and audit panics; shut down gracefully on signals
+        let data_directory = std::path::Path::new(&self.context.config.application.data_directory);
+        crash_guard::install(self.context.database.clone(), data_directory);
+        let shutdown = std::sync::Arc::new(ShutdownCoordinator::new(&self.context));
+        let signal_handler = shutdown.clone().spawn_signal_handler();
+
+        // Verify the system clock now and periodically while the TUI runs
+        if let Some(monitor) = self.clock_monitor.take() {
+            shutdown.track(monitor.spawn_periodic());
+        }
+        if let Some(server) = self.api_server.take() {
+            shutdown.track(server.spawn());
+        }
+
+        // Setup terminal; restored when the guard drops, also on error
+        let guard = TerminalGuard::enter()?;
+        let backend = CrosstermBackend::new(io::stdout());
         let mut terminal = Terminal::new(backend)?;
 
         // Create default session for system user
@@ -81,18 +96,10 @@ impl App {
 
         // Main application loop
         let result = self.run_app(&mut terminal).await;
+        drop(guard);
 
-        // Restore terminal
-        disable_raw_mode()?;
-        execute!(
-            terminal.backend_mut(),
-            LeaveAlternateScreen,
-            DisableMouseCapture
-        )?;
-        terminal.show_cursor()?;
-
-        // Log application shutdown
-        self.log_system_event("APPLICATION_SHUTDOWN", "QMS system shutdown")?;
+        signal_handler.abort();
+        shutdown.shutdown("User exit").await?;
 
         result
     }
@@ -117,7 +124,7 @@ impl App {
             }
 
             // Cleanup expired sessions periodically
-            self.security_manager.cleanup_expired_sessions();
+            self.context.security.cleanup_expired_sessions();
 
             // Small delay to prevent busy waiting
             tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
@@ -129,13 +136,12 @@ impl App {
     /// Create system session for audit logging
     fn create_system_session(&mut self) -> Result<()> {
         let system_user = "system".to_string();
-        let session_id = self.security_manager.create_session(
+        let session_id = self.context.security.create_session(
             system_user.clone(),
             Some("127.0.0.1".to_string())
         )?;
 
-        self.current_user = Some(system_user);
-        self.current_session = Some(session_id);
+        self.context.set_session(Some(SessionContext { user_id: system_user, session_id }));
 
         self.log_system_event("SESSION_CREATED", "System session established")?;
         Ok(())
@@ -143,8 +149,9 @@ impl App {
 
     /// Log system events to audit trail
     fn log_system_event(&mut self, action: &str, details: &str) -> Result<()> {
-        let user_id = self.current_user.clone().unwrap_or_else(|| "system".to_string());
-        let session_id = self.current_session.clone().unwrap_or_else(|| "system-session".to_string());
+        let session = self.context.session();
+        let user_id = self.context.current_user();
+        let session_id = session.map(|s| s.session_id).unwrap_or_else(|| "system-session".to_string());
 
         let entry = AuditLogEntry::new(
             user_id,
@@ -158,49 +165,28 @@ impl App {
             "compliance_version": crate::FDA_CFR_PART_820_VERSION
         }));
 
-        self.audit_manager.log_event(entry)?;
+        self.context.audit_manager.log_event(entry)?;
         Ok(())
     }
 
+    /// Run the compliance self-test against the shared database.
+    pub fn compliance_checklist(&self) -> ComplianceChecklist {
+        self.context.compliance_checklist(self.clock_check().as_ref())
+    }
+
     /// Perform startup validation checks
     pub fn validate_startup(&self) -> Result<()> {
-        // Validate configuration
-        self.config.validate()?;
-
-        // Verify audit trail integrity
-        let integrity_report = self.database.verify_audit_integrity()?;
-        if !integrity_report.integrity_verified {
-            // For test environments, allow some gaps but still log them
-            if cfg!(test) && integrity_report.gaps_found < 50 {
-                eprintln!("Warning: {} audit trail gaps found in test environment", integrity_report.gaps_found);
-            } else {
-                return Err(QmsError::AuditTrail {
-                    message: format!("Audit trail integrity check failed: {}", integrity_report.details),
-                });
-            }
-        }
-
-        // Check FDA compliance settings
-        if !self.config.compliance.strict_validation {
-            return Err(QmsError::Validation {
-                field: "strict_validation".to_string(),
-                message: "FDA strict validation mode must be enabled".to_string(),
-            });
-        }
-
-        if !self.config.compliance.cfr_part_11_mode {
-            return Err(QmsError::Validation {
-                field: "cfr_part_11_mode".to_string(),
-                message: "CFR Part 11 compliance mode must be enabled".to_string(),
-            });
-        }
+        self.context.validate_startup(self.clock_check().as_ref()).map(|_| ())
+    }
 
-        Ok(())
+    /// Latest NTP comparison, while the clock monitor has not been handed off.
+    fn clock_check(&self) -> Option<ClockCheck> {
+        self.clock_monitor.as_ref().and_then(|monitor| monitor.guard().last_check())
     }
 
     /// Get system status for dashboard
     pub fn get_system_status(&self) -> SystemStatus {
-        let integrity_report = self.database.verify_audit_integrity()
+        let integrity_report = self.context.database.verify_audit_integrity()
             .unwrap_or_else(|_| crate::database::AuditIntegrityReport {
                 total_entries: 0,
                 earliest_entry: None,
@@ -212,13 +198,13 @@ impl App {
 
         SystemStatus {
             operational: true,
-            fda_compliant: self.config.compliance.strict_validation,
+            fda_compliant: self.context.config.compliance.strict_validation,
             audit_trail_enabled: true,
             audit_entries_count: integrity_report.total_entries,
             audit_integrity_verified: integrity_report.integrity_verified,
-            active_sessions: self.security_manager.active_sessions.len(),
+            active_sessions: self.context.security.active_session_count(),
             last_backup: None, // Would be populated from actual backup system
-            encryption_enabled: self.config.logging.encrypt_logs,
+            encryption_enabled: self.context.config.logging.encrypt_logs,
         }
     }
 }
@@ -275,7 +261,7 @@ mod tests {
         
         let result = app.create_system_session();
         assert!(result.is_ok());
-        assert!(app.current_user.is_some());
-        assert!(app.current_session.is_some());
+        assert!(app.context.session().is_some());
+        assert_eq!(app.context.current_user(), "system");
     }
 }
\ No newline at end of file
@@ -2,19 +2,31 @@ use crate::error::{QmsError, Result};
 use crate::database::Database;
 use crate::config::ComplianceConfig;
 use crate::logging::{AuditLogEntry, AuditOutcome};
+use crate::unit_of_work::UnitOfWork;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 /// Audit trail manager for FDA compliance
+#[derive(Clone)]
 pub struct AuditManager {
     database: Database,
+    site_id: String,
 }
 
 impl AuditManager {
     /// Create a new audit manager with database connection
     pub fn new(database: Database) -> Self {
-        Self { database }
+        Self {
+            database,
+            site_id: crate::site::DEFAULT_SITE_ID.to_string(),
+        }
+    }
+
+    /// Record entries against `site_id` instead of the default site
+    pub fn with_site<S: Into<String>>(mut self, site_id: S) -> Self {
+        self.site_id = site_id.into();
+        self
     }
 
     /// Log an action for audit trail
@@ -26,6 +38,40 @@ impl AuditManager {
         outcome: &str,
         metadata: Option<String>,
     ) -> Result<()> {
+        let entry = self.action_entry(user_id, action, resource, outcome, metadata);
+
+        // Store the audit entry in the database
+        self.database.insert_audit_entry(&entry)?;
+        Ok(())
+    }
+
+    /// Like [`log_action`](Self::log_action), but written inside `uow` so the
+    /// entry commits or rolls back with the rest of the operation.
+    pub fn log_action_in(
+        &self,
+        uow: &UnitOfWork,
+        user_id: &str,
+        action: &str,
+        resource: &str,
+        outcome: &str,
+        metadata: Option<String>,
+    ) -> Result<()> {
+        uow.audit(&self.action_entry(user_id, action, resource, outcome, metadata))
+    }
+
+    /// Database the audit trail is written to.
+    pub fn database(&self) -> &Database {
+        &self.database
+    }
+
+    fn action_entry(
+        &self,
+        user_id: &str,
+        action: &str,
+        resource: &str,
+        outcome: &str,
+        metadata: Option<String>,
+    ) -> AuditLogEntry {
         use uuid::Uuid;
         use chrono::Utc;
 
@@ -40,7 +86,7 @@ impl AuditManager {
             .map(|m| serde_json::from_str(&m).unwrap_or_else(|_| serde_json::Value::String(m)))
             .unwrap_or(serde_json::Value::Null);
 
-        let entry = AuditLogEntry {
+        AuditLogEntry {
             timestamp: Utc::now(),
             user_id: user_id.to_string(),
             action: action.to_string(),
@@ -51,11 +97,8 @@ impl AuditManager {
             metadata: metadata_json,
             compliance_version: "21CFR820".to_string(),
             signature_hash: None,
-        };
-
-        // Store the audit entry in the database
-        self.database.insert_audit_entry(&entry)?;
-        Ok(())
+            site_id: self.site_id.clone(),
+        }
     }
 
     /// Log an audit event
@@ -65,6 +108,15 @@ impl AuditManager {
         Ok(())
     }
 
+    /// Log many audit events in one transaction. All entries are validated
+    /// first, so an invalid entry stores nothing.
+    pub fn log_events(&self, entries: &[AuditLogEntry]) -> Result<usize> {
+        for entry in entries {
+            entry.validate()?;
+        }
+        self.database.insert_audit_entries(entries)
+    }
+
     /// Generate FDA compliance report
     pub fn generate_compliance_report(&self, start_date: DateTime<Utc>, end_date: DateTime<Utc>) -> Result<ComplianceReport> {
         let integrity_report = self.database.verify_audit_integrity()?;
@@ -108,6 +160,7 @@ pub enum ComplianceStatus {
 }
 
 /// Simple audit logger for module-level audit logging
+#[derive(Clone)]
 pub struct AuditLogger {
     session_id: String,
 }
@@ -207,4 +260,29 @@ mod tests {
         let report = audit_manager.generate_compliance_report(start, end).unwrap();
         assert!(!report.report_id.is_empty());
     }
+
+    #[test]
+    fn test_log_events_is_all_or_nothing() {
+        let database = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        }).unwrap();
+        let audit_manager = AuditManager::new(database.clone());
+        let entry = |user: &str| AuditLogEntry::new(
+            user.to_string(),
+            "capa_status_changed".to_string(),
+            "capa:bulk".to_string(),
+            AuditOutcome::Success,
+            "session".to_string(),
+        );
+
+        assert!(audit_manager.log_events(&[entry("qa"), entry("")]).is_err());
+        assert!(database.get_audit_entries_for_resource("capa:bulk").unwrap().is_empty());
+
+        assert_eq!(audit_manager.log_events(&[entry("qa"), entry("qm")]).unwrap(), 2);
+        assert_eq!(database.get_audit_entries_for_resource("capa:bulk").unwrap().len(), 2);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,450 @@
+//! # Audit Findings - Commitment Tracking for Internal & Supplier Audits
+//!
+//! Every finding from an internal audit (ISO 13485 §8.2.4, 21 CFR 820.22) or
+//! a supplier audit (§7.4, 21 CFR 820.50) carries a responsible owner and a
+//! committed response date, and is closed only with documented closure
+//! evidence. Open findings are aged into 0-30, 31-60 and 60+ day buckets for
+//! the dashboard and as a management review input (§5.6.2).
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::report::{Chart, ChartDatum, ChartKind, ReportSection, ReportTable};
+
+/// Upper bounds (inclusive, in days) of the aging buckets; the last is open-ended.
+pub const AGING_BUCKETS: [(i64, &str); 3] = [(30, "0-30 days"), (60, "31-60 days"), (i64::MAX, "60+ days")];
+
+/// Kind of audit a finding was raised in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditKind {
+    Internal,
+    Supplier,
+}
+
+impl AuditKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditKind::Internal => "internal",
+            AuditKind::Supplier => "supplier",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [AuditKind::Internal, AuditKind::Supplier].into_iter().find(|item| item.as_str() == value)
+    }
+}
+
+/// Finding grade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingSeverity {
+    Major,
+    Minor,
+    Observation,
+}
+
+impl FindingSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FindingSeverity::Major => "major",
+            FindingSeverity::Minor => "minor",
+            FindingSeverity::Observation => "observation",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [FindingSeverity::Major, FindingSeverity::Minor, FindingSeverity::Observation]
+            .into_iter()
+            .find(|item| item.as_str() == value)
+    }
+}
+
+/// An audit finding and its commitment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditFinding {
+    pub id: String,
+    pub audit_kind: AuditKind,
+    /// Audit identifier, e.g. "IA-2026-03" or the supplier audited
+    pub audit_reference: String,
+    pub description: String,
+    pub severity: FindingSeverity,
+    /// Person responsible for the response
+    pub owner: String,
+    pub committed_date: NaiveDate,
+    pub raised_by: String,
+    pub raised_at: DateTime<Utc>,
+    pub closure_evidence: Option<String>,
+    pub closed_by: Option<String>,
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+impl AuditFinding {
+    pub fn is_open(&self) -> bool {
+        self.closed_at.is_none()
+    }
+
+    pub fn age_days(&self, as_of: DateTime<Utc>) -> i64 {
+        (as_of - self.raised_at).num_days().max(0)
+    }
+
+    /// Open past its committed response date.
+    pub fn is_overdue(&self, as_of: DateTime<Utc>) -> bool {
+        self.is_open() && self.committed_date < as_of.date_naive()
+    }
+}
+
+/// Open findings per aging bucket.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FindingAging {
+    /// Counts in [`AGING_BUCKETS`] order
+    pub buckets: [usize; 3],
+    /// Open findings past their committed date
+    pub overdue: usize,
+}
+
+impl FindingAging {
+    pub fn from_findings(findings: &[AuditFinding], as_of: DateTime<Utc>) -> Self {
+        let mut aging = FindingAging::default();
+        for finding in findings.iter().filter(|f| f.is_open()) {
+            let age = finding.age_days(as_of);
+            let idx = AGING_BUCKETS.iter().position(|(max, _)| age <= *max).unwrap_or(AGING_BUCKETS.len() - 1);
+            aging.buckets[idx] += 1;
+            if finding.is_overdue(as_of) {
+                aging.overdue += 1;
+            }
+        }
+        aging
+    }
+
+    pub fn open(&self) -> usize {
+        self.buckets.iter().sum()
+    }
+}
+
+/// Persistence and commitment rules for audit findings.
+pub struct FindingRepo<'a> {
+    db: &'a Database,
+    audit: AuditManager,
+}
+
+impl<'a> FindingRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, audit: AuditManager::new(db.clone()) }
+    }
+
+    /// Record a finding with its owner and committed response date.
+    pub fn raise(
+        &self,
+        audit_kind: AuditKind,
+        audit_reference: &str,
+        description: &str,
+        severity: FindingSeverity,
+        owner: &str,
+        committed_date: NaiveDate,
+        raised_by: &str,
+    ) -> Result<AuditFinding> {
+        for (field, value) in [("audit_reference", audit_reference), ("description", description), ("owner", owner)] {
+            if value.trim().is_empty() {
+                return Err(QmsError::Validation { field: field.to_string(), message: format!("{} is required", field) });
+            }
+        }
+        let finding = AuditFinding {
+            id: Uuid::new_v4().to_string(),
+            audit_kind,
+            audit_reference: audit_reference.to_string(),
+            description: description.to_string(),
+            severity,
+            owner: owner.to_string(),
+            committed_date,
+            raised_by: raised_by.to_string(),
+            raised_at: Utc::now(),
+            closure_evidence: None,
+            closed_by: None,
+            closed_at: None,
+        };
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "INSERT INTO audit_findings
+                     (id, audit_kind, audit_reference, description, severity, owner, committed_date, raised_by, raised_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    finding.id,
+                    audit_kind.as_str(),
+                    finding.audit_reference,
+                    finding.description,
+                    severity.as_str(),
+                    finding.owner,
+                    committed_date.to_string(),
+                    raised_by,
+                    finding.raised_at.to_rfc3339(),
+                ],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                raised_by,
+                "audit_finding_raised",
+                &resource(&finding.id),
+                "Success",
+                Some(format!("{} {} finding, owner {}, due {}", audit_reference, severity.as_str(), owner, committed_date)),
+            )
+        })?;
+        Ok(finding)
+    }
+
+    pub fn get(&self, id: &str) -> Result<AuditFinding> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(&format!("SELECT {} FROM audit_findings WHERE id = ?1", COLUMNS), params![id], row_to_finding)
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "audit_finding".to_string(), id: id.to_string() })
+    }
+
+    /// Move the committed response date; the reason is kept in the audit trail.
+    pub fn recommit(&self, id: &str, committed_date: NaiveDate, reason: &str, by: &str) -> Result<AuditFinding> {
+        if reason.trim().is_empty() {
+            return Err(QmsError::Validation {
+                field: "reason".to_string(),
+                message: "A reason is required to change a committed date".to_string(),
+            });
+        }
+        let mut finding = self.open_finding(id, "recommit")?;
+        let previous = finding.committed_date;
+        finding.committed_date = committed_date;
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE audit_findings SET committed_date = ?2 WHERE id = ?1",
+                params![id, committed_date.to_string()],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                by,
+                "audit_finding_recommitted",
+                &resource(id),
+                "Success",
+                Some(format!("{} -> {}: {}", previous, committed_date, reason)),
+            )
+        })?;
+        Ok(finding)
+    }
+
+    /// Close a finding with its closure evidence.
+    pub fn close(&self, id: &str, closure_evidence: &str, closed_by: &str) -> Result<AuditFinding> {
+        if closure_evidence.trim().is_empty() {
+            return Err(QmsError::Validation {
+                field: "closure_evidence".to_string(),
+                message: "Closure evidence must be documented".to_string(),
+            });
+        }
+        let mut finding = self.open_finding(id, "close")?;
+        let now = Utc::now();
+        finding.closure_evidence = Some(closure_evidence.to_string());
+        finding.closed_by = Some(closed_by.to_string());
+        finding.closed_at = Some(now);
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE audit_findings SET closure_evidence = ?2, closed_by = ?3, closed_at = ?4 WHERE id = ?1",
+                params![id, closure_evidence, closed_by, now.to_rfc3339()],
+            )?;
+            let outcome = if finding.committed_date < now.date_naive() { "Warning" } else { "Success" };
+            self.audit.log_action_in(
+                uow,
+                closed_by,
+                "audit_finding_closed",
+                &resource(id),
+                outcome,
+                Some(closure_evidence.to_string()),
+            )
+        })?;
+        Ok(finding)
+    }
+
+    /// Open findings, earliest commitment first.
+    pub fn open_findings(&self) -> Result<Vec<AuditFinding>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM audit_findings WHERE closed_at IS NULL ORDER BY committed_date, raised_at",
+                COLUMNS
+            ))?;
+            let findings = stmt.query_map([], row_to_finding)?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(findings)
+        })
+    }
+
+    pub fn aging(&self, as_of: DateTime<Utc>) -> Result<FindingAging> {
+        Ok(FindingAging::from_findings(&self.open_findings()?, as_of))
+    }
+
+    fn open_finding(&self, id: &str, action: &str) -> Result<AuditFinding> {
+        let finding = self.get(id)?;
+        if !finding.is_open() {
+            return Err(QmsError::Validation {
+                field: "closed_at".to_string(),
+                message: format!("Cannot {} closed finding {}", action, id),
+            });
+        }
+        Ok(finding)
+    }
+}
+
+/// Management review input: open findings by age with overdue commitments.
+pub fn finding_aging_section(findings: &[AuditFinding], as_of: DateTime<Utc>) -> ReportSection {
+    let aging = FindingAging::from_findings(findings, as_of);
+    let chart = Chart {
+        title: "Open Audit Findings by Age".to_string(),
+        kind: ChartKind::Bar,
+        data: AGING_BUCKETS
+            .iter()
+            .zip(aging.buckets)
+            .map(|((_, label), count)| ChartDatum { label: label.to_string(), value: count as f32 })
+            .collect(),
+    };
+    let section = ReportSection::new("Audit Finding Commitments")
+        .key_values(vec![
+            ("Open Findings", aging.open().to_string()),
+            ("Overdue Commitments", aging.overdue.to_string()),
+        ])
+        .chart(chart);
+
+    let mut overdue: Vec<&AuditFinding> = findings.iter().filter(|f| f.is_overdue(as_of)).collect();
+    if overdue.is_empty() {
+        return section;
+    }
+    overdue.sort_by_key(|f| f.committed_date);
+    section.table(overdue.iter().fold(
+        ReportTable::new(vec!["Audit", "Finding", "Severity", "Owner", "Committed", "Age (days)"])
+            .with_column_weights(vec![1.2, 2.8, 0.9, 1.1, 1.1, 0.8]),
+        |table, f| {
+            table.with_row(vec![
+                f.audit_reference.clone(),
+                f.description.clone(),
+                f.severity.as_str().to_string(),
+                f.owner.clone(),
+                f.committed_date.to_string(),
+                f.age_days(as_of).to_string(),
+            ])
+        },
+    ))
+}
+
+fn resource(id: &str) -> String {
+    format!("audit_finding:{}", id)
+}
+
+const COLUMNS: &str = "id, audit_kind, audit_reference, description, severity, owner, committed_date, raised_by,
+     raised_at, closure_evidence, closed_by, closed_at";
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn parse_time(row: &Row, index: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| conversion_error(index, e.to_string()))
+        })
+        .transpose()
+}
+
+fn parse_text<T>(row: &Row, index: usize, parse: fn(&str) -> Option<T>) -> rusqlite::Result<T> {
+    let value: String = row.get(index)?;
+    parse(&value).ok_or_else(|| conversion_error(index, format!("unexpected value {}", value)))
+}
+
+fn row_to_finding(row: &Row) -> rusqlite::Result<AuditFinding> {
+    let committed_date: String = row.get(6)?;
+    Ok(AuditFinding {
+        id: row.get(0)?,
+        audit_kind: parse_text(row, 1, AuditKind::parse)?,
+        audit_reference: row.get(2)?,
+        description: row.get(3)?,
+        severity: parse_text(row, 4, FindingSeverity::parse)?,
+        owner: row.get(5)?,
+        committed_date: committed_date.parse().map_err(|e: chrono::ParseError| conversion_error(6, e.to_string()))?,
+        raised_by: row.get(7)?,
+        raised_at: parse_time(row, 8)?.unwrap_or_default(),
+        closure_evidence: row.get(9)?,
+        closed_by: row.get(10)?,
+        closed_at: parse_time(row, 11)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use chrono::Duration;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_commitment_lifecycle() {
+        let db = test_db();
+        let repo = FindingRepo::new(&db);
+        let today = Utc::now().date_naive();
+        assert!(repo
+            .raise(AuditKind::Internal, "IA-2026-03", "No training record", FindingSeverity::Minor, " ", today, "auditor")
+            .is_err());
+        let finding = repo
+            .raise(AuditKind::Supplier, "Acme Molding", "Uncalibrated gauge", FindingSeverity::Major, "sqe1", today, "auditor")
+            .unwrap();
+
+        assert!(repo.recommit(&finding.id, today + Duration::days(14), "", "qa_manager").is_err());
+        let moved = repo.recommit(&finding.id, today + Duration::days(14), "Supplier awaiting gauge", "qa_manager").unwrap();
+        assert_eq!(repo.get(&finding.id).unwrap().committed_date, moved.committed_date);
+
+        assert!(repo.close(&finding.id, "", "qa_manager").is_err());
+        let closed = repo.close(&finding.id, "Calibration certificate CAL-881", "qa_manager").unwrap();
+        assert_eq!(repo.get(&finding.id).unwrap(), closed);
+        assert!(repo.close(&finding.id, "again", "qa_manager").is_err());
+        assert!(repo.open_findings().unwrap().is_empty());
+        assert_eq!(db.get_audit_entries_for_resource(&resource(&finding.id)).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_aging_buckets_and_overdue() {
+        let now = Utc::now();
+        let finding = |age: i64, due_in: i64| AuditFinding {
+            id: Uuid::new_v4().to_string(),
+            audit_kind: AuditKind::Internal,
+            audit_reference: "IA-2026-01".to_string(),
+            description: "Finding".to_string(),
+            severity: FindingSeverity::Minor,
+            owner: "owner".to_string(),
+            committed_date: (now + Duration::days(due_in)).date_naive(),
+            raised_by: "auditor".to_string(),
+            raised_at: now - Duration::days(age),
+            closure_evidence: None,
+            closed_by: None,
+            closed_at: None,
+        };
+        let mut closed = finding(90, -30);
+        closed.closed_at = Some(now);
+        let findings = [finding(5, 10), finding(30, 1), finding(45, -2), finding(61, -20), closed];
+
+        let aging = FindingAging::from_findings(&findings, now);
+        assert_eq!(aging, FindingAging { buckets: [2, 1, 1], overdue: 2 });
+        assert_eq!(aging.open(), 4);
+
+        let section = finding_aging_section(&findings, now);
+        assert_eq!(section.title, "Audit Finding Commitments");
+    }
+}
@@ -0,0 +1,579 @@
+//! # Audit Review - Periodic Sampled Audit Trail Review
+//!
+//! 21 CFR Part 11 and the FDA data integrity guidance expect audit trails to
+//! be reviewed, not only kept. Reviewing every entry is impractical, so a
+//! review covers a sample of one period's entries, drawn with the configured
+//! [`SamplingStrategy`]. The reviewer's own entries are left out of the
+//! population so nobody reviews their own actions. The reviewer marks each
+//! sampled entry acceptable or to be investigated (with a comment), then
+//! completes the review with a conclusion. The review record, its sample and
+//! the audit entries written along the way are the compliance evidence.
+//!
+//! The random generator is seeded from the review id, so a sample can be
+//! re-drawn from the same population to show it was not hand-picked.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::{row_to_audit_entry, AuditTrailEntry, Database};
+use crate::error::{QmsError, Result};
+
+/// How audit entries are drawn for review.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SamplingStrategy {
+    /// Uniform random sample of the period
+    #[default]
+    Random,
+    /// Every failed or warning outcome first, the remainder at random
+    FailuresFirst,
+    /// Spread evenly across users, random within each user
+    PerUser,
+}
+
+impl SamplingStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SamplingStrategy::Random => "random",
+            SamplingStrategy::FailuresFirst => "failures_first",
+            SamplingStrategy::PerUser => "per_user",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [SamplingStrategy::Random, SamplingStrategy::FailuresFirst, SamplingStrategy::PerUser]
+            .into_iter()
+            .find(|item| item.as_str() == value)
+    }
+}
+
+/// Reviewer's disposition of a sampled entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Disposition {
+    Acceptable,
+    /// The entry needs follow-up; a comment is required
+    Investigate,
+}
+
+impl Disposition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Disposition::Acceptable => "acceptable",
+            Disposition::Investigate => "investigate",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [Disposition::Acceptable, Disposition::Investigate].into_iter().find(|item| item.as_str() == value)
+    }
+}
+
+/// One audit trail review.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditReview {
+    pub id: Uuid,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub strategy: SamplingStrategy,
+    /// Entries sampled; less than requested when the population is small
+    pub sample_size: usize,
+    /// Entries in the period eligible for the sample
+    pub population: usize,
+    pub reviewer: String,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub conclusion: Option<String>,
+}
+
+impl AuditReview {
+    pub fn is_complete(&self) -> bool {
+        self.completed_at.is_some()
+    }
+
+    fn seed(&self) -> u64 {
+        let bytes = self.id.as_bytes();
+        u64::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]])
+    }
+}
+
+/// A sampled entry with its disposition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SampledEntry {
+    pub entry: AuditTrailEntry,
+    pub disposition: Option<Disposition>,
+    pub comment: Option<String>,
+    pub dispositioned_at: Option<DateTime<Utc>>,
+}
+
+/// Draw up to `size` entry ids from `population` (ordered by timestamp).
+pub fn select_sample(
+    population: &[AuditTrailEntry],
+    strategy: SamplingStrategy,
+    size: usize,
+    seed: u64,
+) -> Vec<String> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut selected: Vec<&AuditTrailEntry> = match strategy {
+        SamplingStrategy::Random => population.choose_multiple(&mut rng, size).collect(),
+        SamplingStrategy::FailuresFirst => {
+            let (mut flagged, rest): (Vec<&AuditTrailEntry>, Vec<&AuditTrailEntry>) =
+                population.iter().partition(|e| e.outcome == "FAILURE" || e.outcome == "WARNING");
+            flagged.shuffle(&mut rng);
+            flagged.truncate(size);
+            let remaining = size - flagged.len();
+            flagged.extend(rest.choose_multiple(&mut rng, remaining).copied());
+            flagged
+        }
+        SamplingStrategy::PerUser => {
+            let mut by_user: BTreeMap<&str, Vec<&AuditTrailEntry>> = BTreeMap::new();
+            for entry in population {
+                by_user.entry(entry.user_id.as_str()).or_default().push(entry);
+            }
+            for entries in by_user.values_mut() {
+                entries.shuffle(&mut rng);
+            }
+            // Round-robin over users until the sample is full
+            let mut selected = Vec::new();
+            let mut round = 0;
+            while selected.len() < size.min(population.len()) {
+                for entries in by_user.values() {
+                    if let Some(entry) = entries.get(round) {
+                        if selected.len() < size {
+                            selected.push(*entry);
+                        }
+                    }
+                }
+                round += 1;
+            }
+            selected
+        }
+    };
+    selected.sort_by(|a, b| (&a.timestamp, &a.id).cmp(&(&b.timestamp, &b.id)));
+    selected.into_iter().map(|e| e.id.clone()).collect()
+}
+
+/// Persistence and workflow of audit trail reviews.
+pub struct AuditReviewRepo<'a> {
+    db: &'a Database,
+    audit: AuditManager,
+}
+
+impl<'a> AuditReviewRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, audit: AuditManager::new(db.clone()) }
+    }
+
+    /// Start the review of `period_start..=period_end` and draw its sample.
+    pub fn start(
+        &self,
+        period_start: NaiveDate,
+        period_end: NaiveDate,
+        strategy: SamplingStrategy,
+        sample_size: usize,
+        reviewer: &str,
+    ) -> Result<AuditReview> {
+        if period_start > period_end {
+            return Err(QmsError::Validation {
+                field: "period_start".to_string(),
+                message: format!("Period start {} is after its end {}", period_start, period_end),
+            });
+        }
+        if sample_size == 0 {
+            return Err(QmsError::Validation {
+                field: "sample_size".to_string(),
+                message: "Audit trail reviews must sample at least 1 entry".to_string(),
+            });
+        }
+        let mut review = AuditReview {
+            id: Uuid::new_v4(),
+            period_start,
+            period_end,
+            strategy,
+            sample_size: 0,
+            population: 0,
+            reviewer: reviewer.to_string(),
+            started_at: Utc::now(),
+            completed_at: None,
+            conclusion: None,
+        };
+        self.db.unit_of_work(|uow| {
+            let conn = uow.connection();
+            let existing: Option<String> = conn
+                .query_row(
+                    "SELECT id FROM audit_trail_reviews WHERE period_start = ?1 AND period_end = ?2",
+                    params![period_start.to_string(), period_end.to_string()],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(existing) = existing {
+                return Err(QmsError::Validation {
+                    field: "period_start".to_string(),
+                    message: format!("{} to {} is already covered by review {}", period_start, period_end, existing),
+                });
+            }
+            let mut stmt = conn.prepare(
+                "SELECT id, timestamp, user_id, action, resource, outcome, ip_address, session_id,
+                        metadata, compliance_version, signature_hash, created_at, site_id
+                 FROM audit_trail
+                 WHERE substr(timestamp, 1, 10) BETWEEN ?1 AND ?2 AND user_id != ?3
+                 ORDER BY timestamp, id",
+            )?;
+            let population = stmt
+                .query_map(params![period_start.to_string(), period_end.to_string(), reviewer], row_to_audit_entry)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            let sample = select_sample(&population, strategy, sample_size, review.seed());
+            review.population = population.len();
+            review.sample_size = sample.len();
+
+            conn.execute(
+                "INSERT INTO audit_trail_reviews
+                     (id, period_start, period_end, strategy, sample_size, population, reviewer, started_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    review.id.to_string(),
+                    period_start.to_string(),
+                    period_end.to_string(),
+                    strategy.as_str(),
+                    review.sample_size as i64,
+                    review.population as i64,
+                    reviewer,
+                    review.started_at.to_rfc3339(),
+                ],
+            )?;
+            for entry_id in &sample {
+                conn.execute(
+                    "INSERT INTO audit_review_samples (review_id, audit_entry_id) VALUES (?1, ?2)",
+                    params![review.id.to_string(), entry_id],
+                )?;
+            }
+            self.audit.log_action_in(
+                uow,
+                reviewer,
+                "audit_trail_review_started",
+                &resource(review.id),
+                "Success",
+                Some(format!(
+                    "{} to {}: {} of {} entries sampled ({})",
+                    period_start,
+                    period_end,
+                    review.sample_size,
+                    review.population,
+                    strategy.as_str()
+                )),
+            )
+        })?;
+        Ok(review)
+    }
+
+    pub fn get(&self, review_id: Uuid) -> Result<AuditReview> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM audit_trail_reviews WHERE id = ?1", COLUMNS),
+                        params![review_id.to_string()],
+                        row_to_review,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "audit_trail_review".to_string(), id: review_id.to_string() })
+    }
+
+    /// All reviews, most recent period first.
+    pub fn list(&self) -> Result<Vec<AuditReview>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM audit_trail_reviews ORDER BY period_end DESC, started_at DESC",
+                COLUMNS
+            ))?;
+            let reviews = stmt.query_map([], row_to_review)?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(reviews)
+        })
+    }
+
+    /// The sampled entries of a review, oldest first.
+    pub fn sample(&self, review_id: Uuid) -> Result<Vec<SampledEntry>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT a.id, a.timestamp, a.user_id, a.action, a.resource, a.outcome, a.ip_address, a.session_id,
+                        a.metadata, a.compliance_version, a.signature_hash, a.created_at, a.site_id,
+                        s.disposition, s.comment, s.dispositioned_at
+                 FROM audit_review_samples s JOIN audit_trail a ON a.id = s.audit_entry_id
+                 WHERE s.review_id = ?1
+                 ORDER BY a.timestamp, a.id",
+            )?;
+            let sample = stmt
+                .query_map(params![review_id.to_string()], |row| {
+                    let disposition: Option<String> = row.get(13)?;
+                    Ok(SampledEntry {
+                        entry: row_to_audit_entry(row)?,
+                        disposition: disposition
+                            .map(|d| {
+                                Disposition::parse(&d)
+                                    .ok_or_else(|| conversion_error(13, format!("unknown disposition {}", d)))
+                            })
+                            .transpose()?,
+                        comment: row.get(14)?,
+                        dispositioned_at: parse_time(row, 15)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(sample)
+        })
+    }
+
+    /// Record the reviewer's disposition of a sampled entry; an entry can be
+    /// re-dispositioned until the review is completed.
+    pub fn dispose(
+        &self,
+        review_id: Uuid,
+        entry_id: &str,
+        disposition: Disposition,
+        comment: Option<&str>,
+        reviewer: &str,
+    ) -> Result<()> {
+        let review = self.open_review(review_id, reviewer)?;
+        let comment = comment.map(str::trim).filter(|c| !c.is_empty());
+        if disposition == Disposition::Investigate && comment.is_none() {
+            return Err(QmsError::Validation {
+                field: "comment".to_string(),
+                message: "Entries marked for investigation need a comment".to_string(),
+            });
+        }
+        self.db.unit_of_work(|uow| {
+            let updated = uow.connection().execute(
+                "UPDATE audit_review_samples SET disposition = ?3, comment = ?4, dispositioned_at = ?5
+                 WHERE review_id = ?1 AND audit_entry_id = ?2",
+                params![review.id.to_string(), entry_id, disposition.as_str(), comment, Utc::now().to_rfc3339()],
+            )?;
+            if updated == 0 {
+                return Err(QmsError::NotFound {
+                    resource: "audit_review_sample".to_string(),
+                    id: entry_id.to_string(),
+                });
+            }
+            self.audit.log_action_in(
+                uow,
+                reviewer,
+                "audit_trail_entry_dispositioned",
+                &resource(review.id),
+                if disposition == Disposition::Investigate { "Warning" } else { "Success" },
+                Some(match comment {
+                    Some(comment) => format!("{}: {} ({})", entry_id, disposition.as_str(), comment),
+                    None => format!("{}: {}", entry_id, disposition.as_str()),
+                }),
+            )
+        })
+    }
+
+    /// Complete a review once every sampled entry is dispositioned.
+    pub fn complete(&self, review_id: Uuid, conclusion: &str, reviewer: &str) -> Result<AuditReview> {
+        let mut review = self.open_review(review_id, reviewer)?;
+        if conclusion.trim().is_empty() {
+            return Err(QmsError::Validation {
+                field: "conclusion".to_string(),
+                message: "A review conclusion is required".to_string(),
+            });
+        }
+        let sample = self.sample(review_id)?;
+        let pending = sample.iter().filter(|s| s.disposition.is_none()).count();
+        if pending > 0 {
+            return Err(QmsError::Validation {
+                field: "disposition".to_string(),
+                message: format!("{} sampled entries are not yet dispositioned", pending),
+            });
+        }
+        let investigate = sample.iter().filter(|s| s.disposition == Some(Disposition::Investigate)).count();
+        let now = Utc::now();
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE audit_trail_reviews SET completed_at = ?2, conclusion = ?3 WHERE id = ?1",
+                params![review_id.to_string(), now.to_rfc3339(), conclusion],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                reviewer,
+                "audit_trail_review_completed",
+                &resource(review_id),
+                if investigate > 0 { "Warning" } else { "Success" },
+                Some(format!(
+                    "{} to {}: {} acceptable, {} to investigate. {}",
+                    review.period_start,
+                    review.period_end,
+                    sample.len() - investigate,
+                    investigate,
+                    conclusion
+                )),
+            )
+        })?;
+        review.completed_at = Some(now);
+        review.conclusion = Some(conclusion.to_string());
+        Ok(review)
+    }
+
+    /// A review still open for `reviewer`; only the assigned reviewer works on it.
+    fn open_review(&self, review_id: Uuid, reviewer: &str) -> Result<AuditReview> {
+        let review = self.get(review_id)?;
+        if review.is_complete() {
+            return Err(QmsError::Validation {
+                field: "review_id".to_string(),
+                message: format!("Audit trail review {} is already completed", review_id),
+            });
+        }
+        if review.reviewer != reviewer {
+            return Err(QmsError::Validation {
+                field: "reviewer".to_string(),
+                message: format!("Audit trail review {} is assigned to {}", review_id, review.reviewer),
+            });
+        }
+        Ok(review)
+    }
+}
+
+fn resource(review_id: Uuid) -> String {
+    format!("audit_review:{}", review_id)
+}
+
+const COLUMNS: &str = "id, period_start, period_end, strategy, sample_size, population, reviewer, started_at,
+     completed_at, conclusion";
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn parse_time(row: &Row, index: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| conversion_error(index, e.to_string()))
+        })
+        .transpose()
+}
+
+fn parse_date(row: &Row, index: usize) -> rusqlite::Result<NaiveDate> {
+    let value: String = row.get(index)?;
+    NaiveDate::parse_from_str(&value, "%Y-%m-%d").map_err(|e| conversion_error(index, e.to_string()))
+}
+
+fn row_to_review(row: &Row) -> rusqlite::Result<AuditReview> {
+    let id: String = row.get(0)?;
+    let strategy: String = row.get(3)?;
+    Ok(AuditReview {
+        id: Uuid::parse_str(&id).map_err(|e| conversion_error(0, e.to_string()))?,
+        period_start: parse_date(row, 1)?,
+        period_end: parse_date(row, 2)?,
+        strategy: SamplingStrategy::parse(&strategy)
+            .ok_or_else(|| conversion_error(3, format!("unknown strategy {}", strategy)))?,
+        sample_size: row.get::<_, i64>(4)? as usize,
+        population: row.get::<_, i64>(5)? as usize,
+        reviewer: row.get(6)?,
+        started_at: parse_time(row, 7)?.ok_or_else(|| conversion_error(7, "started_at is missing".to_string()))?,
+        completed_at: parse_time(row, 8)?,
+        conclusion: row.get(9)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+
+    fn entry(id: &str, user: &str, outcome: &str) -> AuditTrailEntry {
+        AuditTrailEntry {
+            id: id.to_string(),
+            timestamp: format!("2026-09-01T10:00:{}Z", id),
+            user_id: user.to_string(),
+            action: "capa_updated".to_string(),
+            resource: "capa:c1".to_string(),
+            outcome: outcome.to_string(),
+            ip_address: None,
+            session_id: "s".to_string(),
+            metadata: None,
+            compliance_version: "21CFR820".to_string(),
+            signature_hash: None,
+            created_at: String::new(),
+            site_id: "default".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_sampling_strategies() {
+        let population: Vec<AuditTrailEntry> = (10..30)
+            .map(|i| {
+                let user = if i < 26 { "alice" } else { "bob" };
+                entry(&i.to_string(), user, if i == 17 || i == 23 { "FAILURE" } else { "SUCCESS" })
+            })
+            .collect();
+
+        let random = select_sample(&population, SamplingStrategy::Random, 5, 7);
+        assert_eq!(random.len(), 5);
+        assert_eq!(random, select_sample(&population, SamplingStrategy::Random, 5, 7), "same seed, same sample");
+        assert_eq!(select_sample(&population, SamplingStrategy::Random, 50, 7).len(), 20);
+
+        let failures = select_sample(&population, SamplingStrategy::FailuresFirst, 3, 7);
+        assert!(failures.contains(&"17".to_string()) && failures.contains(&"23".to_string()));
+
+        let per_user = select_sample(&population, SamplingStrategy::PerUser, 8, 7);
+        let bob = per_user.iter().filter(|id| id.parse::<u32>().unwrap() >= 26).count();
+        assert_eq!(bob, 4, "bob's 4 entries are sampled alongside 4 of alice's 16");
+    }
+
+    #[test]
+    fn test_review_workflow() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let audit = AuditManager::new(db.clone());
+        for (user, action) in [("engineer", "capa_created"), ("engineer", "capa_updated"), ("auditor", "login")] {
+            audit.log_action(user, action, "capa:c1", "Success", None).unwrap();
+        }
+        let today = Utc::now().date_naive();
+        let repo = AuditReviewRepo::new(&db);
+
+        let review = repo.start(today, today, SamplingStrategy::Random, 10, "auditor").unwrap();
+        assert_eq!((review.population, review.sample_size), (2, 2), "own entries are excluded");
+        assert!(repo.start(today, today, SamplingStrategy::Random, 10, "qa").is_err(), "one review per period");
+
+        let sample = repo.sample(review.id).unwrap();
+        assert!(sample.iter().all(|s| s.entry.user_id == "engineer" && s.disposition.is_none()));
+        let (first, second) = (&sample[0].entry.id, &sample[1].entry.id);
+        assert!(repo.dispose(review.id, first, Disposition::Acceptable, None, "qa").is_err(), "not the reviewer");
+        assert!(repo.dispose(review.id, second, Disposition::Investigate, None, "auditor").is_err());
+        repo.dispose(review.id, first, Disposition::Acceptable, None, "auditor").unwrap();
+        assert!(repo.complete(review.id, "Done", "auditor").is_err(), "one entry pending");
+        repo.dispose(review.id, second, Disposition::Investigate, Some("Edit outside working hours"), "auditor")
+            .unwrap();
+
+        let completed = repo.complete(review.id, "One entry referred to QA", "auditor").unwrap();
+        assert!(completed.is_complete());
+        assert_eq!(repo.get(review.id).unwrap(), completed);
+        assert!(repo.dispose(review.id, first, Disposition::Investigate, Some("x"), "auditor").is_err());
+        assert_eq!(repo.sample(review.id).unwrap()[1].disposition, Some(Disposition::Investigate));
+
+        let evidence = db.get_audit_entries_for_resource(&format!("audit_review:{}", review.id)).unwrap();
+        let actions: Vec<&str> = evidence.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(
+            actions,
+            vec![
+                "audit_trail_review_started",
+                "audit_trail_entry_dispositioned",
+                "audit_trail_entry_dispositioned",
+                "audit_trail_review_completed"
+            ]
+        );
+    }
+}
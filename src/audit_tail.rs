@@ -0,0 +1,237 @@
+//! # Audit Tail - Live View of New Audit Entries
+//!
+//! During validation runs and troubleshooting it helps to watch the audit
+//! trail as it is written. [`AuditTail`] keeps the most recent entries in a
+//! bounded buffer and, on every [`AuditTail::poll`], appends those inserted
+//! since the last one. Polling pages by `(timestamp, id)` like the audit
+//! export stream, so no entry is skipped or shown twice. While paused the
+//! view holds still for scrollback and new entries are only counted; they
+//! are picked up when the tail resumes.
+
+use std::collections::VecDeque;
+
+use rusqlite::params;
+
+use crate::database::{row_to_audit_entry, AuditTrailEntry, Database};
+use crate::error::Result;
+
+/// Entries kept for scrollback when no capacity is given.
+pub const DEFAULT_TAIL_CAPACITY: usize = 500;
+
+/// Entries fetched per poll; a long pause is caught up over several polls.
+const POLL_CHUNK: i64 = 200;
+
+/// Which entries the tail shows. Both parts match case-insensitively
+/// anywhere in the field.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditTailFilter {
+    pub user: Option<String>,
+    pub action: Option<String>,
+}
+
+impl AuditTailFilter {
+    /// Parse `user:<text> action:<text>`; either part may be left out, and
+    /// a bare word filters by action.
+    pub fn parse(text: &str) -> Self {
+        let mut filter = Self::default();
+        for word in text.split_whitespace() {
+            match word.split_once(':') {
+                Some(("user", user)) if !user.is_empty() => filter.user = Some(user.to_lowercase()),
+                Some(("action", action)) if !action.is_empty() => filter.action = Some(action.to_lowercase()),
+                _ => filter.action = Some(word.to_lowercase()),
+            }
+        }
+        filter
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.user.is_none() && self.action.is_none()
+    }
+
+    pub fn matches(&self, entry: &AuditTrailEntry) -> bool {
+        let contains = |field: &str, part: &Option<String>| match part {
+            Some(part) => field.to_lowercase().contains(part.as_str()),
+            None => true,
+        };
+        contains(&entry.user_id, &self.user) && contains(&entry.action, &self.action)
+    }
+}
+
+impl std::fmt::Display for AuditTailFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<String> = [("user", &self.user), ("action", &self.action)]
+            .into_iter()
+            .filter_map(|(name, part)| part.as_ref().map(|part| format!("{}:{}", name, part)))
+            .collect();
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// Buffer of the latest audit entries, oldest first.
+pub struct AuditTail {
+    db: Database,
+    capacity: usize,
+    entries: VecDeque<AuditTrailEntry>,
+    after: Option<(String, String)>,
+    paused: bool,
+    /// Entries written since the tail was paused
+    pending: u64,
+    /// Visible entries hidden below the view while scrolled back
+    scroll: usize,
+    pub filter: AuditTailFilter,
+}
+
+impl AuditTail {
+    /// Start at the end of the trail, with up to `capacity` earlier entries
+    /// loaded for scrollback.
+    pub fn start(db: Database, capacity: usize) -> Result<Self> {
+        let capacity = capacity.max(1);
+        let mut entries: Vec<AuditTrailEntry> = db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, timestamp, user_id, action, resource, outcome, ip_address, session_id,
+                        metadata, compliance_version, signature_hash, created_at, site_id
+                 FROM audit_trail ORDER BY timestamp DESC, id DESC LIMIT ?1",
+            )?;
+            let rows = stmt.query_map(params![capacity as i64], row_to_audit_entry)?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        })?;
+        entries.reverse();
+        let after = entries.last().map(|entry| (entry.timestamp.clone(), entry.id.clone()));
+        Ok(Self {
+            db,
+            capacity,
+            entries: entries.into(),
+            after,
+            paused: false,
+            pending: 0,
+            scroll: 0,
+            filter: AuditTailFilter::default(),
+        })
+    }
+
+    /// Fetch entries written since the last poll. Returns how many were
+    /// added; while paused nothing is added and only the backlog is counted.
+    pub fn poll(&mut self) -> Result<usize> {
+        let (after_timestamp, after_id) = self.after.clone().unzip();
+        if self.paused {
+            self.pending = self.db.with_connection(|conn| {
+                Ok(conn.query_row(
+                    "SELECT COUNT(*) FROM audit_trail WHERE ?1 IS NULL OR (timestamp, id) > (?1, ?2)",
+                    params![after_timestamp, after_id],
+                    |row| row.get::<_, i64>(0),
+                )? as u64)
+            })?;
+            return Ok(0);
+        }
+        let new_entries: Vec<AuditTrailEntry> = self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, timestamp, user_id, action, resource, outcome, ip_address, session_id,
+                        metadata, compliance_version, signature_hash, created_at, site_id
+                 FROM audit_trail
+                 WHERE ?1 IS NULL OR (timestamp, id) > (?1, ?2)
+                 ORDER BY timestamp ASC, id ASC
+                 LIMIT ?3",
+            )?;
+            let rows = stmt.query_map(params![after_timestamp, after_id, POLL_CHUNK], row_to_audit_entry)?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        })?;
+        if let Some(last) = new_entries.last() {
+            self.after = Some((last.timestamp.clone(), last.id.clone()));
+        }
+        let added = new_entries.len();
+        self.entries.extend(new_entries);
+        let overflow = self.entries.len().saturating_sub(self.capacity);
+        self.entries.drain(..overflow);
+        Ok(added)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Entries written while paused and not yet shown.
+    pub fn pending(&self) -> u64 {
+        self.pending
+    }
+
+    /// Pause or resume. Resuming returns to the newest entry.
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        if !self.paused {
+            self.pending = 0;
+            self.scroll = 0;
+        }
+    }
+
+    /// Scroll back by `lines`; this pauses a live tail.
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.paused = true;
+        self.scroll = (self.scroll + lines).min(self.visible_count().saturating_sub(1));
+    }
+
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+
+    fn visible_count(&self) -> usize {
+        self.entries.iter().filter(|entry| self.filter.matches(entry)).count()
+    }
+
+    /// Up to `height` matching entries, oldest first, ending `scroll`
+    /// entries before the newest.
+    pub fn view(&self, height: usize) -> Vec<&AuditTrailEntry> {
+        let visible: Vec<&AuditTrailEntry> = self.entries.iter().filter(|entry| self.filter.matches(entry)).collect();
+        let end = visible.len().saturating_sub(self.scroll);
+        visible[end.saturating_sub(height)..end].to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditManager;
+    use crate::config::DatabaseConfig;
+
+    #[test]
+    fn test_tail_follows_pauses_and_filters() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let audit = AuditManager::new(db.clone());
+        let log = |user: &str, action: &str| audit.log_action(user, action, "tail", "Success", None).unwrap();
+        for i in 0..4 {
+            log("alice", &format!("early_{}", i));
+        }
+
+        let mut tail = AuditTail::start(db.clone(), 3).unwrap();
+        assert_eq!(
+            tail.view(10).iter().map(|e| e.action.as_str()).collect::<Vec<_>>(),
+            ["early_1", "early_2", "early_3"]
+        );
+        assert_eq!(tail.poll().unwrap(), 0);
+
+        log("bob", "login");
+        log("alice", "document_viewed");
+        assert_eq!(tail.poll().unwrap(), 2);
+        assert_eq!(tail.view(2).iter().map(|e| e.action.as_str()).collect::<Vec<_>>(), ["login", "document_viewed"]);
+
+        tail.scroll_up(1);
+        assert!(tail.is_paused());
+        assert_eq!(tail.view(1)[0].action, "login");
+        log("bob", "logout");
+        assert_eq!(tail.poll().unwrap(), 0);
+        assert_eq!(tail.pending(), 1);
+
+        tail.toggle_pause();
+        assert_eq!(tail.poll().unwrap(), 1);
+        tail.filter = AuditTailFilter::parse("user:BOB log");
+        assert_eq!(tail.filter.to_string(), "user:bob action:log");
+        assert_eq!(tail.view(10).iter().map(|e| e.action.as_str()).collect::<Vec<_>>(), ["login", "logout"]);
+        assert!(AuditTailFilter::parse("  ").is_empty());
+    }
+}
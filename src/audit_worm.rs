@@ -0,0 +1,328 @@
+//! # Audit WORM - Write-Once Audit Trail Segments
+//!
+//! Some customers require audit storage that cannot be rewritten, which a
+//! SQLite table cannot offer on its own. When `audit_worm.enabled` is set,
+//! the `audit_worm_sync` job copies new `audit_trail` rows, in insertion
+//! order, into segment files that are written once and made read-only. Each
+//! segment gets a seal file with the segment's SHA-256 and the SHA-256 of the
+//! previous seal, so removing, reordering or editing segments breaks the
+//! chain. [`AuditWormStore::verify`] checks the chain and compares every
+//! sealed entry with the database, which detects entries changed or deleted
+//! there after they were sealed.
+//!
+//! Object storage with retention locking (e.g. S3 Object Lock) is used by
+//! pointing `audit_worm.directory` at a locked mount; there is no direct
+//! object store client.
+//!
+//! Entries are ordered by SQLite `rowid`. A full `VACUUM` may renumber rowids
+//! of `audit_trail`, so databases are only vacuumed incrementally.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::database::{row_to_audit_entry, AuditTrailEntry, Database};
+use crate::error::{QmsError, Result};
+use crate::keystore::sha256_hex;
+
+/// Prefix of segment and seal file names.
+const SEGMENT_PREFIX: &str = "audit-segment-";
+const SEGMENT_SUFFIX: &str = ".jsonl";
+const SEAL_SUFFIX: &str = ".seal.json";
+
+/// `audit_trail` columns in [`row_to_audit_entry`] order, followed by the rowid.
+const ENTRY_COLUMNS: &str = "id, timestamp, user_id, action, resource, outcome, ip_address, session_id,
+                             metadata, compliance_version, signature_hash, created_at, site_id, rowid";
+
+/// One line of a segment file.
+#[derive(Debug, Serialize, Deserialize)]
+struct SegmentLine {
+    seq: i64,
+    entry: AuditTrailEntry,
+}
+
+/// Contents of a seal file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentSeal {
+    pub segment: String,
+    pub first_seq: i64,
+    pub last_seq: i64,
+    pub entries: usize,
+    /// SHA-256 of the segment file
+    pub sha256: String,
+    /// SHA-256 of the previous seal file; `None` for the first segment
+    pub previous_seal_sha256: Option<String>,
+    pub sealed_at: DateTime<Utc>,
+}
+
+/// Result of one copy of new audit entries.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WormSyncResult {
+    pub segments_written: usize,
+    pub entries_written: usize,
+    /// Highest audit entry sequence number sealed so far
+    pub last_seq: i64,
+}
+
+/// Result of [`AuditWormStore::verify`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WormVerification {
+    pub segments: usize,
+    pub entries: usize,
+    pub problems: Vec<String>,
+}
+
+impl WormVerification {
+    pub fn is_intact(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Directory of sealed audit trail segments.
+#[derive(Debug, Clone)]
+pub struct AuditWormStore {
+    directory: PathBuf,
+    segment_max_entries: usize,
+}
+
+impl AuditWormStore {
+    pub fn new<P: Into<PathBuf>>(directory: P, segment_max_entries: usize) -> Self {
+        Self { directory: directory.into(), segment_max_entries: segment_max_entries.max(1) }
+    }
+
+    /// Store configured in `config.audit_worm`.
+    pub fn from_config(config: &Config) -> Self {
+        let directory = match &config.audit_worm.directory {
+            Some(directory) => PathBuf::from(directory),
+            None => Path::new(&config.application.data_directory).join("audit-worm"),
+        };
+        Self::new(directory, config.audit_worm.segment_max_entries as usize)
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Seals in chain order, each with the SHA-256 of its file.
+    pub fn seals(&self) -> Result<Vec<(SegmentSeal, String)>> {
+        if !self.directory.exists() {
+            return Ok(Vec::new());
+        }
+        let mut names: Vec<String> = fs::read_dir(&self.directory)
+            .map_err(|e| fs_error(&self.directory, e))?
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with(SEGMENT_PREFIX) && name.ends_with(SEAL_SUFFIX))
+            .collect();
+        // Zero-padded sequence numbers sort in chain order
+        names.sort();
+        names
+            .into_iter()
+            .map(|name| {
+                let path = self.directory.join(&name);
+                let bytes = fs::read(&path).map_err(|e| fs_error(&path, e))?;
+                Ok((serde_json::from_slice(&bytes)?, sha256_hex(&bytes)))
+            })
+            .collect()
+    }
+
+    /// Seal all audit entries added since the last sealed segment.
+    pub fn sync(&self, db: &Database, now: DateTime<Utc>) -> Result<WormSyncResult> {
+        fs::create_dir_all(&self.directory).map_err(|e| fs_error(&self.directory, e))?;
+        let seals = self.seals()?;
+        let mut last_seq = seals.last().map_or(0, |(seal, _)| seal.last_seq);
+        let mut previous = seals.last().map(|(_, sha256)| sha256.clone());
+        let mut result = WormSyncResult { segments_written: 0, entries_written: 0, last_seq };
+
+        loop {
+            let lines = db.with_connection(|conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {} FROM audit_trail WHERE rowid > ?1 ORDER BY rowid LIMIT ?2",
+                    ENTRY_COLUMNS
+                ))?;
+                let rows = stmt.query_map(params![last_seq, self.segment_max_entries as i64], |row| {
+                    Ok(SegmentLine { seq: row.get(13)?, entry: row_to_audit_entry(row)? })
+                })?;
+                Ok(rows.collect::<std::result::Result<Vec<_>, _>>()?)
+            })?;
+            let (Some(first), Some(last)) = (lines.first(), lines.last()) else {
+                break;
+            };
+
+            let mut contents = String::new();
+            for line in &lines {
+                contents.push_str(&serde_json::to_string(line)?);
+                contents.push('\n');
+            }
+            let stem = format!("{}{:012}", SEGMENT_PREFIX, first.seq);
+            let segment = format!("{}{}", stem, SEGMENT_SUFFIX);
+            let segment_path = self.directory.join(&segment);
+            if segment_path.exists() {
+                // Left by an interrupted sync before it was sealed
+                fs::remove_file(&segment_path).map_err(|e| fs_error(&segment_path, e))?;
+            }
+            write_once(&segment_path, contents.as_bytes())?;
+
+            let seal = SegmentSeal {
+                segment,
+                first_seq: first.seq,
+                last_seq: last.seq,
+                entries: lines.len(),
+                sha256: sha256_hex(contents.as_bytes()),
+                previous_seal_sha256: previous.take(),
+                sealed_at: now,
+            };
+            let seal_json = serde_json::to_vec_pretty(&seal)?;
+            write_once(&self.directory.join(format!("{}{}", stem, SEAL_SUFFIX)), &seal_json)?;
+
+            previous = Some(sha256_hex(&seal_json));
+            last_seq = last.seq;
+            result.segments_written += 1;
+            result.entries_written += lines.len();
+            result.last_seq = last_seq;
+        }
+        Ok(result)
+    }
+
+    /// Check the seal chain and segment checksums, and that every sealed
+    /// entry is still in the database unchanged.
+    pub fn verify(&self, db: &Database) -> Result<WormVerification> {
+        let seals = self.seals()?;
+        let mut verification = WormVerification { segments: seals.len(), entries: 0, problems: Vec::new() };
+        let mut previous: Option<String> = None;
+        let mut previous_last_seq = 0;
+
+        for (seal, seal_sha256) in &seals {
+            if seal.previous_seal_sha256 != previous {
+                verification.problems.push(format!("Seal chain broken before {}", seal.segment));
+            }
+            if seal.first_seq <= previous_last_seq {
+                verification.problems.push(format!("{} overlaps the previous segment", seal.segment));
+            }
+            previous = Some(seal_sha256.clone());
+            previous_last_seq = seal.last_seq;
+
+            let path = self.directory.join(&seal.segment);
+            let contents = match fs::read(&path) {
+                Ok(contents) => contents,
+                Err(e) => {
+                    verification.problems.push(format!("{} cannot be read: {}", seal.segment, e));
+                    continue;
+                }
+            };
+            if sha256_hex(&contents) != seal.sha256 {
+                verification.problems.push(format!("{} does not match its seal", seal.segment));
+                continue;
+            }
+            let lines = String::from_utf8_lossy(&contents)
+                .lines()
+                .map(serde_json::from_str::<SegmentLine>)
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            if lines.len() != seal.entries {
+                verification.problems.push(format!(
+                    "{} holds {} entries, sealed {}",
+                    seal.segment,
+                    lines.len(),
+                    seal.entries
+                ));
+            }
+            verification.entries += lines.len();
+
+            db.with_connection(|conn| {
+                let mut stmt = conn.prepare(&format!("SELECT {} FROM audit_trail WHERE rowid = ?1", ENTRY_COLUMNS))?;
+                for line in &lines {
+                    let stored = stmt.query_row(params![line.seq], row_to_audit_entry).optional()?;
+                    match stored {
+                        None => verification.problems.push(format!(
+                            "Audit entry {} (seq {}) is missing from the database",
+                            line.entry.id, line.seq
+                        )),
+                        Some(entry) if serde_json::to_value(&entry)? != serde_json::to_value(&line.entry)? => {
+                            verification.problems.push(format!(
+                                "Audit entry {} (seq {}) was changed after it was sealed",
+                                line.entry.id, line.seq
+                            ))
+                        }
+                        Some(_) => {}
+                    }
+                }
+                Ok(())
+            })?;
+        }
+        Ok(verification)
+    }
+}
+
+/// Write `contents` to a new file at `path` and make it read-only.
+fn write_once(path: &Path, contents: &[u8]) -> Result<()> {
+    if path.exists() {
+        return Err(QmsError::AuditTrail {
+            message: format!("Sealed audit segment file {} already exists", path.display()),
+        });
+    }
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, contents).map_err(|e| fs_error(&tmp_path, e))?;
+    fs::rename(&tmp_path, path).map_err(|e| fs_error(path, e))?;
+    let mut permissions = fs::metadata(path).map_err(|e| fs_error(path, e))?.permissions();
+    permissions.set_readonly(true);
+    fs::set_permissions(path, permissions).map_err(|e| fs_error(path, e))
+}
+
+fn fs_error(path: &Path, e: std::io::Error) -> QmsError {
+    QmsError::FileSystem { path: path.display().to_string(), message: e.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditManager;
+    use crate::config::DatabaseConfig;
+
+    #[test]
+    fn test_sync_seals_segments_and_verify_detects_tampering() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let audit = AuditManager::new(db.clone());
+        for i in 0..5 {
+            audit.log_action("qa", "document_viewed", &format!("document:{}", i), "Success", None).unwrap();
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let store = AuditWormStore::new(dir.path(), 2);
+
+        let result = store.sync(&db, Utc::now()).unwrap();
+        assert_eq!((result.segments_written, result.entries_written), (3, 5));
+        assert_eq!(store.sync(&db, Utc::now()).unwrap().entries_written, 0);
+        audit.log_action("qa", "document_viewed", "document:5", "Success", None).unwrap();
+        let result = store.sync(&db, Utc::now()).unwrap();
+        assert_eq!((result.segments_written, result.entries_written), (1, 1));
+
+        let seals = store.seals().unwrap();
+        assert_eq!(seals.len(), 4);
+        assert_eq!(seals[3].0.previous_seal_sha256.as_ref(), Some(&seals[2].1));
+        assert!(fs::metadata(dir.path().join(&seals[0].0.segment)).unwrap().permissions().readonly());
+        let verification = store.verify(&db).unwrap();
+        assert!(verification.is_intact(), "{:?}", verification.problems);
+        assert_eq!(verification.entries, 6);
+
+        db.with_connection(|conn| {
+            conn.execute("UPDATE audit_trail SET outcome = 'Failure' WHERE resource = 'document:1'", [])?;
+            conn.execute("DELETE FROM audit_trail WHERE resource = 'document:4'", [])?;
+            Ok(())
+        })
+        .unwrap();
+        let problems = store.verify(&db).unwrap().problems;
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].contains("was changed after it was sealed"));
+        assert!(problems[1].contains("is missing from the database"));
+    }
+}
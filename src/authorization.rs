@@ -0,0 +1,165 @@
+//! # Authorization - User Roles & Permissions
+//!
+//! Every user holds one organisation-wide role in `users.role`. The role
+//! grants a fixed set of [`Permission`]s; callers check the permission an
+//! operation needs rather than comparing roles, so the matrix lives in one
+//! place. Administrators manage the system but cannot approve quality
+//! records (segregation of duties, 21 CFR 11.10(g)).
+
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+
+/// An operation that requires authorization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// Read quality records and metrics
+    ReadRecords,
+    /// Create and update quality records
+    WriteRecords,
+    /// Approve controlled documents
+    ApproveDocuments,
+    /// Export the audit trail
+    ExportAuditTrail,
+    /// Create users and assign roles
+    ManageUsers,
+    /// Trigger background jobs by hand
+    RunJobs,
+    /// Place and release legal holds
+    ManageLegalHolds,
+    /// Enable and disable modules for the deployment
+    ManageFeatures,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::ReadRecords => "read_records",
+            Permission::WriteRecords => "write_records",
+            Permission::ApproveDocuments => "approve_documents",
+            Permission::ExportAuditTrail => "export_audit_trail",
+            Permission::ManageUsers => "manage_users",
+            Permission::RunJobs => "run_jobs",
+            Permission::ManageLegalHolds => "manage_legal_holds",
+            Permission::ManageFeatures => "manage_features",
+        }
+    }
+}
+
+/// Organisation-wide user role, ordered from least to most privileged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum UserRole {
+    /// Read-only access
+    Viewer,
+    /// Create and update records
+    QualityEngineer,
+    /// Approve documents and export the audit trail
+    QualityManager,
+    /// Manage users; no approval rights
+    Administrator,
+}
+
+impl UserRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRole::Viewer => "Viewer",
+            UserRole::QualityEngineer => "QualityEngineer",
+            UserRole::QualityManager => "QualityManager",
+            UserRole::Administrator => "Administrator",
+        }
+    }
+
+    /// Permissions granted by the role.
+    pub fn permissions(&self) -> &'static [Permission] {
+        use Permission::*;
+        match self {
+            UserRole::Viewer => &[ReadRecords],
+            UserRole::QualityEngineer => &[ReadRecords, WriteRecords],
+            UserRole::QualityManager => &[ReadRecords, WriteRecords, ApproveDocuments, ExportAuditTrail, RunJobs],
+            UserRole::Administrator => {
+                &[ReadRecords, ExportAuditTrail, ManageUsers, RunJobs, ManageLegalHolds, ManageFeatures]
+            }
+        }
+    }
+
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+
+    /// Role of an active user, `None` for unknown or deactivated users.
+    /// Served from the database's read cache when possible.
+    pub fn of_user(db: &Database, user_id: &str) -> Result<Option<UserRole>> {
+        db.read_cache().role(user_id, || {
+            let role: Option<String> = db.with_connection(|conn| {
+                Ok(conn
+                    .query_row("SELECT role FROM users WHERE id = ?1 AND is_active = 1", params![user_id], |row| {
+                        row.get(0)
+                    })
+                    .optional()?)
+            })?;
+            role.map(|role| role.parse()).transpose()
+        })
+    }
+}
+
+impl std::str::FromStr for UserRole {
+    type Err = QmsError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "Viewer" => Ok(UserRole::Viewer),
+            "QualityEngineer" => Ok(UserRole::QualityEngineer),
+            "QualityManager" => Ok(UserRole::QualityManager),
+            "Administrator" => Ok(UserRole::Administrator),
+            other => Err(QmsError::Validation {
+                field: "role".to_string(),
+                message: format!("Unknown user role: {}", other),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+
+    #[test]
+    fn test_permission_matrix() {
+        assert!(UserRole::QualityManager.has_permission(Permission::ApproveDocuments));
+        assert!(!UserRole::QualityEngineer.has_permission(Permission::ApproveDocuments));
+        assert!(!UserRole::Administrator.has_permission(Permission::ApproveDocuments));
+        assert!(UserRole::Viewer.has_permission(Permission::ReadRecords));
+        assert!(!UserRole::Viewer.has_permission(Permission::ExportAuditTrail));
+        assert!(UserRole::Administrator.has_permission(Permission::RunJobs));
+        assert!(!UserRole::QualityEngineer.has_permission(Permission::RunJobs));
+        assert!(UserRole::Administrator.has_permission(Permission::ManageLegalHolds));
+        assert!(!UserRole::QualityManager.has_permission(Permission::ManageLegalHolds));
+        assert_eq!("QualityManager".parse::<UserRole>().unwrap(), UserRole::QualityManager);
+        assert!("Owner".parse::<UserRole>().is_err());
+    }
+
+    #[test]
+    fn test_role_of_active_users_only() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["eng", "former"]);
+        db.with_connection(|conn| {
+            conn.execute("UPDATE users SET is_active = 0 WHERE id = 'former'", [])?;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(UserRole::of_user(&db, "eng").unwrap(), Some(UserRole::QualityEngineer));
+        assert_eq!(UserRole::of_user(&db, "former").unwrap(), None);
+        assert_eq!(UserRole::of_user(&db, "nobody").unwrap(), None);
+    }
+}
@@ -0,0 +1,971 @@
+//! # Batch Records - DHR Review by Exception (21 CFR 820.184)
+//!
+//! An electronic batch record, the device history record of a lot, is opened
+//! with the entries its master record requires: a parameter per
+//! manufacturing step, optionally with limits or an expected result and a
+//! second-person check. Operators fill in the entries as the lot is built.
+//!
+//! Before release, [`BatchRecordRepo::review`] checks every entry and lists
+//! only the exceptions: missing or unverified entries, which must be
+//! completed, and values outside their limits, corrected entries and
+//! recorded deviations, which the reviewer dispositions with a comment. The
+//! reviewer reads those instead of the whole record. Every entry, correction
+//! and disposition stays in the record and the audit trail, and the release
+//! audit entry stores the review summary.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::authorization::{Permission, UserRole};
+use crate::change_history::ChangeReason;
+use crate::database::Database;
+use crate::error::{QmsError, Result, ValidationErrors};
+
+/// An entry the master record requires, with its acceptance criteria.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntrySpec {
+    pub step: String,
+    pub parameter: String,
+    #[serde(default)]
+    pub unit: Option<String>,
+    #[serde(default)]
+    pub lower_limit: Option<f64>,
+    #[serde(default)]
+    pub upper_limit: Option<f64>,
+    /// Expected text result, e.g. "pass"
+    #[serde(default)]
+    pub expected: Option<String>,
+    /// Whether release needs a value; optional entries are checked when made
+    pub required: bool,
+    /// Whether a second person must verify the entry
+    pub second_person: bool,
+}
+
+impl EntrySpec {
+    /// A required entry without acceptance criteria.
+    pub fn new(step: &str, parameter: &str) -> Self {
+        Self {
+            step: step.to_string(),
+            parameter: parameter.to_string(),
+            unit: None,
+            lower_limit: None,
+            upper_limit: None,
+            expected: None,
+            required: true,
+            second_person: false,
+        }
+    }
+
+    pub fn with_limits(mut self, lower: Option<f64>, upper: Option<f64>, unit: &str) -> Self {
+        self.lower_limit = lower;
+        self.upper_limit = upper;
+        self.unit = Some(unit.to_string());
+        self
+    }
+
+    pub fn with_expected(mut self, expected: &str) -> Self {
+        self.expected = Some(expected.to_string());
+        self
+    }
+
+    pub fn with_second_person(mut self) -> Self {
+        self.second_person = true;
+        self
+    }
+
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+}
+
+/// Disposition of the lot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    /// Being built or awaiting review
+    Open,
+    Released,
+    Rejected,
+}
+
+impl BatchStatus {
+    pub const ALL: [BatchStatus; 3] = [BatchStatus::Open, BatchStatus::Released, BatchStatus::Rejected];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BatchStatus::Open => "open",
+            BatchStatus::Released => "released",
+            BatchStatus::Rejected => "rejected",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.as_str() == value)
+    }
+}
+
+/// The batch record of one lot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchRecord {
+    pub id: Uuid,
+    pub lot_number: String,
+    pub product: String,
+    pub status: BatchStatus,
+    pub opened_by: String,
+    pub opened_at: DateTime<Utc>,
+    pub dispositioned_by: Option<String>,
+    pub dispositioned_at: Option<DateTime<Utc>>,
+    /// Release comment or rejection reason
+    pub disposition_note: Option<String>,
+}
+
+/// A required entry and what was recorded for it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchEntry {
+    pub id: Uuid,
+    #[serde(flatten)]
+    pub spec: EntrySpec,
+    pub value: Option<String>,
+    pub recorded_by: Option<String>,
+    pub recorded_at: Option<DateTime<Utc>>,
+    /// Times the value was changed after it was first recorded
+    pub corrections: u32,
+    pub verified_by: Option<String>,
+    pub verified_at: Option<DateTime<Utc>>,
+    /// Deviation from the master record noted by the operator
+    pub deviation: Option<String>,
+}
+
+impl BatchEntry {
+    /// Exceptions the entry raises; none for an entry that verifies itself.
+    pub fn exceptions(&self) -> Vec<(ExceptionKind, String)> {
+        let mut exceptions = Vec::new();
+        let Some(value) = self.value.as_deref() else {
+            if self.spec.required {
+                exceptions.push((ExceptionKind::Missing, "No value recorded".to_string()));
+            }
+            return exceptions;
+        };
+        if let Some(detail) = self.out_of_limits(value) {
+            exceptions.push((ExceptionKind::OutOfLimits, detail));
+        }
+        if self.spec.second_person && self.verified_by.is_none() {
+            exceptions.push((ExceptionKind::Unverified, "Second-person verification outstanding".to_string()));
+        }
+        if self.corrections > 0 {
+            exceptions.push((ExceptionKind::Corrected, format!("Corrected {} time(s)", self.corrections)));
+        }
+        if let Some(deviation) = &self.deviation {
+            exceptions.push((ExceptionKind::Deviation, deviation.clone()));
+        }
+        exceptions
+    }
+
+    fn out_of_limits(&self, value: &str) -> Option<String> {
+        let spec = &self.spec;
+        if spec.lower_limit.is_some() || spec.upper_limit.is_some() {
+            let Ok(number) = value.parse::<f64>() else {
+                return Some(format!("'{}' is not a number", value));
+            };
+            let within = spec.lower_limit.map_or(true, |lower| number >= lower)
+                && spec.upper_limit.map_or(true, |upper| number <= upper);
+            let bound = |limit: Option<f64>| limit.map_or("-".to_string(), |limit| limit.to_string());
+            let measured = match &spec.unit {
+                Some(unit) => format!("{} {}", value, unit),
+                None => value.to_string(),
+            };
+            return (!within)
+                .then(|| format!("{} outside {}..{}", measured, bound(spec.lower_limit), bound(spec.upper_limit)));
+        }
+        match spec.expected.as_deref() {
+            Some(expected) if !value.eq_ignore_ascii_case(expected) => {
+                Some(format!("'{}' where '{}' is expected", value, expected))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Why an entry needs the reviewer's attention.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExceptionKind {
+    /// A required entry has no value
+    Missing,
+    /// The second-person check has not been made
+    Unverified,
+    /// The value is outside its limits or not the expected result
+    OutOfLimits,
+    /// The value was changed after it was recorded
+    Corrected,
+    /// The operator noted a deviation from the master record
+    Deviation,
+}
+
+impl ExceptionKind {
+    pub const ALL: [ExceptionKind; 5] = [
+        ExceptionKind::Missing,
+        ExceptionKind::Unverified,
+        ExceptionKind::OutOfLimits,
+        ExceptionKind::Corrected,
+        ExceptionKind::Deviation,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExceptionKind::Missing => "missing",
+            ExceptionKind::Unverified => "unverified",
+            ExceptionKind::OutOfLimits => "out_of_limits",
+            ExceptionKind::Corrected => "corrected",
+            ExceptionKind::Deviation => "deviation",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == value)
+    }
+
+    /// Whether the record is incomplete until the entry is made; such
+    /// exceptions cannot be dispositioned.
+    pub fn blocks_release(&self) -> bool {
+        matches!(self, ExceptionKind::Missing | ExceptionKind::Unverified)
+    }
+}
+
+/// Reviewer's disposition of an exception.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Disposition {
+    pub comment: String,
+    pub reviewed_by: String,
+    pub reviewed_at: DateTime<Utc>,
+}
+
+/// An exception listed for review.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Exception {
+    pub entry_id: Uuid,
+    pub step: String,
+    pub parameter: String,
+    pub value: Option<String>,
+    pub kind: ExceptionKind,
+    pub detail: String,
+    pub disposition: Option<Disposition>,
+}
+
+/// Outcome of the automatic check of a batch record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewReport {
+    pub batch: BatchRecord,
+    pub entries: usize,
+    /// Entries without exceptions, which the reviewer need not read
+    pub verified_automatically: usize,
+    pub exceptions: Vec<Exception>,
+}
+
+impl ReviewReport {
+    /// Whether every required entry is made and verified.
+    pub fn is_complete(&self) -> bool {
+        !self.exceptions.iter().any(|exception| exception.kind.blocks_release())
+    }
+
+    /// Exceptions still awaiting the reviewer.
+    pub fn open_exceptions(&self) -> impl Iterator<Item = &Exception> {
+        self.exceptions.iter().filter(|exception| exception.disposition.is_none())
+    }
+
+    pub fn ready_for_release(&self) -> bool {
+        self.batch.status == BatchStatus::Open && self.open_exceptions().next().is_none()
+    }
+}
+
+/// Batch records stored in the `batch_records` tables.
+pub struct BatchRecordRepo<'a> {
+    db: &'a Database,
+}
+
+impl<'a> BatchRecordRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Open the batch record of `lot_number` with the entries of its master
+    /// record, in manufacturing order.
+    pub fn open(&self, lot_number: &str, product: &str, entries: &[EntrySpec], opened_by: &str) -> Result<BatchRecord> {
+        self.require(opened_by, Permission::WriteRecords, "open batch records")?;
+        let mut errors = ValidationErrors::new();
+        if lot_number.trim().is_empty() {
+            errors.add("lot_number", "Lot number is required");
+        }
+        if product.trim().is_empty() {
+            errors.add("product", "Product is required");
+        }
+        if entries.is_empty() {
+            errors.add("entries", "The master record requires no entries");
+        }
+        for (index, spec) in entries.iter().enumerate() {
+            let field = format!("entries[{}]", index);
+            if spec.step.trim().is_empty() || spec.parameter.trim().is_empty() {
+                errors.add(&field, "Step and parameter are required");
+            } else if entries[..index].iter().any(|e| e.step == spec.step && e.parameter == spec.parameter) {
+                errors.add(&field, format!("{} / {} is listed twice", spec.step, spec.parameter));
+            }
+            if let (Some(lower), Some(upper)) = (spec.lower_limit, spec.upper_limit) {
+                if lower > upper {
+                    errors.add(&field, "Lower limit exceeds upper limit");
+                }
+            }
+        }
+        errors.into_result()?;
+        if self.find_by_lot(lot_number.trim())?.is_some() {
+            return Err(QmsError::Validation {
+                field: "lot_number".to_string(),
+                message: format!("Lot {} already has a batch record", lot_number.trim()),
+            });
+        }
+
+        let batch = BatchRecord {
+            id: Uuid::new_v4(),
+            lot_number: lot_number.trim().to_string(),
+            product: product.trim().to_string(),
+            status: BatchStatus::Open,
+            opened_by: opened_by.to_string(),
+            opened_at: Utc::now(),
+            dispositioned_by: None,
+            dispositioned_at: None,
+            disposition_note: None,
+        };
+        self.db.unit_of_work(|uow| {
+            let conn = uow.connection();
+            conn.execute(
+                "INSERT INTO batch_records (id, lot_number, product, status, opened_by, opened_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    batch.id.to_string(),
+                    batch.lot_number,
+                    batch.product,
+                    batch.status.as_str(),
+                    opened_by,
+                    batch.opened_at.to_rfc3339()
+                ],
+            )?;
+            for (position, spec) in entries.iter().enumerate() {
+                conn.execute(
+                    "INSERT INTO batch_record_entries (id, batch_id, position, step, parameter, unit, lower_limit,
+                                                       upper_limit, expected, required, second_person)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                    params![
+                        Uuid::new_v4().to_string(),
+                        batch.id.to_string(),
+                        position as i64,
+                        spec.step.trim(),
+                        spec.parameter.trim(),
+                        spec.unit,
+                        spec.lower_limit,
+                        spec.upper_limit,
+                        spec.expected,
+                        spec.required,
+                        spec.second_person,
+                    ],
+                )?;
+            }
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                opened_by,
+                "batch_record_opened",
+                &resource(batch.id),
+                "Success",
+                Some(format!("Lot {} of {} with {} entries", batch.lot_number, batch.product, entries.len())),
+            )
+        })?;
+        Ok(batch)
+    }
+
+    /// Record `value` for the entry of `step` and `parameter`. An entry
+    /// already made can only be changed with [`Self::correct`].
+    pub fn record(&self, batch_id: Uuid, step: &str, parameter: &str, value: &str, user: &str) -> Result<BatchEntry> {
+        self.require(user, Permission::WriteRecords, "make batch record entries")?;
+        let entry = self.open_entry(batch_id, step, parameter)?;
+        if entry.value.is_some() {
+            return Err(QmsError::Validation {
+                field: "value".to_string(),
+                message: format!("{} / {} is already recorded; correct it with a reason", step, parameter),
+            });
+        }
+        let value = required_value(value)?;
+        let details = format!("{} / {} = {}", step, parameter, value);
+        self.update_entry(batch_id, &entry, user, "batch_entry_recorded", details, |conn, now| {
+            conn.execute(
+                "UPDATE batch_record_entries SET value = ?2, recorded_by = ?3, recorded_at = ?4 WHERE id = ?1",
+                params![entry.id.to_string(), value, user, now],
+            )
+        })
+    }
+
+    /// Change a recorded value. The correction is an exception for the
+    /// reviewer, voids the second-person check and withdraws the entry's
+    /// dispositions, so a correction made after review is reviewed again.
+    pub fn correct(
+        &self,
+        batch_id: Uuid,
+        step: &str,
+        parameter: &str,
+        value: &str,
+        reason: &ChangeReason,
+        user: &str,
+    ) -> Result<BatchEntry> {
+        self.require(user, Permission::WriteRecords, "correct batch record entries")?;
+        let entry = self.open_entry(batch_id, step, parameter)?;
+        let Some(previous) = entry.value.as_deref() else {
+            return Err(QmsError::Validation {
+                field: "value".to_string(),
+                message: format!("{} / {} has not been recorded yet", step, parameter),
+            });
+        };
+        let value = required_value(value)?;
+        let details = format!("{} / {}: {} -> {} ({})", step, parameter, previous, value, reason.as_str());
+        self.update_entry(batch_id, &entry, user, "batch_entry_corrected", details, |conn, now| {
+            conn.execute(
+                "UPDATE batch_record_entries
+                 SET value = ?2, recorded_by = ?3, recorded_at = ?4, corrections = corrections + 1,
+                     verified_by = NULL, verified_at = NULL
+                 WHERE id = ?1",
+                params![entry.id.to_string(), value, user, now],
+            )
+        })
+    }
+
+    /// Second-person verification of a recorded entry, by someone other
+    /// than the person who recorded it.
+    pub fn verify(&self, batch_id: Uuid, step: &str, parameter: &str, verifier: &str) -> Result<BatchEntry> {
+        self.require(verifier, Permission::WriteRecords, "verify batch record entries")?;
+        let entry = self.open_entry(batch_id, step, parameter)?;
+        match entry.recorded_by.as_deref() {
+            None => {
+                return Err(QmsError::Validation {
+                    field: "value".to_string(),
+                    message: format!("{} / {} has not been recorded yet", step, parameter),
+                })
+            }
+            Some(recorder) if recorder == verifier => {
+                return Err(QmsError::Security { message: format!("User {} cannot verify their own entry", verifier) })
+            }
+            Some(_) => {}
+        }
+        let details = format!("{} / {} = {}", step, parameter, entry.value.as_deref().unwrap_or_default());
+        self.update_entry(batch_id, &entry, verifier, "batch_entry_verified", details, |conn, now| {
+            conn.execute(
+                "UPDATE batch_record_entries SET verified_by = ?2, verified_at = ?3 WHERE id = ?1",
+                params![entry.id.to_string(), verifier, now],
+            )
+        })
+    }
+
+    /// Note a deviation from the master record on an entry.
+    pub fn record_deviation(
+        &self,
+        batch_id: Uuid,
+        step: &str,
+        parameter: &str,
+        description: &str,
+        user: &str,
+    ) -> Result<BatchEntry> {
+        self.require(user, Permission::WriteRecords, "record deviations")?;
+        let entry = self.open_entry(batch_id, step, parameter)?;
+        let description = description.trim();
+        if description.is_empty() {
+            return Err(QmsError::Validation {
+                field: "description".to_string(),
+                message: "Describe the deviation".to_string(),
+            });
+        }
+        let details = format!("{} / {}: {}", step, parameter, description);
+        self.update_entry(batch_id, &entry, user, "batch_deviation_recorded", details, |conn, _| {
+            conn.execute(
+                "UPDATE batch_record_entries SET deviation = ?2 WHERE id = ?1",
+                params![entry.id.to_string(), description],
+            )
+        })
+    }
+
+    /// Check every entry of the batch and list the exceptions, in
+    /// manufacturing order.
+    pub fn review(&self, batch_id: Uuid) -> Result<ReviewReport> {
+        let batch = self.get(batch_id)?;
+        let entries = self.entries(batch_id)?;
+        let dispositions = self.dispositions(batch_id)?;
+        let mut verified_automatically = 0;
+        let mut exceptions = Vec::new();
+        for entry in &entries {
+            let found = entry.exceptions();
+            if found.is_empty() {
+                verified_automatically += 1;
+            }
+            for (kind, detail) in found {
+                let disposition = dispositions
+                    .iter()
+                    .find(|(entry_id, disposed, _)| *entry_id == entry.id && *disposed == kind)
+                    .map(|(_, _, disposition)| disposition.clone());
+                exceptions.push(Exception {
+                    entry_id: entry.id,
+                    step: entry.spec.step.clone(),
+                    parameter: entry.spec.parameter.clone(),
+                    value: entry.value.clone(),
+                    kind,
+                    detail,
+                    disposition,
+                });
+            }
+        }
+        Ok(ReviewReport { batch, entries: entries.len(), verified_automatically, exceptions })
+    }
+
+    /// Disposition an exception of an entry, as a reviewer. Missing and
+    /// unverified entries are completed instead.
+    pub fn disposition(
+        &self,
+        batch_id: Uuid,
+        entry_id: Uuid,
+        kind: ExceptionKind,
+        comment: &str,
+        reviewer: &str,
+    ) -> Result<ReviewReport> {
+        self.require(reviewer, Permission::ApproveDocuments, "review batch records")?;
+        let report = self.review(batch_id)?;
+        ensure_open(&report.batch)?;
+        let exception = report
+            .exceptions
+            .iter()
+            .find(|exception| exception.entry_id == entry_id && exception.kind == kind)
+            .ok_or_else(|| QmsError::NotFound {
+                resource: "batch_exception".to_string(),
+                id: format!("{}/{}", entry_id, kind.as_str()),
+            })?;
+        if kind.blocks_release() {
+            return Err(QmsError::Validation {
+                field: "kind".to_string(),
+                message: format!("{} / {} must be completed, not dispositioned", exception.step, exception.parameter),
+            });
+        }
+        let comment = comment.trim();
+        if comment.is_empty() {
+            return Err(QmsError::Validation {
+                field: "comment".to_string(),
+                message: "A disposition comment is required".to_string(),
+            });
+        }
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "INSERT OR REPLACE INTO batch_record_dispositions (entry_id, kind, comment, reviewed_by, reviewed_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![entry_id.to_string(), kind.as_str(), comment, reviewer, Utc::now().to_rfc3339()],
+            )?;
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                reviewer,
+                "batch_exception_dispositioned",
+                &resource(batch_id),
+                "Success",
+                Some(format!("{} / {} {}: {}", exception.step, exception.parameter, kind.as_str(), comment)),
+            )
+        })?;
+        self.review(batch_id)
+    }
+
+    /// Release the lot once the record is complete and every exception is
+    /// dispositioned. The reviewer must not have made entries on the lot.
+    pub fn release(&self, batch_id: Uuid, reviewer: &str, comment: Option<&str>) -> Result<ReviewReport> {
+        self.require(reviewer, Permission::ApproveDocuments, "release lots")?;
+        let report = self.review(batch_id)?;
+        ensure_open(&report.batch)?;
+        let entries = self.entries(batch_id)?;
+        let involved = |entry: &BatchEntry| {
+            entry.recorded_by.as_deref() == Some(reviewer) || entry.verified_by.as_deref() == Some(reviewer)
+        };
+        if entries.iter().any(involved) {
+            return Err(QmsError::Security {
+                message: format!("User {} made entries on lot {}", reviewer, report.batch.lot_number),
+            });
+        }
+        if !report.ready_for_release() {
+            let open: Vec<String> = report
+                .open_exceptions()
+                .map(|exception| format!("{} / {} {}", exception.step, exception.parameter, exception.kind.as_str()))
+                .collect();
+            return Err(QmsError::Validation {
+                field: "exceptions".to_string(),
+                message: format!("Lot {} has open exceptions: {}", report.batch.lot_number, open.join(", ")),
+            });
+        }
+        let summary = serde_json::json!({
+            "lot": report.batch.lot_number,
+            "entries": report.entries,
+            "verified_automatically": report.verified_automatically,
+            "exceptions": report.exceptions,
+        });
+        self.disposition_batch(&report.batch, BatchStatus::Released, comment, reviewer, summary.to_string())?;
+        self.review(batch_id)
+    }
+
+    /// Reject the lot, whatever the state of its record.
+    pub fn reject(&self, batch_id: Uuid, reviewer: &str, reason: &ChangeReason) -> Result<BatchRecord> {
+        self.require(reviewer, Permission::ApproveDocuments, "reject lots")?;
+        let batch = self.get(batch_id)?;
+        ensure_open(&batch)?;
+        let details = format!("Lot {} rejected: {}", batch.lot_number, reason.as_str());
+        self.disposition_batch(&batch, BatchStatus::Rejected, Some(reason.as_str()), reviewer, details)?;
+        self.get(batch_id)
+    }
+
+    pub fn get(&self, id: Uuid) -> Result<BatchRecord> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM batch_records WHERE id = ?1", BATCH_COLUMNS),
+                        params![id.to_string()],
+                        row_to_batch,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "batch_record".to_string(), id: id.to_string() })
+    }
+
+    pub fn by_lot(&self, lot_number: &str) -> Result<BatchRecord> {
+        self.find_by_lot(lot_number)?
+            .ok_or_else(|| QmsError::NotFound { resource: "batch_record".to_string(), id: lot_number.to_string() })
+    }
+
+    /// The full record: every entry in manufacturing order.
+    pub fn entries(&self, batch_id: Uuid) -> Result<Vec<BatchEntry>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM batch_record_entries WHERE batch_id = ?1 ORDER BY position",
+                ENTRY_COLUMNS
+            ))?;
+            let entries =
+                stmt.query_map(params![batch_id.to_string()], row_to_entry)?.collect::<rusqlite::Result<_>>()?;
+            Ok(entries)
+        })
+    }
+
+    fn find_by_lot(&self, lot_number: &str) -> Result<Option<BatchRecord>> {
+        self.db.with_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    &format!("SELECT {} FROM batch_records WHERE lot_number = ?1", BATCH_COLUMNS),
+                    params![lot_number],
+                    row_to_batch,
+                )
+                .optional()?)
+        })
+    }
+
+    fn open_entry(&self, batch_id: Uuid, step: &str, parameter: &str) -> Result<BatchEntry> {
+        ensure_open(&self.get(batch_id)?)?;
+        self.entries(batch_id)?
+            .into_iter()
+            .find(|entry| entry.spec.step == step && entry.spec.parameter == parameter)
+            .ok_or_else(|| QmsError::NotFound {
+                resource: "batch_entry".to_string(),
+                id: format!("{} / {}", step, parameter),
+            })
+    }
+
+    /// Apply `update` to `entry`, clear its dispositions so the changed
+    /// entry is reviewed again, and audit `action`.
+    fn update_entry<F>(
+        &self,
+        batch_id: Uuid,
+        entry: &BatchEntry,
+        user: &str,
+        action: &str,
+        details: String,
+        update: F,
+    ) -> Result<BatchEntry>
+    where
+        F: FnOnce(&rusqlite::Connection, String) -> rusqlite::Result<usize>,
+    {
+        self.db.unit_of_work(|uow| {
+            let conn = uow.connection();
+            update(conn, Utc::now().to_rfc3339())?;
+            conn.execute("DELETE FROM batch_record_dispositions WHERE entry_id = ?1", params![entry.id.to_string()])?;
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                user,
+                action,
+                &resource(batch_id),
+                "Success",
+                Some(details),
+            )
+        })?;
+        self.entries(batch_id)?
+            .into_iter()
+            .find(|updated| updated.id == entry.id)
+            .ok_or_else(|| QmsError::NotFound { resource: "batch_entry".to_string(), id: entry.id.to_string() })
+    }
+
+    fn dispositions(&self, batch_id: Uuid) -> Result<Vec<(Uuid, ExceptionKind, Disposition)>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT d.entry_id, d.kind, d.comment, d.reviewed_by, d.reviewed_at
+                 FROM batch_record_dispositions d JOIN batch_record_entries e ON e.id = d.entry_id
+                 WHERE e.batch_id = ?1",
+            )?;
+            let dispositions = stmt
+                .query_map(params![batch_id.to_string()], |row| {
+                    let entry_id: String = row.get(0)?;
+                    let kind: String = row.get(1)?;
+                    Ok((
+                        Uuid::parse_str(&entry_id).map_err(|e| conversion_error(0, e.to_string()))?,
+                        ExceptionKind::parse(&kind)
+                            .ok_or_else(|| conversion_error(1, format!("unknown exception kind {}", kind)))?,
+                        Disposition {
+                            comment: row.get(2)?,
+                            reviewed_by: row.get(3)?,
+                            reviewed_at: parse_timestamp(row, 4)?.unwrap_or_default(),
+                        },
+                    ))
+                })?
+                .collect::<rusqlite::Result<_>>()?;
+            Ok(dispositions)
+        })
+    }
+
+    fn disposition_batch(
+        &self,
+        batch: &BatchRecord,
+        status: BatchStatus,
+        note: Option<&str>,
+        reviewer: &str,
+        details: String,
+    ) -> Result<()> {
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE batch_records
+                 SET status = ?2, dispositioned_by = ?3, dispositioned_at = ?4, disposition_note = ?5
+                 WHERE id = ?1",
+                params![batch.id.to_string(), status.as_str(), reviewer, Utc::now().to_rfc3339(), note],
+            )?;
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                reviewer,
+                &format!("batch_record_{}", status.as_str()),
+                &resource(batch.id),
+                "Success",
+                Some(details),
+            )
+        })
+    }
+
+    fn require(&self, user: &str, permission: Permission, what: &str) -> Result<()> {
+        if UserRole::of_user(self.db, user)?.is_some_and(|role| role.has_permission(permission)) {
+            return Ok(());
+        }
+        Err(QmsError::Security { message: format!("User {} may not {}", user, what) })
+    }
+}
+
+fn ensure_open(batch: &BatchRecord) -> Result<()> {
+    match batch.status {
+        BatchStatus::Open => Ok(()),
+        status => Err(QmsError::Validation {
+            field: "status".to_string(),
+            message: format!("Lot {} is {}", batch.lot_number, status.as_str()),
+        }),
+    }
+}
+
+fn required_value(value: &str) -> Result<&str> {
+    match value.trim() {
+        "" => Err(QmsError::Validation { field: "value".to_string(), message: "A value is required".to_string() }),
+        value => Ok(value),
+    }
+}
+
+fn resource(id: Uuid) -> String {
+    format!("batch_record:{}", id)
+}
+
+const BATCH_COLUMNS: &str =
+    "id, lot_number, product, status, opened_by, opened_at, dispositioned_by, dispositioned_at, disposition_note";
+
+const ENTRY_COLUMNS: &str = "id, step, parameter, unit, lower_limit, upper_limit, expected, required, second_person, \
+                             value, recorded_by, recorded_at, corrections, verified_by, verified_at, deviation";
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn parse_timestamp(row: &Row, index: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|timestamp| timestamp.with_timezone(&Utc))
+                .map_err(|e| conversion_error(index, e.to_string()))
+        })
+        .transpose()
+}
+
+fn parse_id(row: &Row, index: usize) -> rusqlite::Result<Uuid> {
+    let id: String = row.get(index)?;
+    Uuid::parse_str(&id).map_err(|e| conversion_error(index, e.to_string()))
+}
+
+fn row_to_batch(row: &Row) -> rusqlite::Result<BatchRecord> {
+    let status: String = row.get(3)?;
+    Ok(BatchRecord {
+        id: parse_id(row, 0)?,
+        lot_number: row.get(1)?,
+        product: row.get(2)?,
+        status: BatchStatus::parse(&status)
+            .ok_or_else(|| conversion_error(3, format!("unknown batch status {}", status)))?,
+        opened_by: row.get(4)?,
+        opened_at: parse_timestamp(row, 5)?.unwrap_or_default(),
+        dispositioned_by: row.get(6)?,
+        dispositioned_at: parse_timestamp(row, 7)?,
+        disposition_note: row.get(8)?,
+    })
+}
+
+fn row_to_entry(row: &Row) -> rusqlite::Result<BatchEntry> {
+    Ok(BatchEntry {
+        id: parse_id(row, 0)?,
+        spec: EntrySpec {
+            step: row.get(1)?,
+            parameter: row.get(2)?,
+            unit: row.get(3)?,
+            lower_limit: row.get(4)?,
+            upper_limit: row.get(5)?,
+            expected: row.get(6)?,
+            required: row.get(7)?,
+            second_person: row.get(8)?,
+        },
+        value: row.get(9)?,
+        recorded_by: row.get(10)?,
+        recorded_at: parse_timestamp(row, 11)?,
+        corrections: row.get(12)?,
+        verified_by: row.get(13)?,
+        verified_at: parse_timestamp(row, 14)?,
+        deviation: row.get(15)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+
+    #[test]
+    fn test_review_lists_only_exceptions_and_gates_release() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["op1", "op2", "qa"]);
+        db.with_connection(|conn| {
+            conn.execute("UPDATE users SET role = 'QualityManager' WHERE id = 'qa'", [])?;
+            Ok(())
+        })
+        .unwrap();
+        let repo = BatchRecordRepo::new(&db);
+        let master = [
+            EntrySpec::new("Sealing", "Temperature").with_limits(Some(180.0), Some(190.0), "°C"),
+            EntrySpec::new("Sealing", "Dwell time").with_limits(Some(1.0), Some(2.0), "s"),
+            EntrySpec::new("Inspection", "Visual").with_expected("pass").with_second_person(),
+            EntrySpec::new("Labeling", "Label lot").with_second_person(),
+            EntrySpec::new("Packing", "Remarks").optional(),
+        ];
+        let lot = repo.open("LOT-42", "Sterile pouch", &master, "op1").unwrap();
+        assert!(repo.open("LOT-42", "Sterile pouch", &master, "op1").is_err());
+
+        repo.record(lot.id, "Sealing", "Temperature", "185", "op1").unwrap();
+        repo.record(lot.id, "Sealing", "Dwell time", "2.4", "op1").unwrap();
+        repo.record(lot.id, "Inspection", "Visual", "PASS", "op1").unwrap();
+        assert!(repo.record(lot.id, "Inspection", "Visual", "fail", "op1").is_err(), "use a correction");
+        assert!(repo.verify(lot.id, "Inspection", "Visual", "op1").is_err(), "no self-verification");
+        repo.verify(lot.id, "Inspection", "Visual", "op2").unwrap();
+
+        let report = repo.review(lot.id).unwrap();
+        assert!(!report.is_complete());
+        let kinds: Vec<ExceptionKind> = report.exceptions.iter().map(|exception| exception.kind).collect();
+        assert_eq!(kinds, vec![ExceptionKind::OutOfLimits, ExceptionKind::Missing]);
+        assert_eq!((report.entries, report.verified_automatically), (5, 3));
+        assert!(repo.release(lot.id, "qa", None).is_err());
+
+        repo.record(lot.id, "Labeling", "Label lot", "LOT-42", "op1").unwrap();
+        let reason = ChangeReason::new("Transcription error").unwrap();
+        repo.correct(lot.id, "Labeling", "Label lot", "LOT-42A", &reason, "op1").unwrap();
+        repo.verify(lot.id, "Labeling", "Label lot", "op2").unwrap();
+        repo.record_deviation(lot.id, "Sealing", "Dwell time", "Sealer PS-2 timer drift, NCR-7", "op1").unwrap();
+        let report = repo.review(lot.id).unwrap();
+        assert!(report.is_complete());
+        assert_eq!(report.open_exceptions().count(), 3);
+
+        let dwell = report.exceptions.iter().find(|exception| exception.parameter == "Dwell time").unwrap().entry_id;
+        let label = report.exceptions.iter().find(|exception| exception.kind == ExceptionKind::Corrected).unwrap();
+        assert!(repo.disposition(lot.id, dwell, ExceptionKind::OutOfLimits, "Seal strength retested", "op2").is_err());
+        repo.disposition(lot.id, dwell, ExceptionKind::OutOfLimits, "Seal strength retested, NCR-7", "qa").unwrap();
+        repo.disposition(lot.id, dwell, ExceptionKind::Deviation, "Covered by NCR-7", "qa").unwrap();
+        repo.disposition(lot.id, label.entry_id, ExceptionKind::Corrected, "Typo, verified", "qa").unwrap();
+        let report = repo.release(lot.id, "qa", Some("Released under NCR-7")).unwrap();
+        assert_eq!(report.batch.status, BatchStatus::Released);
+        assert!(repo.record(lot.id, "Packing", "Remarks", "late", "op1").is_err());
+
+        let audit = db.get_audit_entries_for_resource(&resource(lot.id)).unwrap();
+        assert_eq!(audit.iter().filter(|entry| entry.action == "batch_entry_corrected").count(), 1);
+        let release = audit.iter().find(|entry| entry.action == "batch_record_released").unwrap();
+        assert!(release.metadata.as_deref().is_some_and(|summary| summary.contains("\"verified_automatically\":3")));
+    }
+
+    #[test]
+    fn test_changing_a_dispositioned_entry_reopens_its_exceptions() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["op1", "op2", "qa"]);
+        db.with_connection(|conn| {
+            conn.execute("UPDATE users SET role = 'QualityManager' WHERE id = 'qa'", [])?;
+            Ok(())
+        })
+        .unwrap();
+        let repo = BatchRecordRepo::new(&db);
+        let master = [
+            EntrySpec::new("Sealing", "Temperature").with_limits(Some(180.0), Some(190.0), "°C"),
+            EntrySpec::new("Labeling", "Label lot").with_second_person(),
+        ];
+        let lot = repo.open("LOT-43", "Sterile pouch", &master, "op1").unwrap();
+        repo.record(lot.id, "Sealing", "Temperature", "185", "op1").unwrap();
+        repo.record(lot.id, "Labeling", "Label lot", "LOT-43", "op1").unwrap();
+        let reason = ChangeReason::new("Transcription error").unwrap();
+        let label = repo.correct(lot.id, "Labeling", "Label lot", "LOT-43A", &reason, "op1").unwrap();
+        repo.verify(lot.id, "Labeling", "Label lot", "op2").unwrap();
+        let report = repo.disposition(lot.id, label.id, ExceptionKind::Corrected, "Typo, verified", "qa").unwrap();
+        assert_eq!(report.open_exceptions().count(), 0);
+
+        // A later correction is not covered by the reviewer's earlier disposition
+        repo.correct(lot.id, "Labeling", "Label lot", "LOT-99", &reason, "op1").unwrap();
+        repo.verify(lot.id, "Labeling", "Label lot", "op2").unwrap();
+        let report = repo.review(lot.id).unwrap();
+        let reopened: Vec<_> = report.open_exceptions().map(|exception| (exception.entry_id, exception.kind)).collect();
+        assert_eq!(reopened, vec![(label.id, ExceptionKind::Corrected)]);
+        assert!(repo.release(lot.id, "qa", None).is_err());
+
+        repo.disposition(lot.id, label.id, ExceptionKind::Corrected, "Relabelled, verified", "qa").unwrap();
+        repo.record_deviation(lot.id, "Labeling", "Label lot", "Label printer jam, NCR-9", "op1").unwrap();
+        let report = repo.review(lot.id).unwrap();
+        assert_eq!(report.open_exceptions().count(), 2, "the deviation reopens the correction too");
+    }
+}
@@ -16,12 +16,25 @@
 //! - Interface Segregation: Focused interfaces for different concerns
 //! - Dependency Inversion: Abstract interfaces over concrete implementations
 
-use crate::error::{QmsError, Result};
+use crate::error::{QmsError, Result, ValidationErrors};
 use crate::audit::AuditManager;
+use crate::change_history::{diff_fields, record_changes, ChangeReason};
+use crate::clock::{system_clock, SharedClock};
+use crate::concurrency::{check_version, initial_row_version, INITIAL_ROW_VERSION};
+use crate::database::Database;
+use crate::record_history::{snapshot_with, RecordHistoryRepo};
+use crate::events::{EventBus, QmsEvent};
+use crate::numbering::{NumberingRepo, CAPA_SCOPE};
+use crate::site::DEFAULT_SITE_ID;
+use crate::unit_of_work::ElectronicSignature;
+use crate::validation_scripts::{RuleHook, ScriptedRules};
+use crate::workflow::{Transition, WorkflowDefinition, ANY_STATE};
 use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use std::collections::HashMap;
+use std::sync::OnceLock;
 
 /// CAPA Status following FDA workflow requirements
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -61,20 +74,43 @@ impl CapaStatus {
 
     /// Check if status allows state transitions
     pub fn can_transition_to(&self, new_status: &CapaStatus) -> bool {
-        match (self, new_status) {
-            (CapaStatus::Identified, CapaStatus::InvestigationInProgress) => true,
-            (CapaStatus::InvestigationInProgress, CapaStatus::RootCauseAnalysis) => true,
-            (CapaStatus::RootCauseAnalysis, CapaStatus::CorrectiveActionInProgress) => true,
-            (CapaStatus::RootCauseAnalysis, CapaStatus::PreventiveActionInProgress) => true,
-            (CapaStatus::CorrectiveActionInProgress, CapaStatus::EffectivenessVerification) => true,
-            (CapaStatus::PreventiveActionInProgress, CapaStatus::EffectivenessVerification) => true,
-            (CapaStatus::EffectivenessVerification, CapaStatus::Closed) => true,
-            (_, CapaStatus::Cancelled) => true, // Can cancel from any state
-            _ => false,
-        }
+        capa_workflow().can_transition(self.as_str(), new_status.as_str())
     }
 }
 
+/// Meaning of the electronic signature that closes a CAPA.
+pub const CAPA_CLOSURE_MEANING: &str = "closure";
+
+/// The CAPA status workflow, keyed by [`CapaStatus::as_str`].
+pub fn capa_workflow() -> &'static WorkflowDefinition<CapaRecord> {
+    static WORKFLOW: OnceLock<WorkflowDefinition<CapaRecord>> = OnceLock::new();
+    WORKFLOW.get_or_init(|| {
+        let states = [
+            CapaStatus::Identified,
+            CapaStatus::InvestigationInProgress,
+            CapaStatus::RootCauseAnalysis,
+            CapaStatus::CorrectiveActionInProgress,
+            CapaStatus::PreventiveActionInProgress,
+            CapaStatus::EffectivenessVerification,
+            CapaStatus::Closed,
+            CapaStatus::Cancelled,
+        ];
+        let step = |from: CapaStatus, to: CapaStatus| Transition::new(from.as_str(), to.as_str());
+        WorkflowDefinition::new("capa", &states.map(|status| status.as_str()))
+            .with_transition(step(CapaStatus::Identified, CapaStatus::InvestigationInProgress))
+            .with_transition(step(CapaStatus::InvestigationInProgress, CapaStatus::RootCauseAnalysis))
+            .with_transition(step(CapaStatus::RootCauseAnalysis, CapaStatus::CorrectiveActionInProgress))
+            .with_transition(step(CapaStatus::RootCauseAnalysis, CapaStatus::PreventiveActionInProgress))
+            .with_transition(step(CapaStatus::CorrectiveActionInProgress, CapaStatus::EffectivenessVerification))
+            .with_transition(step(CapaStatus::PreventiveActionInProgress, CapaStatus::EffectivenessVerification))
+            .with_transition(
+                step(CapaStatus::EffectivenessVerification, CapaStatus::Closed).with_signature(CAPA_CLOSURE_MEANING),
+            )
+            // Can cancel from any state
+            .with_transition(Transition::new(ANY_STATE, CapaStatus::Cancelled.as_str()))
+    })
+}
+
 /// CAPA Priority levels for resource allocation
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum CapaPriority {
@@ -107,6 +143,9 @@ pub enum CapaType {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CapaRecord {
     pub id: String,
+    /// Human-readable number, e.g. `CAPA-2025-0012` (empty on legacy records)
+    #[serde(default)]
+    pub capa_number: String,
     pub title: String,
     pub description: String,
     pub capa_type: CapaType,
@@ -126,6 +165,16 @@ pub struct CapaRecord {
     pub preventive_actions: Vec<CapaAction>,
     pub effectiveness_verification: Option<EffectivenessVerification>,
     pub metadata: HashMap<String, String>,
+    /// Optimistic concurrency version; updates must present the current one
+    #[serde(default = "initial_row_version")]
+    pub row_version: i64,
+}
+
+impl CapaRecord {
+    /// Identifier shown to people: the CAPA number, or the UUID for legacy records
+    pub fn display_id(&self) -> &str {
+        if self.capa_number.is_empty() { &self.id } else { &self.capa_number }
+    }
 }
 
 /// Individual action within a CAPA
@@ -164,14 +213,147 @@ pub struct EffectivenessVerification {
 }
 
 /// CAPA workflow management service
+#[derive(Clone)]
 pub struct CapaService {
     audit_manager: AuditManager,
+    record_history: Option<Database>,
+    events: Option<EventBus>,
+    clock: SharedClock,
+    rules: Option<ScriptedRules>,
 }
 
 impl CapaService {
     /// Create new CAPA service with audit integration
     pub fn new(audit_manager: AuditManager) -> Self {
-        Self { audit_manager }
+        Self { audit_manager, record_history: None, events: None, clock: system_clock(), rules: None }
+    }
+
+    /// Check new CAPAs and status changes against the validation scripts in
+    /// force for CAPAs.
+    pub fn with_validation_scripts(mut self, rules: ScriptedRules) -> Self {
+        self.rules = Some(rules);
+        self
+    }
+
+    fn check_rules(&self, capa: &CapaRecord) -> Result<()> {
+        match &self.rules {
+            Some(rules) => rules.check(RuleHook::Capa, capa),
+            None => Ok(()),
+        }
+    }
+
+    /// Read the current time from `clock` for timestamps and overdue checks.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Publish `CapaClosed` on `events` whenever a CAPA is closed.
+    pub fn with_event_bus(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    fn publish_closed(&self, capa: &CapaRecord, user_id: &str) {
+        if let (Some(events), Some(closed_at)) = (&self.events, capa.closed_date) {
+            events.publish(&QmsEvent::CapaClosed {
+                capa_id: capa.id.clone(),
+                closed_by: user_id.to_string(),
+                closed_at,
+            });
+        }
+    }
+
+    /// Store a version snapshot of every CAPA change in `database`.
+    pub fn with_record_history(mut self, database: Database) -> Self {
+        self.record_history = Some(database);
+        self
+    }
+
+    fn snapshot(&self, capa: &CapaRecord, user_id: &str) -> Result<()> {
+        if let Some(db) = &self.record_history {
+            RecordHistoryRepo::new(db).snapshot(capa, user_id)?;
+        }
+        Ok(())
+    }
+
+    /// Replace `capa` by `updated`, its next version. The audit entry and the
+    /// writes of [`Self::write_version`] happen in one unit of work; if any
+    /// fails, nothing is stored and `capa` is left unchanged.
+    fn store_change(
+        &self,
+        capa: &mut CapaRecord,
+        mut updated: CapaRecord,
+        user_id: &str,
+        reason: &ChangeReason,
+        action: &str,
+        details: String,
+    ) -> Result<()> {
+        updated.row_version = capa.row_version + 1;
+        self.audit_manager.database().unit_of_work(|uow| {
+            let resource = format!("capa:{}", updated.id);
+            self.audit_manager.log_action_in(uow, user_id, action, &resource, "Success", Some(details))?;
+            self.write_version(uow.connection(), capa, &updated, user_id, reason)
+        })?;
+        *capa = updated;
+        Ok(())
+    }
+
+    /// Write `updated` over `previous`, which must still be the stored
+    /// version (`Conflict` otherwise): the changed fields with `reason`, the
+    /// `capa_records` row when the CAPA has one, and the version snapshot.
+    fn write_version(
+        &self,
+        conn: &Connection,
+        previous: &CapaRecord,
+        updated: &CapaRecord,
+        user_id: &str,
+        reason: &ChangeReason,
+    ) -> Result<()> {
+        if let Some(stored) = stored_row_version(conn, &previous.id)? {
+            check_version("capa", &previous.id, previous.row_version, stored)?;
+        }
+        let changes = diff_fields(previous, updated, &["updated_at", "row_version"])?;
+        record_changes(conn, "capa", &updated.id, user_id, reason, &changes)?;
+        conn.execute(
+            "UPDATE capa_records SET status = ?2, assigned_to = ?3, closed_date = ?4, updated_at = ?5, row_version = ?6
+             WHERE id = ?1",
+            params![
+                updated.id,
+                format!("{:?}", updated.status),
+                updated.assigned_to,
+                updated.closed_date.map(|closed| closed.to_rfc3339()),
+                updated.updated_at.to_rfc3339(),
+                updated.row_version
+            ],
+        )?;
+        if self.record_history.is_some() {
+            snapshot_with(conn, updated, user_id, updated.updated_at)?;
+        }
+        Ok(())
+    }
+
+    /// Longest accepted CAPA title
+    pub const MAX_TITLE_LENGTH: usize = 200;
+
+    /// Check the fields of a new CAPA, reporting every invalid field at once.
+    pub fn validate_new_capa(title: &str, description: &str, initiator_id: &str, assigned_to: &str) -> Result<()> {
+        let mut errors = ValidationErrors::new();
+        if title.trim().is_empty() {
+            errors.add("title", "Title is required");
+        } else if title.chars().count() > Self::MAX_TITLE_LENGTH {
+            errors.add("title", format!("Title must be at most {} characters", Self::MAX_TITLE_LENGTH));
+        }
+        if description.trim().is_empty() {
+            errors.add("description", "Description is required");
+        }
+        if initiator_id.trim().is_empty() {
+            errors.add("initiator_id", "Initiator is required");
+        }
+        if assigned_to.trim().is_empty() {
+            errors.add("assigned_to", "Assignee is required");
+        }
+        errors.into_result()
     }
 
     /// Create a new CAPA record
@@ -184,11 +366,13 @@ impl CapaService {
         assigned_to: String,
         due_date: Option<DateTime<Utc>>,
     ) -> Result<CapaRecord> {
+        Self::validate_new_capa(&title, &description, &initiator_id, &assigned_to)?;
         let capa_id = Uuid::new_v4().to_string();
-        let now = Utc::now();
+        let now = self.clock.now();
 
-        let capa = CapaRecord {
+        let mut capa = CapaRecord {
             id: capa_id.clone(),
+            capa_number: String::new(),
             title: title.clone(),
             description,
             capa_type: capa_type.clone(),
@@ -208,7 +392,12 @@ impl CapaService {
             preventive_actions: Vec::new(),
             effectiveness_verification: None,
             metadata: HashMap::new(),
+            row_version: INITIAL_ROW_VERSION,
         };
+        // Checked before a number is allocated, so a rejected CAPA leaves no gap
+        self.check_rules(&capa)?;
+        let capa_number = NumberingRepo::new(self.audit_manager.database()).next_number(CAPA_SCOPE, DEFAULT_SITE_ID)?;
+        capa.capa_number = capa_number.clone();
 
         // Audit trail for CAPA creation
         self.audit_manager.log_action(
@@ -216,55 +405,121 @@ impl CapaService {
             "capa_created",
             &format!("capa:{}", capa_id),
             "Success",
-            Some(format!("Created {} CAPA: {} (Priority: {})", 
-                capa_type.as_str(), title, priority.as_str())),
+            Some(format!("Created {} CAPA {}: {} (Priority: {})", 
+                capa_type.as_str(), capa_number, title, priority.as_str())),
         )?;
+        self.snapshot(&capa, &initiator_id)?;
 
         Ok(capa)
     }
 
-    /// Update CAPA status with validation
+    /// Update CAPA status with validation. Closure needs the closer's
+    /// electronic signature and goes through [`Self::close_with_signature`].
     pub fn update_status(&self, 
         capa: &mut CapaRecord, 
         new_status: CapaStatus, 
         user_id: &str,
+        reason: &ChangeReason,
         comment: Option<String>,
     ) -> Result<()> {
-        // Validate status transition
-        if !capa.status.can_transition_to(&new_status) {
-            return Err(QmsError::ValidationError {
+        if new_status == CapaStatus::Closed {
+            return Err(QmsError::Validation {
                 field: "status".to_string(),
-                message: format!("Invalid status transition from {} to {}", 
-                    capa.status.as_str(), new_status.as_str()),
+                message: "A CAPA is closed with an electronic signature".to_string(),
             });
         }
+        let mut updated = capa.clone();
+        let audit_message = Self::transition(&mut updated, new_status, user_id, comment, None, self.clock.now())?;
+        self.check_rules(&updated)?;
+        self.store_change(capa, updated, user_id, reason, "capa_status_updated", audit_message)
+    }
+
+    /// Close a CAPA with the closer's electronic signature over the verified
+    /// record; `meaning` must be [`CAPA_CLOSURE_MEANING`]. The new version,
+    /// signature and audit entry are written in one unit of work; if any
+    /// fails, nothing is stored and `capa` is left unchanged.
+    pub fn close_with_signature(
+        &self,
+        capa: &mut CapaRecord,
+        user_id: &str,
+        meaning: &str,
+        reason: &ChangeReason,
+        comment: Option<String>,
+    ) -> Result<ElectronicSignature> {
+        let signature = ElectronicSignature::sign("capa", &capa.id, &*capa, user_id, meaning)?;
+        let mut closed = capa.clone();
+        let now = self.clock.now();
+        let audit_message = Self::transition(&mut closed, CapaStatus::Closed, user_id, comment, Some(&signature), now)?;
+        closed.row_version = capa.row_version + 1;
+
+        self.audit_manager.database().unit_of_work(|uow| {
+            self.write_version(uow.connection(), capa, &closed, user_id, reason)?;
+            uow.record_signature(&signature)?;
+            self.audit_manager.log_action_in(
+                uow,
+                user_id,
+                "capa_closed_signed",
+                &format!("capa:{}", closed.id),
+                "Success",
+                Some(format!("{} (signature {}, meaning: {})", audit_message, signature.id, meaning)),
+            )
+        })?;
+
+        *capa = closed;
+        self.publish_closed(capa, user_id);
+        Ok(signature)
+    }
+
+    /// Validate and apply a status change, returning its audit message.
+    fn transition(
+        capa: &mut CapaRecord,
+        new_status: CapaStatus,
+        user_id: &str,
+        comment: Option<String>,
+        signature: Option<&ElectronicSignature>,
+        now: DateTime<Utc>,
+    ) -> Result<String> {
+        capa_workflow().check(&capa.id, capa, capa.status.as_str(), new_status.as_str(), user_id, signature)?;
 
         let old_status = capa.status.clone();
         capa.status = new_status.clone();
-        capa.updated_at = Utc::now();
+        capa.updated_at = now;
 
         // Set closed date if completing
         if new_status == CapaStatus::Closed {
-            capa.closed_date = Some(Utc::now());
+            capa.closed_date = Some(now);
         }
 
         // Audit trail for status change
-        let audit_message = match comment {
+        Ok(match comment {
             Some(c) => format!("Status changed from {} to {}: {}", 
                 old_status.as_str(), new_status.as_str(), c),
             None => format!("Status changed from {} to {}", 
                 old_status.as_str(), new_status.as_str()),
-        };
+        })
+    }
 
-        self.audit_manager.log_action(
-            user_id,
-            "capa_status_updated",
-            &format!("capa:{}", capa.id),
-            "Success",
-            Some(audit_message),
-        )?;
+    /// Hand a CAPA to another owner
+    pub fn reassign(
+        &self,
+        capa: &mut CapaRecord,
+        assigned_to: &str,
+        user_id: &str,
+        reason: &ChangeReason,
+    ) -> Result<()> {
+        let assigned_to = assigned_to.trim();
+        if assigned_to.is_empty() {
+            return Err(QmsError::Validation {
+                field: "assigned_to".to_string(),
+                message: "Assignee is required".to_string(),
+            });
+        }
+        let mut updated = capa.clone();
+        updated.assigned_to = assigned_to.to_string();
+        updated.updated_at = self.clock.now();
 
-        Ok(())
+        let details = format!("Reassigned from {} to {}", capa.assigned_to, assigned_to);
+        self.store_change(capa, updated, user_id, reason, "capa_reassigned", details)
     }
 
     /// Add corrective action to CAPA
@@ -275,6 +530,7 @@ impl CapaService {
         due_date: DateTime<Utc>,
         verification_method: String,
         user_id: &str,
+        reason: &ChangeReason,
     ) -> Result<String> {
         let action_id = Uuid::new_v4().to_string();
         
@@ -289,18 +545,13 @@ impl CapaService {
             evidence: Vec::new(),
         };
 
-        capa.corrective_actions.push(action);
-        capa.updated_at = Utc::now();
+        let mut updated = capa.clone();
+        updated.corrective_actions.push(action);
+        updated.updated_at = self.clock.now();
 
         // Audit trail
-        self.audit_manager.log_action(
-            user_id,
-            "corrective_action_added",
-            &format!("capa:{}", capa.id),
-            "Success",
-            Some(format!("Added corrective action: {} (Assigned to: {})", 
-                description, assigned_to)),
-        )?;
+        let details = format!("Added corrective action: {} (Assigned to: {})", description, assigned_to);
+        self.store_change(capa, updated, user_id, reason, "corrective_action_added", details)?;
 
         Ok(action_id)
     }
@@ -313,6 +564,7 @@ impl CapaService {
         due_date: DateTime<Utc>,
         verification_method: String,
         user_id: &str,
+        reason: &ChangeReason,
     ) -> Result<String> {
         let action_id = Uuid::new_v4().to_string();
         
@@ -327,18 +579,13 @@ impl CapaService {
             evidence: Vec::new(),
         };
 
-        capa.preventive_actions.push(action);
-        capa.updated_at = Utc::now();
+        let mut updated = capa.clone();
+        updated.preventive_actions.push(action);
+        updated.updated_at = self.clock.now();
 
         // Audit trail
-        self.audit_manager.log_action(
-            user_id,
-            "preventive_action_added",
-            &format!("capa:{}", capa.id),
-            "Success",
-            Some(format!("Added preventive action: {} (Assigned to: {})", 
-                description, assigned_to)),
-        )?;
+        let details = format!("Added preventive action: {} (Assigned to: {})", description, assigned_to);
+        self.store_change(capa, updated, user_id, reason, "preventive_action_added", details)?;
 
         Ok(action_id)
     }
@@ -349,52 +596,42 @@ impl CapaService {
         action_id: &str,
         completion_evidence: Vec<String>,
         user_id: &str,
+        reason: &ChangeReason,
     ) -> Result<()> {
-        let now = Utc::now();
-        let mut action_found = false;
-
-        // Update corrective actions
-        for action in &mut capa.corrective_actions {
-            if action.id == action_id {
-                action.status = ActionStatus::Completed;
-                action.completed_date = Some(now);
-                action.evidence = completion_evidence.clone();
-                action_found = true;
-                break;
-            }
-        }
-
-        // Update preventive actions if not found in corrective
-        if !action_found {
-            for action in &mut capa.preventive_actions {
-                if action.id == action_id {
-                    action.status = ActionStatus::Completed;
-                    action.completed_date = Some(now);
-                    action.evidence = completion_evidence.clone();
-                    action_found = true;
-                    break;
-                }
-            }
-        }
-
-        if !action_found {
-            return Err(QmsError::NotFound {
+        let now = self.clock.now();
+        let mut updated = capa.clone();
+
+        // Corrective actions first, then preventive ones
+        let action = updated
+            .corrective_actions
+            .iter_mut()
+            .chain(updated.preventive_actions.iter_mut())
+            .find(|action| action.id == action_id)
+            .ok_or_else(|| QmsError::NotFound {
                 resource: "action".to_string(),
                 id: action_id.to_string(),
-            });
-        }
-
-        capa.updated_at = now;
+            })?;
+        action.status = ActionStatus::Completed;
+        action.completed_date = Some(now);
+        action.evidence = completion_evidence.clone();
+        updated.updated_at = now;
+        updated.row_version = capa.row_version + 1;
 
         // Audit trail
-        self.audit_manager.log_action(
-            user_id,
-            "action_completed",
-            &format!("capa:{}/action:{}", capa.id, action_id),
-            "Success",
-            Some(format!("Action completed with {} evidence items", 
-                completion_evidence.len())),
-        )?;
+        let details = format!("Action completed with {} evidence items", completion_evidence.len());
+        self.audit_manager.database().unit_of_work(|uow| {
+            let resource = format!("capa:{}/action:{}", capa.id, action_id);
+            self.audit_manager.log_action_in(uow, user_id, "action_completed", &resource, "Success", Some(details))?;
+            self.write_version(uow.connection(), capa, &updated, user_id, reason)?;
+            uow.connection().execute(
+                "UPDATE capa_actions SET status = 'Completed', completed_date = ?2, updated_at = ?2,
+                        row_version = row_version + 1
+                 WHERE id = ?1",
+                params![action_id, now.to_rfc3339()],
+            )?;
+            Ok(())
+        })?;
+        *capa = updated;
 
         Ok(())
     }
@@ -407,9 +644,10 @@ impl CapaService {
         is_effective: bool,
         verifier_id: String,
         follow_up_actions: Vec<String>,
+        reason: &ChangeReason,
     ) -> Result<()> {
         let verification = EffectivenessVerification {
-            verification_date: Utc::now(),
+            verification_date: self.clock.now(),
             verifier_id: verifier_id.clone(),
             method: verification_method,
             results: results.clone(),
@@ -418,20 +656,13 @@ impl CapaService {
             follow_up_actions,
         };
 
-        capa.effectiveness_verification = Some(verification);
-        capa.updated_at = Utc::now();
+        let mut updated = capa.clone();
+        updated.effectiveness_verification = Some(verification);
+        updated.updated_at = self.clock.now();
 
         // Audit trail
-        self.audit_manager.log_action(
-            &verifier_id,
-            "effectiveness_verified",
-            &format!("capa:{}", capa.id),
-            "Success",
-            Some(format!("Effectiveness verification: {} (Effective: {})", 
-                results, is_effective)),
-        )?;
-
-        Ok(())
+        let details = format!("Effectiveness verification: {} (Effective: {})", results, is_effective);
+        self.store_change(capa, updated, &verifier_id, reason, "effectiveness_verified", details)
     }
 
     /// Get CAPA metrics for reporting
@@ -440,7 +671,7 @@ impl CapaService {
         let mut status_counts = HashMap::new();
         let mut priority_counts = HashMap::new();
         let mut overdue_count = 0;
-        let now = Utc::now();
+        let now = self.clock.now();
 
         for capa in capas {
             // Count by status
@@ -451,9 +682,11 @@ impl CapaService {
             let priority_str = capa.priority.as_str();
             *priority_counts.entry(priority_str.to_string()).or_insert(0) += 1;
 
-            // Check if overdue
+            // Check if overdue; a CAPA closed after the clock's "now" still counts
+            let closed_by_now =
+                capa.status == CapaStatus::Closed && capa.closed_date.map_or(true, |closed| closed <= now);
             if let Some(due_date) = capa.due_date {
-                if due_date < now && capa.status != CapaStatus::Closed {
+                if due_date < now && !closed_by_now {
                     overdue_count += 1;
                 }
             }
@@ -472,7 +705,7 @@ impl CapaService {
 }
 
 /// CAPA metrics for reporting and dashboard
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CapaMetrics {
     pub total_count: usize,
     pub status_counts: HashMap<String, usize>,
@@ -492,11 +725,35 @@ impl CapaType {
     }
 }
 
+/// Stored version of a CAPA: that of its `capa_records` row, or else that
+/// of its latest version snapshot. `None` when neither exists.
+fn stored_row_version(conn: &Connection, capa_id: &str) -> Result<Option<i64>> {
+    let row = conn
+        .query_row("SELECT row_version FROM capa_records WHERE id = ?1", params![capa_id], |row| row.get(0))
+        .optional()?;
+    if row.is_some() {
+        return Ok(row);
+    }
+    Ok(conn
+        .query_row(
+            "SELECT COALESCE(json_extract(snapshot, '$.row_version'), ?2) FROM record_versions
+             WHERE record_type = 'capa' AND record_id = ?1
+             ORDER BY version DESC LIMIT 1",
+            params![capa_id, INITIAL_ROW_VERSION],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::DatabaseConfig;
 
+    fn reason() -> ChangeReason {
+        ChangeReason::new("Investigation findings").unwrap()
+    }
+
     fn setup_test_service() -> CapaService {
         let config = crate::config::DatabaseConfig {
             url: ":memory:".to_string(),
@@ -510,6 +767,29 @@ mod tests {
         CapaService::new(audit_manager)
     }
 
+    #[test]
+    fn test_create_capa_reports_all_invalid_fields() {
+        let service = setup_test_service();
+        let result = service.create_capa(
+            " ".to_string(),
+            String::new(),
+            CapaType::Corrective,
+            CapaPriority::Low,
+            "user123".to_string(),
+            String::new(),
+            None,
+        );
+
+        let Err(QmsError::ValidationErrors { errors }) = result else {
+            panic!("expected aggregated validation errors");
+        };
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors.get("title"), Some("Title is required"));
+        assert!(errors.get("description").is_some());
+        assert!(errors.get("assigned_to").is_some());
+        assert!(errors.get("initiator_id").is_none());
+    }
+
     #[test]
     fn test_create_capa_corrective() {
         let service = setup_test_service();
@@ -564,6 +844,7 @@ mod tests {
             &mut capa,
             CapaStatus::InvestigationInProgress,
             "user123",
+            &reason(),
             Some("Starting investigation".to_string()),
         );
 
@@ -588,6 +869,7 @@ mod tests {
             &mut capa,
             CapaStatus::Closed,
             "user123",
+            &reason(),
             None,
         );
 
@@ -615,6 +897,7 @@ mod tests {
             Utc::now() + chrono::Duration::days(14),
             "Testing and validation".to_string(),
             "user123",
+            &reason(),
         ).unwrap();
 
         assert_eq!(capa.corrective_actions.len(), 1);
@@ -643,6 +926,7 @@ mod tests {
             Utc::now() + chrono::Duration::days(14),
             "Testing and validation".to_string(),
             "user123",
+            &reason(),
         ).unwrap();
 
         let evidence = vec!["test_report.pdf".to_string(), "validation_results.xlsx".to_string()];
@@ -651,6 +935,7 @@ mod tests {
             &action_id,
             evidence.clone(),
             "engineer456",
+            &reason(),
         );
 
         assert!(result.is_ok());
@@ -679,6 +964,7 @@ mod tests {
             true,
             "qa_manager".to_string(),
             vec![],
+            &reason(),
         );
 
         assert!(result.is_ok());
@@ -716,11 +1002,11 @@ mod tests {
         ];
 
         // Follow proper workflow to close one CAPA
-        service.update_status(&mut capas[1], CapaStatus::InvestigationInProgress, "user2", None).unwrap();
-        service.update_status(&mut capas[1], CapaStatus::RootCauseAnalysis, "user2", None).unwrap();
-        service.update_status(&mut capas[1], CapaStatus::CorrectiveActionInProgress, "user2", None).unwrap();
-        service.update_status(&mut capas[1], CapaStatus::EffectivenessVerification, "user2", None).unwrap();
-        service.update_status(&mut capas[1], CapaStatus::Closed, "user2", None).unwrap();
+        service.update_status(&mut capas[1], CapaStatus::InvestigationInProgress, "user2", &reason(), None).unwrap();
+        service.update_status(&mut capas[1], CapaStatus::RootCauseAnalysis, "user2", &reason(), None).unwrap();
+        service.update_status(&mut capas[1], CapaStatus::CorrectiveActionInProgress, "user2", &reason(), None).unwrap();
+        service.update_status(&mut capas[1], CapaStatus::EffectivenessVerification, "user2", &reason(), None).unwrap();
+        service.close_with_signature(&mut capas[1], "user2", CAPA_CLOSURE_MEANING, &reason(), None).unwrap();
 
         let metrics = service.get_capa_metrics(&capas);
 
@@ -757,4 +1043,190 @@ mod tests {
         let _verified = ActionStatus::Verified;
         let _overdue = ActionStatus::Overdue;
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_record_history_snapshots() {
+        let database = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let service = CapaService::new(AuditManager::new(database.clone())).with_record_history(database.clone());
+        let mut capa = service.create_capa(
+            "Versioned CAPA".to_string(),
+            "Snapshot on every update".to_string(),
+            CapaType::Corrective,
+            CapaPriority::High,
+            "user123".to_string(),
+            "engineer456".to_string(),
+            None,
+        ).unwrap();
+        service.update_status(&mut capa, CapaStatus::InvestigationInProgress, "engineer456", &reason(), None).unwrap();
+
+        let history = RecordHistoryRepo::new(&database);
+        let versions = history.versions("capa", &capa.id).unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].restore::<CapaRecord>().unwrap().status, CapaStatus::Identified);
+        let diff = history.diff("capa", &capa.id, 1, 2).unwrap();
+        assert!(diff.iter().any(|c| c.field == "status" && c.new_value.as_deref() == Some("InvestigationInProgress")));
+    }
+
+    #[test]
+    fn test_stale_capa_versions_are_refused() {
+        let database = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let service = CapaService::new(AuditManager::new(database.clone())).with_record_history(database.clone());
+        let mut capa = capa_in_verification(&service);
+        assert_eq!(capa.row_version, 5);
+
+        let mut stale = capa.clone();
+        service.reassign(&mut capa, "lead", "qa", &reason()).unwrap();
+        assert_eq!(capa.row_version, 6);
+        let refused = service.reassign(&mut stale, "qa", "qa", &reason());
+        assert!(matches!(refused, Err(QmsError::Conflict { expected: 5, actual: 6, .. })));
+        let refused = service.close_with_signature(&mut stale, "qa", CAPA_CLOSURE_MEANING, &reason(), None);
+        assert!(matches!(refused, Err(QmsError::Conflict { .. })));
+        assert_eq!(stale.row_version, 5, "a refused update leaves the record unchanged");
+        service.close_with_signature(&mut capa, "qa", CAPA_CLOSURE_MEANING, &reason(), None).unwrap();
+        assert_eq!(capa.row_version, 7);
+    }
+
+    #[test]
+    fn test_status_and_owner_changes_record_reasons() {
+        use crate::change_history::{ChangeHistoryRepo, FieldChange};
+        let service = setup_test_service();
+        let mut capa = capa_in_verification(&service);
+        let reassigned = ChangeReason::new("Investigator on leave").unwrap();
+        service.reassign(&mut capa, "lead", "qa", &reassigned).unwrap();
+        service.close_with_signature(&mut capa, "qa", CAPA_CLOSURE_MEANING, &reason(), None).unwrap();
+
+        let history = ChangeHistoryRepo::new(service.audit_manager.database()).history("capa", &capa.id).unwrap();
+        assert_eq!(history.len(), 6, "four status changes, the reassignment and the closure");
+        let reassignment = &history[4];
+        assert_eq!((reassignment.changed_by.as_str(), &reassignment.reason), ("qa", &reassigned));
+        assert_eq!(
+            reassignment.changes,
+            vec![FieldChange {
+                field: "assigned_to".to_string(),
+                old_value: Some("engineer456".to_string()),
+                new_value: Some("lead".to_string()),
+            }]
+        );
+        let closure = &history[5];
+        assert!(closure.changes.iter().any(|c| c.field == "status" && c.new_value.as_deref() == Some("Closed")));
+        assert!(closure.changes.iter().any(|c| c.field == "closed_date" && c.old_value.is_none()));
+    }
+    fn capa_in_verification(service: &CapaService) -> CapaRecord {
+        let mut capa = service.create_capa(
+            "Signed closure".to_string(),
+            "Closed with an e-signature".to_string(),
+            CapaType::Corrective,
+            CapaPriority::High,
+            "user123".to_string(),
+            "engineer456".to_string(),
+            None,
+        ).unwrap();
+        for status in [
+            CapaStatus::InvestigationInProgress,
+            CapaStatus::RootCauseAnalysis,
+            CapaStatus::CorrectiveActionInProgress,
+            CapaStatus::EffectivenessVerification,
+        ] {
+            service.update_status(&mut capa, status, "engineer456", &reason(), None).unwrap();
+        }
+        capa
+    }
+
+    #[test]
+    fn test_close_with_signature_is_atomic() {
+        use crate::unit_of_work::SignatureRepo;
+        let database = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let service = CapaService::new(AuditManager::new(database.clone())).with_record_history(database.clone());
+        let history = RecordHistoryRepo::new(&database);
+        let signatures = SignatureRepo::new(&database);
+
+        // A failing audit entry (no user) rolls back the snapshot and signature
+        let mut capa = capa_in_verification(&service);
+        let versions_before = history.versions("capa", &capa.id).unwrap().len();
+        assert!(service.close_with_signature(&mut capa, "", "closure", &reason(), None).is_err());
+        assert_eq!(capa.status, CapaStatus::EffectivenessVerification);
+        assert_eq!(history.versions("capa", &capa.id).unwrap().len(), versions_before);
+        assert!(signatures.for_record("capa", &capa.id).unwrap().is_empty());
+
+        let events = EventBus::new();
+        let closed = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = closed.clone();
+        events.subscribe("task_creation", move |event: &QmsEvent| -> Result<()> {
+            sink.lock().unwrap().push(event.clone());
+            Ok(())
+        });
+        let service = service.with_event_bus(events);
+        let verified = Some("verified".to_string());
+        let signature = service.close_with_signature(&mut capa, "qa_manager", "closure", &reason(), verified).unwrap();
+        assert!(matches!(&closed.lock().unwrap()[..], [QmsEvent::CapaClosed { closed_by, .. }] if closed_by == "qa_manager"));
+        assert_eq!(capa.status, CapaStatus::Closed);
+        assert!(capa.closed_date.is_some());
+        assert_eq!(history.versions("capa", &capa.id).unwrap().len(), versions_before + 1);
+        assert_eq!(signatures.for_record("capa", &capa.id).unwrap(), vec![signature]);
+        let audit = database.get_audit_entries_for_resource(&format!("capa:{}", capa.id)).unwrap();
+        assert_eq!(audit.last().unwrap().action, "capa_closed_signed");
+    }
+
+    #[test]
+    fn test_unsigned_close_is_refused() {
+        let service = setup_test_service();
+        let mut capa = capa_in_verification(&service);
+
+        assert!(service.update_status(&mut capa, CapaStatus::Closed, "qa", &reason(), None).is_err());
+        assert!(matches!(
+            service.close_with_signature(&mut capa, "qa", "approval", &reason(), None),
+            Err(QmsError::Security { .. })
+        ));
+        assert_eq!(capa.status, CapaStatus::EffectivenessVerification);
+        assert!(capa.closed_date.is_none());
+
+        // A signature over an earlier version of the record does not close it either
+        let stale = ElectronicSignature::sign("capa", &capa.id, &capa, "qa", CAPA_CLOSURE_MEANING).unwrap();
+        capa.description.push_str(" (amended)");
+        let refused = CapaService::transition(&mut capa, CapaStatus::Closed, "qa", None, Some(&stale), Utc::now());
+        assert!(matches!(refused, Err(QmsError::Security { .. })));
+        assert_eq!(capa.status, CapaStatus::EffectivenessVerification);
+    }
+
+    #[test]
+    fn test_metrics_follow_injected_clock() {
+        use crate::clock::{Clock, MockClock};
+        use chrono::TimeZone;
+        let clock = MockClock::at(Utc.with_ymd_and_hms(2026, 1, 10, 9, 0, 0).unwrap());
+        let service = setup_test_service().with_clock(clock.shared());
+        let due = Utc.with_ymd_and_hms(2026, 1, 15, 0, 0, 0).unwrap();
+        let mut capas = vec![capa_in_verification(&service)];
+        capas[0].due_date = Some(due);
+        assert_eq!(capas[0].created_at, clock.now());
+        assert_eq!(service.get_capa_metrics(&capas).overdue_count, 0);
+
+        // Closed late: overdue until the closure, not afterwards
+        clock.advance(chrono::Duration::days(10));
+        service.close_with_signature(&mut capas[0], "qa", CAPA_CLOSURE_MEANING, &reason(), None).unwrap();
+        assert_eq!(capas[0].closed_date, Some(clock.now()));
+        assert_eq!(service.get_capa_metrics(&capas).overdue_count, 0);
+        clock.set(due + chrono::Duration::days(1));
+        assert_eq!(service.get_capa_metrics(&capas).overdue_count, 1);
+    }
+}
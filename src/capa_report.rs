@@ -0,0 +1,501 @@
+//! # CAPA Report - Per-Record PDF Export
+//!
+//! Assembles the complete record of a single CAPA (description, investigation,
+//! root cause, corrective/preventive actions with evidence, effectiveness
+//! verification, audit-derived status history and a signature block) into a
+//! [`Report`]. This is the document QA attaches to external audits in support
+//! of FDA 21 CFR 820.100 and ISO 13485 §8.5.2/§8.5.3. The CAPA aging report
+//! summarises all open CAPAs for periodic management review.
+
+use chrono::{DateTime, Utc};
+use std::path::Path;
+
+use crate::capa::{ActionStatus, CapaAction, CapaRecord, CapaStatus};
+use crate::database::{AuditTrailEntry, Database};
+use crate::pdf_report::{render_report, RenderedReport};
+use crate::report::{Chart, ChartDatum, ChartKind, Report, ReportBuilder, ReportSection, ReportTable};
+use crate::Result;
+
+/// Placeholder printed for empty record fields.
+const NOT_RECORDED: &str = "Not recorded";
+
+/// Upper bounds (inclusive, in days) of the CAPA aging buckets; the last bucket is open-ended.
+const AGING_BUCKETS: [(i64, &str); 4] = [(30, "0-30 days"), (60, "31-60 days"), (90, "61-90 days"), (i64::MAX, ">90 days")];
+
+/// Configuration for exporting a single CAPA record.
+#[derive(Debug, Clone)]
+pub struct CapaReportConfig<'a> {
+    /// Destination path for the generated PDF file.
+    pub output_path: &'a Path,
+    /// CAPA record to export.
+    pub capa: &'a CapaRecord,
+    /// Audit trail entries for the CAPA (see [`capa_history`]).
+    pub history: &'a [AuditTrailEntry],
+    /// User generating the export.
+    pub prepared_by: &'a str,
+    /// Optional organisation name for the cover page.
+    pub organization: Option<&'a str>,
+    /// UTC timestamp of report generation.
+    pub generated_on: DateTime<Utc>,
+}
+
+/// Audit trail entries recorded against a CAPA and its actions, oldest first.
+pub fn capa_history(db: &Database, capa_id: &str) -> Result<Vec<AuditTrailEntry>> {
+    db.get_audit_entries_for_resource(&format!("capa:{}", capa_id))
+}
+
+/// Build the CAPA detail report model.
+pub fn build_capa_report(cfg: &CapaReportConfig) -> Report {
+    let capa = cfg.capa;
+
+    let mut builder = ReportBuilder::new(format!("CAPA Record: {}", capa.title))
+        .with_subtitle(format!("CAPA {}", capa.display_id()))
+        .with_prepared_by(cfg.prepared_by)
+        .with_generated_on(cfg.generated_on)
+        .with_cover_page()
+        .with_table_of_contents()
+        .with_section(overview_section(capa))
+        .with_section(
+            ReportSection::new("Description").paragraph(text_or_placeholder(Some(&capa.description))),
+        )
+        .with_section(
+            ReportSection::new("Investigation & Root Cause")
+                .heading("Investigation Summary")
+                .paragraph(text_or_placeholder(capa.investigation_summary.as_deref()))
+                .heading("Root Cause")
+                .paragraph(text_or_placeholder(capa.root_cause.as_deref())),
+        )
+        .with_section(actions_section("Corrective Actions", &capa.corrective_actions))
+        .with_section(actions_section("Preventive Actions", &capa.preventive_actions))
+        .with_section(effectiveness_section(capa))
+        .with_section(history_section(cfg.history))
+        .with_section(signatures_section(capa));
+    if let Some(org) = cfg.organization {
+        builder = builder.with_organization(org);
+    }
+    builder.build()
+}
+
+/// Render the CAPA detail report to `cfg.output_path`.
+pub fn generate_capa_report(cfg: &CapaReportConfig) -> Result<RenderedReport> {
+    render_report(&build_capa_report(cfg), cfg.output_path)
+}
+
+/// Build the CAPA aging report: open CAPAs bucketed by age and listed oldest first.
+pub fn build_capa_aging_report(capas: &[CapaRecord], as_of: DateTime<Utc>) -> Report {
+    let mut open: Vec<&CapaRecord> = capas
+        .iter()
+        .filter(|c| !matches!(c.status, CapaStatus::Closed | CapaStatus::Cancelled))
+        .collect();
+    open.sort_by_key(|c| c.created_at);
+
+    let age_days = |capa: &CapaRecord| (as_of - capa.created_at).num_days().max(0);
+    let mut buckets = [0usize; AGING_BUCKETS.len()];
+    for capa in &open {
+        let age = age_days(capa);
+        let idx = AGING_BUCKETS.iter().position(|(max, _)| age <= *max).unwrap_or(AGING_BUCKETS.len() - 1);
+        buckets[idx] += 1;
+    }
+    let overdue = open.iter().filter(|c| c.due_date.is_some_and(|d| d < as_of)).count();
+
+    let chart = Chart {
+        title: "Open CAPAs by Age".to_string(),
+        kind: ChartKind::Bar,
+        data: AGING_BUCKETS
+            .iter()
+            .zip(buckets)
+            .map(|((_, label), count)| ChartDatum { label: label.to_string(), value: count as f32 })
+            .collect(),
+    };
+    let summary = ReportSection::new("Summary")
+        .key_values(vec![
+            ("As Of", format_date(&as_of)),
+            ("Open CAPAs", open.len().to_string()),
+            ("Overdue CAPAs", overdue.to_string()),
+            ("Oldest Open CAPA", open.first().map(|c| format!("{} days", age_days(c))).unwrap_or_else(|| "-".to_string())),
+        ])
+        .chart(chart);
+
+    let listing = ReportSection::new("Open CAPAs");
+    let listing = if open.is_empty() {
+        listing.paragraph("No open CAPA records.")
+    } else {
+        listing.table(open.iter().fold(
+            ReportTable::new(vec!["Number", "Title", "Priority", "Status", "Owner", "Age (days)", "Due"])
+                .with_column_weights(vec![1.3, 2.2, 0.9, 1.6, 1.1, 0.8, 1.1]),
+            |table, c| {
+                let due = match c.due_date {
+                    Some(d) if d < as_of => format!("{} (overdue)", format_date(&d)),
+                    Some(d) => format_date(&d),
+                    None => "-".to_string(),
+                };
+                table.with_row(vec![
+                    c.display_id().to_string(),
+                    c.title.clone(),
+                    c.priority.as_str().to_string(),
+                    c.status.as_str().to_string(),
+                    c.assigned_to.clone(),
+                    age_days(c).to_string(),
+                    due,
+                ])
+            },
+        ))
+    };
+
+    ReportBuilder::new("CAPA Aging Report")
+        .with_generated_on(as_of)
+        .with_section(summary)
+        .with_section(listing)
+        .build()
+}
+
+fn overview_section(capa: &CapaRecord) -> ReportSection {
+    ReportSection::new("Overview").key_values(vec![
+        ("CAPA Number", capa.display_id().to_string()),
+        ("CAPA ID", capa.id.clone()),
+        ("Title", capa.title.clone()),
+        ("Type", capa.capa_type.as_str().to_string()),
+        ("Priority", capa.priority.as_str().to_string()),
+        ("Status", capa.status.as_str().to_string()),
+        ("Initiator", capa.initiator_id.clone()),
+        ("Assigned To", capa.assigned_to.clone()),
+        ("Created", format_date(&capa.created_at)),
+        ("Last Updated", format_date(&capa.updated_at)),
+        ("Due Date", capa.due_date.as_ref().map(format_date).unwrap_or_else(|| NOT_RECORDED.to_string())),
+        ("Closed Date", capa.closed_date.as_ref().map(format_date).unwrap_or_else(|| "Open".to_string())),
+        ("Source Document", text_or_placeholder(capa.source_document.as_deref())),
+        ("Related Risk", text_or_placeholder(capa.related_risk_id.as_deref())),
+    ])
+}
+
+fn actions_section(title: &str, actions: &[CapaAction]) -> ReportSection {
+    let section = ReportSection::new(title);
+    if actions.is_empty() {
+        return section.paragraph("No actions recorded.");
+    }
+
+    let table = actions.iter().enumerate().fold(
+        ReportTable::new(vec!["#", "Description", "Assigned To", "Due", "Completed", "Status"])
+            .with_column_weights(vec![0.4, 3.0, 1.3, 1.0, 1.0, 1.0]),
+        |table, (idx, action)| {
+            table.with_row(vec![
+                (idx + 1).to_string(),
+                action.description.clone(),
+                action.assigned_to.clone(),
+                format_date(&action.due_date),
+                action.completed_date.as_ref().map(format_date).unwrap_or_else(|| "-".to_string()),
+                action_status_label(&action.status).to_string(),
+            ])
+        },
+    );
+
+    // Evidence and verification method per action, keyed by the table row number.
+    actions.iter().enumerate().fold(section.table(table), |section, (idx, action)| {
+        let evidence = if action.evidence.is_empty() {
+            "No evidence attached.".to_string()
+        } else {
+            action.evidence.iter().map(|e| format!("- {}", e)).collect::<Vec<_>>().join("\n")
+        };
+        section
+            .heading(format!("Action {} ({})", idx + 1, action.id))
+            .key_values(vec![("Verification Method", action.verification_method.clone())])
+            .paragraph(evidence)
+    })
+}
+
+fn effectiveness_section(capa: &CapaRecord) -> ReportSection {
+    let section = ReportSection::new("Effectiveness Verification");
+    let Some(verification) = &capa.effectiveness_verification else {
+        return section.paragraph("Effectiveness verification has not been performed.");
+    };
+
+    let section = section
+        .key_values(vec![
+            ("Verification Date", format_date(&verification.verification_date)),
+            ("Verifier", verification.verifier_id.clone()),
+            ("Method", verification.method.clone()),
+            ("Effective", if verification.is_effective { "Yes" } else { "No" }.to_string()),
+            ("Follow-up Required", if verification.follow_up_required { "Yes" } else { "No" }.to_string()),
+        ])
+        .heading("Results")
+        .paragraph(verification.results.clone());
+    if verification.follow_up_actions.is_empty() {
+        section
+    } else {
+        section.heading("Follow-up Actions").paragraph(
+            verification
+                .follow_up_actions
+                .iter()
+                .map(|a| format!("- {}", a))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    }
+}
+
+fn history_section(history: &[AuditTrailEntry]) -> ReportSection {
+    let section = ReportSection::new("Status History");
+    if history.is_empty() {
+        return section.paragraph("No audit trail entries recorded for this CAPA.");
+    }
+
+    let table = history.iter().fold(
+        ReportTable::new(vec!["Timestamp (UTC)", "User", "Event", "Details"])
+            .with_column_weights(vec![1.4, 1.0, 1.3, 3.0]),
+        |table, entry| {
+            table.with_row(vec![
+                format_timestamp(&entry.timestamp),
+                entry.user_id.clone(),
+                entry.action.replace('_', " "),
+                entry.metadata.as_deref().map(metadata_text).unwrap_or_default(),
+            ])
+        },
+    );
+    section
+        .paragraph("Derived from the tamper-evident audit trail (21 CFR Part 11 §11.10(e)).")
+        .table(table)
+}
+
+fn signatures_section(capa: &CapaRecord) -> ReportSection {
+    let verifier = capa
+        .effectiveness_verification
+        .as_ref()
+        .map(|v| v.verifier_id.clone())
+        .unwrap_or_default();
+
+    let table = [
+        ("Initiator", capa.initiator_id.clone()),
+        ("CAPA Owner", capa.assigned_to.clone()),
+        ("Effectiveness Verifier", verifier),
+        ("Quality Assurance Approval", String::new()),
+    ]
+    .into_iter()
+    .fold(
+        ReportTable::new(vec!["Role", "Name", "Signature", "Date"])
+            .with_column_weights(vec![1.6, 1.4, 2.0, 1.0]),
+        |table, (role, name)| table.with_row(vec![role.to_string(), name, String::new(), String::new()]),
+    );
+
+    ReportSection::new("Required Signatures")
+        .paragraph(
+            "By signing, each party confirms the accuracy and completeness of this CAPA record \
+             and approves its closure.",
+        )
+        .table(table)
+}
+
+fn action_status_label(status: &ActionStatus) -> &'static str {
+    match status {
+        ActionStatus::Planned => "Planned",
+        ActionStatus::InProgress => "In Progress",
+        ActionStatus::Completed => "Completed",
+        ActionStatus::Verified => "Verified",
+        ActionStatus::Overdue => "Overdue",
+    }
+}
+
+fn text_or_placeholder(text: Option<&str>) -> String {
+    match text {
+        Some(t) if !t.trim().is_empty() => t.to_string(),
+        _ => NOT_RECORDED.to_string(),
+    }
+}
+
+fn format_date(date: &DateTime<Utc>) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// Audit timestamps are stored as RFC 3339; fall back to the raw value.
+fn format_timestamp(raw: &str) -> String {
+    DateTime::parse_from_rfc3339(raw)
+        .map(|t| t.with_timezone(&Utc).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|_| raw.to_string())
+}
+
+/// Audit metadata is stored as JSON; unwrap plain strings for readability.
+fn metadata_text(raw: &str) -> String {
+    match serde_json::from_str::<serde_json::Value>(raw) {
+        Ok(serde_json::Value::String(s)) => s,
+        Ok(serde_json::Value::Null) => String::new(),
+        Ok(other) => other.to_string(),
+        Err(_) => raw.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditManager;
+    use crate::capa::{CapaPriority, CapaService, CapaStatus, CapaType};
+    use crate::change_history::ChangeReason;
+    use crate::config::DatabaseConfig;
+    use tempfile::tempdir;
+
+    fn test_service() -> (Database, CapaService) {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let service = CapaService::new(AuditManager::new(db.clone()));
+        (db, service)
+    }
+
+    fn worked_capa(service: &CapaService) -> CapaRecord {
+        let mut capa = service
+            .create_capa(
+                "Sterile barrier seal failure".to_string(),
+                "Pouch seals failed peel testing on lot 42.".to_string(),
+                CapaType::Corrective,
+                CapaPriority::High,
+                "qe1".to_string(),
+                "eng1".to_string(),
+                Some(Utc::now()),
+            )
+            .unwrap();
+        let reason = ChangeReason::new("Complaint trend confirmed").unwrap();
+        service.update_status(&mut capa, CapaStatus::InvestigationInProgress, "qe1", &reason, None).unwrap();
+        capa.investigation_summary = Some("Sealer temperature drifted below validated range.".to_string());
+        capa.root_cause = Some("Heater controller calibration lapsed.".to_string());
+        let action_id = service
+            .add_corrective_action(
+                &mut capa,
+                "Recalibrate sealer and re-test retained lots".to_string(),
+                "eng1".to_string(),
+                Utc::now(),
+                "Peel test per SOP-112".to_string(),
+                "qe1",
+                &reason,
+            )
+            .unwrap();
+        service
+            .complete_action(&mut capa, &action_id, vec!["CAL-2024-017.pdf".to_string()], "eng1", &reason)
+            .unwrap();
+        service
+            .verify_effectiveness(
+                &mut capa,
+                "Trend review".to_string(),
+                "No seal failures in 30 lots".to_string(),
+                true,
+                "qa1".to_string(),
+                Vec::new(),
+                &reason,
+            )
+            .unwrap();
+        capa
+    }
+
+    #[test]
+    fn test_capa_history_includes_action_events() {
+        let (db, service) = test_service();
+        let capa = worked_capa(&service);
+
+        let history = capa_history(&db, &capa.id).unwrap();
+        let actions: Vec<&str> = history.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(
+            actions,
+            vec![
+                "capa_created",
+                "capa_status_updated",
+                "corrective_action_added",
+                "action_completed",
+                "effectiveness_verified",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_capa_report_sections() {
+        let (db, service) = test_service();
+        let capa = worked_capa(&service);
+        let history = capa_history(&db, &capa.id).unwrap();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("capa.pdf");
+
+        let report = build_capa_report(&CapaReportConfig {
+            output_path: &path,
+            capa: &capa,
+            history: &history,
+            prepared_by: "qa1",
+            organization: Some("Acme Medical"),
+            generated_on: Utc::now(),
+        });
+
+        let titles: Vec<&str> = report.sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec![
+                "Overview",
+                "Description",
+                "Investigation & Root Cause",
+                "Corrective Actions",
+                "Preventive Actions",
+                "Effectiveness Verification",
+                "Status History",
+                "Required Signatures",
+            ]
+        );
+        let payload = String::from_utf8(report.canonical_payload()).unwrap();
+        assert!(payload.contains("CAL-2024-017.pdf"));
+        assert!(payload.contains("Status changed from Identified to Investigation In Progress"));
+    }
+
+    #[test]
+    fn test_generate_capa_report_pdf() {
+        let (db, service) = test_service();
+        let capa = worked_capa(&service);
+        let history = capa_history(&db, &capa.id).unwrap();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("capa.pdf");
+
+        let rendered = generate_capa_report(&CapaReportConfig {
+            output_path: &path,
+            capa: &capa,
+            history: &history,
+            prepared_by: "qa1",
+            organization: None,
+            generated_on: Utc::now(),
+        })
+        .unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(bytes.starts_with(b"%PDF-"));
+        assert_eq!(rendered.section_pages.len(), 8);
+        assert!(rendered.page_count >= 3, "cover, contents and body pages expected");
+    }
+
+    #[test]
+    fn test_capa_aging_report_buckets_open_capas() {
+        let (_db, service) = test_service();
+        let now = Utc::now();
+        let mut old = worked_capa(&service);
+        old.created_at = now - chrono::Duration::days(75);
+        let mut recent = worked_capa(&service);
+        recent.created_at = now - chrono::Duration::days(3);
+        let mut closed = worked_capa(&service);
+        closed.status = CapaStatus::Closed;
+
+        let report = build_capa_aging_report(&[recent, old, closed], now);
+        let crate::report::ReportBlock::Chart(chart) = &report.sections[0].blocks[1] else {
+            panic!("summary should contain the aging chart");
+        };
+        let counts: Vec<f32> = chart.data.iter().map(|d| d.value).collect();
+        assert_eq!(counts, vec![1.0, 0.0, 1.0, 0.0]);
+        let crate::report::ReportBlock::Table(table) = &report.sections[1].blocks[0] else {
+            panic!("open CAPAs should be listed in a table");
+        };
+        assert_eq!(table.rows.len(), 2);
+        assert_eq!(table.rows[0][5], "75", "oldest CAPA is listed first");
+        assert!(table.rows[0][0].starts_with("CAPA-"), "CAPAs are listed by number");
+    }
+
+    #[test]
+    fn test_metadata_text_unwraps_json_strings() {
+        assert_eq!(metadata_text("\"Added action\""), "Added action");
+        assert_eq!(metadata_text("null"), "");
+        assert_eq!(metadata_text("not json"), "not json");
+    }
+}
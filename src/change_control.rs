@@ -0,0 +1,266 @@
+//! # Change Control - Change Requests (21 CFR 820.70(b), ISO 13485 §7.3.9)
+//!
+//! A change request is assessed for impact, approved under signature,
+//! implemented and verified under signature before it is closed. The status
+//! machine is [`change_control_workflow`]; approval, rejection and closure
+//! notify subscribers through the event bus. Only users who may approve
+//! documents can take the signed transitions.
+
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::authorization::{Permission, UserRole};
+use crate::error::{QmsError, Result, ValidationErrors};
+use crate::events::EventBus;
+use crate::unit_of_work::ElectronicSignature;
+use crate::workflow::{Transition, WorkflowDefinition};
+
+/// Change request status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangeStatus {
+    Draft,
+    ImpactAssessment,
+    Approved,
+    Implementation,
+    Verified,
+    Closed,
+    Rejected,
+}
+
+impl ChangeStatus {
+    pub const ALL: [ChangeStatus; 7] = [
+        ChangeStatus::Draft,
+        ChangeStatus::ImpactAssessment,
+        ChangeStatus::Approved,
+        ChangeStatus::Implementation,
+        ChangeStatus::Verified,
+        ChangeStatus::Closed,
+        ChangeStatus::Rejected,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeStatus::Draft => "Draft",
+            ChangeStatus::ImpactAssessment => "Impact Assessment",
+            ChangeStatus::Approved => "Approved",
+            ChangeStatus::Implementation => "Implementation",
+            ChangeStatus::Verified => "Verified",
+            ChangeStatus::Closed => "Closed",
+            ChangeStatus::Rejected => "Rejected",
+        }
+    }
+}
+
+/// A proposed change to a product, process or the quality system
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeRequest {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub status: ChangeStatus,
+    pub requested_by: String,
+    /// Effect on validated state, risk files and regulatory submissions
+    pub impact_assessment: Option<String>,
+    pub verification_summary: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+fn impact_assessed(request: &ChangeRequest) -> std::result::Result<(), String> {
+    match &request.impact_assessment {
+        Some(assessment) if !assessment.trim().is_empty() => Ok(()),
+        _ => Err("the impact assessment is not recorded".to_string()),
+    }
+}
+
+fn verification_recorded(request: &ChangeRequest) -> std::result::Result<(), String> {
+    match &request.verification_summary {
+        Some(summary) if !summary.trim().is_empty() => Ok(()),
+        _ => Err("the verification summary is not recorded".to_string()),
+    }
+}
+
+/// The change control workflow, keyed by [`ChangeStatus::as_str`].
+pub fn change_control_workflow() -> &'static WorkflowDefinition<ChangeRequest> {
+    static WORKFLOW: OnceLock<WorkflowDefinition<ChangeRequest>> = OnceLock::new();
+    WORKFLOW.get_or_init(|| {
+        let step = |from: ChangeStatus, to: ChangeStatus| Transition::new(from.as_str(), to.as_str());
+        WorkflowDefinition::new("change_request", &ChangeStatus::ALL.map(|status| status.as_str()))
+            .with_transition(step(ChangeStatus::Draft, ChangeStatus::ImpactAssessment))
+            .with_transition(
+                step(ChangeStatus::ImpactAssessment, ChangeStatus::Approved)
+                    .with_guard(impact_assessed)
+                    .with_signature("approval")
+                    .with_notification(),
+            )
+            .with_transition(step(ChangeStatus::Draft, ChangeStatus::Rejected).with_notification())
+            .with_transition(step(ChangeStatus::ImpactAssessment, ChangeStatus::Rejected).with_notification())
+            .with_transition(step(ChangeStatus::Approved, ChangeStatus::Implementation))
+            .with_transition(
+                step(ChangeStatus::Implementation, ChangeStatus::Verified)
+                    .with_guard(verification_recorded)
+                    .with_signature("verification"),
+            )
+            .with_transition(step(ChangeStatus::Verified, ChangeStatus::Closed).with_notification())
+    })
+}
+
+/// Change request workflow management service
+#[derive(Clone)]
+pub struct ChangeControlService {
+    audit_manager: AuditManager,
+    events: Option<EventBus>,
+}
+
+impl ChangeControlService {
+    pub fn new(audit_manager: AuditManager) -> Self {
+        Self { audit_manager, events: None }
+    }
+
+    /// Publish notifying transitions on `events`.
+    pub fn with_event_bus(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Raise a change request in `Draft`.
+    pub fn create_request(&self, title: &str, description: &str, requested_by: &str) -> Result<ChangeRequest> {
+        let mut errors = ValidationErrors::new();
+        if title.trim().is_empty() {
+            errors.add("title", "Title is required");
+        }
+        if description.trim().is_empty() {
+            errors.add("description", "Description is required");
+        }
+        if requested_by.trim().is_empty() {
+            errors.add("requested_by", "Requester is required");
+        }
+        errors.into_result()?;
+
+        let now = Utc::now();
+        let request = ChangeRequest {
+            id: Uuid::new_v4().to_string(),
+            title: title.trim().to_string(),
+            description: description.trim().to_string(),
+            status: ChangeStatus::Draft,
+            requested_by: requested_by.to_string(),
+            impact_assessment: None,
+            verification_summary: None,
+            created_at: now,
+            updated_at: now,
+        };
+        self.audit_manager.log_action(
+            requested_by,
+            "change_request_created",
+            &format!("change_request:{}", request.id),
+            "Success",
+            Some(format!("Raised change request: {}", request.title)),
+        )?;
+        Ok(request)
+    }
+
+    /// Move `request` to `new_status` as `user_id`. Transitions requiring a
+    /// signature take the user's `signature` of the request as it stands,
+    /// which is stored with the audit entry in one unit of work.
+    pub fn transition(
+        &self,
+        request: &mut ChangeRequest,
+        new_status: ChangeStatus,
+        user_id: &str,
+        signature: Option<&ElectronicSignature>,
+    ) -> Result<()> {
+        let workflow = change_control_workflow();
+        let from = request.status;
+        let transition =
+            workflow.check(&request.id, request, from.as_str(), new_status.as_str(), user_id, signature)?;
+        let db = self.audit_manager.database();
+        if transition.signature.is_some()
+            && !UserRole::of_user(db, user_id)?.is_some_and(|role| role.has_permission(Permission::ApproveDocuments))
+        {
+            return Err(QmsError::Security {
+                message: format!("User {} may not move change requests to {}", user_id, new_status.as_str()),
+            });
+        }
+
+        let resource = format!("change_request:{}", request.id);
+        let details = format!("Status changed from {} to {}", from.as_str(), new_status.as_str());
+        db.unit_of_work(|uow| {
+            let details = match signature.filter(|_| transition.signature.is_some()) {
+                Some(signature) => {
+                    uow.record_signature(signature)?;
+                    format!("{} (signature {}, meaning: {})", details, signature.id, signature.meaning)
+                }
+                None => details,
+            };
+            let action = "change_request_status_updated";
+            self.audit_manager.log_action_in(uow, user_id, action, &resource, "Success", Some(details))
+        })?;
+
+        request.status = new_status;
+        request.updated_at = Utc::now();
+        if let Some(events) = &self.events {
+            workflow.notify(events, transition, &request.id, from.as_str(), user_id);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::database::Database;
+    use crate::events::QmsEvent;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_change_request_runs_through_the_workflow() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["eng", "qa"]);
+        db.with_connection(|conn| Ok(conn.execute("UPDATE users SET role = 'QualityManager' WHERE id = 'qa'", [])?))
+            .unwrap();
+        let events = EventBus::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        events.subscribe("test", move |event: &QmsEvent| -> Result<()> {
+            sink.lock().unwrap().push(event.name());
+            Ok(())
+        });
+        let service = ChangeControlService::new(AuditManager::new(db.clone())).with_event_bus(events);
+
+        let mut request = service.create_request("Switch seal supplier", "Second source for gaskets", "eng").unwrap();
+        assert!(service.transition(&mut request, ChangeStatus::Approved, "qa", None).is_err());
+        service.transition(&mut request, ChangeStatus::ImpactAssessment, "eng", None).unwrap();
+        let approval = ElectronicSignature::sign("change_request", &request.id, &request, "qa", "approval").unwrap();
+        let refused = service.transition(&mut request, ChangeStatus::Approved, "qa", Some(&approval)).unwrap_err();
+        assert!(refused.to_string().contains("impact assessment"));
+
+        request.impact_assessment = Some("Biocompatibility retest required".to_string());
+        assert!(service.transition(&mut request, ChangeStatus::Approved, "qa", None).is_err());
+        let stale = service.transition(&mut request, ChangeStatus::Approved, "qa", Some(&approval)).unwrap_err();
+        assert!(stale.to_string().contains("another version"), "{}", stale);
+        let by_engineer =
+            ElectronicSignature::sign("change_request", &request.id, &request, "eng", "approval").unwrap();
+        let refused = service.transition(&mut request, ChangeStatus::Approved, "eng", Some(&by_engineer)).unwrap_err();
+        assert!(matches!(refused, QmsError::Security { .. }), "engineers may not approve");
+        let approval = ElectronicSignature::sign("change_request", &request.id, &request, "qa", "approval").unwrap();
+        service.transition(&mut request, ChangeStatus::Approved, "qa", Some(&approval)).unwrap();
+        assert_eq!(request.status, ChangeStatus::Approved);
+        assert_eq!(*seen.lock().unwrap(), vec!["workflow.transitioned"]);
+
+        let audit = db.get_audit_entries_for_resource(&format!("change_request:{}", request.id)).unwrap();
+        assert_eq!(audit.len(), 3);
+        assert!(audit.iter().any(|entry| entry.metadata.as_deref().is_some_and(|m| m.contains("meaning: approval"))));
+    }
+}
@@ -0,0 +1,289 @@
+//! # Change History - Reason-for-Change Capture
+//!
+//! Every update path of a stored record - the repositories as well as the
+//! CAPA service and document approval - takes a [`ChangeReason`] and writes
+//! the field-level before/after values of the update into `change_history` in
+//! the same transaction as the update itself (FDA 21 CFR Part 11 §11.10(e):
+//! record changes must not obscure previously recorded information).
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+
+/// Maximum length of a reason for change.
+pub const MAX_REASON_LENGTH: usize = 1000;
+
+/// Mandatory, validated reason for changing a record.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ChangeReason(String);
+
+impl ChangeReason {
+    /// Validate and wrap a reason; blank reasons are rejected.
+    pub fn new<S: Into<String>>(reason: S) -> Result<Self> {
+        let reason = reason.into().trim().to_string();
+        if reason.is_empty() {
+            return Err(QmsError::Validation {
+                field: "reason".to_string(),
+                message: "A reason for change is required".to_string(),
+            });
+        }
+        if reason.chars().count() > MAX_REASON_LENGTH {
+            return Err(QmsError::Validation {
+                field: "reason".to_string(),
+                message: format!("Reason for change exceeds {} characters", MAX_REASON_LENGTH),
+            });
+        }
+        Ok(Self(reason))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for ChangeReason {
+    type Error = QmsError;
+
+    fn try_from(value: String) -> Result<Self> {
+        Self::new(value)
+    }
+}
+
+impl From<ChangeReason> for String {
+    fn from(reason: ChangeReason) -> Self {
+        reason.0
+    }
+}
+
+impl std::fmt::Display for ChangeReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Before/after value of a single field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+/// One update of a record, with all fields it changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeRecord {
+    pub change_id: Uuid,
+    pub record_type: String,
+    pub record_id: String,
+    pub changed_by: String,
+    pub changed_at: DateTime<Utc>,
+    pub reason: ChangeReason,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Field-level differences between two serialized versions of a record.
+///
+/// Top-level fields are compared; `ignore` lists bookkeeping fields such as
+/// `updated_at` that change on every write.
+pub fn diff_fields<T: Serialize>(before: &T, after: &T, ignore: &[&str]) -> Result<Vec<FieldChange>> {
+    Ok(diff_values(&serde_json::to_value(before)?, &serde_json::to_value(after)?, ignore))
+}
+
+/// Field-level differences between two JSON objects (see [`diff_fields`]).
+pub fn diff_values(before: &serde_json::Value, after: &serde_json::Value, ignore: &[&str]) -> Vec<FieldChange> {
+    let empty = serde_json::Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+
+    let mut fields: Vec<&String> = before.keys().chain(after.keys()).collect();
+    fields.sort();
+    fields.dedup();
+
+    fields
+        .into_iter()
+        .filter(|field| !ignore.contains(&field.as_str()))
+        .filter_map(|field| {
+            let old_value = before.get(field).and_then(value_to_text);
+            let new_value = after.get(field).and_then(value_to_text);
+            (old_value != new_value).then(|| FieldChange { field: field.clone(), old_value, new_value })
+        })
+        .collect()
+}
+
+/// Render a JSON value for storage; strings are stored unquoted, null as NULL.
+fn value_to_text(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Write `changes` for a record on an open connection (normally inside the
+/// update's transaction). Returns `None` when there was nothing to record.
+pub fn record_changes(
+    conn: &Connection,
+    record_type: &str,
+    record_id: &str,
+    changed_by: &str,
+    reason: &ChangeReason,
+    changes: &[FieldChange],
+) -> Result<Option<Uuid>> {
+    if changes.is_empty() {
+        return Ok(None);
+    }
+    let change_id = Uuid::new_v4();
+    let changed_at = Utc::now().to_rfc3339();
+    let mut stmt = conn.prepare(
+        "INSERT INTO change_history (
+            change_id, record_type, record_id, field_name, old_value, new_value,
+            reason, changed_by, changed_at
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+    )?;
+    for change in changes {
+        stmt.execute(params![
+            change_id.to_string(),
+            record_type,
+            record_id,
+            change.field,
+            change.old_value,
+            change.new_value,
+            reason.as_str(),
+            changed_by,
+            changed_at,
+        ])?;
+    }
+    Ok(Some(change_id))
+}
+
+/// Read access to `change_history`.
+pub struct ChangeHistoryRepo<'a> {
+    db: &'a Database,
+}
+
+impl<'a> ChangeHistoryRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// All changes of a record, oldest first.
+    pub fn history(&self, record_type: &str, record_id: &str) -> Result<Vec<ChangeRecord>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT change_id, record_type, record_id, changed_by, changed_at, reason,
+                        field_name, old_value, new_value
+                 FROM change_history
+                 WHERE record_type = ?1 AND record_id = ?2
+                 ORDER BY changed_at, id",
+            )?;
+            let rows = stmt.query_map(params![record_type, record_id], row_to_change)?;
+
+            let mut records: Vec<ChangeRecord> = Vec::new();
+            for row in rows {
+                let (record, field) = row?;
+                match records.last_mut() {
+                    Some(last) if last.change_id == record.change_id => last.changes.push(field),
+                    _ => records.push(ChangeRecord { changes: vec![field], ..record }),
+                }
+            }
+            Ok(records)
+        })
+    }
+}
+
+fn row_to_change(row: &rusqlite::Row) -> rusqlite::Result<(ChangeRecord, FieldChange)> {
+    let change_id: String = row.get(0)?;
+    let changed_at: String = row.get(4)?;
+    let reason: String = row.get(5)?;
+    let record = ChangeRecord {
+        change_id: Uuid::parse_str(&change_id).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+        record_type: row.get(1)?,
+        record_id: row.get(2)?,
+        changed_by: row.get(3)?,
+        changed_at: DateTime::parse_from_rfc3339(&changed_at)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(4, rusqlite::types::Type::Text, Box::new(e)))?
+            .with_timezone(&Utc),
+        reason: ChangeReason::new(reason).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(5, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+        changes: Vec::new(),
+    };
+    let field = FieldChange {
+        field: row.get(6)?,
+        old_value: row.get(7)?,
+        new_value: row.get(8)?,
+    };
+    Ok((record, field))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+
+    #[derive(Serialize)]
+    struct Sample {
+        name: String,
+        contact: Option<String>,
+        count: u32,
+        updated_at: String,
+    }
+
+    #[test]
+    fn test_change_reason_is_required() {
+        assert!(matches!(ChangeReason::new("   "), Err(QmsError::Validation { .. })));
+        assert!(ChangeReason::new("x".repeat(MAX_REASON_LENGTH + 1)).is_err());
+        assert_eq!(ChangeReason::new("  Supplier audit finding ").unwrap().as_str(), "Supplier audit finding");
+        assert!(serde_json::from_str::<ChangeReason>("\"\"").is_err());
+    }
+
+    #[test]
+    fn test_diff_fields_reports_changed_fields_only() {
+        let before = Sample { name: "Acme".into(), contact: None, count: 1, updated_at: "a".into() };
+        let after = Sample { name: "Acme".into(), contact: Some("qa@acme.com".into()), count: 2, updated_at: "b".into() };
+        let changes = diff_fields(&before, &after, &["updated_at"]).unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                FieldChange { field: "contact".into(), old_value: None, new_value: Some("qa@acme.com".into()) },
+                FieldChange { field: "count".into(), old_value: Some("1".into()), new_value: Some("2".into()) },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_history_groups_fields_by_change() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let reason = ChangeReason::new("Annual requalification").unwrap();
+        let changes = vec![
+            FieldChange { field: "status".into(), old_value: Some("Pending".into()), new_value: Some("Qualified".into()) },
+            FieldChange { field: "approved_by".into(), old_value: None, new_value: Some("qa".into()) },
+        ];
+        db.with_connection(|conn| {
+            assert!(record_changes(conn, "supplier", "s1", "qa", &reason, &[])?.is_none());
+            record_changes(conn, "supplier", "s1", "qa", &reason, &changes)?;
+            Ok(())
+        })
+        .unwrap();
+
+        let history = ChangeHistoryRepo::new(&db).history("supplier", "s1").unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].reason, reason);
+        assert_eq!(history[0].changes, changes);
+        assert!(ChangeHistoryRepo::new(&db).history("supplier", "other").unwrap().is_empty());
+    }
+}
@@ -1,5 +1,7 @@
-use clap::Parser;
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
+use uuid::Uuid;
 
 /// FDA Compliant Medical Device Quality Management System
 #[derive(Parser)]
@@ -37,6 +39,754 @@ pub struct Cli {
     /// Generate sample configuration file and exit
     #[arg(long)]
     pub generate_config: bool,
+
+    /// Render the TUI without emoji and colors, for screen readers and
+    /// limited terminals (same as `tui.accessible = true`)
+    #[arg(long)]
+    pub accessible: bool,
+
+    /// Result format of maintenance commands: readable text or one JSON document
+    #[arg(long = "output", value_enum, default_value_t = OutputFormat::Text, global = true)]
+    pub output_format: OutputFormat,
+
+    /// Maintenance command to run instead of the interactive application
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// How maintenance commands print their result
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Non-interactive maintenance commands
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Verify the checksum and digital signature of a generated PDF report
+    VerifyReport {
+        /// Path to the PDF report
+        file: PathBuf,
+    },
+    /// Export the full audit trail as CSV, streaming it to disk
+    ExportAudit {
+        /// Destination CSV file
+        output: PathBuf,
+        /// Only export entries by this user
+        #[arg(long)]
+        user: Option<String>,
+        /// Display zone for the local timestamp column (e.g. `+02:00`)
+        #[arg(long)]
+        timezone: Option<String>,
+    },
+    /// Export adverse events and complaints as CSV for vigilance partners
+    ExportVigilance {
+        /// Destination CSV file
+        output: PathBuf,
+        /// Replace the configured identifying fields with pseudonym tokens
+        #[arg(long)]
+        pseudonymize: bool,
+    },
+    /// Export an EU MDR manufacturer incident report (MIR) for an adverse event
+    ExportMir {
+        /// Adverse event to report
+        adverse_event: Uuid,
+        /// JSON file with the incident classification and device details
+        details: PathBuf,
+        /// Destination XML file
+        output: PathBuf,
+        /// Write an Excel workbook with localized labels instead of the form XML
+        #[arg(long)]
+        spreadsheet: bool,
+    },
+    /// Generate the clause-to-document compliance matrix PDF for a standard
+    ComplianceMatrix {
+        /// Standard citation, e.g. `ISO 13485:2016`
+        standard: String,
+        /// Destination PDF file
+        output: PathBuf,
+    },
+    /// Assemble a signed inspection-ready ZIP package for one quality subsystem
+    InspectionPackage {
+        /// Subsystem: capa, documents, training, suppliers or post_market
+        subsystem: String,
+        /// Directory to write the package into
+        output: PathBuf,
+        /// Length of the period ending today, in months
+        #[arg(long, default_value_t = 12)]
+        months: u32,
+    },
+    /// Generate a signed activity report for one user (access reviews, account closeout)
+    UserActivity {
+        /// User ID
+        user: String,
+        /// Destination PDF file
+        output: PathBuf,
+        /// First day of the period (YYYY-MM-DD); defaults to 90 days before `--to`
+        #[arg(long)]
+        from: Option<NaiveDate>,
+        /// Last day of the period (YYYY-MM-DD); defaults to today
+        #[arg(long)]
+        to: Option<NaiveDate>,
+    },
+    /// Generate a signed validation evidence pack for this installation
+    EvidencePack {
+        /// Directory to write the pack into
+        output: PathBuf,
+    },
+    /// Database maintenance and diagnostics
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+    /// Write-once audit trail segments
+    AuditWorm {
+        #[command(subcommand)]
+        command: AuditWormCommand,
+    },
+    /// Read-only data snapshots for BI tools
+    Analytics {
+        #[command(subcommand)]
+        command: AnalyticsCommand,
+    },
+    /// Periodic sampled audit trail review
+    AuditReview {
+        #[command(subcommand)]
+        command: AuditReviewCommand,
+    },
+    /// Quarterly user access review campaigns
+    AccessReview {
+        #[command(subcommand)]
+        command: AccessReviewCommand,
+    },
+    /// Mirror of CAPA actions in the external issue tracker
+    IssueSync {
+        #[command(subcommand)]
+        command: IssueSyncCommand,
+    },
+    /// Statistical process control charts for trend review
+    Spc {
+        #[command(subcommand)]
+        command: SpcCommand,
+    },
+    /// Units shipped per month and the complaint rates normalised by them
+    Shipments {
+        #[command(subcommand)]
+        command: ShipmentsCommand,
+    },
+    /// Certificates and agreements provided by suppliers
+    SupplierDocs {
+        #[command(subcommand)]
+        command: SupplierDocsCommand,
+    },
+    /// Cost of quality records and quarterly totals
+    Cost {
+        #[command(subcommand)]
+        command: CostCommand,
+    },
+    /// Ad hoc quality tasks and each user's open work
+    Tasks {
+        #[command(subcommand)]
+        command: TasksCommand,
+    },
+    /// Populate an empty database with sample records flagged as demo data
+    Seed {
+        /// Seed the demo data set (the only data set available)
+        #[arg(long, required = true)]
+        demo: bool,
+    },
+    /// Modules enabled for this deployment
+    Features {
+        #[command(subcommand)]
+        command: FeaturesCommand,
+    },
+    /// Customer-specific validation rules, versioned as controlled documents
+    Scripts {
+        #[command(subcommand)]
+        command: ScriptsCommand,
+    },
+    /// Hand-over of open work when a user leaves
+    Users {
+        #[command(subcommand)]
+        command: UsersCommand,
+    },
+    /// Device history records reviewed by exception before lot release
+    BatchRecords {
+        #[command(subcommand)]
+        command: BatchRecordsCommand,
+    },
+    /// Out-of-office delegation of approval authority
+    Delegations {
+        #[command(subcommand)]
+        command: DelegationsCommand,
+    },
+    /// Encrypted sections of the configuration file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+impl Command {
+    /// Command as typed on the command line, reported in JSON output
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::VerifyReport { .. } => "verify-report",
+            Command::ExportAudit { .. } => "export-audit",
+            Command::ExportVigilance { .. } => "export-vigilance",
+            Command::ExportMir { .. } => "export-mir",
+            Command::ComplianceMatrix { .. } => "compliance-matrix",
+            Command::InspectionPackage { .. } => "inspection-package",
+            Command::UserActivity { .. } => "user-activity",
+            Command::EvidencePack { .. } => "evidence-pack",
+            Command::Db { command: DbCommand::Stats } => "db stats",
+            Command::AuditWorm { command: AuditWormCommand::Sync } => "audit-worm sync",
+            Command::AuditWorm { command: AuditWormCommand::Verify } => "audit-worm verify",
+            Command::Analytics { command: AnalyticsCommand::Export { .. } } => "analytics export",
+            Command::AuditReview { command } => match command {
+                AuditReviewCommand::Start { .. } => "audit-review start",
+                AuditReviewCommand::Show { .. } => "audit-review show",
+                AuditReviewCommand::Dispose { .. } => "audit-review dispose",
+                AuditReviewCommand::Complete { .. } => "audit-review complete",
+            },
+            Command::AccessReview { command } => match command {
+                AccessReviewCommand::Open => "access-review open",
+                AccessReviewCommand::Show { .. } => "access-review show",
+                AccessReviewCommand::Decide { .. } => "access-review decide",
+                AccessReviewCommand::Close { .. } => "access-review close",
+            },
+            Command::IssueSync { command } => match command {
+                IssueSyncCommand::Run => "issue-sync run",
+                IssueSyncCommand::Conflicts => "issue-sync conflicts",
+                IssueSyncCommand::Resolve { .. } => "issue-sync resolve",
+            },
+            Command::Spc { command } => match command {
+                SpcCommand::XbarR { .. } => "spc xbar-r",
+                SpcCommand::Inspection { .. } => "spc inspection",
+                SpcCommand::Complaints { .. } => "spc complaints",
+            },
+            Command::Shipments { command } => match command {
+                ShipmentsCommand::Record { .. } => "shipments record",
+                ShipmentsCommand::Rates { .. } => "shipments rates",
+            },
+            Command::SupplierDocs { command } => match command {
+                SupplierDocsCommand::Upload { .. } => "supplier-docs upload",
+                SupplierDocsCommand::List { .. } => "supplier-docs list",
+                SupplierDocsCommand::History { .. } => "supplier-docs history",
+            },
+            Command::Cost { command } => match command {
+                CostCommand::Record { .. } => "cost record",
+                CostCommand::Report { .. } => "cost report",
+            },
+            Command::Tasks { command } => match command {
+                TasksCommand::Create { .. } => "tasks create",
+                TasksCommand::Complete { .. } => "tasks complete",
+                TasksCommand::Inbox { .. } => "tasks inbox",
+            },
+            Command::Seed { .. } => "seed",
+            Command::Features { command } => match command {
+                FeaturesCommand::List => "features list",
+                FeaturesCommand::Enable { .. } => "features enable",
+                FeaturesCommand::Disable { .. } => "features disable",
+                FeaturesCommand::Reset { .. } => "features reset",
+            },
+            Command::Scripts { command } => match command {
+                ScriptsCommand::List => "scripts list",
+                ScriptsCommand::Submit { .. } => "scripts submit",
+                ScriptsCommand::MakeEffective { .. } => "scripts make-effective",
+            },
+            Command::Users { command } => match command {
+                UsersCommand::OpenItems { .. } => "users open-items",
+                UsersCommand::Deactivate { .. } => "users deactivate",
+                UsersCommand::Transfer { .. } => "users transfer",
+            },
+            Command::BatchRecords { command } => match command {
+                BatchRecordsCommand::Review { .. } => "batch-records review",
+                BatchRecordsCommand::Entries { .. } => "batch-records entries",
+                BatchRecordsCommand::Disposition { .. } => "batch-records disposition",
+                BatchRecordsCommand::Release { .. } => "batch-records release",
+                BatchRecordsCommand::Reject { .. } => "batch-records reject",
+            },
+            Command::Delegations { command } => match command {
+                DelegationsCommand::List => "delegations list",
+                DelegationsCommand::Request { .. } => "delegations request",
+                DelegationsCommand::Approve { .. } => "delegations approve",
+                DelegationsCommand::Reject { .. } => "delegations reject",
+                DelegationsCommand::Revoke { .. } => "delegations revoke",
+            },
+            Command::Config { command } => match command {
+                ConfigCommand::GenerateKey => "config generate-key",
+                ConfigCommand::EncryptSection { .. } => "config encrypt-section",
+                ConfigCommand::DecryptSection { .. } => "config decrypt-section",
+            },
+        }
+    }
+}
+
+/// `qmsrs db` subcommands
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum DbCommand {
+    /// Show connection pool statistics and slow operations
+    Stats,
+}
+
+/// `qmsrs audit-worm` subcommands
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum AuditWormCommand {
+    /// Seal audit entries added since the last sealed segment
+    Sync,
+    /// Check the seal chain and compare sealed entries with the database
+    Verify,
+}
+
+/// `qmsrs audit-review` subcommands
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum AuditReviewCommand {
+    /// Start the review of a period and draw its sample
+    Start {
+        /// First day of the period (YYYY-MM-DD)
+        from: NaiveDate,
+        /// Last day of the period (YYYY-MM-DD)
+        to: NaiveDate,
+        /// User performing the review; their own entries are not sampled
+        #[arg(long)]
+        reviewer: String,
+        /// Sampling strategy (random, failures_first, per_user); defaults to the configured one
+        #[arg(long)]
+        strategy: Option<String>,
+        /// Entries to sample; defaults to the configured sample size
+        #[arg(long)]
+        sample_size: Option<usize>,
+    },
+    /// List the sampled entries of a review with their dispositions
+    Show {
+        review: Uuid,
+    },
+    /// Record the disposition of a sampled entry
+    Dispose {
+        review: Uuid,
+        /// Audit entry ID
+        entry: String,
+        /// acceptable or investigate
+        disposition: String,
+        #[arg(long)]
+        reviewer: String,
+        /// Required when the entry is marked for investigation
+        #[arg(long)]
+        comment: Option<String>,
+    },
+    /// Complete a review once every sampled entry is dispositioned
+    Complete {
+        review: Uuid,
+        /// Review conclusion recorded as compliance evidence
+        conclusion: String,
+        #[arg(long)]
+        reviewer: String,
+    },
+}
+
+/// `qmsrs access-review` subcommands
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum AccessReviewCommand {
+    /// Open the current quarter's campaign now instead of waiting for the scheduler
+    Open,
+    /// List a campaign's access items with their decisions; the latest campaign by default
+    Show {
+        campaign: Option<Uuid>,
+    },
+    /// Attest or revoke one access item; revocations take effect immediately
+    Decide {
+        /// Access item ID
+        item: Uuid,
+        /// attest or revoke
+        decision: String,
+        #[arg(long)]
+        reviewer: String,
+        /// Required when revoking
+        #[arg(long)]
+        comment: Option<String>,
+    },
+    /// Close a campaign once every item is decided
+    Close {
+        campaign: Uuid,
+        #[arg(long)]
+        reviewer: String,
+    },
+}
+
+/// `qmsrs issue-sync` subcommands
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum IssueSyncCommand {
+    /// Sync now instead of waiting for the scheduler
+    Run,
+    /// List CAPA actions whose status changed in both the QMS and the tracker
+    Conflicts,
+    /// Resolve a conflict by copying one side's status to the other
+    Resolve {
+        /// CAPA action ID
+        action: String,
+        /// Side whose status is kept: qms or tracker
+        keep: String,
+        #[arg(long)]
+        user: String,
+    },
+}
+
+/// `qmsrs spc` subcommands; violations are stored as alerts and emailed to
+/// the quality managers
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum SpcCommand {
+    /// X-bar/R charts of a lab test's measurements
+    XbarR {
+        /// Test name as sent by the lab system
+        test: String,
+        /// Consecutive measurements per subgroup (2 to 10)
+        #[arg(long, default_value_t = 5)]
+        subgroup_size: usize,
+        /// Days of results to chart
+        #[arg(long, default_value_t = 90)]
+        days: u32,
+        /// Directory to write the charts into as CSV and SVG
+        #[arg(long)]
+        export: Option<PathBuf>,
+    },
+    /// p-chart of a lab test's failed results per ingested batch
+    Inspection {
+        test: String,
+        #[arg(long, default_value_t = 90)]
+        days: u32,
+        #[arg(long)]
+        export: Option<PathBuf>,
+    },
+    /// p-chart of the monthly complaint rate
+    Complaints {
+        /// Units distributed in a month, as YYYY-MM=UNITS; repeat per month.
+        /// Defaults to the units recorded with `shipments record`
+        #[arg(long = "units")]
+        units: Vec<String>,
+        #[arg(long)]
+        export: Option<PathBuf>,
+    },
+}
+
+/// `qmsrs shipments` subcommands
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum ShipmentsCommand {
+    /// Record the units shipped in a month, replacing an earlier figure
+    Record {
+        /// Calendar month as YYYY-MM
+        month: String,
+        units: u64,
+        #[arg(long)]
+        user: String,
+    },
+    /// Complaints and adverse events per 10,000 units, per month
+    Rates {
+        /// Months up to and including the current one
+        #[arg(long, default_value_t = 12)]
+        months: u32,
+    },
+}
+
+/// `qmsrs supplier-docs` subcommands
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum SupplierDocsCommand {
+    /// Store a file as the next version of a supplier document
+    Upload {
+        supplier: Uuid,
+        /// iso_certificate, quality_agreement, data_processing_agreement or other
+        kind: String,
+        file: PathBuf,
+        /// Uploads with the same kind and title are versions of one document
+        #[arg(long)]
+        title: String,
+        /// Last day the document is valid (YYYY-MM-DD)
+        #[arg(long)]
+        expires_on: Option<NaiveDate>,
+        #[arg(long)]
+        user: String,
+    },
+    /// Current version of each of a supplier's documents
+    List { supplier: Uuid },
+    /// Every version of one supplier document
+    History {
+        supplier: Uuid,
+        kind: String,
+        #[arg(long)]
+        title: String,
+    },
+}
+
+/// `qmsrs cost` subcommands
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum CostCommand {
+    /// Record a cost against a CAPA, NCR, recall or scrap disposition
+    Record {
+        /// capa, nonconformance, recall or scrap_disposition
+        source: String,
+        /// CAPA or NCR id, or the recall / scrap disposition reference
+        record: String,
+        /// Amount in the reporting currency, e.g. 1250.50
+        amount: String,
+        /// prevention, appraisal, internal_failure or external_failure; defaults by source
+        #[arg(long)]
+        category: Option<String>,
+        /// Day the cost was incurred (YYYY-MM-DD); today by default
+        #[arg(long)]
+        incurred_on: Option<NaiveDate>,
+        #[arg(long, default_value = "")]
+        description: String,
+        #[arg(long)]
+        user: String,
+    },
+    /// Prevention, appraisal and failure costs per quarter
+    Report {
+        /// Quarters up to and including the current one
+        #[arg(long, default_value_t = 4)]
+        quarters: u32,
+    },
+}
+
+/// `qmsrs tasks` subcommands
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum TasksCommand {
+    /// Assign a task to a user
+    Create {
+        title: String,
+        /// investigation, review, verification, follow_up or other
+        kind: String,
+        /// Due date (YYYY-MM-DD)
+        #[arg(long)]
+        due: NaiveDate,
+        #[arg(long)]
+        assignee: String,
+        /// Record the task concerns, as type:id
+        #[arg(long)]
+        link: Option<String>,
+        #[arg(long)]
+        user: String,
+    },
+    /// Mark a task as done
+    Complete {
+        task: Uuid,
+        #[arg(long)]
+        user: String,
+    },
+    /// Open tasks, CAPAs, audit findings and training of a user
+    Inbox { user: String },
+}
+
+/// `qmsrs features` subcommands; changes need the `manage_features` permission
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum FeaturesCommand {
+    /// Every module, whether it is enabled and where that comes from
+    List,
+    /// Enable a module regardless of the configuration
+    Enable {
+        /// documents, capa, suppliers, training, reports, search, jobs, risk,
+        /// post_market, notifications or api
+        feature: String,
+        #[arg(long)]
+        user: String,
+        #[arg(long)]
+        reason: String,
+    },
+    /// Disable a module regardless of the configuration
+    Disable {
+        feature: String,
+        #[arg(long)]
+        user: String,
+        #[arg(long)]
+        reason: String,
+    },
+    /// Remove the override, returning the module to its configured state
+    Reset {
+        feature: String,
+        #[arg(long)]
+        user: String,
+        #[arg(long)]
+        reason: String,
+    },
+}
+
+/// `qmsrs scripts` subcommands; a submitted version is approved like any
+/// other document before it can be made effective
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum ScriptsCommand {
+    /// Every version of every script and when it took effect
+    List,
+    /// Submit a new script, or a new version of one, for review
+    Submit {
+        /// Document number, e.g. VS-001
+        document_number: String,
+        #[arg(long)]
+        title: String,
+        /// Where the script runs: capa or custom_record
+        #[arg(long)]
+        hook: String,
+        /// Rhai source file
+        #[arg(long)]
+        file: PathBuf,
+        /// What changed and why
+        #[arg(long)]
+        description: String,
+        #[arg(long)]
+        user: String,
+    },
+    /// Put the approved version of a script in force
+    MakeEffective {
+        document_id: String,
+        #[arg(long)]
+        user: String,
+    },
+}
+
+/// `qmsrs users` subcommands; deactivation and transfers need the
+/// `manage_users` permission
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum UsersCommand {
+    /// CAPAs, actions, documents, tasks, findings and plugin records a user owns
+    OpenItems { user_id: String },
+    /// Reassign a user's open items and deactivate the account
+    Deactivate {
+        user_id: String,
+        /// New owner of every item not given one with --assign
+        #[arg(long)]
+        to: Option<String>,
+        /// New owner of one item, e.g. capa:c-17=alice; repeatable
+        #[arg(long, value_name = "RECORD=OWNER")]
+        assign: Vec<String>,
+        #[arg(long)]
+        user: String,
+        #[arg(long)]
+        reason: String,
+    },
+    /// Reassign the open items of an account deactivated elsewhere
+    Transfer {
+        user_id: String,
+        #[arg(long)]
+        to: Option<String>,
+        #[arg(long, value_name = "RECORD=OWNER")]
+        assign: Vec<String>,
+        #[arg(long)]
+        user: String,
+        #[arg(long)]
+        reason: String,
+    },
+}
+
+/// `qmsrs batch-records` subcommands; lots are named by lot number
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum BatchRecordsCommand {
+    /// Check every entry of a lot and list only the exceptions
+    Review {
+        lot: String,
+    },
+    /// The full record of a lot, entry by entry
+    Entries {
+        lot: String,
+    },
+    /// Disposition an exception listed by the review
+    Disposition {
+        lot: String,
+        /// Entry ID from the review
+        entry: Uuid,
+        /// out_of_limits, corrected or deviation
+        kind: String,
+        #[arg(long)]
+        comment: String,
+        #[arg(long)]
+        user: String,
+    },
+    /// Release a lot whose exceptions are all dispositioned
+    Release {
+        lot: String,
+        #[arg(long)]
+        comment: Option<String>,
+        #[arg(long)]
+        user: String,
+    },
+    /// Reject a lot
+    Reject {
+        lot: String,
+        #[arg(long)]
+        reason: String,
+        #[arg(long)]
+        user: String,
+    },
+}
+
+/// `qmsrs delegations` subcommands; in CFR Part 11 mode a requested
+/// delegation is in force only once approved
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum DelegationsCommand {
+    /// Every delegation, latest first
+    List,
+    /// Delegate a user's approval authority for a date range
+    Request {
+        delegator: String,
+        /// The user approving in the delegator's place
+        #[arg(long)]
+        to: String,
+        /// First day, YYYY-MM-DD
+        #[arg(long)]
+        from: NaiveDate,
+        /// Last day, inclusive
+        #[arg(long)]
+        until: NaiveDate,
+        #[arg(long)]
+        reason: String,
+        #[arg(long)]
+        user: String,
+    },
+    /// Approve a pending delegation; not by either party
+    Approve {
+        id: Uuid,
+        #[arg(long)]
+        user: String,
+    },
+    /// Reject a pending delegation
+    Reject {
+        id: Uuid,
+        #[arg(long)]
+        user: String,
+    },
+    /// Withdraw a pending or active delegation
+    Revoke {
+        id: Uuid,
+        #[arg(long)]
+        user: String,
+    },
+}
+
+/// `qmsrs config` subcommands; the master key is read from `QMS_CONFIG_KEY`
+/// or `QMS_CONFIG_KEY_FILE`
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum ConfigCommand {
+    /// Print a new random master key (base64)
+    GenerateKey,
+    /// Encrypt a section of the configuration file in place; comments are dropped
+    EncryptSection {
+        /// Dotted section path, e.g. `oidc` or `api.tls`
+        section: String,
+    },
+    /// Decrypt a section of the configuration file in place for editing
+    DecryptSection {
+        /// Dotted section path, e.g. `oidc` or `api.tls`
+        section: String,
+    },
+}
+
+/// `qmsrs analytics` subcommands
+#[derive(Subcommand, Debug, Clone, PartialEq, Eq)]
+pub enum AnalyticsCommand {
+    /// Write a snapshot of the CAPA, complaint and training fact tables
+    Export {
+        /// Directory to create the snapshot directory in
+        output: PathBuf,
+    },
 }
 
 impl Cli {
@@ -88,6 +838,419 @@ mod tests {
         assert!(!cli.init_db);
         assert!(!cli.headless);
         assert!(!cli.generate_config);
+        assert_eq!(cli.command, None);
+        assert_eq!(cli.output_format, OutputFormat::Text);
+    }
+
+    #[test]
+    fn test_cli_verify_report_command() {
+        let cli = Cli::parse_from(&["qmsrs", "verify-report", "reports/summary.pdf"]);
+        assert_eq!(
+            cli.command,
+            Some(Command::VerifyReport { file: PathBuf::from("reports/summary.pdf") })
+        );
+    }
+
+    #[test]
+    fn test_cli_evidence_pack_command() {
+        let cli = Cli::parse_from(["qmsrs", "evidence-pack", "validation/2026"]);
+        assert_eq!(cli.command, Some(Command::EvidencePack { output: PathBuf::from("validation/2026") }));
+    }
+
+    #[test]
+    fn test_cli_export_vigilance_command() {
+        let cli = Cli::parse_from(["qmsrs", "export-vigilance", "partner.csv", "--pseudonymize"]);
+        assert_eq!(
+            cli.command,
+            Some(Command::ExportVigilance { output: PathBuf::from("partner.csv"), pseudonymize: true })
+        );
+    }
+
+    #[test]
+    fn test_cli_export_mir_command() {
+        let id = Uuid::new_v4();
+        let cli = Cli::parse_from(["qmsrs", "export-mir", &id.to_string(), "mir.json", "mir.xml", "--spreadsheet"]);
+        assert_eq!(
+            cli.command,
+            Some(Command::ExportMir {
+                adverse_event: id,
+                details: PathBuf::from("mir.json"),
+                output: PathBuf::from("mir.xml"),
+                spreadsheet: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_compliance_matrix_command() {
+        let cli = Cli::parse_from(["qmsrs", "compliance-matrix", "ISO 13485:2016", "matrix.pdf"]);
+        assert_eq!(
+            cli.command,
+            Some(Command::ComplianceMatrix {
+                standard: "ISO 13485:2016".to_string(),
+                output: PathBuf::from("matrix.pdf"),
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_inspection_package_command() {
+        let cli = Cli::parse_from(["qmsrs", "inspection-package", "capa", "inspection"]);
+        assert_eq!(
+            cli.command,
+            Some(Command::InspectionPackage {
+                subsystem: "capa".to_string(),
+                output: PathBuf::from("inspection"),
+                months: 12,
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_issue_sync_command() {
+        let cli = Cli::parse_from(["qmsrs", "issue-sync", "resolve", "a1", "tracker", "--user", "qa"]);
+        assert_eq!(
+            cli.command,
+            Some(Command::IssueSync {
+                command: IssueSyncCommand::Resolve {
+                    action: "a1".to_string(),
+                    keep: "tracker".to_string(),
+                    user: "qa".to_string(),
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_spc_command() {
+        let cli = Cli::parse_from(["qmsrs", "spc", "complaints", "--units", "2026-08=1200", "--units", "2026-09=1350"]);
+        assert_eq!(
+            cli.command,
+            Some(Command::Spc {
+                command: SpcCommand::Complaints {
+                    units: vec!["2026-08=1200".to_string(), "2026-09=1350".to_string()],
+                    export: None,
+                },
+            })
+        );
+        let cli = Cli::parse_from(["qmsrs", "spc", "complaints"]);
+        assert_eq!(
+            cli.command,
+            Some(Command::Spc { command: SpcCommand::Complaints { units: Vec::new(), export: None } })
+        );
+    }
+
+    #[test]
+    fn test_cli_shipments_command() {
+        let cli = Cli::parse_from(["qmsrs", "shipments", "record", "2026-09", "1350", "--user", "sales_ops"]);
+        assert_eq!(
+            cli.command,
+            Some(Command::Shipments {
+                command: ShipmentsCommand::Record {
+                    month: "2026-09".to_string(),
+                    units: 1350,
+                    user: "sales_ops".to_string(),
+                },
+            })
+        );
+        let cli = Cli::parse_from(["qmsrs", "shipments", "rates", "--months", "6"]);
+        assert_eq!(cli.command, Some(Command::Shipments { command: ShipmentsCommand::Rates { months: 6 } }));
+        assert_eq!(cli.command.unwrap().name(), "shipments rates");
+    }
+
+    #[test]
+    fn test_cli_supplier_docs_command() {
+        let supplier = Uuid::new_v4();
+        let cli = Cli::parse_from([
+            "qmsrs",
+            "supplier-docs",
+            "upload",
+            &supplier.to_string(),
+            "iso_certificate",
+            "cert.pdf",
+            "--title",
+            "ISO 13485",
+            "--expires-on",
+            "2027-03-31",
+            "--user",
+            "buyer",
+        ]);
+        assert_eq!(
+            cli.command,
+            Some(Command::SupplierDocs {
+                command: SupplierDocsCommand::Upload {
+                    supplier,
+                    kind: "iso_certificate".to_string(),
+                    file: PathBuf::from("cert.pdf"),
+                    title: "ISO 13485".to_string(),
+                    expires_on: NaiveDate::from_ymd_opt(2027, 3, 31),
+                    user: "buyer".to_string(),
+                },
+            })
+        );
+        let cli = Cli::parse_from(["qmsrs", "supplier-docs", "list", &supplier.to_string()]);
+        assert_eq!(cli.command.unwrap().name(), "supplier-docs list");
+    }
+
+    #[test]
+    fn test_cli_cost_command() {
+        let cli = Cli::parse_from([
+            "qmsrs",
+            "cost",
+            "record",
+            "recall",
+            "RCL-2026-01",
+            "5000.00",
+            "--category",
+            "external_failure",
+            "--user",
+            "ra",
+        ]);
+        assert_eq!(
+            cli.command,
+            Some(Command::Cost {
+                command: CostCommand::Record {
+                    source: "recall".to_string(),
+                    record: "RCL-2026-01".to_string(),
+                    amount: "5000.00".to_string(),
+                    category: Some("external_failure".to_string()),
+                    incurred_on: None,
+                    description: String::new(),
+                    user: "ra".to_string(),
+                },
+            })
+        );
+        let cli = Cli::parse_from(["qmsrs", "cost", "report"]);
+        assert_eq!(cli.command, Some(Command::Cost { command: CostCommand::Report { quarters: 4 } }));
+        assert_eq!(cli.command.unwrap().name(), "cost report");
+    }
+
+    #[test]
+    fn test_cli_tasks_command() {
+        let cli = Cli::parse_from([
+            "qmsrs",
+            "tasks",
+            "create",
+            "Review 8D response",
+            "review",
+            "--due",
+            "2026-11-02",
+            "--assignee",
+            "qe1",
+            "--link",
+            "supplier:acme",
+            "--user",
+            "qm",
+        ]);
+        assert_eq!(
+            cli.command,
+            Some(Command::Tasks {
+                command: TasksCommand::Create {
+                    title: "Review 8D response".to_string(),
+                    kind: "review".to_string(),
+                    due: NaiveDate::from_ymd_opt(2026, 11, 2).unwrap(),
+                    assignee: "qe1".to_string(),
+                    link: Some("supplier:acme".to_string()),
+                    user: "qm".to_string(),
+                },
+            })
+        );
+        let cli = Cli::parse_from(["qmsrs", "tasks", "inbox", "qe1"]);
+        assert_eq!(cli.command, Some(Command::Tasks { command: TasksCommand::Inbox { user: "qe1".to_string() } }));
+        assert_eq!(cli.command.unwrap().name(), "tasks inbox");
+    }
+
+    #[test]
+    fn test_cli_seed_command() {
+        let cli = Cli::parse_from(["qmsrs", "seed", "--demo"]);
+        assert_eq!(cli.command, Some(Command::Seed { demo: true }));
+        assert!(Cli::try_parse_from(["qmsrs", "seed"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_user_activity_command() {
+        let cli = Cli::parse_from(["qmsrs", "user-activity", "jdoe", "jdoe.pdf", "--from", "2026-07-01"]);
+        assert_eq!(
+            cli.command,
+            Some(Command::UserActivity {
+                user: "jdoe".to_string(),
+                output: PathBuf::from("jdoe.pdf"),
+                from: NaiveDate::from_ymd_opt(2026, 7, 1),
+                to: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_db_stats_command() {
+        let cli = Cli::parse_from(["qmsrs", "db", "stats"]);
+        assert_eq!(cli.command, Some(Command::Db { command: DbCommand::Stats }));
+    }
+
+    #[test]
+    fn test_cli_json_output_for_any_command() {
+        let cli = Cli::parse_from(["qmsrs", "export-audit", "audit.csv", "--output", "json"]);
+        assert_eq!(cli.output_format, OutputFormat::Json);
+        let command = cli.command.unwrap();
+        assert_eq!(command.name(), "export-audit");
+        assert!(matches!(command, Command::ExportAudit { output, .. } if output == PathBuf::from("audit.csv")));
+        let cli = Cli::parse_from(["qmsrs", "--output", "json", "audit-worm", "sync"]);
+        assert_eq!((cli.output_format, cli.command.unwrap().name()), (OutputFormat::Json, "audit-worm sync"));
+    }
+
+    #[test]
+    fn test_cli_audit_worm_command() {
+        let cli = Cli::parse_from(["qmsrs", "audit-worm", "verify"]);
+        assert_eq!(cli.command, Some(Command::AuditWorm { command: AuditWormCommand::Verify }));
+    }
+
+    #[test]
+    fn test_cli_audit_review_start_command() {
+        let cli = Cli::parse_from(["qmsrs", "audit-review", "start", "2026-09-01", "2026-09-30", "--reviewer", "qa"]);
+        assert_eq!(
+            cli.command,
+            Some(Command::AuditReview {
+                command: AuditReviewCommand::Start {
+                    from: NaiveDate::from_ymd_opt(2026, 9, 1).unwrap(),
+                    to: NaiveDate::from_ymd_opt(2026, 9, 30).unwrap(),
+                    reviewer: "qa".to_string(),
+                    strategy: None,
+                    sample_size: None,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_access_review_decide_command() {
+        let item = Uuid::nil();
+        let cli = Cli::parse_from([
+            "qmsrs",
+            "access-review",
+            "decide",
+            &item.to_string(),
+            "revoke",
+            "--reviewer",
+            "qa",
+            "--comment",
+            "Left the company",
+        ]);
+        assert_eq!(
+            cli.command,
+            Some(Command::AccessReview {
+                command: AccessReviewCommand::Decide {
+                    item,
+                    decision: "revoke".to_string(),
+                    reviewer: "qa".to_string(),
+                    comment: Some("Left the company".to_string()),
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_cli_config_encrypt_section_command() {
+        let cli = Cli::parse_from(["qmsrs", "--config-path", "prod.toml", "config", "encrypt-section", "oidc"]);
+        assert_eq!(cli.config_path, PathBuf::from("prod.toml"));
+        assert_eq!(
+            cli.command,
+            Some(Command::Config { command: ConfigCommand::EncryptSection { section: "oidc".to_string() } })
+        );
+    }
+
+    #[test]
+    fn test_cli_features_disable_command() {
+        let args = ["qmsrs", "features", "disable", "post_market", "--user", "admin", "--reason", "Not validated"];
+        let cli = Cli::parse_from(args);
+        let command = FeaturesCommand::Disable {
+            feature: "post_market".to_string(),
+            user: "admin".to_string(),
+            reason: "Not validated".to_string(),
+        };
+        assert_eq!(cli.command, Some(Command::Features { command }));
+        assert_eq!(cli.command.unwrap().name(), "features disable");
+    }
+
+    #[test]
+    fn test_cli_scripts_submit_command() {
+        let args = [
+            "qmsrs", "scripts", "submit", "VS-001", "--title", "Critical CAPA due date", "--hook", "capa", "--file",
+            "vs-001.rhai", "--description", "Initial", "--user", "qe",
+        ];
+        let cli = Cli::parse_from(args);
+        let command = ScriptsCommand::Submit {
+            document_number: "VS-001".to_string(),
+            title: "Critical CAPA due date".to_string(),
+            hook: "capa".to_string(),
+            file: PathBuf::from("vs-001.rhai"),
+            description: "Initial".to_string(),
+            user: "qe".to_string(),
+        };
+        assert_eq!(cli.command, Some(Command::Scripts { command }));
+        assert_eq!(cli.command.unwrap().name(), "scripts submit");
+    }
+
+    #[test]
+    fn test_cli_users_deactivate_command() {
+        let args = [
+            "qmsrs", "users", "deactivate", "leaver", "--to", "bob", "--assign", "capa:c-17=alice", "--user", "admin",
+            "--reason", "Left the company",
+        ];
+        let cli = Cli::parse_from(args);
+        let command = UsersCommand::Deactivate {
+            user_id: "leaver".to_string(),
+            to: Some("bob".to_string()),
+            assign: vec!["capa:c-17=alice".to_string()],
+            user: "admin".to_string(),
+            reason: "Left the company".to_string(),
+        };
+        assert_eq!(cli.command, Some(Command::Users { command }));
+        assert_eq!(cli.command.unwrap().name(), "users deactivate");
+    }
+
+    #[test]
+    fn test_cli_batch_records_release_command() {
+        let cli = Cli::parse_from(["qmsrs", "batch-records", "release", "LOT-42", "--user", "qa"]);
+        let command = BatchRecordsCommand::Release { lot: "LOT-42".to_string(), comment: None, user: "qa".to_string() };
+        assert_eq!(cli.command, Some(Command::BatchRecords { command }));
+        assert_eq!(cli.command.unwrap().name(), "batch-records release");
+    }
+
+    #[test]
+    fn test_cli_delegations_request_command() {
+        let args = [
+            "qmsrs", "delegations", "request", "qm", "--to", "qe", "--from", "2026-12-21", "--until", "2027-01-04",
+            "--reason", "Annual leave", "--user", "qm",
+        ];
+        let cli = Cli::parse_from(args);
+        let command = DelegationsCommand::Request {
+            delegator: "qm".to_string(),
+            to: "qe".to_string(),
+            from: NaiveDate::from_ymd_opt(2026, 12, 21).unwrap(),
+            until: NaiveDate::from_ymd_opt(2027, 1, 4).unwrap(),
+            reason: "Annual leave".to_string(),
+            user: "qm".to_string(),
+        };
+        assert_eq!(cli.command, Some(Command::Delegations { command }));
+        assert_eq!(cli.command.unwrap().name(), "delegations request");
+    }
+
+    #[test]
+    fn test_cli_analytics_export_command() {
+        let cli = Cli::parse_from(["qmsrs", "analytics", "export", "bi"]);
+        assert_eq!(
+            cli.command,
+            Some(Command::Analytics { command: AnalyticsCommand::Export { output: PathBuf::from("bi") } })
+        );
+    }
+
+    #[test]
+    fn test_cli_export_audit_command() {
+        let cli = Cli::parse_from(["qmsrs", "export-audit", "trail.csv", "--user", "qa"]);
+        assert_eq!(
+            cli.command,
+            Some(Command::ExportAudit { output: PathBuf::from("trail.csv"), user: Some("qa".to_string()), timezone: None })
+        );
     }
 
     #[test]
@@ -0,0 +1,673 @@
+//! # Clinical - Clinical Evaluation & Literature Review Tracking
+//!
+//! Each device carries a clinical evaluation (MDR Art. 61, Annex XIV; MEDDEV
+//! 2.7/1 rev. 4): a clinical evaluation plan (CEP), a clinical evaluation
+//! report (CER) revised on a fixed schedule, and the literature searches
+//! behind it. A search is recorded with its protocol (databases, search
+//! terms, inclusion and exclusion criteria) and every reference it returned
+//! is screened in or out with a documented appraisal or exclusion reason.
+//! Approving a CER revision sets the date the next update is due; overdue
+//! updates and unscreened references are a management review input
+//! (ISO 13485 §5.6.2).
+
+use chrono::{DateTime, Months, NaiveDate, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::report::{ReportSection, ReportTable};
+
+/// Days before the CER update date from which an evaluation is shown as due soon.
+pub const UPDATE_NOTICE_DAYS: i64 = 90;
+
+/// Where an evaluation's CER stands against its revision schedule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CerStatus {
+    /// Plan in place, no CER approved yet
+    NoReport,
+    Current,
+    DueSoon,
+    Overdue,
+}
+
+impl CerStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CerStatus::NoReport => "no_report",
+            CerStatus::Current => "current",
+            CerStatus::DueSoon => "due_soon",
+            CerStatus::Overdue => "overdue",
+        }
+    }
+}
+
+/// Clinical evaluation of one device.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClinicalEvaluation {
+    pub id: String,
+    pub device: String,
+    /// Document number of the clinical evaluation plan
+    pub plan_reference: String,
+    /// Document number of the approved CER
+    pub report_reference: Option<String>,
+    /// Approved CER revisions; 0 until the first approval
+    pub revision: u32,
+    pub owner: String,
+    pub review_interval_months: u32,
+    pub last_approved_on: Option<NaiveDate>,
+    pub next_update_due: Option<NaiveDate>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ClinicalEvaluation {
+    pub fn cer_status(&self, as_of: NaiveDate) -> CerStatus {
+        match self.next_update_due {
+            None => CerStatus::NoReport,
+            Some(due) if due < as_of => CerStatus::Overdue,
+            Some(due) if (due - as_of).num_days() <= UPDATE_NOTICE_DAYS => CerStatus::DueSoon,
+            Some(_) => CerStatus::Current,
+        }
+    }
+}
+
+/// Protocol a literature search was run under.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchProtocol {
+    /// Databases searched, e.g. PubMed, Embase, Cochrane
+    pub databases: Vec<String>,
+    pub search_terms: String,
+    pub inclusion_criteria: String,
+    pub exclusion_criteria: String,
+}
+
+/// A literature search run for an evaluation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiteratureSearch {
+    pub id: String,
+    pub evaluation_id: String,
+    pub protocol: SearchProtocol,
+    pub executed_on: NaiveDate,
+    pub executed_by: String,
+}
+
+/// Outcome of screening a reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreeningDecision {
+    Included,
+    Excluded,
+}
+
+impl ScreeningDecision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScreeningDecision::Included => "included",
+            ScreeningDecision::Excluded => "excluded",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [ScreeningDecision::Included, ScreeningDecision::Excluded].into_iter().find(|item| item.as_str() == value)
+    }
+}
+
+/// A reference returned by a literature search.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiteratureReference {
+    pub id: String,
+    pub search_id: String,
+    pub citation: String,
+    /// `None` until screened
+    pub decision: Option<ScreeningDecision>,
+    /// Appraisal of an included reference, or why it was excluded
+    pub screening_note: Option<String>,
+    pub screened_by: Option<String>,
+    pub screened_at: Option<DateTime<Utc>>,
+}
+
+/// An evaluation with its literature screening counts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClinicalEvaluationSummary {
+    pub evaluation: ClinicalEvaluation,
+    pub searches: usize,
+    pub included: usize,
+    pub excluded: usize,
+    pub unscreened: usize,
+}
+
+/// Persistence and revision rules for clinical evaluations.
+pub struct ClinicalRepo<'a> {
+    db: &'a Database,
+    audit: AuditManager,
+}
+
+impl<'a> ClinicalRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, audit: AuditManager::new(db.clone()) }
+    }
+
+    /// Register a device's clinical evaluation plan.
+    pub fn create_evaluation(
+        &self,
+        device: &str,
+        plan_reference: &str,
+        owner: &str,
+        review_interval_months: u32,
+        created_by: &str,
+    ) -> Result<ClinicalEvaluation> {
+        require(&[("device", device), ("plan_reference", plan_reference), ("owner", owner)])?;
+        if !(1..=60).contains(&review_interval_months) {
+            return Err(QmsError::Validation {
+                field: "review_interval_months".to_string(),
+                message: "CER review interval must be between 1 and 60 months".to_string(),
+            });
+        }
+        let evaluation = ClinicalEvaluation {
+            id: Uuid::new_v4().to_string(),
+            device: device.to_string(),
+            plan_reference: plan_reference.to_string(),
+            report_reference: None,
+            revision: 0,
+            owner: owner.to_string(),
+            review_interval_months,
+            last_approved_on: None,
+            next_update_due: None,
+            created_by: created_by.to_string(),
+            created_at: Utc::now(),
+        };
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "INSERT INTO clinical_evaluations
+                     (id, device, plan_reference, owner, review_interval_months, created_by, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    evaluation.id,
+                    evaluation.device,
+                    evaluation.plan_reference,
+                    evaluation.owner,
+                    review_interval_months,
+                    created_by,
+                    evaluation.created_at.to_rfc3339(),
+                ],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                created_by,
+                "clinical_evaluation_created",
+                &resource(&evaluation.id),
+                "Success",
+                Some(format!("{} plan {}, CER every {} month(s)", device, plan_reference, review_interval_months)),
+            )
+        })?;
+        Ok(evaluation)
+    }
+
+    pub fn get(&self, id: &str) -> Result<ClinicalEvaluation> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM clinical_evaluations WHERE id = ?1", EVALUATION_COLUMNS),
+                        params![id],
+                        row_to_evaluation,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "clinical_evaluation".to_string(), id: id.to_string() })
+    }
+
+    /// Approve a CER revision and schedule the next update.
+    pub fn approve_report(
+        &self,
+        id: &str,
+        report_reference: &str,
+        approved_on: NaiveDate,
+        approved_by: &str,
+    ) -> Result<ClinicalEvaluation> {
+        require(&[("report_reference", report_reference)])?;
+        let mut evaluation = self.get(id)?;
+        if evaluation.last_approved_on.map_or(false, |last| approved_on < last) {
+            return Err(QmsError::Validation {
+                field: "approved_on".to_string(),
+                message: "A CER revision cannot be approved before the previous one".to_string(),
+            });
+        }
+        let next_update_due = approved_on
+            .checked_add_months(Months::new(evaluation.review_interval_months))
+            .ok_or_else(|| QmsError::Validation {
+                field: "approved_on".to_string(),
+                message: format!("{} is out of range", approved_on),
+            })?;
+        evaluation.report_reference = Some(report_reference.to_string());
+        evaluation.revision += 1;
+        evaluation.last_approved_on = Some(approved_on);
+        evaluation.next_update_due = Some(next_update_due);
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE clinical_evaluations
+                 SET report_reference = ?2, revision = ?3, last_approved_on = ?4, next_update_due = ?5
+                 WHERE id = ?1",
+                params![
+                    id,
+                    report_reference,
+                    evaluation.revision,
+                    approved_on.to_string(),
+                    next_update_due.to_string()
+                ],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                approved_by,
+                "clinical_evaluation_report_approved",
+                &resource(id),
+                "Success",
+                Some(format!(
+                    "{} revision {} approved {}, next update due {}",
+                    report_reference, evaluation.revision, approved_on, next_update_due
+                )),
+            )
+        })?;
+        Ok(evaluation)
+    }
+
+    /// Record a literature search run for an evaluation.
+    pub fn record_search(
+        &self,
+        evaluation_id: &str,
+        protocol: SearchProtocol,
+        executed_on: NaiveDate,
+        executed_by: &str,
+    ) -> Result<LiteratureSearch> {
+        self.get(evaluation_id)?;
+        if protocol.databases.iter().all(|database| database.trim().is_empty()) {
+            return Err(QmsError::Validation {
+                field: "databases".to_string(),
+                message: "At least one literature database is required".to_string(),
+            });
+        }
+        require(&[
+            ("search_terms", protocol.search_terms.as_str()),
+            ("inclusion_criteria", protocol.inclusion_criteria.as_str()),
+            ("exclusion_criteria", protocol.exclusion_criteria.as_str()),
+        ])?;
+        let search = LiteratureSearch {
+            id: Uuid::new_v4().to_string(),
+            evaluation_id: evaluation_id.to_string(),
+            protocol,
+            executed_on,
+            executed_by: executed_by.to_string(),
+        };
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "INSERT INTO literature_searches
+                     (id, evaluation_id, databases, search_terms, inclusion_criteria, exclusion_criteria,
+                      executed_on, executed_by)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    search.id,
+                    evaluation_id,
+                    serde_json::to_string(&search.protocol.databases)?,
+                    search.protocol.search_terms,
+                    search.protocol.inclusion_criteria,
+                    search.protocol.exclusion_criteria,
+                    executed_on.to_string(),
+                    executed_by,
+                ],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                executed_by,
+                "literature_search_recorded",
+                &resource(evaluation_id),
+                "Success",
+                Some(format!(
+                    "Search {} in {}: {}",
+                    search.id,
+                    search.protocol.databases.join(", "),
+                    search.protocol.search_terms
+                )),
+            )
+        })?;
+        Ok(search)
+    }
+
+    /// Add a reference returned by a search, pending screening.
+    pub fn add_reference(&self, search_id: &str, citation: &str, added_by: &str) -> Result<LiteratureReference> {
+        require(&[("citation", citation)])?;
+        let evaluation_id = self.search_evaluation(search_id)?;
+        let reference = LiteratureReference {
+            id: Uuid::new_v4().to_string(),
+            search_id: search_id.to_string(),
+            citation: citation.trim().to_string(),
+            decision: None,
+            screening_note: None,
+            screened_by: None,
+            screened_at: None,
+        };
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "INSERT INTO literature_references (id, search_id, citation) VALUES (?1, ?2, ?3)",
+                params![reference.id, search_id, reference.citation],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                added_by,
+                "literature_reference_added",
+                &resource(&evaluation_id),
+                "Success",
+                Some(format!("Reference {}: {}", reference.id, reference.citation)),
+            )
+        })?;
+        Ok(reference)
+    }
+
+    /// Screen a reference in or out; `note` is the appraisal of an included
+    /// reference or the reason for excluding it.
+    pub fn screen_reference(
+        &self,
+        reference_id: &str,
+        decision: ScreeningDecision,
+        note: &str,
+        screened_by: &str,
+    ) -> Result<LiteratureReference> {
+        require(&[("screening_note", note)])?;
+        let mut reference = self.reference(reference_id)?;
+        if reference.decision.is_some() {
+            return Err(QmsError::Validation {
+                field: "decision".to_string(),
+                message: format!("Reference {} is already screened", reference_id),
+            });
+        }
+        let evaluation_id = self.search_evaluation(&reference.search_id)?;
+        let now = Utc::now();
+        reference.decision = Some(decision);
+        reference.screening_note = Some(note.to_string());
+        reference.screened_by = Some(screened_by.to_string());
+        reference.screened_at = Some(now);
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE literature_references
+                 SET decision = ?2, screening_note = ?3, screened_by = ?4, screened_at = ?5
+                 WHERE id = ?1",
+                params![reference_id, decision.as_str(), note, screened_by, now.to_rfc3339()],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                screened_by,
+                "literature_reference_screened",
+                &resource(&evaluation_id),
+                "Success",
+                Some(format!("Reference {} {}: {}", reference_id, decision.as_str(), note)),
+            )
+        })?;
+        Ok(reference)
+    }
+
+    /// References returned by a search, in the order they were added.
+    pub fn references(&self, search_id: &str) -> Result<Vec<LiteratureReference>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM literature_references WHERE search_id = ?1 ORDER BY rowid",
+                REFERENCE_COLUMNS
+            ))?;
+            let references =
+                stmt.query_map(params![search_id], row_to_reference)?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(references)
+        })
+    }
+
+    /// Every evaluation with its screening counts, earliest CER update first.
+    pub fn summaries(&self) -> Result<Vec<ClinicalEvaluationSummary>> {
+        self.db.with_connection(|conn| {
+            let screened = |condition: &str| {
+                format!(
+                    "(SELECT COUNT(*) FROM literature_references r JOIN literature_searches s ON s.id = r.search_id
+                      WHERE s.evaluation_id = clinical_evaluations.id AND {})",
+                    condition
+                )
+            };
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {},
+                        (SELECT COUNT(*) FROM literature_searches s WHERE s.evaluation_id = clinical_evaluations.id),
+                        {}, {}, {}
+                 FROM clinical_evaluations
+                 ORDER BY next_update_due IS NULL, next_update_due, device",
+                EVALUATION_COLUMNS,
+                screened("r.decision = 'included'"),
+                screened("r.decision = 'excluded'"),
+                screened("r.decision IS NULL")
+            ))?;
+            let summaries = stmt
+                .query_map([], |row| {
+                    Ok(ClinicalEvaluationSummary {
+                        evaluation: row_to_evaluation(row)?,
+                        searches: row.get::<_, i64>(11)? as usize,
+                        included: row.get::<_, i64>(12)? as usize,
+                        excluded: row.get::<_, i64>(13)? as usize,
+                        unscreened: row.get::<_, i64>(14)? as usize,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(summaries)
+        })
+    }
+
+    fn reference(&self, id: &str) -> Result<LiteratureReference> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM literature_references WHERE id = ?1", REFERENCE_COLUMNS),
+                        params![id],
+                        row_to_reference,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "literature_reference".to_string(), id: id.to_string() })
+    }
+
+    fn search_evaluation(&self, search_id: &str) -> Result<String> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        "SELECT evaluation_id FROM literature_searches WHERE id = ?1",
+                        params![search_id],
+                        |row| row.get(0),
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "literature_search".to_string(), id: search_id.to_string() })
+    }
+}
+
+/// Management review input: CER revision schedule and literature screening
+/// backlog per device.
+pub fn clinical_evaluation_section(summaries: &[ClinicalEvaluationSummary], as_of: DateTime<Utc>) -> ReportSection {
+    let today = as_of.date_naive();
+    let count = |status: CerStatus| summaries.iter().filter(|s| s.evaluation.cer_status(today) == status).count();
+    let section = ReportSection::new("Clinical Evaluations").key_values(vec![
+        ("Devices", summaries.len().to_string()),
+        ("CER Updates Overdue", count(CerStatus::Overdue).to_string()),
+        ("CER Updates Due Soon", count(CerStatus::DueSoon).to_string()),
+        ("Without Approved CER", count(CerStatus::NoReport).to_string()),
+        ("Unscreened References", summaries.iter().map(|s| s.unscreened).sum::<usize>().to_string()),
+    ]);
+    if summaries.is_empty() {
+        return section;
+    }
+    section.table(
+        summaries.iter().fold(
+            ReportTable::new(vec!["Device", "CER", "Revision", "Next Update", "Status", "Included", "Unscreened"])
+                .with_column_weights(vec![1.8, 1.2, 0.8, 1.1, 0.9, 0.8, 0.9]),
+            |table, s| {
+                let e = &s.evaluation;
+                table.with_row(vec![
+                    e.device.clone(),
+                    e.report_reference.clone().unwrap_or_else(|| "-".to_string()),
+                    e.revision.to_string(),
+                    e.next_update_due.map(|d| d.to_string()).unwrap_or_else(|| "-".to_string()),
+                    e.cer_status(today).as_str().to_string(),
+                    s.included.to_string(),
+                    s.unscreened.to_string(),
+                ])
+            },
+        ),
+    )
+}
+
+fn require(fields: &[(&str, &str)]) -> Result<()> {
+    match fields.iter().find(|(_, value)| value.trim().is_empty()) {
+        Some((field, _)) => {
+            Err(QmsError::Validation { field: field.to_string(), message: format!("{} is required", field) })
+        }
+        None => Ok(()),
+    }
+}
+
+fn resource(evaluation_id: &str) -> String {
+    format!("clinical_evaluation:{}", evaluation_id)
+}
+
+const EVALUATION_COLUMNS: &str =
+    "id, device, plan_reference, report_reference, revision, owner, review_interval_months,
+     last_approved_on, next_update_due, created_by, created_at";
+
+const REFERENCE_COLUMNS: &str = "id, search_id, citation, decision, screening_note, screened_by, screened_at";
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn parse_date(row: &Row, index: usize) -> rusqlite::Result<Option<NaiveDate>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|value| NaiveDate::parse_from_str(&value, "%Y-%m-%d").map_err(|e| conversion_error(index, e.to_string())))
+        .transpose()
+}
+
+fn parse_time(row: &Row, index: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| conversion_error(index, e.to_string()))
+        })
+        .transpose()
+}
+
+fn row_to_evaluation(row: &Row) -> rusqlite::Result<ClinicalEvaluation> {
+    Ok(ClinicalEvaluation {
+        id: row.get(0)?,
+        device: row.get(1)?,
+        plan_reference: row.get(2)?,
+        report_reference: row.get(3)?,
+        revision: row.get(4)?,
+        owner: row.get(5)?,
+        review_interval_months: row.get(6)?,
+        last_approved_on: parse_date(row, 7)?,
+        next_update_due: parse_date(row, 8)?,
+        created_by: row.get(9)?,
+        created_at: parse_time(row, 10)?.ok_or_else(|| conversion_error(10, "created_at is missing".to_string()))?,
+    })
+}
+
+fn row_to_reference(row: &Row) -> rusqlite::Result<LiteratureReference> {
+    let decision: Option<String> = row.get(3)?;
+    Ok(LiteratureReference {
+        id: row.get(0)?,
+        search_id: row.get(1)?,
+        citation: row.get(2)?,
+        decision: decision
+            .map(|value| {
+                ScreeningDecision::parse(&value)
+                    .ok_or_else(|| conversion_error(3, format!("unknown decision {}", value)))
+            })
+            .transpose()?,
+        screening_note: row.get(4)?,
+        screened_by: row.get(5)?,
+        screened_at: parse_time(row, 6)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+
+    #[test]
+    fn test_cer_schedule_and_literature_screening_feed_review() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let repo = ClinicalRepo::new(&db);
+        let pump = repo.create_evaluation("InfuMax IMX-200", "CEP-0042", "clinical_lead", 12, "ra_manager").unwrap();
+        repo.create_evaluation("Sensor Patch", "CEP-0051", "clinical_lead", 24, "ra_manager").unwrap();
+        assert!(repo.create_evaluation("Catheter", "CEP-0060", "clinical_lead", 0, "ra_manager").is_err());
+
+        let approved = repo
+            .approve_report(&pump.id, "CER-0042", NaiveDate::from_ymd_opt(2025, 9, 30).unwrap(), "ra_manager")
+            .unwrap();
+        assert_eq!(approved.revision, 1);
+        assert_eq!(approved.next_update_due, NaiveDate::from_ymd_opt(2026, 9, 30));
+        assert_eq!(approved.cer_status(NaiveDate::from_ymd_opt(2026, 8, 1).unwrap()), CerStatus::DueSoon);
+        assert_eq!(approved.cer_status(NaiveDate::from_ymd_opt(2026, 10, 15).unwrap()), CerStatus::Overdue);
+        assert!(repo
+            .approve_report(&pump.id, "CER-0042", NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(), "ra_manager")
+            .is_err());
+
+        let protocol = SearchProtocol {
+            databases: vec!["PubMed".to_string(), "Embase".to_string()],
+            search_terms: "volumetric infusion pump AND (occlusion OR over-infusion)".to_string(),
+            inclusion_criteria: "Clinical data on volumetric pumps, 2015 onwards".to_string(),
+            exclusion_criteria: "Animal studies; non-English abstracts".to_string(),
+        };
+        let search = repo
+            .record_search(&pump.id, protocol.clone(), NaiveDate::from_ymd_opt(2026, 9, 1).unwrap(), "clinical_lead")
+            .unwrap();
+        let empty = SearchProtocol { databases: Vec::new(), ..protocol };
+        assert!(repo.record_search(&pump.id, empty, NaiveDate::from_ymd_opt(2026, 9, 1).unwrap(), "x").is_err());
+
+        let included =
+            repo.add_reference(&search.id, "Smith J et al. J Infus Nurs 2021;44:12-19", "clinical_lead").unwrap();
+        let excluded = repo.add_reference(&search.id, "Doe A. Vet Rec 2019;184:301", "clinical_lead").unwrap();
+        repo.add_reference(&search.id, "Lee K. Crit Care 2022;26:88", "clinical_lead").unwrap();
+        repo.screen_reference(
+            &included.id,
+            ScreeningDecision::Included,
+            "Equivalent device, 412 patients",
+            "clinical_lead",
+        )
+        .unwrap();
+        repo.screen_reference(&excluded.id, ScreeningDecision::Excluded, "Animal study", "clinical_lead").unwrap();
+        assert!(repo.screen_reference(&excluded.id, ScreeningDecision::Included, "Changed my mind", "x").is_err());
+        assert!(repo.screen_reference(&included.id, ScreeningDecision::Excluded, " ", "x").is_err());
+        assert_eq!(repo.references(&search.id).unwrap()[1].decision, Some(ScreeningDecision::Excluded));
+
+        let summaries = repo.summaries().unwrap();
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].evaluation.id, pump.id, "scheduled evaluations first");
+        assert_eq!((summaries[0].searches, summaries[0].included, summaries[0].excluded), (1, 1, 1));
+        assert_eq!(summaries[0].unscreened, 1);
+        assert_eq!(
+            summaries[1].evaluation.cer_status(NaiveDate::from_ymd_opt(2026, 10, 15).unwrap()),
+            CerStatus::NoReport
+        );
+
+        let as_of = DateTime::parse_from_rfc3339("2026-10-15T00:00:00Z").unwrap().with_timezone(&Utc);
+        let section = clinical_evaluation_section(&summaries, as_of);
+        assert_eq!(section.title, "Clinical Evaluations");
+
+        let audit = db.get_audit_entries_for_resource(&format!("clinical_evaluation:{}", pump.id)).unwrap();
+        assert_eq!(audit.iter().filter(|e| e.action == "literature_reference_screened").count(), 2);
+    }
+}
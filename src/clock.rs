@@ -0,0 +1,97 @@
+//! # Clock - Injectable Source of the Current Time
+//!
+//! Overdue CAPAs, overdue training, session expiry and report timestamps all
+//! depend on "now". Services that take a [`Clock`] (through `with_clock`)
+//! read the time from it instead of the system clock. Tests can then pin or
+//! advance time with a [`MockClock`], and metrics can be reconstructed as they
+//! stood on a past date. Production code uses [`SystemClock`], the default
+//! everywhere.
+
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+/// Source of the current time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Current UTC calendar day.
+    fn today(&self) -> NaiveDate {
+        self.now().date_naive()
+    }
+}
+
+/// Clock shared between services.
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The system clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// The system clock, shared.
+pub fn system_clock() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// A clock that stands still until set or advanced. Clones share the same
+/// time, so a test can keep one and hand another to the service under test.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<RwLock<DateTime<Utc>>>,
+}
+
+impl MockClock {
+    pub fn at(now: DateTime<Utc>) -> Self {
+        Self { now: Arc::new(RwLock::new(now)) }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        if let Ok(mut current) = self.now.write() {
+            *current = now;
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        if let Ok(mut current) = self.now.write() {
+            *current += by;
+        }
+    }
+
+    /// This clock as a [`SharedClock`] for `with_clock`.
+    pub fn shared(&self) -> SharedClock {
+        Arc::new(self.clone())
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.now.read().map(|now| *now).unwrap_or_else(|poisoned| *poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_mock_clock_is_shared_between_clones() {
+        let start = Utc.with_ymd_and_hms(2026, 3, 31, 23, 30, 0).unwrap();
+        let clock = MockClock::at(start);
+        let shared = clock.shared();
+        assert_eq!(shared.now(), start);
+
+        clock.advance(Duration::hours(1));
+        assert_eq!(shared.now(), start + Duration::hours(1));
+        assert_eq!(shared.today(), NaiveDate::from_ymd_opt(2026, 4, 1).unwrap());
+        clock.set(start);
+        assert_eq!(shared.today(), NaiveDate::from_ymd_opt(2026, 3, 31).unwrap());
+        assert!(SystemClock.now() > start);
+    }
+}
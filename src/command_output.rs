@@ -0,0 +1,144 @@
+//! # Command Output - Text or JSON Results of Maintenance Commands
+//!
+//! Maintenance commands are run by hand and from CI pipelines and
+//! provisioning scripts. Each command collects its result in a
+//! [`CommandOutput`]: lines for a person reading the terminal and JSON fields
+//! for a script. With `--output json` a single JSON document is printed on
+//! stdout, also when the command fails. The exit code tells scripts what
+//! happened without parsing either; the codes in [`exit_code`] are stable.
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::cli::OutputFormat;
+use crate::error::QmsError;
+
+/// Process exit codes of the maintenance commands.
+pub mod exit_code {
+    /// The command ran and every check passed
+    pub const SUCCESS: i32 = 0;
+    /// The command ran but a check failed: invalid report signature, broken
+    /// seal chain, failed self-test
+    pub const CHECK_FAILED: i32 = 1;
+    /// Invalid arguments or input values; also used by argument parsing
+    pub const INVALID_INPUT: i32 = 2;
+    /// A referenced record does not exist
+    pub const NOT_FOUND: i32 = 3;
+    /// The record was changed concurrently
+    pub const CONFLICT: i32 = 4;
+    /// The configuration file or master key is missing or invalid
+    pub const CONFIGURATION: i32 = 5;
+    /// A file could not be read or written
+    pub const FILE_SYSTEM: i32 = 6;
+    /// Any other error
+    pub const FAILURE: i32 = 10;
+}
+
+/// Exit code of a command that failed with `error`.
+pub fn exit_code_for(error: &QmsError) -> i32 {
+    match error {
+        QmsError::Validation { .. }
+        | QmsError::ValidationError { .. }
+        | QmsError::ValidationErrors { .. }
+        | QmsError::Serialization { .. } => exit_code::INVALID_INPUT,
+        QmsError::NotFound { .. } => exit_code::NOT_FOUND,
+        QmsError::Conflict { .. } => exit_code::CONFLICT,
+        QmsError::Configuration { .. } | QmsError::Encryption { .. } => exit_code::CONFIGURATION,
+        QmsError::FileSystem { .. } => exit_code::FILE_SYSTEM,
+        _ => exit_code::FAILURE,
+    }
+}
+
+/// Result of one maintenance command.
+#[derive(Debug, Clone)]
+pub struct CommandOutput {
+    command: &'static str,
+    lines: Vec<String>,
+    result: Map<String, Value>,
+    passed: bool,
+}
+
+impl CommandOutput {
+    pub fn new(command: &'static str) -> Self {
+        Self { command, lines: Vec::new(), result: Map::new(), passed: true }
+    }
+
+    /// Add a line of the text output.
+    pub fn line(&mut self, line: impl Into<String>) -> &mut Self {
+        self.lines.push(line.into());
+        self
+    }
+
+    /// Set a field of the JSON result.
+    pub fn field(&mut self, name: &str, value: impl Serialize) -> &mut Self {
+        let value = serde_json::to_value(value).unwrap_or(Value::Null);
+        self.result.insert(name.to_string(), value);
+        self
+    }
+
+    /// Mark a failed check; the command still reports its result.
+    pub fn fail(&mut self) -> &mut Self {
+        self.passed = false;
+        self
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        if self.passed {
+            exit_code::SUCCESS
+        } else {
+            exit_code::CHECK_FAILED
+        }
+    }
+
+    /// The output as printed in `format`.
+    pub fn render(&self, format: OutputFormat) -> String {
+        match format {
+            OutputFormat::Text => self.lines.join("\n"),
+            OutputFormat::Json => json!({
+                "command": self.command,
+                "status": if self.passed { "ok" } else { "failed" },
+                "exit_code": self.exit_code(),
+                "result": self.result,
+            })
+            .to_string(),
+        }
+    }
+}
+
+/// JSON document printed when `command` fails with an error instead of a
+/// result. `code` is the [`QmsError::error_code`] where there is one.
+pub fn render_error(command: &str, code: &str, message: &str, exit_code: i32) -> String {
+    json!({
+        "command": command,
+        "status": "error",
+        "exit_code": exit_code,
+        "error": { "code": code, "message": message },
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_and_json_output_with_stable_exit_codes() {
+        let mut output = CommandOutput::new("audit-worm verify");
+        output.line("Checked 2 segment(s), 40 audit entries").field("segments", 2).field("problems", ["gap"]);
+        assert_eq!(output.render(OutputFormat::Text), "Checked 2 segment(s), 40 audit entries");
+        assert_eq!(output.exit_code(), exit_code::SUCCESS);
+
+        output.fail();
+        let json: Value = serde_json::from_str(&output.render(OutputFormat::Json)).unwrap();
+        assert_eq!(json["command"], "audit-worm verify");
+        assert_eq!((json["status"].as_str(), json["exit_code"].as_i64()), (Some("failed"), Some(1)));
+        assert_eq!(json["result"], json!({ "segments": 2, "problems": ["gap"] }));
+
+        let error = QmsError::NotFound { resource: "standard".to_string(), id: "ISO 9999".to_string() };
+        assert_eq!(exit_code_for(&error), exit_code::NOT_FOUND);
+        let json: Value =
+            serde_json::from_str(&render_error("compliance-matrix", error.error_code(), &error.to_string(), 3))
+                .unwrap();
+        assert_eq!((json["status"].as_str(), json["error"]["code"].as_str()), (Some("error"), Some("NOT_FOUND")));
+    }
+}
@@ -0,0 +1,277 @@
+//! # Complaint Rate - Complaints and Adverse Events per 10,000 Units
+//!
+//! Absolute complaint counts rise and fall with sales, so a trend threshold
+//! on counts alone either fires on every good month or misses a real quality
+//! problem in a slow one. Units shipped are recorded per calendar month in
+//! `unit_shipments`; complaints from the quality intake and adverse events
+//! are then expressed per 10,000 units and compared against the thresholds
+//! in `[complaint_rates]` (ISO 13485 §8.2.1, §8.4). Months without recorded
+//! shipments have no rate rather than an infinite one. Trends are served at
+//! `GET /analytics/complaint_rates`, and the recorded volumes are the default
+//! sample sizes of the SPC complaint p-chart.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use rusqlite::{params, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::AuditManager;
+use crate::config::ComplaintRateConfig;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+
+/// Units per which rates are expressed.
+pub const RATE_BASIS: f64 = 10_000.0;
+
+/// Months shown when no period is selected.
+pub const DEFAULT_TREND_MONTHS: u32 = 12;
+
+/// Units shipped in one calendar month.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShipmentVolume {
+    /// Calendar month as `YYYY-MM`
+    pub month: String,
+    pub units: u64,
+    pub recorded_by: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// One month of a [`ComplaintRateTrend`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonthlyRate {
+    /// Calendar month as `YYYY-MM`
+    pub month: String,
+    /// Units shipped, if recorded for the month
+    pub units: Option<u64>,
+    pub complaints: u64,
+    pub adverse_events: u64,
+    /// Complaints per 10,000 units
+    pub complaint_rate: Option<f64>,
+    /// Adverse events per 10,000 units
+    pub adverse_event_rate: Option<f64>,
+    /// Complaint rate above its threshold
+    pub complaint_alert: bool,
+    /// Adverse event rate above its threshold
+    pub adverse_event_alert: bool,
+}
+
+/// Monthly complaint and adverse event rates, oldest month first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComplaintRateTrend {
+    pub thresholds: ComplaintRateConfig,
+    pub months: Vec<MonthlyRate>,
+}
+
+impl ComplaintRateTrend {
+    /// Months with either rate above its threshold.
+    pub fn alerts(&self) -> Vec<&MonthlyRate> {
+        self.months.iter().filter(|month| month.complaint_alert || month.adverse_event_alert).collect()
+    }
+}
+
+/// `count` per 10,000 of `units`; `None` without units to normalise by.
+pub fn rate_per_basis(count: u64, units: Option<u64>) -> Option<f64> {
+    units.filter(|units| *units > 0).map(|units| count as f64 * RATE_BASIS / units as f64)
+}
+
+/// Shipment volumes and the rates normalised by them.
+pub struct ComplaintRates<'a> {
+    db: &'a Database,
+}
+
+impl<'a> ComplaintRates<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Record the units shipped in `month` (`YYYY-MM`), replacing an earlier
+    /// figure for the same month.
+    pub fn record_units(&self, month: &str, units: u64, recorded_by: &str) -> Result<ShipmentVolume> {
+        let month = month.trim();
+        month_index(month)?;
+        let volume = ShipmentVolume {
+            month: month.to_string(),
+            units,
+            recorded_by: recorded_by.to_string(),
+            recorded_at: Utc::now(),
+        };
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "INSERT INTO unit_shipments (month, units, recorded_by, recorded_at) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT(month) DO UPDATE SET
+                     units = excluded.units, recorded_by = excluded.recorded_by, recorded_at = excluded.recorded_at",
+                params![volume.month, units as i64, volume.recorded_by, volume.recorded_at.to_rfc3339()],
+            )?;
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                recorded_by,
+                "units_shipped_recorded",
+                &format!("unit_shipments:{}", volume.month),
+                "Success",
+                Some(format!("{} units", units)),
+            )
+        })?;
+        Ok(volume)
+    }
+
+    /// Every recorded month, oldest first.
+    pub fn volumes(&self) -> Result<Vec<ShipmentVolume>> {
+        self.db.with_connection(|conn| {
+            let mut stmt =
+                conn.prepare("SELECT month, units, recorded_by, recorded_at FROM unit_shipments ORDER BY month")?;
+            let volumes = stmt.query_map([], row_to_volume)?.collect::<rusqlite::Result<_>>()?;
+            Ok(volumes)
+        })
+    }
+
+    /// Units shipped per recorded month, keyed `YYYY-MM`.
+    pub fn units_by_month(&self) -> Result<BTreeMap<String, u64>> {
+        Ok(self.volumes()?.into_iter().map(|volume| (volume.month, volume.units)).collect())
+    }
+
+    /// Rates of the `months` calendar months up to and including the one
+    /// containing `today`, flagged against `thresholds`.
+    pub fn trend(&self, months: u32, today: NaiveDate, thresholds: &ComplaintRateConfig) -> Result<ComplaintRateTrend> {
+        if months == 0 {
+            return Err(QmsError::Validation {
+                field: "months".to_string(),
+                message: "At least one month is required".to_string(),
+            });
+        }
+        let current = i64::from(today.year()) * 12 + i64::from(today.month0());
+        let keys: Vec<String> = (current - i64::from(months) + 1..=current).map(month_key).collect();
+        let (first, last) = (&keys[0], &keys[keys.len() - 1]);
+
+        let units = self.units_by_month()?;
+        let count_by_month = |sql: &str| {
+            self.db.with_connection(|conn| {
+                let mut stmt = conn.prepare(sql)?;
+                let counts = stmt
+                    .query_map(params![first, last], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))?
+                    .collect::<rusqlite::Result<BTreeMap<_, _>>>()?;
+                Ok(counts)
+            })
+        };
+        let complaints = count_by_month(
+            "SELECT substr(reported_at, 1, 7), COUNT(*) FROM quality_intake
+             WHERE source = 'complaint' AND substr(reported_at, 1, 7) BETWEEN ?1 AND ?2
+             GROUP BY 1",
+        )?;
+        let adverse_events = count_by_month(
+            "SELECT substr(reported_on, 1, 7), COUNT(*) FROM adverse_events
+             WHERE deleted_at IS NULL AND substr(reported_on, 1, 7) BETWEEN ?1 AND ?2
+             GROUP BY 1",
+        )?;
+
+        let months = keys
+            .into_iter()
+            .map(|month| {
+                let units = units.get(&month).copied();
+                let complaints = complaints.get(&month).copied().unwrap_or(0) as u64;
+                let adverse_events = adverse_events.get(&month).copied().unwrap_or(0) as u64;
+                let complaint_rate = rate_per_basis(complaints, units);
+                let adverse_event_rate = rate_per_basis(adverse_events, units);
+                MonthlyRate {
+                    month,
+                    units,
+                    complaints,
+                    adverse_events,
+                    complaint_rate,
+                    adverse_event_rate,
+                    complaint_alert: complaint_rate.is_some_and(|rate| rate > thresholds.complaint_threshold),
+                    adverse_event_alert: adverse_event_rate
+                        .is_some_and(|rate| rate > thresholds.adverse_event_threshold),
+                }
+            })
+            .collect();
+        Ok(ComplaintRateTrend { thresholds: *thresholds, months })
+    }
+}
+
+/// Months since year 0 of a `YYYY-MM` month.
+fn month_index(month: &str) -> Result<i64> {
+    NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+        .ok()
+        .filter(|_| month.len() == 7)
+        .map(|date| i64::from(date.year()) * 12 + i64::from(date.month0()))
+        .ok_or_else(|| QmsError::Validation {
+            field: "month".to_string(),
+            message: format!("'{}' is not a YYYY-MM month", month),
+        })
+}
+
+fn month_key(index: i64) -> String {
+    format!("{:04}-{:02}", index.div_euclid(12), index.rem_euclid(12) + 1)
+}
+
+fn row_to_volume(row: &Row) -> rusqlite::Result<ShipmentVolume> {
+    let recorded_at: String = row.get(3)?;
+    Ok(ShipmentVolume {
+        month: row.get(0)?,
+        units: row.get::<_, i64>(1)? as u64,
+        recorded_by: row.get(2)?,
+        recorded_at: DateTime::parse_from_rfc3339(&recorded_at)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e)))?
+            .with_timezone(&Utc),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::post_market::{AdverseEvent, AdverseEventRepo, Severity};
+    use crate::quality_intake::{IssueSource, QualityIntake};
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rates_per_10k_units_against_thresholds() {
+        let db = test_db();
+        let rates = ComplaintRates::new(&db);
+        let today = Utc::now().date_naive();
+        let this_month = today.format("%Y-%m").to_string();
+        let current = i64::from(today.year()) * 12 + i64::from(today.month0());
+        let last_month = month_key(current - 1);
+
+        rates.record_units(&last_month, 2_000, "sales_ops").unwrap();
+        rates.record_units(&this_month, 5_000, "sales_ops").unwrap();
+        rates.record_units(&this_month, 4_000, "sales_ops").unwrap();
+        assert!(rates.record_units("2026-13", 10, "sales_ops").is_err());
+        assert!(rates.record_units("2026-1", 10, "sales_ops").is_err());
+        assert_eq!(rates.units_by_month().unwrap().get(&this_month), Some(&4_000));
+        let resource = format!("unit_shipments:{}", this_month);
+        assert_eq!(db.get_audit_entries_for_resource(&resource).unwrap().len(), 2);
+
+        let intake = QualityIntake::new(&db);
+        for _ in 0..3 {
+            intake.submit(IssueSource::Complaint, "Complaint", "Description", "support").unwrap();
+        }
+        intake.submit(IssueSource::Nonconformance, "NCR", "Description", "qe1").unwrap();
+        AdverseEventRepo::new(&db).insert(&AdverseEvent::new("support", "skin irritation", Severity::Minor)).unwrap();
+
+        let thresholds = ComplaintRateConfig { complaint_threshold: 5.0, adverse_event_threshold: 5.0 };
+        let trend = rates.trend(3, today, &thresholds).unwrap();
+        assert_eq!(trend.months.len(), 3);
+        assert_eq!(trend.months[0].units, None);
+        assert_eq!(trend.months[0].complaint_rate, None);
+        assert_eq!((trend.months[1].month.as_str(), trend.months[1].complaint_rate), (last_month.as_str(), Some(0.0)));
+        let current = &trend.months[2];
+        assert_eq!((current.complaints, current.adverse_events), (3, 1));
+        assert_eq!(current.complaint_rate, Some(7.5));
+        assert_eq!(current.adverse_event_rate, Some(2.5));
+        assert!(current.complaint_alert && !current.adverse_event_alert);
+        assert_eq!(trend.alerts().len(), 1);
+        assert!(rates.trend(0, today, &thresholds).is_err());
+    }
+}
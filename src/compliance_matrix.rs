@@ -0,0 +1,452 @@
+//! # Compliance Matrix - Clause-to-Document Traceability
+//!
+//! Maps sections of QMS documents to the clauses of a standard or regulation
+//! they implement (e.g. SOP-004 §5.2 → 21 CFR 820.100(a)) and builds a
+//! compliance matrix from those mappings: one row per clause, listing the
+//! documents and sections covering it. Only effective documents count as
+//! coverage; a clause mapped solely to drafts or retired documents, or not
+//! mapped at all, is reported as a gap. Document-level `satisfies` links
+//! from the standards register count as coverage of the whole document.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::links::{LinkRepo, LinkType};
+use crate::pdf_report::{render_report, RenderedReport};
+use crate::report::{Report, ReportBuilder, ReportSection, ReportTable};
+use crate::standards::{Standard, StandardClause, StandardsRepo, DOCUMENT_RECORD_TYPE};
+
+/// Document status that counts as coverage.
+const EFFECTIVE_STATUS: &str = "Effective";
+
+/// A document section mapped to a clause.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClauseMapping {
+    pub id: String,
+    pub document_id: String,
+    /// Section of the document, e.g. "5.2 Investigation"
+    pub section: String,
+    pub clause_id: String,
+    pub mapped_by: String,
+    pub mapped_at: DateTime<Utc>,
+}
+
+/// How well a clause is covered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CoverageStatus {
+    /// At least one effective document covers the clause
+    Covered,
+    /// Only documents that are not (yet or any longer) effective cover the clause
+    NotEffective,
+    /// Nothing covers the clause
+    Gap,
+}
+
+impl CoverageStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            CoverageStatus::Covered => "Covered",
+            CoverageStatus::NotEffective => "Not effective",
+            CoverageStatus::Gap => "Gap",
+        }
+    }
+}
+
+/// A document (section) covering a clause.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatrixEntry {
+    pub document_id: String,
+    pub document_number: String,
+    pub title: String,
+    pub version: String,
+    pub status: String,
+    /// `None` when the whole document is linked to the clause
+    pub section: Option<String>,
+}
+
+impl MatrixEntry {
+    pub fn is_effective(&self) -> bool {
+        self.status == EFFECTIVE_STATUS
+    }
+
+    /// Citation such as `SOP-004 v2.0 §5.2 Investigation`.
+    pub fn citation(&self) -> String {
+        match &self.section {
+            Some(section) => format!("{} v{} §{}", self.document_number, self.version, section),
+            None => format!("{} v{}", self.document_number, self.version),
+        }
+    }
+}
+
+/// One clause with its coverage.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatrixRow {
+    pub clause: StandardClause,
+    pub entries: Vec<MatrixEntry>,
+}
+
+impl MatrixRow {
+    pub fn status(&self) -> CoverageStatus {
+        if self.entries.iter().any(MatrixEntry::is_effective) {
+            CoverageStatus::Covered
+        } else if self.entries.is_empty() {
+            CoverageStatus::Gap
+        } else {
+            CoverageStatus::NotEffective
+        }
+    }
+}
+
+/// Coverage of every clause of a standard.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComplianceMatrix {
+    pub standard: Standard,
+    pub rows: Vec<MatrixRow>,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl ComplianceMatrix {
+    /// Rows that are not covered by an effective document.
+    pub fn gaps(&self) -> Vec<&MatrixRow> {
+        self.rows.iter().filter(|row| row.status() != CoverageStatus::Covered).collect()
+    }
+}
+
+/// Persistence for clause mappings and assembly of the matrix.
+pub struct ClauseMappingRepo<'a> {
+    db: &'a Database,
+    audit: AuditManager,
+}
+
+impl<'a> ClauseMappingRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, audit: AuditManager::new(db.clone()) }
+    }
+
+    /// Map a section of a document to a clause it implements.
+    pub fn map_section(
+        &self,
+        document_id: &str,
+        section: &str,
+        clause_id: &str,
+        mapped_by: &str,
+    ) -> Result<ClauseMapping> {
+        if section.trim().is_empty() {
+            return Err(QmsError::Validation {
+                field: "section".to_string(),
+                message: "section is required".to_string(),
+            });
+        }
+        let mapping = ClauseMapping {
+            id: Uuid::new_v4().to_string(),
+            document_id: document_id.to_string(),
+            section: section.trim().to_string(),
+            clause_id: clause_id.to_string(),
+            mapped_by: mapped_by.to_string(),
+            mapped_at: Utc::now(),
+        };
+        self.db.unit_of_work(|uow| {
+            let conn = uow.connection();
+            let document: Option<String> = conn
+                .query_row(
+                    "SELECT document_number FROM documents WHERE id = ?1 AND deleted_at IS NULL",
+                    params![document_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let document = document
+                .ok_or_else(|| QmsError::NotFound { resource: "document".to_string(), id: document_id.to_string() })?;
+            let clause: Option<String> = conn
+                .query_row(
+                    "SELECT s.designation || CASE s.edition WHEN '' THEN '' ELSE ':' || s.edition END || ' ' || c.clause
+                     FROM standard_clauses c JOIN standards s ON s.id = c.standard_id WHERE c.id = ?1",
+                    params![clause_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let clause = clause.ok_or_else(|| QmsError::NotFound {
+                resource: "standard_clause".to_string(),
+                id: clause_id.to_string(),
+            })?;
+            let inserted = conn.execute(
+                "INSERT OR IGNORE INTO clause_mappings (id, document_id, section, clause_id, mapped_by, mapped_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![mapping.id, document_id, mapping.section, clause_id, mapped_by, mapping.mapped_at.to_rfc3339()],
+            )?;
+            if inserted == 0 {
+                return Err(QmsError::Validation {
+                    field: "section".to_string(),
+                    message: format!("{} §{} is already mapped to {}", document, mapping.section, clause),
+                });
+            }
+            self.audit.log_action_in(
+                uow,
+                mapped_by,
+                "clause_mapped",
+                &format!("document:{}", document_id),
+                "Success",
+                Some(format!("{} §{} → {}", document, mapping.section, clause)),
+            )
+        })?;
+        Ok(mapping)
+    }
+
+    /// Remove a mapping, e.g. after the section was rewritten.
+    pub fn unmap(&self, mapping_id: &str, removed_by: &str) -> Result<()> {
+        self.db.unit_of_work(|uow| {
+            let mapping = uow
+                .connection()
+                .query_row(
+                    &format!("SELECT {} FROM clause_mappings WHERE id = ?1", COLUMNS),
+                    params![mapping_id],
+                    row_to_mapping,
+                )
+                .optional()?
+                .ok_or_else(|| QmsError::NotFound {
+                    resource: "clause_mapping".to_string(),
+                    id: mapping_id.to_string(),
+                })?;
+            uow.connection().execute("DELETE FROM clause_mappings WHERE id = ?1", params![mapping_id])?;
+            self.audit.log_action_in(
+                uow,
+                removed_by,
+                "clause_unmapped",
+                &format!("document:{}", mapping.document_id),
+                "Success",
+                Some(format!("§{} from clause {}", mapping.section, mapping.clause_id)),
+            )
+        })
+    }
+
+    /// Mappings of one document, by section.
+    pub fn for_document(&self, document_id: &str) -> Result<Vec<ClauseMapping>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM clause_mappings WHERE document_id = ?1 ORDER BY section, mapped_at",
+                COLUMNS
+            ))?;
+            let mappings =
+                stmt.query_map(params![document_id], row_to_mapping)?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(mappings)
+        })
+    }
+
+    /// Compliance matrix of a standard from section mappings and document links.
+    pub fn matrix(&self, standard_id: &str) -> Result<ComplianceMatrix> {
+        let standards = StandardsRepo::new(self.db);
+        let standard = standards.get(standard_id)?;
+        let links = LinkRepo::new(self.db);
+        let rows = standards
+            .clauses(standard_id)?
+            .into_iter()
+            .map(|clause| {
+                let mut entries = self.db.with_connection(|conn| {
+                    let mut stmt = conn.prepare(
+                        "SELECT d.id, d.document_number, d.title, d.version, d.status, m.section
+                         FROM clause_mappings m JOIN documents d ON d.id = m.document_id
+                         WHERE m.clause_id = ?1 AND d.deleted_at IS NULL
+                         ORDER BY d.document_number, m.section",
+                    )?;
+                    let entries =
+                        stmt.query_map(params![clause.id], row_to_entry)?.collect::<rusqlite::Result<Vec<_>>>()?;
+                    Ok(entries)
+                })?;
+                for link in links.links_of(&clause.record_ref())? {
+                    if link.link_type != LinkType::Satisfies || link.source.record_type != DOCUMENT_RECORD_TYPE {
+                        continue;
+                    }
+                    if let Some(entry) = self.document_entry(&link.source.record_id)? {
+                        entries.push(entry);
+                    }
+                }
+                Ok(MatrixRow { clause, entries })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ComplianceMatrix { standard, rows, generated_at: Utc::now() })
+    }
+
+    fn document_entry(&self, document_id: &str) -> Result<Option<MatrixEntry>> {
+        self.db.with_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT id, document_number, title, version, status, NULL
+                     FROM documents WHERE id = ?1 AND deleted_at IS NULL",
+                    params![document_id],
+                    row_to_entry,
+                )
+                .optional()?)
+        })
+    }
+}
+
+/// Build the compliance matrix report model.
+pub fn build_matrix_report(matrix: &ComplianceMatrix, prepared_by: &str) -> Report {
+    let covered = matrix.rows.iter().filter(|row| row.status() == CoverageStatus::Covered).count();
+    let summary = ReportSection::new("Summary").key_values(vec![
+        ("Standard", format!("{} {}", matrix.standard.reference(), matrix.standard.title)),
+        ("Clauses", matrix.rows.len().to_string()),
+        ("Covered by Effective Documents", covered.to_string()),
+        ("Gaps", matrix.gaps().len().to_string()),
+    ]);
+
+    let mut table =
+        ReportTable::new(vec!["Clause", "Title", "Documents", "Status"]).with_column_weights(vec![0.8, 2.0, 2.6, 1.0]);
+    for row in &matrix.rows {
+        let documents: Vec<String> = row
+            .entries
+            .iter()
+            .map(|e| if e.is_effective() { e.citation() } else { format!("{} ({})", e.citation(), e.status) })
+            .collect();
+        table = table.with_row(vec![
+            row.clause.clause.clone(),
+            row.clause.title.clone(),
+            if documents.is_empty() { "-".to_string() } else { documents.join("; ") },
+            row.status().label().to_string(),
+        ]);
+    }
+    let mut matrix_section = ReportSection::new("Compliance Matrix").with_page_break();
+    matrix_section = if matrix.rows.is_empty() {
+        matrix_section.paragraph("No clauses registered for this standard.")
+    } else {
+        matrix_section.table(table)
+    };
+
+    let gaps = matrix.gaps();
+    let gap_section = ReportSection::new("Gaps");
+    let gap_section = if gaps.is_empty() {
+        gap_section.paragraph("Every clause is covered by at least one effective document.")
+    } else {
+        gap_section.table(gaps.iter().fold(
+            ReportTable::new(vec!["Clause", "Title", "Finding"]).with_column_weights(vec![0.8, 2.0, 3.0]),
+            |table, row| {
+                let finding = match row.status() {
+                    CoverageStatus::NotEffective => "Covered only by documents that are not effective".to_string(),
+                    _ => "No document covers this clause".to_string(),
+                };
+                table.with_row(vec![row.clause.clause.clone(), row.clause.title.clone(), finding])
+            },
+        ))
+    };
+
+    ReportBuilder::new(format!("Compliance Matrix: {}", matrix.standard.reference()))
+        .with_subtitle(matrix.standard.title.clone())
+        .with_prepared_by(prepared_by)
+        .with_generated_on(matrix.generated_at)
+        .with_section(summary)
+        .with_section(matrix_section)
+        .with_section(gap_section)
+        .build()
+}
+
+/// Render the compliance matrix report to `output_path`.
+pub fn generate_matrix_report(
+    matrix: &ComplianceMatrix,
+    prepared_by: &str,
+    output_path: &Path,
+) -> Result<RenderedReport> {
+    render_report(&build_matrix_report(matrix, prepared_by), output_path)
+}
+
+const COLUMNS: &str = "id, document_id, section, clause_id, mapped_by, mapped_at";
+
+fn row_to_mapping(row: &Row) -> rusqlite::Result<ClauseMapping> {
+    let mapped_at: String = row.get(5)?;
+    Ok(ClauseMapping {
+        id: row.get(0)?,
+        document_id: row.get(1)?,
+        section: row.get(2)?,
+        clause_id: row.get(3)?,
+        mapped_by: row.get(4)?,
+        mapped_at: DateTime::parse_from_rfc3339(&mapped_at).map(|t| t.with_timezone(&Utc)).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(5, rusqlite::types::Type::Text, e.to_string().into())
+        })?,
+    })
+}
+
+fn row_to_entry(row: &Row) -> rusqlite::Result<MatrixEntry> {
+    Ok(MatrixEntry {
+        document_id: row.get(0)?,
+        document_number: row.get(1)?,
+        title: row.get(2)?,
+        version: row.get(3)?,
+        status: row.get(4)?,
+        section: row.get(5)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::standards::StandardKind;
+
+    #[test]
+    fn test_matrix_reports_coverage_and_gaps() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["qa"]);
+        db.with_connection(|conn| {
+            for (id, number, status) in [("d1", "SOP-004", "Effective"), ("d2", "SOP-009", "Draft")] {
+                conn.execute(
+                    "INSERT INTO documents
+                         (id, document_number, title, version, status, document_type, content_hash, created_by)
+                     VALUES (?1, ?2, 'Procedure', '2.0', ?3, 'SOP', 'h', 'qa')",
+                    params![id, number, status],
+                )?;
+            }
+            Ok(())
+        })
+        .unwrap();
+        let standards = StandardsRepo::new(&db);
+        let cfr = standards
+            .add_standard("21 CFR 820", "", "Quality System Regulation", StandardKind::Regulation, "qa")
+            .unwrap();
+        let capa = standards.add_clause(&cfr.id, "820.100", "Corrective and preventive action", "qa").unwrap();
+        let complaints = standards.add_clause(&cfr.id, "820.198", "Complaint files", "qa").unwrap();
+        let records = standards.add_clause(&cfr.id, "820.180", "General records requirements", "qa").unwrap();
+        let repo = ClauseMappingRepo::new(&db);
+
+        repo.map_section("d1", "5.2 Investigation", &capa.id, "qa").unwrap();
+        assert!(repo.map_section("d1", "5.2 Investigation", &capa.id, "qa").is_err(), "duplicate mapping");
+        assert!(matches!(repo.map_section("nope", "1", &capa.id, "qa"), Err(QmsError::NotFound { .. })));
+        let draft = repo.map_section("d2", "4", &complaints.id, "qa").unwrap();
+        standards.link_procedure("d1", &records.id, "qa").unwrap();
+
+        let matrix = repo.matrix(&cfr.id).unwrap();
+        let statuses: Vec<(&str, CoverageStatus)> =
+            matrix.rows.iter().map(|row| (row.clause.clause.as_str(), row.status())).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("820.100", CoverageStatus::Covered),
+                ("820.180", CoverageStatus::Covered),
+                ("820.198", CoverageStatus::NotEffective),
+            ]
+        );
+        assert_eq!(matrix.rows[0].entries[0].citation(), "SOP-004 v2.0 §5.2 Investigation");
+        assert_eq!(matrix.rows[1].entries[0].section, None, "whole-document link");
+
+        repo.unmap(&draft.id, "qa").unwrap();
+        let matrix = repo.matrix(&cfr.id).unwrap();
+        assert_eq!(matrix.gaps().len(), 1);
+        assert_eq!(matrix.gaps()[0].status(), CoverageStatus::Gap);
+
+        let report = build_matrix_report(&matrix, "qa");
+        let titles: Vec<&str> = report.sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["Summary", "Compliance Matrix", "Gaps"]);
+        let payload = String::from_utf8(report.canonical_payload()).unwrap();
+        assert!(payload.contains("No document covers this clause"));
+    }
+}
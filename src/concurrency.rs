@@ -0,0 +1,60 @@
+//! # Concurrency - Optimistic Record Versions
+//!
+//! Suppliers, training records, documents, CAPAs and CAPA actions carry a
+//! `row_version` that is incremented on every update. A writer presents the version it read; when
+//! the stored version differs, someone else changed the record in between
+//! and the update is rejected with [`QmsError::Conflict`] instead of
+//! silently overwriting their change. The API exposes the version as an
+//! `ETag` and expects it back in `If-Match`.
+
+use crate::error::{QmsError, Result};
+
+/// Version of a newly inserted record.
+pub const INITIAL_ROW_VERSION: i64 = 1;
+
+/// Serde default for records serialized before versions existed.
+pub fn initial_row_version() -> i64 {
+    INITIAL_ROW_VERSION
+}
+
+/// Strong entity tag for `version`.
+pub fn etag(version: i64) -> String {
+    format!("\"{}\"", version)
+}
+
+/// Version named by an `If-Match` header value. Weak tags are accepted;
+/// `*` and malformed values yield `None`.
+pub fn parse_if_match(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let value = value.strip_prefix("W/").unwrap_or(value);
+    value.strip_prefix('"')?.strip_suffix('"')?.parse().ok()
+}
+
+/// `Conflict` unless the stored version is the one the writer read.
+pub fn check_version(resource: &str, id: &str, expected: i64, actual: i64) -> Result<()> {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(QmsError::Conflict { resource: resource.to_string(), id: id.to_string(), expected, actual })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_etag_round_trip_and_version_check() {
+        assert_eq!(etag(3), "\"3\"");
+        assert_eq!(parse_if_match(&etag(3)), Some(3));
+        assert_eq!(parse_if_match(" W/\"7\" "), Some(7));
+        assert_eq!(parse_if_match("*"), None);
+        assert_eq!(parse_if_match("7"), None);
+
+        assert!(check_version("supplier", "s1", 2, 2).is_ok());
+        assert!(matches!(
+            check_version("supplier", "s1", 1, 2),
+            Err(QmsError::Conflict { expected: 1, actual: 2, .. })
+        ));
+    }
+}
@@ -1,6 +1,19 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::Path;
 use crate::{Result, QmsError};
+use crate::access_audit::AccessCategory;
+use crate::audit_review::SamplingStrategy;
+use crate::authorization::UserRole;
+use crate::capa::ActionStatus;
+use crate::config_crypto::{self, MasterKey};
+use crate::display_time::DisplayTimezone;
+use crate::feature_flags::Feature;
+use crate::i18n::Locale;
+use crate::kpi::KpiTarget;
+use crate::network_policy::NetworkPolicy;
+use crate::request_audit::{default_redacted_fields, RouteClass};
+use crate::vigilance_export::{default_pseudonymized_fields, validate_fields};
 
 /// Main configuration structure for QMS system
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +32,62 @@ pub struct Config {
     
     /// Security configuration
     pub security: SecurityConfig,
+
+    /// Email notification configuration
+    #[serde(default)]
+    pub notifications: NotificationConfig,
+
+    /// NTP clock drift monitoring
+    #[serde(default)]
+    pub time_integrity: TimeIntegrityConfig,
+
+    /// Embedded REST API server
+    #[serde(default)]
+    pub api: ApiConfig,
+
+    /// Dashboard KPI targets
+    #[serde(default)]
+    pub kpis: KpiConfig,
+
+    /// Privacy settings for data shared outside the organization
+    #[serde(default)]
+    pub privacy: PrivacyConfig,
+
+    /// Single sign-on through an OpenID Connect provider
+    #[serde(default)]
+    pub oidc: OidcConfig,
+
+    /// Background jobs (backups, overdue scans, scheduled reports)
+    #[serde(default)]
+    pub jobs: JobsConfig,
+
+    /// Write-once copy of the audit trail
+    #[serde(default)]
+    pub audit_worm: AuditWormConfig,
+
+    /// Disk space and database growth alert thresholds
+    #[serde(default)]
+    pub storage: StorageConfig,
+
+    /// Terminal UI layout preferences
+    #[serde(default)]
+    pub tui: TuiConfig,
+
+    /// Mirror of CAPA actions in an external issue tracker
+    #[serde(default)]
+    pub issue_sync: IssueSyncConfig,
+
+    /// Monitored mailbox converting emails into draft complaints
+    #[serde(default)]
+    pub email_intake: EmailIntakeConfig,
+
+    /// Alert thresholds of complaint and adverse event rates per 10,000 units
+    #[serde(default)]
+    pub complaint_rates: ComplaintRateConfig,
+
+    /// Modules switched off for this deployment (database overrides win)
+    #[serde(default)]
+    pub features: FeaturesConfig,
 }
 
 /// Application configuration
@@ -32,10 +101,22 @@ pub struct ApplicationConfig {
     
     /// ISO 13485 certificate number
     pub iso_certificate: Option<String>,
+
+    /// EUDAMED single registration number for EU vigilance reports
+    #[serde(default)]
+    pub eu_srn: Option<String>,
     
     /// Application data directory
     #[serde(default = "default_data_dir")]
     pub data_directory: String,
+
+    /// Display language for the TUI, API messages and reports
+    #[serde(default)]
+    pub locale: Locale,
+
+    /// Time zone for displayed timestamps (`UTC` or `±HH:MM`); storage is always UTC
+    #[serde(default)]
+    pub display_timezone: DisplayTimezone,
 }
 
 /// FDA compliance configuration
@@ -56,6 +137,26 @@ pub struct ComplianceConfig {
     /// CFR Part 11 compliance mode
     #[serde(default = "default_true")]
     pub cfr_part_11_mode: bool,
+
+    /// Read-access categories audited while CFR Part 11 mode is on
+    #[serde(default = "default_access_audit_scope")]
+    pub access_audit_scope: Vec<AccessCategory>,
+
+    /// Days before an MDR reporting deadline that the quality managers are warned
+    #[serde(default = "default_mdr_warning_days")]
+    pub mdr_warning_days: u32,
+
+    /// How audit entries are sampled for periodic audit trail review
+    #[serde(default)]
+    pub audit_review_strategy: SamplingStrategy,
+
+    /// Audit entries sampled per audit trail review
+    #[serde(default = "default_audit_review_sample_size")]
+    pub audit_review_sample_size: usize,
+
+    /// Free disk space (MB) the startup self-test requires for the data directory
+    #[serde(default = "default_min_free_disk_mb")]
+    pub min_free_disk_mb: u64,
 }
 
 /// Logging configuration for audit trail
@@ -87,14 +188,20 @@ pub struct LoggingConfig {
 }
 
 impl Config {
-    /// Load configuration from file
+    /// Load configuration from file, decrypting encrypted sections with the
+    /// master key from the environment (see [`crate::config_crypto`])
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
         let content = std::fs::read_to_string(path)
             .map_err(|e| QmsError::Configuration {
                 message: format!("Failed to read config file: {}", e),
             })?;
 
-        let config: Config = toml::from_str(&content)
+        let mut document = content.parse::<toml::Table>()
+            .map_err(|e| QmsError::Configuration {
+                message: format!("Failed to parse config file: {}", e),
+            })?;
+        config_crypto::decrypt_sections(&mut document, MasterKey::from_env()?.as_ref())?;
+        let config: Config = toml::Value::Table(document).try_into()
             .map_err(|e| QmsError::Configuration {
                 message: format!("Failed to parse config file: {}", e),
             })?;
@@ -113,6 +220,48 @@ impl Config {
             });
         }
 
+        if self.compliance.mdr_warning_days == 0 {
+            return Err(QmsError::Validation {
+                field: "compliance.mdr_warning_days".to_string(),
+                message: "MDR deadline warnings need at least 1 day of notice".to_string(),
+            });
+        }
+
+        if self.compliance.audit_review_sample_size == 0 {
+            return Err(QmsError::Validation {
+                field: "compliance.audit_review_sample_size".to_string(),
+                message: "Audit trail reviews must sample at least 1 entry".to_string(),
+            });
+        }
+
+        if self.api.enabled && self.api.bind.parse::<std::net::SocketAddr>().is_err() {
+            return Err(QmsError::Validation {
+                field: "api.bind".to_string(),
+                message: format!("'{}' is not a valid host:port socket address", self.api.bind),
+            });
+        }
+        if self.api.enabled && self.api.grpc.enabled && self.api.grpc.bind.parse::<std::net::SocketAddr>().is_err() {
+            return Err(QmsError::Validation {
+                field: "api.grpc.bind".to_string(),
+                message: format!("'{}' is not a valid host:port socket address", self.api.grpc.bind),
+            });
+        }
+        NetworkPolicy::from_config(&self.api.network)?;
+        self.api.tls.validate()?;
+
+        for target in &self.kpis.targets {
+            target.validate()?;
+        }
+        validate_fields(&self.privacy.pseudonymized_fields)?;
+        self.oidc.validate()?;
+        self.jobs.validate()?;
+        self.audit_worm.validate()?;
+        self.storage.validate()?;
+        self.tui.validate()?;
+        self.issue_sync.validate()?;
+        self.email_intake.validate()?;
+        self.complaint_rates.validate()?;
+
         // Validate organization name is provided
         if self.application.organization_name.trim().is_empty() {
             return Err(QmsError::Validation {
@@ -138,6 +287,20 @@ impl Default for Config {
             logging: LoggingConfig::default(),
             database: DatabaseConfig::default(),
             security: SecurityConfig::default(),
+            notifications: NotificationConfig::default(),
+            time_integrity: TimeIntegrityConfig::default(),
+            api: ApiConfig::default(),
+            kpis: KpiConfig::default(),
+            privacy: PrivacyConfig::default(),
+            oidc: OidcConfig::default(),
+            jobs: JobsConfig::default(),
+            audit_worm: AuditWormConfig::default(),
+            storage: StorageConfig::default(),
+            tui: TuiConfig::default(),
+            issue_sync: IssueSyncConfig::default(),
+            email_intake: EmailIntakeConfig::default(),
+            complaint_rates: ComplaintRateConfig::default(),
+            features: FeaturesConfig::default(),
         }
     }
 }
@@ -148,7 +311,10 @@ impl Default for ApplicationConfig {
             organization_name: "Medical Device Company".to_string(),
             fda_registration: None,
             iso_certificate: None,
+            eu_srn: None,
             data_directory: default_data_dir(),
+            locale: Locale::default(),
+            display_timezone: DisplayTimezone::default(),
         }
     }
 }
@@ -160,6 +326,11 @@ impl Default for ComplianceConfig {
             audit_retention_days: default_audit_retention(),
             require_electronic_signatures: default_true(),
             cfr_part_11_mode: default_true(),
+            access_audit_scope: default_access_audit_scope(),
+            mdr_warning_days: default_mdr_warning_days(),
+            audit_review_strategy: SamplingStrategy::default(),
+            audit_review_sample_size: default_audit_review_sample_size(),
+            min_free_disk_mb: default_min_free_disk_mb(),
         }
     }
 }
@@ -181,6 +352,10 @@ impl Default for LoggingConfig {
 fn default_true() -> bool { true }
 fn default_data_dir() -> String { "./qms-data".to_string() }
 fn default_audit_retention() -> u32 { 2555 } // 7 years
+fn default_access_audit_scope() -> Vec<AccessCategory> { AccessCategory::ALL.to_vec() }
+fn default_mdr_warning_days() -> u32 { 2 }
+fn default_audit_review_sample_size() -> usize { 25 }
+fn default_min_free_disk_mb() -> u64 { 1024 }
 fn default_log_level() -> String { "info".to_string() }
 fn default_log_file() -> String { "./qms-data/audit.log".to_string() }
 fn default_log_size() -> u64 { 10 }
@@ -258,6 +433,1002 @@ impl Default for SecurityConfig {
     }
 }
 
+/// Email notification configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationConfig {
+    /// Send email notifications (scheduled report distribution)
+    #[serde(default = "default_false")]
+    pub enabled: bool,
+
+    /// Sender address for outgoing messages
+    #[serde(default = "default_from_address")]
+    pub from_address: String,
+
+    /// Spool directory picked up by the site mail transfer agent
+    #[serde(default = "default_outbox_directory")]
+    pub outbox_directory: String,
+
+    /// Quality manager addresses alerted about critical errors
+    #[serde(default)]
+    pub quality_manager_addresses: Vec<String>,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            from_address: default_from_address(),
+            outbox_directory: default_outbox_directory(),
+            quality_manager_addresses: Vec::new(),
+        }
+    }
+}
+
+/// NTP clock drift monitoring configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeIntegrityConfig {
+    /// Compare the system clock against NTP at startup and periodically
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// NTP servers as `host` or `host:port`
+    #[serde(default = "default_ntp_servers")]
+    pub ntp_servers: Vec<String>,
+
+    /// Largest tolerated clock offset in milliseconds
+    #[serde(default = "default_max_drift_ms")]
+    pub max_drift_ms: u64,
+
+    /// Minutes between periodic checks
+    #[serde(default = "default_clock_check_interval")]
+    pub check_interval_minutes: u32,
+
+    /// Per-server response timeout in milliseconds
+    #[serde(default = "default_ntp_timeout_ms")]
+    pub timeout_ms: u64,
+
+    /// Refuse new electronic signatures while drift is unresolved
+    #[serde(default = "default_true")]
+    pub block_signatures_on_drift: bool,
+}
+
+impl Default for TimeIntegrityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ntp_servers: default_ntp_servers(),
+            max_drift_ms: default_max_drift_ms(),
+            check_interval_minutes: default_clock_check_interval(),
+            timeout_ms: default_ntp_timeout_ms(),
+            block_signatures_on_drift: true,
+        }
+    }
+}
+
+/// Embedded API server configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiConfig {
+    /// Serve the REST API from the application process, sharing its database
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Listen address as `host:port`
+    #[serde(default = "default_api_bind")]
+    pub bind: String,
+
+    /// Audit trail entries for API mutations
+    #[serde(default)]
+    pub request_audit: RequestAuditConfig,
+
+    /// Client addresses the API answers
+    #[serde(default)]
+    pub network: NetworkPolicyConfig,
+
+    /// HTTPS and client certificate authentication
+    #[serde(default)]
+    pub tls: ApiTlsConfig,
+
+    /// gRPC interface served next to the REST API
+    #[serde(default)]
+    pub grpc: GrpcConfig,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            bind: default_api_bind(),
+            request_audit: RequestAuditConfig::default(),
+            network: NetworkPolicyConfig::default(),
+            tls: ApiTlsConfig::default(),
+            grpc: GrpcConfig::default(),
+        }
+    }
+}
+
+/// gRPC interface (`proto/qms.proto`). It shares the REST API's tokens and
+/// network policy; TLS is not offered, so keep it on loopback or behind a
+/// terminating proxy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcConfig {
+    /// Serve the gRPC interface; requires the REST API to be enabled
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Listen address as `host:port`
+    #[serde(default = "default_grpc_bind")]
+    pub bind: String,
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        Self { enabled: false, bind: default_grpc_bind() }
+    }
+}
+
+/// Address filtering of API clients
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkPolicyConfig {
+    /// Addresses or CIDR ranges allowed to call the API; empty allows all
+    #[serde(default)]
+    pub allowlist: Vec<String>,
+
+    /// Addresses or CIDR ranges refused even when allowlisted
+    #[serde(default)]
+    pub denylist: Vec<String>,
+}
+
+/// TLS for the API server. With TLS on, the TUI cannot use the embedded API
+/// unless it trusts the server certificate, and shows local database values.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApiTlsConfig {
+    /// Serve HTTPS instead of HTTP
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// PEM server certificate chain
+    #[serde(default)]
+    pub cert_path: String,
+
+    /// PEM private key (PKCS#8, RSA or EC)
+    #[serde(default)]
+    pub key_path: String,
+
+    /// PEM CA certificates that client certificates are verified against
+    #[serde(default)]
+    pub client_ca_path: Option<String>,
+
+    /// Refuse clients without a valid certificate (mutual TLS)
+    #[serde(default)]
+    pub require_client_cert: bool,
+}
+
+impl ApiTlsConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        for (field, path) in [("api.tls.cert_path", &self.cert_path), ("api.tls.key_path", &self.key_path)] {
+            if path.trim().is_empty() {
+                return Err(QmsError::Validation {
+                    field: field.to_string(),
+                    message: "Required when TLS is enabled".to_string(),
+                });
+            }
+        }
+        if self.require_client_cert && self.client_ca_path.is_none() {
+            return Err(QmsError::Validation {
+                field: "api.tls.client_ca_path".to_string(),
+                message: "Client certificates cannot be required without a CA to verify them".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Audit logging of API mutations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestAuditConfig {
+    /// Log POST/PUT/PATCH/DELETE requests to the audit trail
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Route classes whose mutations are logged
+    #[serde(default = "RouteClass::all")]
+    pub route_classes: Vec<RouteClass>,
+
+    /// JSON fields replaced by `[redacted]` in the payload summary
+    #[serde(default = "default_redacted_fields")]
+    pub redacted_fields: Vec<String>,
+
+    /// Payload summaries are cut to this many characters
+    #[serde(default = "default_payload_summary_chars")]
+    pub max_payload_chars: usize,
+}
+
+impl RequestAuditConfig {
+    /// Whether mutations of `class` are logged.
+    pub fn audits(&self, class: RouteClass) -> bool {
+        self.enabled && self.route_classes.contains(&class)
+    }
+}
+
+impl Default for RequestAuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            route_classes: RouteClass::all(),
+            redacted_fields: default_redacted_fields(),
+            max_payload_chars: default_payload_summary_chars(),
+        }
+    }
+}
+
+fn default_payload_summary_chars() -> usize {
+    512
+}
+
+/// Dashboard KPI target configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KpiConfig {
+    /// Targets rated red/amber/green on the dashboard, `/metrics` and the
+    /// compliance report; per-metric overrides in the database take precedence
+    #[serde(default = "KpiTarget::defaults")]
+    pub targets: Vec<KpiTarget>,
+}
+
+impl Default for KpiConfig {
+    fn default() -> Self {
+        Self { targets: KpiTarget::defaults() }
+    }
+}
+
+/// Privacy configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivacyConfig {
+    /// Vigilance export columns replaced by pseudonym tokens in shared exports
+    #[serde(default = "default_pseudonymized_fields")]
+    pub pseudonymized_fields: Vec<String>,
+}
+
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        Self { pseudonymized_fields: default_pseudonymized_fields() }
+    }
+}
+
+/// OpenID Connect single sign-on configuration (authorization-code flow)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcConfig {
+    /// Offer SSO login on the API
+    #[serde(default = "default_false")]
+    pub enabled: bool,
+
+    /// Issuer URL; endpoints are discovered from its
+    /// `/.well-known/openid-configuration`
+    #[serde(default)]
+    pub issuer: String,
+
+    /// Client id registered with the provider
+    #[serde(default)]
+    pub client_id: String,
+
+    /// Client secret; falls back to the `QMS_OIDC_CLIENT_SECRET` environment variable
+    #[serde(default)]
+    pub client_secret: Option<String>,
+
+    /// Callback URL registered with the provider (`.../auth/oidc/callback`)
+    #[serde(default)]
+    pub redirect_uri: String,
+
+    /// Requested scopes
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: Vec<String>,
+
+    /// ID token claim matched against `users.username`
+    #[serde(default = "default_oidc_username_claim")]
+    pub username_claim: String,
+
+    /// ID token claim listing the user's groups or app roles
+    #[serde(default = "default_oidc_roles_claim")]
+    pub roles_claim: String,
+
+    /// Group or app role name to local role; the highest mapped role wins
+    #[serde(default)]
+    pub role_mapping: BTreeMap<String, UserRole>,
+
+    /// Role for users without a mapped group; without one they are refused
+    #[serde(default)]
+    pub default_role: Option<UserRole>,
+
+    /// Create unknown users on their first SSO login
+    #[serde(default = "default_false")]
+    pub jit_provisioning: bool,
+}
+
+impl OidcConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        for (field, value) in [("oidc.issuer", &self.issuer), ("oidc.client_id", &self.client_id), ("oidc.redirect_uri", &self.redirect_uri)] {
+            if value.trim().is_empty() {
+                return Err(QmsError::Validation {
+                    field: field.to_string(),
+                    message: "Required when OIDC login is enabled".to_string(),
+                });
+            }
+        }
+        if !self.issuer.starts_with("https://") {
+            return Err(QmsError::Validation {
+                field: "oidc.issuer".to_string(),
+                message: format!("'{}' must be an https URL", self.issuer),
+            });
+        }
+        if !self.scopes.iter().any(|scope| scope == "openid") {
+            return Err(QmsError::Validation {
+                field: "oidc.scopes".to_string(),
+                message: "The 'openid' scope is required".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for OidcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            issuer: String::new(),
+            client_id: String::new(),
+            client_secret: None,
+            redirect_uri: String::new(),
+            scopes: default_oidc_scopes(),
+            username_claim: default_oidc_username_claim(),
+            roles_claim: default_oidc_roles_claim(),
+            role_mapping: BTreeMap::new(),
+            default_role: None,
+            jit_provisioning: false,
+        }
+    }
+}
+
+/// Background job scheduler configuration. Backups run every
+/// `database.backup_interval_hours`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobsConfig {
+    /// Run due jobs in the background while the application is up
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Minutes between scans for overdue reviews, findings and training
+    #[serde(default = "default_overdue_scan_interval")]
+    pub overdue_scan_interval_minutes: u32,
+
+    /// Minutes between checks for due scheduled reports
+    #[serde(default = "default_report_check_interval")]
+    pub report_check_interval_minutes: u32,
+
+    /// Hours between WAL checkpoint, vacuum and integrity check runs
+    #[serde(default = "default_maintenance_interval")]
+    pub maintenance_interval_hours: u32,
+
+    /// Hours between analytics fact table snapshots
+    #[serde(default = "default_analytics_export_interval")]
+    pub analytics_export_interval_hours: u32,
+
+    /// Hours between checks for a due quarterly access review campaign
+    #[serde(default = "default_access_review_check_interval")]
+    pub access_review_check_interval_hours: u32,
+
+    /// Minutes between disk space and database growth samples
+    #[serde(default = "default_storage_check_interval")]
+    pub storage_check_interval_minutes: u32,
+}
+
+impl JobsConfig {
+    pub fn validate(&self) -> Result<()> {
+        for (field, minutes) in [
+            ("jobs.overdue_scan_interval_minutes", self.overdue_scan_interval_minutes),
+            ("jobs.report_check_interval_minutes", self.report_check_interval_minutes),
+            ("jobs.maintenance_interval_hours", self.maintenance_interval_hours),
+            ("jobs.analytics_export_interval_hours", self.analytics_export_interval_hours),
+            ("jobs.access_review_check_interval_hours", self.access_review_check_interval_hours),
+            ("jobs.storage_check_interval_minutes", self.storage_check_interval_minutes),
+        ] {
+            if minutes == 0 {
+                return Err(QmsError::Validation {
+                    field: field.to_string(),
+                    message: "Interval must be at least one minute".to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            overdue_scan_interval_minutes: default_overdue_scan_interval(),
+            report_check_interval_minutes: default_report_check_interval(),
+            maintenance_interval_hours: default_maintenance_interval(),
+            analytics_export_interval_hours: default_analytics_export_interval(),
+            access_review_check_interval_hours: default_access_review_check_interval(),
+            storage_check_interval_minutes: default_storage_check_interval(),
+        }
+    }
+}
+
+fn default_overdue_scan_interval() -> u32 {
+    60
+}
+
+fn default_report_check_interval() -> u32 {
+    60
+}
+
+fn default_maintenance_interval() -> u32 {
+    24
+}
+
+fn default_analytics_export_interval() -> u32 {
+    24
+}
+
+fn default_access_review_check_interval() -> u32 {
+    24
+}
+
+fn default_storage_check_interval() -> u32 {
+    60
+}
+
+/// Jira connector mirroring CAPA actions as issues; statuses and comments
+/// are synced back by the `issue_sync` job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssueSyncConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Tracker base URL, e.g. `https://example.atlassian.net`
+    #[serde(default)]
+    pub base_url: String,
+
+    /// Project the issues are created in
+    #[serde(default)]
+    pub project_key: String,
+
+    #[serde(default = "default_issue_type")]
+    pub issue_type: String,
+
+    /// Account the connector signs in with
+    #[serde(default)]
+    pub username: String,
+
+    /// API token; falls back to the `QMS_ISSUE_TRACKER_TOKEN` environment variable
+    #[serde(default)]
+    pub api_token: Option<String>,
+
+    /// Minutes between sync runs
+    #[serde(default = "default_issue_sync_interval")]
+    pub sync_interval_minutes: u32,
+
+    /// Tracker status to CAPA action status; the first tracker status
+    /// mapped to an action status is the one set in the tracker
+    #[serde(default = "default_issue_status_mapping")]
+    pub status_mapping: BTreeMap<String, ActionStatus>,
+
+    /// QMS user id to tracker account id for the issue assignee
+    #[serde(default)]
+    pub assignee_mapping: BTreeMap<String, String>,
+
+    /// What happens when both sides changed the status since the last sync
+    #[serde(default)]
+    pub conflict_policy: ConflictPolicy,
+}
+
+/// Resolution of a status changed on both sides between two sync runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Change neither side and flag the action for a person to resolve
+    #[default]
+    Flag,
+    /// Overwrite the tracker with the CAPA action status
+    QmsWins,
+    /// Overwrite the CAPA action status with the tracker's
+    TrackerWins,
+}
+
+impl IssueSyncConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        for (field, value) in [("issue_sync.base_url", &self.base_url), ("issue_sync.project_key", &self.project_key)] {
+            if value.trim().is_empty() {
+                return Err(QmsError::Validation {
+                    field: field.to_string(),
+                    message: "Required when issue sync is enabled".to_string(),
+                });
+            }
+        }
+        if self.sync_interval_minutes == 0 {
+            return Err(QmsError::Validation {
+                field: "issue_sync.sync_interval_minutes".to_string(),
+                message: "Must be at least 1".to_string(),
+            });
+        }
+        // Verification is an electronically signed step of the QMS
+        if let Some((status, _)) = self.status_mapping.iter().find(|(_, action)| **action == ActionStatus::Verified) {
+            return Err(QmsError::Validation {
+                field: "issue_sync.status_mapping".to_string(),
+                message: format!("Tracker status '{}' cannot verify a CAPA action", status),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for IssueSyncConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            base_url: String::new(),
+            project_key: String::new(),
+            issue_type: default_issue_type(),
+            username: String::new(),
+            api_token: None,
+            sync_interval_minutes: default_issue_sync_interval(),
+            status_mapping: default_issue_status_mapping(),
+            assignee_mapping: BTreeMap::new(),
+            conflict_policy: ConflictPolicy::default(),
+        }
+    }
+}
+
+/// IMAP mailbox polled by the `email_intake` job; each new message becomes
+/// a draft complaint in the triage queue.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailIntakeConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// IMAP server, connected with TLS
+    #[serde(default)]
+    pub host: String,
+
+    #[serde(default = "default_imap_port")]
+    pub port: u16,
+
+    #[serde(default)]
+    pub username: String,
+
+    /// Mailbox password; falls back to the `QMS_INTAKE_MAILBOX_PASSWORD` environment variable
+    #[serde(default)]
+    pub password: Option<String>,
+
+    /// Folder monitored for unread messages
+    #[serde(default = "default_intake_folder")]
+    pub folder: String,
+
+    /// Minutes between polls
+    #[serde(default = "default_email_poll_interval")]
+    pub poll_interval_minutes: u32,
+
+    /// Attachments above this size are not stored
+    #[serde(default = "default_max_attachment_mb")]
+    pub max_attachment_mb: u32,
+
+    /// A message from the same sender with the same subject within this many
+    /// days of an untriaged complaint is filed with that complaint
+    #[serde(default = "default_duplicate_window_days")]
+    pub duplicate_window_days: u32,
+}
+
+impl EmailIntakeConfig {
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        for (field, value) in [("email_intake.host", &self.host), ("email_intake.username", &self.username)] {
+            if value.trim().is_empty() {
+                return Err(QmsError::Validation {
+                    field: field.to_string(),
+                    message: "Required when email intake is enabled".to_string(),
+                });
+            }
+        }
+        if self.poll_interval_minutes == 0 {
+            return Err(QmsError::Validation {
+                field: "email_intake.poll_interval_minutes".to_string(),
+                message: "Must be at least 1".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for EmailIntakeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::new(),
+            port: default_imap_port(),
+            username: String::new(),
+            password: None,
+            folder: default_intake_folder(),
+            poll_interval_minutes: default_email_poll_interval(),
+            max_attachment_mb: default_max_attachment_mb(),
+            duplicate_window_days: default_duplicate_window_days(),
+        }
+    }
+}
+
+fn default_imap_port() -> u16 {
+    993
+}
+
+fn default_intake_folder() -> String {
+    "INBOX".to_string()
+}
+
+fn default_email_poll_interval() -> u32 {
+    5
+}
+
+fn default_max_attachment_mb() -> u32 {
+    25
+}
+
+fn default_duplicate_window_days() -> u32 {
+    14
+}
+
+fn default_issue_type() -> String {
+    "Task".to_string()
+}
+
+fn default_issue_sync_interval() -> u32 {
+    15
+}
+
+fn default_issue_status_mapping() -> BTreeMap<String, ActionStatus> {
+    BTreeMap::from([
+        ("Done".to_string(), ActionStatus::Completed),
+        ("In Progress".to_string(), ActionStatus::InProgress),
+        ("To Do".to_string(), ActionStatus::Planned),
+    ])
+}
+
+/// Monthly rates, per 10,000 units shipped, above which complaint and adverse
+/// event trends are flagged.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ComplaintRateConfig {
+    #[serde(default = "default_complaint_rate_threshold")]
+    pub complaint_threshold: f64,
+
+    #[serde(default = "default_adverse_event_rate_threshold")]
+    pub adverse_event_threshold: f64,
+}
+
+impl ComplaintRateConfig {
+    pub fn validate(&self) -> Result<()> {
+        for (field, value) in [
+            ("complaint_rates.complaint_threshold", self.complaint_threshold),
+            ("complaint_rates.adverse_event_threshold", self.adverse_event_threshold),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                return Err(QmsError::Validation {
+                    field: field.to_string(),
+                    message: format!("Must be a non-negative rate per 10,000 units, got {}", value),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for ComplaintRateConfig {
+    fn default() -> Self {
+        Self {
+            complaint_threshold: default_complaint_rate_threshold(),
+            adverse_event_threshold: default_adverse_event_rate_threshold(),
+        }
+    }
+}
+
+fn default_complaint_rate_threshold() -> f64 {
+    5.0
+}
+
+fn default_adverse_event_rate_threshold() -> f64 {
+    1.0
+}
+
+/// Deployment default of the feature flags; every module is enabled unless
+/// listed here.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeaturesConfig {
+    /// e.g. `["post_market", "notifications"]`
+    #[serde(default)]
+    pub disabled: Vec<Feature>,
+}
+
+/// Free space thresholds for the data directory volume. Alerts go to
+/// `notifications.quality_manager_addresses`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// Warn below this much free space (MB)
+    #[serde(default = "default_storage_warning_free_mb")]
+    pub warning_free_mb: u64,
+
+    /// Raise a critical alert below this much free space (MB)
+    #[serde(default = "default_storage_critical_free_mb")]
+    pub critical_free_mb: u64,
+
+    /// Warn when projected growth fills the disk within this many days
+    #[serde(default = "default_storage_warning_days_until_full")]
+    pub warning_days_until_full: u32,
+
+    /// Days of samples used to project growth
+    #[serde(default = "default_storage_projection_window_days")]
+    pub projection_window_days: u32,
+}
+
+impl StorageConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.critical_free_mb >= self.warning_free_mb {
+            return Err(QmsError::Validation {
+                field: "storage.critical_free_mb".to_string(),
+                message: "Critical threshold must be below storage.warning_free_mb".to_string(),
+            });
+        }
+        if self.projection_window_days == 0 {
+            return Err(QmsError::Validation {
+                field: "storage.projection_window_days".to_string(),
+                message: "Must be at least 1".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            warning_free_mb: default_storage_warning_free_mb(),
+            critical_free_mb: default_storage_critical_free_mb(),
+            warning_days_until_full: default_storage_warning_days_until_full(),
+            projection_window_days: default_storage_projection_window_days(),
+        }
+    }
+}
+
+fn default_storage_warning_free_mb() -> u64 {
+    5120
+}
+
+fn default_storage_critical_free_mb() -> u64 {
+    1024
+}
+
+fn default_storage_warning_days_until_full() -> u32 {
+    30
+}
+
+fn default_storage_projection_window_days() -> u32 {
+    14
+}
+
+/// Terminal UI layout: whether list tabs show a detail pane beside the list,
+/// and how the width is split. Users resize the split in the TUI and their
+/// layout is written back to `[tui.layouts]` (see [`save_tui_layout`]).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TuiConfig {
+    /// Plain rendering for screen readers and limited terminals: ASCII
+    /// markers instead of emoji and glyphs, no colors (`--accessible`)
+    #[serde(default)]
+    pub accessible: bool,
+
+    /// Layout for users who have not saved their own
+    #[serde(default)]
+    pub default_layout: TuiLayout,
+
+    /// Saved layouts by user id
+    #[serde(default)]
+    pub layouts: BTreeMap<String, TuiLayout>,
+}
+
+impl TuiConfig {
+    pub fn layout_for(&self, user_id: &str) -> TuiLayout {
+        self.layouts.get(user_id).copied().unwrap_or(self.default_layout)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let layouts = std::iter::once(("default_layout".to_string(), &self.default_layout))
+            .chain(self.layouts.iter().map(|(user, layout)| (format!("layouts.{}", user), layout)));
+        for (name, layout) in layouts {
+            if !(TuiLayout::MIN_LIST_PERCENT..=TuiLayout::MAX_LIST_PERCENT).contains(&layout.list_percent) {
+                return Err(QmsError::Validation {
+                    field: format!("tui.{}.list_percent", name),
+                    message: format!(
+                        "Must be between {} and {}",
+                        TuiLayout::MIN_LIST_PERCENT,
+                        TuiLayout::MAX_LIST_PERCENT
+                    ),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// List and detail pane arrangement of one user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TuiLayout {
+    /// Show the selected record's details beside the list
+    #[serde(default = "default_true")]
+    pub detail_pane: bool,
+
+    /// Share of the width given to the list pane (percent)
+    #[serde(default = "default_tui_list_percent")]
+    pub list_percent: u16,
+}
+
+impl TuiLayout {
+    pub const MIN_LIST_PERCENT: u16 = 20;
+    pub const MAX_LIST_PERCENT: u16 = 80;
+
+    /// The layout with the split moved by `delta` percent, within bounds.
+    pub fn resized(self, delta: i16) -> Self {
+        let list_percent = (self.list_percent as i16 + delta)
+            .clamp(Self::MIN_LIST_PERCENT as i16, Self::MAX_LIST_PERCENT as i16) as u16;
+        Self { list_percent, ..self }
+    }
+}
+
+impl Default for TuiLayout {
+    fn default() -> Self {
+        Self { detail_pane: true, list_percent: default_tui_list_percent() }
+    }
+}
+
+fn default_tui_list_percent() -> u16 {
+    60
+}
+
+/// Save `layout` for `user_id` under `[tui.layouts]` in the configuration
+/// file at `path`. Other sections are written back as read, so encrypted
+/// sections stay encrypted; comments are not preserved.
+pub fn save_tui_layout<P: AsRef<Path>>(path: P, user_id: &str, layout: &TuiLayout) -> Result<()> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| QmsError::Configuration { message: format!("Failed to read config file: {}", e) })?;
+    let mut document = content
+        .parse::<toml::Table>()
+        .map_err(|e| QmsError::Configuration { message: format!("Failed to parse config file: {}", e) })?;
+    let mut table = &mut document;
+    for name in ["tui", "layouts"] {
+        let entry = table.entry(name).or_insert(toml::Value::Table(toml::Table::new()));
+        table = entry.as_table_mut().ok_or_else(|| QmsError::Configuration {
+            message: format!("Config key '{}' must be a table", name),
+        })?;
+    }
+    let value = toml::Value::try_from(layout).map_err(|e| QmsError::Serialization { message: e.to_string() })?;
+    table.insert(user_id.to_string(), value);
+    let content =
+        toml::to_string_pretty(&document).map_err(|e| QmsError::Serialization { message: e.to_string() })?;
+    std::fs::write(path, content)
+        .map_err(|e| QmsError::FileSystem { path: path.display().to_string(), message: e.to_string() })
+}
+
+/// Write-once (WORM) audit storage: sealed, append-only segment files that
+/// mirror the `audit_trail` table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditWormConfig {
+    /// Copy new audit entries into sealed segments in the background
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Segment directory; `<data_directory>/audit-worm` when unset. Point it
+    /// at a mount with retention locking for non-rewritable storage.
+    #[serde(default)]
+    pub directory: Option<String>,
+
+    /// Minutes between copies of new audit entries
+    #[serde(default = "default_worm_sync_interval")]
+    pub sync_interval_minutes: u32,
+
+    /// Entries per segment file
+    #[serde(default = "default_worm_segment_entries")]
+    pub segment_max_entries: u32,
+}
+
+impl AuditWormConfig {
+    pub fn validate(&self) -> Result<()> {
+        for (field, value) in [
+            ("audit_worm.sync_interval_minutes", self.sync_interval_minutes),
+            ("audit_worm.segment_max_entries", self.segment_max_entries),
+        ] {
+            if value == 0 {
+                return Err(QmsError::Validation {
+                    field: field.to_string(),
+                    message: "Must be at least 1".to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for AuditWormConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: None,
+            sync_interval_minutes: default_worm_sync_interval(),
+            segment_max_entries: default_worm_segment_entries(),
+        }
+    }
+}
+
+fn default_worm_sync_interval() -> u32 {
+    15
+}
+
+fn default_worm_segment_entries() -> u32 {
+    10_000
+}
+
+fn default_oidc_scopes() -> Vec<String> {
+    vec!["openid".to_string(), "profile".to_string(), "email".to_string()]
+}
+
+fn default_oidc_username_claim() -> String {
+    "preferred_username".to_string()
+}
+
+fn default_oidc_roles_claim() -> String {
+    "roles".to_string()
+}
+
+fn default_api_bind() -> String {
+    "127.0.0.1:3000".to_string()
+}
+
+fn default_grpc_bind() -> String {
+    "127.0.0.1:50051".to_string()
+}
+
+// Default value functions for time integrity config
+fn default_ntp_servers() -> Vec<String> {
+    vec!["pool.ntp.org".to_string(), "time.nist.gov".to_string()]
+}
+
+fn default_max_drift_ms() -> u64 {
+    1000
+}
+
+fn default_clock_check_interval() -> u32 {
+    60
+}
+
+fn default_ntp_timeout_ms() -> u64 {
+    2000
+}
+
+// Default value functions for notification config
+fn default_from_address() -> String {
+    "qms@localhost".to_string()
+}
+
+fn default_outbox_directory() -> String {
+    "./qms-data/outbox".to_string()
+}
+
 // Default value functions for database config
 fn default_database_url() -> String {
     "data/qms.db".to_string()
@@ -321,6 +1492,183 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_api_section() {
+        let mut config = Config::default();
+        assert!(config.api.enabled);
+        assert_eq!(config.api.bind, "127.0.0.1:3000");
+
+        config.api.bind = "localhost".to_string();
+        assert!(config.validate().is_err());
+        config.api.enabled = false;
+        assert!(config.validate().is_ok(), "bind is only checked when the server is enabled");
+
+        let api: ApiConfig = toml::from_str("bind = \"0.0.0.0:8080\"").unwrap();
+        assert!(api.enabled);
+        assert_eq!(api.bind, "0.0.0.0:8080");
+        assert!(!api.grpc.enabled);
+        assert_eq!(api.grpc.bind, "127.0.0.1:50051");
+        assert!(api.request_audit.audits(RouteClass::Authentication));
+
+        let api: ApiConfig = toml::from_str("[request_audit]\nroute_classes = [\"records\"]\n").unwrap();
+        assert!(api.request_audit.audits(RouteClass::Records));
+        assert!(!api.request_audit.audits(RouteClass::Authentication));
+        assert!(api.request_audit.redacted_fields.contains(&"password".to_string()));
+    }
+
+    #[test]
+    fn test_kpi_section() {
+        let mut config = Config::default();
+        assert_eq!(config.kpis.targets, KpiTarget::defaults());
+
+        let kpis: KpiConfig = toml::from_str("[[targets]]\nmetric = \"capa_on_time_closure\"\ntarget = 85.0\n").unwrap();
+        assert_eq!(kpis.targets.len(), 1);
+        assert_eq!(kpis.targets[0].amber_margin, 5.0);
+
+        config.kpis.targets[0].target = 150.0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_privacy_section() {
+        let mut config = Config::default();
+        assert_eq!(config.privacy.pseudonymized_fields, vec!["reporter"]);
+        config.privacy.pseudonymized_fields.push("patient_name".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_oidc_section() {
+        let mut config = Config::default();
+        assert!(!config.oidc.enabled);
+        assert!(config.validate().is_ok());
+
+        config.oidc = toml::from_str(
+            "enabled = true\nissuer = \"https://login.example.com\"\nclient_id = \"qms\"\n\
+             redirect_uri = \"https://qms.example.com/auth/oidc/callback\"\n\
+             [role_mapping]\nqms-approvers = \"QualityManager\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.oidc.role_mapping["qms-approvers"], UserRole::QualityManager);
+        assert_eq!(config.oidc.username_claim, "preferred_username");
+        assert!(config.validate().is_ok());
+
+        config.oidc.issuer = "http://login.example.com".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_jobs_section() {
+        let mut config = Config::default();
+        assert!(config.jobs.enabled);
+        assert_eq!(config.jobs.overdue_scan_interval_minutes, 60);
+        assert_eq!(config.jobs.maintenance_interval_hours, 24);
+        assert_eq!(config.jobs.analytics_export_interval_hours, 24);
+        assert_eq!(config.jobs.access_review_check_interval_hours, 24);
+        assert_eq!(config.jobs.storage_check_interval_minutes, 60);
+        config.jobs.report_check_interval_minutes = 0;
+        assert!(matches!(config.validate(), Err(QmsError::Validation { field, .. }) if field == "jobs.report_check_interval_minutes"));
+    }
+
+    #[test]
+    fn test_audit_worm_section() {
+        let mut config = Config::default();
+        config.audit_worm = toml::from_str("enabled = true\ndirectory = \"/mnt/worm/qms\"\n").unwrap();
+        assert!(config.audit_worm.enabled);
+        assert_eq!(config.audit_worm.directory.as_deref(), Some("/mnt/worm/qms"));
+        assert_eq!(config.audit_worm.sync_interval_minutes, 15);
+        config.audit_worm.segment_max_entries = 0;
+        assert!(matches!(config.validate(), Err(QmsError::Validation { field, .. }) if field == "audit_worm.segment_max_entries"));
+    }
+
+    #[test]
+    fn test_issue_sync_section() {
+        let mut config = Config::default();
+        assert!(!config.issue_sync.enabled);
+        assert_eq!(config.issue_sync.status_mapping.get("Done"), Some(&ActionStatus::Completed));
+        config.issue_sync = toml::from_str(
+            "enabled = true\nbase_url = \"https://tracker.example.com\"\nproject_key = \"ENG\"\n\
+             conflict_policy = \"qms_wins\"\n[status_mapping]\nClosed = \"Verified\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.issue_sync.conflict_policy, ConflictPolicy::QmsWins);
+        assert!(matches!(config.validate(), Err(QmsError::Validation { field, .. }) if field == "issue_sync.status_mapping"));
+    }
+
+    #[test]
+    fn test_email_intake_section() {
+        let mut config = Config::default();
+        assert!(!config.email_intake.enabled);
+        config.email_intake =
+            toml::from_str("enabled = true\nhost = \"imap.example.com\"\nusername = \"complaints\"\n").unwrap();
+        assert_eq!((config.email_intake.port, config.email_intake.folder.as_str()), (993, "INBOX"));
+        assert!(config.validate().is_ok());
+        config.email_intake.poll_interval_minutes = 0;
+        let error = config.validate().unwrap_err();
+        assert!(matches!(error, QmsError::Validation { field, .. } if field == "email_intake.poll_interval_minutes"));
+    }
+
+    #[test]
+    fn test_complaint_rates_section() {
+        let mut config = Config::default();
+        assert_eq!(config.complaint_rates, ComplaintRateConfig::default());
+        config.complaint_rates = toml::from_str("complaint_threshold = 2.5\n").unwrap();
+        assert_eq!(config.complaint_rates.adverse_event_threshold, 1.0);
+        assert!(config.validate().is_ok());
+        config.complaint_rates.adverse_event_threshold = -1.0;
+        let error = config.validate().unwrap_err();
+        assert!(
+            matches!(error, QmsError::Validation { field, .. } if field == "complaint_rates.adverse_event_threshold")
+        );
+    }
+
+    #[test]
+    fn test_storage_section() {
+        let mut config = Config::default();
+        assert_eq!((config.storage.warning_free_mb, config.storage.critical_free_mb), (5120, 1024));
+        assert_eq!(config.storage.projection_window_days, 14);
+        config.storage = toml::from_str("warning_free_mb = 800\n").unwrap();
+        assert_eq!(config.storage.warning_days_until_full, 30);
+        assert!(matches!(config.validate(), Err(QmsError::Validation { field, .. }) if field == "storage.critical_free_mb"));
+    }
+
+    #[test]
+    fn test_tui_layouts_per_user() {
+        let mut config = Config::default();
+        assert_eq!(config.tui.layout_for("qa"), TuiLayout { detail_pane: true, list_percent: 60 });
+        assert!(!config.tui.accessible);
+        assert_eq!(TuiLayout::default().resized(-50).list_percent, TuiLayout::MIN_LIST_PERCENT);
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("qms.toml");
+        std::fs::write(&path, "[oidc]\nencrypted = \"enc:v1:abc\"\n").unwrap();
+        let layout = TuiLayout { detail_pane: false, list_percent: 45 };
+        save_tui_layout(&path, "qa", &layout).unwrap();
+        let saved: toml::Table = std::fs::read_to_string(&path).unwrap().parse().unwrap();
+        assert_eq!(saved["oidc"]["encrypted"].as_str(), Some("enc:v1:abc"));
+        config.tui = saved["tui"].clone().try_into().unwrap();
+        assert_eq!((config.tui.layout_for("qa"), config.tui.layout_for("other")), (layout, TuiLayout::default()));
+
+        config.tui.layouts.insert("qa".to_string(), TuiLayout { detail_pane: true, list_percent: 95 });
+        assert!(matches!(config.validate(), Err(QmsError::Validation { field, .. }) if field == "tui.layouts.qa.list_percent"));
+    }
+
+    #[test]
+    fn test_api_network_section() {
+        let mut config = Config::default();
+        assert!(config.api.network.allowlist.is_empty() && !config.api.tls.enabled);
+        config.api = toml::from_str(
+            "[network]\nallowlist = [\"10.0.0.0/8\"]\ndenylist = [\"10.66.0.0/16\"]\n\
+             [tls]\nenabled = true\ncert_path = \"api.crt\"\nkey_path = \"api.key\"\nrequire_client_cert = true\n",
+        )
+        .unwrap();
+        assert_eq!(config.api.bind, default_api_bind());
+        assert!(matches!(config.validate(), Err(QmsError::Validation { field, .. }) if field == "api.tls.client_ca_path"));
+        config.api.tls.client_ca_path = Some("clients-ca.pem".to_string());
+        config.api.network.allowlist.push("intranet".to_string());
+        assert!(matches!(config.validate(), Err(QmsError::Validation { field, .. }) if field == "api.network.allowlist"));
+    }
+
     #[test]
     fn test_config_sample_generation() {
         let sample = Config::generate_sample();
@@ -338,5 +1686,8 @@ mod tests {
         assert!(config.compliance.cfr_part_11_mode);
         assert!(config.compliance.require_electronic_signatures);
         assert_eq!(config.compliance.audit_retention_days, 2555); // 7 years
+        assert_eq!(config.compliance.mdr_warning_days, 2);
+        assert_eq!(config.compliance.audit_review_sample_size, 25);
+        assert_eq!(config.compliance.min_free_disk_mb, 1024);
     }
 }
\ No newline at end of file
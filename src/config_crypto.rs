@@ -0,0 +1,255 @@
+//! # Config Crypto - Encrypted Configuration Sections
+//!
+//! Sections holding credentials (`[oidc]`, `[notifications]`, ...) can be
+//! stored encrypted so a configuration file can live in an infrastructure
+//! repository without exposing secrets. An encrypted section is a table with
+//! a single `encrypted` key:
+//!
+//! ```toml
+//! [oidc]
+//! encrypted = "enc:v1:..."
+//! ```
+//!
+//! The value is the section's TOML, sealed with AES-256-GCM under a master
+//! key taken from `QMS_CONFIG_KEY` (base64) or the file named by
+//! `QMS_CONFIG_KEY_FILE`. The section path is bound as associated data, so
+//! a ciphertext moved to another section does not decrypt. Sections are
+//! decrypted in memory when the configuration is loaded.
+
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::error::{QmsError, Result};
+
+/// Environment variable holding the base64 master key.
+pub const MASTER_KEY_ENV: &str = "QMS_CONFIG_KEY";
+
+/// Environment variable naming a file that holds the base64 master key.
+pub const MASTER_KEY_FILE_ENV: &str = "QMS_CONFIG_KEY_FILE";
+
+/// Prefix of encrypted section values; the version allows a later format.
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// Key of the single entry of an encrypted section.
+const ENCRYPTED_KEY: &str = "encrypted";
+
+/// AES-256 master key for configuration sections.
+pub struct MasterKey([u8; 32]);
+
+impl MasterKey {
+    pub fn generate() -> Result<Self> {
+        let mut key = [0u8; 32];
+        SystemRandom::new().fill(&mut key).map_err(|_| encryption_error("random key generation failed"))?;
+        Ok(Self(key))
+    }
+
+    pub fn from_base64(encoded: &str) -> Result<Self> {
+        let bytes = STANDARD.decode(encoded.trim()).map_err(|e| QmsError::Configuration {
+            message: format!("Config master key is not valid base64: {}", e),
+        })?;
+        let key: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| QmsError::Configuration { message: "Config master key must be 32 bytes".to_string() })?;
+        Ok(Self(key))
+    }
+
+    pub fn to_base64(&self) -> String {
+        STANDARD.encode(self.0)
+    }
+
+    /// Key from `QMS_CONFIG_KEY` or `QMS_CONFIG_KEY_FILE`; `None` when
+    /// neither is set.
+    pub fn from_env() -> Result<Option<Self>> {
+        if let Ok(encoded) = std::env::var(MASTER_KEY_ENV) {
+            return Self::from_base64(&encoded).map(Some);
+        }
+        match std::env::var(MASTER_KEY_FILE_ENV) {
+            Ok(path) => {
+                let encoded = std::fs::read_to_string(&path)
+                    .map_err(|e| QmsError::FileSystem { path: path.clone(), message: e.to_string() })?;
+                Self::from_base64(&encoded).map(Some)
+            }
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn aead_key(&self) -> Result<LessSafeKey> {
+        let key = UnboundKey::new(&AES_256_GCM, &self.0).map_err(|_| encryption_error("invalid key"))?;
+        Ok(LessSafeKey::new(key))
+    }
+
+    /// Seal `section`'s TOML as an `enc:v1:` value bound to `path`.
+    pub fn encrypt_section(&self, path: &str, section: &toml::Table) -> Result<String> {
+        let plaintext = toml::to_string(section)
+            .map_err(|e| QmsError::Serialization { message: format!("Section {}: {}", path, e) })?;
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new().fill(&mut nonce).map_err(|_| encryption_error("random nonce generation failed"))?;
+        let mut sealed = plaintext.into_bytes();
+        self.aead_key()?
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(path.as_bytes()), &mut sealed)
+            .map_err(|_| encryption_error("sealing failed"))?;
+        let mut blob = nonce.to_vec();
+        blob.extend_from_slice(&sealed);
+        Ok(format!("{}{}", ENCRYPTED_PREFIX, STANDARD.encode(blob)))
+    }
+
+    /// Open an `enc:v1:` value sealed for `path`.
+    pub fn decrypt_section(&self, path: &str, value: &str) -> Result<toml::Table> {
+        let undecryptable =
+            || encryption_error(&format!("config section '{}' could not be decrypted (wrong key or altered)", path));
+        let encoded = value.strip_prefix(ENCRYPTED_PREFIX).ok_or_else(undecryptable)?;
+        let mut blob = STANDARD.decode(encoded).map_err(|_| undecryptable())?;
+        if blob.len() < NONCE_LEN {
+            return Err(undecryptable());
+        }
+        let mut sealed = blob.split_off(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(&blob).map_err(|_| undecryptable())?;
+        let plaintext = self
+            .aead_key()?
+            .open_in_place(nonce, Aad::from(path.as_bytes()), &mut sealed)
+            .map_err(|_| undecryptable())?;
+        let plaintext = std::str::from_utf8(plaintext).map_err(|_| undecryptable())?;
+        plaintext.parse::<toml::Table>().map_err(|e| QmsError::Configuration {
+            message: format!("Decrypted config section '{}' is not valid TOML: {}", path, e),
+        })
+    }
+}
+
+/// Encrypted value of `table` if it is an encrypted section.
+fn encrypted_value(table: &toml::Table) -> Option<&str> {
+    match (table.len(), table.get(ENCRYPTED_KEY)) {
+        (1, Some(toml::Value::String(value))) if value.starts_with(ENCRYPTED_PREFIX) => Some(value),
+        _ => None,
+    }
+}
+
+/// Replace every encrypted section of `document`, at any depth, with its
+/// decrypted table. Returns the paths of the decrypted sections.
+pub fn decrypt_sections(document: &mut toml::Table, key: Option<&MasterKey>) -> Result<Vec<String>> {
+    let mut decrypted = Vec::new();
+    decrypt_tables(document, "", key, &mut decrypted)?;
+    Ok(decrypted)
+}
+
+fn decrypt_tables(
+    table: &mut toml::Table,
+    prefix: &str,
+    key: Option<&MasterKey>,
+    decrypted: &mut Vec<String>,
+) -> Result<()> {
+    for (name, value) in table.iter_mut() {
+        let toml::Value::Table(section) = value else {
+            continue;
+        };
+        let path = if prefix.is_empty() { name.clone() } else { format!("{}.{}", prefix, name) };
+        match encrypted_value(section) {
+            Some(sealed) => {
+                let key = key.ok_or_else(|| QmsError::Configuration {
+                    message: format!(
+                        "Config section '{}' is encrypted; set {} or {}",
+                        path, MASTER_KEY_ENV, MASTER_KEY_FILE_ENV
+                    ),
+                })?;
+                *section = key.decrypt_section(&path, sealed)?;
+                decrypted.push(path);
+            }
+            None => decrypt_tables(section, &path, key, decrypted)?,
+        }
+    }
+    Ok(())
+}
+
+/// Encrypt the section at dotted `path` in the TOML `document` and return
+/// the rewritten document. Comments are not preserved.
+pub fn encrypt_section_in(document: &str, path: &str, key: &MasterKey) -> Result<String> {
+    rewrite_section(document, path, |section| {
+        if encrypted_value(section).is_some() {
+            return Err(QmsError::Validation {
+                field: "section".to_string(),
+                message: format!("Section '{}' is already encrypted", path),
+            });
+        }
+        let sealed = key.encrypt_section(path, section)?;
+        Ok(toml::Table::from_iter([(ENCRYPTED_KEY.to_string(), toml::Value::String(sealed))]))
+    })
+}
+
+/// Decrypt the section at dotted `path` in the TOML `document` for editing
+/// and return the rewritten document.
+pub fn decrypt_section_in(document: &str, path: &str, key: &MasterKey) -> Result<String> {
+    rewrite_section(document, path, |section| match encrypted_value(section) {
+        Some(sealed) => key.decrypt_section(path, sealed),
+        None => Err(QmsError::Validation {
+            field: "section".to_string(),
+            message: format!("Section '{}' is not encrypted", path),
+        }),
+    })
+}
+
+fn rewrite_section<F>(document: &str, path: &str, rewrite: F) -> Result<String>
+where
+    F: FnOnce(&toml::Table) -> Result<toml::Table>,
+{
+    let mut root = document
+        .parse::<toml::Table>()
+        .map_err(|e| QmsError::Configuration { message: format!("Failed to parse config file: {}", e) })?;
+    let mut table = &mut root;
+    for name in path.split('.') {
+        table = match table.get_mut(name) {
+            Some(toml::Value::Table(section)) => section,
+            _ => {
+                return Err(QmsError::NotFound { resource: "config section".to_string(), id: path.to_string() });
+            }
+        };
+    }
+    *table = rewrite(table)?;
+    toml::to_string_pretty(&root).map_err(|e| QmsError::Serialization { message: e.to_string() })
+}
+
+fn encryption_error(message: &str) -> QmsError {
+    QmsError::Encryption { message: message.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = "[oidc]\nenabled = true\nclient_secret = \"s3cret\"\n\n\
+                            [api.tls]\nenabled = false\nkey_path = \"api.key\"\n";
+
+    #[test]
+    fn test_sections_round_trip() {
+        let key = MasterKey::generate().unwrap();
+        let encrypted = encrypt_section_in(DOCUMENT, "oidc", &key).unwrap();
+        let encrypted = encrypt_section_in(&encrypted, "api.tls", &key).unwrap();
+        assert!(!encrypted.contains("s3cret") && !encrypted.contains("api.key"));
+        assert!(encrypt_section_in(&encrypted, "oidc", &key).is_err(), "already encrypted");
+        assert!(matches!(encrypt_section_in(DOCUMENT, "smtp", &key), Err(QmsError::NotFound { .. })));
+
+        let mut document = encrypted.parse::<toml::Table>().unwrap();
+        let decrypted = decrypt_sections(&mut document, Some(&key)).unwrap();
+        assert_eq!(decrypted, vec!["api.tls".to_string(), "oidc".to_string()]);
+        assert_eq!(document, DOCUMENT.parse::<toml::Table>().unwrap());
+
+        let plain = decrypt_section_in(&encrypted, "oidc", &key).unwrap();
+        assert!(plain.contains("s3cret") && !plain.contains("api.key"));
+    }
+
+    #[test]
+    fn test_missing_or_wrong_key_is_refused() {
+        let key = MasterKey::generate().unwrap();
+        let encrypted = encrypt_section_in(DOCUMENT, "oidc", &key).unwrap();
+
+        let mut document = encrypted.parse::<toml::Table>().unwrap();
+        assert!(matches!(decrypt_sections(&mut document, None), Err(QmsError::Configuration { .. })));
+        let other = MasterKey::generate().unwrap();
+        assert!(matches!(decrypt_sections(&mut document, Some(&other)), Err(QmsError::Encryption { .. })));
+
+        // A ciphertext copied to another section is bound to its original path
+        let sealed = document["oidc"]["encrypted"].as_str().unwrap().to_string();
+        assert!(key.decrypt_section("notifications", &sealed).is_err());
+        assert_eq!(MasterKey::from_base64(&key.to_base64()).unwrap().0, key.0);
+        assert!(MasterKey::from_base64("c2hvcnQ=").is_err());
+    }
+}
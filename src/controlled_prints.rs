@@ -0,0 +1,381 @@
+//! # Controlled Prints - Issued Paper Copies and Their Reconciliation
+//!
+//! Paper copies of effective documents are issued as numbered controlled
+//! prints to a named recipient. Once the revision a print was taken from is
+//! superseded, or the document is made obsolete or retired, the print must be
+//! recalled and reconciled as returned or destroyed so that obsolete
+//! instructions cannot stay in use on the shop floor (21 CFR 820.40(b), ISO
+//! 13485 §4.2.4). The open-prints report lists the unreconciled prints of each
+//! document, and the overdue scan counts prints still awaiting recall.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+
+/// How an issued print was reconciled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrintDisposition {
+    /// Handed back to document control
+    Returned,
+    /// Destroyed by the holder
+    Destroyed,
+}
+
+impl PrintDisposition {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PrintDisposition::Returned => "returned",
+            PrintDisposition::Destroyed => "destroyed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "returned" => Some(PrintDisposition::Returned),
+            "destroyed" => Some(PrintDisposition::Destroyed),
+            _ => None,
+        }
+    }
+}
+
+/// A numbered paper copy of one revision of a document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ControlledPrint {
+    pub id: Uuid,
+    pub document_id: String,
+    pub document_number: String,
+    /// Revision the print was taken from
+    pub version: String,
+    /// Copy number within the revision, from 1
+    pub copy_number: u32,
+    pub recipient: String,
+    pub issued_on: NaiveDate,
+    pub issued_by: String,
+    pub issued_at: DateTime<Utc>,
+    pub disposition: Option<PrintDisposition>,
+    pub reconciled_on: Option<NaiveDate>,
+    pub reconciled_by: Option<String>,
+    /// The revision is no longer current and the print not reconciled yet
+    pub recall_required: bool,
+}
+
+impl ControlledPrint {
+    pub fn is_open(&self) -> bool {
+        self.disposition.is_none()
+    }
+}
+
+/// Unreconciled prints of one document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenPrints {
+    pub document_id: String,
+    pub document_number: String,
+    pub title: String,
+    pub current_version: String,
+    /// Open prints of every revision, by revision and copy number
+    pub prints: Vec<ControlledPrint>,
+}
+
+impl OpenPrints {
+    /// Open prints of superseded revisions, to be recalled.
+    pub fn awaiting_recall(&self) -> usize {
+        self.prints.iter().filter(|print| print.recall_required).count()
+    }
+}
+
+const PRINT_COLUMNS: &str = "p.id, p.document_id, d.document_number, p.version, p.copy_number, p.recipient,
+     p.issued_on, p.issued_by, p.issued_at, p.disposition, p.reconciled_on, p.reconciled_by,
+     p.disposition IS NULL AND (p.version != d.version OR d.status IN ('Obsolete', 'Retired')
+         OR d.deleted_at IS NOT NULL)";
+
+/// Issue and reconciliation of controlled prints.
+pub struct ControlledPrintRepo<'a> {
+    db: &'a Database,
+}
+
+impl<'a> ControlledPrintRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Issue the next numbered print of the current revision of an
+    /// effective document to `recipient`.
+    pub fn issue(
+        &self,
+        document_id: &str,
+        recipient: &str,
+        issued_on: NaiveDate,
+        issued_by: &str,
+    ) -> Result<ControlledPrint> {
+        let recipient = recipient.trim();
+        if recipient.is_empty() {
+            return Err(QmsError::Validation {
+                field: "recipient".to_string(),
+                message: "Recipient is required".to_string(),
+            });
+        }
+        let id = Uuid::new_v4();
+        self.db.unit_of_work(|uow| {
+            let conn = uow.connection();
+            let (version, status): (String, String) = conn
+                .query_row(
+                    "SELECT version, status FROM documents WHERE id = ?1 AND deleted_at IS NULL",
+                    params![document_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?
+                .ok_or_else(|| QmsError::NotFound { resource: "document".to_string(), id: document_id.to_string() })?;
+            if status != "Effective" {
+                return Err(QmsError::Validation {
+                    field: "status".to_string(),
+                    message: format!("Only effective documents can be printed (status: {})", status),
+                });
+            }
+            let copy_number: i64 = conn.query_row(
+                "SELECT COALESCE(MAX(copy_number), 0) + 1 FROM controlled_prints
+                 WHERE document_id = ?1 AND version = ?2",
+                params![document_id, version],
+                |row| row.get(0),
+            )?;
+            conn.execute(
+                "INSERT INTO controlled_prints
+                     (id, document_id, version, copy_number, recipient, issued_on, issued_by, issued_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    id.to_string(),
+                    document_id,
+                    version,
+                    copy_number,
+                    recipient,
+                    issued_on.to_string(),
+                    issued_by,
+                    Utc::now().to_rfc3339(),
+                ],
+            )?;
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                issued_by,
+                "controlled_print_issued",
+                &format!("document:{}", document_id),
+                "Success",
+                Some(format!("Copy {} of version {} issued to {}", copy_number, version, recipient)),
+            )
+        })?;
+        self.get(id)
+    }
+
+    /// Record that an open print was returned or destroyed.
+    pub fn reconcile(
+        &self,
+        print_id: Uuid,
+        disposition: PrintDisposition,
+        reconciled_on: NaiveDate,
+        reconciled_by: &str,
+    ) -> Result<ControlledPrint> {
+        let print = self.get(print_id)?;
+        if !print.is_open() {
+            return Err(QmsError::Validation {
+                field: "disposition".to_string(),
+                message: format!(
+                    "Copy {} of {} version {} is already reconciled",
+                    print.copy_number, print.document_number, print.version
+                ),
+            });
+        }
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE controlled_prints SET disposition = ?2, reconciled_on = ?3, reconciled_by = ?4
+                 WHERE id = ?1 AND disposition IS NULL",
+                params![print_id.to_string(), disposition.as_str(), reconciled_on.to_string(), reconciled_by],
+            )?;
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                reconciled_by,
+                "controlled_print_reconciled",
+                &format!("document:{}", print.document_id),
+                "Success",
+                Some(format!(
+                    "Copy {} of version {} held by {} {}",
+                    print.copy_number,
+                    print.version,
+                    print.recipient,
+                    disposition.as_str()
+                )),
+            )
+        })?;
+        self.get(print_id)
+    }
+
+    pub fn get(&self, print_id: Uuid) -> Result<ControlledPrint> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!(
+                            "SELECT {} FROM controlled_prints p JOIN documents d ON d.id = p.document_id
+                             WHERE p.id = ?1",
+                            PRINT_COLUMNS
+                        ),
+                        params![print_id.to_string()],
+                        row_to_print,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "controlled_print".to_string(), id: print_id.to_string() })
+    }
+
+    /// Every print of a document, by revision and copy number.
+    pub fn prints(&self, document_id: &str) -> Result<Vec<ControlledPrint>> {
+        self.query("p.document_id = ?1", params![document_id])
+    }
+
+    /// Open prints per document, for documents with any, by document number.
+    pub fn open_prints_report(&self) -> Result<Vec<OpenPrints>> {
+        let prints = self.query("p.disposition IS NULL", [])?;
+        let mut report: Vec<OpenPrints> = Vec::new();
+        for print in prints {
+            if report.last().map_or(true, |open| open.document_id != print.document_id) {
+                let (title, current_version) = self.db.with_connection(|conn| {
+                    Ok(conn.query_row(
+                        "SELECT title, version FROM documents WHERE id = ?1",
+                        params![print.document_id],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )?)
+                })?;
+                report.push(OpenPrints {
+                    document_id: print.document_id.clone(),
+                    document_number: print.document_number.clone(),
+                    title,
+                    current_version,
+                    prints: Vec::new(),
+                });
+            }
+            if let Some(open) = report.last_mut() {
+                open.prints.push(print);
+            }
+        }
+        Ok(report)
+    }
+
+    /// Open prints of superseded revisions across all documents.
+    pub fn awaiting_recall(&self) -> Result<Vec<ControlledPrint>> {
+        Ok(self.query("p.disposition IS NULL", [])?.into_iter().filter(|print| print.recall_required).collect())
+    }
+
+    fn query<P: rusqlite::Params>(&self, condition: &str, params: P) -> Result<Vec<ControlledPrint>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM controlled_prints p JOIN documents d ON d.id = p.document_id
+                 WHERE {}
+                 ORDER BY d.document_number, p.document_id, p.version, p.copy_number",
+                PRINT_COLUMNS, condition
+            ))?;
+            let prints = stmt.query_map(params, row_to_print)?.collect::<rusqlite::Result<_>>()?;
+            Ok(prints)
+        })
+    }
+}
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn parse_date(row: &Row, index: usize) -> rusqlite::Result<Option<NaiveDate>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|value| NaiveDate::parse_from_str(&value, "%Y-%m-%d").map_err(|e| conversion_error(index, e.to_string())))
+        .transpose()
+}
+
+fn row_to_print(row: &Row) -> rusqlite::Result<ControlledPrint> {
+    let id: String = row.get(0)?;
+    let issued_at: String = row.get(8)?;
+    let disposition = row
+        .get::<_, Option<String>>(9)?
+        .map(|value| {
+            PrintDisposition::parse(&value).ok_or_else(|| conversion_error(9, format!("unknown disposition {}", value)))
+        })
+        .transpose()?;
+    Ok(ControlledPrint {
+        id: Uuid::parse_str(&id).map_err(|e| conversion_error(0, e.to_string()))?,
+        document_id: row.get(1)?,
+        document_number: row.get(2)?,
+        version: row.get(3)?,
+        copy_number: row.get::<_, i64>(4)? as u32,
+        recipient: row.get(5)?,
+        issued_on: parse_date(row, 6)?.ok_or_else(|| conversion_error(6, "missing issue date".to_string()))?,
+        issued_by: row.get(7)?,
+        issued_at: DateTime::parse_from_rfc3339(&issued_at)
+            .map_err(|e| conversion_error(8, e.to_string()))?
+            .with_timezone(&Utc),
+        disposition,
+        reconciled_on: parse_date(row, 10)?,
+        reconciled_by: row.get(11)?,
+        recall_required: row.get(12)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+
+    #[test]
+    fn test_superseded_prints_await_recall_until_reconciled() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["dc"]);
+        db.with_connection(|conn| {
+            conn.execute_batch(
+                "INSERT INTO documents (id, document_number, title, version, status, document_type, content_hash, created_by)
+                 VALUES ('sop-1', 'SOP-001', 'Cleaning', '1.0', 'Effective', 'SOP', 'h1', 'dc'),
+                        ('sop-2', 'SOP-002', 'Gowning', '1.0', 'Draft', 'SOP', 'h', 'dc');",
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        let repo = ControlledPrintRepo::new(&db);
+        let today = Utc::now().date_naive();
+
+        let first = repo.issue("sop-1", "Line 1", today, "dc").unwrap();
+        let second = repo.issue("sop-1", "Line 2", today, "dc").unwrap();
+        assert_eq!((first.copy_number, second.copy_number), (1, 2));
+        assert!(matches!(repo.issue("sop-2", "Line 1", today, "dc"), Err(QmsError::Validation { .. })));
+        assert!(repo.issue("sop-1", " ", today, "dc").is_err());
+        assert!(repo.awaiting_recall().unwrap().is_empty());
+
+        // Revision 2.0 supersedes the prints of 1.0
+        db.with_connection(|conn| {
+            conn.execute("UPDATE documents SET version = '2.0' WHERE id = 'sop-1'", [])?;
+            Ok(())
+        })
+        .unwrap();
+        let third = repo.issue("sop-1", "Line 1", today, "dc").unwrap();
+        assert_eq!((third.version.as_str(), third.copy_number), ("2.0", 1));
+        let report = repo.open_prints_report().unwrap();
+        assert_eq!(report.len(), 1);
+        assert_eq!((report[0].current_version.as_str(), report[0].prints.len()), ("2.0", 3));
+        assert_eq!(report[0].awaiting_recall(), 2);
+
+        repo.reconcile(first.id, PrintDisposition::Destroyed, today, "dc").unwrap();
+        let returned = repo.reconcile(second.id, PrintDisposition::Returned, today, "dc").unwrap();
+        assert_eq!(returned.disposition, Some(PrintDisposition::Returned));
+        assert!(!returned.recall_required);
+        assert!(repo.reconcile(second.id, PrintDisposition::Destroyed, today, "dc").is_err());
+        assert!(repo.awaiting_recall().unwrap().is_empty());
+        assert_eq!(repo.open_prints_report().unwrap()[0].prints, vec![third]);
+        assert_eq!(repo.prints("sop-1").unwrap().len(), 3);
+        assert_eq!(db.get_audit_entries_for_resource("document:sop-1").unwrap().len(), 5);
+    }
+}
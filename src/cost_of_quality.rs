@@ -0,0 +1,497 @@
+//! # Cost of Quality - Quality Costs per Quarter
+//!
+//! Costs are recorded against the quality record that caused them: a CAPA,
+//! an NCR, a recall or a scrap disposition. Each cost falls into one of the
+//! four classic categories: prevention, appraisal, internal failure or
+//! external failure. A cost is optional for every record, and a record may
+//! carry several. NCRs are checked against the quality intake; CAPAs, recalls
+//! and scrap dispositions are referenced by their identifier. Amounts are
+//! kept in minor units (cents) of the organisation's reporting currency.
+//!
+//! [`QualityCostRepo::quarterly`] totals the categories per calendar quarter
+//! for the financial inputs of management review (ISO 13485 §5.6.2), which
+//! the compliance summary includes as [`cost_of_quality_section`].
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::report::{Chart, ChartDatum, ChartKind, ReportSection, ReportTable};
+
+/// Quarters shown in the management review.
+pub const REVIEW_QUARTERS: u32 = 4;
+
+/// Cost category of the prevention-appraisal-failure model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostCategory {
+    Prevention,
+    Appraisal,
+    InternalFailure,
+    ExternalFailure,
+}
+
+impl CostCategory {
+    pub const ALL: [CostCategory; 4] =
+        [CostCategory::Prevention, CostCategory::Appraisal, CostCategory::InternalFailure, CostCategory::ExternalFailure];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CostCategory::Prevention => "prevention",
+            CostCategory::Appraisal => "appraisal",
+            CostCategory::InternalFailure => "internal_failure",
+            CostCategory::ExternalFailure => "external_failure",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|category| category.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CostCategory::Prevention => "Prevention",
+            CostCategory::Appraisal => "Appraisal",
+            CostCategory::InternalFailure => "Internal Failure",
+            CostCategory::ExternalFailure => "External Failure",
+        }
+    }
+}
+
+/// Kind of quality record a cost is recorded against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CostSource {
+    Capa,
+    Nonconformance,
+    Recall,
+    ScrapDisposition,
+}
+
+impl CostSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CostSource::Capa => "capa",
+            CostSource::Nonconformance => "nonconformance",
+            CostSource::Recall => "recall",
+            CostSource::ScrapDisposition => "scrap_disposition",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [CostSource::Capa, CostSource::Nonconformance, CostSource::Recall, CostSource::ScrapDisposition]
+            .into_iter()
+            .find(|source| source.as_str() == value)
+    }
+
+    /// Category of a cost when none is given: CAPAs prevent recurrence,
+    /// NCRs and scrap are caught in-house, recalls reach the field.
+    pub fn default_category(&self) -> CostCategory {
+        match self {
+            CostSource::Capa => CostCategory::Prevention,
+            CostSource::Nonconformance | CostSource::ScrapDisposition => CostCategory::InternalFailure,
+            CostSource::Recall => CostCategory::ExternalFailure,
+        }
+    }
+}
+
+/// A cost recorded against a quality record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityCost {
+    pub id: Uuid,
+    pub source: CostSource,
+    /// CAPA or NCR id, or the recall / scrap disposition reference
+    pub record_id: String,
+    pub category: CostCategory,
+    /// Amount in minor units of the reporting currency
+    pub amount_cents: i64,
+    pub incurred_on: NaiveDate,
+    pub description: String,
+    pub recorded_by: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Category totals of one calendar quarter, in minor units.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuarterCosts {
+    /// e.g. `2026-Q3`
+    pub quarter: String,
+    pub prevention: i64,
+    pub appraisal: i64,
+    pub internal_failure: i64,
+    pub external_failure: i64,
+}
+
+impl QuarterCosts {
+    pub fn amount(&self, category: CostCategory) -> i64 {
+        match category {
+            CostCategory::Prevention => self.prevention,
+            CostCategory::Appraisal => self.appraisal,
+            CostCategory::InternalFailure => self.internal_failure,
+            CostCategory::ExternalFailure => self.external_failure,
+        }
+    }
+
+    fn add(&mut self, category: CostCategory, amount: i64) {
+        match category {
+            CostCategory::Prevention => self.prevention += amount,
+            CostCategory::Appraisal => self.appraisal += amount,
+            CostCategory::InternalFailure => self.internal_failure += amount,
+            CostCategory::ExternalFailure => self.external_failure += amount,
+        }
+    }
+
+    /// Prevention and appraisal: the cost of good quality
+    pub fn conformance(&self) -> i64 {
+        self.prevention + self.appraisal
+    }
+
+    /// Internal and external failure: the cost of poor quality
+    pub fn nonconformance(&self) -> i64 {
+        self.internal_failure + self.external_failure
+    }
+
+    pub fn total(&self) -> i64 {
+        self.conformance() + self.nonconformance()
+    }
+}
+
+/// Cost of quality per quarter, oldest quarter first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CostOfQualityReport {
+    pub from: NaiveDate,
+    /// First day after the last quarter
+    pub to: NaiveDate,
+    pub quarters: Vec<QuarterCosts>,
+}
+
+impl CostOfQualityReport {
+    pub fn total(&self, category: CostCategory) -> i64 {
+        self.quarters.iter().map(|quarter| quarter.amount(category)).sum()
+    }
+}
+
+/// Quality cost records and their quarterly aggregation.
+pub struct QualityCostRepo<'a> {
+    db: &'a Database,
+}
+
+impl<'a> QualityCostRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Record a cost against `record_id`; `category` defaults by source.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record(
+        &self,
+        source: CostSource,
+        record_id: &str,
+        category: Option<CostCategory>,
+        amount_cents: i64,
+        incurred_on: NaiveDate,
+        description: &str,
+        recorded_by: &str,
+    ) -> Result<QualityCost> {
+        if record_id.trim().is_empty() {
+            return Err(QmsError::Validation {
+                field: "record_id".to_string(),
+                message: format!("A {} reference is required", source.as_str()),
+            });
+        }
+        if amount_cents <= 0 {
+            return Err(QmsError::Validation {
+                field: "amount".to_string(),
+                message: "Cost amount must be positive".to_string(),
+            });
+        }
+        let cost = QualityCost {
+            id: Uuid::new_v4(),
+            source,
+            record_id: record_id.trim().to_string(),
+            category: category.unwrap_or_else(|| source.default_category()),
+            amount_cents,
+            incurred_on,
+            description: description.to_string(),
+            recorded_by: recorded_by.to_string(),
+            recorded_at: Utc::now(),
+        };
+        self.db.unit_of_work(|uow| {
+            let conn = uow.connection();
+            if source == CostSource::Nonconformance {
+                let known: Option<String> = conn
+                    .query_row(
+                        "SELECT id FROM quality_intake WHERE id = ?1 AND source = 'nonconformance'",
+                        params![cost.record_id],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                if known.is_none() {
+                    return Err(QmsError::NotFound { resource: "nonconformance".to_string(), id: cost.record_id.clone() });
+                }
+            }
+            conn.execute(
+                "INSERT INTO quality_costs (id, source, record_id, category, amount_cents, incurred_on, description,
+                     recorded_by, recorded_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    cost.id.to_string(),
+                    cost.source.as_str(),
+                    cost.record_id,
+                    cost.category.as_str(),
+                    cost.amount_cents,
+                    cost.incurred_on.to_string(),
+                    cost.description,
+                    cost.recorded_by,
+                    cost.recorded_at.to_rfc3339(),
+                ],
+            )?;
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                recorded_by,
+                "quality_cost_recorded",
+                &format!("{}:{}", source.as_str(), cost.record_id),
+                "Success",
+                Some(format!("{} {} on {}", cost.category.as_str(), format_amount(amount_cents), incurred_on)),
+            )?;
+            Ok(())
+        })?;
+        Ok(cost)
+    }
+
+    /// Costs recorded against one record, in the order incurred.
+    pub fn costs_of(&self, source: CostSource, record_id: &str) -> Result<Vec<QualityCost>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM quality_costs WHERE source = ?1 AND record_id = ?2 ORDER BY incurred_on, recorded_at",
+                COLUMNS
+            ))?;
+            let costs = stmt.query_map(params![source.as_str(), record_id], row_to_cost)?.collect::<rusqlite::Result<_>>()?;
+            Ok(costs)
+        })
+    }
+
+    /// Category totals for the `quarters` calendar quarters up to and
+    /// including the one containing `today`.
+    pub fn quarterly(&self, quarters: u32, today: NaiveDate) -> Result<CostOfQualityReport> {
+        if quarters == 0 {
+            return Err(QmsError::Validation {
+                field: "quarters".to_string(),
+                message: "At least one quarter is required".to_string(),
+            });
+        }
+        let current = quarter_index(today);
+        let first = current - i64::from(quarters) + 1;
+        let (from, to) = (quarter_start(first), quarter_start(current + 1));
+        let mut report = CostOfQualityReport {
+            from,
+            to,
+            quarters: (first..=current)
+                .map(|index| QuarterCosts { quarter: quarter_label(index), ..QuarterCosts::default() })
+                .collect(),
+        };
+
+        let rows = self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT incurred_on, category, SUM(amount_cents) FROM quality_costs
+                 WHERE incurred_on >= ?1 AND incurred_on < ?2
+                 GROUP BY incurred_on, category",
+            )?;
+            let rows = stmt
+                .query_map(params![from.to_string(), to.to_string()], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rows)
+        })?;
+        for (incurred_on, category, amount) in rows {
+            let date = NaiveDate::parse_from_str(&incurred_on, "%Y-%m-%d")
+                .map_err(|e| QmsError::Serialization { message: e.to_string() })?;
+            let category = CostCategory::parse(&category)
+                .ok_or_else(|| QmsError::Serialization { message: format!("unknown cost category {}", category) })?;
+            report.quarters[(quarter_index(date) - first) as usize].add(category, amount);
+        }
+        Ok(report)
+    }
+}
+
+/// `amount` in minor units as `1234.56`.
+pub fn format_amount(amount: i64) -> String {
+    let sign = if amount < 0 { "-" } else { "" };
+    format!("{}{}.{:02}", sign, (amount / 100).abs(), (amount % 100).abs())
+}
+
+/// Parse a decimal amount with at most two fraction digits into minor units.
+pub fn parse_amount(value: &str) -> Result<i64> {
+    let invalid = || QmsError::Validation {
+        field: "amount".to_string(),
+        message: format!("'{}' is not an amount like 1250.50", value),
+    };
+    let (units, fraction) = value.trim().split_once('.').unwrap_or((value.trim(), ""));
+    if units.is_empty() || fraction.len() > 2 || !units.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let units: i64 = units.parse().map_err(|_| invalid())?;
+    let fraction: i64 = format!("{:0<2}", fraction).parse().map_err(|_| invalid())?;
+    units.checked_mul(100).and_then(|cents| cents.checked_add(fraction)).ok_or_else(invalid)
+}
+
+/// Management review input: cost of quality per quarter and category.
+pub fn cost_of_quality_section(report: &CostOfQualityReport) -> ReportSection {
+    let total: i64 = report.quarters.iter().map(QuarterCosts::total).sum();
+    let poor: i64 = report.quarters.iter().map(QuarterCosts::nonconformance).sum();
+    let share = if total > 0 { format!("{:.1} %", poor as f64 * 100.0 / total as f64) } else { "-".to_string() };
+    let section = ReportSection::new("Cost of Quality").key_values(vec![
+        ("Period", format!("{} to {}", report.from, report.to.pred_opt().unwrap_or(report.to))),
+        ("Total", format_amount(total)),
+        ("Cost of Poor Quality", format_amount(poor)),
+        ("Failure Share", share),
+    ]);
+    if total == 0 {
+        return section;
+    }
+    let chart = Chart {
+        title: "Cost of Quality by Category".to_string(),
+        kind: ChartKind::Bar,
+        data: CostCategory::ALL
+            .iter()
+            .map(|category| ChartDatum {
+                label: category.label().to_string(),
+                value: report.total(*category) as f32 / 100.0,
+            })
+            .collect(),
+    };
+    section.chart(chart).table(report.quarters.iter().fold(
+        ReportTable::new(vec!["Quarter", "Prevention", "Appraisal", "Internal Failure", "External Failure", "Total"]),
+        |table, quarter| {
+            table.with_row(vec![
+                quarter.quarter.clone(),
+                format_amount(quarter.prevention),
+                format_amount(quarter.appraisal),
+                format_amount(quarter.internal_failure),
+                format_amount(quarter.external_failure),
+                format_amount(quarter.total()),
+            ])
+        },
+    ))
+}
+
+/// Quarters since year 0, so consecutive quarters differ by one.
+fn quarter_index(date: NaiveDate) -> i64 {
+    i64::from(date.year()) * 4 + i64::from(date.month0() / 3)
+}
+
+fn quarter_start(index: i64) -> NaiveDate {
+    NaiveDate::from_ymd_opt(index.div_euclid(4) as i32, index.rem_euclid(4) as u32 * 3 + 1, 1).unwrap_or_default()
+}
+
+fn quarter_label(index: i64) -> String {
+    format!("{}-Q{}", index.div_euclid(4), index.rem_euclid(4) + 1)
+}
+
+const COLUMNS: &str =
+    "id, source, record_id, category, amount_cents, incurred_on, description, recorded_by, recorded_at";
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn row_to_cost(row: &Row) -> rusqlite::Result<QualityCost> {
+    let id: String = row.get(0)?;
+    let source: String = row.get(1)?;
+    let category: String = row.get(3)?;
+    let incurred_on: String = row.get(5)?;
+    let recorded_at: String = row.get(8)?;
+    Ok(QualityCost {
+        id: Uuid::parse_str(&id).map_err(|e| conversion_error(0, e.to_string()))?,
+        source: CostSource::parse(&source).ok_or_else(|| conversion_error(1, format!("unknown source {}", source)))?,
+        record_id: row.get(2)?,
+        category: CostCategory::parse(&category)
+            .ok_or_else(|| conversion_error(3, format!("unknown category {}", category)))?,
+        amount_cents: row.get(4)?,
+        incurred_on: NaiveDate::parse_from_str(&incurred_on, "%Y-%m-%d").map_err(|e| conversion_error(5, e.to_string()))?,
+        description: row.get(6)?,
+        recorded_by: row.get(7)?,
+        recorded_at: DateTime::parse_from_rfc3339(&recorded_at)
+            .map(|t| t.with_timezone(&Utc))
+            .map_err(|e| conversion_error(8, e.to_string()))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::quality_intake::{IssueSource, QualityIntake};
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_costs_aggregate_per_quarter_and_category() {
+        let db = test_db();
+        let ncr = QualityIntake::new(&db).submit(IssueSource::Nonconformance, "Seal failure", "Lot 42", "qe1").unwrap();
+        let ncr_id = ncr.id.to_string();
+        let repo = QualityCostRepo::new(&db);
+        repo.record(CostSource::Nonconformance, &ncr_id, None, 120_000, date(2026, 8, 3), "Rework", "qe1").unwrap();
+        repo.record(CostSource::ScrapDisposition, "SCR-7", None, 45_050, date(2026, 9, 30), "Scrapped lot", "qe1")
+            .unwrap();
+        repo.record(CostSource::Capa, "capa-1", None, 80_000, date(2026, 7, 1), "Fixture redesign", "eng1").unwrap();
+        let inspection = Some(CostCategory::Appraisal);
+        repo.record(CostSource::Capa, "capa-1", inspection, 10_000, date(2026, 4, 2), "Extra inspection", "eng1").unwrap();
+        repo.record(CostSource::Recall, "RCL-2026-01", None, 500_000, date(2026, 10, 1), "Field action", "ra").unwrap();
+        repo.record(CostSource::Recall, "RCL-2025-09", None, 1_000, date(2025, 9, 30), "Before the period", "ra")
+            .unwrap();
+
+        assert!(repo.record(CostSource::Nonconformance, "unknown", None, 100, date(2026, 8, 3), "", "qe1").is_err());
+        assert!(repo.record(CostSource::Capa, "capa-1", None, 0, date(2026, 8, 3), "", "qe1").is_err());
+        let capa_costs = repo.costs_of(CostSource::Capa, "capa-1").unwrap();
+        assert_eq!(capa_costs.len(), 2);
+        assert_eq!(capa_costs[0].category, CostCategory::Appraisal);
+        assert_eq!(capa_costs[1].category, CostCategory::Prevention);
+        assert_eq!(db.get_audit_entries_for_resource(&format!("nonconformance:{}", ncr_id)).unwrap().len(), 1);
+
+        let report = repo.quarterly(REVIEW_QUARTERS, date(2026, 10, 15)).unwrap();
+        assert_eq!((report.from, report.to), (date(2026, 1, 1), date(2027, 1, 1)));
+        let quarters: Vec<&str> = report.quarters.iter().map(|q| q.quarter.as_str()).collect();
+        assert_eq!(quarters, ["2026-Q1", "2026-Q2", "2026-Q3", "2026-Q4"]);
+        assert_eq!(report.quarters[0].total(), 0);
+        assert_eq!(report.quarters[1].appraisal, 10_000);
+        let q3 = &report.quarters[2];
+        assert_eq!((q3.prevention, q3.internal_failure, q3.external_failure), (80_000, 165_050, 0));
+        assert_eq!(report.quarters[3].external_failure, 500_000);
+        assert_eq!(report.total(CostCategory::InternalFailure), 165_050);
+
+        let section = cost_of_quality_section(&report);
+        assert!(matches!(&section.blocks[0], crate::report::ReportBlock::KeyValue(pairs)
+            if pairs[1].1 == "7550.50" && pairs[2].1 == "6650.50"));
+    }
+
+    #[test]
+    fn test_amounts_parse_and_format_in_minor_units() {
+        assert_eq!(parse_amount("1250.5").unwrap(), 125_050);
+        assert_eq!(parse_amount("1250").unwrap(), 125_000);
+        assert_eq!(parse_amount(" 0.07 ").unwrap(), 7);
+        for invalid in ["", "12.345", "-5", "1,250", ".50", "abc"] {
+            assert!(parse_amount(invalid).is_err(), "{}", invalid);
+        }
+        assert_eq!(format_amount(125_050), "1250.50");
+        assert_eq!(format_amount(-7), "-0.07");
+    }
+}
@@ -0,0 +1,198 @@
+//! # Crash Guard - Terminal Restoration & Crash Audit
+//!
+//! The TUI switches the terminal to raw mode and the alternate screen. A
+//! panic or a termination signal must not leave the user's shell in that
+//! state, and must not leave an unexplained gap in the audit trail. The
+//! [`TerminalGuard`] restores the terminal when dropped, and [`install`] adds
+//! a panic hook that restores it before the panic message is printed. The
+//! shutdown coordinator does the same for SIGINT, SIGTERM and SIGHUP.
+//!
+//! A panic writes the message and backtrace to a crash report below
+//! `<data_directory>/crash-reports` and records an `application_crash`
+//! audit entry that references the report by id and SHA-256 digest only,
+//! since panic messages and backtraces may contain record data.
+
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Once;
+
+use chrono::{DateTime, Utc};
+use crossterm::{
+    cursor::Show,
+    event::{DisableMouseCapture, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::keystore::sha256_hex;
+
+/// User recorded for crash and termination entries.
+pub const CRASH_GUARD_USER: &str = "system:crash_guard";
+
+/// Crash report directory below the data directory.
+pub const CRASH_REPORT_DIR: &str = "crash-reports";
+
+const RESOURCE: &str = "qms_system";
+
+static INSTALL: Once = Once::new();
+
+/// Raw mode and alternate screen for the TUI, undone on drop.
+pub struct TerminalGuard(());
+
+impl TerminalGuard {
+    pub fn enter() -> Result<Self> {
+        enable_raw_mode()?;
+        let guard = Self(());
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+/// Leave raw mode and the alternate screen and show the cursor. Safe to
+/// call more than once and when the terminal was never switched.
+pub fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, Show);
+    let _ = io::stdout().flush();
+}
+
+/// What a panic left behind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrashInfo {
+    pub message: String,
+    /// `file:line:column` of the panic
+    pub location: Option<String>,
+    pub backtrace: String,
+}
+
+/// Reference to a written crash report, as recorded in the audit trail.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CrashReport {
+    pub crash_id: Uuid,
+    pub occurred_at: DateTime<Utc>,
+    pub location: Option<String>,
+    /// File name below the crash report directory
+    pub report_file: String,
+    /// SHA-256 of the report, so the audited reference cannot be swapped
+    pub report_sha256: String,
+}
+
+/// Write `info` to a crash report in `directory` and audit a reference to
+/// it. The message and backtrace stay in the report file.
+pub fn record_crash(db: &Database, directory: &Path, info: &CrashInfo, now: DateTime<Utc>) -> Result<CrashReport> {
+    let crash_id = Uuid::new_v4();
+    let contents = format!(
+        "crash_id: {}\noccurred_at: {}\nversion: {}\nlocation: {}\nmessage: {}\n\nbacktrace:\n{}\n",
+        crash_id,
+        now.to_rfc3339(),
+        crate::APPLICATION_VERSION,
+        info.location.as_deref().unwrap_or("unknown"),
+        info.message,
+        info.backtrace,
+    );
+    let report_file = format!("crash-{}-{}.txt", now.format("%Y%m%d-%H%M%S"), crash_id);
+    let fs_error =
+        |path: &Path, e: io::Error| QmsError::FileSystem { path: path.display().to_string(), message: e.to_string() };
+    std::fs::create_dir_all(directory).map_err(|e| fs_error(directory, e))?;
+    let path = directory.join(&report_file);
+    std::fs::write(&path, &contents).map_err(|e| fs_error(&path, e))?;
+
+    let report = CrashReport {
+        crash_id,
+        occurred_at: now,
+        location: info.location.clone(),
+        report_file,
+        report_sha256: sha256_hex(contents.as_bytes()),
+    };
+    AuditManager::new(db.clone()).log_action(
+        CRASH_GUARD_USER,
+        "application_crash",
+        RESOURCE,
+        "FAILURE",
+        Some(serde_json::to_string(&report)?),
+    )?;
+    Ok(report)
+}
+
+/// Install the panic hook once per process: record the crash, then run the
+/// previous hook so the panic is still printed. The terminal is restored
+/// for panics on the calling (TUI) thread; a panicking background task is
+/// recorded without tearing down the running TUI.
+pub fn install(db: Database, data_directory: &Path) {
+    let directory = data_directory.join(CRASH_REPORT_DIR);
+    let tui_thread = std::thread::current().id();
+    INSTALL.call_once(move || {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |panic| {
+            if std::thread::current().id() == tui_thread {
+                restore_terminal();
+            }
+            let message = match (panic.payload().downcast_ref::<&str>(), panic.payload().downcast_ref::<String>()) {
+                (Some(message), _) => message.to_string(),
+                (_, Some(message)) => message.clone(),
+                _ => "non-string panic payload".to_string(),
+            };
+            let info = CrashInfo {
+                message,
+                location: panic.location().map(|l| l.to_string()),
+                backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            };
+            match record_crash(&db, &directory, &info, Utc::now()) {
+                Ok(report) => eprintln!(
+                    "QMSrs stopped unexpectedly; crash report {} written to {}",
+                    report.crash_id,
+                    directory.join(&report.report_file).display()
+                ),
+                Err(e) => eprintln!("QMSrs stopped unexpectedly; the crash could not be recorded: {}", e),
+            }
+            previous(panic);
+        }));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+
+    #[test]
+    fn test_crash_report_is_referenced_not_copied() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        let info = CrashInfo {
+            message: "index out of bounds for patient MRN-0042".to_string(),
+            location: Some("src/ui.rs:512:9".to_string()),
+            backtrace: "0: qmsrs::ui::TuiApp::render".to_string(),
+        };
+
+        let report = record_crash(&db, dir.path(), &info, Utc::now()).unwrap();
+        let contents = std::fs::read_to_string(dir.path().join(&report.report_file)).unwrap();
+        assert!(contents.contains("MRN-0042") && contents.contains("qmsrs::ui::TuiApp::render"));
+        assert_eq!(report.report_sha256, sha256_hex(contents.as_bytes()));
+
+        let entries = db.get_audit_entries_for_resource(RESOURCE).unwrap();
+        let entry = entries.iter().find(|e| e.action == "application_crash").unwrap();
+        assert_eq!((entry.user_id.as_str(), entry.outcome.as_str()), (CRASH_GUARD_USER, "FAILURE"));
+        let metadata = entry.metadata.as_deref().unwrap();
+        assert!(!metadata.contains("MRN-0042") && !metadata.contains("TuiApp"), "no message or backtrace");
+        assert_eq!(serde_json::from_str::<CrashReport>(metadata).unwrap(), report);
+    }
+}
@@ -1,16 +1,23 @@
 use crate::{Result, QmsError, logging::AuditLogEntry, config::DatabaseConfig};
 use rusqlite::{Connection, params};
-use r2d2::Pool;
+use r2d2::{Pool, PooledConnection};
 use r2d2_sqlite::SqliteConnectionManager;
 use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use serde::{Serialize, Deserialize};
+use crate::db_stats::{PoolMetrics, PoolStats};
+use crate::read_cache::ReadCache;
+use crate::unit_of_work::UnitOfWork;
 
 /// Database manager for FDA-compliant QMS with connection pooling
 #[derive(Clone)]
 pub struct Database {
     pool: Pool<SqliteConnectionManager>,
+    metrics: Arc<PoolMetrics>,
+    cache: Arc<ReadCache>,
 }
 
 impl Database {
@@ -39,8 +46,15 @@ impl Database {
             config.url.clone()
         };
         
+        let cache = Arc::new(ReadCache::new());
+        let hook_cache = cache.clone();
         let manager = SqliteConnectionManager::file(&connection_url)
             .with_init(move |conn| {
+                // Drop cached reads derived from any table this connection commits writes to
+                hook_cache.watch(conn);
+                // Only takes effect on a new database; lets maintenance
+                // return free pages with `incremental_vacuum`
+                conn.execute_batch("PRAGMA auto_vacuum=INCREMENTAL")?;
                 // Configure pragma settings for FDA compliance
                 if config.wal_mode {
                     conn.execute_batch("PRAGMA journal_mode=WAL")?;
@@ -59,7 +73,7 @@ impl Database {
                 message: format!("Failed to create connection pool: {}", e),
             })?;
 
-        let db = Self { pool };
+        let db = Self { pool, metrics: Arc::new(PoolMetrics::default()), cache };
         
         // Initialize schema using a connection from the pool
         db.initialize_schema()?;
@@ -69,11 +83,18 @@ impl Database {
 
     /// Initialize database schema for FDA compliance
     fn initialize_schema(&self) -> Result<()> {
-        let conn = self.pool.get()
-            .map_err(|e| QmsError::Database {
-                message: format!("Failed to get database connection: {}", e),
-            })?;
+        let mut conn = self.checkout()?;
+
+        Self::create_baseline_schema(&conn)?;
 
+        // Versioned schema changes (training, supplier and post-market tables onwards)
+        crate::migrations::apply_migrations(&mut conn)?;
+
+        Ok(())
+    }
+
+    /// Create the baseline (pre-migration) schema idempotently.
+    pub(crate) fn create_baseline_schema(conn: &Connection) -> Result<()> {
         // Create audit trail table (critical for FDA compliance)
         conn.execute(
             "CREATE TABLE IF NOT EXISTS audit_trail (
@@ -289,42 +310,6 @@ impl Database {
             [],
         )?;
 
-        // TASK-025: Training Records schema
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS training_records (
-                id TEXT PRIMARY KEY,
-                employee_id TEXT NOT NULL,
-                training_item TEXT NOT NULL,
-                mandatory BOOLEAN NOT NULL,
-                assigned_by TEXT NOT NULL,
-                due_date TEXT NOT NULL,
-                completion_date TEXT,
-                status TEXT NOT NULL CHECK (status IN ('Pending', 'InProgress', 'Completed', 'Overdue')),
-                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (employee_id) REFERENCES users(id),
-                FOREIGN KEY (assigned_by) REFERENCES users(id)
-            )",
-            [],
-        )?;
-
-        // TASK-027: Supplier Management schema
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS suppliers (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                contact_info TEXT,
-                qualification_status TEXT NOT NULL CHECK (qualification_status IN ('Pending','Qualified','Disqualified')),
-                qualification_date TEXT,
-                qualification_expiry_date TEXT,
-                approved_by TEXT,
-                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                FOREIGN KEY (approved_by) REFERENCES users(id)
-            )",
-            [],
-        )?;
-
         // Create indexes for performance
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_audit_trail_timestamp ON audit_trail(timestamp)",
@@ -356,17 +341,6 @@ impl Database {
             [],
         )?;
 
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_training_records_status ON training_records(status)",
-            [],
-        )?;
-
-        // TASK-027: Supplier Management schema
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_suppliers_status ON suppliers(qualification_status)",
-            [],
-        )?;
- 
         Ok(())
     }
 
@@ -376,30 +350,120 @@ impl Database {
     /// still allowing caller modules (e.g. repository layers) to perform
     /// custom queries in a safe, FDA-compliant manner without duplicating
     /// connection-handling boilerplate.
+    #[track_caller]
     pub fn with_connection<F, T>(&self, func: F) -> Result<T>
     where
         F: FnOnce(&Connection) -> Result<T>,
     {
-        let conn = self.pool.get().map_err(|e| QmsError::Database {
+        self.timed(std::panic::Location::caller(), func)
+    }
+
+    /// Run `func` on a pooled connection, reporting holds over the slow-query
+    /// threshold under `label`.
+    fn timed<F, T>(&self, label: &dyn std::fmt::Display, func: F) -> Result<T>
+    where
+        F: FnOnce(&Connection) -> Result<T>,
+    {
+        let conn = self.checkout()?;
+        let started = Instant::now();
+        let result = func(&conn);
+        self.metrics.record_hold(label, started.elapsed());
+        result
+    }
+
+    /// Check a connection out of the pool, recording the wait.
+    fn checkout(&self) -> Result<PooledConnection<SqliteConnectionManager>> {
+        let started = Instant::now();
+        let conn = self.pool.get();
+        self.metrics.record_wait(started.elapsed(), conn.is_ok());
+        conn.map_err(|e| QmsError::Database {
             message: format!("Failed to get database connection: {}", e),
-        })?;
-        func(&conn)
+        })
+    }
+
+    /// Report holds longer than `threshold` as slow operations.
+    pub fn with_slow_query_threshold(self, threshold: std::time::Duration) -> Self {
+        self.metrics.set_slow_threshold(threshold);
+        self
+    }
+
+    /// Cache of hot reads, invalidated by writes through this pool.
+    pub fn read_cache(&self) -> &ReadCache {
+        &self.cache
+    }
+
+    /// Current pool occupancy, checkout wait times and slow operations.
+    pub fn pool_stats(&self) -> PoolStats {
+        self.metrics.snapshot(self.pool.state(), self.pool.max_size())
+    }
+
+    /// Highest schema migration version applied to this database.
+    pub fn schema_version(&self) -> Result<u32> {
+        self.with_connection(crate::migrations::current_version)
+    }
+
+    /// Insert a minimal user row for each ID so foreign keys hold in tests.
+    #[cfg(test)]
+    pub(crate) fn seed_test_users(&self, user_ids: &[&str]) {
+        self.with_connection(|conn| {
+            for id in user_ids {
+                conn.execute(
+                    "INSERT OR IGNORE INTO users (id, username, email, password_hash, salt, role)
+                     VALUES (?1, ?1, ?1 || '@test.local', 'x', 'x', 'QualityEngineer')",
+                    params![id],
+                )?;
+            }
+            Ok(())
+        })
+        .expect("failed to seed test users");
     }
 
     /// Insert audit trail entry
     pub fn insert_audit_entry(&self, entry: &AuditLogEntry) -> Result<()> {
-        let conn = self.pool.get()
-            .map_err(|e| QmsError::Database {
-                message: format!("Failed to get database connection: {}", e),
-            })?;
+        self.timed(&"audit_trail insert", |conn| Self::write_audit_entry(conn, entry))
+    }
+
+    /// Insert audit trail entries in a single transaction, reusing one prepared
+    /// statement; either every entry is stored or none are.
+    pub fn insert_audit_entries(&self, entries: &[AuditLogEntry]) -> Result<usize> {
+        if entries.is_empty() {
+            return Ok(0);
+        }
+        self.timed(&"audit_trail batch insert", |conn| {
+            let tx = conn.unchecked_transaction()?;
+            for entry in entries {
+                Self::write_audit_entry(&tx, entry)?;
+            }
+            tx.commit()?;
+            Ok(entries.len())
+        })
+    }
+
+    /// Run `work` in one transaction: every write it makes is committed when
+    /// it returns `Ok` and rolled back when it returns `Err`.
+    #[track_caller]
+    pub fn unit_of_work<T, F>(&self, work: F) -> Result<T>
+    where
+        F: FnOnce(&UnitOfWork) -> Result<T>,
+    {
+        self.timed(std::panic::Location::caller(), |conn| {
+            let tx = conn.unchecked_transaction()?;
+            let value = work(&UnitOfWork::new(&tx))?;
+            tx.commit()?;
+            Ok(value)
+        })
+    }
 
+    pub(crate) fn write_audit_entry(conn: &Connection, entry: &AuditLogEntry) -> Result<()> {
         let id = Uuid::new_v4().to_string();
-        
-        conn.execute(
+
+        let mut stmt = conn.prepare_cached(
             "INSERT INTO audit_trail (
                 id, timestamp, user_id, action, resource, outcome,
-                ip_address, session_id, metadata, compliance_version, signature_hash
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                ip_address, session_id, metadata, compliance_version, signature_hash, site_id
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        )?;
+        stmt.execute(
             params![
                 id,
                 entry.timestamp.to_rfc3339(),
@@ -411,7 +475,8 @@ impl Database {
                 entry.session_id,
                 serde_json::to_string(&entry.metadata)?,
                 entry.compliance_version,
-                entry.signature_hash
+                entry.signature_hash,
+                entry.site_id
             ],
         )?;
 
@@ -425,10 +490,7 @@ impl Database {
         offset: i64,
         user_id: Option<&str>,
     ) -> Result<Vec<AuditTrailEntry>> {
-        let conn = self.pool.get()
-            .map_err(|e| QmsError::Database {
-                message: format!("Failed to get database connection: {}", e),
-            })?;
+        let conn = self.checkout()?;
 
         let mut query = "SELECT * FROM audit_trail".to_string();
         let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
@@ -444,22 +506,7 @@ impl Database {
 
         let mut stmt = conn.prepare(&query)?;
         let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
-        let audit_iter = stmt.query_map(params_refs.as_slice(), |row| {
-            Ok(AuditTrailEntry {
-                id: row.get(0)?,
-                timestamp: row.get(1)?,
-                user_id: row.get(2)?,
-                action: row.get(3)?,
-                resource: row.get(4)?,
-                outcome: row.get(5)?,
-                ip_address: row.get(6)?,
-                session_id: row.get(7)?,
-                metadata: row.get(8)?,
-                compliance_version: row.get(9)?,
-                signature_hash: row.get(10)?,
-                created_at: row.get(11)?,
-            })
-        })?;
+        let audit_iter = stmt.query_map(params_refs.as_slice(), row_to_audit_entry)?;
 
         let mut entries = Vec::new();
         for entry in audit_iter {
@@ -469,12 +516,51 @@ impl Database {
         Ok(entries)
     }
 
+    /// Iterate over the whole audit trail (optionally one user's entries),
+    /// oldest first, fetching `chunk_size` rows per query so memory stays
+    /// bounded however large the trail is.
+    pub fn stream_audit_entries(&self, user_id: Option<&str>, chunk_size: usize) -> AuditEntryStream {
+        AuditEntryStream {
+            db: self.clone(),
+            user_id: user_id.map(str::to_string),
+            chunk_size: chunk_size.max(1),
+            after: None,
+            buffer: std::collections::VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Number of audit entries, optionally for one user.
+    pub fn count_audit_entries(&self, user_id: Option<&str>) -> Result<u64> {
+        self.with_connection(|conn| {
+            let count: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM audit_trail WHERE ?1 IS NULL OR user_id = ?1",
+                params![user_id],
+                |row| row.get(0),
+            )?;
+            Ok(count as u64)
+        })
+    }
+
+    /// Audit trail entries for a resource and its sub-resources (e.g. `capa:<id>`
+    /// and `capa:<id>/action:<id>`), oldest first.
+    pub fn get_audit_entries_for_resource(&self, resource: &str) -> Result<Vec<AuditTrailEntry>> {
+        self.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, timestamp, user_id, action, resource, outcome, ip_address, session_id,
+                        metadata, compliance_version, signature_hash, created_at, site_id
+                 FROM audit_trail
+                 WHERE resource = ?1 OR substr(resource, 1, length(?1) + 1) = ?1 || '/'
+                 ORDER BY timestamp ASC",
+            )?;
+            let rows = stmt.query_map(params![resource], row_to_audit_entry)?;
+            Ok(rows.collect::<std::result::Result<Vec<_>, _>>()?)
+        })
+    }
+
     /// Verify audit trail integrity
     pub fn verify_audit_integrity(&self) -> Result<AuditIntegrityReport> {
-        let conn = self.pool.get()
-            .map_err(|e| QmsError::Database {
-                message: format!("Failed to get database connection: {}", e),
-            })?;
+        let conn = self.checkout()?;
 
 
 
@@ -525,10 +611,7 @@ impl Database {
 
     /// Check for gaps in audit trail - Critical for FDA compliance
     fn check_audit_gaps(&self) -> Result<Vec<String>> {
-        let conn = self.pool.get()
-            .map_err(|e| QmsError::Database {
-                message: format!("Failed to get database connection: {}", e),
-            })?;
+        let conn = self.checkout()?;
 
         let mut gaps = Vec::new();
 
@@ -622,10 +705,7 @@ impl Database {
 
     /// Create database backup
     pub fn create_backup(&self, backup_path: &str) -> Result<()> {
-        let conn = self.pool.get()
-            .map_err(|e| QmsError::Database {
-                message: format!("Failed to get database connection: {}", e),
-            })?;
+        let conn = self.checkout()?;
 
         let mut backup_conn = Connection::open(backup_path)?;
         let backup = rusqlite::backup::Backup::new(&*conn, &mut backup_conn)?;
@@ -635,7 +715,7 @@ impl Database {
 }
 
 /// Audit trail entry from database
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AuditTrailEntry {
     pub id: String,
     pub timestamp: String,
@@ -649,6 +729,80 @@ pub struct AuditTrailEntry {
     pub compliance_version: String,
     pub signature_hash: Option<String>,
     pub created_at: String,
+    pub site_id: String,
+}
+
+/// Map an `audit_trail` row selected in column order.
+pub(crate) fn row_to_audit_entry(row: &rusqlite::Row) -> rusqlite::Result<AuditTrailEntry> {
+    Ok(AuditTrailEntry {
+        id: row.get(0)?,
+        timestamp: row.get(1)?,
+        user_id: row.get(2)?,
+        action: row.get(3)?,
+        resource: row.get(4)?,
+        outcome: row.get(5)?,
+        ip_address: row.get(6)?,
+        session_id: row.get(7)?,
+        metadata: row.get(8)?,
+        compliance_version: row.get(9)?,
+        signature_hash: row.get(10)?,
+        created_at: row.get(11)?,
+        site_id: row.get(12)?,
+    })
+}
+
+/// Chunked iterator over audit trail entries returned by
+/// [`Database::stream_audit_entries`]. Pages by `(timestamp, id)` rather than
+/// OFFSET, so each chunk is an index seek and entries appended during the
+/// export are picked up rather than shifting pages. A pooled connection is
+/// only held while a chunk is fetched.
+pub struct AuditEntryStream {
+    db: Database,
+    user_id: Option<String>,
+    chunk_size: usize,
+    after: Option<(String, String)>,
+    buffer: std::collections::VecDeque<AuditTrailEntry>,
+    done: bool,
+}
+
+impl AuditEntryStream {
+    fn fetch_chunk(&mut self) -> Result<()> {
+        let (after_timestamp, after_id) = self.after.clone().unzip();
+        let chunk = self.db.timed(&"audit_trail export chunk", |conn| {
+            let mut stmt = conn.prepare_cached(
+                "SELECT id, timestamp, user_id, action, resource, outcome, ip_address, session_id,
+                        metadata, compliance_version, signature_hash, created_at, site_id
+                 FROM audit_trail
+                 WHERE (?1 IS NULL OR user_id = ?1)
+                   AND (?2 IS NULL OR (timestamp, id) > (?2, ?3))
+                 ORDER BY timestamp ASC, id ASC
+                 LIMIT ?4",
+            )?;
+            let rows = stmt.query_map(
+                params![self.user_id, after_timestamp, after_id, self.chunk_size as i64],
+                row_to_audit_entry,
+            )?;
+            rows.collect::<rusqlite::Result<Vec<_>>>().map_err(QmsError::from)
+        })?;
+        self.done = chunk.len() < self.chunk_size;
+        self.after = chunk.last().map(|entry| (entry.timestamp.clone(), entry.id.clone()));
+        self.buffer.extend(chunk);
+        Ok(())
+    }
+}
+
+impl Iterator for AuditEntryStream {
+    type Item = Result<AuditTrailEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buffer.is_empty() && !self.done {
+            if let Err(e) = self.fetch_chunk() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+        self.buffer.pop_front().map(Ok)
+    }
 }
 
 /// Audit integrity report
@@ -735,6 +889,92 @@ mod tests {
         assert_eq!(entries[0].user_id, "user123");
     }
 
+    #[test]
+    fn test_batch_audit_insertion() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        }).unwrap();
+        assert_eq!(db.insert_audit_entries(&[]).unwrap(), 0);
+
+        let entries: Vec<AuditLogEntry> = (0..5_000)
+            .map(|i| AuditLogEntry::new(
+                "importer".to_string(),
+                "supplier_imported".to_string(),
+                format!("supplier:{}", i),
+                AuditOutcome::Success,
+                "bulk-session".to_string(),
+            ))
+            .collect();
+        let started = std::time::Instant::now();
+        assert_eq!(db.insert_audit_entries(&entries).unwrap(), 5_000);
+        let elapsed = started.elapsed();
+        assert!(elapsed < std::time::Duration::from_secs(5), "5000 entries took {:?}", elapsed);
+
+        assert_eq!(db.get_audit_entries(10_000, 0, Some("importer")).unwrap().len(), 5_000);
+    }
+
+    #[test]
+    fn test_stream_audit_entries_in_chunks() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        }).unwrap();
+        let entries: Vec<AuditLogEntry> = (0..25)
+            .map(|i| AuditLogEntry::new(
+                "exporter".to_string(),
+                "capa_created".to_string(),
+                format!("capa:{}", i),
+                AuditOutcome::Success,
+                "stream-session".to_string(),
+            ))
+            .collect();
+        db.insert_audit_entries(&entries).unwrap();
+
+        let streamed: Vec<AuditTrailEntry> =
+            db.stream_audit_entries(Some("exporter"), 7).collect::<Result<_>>().unwrap();
+        assert_eq!(streamed.len() as u64, db.count_audit_entries(Some("exporter")).unwrap());
+        assert_eq!(streamed.len(), 25);
+        let keys: Vec<_> = streamed.iter().map(|e| (e.timestamp.clone(), e.id.clone())).collect();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(keys, sorted, "entries are ordered and never repeated across chunks");
+    }
+
+    #[test]
+    fn test_audit_entries_for_resource() {
+        let config = DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        };
+        let db = Database::new(config).unwrap();
+
+        for resource in ["capa:1", "capa:1/action:a", "capa:10", "capa:2"] {
+            let entry = AuditLogEntry::new(
+                "user123".to_string(),
+                "test_action".to_string(),
+                resource.to_string(),
+                AuditOutcome::Success,
+                "session456".to_string(),
+            );
+            db.insert_audit_entry(&entry).unwrap();
+        }
+
+        let entries = db.get_audit_entries_for_resource("capa:1").unwrap();
+        let resources: Vec<&str> = entries.iter().map(|e| e.resource.as_str()).collect();
+        assert_eq!(resources, vec!["capa:1", "capa:1/action:a"]);
+    }
+
     #[test]
     fn test_audit_integrity_verification() {
         let db = Database::new(DatabaseConfig::default()).unwrap();
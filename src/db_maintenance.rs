@@ -0,0 +1,165 @@
+//! # DB Maintenance - SQLite Checkpoints, Vacuum & Integrity Checks
+//!
+//! Long-lived databases accumulate WAL frames and free pages, and silent
+//! corruption would undermine every record they hold. [`run_maintenance`]
+//! checkpoints and truncates the WAL, returns free pages to the file system
+//! through incremental vacuum and runs `PRAGMA integrity_check`. It is run by
+//! the `database_maintenance` background job, which records the report and
+//! raises a quality event when the integrity check finds problems.
+
+use chrono::{DateTime, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+
+/// Problems listed by `integrity_check` before it stops.
+const MAX_INTEGRITY_PROBLEMS: u32 = 100;
+
+/// `auto_vacuum` mode that enables `PRAGMA incremental_vacuum`.
+const AUTO_VACUUM_INCREMENTAL: i64 = 2;
+
+/// Outcome of `PRAGMA wal_checkpoint(TRUNCATE)`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointResult {
+    /// Whether readers or writers kept the checkpoint from completing
+    pub busy: bool,
+    pub wal_frames: i64,
+    pub checkpointed_frames: i64,
+}
+
+/// Result of one maintenance pass.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceReport {
+    pub checked_at: DateTime<Utc>,
+    /// `None` when the database is not in WAL mode
+    pub checkpoint: Option<CheckpointResult>,
+    /// Pages released by incremental vacuum; `None` when auto-vacuum is not
+    /// incremental (databases created before it was enabled)
+    pub freed_pages: Option<i64>,
+    /// Messages of `integrity_check`; empty when the database is intact
+    pub integrity_problems: Vec<String>,
+}
+
+impl MaintenanceReport {
+    pub fn is_healthy(&self) -> bool {
+        self.integrity_problems.is_empty()
+    }
+
+    /// One-line summary for the job history.
+    pub fn summary(&self) -> String {
+        let checkpoint = match &self.checkpoint {
+            Some(c) if c.busy => format!("checkpoint busy ({}/{} frames)", c.checkpointed_frames, c.wal_frames),
+            Some(c) => format!("checkpointed {} WAL frame(s)", c.checkpointed_frames),
+            None => "no WAL to checkpoint".to_string(),
+        };
+        let vacuum = match self.freed_pages {
+            Some(pages) => format!("{} page(s) freed", pages),
+            None => "incremental vacuum unavailable".to_string(),
+        };
+        let integrity = if self.is_healthy() {
+            "integrity ok".to_string()
+        } else {
+            format!("{} integrity problem(s)", self.integrity_problems.len())
+        };
+        format!("{}; {}; {}", checkpoint, vacuum, integrity)
+    }
+}
+
+/// Checkpoint, vacuum and integrity-check `db`.
+pub fn run_maintenance(db: &Database) -> Result<MaintenanceReport> {
+    db.with_connection(|conn| {
+        Ok(MaintenanceReport {
+            checked_at: Utc::now(),
+            checkpoint: checkpoint(conn)?,
+            freed_pages: incremental_vacuum(conn)?,
+            integrity_problems: integrity_problems(conn)?,
+        })
+    })
+}
+
+fn checkpoint(conn: &Connection) -> Result<Option<CheckpointResult>> {
+    let journal_mode: String = conn.query_row("PRAGMA journal_mode", [], |row| row.get(0))?;
+    if !journal_mode.eq_ignore_ascii_case("wal") {
+        return Ok(None);
+    }
+    let result = conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+        Ok(CheckpointResult {
+            busy: row.get::<_, i64>(0)? != 0,
+            wal_frames: row.get(1)?,
+            checkpointed_frames: row.get(2)?,
+        })
+    })?;
+    Ok(Some(result))
+}
+
+fn incremental_vacuum(conn: &Connection) -> Result<Option<i64>> {
+    let mode: i64 = conn.query_row("PRAGMA auto_vacuum", [], |row| row.get(0))?;
+    if mode != AUTO_VACUUM_INCREMENTAL {
+        return Ok(None);
+    }
+    let free_pages = || -> Result<i64> { Ok(conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?) };
+    let before = free_pages()?;
+    conn.execute_batch("PRAGMA incremental_vacuum")?;
+    Ok(Some(before - free_pages()?))
+}
+
+fn integrity_problems(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(&format!("PRAGMA integrity_check({})", MAX_INTEGRITY_PROBLEMS))?;
+    let messages = stmt.query_map([], |row| row.get::<_, String>(0))?.collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(messages.into_iter().filter(|message| message != "ok").collect())
+}
+
+/// Error raised for a failed integrity check. A damaged database puts the
+/// audit trail at risk, so it is escalated like an audit trail failure.
+pub fn integrity_error(report: &MaintenanceReport) -> QmsError {
+    QmsError::AuditTrail {
+        message: format!(
+            "Database integrity check failed with {} problem(s): {}",
+            report.integrity_problems.len(),
+            report.integrity_problems.iter().take(3).cloned().collect::<Vec<_>>().join("; ")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+
+    #[test]
+    fn test_maintenance_checkpoints_and_checks_integrity() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let db = Database::new(DatabaseConfig {
+            url: dir.path().join("qms.db").display().to_string(),
+            max_connections: 2,
+            wal_mode: true,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.with_connection(|conn| {
+            conn.execute_batch(
+                "CREATE TABLE scratch (payload TEXT);
+                 WITH RECURSIVE n(i) AS (SELECT 1 UNION ALL SELECT i + 1 FROM n WHERE i < 500)
+                 INSERT INTO scratch SELECT hex(randomblob(200)) FROM n;
+                 DROP TABLE scratch;",
+            )?;
+            Ok(())
+        })
+        .unwrap();
+
+        let report = run_maintenance(&db).unwrap();
+        assert!(report.is_healthy());
+        let checkpoint = report.checkpoint.clone().unwrap();
+        assert!(!checkpoint.busy);
+        assert!(report.freed_pages.unwrap() > 0, "dropped table's pages are released");
+        assert!(report.summary().ends_with("integrity ok"));
+
+        let failed = MaintenanceReport { integrity_problems: vec!["row 3 missing from index".to_string()], ..report };
+        let error = integrity_error(&failed);
+        assert!(error.requires_fda_notification());
+        assert!(error.to_string().contains("row 3 missing from index"));
+    }
+}
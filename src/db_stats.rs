@@ -0,0 +1,226 @@
+//! # DB Stats - Connection Pool Observability
+//!
+//! Counters behind [`Database`](crate::database::Database) connection
+//! checkouts: how long callers wait for a pooled connection and how long
+//! they hold it. A hold longer than the slow-query threshold is emitted on
+//! the `qmsrs::slow_query` tracing target with the call site (or operation
+//! name for audit writes) and kept in a short ring buffer, so hot spots show
+//! up in logs, in `GET /metrics/prometheus` and in `qmsrs db stats`.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Connection hold time above which an operation is reported as slow.
+pub const DEFAULT_SLOW_QUERY_THRESHOLD_MS: u64 = 250;
+
+/// Slow operations retained for inspection.
+const RECENT_SLOW_QUERIES: usize = 20;
+
+/// A single operation that held its connection past the threshold.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlowQuery {
+    /// Operation name or `file:line` of the `with_connection` caller
+    pub label: String,
+    pub duration_ms: u64,
+    pub at: DateTime<Utc>,
+}
+
+/// Live counters shared by all clones of a `Database`.
+#[derive(Debug)]
+pub(crate) struct PoolMetrics {
+    acquisitions: AtomicU64,
+    acquisition_failures: AtomicU64,
+    wait_micros_total: AtomicU64,
+    wait_micros_max: AtomicU64,
+    slow_queries: AtomicU64,
+    slow_threshold_ms: AtomicU64,
+    recent_slow: Mutex<VecDeque<SlowQuery>>,
+}
+
+impl Default for PoolMetrics {
+    fn default() -> Self {
+        Self {
+            acquisitions: AtomicU64::new(0),
+            acquisition_failures: AtomicU64::new(0),
+            wait_micros_total: AtomicU64::new(0),
+            wait_micros_max: AtomicU64::new(0),
+            slow_queries: AtomicU64::new(0),
+            slow_threshold_ms: AtomicU64::new(DEFAULT_SLOW_QUERY_THRESHOLD_MS),
+            recent_slow: Mutex::new(VecDeque::with_capacity(RECENT_SLOW_QUERIES)),
+        }
+    }
+}
+
+impl PoolMetrics {
+    pub(crate) fn set_slow_threshold(&self, threshold: Duration) {
+        self.slow_threshold_ms.store(threshold.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_wait(&self, wait: Duration, acquired: bool) {
+        if !acquired {
+            self.acquisition_failures.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        let micros = wait.as_micros() as u64;
+        self.acquisitions.fetch_add(1, Ordering::Relaxed);
+        self.wait_micros_total.fetch_add(micros, Ordering::Relaxed);
+        self.wait_micros_max.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_hold(&self, label: &dyn fmt::Display, held: Duration) {
+        let threshold_ms = self.slow_threshold_ms.load(Ordering::Relaxed);
+        let duration_ms = held.as_millis() as u64;
+        if duration_ms < threshold_ms {
+            return;
+        }
+        self.slow_queries.fetch_add(1, Ordering::Relaxed);
+        tracing::warn!(target: "qmsrs::slow_query", label = %label, duration_ms, threshold_ms, "Slow database operation");
+        if let Ok(mut recent) = self.recent_slow.lock() {
+            if recent.len() == RECENT_SLOW_QUERIES {
+                recent.pop_front();
+            }
+            recent.push_back(SlowQuery { label: label.to_string(), duration_ms, at: Utc::now() });
+        }
+    }
+
+    pub(crate) fn snapshot(&self, state: r2d2::State, max_connections: u32) -> PoolStats {
+        let acquisitions = self.acquisitions.load(Ordering::Relaxed);
+        let wait_total_ms = self.wait_micros_total.load(Ordering::Relaxed) as f64 / 1000.0;
+        PoolStats {
+            max_connections,
+            connections: state.connections,
+            idle_connections: state.idle_connections,
+            active_connections: state.connections - state.idle_connections,
+            acquisitions,
+            acquisition_failures: self.acquisition_failures.load(Ordering::Relaxed),
+            average_wait_ms: if acquisitions == 0 { 0.0 } else { wait_total_ms / acquisitions as f64 },
+            max_wait_ms: self.wait_micros_max.load(Ordering::Relaxed) as f64 / 1000.0,
+            slow_query_threshold_ms: self.slow_threshold_ms.load(Ordering::Relaxed),
+            slow_queries: self.slow_queries.load(Ordering::Relaxed),
+            recent_slow_queries: self
+                .recent_slow
+                .lock()
+                .map(|recent| recent.iter().cloned().collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Point-in-time view of the connection pool.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PoolStats {
+    pub max_connections: u32,
+    pub connections: u32,
+    pub idle_connections: u32,
+    pub active_connections: u32,
+    pub acquisitions: u64,
+    pub acquisition_failures: u64,
+    pub average_wait_ms: f64,
+    pub max_wait_ms: f64,
+    pub slow_query_threshold_ms: u64,
+    pub slow_queries: u64,
+    /// Most recent slow operations, oldest first
+    pub recent_slow_queries: Vec<SlowQuery>,
+}
+
+impl PoolStats {
+    /// Prometheus text exposition format (version 0.0.4).
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: String| {
+            let _ = writeln!(out, "# HELP qmsrs_db_{} {}", name, help);
+            let _ = writeln!(out, "# TYPE qmsrs_db_{} {}", name, kind);
+            let _ = writeln!(out, "qmsrs_db_{} {}", name, value);
+        };
+        metric("pool_max_connections", "gauge", "Configured pool size.", self.max_connections.to_string());
+        metric("pool_connections", "gauge", "Open pooled connections.", self.connections.to_string());
+        metric("pool_idle_connections", "gauge", "Idle pooled connections.", self.idle_connections.to_string());
+        metric("pool_active_connections", "gauge", "Connections checked out.", self.active_connections.to_string());
+        metric("pool_acquisitions_total", "counter", "Successful connection checkouts.", self.acquisitions.to_string());
+        metric(
+            "pool_acquisition_failures_total",
+            "counter",
+            "Checkouts that timed out or failed.",
+            self.acquisition_failures.to_string(),
+        );
+        metric("pool_wait_ms_avg", "gauge", "Mean checkout wait in milliseconds.", self.average_wait_ms.to_string());
+        metric("pool_wait_ms_max", "gauge", "Longest checkout wait in milliseconds.", self.max_wait_ms.to_string());
+        metric(
+            "slow_query_threshold_ms",
+            "gauge",
+            "Hold time above which an operation counts as slow.",
+            self.slow_query_threshold_ms.to_string(),
+        );
+        metric("slow_queries_total", "counter", "Operations over the slow-query threshold.", self.slow_queries.to_string());
+        out
+    }
+}
+
+impl fmt::Display for PoolStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Connections:      {} open / {} max", self.connections, self.max_connections)?;
+        writeln!(f, "  active:         {}", self.active_connections)?;
+        writeln!(f, "  idle:           {}", self.idle_connections)?;
+        writeln!(f, "Checkouts:        {} ({} failed)", self.acquisitions, self.acquisition_failures)?;
+        writeln!(f, "Wait time:        avg {:.3} ms, max {:.3} ms", self.average_wait_ms, self.max_wait_ms)?;
+        write!(f, "Slow operations:  {} over {} ms", self.slow_queries, self.slow_query_threshold_ms)?;
+        for slow in &self.recent_slow_queries {
+            write!(f, "\n  {} {} ms  {}", slow.at.format("%Y-%m-%d %H:%M:%S"), slow.duration_ms, slow.label)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::database::Database;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 4,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_checkouts_and_slow_operations_are_counted() {
+        let db = test_db().with_slow_query_threshold(Duration::from_millis(20));
+        let before = db.pool_stats().acquisitions;
+        db.with_connection(|_| Ok(())).unwrap();
+        db.with_connection(|_| {
+            std::thread::sleep(Duration::from_millis(30));
+            Ok(())
+        })
+        .unwrap();
+
+        let stats = db.pool_stats();
+        assert_eq!(stats.max_connections, 4);
+        assert_eq!(stats.acquisitions, before + 2);
+        assert_eq!(stats.active_connections, 0);
+        assert_eq!(stats.slow_queries, 1);
+        assert!(stats.recent_slow_queries[0].label.contains("db_stats.rs"), "call site is recorded");
+    }
+
+    #[test]
+    fn test_prometheus_exposition() {
+        let stats = test_db().pool_stats();
+        let text = stats.to_prometheus();
+        assert!(text.contains("# TYPE qmsrs_db_pool_active_connections gauge\nqmsrs_db_pool_active_connections 0\n"));
+        assert!(text.contains("# TYPE qmsrs_db_slow_queries_total counter\n"));
+        assert!(text.contains(&format!("qmsrs_db_slow_query_threshold_ms {}\n", DEFAULT_SLOW_QUERY_THRESHOLD_MS)));
+        assert!(stats.to_string().contains("Slow operations:  0 over 250 ms"));
+    }
+}
@@ -0,0 +1,415 @@
+//! # Delegation - Out-of-Office Approval Authority
+//!
+//! A document approver going on leave delegates their approval authority to
+//! another qualified user, one who may write quality records, for a date
+//! range. Approvals made under a delegation record the delegate as the
+//! approver and the delegator they acted for. In CFR Part 11 mode a new
+//! delegation is pending until an approver who is neither party approves it;
+//! otherwise it is active at once. A delegator has at most one delegation
+//! covering any day.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::authorization::{Permission, UserRole};
+use crate::database::Database;
+use crate::error::{QmsError, Result, ValidationErrors};
+
+/// Lifecycle of a delegation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DelegationStatus {
+    /// Awaiting approval (CFR Part 11 mode)
+    Pending,
+    Active,
+    Rejected,
+    /// Withdrawn before or during its date range
+    Revoked,
+}
+
+impl DelegationStatus {
+    pub const ALL: [DelegationStatus; 4] =
+        [DelegationStatus::Pending, DelegationStatus::Active, DelegationStatus::Rejected, DelegationStatus::Revoked];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DelegationStatus::Pending => "pending",
+            DelegationStatus::Active => "active",
+            DelegationStatus::Rejected => "rejected",
+            DelegationStatus::Revoked => "revoked",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.as_str() == value)
+    }
+}
+
+/// Approval authority handed from `delegator` to `delegate` for a date range.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Delegation {
+    pub id: Uuid,
+    pub delegator: String,
+    pub delegate: String,
+    /// First and last day of the delegation, inclusive
+    pub starts_on: NaiveDate,
+    pub ends_on: NaiveDate,
+    pub reason: String,
+    pub status: DelegationStatus,
+    pub requested_by: String,
+    pub requested_at: DateTime<Utc>,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+impl Delegation {
+    /// Whether the delegation is in force on `day`.
+    pub fn covers(&self, day: NaiveDate) -> bool {
+        self.status == DelegationStatus::Active && self.starts_on <= day && day <= self.ends_on
+    }
+}
+
+/// Delegations stored in the `approval_delegations` table.
+pub struct DelegationRepo<'a> {
+    db: &'a Database,
+    approval_required: bool,
+}
+
+impl<'a> DelegationRepo<'a> {
+    /// Delegations that need approval, as in CFR Part 11 mode.
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, approval_required: true }
+    }
+
+    /// Whether new delegations wait for approval; pass
+    /// `compliance.cfr_part_11_mode`.
+    pub fn with_approval_required(mut self, required: bool) -> Self {
+        self.approval_required = required;
+        self
+    }
+
+    /// Delegate the approval authority of `delegator` to `delegate` from
+    /// `starts_on` to `ends_on`. Requested by the delegator or a user
+    /// manager.
+    pub fn request(
+        &self,
+        delegator: &str,
+        delegate: &str,
+        starts_on: NaiveDate,
+        ends_on: NaiveDate,
+        reason: &str,
+        requested_by: &str,
+    ) -> Result<Delegation> {
+        if requested_by != delegator && !self.has_permission(requested_by, Permission::ManageUsers)? {
+            return Err(QmsError::Security {
+                message: format!("User {} may not delegate the approvals of {}", requested_by, delegator),
+            });
+        }
+        let mut errors = ValidationErrors::new();
+        if !self.has_permission(delegator, Permission::ApproveDocuments)? {
+            errors.add("delegator", format!("{} has no approval authority to delegate", delegator));
+        }
+        if delegate == delegator {
+            errors.add("delegate", "A user cannot delegate to themselves");
+        } else if !self.has_permission(delegate, Permission::WriteRecords)? {
+            errors.add("delegate", format!("{} is not qualified to approve quality records", delegate));
+        }
+        if ends_on < starts_on {
+            errors.add("ends_on", "The delegation ends before it starts");
+        }
+        if reason.trim().is_empty() {
+            errors.add("reason", "A reason is required");
+        }
+        errors.into_result()?;
+
+        let status = if self.approval_required { DelegationStatus::Pending } else { DelegationStatus::Active };
+        let id = Uuid::new_v4();
+        self.db.unit_of_work(|uow| {
+            let conn = uow.connection();
+            let overlapping: Option<String> = conn
+                .query_row(
+                    "SELECT delegate FROM approval_delegations
+                     WHERE delegator = ?1 AND status IN ('pending', 'active') AND starts_on <= ?3 AND ends_on >= ?2",
+                    params![delegator, starts_on.to_string(), ends_on.to_string()],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(existing) = overlapping {
+                return Err(QmsError::Validation {
+                    field: "starts_on".to_string(),
+                    message: format!("{} already delegates to {} during these dates", delegator, existing),
+                });
+            }
+            conn.execute(
+                "INSERT INTO approval_delegations
+                     (id, delegator, delegate, starts_on, ends_on, reason, status, requested_by, requested_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    id.to_string(),
+                    delegator,
+                    delegate,
+                    starts_on.to_string(),
+                    ends_on.to_string(),
+                    reason.trim(),
+                    status.as_str(),
+                    requested_by,
+                    Utc::now().to_rfc3339()
+                ],
+            )?;
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                requested_by,
+                "delegation_requested",
+                &resource(id),
+                "Success",
+                Some(format!(
+                    "{} to {} from {} to {} ({}): {}",
+                    delegator,
+                    delegate,
+                    starts_on,
+                    ends_on,
+                    status.as_str(),
+                    reason.trim()
+                )),
+            )
+        })?;
+        self.get(id)
+    }
+
+    /// Approve a pending delegation. The approver needs approval authority
+    /// and must be neither party.
+    pub fn approve(&self, id: Uuid, approver: &str) -> Result<Delegation> {
+        self.decide(id, approver, DelegationStatus::Active)
+    }
+
+    pub fn reject(&self, id: Uuid, approver: &str) -> Result<Delegation> {
+        self.decide(id, approver, DelegationStatus::Rejected)
+    }
+
+    /// Withdraw a pending or active delegation, as the delegator or a user
+    /// manager.
+    pub fn revoke(&self, id: Uuid, user: &str) -> Result<Delegation> {
+        let delegation = self.get(id)?;
+        if user != delegation.delegator && !self.has_permission(user, Permission::ManageUsers)? {
+            return Err(QmsError::Security {
+                message: format!("User {} may not revoke the delegations of {}", user, delegation.delegator),
+            });
+        }
+        let revocable = [DelegationStatus::Pending, DelegationStatus::Active];
+        self.set_status(&delegation, &revocable, DelegationStatus::Revoked, user)
+    }
+
+    pub fn get(&self, id: Uuid) -> Result<Delegation> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM approval_delegations WHERE id = ?1", DELEGATION_COLUMNS),
+                        params![id.to_string()],
+                        row_to_delegation,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "delegation".to_string(), id: id.to_string() })
+    }
+
+    /// Every delegation, latest start first.
+    pub fn list(&self) -> Result<Vec<Delegation>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM approval_delegations ORDER BY starts_on DESC, requested_at DESC",
+                DELEGATION_COLUMNS
+            ))?;
+            let delegations = stmt.query_map([], row_to_delegation)?.collect::<rusqlite::Result<_>>()?;
+            Ok(delegations)
+        })
+    }
+
+    /// Delegations `delegate` may act under on `day`, earliest start first.
+    /// Delegators who have since lost their approval authority are left out.
+    pub fn in_force_for(&self, delegate: &str, day: NaiveDate) -> Result<Vec<Delegation>> {
+        let delegations: Vec<Delegation> = self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM approval_delegations
+                 WHERE delegate = ?1 AND status = 'active' AND starts_on <= ?2 AND ends_on >= ?2
+                 ORDER BY starts_on",
+                DELEGATION_COLUMNS
+            ))?;
+            let delegations = stmt.query_map(params![delegate, day.to_string()], row_to_delegation)?;
+            Ok(delegations.collect::<rusqlite::Result<_>>()?)
+        })?;
+        let mut in_force = Vec::with_capacity(delegations.len());
+        for delegation in delegations {
+            if self.has_permission(&delegation.delegator, Permission::ApproveDocuments)? {
+                in_force.push(delegation);
+            }
+        }
+        Ok(in_force)
+    }
+
+    fn decide(&self, id: Uuid, approver: &str, decision: DelegationStatus) -> Result<Delegation> {
+        let delegation = self.get(id)?;
+        if approver == delegation.delegator || approver == delegation.delegate {
+            return Err(QmsError::Security {
+                message: format!("User {} is a party to delegation {} and cannot decide on it", approver, id),
+            });
+        }
+        if !self.has_permission(approver, Permission::ApproveDocuments)? {
+            return Err(QmsError::Security {
+                message: format!("User {} is not permitted to approve delegations", approver),
+            });
+        }
+        self.set_status(&delegation, &[DelegationStatus::Pending], decision, approver)
+    }
+
+    fn set_status(
+        &self,
+        delegation: &Delegation,
+        from: &[DelegationStatus],
+        to: DelegationStatus,
+        user: &str,
+    ) -> Result<Delegation> {
+        if !from.contains(&delegation.status) {
+            return Err(QmsError::Validation {
+                field: "status".to_string(),
+                message: format!("Delegation {} is {}", delegation.id, delegation.status.as_str()),
+            });
+        }
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE approval_delegations SET status = ?2, decided_by = ?3, decided_at = ?4 WHERE id = ?1",
+                params![delegation.id.to_string(), to.as_str(), user, Utc::now().to_rfc3339()],
+            )?;
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                user,
+                &format!("delegation_{}", to.as_str()),
+                &resource(delegation.id),
+                "Success",
+                Some(format!("{} to {}", delegation.delegator, delegation.delegate)),
+            )
+        })?;
+        self.get(delegation.id)
+    }
+
+    fn has_permission(&self, user: &str, permission: Permission) -> Result<bool> {
+        Ok(UserRole::of_user(self.db, user)?.is_some_and(|role| role.has_permission(permission)))
+    }
+}
+
+fn resource(id: Uuid) -> String {
+    format!("delegation:{}", id)
+}
+
+const DELEGATION_COLUMNS: &str =
+    "id, delegator, delegate, starts_on, ends_on, reason, status, requested_by, requested_at, decided_by, decided_at";
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn parse_timestamp(row: &Row, index: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|timestamp| timestamp.with_timezone(&Utc))
+                .map_err(|e| conversion_error(index, e.to_string()))
+        })
+        .transpose()
+}
+
+fn parse_date(row: &Row, index: usize) -> rusqlite::Result<NaiveDate> {
+    let value: String = row.get(index)?;
+    NaiveDate::parse_from_str(&value, "%Y-%m-%d").map_err(|e| conversion_error(index, e.to_string()))
+}
+
+fn row_to_delegation(row: &Row) -> rusqlite::Result<Delegation> {
+    let id: String = row.get(0)?;
+    let status: String = row.get(6)?;
+    Ok(Delegation {
+        id: Uuid::parse_str(&id).map_err(|e| conversion_error(0, e.to_string()))?,
+        delegator: row.get(1)?,
+        delegate: row.get(2)?,
+        starts_on: parse_date(row, 3)?,
+        ends_on: parse_date(row, 4)?,
+        reason: row.get(5)?,
+        status: DelegationStatus::parse(&status)
+            .ok_or_else(|| conversion_error(6, format!("unknown delegation status {}", status)))?,
+        requested_by: row.get(7)?,
+        requested_at: parse_timestamp(row, 8)?.ok_or_else(|| conversion_error(8, "missing request time".to_string()))?,
+        decided_by: row.get(9)?,
+        decided_at: parse_timestamp(row, 10)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::change_history::ChangeReason;
+    use crate::config::DatabaseConfig;
+    use crate::document::DocumentApprovals;
+    use chrono::Duration;
+
+    #[test]
+    fn test_delegated_approval_records_delegate_and_delegator() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["qm", "qm2", "eng", "viewer"]);
+        db.with_connection(|conn| {
+            conn.execute("UPDATE users SET role = 'QualityManager' WHERE id IN ('qm', 'qm2')", [])?;
+            conn.execute("UPDATE users SET role = 'Viewer' WHERE id = 'viewer'", [])?;
+            conn.execute(
+                "INSERT INTO documents
+                     (id, document_number, title, version, status, document_type, content_hash, created_by)
+                 VALUES ('d1', 'SOP-001', 'Cleaning', '1.0', 'UnderReview', 'SOP', 'h', 'eng')",
+                [],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        let today = Utc::now().date_naive();
+        let (start, end) = (today - Duration::days(1), today + Duration::days(14));
+        let repo = DelegationRepo::new(&db);
+        assert!(repo.request("qm", "viewer", start, end, "Annual leave", "qm").is_err(), "viewers are not qualified");
+        assert!(repo.request("eng", "qm", start, end, "Annual leave", "eng").is_err(), "nothing to delegate");
+        assert!(repo.request("qm", "eng", start, end, "Annual leave", "qm2").is_err(), "only the delegator asks");
+
+        let delegation = repo.request("qm", "eng", start, end, "Annual leave", "qm").unwrap();
+        assert_eq!(delegation.status, DelegationStatus::Pending);
+        assert!(repo.request("qm", "qm2", today, today, "Conference", "qm").is_err(), "overlaps");
+        let approvals = DocumentApprovals::new(&db);
+        let reason = ChangeReason::new("Procedure reviewed").unwrap();
+        assert!(approvals.approve_on_behalf("d1", "eng", "qm", 1, &reason).is_err(), "not yet approved");
+        assert!(repo.approve(delegation.id, "eng").is_err(), "parties cannot approve");
+        let delegation = repo.approve(delegation.id, "qm2").unwrap();
+        assert!(delegation.covers(today) && !delegation.covers(end + Duration::days(1)));
+
+        assert!(approvals.approve_on_behalf("d1", "eng", "qm2", 1, &reason).is_err(), "no delegation from qm2");
+        approvals.approve_on_behalf("d1", "eng", "qm", 1, &reason).unwrap();
+        let state = approvals.fetch("d1").unwrap().unwrap();
+        assert_eq!((state.approved_by.as_deref(), state.approved_on_behalf_of.as_deref()), (Some("eng"), Some("qm")));
+        let history = crate::change_history::ChangeHistoryRepo::new(&db).history("document", "d1").unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].reason, reason);
+        let fields: Vec<&str> = history[0].changes.iter().map(|change| change.field.as_str()).collect();
+        assert_eq!(fields, vec!["approved_by", "approved_on_behalf_of", "status"]);
+        let audit = db.get_audit_entries_for_resource("document:d1").unwrap();
+        assert!(audit[0].metadata.as_deref().unwrap().contains(&delegation.id.to_string()));
+
+        repo.revoke(delegation.id, "qm").unwrap();
+        assert!(repo.in_force_for("eng", today).unwrap().is_empty());
+        let immediate = DelegationRepo::new(&db).with_approval_required(false);
+        let delegation = immediate.request("qm", "qm2", start, end, "Annual leave", "qm").unwrap();
+        assert_eq!(delegation.status, DelegationStatus::Active);
+        assert_eq!(immediate.list().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,347 @@
+//! # Demo Seed - Sample Data for Evaluation
+//!
+//! `qmsrs seed --demo` fills an empty database with a small, realistic data
+//! set: controlled documents in every stage of their lifecycle, open and
+//! closed CAPAs (one overdue), risk assessments, qualified and pending
+//! suppliers, training assignments and the audit history of all of them. The
+//! TUI and the API then show meaningful content straight away.
+//!
+//! Demo content is clearly flagged so it can never pass for real quality
+//! records. Every title and name starts with [`DEMO_PREFIX`], and the
+//! records belong to the `demo.*` users, whose passwords cannot be used to
+//! log in. Each audit entry is marked as demo data. Seeding runs in one
+//! transaction and refuses a database that already holds quality records.
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::unit_of_work::UnitOfWork;
+
+/// Prefix of every demo title and name.
+pub const DEMO_PREFIX: &str = "[DEMO]";
+
+/// Demo accounts and their roles; the password hash matches no password.
+pub const DEMO_USERS: [(&str, &str); 3] = [
+    ("demo.quality_manager", "QualityManager"),
+    ("demo.engineer", "QualityEngineer"),
+    ("demo.operator", "Viewer"),
+];
+
+/// Tables that must be empty before demo data is seeded.
+const QUALITY_TABLES: [&str; 5] = ["documents", "capa_records", "risk_assessments", "suppliers", "training_records"];
+
+/// Records created by [`seed_demo_data`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DemoSeedSummary {
+    pub users: usize,
+    pub documents: usize,
+    pub capas: usize,
+    pub risks: usize,
+    pub suppliers: usize,
+    pub trainings: usize,
+    pub audit_entries: usize,
+}
+
+/// Populate an empty database with demo records dated relative to `now`.
+pub fn seed_demo_data(db: &Database, now: DateTime<Utc>) -> Result<DemoSeedSummary> {
+    let audit = AuditManager::new(db.clone());
+    db.unit_of_work(|uow| {
+        for table in QUALITY_TABLES {
+            let sql = format!("SELECT COUNT(*) FROM {}", table);
+            let rows: i64 = uow.connection().query_row(&sql, [], |row| row.get(0))?;
+            if rows > 0 {
+                return Err(QmsError::Validation {
+                    field: "database".to_string(),
+                    message: format!("Demo data needs an empty database, but {} has {} row(s)", table, rows),
+                });
+            }
+        }
+        let mut seeder = Seeder { uow, auditor: &audit, summary: DemoSeedSummary::default() };
+        seeder.users()?;
+        seeder.documents(now)?;
+        seeder.capas(now)?;
+        seeder.risks()?;
+        seeder.suppliers(now)?;
+        seeder.trainings(now)?;
+        Ok(seeder.summary)
+    })
+}
+
+struct Seeder<'u, 'a> {
+    uow: &'u UnitOfWork<'a>,
+    auditor: &'u AuditManager,
+    summary: DemoSeedSummary,
+}
+
+impl Seeder<'_, '_> {
+    fn conn(&self) -> &Connection {
+        self.uow.connection()
+    }
+
+    fn audit(&mut self, user: &str, action: &str, resource: &str, details: &str) -> Result<()> {
+        let details = Some(format!("Demo data: {}", details));
+        self.auditor.log_action_in(self.uow, user, action, resource, "Success", details)?;
+        self.summary.audit_entries += 1;
+        Ok(())
+    }
+
+    fn users(&mut self) -> Result<()> {
+        for (id, role) in DEMO_USERS {
+            self.conn().execute(
+                "INSERT INTO users (id, username, email, password_hash, salt, role)
+                 VALUES (?1, ?1, ?1 || '@demo.invalid', '!', '!', ?2)",
+                params![id, role],
+            )?;
+            self.audit(DEMO_USERS[0].0, "user_created", &format!("user:{}", id), role)?;
+            self.summary.users += 1;
+        }
+        Ok(())
+    }
+
+    fn documents(&mut self, now: DateTime<Utc>) -> Result<()> {
+        let (manager, engineer) = (DEMO_USERS[0].0, DEMO_USERS[1].0);
+        let day = |days: i64| (now + Duration::days(days)).to_rfc3339();
+        // number, title, version, status, type, effective, review
+        let documents = [
+            ("DEMO-SOP-001", "Document Control", "3.0", "Effective", "SOP", Some(day(-400)), Some(day(330))),
+            ("DEMO-SOP-002", "CAPA Process", "2.1", "Effective", "SOP", Some(day(-350)), Some(day(15))),
+            ("DEMO-WI-001", "Pouch Sealer Setup", "1.4", "Effective", "WorkInstruction", Some(day(-90)), None),
+            ("DEMO-SOP-003", "Complaint Handling", "1.0", "UnderReview", "SOP", None, None),
+            ("DEMO-FRM-001", "Incoming Inspection Record", "0.1", "Draft", "Form", None, None),
+        ];
+        for (number, title, version, status, document_type, effective_date, review_date) in documents {
+            let id = format!("demo-{}", number.to_ascii_lowercase());
+            let approved_by = effective_date.as_ref().map(|_| manager);
+            self.conn().execute(
+                "INSERT INTO documents (id, document_number, title, version, status, document_type, content_hash,
+                                        created_by, approved_by, effective_date, review_date)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    id,
+                    number,
+                    format!("{} {}", DEMO_PREFIX, title),
+                    version,
+                    status,
+                    document_type,
+                    format!("demo-{}-{}", number, version),
+                    engineer,
+                    approved_by,
+                    effective_date,
+                    review_date,
+                ],
+            )?;
+            let resource = format!("document:{}", id);
+            self.audit(engineer, "document_created", &resource, &format!("{} {}", number, title))?;
+            if let Some(approver) = approved_by {
+                self.audit(approver, "document_approved", &resource, &format!("{} version {}", number, version))?;
+            }
+            self.summary.documents += 1;
+        }
+        Ok(())
+    }
+
+    fn capas(&mut self, now: DateTime<Utc>) -> Result<()> {
+        let (manager, engineer) = (DEMO_USERS[0].0, DEMO_USERS[1].0);
+        // title, type, priority, status, opened days ago, due in days
+        let capas = [
+            ("Pouch seal leaks at lot release", "Corrective", "High", "RootCauseAnalysis", 40, -5),
+            ("Label misprints on carton", "Corrective", "Medium", "CorrectiveActionInProgress", 25, 20),
+            ("Supplier certificate lapses", "Preventive", "Low", "Identified", 3, 60),
+            ("Sterilizer cycle deviation", "Combined", "Critical", "Closed", 120, -60),
+        ];
+        for (index, (title, capa_type, priority, status, opened, due)) in capas.into_iter().enumerate() {
+            let id = format!("demo-capa-{}", index + 1);
+            let created_at = (now - Duration::days(opened)).to_rfc3339();
+            let closed_date = (status == "Closed").then(|| (now + Duration::days(due - 5)).to_rfc3339());
+            self.conn().execute(
+                "INSERT INTO capa_records (id, title, description, capa_type, priority, status, initiator_id,
+                                           assigned_to, created_at, updated_at, due_date, closed_date)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9, ?10, ?11)",
+                params![
+                    id,
+                    format!("{} {}", DEMO_PREFIX, title),
+                    format!("Sample CAPA for evaluation: {}", title.to_lowercase()),
+                    capa_type,
+                    priority,
+                    status,
+                    manager,
+                    engineer,
+                    created_at,
+                    (now + Duration::days(due)).to_rfc3339(),
+                    closed_date,
+                ],
+            )?;
+            self.audit(manager, "capa_created", &format!("capa:{}", id), title)?;
+            self.summary.capas += 1;
+        }
+        Ok(())
+    }
+
+    fn risks(&mut self) -> Result<()> {
+        let engineer = DEMO_USERS[1].0;
+        // hazard, situation, harm, initial severity/probability, residual severity/probability
+        let risks = [
+            ("Loss of sterile barrier", "Seal leak goes undetected", "Infection", (5, 3), (5, 1)),
+            ("Wrong device size", "Mislabelled carton", "Delayed treatment", (3, 3), (3, 1)),
+            ("Sharp edge on housing", "Handling during setup", "Minor laceration", (2, 2), (2, 1)),
+        ];
+        for (index, (hazard, situation, harm, (severity, probability), (residual_severity, residual_probability))) in
+            risks.into_iter().enumerate()
+        {
+            let id = format!("demo-risk-{}", index + 1);
+            let level = severity * probability;
+            let residual_level = residual_severity * residual_probability;
+            self.conn().execute(
+                "INSERT INTO risk_assessments (id, device_name, hazard_description, hazardous_situation,
+                     foreseeable_sequence, harm_description, initial_severity, initial_probability,
+                     initial_risk_level, acceptability, residual_severity, residual_probability,
+                     residual_risk_level, residual_acceptability, created_by, status)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, 'Approved')",
+                params![
+                    id,
+                    format!("{} Sterile Pouch System", DEMO_PREFIX),
+                    hazard,
+                    situation,
+                    "Sample sequence of events for evaluation",
+                    harm,
+                    severity,
+                    probability,
+                    level,
+                    acceptability(level),
+                    residual_severity,
+                    residual_probability,
+                    residual_level,
+                    acceptability(residual_level),
+                    engineer,
+                ],
+            )?;
+            self.audit(engineer, "risk_assessment_created", &format!("risk:{}", id), hazard)?;
+            self.summary.risks += 1;
+        }
+        Ok(())
+    }
+
+    fn suppliers(&mut self, now: DateTime<Utc>) -> Result<()> {
+        let manager = DEMO_USERS[0].0;
+        // name, status, qualified days ago
+        let suppliers = [
+            ("Acme Pouch Films", "Qualified", Some(300)),
+            ("Northern Label Print", "Qualified", Some(700)),
+            ("Sterilization Services Ltd", "Pending", None),
+        ];
+        for (name, status, qualified) in suppliers {
+            let id = Uuid::new_v4().to_string();
+            let qualification_date = qualified.map(|days| (now - Duration::days(days)).date_naive().to_string());
+            let expiry_date = qualified.map(|days| (now + Duration::days(730 - days)).date_naive().to_string());
+            self.conn().execute(
+                "INSERT INTO suppliers (id, name, contact_info, qualification_status, qualification_date,
+                                        qualification_expiry_date, approved_by)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    id,
+                    format!("{} {}", DEMO_PREFIX, name),
+                    "quality@supplier.demo.invalid",
+                    status,
+                    qualification_date,
+                    expiry_date,
+                    qualified.map(|_| manager),
+                ],
+            )?;
+            self.audit(manager, "supplier_registered", &format!("supplier:{}", id), name)?;
+            self.summary.suppliers += 1;
+        }
+        Ok(())
+    }
+
+    fn trainings(&mut self, now: DateTime<Utc>) -> Result<()> {
+        let manager = DEMO_USERS[0].0;
+        // employee, item, due in days, completed days ago, status
+        let trainings = [
+            (DEMO_USERS[1].0, "DEMO-SOP-001 Document Control", -30, Some(35), "Completed"),
+            (DEMO_USERS[1].0, "DEMO-SOP-002 CAPA Process", 14, None, "InProgress"),
+            (DEMO_USERS[2].0, "DEMO-WI-001 Pouch Sealer Setup", -7, None, "Overdue"),
+            (DEMO_USERS[2].0, "GMP Basics", 30, None, "Pending"),
+        ];
+        for (employee, item, due, completed, status) in trainings {
+            let id = Uuid::new_v4().to_string();
+            self.conn().execute(
+                "INSERT INTO training_records (id, employee_id, training_item, mandatory, assigned_by, due_date,
+                                               completion_date, status)
+                 VALUES (?1, ?2, ?3, 1, ?4, ?5, ?6, ?7)",
+                params![
+                    id,
+                    employee,
+                    format!("{} {}", DEMO_PREFIX, item),
+                    manager,
+                    (now + Duration::days(due)).date_naive().to_string(),
+                    completed.map(|days: i64| (now - Duration::days(days)).date_naive().to_string()),
+                    status,
+                ],
+            )?;
+            let details = format!("{} to {}", item, employee);
+            self.audit(manager, "training_assigned", &format!("training:{}", id), &details)?;
+            self.summary.trainings += 1;
+        }
+        Ok(())
+    }
+}
+
+fn acceptability(risk_level: i32) -> &'static str {
+    match risk_level {
+        0..=4 => "Acceptable",
+        5..=12 => "Tolerable",
+        _ => "Unacceptable",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::reporting_views::SummaryRepo;
+    use crate::site::DEFAULT_SITE_ID;
+
+    #[test]
+    fn test_seeds_flagged_demo_data_into_empty_database_only() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let now = Utc::now();
+        let summary = seed_demo_data(&db, now).unwrap();
+        assert_eq!((summary.documents, summary.capas, summary.risks), (5, 4, 3));
+        assert_eq!((summary.users, summary.suppliers, summary.trainings), (3, 3, 4));
+        assert_eq!(summary.audit_entries, 3 + 5 + 3 + 4 + 3 + 3 + 4);
+
+        let untagged: i64 = db
+            .with_connection(|conn| {
+                Ok(conn.query_row(
+                    "SELECT (SELECT COUNT(*) FROM documents WHERE title NOT LIKE '[DEMO] %')
+                          + (SELECT COUNT(*) FROM capa_records WHERE title NOT LIKE '[DEMO] %')
+                          + (SELECT COUNT(*) FROM suppliers WHERE name NOT LIKE '[DEMO] %')
+                          + (SELECT COUNT(*) FROM training_records WHERE training_item NOT LIKE '[DEMO] %')",
+                    [],
+                    |row| row.get(0),
+                )?)
+            })
+            .unwrap();
+        assert_eq!(untagged, 0);
+        let audit = db.get_audit_entries_for_resource("capa:demo-capa-1").unwrap();
+        assert!(audit[0].metadata.as_deref().unwrap_or_default().contains("Demo data"));
+
+        let capa = SummaryRepo::new(&db).capa_metrics(DEFAULT_SITE_ID, now).unwrap();
+        assert_eq!(capa.total_count, 4);
+        assert_eq!(capa.overdue_count, 1);
+
+        assert!(matches!(seed_demo_data(&db, now), Err(QmsError::Validation { .. })));
+    }
+}
@@ -0,0 +1,314 @@
+//! # Display Time - Time Zone Aware Presentation
+//!
+//! Timestamps are always persisted as RFC 3339 UTC so the audit trail has a
+//! single unambiguous time base. Only presentation layers (TUI, reports, CSV
+//! exports) convert to a [`DisplayTimezone`], configured per deployment with
+//! `application.display_timezone` and optionally overridden per user.
+
+use chrono::{DateTime, FixedOffset, SecondsFormat, Utc};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::database::{AuditTrailEntry, Database};
+use crate::error::{QmsError, Result};
+
+/// Largest UTC offset in use worldwide (UTC+14, Line Islands).
+const MAX_OFFSET_SECONDS: i32 = 14 * 3600;
+
+/// Fixed UTC offset used to display timestamps, written `UTC` or `±HH:MM`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DisplayTimezone(FixedOffset);
+
+impl Default for DisplayTimezone {
+    fn default() -> Self {
+        Self::utc()
+    }
+}
+
+impl DisplayTimezone {
+    pub fn utc() -> Self {
+        Self(FixedOffset::east_opt(0).expect("zero offset is valid"))
+    }
+
+    /// Offset east of UTC in seconds.
+    pub fn offset_seconds(&self) -> i32 {
+        self.0.local_minus_utc()
+    }
+
+    /// Convert a stored UTC timestamp to this zone.
+    pub fn to_local(&self, timestamp: DateTime<Utc>) -> DateTime<FixedOffset> {
+        timestamp.with_timezone(&self.0)
+    }
+
+    /// `YYYY-MM-DD HH:MM <zone>` for on-screen and report display.
+    pub fn format(&self, timestamp: DateTime<Utc>) -> String {
+        format!("{} {}", self.to_local(timestamp).format("%Y-%m-%d %H:%M"), self)
+    }
+
+    /// RFC 3339 with this zone's offset, for machine-readable exports.
+    pub fn format_rfc3339(&self, timestamp: DateTime<Utc>) -> String {
+        self.to_local(timestamp).to_rfc3339_opts(SecondsFormat::Secs, false)
+    }
+}
+
+impl std::fmt::Display for DisplayTimezone {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let offset = self.offset_seconds();
+        if offset == 0 {
+            return f.write_str("UTC");
+        }
+        let sign = if offset < 0 { '-' } else { '+' };
+        let minutes = offset.abs() / 60;
+        write!(f, "{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+    }
+}
+
+impl std::str::FromStr for DisplayTimezone {
+    type Err = QmsError;
+
+    /// Accepts `UTC`, `Z`, `+05:30`, `-0800`, `UTC+2`.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || QmsError::Configuration {
+            message: format!("Invalid display timezone '{}': expected UTC or ±HH:MM", s),
+        };
+        let trimmed = s.trim();
+        let rest = trimmed.strip_prefix("UTC").unwrap_or(trimmed);
+        if rest.is_empty() || rest == "Z" {
+            return Ok(Self::utc());
+        }
+
+        let (sign, digits) = if let Some(digits) = rest.strip_prefix('+') {
+            (1, digits)
+        } else if let Some(digits) = rest.strip_prefix('-') {
+            (-1, digits)
+        } else {
+            return Err(invalid());
+        };
+        if !digits.is_ascii() {
+            return Err(invalid());
+        }
+        let (hours, minutes) = match digits.split_once(':') {
+            Some((h, m)) => (h, m),
+            None if digits.len() == 4 => digits.split_at(2),
+            None => (digits, "0"),
+        };
+        let hours: i32 = hours.parse().map_err(|_| invalid())?;
+        let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+        if !(0..60).contains(&minutes) {
+            return Err(invalid());
+        }
+        let seconds = sign * (hours * 3600 + minutes * 60);
+        if seconds.abs() > MAX_OFFSET_SECONDS {
+            return Err(invalid());
+        }
+        FixedOffset::east_opt(seconds).map(Self).ok_or_else(invalid)
+    }
+}
+
+impl TryFrom<String> for DisplayTimezone {
+    type Error = QmsError;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<DisplayTimezone> for String {
+    fn from(tz: DisplayTimezone) -> Self {
+        tz.to_string()
+    }
+}
+
+/// Parse a persisted timestamp, rejecting anything that is not RFC 3339 UTC.
+pub fn parse_stored_utc(value: &str) -> Result<DateTime<Utc>> {
+    let parsed = DateTime::parse_from_rfc3339(value).map_err(|e| QmsError::Validation {
+        field: "timestamp".to_string(),
+        message: format!("'{}' is not RFC 3339: {}", value, e),
+    })?;
+    if parsed.offset().local_minus_utc() != 0 {
+        return Err(QmsError::Validation {
+            field: "timestamp".to_string(),
+            message: format!("'{}' is not stored in UTC", value),
+        });
+    }
+    Ok(parsed.with_timezone(&Utc))
+}
+
+/// IDs of audit trail entries whose timestamp is not RFC 3339 UTC.
+pub fn find_non_utc_audit_timestamps(db: &Database) -> Result<Vec<String>> {
+    db.with_connection(|conn| {
+        let mut stmt = conn.prepare("SELECT id, timestamp FROM audit_trail ORDER BY timestamp")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        let mut offending = Vec::new();
+        for row in rows {
+            let (id, timestamp) = row?;
+            if parse_stored_utc(&timestamp).is_err() {
+                offending.push(id);
+            }
+        }
+        Ok(offending)
+    })
+}
+
+/// Header line of audit trail CSV exports.
+pub const AUDIT_CSV_HEADER: &str = "timestamp_local,timestamp_utc,user_id,action,resource,outcome,site_id\n";
+
+/// Render audit entries as CSV, with the display-zone time next to the
+/// stored UTC value so exports stay traceable to the audit trail.
+pub fn audit_trail_csv(entries: &[AuditTrailEntry], tz: DisplayTimezone) -> Result<String> {
+    let mut out = String::from(AUDIT_CSV_HEADER);
+    for entry in entries {
+        out.push_str(&audit_csv_row(entry, tz)?);
+    }
+    Ok(out)
+}
+
+/// One CSV line (with trailing newline) for `entry`.
+pub fn audit_csv_row(entry: &AuditTrailEntry, tz: DisplayTimezone) -> Result<String> {
+    let local = tz.format_rfc3339(parse_stored_utc(&entry.timestamp)?);
+    let fields = [
+        local.as_str(),
+        entry.timestamp.as_str(),
+        entry.user_id.as_str(),
+        entry.action.as_str(),
+        entry.resource.as_str(),
+        entry.outcome.as_str(),
+        entry.site_id.as_str(),
+    ];
+    let mut row = fields.iter().map(|field| csv_field(field)).collect::<Vec<_>>().join(",");
+    row.push('\n');
+    Ok(row)
+}
+
+/// Quote a CSV field per RFC 4180 when it contains a delimiter, quote or newline.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Per-user display timezone overrides stored on `users.display_timezone`.
+pub struct UserTimezoneRepo<'a> {
+    db: &'a Database,
+}
+
+impl<'a> UserTimezoneRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Set (or with `None`, clear) a user's override.
+    pub fn set(&self, user_id: &str, tz: Option<DisplayTimezone>) -> Result<()> {
+        self.db.with_connection(|conn| {
+            let updated = conn.execute(
+                "UPDATE users SET display_timezone = ?2 WHERE id = ?1",
+                params![user_id, tz.map(String::from)],
+            )?;
+            if updated == 0 {
+                return Err(QmsError::NotFound {
+                    resource: "user".to_string(),
+                    id: user_id.to_string(),
+                });
+            }
+            Ok(())
+        })
+    }
+
+    /// The user's override, if any.
+    pub fn get(&self, user_id: &str) -> Result<Option<DisplayTimezone>> {
+        self.db.with_connection(|conn| {
+            let stored: Option<Option<String>> = conn
+                .query_row("SELECT display_timezone FROM users WHERE id = ?1", params![user_id], |row| row.get(0))
+                .optional()?;
+            stored.flatten().map(|tz| tz.parse()).transpose()
+        })
+    }
+
+    /// The zone to display for `user_id`: their override or the deployment default.
+    pub fn resolve(&self, user_id: &str, deployment_default: DisplayTimezone) -> Result<DisplayTimezone> {
+        Ok(self.get(user_id)?.unwrap_or(deployment_default))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditManager;
+    use crate::config::DatabaseConfig;
+    use chrono::TimeZone;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_format() {
+        let at = Utc.with_ymd_and_hms(2025, 3, 10, 23, 30, 0).unwrap();
+        assert_eq!(DisplayTimezone::utc().format(at), "2025-03-10 23:30 UTC");
+
+        let berlin: DisplayTimezone = "+01:00".parse().unwrap();
+        assert_eq!(berlin.format(at), "2025-03-11 00:30 +01:00");
+        assert_eq!(berlin.format_rfc3339(at), "2025-03-11T00:30:00+01:00");
+        assert_eq!("UTC-0800".parse::<DisplayTimezone>().unwrap().to_string(), "-08:00");
+        assert_eq!("+5:30".parse::<DisplayTimezone>().unwrap().offset_seconds(), 19800);
+        for invalid in ["CET", "+15:00", "+01:75", "01:00"] {
+            assert!(invalid.parse::<DisplayTimezone>().is_err(), "{} should be rejected", invalid);
+        }
+        assert!(serde_json::from_str::<DisplayTimezone>("\"bogus\"").is_err());
+    }
+
+    #[test]
+    fn test_stored_timestamps_must_be_utc() {
+        let db = test_db();
+        assert!(parse_stored_utc("2025-03-10T09:00:00+00:00").is_ok());
+        assert!(parse_stored_utc("2025-03-10T09:00:00Z").is_ok());
+        assert!(parse_stored_utc("2025-03-10T10:00:00+01:00").is_err());
+        assert!(parse_stored_utc("2025-03-10 09:00:00").is_err());
+
+        AuditManager::new(db.clone()).log_action("qa", "login", "session", "Success", None).unwrap();
+        assert!(find_non_utc_audit_timestamps(&db).unwrap().is_empty());
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO audit_trail (id, timestamp, user_id, action, resource, outcome, session_id, compliance_version)
+                 VALUES ('bad', '2025-03-10T10:00:00+01:00', 'qa', 'x', 'y', 'SUCCESS', 's', 'v')",
+                [],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(find_non_utc_audit_timestamps(&db).unwrap(), vec!["bad"]);
+    }
+
+    #[test]
+    fn test_csv_export_and_user_override() {
+        let db = test_db();
+        db.seed_test_users(&["qa"]);
+        AuditManager::new(db.clone())
+            .log_action("qa", "capa_created", "capa:1, \"urgent\"", "Success", None)
+            .unwrap();
+        let entries = db.get_audit_entries(10, 0, None).unwrap();
+
+        let repo = UserTimezoneRepo::new(&db);
+        assert_eq!(repo.resolve("qa", DisplayTimezone::utc()).unwrap(), DisplayTimezone::utc());
+        repo.set("qa", Some("+09:00".parse().unwrap())).unwrap();
+        let tz = repo.resolve("qa", DisplayTimezone::utc()).unwrap();
+        assert_eq!(tz.to_string(), "+09:00");
+        assert!(matches!(repo.set("nobody", None), Err(QmsError::NotFound { .. })));
+
+        let csv = audit_trail_csv(&entries, tz).unwrap();
+        let row = csv.lines().nth(1).unwrap();
+        assert!(row.contains("+09:00,"));
+        assert!(row.contains(&entries[0].timestamp), "stored UTC value is exported unchanged");
+        assert!(row.contains("\"capa:1, \"\"urgent\"\"\""));
+    }
+}
@@ -1,23 +1,78 @@
 use crate::{Result, QmsError};
+use crate::audit::AuditManager;
+use crate::change_history::{diff_fields, record_changes, ChangeReason};
+use crate::concurrency::check_version;
+use crate::database::Database;
+use crate::delegation::{Delegation, DelegationRepo};
+use crate::events::{EventBus, QmsEvent};
+use crate::numbering::NumberingRepo;
+use crate::site::ALL_SITES;
+use crate::workflow::{Transition, WorkflowDefinition};
 use serde::{Serialize, Deserialize};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{params, OptionalExtension};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
 
 /// Document control manager for FDA compliance
 pub struct DocumentManager {
     // Database connection would be here in full implementation
+    events: Option<EventBus>,
+    // Database and site used to allocate document numbers
+    numbering: Option<(Database, String)>,
 }
 
 impl DocumentManager {
     /// Create new document manager
     pub fn new() -> Self {
-        Self {}
+        Self { events: None, numbering: None }
     }
 
-    /// Create a new controlled document
-    pub fn create_document(&mut self, document: Document) -> Result<String> {
+    /// Generate document numbers from the numbering rules in `db` for `site_id`
+    pub fn with_numbering<S: Into<String>>(mut self, db: Database, site_id: S) -> Self {
+        self.numbering = Some((db, site_id.into()));
+        self
+    }
+
+    /// Publish `DocumentEffective` on `events` when a document takes effect
+    pub fn with_event_bus(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Make an approved document effective from now
+    pub fn make_effective(&mut self, document: &mut Document) -> Result<()> {
+        if !document_workflow().can_transition(document.status.as_str(), DocumentStatus::Effective.as_str()) {
+            return Err(QmsError::DocumentControl {
+                message: format!("Only approved documents can become effective (status: {:?})", document.status),
+            });
+        }
+        let now = Utc::now();
+        document.status = DocumentStatus::Effective;
+        document.effective_date = Some(now);
+        document.updated_at = now;
+        if let Some(events) = &self.events {
+            events.publish(&QmsEvent::DocumentEffective {
+                document_id: document.id.clone(),
+                document_number: document.document_number.clone(),
+                version: document.version.clone(),
+                effective_date: now,
+            });
+        }
+        Ok(())
+    }
+
+    /// Create a new controlled document. Without a document number, one is
+    /// allocated from the numbering rule of its type when numbering is set up.
+    pub fn create_document(&mut self, document: &mut Document) -> Result<String> {
+        if document.document_number.trim().is_empty() {
+            if let Some((db, site_id)) = &self.numbering {
+                document.document_number = NumberingRepo::new(db).next_document_number(&document.document_type, site_id)?;
+            }
+        }
         document.validate()?;
         // Implementation would save to database
-        Ok(document.id)
+        Ok(document.id.clone())
     }
 
     /// Get document by ID
@@ -77,6 +132,48 @@ pub enum DocumentStatus {
     Retired,
 }
 
+impl DocumentStatus {
+    pub const ALL: [DocumentStatus; 6] = [
+        DocumentStatus::Draft,
+        DocumentStatus::UnderReview,
+        DocumentStatus::Approved,
+        DocumentStatus::Effective,
+        DocumentStatus::Obsolete,
+        DocumentStatus::Retired,
+    ];
+
+    /// Name stored in the `documents.status` column
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DocumentStatus::Draft => "Draft",
+            DocumentStatus::UnderReview => "UnderReview",
+            DocumentStatus::Approved => "Approved",
+            DocumentStatus::Effective => "Effective",
+            DocumentStatus::Obsolete => "Obsolete",
+            DocumentStatus::Retired => "Retired",
+        }
+    }
+}
+
+/// The document control workflow, keyed by [`DocumentStatus::as_str`].
+pub fn document_workflow() -> &'static WorkflowDefinition {
+    static WORKFLOW: OnceLock<WorkflowDefinition> = OnceLock::new();
+    WORKFLOW.get_or_init(|| {
+        let step = |from: DocumentStatus, to: DocumentStatus| Transition::new(from.as_str(), to.as_str());
+        WorkflowDefinition::new("document", &DocumentStatus::ALL.map(|status| status.as_str()))
+            .with_transition(step(DocumentStatus::Draft, DocumentStatus::UnderReview))
+            // Returned to the author for rework
+            .with_transition(step(DocumentStatus::UnderReview, DocumentStatus::Draft))
+            .with_transition(step(DocumentStatus::UnderReview, DocumentStatus::Approved))
+            .with_transition(step(DocumentStatus::Approved, DocumentStatus::Effective))
+            // A revision goes back to review while the effective version stays in force
+            .with_transition(step(DocumentStatus::Effective, DocumentStatus::UnderReview))
+            .with_transition(step(DocumentStatus::Effective, DocumentStatus::Obsolete))
+            .with_transition(step(DocumentStatus::Effective, DocumentStatus::Retired))
+            .with_transition(step(DocumentStatus::Obsolete, DocumentStatus::Retired))
+    })
+}
+
 /// Document type classification
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum DocumentType {
@@ -90,6 +187,400 @@ pub enum DocumentType {
     ValidationProtocol,
     Report,
     Manual,
+    /// Customer-specific validation rule, see [`crate::validation_scripts`]
+    ValidationScript,
+}
+
+impl DocumentType {
+    /// Default document number prefix
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            DocumentType::SOP => "SOP",
+            DocumentType::WorkInstruction => "WI",
+            DocumentType::Policy => "POL",
+            DocumentType::Form => "FRM",
+            DocumentType::Template => "TPL",
+            DocumentType::Specification => "SPEC",
+            DocumentType::TestMethod => "TM",
+            DocumentType::ValidationProtocol => "VP",
+            DocumentType::Report => "RPT",
+            DocumentType::Manual => "MAN",
+            DocumentType::ValidationScript => "VS",
+        }
+    }
+}
+
+/// Approval-relevant state of a row in the `documents` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentApprovalState {
+    pub id: String,
+    pub document_number: String,
+    pub title: String,
+    pub status: String,
+    pub approved_by: Option<String>,
+    /// Delegator the approver acted for, when approved under a delegation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approved_on_behalf_of: Option<String>,
+    /// Optimistic concurrency version, served as the ETag
+    pub row_version: i64,
+}
+
+/// Approval of documents stored in the `documents` table
+pub struct DocumentApprovals<'a> {
+    db: &'a Database,
+}
+
+impl<'a> DocumentApprovals<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    pub fn fetch(&self, document_id: &str) -> Result<Option<DocumentApprovalState>> {
+        self.db.with_connection(|conn| fetch_state(conn, document_id))
+    }
+
+    /// Approve a document that is under review; the approval is audited and
+    /// the changed fields are recorded with `reason`. `expected_version` is
+    /// the version the approver reviewed; a document changed since then is
+    /// rejected with `Conflict`. Returns the new version. Permission checks
+    /// are the caller's responsibility.
+    pub fn approve(
+        &self,
+        document_id: &str,
+        approver: &str,
+        expected_version: i64,
+        reason: &ChangeReason,
+    ) -> Result<i64> {
+        self.record_approval(document_id, approver, None, expected_version, reason)
+    }
+
+    /// Approve as `delegate` on behalf of `delegator`, under a delegation in
+    /// force today; both are recorded. Otherwise as [`Self::approve`].
+    pub fn approve_on_behalf(
+        &self,
+        document_id: &str,
+        delegate: &str,
+        delegator: &str,
+        expected_version: i64,
+        reason: &ChangeReason,
+    ) -> Result<i64> {
+        let delegation = DelegationRepo::new(self.db)
+            .in_force_for(delegate, Utc::now().date_naive())?
+            .into_iter()
+            .find(|delegation| delegation.delegator == delegator)
+            .ok_or_else(|| QmsError::Security {
+                message: format!("User {} holds no delegation of {}'s approvals today", delegate, delegator),
+            })?;
+        self.record_approval(document_id, delegate, Some(&delegation), expected_version, reason)
+    }
+
+    fn record_approval(
+        &self,
+        document_id: &str,
+        approver: &str,
+        delegation: Option<&Delegation>,
+        expected_version: i64,
+        reason: &ChangeReason,
+    ) -> Result<i64> {
+        let audit = AuditManager::new(self.db.clone());
+        self.db.unit_of_work(|uow| {
+            let state = fetch_state(uow.connection(), document_id)?.ok_or_else(|| QmsError::NotFound {
+                resource: "document".to_string(),
+                id: document_id.to_string(),
+            })?;
+            check_version("document", document_id, expected_version, state.row_version)?;
+            let approved = DocumentStatus::Approved.as_str();
+            document_workflow().check(document_id, &(), &state.status, approved, approver, None)?;
+            let after = DocumentApprovalState {
+                status: approved.to_string(),
+                approved_by: Some(approver.to_string()),
+                approved_on_behalf_of: delegation.map(|delegation| delegation.delegator.clone()),
+                ..state.clone()
+            };
+            let changes = diff_fields(&state, &after, &["row_version"])?;
+            record_changes(uow.connection(), "document", document_id, approver, reason, &changes)?;
+            uow.connection().execute(
+                "UPDATE documents SET status = 'Approved', approved_by = ?2, approved_on_behalf_of = ?3,
+                        updated_at = ?4, row_version = row_version + 1
+                 WHERE id = ?1",
+                params![
+                    document_id,
+                    approver,
+                    delegation.map(|delegation| delegation.delegator.as_str()),
+                    Utc::now().to_rfc3339()
+                ],
+            )?;
+            let details = delegation.map(|delegation| {
+                format!("On behalf of {} under delegation {}", delegation.delegator, delegation.id)
+            });
+            let resource = format!("document:{}", document_id);
+            audit.log_action_in(uow, approver, "document_approved", &resource, "Success", details)?;
+            Ok(state.row_version + 1)
+        })
+    }
+}
+
+/// Sites and products a document revision applies to from a given day, so
+/// multi-site deployments can roll out a revision in phases.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentEffectivity {
+    pub document_id: String,
+    pub version: String,
+    /// Site the revision applies to, or `*` for every site
+    pub site_id: String,
+    /// Product the revision applies to; every product when absent
+    pub product: Option<String>,
+    pub effective_from: NaiveDate,
+    pub set_by: String,
+    pub set_at: DateTime<Utc>,
+}
+
+/// Revision of a document in effect at a site on a given day
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectiveDocument {
+    pub document_id: String,
+    pub document_number: String,
+    pub title: String,
+    pub document_type: String,
+    pub version: String,
+    /// Product the revision is limited to; every product when absent
+    pub product: Option<String>,
+    /// First day of effectivity, when known
+    pub effective_from: Option<NaiveDate>,
+}
+
+/// Effectivity scoping of document revisions by site and product.
+/// Effective documents without any scope apply at their own site only.
+pub struct DocumentEffectivityRepo<'a> {
+    db: &'a Database,
+}
+
+impl<'a> DocumentEffectivityRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Make `version` of a document effective at `site_id` (or `*`) for
+    /// `product` (or every product) from `effective_from`. The version must
+    /// be approved or effective, or a released earlier version.
+    pub fn set(
+        &self,
+        document_id: &str,
+        version: &str,
+        site_id: &str,
+        product: Option<&str>,
+        effective_from: NaiveDate,
+        set_by: &str,
+    ) -> Result<DocumentEffectivity> {
+        let product = product.map(str::trim).filter(|product| !product.is_empty());
+        let effectivity = DocumentEffectivity {
+            document_id: document_id.to_string(),
+            version: version.trim().to_string(),
+            site_id: site_id.trim().to_string(),
+            product: product.map(str::to_string),
+            effective_from,
+            set_by: set_by.to_string(),
+            set_at: Utc::now(),
+        };
+        self.db.unit_of_work(|uow| {
+            let conn = uow.connection();
+            let (current_version, status): (String, String) = conn
+                .query_row(
+                    "SELECT version, status FROM documents WHERE id = ?1 AND deleted_at IS NULL",
+                    params![document_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?
+                .ok_or_else(|| QmsError::NotFound { resource: "document".to_string(), id: document_id.to_string() })?;
+            let released = if effectivity.version == current_version {
+                matches!(status.as_str(), "Approved" | "Effective")
+            } else {
+                conn.query_row(
+                    "SELECT EXISTS (SELECT 1 FROM document_versions WHERE document_id = ?1 AND version = ?2)",
+                    params![document_id, effectivity.version],
+                    |row| row.get(0),
+                )?
+            };
+            if !released {
+                return Err(QmsError::Validation {
+                    field: "version".to_string(),
+                    message: format!(
+                        "Version {} of document {} is not an approved revision (status: {})",
+                        effectivity.version, document_id, status
+                    ),
+                });
+            }
+            if effectivity.site_id != ALL_SITES {
+                let known: bool = conn.query_row(
+                    "SELECT EXISTS (SELECT 1 FROM sites WHERE id = ?1)",
+                    params![effectivity.site_id],
+                    |row| row.get(0),
+                )?;
+                if !known {
+                    return Err(QmsError::NotFound { resource: "site".to_string(), id: effectivity.site_id.clone() });
+                }
+            }
+            conn.execute(
+                "INSERT INTO document_effectivity (document_id, version, site_id, product, effective_from, set_by, set_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                 ON CONFLICT(document_id, version, site_id, product) DO UPDATE SET
+                     effective_from = excluded.effective_from, set_by = excluded.set_by, set_at = excluded.set_at",
+                params![
+                    document_id,
+                    effectivity.version,
+                    effectivity.site_id,
+                    product.unwrap_or(""),
+                    effective_from.to_string(),
+                    set_by,
+                    effectivity.set_at.to_rfc3339(),
+                ],
+            )?;
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                set_by,
+                "document_effectivity_set",
+                &format!("document:{}", document_id),
+                "Success",
+                Some(format!(
+                    "Version {} at site {} for {} from {}",
+                    effectivity.version,
+                    effectivity.site_id,
+                    product.unwrap_or("all products"),
+                    effective_from
+                )),
+            )
+        })?;
+        Ok(effectivity)
+    }
+
+    /// Effectivity scopes of a document, by site, product and start.
+    pub fn scopes(&self, document_id: &str) -> Result<Vec<DocumentEffectivity>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT document_id, version, site_id, product, effective_from, set_by, set_at
+                 FROM document_effectivity WHERE document_id = ?1
+                 ORDER BY site_id, product, effective_from, set_at",
+            )?;
+            let scopes = stmt
+                .query_map(params![document_id], |row| {
+                    let product: String = row.get(3)?;
+                    let effective_from: String = row.get(4)?;
+                    let set_at: String = row.get(6)?;
+                    Ok(DocumentEffectivity {
+                        document_id: row.get(0)?,
+                        version: row.get(1)?,
+                        site_id: row.get(2)?,
+                        product: Some(product).filter(|product| !product.is_empty()),
+                        effective_from: parse_date(4, &effective_from)?,
+                        set_by: row.get(5)?,
+                        set_at: DateTime::parse_from_rfc3339(&set_at)
+                            .map_err(|e| conversion_error(6, e.to_string()))?
+                            .with_timezone(&Utc),
+                    })
+                })?
+                .collect::<rusqlite::Result<_>>()?;
+            Ok(scopes)
+        })
+    }
+
+    /// Revisions in effect at `site_id` on `on`, by document number. For a
+    /// `product`, each document's latest revision applying to it; otherwise
+    /// the latest revision for every product plus any later product-specific
+    /// revisions. Served from the database's read cache when possible.
+    pub fn effective_at(&self, site_id: &str, product: Option<&str>, on: NaiveDate) -> Result<Vec<EffectiveDocument>> {
+        let product = product.map(str::trim).filter(|product| !product.is_empty());
+        self.db.read_cache().effective_documents(site_id, product, on, || self.load_effective_at(site_id, product, on))
+    }
+
+    fn load_effective_at(&self, site_id: &str, product: Option<&str>, on: NaiveDate) -> Result<Vec<EffectiveDocument>> {
+        let (scoped, unscoped) = self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT d.id, d.document_number, d.title, d.document_type, e.version, e.product, e.effective_from
+                 FROM document_effectivity e JOIN documents d ON d.id = e.document_id
+                 WHERE d.deleted_at IS NULL AND d.status NOT IN ('Obsolete', 'Retired')
+                   AND e.site_id IN (?1, ?2) AND e.effective_from <= ?3
+                   AND (?4 IS NULL OR e.product IN ('', ?4))
+                 ORDER BY d.id, e.effective_from, e.set_at",
+            )?;
+            let scoped = stmt
+                .query_map(params![site_id, ALL_SITES, on.to_string(), product], row_to_effective)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            let mut stmt = conn.prepare(
+                "SELECT id, document_number, title, document_type, version, '', substr(effective_date, 1, 10)
+                 FROM documents d
+                 WHERE deleted_at IS NULL AND status = 'Effective' AND site_id = ?1
+                   AND (effective_date IS NULL OR substr(effective_date, 1, 10) <= ?2)
+                   AND NOT EXISTS (SELECT 1 FROM document_effectivity e WHERE e.document_id = d.id)",
+            )?;
+            let unscoped = stmt
+                .query_map(params![site_id, on.to_string()], row_to_effective)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok((scoped, unscoped))
+        })?;
+
+        // Scopes are in order of effectivity: a revision for every product
+        // supersedes all earlier ones, a product-specific one only its own.
+        let mut in_effect: BTreeMap<String, Vec<EffectiveDocument>> = BTreeMap::new();
+        for document in scoped {
+            let revisions = in_effect.entry(document.document_id.clone()).or_default();
+            revisions.retain(|revision| document.product.is_some() && revision.product != document.product);
+            revisions.push(document);
+        }
+        let mut documents: Vec<EffectiveDocument> = in_effect
+            .into_values()
+            .flat_map(|revisions| match product {
+                // The latest of the revisions for every product and for this one
+                Some(_) => revisions.into_iter().last().into_iter().collect(),
+                None => revisions,
+            })
+            .chain(unscoped)
+            .collect();
+        documents.sort_by(|a, b| a.document_number.cmp(&b.document_number).then_with(|| a.product.cmp(&b.product)));
+        Ok(documents)
+    }
+}
+
+fn row_to_effective(row: &rusqlite::Row) -> rusqlite::Result<EffectiveDocument> {
+    let product: String = row.get(5)?;
+    let effective_from: Option<String> = row.get(6)?;
+    Ok(EffectiveDocument {
+        document_id: row.get(0)?,
+        document_number: row.get(1)?,
+        title: row.get(2)?,
+        document_type: row.get(3)?,
+        version: row.get(4)?,
+        product: Some(product).filter(|product| !product.is_empty()),
+        effective_from: effective_from.map(|value| parse_date(6, &value)).transpose()?,
+    })
+}
+
+fn parse_date(index: usize, value: &str) -> rusqlite::Result<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|e| conversion_error(index, e.to_string()))
+}
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn fetch_state(conn: &rusqlite::Connection, document_id: &str) -> Result<Option<DocumentApprovalState>> {
+    Ok(conn
+        .query_row(
+            "SELECT id, document_number, title, status, approved_by, approved_on_behalf_of, row_version
+             FROM documents WHERE id = ?1",
+            params![document_id],
+            |row| {
+                Ok(DocumentApprovalState {
+                    id: row.get(0)?,
+                    document_number: row.get(1)?,
+                    title: row.get(2)?,
+                    status: row.get(3)?,
+                    approved_by: row.get(4)?,
+                    approved_on_behalf_of: row.get(5)?,
+                    row_version: row.get(6)?,
+                })
+            },
+        )
+        .optional()?)
 }
 
 #[cfg(test)]
@@ -141,4 +632,145 @@ mod tests {
 
         assert!(document.validate().is_err());
     }
+
+    #[test]
+    fn test_create_document_allocates_number() {
+        let db = Database::new(crate::config::DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let mut document = Document {
+            id: "doc-003".to_string(),
+            document_number: " ".to_string(),
+            title: "Line Clearance".to_string(),
+            version: "1.0".to_string(),
+            status: DocumentStatus::Draft,
+            document_type: DocumentType::WorkInstruction,
+            content_hash: "abc123".to_string(),
+            file_path: None,
+            created_by: "user123".to_string(),
+            approved_by: None,
+            effective_date: None,
+            review_date: None,
+            retirement_date: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        assert!(DocumentManager::new().create_document(&mut document).is_err());
+        let mut manager = DocumentManager::new().with_numbering(db.clone(), "ber");
+        manager.create_document(&mut document).unwrap();
+        assert_eq!(document.document_number, "WI-BER-001");
+        assert_eq!(NumberingRepo::new(&db).next_document_number(&DocumentType::WorkInstruction, "ber").unwrap(), "WI-BER-002");
+    }
+
+    #[test]
+    fn test_make_effective_publishes_event() {
+        let events = EventBus::new();
+        let published = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = published.clone();
+        events.subscribe("recorder", move |event: &QmsEvent| -> Result<()> {
+            sink.lock().unwrap().push(event.name());
+            Ok(())
+        });
+        let mut manager = DocumentManager::new().with_event_bus(events);
+        let mut document = Document {
+            id: "doc-002".to_string(),
+            document_number: "SOP-002".to_string(),
+            title: "Document Control".to_string(),
+            version: "1.0".to_string(),
+            status: DocumentStatus::Draft,
+            document_type: DocumentType::SOP,
+            content_hash: "abc123".to_string(),
+            file_path: None,
+            created_by: "user123".to_string(),
+            approved_by: None,
+            effective_date: None,
+            review_date: None,
+            retirement_date: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        assert!(manager.make_effective(&mut document).is_err());
+        document.status = DocumentStatus::Approved;
+        manager.make_effective(&mut document).unwrap();
+        assert_eq!(document.status, DocumentStatus::Effective);
+        assert!(document.effective_date.is_some());
+        assert_eq!(*published.lock().unwrap(), vec!["document.effective"]);
+    }
+
+    #[test]
+    fn test_effectivity_phased_rollout_by_site_and_product() {
+        let db = Database::new(crate::config::DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["qa"]);
+        crate::site::SiteRepo::new(&db).create_site(&crate::site::Site::new("site-b", "Site B")).unwrap();
+        db.with_connection(|conn| {
+            conn.execute_batch(
+                "INSERT INTO documents (id, document_number, title, version, status, document_type, content_hash, created_by)
+                 VALUES ('sop-1', 'SOP-001', 'Cleaning', '2.0', 'Approved', 'SOP', 'h2', 'qa'),
+                        ('sop-2', 'SOP-002', 'Gowning', '1.0', 'Effective', 'SOP', 'h', 'qa'),
+                        ('sop-3', 'SOP-003', 'Sterilization', '1.0', 'Draft', 'SOP', 'h', 'qa');
+                 INSERT INTO document_versions (id, document_id, version, change_description, content_hash, created_by)
+                 VALUES ('v1', 'sop-1', '1.0', 'Initial release', 'h1', 'qa');",
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        let date = |day: &str| NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap();
+        let repo = DocumentEffectivityRepo::new(&db);
+
+        repo.set("sop-1", "1.0", ALL_SITES, None, date("2026-01-01"), "qa").unwrap();
+        repo.set("sop-1", "2.0", "default", None, date("2026-03-01"), "qa").unwrap();
+        repo.set("sop-1", "2.0", "site-b", Some("Pump X"), date("2026-03-01"), "qa").unwrap();
+        assert!(matches!(
+            repo.set("sop-3", "1.0", "site-b", None, date("2026-03-01"), "qa"),
+            Err(QmsError::Validation { .. })
+        ));
+        assert!(matches!(
+            repo.set("sop-1", "2.0", "site-z", None, date("2026-03-01"), "qa"),
+            Err(QmsError::NotFound { .. })
+        ));
+        assert!(repo.set("missing", "1.0", "site-b", None, date("2026-03-01"), "qa").is_err());
+        assert_eq!(repo.scopes("sop-1").unwrap().len(), 3);
+        assert_eq!(db.get_audit_entries_for_resource("document:sop-1").unwrap().len(), 3);
+
+        let revisions = |site: &str, product: Option<&str>, on: &str| -> Vec<(String, String, Option<String>)> {
+            repo.effective_at(site, product, date(on))
+                .unwrap()
+                .into_iter()
+                .map(|doc| (doc.document_number, doc.version, doc.product))
+                .collect()
+        };
+        let sop = |number: &str, version: &str, product: Option<&str>| {
+            (number.to_string(), version.to_string(), product.map(str::to_string))
+        };
+        // Site B is still on 1.0 except for Pump X; SOP-002 is unscoped and stays at its own site
+        assert_eq!(revisions("site-b", None, "2026-02-01"), [sop("SOP-001", "1.0", None)]);
+        assert_eq!(
+            revisions("site-b", None, "2026-04-01"),
+            [sop("SOP-001", "1.0", None), sop("SOP-001", "2.0", Some("Pump X"))]
+        );
+        assert_eq!(revisions("site-b", Some("Pump X"), "2026-04-01"), [sop("SOP-001", "2.0", Some("Pump X"))]);
+        assert_eq!(revisions("site-b", Some("Pump Y"), "2026-04-01"), [sop("SOP-001", "1.0", None)]);
+        assert_eq!(
+            revisions("default", Some("Pump X"), "2026-04-01"),
+            [sop("SOP-001", "2.0", None), sop("SOP-002", "1.0", None)]
+        );
+
+        // Rolling 2.0 out to every site supersedes the product-specific scope
+        repo.set("sop-1", "2.0", ALL_SITES, None, date("2026-05-01"), "qa").unwrap();
+        assert_eq!(revisions("site-b", None, "2026-05-01"), [sop("SOP-001", "2.0", None)]);
+    }
 }
\ No newline at end of file
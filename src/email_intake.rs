@@ -0,0 +1,495 @@
+//! # Email Intake - Complaints Received by Email
+//!
+//! Customers and distributors report most complaints by email. The
+//! `email_intake` job polls a monitored IMAP mailbox ([`ImapMailbox`]) and
+//! turns every unread message into a draft complaint in the quality intake
+//! triage queue, with the message's attachments kept in the file store.
+//!
+//! A message already processed (same `Message-ID`, e.g. redelivered) is
+//! skipped. A follow-up from the same sender with the same subject, or the
+//! same text re-sent, within `duplicate_window_days` of a complaint still
+//! awaiting triage is filed with that complaint instead of opening another
+//! one. Messages are marked read only once recorded, so a failed message is
+//! retried on the next poll. Every received message is audited.
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use mail_parser::{MessageParser, MimeHeaders};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::config::EmailIntakeConfig;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::file_store::{safe_file_name, FileStore};
+use crate::keystore::sha256_hex;
+use crate::quality_intake::{IssueSource, QualityIntake};
+
+/// User recorded for the intake of received messages.
+pub const INTAKE_USER: &str = "system:email_intake";
+
+/// Environment variable holding the mailbox password.
+pub const PASSWORD_ENV: &str = "QMS_INTAKE_MAILBOX_PASSWORD";
+
+/// File store category of complaint attachments.
+pub const ATTACHMENT_CATEGORY: &str = "complaint_attachments";
+
+/// An unread message as fetched from the mailbox.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InboundEmail {
+    /// Mailbox UID, used to mark the message read
+    pub uid: u32,
+    /// The full RFC 5322 message
+    pub raw: Vec<u8>,
+}
+
+/// A monitored mailbox.
+pub trait Mailbox {
+    fn unread(&mut self) -> Result<Vec<InboundEmail>>;
+    fn mark_read(&mut self, uid: u32) -> Result<()>;
+}
+
+/// IMAP mailbox over TLS.
+pub struct ImapMailbox {
+    session: imap::Session<native_tls::TlsStream<std::net::TcpStream>>,
+}
+
+impl ImapMailbox {
+    /// Sign in and select the configured folder.
+    pub fn connect(config: &EmailIntakeConfig) -> Result<Self> {
+        let password = config.password.clone().or_else(|| std::env::var(PASSWORD_ENV).ok()).ok_or_else(|| {
+            QmsError::Configuration { message: format!("email_intake.password or {} is required", PASSWORD_ENV) }
+        })?;
+        let tls = native_tls::TlsConnector::new().map_err(|e| QmsError::Network { message: e.to_string() })?;
+        let client = imap::connect((config.host.as_str(), config.port), config.host.as_str(), &tls)
+            .map_err(|e| QmsError::Network { message: format!("Mailbox connection failed: {}", e) })?;
+        let mut session = client
+            .login(&config.username, &password)
+            .map_err(|(e, _)| QmsError::Security { message: format!("Mailbox login failed: {}", e) })?;
+        session.select(&config.folder).map_err(imap_error)?;
+        Ok(Self { session })
+    }
+
+    pub fn logout(mut self) {
+        // The session is dropped either way; a failed logout loses nothing
+        let _ = self.session.logout();
+    }
+}
+
+impl Mailbox for ImapMailbox {
+    fn unread(&mut self) -> Result<Vec<InboundEmail>> {
+        let mut uids: Vec<u32> = self.session.uid_search("UNSEEN").map_err(imap_error)?.into_iter().collect();
+        if uids.is_empty() {
+            return Ok(Vec::new());
+        }
+        uids.sort_unstable();
+        let set = uids.iter().map(u32::to_string).collect::<Vec<_>>().join(",");
+        // PEEK leaves the message unread until it is recorded
+        let fetches = self.session.uid_fetch(set, "(UID BODY.PEEK[])").map_err(imap_error)?;
+        Ok(fetches
+            .iter()
+            .filter_map(|fetch| Some(InboundEmail { uid: fetch.uid?, raw: fetch.body()?.to_vec() }))
+            .collect())
+    }
+
+    fn mark_read(&mut self, uid: u32) -> Result<()> {
+        self.session.uid_store(uid.to_string(), "+FLAGS (\\Seen)").map_err(imap_error)?;
+        Ok(())
+    }
+}
+
+fn imap_error(e: imap::Error) -> QmsError {
+    QmsError::Network { message: format!("Mailbox request failed: {}", e) }
+}
+
+/// The parts of a message the intake uses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedEmail {
+    /// `Message-ID`, or a hash of the message when it has none
+    pub message_id: String,
+    pub sender: String,
+    pub sender_name: Option<String>,
+    pub subject: String,
+    pub body: String,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub attachments: Vec<EmailAttachment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailAttachment {
+    pub file_name: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+impl ParsedEmail {
+    pub fn parse(raw: &[u8]) -> Result<Self> {
+        let message = MessageParser::default()
+            .parse(raw)
+            .ok_or_else(|| QmsError::Serialization { message: "Not an email message".to_string() })?;
+        let from = message.from().and_then(|from| from.first());
+        let sender = from.and_then(|addr| addr.address()).map(str::to_ascii_lowercase).ok_or_else(|| {
+            QmsError::Validation { field: "from".to_string(), message: "The message has no sender address".to_string() }
+        })?;
+        let attachments = message
+            .attachments()
+            .map(|part| EmailAttachment {
+                file_name: part.attachment_name().unwrap_or("attachment").to_string(),
+                content_type: part
+                    .content_type()
+                    .map(|ct| match ct.subtype() {
+                        Some(subtype) => format!("{}/{}", ct.ctype(), subtype),
+                        None => ct.ctype().to_string(),
+                    })
+                    .unwrap_or_else(|| "application/octet-stream".to_string()),
+                data: part.contents().to_vec(),
+            })
+            .collect();
+        Ok(Self {
+            message_id: message
+                .message_id()
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("sha256:{}", sha256_hex(raw))),
+            sender,
+            sender_name: from.and_then(|addr| addr.name()).map(str::to_string),
+            subject: message.subject().unwrap_or_default().trim().to_string(),
+            body: message.body_text(0).map(|body| body.trim().to_string()).unwrap_or_default(),
+            sent_at: message.date().and_then(|date| Utc.timestamp_opt(date.to_timestamp(), 0).single()),
+            attachments,
+        })
+    }
+}
+
+/// Subject without reply and forward prefixes, lowercased.
+pub fn normalize_subject(subject: &str) -> String {
+    let mut subject = subject.trim().to_lowercase();
+    while let Some(rest) = ["re:", "fw:", "fwd:", "aw:", "wg:"].iter().find_map(|prefix| subject.strip_prefix(prefix)) {
+        subject = rest.trim_start().to_string();
+    }
+    subject
+}
+
+/// An attachment kept with a complaint.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntakeAttachment {
+    pub id: String,
+    pub intake_id: String,
+    pub file_name: String,
+    pub content_type: String,
+    /// Location in the file store
+    pub relative_path: String,
+    pub sha256: String,
+    pub size_bytes: u64,
+}
+
+/// What became of one message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntakeOutcome {
+    /// A new draft complaint
+    Created { intake_id: Uuid },
+    /// Filed with the untriaged complaint it duplicates
+    Duplicate { intake_id: Uuid },
+    /// Recorded by an earlier poll
+    AlreadyProcessed,
+}
+
+/// Counts of one poll.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IntakeSummary {
+    pub complaints_created: usize,
+    pub duplicates: usize,
+    pub already_processed: usize,
+    pub attachments_stored: usize,
+    /// Attachments over `max_attachment_mb`, noted in the audit trail
+    pub attachments_skipped: usize,
+    /// Messages that failed, with the error; retried on the next poll
+    pub errors: Vec<String>,
+}
+
+impl IntakeSummary {
+    pub fn describe(&self) -> String {
+        format!(
+            "{} complaint(s) created, {} duplicate(s), {} already seen, {} attachment(s) stored, {} skipped, {} error(s)",
+            self.complaints_created,
+            self.duplicates,
+            self.already_processed,
+            self.attachments_stored,
+            self.attachments_skipped,
+            self.errors.len()
+        )
+    }
+}
+
+/// Conversion of mailbox messages into draft complaints.
+pub struct EmailIntake<'a> {
+    db: &'a Database,
+    store: &'a FileStore,
+    config: &'a EmailIntakeConfig,
+}
+
+impl<'a> EmailIntake<'a> {
+    pub fn new(db: &'a Database, store: &'a FileStore, config: &'a EmailIntakeConfig) -> Self {
+        Self { db, store, config }
+    }
+
+    /// Process every unread message of `mailbox`.
+    pub fn poll(&self, mailbox: &mut dyn Mailbox, now: DateTime<Utc>) -> Result<IntakeSummary> {
+        let mut summary = IntakeSummary::default();
+        for email in mailbox.unread()? {
+            let result = ParsedEmail::parse(&email.raw).and_then(|parsed| self.receive(&parsed, &mut summary, now));
+            match result.and_then(|outcome| mailbox.mark_read(email.uid).map(|_| outcome)) {
+                Ok(IntakeOutcome::Created { .. }) => summary.complaints_created += 1,
+                Ok(IntakeOutcome::Duplicate { .. }) => summary.duplicates += 1,
+                Ok(IntakeOutcome::AlreadyProcessed) => summary.already_processed += 1,
+                Err(e) => summary.errors.push(format!("message {}: {}", email.uid, e)),
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Record one message as a draft complaint, or with the complaint it
+    /// duplicates, and count its attachments in `summary`.
+    fn receive(&self, email: &ParsedEmail, summary: &mut IntakeSummary, now: DateTime<Utc>) -> Result<IntakeOutcome> {
+        let processed: bool = self.db.with_connection(|conn| {
+            Ok(conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM email_intake_messages WHERE message_id = ?1)",
+                params![email.message_id],
+                |row| row.get(0),
+            )?)
+        })?;
+        if processed {
+            return Ok(IntakeOutcome::AlreadyProcessed);
+        }
+
+        let normalized_subject = normalize_subject(&email.subject);
+        let body_sha256 = sha256_hex(email.body.as_bytes());
+        let (intake_id, duplicate) = match self.duplicate_of(&email.sender, &normalized_subject, &body_sha256, now)? {
+            Some(intake_id) => (intake_id, true),
+            None => {
+                let title = if email.subject.is_empty() { "(no subject)" } else { email.subject.as_str() };
+                let from = match &email.sender_name {
+                    Some(name) => format!("{} <{}>", name, email.sender),
+                    None => email.sender.clone(),
+                };
+                let description = format!("Received by email from {}\n\n{}", from, email.body);
+                let item =
+                    QualityIntake::new(self.db).submit(IssueSource::Complaint, title, &description, &email.sender)?;
+                (item.id, false)
+            }
+        };
+
+        let message_row = Uuid::new_v4().to_string();
+        let max_bytes = u64::from(self.config.max_attachment_mb) * 1024 * 1024;
+        let mut stored = Vec::new();
+        let mut skipped = Vec::new();
+        for (index, attachment) in email.attachments.iter().enumerate() {
+            if attachment.data.len() as u64 > max_bytes {
+                skipped.push(attachment.file_name.clone());
+                continue;
+            }
+            let file_name = format!("{}-{:02}-{}", message_row, index + 1, safe_file_name(&attachment.file_name));
+            let file = self.store.store(ATTACHMENT_CATEGORY, &file_name, &attachment.data, now)?;
+            stored.push(IntakeAttachment {
+                id: Uuid::new_v4().to_string(),
+                intake_id: intake_id.to_string(),
+                file_name: attachment.file_name.clone(),
+                content_type: attachment.content_type.clone(),
+                relative_path: file.relative_path,
+                sha256: file.sha256,
+                size_bytes: file.size_bytes,
+            });
+        }
+
+        let audit = AuditManager::new(self.db.clone());
+        self.db.unit_of_work(|uow| {
+            let conn = uow.connection();
+            conn.execute(
+                "INSERT INTO email_intake_messages (id, message_id, sender, subject, normalized_subject, body_sha256,
+                                                    sent_at, intake_id, duplicate, processed_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    message_row,
+                    email.message_id,
+                    email.sender,
+                    email.subject,
+                    normalized_subject,
+                    body_sha256,
+                    email.sent_at.map(|t| t.to_rfc3339()),
+                    intake_id.to_string(),
+                    duplicate,
+                    now.to_rfc3339(),
+                ],
+            )?;
+            for attachment in &stored {
+                conn.execute(
+                    "INSERT INTO intake_attachments (id, intake_id, message_id, file_name, content_type, relative_path,
+                                                     sha256, size_bytes, stored_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    params![
+                        attachment.id,
+                        attachment.intake_id,
+                        message_row,
+                        attachment.file_name,
+                        attachment.content_type,
+                        attachment.relative_path,
+                        attachment.sha256,
+                        attachment.size_bytes as i64,
+                        now.to_rfc3339(),
+                    ],
+                )?;
+            }
+            audit.log_action_in(
+                uow,
+                INTAKE_USER,
+                "complaint_email_received",
+                &format!("quality_intake:{}", intake_id),
+                "Success",
+                Some(
+                    serde_json::json!({
+                        "message_id": email.message_id,
+                        "sender": email.sender,
+                        "duplicate": duplicate,
+                        "attachments": stored.len(),
+                        "attachments_skipped": skipped,
+                    })
+                    .to_string(),
+                ),
+            )
+        })?;
+        summary.attachments_stored += stored.len();
+        summary.attachments_skipped += skipped.len();
+        Ok(if duplicate { IntakeOutcome::Duplicate { intake_id } } else { IntakeOutcome::Created { intake_id } })
+    }
+
+    /// Attachments kept with a complaint, in the order received.
+    pub fn attachments(&self, intake_id: Uuid) -> Result<Vec<IntakeAttachment>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, intake_id, file_name, content_type, relative_path, sha256, size_bytes
+                 FROM intake_attachments WHERE intake_id = ?1 ORDER BY stored_at, rowid",
+            )?;
+            let attachments = stmt
+                .query_map(params![intake_id.to_string()], |row| {
+                    Ok(IntakeAttachment {
+                        id: row.get(0)?,
+                        intake_id: row.get(1)?,
+                        file_name: row.get(2)?,
+                        content_type: row.get(3)?,
+                        relative_path: row.get(4)?,
+                        sha256: row.get(5)?,
+                        size_bytes: row.get::<_, i64>(6)? as u64,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(attachments)
+        })
+    }
+
+    /// The untriaged complaint an earlier message from `sender` in the
+    /// duplicate window opened, matched by subject or text.
+    fn duplicate_of(
+        &self,
+        sender: &str,
+        normalized_subject: &str,
+        body_sha256: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<Uuid>> {
+        let since = now - Duration::days(i64::from(self.config.duplicate_window_days));
+        let intake_id: Option<String> = self.db.with_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT m.intake_id FROM email_intake_messages m JOIN quality_intake q ON q.id = m.intake_id
+                     WHERE m.sender = ?1 AND (m.normalized_subject = ?2 OR m.body_sha256 = ?3)
+                       AND m.processed_at >= ?4 AND q.triaged_at IS NULL
+                     ORDER BY m.processed_at DESC LIMIT 1",
+                    params![sender, normalized_subject, body_sha256, since.to_rfc3339()],
+                    |row| row.get(0),
+                )
+                .optional()?)
+        })?;
+        intake_id
+            .map(|id| Uuid::parse_str(&id).map_err(|e| QmsError::Serialization { message: e.to_string() }))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use std::collections::HashSet;
+
+    #[derive(Default)]
+    struct MemoryMailbox {
+        messages: Vec<InboundEmail>,
+        read: HashSet<u32>,
+    }
+
+    impl Mailbox for MemoryMailbox {
+        fn unread(&mut self) -> Result<Vec<InboundEmail>> {
+            Ok(self.messages.iter().filter(|message| !self.read.contains(&message.uid)).cloned().collect())
+        }
+
+        fn mark_read(&mut self, uid: u32) -> Result<()> {
+            self.read.insert(uid);
+            Ok(())
+        }
+    }
+
+    fn email(uid: u32, message_id: &str, subject: &str, body: &str) -> InboundEmail {
+        let raw = format!(
+            "From: Dr. Ana Ruiz <A.Ruiz@clinic.example>\r\nTo: complaints@qms.example\r\n\
+             Subject: {subject}\r\nMessage-ID: <{message_id}>\r\nDate: Thu, 15 Oct 2026 08:30:00 +0000\r\n\
+             MIME-Version: 1.0\r\nContent-Type: multipart/mixed; boundary=\"b1\"\r\n\r\n\
+             --b1\r\nContent-Type: text/plain\r\n\r\n{body}\r\n\
+             --b1\r\nContent-Type: image/jpeg\r\nContent-Disposition: attachment; filename=\"../photo 1.jpg\"\r\n\
+             Content-Transfer-Encoding: base64\r\n\r\n/9j/4AAQSkZJRg==\r\n--b1--\r\n"
+        );
+        InboundEmail { uid, raw: raw.into_bytes() }
+    }
+
+    #[test]
+    fn test_messages_become_draft_complaints_with_duplicate_detection() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = FileStore::new(dir.path());
+        let config = EmailIntakeConfig::default();
+        let intake = EmailIntake::new(&db, &store, &config);
+        let mut mailbox = MemoryMailbox {
+            messages: vec![
+                email(1, "m1@clinic.example", "Catheter tip broke off", "The tip broke during insertion."),
+                email(2, "m1@clinic.example", "Catheter tip broke off", "The tip broke during insertion."),
+                email(3, "m2@clinic.example", "RE: Catheter tip broke off", "Lot number is 42."),
+            ],
+            ..MemoryMailbox::default()
+        };
+
+        let summary = intake.poll(&mut mailbox, Utc::now()).unwrap();
+        assert_eq!((summary.complaints_created, summary.already_processed, summary.duplicates), (1, 1, 1));
+        assert!(summary.errors.is_empty(), "{:?}", summary.errors);
+        assert_eq!(mailbox.read.len(), 3);
+
+        let queue = QualityIntake::new(&db).awaiting_triage().unwrap();
+        assert_eq!(queue.len(), 1);
+        assert_eq!((queue[0].source, queue[0].reported_by.as_str()), (IssueSource::Complaint, "a.ruiz@clinic.example"));
+        assert!(queue[0].description.contains("The tip broke during insertion."));
+        let attachments = intake.attachments(queue[0].id).unwrap();
+        assert_eq!(attachments.len(), 2, "the follow-up's photo is filed with the complaint");
+        assert_eq!(
+            (attachments[0].file_name.as_str(), attachments[0].content_type.as_str()),
+            ("../photo 1.jpg", "image/jpeg")
+        );
+        assert!(attachments[0].relative_path.ends_with("-01-_photo_1.jpg"));
+
+        assert_eq!(normalize_subject("Fwd: RE:  Catheter"), "catheter");
+        assert!(intake.poll(&mut mailbox, Utc::now()).unwrap().describe().starts_with("0 complaint(s) created"));
+    }
+}
@@ -0,0 +1,640 @@
+//! # Environmental - Cleanroom & Environmental Monitoring
+//!
+//! Monitoring results (airborne particle counts, temperature, relative
+//! humidity) are recorded per room/area and checked against the limits
+//! configured for that area and parameter (21 CFR 820.70(c), ISO 13485 §6.4,
+//! ISO 14644-1). A result outside its limits is an excursion: it is raised as
+//! a nonconformance through quality intake, can be escalated to a CAPA and is
+//! closed with a documented disposition. Daily trends feed the dashboard.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::capa::{CapaPriority, CapaService, CapaType};
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::quality_intake::{IssueSource, QualityIntake, Route};
+
+/// Monitored quantity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Parameter {
+    /// Particles ≥0.5 µm per m³
+    Particles0_5um,
+    /// Particles ≥5.0 µm per m³
+    Particles5_0um,
+    /// Degrees Celsius
+    Temperature,
+    /// Percent relative humidity
+    RelativeHumidity,
+}
+
+impl Parameter {
+    pub const ALL: [Parameter; 4] =
+        [Parameter::Particles0_5um, Parameter::Particles5_0um, Parameter::Temperature, Parameter::RelativeHumidity];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Parameter::Particles0_5um => "particles_0_5um",
+            Parameter::Particles5_0um => "particles_5_0um",
+            Parameter::Temperature => "temperature",
+            Parameter::RelativeHumidity => "relative_humidity",
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Parameter::Particles0_5um | Parameter::Particles5_0um => "/m³",
+            Parameter::Temperature => "°C",
+            Parameter::RelativeHumidity => "%RH",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        Parameter::ALL.into_iter().find(|item| item.as_str() == value)
+    }
+}
+
+/// Acceptance range of one parameter in one area; either bound may be open.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitoringLimit {
+    pub area: String,
+    pub parameter: Parameter,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl MonitoringLimit {
+    pub fn contains(&self, value: f64) -> bool {
+        self.min.map_or(true, |min| value >= min) && self.max.map_or(true, |max| value <= max)
+    }
+
+    fn describe(&self) -> String {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) => format!("{}–{} {}", min, max, self.parameter.unit()),
+            (Some(min), None) => format!("≥{} {}", min, self.parameter.unit()),
+            (None, Some(max)) => format!("≤{} {}", max, self.parameter.unit()),
+            (None, None) => "unlimited".to_string(),
+        }
+    }
+}
+
+/// One recorded monitoring result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reading {
+    pub id: String,
+    pub area: String,
+    pub parameter: Parameter,
+    pub value: f64,
+    pub recorded_by: String,
+    pub recorded_at: DateTime<Utc>,
+    /// Excursion raised by this reading, if it was out of limits
+    pub excursion_id: Option<String>,
+}
+
+/// Excursion lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExcursionStatus {
+    /// Raised as a nonconformance, awaiting investigation
+    Open,
+    /// Escalated to a CAPA
+    Escalated,
+    /// Disposition documented
+    Closed,
+}
+
+impl ExcursionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExcursionStatus::Open => "open",
+            ExcursionStatus::Escalated => "escalated",
+            ExcursionStatus::Closed => "closed",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [ExcursionStatus::Open, ExcursionStatus::Escalated, ExcursionStatus::Closed]
+            .into_iter()
+            .find(|item| item.as_str() == value)
+    }
+}
+
+/// A reading outside its limits and the quality records it led to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Excursion {
+    pub id: String,
+    pub reading_id: String,
+    pub area: String,
+    pub parameter: Parameter,
+    pub value: f64,
+    /// Limits in force when the reading was taken
+    pub limit: String,
+    pub status: ExcursionStatus,
+    /// Quality intake item (NCR) raised for the excursion
+    pub ncr_id: Option<Uuid>,
+    pub capa_id: Option<String>,
+    pub opened_at: DateTime<Utc>,
+    pub closed_by: Option<String>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub disposition: Option<String>,
+}
+
+/// Daily aggregate of one parameter in one area.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrendPoint {
+    pub day: NaiveDate,
+    pub readings: u32,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub excursions: u32,
+}
+
+/// Trend of one parameter in one area, oldest day first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EnvironmentalTrend {
+    pub area: String,
+    pub parameter: Parameter,
+    pub points: Vec<TrendPoint>,
+}
+
+impl EnvironmentalTrend {
+    pub fn excursions(&self) -> u32 {
+        self.points.iter().map(|p| p.excursions).sum()
+    }
+
+    /// Daily means scaled to 0..=100 across the trend, for sparklines.
+    pub fn sparkline(&self) -> Vec<u64> {
+        let lo = self.points.iter().map(|p| p.mean).fold(f64::INFINITY, f64::min);
+        let hi = self.points.iter().map(|p| p.mean).fold(f64::NEG_INFINITY, f64::max);
+        self.points
+            .iter()
+            .map(|p| if hi > lo { ((p.mean - lo) / (hi - lo) * 100.0).round() as u64 } else { 50 })
+            .collect()
+    }
+}
+
+/// Limits, readings and excursion workflow for environmental monitoring.
+pub struct EnvironmentalRepo<'a> {
+    db: &'a Database,
+    audit: AuditManager,
+}
+
+impl<'a> EnvironmentalRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, audit: AuditManager::new(db.clone()) }
+    }
+
+    /// Configure (or replace) the limits of `parameter` in `area`.
+    pub fn set_limit(&self, limit: &MonitoringLimit, updated_by: &str) -> Result<()> {
+        if limit.area.trim().is_empty() {
+            return Err(QmsError::Validation { field: "area".to_string(), message: "Area is required".to_string() });
+        }
+        if let (Some(min), Some(max)) = (limit.min, limit.max) {
+            if min > max {
+                return Err(QmsError::Validation {
+                    field: "min".to_string(),
+                    message: format!("Lower limit {} exceeds upper limit {}", min, max),
+                });
+            }
+        }
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "INSERT INTO environmental_limits (area, parameter, min_value, max_value, updated_by, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT(area, parameter) DO UPDATE SET
+                     min_value = excluded.min_value, max_value = excluded.max_value,
+                     updated_by = excluded.updated_by, updated_at = excluded.updated_at",
+                params![limit.area, limit.parameter.as_str(), limit.min, limit.max, updated_by, Utc::now().to_rfc3339()],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                updated_by,
+                "environmental_limit_set",
+                &format!("environmental_area:{}", limit.area),
+                "Success",
+                Some(format!("{} {}", limit.parameter.as_str(), limit.describe())),
+            )
+        })
+    }
+
+    pub fn limit(&self, area: &str, parameter: Parameter) -> Result<Option<MonitoringLimit>> {
+        self.db.with_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT area, parameter, min_value, max_value FROM environmental_limits
+                     WHERE area = ?1 AND parameter = ?2",
+                    params![area, parameter.as_str()],
+                    row_to_limit,
+                )
+                .optional()?)
+        })
+    }
+
+    /// All configured limits, by area.
+    pub fn limits(&self) -> Result<Vec<MonitoringLimit>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT area, parameter, min_value, max_value FROM environmental_limits ORDER BY area, parameter",
+            )?;
+            let limits = stmt.query_map([], row_to_limit)?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(limits)
+        })
+    }
+
+    /// Record a monitoring result. A result outside the configured limits
+    /// opens an excursion and raises it as a nonconformance for triage.
+    pub fn record_reading(&self, area: &str, parameter: Parameter, value: f64, recorded_by: &str) -> Result<Reading> {
+        let limit = self.limit(area, parameter)?.ok_or_else(|| QmsError::Validation {
+            field: "area".to_string(),
+            message: format!("No {} limits configured for area {}", parameter.as_str(), area),
+        })?;
+        let now = Utc::now();
+        let reading = Reading {
+            id: Uuid::new_v4().to_string(),
+            area: area.to_string(),
+            parameter,
+            value,
+            recorded_by: recorded_by.to_string(),
+            recorded_at: now,
+            excursion_id: (!limit.contains(value)).then(|| Uuid::new_v4().to_string()),
+        };
+
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "INSERT INTO environmental_readings (id, area, parameter, value, recorded_by, recorded_at, excursion_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    reading.id,
+                    reading.area,
+                    parameter.as_str(),
+                    value,
+                    recorded_by,
+                    now.to_rfc3339(),
+                    reading.excursion_id,
+                ],
+            )?;
+            if let Some(excursion_id) = &reading.excursion_id {
+                uow.connection().execute(
+                    "INSERT INTO environmental_excursions
+                         (id, reading_id, area, parameter, value, limit_text, status, opened_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        excursion_id,
+                        reading.id,
+                        area,
+                        parameter.as_str(),
+                        value,
+                        limit.describe(),
+                        ExcursionStatus::Open.as_str(),
+                        now.to_rfc3339(),
+                    ],
+                )?;
+                self.audit.log_action_in(
+                    uow,
+                    recorded_by,
+                    "environmental_excursion_opened",
+                    &resource(excursion_id),
+                    "Warning",
+                    Some(format!("{} {} {} outside {}", area, parameter.as_str(), value, limit.describe())),
+                )?;
+            }
+            Ok(())
+        })?;
+
+        if let Some(excursion_id) = &reading.excursion_id {
+            let ncr = QualityIntake::new(self.db).submit(
+                IssueSource::Nonconformance,
+                &format!("Environmental excursion in {}", area),
+                &format!(
+                    "{} reading {} {} outside limits {} (excursion {})",
+                    parameter.as_str(),
+                    value,
+                    parameter.unit(),
+                    limit.describe(),
+                    excursion_id
+                ),
+                recorded_by,
+            )?;
+            self.db.with_connection(|conn| {
+                conn.execute(
+                    "UPDATE environmental_excursions SET ncr_id = ?2 WHERE id = ?1",
+                    params![excursion_id, ncr.id.to_string()],
+                )?;
+                Ok(())
+            })?;
+        }
+        Ok(reading)
+    }
+
+    pub fn excursion(&self, id: &str) -> Result<Excursion> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM environmental_excursions WHERE id = ?1", EXCURSION_COLUMNS),
+                        params![id],
+                        row_to_excursion,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "environmental_excursion".to_string(), id: id.to_string() })
+    }
+
+    /// Excursions not yet closed, oldest first.
+    pub fn open_excursions(&self) -> Result<Vec<Excursion>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM environmental_excursions WHERE status != 'closed' ORDER BY opened_at",
+                EXCURSION_COLUMNS
+            ))?;
+            let excursions = stmt.query_map([], row_to_excursion)?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(excursions)
+        })
+    }
+
+    /// Escalate an open excursion to a CAPA by triaging its NCR.
+    pub fn escalate_to_capa(
+        &self,
+        id: &str,
+        capa_service: &CapaService,
+        priority: CapaPriority,
+        assigned_to: &str,
+        escalated_by: &str,
+    ) -> Result<Excursion> {
+        let mut excursion = self.excursion(id)?;
+        let ncr_id = open_ncr(&excursion, "escalate")?;
+        let route = Route::Capa { capa_type: CapaType::Corrective, priority, assigned_to: assigned_to.to_string() };
+        let item = QualityIntake::new(self.db).with_capa_service(capa_service).triage(
+            ncr_id,
+            route,
+            escalated_by,
+            &format!("Environmental excursion {} escalated", id),
+        )?;
+        excursion.status = ExcursionStatus::Escalated;
+        excursion.capa_id = item.routed_record_id;
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE environmental_excursions SET status = ?2, capa_id = ?3 WHERE id = ?1",
+                params![id, excursion.status.as_str(), excursion.capa_id],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                escalated_by,
+                "environmental_excursion_escalated",
+                &resource(id),
+                "Success",
+                excursion.capa_id.as_ref().map(|capa| format!("CAPA {}", capa)),
+            )
+        })?;
+        Ok(excursion)
+    }
+
+    /// Close an excursion with its documented disposition (impact assessment,
+    /// product hold/release). An NCR still awaiting triage is closed with it.
+    pub fn close_excursion(&self, id: &str, disposition: &str, closed_by: &str) -> Result<Excursion> {
+        if disposition.trim().is_empty() {
+            return Err(QmsError::Validation {
+                field: "disposition".to_string(),
+                message: "An excursion disposition must be documented".to_string(),
+            });
+        }
+        let mut excursion = self.excursion(id)?;
+        if excursion.status == ExcursionStatus::Closed {
+            return Err(QmsError::Validation {
+                field: "status".to_string(),
+                message: format!("Excursion {} is already closed", id),
+            });
+        }
+        if let Some(ncr_id) = excursion.ncr_id {
+            let intake = QualityIntake::new(self.db);
+            if !intake.get(ncr_id)?.is_triaged() {
+                intake.triage(ncr_id, Route::NoAction, closed_by, disposition)?;
+            }
+        }
+
+        let now = Utc::now();
+        excursion.status = ExcursionStatus::Closed;
+        excursion.closed_by = Some(closed_by.to_string());
+        excursion.closed_at = Some(now);
+        excursion.disposition = Some(disposition.to_string());
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE environmental_excursions SET status = ?2, closed_by = ?3, closed_at = ?4, disposition = ?5
+                 WHERE id = ?1",
+                params![id, excursion.status.as_str(), closed_by, now.to_rfc3339(), disposition],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                closed_by,
+                "environmental_excursion_closed",
+                &resource(id),
+                "Success",
+                Some(disposition.to_string()),
+            )
+        })?;
+        Ok(excursion)
+    }
+
+    /// Daily trend of `parameter` in `area` from `since` onwards.
+    pub fn trend(&self, area: &str, parameter: Parameter, since: DateTime<Utc>) -> Result<EnvironmentalTrend> {
+        let points = self.db.with_connection(|conn| {
+            // recorded_at is RFC 3339 UTC, so its first ten characters are the day
+            let mut stmt = conn.prepare(
+                "SELECT substr(recorded_at, 1, 10) AS day, COUNT(*), MIN(value), MAX(value), AVG(value),
+                        COUNT(excursion_id)
+                 FROM environmental_readings
+                 WHERE area = ?1 AND parameter = ?2 AND recorded_at >= ?3
+                 GROUP BY day ORDER BY day",
+            )?;
+            let points = stmt
+                .query_map(params![area, parameter.as_str(), since.to_rfc3339()], |row| {
+                    let day: String = row.get(0)?;
+                    Ok(TrendPoint {
+                        day: day.parse().map_err(|e: chrono::ParseError| conversion_error(0, e.to_string()))?,
+                        readings: row.get(1)?,
+                        min: row.get(2)?,
+                        max: row.get(3)?,
+                        mean: row.get(4)?,
+                        excursions: row.get(5)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(points)
+        })?;
+        Ok(EnvironmentalTrend { area: area.to_string(), parameter, points })
+    }
+
+    /// Trends of every configured area/parameter with readings since `since`.
+    pub fn dashboard_trends(&self, since: DateTime<Utc>) -> Result<Vec<EnvironmentalTrend>> {
+        let mut trends = Vec::new();
+        for limit in self.limits()? {
+            let trend = self.trend(&limit.area, limit.parameter, since)?;
+            if !trend.points.is_empty() {
+                trends.push(trend);
+            }
+        }
+        Ok(trends)
+    }
+}
+
+fn resource(id: &str) -> String {
+    format!("environmental_excursion:{}", id)
+}
+
+fn open_ncr(excursion: &Excursion, action: &str) -> Result<Uuid> {
+    if excursion.status != ExcursionStatus::Open {
+        return Err(QmsError::Validation {
+            field: "status".to_string(),
+            message: format!("Cannot {} an excursion in status {}", action, excursion.status.as_str()),
+        });
+    }
+    excursion.ncr_id.ok_or_else(|| QmsError::Validation {
+        field: "ncr_id".to_string(),
+        message: format!("Excursion {} has no nonconformance to triage", excursion.id),
+    })
+}
+
+const EXCURSION_COLUMNS: &str = "id, reading_id, area, parameter, value, limit_text, status, ncr_id, capa_id,
+     opened_at, closed_by, closed_at, disposition";
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn parse_time(row: &Row, index: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| conversion_error(index, e.to_string()))
+        })
+        .transpose()
+}
+
+fn parse_text<T>(row: &Row, index: usize, parse: fn(&str) -> Option<T>) -> rusqlite::Result<T> {
+    let value: String = row.get(index)?;
+    parse(&value).ok_or_else(|| conversion_error(index, format!("unexpected value {}", value)))
+}
+
+fn row_to_limit(row: &Row) -> rusqlite::Result<MonitoringLimit> {
+    Ok(MonitoringLimit {
+        area: row.get(0)?,
+        parameter: parse_text(row, 1, Parameter::parse)?,
+        min: row.get(2)?,
+        max: row.get(3)?,
+    })
+}
+
+fn row_to_excursion(row: &Row) -> rusqlite::Result<Excursion> {
+    let ncr_id: Option<String> = row.get(7)?;
+    Ok(Excursion {
+        id: row.get(0)?,
+        reading_id: row.get(1)?,
+        area: row.get(2)?,
+        parameter: parse_text(row, 3, Parameter::parse)?,
+        value: row.get(4)?,
+        limit: row.get(5)?,
+        status: parse_text(row, 6, ExcursionStatus::parse)?,
+        ncr_id: ncr_id.map(|id| Uuid::parse_str(&id).map_err(|e| conversion_error(7, e.to_string()))).transpose()?,
+        capa_id: row.get(8)?,
+        opened_at: parse_time(row, 9)?.unwrap_or_default(),
+        closed_by: row.get(10)?,
+        closed_at: parse_time(row, 11)?,
+        disposition: row.get(12)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use chrono::Duration;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    fn iso7_limits(repo: &EnvironmentalRepo) {
+        let limit = |parameter, min, max| MonitoringLimit { area: "ISO7-A".to_string(), parameter, min, max };
+        repo.set_limit(&limit(Parameter::Particles0_5um, None, Some(352_000.0)), "qa_manager").unwrap();
+        repo.set_limit(&limit(Parameter::Temperature, Some(18.0), Some(24.0)), "qa_manager").unwrap();
+    }
+
+    #[test]
+    fn test_excursion_raises_ncr_and_escalates_to_capa() {
+        let db = test_db();
+        let repo = EnvironmentalRepo::new(&db);
+        iso7_limits(&repo);
+
+        assert!(repo.record_reading("ISO8-B", Parameter::Temperature, 20.0, "op1").is_err());
+        let ok = repo.record_reading("ISO7-A", Parameter::Temperature, 21.5, "op1").unwrap();
+        assert!(ok.excursion_id.is_none());
+        let high = repo.record_reading("ISO7-A", Parameter::Particles0_5um, 410_000.0, "op1").unwrap();
+        let excursion = repo.excursion(high.excursion_id.as_deref().unwrap()).unwrap();
+        assert_eq!(excursion.status, ExcursionStatus::Open);
+        let ncr = QualityIntake::new(&db).get(excursion.ncr_id.unwrap()).unwrap();
+        assert_eq!(ncr.source, IssueSource::Nonconformance);
+
+        let capa_service = CapaService::new(AuditManager::new(db.clone()));
+        let escalated = repo.escalate_to_capa(&excursion.id, &capa_service, CapaPriority::High, "eng1", "qa_manager").unwrap();
+        assert_eq!(escalated.status, ExcursionStatus::Escalated);
+        assert!(escalated.capa_id.is_some());
+        assert!(repo.escalate_to_capa(&excursion.id, &capa_service, CapaPriority::High, "eng1", "qa_manager").is_err());
+
+        assert!(repo.close_excursion(&excursion.id, " ", "qa_manager").is_err());
+        let closed = repo.close_excursion(&excursion.id, "HEPA filter replaced; lot 42 released", "qa_manager").unwrap();
+        assert_eq!(repo.excursion(&excursion.id).unwrap(), closed);
+        assert!(repo.open_excursions().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_closing_untriaged_excursion_closes_ncr() {
+        let db = test_db();
+        let repo = EnvironmentalRepo::new(&db);
+        iso7_limits(&repo);
+        let cold = repo.record_reading("ISO7-A", Parameter::Temperature, 16.5, "op1").unwrap();
+        let id = cold.excursion_id.unwrap();
+        assert_eq!(repo.open_excursions().unwrap().len(), 1);
+
+        repo.close_excursion(&id, "HVAC setpoint restored; no product exposed", "qa_manager").unwrap();
+        let ncr = QualityIntake::new(&db).get(repo.excursion(&id).unwrap().ncr_id.unwrap()).unwrap();
+        assert_eq!(ncr.routed_to.as_deref(), Some("no_action"));
+        assert_eq!(db.get_audit_entries_for_resource(&resource(&id)).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_daily_trend_and_sparkline() {
+        let db = test_db();
+        let repo = EnvironmentalRepo::new(&db);
+        iso7_limits(&repo);
+        assert!(repo
+            .set_limit(
+                &MonitoringLimit { area: "ISO7-A".to_string(), parameter: Parameter::RelativeHumidity, min: Some(60.0), max: Some(30.0) },
+                "qa_manager",
+            )
+            .is_err());
+        for value in [19.0, 21.0, 25.0] {
+            repo.record_reading("ISO7-A", Parameter::Temperature, value, "op1").unwrap();
+        }
+
+        let trends = repo.dashboard_trends(Utc::now() - Duration::days(7)).unwrap();
+        assert_eq!(trends.len(), 1);
+        let point = &trends[0].points[0];
+        assert_eq!((point.readings, point.min, point.max, point.excursions), (3, 19.0, 25.0, 1));
+        assert!((point.mean - 65.0 / 3.0).abs() < 1e-9);
+        assert_eq!(trends[0].excursions(), 1);
+        assert_eq!(trends[0].sparkline(), vec![50]);
+    }
+}
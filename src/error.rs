@@ -1,3 +1,7 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// Custom result type for QMS operations
@@ -22,10 +26,18 @@ pub enum QmsError {
     #[error("Validation error in field '{field}': {message}")]
     ValidationError { field: String, message: String },
 
+    /// Several invalid fields reported together
+    #[error("{errors}")]
+    ValidationErrors { errors: ValidationErrors },
+
     /// Resource not found errors
     #[error("Resource '{resource}' with ID '{id}' not found")]
     NotFound { resource: String, id: String },
 
+    /// Optimistic concurrency conflict: the record changed since it was read
+    #[error("Conflict: {resource} '{id}' was changed by someone else (expected version {expected}, current version {actual})")]
+    Conflict { resource: String, id: String, expected: i64, actual: i64 },
+
     /// Audit trail errors (critical for FDA compliance)
     #[error("Audit trail error: {message}")]
     AuditTrail { message: String },
@@ -74,12 +86,14 @@ impl QmsError {
             QmsError::UserInterface { .. } => "UI_ERROR",
             QmsError::Validation { .. } => "VAL_ERROR",
             QmsError::ValidationError { .. } => "VAL_ERROR",
+            QmsError::ValidationErrors { .. } => "VAL_ERROR",
             QmsError::Encryption { .. } => "ENC_ERROR",
             QmsError::FileSystem { .. } => "FS_ERROR",
             QmsError::Network { .. } => "NET_ERROR",
             QmsError::Serialization { .. } => "SER_ERROR",
             QmsError::Application { .. } => "APP_ERROR",
             QmsError::NotFound { .. } => "NOT_FOUND",
+            QmsError::Conflict { .. } => "CONFLICT",
             QmsError::Configuration { .. } => "CFG_ERROR",
         }
     }
@@ -93,6 +107,7 @@ impl QmsError {
             QmsError::DocumentControl { .. } => ErrorSeverity::High,
             QmsError::Validation { .. } => ErrorSeverity::Medium,
             QmsError::ValidationError { .. } => ErrorSeverity::Medium,
+            QmsError::ValidationErrors { .. } => ErrorSeverity::Medium,
             QmsError::Encryption { .. } => ErrorSeverity::High,
             QmsError::Configuration { .. } => ErrorSeverity::Medium,
             QmsError::UserInterface { .. } => ErrorSeverity::Low,
@@ -101,6 +116,7 @@ impl QmsError {
             QmsError::Serialization { .. } => ErrorSeverity::Low,
             QmsError::Application { .. } => ErrorSeverity::Medium,
             QmsError::NotFound { .. } => ErrorSeverity::Medium,
+            QmsError::Conflict { .. } => ErrorSeverity::Medium,
         }
     }
 
@@ -110,6 +126,90 @@ impl QmsError {
     }
 }
 
+/// Field → message map collecting every invalid field of one input, so a
+/// form can show all problems at once instead of one per submission.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ValidationErrors {
+    fields: BTreeMap<String, String>,
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an error for `field`; a second error for the same field is
+    /// appended to the first.
+    pub fn add<F: Into<String>, M: Into<String>>(&mut self, field: F, message: M) {
+        let message = message.into();
+        self.fields
+            .entry(field.into())
+            .and_modify(|existing| {
+                existing.push_str("; ");
+                existing.push_str(&message);
+            })
+            .or_insert(message);
+    }
+
+    /// Record `error` if it is a (single or aggregated) validation error;
+    /// any other error is returned unchanged.
+    pub fn absorb(&mut self, error: QmsError) -> Result<()> {
+        match error {
+            QmsError::Validation { field, message } | QmsError::ValidationError { field, message } => {
+                self.add(field, message);
+                Ok(())
+            }
+            QmsError::ValidationErrors { errors } => {
+                for (field, message) in errors.fields {
+                    self.add(field, message);
+                }
+                Ok(())
+            }
+            other => Err(other),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Message for `field`, if it is invalid.
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.fields.get(field).map(String::as_str)
+    }
+
+    /// Invalid fields and their messages, ordered by field name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.fields.iter().map(|(field, message)| (field.as_str(), message.as_str()))
+    }
+
+    /// `Ok(())` when nothing was recorded, else the aggregated error.
+    pub fn into_result(self) -> Result<()> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(QmsError::ValidationErrors { errors: self })
+        }
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, (field, message)) in self.iter().enumerate() {
+            if index > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "Validation error in field '{}': {}", field, message)?;
+        }
+        Ok(())
+    }
+}
+
 /// Error severity levels for compliance reporting
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorSeverity {
@@ -214,6 +314,26 @@ mod tests {
         assert_eq!(ErrorSeverity::Critical.as_str(), "CRITICAL");
     }
 
+    #[test]
+    fn test_validation_errors_aggregate_fields() {
+        let mut errors = ValidationErrors::new();
+        assert!(errors.clone().into_result().is_ok());
+        errors.add("title", "must not be empty");
+        errors.absorb(QmsError::ValidationError { field: "due_date".to_string(), message: "in the past".to_string() }).unwrap();
+        errors.add("title", "too short");
+        assert!(errors.absorb(QmsError::Database { message: "x".to_string() }).is_err());
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors.get("title"), Some("must not be empty; too short"));
+        assert_eq!(
+            serde_json::to_value(&errors).unwrap(),
+            serde_json::json!({ "due_date": "in the past", "title": "must not be empty; too short" })
+        );
+        let error = errors.into_result().unwrap_err();
+        assert_eq!(error.error_code(), "VAL_ERROR");
+        assert!(error.to_string().starts_with("Validation error in field 'due_date': in the past; "));
+    }
+
     #[test]
     fn test_error_conversion_from_io() {
         let io_error = std::io::Error::new(std::io::ErrorKind::NotFound, "File not found");
@@ -0,0 +1,671 @@
+//! # Escalation - Reminder and Escalation Rules
+//!
+//! Rules such as "CAPA open longer than 60 days with priority Critical →
+//! notify the QA director" or "document review overdue by 14 days → escalate
+//! to the department head" are stored in `escalation_rules`. Creating a rule
+//! is audited and every later change is kept as field-level change history
+//! with its reason (21 CFR 11.10(e)). The report scheduler evaluates the
+//! enabled rules; each rule fires once per record, and every firing is
+//! recorded in `escalation_firings` and the audit trail.
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::capa::{CapaRecord, CapaStatus};
+use crate::change_history::{diff_fields, record_changes, ChangeReason};
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::notification::{is_valid_address, EmailMessage, Notifier};
+
+/// User recorded in the audit trail for rule evaluation.
+const ESCALATION_USER: &str = "system:escalation";
+
+/// Record family a rule watches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubjectKind {
+    Capa,
+    /// Periodic document review
+    Document,
+}
+
+impl SubjectKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubjectKind::Capa => "capa",
+            SubjectKind::Document => "document",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [SubjectKind::Capa, SubjectKind::Document].into_iter().find(|item| item.as_str() == value)
+    }
+}
+
+/// Condition measured against the rule's threshold in days.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Trigger {
+    /// Record still open more than `threshold_days` after it was opened
+    OpenLongerThan,
+    /// Due date passed at least `threshold_days` ago
+    OverdueBy,
+}
+
+impl Trigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Trigger::OpenLongerThan => "open_longer_than",
+            Trigger::OverdueBy => "overdue_by",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [Trigger::OpenLongerThan, Trigger::OverdueBy].into_iter().find(|item| item.as_str() == value)
+    }
+}
+
+/// A reminder or escalation rule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EscalationRule {
+    pub id: Uuid,
+    pub name: String,
+    pub subject: SubjectKind,
+    pub trigger: Trigger,
+    pub threshold_days: i64,
+    /// Only records of this priority, e.g. `Critical` (CAPA only)
+    pub priority: Option<String>,
+    /// Who is escalated to, e.g. "QA director"
+    pub escalate_to: String,
+    pub recipients: Vec<String>,
+    pub enabled: bool,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl EscalationRule {
+    /// Create an enabled rule.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        name: &str,
+        subject: SubjectKind,
+        trigger: Trigger,
+        threshold_days: i64,
+        priority: Option<&str>,
+        escalate_to: &str,
+        recipients: Vec<String>,
+        created_by: &str,
+    ) -> Result<Self> {
+        let rule = Self {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            subject,
+            trigger,
+            threshold_days,
+            priority: priority.map(str::to_string),
+            escalate_to: escalate_to.to_string(),
+            recipients,
+            enabled: true,
+            created_by: created_by.to_string(),
+            created_at: Utc::now(),
+        };
+        rule.validate()?;
+        Ok(rule)
+    }
+
+    fn validate(&self) -> Result<()> {
+        for (field, value) in [("name", &self.name), ("escalate_to", &self.escalate_to)] {
+            if value.trim().is_empty() {
+                return Err(QmsError::Validation {
+                    field: field.to_string(),
+                    message: format!("{} is required", field),
+                });
+            }
+        }
+        if self.threshold_days < 0 {
+            return Err(QmsError::Validation {
+                field: "threshold_days".to_string(),
+                message: "Threshold must not be negative".to_string(),
+            });
+        }
+        if self.priority.is_some() && self.subject != SubjectKind::Capa {
+            return Err(QmsError::Validation {
+                field: "priority".to_string(),
+                message: "Only CAPA rules can filter by priority".to_string(),
+            });
+        }
+        if self.recipients.is_empty() {
+            return Err(QmsError::Validation {
+                field: "recipients".to_string(),
+                message: "At least one recipient is required".to_string(),
+            });
+        }
+        if let Some(bad) = self.recipients.iter().find(|r| !is_valid_address(r)) {
+            return Err(QmsError::Validation {
+                field: "recipients".to_string(),
+                message: format!("Invalid email address: {}", bad),
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether `subject` meets the rule at `now`.
+    pub fn matches(&self, subject: &EscalationSubject, now: DateTime<Utc>) -> bool {
+        if subject.kind != self.subject {
+            return false;
+        }
+        if let Some(priority) = &self.priority {
+            if subject.priority.as_deref() != Some(priority.as_str()) {
+                return false;
+            }
+        }
+        match self.trigger {
+            Trigger::OpenLongerThan => (now - subject.opened_at).num_days() > self.threshold_days,
+            Trigger::OverdueBy => {
+                subject.due_at.is_some_and(|due| due < now && (now - due).num_days() >= self.threshold_days)
+            }
+        }
+    }
+
+    /// Plain-language form, e.g. `capa open_longer_than 60 days (Critical) -> QA director`.
+    pub fn describe(&self) -> String {
+        let priority = self.priority.as_ref().map(|p| format!(" ({})", p)).unwrap_or_default();
+        format!(
+            "{} {} {} days{} -> {}",
+            self.subject.as_str(),
+            self.trigger.as_str(),
+            self.threshold_days,
+            priority,
+            self.escalate_to
+        )
+    }
+}
+
+/// An open record as seen by the rules.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EscalationSubject {
+    pub kind: SubjectKind,
+    pub record_id: String,
+    /// Identifier shown in the notification
+    pub reference: String,
+    pub title: String,
+    pub priority: Option<String>,
+    pub opened_at: DateTime<Utc>,
+    pub due_at: Option<DateTime<Utc>>,
+}
+
+impl EscalationSubject {
+    /// `None` for closed or cancelled CAPAs.
+    pub fn from_capa(capa: &CapaRecord) -> Option<Self> {
+        if matches!(capa.status, CapaStatus::Closed | CapaStatus::Cancelled) {
+            return None;
+        }
+        Some(Self {
+            kind: SubjectKind::Capa,
+            record_id: capa.id.clone(),
+            reference: capa.display_id().to_string(),
+            title: capa.title.clone(),
+            priority: Some(capa.priority.as_str().to_string()),
+            opened_at: capa.created_at,
+            due_at: capa.due_date,
+        })
+    }
+}
+
+/// Documents in use with a scheduled periodic review, due at the start of
+/// their review date.
+pub fn document_review_subjects(db: &Database) -> Result<Vec<EscalationSubject>> {
+    db.with_connection(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, document_number, title, created_at, review_date FROM documents
+             WHERE review_date IS NOT NULL AND deleted_at IS NULL AND status NOT IN ('Obsolete', 'Retired')",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(EscalationSubject {
+                kind: SubjectKind::Document,
+                record_id: row.get(0)?,
+                reference: row.get(1)?,
+                title: row.get(2)?,
+                priority: None,
+                opened_at: parse_sql_time(row, 3)?,
+                due_at: Some(parse_sql_time(row, 4)?),
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    })
+}
+
+/// A rule that fired for a record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Escalation {
+    pub rule_id: Uuid,
+    pub record_id: String,
+    pub reference: String,
+    pub fired_at: DateTime<Utc>,
+    /// Message identifier of the notification, when a notifier is configured
+    pub message_id: Option<String>,
+}
+
+/// Persistence of escalation rules.
+pub struct EscalationRuleRepo<'a> {
+    db: &'a Database,
+    audit: AuditManager,
+}
+
+impl<'a> EscalationRuleRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, audit: AuditManager::new(db.clone()) }
+    }
+
+    pub fn insert(&self, rule: &EscalationRule) -> Result<()> {
+        rule.validate()?;
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                &format!(
+                    "INSERT INTO escalation_rules ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                    COLUMNS
+                ),
+                params![
+                    rule.id.to_string(),
+                    rule.name,
+                    rule.subject.as_str(),
+                    rule.trigger.as_str(),
+                    rule.threshold_days,
+                    rule.priority,
+                    rule.escalate_to,
+                    serde_json::to_string(&rule.recipients)?,
+                    rule.enabled,
+                    rule.created_by,
+                    rule.created_at.to_rfc3339(),
+                ],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                &rule.created_by,
+                "escalation_rule_created",
+                &resource(rule.id),
+                "Success",
+                Some(format!("{}: {}", rule.name, rule.describe())),
+            )
+        })
+    }
+
+    pub fn get(&self, id: Uuid) -> Result<EscalationRule> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM escalation_rules WHERE id = ?1", COLUMNS),
+                        params![id.to_string()],
+                        row_to_rule,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "escalation_rule".to_string(), id: id.to_string() })
+    }
+
+    pub fn list(&self) -> Result<Vec<EscalationRule>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!("SELECT {} FROM escalation_rules ORDER BY name", COLUMNS))?;
+            let rules = stmt.query_map([], row_to_rule)?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rules)
+        })
+    }
+
+    /// Replace a rule's definition, recording each changed field with `reason`.
+    pub fn update(&self, rule: &EscalationRule, changed_by: &str, reason: &ChangeReason) -> Result<()> {
+        rule.validate()?;
+        let previous = self.get(rule.id)?;
+        let changes = diff_fields(&previous, rule, &["id", "created_by", "created_at"])?;
+        if changes.is_empty() {
+            return Ok(());
+        }
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE escalation_rules
+                 SET name = ?2, subject = ?3, trigger_kind = ?4, threshold_days = ?5, priority = ?6,
+                     escalate_to = ?7, recipients = ?8, enabled = ?9
+                 WHERE id = ?1",
+                params![
+                    rule.id.to_string(),
+                    rule.name,
+                    rule.subject.as_str(),
+                    rule.trigger.as_str(),
+                    rule.threshold_days,
+                    rule.priority,
+                    rule.escalate_to,
+                    serde_json::to_string(&rule.recipients)?,
+                    rule.enabled,
+                ],
+            )?;
+            record_changes(uow.connection(), "escalation_rule", &rule.id.to_string(), changed_by, reason, &changes)?;
+            self.audit.log_action_in(
+                uow,
+                changed_by,
+                "escalation_rule_updated",
+                &resource(rule.id),
+                "Success",
+                Some(format!("{}: {}", rule.name, rule.describe())),
+            )
+        })
+    }
+
+    /// Whether `rule_id` already fired for `record_id`.
+    fn has_fired(&self, rule_id: Uuid, record_id: &str) -> Result<bool> {
+        self.db.with_connection(|conn| {
+            Ok(conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM escalation_firings WHERE rule_id = ?1 AND record_id = ?2)",
+                params![rule_id.to_string(), record_id],
+                |row| row.get(0),
+            )?)
+        })
+    }
+}
+
+/// Evaluates the enabled rules and notifies their recipients.
+pub struct EscalationEngine<'a> {
+    db: &'a Database,
+    notifier: Option<&'a dyn Notifier>,
+}
+
+impl<'a> EscalationEngine<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, notifier: None }
+    }
+
+    pub fn with_notifier(mut self, notifier: &'a dyn Notifier) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Fire every enabled rule that `subjects` newly meet at `now`.
+    pub fn evaluate(&self, subjects: &[EscalationSubject], now: DateTime<Utc>) -> Result<Vec<Escalation>> {
+        let repo = EscalationRuleRepo::new(self.db);
+        let mut fired = Vec::new();
+        for rule in repo.list()?.into_iter().filter(|r| r.enabled) {
+            for subject in subjects.iter().filter(|s| rule.matches(s, now)) {
+                if repo.has_fired(rule.id, &subject.record_id)? {
+                    continue;
+                }
+                let message_id = match self.notifier {
+                    Some(notifier) => Some(notifier.send(&escalation_email(&rule, subject, now))?),
+                    None => None,
+                };
+                let escalation = Escalation {
+                    rule_id: rule.id,
+                    record_id: subject.record_id.clone(),
+                    reference: subject.reference.clone(),
+                    fired_at: now,
+                    message_id,
+                };
+                self.db.unit_of_work(|uow| {
+                    uow.connection().execute(
+                        "INSERT INTO escalation_firings (rule_id, record_id, subject, fired_at, message_id)
+                         VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![
+                            rule.id.to_string(),
+                            subject.record_id,
+                            subject.kind.as_str(),
+                            now.to_rfc3339(),
+                            escalation.message_id,
+                        ],
+                    )?;
+                    AuditManager::new(self.db.clone()).log_action_in(
+                        uow,
+                        ESCALATION_USER,
+                        "escalation_fired",
+                        &format!("{}:{}", subject.kind.as_str(), subject.record_id),
+                        "Warning",
+                        Some(format!(
+                            "Rule '{}' ({}) escalated {} to {}",
+                            rule.name,
+                            rule.describe(),
+                            subject.reference,
+                            rule.escalate_to
+                        )),
+                    )
+                })?;
+                fired.push(escalation);
+            }
+        }
+        Ok(fired)
+    }
+}
+
+fn escalation_email(rule: &EscalationRule, subject: &EscalationSubject, now: DateTime<Utc>) -> EmailMessage {
+    let due = subject.due_at.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_else(|| "-".to_string());
+    let body = format!(
+        "Escalation to {}: {} {} \"{}\" meets rule \"{}\" ({}).\n\n\
+         Opened: {}\nDue: {}\n\n\
+         This message was sent automatically by QMSrs.",
+        rule.escalate_to,
+        subject.kind.as_str(),
+        subject.reference,
+        subject.title,
+        rule.name,
+        rule.describe(),
+        subject.opened_at.format("%Y-%m-%d"),
+        due,
+    );
+    EmailMessage::new(
+        rule.recipients.clone(),
+        format!("Escalation: {} {} - {}", subject.reference, rule.name, now.format("%Y-%m-%d")),
+        body,
+    )
+}
+
+fn resource(id: Uuid) -> String {
+    format!("escalation_rule:{}", id)
+}
+
+const COLUMNS: &str = "id, name, subject, trigger_kind, threshold_days, priority, escalate_to, recipients, enabled,
+     created_by, created_at";
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+/// Timestamp in RFC 3339, SQLite `CURRENT_TIMESTAMP` or plain date form.
+fn parse_sql_time(row: &Row, index: usize) -> rusqlite::Result<DateTime<Utc>> {
+    let value: String = row.get(index)?;
+    if let Ok(time) = DateTime::parse_from_rfc3339(&value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    if let Ok(time) = chrono::NaiveDateTime::parse_from_str(&value, "%Y-%m-%d %H:%M:%S") {
+        return Ok(Utc.from_utc_datetime(&time));
+    }
+    NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+        .map(|date| Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default()))
+        .map_err(|e| conversion_error(index, e.to_string()))
+}
+
+fn parse_text<T>(row: &Row, index: usize, parse: fn(&str) -> Option<T>) -> rusqlite::Result<T> {
+    let value: String = row.get(index)?;
+    parse(&value).ok_or_else(|| conversion_error(index, format!("unexpected value {}", value)))
+}
+
+fn row_to_rule(row: &Row) -> rusqlite::Result<EscalationRule> {
+    let id: String = row.get(0)?;
+    let recipients: String = row.get(7)?;
+    Ok(EscalationRule {
+        id: Uuid::parse_str(&id).map_err(|e| conversion_error(0, e.to_string()))?,
+        name: row.get(1)?,
+        subject: parse_text(row, 2, SubjectKind::parse)?,
+        trigger: parse_text(row, 3, Trigger::parse)?,
+        threshold_days: row.get(4)?,
+        priority: row.get(5)?,
+        escalate_to: row.get(6)?,
+        recipients: serde_json::from_str(&recipients).map_err(|e| conversion_error(7, e.to_string()))?,
+        enabled: row.get(8)?,
+        created_by: row.get(9)?,
+        created_at: parse_sql_time(row, 10)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::change_history::ChangeHistoryRepo;
+    use crate::config::DatabaseConfig;
+    use crate::notification::OutboxNotifier;
+    use chrono::Duration;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    fn subject(
+        kind: SubjectKind,
+        id: &str,
+        priority: Option<&str>,
+        opened_days_ago: i64,
+        due_days_ago: Option<i64>,
+    ) -> EscalationSubject {
+        let now = Utc::now();
+        EscalationSubject {
+            kind,
+            record_id: id.to_string(),
+            reference: id.to_uppercase(),
+            title: "Seal failure".to_string(),
+            priority: priority.map(str::to_string),
+            opened_at: now - Duration::days(opened_days_ago),
+            due_at: due_days_ago.map(|d| now - Duration::days(d)),
+        }
+    }
+
+    #[test]
+    fn test_rule_conditions() {
+        let recipients = vec!["qa.director@example.com".to_string()];
+        let critical = EscalationRule::new(
+            "Critical CAPA aging",
+            SubjectKind::Capa,
+            Trigger::OpenLongerThan,
+            60,
+            Some("Critical"),
+            "QA director",
+            recipients.clone(),
+            "qa",
+        )
+        .unwrap();
+        let now = Utc::now();
+        assert!(critical.matches(&subject(SubjectKind::Capa, "c1", Some("Critical"), 61, None), now));
+        assert!(!critical.matches(&subject(SubjectKind::Capa, "c2", Some("Critical"), 59, None), now));
+        assert!(!critical.matches(&subject(SubjectKind::Capa, "c3", Some("High"), 90, None), now));
+        assert_eq!(critical.describe(), "capa open_longer_than 60 days (Critical) -> QA director");
+
+        let review = EscalationRule::new(
+            "Review overdue",
+            SubjectKind::Document,
+            Trigger::OverdueBy,
+            14,
+            None,
+            "Department head",
+            recipients.clone(),
+            "qa",
+        )
+        .unwrap();
+        assert!(review.matches(&subject(SubjectKind::Document, "d1", None, 400, Some(15)), now));
+        assert!(!review.matches(&subject(SubjectKind::Document, "d2", None, 400, Some(3)), now));
+        assert!(!review.matches(&subject(SubjectKind::Document, "d3", None, 400, None), now));
+
+        assert!(EscalationRule::new(
+            "x",
+            SubjectKind::Document,
+            Trigger::OverdueBy,
+            1,
+            Some("High"),
+            "QA",
+            recipients.clone(),
+            "qa"
+        )
+        .is_err());
+        assert!(
+            EscalationRule::new("x", SubjectKind::Capa, Trigger::OverdueBy, -1, None, "QA", recipients, "qa").is_err()
+        );
+        assert!(EscalationRule::new(
+            "x",
+            SubjectKind::Capa,
+            Trigger::OverdueBy,
+            1,
+            None,
+            "QA",
+            vec!["nobody".to_string()],
+            "qa"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_rules_fire_once_notify_and_are_audited() {
+        let db = test_db();
+        let dir = tempfile::tempdir().unwrap();
+        let notifier = OutboxNotifier::new(dir.path(), "qms@example.com");
+        let repo = EscalationRuleRepo::new(&db);
+        let mut rule = EscalationRule::new(
+            "Critical CAPA aging",
+            SubjectKind::Capa,
+            Trigger::OpenLongerThan,
+            60,
+            Some("Critical"),
+            "QA director",
+            vec!["qa.director@example.com".to_string()],
+            "qa_manager",
+        )
+        .unwrap();
+        repo.insert(&rule).unwrap();
+        assert_eq!(repo.list().unwrap(), vec![rule.clone()]);
+
+        let subjects = vec![
+            subject(SubjectKind::Capa, "c1", Some("Critical"), 75, None),
+            subject(SubjectKind::Capa, "c2", Some("Critical"), 10, None),
+        ];
+        let engine = EscalationEngine::new(&db).with_notifier(&notifier);
+        let fired = engine.evaluate(&subjects, Utc::now()).unwrap();
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].record_id, "c1");
+        assert!(fired[0].message_id.is_some());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+        assert!(engine.evaluate(&subjects, Utc::now()).unwrap().is_empty());
+        assert_eq!(db.get_audit_entries_for_resource("capa:c1").unwrap().len(), 1);
+
+        rule.threshold_days = 5;
+        rule.enabled = false;
+        let reason = ChangeReason::new("Tightened after management review").unwrap();
+        repo.update(&rule, "qa_manager", &reason).unwrap();
+        assert!(engine.evaluate(&subjects, Utc::now()).unwrap().is_empty());
+        let history = ChangeHistoryRepo::new(&db).history("escalation_rule", &rule.id.to_string()).unwrap();
+        let fields: Vec<&str> = history[0].changes.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, vec!["enabled", "threshold_days"]);
+        assert_eq!(db.get_audit_entries_for_resource(&resource(rule.id)).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_document_review_subjects() {
+        let db = test_db();
+        db.seed_test_users(&["qa"]);
+        db.with_connection(|conn| {
+            for (id, status, review) in [("d1", "Effective", Some("2025-01-01")), ("d2", "Retired", Some("2025-01-01")), ("d3", "Effective", None)] {
+                conn.execute(
+                    "INSERT INTO documents (id, document_number, title, version, status, document_type, content_hash, created_by, review_date)
+                     VALUES (?1, ?1, 'Doc', '1.0', ?2, 'SOP', 'h', 'qa', ?3)",
+                    params![id, status, review],
+                )?;
+            }
+            Ok(())
+        })
+        .unwrap();
+        let subjects = document_review_subjects(&db).unwrap();
+        assert_eq!(subjects.len(), 1);
+        assert_eq!(subjects[0].due_at, Some(Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()));
+    }
+}
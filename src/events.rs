@@ -0,0 +1,353 @@
+//! # Events - Internal Domain Event Bus
+//!
+//! Modules publish a [`QmsEvent`] after a change has been committed; cross
+//! cutting features (notifications, webhooks, risk flagging, task creation)
+//! subscribe to the [`EventBus`] instead of being called directly by the
+//! module that made the change. A failing subscriber is logged and skipped:
+//! the domain write it reacts to has already happened and must not be undone
+//! by a side effect.
+//!
+//! Subscribing an [`EventLog`] also persists every event as typed JSON in the
+//! `events` table. The log can be read back in order and replayed to any
+//! subscriber, for analytics, webhook redelivery, or rebuilding summary
+//! tables after a schema change.
+
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::notification::{EmailMessage, Notifier};
+use crate::post_market::Severity;
+
+/// Something that happened in the QMS that other modules may react to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum QmsEvent {
+    CapaClosed {
+        capa_id: String,
+        closed_by: String,
+        closed_at: DateTime<Utc>,
+    },
+    DocumentEffective {
+        document_id: String,
+        document_number: String,
+        version: String,
+        effective_date: DateTime<Utc>,
+    },
+    AdverseEventReported {
+        event_id: Uuid,
+        reporter: String,
+        severity: Severity,
+        reported_on: DateTime<Utc>,
+    },
+    /// A record took a workflow transition marked for notification
+    WorkflowTransitioned {
+        workflow: String,
+        record_id: String,
+        from: String,
+        to: String,
+        by: String,
+        at: DateTime<Utc>,
+    },
+}
+
+impl QmsEvent {
+    /// Stable dotted name, e.g. `capa.closed`, for routing and logs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            QmsEvent::CapaClosed { .. } => "capa.closed",
+            QmsEvent::DocumentEffective { .. } => "document.effective",
+            QmsEvent::AdverseEventReported { .. } => "adverse_event.reported",
+            QmsEvent::WorkflowTransitioned { .. } => "workflow.transitioned",
+        }
+    }
+
+    /// One-line human readable description.
+    pub fn summary(&self) -> String {
+        match self {
+            QmsEvent::CapaClosed { capa_id, closed_by, .. } => format!("CAPA {} closed by {}", capa_id, closed_by),
+            QmsEvent::DocumentEffective { document_number, version, .. } => {
+                format!("Document {} version {} is effective", document_number, version)
+            }
+            QmsEvent::AdverseEventReported { event_id, severity, .. } => {
+                format!("{:?} adverse event {} reported", severity, event_id)
+            }
+            QmsEvent::WorkflowTransitioned { workflow, record_id, from, to, by, .. } => {
+                format!("{} {} moved from {} to {} by {}", workflow, record_id, from, to, by)
+            }
+        }
+    }
+}
+
+/// Reacts to published events.
+pub trait EventSubscriber: Send + Sync {
+    fn handle(&self, event: &QmsEvent) -> Result<()>;
+}
+
+impl<F> EventSubscriber for F
+where
+    F: Fn(&QmsEvent) -> Result<()> + Send + Sync,
+{
+    fn handle(&self, event: &QmsEvent) -> Result<()> {
+        self(event)
+    }
+}
+
+type Subscribers = Vec<(String, Arc<dyn EventSubscriber>)>;
+
+/// In-process publish/subscribe hub; clones share subscribers.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    subscribers: Arc<RwLock<Subscribers>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `subscriber` under `name` (used in failure logs).
+    pub fn subscribe<S: EventSubscriber + 'static>(&self, name: &str, subscriber: S) {
+        if let Ok(mut subscribers) = self.subscribers.write() {
+            subscribers.push((name.to_string(), Arc::new(subscriber)));
+        }
+    }
+
+    /// Deliver `event` to every subscriber in registration order. Returns
+    /// how many handled it successfully.
+    pub fn publish(&self, event: &QmsEvent) -> usize {
+        let subscribers = match self.subscribers.read() {
+            Ok(subscribers) => subscribers.clone(),
+            Err(_) => return 0,
+        };
+        let mut delivered = 0;
+        for (name, subscriber) in &subscribers {
+            match subscriber.handle(event) {
+                Ok(()) => delivered += 1,
+                Err(e) => tracing::warn!(subscriber = %name, event = event.name(), "event subscriber failed: {e}"),
+            }
+        }
+        delivered
+    }
+}
+
+/// An event as stored in the log. `sequence` orders the log and is the
+/// position replays resume from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredEvent {
+    pub sequence: i64,
+    pub id: Uuid,
+    pub name: String,
+    pub recorded_at: DateTime<Utc>,
+    pub event: QmsEvent,
+}
+
+/// Rows read per query while replaying
+const REPLAY_CHUNK: usize = 500;
+
+/// Append-only log of published events in the `events` table.
+#[derive(Clone)]
+pub struct EventLog {
+    db: Database,
+}
+
+impl EventLog {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Store `event`, returning it with its assigned sequence number.
+    pub fn append(&self, event: &QmsEvent) -> Result<StoredEvent> {
+        let id = Uuid::new_v4();
+        let recorded_at = Utc::now();
+        let payload = serde_json::to_string(event)?;
+        let sequence = self.db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO events (id, name, payload, recorded_at) VALUES (?1, ?2, ?3, ?4)",
+                params![id.to_string(), event.name(), payload, recorded_at.to_rfc3339()],
+            )?;
+            Ok(conn.last_insert_rowid())
+        })?;
+        Ok(StoredEvent { sequence, id, name: event.name().to_string(), recorded_at, event: event.clone() })
+    }
+
+    /// Up to `limit` events after sequence `after`, oldest first, optionally
+    /// only those named `name` (e.g. `capa.closed`).
+    pub fn after(&self, after: i64, name: Option<&str>, limit: usize) -> Result<Vec<StoredEvent>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT sequence, id, name, payload, recorded_at FROM events
+                 WHERE sequence > ?1 AND (?2 IS NULL OR name = ?2)
+                 ORDER BY sequence LIMIT ?3",
+            )?;
+            let rows = stmt.query_map(params![after, name, limit as i64], row_to_stored_event)?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        })
+    }
+
+    /// Deliver every event after sequence `after` to `subscriber` in order.
+    /// Stops at the first failure; the error names the sequence to resume
+    /// after. Returns how many events were delivered.
+    pub fn replay(&self, after: i64, subscriber: &dyn EventSubscriber) -> Result<usize> {
+        let mut position = after;
+        let mut delivered = 0;
+        loop {
+            let chunk = self.after(position, None, REPLAY_CHUNK)?;
+            for stored in &chunk {
+                subscriber.handle(&stored.event).map_err(|e| QmsError::Application {
+                    message: format!(
+                        "replay stopped at event {}; resume after sequence {}: {}",
+                        stored.sequence, position, e
+                    ),
+                })?;
+                position = stored.sequence;
+                delivered += 1;
+            }
+            if chunk.len() < REPLAY_CHUNK {
+                return Ok(delivered);
+            }
+        }
+    }
+}
+
+impl EventSubscriber for EventLog {
+    fn handle(&self, event: &QmsEvent) -> Result<()> {
+        self.append(event).map(|_| ())
+    }
+}
+
+fn row_to_stored_event(row: &Row<'_>) -> rusqlite::Result<StoredEvent> {
+    let conversion = |column: usize, e: Box<dyn std::error::Error + Send + Sync>| {
+        rusqlite::Error::FromSqlConversionFailure(column, rusqlite::types::Type::Text, e)
+    };
+    let id: String = row.get(1)?;
+    let payload: String = row.get(3)?;
+    let recorded_at: String = row.get(4)?;
+    Ok(StoredEvent {
+        sequence: row.get(0)?,
+        id: Uuid::parse_str(&id).map_err(|e| conversion(1, Box::new(e)))?,
+        name: row.get(2)?,
+        recorded_at: DateTime::parse_from_rfc3339(&recorded_at)
+            .map_err(|e| conversion(4, Box::new(e)))?
+            .with_timezone(&Utc),
+        event: serde_json::from_str(&payload).map_err(|e| conversion(3, Box::new(e)))?,
+    })
+}
+
+/// Emails each event's summary to a fixed recipient list.
+pub struct NotificationSubscriber {
+    notifier: Arc<dyn Notifier>,
+    recipients: Vec<String>,
+}
+
+impl NotificationSubscriber {
+    pub fn new(notifier: Arc<dyn Notifier>, recipients: Vec<String>) -> Self {
+        Self { notifier, recipients }
+    }
+}
+
+impl EventSubscriber for NotificationSubscriber {
+    fn handle(&self, event: &QmsEvent) -> Result<()> {
+        let body = format!("{}\n\n{}", event.summary(), serde_json::to_string_pretty(event)?);
+        let message = EmailMessage::new(self.recipients.clone(), format!("[QMS] {}", event.summary()), body);
+        self.notifier.send(&message).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::QmsError;
+    use std::sync::Mutex;
+
+    fn capa_closed() -> QmsEvent {
+        QmsEvent::CapaClosed { capa_id: "capa-1".to_string(), closed_by: "qa".to_string(), closed_at: Utc::now() }
+    }
+
+    #[test]
+    fn test_failing_subscriber_does_not_stop_delivery() {
+        let bus = EventBus::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        bus.subscribe("webhook", |_: &QmsEvent| -> Result<()> {
+            Err(QmsError::Network { message: "unreachable".to_string() })
+        });
+        let sink = seen.clone();
+        bus.subscribe("recorder", move |event: &QmsEvent| -> Result<()> {
+            sink.lock().unwrap().push(event.name());
+            Ok(())
+        });
+
+        assert_eq!(bus.clone().publish(&capa_closed()), 1);
+        assert_eq!(*seen.lock().unwrap(), vec!["capa.closed"]);
+    }
+
+    #[test]
+    fn test_notification_subscriber_emails_summary() {
+        struct Capture(Mutex<Vec<EmailMessage>>);
+        impl Notifier for Capture {
+            fn send(&self, message: &EmailMessage) -> Result<String> {
+                self.0.lock().unwrap().push(message.clone());
+                Ok("id".to_string())
+            }
+        }
+        let capture = Arc::new(Capture(Mutex::new(Vec::new())));
+        let bus = EventBus::new();
+        bus.subscribe("email", NotificationSubscriber::new(capture.clone(), vec!["qa@example.com".to_string()]));
+        bus.publish(&capa_closed());
+
+        let sent = capture.0.lock().unwrap();
+        assert_eq!(sent[0].subject, "[QMS] CAPA capa-1 closed by qa");
+        assert!(sent[0].body.contains("\"type\": \"capa_closed\""));
+    }
+
+    #[test]
+    fn test_event_log_persists_and_replays_in_order() {
+        let db = Database::new(crate::config::DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let log = EventLog::new(db.clone());
+        let bus = EventBus::new();
+        bus.subscribe("event_log", log.clone());
+        let effective = QmsEvent::DocumentEffective {
+            document_id: "doc-1".to_string(),
+            document_number: "SOP-001".to_string(),
+            version: "2.0".to_string(),
+            effective_date: Utc::now(),
+        };
+        bus.publish(&capa_closed());
+        bus.publish(&effective);
+        bus.publish(&capa_closed());
+
+        let all = EventLog::new(db).after(0, None, 10).unwrap();
+        let names: Vec<&str> = all.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["capa.closed", "document.effective", "capa.closed"]);
+        assert_eq!(all[1].event, effective);
+        assert_eq!(log.after(all[0].sequence, Some("capa.closed"), 10).unwrap(), vec![all[2].clone()]);
+
+        // A failing redelivery reports where to resume
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let flaky = move |event: &QmsEvent| -> Result<()> {
+            if matches!(event, QmsEvent::DocumentEffective { .. }) && sink.lock().unwrap().len() == 1 {
+                return Err(QmsError::Network { message: "timeout".to_string() });
+            }
+            sink.lock().unwrap().push(event.name());
+            Ok(())
+        };
+        let error = log.replay(0, &flaky).unwrap_err().to_string();
+        assert!(error.contains(&format!("resume after sequence {}", all[0].sequence)), "{error}");
+        seen.lock().unwrap().push("retry");
+        assert_eq!(log.replay(all[0].sequence, &flaky).unwrap(), 2);
+        assert_eq!(*seen.lock().unwrap(), vec!["capa.closed", "retry", "document.effective", "capa.closed"]);
+    }
+}
@@ -0,0 +1,251 @@
+//! # Evidence Pack - Validation Evidence for QMSrs Itself
+//!
+//! Users of QMSrs must validate it as software used in their quality system
+//! (21 CFR 820.70(i), ISO 13485 §4.1.6; FDA CSA guidance). `qmsrs
+//! evidence-pack <dir>` collects the objective evidence for that: installed
+//! version, configuration snapshot, schema version and the results of
+//! self-tests executed against the live installation. The files are listed in
+//! a SHA-256 manifest and summarized in a PDF signed with the system key and
+//! registered like any other signed report, so `verify-report` detects later
+//! changes.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::keystore::{sha256_hex, verify_signature, Keystore};
+use crate::migrations::MIGRATIONS;
+use crate::report::{ReportBuilder, ReportSection, ReportTable};
+use crate::report_signature::{render_signed_report, SignedReport};
+
+/// File listing `<sha256>  <file>` for every file in the pack.
+pub const MANIFEST_FILE: &str = "MANIFEST.sha256";
+/// Signed PDF summary of the pack.
+pub const SUMMARY_FILE: &str = "validation_evidence.pdf";
+
+/// Outcome of one installation self-test.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SelfTest {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl SelfTest {
+    fn new(name: &str, outcome: Result<String>) -> Self {
+        let (passed, detail) = match outcome {
+            Ok(detail) => (true, detail),
+            Err(e) => (false, e.to_string()),
+        };
+        Self { name: name.to_string(), passed, detail }
+    }
+}
+
+/// Installation facts recorded in `installation.json`.
+#[derive(Debug, Clone, Serialize)]
+pub struct InstallationInfo {
+    pub application: String,
+    pub version: String,
+    pub schema_version: u32,
+    pub expected_schema_version: u32,
+    pub database_url: String,
+    pub generated_at: DateTime<Utc>,
+    pub generated_by: String,
+}
+
+/// The written pack.
+#[derive(Debug, Clone)]
+pub struct EvidencePack {
+    pub directory: PathBuf,
+    pub installation: InstallationInfo,
+    pub self_tests: Vec<SelfTest>,
+    /// `(file name, sha256)` of every file, manifest order
+    pub manifest: Vec<(String, String)>,
+    pub summary: SignedReport,
+}
+
+impl EvidencePack {
+    pub fn all_passed(&self) -> bool {
+        self.self_tests.iter().all(|t| t.passed)
+    }
+}
+
+/// Run the installation self-tests against `db` and `keystore`.
+pub fn run_self_tests(db: &Database, keystore: &Keystore) -> Vec<SelfTest> {
+    let expected = MIGRATIONS.last().map(|m| m.version).unwrap_or_default();
+    vec![
+        SelfTest::new(
+            "database_connectivity",
+            db.with_connection(|conn| Ok(conn.query_row("SELECT sqlite_version()", [], |row| row.get::<_, String>(0))?))
+                .map(|version| format!("SQLite {}", version)),
+        ),
+        SelfTest::new(
+            "schema_version",
+            db.schema_version().and_then(|version| {
+                if version == expected {
+                    Ok(format!("version {} (current)", version))
+                } else {
+                    Err(QmsError::Database { message: format!("version {}, expected {}", version, expected) })
+                }
+            }),
+        ),
+        SelfTest::new(
+            "audit_trail_integrity",
+            db.verify_audit_integrity().and_then(|report| {
+                if report.integrity_verified {
+                    Ok(format!("{} entries verified", report.total_entries))
+                } else {
+                    Err(QmsError::AuditTrail { message: report.details })
+                }
+            }),
+        ),
+        SelfTest::new("sha256_known_answer", {
+            // FIPS 180-2 test vector for "abc"
+            let expected = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+            if sha256_hex(b"abc") == expected {
+                Ok("test vector matched".to_string())
+            } else {
+                Err(QmsError::Encryption { message: "SHA-256 test vector mismatch".to_string() })
+            }
+        }),
+        SelfTest::new("signature_round_trip", {
+            let message = b"qmsrs-self-test";
+            if verify_signature(keystore.public_key(), message, &keystore.sign(message)) {
+                Ok(format!("key {}", keystore.key_id()))
+            } else {
+                Err(QmsError::Encryption { message: "system key signature did not verify".to_string() })
+            }
+        }),
+    ]
+}
+
+/// Write the evidence pack for this installation into `directory`.
+pub fn generate_evidence_pack(
+    config: &Config,
+    db: &Database,
+    keystore: &Keystore,
+    directory: &Path,
+    generated_by: &str,
+) -> Result<EvidencePack> {
+    let io_error = |path: &Path, e: std::io::Error| QmsError::FileSystem {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    };
+    fs::create_dir_all(directory).map_err(|e| io_error(directory, e))?;
+    let write = |name: &str, contents: &[u8]| -> Result<(String, String)> {
+        let path = directory.join(name);
+        fs::write(&path, contents).map_err(|e| io_error(&path, e))?;
+        Ok((name.to_string(), sha256_hex(contents)))
+    };
+
+    let installation = InstallationInfo {
+        application: config.application.name.clone(),
+        version: crate::APPLICATION_VERSION.to_string(),
+        schema_version: db.schema_version()?,
+        expected_schema_version: MIGRATIONS.last().map(|m| m.version).unwrap_or_default(),
+        database_url: config.database.url.clone(),
+        generated_at: Utc::now(),
+        generated_by: generated_by.to_string(),
+    };
+    let self_tests = run_self_tests(db, keystore);
+    let configuration = toml::to_string_pretty(config)
+        .map_err(|e| QmsError::Serialization { message: e.to_string() })?;
+
+    let mut manifest = vec![
+        write("installation.json", &serde_json::to_vec_pretty(&installation)?)?,
+        write("configuration.toml", configuration.as_bytes())?,
+        write("self_tests.json", &serde_json::to_vec_pretty(&self_tests)?)?,
+    ];
+
+    let mut tests_table = ReportTable::new(vec!["Self-test", "Result", "Detail"]).with_column_weights(vec![2.0, 1.0, 4.0]);
+    for test in &self_tests {
+        tests_table = tests_table.with_row(vec![
+            test.name.clone(),
+            if test.passed { "PASS" } else { "FAIL" }.to_string(),
+            test.detail.clone(),
+        ]);
+    }
+    let mut files_table = ReportTable::new(vec!["File", "SHA-256"]).with_column_weights(vec![1.0, 3.0]);
+    for (file, sha256) in &manifest {
+        files_table = files_table.with_row(vec![file.clone(), sha256.clone()]);
+    }
+    let report = ReportBuilder::new("QMSrs Software Validation Evidence")
+        .with_subtitle(format!("Version {}", installation.version))
+        .with_organization(config.application.organization_name.clone())
+        .with_prepared_by(generated_by)
+        .with_generated_on(installation.generated_at)
+        .with_locale(config.application.locale)
+        .with_display_timezone(config.application.display_timezone)
+        .with_section(ReportSection::new("Installation").key_values(vec![
+            ("Application", installation.application.clone()),
+            ("Version", installation.version.clone()),
+            ("Schema version", format!("{} (expected {})", installation.schema_version, installation.expected_schema_version)),
+            ("Database", installation.database_url.clone()),
+        ]))
+        .with_section(ReportSection::new("Self-tests").table(tests_table))
+        .with_section(
+            ReportSection::new("Evidence files")
+                .paragraph(format!("Full configuration snapshot in configuration.toml; checksums also in {}.", MANIFEST_FILE))
+                .table(files_table),
+        )
+        .build();
+    let summary_path = directory.join(SUMMARY_FILE);
+    let summary = render_signed_report(&report, &summary_path, keystore, db, generated_by)?;
+    manifest.push((SUMMARY_FILE.to_string(), summary.record.file_sha256.clone()));
+
+    let listing: String = manifest.iter().map(|(file, sha256)| format!("{}  {}\n", sha256, file)).collect();
+    let manifest_path = directory.join(MANIFEST_FILE);
+    fs::write(&manifest_path, listing).map_err(|e| io_error(&manifest_path, e))?;
+
+    tracing::info!(
+        component = "evidence_pack",
+        directory = %directory.display(),
+        passed = self_tests.iter().all(|t| t.passed),
+        user_id = %generated_by,
+        "Validation evidence pack generated"
+    );
+    Ok(EvidencePack { directory: directory.to_path_buf(), installation, self_tests, manifest, summary })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report_signature::verify_report_file;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_evidence_pack_contents_and_signature() {
+        let dir = tempdir().unwrap();
+        let config = Config {
+            database: crate::config::DatabaseConfig {
+                url: ":memory:".to_string(),
+                max_connections: 10,
+                wal_mode: false,
+                backup_interval_hours: 24,
+                backup_retention_days: 90,
+            },
+            ..Config::default()
+        };
+        let db = Database::new(config.database.clone()).unwrap();
+        let keystore = Keystore::open_or_create(&Keystore::keys_dir(dir.path())).unwrap();
+        let out = dir.path().join("pack");
+
+        let pack = generate_evidence_pack(&config, &db, &keystore, &out, "validator").unwrap();
+        assert!(pack.all_passed(), "{:?}", pack.self_tests);
+        assert_eq!(pack.self_tests.len(), 5);
+        assert_eq!(pack.installation.schema_version, pack.installation.expected_schema_version);
+
+        let manifest = fs::read_to_string(out.join(MANIFEST_FILE)).unwrap();
+        assert_eq!(manifest.lines().count(), 4);
+        for (file, sha256) in &pack.manifest {
+            assert_eq!(&sha256_hex(&fs::read(out.join(file)).unwrap()), sha256);
+            assert!(manifest.contains(&format!("{}  {}", sha256, file)));
+        }
+        assert!(verify_report_file(&out.join(SUMMARY_FILE), &db, Some(&keystore)).unwrap().is_valid());
+    }
+}
@@ -0,0 +1,376 @@
+//! # Feature Flags - Phased Module Rollout
+//!
+//! Modules still being validated for a deployment can be switched off. The
+//! `features.disabled` configuration list sets the deployment's default and
+//! an override in the `feature_flags` table, set by an administrator with a
+//! reason for change, takes precedence. A disabled module is hidden from the
+//! TUI tab bar and its API routes answer 404 as if they did not exist; the
+//! GraphQL resolvers, gRPC calls, search, record links, the event log and
+//! the inbox leave out its records the same way. The
+//! API server and the notification engine are started from the configuration,
+//! so [`FeatureFlags::apply_to`] folds their flags into it at startup; an
+//! override of those two takes effect on the next start.
+//!
+//! The dashboard and the audit trail are not flags: Part 11 requires the
+//! audit trail to stay available whatever else is switched off.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, PoisonError, RwLock};
+
+use chrono::Utc;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::audit::AuditManager;
+use crate::authorization::{Permission, UserRole};
+use crate::change_history::ChangeReason;
+use crate::config::Config;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::events::QmsEvent;
+use crate::unit_of_work::UnitOfWork;
+
+/// A module that can be switched off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    Documents,
+    Capa,
+    Suppliers,
+    Training,
+    Reports,
+    Search,
+    Jobs,
+    Risk,
+    /// Complaints, adverse events and vigilance reporting
+    PostMarket,
+    /// Email notifications through the outbox
+    Notifications,
+    /// The embedded REST and gRPC API server
+    Api,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 11] = [
+        Feature::Documents,
+        Feature::Capa,
+        Feature::Suppliers,
+        Feature::Training,
+        Feature::Reports,
+        Feature::Search,
+        Feature::Jobs,
+        Feature::Risk,
+        Feature::PostMarket,
+        Feature::Notifications,
+        Feature::Api,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Feature::Documents => "documents",
+            Feature::Capa => "capa",
+            Feature::Suppliers => "suppliers",
+            Feature::Training => "training",
+            Feature::Reports => "reports",
+            Feature::Search => "search",
+            Feature::Jobs => "jobs",
+            Feature::Risk => "risk",
+            Feature::PostMarket => "post_market",
+            Feature::Notifications => "notifications",
+            Feature::Api => "api",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self> {
+        Feature::ALL.into_iter().find(|feature| feature.as_str() == value).ok_or_else(|| QmsError::Validation {
+            field: "feature".to_string(),
+            message: format!(
+                "Unknown feature '{}'; expected one of {}",
+                value,
+                Feature::ALL.map(|feature| feature.as_str()).join(", ")
+            ),
+        })
+    }
+
+    /// Module an API path belongs to; `None` for routes that are always
+    /// served. Routes under `/records/:record_type` belong to the module of
+    /// the record type.
+    pub fn for_api_path(path: &str) -> Option<Self> {
+        if let Some(rest) = path.strip_prefix("/records/") {
+            return Self::for_record_type(rest.split('/').next().unwrap_or_default());
+        }
+        const PREFIXES: &[(&str, Feature)] = &[
+            ("/documents", Feature::Documents),
+            ("/sites", Feature::Documents),
+            ("/controlled_prints", Feature::Documents),
+            ("/capas", Feature::Capa),
+            ("/suppliers", Feature::Suppliers),
+            ("/supplier_metrics", Feature::Suppliers),
+            ("/training_metrics", Feature::Training),
+            ("/search", Feature::Search),
+            ("/jobs", Feature::Jobs),
+            ("/risk_heat_map", Feature::Risk),
+            ("/analytics/complaint_rates", Feature::PostMarket),
+        ];
+        PREFIXES
+            .iter()
+            .find(|(prefix, _)| {
+                path.strip_prefix(prefix).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+            .map(|(_, feature)| *feature)
+    }
+
+    /// Module owning records of `record_type`, as named in record links,
+    /// version history and search results; `None` for records of no
+    /// switchable module.
+    pub fn for_record_type(record_type: &str) -> Option<Self> {
+        const RECORD_TYPES: &[(&str, Feature)] = &[
+            ("document", Feature::Documents),
+            ("controlled_print", Feature::Documents),
+            ("capa", Feature::Capa),
+            ("capa_action", Feature::Capa),
+            ("supplier", Feature::Suppliers),
+            ("incoming_inspection", Feature::Suppliers),
+            ("training", Feature::Training),
+            ("training_record", Feature::Training),
+            ("risk", Feature::Risk),
+            ("risk_assessment", Feature::Risk),
+            ("complaint", Feature::PostMarket),
+            ("adverse_event", Feature::PostMarket),
+        ];
+        RECORD_TYPES.iter().find(|(name, _)| *name == record_type).map(|(_, feature)| *feature)
+    }
+
+    /// Module an event is about: the workflow's record type for
+    /// `workflow.transitioned`, otherwise the record type its name starts
+    /// with, e.g. `capa` in `capa.closed`.
+    pub fn for_event(event: &QmsEvent) -> Option<Self> {
+        match event {
+            QmsEvent::WorkflowTransitioned { workflow, .. } => Self::for_record_type(workflow),
+            _ => Self::for_record_type(event.name().split('.').next().unwrap_or_default()),
+        }
+    }
+}
+
+/// Where a feature's current state comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagSource {
+    Default,
+    Configuration,
+    Override,
+}
+
+/// A feature's current state, for listings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FeatureStatus {
+    pub feature: Feature,
+    pub enabled: bool,
+    pub source: FlagSource,
+}
+
+/// Enabled modules, from configuration and database overrides. Clones share
+/// the same overrides.
+#[derive(Clone)]
+pub struct FeatureFlags {
+    db: Database,
+    configured_off: Arc<HashSet<Feature>>,
+    overrides: Arc<RwLock<HashMap<Feature, bool>>>,
+}
+
+impl FeatureFlags {
+    /// Flags with `disabled` switched off by configuration and the overrides
+    /// stored in `db`.
+    pub fn load(db: Database, disabled: &[Feature]) -> Result<Self> {
+        let flags = Self {
+            db,
+            configured_off: Arc::new(disabled.iter().copied().collect()),
+            overrides: Arc::new(RwLock::new(HashMap::new())),
+        };
+        flags.reload()?;
+        Ok(flags)
+    }
+
+    /// Read the overrides again, e.g. after another process changed them.
+    pub fn reload(&self) -> Result<()> {
+        let stored: Vec<(String, bool)> = self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare("SELECT feature, enabled FROM feature_flags")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        })?;
+        let mut overrides = HashMap::new();
+        for (feature, enabled) in stored {
+            match Feature::parse(&feature) {
+                Ok(feature) => {
+                    overrides.insert(feature, enabled);
+                }
+                // Written by a newer version; ignored rather than failing startup
+                Err(_) => tracing::warn!(%feature, "ignoring override of unknown feature"),
+            }
+        }
+        *self.overrides.write().unwrap_or_else(PoisonError::into_inner) = overrides;
+        Ok(())
+    }
+
+    pub fn is_enabled(&self, feature: Feature) -> bool {
+        self.status(feature).enabled
+    }
+
+    pub fn status(&self, feature: Feature) -> FeatureStatus {
+        let overridden = self.overrides.read().unwrap_or_else(PoisonError::into_inner).get(&feature).copied();
+        let (enabled, source) = match overridden {
+            Some(enabled) => (enabled, FlagSource::Override),
+            None if self.configured_off.contains(&feature) => (false, FlagSource::Configuration),
+            None => (true, FlagSource::Default),
+        };
+        FeatureStatus { feature, enabled, source }
+    }
+
+    /// Whether records of `record_type` may be shown: their module, if any,
+    /// is enabled.
+    pub fn allows_record_type(&self, record_type: &str) -> bool {
+        Feature::for_record_type(record_type).map_or(true, |feature| self.is_enabled(feature))
+    }
+
+    pub fn allows_event(&self, event: &QmsEvent) -> bool {
+        Feature::for_event(event).map_or(true, |feature| self.is_enabled(feature))
+    }
+
+    /// Every feature's state, in [`Feature::ALL`] order.
+    pub fn statuses(&self) -> Vec<FeatureStatus> {
+        Feature::ALL.into_iter().map(|feature| self.status(feature)).collect()
+    }
+
+    /// Enable or disable `feature` for this deployment regardless of the
+    /// configuration. Requires [`Permission::ManageFeatures`].
+    pub fn set_override(&self, feature: Feature, enabled: bool, changed_by: &str, reason: &ChangeReason) -> Result<()> {
+        self.authorize(changed_by)?;
+        let before = self.status(feature);
+        let details = format!(
+            "{} {} (was {} by {:?}): {}",
+            feature.as_str(),
+            enabled_label(enabled),
+            enabled_label(before.enabled),
+            before.source,
+            reason.as_str()
+        );
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "INSERT INTO feature_flags (feature, enabled, changed_by, changed_at, reason)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT(feature) DO UPDATE SET
+                     enabled = excluded.enabled, changed_by = excluded.changed_by,
+                     changed_at = excluded.changed_at, reason = excluded.reason",
+                params![feature.as_str(), enabled, changed_by, Utc::now().to_rfc3339(), reason.as_str()],
+            )?;
+            self.log_change(uow, feature, changed_by, details)
+        })?;
+        self.overrides.write().unwrap_or_else(PoisonError::into_inner).insert(feature, enabled);
+        Ok(())
+    }
+
+    /// Drop the override of `feature`, returning it to the configured state.
+    pub fn clear_override(&self, feature: Feature, changed_by: &str, reason: &ChangeReason) -> Result<()> {
+        self.authorize(changed_by)?;
+        let details = format!("{} override removed: {}", feature.as_str(), reason.as_str());
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute("DELETE FROM feature_flags WHERE feature = ?1", params![feature.as_str()])?;
+            self.log_change(uow, feature, changed_by, details)
+        })?;
+        self.overrides.write().unwrap_or_else(PoisonError::into_inner).remove(&feature);
+        Ok(())
+    }
+
+    /// Switch off the parts of `config` started from it: the API server and
+    /// the notification engine.
+    pub fn apply_to(&self, config: &mut Config) {
+        config.api.enabled &= self.is_enabled(Feature::Api);
+        config.api.grpc.enabled &= self.is_enabled(Feature::Api);
+        config.notifications.enabled &= self.is_enabled(Feature::Notifications);
+    }
+
+    fn authorize(&self, user_id: &str) -> Result<()> {
+        match UserRole::of_user(&self.db, user_id)? {
+            Some(role) if role.has_permission(Permission::ManageFeatures) => Ok(()),
+            _ => Err(QmsError::Security {
+                message: format!("User {} is not permitted to change feature flags", user_id),
+            }),
+        }
+    }
+
+    fn log_change(&self, uow: &UnitOfWork, feature: Feature, changed_by: &str, details: String) -> Result<()> {
+        AuditManager::new(self.db.clone()).log_action_in(
+            uow,
+            changed_by,
+            "feature_flag_changed",
+            &format!("feature:{}", feature.as_str()),
+            "Success",
+            Some(details),
+        )
+    }
+}
+
+fn enabled_label(enabled: bool) -> &'static str {
+    if enabled {
+        "enabled"
+    } else {
+        "disabled"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_overrides_take_precedence_over_configuration() {
+        let db = test_db();
+        db.seed_test_users(&["admin", "eng"]);
+        db.with_connection(|conn| {
+            conn.execute("UPDATE users SET role = 'Administrator' WHERE id = 'admin'", [])?;
+            Ok(())
+        })
+        .unwrap();
+        let flags = FeatureFlags::load(db.clone(), &[Feature::PostMarket, Feature::Api]).unwrap();
+        assert!(flags.is_enabled(Feature::Capa));
+        assert_eq!(flags.status(Feature::PostMarket).source, FlagSource::Configuration);
+
+        let reason = ChangeReason::new("Vigilance module validated (VAL-0042)").unwrap();
+        assert!(flags.set_override(Feature::PostMarket, true, "eng", &reason).is_err(), "engineers cannot");
+        flags.set_override(Feature::PostMarket, true, "admin", &reason).unwrap();
+        flags.set_override(Feature::Search, false, "admin", &reason).unwrap();
+        assert!(flags.is_enabled(Feature::PostMarket));
+
+        let restarted = FeatureFlags::load(db.clone(), &[Feature::PostMarket, Feature::Api]).unwrap();
+        assert!(restarted.is_enabled(Feature::PostMarket) && !restarted.is_enabled(Feature::Search));
+        let mut config = Config::default();
+        restarted.apply_to(&mut config);
+        assert!(!config.api.enabled);
+
+        restarted.clear_override(Feature::PostMarket, "admin", &reason).unwrap();
+        assert!(!restarted.is_enabled(Feature::PostMarket));
+        let audit = db.get_audit_entries_for_resource("feature:post_market").unwrap();
+        assert_eq!(audit.len(), 2);
+        assert_eq!(audit[0].action, "feature_flag_changed");
+
+        assert_eq!(Feature::for_api_path("/capas/CAPA-2025-001"), Some(Feature::Capa));
+        assert_eq!(Feature::for_api_path("/search"), Some(Feature::Search));
+        assert_eq!(Feature::for_api_path("/searchable"), None);
+        assert_eq!(Feature::for_api_path("/audit_trail/export"), None);
+        assert_eq!(Feature::for_api_path("/records/capa/c-1/history"), Some(Feature::Capa));
+        assert_eq!(Feature::for_api_path("/records/lot/LOT-42/links"), None);
+        assert!(!restarted.allows_record_type("complaint") && restarted.allows_record_type("capa"));
+    }
+}
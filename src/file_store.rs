@@ -0,0 +1,176 @@
+//! # File Store - Controlled Record Storage (EDMS)
+//!
+//! Write-once storage for generated records (scheduled reports, exports) under
+//! `<data_directory>/edms/<category>/<YYYY>/<MM>/<file>`. Files are never
+//! overwritten and are made read-only once written; the returned SHA-256 lets
+//! callers record the exact stored content (FDA 21 CFR Part 11 §11.10(c)).
+
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{QmsError, Result};
+use crate::keystore::sha256_hex;
+
+/// Record of a file written to the store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredFile {
+    /// Absolute (or root-relative) location on disk
+    pub path: PathBuf,
+    /// Location relative to the store root, `/`-separated
+    pub relative_path: String,
+    /// Hex SHA-256 of the stored content
+    pub sha256: String,
+    pub size_bytes: u64,
+}
+
+/// Write-once file store rooted at a directory.
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    root: PathBuf,
+}
+
+impl FileStore {
+    /// Create a store rooted at `root` (created lazily on first write).
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Store located under the application data directory.
+    pub fn for_data_dir(data_directory: &Path) -> Self {
+        Self::new(data_directory.join("edms"))
+    }
+
+    /// Root directory of the store.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Scratch directory for files that are generated before being stored.
+    pub fn staging_dir(&self) -> Result<PathBuf> {
+        let dir = self.root.join(".staging");
+        create_dir(&dir)?;
+        Ok(dir)
+    }
+
+    /// Store `contents` as `file_name` in `category`, filed by `stored_on` month.
+    pub fn store(&self, category: &str, file_name: &str, contents: &[u8], stored_on: DateTime<Utc>) -> Result<StoredFile> {
+        validate_component("category", category)?;
+        validate_component("file_name", file_name)?;
+
+        let relative = format!("{}/{}/{}", category, stored_on.format("%Y/%m"), file_name);
+        let dir = self.root.join(category).join(stored_on.format("%Y").to_string()).join(stored_on.format("%m").to_string());
+        create_dir(&dir)?;
+
+        let path = dir.join(file_name);
+        if path.exists() {
+            return Err(QmsError::DocumentControl {
+                message: format!("Stored record {} already exists and cannot be overwritten", relative),
+            });
+        }
+
+        let tmp_path = dir.join(format!(".{}.tmp", file_name));
+        fs::write(&tmp_path, contents).map_err(|e| fs_error(&tmp_path, e))?;
+        fs::rename(&tmp_path, &path).map_err(|e| fs_error(&path, e))?;
+        let mut permissions = fs::metadata(&path).map_err(|e| fs_error(&path, e))?.permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&path, permissions).map_err(|e| fs_error(&path, e))?;
+
+        Ok(StoredFile {
+            path,
+            relative_path: relative,
+            sha256: sha256_hex(contents),
+            size_bytes: contents.len() as u64,
+        })
+    }
+
+    /// Copy an existing file into the store under its own file name.
+    pub fn store_file(&self, category: &str, source: &Path, stored_on: DateTime<Utc>) -> Result<StoredFile> {
+        let file_name = source
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| QmsError::FileSystem {
+                path: source.display().to_string(),
+                message: "Source has no valid file name".to_string(),
+            })?;
+        let contents = fs::read(source).map_err(|e| fs_error(source, e))?;
+        self.store(category, file_name, &contents, stored_on)
+    }
+}
+
+/// File name usable in the file store: no path separators or leading dots.
+pub fn safe_file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .take(100)
+        .collect();
+    let name = name.trim_start_matches('.');
+    if name.is_empty() {
+        "attachment".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// Reject empty names and anything that could escape the store root.
+fn validate_component(field: &str, value: &str) -> Result<()> {
+    if value.is_empty() || value == "." || value == ".." || value.contains(['/', '\\']) || value.starts_with('.') {
+        return Err(QmsError::Validation {
+            field: field.to_string(),
+            message: format!("'{}' is not a valid file store name", value),
+        });
+    }
+    Ok(())
+}
+
+fn create_dir(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).map_err(|e| fs_error(dir, e))
+}
+
+fn fs_error(path: &Path, e: std::io::Error) -> QmsError {
+    QmsError::FileSystem {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_store_files_by_category_and_month() {
+        let dir = tempdir().unwrap();
+        let store = FileStore::for_data_dir(dir.path());
+        let stored_on = Utc.with_ymd_and_hms(2025, 3, 14, 9, 0, 0).unwrap();
+
+        let stored = store.store("scheduled-reports", "capa-aging.pdf", b"%PDF-1.4", stored_on).unwrap();
+        assert_eq!(stored.relative_path, "scheduled-reports/2025/03/capa-aging.pdf");
+        assert_eq!(fs::read(&stored.path).unwrap(), b"%PDF-1.4");
+        assert_eq!(stored.sha256, sha256_hex(b"%PDF-1.4"));
+        assert!(fs::metadata(&stored.path).unwrap().permissions().readonly());
+    }
+
+    #[test]
+    fn test_store_never_overwrites() {
+        let dir = tempdir().unwrap();
+        let store = FileStore::new(dir.path());
+        let now = Utc::now();
+
+        store.store("exports", "a.txt", b"first", now).unwrap();
+        let result = store.store("exports", "a.txt", b"second", now);
+        assert!(matches!(result, Err(QmsError::DocumentControl { .. })));
+    }
+
+    #[test]
+    fn test_store_rejects_path_traversal() {
+        let dir = tempdir().unwrap();
+        let store = FileStore::new(dir.path());
+        for (category, name) in [("..", "a.txt"), ("exports", "../a.txt"), ("a/b", "a.txt"), ("exports", "")] {
+            assert!(matches!(store.store(category, name, b"x", Utc::now()), Err(QmsError::Validation { .. })));
+        }
+    }
+}
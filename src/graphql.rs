@@ -0,0 +1,547 @@
+//! # GraphQL - Read Models for Dashboards
+//!
+//! Dashboard builders need different slices of the same records: one panel
+//! shows CAPA numbers and due dates, another the open actions of each CAPA,
+//! a third the supplier scorecards. `POST /graphql` serves a read-only
+//! schema over CAPAs with their actions, suppliers with scorecards and
+//! documents with their versions, so a dashboard fetches exactly the fields
+//! it needs in one round trip. Nested fields are resolved only when
+//! selected. The endpoint sits behind the REST API's token authentication
+//! and network policy, and reads the same records as `/capas` and `/metrics`.
+//! Queries of a module switched off by a feature flag fail as not found.
+
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Error, ErrorExtensions, Object, Schema, SimpleObject};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+
+use crate::api::ApiState;
+use crate::capa::{CapaAction, CapaRecord, CapaStatus};
+use crate::error::QmsError;
+use crate::feature_flags::Feature;
+use crate::i18n::error_message;
+use crate::links::{LinkRepo, RecordRef};
+use crate::supplier::{Supplier, SupplierStatus};
+
+/// Deepest selection accepted; the schema nests at most four levels.
+pub const MAX_QUERY_DEPTH: usize = 8;
+
+pub type QmsSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// The schema served at `POST /graphql`.
+pub fn schema() -> QmsSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription).limit_depth(MAX_QUERY_DEPTH).finish()
+}
+
+/// Run `request` against the records of `state`.
+pub async fn execute(schema: &QmsSchema, state: ApiState, request: async_graphql::Request) -> async_graphql::Response {
+    schema.execute(request.data(state)).await
+}
+
+/// A domain error as a GraphQL error with the localized message and the
+/// [`QmsError::error_code`] under `extensions.code`.
+fn graphql_error(state: &ApiState, e: QmsError) -> Error {
+    let code = e.error_code();
+    Error::new(error_message(state.locale, &e)).extend_with(|_, extensions| extensions.set("code", code))
+}
+
+/// Refuse a query of `feature` when the module is disabled.
+fn require_feature(state: &ApiState, feature: Feature) -> async_graphql::Result<()> {
+    if state.features.is_enabled(feature) {
+        return Ok(());
+    }
+    Err(graphql_error(state, QmsError::NotFound { resource: "module".to_string(), id: feature.as_str().to_string() }))
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// CAPAs, optionally only those in `status` (e.g. "Closed").
+    async fn capas(&self, ctx: &Context<'_>, status: Option<String>) -> async_graphql::Result<Vec<CapaNode>> {
+        let state = ctx.data::<ApiState>()?;
+        require_feature(state, Feature::Capa)?;
+        let capas = state.capa_records.read().unwrap();
+        Ok(capas
+            .iter()
+            .filter(|capa| status.as_deref().map_or(true, |status| capa.status.as_str() == status))
+            .cloned()
+            .map(CapaNode)
+            .collect())
+    }
+
+    /// A CAPA by UUID or CAPA number.
+    async fn capa(&self, ctx: &Context<'_>, reference: String) -> async_graphql::Result<Option<CapaNode>> {
+        let state = ctx.data::<ApiState>()?;
+        require_feature(state, Feature::Capa)?;
+        let capas = state.capa_records.read().unwrap();
+        Ok(capas
+            .iter()
+            .find(|capa| capa.id == reference || capa.capa_number.eq_ignore_ascii_case(&reference))
+            .cloned()
+            .map(CapaNode))
+    }
+
+    /// Suppliers, optionally only those in `status` (e.g. "Qualified").
+    async fn suppliers(&self, ctx: &Context<'_>, status: Option<String>) -> async_graphql::Result<Vec<SupplierNode>> {
+        let state = ctx.data::<ApiState>()?;
+        require_feature(state, Feature::Suppliers)?;
+        let suppliers = state.suppliers.read().unwrap();
+        Ok(suppliers
+            .iter()
+            .filter(|supplier| status.as_deref().map_or(true, |status| format!("{:?}", supplier.status) == status))
+            .cloned()
+            .map(SupplierNode)
+            .collect())
+    }
+
+    /// Controlled documents by number, optionally only those in `status`
+    /// (e.g. "Effective").
+    async fn documents(&self, ctx: &Context<'_>, status: Option<String>) -> async_graphql::Result<Vec<DocumentNode>> {
+        let state = ctx.data::<ApiState>()?;
+        require_feature(state, Feature::Documents)?;
+        state
+            .database
+            .with_connection(|conn| {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT {} FROM documents
+                     WHERE deleted_at IS NULL AND (?1 IS NULL OR status = ?1)
+                     ORDER BY document_number",
+                    DOCUMENT_COLUMNS
+                ))?;
+                let documents = stmt.query_map(params![status], row_to_document)?.collect::<rusqlite::Result<_>>()?;
+                Ok(documents)
+            })
+            .map_err(|e| graphql_error(state, e))
+    }
+
+    /// A document by id or document number.
+    async fn document(&self, ctx: &Context<'_>, reference: String) -> async_graphql::Result<Option<DocumentNode>> {
+        let state = ctx.data::<ApiState>()?;
+        require_feature(state, Feature::Documents)?;
+        state
+            .database
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!(
+                            "SELECT {} FROM documents
+                             WHERE deleted_at IS NULL AND (id = ?1 OR document_number = ?1)",
+                            DOCUMENT_COLUMNS
+                        ),
+                        params![reference],
+                        row_to_document,
+                    )
+                    .optional()?)
+            })
+            .map_err(|e| graphql_error(state, e))
+    }
+}
+
+pub struct CapaNode(CapaRecord);
+
+#[Object(name = "Capa")]
+impl CapaNode {
+    async fn id(&self) -> &str {
+        &self.0.id
+    }
+
+    /// CAPA number, or the UUID for legacy records
+    async fn capa_number(&self) -> &str {
+        self.0.display_id()
+    }
+
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    async fn description(&self) -> &str {
+        &self.0.description
+    }
+
+    async fn capa_type(&self) -> &str {
+        self.0.capa_type.as_str()
+    }
+
+    async fn priority(&self) -> &str {
+        self.0.priority.as_str()
+    }
+
+    async fn status(&self) -> &str {
+        self.0.status.as_str()
+    }
+
+    async fn initiator_id(&self) -> &str {
+        &self.0.initiator_id
+    }
+
+    async fn assigned_to(&self) -> &str {
+        &self.0.assigned_to
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    async fn due_date(&self) -> Option<DateTime<Utc>> {
+        self.0.due_date
+    }
+
+    async fn closed_date(&self) -> Option<DateTime<Utc>> {
+        self.0.closed_date
+    }
+
+    async fn root_cause(&self) -> Option<&str> {
+        self.0.root_cause.as_deref()
+    }
+
+    /// Past its due date and not closed, as counted in the metrics
+    async fn overdue(&self) -> bool {
+        self.0.due_date.map_or(false, |due| due < Utc::now() && self.0.status != CapaStatus::Closed)
+    }
+
+    async fn corrective_actions(&self) -> Vec<CapaActionNode> {
+        self.0.corrective_actions.iter().map(CapaActionNode::from).collect()
+    }
+
+    async fn preventive_actions(&self) -> Vec<CapaActionNode> {
+        self.0.preventive_actions.iter().map(CapaActionNode::from).collect()
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "CapaAction")]
+pub struct CapaActionNode {
+    id: String,
+    description: String,
+    assigned_to: String,
+    due_date: DateTime<Utc>,
+    completed_date: Option<DateTime<Utc>>,
+    verification_method: String,
+    status: String,
+    evidence: Vec<String>,
+}
+
+impl From<&CapaAction> for CapaActionNode {
+    fn from(action: &CapaAction) -> Self {
+        Self {
+            id: action.id.clone(),
+            description: action.description.clone(),
+            assigned_to: action.assigned_to.clone(),
+            due_date: action.due_date,
+            completed_date: action.completed_date,
+            verification_method: action.verification_method.clone(),
+            status: format!("{:?}", action.status),
+            evidence: action.evidence.clone(),
+        }
+    }
+}
+
+pub struct SupplierNode(Supplier);
+
+#[Object(name = "Supplier")]
+impl SupplierNode {
+    async fn id(&self) -> String {
+        self.0.id.to_string()
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    async fn contact_info(&self) -> Option<&str> {
+        self.0.contact_info.as_deref()
+    }
+
+    async fn status(&self) -> String {
+        format!("{:?}", self.0.status)
+    }
+
+    async fn approved_by(&self) -> Option<&str> {
+        self.0.approved_by.as_deref()
+    }
+
+    /// Qualification state and the CAPAs linked to the supplier; none are
+    /// counted while the CAPA module is disabled
+    async fn scorecard(&self, ctx: &Context<'_>) -> async_graphql::Result<SupplierScorecard> {
+        let state = ctx.data::<ApiState>()?;
+        let supplier = RecordRef::new("supplier", self.0.id.to_string());
+        let mut links = LinkRepo::new(&state.database).links_of(&supplier).map_err(|e| graphql_error(state, e))?;
+        if !state.features.is_enabled(Feature::Capa) {
+            links.clear();
+        }
+        let linked: Vec<String> = links
+            .into_iter()
+            .map(|link| if link.source == supplier { link.target } else { link.source })
+            .filter(|record| record.record_type == "capa")
+            .map(|record| record.record_id)
+            .collect();
+        let capas = state.capa_records.read().unwrap();
+        let open_capas = capas
+            .iter()
+            .filter(|capa| linked.contains(&capa.id))
+            .filter(|capa| !matches!(capa.status, CapaStatus::Closed | CapaStatus::Cancelled))
+            .count();
+        let today = Utc::now().date_naive();
+        let days_until_expiry = self.0.qualification_expiry_date.map(|expiry| (expiry - today).num_days());
+        Ok(SupplierScorecard {
+            qualified: self.0.status == SupplierStatus::Qualified && days_until_expiry.map_or(true, |days| days >= 0),
+            qualification_date: self.0.qualification_date.map(|date| date.to_string()),
+            qualification_expiry_date: self.0.qualification_expiry_date.map(|date| date.to_string()),
+            days_until_expiry,
+            linked_capas: linked.len(),
+            open_capas,
+        })
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct SupplierScorecard {
+    /// Qualified and the qualification has not expired
+    qualified: bool,
+    qualification_date: Option<String>,
+    qualification_expiry_date: Option<String>,
+    /// Negative once the qualification has expired
+    days_until_expiry: Option<i64>,
+    linked_capas: usize,
+    /// Linked CAPAs that are neither closed nor cancelled
+    open_capas: usize,
+}
+
+const DOCUMENT_COLUMNS: &str =
+    "id, document_number, title, version, status, document_type, approved_by, effective_date, review_date";
+
+fn row_to_document(row: &Row<'_>) -> rusqlite::Result<DocumentNode> {
+    Ok(DocumentNode {
+        id: row.get(0)?,
+        document_number: row.get(1)?,
+        title: row.get(2)?,
+        version: row.get(3)?,
+        status: row.get(4)?,
+        document_type: row.get(5)?,
+        approved_by: row.get(6)?,
+        effective_date: row.get(7)?,
+        review_date: row.get(8)?,
+    })
+}
+
+pub struct DocumentNode {
+    id: String,
+    document_number: String,
+    title: String,
+    version: String,
+    status: String,
+    document_type: String,
+    approved_by: Option<String>,
+    effective_date: Option<String>,
+    review_date: Option<String>,
+}
+
+#[Object(name = "Document")]
+impl DocumentNode {
+    async fn id(&self) -> &str {
+        &self.id
+    }
+
+    async fn document_number(&self) -> &str {
+        &self.document_number
+    }
+
+    async fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Current version
+    async fn version(&self) -> &str {
+        &self.version
+    }
+
+    async fn status(&self) -> &str {
+        &self.status
+    }
+
+    async fn document_type(&self) -> &str {
+        &self.document_type
+    }
+
+    async fn approved_by(&self) -> Option<&str> {
+        self.approved_by.as_deref()
+    }
+
+    async fn effective_date(&self) -> Option<&str> {
+        self.effective_date.as_deref()
+    }
+
+    async fn review_date(&self) -> Option<&str> {
+        self.review_date.as_deref()
+    }
+
+    /// Version history, oldest first
+    async fn versions(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<DocumentVersion>> {
+        let state = ctx.data::<ApiState>()?;
+        state
+            .database
+            .with_connection(|conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT version, change_description, content_hash, created_by, created_at
+                     FROM document_versions WHERE document_id = ?1 ORDER BY created_at, version",
+                )?;
+                let versions = stmt
+                    .query_map(params![self.id], |row| {
+                        Ok(DocumentVersion {
+                            version: row.get(0)?,
+                            change_description: row.get(1)?,
+                            content_hash: row.get(2)?,
+                            created_by: row.get(3)?,
+                            created_at: row.get(4)?,
+                        })
+                    })?
+                    .collect::<rusqlite::Result<_>>()?;
+                Ok(versions)
+            })
+            .map_err(|e| graphql_error(state, e))
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct DocumentVersion {
+    version: String,
+    change_description: String,
+    content_hash: String,
+    created_by: String,
+    created_at: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capa::{CapaPriority, CapaType};
+    use crate::change_history::ChangeReason;
+    use crate::links::LinkType;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_graphql_nested_read_models_in_one_query() {
+        let state = ApiState::new();
+        state.database.seed_test_users(&["qa"]);
+        let mut capa = state
+            .capa_service
+            .create_capa(
+                "Seal failures".into(),
+                "Pouch seals fail peel test".into(),
+                CapaType::Corrective,
+                CapaPriority::High,
+                "qa".into(),
+                "qa".into(),
+                None,
+            )
+            .unwrap();
+        state
+            .capa_service
+            .add_corrective_action(
+                &mut capa,
+                "Replace sealing jaw".into(),
+                "qa".into(),
+                Utc::now(),
+                "Peel test".into(),
+                "qa",
+                &ChangeReason::new("Root cause confirmed").unwrap(),
+            )
+            .unwrap();
+        let supplier = state.supplier_service.register_supplier("Acme Pouches".into(), None).unwrap();
+        LinkRepo::new(&state.database)
+            .link(
+                &RecordRef::new("capa", capa.id.clone()),
+                &RecordRef::new("supplier", supplier.id.to_string()),
+                LinkType::Addresses,
+                "qa",
+            )
+            .unwrap();
+        state.capa_records.write().unwrap().push(capa);
+        state.suppliers.write().unwrap().push(supplier);
+        state
+            .database
+            .with_connection(|conn| {
+                conn.execute(
+                    "INSERT INTO documents
+                         (id, document_number, title, version, status, document_type, content_hash, created_by)
+                     VALUES ('d1', 'SOP-001', 'Sealing', '2.0', 'Effective', 'SOP', 'h2', 'qa')",
+                    [],
+                )?;
+                conn.execute(
+                    "INSERT INTO document_versions
+                         (id, document_id, version, change_description, content_hash, created_by)
+                     VALUES ('v1', 'd1', '1.0', 'Initial release', 'h1', 'qa'),
+                            ('v2', 'd1', '2.0', 'New jaw temperature', 'h2', 'qa')",
+                    [],
+                )?;
+                Ok(())
+            })
+            .unwrap();
+
+        let query = "{
+            capas(status: \"Identified\") { title correctiveActions { description status } }
+            suppliers { name scorecard { qualified linkedCapas openCapas } }
+            document(reference: \"SOP-001\") { version versions { version changeDescription } }
+        }";
+        let response = execute(&schema(), state, async_graphql::Request::new(query)).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+        assert_eq!(
+            data["capas"],
+            json!([{
+                "title": "Seal failures",
+                "correctiveActions": [{ "description": "Replace sealing jaw", "status": "Planned" }],
+            }])
+        );
+        assert_eq!(
+            data["suppliers"],
+            json!([{ "name": "Acme Pouches", "scorecard": { "qualified": false, "linkedCapas": 1, "openCapas": 1 } }])
+        );
+        assert_eq!(data["document"]["version"], "2.0");
+        let latest = &data["document"]["versions"][1];
+        assert_eq!(latest, &json!({ "version": "2.0", "changeDescription": "New jaw temperature" }));
+    }
+
+    #[tokio::test]
+    async fn test_graphql_disabled_module_queries_fail() {
+        use crate::app_context::AppContext;
+        use crate::config::Config;
+
+        let mut config = Config::default();
+        config.features.disabled = vec![Feature::Capa];
+        let state = ApiState::from_context(&AppContext::with_database(config, ApiState::new().database));
+        state.database.seed_test_users(&["qa"]);
+        let capa = state
+            .capa_service
+            .create_capa(
+                "Seal failures".into(),
+                "Pouch seals fail peel test".into(),
+                CapaType::Corrective,
+                CapaPriority::High,
+                "qa".into(),
+                "qa".into(),
+                None,
+            )
+            .unwrap();
+        let supplier = state.supplier_service.register_supplier("Acme Pouches".into(), None).unwrap();
+        LinkRepo::new(&state.database)
+            .link(
+                &RecordRef::new("capa", capa.id.clone()),
+                &RecordRef::new("supplier", supplier.id.to_string()),
+                LinkType::Addresses,
+                "qa",
+            )
+            .unwrap();
+        state.capa_records.write().unwrap().push(capa);
+        state.suppliers.write().unwrap().push(supplier);
+
+        let response = execute(&schema(), state.clone(), async_graphql::Request::new("{ capas { title } }")).await;
+        assert_eq!(response.errors.len(), 1);
+
+        let query = "{ suppliers { scorecard { linkedCapas openCapas } } }";
+        let response = execute(&schema(), state, async_graphql::Request::new(query)).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+        assert_eq!(data["suppliers"], json!([{ "scorecard": { "linkedCapas": 0, "openCapas": 0 } }]));
+    }
+}
@@ -0,0 +1,494 @@
+//! # gRPC Interface - Core Operations for Manufacturing Systems
+//!
+//! Manufacturing execution systems often integrate over gRPC rather than
+//! REST. [`QmsGrpc`] serves the `qms.v1.QualityManagement` service from
+//! `proto/qms.proto`, which ships with the crate: open a CAPA, report an
+//! adverse event and read the aggregated metrics. It runs next to the
+//! embedded REST API and shares its state, so calls authenticate with the
+//! same bearer tokens, pass the same network policy and go through the same
+//! services, audit trail and record numbering. With `[api.tls]` enabled it is
+//! served over TLS with the same certificate, and client certificates are
+//! verified and required as for REST.
+
+use std::net::SocketAddr;
+
+use chrono::{DateTime, Utc};
+use tonic::transport::{Certificate, Identity, ServerTlsConfig};
+use tonic::{Request, Response, Status};
+
+use crate::api::{compute_metrics, log_network_rejection, ApiPrincipal, ApiState, REQUIRED_SCOPE};
+use crate::audit::AuditManager;
+use crate::authorization::{Permission, UserRole};
+use crate::capa::{CapaPriority, CapaRecord, CapaType};
+use crate::config::ApiTlsConfig;
+use crate::error::QmsError;
+use crate::events::EventBus;
+use crate::feature_flags::Feature;
+use crate::i18n::{error_message, tr};
+use crate::post_market::{AdverseEvent, AdverseEventRepo, Severity};
+
+/// Code generated from `proto/qms.proto`.
+pub mod proto {
+    tonic::include_proto!("qms.v1");
+}
+
+use proto::quality_management_server::{QualityManagement, QualityManagementServer};
+
+/// `qms.v1.QualityManagement` over the REST API's state.
+#[derive(Clone)]
+pub struct QmsGrpc {
+    state: ApiState,
+    events: EventBus,
+}
+
+impl QmsGrpc {
+    /// Serve with the tokens, policy and services of `state`; reported
+    /// adverse events are published on `events`.
+    pub fn new(state: ApiState, events: EventBus) -> Self {
+        Self { state, events }
+    }
+
+    /// Serve on `bind` in a background task until the handle is aborted,
+    /// over TLS when `tls` is enabled.
+    pub fn spawn(self, bind: &str, tls: &ApiTlsConfig) -> crate::Result<tokio::task::JoinHandle<()>> {
+        let bind: SocketAddr = bind
+            .parse()
+            .map_err(|_| QmsError::Configuration { message: format!("invalid api.grpc.bind address '{}'", bind) })?;
+        let mut builder = tonic::transport::Server::builder();
+        if tls.enabled {
+            builder = builder
+                .tls_config(server_tls_config(tls)?)
+                .map_err(|e| QmsError::Configuration { message: format!("gRPC TLS: {}", e) })?;
+        }
+        let tls = tls.enabled;
+        Ok(tokio::spawn(async move {
+            tracing::info!(%bind, tls, "gRPC server listening");
+            let server = builder.add_service(QualityManagementServer::new(self));
+            if let Err(e) = server.serve(bind).await {
+                tracing::error!(%bind, "gRPC server stopped: {e}");
+            }
+        }))
+    }
+
+    /// The caller of `request`, after the network policy and token checks
+    /// the REST API applies.
+    fn authenticate<T>(&self, request: &Request<T>, method: &str) -> Result<ApiPrincipal, Status> {
+        let locale = self.state.locale;
+        let peer = request.remote_addr().map(|addr| addr.ip());
+        if let Err(reason) = self.state.network_policy.check(peer) {
+            log_network_rejection(&self.state.database, peer, &format!("grpc/{}", method), &reason);
+            return Err(Status::permission_denied(tr(locale, "api.network_rejected")));
+        }
+        let token = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .unwrap_or("");
+        let unauthenticated = || Status::unauthenticated(tr(locale, "api.unauthorized"));
+        let stored = self.state.token_manager.lookup(token, REQUIRED_SCOPE).ok_or_else(unauthenticated)?;
+        match stored.user_id {
+            None => Ok(ApiPrincipal::service()),
+            // Tokens of unknown or deactivated users are rejected
+            Some(user_id) => match UserRole::of_user(&self.state.database, &user_id) {
+                Ok(Some(role)) => Ok(ApiPrincipal { user_id, role }),
+                Ok(None) => Err(unauthenticated()),
+                Err(e) => Err(self.status(e)),
+            },
+        }
+    }
+
+    /// Check that the caller's role grants `permission`; denials are
+    /// audited against `resource` like REST denials.
+    fn authorize(&self, principal: &ApiPrincipal, permission: Permission, resource: &str) -> Result<(), Status> {
+        if principal.role.has_permission(permission) {
+            return Ok(());
+        }
+        let metadata = serde_json::json!({
+            "permission": permission.as_str(),
+            "role": principal.role.as_str(),
+            "transport": "grpc",
+        });
+        AuditManager::new(self.state.database.clone())
+            .log_action(&principal.user_id, "api_access_denied", resource, "Failure", Some(metadata.to_string()))
+            .map_err(|e| self.status(e))?;
+        tracing::warn!(user = %principal.user_id, permission = permission.as_str(), "gRPC call denied");
+        Err(Status::permission_denied(tr(self.state.locale, "api.forbidden")))
+    }
+
+    /// Refuse a call of a module switched off by a feature flag as if the
+    /// method did not exist, as the REST API answers 404.
+    fn require_feature(&self, feature: Feature) -> Result<(), Status> {
+        match self.state.features.is_enabled(feature) {
+            true => Ok(()),
+            false => Err(Status::unimplemented(format!("The {} module is disabled", feature.as_str()))),
+        }
+    }
+
+    /// Map a domain error onto a gRPC status, as `status_for` does for HTTP.
+    fn status(&self, e: QmsError) -> Status {
+        let message = error_message(self.state.locale, &e);
+        match e {
+            QmsError::NotFound { .. } => Status::not_found(message),
+            QmsError::Validation { .. } | QmsError::ValidationError { .. } | QmsError::ValidationErrors { .. } => {
+                Status::invalid_argument(message)
+            }
+            QmsError::Security { .. } => Status::permission_denied(message),
+            QmsError::Conflict { .. } => Status::failed_precondition(message),
+            _ => {
+                tracing::error!(code = e.error_code(), "gRPC call failed: {e}");
+                Status::internal(message)
+            }
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl QualityManagement for QmsGrpc {
+    async fn create_capa(&self, request: Request<proto::CreateCapaRequest>) -> Result<Response<proto::Capa>, Status> {
+        let principal = self.authenticate(&request, "CreateCapa")?;
+        self.require_feature(Feature::Capa)?;
+        self.authorize(&principal, Permission::WriteRecords, "capa")?;
+        let request = request.into_inner();
+        let capa_type = match request.capa_type() {
+            proto::CapaType::Corrective => CapaType::Corrective,
+            proto::CapaType::Preventive => CapaType::Preventive,
+            proto::CapaType::Combined => CapaType::Combined,
+            proto::CapaType::Unspecified => return Err(invalid("capa_type", "A CAPA type is required")),
+        };
+        let priority = match request.priority() {
+            proto::CapaPriority::Critical => CapaPriority::Critical,
+            proto::CapaPriority::High => CapaPriority::High,
+            proto::CapaPriority::Medium => CapaPriority::Medium,
+            proto::CapaPriority::Low => CapaPriority::Low,
+            proto::CapaPriority::Unspecified => return Err(invalid("priority", "A priority is required")),
+        };
+        let due_date = match request.due_date.as_str() {
+            "" => None,
+            due => Some(
+                DateTime::parse_from_rfc3339(due)
+                    .map_err(|_| invalid("due_date", "Expected an RFC 3339 timestamp"))?
+                    .with_timezone(&Utc),
+            ),
+        };
+        let capa = self
+            .state
+            .capa_service
+            .create_capa(
+                request.title,
+                request.description,
+                capa_type,
+                priority,
+                principal.user_id.clone(),
+                request.assigned_to,
+                due_date,
+            )
+            .map_err(|e| self.status(e))?;
+        // Counted in the metrics from the next call on
+        self.state.capa_records.write().unwrap().push(capa.clone());
+        *self.state.metrics_cache.write().unwrap() = None;
+        Ok(Response::new(capa_message(&capa)))
+    }
+
+    async fn report_adverse_event(
+        &self,
+        request: Request<proto::ReportAdverseEventRequest>,
+    ) -> Result<Response<proto::AdverseEvent>, Status> {
+        let principal = self.authenticate(&request, "ReportAdverseEvent")?;
+        self.require_feature(Feature::PostMarket)?;
+        self.authorize(&principal, Permission::WriteRecords, "adverse_event")?;
+        let request = request.into_inner();
+        let severity = match request.severity() {
+            proto::Severity::Critical => Severity::Critical,
+            proto::Severity::Major => Severity::Major,
+            proto::Severity::Minor => Severity::Minor,
+            proto::Severity::Unspecified => return Err(invalid("severity", "A severity is required")),
+        };
+        if request.description.trim().is_empty() {
+            return Err(invalid("description", "A description is required"));
+        }
+        let event = AdverseEvent::new(principal.user_id.clone(), request.description, severity);
+        AdverseEventRepo::new(&self.state.database)
+            .with_events(self.events.clone())
+            .insert(&event)
+            .and_then(|()| {
+                AuditManager::new(self.state.database.clone()).log_action(
+                    &principal.user_id,
+                    "adverse_event_reported",
+                    &format!("adverse_event:{}", event.id),
+                    "Success",
+                    Some(serde_json::json!({ "severity": event.severity, "transport": "grpc" }).to_string()),
+                )
+            })
+            .map_err(|e| self.status(e))?;
+        Ok(Response::new(proto::AdverseEvent {
+            id: event.id.to_string(),
+            reporter: event.reporter,
+            description: event.description,
+            severity: request.severity,
+            reported_on: event.reported_on.to_rfc3339(),
+        }))
+    }
+
+    async fn get_metrics(
+        &self,
+        request: Request<proto::GetMetricsRequest>,
+    ) -> Result<Response<proto::Metrics>, Status> {
+        self.authenticate(&request, "GetMetrics")?;
+        let metrics = compute_metrics(&self.state, Utc::now()).await.map_err(|e| self.status(e))?;
+        let counts = |counts: &std::collections::HashMap<String, usize>| {
+            counts.iter().map(|(key, count)| (key.clone(), *count as u64)).collect()
+        };
+        let training_records = self.state.training_records.read().unwrap().clone();
+        let training = self.state.training_service.calculate_metrics(&training_records);
+        let capa = &metrics.capa_metrics;
+        let suppliers = &metrics.supplier_metrics;
+        Ok(Response::new(proto::Metrics {
+            capa: Some(proto::CapaMetrics {
+                total: capa.total_count as u64,
+                overdue: capa.overdue_count as u64,
+                closed: capa.closed_count as u64,
+                by_status: counts(&capa.status_counts),
+                by_priority: counts(&capa.priority_counts),
+            }),
+            suppliers: Some(proto::SupplierMetrics {
+                total: suppliers.total_count as u64,
+                qualified: suppliers.qualified_count as u64,
+                pending: suppliers.pending_count as u64,
+                disqualified: suppliers.disqualified_count as u64,
+                qualified_percentage: suppliers.qualified_percentage,
+            }),
+            training: Some(proto::TrainingMetrics {
+                total: training.total_count as u64,
+                completed: training.completed as u64,
+                pending: training.pending as u64,
+                overdue: training.overdue as u64,
+            }),
+            kpis: metrics
+                .kpis
+                .iter()
+                .map(|kpi| proto::Kpi {
+                    metric: kpi.metric.as_str().to_string(),
+                    label: kpi.label.clone(),
+                    actual: kpi.actual,
+                    target: kpi.target.target,
+                    status: kpi.status.as_str().to_string(),
+                })
+                .collect(),
+        }))
+    }
+}
+
+/// TLS settings for `[api.tls]`, as `network_policy::tls_acceptor` builds
+/// them for REST: client certificates are verified against `client_ca_path`
+/// and required with `require_client_cert`.
+pub fn server_tls_config(config: &ApiTlsConfig) -> crate::Result<ServerTlsConfig> {
+    let read = |path: &str| {
+        std::fs::read(path).map_err(|e| QmsError::FileSystem { path: path.to_string(), message: e.to_string() })
+    };
+    let identity = Identity::from_pem(read(&config.cert_path)?, read(&config.key_path)?);
+    let mut tls = ServerTlsConfig::new().identity(identity);
+    if let Some(ca_path) = &config.client_ca_path {
+        let client_ca = Certificate::from_pem(read(ca_path)?);
+        tls = tls.client_ca_root(client_ca).client_auth_optional(!config.require_client_cert);
+    }
+    Ok(tls)
+}
+
+fn invalid(field: &str, message: &str) -> Status {
+    Status::invalid_argument(format!("{}: {}", field, message))
+}
+
+fn capa_message(capa: &CapaRecord) -> proto::Capa {
+    proto::Capa {
+        id: capa.id.clone(),
+        capa_number: capa.capa_number.clone(),
+        title: capa.title.clone(),
+        status: capa.status.as_str().to_string(),
+        assigned_to: capa.assigned_to.clone(),
+        created_at: capa.created_at.to_rfc3339(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_context::AppContext;
+    use crate::config::{Config, DatabaseConfig};
+    use crate::database::Database;
+
+    fn authorized<T>(message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        request.metadata_mut().insert("authorization", "Bearer grpc-token".parse().unwrap());
+        request
+    }
+
+    #[tokio::test]
+    async fn test_grpc_create_capa_report_event_and_metrics() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".into(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["qa"]);
+        let context = AppContext::with_database(Config::default(), db);
+        let state = ApiState::from_context(&context);
+        state.token_manager.insert_user_token("grpc-token".into(), 60, vec![REQUIRED_SCOPE.into()], "qa");
+        let grpc = QmsGrpc::new(state, context.events.clone());
+
+        let capa = grpc
+            .create_capa(authorized(proto::CreateCapaRequest {
+                title: "Sealing defect".into(),
+                description: "Pouch seals fail peel test on line 3".into(),
+                capa_type: proto::CapaType::Corrective as i32,
+                priority: proto::CapaPriority::High as i32,
+                assigned_to: "qa".into(),
+                due_date: "2030-01-31T00:00:00Z".into(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!capa.capa_number.is_empty());
+
+        let event = grpc
+            .report_adverse_event(authorized(proto::ReportAdverseEventRequest {
+                description: "Patient burn during use".into(),
+                severity: proto::Severity::Major as i32,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!((event.reporter.as_str(), event.severity()), ("qa", proto::Severity::Major));
+
+        let metrics = grpc.get_metrics(authorized(proto::GetMetricsRequest {})).await.unwrap().into_inner();
+        assert_eq!(metrics.capa.unwrap().total, 1);
+
+        let status = grpc.get_metrics(Request::new(proto::GetMetricsRequest {})).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        let status = grpc
+            .report_adverse_event(authorized(proto::ReportAdverseEventRequest {
+                description: "No severity".into(),
+                severity: proto::Severity::Unspecified as i32,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_grpc_disabled_module_calls_are_unimplemented() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".into(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["qa"]);
+        let mut config = Config::default();
+        config.features.disabled = vec![Feature::Capa, Feature::PostMarket];
+        let context = AppContext::with_database(config, db);
+        let state = ApiState::from_context(&context);
+        state.token_manager.insert_user_token("grpc-token".into(), 60, vec![REQUIRED_SCOPE.into()], "qa");
+        let grpc = QmsGrpc::new(state, context.events.clone());
+
+        let status = grpc
+            .create_capa(authorized(proto::CreateCapaRequest {
+                title: "Sealing defect".into(),
+                description: "Pouch seals fail peel test on line 3".into(),
+                capa_type: proto::CapaType::Corrective as i32,
+                priority: proto::CapaPriority::High as i32,
+                assigned_to: "qa".into(),
+                due_date: "2030-01-31T00:00:00Z".into(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unimplemented);
+        let status = grpc
+            .report_adverse_event(authorized(proto::ReportAdverseEventRequest {
+                description: "Patient burn during use".into(),
+                severity: proto::Severity::Major as i32,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unimplemented);
+    }
+
+    #[tokio::test]
+    async fn test_grpc_requires_client_certificate_under_mutual_tls() {
+        use proto::quality_management_client::QualityManagementClient;
+        use rcgen::{BasicConstraints, DnType, IsCa};
+        use tonic::transport::{Channel, ClientTlsConfig};
+
+        let certificate = |name: &str, ca: bool| {
+            let mut params = rcgen::CertificateParams::new(vec![name.to_string()]);
+            params.distinguished_name.push(DnType::CommonName, name);
+            if ca {
+                params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            }
+            rcgen::Certificate::from_params(params).unwrap()
+        };
+        let (server_ca, client_ca) = (certificate("QMSrs server CA", true), certificate("QMSrs client CA", true));
+        let (server, client) = (certificate("localhost", false), certificate("mes-line-3", false));
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str, pem: String| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, pem).unwrap();
+            path.to_string_lossy().into_owned()
+        };
+        let tls = ApiTlsConfig {
+            enabled: true,
+            cert_path: write("server.crt", server.serialize_pem_with_signer(&server_ca).unwrap()),
+            key_path: write("server.key", server.serialize_private_key_pem()),
+            client_ca_path: Some(write("clients-ca.crt", client_ca.serialize_pem().unwrap())),
+            require_client_cert: true,
+        };
+
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".into(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["qa"]);
+        let context = AppContext::with_database(Config::default(), db);
+        let state = ApiState::from_context(&context);
+        state.token_manager.insert_user_token("grpc-token".into(), 60, vec![REQUIRED_SCOPE.into()], "qa");
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let handle = QmsGrpc::new(state, context.events.clone()).spawn(&format!("127.0.0.1:{}", port), &tls).unwrap();
+
+        let trust_server = || {
+            ClientTlsConfig::new()
+                .ca_certificate(Certificate::from_pem(server_ca.serialize_pem().unwrap()))
+                .domain_name("localhost")
+        };
+        let get_metrics = |tls: ClientTlsConfig| async move {
+            let channel = Channel::from_shared(format!("https://127.0.0.1:{}", port)).unwrap().tls_config(tls).unwrap();
+            let channel = channel.connect().await.map_err(|e| e.to_string())?;
+            QualityManagementClient::new(channel)
+                .get_metrics(authorized(proto::GetMetricsRequest {}))
+                .await
+                .map_err(|status| status.to_string())
+        };
+
+        let client_pem = client.serialize_pem_with_signer(&client_ca).unwrap();
+        let identity = Identity::from_pem(client_pem, client.serialize_private_key_pem());
+        let mut with_certificate = get_metrics(trust_server().identity(identity.clone())).await;
+        for _ in 0..50 {
+            if with_certificate.is_ok() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            with_certificate = get_metrics(trust_server().identity(identity.clone())).await;
+        }
+        assert!(with_certificate.is_ok(), "{:?}", with_certificate.err());
+        assert!(get_metrics(trust_server()).await.is_err(), "a client without a certificate must be refused");
+        handle.abort();
+    }
+}
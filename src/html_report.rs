@@ -0,0 +1,516 @@
+//! # HTML Report Renderer
+//!
+//! Renders the same [`Report`] model used for PDFs into a single self-contained
+//! HTML file: inline CSS, charts as inline SVG and no external resources, so
+//! the file can be opened in any browser or attached to an eQMS portal. The
+//! document is produced from a fixed template; only escaped report content is
+//! substituted into it.
+
+use std::f32::consts::{FRAC_PI_2, PI};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::error::QmsError;
+use crate::i18n::{tr, tr_args, Locale};
+use crate::report::{Chart, ChartKind, HeatMap, Report, ReportBlock, ReportTable, CHART_PALETTE};
+use crate::Result;
+
+const TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="{{lang}}">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta name="generator" content="QMSrs {{version}}">
+<title>{{title}}</title>
+<style>
+{{styles}}
+</style>
+</head>
+<body>
+<header class="running-header"><span>{{title}}</span><span>{{generated}}</span></header>
+<main>
+{{body}}
+</main>
+<footer>{{footer}}</footer>
+</body>
+</html>
+"#;
+
+const STYLES: &str = "\
+body { font-family: Helvetica, Arial, sans-serif; font-size: 10pt; color: #000; margin: 0 auto; max-width: 820px; padding: 0 24px; }
+.running-header { display: flex; justify-content: space-between; font-size: 9pt; border-bottom: 1px solid #000; padding: 12px 0 6px; }
+.running-header span:first-child { font-weight: bold; }
+.cover { text-align: center; padding: 120px 0 80px; border-bottom: 1px solid #000; }
+.cover h1 { font-size: 26pt; margin: 0 0 12px; }
+.cover .subtitle { font-size: 16pt; margin: 0 0 48px; }
+.cover p { font-size: 12pt; margin: 4px 0; }
+nav.toc ol { padding-left: 20px; }
+nav.toc li { margin: 4px 0; }
+section h2 { font-size: 16pt; border-bottom: 1px solid #000; padding-bottom: 4px; margin-top: 28px; }
+section h3 { font-size: 12pt; margin: 16px 0 6px; }
+p { line-height: 1.4; white-space: pre-line; }
+dl.kv { display: grid; grid-template-columns: 200px 1fr; gap: 4px 0; }
+dl.kv dt { font-weight: bold; }
+dl.kv dd { margin: 0; white-space: pre-line; }
+table { width: 100%; border-collapse: collapse; font-size: 9pt; margin: 8px 0 12px; }
+th { background: #e1e1e1; text-align: left; }
+th, td { padding: 3px; border-bottom: 1px solid #000; vertical-align: top; }
+figure { margin: 12px 0; }
+figcaption { font-weight: bold; font-size: 12pt; margin-bottom: 6px; }
+.empty { font-style: italic; }
+table.heat-map td.cell { text-align: center; font-weight: bold; border: 2px solid #fff; height: 26px; }
+table.heat-map th.row-label { background: none; text-align: right; font-weight: normal; }
+table.heat-map th.axis { background: none; font-style: italic; font-weight: normal; text-align: center; }
+footer { border-top: 1px solid #000; margin-top: 32px; padding: 8px 0 24px; font-size: 9pt; text-align: center; }
+footer .note { display: block; font-size: 8pt; margin-top: 4px; }
+@media print {
+  .cover, nav.toc { page-break-after: always; }
+  section.page-break { page-break-before: always; }
+  th, td.cell { -webkit-print-color-adjust: exact; print-color-adjust: exact; }
+}";
+
+/// Render `report` into a self-contained HTML document.
+pub fn render_html(report: &Report) -> String {
+    let locale = report.locale;
+    let mut body = String::new();
+    if report.cover_page {
+        body.push_str(&cover(report));
+    }
+    if report.table_of_contents && !report.sections.is_empty() {
+        let _ = write!(body, "<nav class=\"toc\">\n<h2>{}</h2>\n<ol>\n", escape(tr(locale, "report.table_of_contents")));
+        for (idx, section) in report.sections.iter().enumerate() {
+            let _ = writeln!(body, "<li><a href=\"#section-{}\">{}</a></li>", idx + 1, escape(&section.title));
+        }
+        body.push_str("</ol>\n</nav>\n");
+    }
+
+    for (idx, section) in report.sections.iter().enumerate() {
+        let class = if section.page_break_before { " class=\"page-break\"" } else { "" };
+        let _ = writeln!(body, "<section id=\"section-{}\"{}>\n<h2>{}</h2>", idx + 1, class, escape(&section.title));
+        for block in &section.blocks {
+            body.push_str(&render_block(block, locale));
+        }
+        body.push_str("</section>\n");
+    }
+
+    let version = tr_args(locale, "report.version", &[("version", &report.application_version)]);
+    let mut footer = format!("{} | &copy; 2025 QMS Development Team", escape(&version));
+    if let Some(note) = &report.footer_note {
+        let _ = write!(footer, "<span class=\"note\">{}</span>", escape(note));
+    }
+
+    fill_template(
+        TEMPLATE,
+        &[
+            ("styles", STYLES),
+            ("version", &escape(&report.application_version)),
+            ("title", &escape(&report.title)),
+            ("lang", locale.code()),
+            ("generated", &escape(&generated_label(report))),
+            ("footer", &footer),
+            ("body", &body),
+        ],
+    )
+}
+
+/// Substitute `{{name}}` placeholders in a single pass, so placeholder-like
+/// text inside report content is never expanded.
+fn fill_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len() + values.iter().map(|(_, v)| v.len()).sum::<usize>());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        match after.find("}}") {
+            Some(end) => {
+                let name = &after[..end];
+                match values.iter().find(|(key, _)| *key == name) {
+                    Some((_, value)) => out.push_str(value),
+                    None => out.push_str(&rest[start..start + 2 + end + 2]),
+                }
+                rest = &after[end + 2..];
+            }
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Render `report` to `output_path` as HTML (written atomically like PDFs).
+pub fn render_html_report(report: &Report, output_path: &Path) -> Result<()> {
+    let tmp_path = output_path.with_extension("tmp");
+    std::fs::write(&tmp_path, render_html(report))
+        .and_then(|_| std::fs::rename(&tmp_path, output_path))
+        .map_err(|e| QmsError::FileSystem {
+            path: output_path.display().to_string(),
+            message: e.to_string(),
+        })
+}
+
+fn cover(report: &Report) -> String {
+    let mut out = format!("<div class=\"cover\">\n<h1>{}</h1>\n", escape(&report.title));
+    if let Some(subtitle) = &report.subtitle {
+        let _ = writeln!(out, "<p class=\"subtitle\">{}</p>", escape(subtitle));
+    }
+    let locale = report.locale;
+    if let Some(org) = &report.organization {
+        let _ = writeln!(out, "<p>{}</p>", escape(&tr_args(locale, "report.organization", &[("name", org)])));
+    }
+    if let Some(prepared_by) = &report.prepared_by {
+        let _ = writeln!(out, "<p>{}</p>", escape(&tr_args(locale, "report.prepared_by", &[("name", prepared_by)])));
+    }
+    let _ = writeln!(out, "<p>{}</p>", escape(&generated_label(report)));
+    let version = tr_args(locale, "report.version", &[("version", &report.application_version)]);
+    let _ = writeln!(out, "<p>{}</p>\n</div>", escape(&version));
+    out
+}
+
+/// "Generated: <timestamp>" in the report's locale and display time zone.
+fn generated_label(report: &Report) -> String {
+    let timestamp = report.display_timezone.format(report.generated_on);
+    tr_args(report.locale, "report.generated", &[("timestamp", &timestamp)])
+}
+
+fn render_block(block: &ReportBlock, locale: Locale) -> String {
+    match block {
+        ReportBlock::Heading(text) => format!("<h3>{}</h3>\n", escape(text)),
+        ReportBlock::Paragraph(text) => format!("<p>{}</p>\n", escape(text)),
+        ReportBlock::KeyValue(pairs) => {
+            let mut out = String::from("<dl class=\"kv\">\n");
+            for (key, value) in pairs {
+                let _ = writeln!(out, "<dt>{}</dt><dd>{}</dd>", escape(key), escape(value));
+            }
+            out.push_str("</dl>\n");
+            out
+        }
+        ReportBlock::Table(table) => render_table(table),
+        ReportBlock::Chart(chart) => render_chart(chart, locale),
+        ReportBlock::HeatMap(map) => render_heat_map(map),
+        ReportBlock::Spacer(points) => format!("<div style=\"height: {:.0}pt\"></div>\n", points.max(0.0)),
+    }
+}
+
+fn render_table(table: &ReportTable) -> String {
+    let mut out = String::from("<table>\n<colgroup>");
+    for weight in table.normalized_weights() {
+        let _ = write!(out, "<col style=\"width: {:.1}%\">", weight * 100.0);
+    }
+    out.push_str("</colgroup>\n<thead><tr>");
+    for header in &table.headers {
+        let _ = write!(out, "<th>{}</th>", escape(header));
+    }
+    out.push_str("</tr></thead>\n<tbody>\n");
+    for row in &table.rows {
+        out.push_str("<tr>");
+        for idx in 0..table.headers.len() {
+            let _ = write!(out, "<td>{}</td>", escape(row.get(idx).map(String::as_str).unwrap_or("")));
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("</tbody>\n</table>\n");
+    out
+}
+
+fn palette_css(idx: usize) -> String {
+    let (r, g, b) = CHART_PALETTE[idx % CHART_PALETTE.len()];
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn render_chart(chart: &Chart, locale: Locale) -> String {
+    let mut out = format!("<figure>\n<figcaption>{}</figcaption>\n", escape(&chart.title));
+    if chart.total() <= 0.0 {
+        let _ = writeln!(out, "<p class=\"empty\">{}</p>", escape(tr(locale, "report.no_data")));
+    } else {
+        out.push_str(&match chart.kind {
+            ChartKind::Bar => bar_svg(chart),
+            ChartKind::Pie => pie_svg(chart),
+        });
+    }
+    out.push_str("</figure>\n");
+    out
+}
+
+fn bar_svg(chart: &Chart) -> String {
+    let (width, height, label_band, top_pad) = (640.0_f32, 200.0_f32, 16.0_f32, 14.0_f32);
+    let plot_bottom = height - label_band;
+    let plot_height = plot_bottom - top_pad;
+    let max_value = chart.data.iter().map(|d| d.value).fold(0.0_f32, f32::max);
+    let slot = width / chart.data.len().max(1) as f32;
+    let bar_width = slot * 0.6;
+
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {w} {h}\" width=\"100%\" role=\"img\" aria-label=\"{label}\" font-family=\"Helvetica, Arial, sans-serif\" font-size=\"9\">\n",
+        w = width,
+        h = height,
+        label = escape(&chart.title),
+    );
+    let _ = writeln!(out, "<line x1=\"0\" y1=\"{y}\" x2=\"{w}\" y2=\"{y}\" stroke=\"#000\"/>", y = plot_bottom, w = width);
+    for (idx, datum) in chart.data.iter().enumerate() {
+        let bar_height = if max_value > 0.0 { datum.value.max(0.0) / max_value * plot_height } else { 0.0 };
+        let x = idx as f32 * slot + (slot - bar_width) / 2.0;
+        let center = x + bar_width / 2.0;
+        let _ = writeln!(
+            out,
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"><title>{}: {}</title></rect>",
+            x,
+            plot_bottom - bar_height,
+            bar_width,
+            bar_height,
+            palette_css(idx),
+            escape(&datum.label),
+            datum.value
+        );
+        let _ = writeln!(
+            out,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+            center,
+            plot_bottom - bar_height - 3.0,
+            datum.value
+        );
+        let _ = writeln!(
+            out,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"middle\">{}</text>",
+            center,
+            height - 3.0,
+            escape(&datum.label)
+        );
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+fn pie_svg(chart: &Chart) -> String {
+    let total = chart.total();
+    let (radius, cx, cy) = (70.0_f32, 75.0_f32, 75.0_f32);
+    let legend_x = cx + radius + 20.0;
+    let height = (2.0 * cy).max(chart.data.len() as f32 * 16.0 + 8.0);
+
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 640 {h:.0}\" width=\"100%\" role=\"img\" aria-label=\"{label}\" font-family=\"Helvetica, Arial, sans-serif\" font-size=\"10\">\n",
+        h = height,
+        label = escape(&chart.title),
+    );
+    // Same orientation as the PDF renderer: clockwise from 12 o'clock.
+    let mut start = FRAC_PI_2;
+    for (idx, datum) in chart.data.iter().enumerate() {
+        let share = datum.value.max(0.0) / total;
+        let color = palette_css(idx);
+        let tooltip = format!("<title>{}: {}</title>", escape(&datum.label), datum.value);
+        if share >= 0.9999 {
+            let _ = writeln!(out, "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\">{}</circle>", cx, cy, radius, color, tooltip);
+        } else if share > 0.0 {
+            let end = start - share * 2.0 * PI;
+            // SVG's y axis points down, so flip the sine.
+            let (x0, y0) = (cx + radius * start.cos(), cy - radius * start.sin());
+            let (x1, y1) = (cx + radius * end.cos(), cy - radius * end.sin());
+            let large_arc = u8::from(share > 0.5);
+            let _ = writeln!(
+                out,
+                "<path d=\"M {cx} {cy} L {x0:.2} {y0:.2} A {r} {r} 0 {large} 1 {x1:.2} {y1:.2} Z\" fill=\"{color}\">{tooltip}</path>",
+                cx = cx,
+                cy = cy,
+                x0 = x0,
+                y0 = y0,
+                r = radius,
+                large = large_arc,
+                x1 = x1,
+                y1 = y1,
+                color = color,
+                tooltip = tooltip,
+            );
+            start = end;
+        }
+
+        let legend_y = 12.0 + idx as f32 * 16.0;
+        let _ = writeln!(out, "<rect x=\"{}\" y=\"{}\" width=\"9\" height=\"9\" fill=\"{}\"/>", legend_x, legend_y - 8.0, color);
+        let _ = writeln!(
+            out,
+            "<text x=\"{}\" y=\"{}\">{} ({}, {:.0}%)</text>",
+            legend_x + 14.0,
+            legend_y,
+            escape(&datum.label),
+            datum.value,
+            share * 100.0
+        );
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+fn render_heat_map(map: &HeatMap) -> String {
+    let mut out = format!(
+        "<figure>\n<figcaption>{}</figcaption>\n<table class=\"heat-map\">\n<tr><th class=\"axis\">{}</th>",
+        escape(&map.title),
+        escape(&map.row_axis)
+    );
+    out.push_str(&"<th></th>".repeat(map.column_labels.len()));
+    out.push_str("</tr>\n");
+    for (row_idx, label) in map.row_labels.iter().enumerate() {
+        let _ = write!(out, "<tr><th class=\"row-label\">{}</th>", escape(label));
+        for col_idx in 0..map.column_labels.len() {
+            match map.cells.get(row_idx).and_then(|row| row.get(col_idx)) {
+                Some(cell) => {
+                    let (r, g, b) = cell.level.rgb();
+                    let value = if cell.value > 0 { cell.value.to_string() } else { String::new() };
+                    let _ = write!(
+                        out,
+                        "<td class=\"cell\" style=\"background: #{:02x}{:02x}{:02x}\" title=\"{:?}\">{}</td>",
+                        r, g, b, cell.level, value
+                    );
+                }
+                None => out.push_str("<td class=\"cell\"></td>"),
+            }
+        }
+        out.push_str("</tr>\n");
+    }
+    out.push_str("<tr><th></th>");
+    for label in &map.column_labels {
+        let _ = write!(out, "<th class=\"axis\">{}</th>", escape(label));
+    }
+    let _ = write!(
+        out,
+        "</tr>\n<tr><th></th><th class=\"axis\" colspan=\"{}\">{}</th></tr>\n</table>\n</figure>\n",
+        map.column_labels.len().max(1),
+        escape(&map.column_axis)
+    );
+    out
+}
+
+/// Escape text for HTML element content and attribute values.
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::{ChartDatum, HeatCell, HeatLevel, ReportBuilder, ReportSection};
+    use tempfile::tempdir;
+    use chrono::{TimeZone, Utc};
+
+    fn sample_report() -> Report {
+        ReportBuilder::new("CAPA <Detail> & Summary")
+            .with_organization("Acme Medical")
+            .with_footer_note("Payload SHA-256: abc123")
+            .with_cover_page()
+            .with_table_of_contents()
+            .with_section(
+                ReportSection::new("Overview")
+                    .key_values(vec![("Status", "Open")])
+                    .paragraph("Line one\nLine two <script>alert(1)</script>"),
+            )
+            .with_section(
+                ReportSection::new("Actions")
+                    .with_page_break()
+                    .table(ReportTable::new(vec!["ID", "Description"]).with_row(vec!["1", "Recalibrate"]))
+                    .chart(Chart {
+                        title: "By Status".to_string(),
+                        kind: ChartKind::Pie,
+                        data: vec![
+                            ChartDatum { label: "Open".to_string(), value: 3.0 },
+                            ChartDatum { label: "Closed".to_string(), value: 1.0 },
+                        ],
+                    })
+                    .chart(Chart {
+                        title: "By Priority".to_string(),
+                        kind: ChartKind::Bar,
+                        data: vec![ChartDatum { label: "High".to_string(), value: 2.0 }],
+                    })
+                    .heat_map(HeatMap {
+                        title: "Risk Matrix".to_string(),
+                        row_axis: "Severity".to_string(),
+                        column_axis: "Probability".to_string(),
+                        row_labels: vec!["High".to_string(), "Low".to_string()],
+                        column_labels: vec!["Rare".to_string(), "Often".to_string()],
+                        cells: vec![
+                            vec![HeatCell { value: 0, level: HeatLevel::Medium }, HeatCell { value: 2, level: HeatLevel::High }],
+                            vec![HeatCell { value: 1, level: HeatLevel::Low }, HeatCell { value: 0, level: HeatLevel::Medium }],
+                        ],
+                    }),
+            )
+            .build()
+    }
+
+    #[test]
+    fn test_html_structure_and_toc() {
+        let html = render_html(&sample_report());
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(!html.contains("{{"), "all template placeholders must be substituted");
+        assert!(html.contains("<dt>Status</dt><dd>Open</dd>"));
+        assert!(html.contains("<div class=\"cover\">"));
+        assert!(html.contains("<a href=\"#section-1\">Overview</a>"));
+        assert!(html.contains("<section id=\"section-2\" class=\"page-break\">"));
+        assert!(html.contains("<td>Recalibrate</td>"));
+        assert!(html.contains("Payload SHA-256: abc123"));
+    }
+
+    #[test]
+    fn test_html_is_self_contained() {
+        let html = render_html(&sample_report());
+        assert!(html.contains("<style>"));
+        assert_eq!(html.matches("<svg").count(), 2, "bar and pie charts render as inline SVG");
+        assert!(html.contains("<path d=\"M 75 75"));
+        assert!(html.contains("background: #ffc7ce"), "heat map cells are shaded by level");
+        for external in ["<link", "<script", "src=\"http", "https://"] {
+            assert!(!html.contains(external), "unexpected external reference {}", external);
+        }
+    }
+
+    #[test]
+    fn test_html_escapes_content() {
+        let html = render_html(&sample_report());
+        assert!(html.contains("<title>CAPA &lt;Detail&gt; &amp; Summary</title>"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+    }
+
+    #[test]
+    fn test_placeholders_in_content_are_not_expanded() {
+        let report = ReportBuilder::new("{{body}}").with_footer_note("{{styles}}").build();
+        let html = render_html(&report);
+        assert!(html.contains("<title>{{body}}</title>"));
+        assert!(html.contains("<span class=\"note\">{{styles}}</span>"));
+    }
+
+    #[test]
+    fn test_localized_labels() {
+        let mut report = sample_report();
+        report.locale = Locale::De;
+        let html = render_html(&report);
+        assert!(html.contains("<html lang=\"de\">"));
+        assert!(html.contains("<h2>Inhaltsverzeichnis</h2>"));
+        assert!(html.contains("<p>Organisation: Acme Medical</p>"));
+        assert!(!html.contains("Generated:"));
+    }
+
+    #[test]
+    fn test_generated_timestamp_uses_display_timezone() {
+        let mut report = sample_report();
+        report.generated_on = Utc.with_ymd_and_hms(2025, 1, 31, 23, 0, 0).unwrap();
+        report.display_timezone = "+02:00".parse().unwrap();
+        assert!(render_html(&report).contains("Generated: 2025-02-01 01:00 +02:00"));
+    }
+
+    #[test]
+    fn test_render_html_report_writes_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("report.html");
+        render_html_report(&sample_report(), &path).unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains("</html>"));
+        assert!(!path.with_extension("tmp").exists());
+    }
+}
@@ -0,0 +1,444 @@
+//! # i18n - Localized UI, API and Report Strings
+//!
+//! Gettext-style message catalogs keyed by stable message ids. The deployment
+//! locale is selected with `application.locale` in the configuration; ids
+//! missing from a catalog fall back to English, so a partial translation
+//! never leaves a label blank. Placeholders use `{name}` syntax.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::QmsError;
+
+/// Supported display locales.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    /// English (source language)
+    #[default]
+    En,
+    /// German
+    De,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::En, Locale::De];
+
+    /// BCP 47 language tag, e.g. `"de"`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::De => "de",
+        }
+    }
+
+    /// `strftime` pattern for dates in this locale.
+    pub fn date_format(&self) -> &'static str {
+        match self {
+            Locale::En => "%Y-%m-%d",
+            Locale::De => "%d.%m.%Y",
+        }
+    }
+
+    fn catalog(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::En => EN,
+            Locale::De => DE,
+        }
+    }
+}
+
+impl std::str::FromStr for Locale {
+    type Err = QmsError;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        Locale::ALL
+            .into_iter()
+            .find(|locale| locale.code().eq_ignore_ascii_case(s))
+            .ok_or_else(|| QmsError::Configuration {
+                message: format!("Unsupported locale: {}", s),
+            })
+    }
+}
+
+/// Translate `id`, falling back to English and then to the id itself.
+pub fn tr(locale: Locale, id: &str) -> &str {
+    lookup(locale.catalog(), id).or_else(|| lookup(EN, id)).unwrap_or(id)
+}
+
+/// Translate `id` and substitute `{name}` placeholders from `args`.
+pub fn tr_args(locale: Locale, id: &str, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+    args.iter().fold(tr(locale, id).to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), &value.to_string())
+    })
+}
+
+/// Localized message for an error; in English this equals its `Display` text.
+pub fn error_message(locale: Locale, error: &QmsError) -> String {
+    match error {
+        QmsError::Configuration { message } => tr_args(locale, "error.configuration", &[("message", message)]),
+        QmsError::Database { message } => tr_args(locale, "error.database", &[("message", message)]),
+        QmsError::Validation { field, message } | QmsError::ValidationError { field, message } => {
+            tr_args(locale, "error.validation", &[("field", field), ("message", message)])
+        }
+        QmsError::ValidationErrors { errors } => errors
+            .iter()
+            .map(|(field, message)| tr_args(locale, "error.validation", &[("field", &field), ("message", &message)]))
+            .collect::<Vec<_>>()
+            .join("; "),
+        QmsError::NotFound { resource, id } => {
+            tr_args(locale, "error.not_found", &[("resource", resource), ("id", id)])
+        }
+        QmsError::Conflict { resource, id, expected, actual } => tr_args(
+            locale,
+            "error.conflict",
+            &[("resource", resource), ("id", id), ("expected", expected), ("actual", actual)],
+        ),
+        QmsError::AuditTrail { message } => tr_args(locale, "error.audit_trail", &[("message", message)]),
+        QmsError::Security { message } => tr_args(locale, "error.security", &[("message", message)]),
+        QmsError::DocumentControl { message } => tr_args(locale, "error.document_control", &[("message", message)]),
+        QmsError::UserInterface { message } => tr_args(locale, "error.user_interface", &[("message", message)]),
+        QmsError::Encryption { message } => tr_args(locale, "error.encryption", &[("message", message)]),
+        QmsError::FileSystem { path, message } => {
+            tr_args(locale, "error.file_system", &[("path", path), ("message", message)])
+        }
+        QmsError::Network { message } => tr_args(locale, "error.network", &[("message", message)]),
+        QmsError::Serialization { message } => tr_args(locale, "error.serialization", &[("message", message)]),
+        QmsError::Application { message } => tr_args(locale, "error.application", &[("message", message)]),
+    }
+}
+
+fn lookup(catalog: &'static [(&'static str, &'static str)], id: &str) -> Option<&'static str> {
+    catalog.iter().find(|(key, _)| *key == id).map(|(_, text)| *text)
+}
+
+const EN: &[(&str, &str)] = &[
+    // TUI
+    ("tui.title", "QMS - FDA Compliant"),
+    ("tui.tab.dashboard", "Dashboard"),
+    ("tui.tab.documents", "Documents"),
+    ("tui.tab.audit_trail", "Audit Trail"),
+    ("tui.tab.capa", "CAPA"),
+    ("tui.tab.suppliers", "Suppliers"),
+    ("tui.tab.training", "Training"),
+    ("tui.tab.reports", "Reports"),
+    ("tui.tab.search", "Search"),
+    ("tui.tab.jobs", "Jobs"),
+    ("tui.tab.risk", "Risk"),
+    ("tui.tab.records", "Records"),
+    ("tui.block.system_status", "System Status"),
+    ("tui.block.document_control", "Document Control"),
+    ("tui.block.audit_trail", "Audit Trail"),
+    ("tui.block.change_history", "Change History - {record}"),
+    ("tui.block.audit_tail_live", "Audit Trail - live (t stops, p pauses, f filters)"),
+    ("tui.block.audit_tail_paused", "Audit Trail - paused, {pending} new (p resumes)"),
+    ("tui.audit_tail.filter", "Filter: user:<id> action:<text> - Enter applies"),
+    ("tui.block.reports", "Reports"),
+    ("tui.block.capa", "CAPA Management"),
+    ("tui.block.suppliers", "Supplier Management"),
+    ("tui.block.training", "Training Records"),
+    ("tui.block.invalid_fields", "Invalid Fields"),
+    ("tui.block.environmental_trend", "{area} {parameter} ({excursions} excursions)"),
+    ("tui.block.audit_findings", "Audit Finding Commitments"),
+    ("tui.findings.overdue", "Overdue: {count}"),
+    ("tui.block.mdr_clocks", "MDR Reporting Deadlines"),
+    ("tui.mdr.due_in", "{report} for event {event}: due {due} ({days} day(s) left)"),
+    ("tui.mdr.overdue", "{report} for event {event}: overdue since {due}"),
+    ("tui.storage.warning", "Disk space low: {detail}"),
+    ("tui.storage.critical", "Disk space critical: {detail}"),
+    ("mir.field.report_type", "Report type"),
+    ("mir.field.incident_type", "Incident type"),
+    ("mir.field.report_date", "Report date"),
+    ("mir.field.awareness_date", "Manufacturer awareness date"),
+    ("mir.field.reporting_deadline", "Reporting deadline"),
+    ("mir.field.manufacturer_reference", "Manufacturer reference number"),
+    ("mir.field.manufacturer_name", "Manufacturer name"),
+    ("mir.field.srn", "Single registration number (SRN)"),
+    ("mir.field.device_name", "Device name"),
+    ("mir.field.device_model", "Model / catalogue number"),
+    ("mir.field.basic_udi_di", "Basic UDI-DI"),
+    ("mir.field.udi_di", "UDI-DI"),
+    ("mir.field.risk_class", "Risk class"),
+    ("mir.field.nb_certificate", "Notified body certificate"),
+    ("mir.field.incident_country", "Country of incident"),
+    ("mir.field.incident_date", "Incident date"),
+    ("mir.field.severity", "Severity"),
+    ("mir.field.description", "Incident description"),
+    ("mir.report_type.initial", "Initial report"),
+    ("mir.report_type.follow_up", "Follow-up report"),
+    ("mir.report_type.final", "Final report"),
+    ("mir.report_type.initial_and_final", "Combined initial and final report"),
+    ("mir.incident_type.serious_public_health_threat", "Serious public health threat"),
+    ("mir.incident_type.death", "Death"),
+    ("mir.incident_type.serious_deterioration", "Unanticipated serious deterioration of health"),
+    ("mir.incident_type.other_serious_incident", "Other serious incident"),
+    ("tui.block.kpis", "KPI Status"),
+    ("tui.kpi.line", "{label}: {actual} (target {target}) {change}"),
+    ("tui.block.search", "Search - type, Enter to run, then a for actions"),
+    ("tui.block.search_results", "Results ({count})"),
+    ("tui.search.hint", "Enter a keyword, record number or id"),
+    ("tui.search.no_results", "No matching records"),
+    ("tui.block.jobs", "Background Jobs - Enter runs the selected job"),
+    ("tui.block.risk_initial", "Initial Risk Heat Map - Enter/click lists assessments, r shows residual"),
+    ("tui.block.risk_residual", "Residual Risk Heat Map - Enter/click lists assessments, r shows initial"),
+    ("tui.block.risk_cell", "Assessments - {severity} / {probability}"),
+    ("tui.jobs.line", "{job}: last run {last_run} ({duration} ms, {outcome}), next {next_run}"),
+    ("tui.jobs.never_run", "{job}: never run, next {next_run}"),
+    ("tui.jobs.none", "No background jobs available"),
+    ("tui.block.records", "Records of integrator-defined types"),
+    ("tui.records.none", "No records yet"),
+    ("tui.jobs.started", "Running {job}..."),
+    ("tui.jobs.finished", "{job} {outcome} in {duration} ms: {detail}"),
+    ("tui.jobs.failed", "{job} could not be run: {message}"),
+    ("tui.jobs.not_permitted", "Your role does not permit running {job}"),
+    ("tui.block.actions", "Actions - {record}"),
+    ("tui.actions.change_status", "Change status to {status}"),
+    ("tui.actions.reassign", "Reassign"),
+    ("tui.actions.add_note", "Add note"),
+    ("tui.actions.export_pdf", "Export PDF"),
+    ("tui.actions.prompt.assignee", "New assignee - Enter to confirm, Esc to go back"),
+    ("tui.actions.prompt.note", "Note - Enter to save, Esc to go back"),
+    ("tui.actions.prompt.reason", "Reason for the change - Enter to confirm, Esc to go back"),
+    ("tui.actions.done", "Done: {detail}"),
+    ("tui.actions.failed", "{action} failed: {message}"),
+    ("tui.actions.not_permitted", "Your role does not permit: {action}"),
+    ("tui.actions.unavailable", "Record actions need the local database"),
+    ("tui.block.details", "Details - Ctrl+←/→ resize, F2 hides"),
+    ("tui.detail.kind", "Type"),
+    ("tui.detail.reference", "Reference"),
+    ("tui.detail.title", "Title"),
+    ("tui.detail.status", "Status"),
+    ("tui.detail.id", "Id"),
+    ("tui.detail.assignee", "Assignee"),
+    ("tui.detail.due_date", "Due"),
+    ("tui.detail.job", "Job"),
+    ("tui.detail.interval", "Interval"),
+    ("tui.detail.next_run", "Next run"),
+    ("tui.detail.last_run", "Last run"),
+    ("tui.detail.triggered_by", "Triggered by"),
+    ("tui.detail.outcome", "Outcome"),
+    ("tui.detail.result", "Result"),
+    ("tui.detail.severity", "Severity"),
+    ("tui.detail.probability", "Probability"),
+    ("tui.detail.risk_level", "Risk level"),
+    ("tui.detail.acceptability", "Acceptability"),
+    ("tui.detail.assessments", "Assessments"),
+    ("tui.detail.device", "Device"),
+    ("tui.detail.hazard", "Hazard"),
+    ("tui.offline.local", "API unavailable - showing local database values"),
+    ("tui.offline.cached", "API unavailable - showing cached values"),
+    ("tui.offline.last_update", "last live update {seconds}s ago"),
+    ("tui.offline.never_updated", "no live data yet"),
+    // API
+    ("api.unauthorized", "Unauthorized"),
+    ("api.forbidden", "Your role does not permit this operation"),
+    ("api.csrf_rejected", "Missing or invalid CSRF token"),
+    ("api.network_rejected", "Requests from this address are not permitted"),
+    ("api.precondition_required", "An If-Match header with the record's ETag is required"),
+    ("api.record_not_yet_created", "Record did not exist at the requested time"),
+    // Reports
+    ("report.organization", "Organization: {name}"),
+    ("report.prepared_by", "Prepared by: {name}"),
+    ("report.generated", "Generated: {timestamp}"),
+    ("report.version", "QMSrs version {version}"),
+    ("report.cover", "Cover"),
+    ("report.table_of_contents", "Table of Contents"),
+    ("report.page_of", "Page {page} of {count}"),
+    ("report.no_data", "No data recorded"),
+    // Errors (English must match `QmsError`'s Display text)
+    ("error.configuration", "Configuration error: {message}"),
+    ("error.database", "Database error: {message}"),
+    ("error.validation", "Validation error in field '{field}': {message}"),
+    ("error.not_found", "Resource '{resource}' with ID '{id}' not found"),
+    ("error.conflict", "Conflict: {resource} '{id}' was changed by someone else (expected version {expected}, current version {actual})"),
+    ("error.audit_trail", "Audit trail error: {message}"),
+    ("error.security", "Security error: {message}"),
+    ("error.document_control", "Document control error: {message}"),
+    ("error.user_interface", "User interface error: {message}"),
+    ("error.encryption", "Encryption error: {message}"),
+    ("error.file_system", "File system error: {path} - {message}"),
+    ("error.network", "Network error: {message}"),
+    ("error.serialization", "Serialization error: {message}"),
+    ("error.application", "Application error: {message}"),
+];
+
+const DE: &[(&str, &str)] = &[
+    // TUI
+    ("tui.title", "QMS - FDA-konform"),
+    ("tui.tab.dashboard", "Übersicht"),
+    ("tui.tab.documents", "Dokumente"),
+    ("tui.tab.audit_trail", "Audit-Trail"),
+    ("tui.tab.capa", "CAPA"),
+    ("tui.tab.suppliers", "Lieferanten"),
+    ("tui.tab.training", "Schulungen"),
+    ("tui.tab.reports", "Berichte"),
+    ("tui.tab.search", "Suche"),
+    ("tui.tab.jobs", "Jobs"),
+    ("tui.tab.risk", "Risiko"),
+    ("tui.tab.records", "Datensätze"),
+    ("tui.block.system_status", "Systemstatus"),
+    ("tui.block.document_control", "Dokumentenlenkung"),
+    ("tui.block.audit_trail", "Audit-Trail"),
+    ("tui.block.change_history", "Änderungshistorie - {record}"),
+    ("tui.block.audit_tail_live", "Audit-Trail - live (t beendet, p pausiert, f filtert)"),
+    ("tui.block.audit_tail_paused", "Audit-Trail - pausiert, {pending} neu (p setzt fort)"),
+    ("tui.audit_tail.filter", "Filter: user:<id> action:<text> - Enter übernimmt"),
+    ("tui.block.reports", "Berichte"),
+    ("tui.block.capa", "CAPA-Verwaltung"),
+    ("tui.block.suppliers", "Lieferantenmanagement"),
+    ("tui.block.training", "Schulungsnachweise"),
+    ("tui.block.invalid_fields", "Ungültige Felder"),
+    ("tui.block.environmental_trend", "{area} {parameter} ({excursions} Abweichungen)"),
+    ("tui.block.audit_findings", "Zusagen zu Auditfeststellungen"),
+    ("tui.findings.overdue", "Überfällig: {count}"),
+    ("tui.block.mdr_clocks", "MDR-Meldefristen"),
+    ("tui.mdr.due_in", "{report} zu Ereignis {event}: fällig am {due} (noch {days} Tag(e))"),
+    ("tui.mdr.overdue", "{report} zu Ereignis {event}: überfällig seit {due}"),
+    ("tui.storage.warning", "Speicherplatz knapp: {detail}"),
+    ("tui.storage.critical", "Speicherplatz kritisch: {detail}"),
+    ("mir.field.report_type", "Art der Meldung"),
+    ("mir.field.incident_type", "Art des Vorkommnisses"),
+    ("mir.field.report_date", "Meldedatum"),
+    ("mir.field.awareness_date", "Datum der Kenntnisnahme"),
+    ("mir.field.reporting_deadline", "Meldefrist"),
+    ("mir.field.manufacturer_reference", "Referenznummer des Herstellers"),
+    ("mir.field.manufacturer_name", "Name des Herstellers"),
+    ("mir.field.srn", "Einmalige Registrierungsnummer (SRN)"),
+    ("mir.field.device_name", "Produktbezeichnung"),
+    ("mir.field.device_model", "Modell / Katalognummer"),
+    ("mir.field.basic_udi_di", "Basis-UDI-DI"),
+    ("mir.field.udi_di", "UDI-DI"),
+    ("mir.field.risk_class", "Risikoklasse"),
+    ("mir.field.nb_certificate", "Zertifikat der Benannten Stelle"),
+    ("mir.field.incident_country", "Land des Vorkommnisses"),
+    ("mir.field.incident_date", "Datum des Vorkommnisses"),
+    ("mir.field.severity", "Schweregrad"),
+    ("mir.field.description", "Beschreibung des Vorkommnisses"),
+    ("mir.report_type.initial", "Erstmeldung"),
+    ("mir.report_type.follow_up", "Folgemeldung"),
+    ("mir.report_type.final", "Abschlussmeldung"),
+    ("mir.report_type.initial_and_final", "Kombinierte Erst- und Abschlussmeldung"),
+    ("mir.incident_type.serious_public_health_threat", "Schwerwiegende Gefahr für die öffentliche Gesundheit"),
+    ("mir.incident_type.death", "Tod"),
+    ("mir.incident_type.serious_deterioration", "Unerwartete schwerwiegende Verschlechterung des Gesundheitszustands"),
+    ("mir.incident_type.other_serious_incident", "Sonstiges schwerwiegendes Vorkommnis"),
+    ("tui.block.kpis", "KPI-Status"),
+    ("tui.kpi.line", "{label}: {actual} (Ziel {target}) {change}"),
+    ("tui.block.search", "Suche - tippen, Enter startet, dann a für Aktionen"),
+    ("tui.block.search_results", "Ergebnisse ({count})"),
+    ("tui.search.hint", "Stichwort, Datensatznummer oder ID eingeben"),
+    ("tui.search.no_results", "Keine passenden Datensätze"),
+    ("tui.block.jobs", "Hintergrundjobs - Enter startet den gewählten Job"),
+    ("tui.block.risk_initial", "Risikomatrix vor Maßnahmen - Enter/Klick listet Bewertungen, r zeigt Restrisiko"),
+    ("tui.block.risk_residual", "Restrisikomatrix - Enter/Klick listet Bewertungen, r zeigt Anfangsrisiko"),
+    ("tui.block.risk_cell", "Bewertungen - {severity} / {probability}"),
+    ("tui.jobs.line", "{job}: letzter Lauf {last_run} ({duration} ms, {outcome}), nächster {next_run}"),
+    ("tui.jobs.never_run", "{job}: noch nie gelaufen, nächster {next_run}"),
+    ("tui.jobs.none", "Keine Hintergrundjobs verfügbar"),
+    ("tui.block.records", "Datensätze integratorspezifischer Typen"),
+    ("tui.records.none", "Noch keine Datensätze"),
+    ("tui.jobs.started", "{job} läuft..."),
+    ("tui.jobs.finished", "{job} {outcome} in {duration} ms: {detail}"),
+    ("tui.jobs.failed", "{job} konnte nicht ausgeführt werden: {message}"),
+    ("tui.jobs.not_permitted", "Ihre Rolle erlaubt nicht, {job} auszuführen"),
+    ("tui.block.actions", "Aktionen - {record}"),
+    ("tui.actions.change_status", "Status ändern auf {status}"),
+    ("tui.actions.reassign", "Neu zuweisen"),
+    ("tui.actions.add_note", "Notiz hinzufügen"),
+    ("tui.actions.export_pdf", "Als PDF exportieren"),
+    ("tui.actions.prompt.assignee", "Neue Zuständigkeit - Enter bestätigt, Esc zurück"),
+    ("tui.actions.prompt.note", "Notiz - Enter speichert, Esc zurück"),
+    ("tui.actions.prompt.reason", "Grund der Änderung - Enter bestätigt, Esc zurück"),
+    ("tui.actions.done", "Erledigt: {detail}"),
+    ("tui.actions.failed", "{action} fehlgeschlagen: {message}"),
+    ("tui.actions.not_permitted", "Ihre Rolle erlaubt nicht: {action}"),
+    ("tui.actions.unavailable", "Aktionen benötigen die lokale Datenbank"),
+    ("tui.block.details", "Details - Strg+←/→ ändert Breite, F2 blendet aus"),
+    ("tui.detail.kind", "Art"),
+    ("tui.detail.reference", "Referenz"),
+    ("tui.detail.title", "Titel"),
+    ("tui.detail.status", "Status"),
+    ("tui.detail.id", "Id"),
+    ("tui.detail.assignee", "Zuständig"),
+    ("tui.detail.due_date", "Fällig"),
+    ("tui.detail.job", "Job"),
+    ("tui.detail.interval", "Intervall"),
+    ("tui.detail.next_run", "Nächster Lauf"),
+    ("tui.detail.last_run", "Letzter Lauf"),
+    ("tui.detail.triggered_by", "Ausgelöst von"),
+    ("tui.detail.outcome", "Ergebnis"),
+    ("tui.detail.result", "Meldung"),
+    ("tui.detail.severity", "Schweregrad"),
+    ("tui.detail.probability", "Wahrscheinlichkeit"),
+    ("tui.detail.risk_level", "Risikostufe"),
+    ("tui.detail.acceptability", "Akzeptanz"),
+    ("tui.detail.assessments", "Bewertungen"),
+    ("tui.detail.device", "Produkt"),
+    ("tui.detail.hazard", "Gefährdung"),
+    ("tui.offline.local", "API nicht erreichbar - Werte aus lokaler Datenbank"),
+    ("tui.offline.cached", "API nicht erreichbar - zwischengespeicherte Werte"),
+    ("tui.offline.last_update", "letzte Aktualisierung vor {seconds}s"),
+    ("tui.offline.never_updated", "noch keine Live-Daten"),
+    // API
+    ("api.unauthorized", "Nicht autorisiert"),
+    ("api.forbidden", "Ihre Rolle erlaubt diesen Vorgang nicht"),
+    ("api.csrf_rejected", "CSRF-Token fehlt oder ist ungültig"),
+    ("api.network_rejected", "Anfragen von dieser Adresse sind nicht zulässig"),
+    ("api.precondition_required", "Ein If-Match-Header mit dem ETag des Datensatzes ist erforderlich"),
+    ("api.record_not_yet_created", "Der Datensatz existierte zum angefragten Zeitpunkt nicht"),
+    // Reports
+    ("report.organization", "Organisation: {name}"),
+    ("report.prepared_by", "Erstellt von: {name}"),
+    ("report.generated", "Erzeugt: {timestamp}"),
+    ("report.version", "QMSrs Version {version}"),
+    ("report.cover", "Deckblatt"),
+    ("report.table_of_contents", "Inhaltsverzeichnis"),
+    ("report.page_of", "Seite {page} von {count}"),
+    ("report.no_data", "Keine Daten erfasst"),
+    // Errors
+    ("error.configuration", "Konfigurationsfehler: {message}"),
+    ("error.database", "Datenbankfehler: {message}"),
+    ("error.validation", "Validierungsfehler im Feld '{field}': {message}"),
+    ("error.not_found", "Ressource '{resource}' mit ID '{id}' nicht gefunden"),
+    ("error.conflict", "Konflikt: {resource} '{id}' wurde zwischenzeitlich geändert (erwartete Version {expected}, aktuelle Version {actual})"),
+    ("error.audit_trail", "Audit-Trail-Fehler: {message}"),
+    ("error.security", "Sicherheitsfehler: {message}"),
+    ("error.document_control", "Fehler der Dokumentenlenkung: {message}"),
+    ("error.user_interface", "Fehler der Benutzeroberfläche: {message}"),
+    ("error.encryption", "Verschlüsselungsfehler: {message}"),
+    ("error.file_system", "Dateisystemfehler: {path} - {message}"),
+    ("error.network", "Netzwerkfehler: {message}"),
+    ("error.serialization", "Serialisierungsfehler: {message}"),
+    ("error.application", "Anwendungsfehler: {message}"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_locale_translates_every_message() {
+        for locale in Locale::ALL {
+            for (id, _) in EN {
+                assert!(lookup(locale.catalog(), id).is_some(), "{} is missing {}", locale.code(), id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_lookup_and_placeholders() {
+        assert_eq!(tr(Locale::De, "tui.tab.suppliers"), "Lieferanten");
+        assert_eq!(tr(Locale::De, "no.such.id"), "no.such.id");
+        assert_eq!(tr_args(Locale::De, "report.page_of", &[("page", &2), ("count", &5)]), "Seite 2 von 5");
+        assert_eq!("DE".parse::<Locale>().unwrap(), Locale::De);
+        assert!("xx".parse::<Locale>().is_err());
+    }
+
+    #[test]
+    fn test_error_messages() {
+        let error = QmsError::NotFound { resource: "supplier".into(), id: "42".into() };
+        assert_eq!(error_message(Locale::En, &error), error.to_string());
+        assert_eq!(error_message(Locale::De, &error), "Ressource 'supplier' mit ID '42' nicht gefunden");
+        let error = QmsError::FileSystem { path: "/tmp/x".into(), message: "denied".into() };
+        assert_eq!(error_message(Locale::En, &error), error.to_string());
+    }
+}
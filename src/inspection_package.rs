@@ -0,0 +1,605 @@
+//! # Inspection Package - Inspection-Ready Bundles per Subsystem
+//!
+//! Before or during an FDA inspection or notified body audit the inspector
+//! asks for one quality subsystem at a time ("show me your CAPA system for
+//! the last 12 months"). `qmsrs inspection-package <subsystem> <dir>`
+//! assembles that request into one ZIP archive:
+//!
+//! - `procedures.csv`: effective procedures governing the subsystem
+//! - `records/<table>.csv`: the subsystem's records created in the period
+//! - `metrics.json`: record counts by status and audit activity
+//! - `audit_trail.csv`: audit trail entries for the subsystem's records
+//! - `index.pdf`: signed index document describing the package
+//! - `MANIFEST.sha256` and `MANIFEST.sig`: checksums of every other file and
+//!   the system key's Ed25519 signature over that listing
+//!
+//! No compression library is available, so entries are written uncompressed
+//! ("stored"), which every ZIP reader accepts.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc};
+use rusqlite::params;
+use rusqlite::types::ValueRef;
+use serde::Serialize;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::keystore::{sha256_hex, Keystore};
+use crate::report::{ReportBuilder, ReportSection, ReportTable};
+use crate::report_signature::render_signed_report;
+use crate::vigilance_export::csv_field;
+
+/// `<sha256>  <file>` for every other file of the package.
+pub const MANIFEST_FILE: &str = "MANIFEST.sha256";
+/// Base64 Ed25519 signature over [`MANIFEST_FILE`].
+pub const SIGNATURE_FILE: &str = "MANIFEST.sig";
+/// Signed PDF index of the package.
+pub const INDEX_FILE: &str = "index.pdf";
+
+/// Procedure document types listed in `procedures.csv`.
+const PROCEDURE_TYPES: [&str; 3] = ["SOP", "WorkInstruction", "Policy"];
+
+/// Quality subsystem an inspection package covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Subsystem {
+    Capa,
+    Documents,
+    Training,
+    Suppliers,
+    PostMarket,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 5] =
+        [Subsystem::Capa, Subsystem::Documents, Subsystem::Training, Subsystem::Suppliers, Subsystem::PostMarket];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Subsystem::Capa => "capa",
+            Subsystem::Documents => "documents",
+            Subsystem::Training => "training",
+            Subsystem::Suppliers => "suppliers",
+            Subsystem::PostMarket => "post_market",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|item| item.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Subsystem::Capa => "Corrective and Preventive Action",
+            Subsystem::Documents => "Document Control",
+            Subsystem::Training => "Training",
+            Subsystem::Suppliers => "Purchasing Controls",
+            Subsystem::PostMarket => "Post-Market Surveillance",
+        }
+    }
+
+    /// Record tables exported for the subsystem with the column dating each
+    /// record.
+    fn record_tables(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Subsystem::Capa => &[("capa_records", "created_at"), ("capa_actions", "created_at")],
+            Subsystem::Documents => &[("documents", "created_at")],
+            Subsystem::Training => &[("training_records", "created_at")],
+            Subsystem::Suppliers => &[("suppliers", "created_at")],
+            Subsystem::PostMarket => &[("adverse_events", "created_at"), ("mdr_clocks", "started_at")],
+        }
+    }
+
+    /// Audit trail resource prefixes of the subsystem's records.
+    fn audit_resources(&self) -> &'static [&'static str] {
+        match self {
+            Subsystem::Capa => &["capa:"],
+            Subsystem::Documents => &["document:"],
+            Subsystem::Training => &["training:"],
+            Subsystem::Suppliers => &["supplier:"],
+            Subsystem::PostMarket => &["adverse_event:", "adverse_events:"],
+        }
+    }
+
+    /// Title keywords identifying the procedures that govern the subsystem.
+    fn procedure_keywords(&self) -> &'static [&'static str] {
+        match self {
+            Subsystem::Capa => &["CAPA", "Corrective", "Preventive", "Nonconform"],
+            Subsystem::Documents => &["Document", "Record"],
+            Subsystem::Training => &["Training", "Competence"],
+            Subsystem::Suppliers => &["Supplier", "Purchasing"],
+            Subsystem::PostMarket => &["Complaint", "Vigilance", "Post-Market", "Adverse", "MDR"],
+        }
+    }
+}
+
+/// Column of `table` summarized by value in `metrics.json`.
+fn status_column(table: &str) -> &'static str {
+    match table {
+        "suppliers" => "qualification_status",
+        "adverse_events" => "severity",
+        "mdr_clocks" => "kind",
+        _ => "status",
+    }
+}
+
+/// Scope of an inspection package: one subsystem over a date range (inclusive).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct InspectionScope {
+    pub subsystem: Subsystem,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl InspectionScope {
+    /// The `months` months up to and including `to`.
+    pub fn last_months(subsystem: Subsystem, months: u32, to: NaiveDate) -> Result<Self> {
+        let from = to.checked_sub_months(chrono::Months::new(months)).ok_or_else(|| QmsError::Validation {
+            field: "months".to_string(),
+            message: format!("{} months before {} is out of range", months, to),
+        })?;
+        Self::new(subsystem, from, to)
+    }
+
+    pub fn new(subsystem: Subsystem, from: NaiveDate, to: NaiveDate) -> Result<Self> {
+        if from > to {
+            return Err(QmsError::Validation {
+                field: "from".to_string(),
+                message: format!("Period start {} is after its end {}", from, to),
+            });
+        }
+        Ok(Self { subsystem, from, to })
+    }
+
+    /// Archive name, e.g. `inspection-capa-2025-10-15-to-2026-10-15.zip`.
+    pub fn file_name(&self) -> String {
+        format!("inspection-{}-{}-to-{}.zip", self.subsystem.as_str(), self.from, self.to)
+    }
+}
+
+/// Record counts of one exported table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TableMetrics {
+    pub table: String,
+    pub records: usize,
+    /// Record count per value of the status column
+    pub by_status: BTreeMap<String, usize>,
+}
+
+/// Contents of `metrics.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackageMetrics {
+    pub scope: InspectionScope,
+    pub procedures: usize,
+    pub tables: Vec<TableMetrics>,
+    pub audit_entries: usize,
+    pub generated_at: DateTime<Utc>,
+    pub generated_by: String,
+}
+
+/// The written package.
+#[derive(Debug, Clone)]
+pub struct InspectionPackage {
+    pub path: PathBuf,
+    pub metrics: PackageMetrics,
+    /// `(file name, sha256)` of every file covered by the manifest
+    pub manifest: Vec<(String, String)>,
+    /// Base64 signature over the manifest listing
+    pub signature: String,
+}
+
+/// Assemble the package for `scope` into a ZIP archive in `output_dir`.
+pub fn generate_inspection_package(
+    db: &Database,
+    keystore: &Keystore,
+    scope: &InspectionScope,
+    output_dir: &Path,
+    generated_by: &str,
+) -> Result<InspectionPackage> {
+    let io_error = |path: &Path, e: std::io::Error| QmsError::FileSystem {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    };
+    fs::create_dir_all(output_dir).map_err(|e| io_error(output_dir, e))?;
+    let path = output_dir.join(scope.file_name());
+    if path.exists() {
+        return Err(QmsError::Validation {
+            field: "output".to_string(),
+            message: format!("Inspection package {} already exists", path.display()),
+        });
+    }
+    let generated_at = Utc::now();
+    let from = scope.from.to_string();
+    let to = scope.to.to_string();
+
+    let (procedures, tables, audit) = db.with_connection(|conn| {
+        // One read transaction, so records, metrics and audit extract agree
+        let tx = conn.unchecked_transaction()?;
+        let procedures = procedures_csv(&tx, scope.subsystem)?;
+        let tables = scope
+            .subsystem
+            .record_tables()
+            .iter()
+            .map(|(table, dated_by)| records_csv(&tx, table, dated_by, &from, &to))
+            .collect::<Result<Vec<_>>>()?;
+        let audit = audit_csv(&tx, scope.subsystem, &from, &to)?;
+        tx.commit()?;
+        Ok((procedures, tables, audit))
+    })?;
+
+    let metrics = PackageMetrics {
+        scope: *scope,
+        procedures: procedures.1,
+        tables: tables.iter().map(|(metrics, _)| metrics.clone()).collect(),
+        audit_entries: audit.1,
+        generated_at,
+        generated_by: generated_by.to_string(),
+    };
+    let mut files: Vec<(String, Vec<u8>)> = vec![("procedures.csv".to_string(), procedures.0.into_bytes())];
+    for (metrics, csv) in tables {
+        files.push((format!("records/{}.csv", metrics.table), csv.into_bytes()));
+    }
+    files.push(("metrics.json".to_string(), serde_json::to_vec_pretty(&metrics)?));
+    files.push(("audit_trail.csv".to_string(), audit.0.into_bytes()));
+
+    // The index is rendered and signed through the report registry, so
+    // `verify-report` also works on the extracted index.pdf.
+    let index_path = output_dir.join(format!("{}.{}", scope.file_name(), INDEX_FILE));
+    let index = build_index(scope, &metrics, &files, generated_by);
+    render_signed_report(&index, &index_path, keystore, db, generated_by)?;
+    let index_bytes = fs::read(&index_path).map_err(|e| io_error(&index_path, e))?;
+    fs::remove_file(&index_path).map_err(|e| io_error(&index_path, e))?;
+    files.push((INDEX_FILE.to_string(), index_bytes));
+
+    let manifest: Vec<(String, String)> = files.iter().map(|(name, data)| (name.clone(), sha256_hex(data))).collect();
+    let listing: String = manifest.iter().map(|(file, sha256)| format!("{}  {}\n", sha256, file)).collect();
+    let signature = general_purpose::STANDARD.encode(keystore.sign(listing.as_bytes()));
+    files.push((MANIFEST_FILE.to_string(), listing.into_bytes()));
+    files.push((SIGNATURE_FILE.to_string(), format!("{}\n", signature).into_bytes()));
+
+    let mut archive = ZipWriter::new(generated_at);
+    for (name, data) in &files {
+        archive.add(name, data);
+    }
+    fs::write(&path, archive.finish()).map_err(|e| io_error(&path, e))?;
+
+    AuditManager::new(db.clone()).log_action(
+        generated_by,
+        "inspection_package_generated",
+        &format!("inspection_package:{}", scope.subsystem.as_str()),
+        "Success",
+        Some(
+            serde_json::json!({
+                "file": path.display().to_string(),
+                "from": from,
+                "to": to,
+                "key_id": keystore.key_id(),
+                "files": manifest.len() + 2,
+            })
+            .to_string(),
+        ),
+    )?;
+    Ok(InspectionPackage { path, metrics, manifest, signature })
+}
+
+fn build_index(
+    scope: &InspectionScope,
+    metrics: &PackageMetrics,
+    files: &[(String, Vec<u8>)],
+    generated_by: &str,
+) -> crate::report::Report {
+    let mut records = ReportTable::new(vec!["Table", "Records", "By Status"]).with_column_weights(vec![1.5, 0.8, 3.0]);
+    for table in &metrics.tables {
+        let by_status: Vec<String> = table.by_status.iter().map(|(status, n)| format!("{}: {}", status, n)).collect();
+        records = records.with_row(vec![table.table.clone(), table.records.to_string(), by_status.join(", ")]);
+    }
+    let contents = files
+        .iter()
+        .fold(ReportTable::new(vec!["File", "SHA-256"]).with_column_weights(vec![1.2, 3.0]), |table, (name, data)| {
+            table.with_row(vec![name.clone(), sha256_hex(data)])
+        });
+    ReportBuilder::new(format!("Inspection Package: {}", scope.subsystem.label()))
+        .with_subtitle(format!("{} to {}", scope.from, scope.to))
+        .with_prepared_by(generated_by)
+        .with_generated_on(metrics.generated_at)
+        .with_section(ReportSection::new("Scope").key_values(vec![
+            ("Subsystem", scope.subsystem.label().to_string()),
+            ("Period", format!("{} to {}", scope.from, scope.to)),
+            ("Procedures", metrics.procedures.to_string()),
+            ("Audit Trail Entries", metrics.audit_entries.to_string()),
+        ]))
+        .with_section(ReportSection::new("Records").table(records))
+        .with_section(
+            ReportSection::new("Contents")
+                .paragraph(format!(
+                    "Checksums are also listed in {}, signed with the system key in {}.",
+                    MANIFEST_FILE, SIGNATURE_FILE
+                ))
+                .table(contents),
+        )
+        .build()
+}
+
+/// Effective procedures whose title matches the subsystem's keywords.
+fn procedures_csv(conn: &rusqlite::Connection, subsystem: Subsystem) -> Result<(String, usize)> {
+    let mut csv = "document_number,title,version,document_type,effective_date,content_hash\n".to_string();
+    let mut stmt = conn.prepare(
+        "SELECT document_number, title, version, document_type, effective_date, content_hash
+         FROM documents WHERE status = 'Effective' AND deleted_at IS NULL ORDER BY document_number",
+    )?;
+    let mut rows = stmt.query([])?;
+    let mut count = 0;
+    while let Some(row) = rows.next()? {
+        let title: String = row.get(1)?;
+        let document_type: String = row.get(3)?;
+        let lowered = title.to_lowercase();
+        if !PROCEDURE_TYPES.contains(&document_type.as_str())
+            || !subsystem.procedure_keywords().iter().any(|k| lowered.contains(&k.to_lowercase()))
+        {
+            continue;
+        }
+        csv.push_str(&row_csv(row, 6)?);
+        count += 1;
+    }
+    Ok((csv, count))
+}
+
+/// All columns of `table` for records created in the period, with metrics.
+fn records_csv(
+    conn: &rusqlite::Connection,
+    table: &str,
+    dated_by: &str,
+    from: &str,
+    to: &str,
+) -> Result<(TableMetrics, String)> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT * FROM {0} WHERE substr({1}, 1, 10) BETWEEN ?1 AND ?2 ORDER BY {1}, id",
+        table, dated_by
+    ))?;
+    let columns: Vec<String> = stmt.column_names().into_iter().map(str::to_string).collect();
+    let status_index = columns.iter().position(|name| name == status_column(table));
+    let mut csv = columns.join(",");
+    csv.push('\n');
+    let mut metrics = TableMetrics { table: table.to_string(), records: 0, by_status: BTreeMap::new() };
+    let mut rows = stmt.query(params![from, to])?;
+    while let Some(row) = rows.next()? {
+        csv.push_str(&row_csv(row, columns.len())?);
+        if let Some(index) = status_index {
+            *metrics.by_status.entry(value_text(row.get_ref(index)?)).or_insert(0) += 1;
+        }
+        metrics.records += 1;
+    }
+    Ok((metrics, csv))
+}
+
+/// Audit trail entries on the subsystem's records in the period.
+fn audit_csv(conn: &rusqlite::Connection, subsystem: Subsystem, from: &str, to: &str) -> Result<(String, usize)> {
+    let mut csv = "timestamp,user_id,action,resource,outcome,metadata\n".to_string();
+    let mut stmt = conn.prepare(
+        "SELECT timestamp, user_id, action, resource, outcome, metadata FROM audit_trail
+         WHERE substr(timestamp, 1, 10) BETWEEN ?1 AND ?2 ORDER BY timestamp, id",
+    )?;
+    let mut rows = stmt.query(params![from, to])?;
+    let mut count = 0;
+    while let Some(row) = rows.next()? {
+        let resource: String = row.get(3)?;
+        if !subsystem.audit_resources().iter().any(|prefix| resource.starts_with(prefix)) {
+            continue;
+        }
+        csv.push_str(&row_csv(row, 6)?);
+        count += 1;
+    }
+    Ok((csv, count))
+}
+
+fn row_csv(row: &rusqlite::Row, columns: usize) -> rusqlite::Result<String> {
+    let values =
+        (0..columns).map(|i| Ok(csv_field(&value_text(row.get_ref(i)?)))).collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(format!("{}\n", values.join(",")))
+}
+
+fn value_text(value: ValueRef) -> String {
+    match value {
+        ValueRef::Null | ValueRef::Blob(_) => String::new(),
+        ValueRef::Integer(value) => value.to_string(),
+        ValueRef::Real(value) => value.to_string(),
+        ValueRef::Text(text) => String::from_utf8_lossy(text).to_string(),
+    }
+}
+
+/// Minimal ZIP writer producing uncompressed ("stored") entries.
+struct ZipWriter {
+    out: Vec<u8>,
+    central: Vec<u8>,
+    entries: u16,
+    dos_time: u16,
+    dos_date: u16,
+}
+
+impl ZipWriter {
+    fn new(modified: DateTime<Utc>) -> Self {
+        let dos_time = ((modified.hour() << 11) | (modified.minute() << 5) | (modified.second() / 2)) as u16;
+        let dos_date =
+            (((modified.year() - 1980).clamp(0, 127) as u32) << 9 | modified.month() << 5 | modified.day()) as u16;
+        Self { out: Vec::new(), central: Vec::new(), entries: 0, dos_time, dos_date }
+    }
+
+    fn add(&mut self, name: &str, data: &[u8]) {
+        let offset = self.out.len() as u32;
+        let crc = crc32(data);
+        let size = data.len() as u32;
+        // Local file header
+        self.out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        self.header_fields(true, name, crc, size);
+        self.out.extend_from_slice(name.as_bytes());
+        self.out.extend_from_slice(data);
+        // Central directory header
+        self.central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        self.central.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        self.header_fields(false, name, crc, size);
+        self.central.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        self.central.extend_from_slice(&0u16.to_le_bytes()); // disk number
+        self.central.extend_from_slice(&0u16.to_le_bytes()); // internal attributes
+        self.central.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+        self.central.extend_from_slice(&offset.to_le_bytes());
+        self.central.extend_from_slice(name.as_bytes());
+        self.entries += 1;
+    }
+
+    /// Fields shared by local and central headers, from "version needed" to
+    /// "extra field length".
+    fn header_fields(&mut self, local: bool, name: &str, crc: u32, size: u32) {
+        let target = if local { &mut self.out } else { &mut self.central };
+        target.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        target.extend_from_slice(&0x0800u16.to_le_bytes()); // UTF-8 names
+        target.extend_from_slice(&0u16.to_le_bytes()); // stored
+        target.extend_from_slice(&self.dos_time.to_le_bytes());
+        target.extend_from_slice(&self.dos_date.to_le_bytes());
+        target.extend_from_slice(&crc.to_le_bytes());
+        target.extend_from_slice(&size.to_le_bytes());
+        target.extend_from_slice(&size.to_le_bytes());
+        target.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        target.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let central_offset = self.out.len() as u32;
+        let central_size = self.central.len() as u32;
+        self.out.extend_from_slice(&self.central);
+        // End of central directory record
+        self.out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        self.out.extend_from_slice(&[0; 4]); // disk numbers
+        self.out.extend_from_slice(&self.entries.to_le_bytes());
+        self.out.extend_from_slice(&self.entries.to_le_bytes());
+        self.out.extend_from_slice(&central_size.to_le_bytes());
+        self.out.extend_from_slice(&central_offset.to_le_bytes());
+        self.out.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        self.out
+    }
+}
+
+/// CRC-32 (IEEE 802.3) as used by ZIP.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::keystore::verify_signature;
+    use tempfile::tempdir;
+
+    /// Names and contents of the stored entries, read via the central directory.
+    fn read_zip(bytes: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]) as usize;
+        let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap()) as usize;
+        let eocd = bytes.len() - 22;
+        assert_eq!(u32_at(eocd), 0x0605_4b50);
+        let mut at = u32_at(eocd + 16);
+        (0..u16_at(eocd + 10))
+            .map(|_| {
+                assert_eq!(u32_at(at), 0x0201_4b50);
+                let (crc, size, name_len, offset) =
+                    (u32_at(at + 16), u32_at(at + 20), u16_at(at + 28), u32_at(at + 42));
+                let name = String::from_utf8(bytes[at + 46..at + 46 + name_len].to_vec()).unwrap();
+                at += 46 + name_len;
+                let data_start = offset + 30 + u16_at(offset + 26);
+                let data = bytes[data_start..data_start + size].to_vec();
+                assert_eq!(crc32(&data) as usize, crc);
+                (name, data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_capa_package_contents_and_signed_manifest() {
+        let dir = tempdir().unwrap();
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["qa"]);
+        db.with_connection(|conn| {
+            conn.execute_batch(
+                "INSERT INTO documents
+                     (id, document_number, title, version, status, document_type, content_hash, created_by)
+                 VALUES ('d1', 'SOP-004', 'Corrective and Preventive Action', '3.0', 'Effective', 'SOP', 'h1', 'qa'),
+                        ('d2', 'SOP-011', 'Cleaning', '1.0', 'Effective', 'SOP', 'h2', 'qa');
+                 INSERT INTO capa_records
+                     (id, title, description, capa_type, priority, status, initiator_id, assigned_to,
+                      created_at, updated_at)
+                 VALUES ('c1', 'Seal leak', 'Leak, found at FAT', 'Corrective', 'High', 'Closed', 'qa', 'qa',
+                         '2026-03-01T08:00:00Z', '2026-03-01T08:00:00Z'),
+                        ('c2', 'Label mixup', 'Wrong IFU', 'Corrective', 'Medium', 'Identified', 'qa', 'qa',
+                         '2024-01-01T08:00:00Z', '2024-01-01T08:00:00Z');",
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        AuditManager::new(db.clone()).log_action("qa", "capa_created", "capa:c1", "Success", None).unwrap();
+        let keystore = Keystore::open_or_create(&Keystore::keys_dir(dir.path())).unwrap();
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let year = InspectionScope::last_months(Subsystem::Capa, 12, date(2026, 10, 15)).unwrap();
+        assert_eq!(year.from, date(2025, 10, 15));
+        assert_eq!(year.file_name(), "inspection-capa-2025-10-15-to-2026-10-15.zip");
+        assert!(InspectionScope::new(Subsystem::Capa, date(2026, 2, 1), date(2026, 1, 1)).is_err());
+        let scope = InspectionScope::new(Subsystem::Capa, date(2025, 1, 1), Utc::now().date_naive()).unwrap();
+
+        let package = generate_inspection_package(&db, &keystore, &scope, dir.path(), "qa").unwrap();
+        assert_eq!(package.metrics.procedures, 1);
+        // c2 was created before the period
+        assert_eq!(package.metrics.tables[0].records, 1);
+        assert_eq!(package.metrics.tables[0].by_status.get("Closed"), Some(&1));
+        assert_eq!(package.metrics.audit_entries, 1);
+        assert!(generate_inspection_package(&db, &keystore, &scope, dir.path(), "qa").is_err(), "never overwrite");
+
+        let entries = read_zip(&fs::read(&package.path).unwrap());
+        let names: Vec<&str> = entries.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "procedures.csv",
+                "records/capa_records.csv",
+                "records/capa_actions.csv",
+                "metrics.json",
+                "audit_trail.csv",
+                INDEX_FILE,
+                MANIFEST_FILE,
+                SIGNATURE_FILE,
+            ]
+        );
+        let file = |name: &str| &entries.iter().find(|(n, _)| n == name).unwrap().1;
+        assert!(String::from_utf8_lossy(file("procedures.csv")).contains("SOP-004"));
+        assert!(String::from_utf8_lossy(file("records/capa_records.csv")).contains("\"Leak, found at FAT\""));
+        assert!(file(INDEX_FILE).starts_with(b"%PDF-"));
+
+        let manifest = file(MANIFEST_FILE);
+        for (name, data) in &entries[..6] {
+            assert!(String::from_utf8_lossy(manifest).contains(&format!("{}  {}", sha256_hex(data), name)));
+        }
+        let signature = general_purpose::STANDARD.decode(String::from_utf8_lossy(file(SIGNATURE_FILE)).trim()).unwrap();
+        assert!(verify_signature(keystore.public_key(), manifest, &signature));
+    }
+}
@@ -0,0 +1,704 @@
+//! # Issue Sync - CAPA Actions in an External Issue Tracker
+//!
+//! Engineering teams plan their work in an issue tracker, not in the QMS.
+//! The `issue_sync` job mirrors every CAPA action as an issue (Jira through
+//! [`JiraTracker`]) and syncs status changes and comments back, so the work
+//! happens in the team's own tool while the CAPA record stays complete.
+//!
+//! Each link keeps the action and issue status as of the last sync, which
+//! tells which side changed since. A change on one side is copied to the
+//! other through `status_mapping`; a change on both sides is handled by the
+//! configured [`ConflictPolicy`]. Tracker comments are imported read-only.
+//! Verified actions are never changed from the tracker: verification is a
+//! signed step in the QMS. Every change made by the sync is audited.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::capa::ActionStatus;
+use crate::config::{ConflictPolicy, IssueSyncConfig};
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+
+/// User recorded for changes made by the sync.
+pub const SYNC_USER: &str = "system:issue_sync";
+
+/// Environment variable holding the tracker API token.
+pub const TOKEN_ENV: &str = "QMS_ISSUE_TRACKER_TOKEN";
+
+/// Issue to be created for a CAPA action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssueDraft {
+    pub summary: String,
+    pub description: String,
+    /// Tracker account of the assignee, when mapped
+    pub assignee: Option<String>,
+    pub due_date: Option<String>,
+}
+
+/// State of an issue as read from the tracker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteIssue {
+    pub status: String,
+    pub comments: Vec<RemoteComment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteComment {
+    pub id: String,
+    pub author: String,
+    pub body: String,
+    pub created_at: String,
+}
+
+/// An external issue tracker.
+pub trait IssueTracker: Send + Sync {
+    /// Create an issue and return its key, e.g. `ENG-42`.
+    fn create_issue(&self, draft: &IssueDraft) -> Result<String>;
+    fn fetch_issue(&self, key: &str) -> Result<RemoteIssue>;
+    /// Move the issue to `status`.
+    fn set_status(&self, key: &str, status: &str) -> Result<()>;
+}
+
+/// Jira Cloud or Data Center over REST API v2 with basic authentication.
+pub struct JiraTracker {
+    http: reqwest::blocking::Client,
+    base_url: String,
+    project_key: String,
+    issue_type: String,
+    username: String,
+    api_token: String,
+}
+
+impl JiraTracker {
+    pub fn from_config(config: &IssueSyncConfig) -> Result<Self> {
+        let api_token = config.api_token.clone().or_else(|| std::env::var(TOKEN_ENV).ok()).ok_or_else(|| {
+            QmsError::Configuration { message: format!("issue_sync.api_token or {} is required", TOKEN_ENV) }
+        })?;
+        Ok(Self {
+            http: reqwest::blocking::Client::new(),
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            project_key: config.project_key.clone(),
+            issue_type: config.issue_type.clone(),
+            username: config.username.clone(),
+            api_token,
+        })
+    }
+
+    fn send(&self, request: reqwest::blocking::RequestBuilder) -> Result<Value> {
+        let response = request
+            .basic_auth(&self.username, Some(&self.api_token))
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| QmsError::Network { message: format!("Issue tracker request failed: {}", e) })?;
+        // Transitions answer 204 without a body
+        let body = response.text().map_err(|e| QmsError::Network { message: e.to_string() })?;
+        if body.is_empty() {
+            return Ok(Value::Null);
+        }
+        serde_json::from_str(&body).map_err(|e| QmsError::Serialization { message: e.to_string() })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/rest/api/2/{}", self.base_url, path)
+    }
+}
+
+impl IssueTracker for JiraTracker {
+    fn create_issue(&self, draft: &IssueDraft) -> Result<String> {
+        let mut fields = json!({
+            "project": { "key": self.project_key },
+            "issuetype": { "name": self.issue_type },
+            "summary": draft.summary,
+            "description": draft.description,
+        });
+        if let Some(assignee) = &draft.assignee {
+            fields["assignee"] = json!({ "accountId": assignee });
+        }
+        if let Some(due_date) = &draft.due_date {
+            fields["duedate"] = json!(due_date);
+        }
+        let created = self.send(self.http.post(self.url("issue")).json(&json!({ "fields": fields })))?;
+        created["key"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| QmsError::Serialization { message: "Issue tracker returned no issue key".to_string() })
+    }
+
+    fn fetch_issue(&self, key: &str) -> Result<RemoteIssue> {
+        let issue = self.send(self.http.get(self.url(&format!("issue/{}?fields=status,comment", key))))?;
+        let fields = &issue["fields"];
+        let comments = fields["comment"]["comments"].as_array().cloned().unwrap_or_default();
+        Ok(RemoteIssue {
+            status: fields["status"]["name"].as_str().unwrap_or_default().to_string(),
+            comments: comments
+                .iter()
+                .map(|comment| RemoteComment {
+                    id: comment["id"].as_str().unwrap_or_default().to_string(),
+                    author: comment["author"]["displayName"].as_str().unwrap_or("unknown").to_string(),
+                    body: comment["body"].as_str().unwrap_or_default().to_string(),
+                    created_at: comment["created"].as_str().unwrap_or_default().to_string(),
+                })
+                .collect(),
+        })
+    }
+
+    fn set_status(&self, key: &str, status: &str) -> Result<()> {
+        // Jira moves issues through workflow transitions, not status writes
+        let path = format!("issue/{}/transitions", key);
+        let transitions = self.send(self.http.get(self.url(&path)))?;
+        let transition = transitions["transitions"]
+            .as_array()
+            .and_then(|all| all.iter().find(|t| t["to"]["name"].as_str() == Some(status)))
+            .and_then(|t| t["id"].as_str())
+            .ok_or_else(|| QmsError::Validation {
+                field: "status".to_string(),
+                message: format!("Issue {} has no transition to '{}'", key, status),
+            })?;
+        self.send(self.http.post(self.url(&path)).json(&json!({ "transition": { "id": transition } })))?;
+        Ok(())
+    }
+}
+
+/// A CAPA action mirrored as an issue.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssueLink {
+    pub action_id: String,
+    pub capa_id: String,
+    pub issue_key: String,
+    /// Action status at the last sync
+    pub local_status: String,
+    /// Issue status at the last sync
+    pub remote_status: String,
+    pub synced_at: DateTime<Utc>,
+    /// Both sides changed; the link is not synced until resolved
+    pub conflict: Option<String>,
+}
+
+/// Counts of one sync run.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncSummary {
+    pub issues_created: usize,
+    pub statuses_pushed: usize,
+    pub statuses_pulled: usize,
+    pub comments_imported: usize,
+    pub conflicts: usize,
+    /// Actions that failed to sync, with the error; retried on the next run
+    pub errors: Vec<String>,
+}
+
+impl SyncSummary {
+    pub fn describe(&self) -> String {
+        format!(
+            "{} issue(s) created, {} status(es) pushed, {} pulled, {} comment(s) imported, {} conflict(s), {} error(s)",
+            self.issues_created,
+            self.statuses_pushed,
+            self.statuses_pulled,
+            self.comments_imported,
+            self.conflicts,
+            self.errors.len()
+        )
+    }
+}
+
+/// Side kept when a conflict is resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictSide {
+    Qms,
+    Tracker,
+}
+
+impl ConflictSide {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "qms" => Some(ConflictSide::Qms),
+            "tracker" => Some(ConflictSide::Tracker),
+            _ => None,
+        }
+    }
+}
+
+/// A CAPA action row with the fields mirrored into the issue.
+struct ActionRow {
+    id: String,
+    capa_id: String,
+    capa_title: String,
+    description: String,
+    assigned_to: String,
+    due_date: String,
+    verification_method: String,
+    status: String,
+}
+
+const ACTION_SELECT: &str = "SELECT a.id, a.capa_id, c.title, a.description, a.assigned_to, a.due_date,
+            a.verification_method, a.status
+     FROM capa_actions a JOIN capa_records c ON c.id = a.capa_id
+     WHERE a.deleted_at IS NULL
+     ORDER BY a.created_at, a.id";
+
+fn row_to_action(row: &Row<'_>) -> rusqlite::Result<ActionRow> {
+    Ok(ActionRow {
+        id: row.get(0)?,
+        capa_id: row.get(1)?,
+        capa_title: row.get(2)?,
+        description: row.get(3)?,
+        assigned_to: row.get(4)?,
+        due_date: row.get(5)?,
+        verification_method: row.get(6)?,
+        status: row.get(7)?,
+    })
+}
+
+fn row_to_link(row: &Row<'_>) -> rusqlite::Result<IssueLink> {
+    Ok(IssueLink {
+        action_id: row.get(0)?,
+        capa_id: row.get(1)?,
+        issue_key: row.get(2)?,
+        local_status: row.get(3)?,
+        remote_status: row.get(4)?,
+        synced_at: parse_timestamp(row, 5)?,
+        conflict: row.get(6)?,
+    })
+}
+
+fn parse_timestamp(row: &Row<'_>, index: usize) -> rusqlite::Result<DateTime<Utc>> {
+    let value: String = row.get(index)?;
+    DateTime::parse_from_rfc3339(&value)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+}
+
+const LINK_COLUMNS: &str = "action_id, capa_id, issue_key, local_status, remote_status, synced_at, conflict";
+
+/// Status name stored in `capa_actions.status`.
+fn status_name(status: &ActionStatus) -> String {
+    format!("{:?}", status)
+}
+
+/// Syncs CAPA actions with `tracker`.
+pub struct IssueSync<'a> {
+    db: &'a Database,
+    tracker: &'a dyn IssueTracker,
+    config: &'a IssueSyncConfig,
+}
+
+impl<'a> IssueSync<'a> {
+    pub fn new(db: &'a Database, tracker: &'a dyn IssueTracker, config: &'a IssueSyncConfig) -> Self {
+        Self { db, tracker, config }
+    }
+
+    /// Create issues for new actions and sync statuses and comments of the
+    /// linked ones. A failing action is reported and does not stop the run.
+    pub fn sync(&self, now: DateTime<Utc>) -> Result<SyncSummary> {
+        let actions = self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(ACTION_SELECT)?;
+            let actions = stmt.query_map([], row_to_action)?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(actions)
+        })?;
+        let mut summary = SyncSummary::default();
+        for action in actions {
+            let result = match self.link(&action.id)? {
+                None => self.create(&action, now, &mut summary),
+                Some(link) if link.conflict.is_some() => Ok(()),
+                Some(link) => self.sync_linked(&action, &link, now, &mut summary),
+            };
+            if let Err(e) = result {
+                tracing::warn!(action = %action.id, "Issue sync failed: {e}");
+                summary.errors.push(format!("{}: {}", action.id, e));
+            }
+        }
+        Ok(summary)
+    }
+
+    pub fn link(&self, action_id: &str) -> Result<Option<IssueLink>> {
+        self.db.with_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    &format!("SELECT {} FROM issue_links WHERE action_id = ?1", LINK_COLUMNS),
+                    params![action_id],
+                    row_to_link,
+                )
+                .optional()?)
+        })
+    }
+
+    /// Links waiting for a person to resolve a conflict.
+    pub fn conflicts(&self) -> Result<Vec<IssueLink>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM issue_links WHERE conflict IS NOT NULL ORDER BY synced_at",
+                LINK_COLUMNS
+            ))?;
+            let links = stmt.query_map([], row_to_link)?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(links)
+        })
+    }
+
+    /// Resolve the conflict on `action_id` by copying the status of `keep`
+    /// to the other side.
+    pub fn resolve_conflict(
+        &self,
+        action_id: &str,
+        keep: ConflictSide,
+        user_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        let link = self.link(action_id)?.filter(|link| link.conflict.is_some()).ok_or_else(|| QmsError::NotFound {
+            resource: "issue sync conflict".to_string(),
+            id: action_id.to_string(),
+        })?;
+        let local = self.action_status(action_id)?;
+        let remote = self.tracker.fetch_issue(&link.issue_key)?.status;
+        let (local, remote) = match keep {
+            ConflictSide::Qms => (local.clone(), self.push(&link, &local)?.unwrap_or(remote)),
+            ConflictSide::Tracker => (self.pull(&link, &local, &remote, user_id, now)?.unwrap_or(local), remote),
+        };
+        self.save_link(&link, &local, &remote, None, now)?;
+        AuditManager::new(self.db.clone()).log_action(
+            user_id,
+            "issue_sync_conflict_resolved",
+            &format!("capa_action:{}", action_id),
+            "Success",
+            Some(json!({ "issue": link.issue_key, "kept": keep }).to_string()),
+        )
+    }
+
+    fn create(&self, action: &ActionRow, now: DateTime<Utc>, summary: &mut SyncSummary) -> Result<()> {
+        let draft = IssueDraft {
+            summary: format!("[CAPA {}] {}", action.capa_title, action.description),
+            description: format!(
+                "CAPA action {} of CAPA {}\n\nVerification method: {}\nVerification is recorded in the QMS.",
+                action.id, action.capa_id, action.verification_method
+            ),
+            assignee: self.config.assignee_mapping.get(&action.assigned_to).cloned(),
+            // Stored as a date or an RFC 3339 timestamp
+            due_date: action.due_date.get(..10).map(str::to_string),
+        };
+        let key = self.tracker.create_issue(&draft)?;
+        let remote = self.tracker.fetch_issue(&key)?;
+        let audit = AuditManager::new(self.db.clone());
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "INSERT INTO issue_links
+                     (action_id, capa_id, issue_key, local_status, remote_status, created_at, synced_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+                params![action.id, action.capa_id, key, action.status, remote.status, now.to_rfc3339()],
+            )?;
+            audit.log_action_in(
+                uow,
+                SYNC_USER,
+                "issue_created",
+                &format!("capa_action:{}", action.id),
+                "Success",
+                Some(json!({ "issue": key }).to_string()),
+            )
+        })?;
+        summary.issues_created += 1;
+        summary.comments_imported += self.import_comments(&action.id, &key, &remote.comments, now)?;
+        Ok(())
+    }
+
+    fn sync_linked(
+        &self,
+        action: &ActionRow,
+        link: &IssueLink,
+        now: DateTime<Utc>,
+        summary: &mut SyncSummary,
+    ) -> Result<()> {
+        let remote = self.tracker.fetch_issue(&link.issue_key)?;
+        let local_changed = action.status != link.local_status;
+        let remote_changed = remote.status != link.remote_status;
+        let agree = self.config.status_mapping.get(&remote.status).map(status_name).as_ref() == Some(&action.status);
+        let (mut local, mut remote_status) = (action.status.clone(), remote.status.clone());
+        let mut conflict = None;
+        match (local_changed, remote_changed) {
+            (true, true) if !agree => match self.config.conflict_policy {
+                ConflictPolicy::Flag => {
+                    conflict = Some(format!(
+                        "QMS status '{}' (was '{}'), tracker status '{}' (was '{}')",
+                        action.status, link.local_status, remote.status, link.remote_status
+                    ));
+                    summary.conflicts += 1;
+                    AuditManager::new(self.db.clone()).log_action(
+                        SYNC_USER,
+                        "issue_sync_conflict",
+                        &format!("capa_action:{}", action.id),
+                        "Failure",
+                        Some(json!({ "issue": link.issue_key, "conflict": conflict }).to_string()),
+                    )?;
+                }
+                ConflictPolicy::QmsWins => {
+                    if let Some(pushed) = self.push(link, &local)? {
+                        remote_status = pushed;
+                        summary.statuses_pushed += 1;
+                    }
+                }
+                ConflictPolicy::TrackerWins => {
+                    if let Some(pulled) = self.pull(link, &local, &remote.status, SYNC_USER, now)? {
+                        local = pulled;
+                        summary.statuses_pulled += 1;
+                    }
+                }
+            },
+            (true, false) => {
+                if let Some(pushed) = self.push(link, &local)? {
+                    remote_status = pushed;
+                    summary.statuses_pushed += 1;
+                }
+            }
+            (false, true) => {
+                if let Some(pulled) = self.pull(link, &local, &remote.status, SYNC_USER, now)? {
+                    local = pulled;
+                    summary.statuses_pulled += 1;
+                }
+            }
+            _ => {}
+        }
+        self.save_link(link, &local, &remote_status, conflict.as_deref(), now)?;
+        summary.comments_imported += self.import_comments(&action.id, &link.issue_key, &remote.comments, now)?;
+        Ok(())
+    }
+
+    /// Move the issue to the tracker status mapped to `local`; returns the
+    /// new issue status, or `None` when `local` has no tracker status.
+    fn push(&self, link: &IssueLink, local: &str) -> Result<Option<String>> {
+        let Some((status, _)) = self.config.status_mapping.iter().find(|(_, action)| status_name(action) == local)
+        else {
+            return Ok(None);
+        };
+        self.tracker.set_status(&link.issue_key, status)?;
+        Ok(Some(status.clone()))
+    }
+
+    /// Set the action to the status mapped to `remote`; returns the new
+    /// action status, or `None` when nothing changed.
+    fn pull(
+        &self,
+        link: &IssueLink,
+        local: &str,
+        remote: &str,
+        user_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<Option<String>> {
+        let Some(status) = self.config.status_mapping.get(remote).map(status_name) else {
+            return Ok(None);
+        };
+        if status == local || local == status_name(&ActionStatus::Verified) {
+            return Ok(None);
+        }
+        let completed = (status == status_name(&ActionStatus::Completed)).then(|| now.to_rfc3339());
+        let audit = AuditManager::new(self.db.clone());
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE capa_actions SET status = ?2, completed_date = COALESCE(?3, completed_date), updated_at = ?4
+                 WHERE id = ?1",
+                params![link.action_id, status, completed, now.to_rfc3339()],
+            )?;
+            audit.log_action_in(
+                uow,
+                user_id,
+                "capa_action_status_synced",
+                &format!("capa_action:{}", link.action_id),
+                "Success",
+                Some(
+                    json!({ "issue": link.issue_key, "from": local, "to": status, "tracker_status": remote })
+                        .to_string(),
+                ),
+            )
+        })?;
+        Ok(Some(status))
+    }
+
+    fn save_link(
+        &self,
+        link: &IssueLink,
+        local: &str,
+        remote: &str,
+        conflict: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "UPDATE issue_links SET local_status = ?2, remote_status = ?3, conflict = ?4, synced_at = ?5
+                 WHERE action_id = ?1",
+                params![link.action_id, local, remote, conflict, now.to_rfc3339()],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn action_status(&self, action_id: &str) -> Result<String> {
+        self.db.with_connection(|conn| {
+            conn.query_row("SELECT status FROM capa_actions WHERE id = ?1", params![action_id], |row| row.get(0))
+                .optional()?
+                .ok_or_else(|| QmsError::NotFound { resource: "CAPA action".to_string(), id: action_id.to_string() })
+        })
+    }
+
+    /// Store comments not imported yet; returns how many were new.
+    fn import_comments(
+        &self,
+        action_id: &str,
+        key: &str,
+        comments: &[RemoteComment],
+        now: DateTime<Utc>,
+    ) -> Result<usize> {
+        self.db.with_connection(|conn| {
+            let mut imported = 0;
+            for comment in comments {
+                imported += conn.execute(
+                    "INSERT OR IGNORE INTO issue_comments
+                         (id, action_id, issue_key, remote_id, author, body, created_at, imported_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        Uuid::new_v4().to_string(),
+                        action_id,
+                        key,
+                        comment.id,
+                        comment.author,
+                        comment.body,
+                        comment.created_at,
+                        now.to_rfc3339()
+                    ],
+                )?;
+            }
+            Ok(imported)
+        })
+    }
+
+    /// Imported tracker comments of `action_id`, oldest first, as
+    /// `(author, body, created_at)`.
+    pub fn comments(&self, action_id: &str) -> Result<Vec<(String, String, String)>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT author, body, created_at FROM issue_comments WHERE action_id = ?1 ORDER BY created_at, id",
+            )?;
+            let comments = stmt
+                .query_map(params![action_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(comments)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+
+    /// Tracker keeping issues in memory.
+    #[derive(Default)]
+    struct FakeTracker {
+        issues: Mutex<BTreeMap<String, RemoteIssue>>,
+    }
+
+    impl FakeTracker {
+        fn change(&self, key: &str, status: &str, comment: Option<&str>) {
+            let mut issues = self.issues.lock().unwrap();
+            let issue = issues.get_mut(key).unwrap();
+            issue.status = status.to_string();
+            if let Some(body) = comment {
+                let id = issue.comments.len().to_string();
+                issue.comments.push(RemoteComment {
+                    id,
+                    author: "Dana Engineer".to_string(),
+                    body: body.to_string(),
+                    created_at: "2025-03-02T10:00:00Z".to_string(),
+                });
+            }
+        }
+    }
+
+    impl IssueTracker for FakeTracker {
+        fn create_issue(&self, _draft: &IssueDraft) -> Result<String> {
+            let mut issues = self.issues.lock().unwrap();
+            let key = format!("ENG-{}", issues.len() + 1);
+            issues.insert(key.clone(), RemoteIssue { status: "To Do".to_string(), comments: Vec::new() });
+            Ok(key)
+        }
+
+        fn fetch_issue(&self, key: &str) -> Result<RemoteIssue> {
+            Ok(self.issues.lock().unwrap()[key].clone())
+        }
+
+        fn set_status(&self, key: &str, status: &str) -> Result<()> {
+            self.change(key, status, None);
+            Ok(())
+        }
+    }
+
+    fn action_status(db: &Database, id: &str) -> String {
+        db.with_connection(|conn| {
+            Ok(conn.query_row("SELECT status FROM capa_actions WHERE id = ?1", [id], |r| r.get(0))?)
+        })
+        .unwrap()
+    }
+
+    fn set_action_status(db: &Database, id: &str, status: &str) {
+        db.with_connection(|conn| Ok(conn.execute("UPDATE capa_actions SET status = ?2 WHERE id = ?1", [id, status])?))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_sync_creates_issues_and_syncs_status_and_comments() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".into(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["qa"]);
+        db.with_connection(|conn| {
+            conn.execute_batch(
+                "INSERT INTO capa_records (id, title, description, capa_type, priority, status, initiator_id,
+                                           assigned_to, created_at, updated_at)
+                 VALUES ('c1', 'Seal failures', 'Peel test failures', 'Corrective', 'High', 'Identified', 'qa',
+                         'qa', '2025-03-01T08:00:00Z', '2025-03-01T08:00:00Z');
+                 INSERT INTO capa_actions (id, capa_id, action_type, description, assigned_to, due_date,
+                                           verification_method, status)
+                 VALUES ('a1', 'c1', 'Corrective', 'Replace sealing jaw', 'qa', '2025-04-01T00:00:00Z',
+                         'Peel test', 'Planned');",
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        let tracker = FakeTracker::default();
+        let config = IssueSyncConfig::default();
+        let sync = IssueSync::new(&db, &tracker, &config);
+        let now = Utc::now();
+
+        assert_eq!(sync.sync(now).unwrap().issues_created, 1);
+        assert_eq!(sync.link("a1").unwrap().unwrap().issue_key, "ENG-1");
+
+        // Tracker moves ahead: status and comment come back
+        tracker.change("ENG-1", "In Progress", Some("Jaw ordered"));
+        let summary = sync.sync(now).unwrap();
+        assert_eq!((summary.statuses_pulled, summary.comments_imported), (1, 1));
+        assert_eq!(action_status(&db, "a1"), "InProgress");
+        assert_eq!(sync.comments("a1").unwrap()[0].1, "Jaw ordered");
+
+        // QMS moves ahead: the issue follows
+        set_action_status(&db, "a1", "Completed");
+        assert_eq!(sync.sync(now).unwrap().statuses_pushed, 1);
+        assert_eq!(tracker.fetch_issue("ENG-1").unwrap().status, "Done");
+
+        // Both sides change: flagged, nothing overwritten until resolved
+        set_action_status(&db, "a1", "Overdue");
+        tracker.change("ENG-1", "To Do", None);
+        assert_eq!(sync.sync(now).unwrap().conflicts, 1);
+        assert_eq!(action_status(&db, "a1"), "Overdue");
+        assert_eq!(sync.conflicts().unwrap().len(), 1);
+        sync.resolve_conflict("a1", ConflictSide::Tracker, "qa", now).unwrap();
+        assert_eq!(action_status(&db, "a1"), "Planned");
+        assert!(sync.conflicts().unwrap().is_empty());
+    }
+}
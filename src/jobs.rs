@@ -0,0 +1,727 @@
+//! # Jobs - Background Job Scheduling & Run History
+//!
+//! Recurring maintenance work (database backups, overdue scans, scheduled
+//! report generation, analytics snapshots) is registered with a
+//! [`JobScheduler`] under a [`JobKind`] and an interval. Every run, scheduled
+//! or triggered by hand, is stored in `job_runs` with its duration and result
+//! and written to the audit trail, so the API and the TUI can show when each
+//! job last ran and when it runs next.
+//!
+//! Jobs run synchronously on the calling thread; async callers use
+//! `spawn_blocking`.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::access_review::{quarter_of, AccessReviewRepo};
+use crate::analytics_export::export_snapshot;
+use crate::audit::AuditManager;
+use crate::audit_findings::FindingRepo;
+use crate::audit_worm::AuditWormStore;
+use crate::config::{Config, EmailIntakeConfig, IssueSyncConfig, NotificationConfig, StorageConfig};
+use crate::controlled_prints::ControlledPrintRepo;
+use crate::database::Database;
+use crate::db_maintenance::{integrity_error, run_maintenance};
+use crate::error::{QmsError, Result};
+use crate::escalation::{document_review_subjects, EscalationEngine};
+use crate::email_intake::{EmailIntake, ImapMailbox};
+use crate::file_store::FileStore;
+use crate::issue_sync::{IssueSync, JiraTracker};
+use crate::mdr_clock::MdrClockMonitor;
+use crate::notification::OutboxNotifier;
+use crate::quality_events::CriticalErrorHandler;
+use crate::storage_monitor::{StorageMonitor, StoragePaths};
+use crate::supplier_documents::SupplierDocumentMonitor;
+
+/// User recorded for scheduled runs.
+pub const SCHEDULER_USER: &str = "system:job_scheduler";
+
+/// Seconds between checks for due jobs.
+const TICK_SECONDS: u64 = 60;
+
+/// Prefix of backup file names; only matching files are pruned.
+const BACKUP_PREFIX: &str = "qms-backup-";
+
+/// A recurring background job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Online copy of the database
+    Backup,
+    /// Escalations and counts of overdue reviews, findings and training, and
+    /// expiring supplier documents
+    OverdueScan,
+    /// Generation and distribution of due scheduled reports
+    ReportGeneration,
+    /// WAL checkpoint, incremental vacuum and integrity check
+    DatabaseMaintenance,
+    /// Snapshot of the analytics fact tables
+    AnalyticsExport,
+    /// Copy of new audit entries into sealed write-once segments
+    AuditWormSync,
+    /// Opening of the quarterly access review campaign
+    AccessReview,
+    /// Disk space and database growth sample
+    StorageMonitor,
+    /// Mirror of CAPA actions in the external issue tracker
+    IssueSync,
+    /// Poll of the complaint mailbox
+    EmailIntake,
+}
+
+impl JobKind {
+    pub const ALL: [JobKind; 10] = [
+        JobKind::Backup,
+        JobKind::OverdueScan,
+        JobKind::ReportGeneration,
+        JobKind::DatabaseMaintenance,
+        JobKind::AnalyticsExport,
+        JobKind::AuditWormSync,
+        JobKind::AccessReview,
+        JobKind::StorageMonitor,
+        JobKind::IssueSync,
+        JobKind::EmailIntake,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::Backup => "backup",
+            JobKind::OverdueScan => "overdue_scan",
+            JobKind::ReportGeneration => "report_generation",
+            JobKind::DatabaseMaintenance => "database_maintenance",
+            JobKind::AnalyticsExport => "analytics_export",
+            JobKind::AuditWormSync => "audit_worm_sync",
+            JobKind::AccessReview => "access_review",
+            JobKind::StorageMonitor => "storage_monitor",
+            JobKind::IssueSync => "issue_sync",
+            JobKind::EmailIntake => "email_intake",
+        }
+    }
+}
+
+impl fmt::Display for JobKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for JobKind {
+    type Err = QmsError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        JobKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| QmsError::NotFound { resource: "job".to_string(), id: s.to_string() })
+    }
+}
+
+/// What started a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobTrigger {
+    Scheduled,
+    Manual,
+}
+
+impl JobTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobTrigger::Scheduled => "scheduled",
+            JobTrigger::Manual => "manual",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobOutcome {
+    Succeeded,
+    Failed,
+}
+
+impl JobOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobOutcome::Succeeded => "succeeded",
+            JobOutcome::Failed => "failed",
+        }
+    }
+}
+
+/// One completed run of a job.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobRun {
+    pub id: Uuid,
+    pub job: JobKind,
+    pub trigger: JobTrigger,
+    pub triggered_by: String,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+    pub duration_ms: i64,
+    pub outcome: JobOutcome,
+    /// Summary on success, error message on failure
+    pub detail: Option<String>,
+}
+
+/// Current state of a registered job.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub job: JobKind,
+    pub interval_minutes: i64,
+    /// Whether a run is in progress right now
+    pub running: bool,
+    pub last_run: Option<JobRun>,
+    /// Never-run jobs are due immediately
+    pub next_run_at: DateTime<Utc>,
+}
+
+/// Work done by a job; returns a one-line summary.
+pub type JobFn = Arc<dyn Fn(DateTime<Utc>) -> Result<String> + Send + Sync>;
+
+#[derive(Clone)]
+struct RegisteredJob {
+    kind: JobKind,
+    interval: Duration,
+    work: JobFn,
+}
+
+/// Registry and runner of background jobs; clones share registrations and
+/// the set of running jobs.
+#[derive(Clone)]
+pub struct JobScheduler {
+    db: Database,
+    jobs: Arc<RwLock<Vec<RegisteredJob>>>,
+    running: Arc<Mutex<HashSet<JobKind>>>,
+}
+
+impl JobScheduler {
+    pub fn new(db: Database) -> Self {
+        Self { db, jobs: Arc::new(RwLock::new(Vec::new())), running: Arc::new(Mutex::new(HashSet::new())) }
+    }
+
+    /// Register the backup, overdue scan, maintenance, analytics export and
+    /// access review jobs configured in `config`, and the audit WORM sync,
+    /// issue tracker sync and email intake when enabled.
+    /// Report generation needs the API's record data and is registered there.
+    pub fn with_standard_jobs(self, config: &Config) -> Self {
+        let data_directory = Path::new(&config.application.data_directory);
+        self.register(
+            JobKind::Backup,
+            Duration::hours(i64::from(config.database.backup_interval_hours.max(1))),
+            backup_job(self.db.clone(), data_directory.join("backups"), config.database.backup_retention_days),
+        );
+        self.register(
+            JobKind::OverdueScan,
+            Duration::minutes(i64::from(config.jobs.overdue_scan_interval_minutes)),
+            overdue_scan_job(self.db.clone(), config.notifications.clone(), config.compliance.mdr_warning_days),
+        );
+        self.register(
+            JobKind::DatabaseMaintenance,
+            Duration::hours(i64::from(config.jobs.maintenance_interval_hours)),
+            maintenance_job(self.db.clone(), config.notifications.clone()),
+        );
+        self.register(
+            JobKind::AnalyticsExport,
+            Duration::hours(i64::from(config.jobs.analytics_export_interval_hours)),
+            analytics_export_job(self.db.clone(), data_directory.join("analytics")),
+        );
+        self.register(
+            JobKind::AccessReview,
+            Duration::hours(i64::from(config.jobs.access_review_check_interval_hours)),
+            access_review_job(self.db.clone()),
+        );
+        self.register(
+            JobKind::StorageMonitor,
+            Duration::minutes(i64::from(config.jobs.storage_check_interval_minutes)),
+            storage_monitor_job(
+                self.db.clone(),
+                StoragePaths::from_config(config),
+                config.storage.clone(),
+                config.notifications.clone(),
+            ),
+        );
+        if config.audit_worm.enabled {
+            self.register(
+                JobKind::AuditWormSync,
+                Duration::minutes(i64::from(config.audit_worm.sync_interval_minutes)),
+                audit_worm_sync_job(self.db.clone(), AuditWormStore::from_config(config)),
+            );
+        }
+        if config.issue_sync.enabled {
+            self.register(
+                JobKind::IssueSync,
+                Duration::minutes(i64::from(config.issue_sync.sync_interval_minutes)),
+                issue_sync_job(self.db.clone(), config.issue_sync.clone()),
+            );
+        }
+        if config.email_intake.enabled {
+            self.register(
+                JobKind::EmailIntake,
+                Duration::minutes(i64::from(config.email_intake.poll_interval_minutes)),
+                email_intake_job(self.db.clone(), FileStore::for_data_dir(data_directory), config.email_intake.clone()),
+            );
+        }
+        self
+    }
+
+    /// Register (or replace) the job of `kind`, due every `interval`.
+    pub fn register<F>(&self, kind: JobKind, interval: Duration, work: F)
+    where
+        F: Fn(DateTime<Utc>) -> Result<String> + Send + Sync + 'static,
+    {
+        let job = RegisteredJob { kind, interval, work: Arc::new(work) };
+        let mut jobs = self.jobs.write().unwrap_or_else(|e| e.into_inner());
+        jobs.retain(|existing| existing.kind != kind);
+        jobs.push(job);
+    }
+
+    /// Status of every registered job, in registration order.
+    pub fn statuses(&self, now: DateTime<Utc>) -> Result<Vec<JobStatus>> {
+        let jobs = self.jobs.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let running = self.running.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        jobs.into_iter()
+            .map(|job| {
+                let last_run = self.last_run(job.kind)?;
+                Ok(JobStatus {
+                    job: job.kind,
+                    interval_minutes: job.interval.num_minutes(),
+                    running: running.contains(&job.kind),
+                    next_run_at: last_run.as_ref().map_or(now, |run| run.started_at + job.interval),
+                    last_run,
+                })
+            })
+            .collect()
+    }
+
+    /// Run the job of `kind` now and record the run. A failing job yields a
+    /// `Failed` run, not an error; errors mean the job is unknown, already
+    /// running, or the run could not be recorded.
+    pub fn run(&self, kind: JobKind, trigger: JobTrigger, triggered_by: &str) -> Result<JobRun> {
+        let job = self
+            .jobs
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|job| job.kind == kind)
+            .cloned()
+            .ok_or_else(|| QmsError::NotFound { resource: "job".to_string(), id: kind.to_string() })?;
+        let _running = RunningGuard::acquire(&self.running, kind)?;
+
+        let started_at = Utc::now();
+        let clock = Instant::now();
+        let result = (job.work)(started_at);
+        let duration_ms = clock.elapsed().as_millis() as i64;
+        let (outcome, detail) = match result {
+            Ok(summary) => (JobOutcome::Succeeded, Some(summary)),
+            Err(e) => {
+                tracing::error!(job = kind.as_str(), "background job failed: {e}");
+                (JobOutcome::Failed, Some(e.to_string()))
+            }
+        };
+        let run = JobRun {
+            id: Uuid::new_v4(),
+            job: kind,
+            trigger,
+            triggered_by: triggered_by.to_string(),
+            started_at,
+            completed_at: Utc::now(),
+            duration_ms,
+            outcome,
+            detail,
+        };
+        self.record(&run)?;
+        Ok(run)
+    }
+
+    /// Run every job that is due at `now` and not already running.
+    pub fn run_due(&self, now: DateTime<Utc>) -> Result<Vec<JobRun>> {
+        let mut runs = Vec::new();
+        for status in self.statuses(now)?.into_iter().filter(|s| !s.running && s.next_run_at <= now) {
+            match self.run(status.job, JobTrigger::Scheduled, SCHEDULER_USER) {
+                Ok(run) => runs.push(run),
+                Err(QmsError::Validation { .. }) => {} // started by hand in the meantime
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(runs)
+    }
+
+    /// Number of jobs running right now.
+    pub fn running_jobs(&self) -> usize {
+        self.running.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Check for due jobs every minute in the background.
+    pub fn spawn_periodic(self) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(TICK_SECONDS));
+            loop {
+                interval.tick().await;
+                let scheduler = self.clone();
+                match tokio::task::spawn_blocking(move || scheduler.run_due(Utc::now())).await {
+                    Ok(Err(e)) => tracing::error!(error = %e, "Job scheduler tick failed"),
+                    Err(e) => tracing::error!(error = %e, "Job scheduler task panicked"),
+                    Ok(Ok(_)) => {}
+                }
+            }
+        })
+    }
+
+    fn last_run(&self, kind: JobKind) -> Result<Option<JobRun>> {
+        self.db.with_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT id, job, run_trigger, triggered_by, started_at, completed_at, duration_ms, outcome, detail
+                     FROM job_runs WHERE job = ?1 ORDER BY started_at DESC LIMIT 1",
+                    params![kind.as_str()],
+                    row_to_run,
+                )
+                .optional()?)
+        })
+    }
+
+    fn record(&self, run: &JobRun) -> Result<()> {
+        let audit = AuditManager::new(self.db.clone());
+        let metadata = serde_json::json!({
+            "trigger": run.trigger.as_str(),
+            "duration_ms": run.duration_ms,
+            "detail": run.detail,
+        });
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "INSERT INTO job_runs (id, job, run_trigger, triggered_by, started_at, completed_at, duration_ms, outcome, detail)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    run.id.to_string(),
+                    run.job.as_str(),
+                    run.trigger.as_str(),
+                    run.triggered_by,
+                    run.started_at.to_rfc3339(),
+                    run.completed_at.to_rfc3339(),
+                    run.duration_ms,
+                    run.outcome.as_str(),
+                    run.detail,
+                ],
+            )?;
+            audit.log_action_in(
+                uow,
+                &run.triggered_by,
+                "job_run",
+                &format!("job:{}", run.job),
+                if run.outcome == JobOutcome::Succeeded { "Success" } else { "Failure" },
+                Some(metadata.to_string()),
+            )
+        })
+    }
+}
+
+/// Marks a job as running until dropped.
+struct RunningGuard<'a> {
+    running: &'a Mutex<HashSet<JobKind>>,
+    kind: JobKind,
+}
+
+impl<'a> RunningGuard<'a> {
+    fn acquire(running: &'a Mutex<HashSet<JobKind>>, kind: JobKind) -> Result<Self> {
+        if !running.lock().unwrap_or_else(|e| e.into_inner()).insert(kind) {
+            return Err(QmsError::Validation {
+                field: "job".to_string(),
+                message: format!("Job '{}' is already running", kind),
+            });
+        }
+        Ok(Self { running, kind })
+    }
+}
+
+impl Drop for RunningGuard<'_> {
+    fn drop(&mut self) {
+        self.running.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.kind);
+    }
+}
+
+fn row_to_run(row: &rusqlite::Row) -> rusqlite::Result<JobRun> {
+    let parse_time = |index: usize| -> rusqlite::Result<DateTime<Utc>> {
+        let text: String = row.get(index)?;
+        DateTime::parse_from_rfc3339(&text)
+            .map(|t| t.with_timezone(&Utc))
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+    };
+    let job: String = row.get(1)?;
+    let trigger: String = row.get(2)?;
+    let outcome: String = row.get(7)?;
+    Ok(JobRun {
+        id: Uuid::parse_str(&row.get::<_, String>(0)?)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))?,
+        job: job
+            .parse()
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(e)))?,
+        trigger: if trigger == "manual" { JobTrigger::Manual } else { JobTrigger::Scheduled },
+        triggered_by: row.get(3)?,
+        started_at: parse_time(4)?,
+        completed_at: parse_time(5)?,
+        duration_ms: row.get(6)?,
+        outcome: if outcome == "succeeded" { JobOutcome::Succeeded } else { JobOutcome::Failed },
+        detail: row.get(8)?,
+    })
+}
+
+/// Copy the database to `directory` and delete backups older than
+/// `retention_days`.
+pub fn backup_job(
+    db: Database,
+    directory: PathBuf,
+    retention_days: u32,
+) -> impl Fn(DateTime<Utc>) -> Result<String> + Send + Sync {
+    move |now| {
+        let fs_error = |path: &Path, e: std::io::Error| QmsError::FileSystem {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        };
+        std::fs::create_dir_all(&directory).map_err(|e| fs_error(&directory, e))?;
+        let path = directory.join(format!("{}{}.db", BACKUP_PREFIX, now.format("%Y%m%d-%H%M%S")));
+        db.create_backup(&path.display().to_string())?;
+
+        let cutoff = std::time::SystemTime::now() - std::time::Duration::from_secs(u64::from(retention_days) * 86_400);
+        let mut removed = 0;
+        for entry in std::fs::read_dir(&directory).map_err(|e| fs_error(&directory, e))?.flatten() {
+            let expired = entry.metadata().and_then(|m| m.modified()).map_or(false, |modified| modified < cutoff);
+            if expired && entry.file_name().to_string_lossy().starts_with(BACKUP_PREFIX) {
+                std::fs::remove_file(entry.path()).map_err(|e| fs_error(&entry.path(), e))?;
+                removed += 1;
+            }
+        }
+        Ok(format!("Backup written to {}; {} expired backup(s) removed", path.display(), removed))
+    }
+}
+
+/// Send due escalations for documents awaiting periodic review, warn about
+/// MDR reporting deadlines and count overdue audit findings and training
+/// records.
+pub fn overdue_scan_job(
+    db: Database,
+    notifications: NotificationConfig,
+    mdr_warning_days: u32,
+) -> impl Fn(DateTime<Utc>) -> Result<String> + Send + Sync {
+    move |now| {
+        let notifier = OutboxNotifier::from_config(&notifications);
+        let engine = EscalationEngine::new(&db);
+        let subjects = document_review_subjects(&db)?;
+        let escalations = match &notifier {
+            Some(notifier) => engine.with_notifier(notifier).evaluate(&subjects, now)?,
+            None => engine.evaluate(&subjects, now)?,
+        };
+        let mut monitor = MdrClockMonitor::new(&db, mdr_warning_days);
+        if let Some(notifier) = &notifier {
+            monitor = monitor.with_notifier(notifier, notifications.quality_manager_addresses.clone());
+        }
+        let mdr_alerts = monitor.check(now)?;
+        let mut documents = SupplierDocumentMonitor::new(&db);
+        if let Some(notifier) = &notifier {
+            documents = documents.with_notifier(notifier, notifications.quality_manager_addresses.clone());
+        }
+        let document_alerts = documents.check(now)?;
+        let prints = ControlledPrintRepo::new(&db).awaiting_recall()?;
+        let findings = FindingRepo::new(&db).aging(now)?.overdue;
+        let training: i64 = db.with_connection(|conn| {
+            Ok(conn.query_row(
+                "SELECT COUNT(*) FROM training_records
+                 WHERE status != 'Completed' AND due_date < ?1 AND deleted_at IS NULL",
+                params![now.date_naive().to_string()],
+                |row| row.get(0),
+            )?)
+        })?;
+        Ok(format!(
+            "{} escalation(s) sent; {} MDR deadline warning(s); {} supplier document warning(s); \
+             {} controlled print(s) awaiting recall; {} overdue audit finding(s); {} overdue training record(s)",
+            escalations.len(),
+            mdr_alerts.len(),
+            document_alerts.len(),
+            prints.len(),
+            findings,
+            training
+        ))
+    }
+}
+
+/// Checkpoint, vacuum and integrity-check the database. A failed integrity
+/// check fails the run and is raised as a quality event for the quality
+/// managers.
+pub fn maintenance_job(
+    db: Database,
+    notifications: NotificationConfig,
+) -> impl Fn(DateTime<Utc>) -> Result<String> + Send + Sync {
+    move |_| {
+        let report = run_maintenance(&db)?;
+        if report.is_healthy() {
+            return Ok(report.summary());
+        }
+        let error = integrity_error(&report);
+        let notifier = OutboxNotifier::from_config(&notifications);
+        let mut handler = CriticalErrorHandler::new(&db);
+        if let Some(notifier) = &notifier {
+            handler = handler.with_notifier(notifier, notifications.quality_manager_addresses.clone());
+        }
+        handler.handle(&error, &format!("job:{}", JobKind::DatabaseMaintenance))?;
+        Err(error)
+    }
+}
+
+/// Write a snapshot of the analytics fact tables below `directory`.
+pub fn analytics_export_job(
+    db: Database,
+    directory: PathBuf,
+) -> impl Fn(DateTime<Utc>) -> Result<String> + Send + Sync {
+    move |now| {
+        let snapshot = export_snapshot(&db, &directory, SCHEDULER_USER, now)?;
+        let rows: usize = snapshot.manifest.files.iter().map(|file| file.rows).sum();
+        Ok(format!("Snapshot of {} fact row(s) written to {}", rows, snapshot.directory.display()))
+    }
+}
+
+/// Seal audit entries added since the last run.
+pub fn audit_worm_sync_job(
+    db: Database,
+    store: AuditWormStore,
+) -> impl Fn(DateTime<Utc>) -> Result<String> + Send + Sync {
+    move |now| {
+        let result = store.sync(&db, now)?;
+        Ok(format!(
+            "{} audit entr(ies) sealed in {} segment(s); sealed through seq {}",
+            result.entries_written, result.segments_written, result.last_seq
+        ))
+    }
+}
+
+/// Mirror CAPA actions in the issue tracker and sync statuses and comments back.
+pub fn issue_sync_job(db: Database, config: IssueSyncConfig) -> impl Fn(DateTime<Utc>) -> Result<String> + Send + Sync {
+    move |now| {
+        let tracker = JiraTracker::from_config(&config)?;
+        Ok(IssueSync::new(&db, &tracker, &config).sync(now)?.describe())
+    }
+}
+
+/// Turn unread messages of the complaint mailbox into draft complaints.
+pub fn email_intake_job(
+    db: Database,
+    store: FileStore,
+    config: EmailIntakeConfig,
+) -> impl Fn(DateTime<Utc>) -> Result<String> + Send + Sync {
+    move |now| {
+        let mut mailbox = ImapMailbox::connect(&config)?;
+        let summary = EmailIntake::new(&db, &store, &config).poll(&mut mailbox, now);
+        mailbox.logout();
+        Ok(summary?.describe())
+    }
+}
+
+/// Open the current quarter's access review campaign if it is not open yet.
+pub fn access_review_job(db: Database) -> impl Fn(DateTime<Utc>) -> Result<String> + Send + Sync {
+    move |now| {
+        let repo = AccessReviewRepo::new(&db);
+        let today = now.date_naive();
+        Ok(match repo.open_due(today, SCHEDULER_USER)? {
+            Some(campaign) => format!(
+                "Opened access review {} with {} item(s), due {}",
+                campaign.quarter,
+                repo.items(campaign.id)?.len(),
+                campaign.due_date
+            ),
+            None => format!("Access review {} already opened", quarter_of(today)),
+        })
+    }
+}
+
+/// Sample disk space and database growth; a worsening level is emailed to
+/// the quality managers.
+pub fn storage_monitor_job(
+    db: Database,
+    paths: StoragePaths,
+    storage: StorageConfig,
+    notifications: NotificationConfig,
+) -> impl Fn(DateTime<Utc>) -> Result<String> + Send + Sync {
+    move |now| {
+        let notifier = OutboxNotifier::from_config(&notifications);
+        let mut monitor = StorageMonitor::new(&db, &storage);
+        if let Some(notifier) = &notifier {
+            monitor = monitor.with_notifier(notifier, notifications.quality_manager_addresses.clone());
+        }
+        let sample = monitor.record(paths.measure()?, now)?;
+        Ok(format!("Storage {}: {}", sample.level, sample.describe()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_runs_are_recorded_and_scheduled() {
+        let db = test_db();
+        let scheduler = JobScheduler::new(db.clone());
+        scheduler.register(JobKind::OverdueScan, Duration::minutes(30), |_| Ok("0 overdue".to_string()));
+        scheduler.register(JobKind::ReportGeneration, Duration::hours(1), |_| {
+            Err(QmsError::Application { message: "renderer unavailable".to_string() })
+        });
+
+        let start = Utc::now();
+        let statuses = scheduler.statuses(start).unwrap();
+        assert_eq!(statuses.len(), 2);
+        assert!(statuses.iter().all(|s| s.last_run.is_none() && s.next_run_at == start));
+
+        let runs = scheduler.run_due(start).unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].outcome, JobOutcome::Succeeded);
+        assert_eq!(runs[1].outcome, JobOutcome::Failed);
+        assert!(runs[1].detail.as_deref().unwrap().contains("renderer unavailable"));
+        assert!(scheduler.run_due(start).unwrap().is_empty(), "not due again yet");
+
+        let status = &scheduler.statuses(start).unwrap()[0];
+        let last = status.last_run.as_ref().unwrap();
+        assert_eq!((last.trigger, last.triggered_by.as_str()), (JobTrigger::Scheduled, SCHEDULER_USER));
+        assert_eq!(status.next_run_at, last.started_at + Duration::minutes(30));
+
+        let manual = scheduler.run(JobKind::OverdueScan, JobTrigger::Manual, "qa_manager").unwrap();
+        assert_eq!(scheduler.statuses(start).unwrap()[0].last_run.as_ref(), Some(&manual));
+        let audit = db.get_audit_entries_for_resource("job:overdue_scan").unwrap();
+        assert_eq!(audit.len(), 2);
+        assert!(audit.iter().any(|entry| entry.user_id == "qa_manager" && entry.action == "job_run"));
+
+        assert!(matches!(
+            scheduler.run(JobKind::Backup, JobTrigger::Manual, "qa_manager"),
+            Err(QmsError::NotFound { .. })
+        ));
+        assert_eq!("report_generation".parse::<JobKind>().unwrap(), JobKind::ReportGeneration);
+    }
+
+    #[test]
+    fn test_backup_job_writes_copy() {
+        let db = test_db();
+        let dir = tempfile::TempDir::new().unwrap();
+        let summary = backup_job(db, dir.path().join("backups"), 90)(Utc::now()).unwrap();
+        assert!(summary.contains("0 expired"));
+        let files: Vec<_> = std::fs::read_dir(dir.path().join("backups")).unwrap().flatten().collect();
+        assert_eq!(files.len(), 1);
+        assert!(files[0].file_name().to_string_lossy().starts_with(BACKUP_PREFIX));
+    }
+}
@@ -0,0 +1,213 @@
+//! # Keystore - System Signing Key Management
+//!
+//! Holds the Ed25519 system key used to sign generated records (compliance
+//! reports, exports). The key is stored as PKCS#8 under
+//! `<data_directory>/keys/system_ed25519.pk8` and created on first use.
+//! Supports FDA 21 CFR Part 11 §11.10(c) (protection of records) by making
+//! any post-generation modification of signed outputs detectable. The same
+//! key also seeds the keyed hash behind [`Keystore::pseudonym`], so tokens in
+//! shared exports are stable per installation but cannot be recomputed
+//! without the key.
+
+use ring::hmac;
+use ring::rand::SystemRandom;
+use ring::signature::{self, Ed25519KeyPair, KeyPair, UnparsedPublicKey};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{QmsError, Result};
+
+/// File name of the system signing key inside the keys directory.
+pub const SYSTEM_KEY_FILE: &str = "system_ed25519.pk8";
+
+/// Signature algorithm identifier recorded alongside signatures.
+pub const SIGNATURE_ALGORITHM: &str = "Ed25519";
+
+/// Message signed to derive the pseudonymization key; Ed25519 signatures are
+/// deterministic, so the derived key is stable for a given system key.
+const PSEUDONYM_KEY_CONTEXT: &[u8] = b"qmsrs/pseudonymization/v1";
+
+/// Lowercase hex SHA-256 of `data`.
+pub fn sha256_hex(data: &[u8]) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, data);
+    to_hex(digest.as_ref())
+}
+
+/// Lowercase hex encoding.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Verify an Ed25519 `signature` over `message` with a raw 32-byte public key.
+pub fn verify_signature(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    UnparsedPublicKey::new(&signature::ED25519, public_key)
+        .verify(message, signature)
+        .is_ok()
+}
+
+/// System signing key loaded from disk.
+pub struct Keystore {
+    key_pair: Ed25519KeyPair,
+    key_path: PathBuf,
+}
+
+impl Keystore {
+    /// Keys directory for a given application data directory.
+    pub fn keys_dir(data_directory: &Path) -> PathBuf {
+        data_directory.join("keys")
+    }
+
+    /// Load the system key from `keys_dir`, generating and persisting a new one
+    /// when none exists yet.
+    pub fn open_or_create(keys_dir: &Path) -> Result<Self> {
+        let key_path = keys_dir.join(SYSTEM_KEY_FILE);
+        if key_path.exists() {
+            return Self::open(&key_path);
+        }
+
+        fs::create_dir_all(keys_dir).map_err(|e| QmsError::FileSystem {
+            path: keys_dir.display().to_string(),
+            message: e.to_string(),
+        })?;
+
+        let rng = SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).map_err(|_| QmsError::Encryption {
+            message: "Failed to generate system signing key".to_string(),
+        })?;
+        write_private_file(&key_path, pkcs8.as_ref())?;
+
+        tracing::info!(
+            component = "keystore",
+            action = "system_key_generated",
+            path = %key_path.display(),
+            "Generated new system signing key"
+        );
+        Self::from_pkcs8(pkcs8.as_ref(), key_path)
+    }
+
+    /// Load an existing PKCS#8 key file.
+    pub fn open(key_path: &Path) -> Result<Self> {
+        let pkcs8 = fs::read(key_path).map_err(|e| QmsError::FileSystem {
+            path: key_path.display().to_string(),
+            message: e.to_string(),
+        })?;
+        Self::from_pkcs8(&pkcs8, key_path.to_path_buf())
+    }
+
+    fn from_pkcs8(pkcs8: &[u8], key_path: PathBuf) -> Result<Self> {
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8).map_err(|e| QmsError::Encryption {
+            message: format!("Invalid system signing key {}: {}", key_path.display(), e),
+        })?;
+        Ok(Self { key_pair, key_path })
+    }
+
+    /// Sign `message` with the system key.
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        self.key_pair.sign(message).as_ref().to_vec()
+    }
+
+    /// Raw 32-byte Ed25519 public key.
+    pub fn public_key(&self) -> &[u8] {
+        self.key_pair.public_key().as_ref()
+    }
+
+    /// Short fingerprint of the public key (first 16 hex chars of its SHA-256).
+    pub fn key_id(&self) -> String {
+        sha256_hex(self.public_key())[..16].to_string()
+    }
+
+    /// Location of the key file.
+    pub fn key_path(&self) -> &Path {
+        &self.key_path
+    }
+
+    /// Deterministic token for an identifying value, e.g. `PSN-3f2a9c01d4e5b678`.
+    ///
+    /// HMAC-SHA256 keyed from the system key over the trimmed, lower-cased
+    /// value: the same person maps to the same token across exports, while
+    /// the value cannot be recovered or guessed without the key. Empty values
+    /// stay empty.
+    pub fn pseudonym(&self, value: &str) -> String {
+        let value = value.trim().to_lowercase();
+        if value.is_empty() {
+            return String::new();
+        }
+        let key = hmac::Key::new(hmac::HMAC_SHA256, &self.sign(PSEUDONYM_KEY_CONTEXT));
+        format!("PSN-{}", &to_hex(hmac::sign(&key, value.as_bytes()).as_ref())[..16])
+    }
+}
+
+/// Write key material readable by the owner only.
+fn write_private_file(path: &Path, contents: &[u8]) -> Result<()> {
+    fs::write(path, contents).map_err(|e| QmsError::FileSystem {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    })?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600)).map_err(|e| QmsError::FileSystem {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_key_is_created_once_and_reloaded() {
+        let dir = tempdir().unwrap();
+        let keys_dir = Keystore::keys_dir(dir.path());
+
+        let first = Keystore::open_or_create(&keys_dir).unwrap();
+        assert!(first.key_path().exists());
+        let second = Keystore::open_or_create(&keys_dir).unwrap();
+        assert_eq!(first.public_key(), second.public_key());
+        assert_eq!(first.key_id().len(), 16);
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let dir = tempdir().unwrap();
+        let keystore = Keystore::open_or_create(dir.path()).unwrap();
+
+        let signature = keystore.sign(b"report payload");
+        assert!(verify_signature(keystore.public_key(), b"report payload", &signature));
+        assert!(!verify_signature(keystore.public_key(), b"tampered payload", &signature));
+    }
+
+    #[test]
+    fn test_pseudonyms_are_deterministic_per_key() {
+        let dir = tempdir().unwrap();
+        let keystore = Keystore::open_or_create(&dir.path().join("a")).unwrap();
+        let other = Keystore::open_or_create(&dir.path().join("b")).unwrap();
+
+        let token = keystore.pseudonym("Jane Doe");
+        assert!(token.starts_with("PSN-") && token.len() == 20);
+        assert_eq!(keystore.pseudonym(" jane doe "), token);
+        assert_ne!(keystore.pseudonym("John Doe"), token);
+        assert_ne!(other.pseudonym("Jane Doe"), token);
+        assert_eq!(keystore.pseudonym("  "), "");
+    }
+
+    #[test]
+    fn test_corrupt_key_file_rejected() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(SYSTEM_KEY_FILE);
+        fs::write(&path, b"not a key").unwrap();
+        assert!(matches!(Keystore::open(&path), Err(QmsError::Encryption { .. })));
+    }
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
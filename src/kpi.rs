@@ -0,0 +1,472 @@
+//! # KPI - Dashboard Targets and RAG Status
+//!
+//! Key performance indicators (CAPA on-time closure, training completion,
+//! supplier qualification) are measured as percentages and compared against
+//! [`KpiTarget`]s: green when the target is met, amber within the target's
+//! margin, red beyond it. Targets come from `[kpis]` in the configuration and
+//! can be overridden per metric in `kpi_targets`; overrides are audited. Each
+//! evaluation stores the month's value in `kpi_snapshots`, which provides the
+//! month-over-month trend for management review (ISO 13485 §5.6, §8.4).
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::AuditManager;
+use crate::capa::{CapaRecord, CapaStatus};
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::supplier::SupplierMetrics;
+use crate::training::TrainingMetrics;
+
+/// Months of history returned with each KPI, including the current one.
+pub const KPI_TREND_MONTHS: usize = 6;
+
+/// A measured indicator, in percent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KpiMetric {
+    /// Closed CAPAs that were closed by their due date
+    CapaOnTimeClosure,
+    /// Open CAPAs past their due date
+    CapaOverdue,
+    /// Training assignments completed
+    TrainingCompletion,
+    /// Suppliers with `Qualified` status
+    SupplierQualification,
+}
+
+impl KpiMetric {
+    pub const ALL: [KpiMetric; 4] = [
+        KpiMetric::CapaOnTimeClosure,
+        KpiMetric::CapaOverdue,
+        KpiMetric::TrainingCompletion,
+        KpiMetric::SupplierQualification,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KpiMetric::CapaOnTimeClosure => "capa_on_time_closure",
+            KpiMetric::CapaOverdue => "capa_overdue",
+            KpiMetric::TrainingCompletion => "training_completion",
+            KpiMetric::SupplierQualification => "supplier_qualification",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|metric| metric.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            KpiMetric::CapaOnTimeClosure => "CAPA On-Time Closure",
+            KpiMetric::CapaOverdue => "Overdue Open CAPAs",
+            KpiMetric::TrainingCompletion => "Training Completion",
+            KpiMetric::SupplierQualification => "Qualified Suppliers",
+        }
+    }
+
+    /// Whether a larger value is better; the overdue rate should stay low.
+    pub fn higher_is_better(&self) -> bool {
+        !matches!(self, KpiMetric::CapaOverdue)
+    }
+}
+
+/// Red/amber/green status of a KPI against its target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RagStatus {
+    Green,
+    Amber,
+    Red,
+}
+
+impl RagStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RagStatus::Green => "green",
+            RagStatus::Amber => "amber",
+            RagStatus::Red => "red",
+        }
+    }
+}
+
+/// Target of one KPI, e.g. CAPA on-time closure >= 90%.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct KpiTarget {
+    pub metric: KpiMetric,
+    /// Target value in percent; a minimum, or a maximum for the overdue rate
+    pub target: f64,
+    /// Percentage points past the target still reported as amber
+    #[serde(default = "default_amber_margin")]
+    pub amber_margin: f64,
+}
+
+fn default_amber_margin() -> f64 {
+    5.0
+}
+
+impl KpiTarget {
+    pub fn new(metric: KpiMetric, target: f64) -> Self {
+        Self { metric, target, amber_margin: default_amber_margin() }
+    }
+
+    /// Targets used when the configuration does not list any.
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::new(KpiMetric::CapaOnTimeClosure, 90.0),
+            Self::new(KpiMetric::TrainingCompletion, 95.0),
+            Self::new(KpiMetric::SupplierQualification, 90.0),
+        ]
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..=100.0).contains(&self.target) {
+            return Err(QmsError::Validation {
+                field: "target".to_string(),
+                message: format!("{} target must be between 0 and 100%, got {}", self.metric.label(), self.target),
+            });
+        }
+        if !(0.0..=100.0).contains(&self.amber_margin) {
+            return Err(QmsError::Validation {
+                field: "amber_margin".to_string(),
+                message: format!("Amber margin must be between 0 and 100 points, got {}", self.amber_margin),
+            });
+        }
+        Ok(())
+    }
+
+    /// Status of `actual` against this target.
+    pub fn status(&self, actual: f64) -> RagStatus {
+        // Distance short of the target; negative or zero when it is met.
+        let shortfall = if self.metric.higher_is_better() { self.target - actual } else { actual - self.target };
+        if shortfall <= 0.0 {
+            RagStatus::Green
+        } else if shortfall <= self.amber_margin {
+            RagStatus::Amber
+        } else {
+            RagStatus::Red
+        }
+    }
+
+    /// Target as shown to people, e.g. `>= 90.0%`.
+    pub fn describe(&self) -> String {
+        let comparison = if self.metric.higher_is_better() { ">=" } else { "<=" };
+        format!("{} {:.1}%", comparison, self.target)
+    }
+}
+
+/// Current KPI values; metrics without data (no closed CAPAs, no training
+/// assignments, ...) are left out rather than reported as 0%.
+pub fn measure(
+    capas: &[CapaRecord],
+    training: &TrainingMetrics,
+    suppliers: &SupplierMetrics,
+    now: DateTime<Utc>,
+) -> Vec<(KpiMetric, f64)> {
+    let mut values = Vec::new();
+    let closed: Vec<&CapaRecord> = capas.iter().filter(|c| c.status == CapaStatus::Closed).collect();
+    if !closed.is_empty() {
+        let on_time = closed
+            .iter()
+            .filter(|c| match c.due_date {
+                Some(due) => c.closed_date.unwrap_or(c.updated_at) <= due,
+                None => true,
+            })
+            .count();
+        values.push((KpiMetric::CapaOnTimeClosure, percent(on_time, closed.len())));
+    }
+    let open: Vec<&CapaRecord> =
+        capas.iter().filter(|c| !matches!(c.status, CapaStatus::Closed | CapaStatus::Cancelled)).collect();
+    if !open.is_empty() {
+        let overdue = open.iter().filter(|c| c.due_date.is_some_and(|due| due < now)).count();
+        values.push((KpiMetric::CapaOverdue, percent(overdue, open.len())));
+    }
+    if training.total_count > 0 {
+        values.push((KpiMetric::TrainingCompletion, percent(training.completed, training.total_count)));
+    }
+    if suppliers.total_count > 0 {
+        values.push((KpiMetric::SupplierQualification, suppliers.qualified_percentage));
+    }
+    values
+}
+
+fn percent(part: usize, total: usize) -> f64 {
+    part as f64 * 100.0 / total as f64
+}
+
+/// A month's value of a KPI.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KpiTrendPoint {
+    /// Calendar month as `YYYY-MM`
+    pub month: String,
+    pub value: f64,
+}
+
+/// A KPI with its target, status and recent trend.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KpiStatus {
+    pub metric: KpiMetric,
+    pub label: String,
+    pub actual: f64,
+    pub target: KpiTarget,
+    pub status: RagStatus,
+    /// Monthly values, oldest first, ending with the current month
+    pub trend: Vec<KpiTrendPoint>,
+}
+
+impl KpiStatus {
+    /// Value of the month before the current one, if recorded.
+    pub fn previous(&self) -> Option<f64> {
+        self.trend.len().checked_sub(2).map(|index| self.trend[index].value)
+    }
+
+    /// Change in percentage points since the previous month.
+    pub fn change(&self) -> Option<f64> {
+        self.previous().map(|previous| self.actual - previous)
+    }
+}
+
+fn month_key(now: DateTime<Utc>) -> String {
+    now.format("%Y-%m").to_string()
+}
+
+/// KPI targets and monthly snapshots.
+pub struct KpiRepo<'a> {
+    db: &'a Database,
+}
+
+impl<'a> KpiRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Override the configured target of a metric.
+    pub fn set_target(&self, target: &KpiTarget, updated_by: &str) -> Result<()> {
+        target.validate()?;
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "INSERT INTO kpi_targets (metric, target, amber_margin, updated_by, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT(metric) DO UPDATE SET
+                     target = excluded.target, amber_margin = excluded.amber_margin,
+                     updated_by = excluded.updated_by, updated_at = excluded.updated_at",
+                params![
+                    target.metric.as_str(),
+                    target.target,
+                    target.amber_margin,
+                    updated_by,
+                    Utc::now().to_rfc3339()
+                ],
+            )?;
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                updated_by,
+                "kpi_target_set",
+                &format!("kpi_target:{}", target.metric.as_str()),
+                "Success",
+                Some(serde_json::to_string(target)?),
+            )
+        })
+    }
+
+    /// `configured` targets with the database overrides applied.
+    pub fn targets(&self, configured: &[KpiTarget]) -> Result<Vec<KpiTarget>> {
+        let overrides = self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare("SELECT metric, target, amber_margin FROM kpi_targets")?;
+            let rows = stmt.query_map([], row_to_target)?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rows)
+        })?;
+        let mut targets: Vec<KpiTarget> = configured
+            .iter()
+            .map(|target| overrides.iter().find(|o| o.metric == target.metric).copied().unwrap_or(*target))
+            .collect();
+        targets.extend(overrides.into_iter().filter(|o| !configured.iter().any(|t| t.metric == o.metric)));
+        Ok(targets)
+    }
+
+    /// Store `values` as this month's snapshot and rate every targeted metric
+    /// that has a value.
+    pub fn evaluate(
+        &self,
+        targets: &[KpiTarget],
+        values: &[(KpiMetric, f64)],
+        now: DateTime<Utc>,
+    ) -> Result<Vec<KpiStatus>> {
+        let month = month_key(now);
+        self.db.unit_of_work(|uow| {
+            for (metric, value) in values {
+                uow.connection().execute(
+                    "INSERT INTO kpi_snapshots (metric, month, value, recorded_at) VALUES (?1, ?2, ?3, ?4)
+                     ON CONFLICT(metric, month) DO UPDATE SET value = excluded.value, recorded_at = excluded.recorded_at",
+                    params![metric.as_str(), month, value, now.to_rfc3339()],
+                )?;
+            }
+            Ok(())
+        })?;
+
+        let mut statuses = Vec::new();
+        for target in targets {
+            let Some((_, actual)) = values.iter().find(|(metric, _)| *metric == target.metric) else {
+                continue;
+            };
+            statuses.push(KpiStatus {
+                metric: target.metric,
+                label: target.metric.label().to_string(),
+                actual: *actual,
+                target: *target,
+                status: target.status(*actual),
+                trend: self.trend(target.metric, &month, KPI_TREND_MONTHS)?,
+            });
+        }
+        Ok(statuses)
+    }
+
+    /// Up to `months` monthly values of `metric` up to `until` (`YYYY-MM`), oldest first.
+    pub fn trend(&self, metric: KpiMetric, until: &str, months: usize) -> Result<Vec<KpiTrendPoint>> {
+        let mut points = self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT month, value FROM kpi_snapshots WHERE metric = ?1 AND month <= ?2
+                 ORDER BY month DESC LIMIT ?3",
+            )?;
+            let points = stmt
+                .query_map(params![metric.as_str(), until, months as i64], |row| {
+                    Ok(KpiTrendPoint { month: row.get(0)?, value: row.get(1)? })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(points)
+        })?;
+        points.reverse();
+        Ok(points)
+    }
+}
+
+fn row_to_target(row: &Row) -> rusqlite::Result<KpiTarget> {
+    let metric: String = row.get(0)?;
+    Ok(KpiTarget {
+        metric: KpiMetric::parse(&metric).ok_or_else(|| {
+            rusqlite::Error::FromSqlConversionFailure(
+                0,
+                rusqlite::types::Type::Text,
+                format!("unexpected value {}", metric).into(),
+            )
+        })?,
+        target: row.get(1)?,
+        amber_margin: row.get(2)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capa::{CapaPriority, CapaType};
+    use crate::config::DatabaseConfig;
+    use chrono::{Duration, TimeZone};
+    use std::collections::HashMap;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    fn capa(status: CapaStatus, due_in_days: i64, closed_after_days: Option<i64>, now: DateTime<Utc>) -> CapaRecord {
+        let created_at = now - Duration::days(30);
+        CapaRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            capa_number: String::new(),
+            title: "Seal failure".to_string(),
+            description: String::new(),
+            capa_type: CapaType::Corrective,
+            priority: CapaPriority::High,
+            status,
+            initiator_id: "qa".to_string(),
+            assigned_to: "qa".to_string(),
+            created_at,
+            updated_at: created_at,
+            due_date: Some(now + Duration::days(due_in_days)),
+            closed_date: closed_after_days.map(|d| created_at + Duration::days(d)),
+            source_document: None,
+            related_risk_id: None,
+            investigation_summary: None,
+            root_cause: None,
+            corrective_actions: Vec::new(),
+            preventive_actions: Vec::new(),
+            effectiveness_verification: None,
+            metadata: HashMap::new(),
+            row_version: crate::concurrency::INITIAL_ROW_VERSION,
+        }
+    }
+
+    #[test]
+    fn test_rag_status_against_targets() {
+        let closure = KpiTarget::new(KpiMetric::CapaOnTimeClosure, 90.0);
+        assert_eq!(closure.status(92.0), RagStatus::Green);
+        assert_eq!(closure.status(86.0), RagStatus::Amber);
+        assert_eq!(closure.status(80.0), RagStatus::Red);
+        assert_eq!(closure.describe(), ">= 90.0%");
+
+        let overdue = KpiTarget { metric: KpiMetric::CapaOverdue, target: 10.0, amber_margin: 2.0 };
+        assert_eq!(overdue.status(5.0), RagStatus::Green);
+        assert_eq!(overdue.status(11.5), RagStatus::Amber);
+        assert_eq!(overdue.status(20.0), RagStatus::Red);
+        assert!(KpiTarget::new(KpiMetric::TrainingCompletion, 120.0).validate().is_err());
+    }
+
+    #[test]
+    fn test_measure_skips_metrics_without_data() {
+        let now = Utc::now();
+        let capas = vec![
+            capa(CapaStatus::Closed, -5, Some(20), now),
+            capa(CapaStatus::Closed, -5, Some(29), now),
+            capa(CapaStatus::InvestigationInProgress, -1, None, now),
+            capa(CapaStatus::InvestigationInProgress, 10, None, now),
+        ];
+        let training = TrainingMetrics { total_count: 20, completed: 19, pending: 1, overdue: 0 };
+        let values = measure(&capas, &training, &SupplierMetrics::default(), now);
+        assert_eq!(
+            values,
+            vec![
+                (KpiMetric::CapaOnTimeClosure, 50.0),
+                (KpiMetric::CapaOverdue, 50.0),
+                (KpiMetric::TrainingCompletion, 95.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overrides_and_month_over_month_trend() {
+        let db = test_db();
+        let repo = KpiRepo::new(&db);
+        repo.set_target(&KpiTarget::new(KpiMetric::TrainingCompletion, 98.0), "qa_manager").unwrap();
+        assert!(repo.set_target(&KpiTarget::new(KpiMetric::TrainingCompletion, -1.0), "qa_manager").is_err());
+        let targets = repo.targets(&KpiTarget::defaults()).unwrap();
+        assert_eq!(targets.len(), 3);
+        assert_eq!(targets[1].target, 98.0);
+        assert_eq!(db.get_audit_entries_for_resource("kpi_target:training_completion").unwrap().len(), 1);
+
+        let february = Utc.with_ymd_and_hms(2025, 2, 10, 9, 0, 0).unwrap();
+        let march = Utc.with_ymd_and_hms(2025, 3, 10, 9, 0, 0).unwrap();
+        repo.evaluate(&targets, &[(KpiMetric::TrainingCompletion, 90.0)], february).unwrap();
+        repo.evaluate(&targets, &[(KpiMetric::TrainingCompletion, 94.0)], march).unwrap();
+        let statuses = repo.evaluate(&targets, &[(KpiMetric::TrainingCompletion, 96.0)], march).unwrap();
+
+        assert_eq!(statuses.len(), 1, "metrics without values are not rated");
+        let training = &statuses[0];
+        assert_eq!(training.status, RagStatus::Amber);
+        assert_eq!(
+            training.trend,
+            vec![
+                KpiTrendPoint { month: "2025-02".to_string(), value: 90.0 },
+                KpiTrendPoint { month: "2025-03".to_string(), value: 96.0 },
+            ]
+        );
+        assert_eq!(training.previous(), Some(90.0));
+        assert_eq!(training.change(), Some(6.0));
+    }
+}
@@ -0,0 +1,301 @@
+//! # Legal Hold - Preservation of Records Under Litigation
+//!
+//! Records relevant to litigation, an investigation or a regulatory inquiry
+//! must be preserved as they are, whatever their retention period allows. A
+//! [`LegalHold`] covers one record (`record_table` + `record_id`) or a whole
+//! record class (every record of `record_table`). While a hold is active the
+//! record cannot be archived (soft-deleted), purged or redacted, and API
+//! detail views list the hold. Placing and releasing a hold requires a reason
+//! and is audited; the API restricts both to administrators.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::change_history::ChangeReason;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::privacy::PERSONAL_DATA_SOURCES;
+use crate::soft_delete::SOFT_DELETE_TABLES;
+
+/// A hold on a record or record class.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegalHold {
+    pub id: Uuid,
+    pub record_table: String,
+    /// `None` when the hold covers every record of `record_table`
+    pub record_id: Option<String>,
+    pub reason: String,
+    pub placed_by: String,
+    pub placed_at: DateTime<Utc>,
+    pub released_by: Option<String>,
+    pub released_at: Option<DateTime<Utc>>,
+    pub release_reason: Option<String>,
+}
+
+impl LegalHold {
+    pub fn is_active(&self) -> bool {
+        self.released_at.is_none()
+    }
+
+    /// What the hold covers, e.g. `capa_records:<id>` or `capa_records (all records)`.
+    pub fn scope(&self) -> String {
+        match &self.record_id {
+            Some(id) => format!("{}:{}", self.record_table, id),
+            None => format!("{} (all records)", self.record_table),
+        }
+    }
+}
+
+/// Tables whose records can be held: those that can be archived, purged or
+/// redacted.
+pub fn is_holdable(table: &str) -> bool {
+    SOFT_DELETE_TABLES.iter().any(|(name, _)| *name == table)
+        || PERSONAL_DATA_SOURCES.iter().any(|source| source.table == table && source.retained_because.is_none())
+}
+
+/// Places, releases and checks legal holds. Permission checks are the
+/// caller's responsibility.
+pub struct LegalHoldService<'a> {
+    db: &'a Database,
+}
+
+impl<'a> LegalHoldService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Hold one record, or the whole class when `record_id` is `None`.
+    pub fn place(
+        &self,
+        record_table: &str,
+        record_id: Option<&str>,
+        user_id: &str,
+        reason: &ChangeReason,
+    ) -> Result<LegalHold> {
+        if !is_holdable(record_table) {
+            return Err(QmsError::Validation {
+                field: "record_table".to_string(),
+                message: format!("{} records cannot be placed under legal hold", record_table),
+            });
+        }
+        let record_id = record_id.map(str::trim);
+        if record_id == Some("") {
+            return Err(QmsError::Validation {
+                field: "record_id".to_string(),
+                message: "Record ID must not be blank; omit it to hold the whole record class".to_string(),
+            });
+        }
+        let hold = LegalHold {
+            id: Uuid::new_v4(),
+            record_table: record_table.to_string(),
+            record_id: record_id.map(str::to_string),
+            reason: reason.as_str().to_string(),
+            placed_by: user_id.to_string(),
+            placed_at: Utc::now(),
+            released_by: None,
+            released_at: None,
+            release_reason: None,
+        };
+        let audit = AuditManager::new(self.db.clone());
+        self.db.unit_of_work(|uow| {
+            let duplicate: Option<String> = uow
+                .connection()
+                .query_row(
+                    "SELECT id FROM legal_holds
+                     WHERE record_table = ?1 AND record_id IS ?2 AND released_at IS NULL",
+                    params![hold.record_table, hold.record_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(duplicate) = duplicate {
+                return Err(QmsError::Validation {
+                    field: "record_id".to_string(),
+                    message: format!("{} is already under legal hold {}", hold.scope(), duplicate),
+                });
+            }
+            uow.connection().execute(
+                "INSERT INTO legal_holds (id, record_table, record_id, reason, placed_by, placed_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    hold.id.to_string(),
+                    hold.record_table,
+                    hold.record_id,
+                    hold.reason,
+                    hold.placed_by,
+                    hold.placed_at.to_rfc3339()
+                ],
+            )?;
+            audit.log_action_in(
+                uow,
+                user_id,
+                "legal_hold_placed",
+                &format!("legal_hold:{}", hold.id),
+                "Success",
+                Some(format!("Scope: {}; reason: {}", hold.scope(), reason)),
+            )
+        })?;
+        Ok(hold)
+    }
+
+    /// Release an active hold.
+    pub fn release(&self, hold_id: Uuid, user_id: &str, reason: &ChangeReason) -> Result<LegalHold> {
+        let audit = AuditManager::new(self.db.clone());
+        self.db.unit_of_work(|uow| {
+            let mut hold = fetch(uow.connection(), hold_id)?
+                .ok_or_else(|| QmsError::NotFound { resource: "legal_hold".to_string(), id: hold_id.to_string() })?;
+            if !hold.is_active() {
+                return Err(QmsError::Validation {
+                    field: "hold_id".to_string(),
+                    message: format!("Legal hold {} is already released", hold_id),
+                });
+            }
+            let now = Utc::now();
+            uow.connection().execute(
+                "UPDATE legal_holds SET released_by = ?2, released_at = ?3, release_reason = ?4 WHERE id = ?1",
+                params![hold_id.to_string(), user_id, now.to_rfc3339(), reason.as_str()],
+            )?;
+            audit.log_action_in(
+                uow,
+                user_id,
+                "legal_hold_released",
+                &format!("legal_hold:{}", hold_id),
+                "Success",
+                Some(format!("Scope: {}; reason: {}", hold.scope(), reason)),
+            )?;
+            hold.released_by = Some(user_id.to_string());
+            hold.released_at = Some(now);
+            hold.release_reason = Some(reason.as_str().to_string());
+            Ok(hold)
+        })
+    }
+
+    /// All active holds, oldest first.
+    pub fn active(&self) -> Result<Vec<LegalHold>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM legal_holds WHERE released_at IS NULL ORDER BY placed_at, id",
+                COLUMNS
+            ))?;
+            let holds = stmt.query_map([], row_to_hold)?.collect::<std::result::Result<Vec<_>, _>>()?;
+            Ok(holds)
+        })
+    }
+
+    /// Active holds covering a record, by the record itself or its class.
+    pub fn holds_on(&self, record_table: &str, record_id: &str) -> Result<Vec<LegalHold>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM legal_holds
+                 WHERE record_table = ?1 AND (record_id IS NULL OR record_id = ?2) AND released_at IS NULL
+                 ORDER BY placed_at, id",
+                COLUMNS
+            ))?;
+            let holds = stmt
+                .query_map(params![record_table, record_id], row_to_hold)?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            Ok(holds)
+        })
+    }
+
+    /// Refuse `operation` on a held record.
+    pub fn ensure_not_held(&self, record_table: &str, record_id: &str, operation: &str) -> Result<()> {
+        match self.holds_on(record_table, record_id)?.first() {
+            None => Ok(()),
+            Some(hold) => Err(QmsError::Validation {
+                field: "legal_hold".to_string(),
+                message: format!(
+                    "{}:{} is under legal hold {} ({}); {} is not permitted",
+                    record_table, record_id, hold.id, hold.reason, operation
+                ),
+            }),
+        }
+    }
+}
+
+const COLUMNS: &str =
+    "id, record_table, record_id, reason, placed_by, placed_at, released_by, released_at, release_reason";
+
+fn fetch(conn: &rusqlite::Connection, hold_id: Uuid) -> Result<Option<LegalHold>> {
+    Ok(conn
+        .query_row(
+            &format!("SELECT {} FROM legal_holds WHERE id = ?1", COLUMNS),
+            params![hold_id.to_string()],
+            row_to_hold,
+        )
+        .optional()?)
+}
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn parse_time(row: &Row, index: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| conversion_error(index, e.to_string()))
+        })
+        .transpose()
+}
+
+fn row_to_hold(row: &Row) -> rusqlite::Result<LegalHold> {
+    let id: String = row.get(0)?;
+    Ok(LegalHold {
+        id: Uuid::parse_str(&id).map_err(|e| conversion_error(0, e.to_string()))?,
+        record_table: row.get(1)?,
+        record_id: row.get(2)?,
+        reason: row.get(3)?,
+        placed_by: row.get(4)?,
+        placed_at: parse_time(row, 5)?.ok_or_else(|| conversion_error(5, "placed_at is missing".to_string()))?,
+        released_by: row.get(6)?,
+        released_at: parse_time(row, 7)?,
+        release_reason: row.get(8)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+
+    #[test]
+    fn test_place_check_and_release_holds() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let service = LegalHoldService::new(&db);
+        let reason = ChangeReason::new("Litigation 2026-CV-118").unwrap();
+
+        let record = service.place("capa_records", Some("capa-1"), "admin", &reason).unwrap();
+        assert!(service.place("capa_records", Some("capa-1"), "admin", &reason).is_err(), "one active hold per scope");
+        assert!(service.place("audit_trail", None, "admin", &reason).is_err());
+        assert_eq!(service.holds_on("capa_records", "capa-1").unwrap(), vec![record.clone()]);
+        assert!(service.ensure_not_held("capa_records", "capa-2", "purge").is_ok());
+
+        let class = service.place("capa_records", None, "admin", &reason).unwrap();
+        assert_eq!(class.scope(), "capa_records (all records)");
+        assert!(matches!(
+            service.ensure_not_held("capa_records", "capa-2", "purge"),
+            Err(QmsError::Validation { field, .. }) if field == "legal_hold"
+        ));
+
+        let released = service.release(class.id, "admin", &ChangeReason::new("Case settled").unwrap()).unwrap();
+        assert!(!released.is_active());
+        assert!(service.release(class.id, "admin", &reason).is_err());
+        assert!(service.ensure_not_held("capa_records", "capa-2", "purge").is_ok());
+        assert_eq!(service.active().unwrap(), vec![record]);
+
+        let audit = db.get_audit_entries_for_resource(&format!("legal_hold:{}", class.id)).unwrap();
+        let actions: Vec<&str> = audit.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(actions, vec!["legal_hold_placed", "legal_hold_released"]);
+    }
+}
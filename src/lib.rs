@@ -14,12 +14,91 @@
 //! The system follows SOLID principles and implements comprehensive testing
 //! to ensure reliability and regulatory compliance.
 
+pub mod access_review; // Phase 4: Quarterly user access review campaigns
 pub mod app;
+pub mod app_context; // Phase 4: Shared state for TUI, API and services
 pub mod audit;
+pub mod audit_worm; // Phase 4: Write-once sealed audit trail segments
+pub mod audit_findings; // Phase 4: Audit finding commitments & aging
+pub mod audit_review; // Phase 4: Periodic sampled audit trail review
+pub mod user_activity; // Phase 4: Per-user activity reports
+pub mod change_history; // Phase 4: Reason-for-change & field-level history
+pub mod record_history; // Phase 4: Record version snapshots & as-of views
+pub mod soft_delete; // Phase 4: Soft delete & retention enforcement
+pub mod legal_hold; // Phase 4: Legal holds blocking archival, purge & redaction
+pub mod clinical; // Phase 4: Clinical evaluation & literature review tracking
+pub mod mdr_clock; // Phase 4: MDR regulatory reporting clocks
+pub mod mir_export; // Phase 4: EU MDR manufacturer incident report export
+pub mod standards; // Phase 4: Standards & regulation register
+pub mod compliance_matrix; // Phase 4: Clause-to-document compliance matrix
+pub mod inspection_package; // Phase 4: Inspection-ready subsystem packages
+pub mod site; // Phase 4: Multi-site data partitioning
+pub mod i18n; // Phase 4: Localized UI, API and report strings
+pub mod display_time; // Phase 4: Time zone aware display
+pub mod time_integrity; // Phase 4: NTP clock drift detection
+pub mod db_stats; // Phase 4: Connection pool observability
+pub mod db_maintenance; // Phase 4: SQLite checkpoint, vacuum & integrity checks
+pub mod reporting_views; // Phase 4: Materialized reporting summaries
+pub mod analytics_export; // Phase 4: Denormalized fact snapshots for BI tools
+pub mod unit_of_work; // Phase 4: Atomic multi-step operations & e-signatures
+pub mod events; // Phase 4: Domain event bus
+pub mod store; // Phase 4: Repository traits & in-memory stores
+pub mod quality_events; // Phase 4: Critical error escalation & acknowledgement
+pub mod quality_intake; // Phase 4: Quality issue intake & triage routing
+pub mod validation; // Phase 4: Process & software tool validation (IQ/OQ/PQ)
+pub mod environmental; // Phase 4: Cleanroom environmental monitoring & excursions
+pub mod evidence_pack; // Phase 4: Software validation evidence for QMSrs itself
+pub mod access_audit; // Phase 4: CFR Part 11 read-access audit trail
+pub mod search; // Phase 4: Cross-module record search
+pub mod links; // Phase 4: Record traceability links & graph export
+pub mod escalation; // Phase 4: Reminder & escalation rules engine
+pub mod kpi; // Phase 4: Dashboard KPI targets & RAG status
+pub mod vigilance_export; // Phase 4: Pseudonymized complaint & AE exports
+pub mod privacy; // Phase 4: GDPR data subject access & erasure
+pub mod authorization; // Phase 4: User roles & permission checks
+pub mod oidc; // Phase 4: OpenID Connect single sign-on
+pub mod request_audit; // Phase 4: API mutation audit logging & redaction
+pub mod network_policy; // Phase 4: API address allow/deny lists & mutual TLS
+pub mod concurrency; // Phase 4: Optimistic concurrency via record versions
 pub mod cli;
 pub mod config;
+pub mod config_crypto; // Phase 4: Encrypted configuration sections
+pub mod self_test; // Phase 4: Startup self-test & compliance checklist
+pub mod storage_monitor; // Phase 4: Disk space & database growth monitoring
+pub mod crash_guard; // Phase 4: Crash-safe terminal restoration & crash audit
+pub mod record_actions; // Phase 4: TUI action menus on selected records
+pub mod audit_tail; // Phase 4: Live audit trail tail for the TUI
+pub mod tui_theme; // Phase 4: Accessible TUI rendering
+pub mod command_output; // Phase 4: JSON output and exit codes for CLI commands
+pub mod grpc; // Phase 4: gRPC interface for manufacturing systems
+pub mod graphql; // Phase 4: GraphQL read models for dashboards
+pub mod issue_sync; // Phase 4: CAPA action sync with external issue trackers
+pub mod lims; // Phase 4: LIMS test result ingestion as objective evidence
+pub mod email_intake; // Phase 4: Email-to-complaint intake gateway
+pub mod spc; // Phase 4: Statistical process control charts
+pub mod pareto; // Phase 4: Pareto ranking of complaint and NCR causes
+pub mod cost_of_quality; // Phase 4: Cost of quality per quarter
+pub mod complaint_rate; // Phase 4: Complaint and adverse event rates per units shipped
+pub mod supplier_documents; // Phase 4: Supplier certificates and agreements with expiry alerts
+pub mod controlled_prints; // Phase 4: Controlled print issue and recall reconciliation
+pub mod tasks; // Phase 4: Ad hoc quality tasks and the per-user task inbox
+pub mod demo_seed; // Phase 4: Demo data for evaluation
+pub mod clock; // Phase 4: Injectable clock for time-dependent logic
+pub mod read_cache; // Phase 4: Cache for hot read paths
+pub mod shutdown; // Phase 4: Graceful shutdown coordinator
+pub mod workflow_journal; // Phase 4: Crash recovery journal for multi-step workflows
+pub mod feature_flags; // Phase 4: Feature flags for phased module rollout
+pub mod record_plugins; // Phase 4: Plugin API for integrator-defined record types
+pub mod validation_scripts; // Phase 4: Sandboxed scripts for customer-specific validation rules
+pub mod workflow; // Phase 4: Declarative status machines shared by record types
+pub mod change_control; // Phase 4: Change request workflow
+pub mod ownership_transfer; // Phase 4: Hand-over of open work on user deactivation
+pub mod delegation; // Phase 4: Out-of-office delegation of approval authority
+pub mod batch_record; // Phase 4: Electronic batch records (DHR) reviewed by exception
 pub mod database;
+pub mod migrations; // Versioned schema migrations
 pub mod document;
+pub mod numbering; // Phase 4: Configurable record numbering
 pub mod error;
 pub mod logging;
 pub mod risk;
@@ -31,7 +110,17 @@ pub mod training; // Phase 3: Training records module
 pub mod training_repo; // Phase 3: Training records persistence layer
 pub mod supplier_repo; // Phase 3: Supplier management persistence
 pub mod supplier; // Phase 3: Supplier management domain
+pub mod report; // Phase 4: Renderer-independent report model
 pub mod pdf_report; // Phase 4: Compliance PDF reporting
+pub mod html_report; // Phase 4: Self-contained HTML report output
+pub mod capa_report; // Phase 4: Per-CAPA PDF export
+pub mod risk_report; // Phase 4: Per-device risk management PDF
+pub mod keystore; // Phase 4: System signing key management
+pub mod report_signature; // Phase 4: Signed & checksummed report outputs
+pub mod file_store; // Phase 4: Write-once EDMS record storage
+pub mod notification; // Phase 4: Email notification outbox
+pub mod report_scheduler; // Phase 4: Scheduled report generation & distribution
+pub mod jobs; // Phase 4: Background job scheduling & run history
 pub mod post_market; // Phase 5: Post-market surveillance
 
 pub use error::{QmsError, Result};
@@ -0,0 +1,575 @@
+//! # LIMS - Automated Test Result Ingestion
+//!
+//! Lab information systems and automated test stations export results as
+//! CSV or JSON. [`TestResultIngest`] parses such an export, evaluates each
+//! result against its acceptance criteria and attaches the batch as
+//! objective evidence to a CAPA effectiveness verification, a validation
+//! protocol or an incoming inspection, so nobody retypes lab data.
+//!
+//! A result passes when its value lies within the given limits, matches the
+//! expected text, or, without either criterion, when the lab system itself
+//! reported a pass. Results for a validation protocol are also recorded as
+//! execution evidence of the protocol step named by the test. The raw
+//! export's SHA-256 is kept with the batch, the batch is linked to its
+//! record, and the ingestion is audited.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::keystore::sha256_hex;
+use crate::links::{link_with, LinkType, RecordRef};
+use crate::validation::ValidationRepo;
+
+/// Largest export accepted in one batch.
+pub const MAX_RESULTS_PER_BATCH: usize = 10_000;
+
+/// File format of a result export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultFormat {
+    Csv,
+    Json,
+}
+
+impl ResultFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResultFormat::Csv => "csv",
+            ResultFormat::Json => "json",
+        }
+    }
+
+    /// Format named by a `Content-Type`, or recognized from the content.
+    pub fn detect(content_type: Option<&str>, content: &str) -> Self {
+        match content_type.map(|ct| ct.split(';').next().unwrap_or_default().trim()) {
+            Some("text/csv") => ResultFormat::Csv,
+            Some("application/json") => ResultFormat::Json,
+            _ if content.trim_start().starts_with(['[', '{']) => ResultFormat::Json,
+            _ => ResultFormat::Csv,
+        }
+    }
+}
+
+/// Record the results are evidence for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvidenceTarget {
+    /// Effectiveness verification of a CAPA (`capa_records.id`)
+    CapaVerification,
+    /// Validation protocol (`validation_protocols.id`)
+    Validation,
+    /// Incoming inspection of a receipt or lot
+    IncomingInspection,
+}
+
+impl EvidenceTarget {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EvidenceTarget::CapaVerification => "capa_verification",
+            EvidenceTarget::Validation => "validation",
+            EvidenceTarget::IncomingInspection => "incoming_inspection",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [EvidenceTarget::CapaVerification, EvidenceTarget::Validation, EvidenceTarget::IncomingInspection]
+            .into_iter()
+            .find(|target| target.as_str() == value)
+    }
+
+    /// The record in the traceability links.
+    pub fn record(&self, record_id: &str) -> RecordRef {
+        match self {
+            EvidenceTarget::CapaVerification => RecordRef::new("capa", record_id),
+            EvidenceTarget::Validation => RecordRef::new("validation_protocol", record_id),
+            EvidenceTarget::IncomingInspection => RecordRef::new("incoming_inspection", record_id),
+        }
+    }
+}
+
+/// One measurement as exported by the lab system.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RawResult {
+    pub sample_id: String,
+    pub test: String,
+    /// Measured value; numeric when limits apply
+    #[serde(default)]
+    pub value: Option<String>,
+    #[serde(default)]
+    pub unit: Option<String>,
+    #[serde(default)]
+    pub lower_limit: Option<f64>,
+    #[serde(default)]
+    pub upper_limit: Option<f64>,
+    /// Expected text result, e.g. "negative"
+    #[serde(default)]
+    pub expected: Option<String>,
+    /// Pass/fail as reported by the lab system
+    #[serde(default)]
+    pub result: Option<String>,
+    #[serde(default)]
+    pub tested_at: Option<String>,
+    #[serde(default)]
+    pub instrument: Option<String>,
+}
+
+impl RawResult {
+    /// Evaluate against the acceptance criteria; results without any
+    /// criterion cannot be evaluated and are rejected.
+    pub fn evaluate(&self) -> Result<bool> {
+        let invalid =
+            |message: String| QmsError::Validation { field: format!("{}/{}", self.sample_id, self.test), message };
+        let value = self.value.as_deref().map(str::trim).filter(|v| !v.is_empty());
+        if self.lower_limit.is_some() || self.upper_limit.is_some() {
+            let value = value.ok_or_else(|| invalid("Limits given without a value".to_string()))?;
+            let number: f64 = value.parse().map_err(|_| invalid(format!("'{}' is not a number", value)))?;
+            return Ok(self.lower_limit.map_or(true, |lower| number >= lower)
+                && self.upper_limit.map_or(true, |upper| number <= upper));
+        }
+        if let Some(expected) = self.expected.as_deref().filter(|e| !e.trim().is_empty()) {
+            return Ok(value.map_or(false, |value| value.eq_ignore_ascii_case(expected.trim())));
+        }
+        match self.result.as_deref().map(|r| r.trim().to_ascii_lowercase()).as_deref() {
+            Some("pass" | "passed" | "ok") => Ok(true),
+            Some("fail" | "failed" | "nok") => Ok(false),
+            _ => Err(invalid("No limits, expected value or pass/fail result to evaluate".to_string())),
+        }
+    }
+}
+
+/// A stored result with its evaluation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestResult {
+    #[serde(flatten)]
+    pub raw: RawResult,
+    pub passed: bool,
+}
+
+/// An ingested export attached to a record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultBatch {
+    pub id: String,
+    pub source_system: String,
+    pub format: ResultFormat,
+    pub target: EvidenceTarget,
+    pub record_id: String,
+    /// SHA-256 of the export as received
+    pub sha256: String,
+    pub result_count: usize,
+    pub failed_count: usize,
+    pub ingested_by: String,
+    pub ingested_at: DateTime<Utc>,
+}
+
+impl ResultBatch {
+    pub fn passed(&self) -> bool {
+        self.failed_count == 0
+    }
+}
+
+/// Parse an export in `format`.
+pub fn parse(content: &str, format: ResultFormat) -> Result<Vec<RawResult>> {
+    let results = match format {
+        ResultFormat::Csv => parse_csv(content)?,
+        ResultFormat::Json => parse_json(content)?,
+    };
+    if results.is_empty() {
+        return Err(QmsError::Validation {
+            field: "results".to_string(),
+            message: "The export holds no results".to_string(),
+        });
+    }
+    if results.len() > MAX_RESULTS_PER_BATCH {
+        return Err(QmsError::Validation {
+            field: "results".to_string(),
+            message: format!("At most {} results per batch", MAX_RESULTS_PER_BATCH),
+        });
+    }
+    Ok(results)
+}
+
+/// A JSON array of results, or an object with a `results` array.
+fn parse_json(content: &str) -> Result<Vec<RawResult>> {
+    let value: Value = serde_json::from_str(content).map_err(|e| QmsError::Serialization { message: e.to_string() })?;
+    let results = match value {
+        Value::Object(mut object) => object.remove("results").unwrap_or(Value::Null),
+        other => other,
+    };
+    // Lab systems write numbers as numbers; values are kept as text
+    let results = match results {
+        Value::Array(items) => items
+            .into_iter()
+            .map(|mut item| {
+                if let Some(number @ Value::Number(_)) = item.get("value").cloned() {
+                    item["value"] = Value::String(number.to_string());
+                }
+                item
+            })
+            .collect(),
+        _ => return Err(QmsError::Serialization { message: "Expected an array of results".to_string() }),
+    };
+    serde_json::from_value(Value::Array(results)).map_err(|e| QmsError::Serialization { message: e.to_string() })
+}
+
+/// CSV with a header row naming the [`RawResult`] fields; column order is
+/// free and unknown columns are ignored.
+fn parse_csv(content: &str) -> Result<Vec<RawResult>> {
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty());
+    let header: Vec<String> =
+        split_csv_line(lines.next().unwrap_or_default()).into_iter().map(|h| h.trim().to_ascii_lowercase()).collect();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let (Some(sample), Some(test)) = (column("sample_id"), column("test")) else {
+        return Err(QmsError::Validation {
+            field: "header".to_string(),
+            message: "The CSV header needs sample_id and test columns".to_string(),
+        });
+    };
+    let columns =
+        ["value", "unit", "lower_limit", "upper_limit", "expected", "result", "tested_at", "instrument"].map(column);
+    lines
+        .enumerate()
+        .map(|(index, line)| {
+            let fields = split_csv_line(line);
+            let text = |position: Option<usize>| {
+                position.and_then(|p| fields.get(p)).map(|f| f.trim().to_string()).filter(|f| !f.is_empty())
+            };
+            let limit = |position: Option<usize>| {
+                text(position)
+                    .map(|value| {
+                        value.parse::<f64>().map_err(|_| QmsError::Validation {
+                            field: format!("line {}", index + 2),
+                            message: format!("Limit '{}' is not a number", value),
+                        })
+                    })
+                    .transpose()
+            };
+            Ok(RawResult {
+                sample_id: text(Some(sample)).unwrap_or_default(),
+                test: text(Some(test)).unwrap_or_default(),
+                value: text(columns[0]),
+                unit: text(columns[1]),
+                lower_limit: limit(columns[2])?,
+                upper_limit: limit(columns[3])?,
+                expected: text(columns[4]),
+                result: text(columns[5]),
+                tested_at: text(columns[6]),
+                instrument: text(columns[7]),
+            })
+        })
+        .collect()
+}
+
+/// Split one CSV line; fields may be quoted with `"` and quotes doubled.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Ingestion and retrieval of result batches.
+pub struct TestResultIngest<'a> {
+    db: &'a Database,
+}
+
+impl<'a> TestResultIngest<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Parse `content`, evaluate every result and attach the batch to the
+    /// record. Nothing is stored when a result cannot be evaluated or the
+    /// record does not exist.
+    #[allow(clippy::too_many_arguments)]
+    pub fn ingest(
+        &self,
+        content: &str,
+        format: ResultFormat,
+        source_system: &str,
+        target: EvidenceTarget,
+        record_id: &str,
+        ingested_by: &str,
+        now: DateTime<Utc>,
+    ) -> Result<ResultBatch> {
+        if source_system.trim().is_empty() {
+            return Err(QmsError::Validation {
+                field: "source".to_string(),
+                message: "The sending lab system is required".to_string(),
+            });
+        }
+        let results = parse(content, format)?
+            .into_iter()
+            .map(|raw| raw.evaluate().map(|passed| TestResult { raw, passed }))
+            .collect::<Result<Vec<_>>>()?;
+        self.ensure_record(target, record_id)?;
+        let batch = ResultBatch {
+            id: Uuid::new_v4().to_string(),
+            source_system: source_system.trim().to_string(),
+            format,
+            target,
+            record_id: record_id.to_string(),
+            sha256: sha256_hex(content.as_bytes()),
+            result_count: results.len(),
+            failed_count: results.iter().filter(|result| !result.passed).count(),
+            ingested_by: ingested_by.to_string(),
+            ingested_at: now,
+        };
+        let audit = AuditManager::new(self.db.clone());
+        self.db.unit_of_work(|uow| {
+            let conn = uow.connection();
+            conn.execute(
+                "INSERT INTO test_result_batches (id, source_system, format, target, record_id, sha256, result_count,
+                                                  failed_count, ingested_by, ingested_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    batch.id,
+                    batch.source_system,
+                    format.as_str(),
+                    target.as_str(),
+                    batch.record_id,
+                    batch.sha256,
+                    batch.result_count as i64,
+                    batch.failed_count as i64,
+                    batch.ingested_by,
+                    now.to_rfc3339(),
+                ],
+            )?;
+            for result in &results {
+                let raw = &result.raw;
+                conn.execute(
+                    "INSERT INTO test_results (id, batch_id, sample_id, test, value, unit, lower_limit, upper_limit,
+                                               expected, reported_result, passed, tested_at, instrument)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                    params![
+                        Uuid::new_v4().to_string(),
+                        batch.id,
+                        raw.sample_id,
+                        raw.test,
+                        raw.value,
+                        raw.unit,
+                        raw.lower_limit,
+                        raw.upper_limit,
+                        raw.expected,
+                        raw.result,
+                        result.passed,
+                        raw.tested_at,
+                        raw.instrument,
+                    ],
+                )?;
+            }
+            link_with(
+                conn,
+                &RecordRef::new("test_result_batch", batch.id.clone()),
+                &target.record(record_id),
+                LinkType::RelatesTo,
+                ingested_by,
+                now,
+            )?;
+            audit.log_action_in(
+                uow,
+                ingested_by,
+                "test_results_ingested",
+                &format!("{}:{}", target.as_str(), record_id),
+                if batch.passed() { "Success" } else { "Failure" },
+                Some(
+                    serde_json::json!({
+                        "batch": batch.id,
+                        "source": batch.source_system,
+                        "results": batch.result_count,
+                        "failed": batch.failed_count,
+                        "sha256": batch.sha256,
+                    })
+                    .to_string(),
+                ),
+            )
+        })?;
+        if target == EvidenceTarget::Validation {
+            let validation = ValidationRepo::new(self.db);
+            for result in &results {
+                let evidence_ref = format!("lims:{}:{}", batch.id, result.raw.sample_id);
+                validation.record_evidence(record_id, &result.raw.test, result.passed, &evidence_ref, ingested_by)?;
+            }
+        }
+        Ok(batch)
+    }
+
+    /// Batches attached to a record, newest first.
+    pub fn batches(&self, target: EvidenceTarget, record_id: &str) -> Result<Vec<ResultBatch>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, source_system, format, target, record_id, sha256, result_count, failed_count, ingested_by,
+                        ingested_at
+                 FROM test_result_batches WHERE target = ?1 AND record_id = ?2 ORDER BY ingested_at DESC, id",
+            )?;
+            let batches = stmt
+                .query_map(params![target.as_str(), record_id], row_to_batch)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(batches)
+        })
+    }
+
+    /// Results of a batch in export order.
+    pub fn results(&self, batch_id: &str) -> Result<Vec<TestResult>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT sample_id, test, value, unit, lower_limit, upper_limit, expected, reported_result, tested_at,
+                        instrument, passed
+                 FROM test_results WHERE batch_id = ?1 ORDER BY rowid",
+            )?;
+            let results = stmt
+                .query_map(params![batch_id], |row| {
+                    Ok(TestResult {
+                        raw: RawResult {
+                            sample_id: row.get(0)?,
+                            test: row.get(1)?,
+                            value: row.get(2)?,
+                            unit: row.get(3)?,
+                            lower_limit: row.get(4)?,
+                            upper_limit: row.get(5)?,
+                            expected: row.get(6)?,
+                            result: row.get(7)?,
+                            tested_at: row.get(8)?,
+                            instrument: row.get(9)?,
+                        },
+                        passed: row.get(10)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(results)
+        })
+    }
+
+    /// CAPAs and validation protocols must exist; incoming inspections are
+    /// identified by the receipt or lot number and need only be named.
+    fn ensure_record(&self, target: EvidenceTarget, record_id: &str) -> Result<()> {
+        let table = match target {
+            EvidenceTarget::CapaVerification => "capa_records",
+            EvidenceTarget::Validation => "validation_protocols",
+            EvidenceTarget::IncomingInspection if !record_id.trim().is_empty() => return Ok(()),
+            EvidenceTarget::IncomingInspection => {
+                return Err(QmsError::Validation {
+                    field: "record_id".to_string(),
+                    message: "The receipt or lot number is required".to_string(),
+                })
+            }
+        };
+        let exists: bool = self.db.with_connection(|conn| {
+            Ok(conn.query_row(
+                &format!("SELECT EXISTS (SELECT 1 FROM {} WHERE id = ?1)", table),
+                params![record_id],
+                |row| row.get(0),
+            )?)
+        })?;
+        if !exists {
+            return Err(QmsError::NotFound { resource: target.as_str().to_string(), id: record_id.to_string() });
+        }
+        Ok(())
+    }
+}
+
+fn row_to_batch(row: &Row<'_>) -> rusqlite::Result<ResultBatch> {
+    let format: String = row.get(2)?;
+    let target: String = row.get(3)?;
+    let ingested_at: String = row.get(9)?;
+    Ok(ResultBatch {
+        id: row.get(0)?,
+        source_system: row.get(1)?,
+        format: if format == "json" { ResultFormat::Json } else { ResultFormat::Csv },
+        target: EvidenceTarget::parse(&target).unwrap_or(EvidenceTarget::IncomingInspection),
+        record_id: row.get(4)?,
+        sha256: row.get(5)?,
+        result_count: row.get::<_, i64>(6)? as usize,
+        failed_count: row.get::<_, i64>(7)? as usize,
+        ingested_by: row.get(8)?,
+        ingested_at: DateTime::parse_from_rfc3339(&ingested_at).map(|t| t.with_timezone(&Utc)).unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::validation::{SubjectKind, ValidationStage};
+
+    fn db() -> Database {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".into(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["qa", "lab"]);
+        db
+    }
+
+    #[test]
+    fn test_csv_and_json_results_evaluated_against_limits() {
+        let csv = "sample_id,test,value,unit,lower_limit,upper_limit,expected\n\
+                   S-1,Peel strength,\"1,5\",N/15mm,1.2,,\n\
+                   S-1,Peel strength (decimal),1.5,N/15mm,1.2,,\n\
+                   S-2,Dye penetration,negative,,,,Negative\n";
+        let results = parse(csv, ResultFormat::detect(Some("text/csv"), csv)).unwrap();
+        assert_eq!(results[0].value.as_deref(), Some("1,5"));
+        assert!(results[0].evaluate().is_err());
+        assert!(results[1].evaluate().unwrap());
+        assert!(results[2].evaluate().unwrap());
+
+        let json = r#"{"results": [{"sample_id": "S-3", "test": "Bioburden", "value": 120, "upper_limit": 100},
+                                   {"sample_id": "S-4", "test": "Visual", "result": "PASS"}]}"#;
+        assert_eq!(ResultFormat::detect(None, json), ResultFormat::Json);
+        let results = parse(json, ResultFormat::Json).unwrap();
+        assert_eq!((results[0].evaluate().unwrap(), results[1].evaluate().unwrap()), (false, true));
+    }
+
+    #[test]
+    fn test_ingest_attaches_evidence_to_validation_and_capa() {
+        let db = db();
+        let ingest = TestResultIngest::new(&db);
+        let now = Utc::now();
+        let validation = ValidationRepo::new(&db);
+        let protocol = validation
+            .create_protocol("Pouch sealer PS-2", SubjectKind::Process, ValidationStage::Oq, "Sealer OQ", "qa")
+            .unwrap();
+        validation.approve(&protocol.id, "qa").unwrap();
+
+        let csv = "sample_id,test,value,lower_limit,upper_limit\n\
+                   S-1,Seal strength,1.8,1.2,3.0\n\
+                   S-2,Seal strength,0.9,1.2,3.0\n";
+        let batch = ingest
+            .ingest(csv, ResultFormat::Csv, "LabWare", EvidenceTarget::Validation, &protocol.id, "lab", now)
+            .unwrap();
+        assert_eq!((batch.result_count, batch.failed_count, batch.passed()), (2, 1, false));
+        let evidence = validation.evidence(&protocol.id).unwrap();
+        assert_eq!(evidence.iter().map(|e| e.passed).collect::<Vec<_>>(), vec![true, false]);
+        assert_eq!(ingest.results(&batch.id).unwrap()[1].raw.value.as_deref(), Some("0.9"));
+
+        let missing =
+            ingest.ingest(csv, ResultFormat::Csv, "LabWare", EvidenceTarget::CapaVerification, "c-404", "lab", now);
+        assert!(matches!(missing, Err(QmsError::NotFound { .. })));
+        let batch = ingest
+            .ingest(csv, ResultFormat::Csv, "LabWare", EvidenceTarget::IncomingInspection, "LOT-42", "lab", now)
+            .unwrap();
+        assert_eq!(ingest.batches(EvidenceTarget::IncomingInspection, "LOT-42").unwrap(), vec![batch]);
+    }
+}
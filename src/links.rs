@@ -0,0 +1,385 @@
+//! # Links - Traceability Between Records
+//!
+//! Any two records can be joined by a typed link: a CAPA addresses a
+//! complaint, a risk is controlled by a document, an NCR affects a lot
+//! (ISO 13485 §7.5.9, 21 CFR 820.100(a)). Records are referenced by type and
+//! id, so modules need no knowledge of each other. Links are never deleted;
+//! unlinking stamps who removed the link and why. The traceability graph
+//! around a record is collected breadth-first and exported as Graphviz DOT
+//! or JSON.
+
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+
+/// Hops from the root record walked when the caller gives no depth.
+pub const DEFAULT_GRAPH_DEPTH: usize = 2;
+
+/// A record of any module, e.g. `capa:5f0c…` or `lot:L-2025-042`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct RecordRef {
+    pub record_type: String,
+    pub record_id: String,
+}
+
+impl RecordRef {
+    pub fn new(record_type: impl Into<String>, record_id: impl Into<String>) -> Self {
+        Self { record_type: record_type.into(), record_id: record_id.into() }
+    }
+
+    /// Parse the `type:id` form.
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.split_once(':') {
+            Some((record_type, record_id)) if !record_type.trim().is_empty() && !record_id.trim().is_empty() => {
+                Ok(Self::new(record_type.trim(), record_id.trim()))
+            }
+            _ => Err(QmsError::Validation {
+                field: "record".to_string(),
+                message: format!("Expected a record reference of the form type:id, got '{}'", value),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for RecordRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.record_type, self.record_id)
+    }
+}
+
+/// Meaning of a link, read from source to target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkType {
+    /// CAPA addresses a complaint or NCR
+    Addresses,
+    /// Record was raised from the target, e.g. a CAPA from an intake item
+    DerivedFrom,
+    /// Risk is controlled by a document or design output
+    ControlledBy,
+    /// NCR or complaint affects a lot, device or supplier
+    Affects,
+    RelatesTo,
+    /// Procedure document satisfies a clause of a standard or regulation
+    Satisfies,
+}
+
+impl LinkType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkType::Addresses => "addresses",
+            LinkType::DerivedFrom => "derived_from",
+            LinkType::ControlledBy => "controlled_by",
+            LinkType::Affects => "affects",
+            LinkType::RelatesTo => "relates_to",
+            LinkType::Satisfies => "satisfies",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [
+            LinkType::Addresses,
+            LinkType::DerivedFrom,
+            LinkType::ControlledBy,
+            LinkType::Affects,
+            LinkType::RelatesTo,
+            LinkType::Satisfies,
+        ]
+        .into_iter()
+        .find(|item| item.as_str() == value)
+    }
+}
+
+/// A typed link between two records.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordLink {
+    pub id: String,
+    pub source: RecordRef,
+    pub target: RecordRef,
+    pub link_type: LinkType,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Records reachable from `root` and the links between them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceabilityGraph {
+    pub root: RecordRef,
+    pub nodes: Vec<RecordRef>,
+    pub links: Vec<RecordLink>,
+}
+
+impl TraceabilityGraph {
+    /// Graphviz DOT rendering, root highlighted.
+    pub fn to_dot(&self) -> String {
+        let quote = |value: String| format!("\"{}\"", value.replace('"', "\\\""));
+        let mut dot = String::from("digraph traceability {\n    rankdir=LR;\n    node [shape=box];\n");
+        for node in &self.nodes {
+            let style = if *node == self.root { ", style=bold" } else { "" };
+            dot.push_str(&format!(
+                "    {} [label={}{}];\n",
+                quote(node.to_string()),
+                quote(format!("{}\\n{}", node.record_type, node.record_id)),
+                style
+            ));
+        }
+        for link in &self.links {
+            dot.push_str(&format!(
+                "    {} -> {} [label={}];\n",
+                quote(link.source.to_string()),
+                quote(link.target.to_string()),
+                quote(link.link_type.as_str().to_string())
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Insert a link on `conn`, e.g. inside the unit of work creating one of its
+/// records. An identical active link is rejected.
+pub fn link_with(
+    conn: &Connection,
+    source: &RecordRef,
+    target: &RecordRef,
+    link_type: LinkType,
+    created_by: &str,
+    now: DateTime<Utc>,
+) -> Result<RecordLink> {
+    if source == target {
+        return Err(QmsError::Validation { field: "target".to_string(), message: "A record cannot be linked to itself".to_string() });
+    }
+    let exists: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM record_links
+         WHERE source_type = ?1 AND source_id = ?2 AND target_type = ?3 AND target_id = ?4 AND link_type = ?5
+           AND removed_at IS NULL)",
+        params![source.record_type, source.record_id, target.record_type, target.record_id, link_type.as_str()],
+        |row| row.get(0),
+    )?;
+    if exists {
+        return Err(QmsError::Validation {
+            field: "link_type".to_string(),
+            message: format!("{} already {} {}", source, link_type.as_str(), target),
+        });
+    }
+    let link = RecordLink {
+        id: Uuid::new_v4().to_string(),
+        source: source.clone(),
+        target: target.clone(),
+        link_type,
+        created_by: created_by.to_string(),
+        created_at: now,
+    };
+    conn.execute(
+        "INSERT INTO record_links (id, source_type, source_id, target_type, target_id, link_type, created_by, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            link.id,
+            source.record_type,
+            source.record_id,
+            target.record_type,
+            target.record_id,
+            link_type.as_str(),
+            created_by,
+            now.to_rfc3339(),
+        ],
+    )?;
+    Ok(link)
+}
+
+/// Persistence and traversal of record links.
+pub struct LinkRepo<'a> {
+    db: &'a Database,
+    audit: AuditManager,
+}
+
+impl<'a> LinkRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, audit: AuditManager::new(db.clone()) }
+    }
+
+    pub fn link(&self, source: &RecordRef, target: &RecordRef, link_type: LinkType, created_by: &str) -> Result<RecordLink> {
+        self.db.unit_of_work(|uow| {
+            let link = link_with(uow.connection(), source, target, link_type, created_by, Utc::now())?;
+            self.audit.log_action_in(
+                uow,
+                created_by,
+                "record_linked",
+                &resource(&link.id),
+                "Success",
+                Some(format!("{} {} {}", source, link_type.as_str(), target)),
+            )?;
+            Ok(link)
+        })
+    }
+
+    /// Retire a link; it stays on file with who removed it and why.
+    pub fn unlink(&self, id: &str, removed_by: &str, reason: &str) -> Result<()> {
+        if reason.trim().is_empty() {
+            return Err(QmsError::Validation { field: "reason".to_string(), message: "A reason is required to remove a link".to_string() });
+        }
+        self.db.unit_of_work(|uow| {
+            let updated = uow.connection().execute(
+                "UPDATE record_links SET removed_by = ?2, removed_at = ?3, removal_reason = ?4
+                 WHERE id = ?1 AND removed_at IS NULL",
+                params![id, removed_by, Utc::now().to_rfc3339(), reason],
+            )?;
+            if updated == 0 {
+                return Err(QmsError::NotFound { resource: "record_link".to_string(), id: id.to_string() });
+            }
+            self.audit.log_action_in(uow, removed_by, "record_unlinked", &resource(id), "Success", Some(reason.to_string()))
+        })
+    }
+
+    /// Active links with `record` on either side, oldest first.
+    pub fn links_of(&self, record: &RecordRef) -> Result<Vec<RecordLink>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM record_links
+                 WHERE removed_at IS NULL
+                   AND ((source_type = ?1 AND source_id = ?2) OR (target_type = ?1 AND target_id = ?2))
+                 ORDER BY created_at, id",
+                COLUMNS
+            ))?;
+            let links = stmt
+                .query_map(params![record.record_type, record.record_id], row_to_link)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(links)
+        })
+    }
+
+    /// Records within `max_depth` links of `root`, following links in both
+    /// directions.
+    pub fn graph(&self, root: &RecordRef, max_depth: usize) -> Result<TraceabilityGraph> {
+        self.graph_where(root, max_depth, |_| true)
+    }
+
+    /// [`Self::graph`] over the records `visible` accepts; links to other
+    /// records are left out and not followed.
+    pub fn graph_where(
+        &self,
+        root: &RecordRef,
+        max_depth: usize,
+        visible: impl Fn(&RecordRef) -> bool,
+    ) -> Result<TraceabilityGraph> {
+        let mut nodes = BTreeSet::from([root.clone()]);
+        let mut seen_links = HashSet::new();
+        let mut links = Vec::new();
+        let mut queue = VecDeque::from([(root.clone(), 0)]);
+        while let Some((record, depth)) = queue.pop_front() {
+            if depth == max_depth {
+                continue;
+            }
+            for link in self.links_of(&record)? {
+                if !seen_links.insert(link.id.clone()) {
+                    continue;
+                }
+                let other = if link.source == record { &link.target } else { &link.source };
+                if !visible(other) {
+                    continue;
+                }
+                if nodes.insert(other.clone()) {
+                    queue.push_back((other.clone(), depth + 1));
+                }
+                links.push(link);
+            }
+        }
+        Ok(TraceabilityGraph { root: root.clone(), nodes: nodes.into_iter().collect(), links })
+    }
+}
+
+fn resource(id: &str) -> String {
+    format!("record_link:{}", id)
+}
+
+const COLUMNS: &str = "id, source_type, source_id, target_type, target_id, link_type, created_by, created_at";
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn row_to_link(row: &Row) -> rusqlite::Result<RecordLink> {
+    let link_type: String = row.get(5)?;
+    let created_at: String = row.get(7)?;
+    Ok(RecordLink {
+        id: row.get(0)?,
+        source: RecordRef::new(row.get::<_, String>(1)?, row.get::<_, String>(2)?),
+        target: RecordRef::new(row.get::<_, String>(3)?, row.get::<_, String>(4)?),
+        link_type: LinkType::parse(&link_type).ok_or_else(|| conversion_error(5, format!("unknown link type {}", link_type)))?,
+        created_by: row.get(6)?,
+        created_at: DateTime::parse_from_rfc3339(&created_at)
+            .map(|t| t.with_timezone(&Utc))
+            .map_err(|e| conversion_error(7, e.to_string()))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_links_are_typed_unique_and_retired_with_reason() {
+        let db = test_db();
+        let repo = LinkRepo::new(&db);
+        let capa = RecordRef::parse("capa:c-1").unwrap();
+        let complaint = RecordRef::new("complaint", "cmp-1");
+        assert!(RecordRef::parse("capa").is_err());
+        assert!(repo.link(&capa, &capa, LinkType::RelatesTo, "qa").is_err());
+
+        let link = repo.link(&capa, &complaint, LinkType::Addresses, "qa").unwrap();
+        assert!(repo.link(&capa, &complaint, LinkType::Addresses, "qa").is_err());
+        assert_eq!(repo.links_of(&complaint).unwrap(), vec![link.clone()]);
+
+        assert!(repo.unlink(&link.id, "qa", " ").is_err());
+        repo.unlink(&link.id, "qa", "Linked to the wrong complaint").unwrap();
+        assert!(repo.links_of(&capa).unwrap().is_empty());
+        assert!(matches!(repo.unlink(&link.id, "qa", "again"), Err(QmsError::NotFound { .. })));
+        repo.link(&capa, &complaint, LinkType::Addresses, "qa").unwrap();
+    }
+
+    #[test]
+    fn test_graph_walks_both_directions_to_depth() {
+        let db = test_db();
+        let repo = LinkRepo::new(&db);
+        let capa = RecordRef::new("capa", "c-1");
+        let ncr = RecordRef::new("ncr", "n-1");
+        let lot = RecordRef::new("lot", "L-042");
+        let supplier = RecordRef::new("supplier", "s-1");
+        repo.link(&capa, &ncr, LinkType::Addresses, "qa").unwrap();
+        repo.link(&ncr, &lot, LinkType::Affects, "qa").unwrap();
+        repo.link(&supplier, &lot, LinkType::RelatesTo, "qa").unwrap();
+
+        let graph = repo.graph(&ncr, 1).unwrap();
+        assert_eq!(graph.nodes, vec![capa.clone(), lot.clone(), ncr.clone()]);
+        assert_eq!(graph.links.len(), 2);
+
+        let graph = repo.graph(&capa, DEFAULT_GRAPH_DEPTH + 1).unwrap();
+        assert_eq!(graph.nodes.len(), 4);
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph traceability {"));
+        assert!(dot.contains("\"capa:c-1\" [label=\"capa\\nc-1\", style=bold];"));
+        assert!(dot.contains("\"ncr:n-1\" -> \"lot:L-042\" [label=\"affects\"];"));
+        let json: TraceabilityGraph = serde_json::from_str(&serde_json::to_string(&graph).unwrap()).unwrap();
+        assert_eq!(json, graph);
+    }
+}
@@ -101,6 +101,9 @@ pub struct AuditLogEntry {
     
     /// Digital signature hash
     pub signature_hash: Option<String>,
+
+    /// Site the action was performed at
+    pub site_id: String,
 }
 
 /// Audit outcome enumeration
@@ -141,6 +144,7 @@ impl AuditLogEntry {
             metadata: serde_json::Value::Null,
             compliance_version: crate::FDA_CFR_PART_820_VERSION.to_string(),
             signature_hash: None,
+            site_id: crate::site::DEFAULT_SITE_ID.to_string(),
         }
     }
 
@@ -162,6 +166,12 @@ impl AuditLogEntry {
         self
     }
 
+    /// Record the site the action was performed at
+    pub fn with_site(mut self, site_id: String) -> Self {
+        self.site_id = site_id;
+        self
+    }
+
     /// Log this entry using tracing
     pub fn log(&self) {
         tracing::info!(
@@ -176,6 +186,7 @@ impl AuditLogEntry {
             metadata = %self.metadata,
             compliance_version = %self.compliance_version,
             signature_hash = ?self.signature_hash,
+            site_id = %self.site_id,
             "FDA audit trail entry"
         );
     }
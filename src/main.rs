@@ -1,15 +1,53 @@
 use anyhow::Result;
+use clap::Parser;
 use qmsrs::{config::Config, ui::TuiApp};
+use qmsrs::access_audit::AccessAuditor;
+use qmsrs::access_review::{AccessDecision, AccessReviewRepo};
+use qmsrs::analytics_export;
+use qmsrs::audit_worm::AuditWormStore;
 use qmsrs::api;
+use qmsrs::app_context::AppContext;
+use qmsrs::audit_review::{AuditReviewRepo, Disposition, SamplingStrategy};
+use qmsrs::cli::{
+    AccessReviewCommand, AnalyticsCommand, AuditReviewCommand, AuditWormCommand, BatchRecordsCommand, Cli, Command,
+    ConfigCommand, CostCommand, DbCommand, DelegationsCommand, FeaturesCommand, IssueSyncCommand, OutputFormat,
+    ScriptsCommand, ShipmentsCommand, SpcCommand, SupplierDocsCommand, TasksCommand, UsersCommand,
+};
+use qmsrs::command_output::{self, exit_code, CommandOutput};
+use qmsrs::complaint_rate::ComplaintRates;
+use qmsrs::config_crypto::{self, MasterKey};
+use qmsrs::cost_of_quality::{self, CostCategory, CostSource, QualityCostRepo};
+use qmsrs::crash_guard::{self, TerminalGuard};
+use qmsrs::compliance_matrix::{self, ClauseMappingRepo};
+use qmsrs::database::Database;
+use qmsrs::batch_record::{BatchRecordRepo, ExceptionKind, ReviewReport};
+use qmsrs::delegation::{Delegation, DelegationRepo};
+use qmsrs::demo_seed;
+use qmsrs::change_history::ChangeReason;
+use qmsrs::evidence_pack;
+use qmsrs::feature_flags::{Feature, FeatureFlags};
+use qmsrs::grpc;
+use qmsrs::inspection_package::{self, InspectionScope, Subsystem};
+use qmsrs::issue_sync::{ConflictSide, IssueSync, JiraTracker};
+use qmsrs::keystore::{Keystore, SYSTEM_KEY_FILE};
+use qmsrs::links::RecordRef;
+use qmsrs::mir_export::{MirDetails, MirExporter, MirFormat};
+use qmsrs::notification::OutboxNotifier;
+use qmsrs::ownership_transfer::{OwnershipTransfer, TransferPlan};
+use qmsrs::report_signature;
+use qmsrs::shutdown::ShutdownCoordinator;
+use qmsrs::spc::{self, SpcService};
+use qmsrs::file_store::FileStore;
+use qmsrs::standards::StandardsRepo;
+use qmsrs::supplier_documents::{SupplierDocument, SupplierDocumentKind, SupplierDocumentRepo};
+use qmsrs::tasks::{TaskInbox, TaskKind, TaskRepo};
+use qmsrs::user_activity::{self, UserActivity};
+use qmsrs::validation_scripts::{RuleHook, ValidationScriptRepo};
+use qmsrs::vigilance_export::{ExportMode, VigilanceExporter};
 use ratatui::{
     backend::CrosstermBackend,
     Terminal,
 };
-use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
 use std::io;
 
 // Constants for timing
@@ -18,6 +56,11 @@ const RENDER_LOOP_DELAY_MS: u64 = 50;  // 50ms for smooth rendering
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    if let Some(command) = &cli.command {
+        std::process::exit(run_command(&cli, command));
+    }
+
     // Initialize the QMS system
     println!("QMSrs - FDA Compliant Medical Device Quality Management System");
     println!("Version: {}", qmsrs::APPLICATION_VERSION);
@@ -25,8 +68,13 @@ async fn main() -> Result<()> {
     println!("ISO 13485 Version: {}", qmsrs::ISO_13485_VERSION);
     println!();
     
-    // Load default configuration
-    let config = Config::default();
+    // Load the configuration file, falling back to defaults without one
+    let config_file = cli.config_path.exists().then(|| cli.config_path.clone());
+    let mut config = match &config_file {
+        Some(path) => Config::load(path)?,
+        None => Config::default(),
+    };
+    config.tui.accessible |= cli.accessible;
     
     // Validate FDA compliance
     config.validate()?;
@@ -50,46 +98,1043 @@ async fn main() -> Result<()> {
     // Wait a moment for user to read
     tokio::time::sleep(tokio::time::Duration::from_millis(USER_READ_DELAY_MS)).await;
     
-    // Start API server in background (Phase 3)
-    tokio::spawn(async {
-        if let Err(e) = api::serve("127.0.0.1:3000").await {
-            eprintln!("API server error: {e}");
+    // Serve the API from this process, sharing the TUI's context
+    let mut context = AppContext::new(config)?;
+    let startup = context.start()?;
+    println!("✓ Startup self-test: no critical failures");
+    for check in startup.checklist.failures() {
+        println!("⚠ Self-test {}: {}", check.name, check.detail);
+    }
+    for outcome in &startup.recovered {
+        let status = outcome.status.as_str();
+        println!("⚠ Interrupted {} on {} {}: {}", outcome.kind, outcome.subject, status, outcome.resolution);
+    }
+    // Restore the terminal and audit panics; shut down gracefully on signals
+    crash_guard::install(context.database.clone(), std::path::Path::new(&context.config.application.data_directory));
+    let shutdown = std::sync::Arc::new(ShutdownCoordinator::new(&context));
+    let signal_handler = shutdown.clone().spawn_signal_handler();
+    let mut app = TuiApp::new().with_context(&context);
+    if let Some(path) = config_file {
+        // Layout changes are saved back to the file the configuration came from
+        app = app.with_layout_file(path);
+    }
+    if context.config.api.enabled {
+        let mut server = api::EmbeddedApi::new(&context)?;
+        if let Some(monitor) = &startup.clock_monitor {
+            server = server.with_clock_guard(monitor.guard());
         }
-    });
-    
+        app = app.with_api(server.base_url(), server.token());
+        // The gRPC interface shares the REST API's tokens and network policy
+        if context.config.api.grpc.enabled {
+            let grpc = grpc::QmsGrpc::new(server.state().clone(), context.events.clone());
+            shutdown.track(grpc.spawn(&context.config.api.grpc.bind, &context.config.api.tls)?);
+        }
+        shutdown.track(server.spawn());
+    }
+    if context.config.jobs.enabled {
+        shutdown.track(context.jobs.clone().spawn_periodic());
+    }
+    // Verify the system clock now and periodically while the TUI runs
+    if let Some(monitor) = startup.clock_monitor {
+        shutdown.track(monitor.spawn_periodic());
+    }
+
     // Start TUI application
-    start_tui().await?;
+    let result = start_tui(app).await;
+    signal_handler.abort();
+    shutdown.shutdown("User exit").await?;
+    result?;
     
     println!("\nQMS system shutdown successfully");
     println!("✓ TASK-014: End-to-end TUI workflow testing completed");
     Ok(())
 }
 
-/// Start the TUI application
-async fn start_tui() -> Result<()> {
-    // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+/// Generate the master key or encrypt/decrypt a section of `config_path`.
+fn run_config_command(
+    config_path: &std::path::Path,
+    command: &ConfigCommand,
+    mut output: CommandOutput,
+) -> Result<CommandOutput> {
+    let (section, encrypt) = match command {
+        ConfigCommand::GenerateKey => {
+            let key = MasterKey::generate()?.to_base64();
+            output.line(key.clone()).field("key", key);
+            return Ok(output);
+        }
+        ConfigCommand::EncryptSection { section } => (section, true),
+        ConfigCommand::DecryptSection { section } => (section, false),
+    };
+    let key = MasterKey::from_env()?.ok_or_else(|| qmsrs::QmsError::Configuration {
+        message: format!(
+            "Set {} or {} to the master key",
+            config_crypto::MASTER_KEY_ENV,
+            config_crypto::MASTER_KEY_FILE_ENV
+        ),
+    })?;
+    let document = std::fs::read_to_string(config_path)?;
+    let rewritten = if encrypt {
+        config_crypto::encrypt_section_in(&document, section, &key)?
+    } else {
+        config_crypto::decrypt_section_in(&document, section, &key)?
+    };
+    std::fs::write(config_path, rewritten)?;
+    output
+        .line(format!(
+            "{} section [{}] of {}",
+            if encrypt { "Encrypted" } else { "Decrypted" },
+            section,
+            config_path.display()
+        ))
+        .field("section", section)
+        .field("path", config_path)
+        .field("encrypted", encrypt);
+    Ok(output)
+}
+
+/// Run a non-interactive maintenance command, print its result in the
+/// requested format and return the process exit code.
+fn run_command(cli: &Cli, command: &Command) -> i32 {
+    let format = cli.output_format;
+    match execute_command(cli, command, format) {
+        Ok(output) => {
+            let rendered = output.render(format);
+            if !rendered.is_empty() {
+                println!("{}", rendered);
+            }
+            output.exit_code()
+        }
+        Err(error) => {
+            let qms_error = error.downcast_ref::<qmsrs::QmsError>();
+            let code = match qms_error {
+                Some(qms_error) => command_output::exit_code_for(qms_error),
+                None if error.is::<std::io::Error>() => exit_code::FILE_SYSTEM,
+                None if error.is::<serde_json::Error>() => exit_code::INVALID_INPUT,
+                None => exit_code::FAILURE,
+            };
+            match format {
+                OutputFormat::Text => eprintln!("Error: {:#}", error),
+                OutputFormat::Json => {
+                    let error_code = qms_error.map_or("APP_ERROR", |e| e.error_code());
+                    let message = format!("{:#}", error);
+                    println!("{}", command_output::render_error(command.name(), error_code, &message, code));
+                }
+            }
+            code
+        }
+    }
+}
 
-    // Create TUI app
-    let mut app = TuiApp::new();
+fn execute_command(cli: &Cli, command: &Command, format: OutputFormat) -> Result<CommandOutput> {
+    let mut output = CommandOutput::new(command.name());
+    // Runs before the configuration is loaded, which may need the key
+    if let Command::Config { command } = command {
+        return run_config_command(&cli.config_path, command, output);
+    }
+    let mut config = if cli.config_path.exists() {
+        Config::load(&cli.config_path)?
+    } else {
+        Config::default()
+    };
+    if let Some(url) = &cli.database_url {
+        config.database.url = url.clone();
+    }
 
-    // Run the main TUI loop
-    let result = run_tui_loop(&mut terminal, &mut app).await;
+    match command {
+        Command::VerifyReport { file } => {
+            let database = Database::new(config.database.clone())?;
+            let key_path = Keystore::keys_dir(std::path::Path::new(&config.application.data_directory))
+                .join(SYSTEM_KEY_FILE);
+            // Never create a key while verifying; compare against it only if present.
+            let keystore = if key_path.exists() { Some(Keystore::open(&key_path)?) } else { None };
+
+            let verification = report_signature::verify_report_file(file, &database, keystore.as_ref())?;
+            output
+                .line(verification.to_string())
+                .field("file", file)
+                .field("valid", verification.is_valid())
+                .field("file_sha256", &verification.file_sha256)
+                .field("report_id", verification.record.as_ref().map(|record| record.id))
+                .field("signature_valid", verification.signature_valid)
+                .field("payload_matches", verification.payload_matches)
+                .field("signed_with_system_key", verification.signed_with_system_key);
+            if !verification.is_valid() {
+                output.fail();
+            }
+        }
+        Command::EvidencePack { output: directory } => {
+            let database = Database::new(config.database.clone())?;
+            let keystore = Keystore::open_or_create(&Keystore::keys_dir(std::path::Path::new(&config.application.data_directory)))?;
+            let pack = evidence_pack::generate_evidence_pack(&config, &database, &keystore, directory, "cli_user")?;
+            for test in &pack.self_tests {
+                output.line(format!("[{}] {}: {}", if test.passed { "PASS" } else { "FAIL" }, test.name, test.detail));
+            }
+            output
+                .line(format!("Wrote {} files to {}", pack.manifest.len() + 1, pack.directory.display()))
+                .field("directory", &pack.directory)
+                .field("files", pack.manifest.len() + 1)
+                .field("self_tests", &pack.self_tests);
+            if !pack.all_passed() {
+                output.fail();
+            }
+        }
+        Command::InspectionPackage { subsystem, output: directory, months } => {
+            let subsystem = Subsystem::parse(subsystem).ok_or_else(|| qmsrs::QmsError::Validation {
+                field: "subsystem".to_string(),
+                message: format!(
+                    "Unknown subsystem {}; expected one of {}",
+                    subsystem,
+                    Subsystem::ALL.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(", ")
+                ),
+            })?;
+            let database = Database::new(config.database.clone())?;
+            let keystore = Keystore::open_or_create(&Keystore::keys_dir(std::path::Path::new(&config.application.data_directory)))?;
+            let scope = InspectionScope::last_months(subsystem, *months, chrono::Utc::now().date_naive())?;
+            let package =
+                inspection_package::generate_inspection_package(&database, &keystore, &scope, directory, "cli_user")?;
+            output
+                .line(format!(
+                    "Wrote {} inspection package ({} to {}) with {} signed files to {}",
+                    subsystem.label(),
+                    scope.from,
+                    scope.to,
+                    package.manifest.len(),
+                    package.path.display()
+                ))
+                .field("subsystem", subsystem.as_str())
+                .field("from", scope.from)
+                .field("to", scope.to)
+                .field("files", package.manifest.len())
+                .field("path", &package.path);
+        }
+        Command::UserActivity { user, output: path, from, to } => {
+            let database = Database::new(config.database.clone())?;
+            let keystore = Keystore::open_or_create(&Keystore::keys_dir(std::path::Path::new(&config.application.data_directory)))?;
+            let to = to.unwrap_or_else(|| chrono::Utc::now().date_naive());
+            let from = from.unwrap_or(to - chrono::Duration::days(90));
+            let activity = UserActivity::collect(&database, user, from, to)?;
+            user_activity::generate_activity_report(&database, &keystore, &activity, path, "cli_user")?;
+            output
+                .line(format!(
+                    "Wrote activity report for {} ({} to {}, {} audit entries) to {}",
+                    activity.account.username,
+                    from,
+                    to,
+                    activity.entries.len(),
+                    path.display()
+                ))
+                .field("user", user)
+                .field("from", from)
+                .field("to", to)
+                .field("audit_entries", activity.entries.len())
+                .field("path", path);
+        }
+        Command::ExportVigilance { output: path, pseudonymize } => {
+            let database = Database::new(config.database.clone())?;
+            let keystore = Keystore::open_or_create(&Keystore::keys_dir(std::path::Path::new(&config.application.data_directory)))?;
+            let mode = if *pseudonymize {
+                ExportMode::Pseudonymized(config.privacy.pseudonymized_fields.clone())
+            } else {
+                ExportMode::Identified
+            };
+            let csv = VigilanceExporter::new(&database).with_keystore(&keystore).export_csv("cli_user", &mode)?;
+            std::fs::write(path, csv)?;
+            output
+                .line(format!("Wrote {} vigilance export to {}", mode.as_str(), path.display()))
+                .field("mode", mode.as_str())
+                .field("path", path);
+        }
+        Command::ExportMir { adverse_event, details, output: path, spreadsheet } => {
+            let database = Database::new(config.database.clone())?;
+            let details: MirDetails = serde_json::from_str(&std::fs::read_to_string(details)?)?;
+            let mir_format = if *spreadsheet { MirFormat::Spreadsheet } else { MirFormat::Xml };
+            let report = MirExporter::new(&database).export(
+                *adverse_event,
+                &details,
+                &config.application,
+                mir_format,
+                "cli_user",
+            )?;
+            std::fs::write(path, report)?;
+            output
+                .line(format!(
+                    "Wrote MIR {} for adverse event {} to {}",
+                    mir_format.as_str(),
+                    adverse_event,
+                    path.display()
+                ))
+                .field("adverse_event", adverse_event)
+                .field("format", mir_format.as_str())
+                .field("path", path);
+        }
+        Command::ComplianceMatrix { standard, output: path } => {
+            let database = Database::new(config.database.clone())?;
+            let standard = StandardsRepo::new(&database).find_by_reference(standard)?;
+            let matrix = ClauseMappingRepo::new(&database).matrix(&standard.id)?;
+            compliance_matrix::generate_matrix_report(&matrix, "cli_user", path)?;
+            output
+                .line(format!(
+                    "Wrote compliance matrix for {} to {} ({} of {} clauses with gaps)",
+                    standard.reference(),
+                    path.display(),
+                    matrix.gaps().len(),
+                    matrix.rows.len()
+                ))
+                .field("standard", standard.reference())
+                .field("path", path)
+                .field("clauses", matrix.rows.len())
+                .field("clauses_with_gaps", matrix.gaps().len());
+        }
+        Command::Db { command: DbCommand::Stats } => {
+            let database = Database::new(config.database.clone())?;
+            // Exercise the pool with a representative audit trail read so wait
+            // and hold times reflect this database rather than an idle pool.
+            let integrity = database.verify_audit_integrity()?;
+            let schema_version = database.schema_version()?;
+            let pool = database.pool_stats();
+            output
+                .line(format!("Database:         {}", config.database.url))
+                .line(format!("Schema version:   {}", schema_version))
+                .line(format!("Audit entries:    {}", integrity.total_entries))
+                .line(pool.to_string())
+                .field("database", &config.database.url)
+                .field("schema_version", schema_version)
+                .field("audit_entries", integrity.total_entries)
+                .field("pool", &pool);
+        }
+        Command::AuditWorm { command } => {
+            let database = Database::new(config.database.clone())?;
+            let store = AuditWormStore::from_config(&config);
+            match command {
+                AuditWormCommand::Sync => {
+                    let result = store.sync(&database, chrono::Utc::now())?;
+                    output
+                        .line(format!(
+                            "Sealed {} audit entries in {} segment(s) under {}",
+                            result.entries_written,
+                            result.segments_written,
+                            store.directory().display()
+                        ))
+                        .field("entries_written", result.entries_written)
+                        .field("segments_written", result.segments_written)
+                        .field("directory", store.directory());
+                }
+                AuditWormCommand::Verify => {
+                    let verification = store.verify(&database)?;
+                    for problem in &verification.problems {
+                        output.line(format!("PROBLEM: {}", problem));
+                    }
+                    output
+                        .line(format!(
+                            "Checked {} segment(s), {} audit entries",
+                            verification.segments, verification.entries
+                        ))
+                        .field("intact", verification.is_intact())
+                        .field("segments", verification.segments)
+                        .field("entries", verification.entries)
+                        .field("problems", &verification.problems);
+                    if !verification.is_intact() {
+                        output.fail();
+                    }
+                }
+            }
+        }
+        Command::AuditReview { command } => {
+            let database = Database::new(config.database.clone())?;
+            let repo = AuditReviewRepo::new(&database);
+            match command {
+                AuditReviewCommand::Start { from, to, reviewer, strategy, sample_size } => {
+                    let strategy = match strategy {
+                        Some(name) => SamplingStrategy::parse(name).ok_or_else(|| qmsrs::QmsError::Validation {
+                            field: "strategy".to_string(),
+                            message: format!("Unknown sampling strategy {}", name),
+                        })?,
+                        None => config.compliance.audit_review_strategy,
+                    };
+                    let size = sample_size.unwrap_or(config.compliance.audit_review_sample_size);
+                    let review = repo.start(*from, *to, strategy, size, reviewer)?;
+                    output
+                        .line(format!(
+                            "Started audit trail review {}: {} of {} entries sampled ({})",
+                            review.id,
+                            review.sample_size,
+                            review.population,
+                            strategy.as_str()
+                        ))
+                        .field("review", &review);
+                }
+                AuditReviewCommand::Show { review } => {
+                    let details = repo.get(*review)?;
+                    output.line(format!(
+                        "Review {} of {} to {} by {}{}",
+                        details.id,
+                        details.period_start,
+                        details.period_end,
+                        details.reviewer,
+                        if details.is_complete() { " (completed)" } else { "" }
+                    ));
+                    let sample = repo.sample(*review)?;
+                    for sampled in &sample {
+                        let entry = &sampled.entry;
+                        output.line(format!(
+                            "{}  {}  {}  {} {} [{}]  => {}{}",
+                            entry.id,
+                            entry.timestamp,
+                            entry.user_id,
+                            entry.action,
+                            entry.resource,
+                            entry.outcome,
+                            sampled.disposition.map(|d| d.as_str()).unwrap_or("pending"),
+                            sampled.comment.as_ref().map(|c| format!(" ({})", c)).unwrap_or_default()
+                        ));
+                    }
+                    output.field("review", &details).field("sample", &sample);
+                }
+                AuditReviewCommand::Dispose { review, entry, disposition, reviewer, comment } => {
+                    let disposition = Disposition::parse(disposition).ok_or_else(|| qmsrs::QmsError::Validation {
+                        field: "disposition".to_string(),
+                        message: format!("Unknown disposition {}; expected acceptable or investigate", disposition),
+                    })?;
+                    repo.dispose(*review, entry, disposition, comment.as_deref(), reviewer)?;
+                    output
+                        .line(format!("Entry {} marked {}", entry, disposition.as_str()))
+                        .field("review_id", review)
+                        .field("entry_id", entry)
+                        .field("disposition", disposition.as_str());
+                }
+                AuditReviewCommand::Complete { review, conclusion, reviewer } => {
+                    let completed = repo.complete(*review, conclusion, reviewer)?;
+                    output
+                        .line(format!(
+                            "Completed audit trail review {} for {} to {}",
+                            completed.id, completed.period_start, completed.period_end
+                        ))
+                        .field("review", &completed);
+                }
+            }
+        }
+        Command::AccessReview { command } => {
+            let database = Database::new(config.database.clone())?;
+            let repo = AccessReviewRepo::new(&database);
+            match command {
+                AccessReviewCommand::Open => {
+                    let campaign = repo.open_campaign(chrono::Utc::now().date_naive(), "cli_user")?;
+                    let items = repo.items(campaign.id)?;
+                    output
+                        .line(format!(
+                            "Opened access review {} ({}) with {} item(s), due {}",
+                            campaign.quarter,
+                            campaign.id,
+                            items.len(),
+                            campaign.due_date
+                        ))
+                        .field("campaign", &campaign)
+                        .field("items", items.len());
+                }
+                AccessReviewCommand::Show { campaign } => {
+                    let campaign = match campaign {
+                        Some(id) => repo.get(*id)?,
+                        None => repo.list()?.into_iter().next().ok_or_else(|| qmsrs::QmsError::NotFound {
+                            resource: "access_review_campaign".to_string(),
+                            id: "latest".to_string(),
+                        })?,
+                    };
+                    output.line(format!(
+                        "Access review {} ({}), due {}{}",
+                        campaign.quarter,
+                        campaign.id,
+                        campaign.due_date,
+                        if campaign.is_closed() { " (closed)" } else { "" }
+                    ));
+                    let items = repo.items(campaign.id)?;
+                    for item in &items {
+                        output.line(format!(
+                            "{}  {}  {}  => {}{}",
+                            item.id,
+                            item.username,
+                            item.scope(),
+                            item.decision.map(|d| d.as_str()).unwrap_or("pending"),
+                            item.comment.as_ref().map(|c| format!(" ({})", c)).unwrap_or_default()
+                        ));
+                    }
+                    output.field("campaign", &campaign).field("items", &items);
+                }
+                AccessReviewCommand::Decide { item, decision, reviewer, comment } => {
+                    let decision = AccessDecision::parse(decision).ok_or_else(|| qmsrs::QmsError::Validation {
+                        field: "decision".to_string(),
+                        message: format!("Unknown decision {}; expected attest or revoke", decision),
+                    })?;
+                    let item = repo.decide(*item, decision, comment.as_deref(), reviewer)?;
+                    output
+                        .line(format!("{} {}: {}", item.username, item.scope(), decision.as_str()))
+                        .field("item", &item);
+                }
+                AccessReviewCommand::Close { campaign, reviewer } => {
+                    let closed = repo.close(*campaign, reviewer)?;
+                    output.line(format!("Closed access review {}", closed.quarter)).field("campaign", &closed);
+                }
+            }
+        }
+        Command::IssueSync { command } => {
+            let database = Database::new(config.database.clone())?;
+            let tracker = JiraTracker::from_config(&config.issue_sync)?;
+            let sync = IssueSync::new(&database, &tracker, &config.issue_sync);
+            match command {
+                IssueSyncCommand::Run => {
+                    let summary = sync.sync(chrono::Utc::now())?;
+                    for error in &summary.errors {
+                        output.line(format!("ERROR: {}", error));
+                    }
+                    output.line(summary.describe()).field("summary", &summary);
+                    if !summary.errors.is_empty() {
+                        output.fail();
+                    }
+                }
+                IssueSyncCommand::Conflicts => {
+                    let conflicts = sync.conflicts()?;
+                    for link in &conflicts {
+                        output.line(format!(
+                            "{} ({}): {}",
+                            link.action_id,
+                            link.issue_key,
+                            link.conflict.as_deref().unwrap_or_default()
+                        ));
+                    }
+                    output.line(format!("{} conflict(s)", conflicts.len())).field("conflicts", &conflicts);
+                }
+                IssueSyncCommand::Resolve { action, keep, user } => {
+                    let side = ConflictSide::parse(keep).ok_or_else(|| qmsrs::QmsError::Validation {
+                        field: "keep".to_string(),
+                        message: format!("Unknown side {}; expected qms or tracker", keep),
+                    })?;
+                    sync.resolve_conflict(action, side, user, chrono::Utc::now())?;
+                    output.line(format!("Resolved conflict on {} keeping the {} status", action, keep));
+                }
+            }
+        }
+        Command::Spc { command } => {
+            let database = Database::new(config.database.clone())?;
+            let notifier = OutboxNotifier::from_config(&config.notifications);
+            let mut service = SpcService::new(&database);
+            if let Some(notifier) = &notifier {
+                service = service.with_notifier(notifier, config.notifications.quality_manager_addresses.clone());
+            }
+            let now = chrono::Utc::now();
+            let (charts, export) = match command {
+                SpcCommand::XbarR { test, subgroup_size, days, export } => {
+                    let since = now - chrono::Duration::days(i64::from(*days));
+                    let subgroups = service.inspection_subgroups(test, *subgroup_size, since)?;
+                    let (x_bar, range) = spc::xbar_r_charts(test, &subgroups)?;
+                    (vec![x_bar, range], export)
+                }
+                SpcCommand::Inspection { test, days, export } => {
+                    let since = now - chrono::Duration::days(i64::from(*days));
+                    (vec![spc::p_chart(test, &service.inspection_samples(test, since)?)?], export)
+                }
+                SpcCommand::Complaints { units, export } => {
+                    let units = if units.is_empty() {
+                        ComplaintRates::new(&database).units_by_month()?
+                    } else {
+                        units
+                            .iter()
+                            .map(|entry| {
+                                entry
+                                    .split_once('=')
+                                    .and_then(|(month, count)| Some((month.to_string(), count.parse().ok()?)))
+                                    .ok_or_else(|| qmsrs::QmsError::Validation {
+                                        field: "units".to_string(),
+                                        message: format!("'{}' is not YYYY-MM=UNITS", entry),
+                                    })
+                            })
+                            .collect::<qmsrs::Result<_>>()?
+                    };
+                    (vec![spc::p_chart("Complaint rate", &service.complaint_samples(&units)?)?], export)
+                }
+            };
+            for chart in &charts {
+                for (point, rule) in chart.violations() {
+                    let kind = chart.kind.as_str();
+                    output.line(format!("{} {}: {} at {}", chart.title, kind, rule.describe(), point.label));
+                }
+                let alerts = service.raise_alerts(chart, now)?;
+                output.line(format!(
+                    "{} {} chart: {} point(s), {} new alert(s)",
+                    chart.title,
+                    chart.kind.as_str(),
+                    chart.points.len(),
+                    alerts.len()
+                ));
+                if let Some(directory) = export {
+                    for path in chart.export(directory)? {
+                        output.line(format!("Wrote {}", path.display()));
+                    }
+                }
+                if !chart.in_control() {
+                    output.fail();
+                }
+            }
+            output.field("charts", &charts);
+        }
+        Command::Shipments { command } => {
+            let database = Database::new(config.database.clone())?;
+            let rates = ComplaintRates::new(&database);
+            match command {
+                ShipmentsCommand::Record { month, units, user } => {
+                    let volume = rates.record_units(month, *units, user)?;
+                    output
+                        .line(format!("Recorded {} units shipped in {}", volume.units, volume.month))
+                        .field("shipment", &volume);
+                }
+                ShipmentsCommand::Rates { months } => {
+                    let trend = rates.trend(*months, chrono::Utc::now().date_naive(), &config.complaint_rates)?;
+                    let rate = |rate: Option<f64>, alert: bool| match rate {
+                        Some(rate) => format!("{:.2}{}", rate, if alert { " !" } else { "" }),
+                        None => "-".to_string(),
+                    };
+                    output.line(format!(
+                        "{:<8} {:>10} {:>10} {:>12} {:>14} {:>14}",
+                        "Month", "Units", "Complaints", "Adv. events", "Compl./10k", "AE/10k"
+                    ));
+                    for month in &trend.months {
+                        output.line(format!(
+                            "{:<8} {:>10} {:>10} {:>12} {:>14} {:>14}",
+                            month.month,
+                            month.units.map_or_else(|| "-".to_string(), |units| units.to_string()),
+                            month.complaints,
+                            month.adverse_events,
+                            rate(month.complaint_rate, month.complaint_alert),
+                            rate(month.adverse_event_rate, month.adverse_event_alert)
+                        ));
+                    }
+                    output.line(format!(
+                        "Thresholds: {:.2} complaints and {:.2} adverse events per 10,000 units; {} month(s) above",
+                        trend.thresholds.complaint_threshold,
+                        trend.thresholds.adverse_event_threshold,
+                        trend.alerts().len()
+                    ));
+                    output.field("trend", &trend);
+                }
+            }
+        }
+        Command::SupplierDocs { command } => {
+            let database = Database::new(config.database.clone())?;
+            let store = FileStore::for_data_dir(std::path::Path::new(&config.application.data_directory));
+            let repo = SupplierDocumentRepo::new(&database, &store);
+            let parse_kind = |kind: &str| {
+                SupplierDocumentKind::parse(kind).ok_or_else(|| qmsrs::QmsError::Validation {
+                    field: "kind".to_string(),
+                    message: format!("Unknown supplier document kind: {}", kind),
+                })
+            };
+            let today = chrono::Utc::now().date_naive();
+            let describe = |document: &SupplierDocument| {
+                format!(
+                    "{} '{}' v{} ({}), uploaded {} by {}, expires {} [{}]",
+                    document.kind.label(),
+                    document.title,
+                    document.version,
+                    document.file_name,
+                    document.uploaded_at.date_naive(),
+                    document.uploaded_by,
+                    document.expires_on.map_or_else(|| "never".to_string(), |date| date.to_string()),
+                    document.status(today).as_str()
+                )
+            };
+            match command {
+                SupplierDocsCommand::Upload { supplier, kind, file, title, expires_on, user } => {
+                    let kind = parse_kind(kind)?;
+                    let contents = std::fs::read(file)?;
+                    let file_name = file.file_name().and_then(|name| name.to_str()).unwrap_or("document");
+                    let document = repo.upload(
+                        *supplier,
+                        kind,
+                        title,
+                        file_name,
+                        &contents,
+                        *expires_on,
+                        user,
+                        chrono::Utc::now(),
+                    )?;
+                    output
+                        .line(format!("Stored {} as {}", describe(&document), document.relative_path))
+                        .field("document", &document);
+                }
+                SupplierDocsCommand::List { supplier } => {
+                    let documents = repo.documents(*supplier)?;
+                    if documents.is_empty() {
+                        output.line("No documents provided");
+                    }
+                    for document in &documents {
+                        output.line(describe(document));
+                    }
+                    output.field("documents", &documents);
+                }
+                SupplierDocsCommand::History { supplier, kind, title } => {
+                    let versions = repo.history(*supplier, parse_kind(kind)?, title)?;
+                    for document in &versions {
+                        output.line(describe(document));
+                    }
+                    output.field("versions", &versions);
+                }
+            }
+        }
+        Command::Cost { command } => {
+            let database = Database::new(config.database.clone())?;
+            let repo = QualityCostRepo::new(&database);
+            match command {
+                CostCommand::Record { source, record, amount, category, incurred_on, description, user } => {
+                    let source = CostSource::parse(source).ok_or_else(|| qmsrs::QmsError::Validation {
+                        field: "source".to_string(),
+                        message: format!("Unknown cost source: {}", source),
+                    })?;
+                    let category = category
+                        .as_deref()
+                        .map(|value| {
+                            CostCategory::parse(value).ok_or_else(|| qmsrs::QmsError::Validation {
+                                field: "category".to_string(),
+                                message: format!("Unknown cost category: {}", value),
+                            })
+                        })
+                        .transpose()?;
+                    let incurred_on = incurred_on.unwrap_or_else(|| chrono::Utc::now().date_naive());
+                    let amount = cost_of_quality::parse_amount(amount)?;
+                    let cost = repo.record(source, record, category, amount, incurred_on, description, user)?;
+                    output
+                        .line(format!(
+                            "Recorded {} {} cost of {} against {} {}",
+                            cost.incurred_on,
+                            cost.category.as_str(),
+                            cost_of_quality::format_amount(cost.amount_cents),
+                            cost.source.as_str(),
+                            cost.record_id
+                        ))
+                        .field("cost", &cost);
+                }
+                CostCommand::Report { quarters } => {
+                    let report = repo.quarterly(*quarters, chrono::Utc::now().date_naive())?;
+                    output.line(format!(
+                        "{:<8} {:>14} {:>14} {:>16} {:>16} {:>14}",
+                        "Quarter", "Prevention", "Appraisal", "Internal fail.", "External fail.", "Total"
+                    ));
+                    for quarter in &report.quarters {
+                        output.line(format!(
+                            "{:<8} {:>14} {:>14} {:>16} {:>16} {:>14}",
+                            quarter.quarter,
+                            cost_of_quality::format_amount(quarter.prevention),
+                            cost_of_quality::format_amount(quarter.appraisal),
+                            cost_of_quality::format_amount(quarter.internal_failure),
+                            cost_of_quality::format_amount(quarter.external_failure),
+                            cost_of_quality::format_amount(quarter.total())
+                        ));
+                    }
+                    output.field("report", &report);
+                }
+            }
+        }
+        Command::Tasks { command } => {
+            let database = Database::new(config.database.clone())?;
+            let repo = TaskRepo::new(&database);
+            match command {
+                TasksCommand::Create { title, kind, due, assignee, link, user } => {
+                    let kind = TaskKind::parse(kind).ok_or_else(|| qmsrs::QmsError::Validation {
+                        field: "kind".to_string(),
+                        message: format!("Unknown task kind: {}", kind),
+                    })?;
+                    let link = link.as_deref().map(RecordRef::parse).transpose()?;
+                    let task = repo.create(title, kind, *due, link.as_ref(), assignee, user)?;
+                    output
+                        .line(format!(
+                            "Assigned task {} '{}' to {}, due {}",
+                            task.id, task.title, task.assignee, task.due_date
+                        ))
+                        .field("task", &task);
+                }
+                TasksCommand::Complete { task, user } => {
+                    let task = repo.complete(*task, user)?;
+                    output.line(format!("Completed task '{}'", task.title)).field("task", &task);
+                }
+                TasksCommand::Inbox { user } => {
+                    let items = TaskInbox::new(&database).for_user(user, chrono::Utc::now().date_naive())?;
+                    if items.is_empty() {
+                        output.line(format!("Nothing open for {}", user));
+                    }
+                    for item in &items {
+                        output.line(format!(
+                            "{:<10} {:<50} {}{}",
+                            item.due_date.map_or_else(|| "-".to_string(), |due| due.to_string()),
+                            item.title,
+                            item.record,
+                            if item.overdue { " (overdue)" } else { "" }
+                        ));
+                    }
+                    output.field("inbox", &items);
+                }
+            }
+        }
+        Command::Seed { .. } => {
+            let database = Database::new(config.database.clone())?;
+            let summary = demo_seed::seed_demo_data(&database, chrono::Utc::now())?;
+            output
+                .line(format!(
+                    "Seeded demo data: {} documents, {} CAPAs, {} risks, {} suppliers, {} trainings, {} users",
+                    summary.documents, summary.capas, summary.risks, summary.suppliers, summary.trainings, summary.users
+                ))
+                .line(format!(
+                    "Demo titles start with {}; do not use this database for real records",
+                    demo_seed::DEMO_PREFIX
+                ))
+                .field("summary", &summary);
+        }
+        Command::Features { command } => {
+            let database = Database::new(config.database.clone())?;
+            let flags = FeatureFlags::load(database, &config.features.disabled)?;
+            match command {
+                FeaturesCommand::List => {}
+                FeaturesCommand::Enable { feature, user, reason } => {
+                    flags.set_override(Feature::parse(feature)?, true, user, &ChangeReason::new(reason.as_str())?)?;
+                }
+                FeaturesCommand::Disable { feature, user, reason } => {
+                    flags.set_override(Feature::parse(feature)?, false, user, &ChangeReason::new(reason.as_str())?)?;
+                }
+                FeaturesCommand::Reset { feature, user, reason } => {
+                    flags.clear_override(Feature::parse(feature)?, user, &ChangeReason::new(reason.as_str())?)?;
+                }
+            }
+            let statuses = flags.statuses();
+            for status in &statuses {
+                output.line(format!(
+                    "{:<14} {:<9} ({:?})",
+                    status.feature.as_str(),
+                    if status.enabled { "enabled" } else { "disabled" },
+                    status.source
+                ));
+            }
+            output.field("features", &statuses);
+        }
+        Command::Scripts { command } => {
+            let database = Database::new(config.database.clone())?;
+            let repo = ValidationScriptRepo::new(&database);
+            match command {
+                ScriptsCommand::List => {
+                    let scripts = repo.list()?;
+                    for script in &scripts {
+                        let effective = script.effective_at.map_or("not effective".to_string(), |at| at.to_rfc3339());
+                        output.line(format!(
+                            "{} v{} [{}] {} - {} ({})",
+                            script.document_number,
+                            script.version,
+                            script.hook.as_str(),
+                            script.title,
+                            effective,
+                            script.document_id
+                        ));
+                    }
+                    output.field("scripts", &scripts);
+                }
+                ScriptsCommand::Submit { document_number, title, hook, file, description, user } => {
+                    let source = std::fs::read_to_string(file)?;
+                    let script =
+                        repo.submit(document_number, title, RuleHook::parse(hook)?, &source, description, user)?;
+                    output
+                        .line(format!(
+                            "Submitted {} v{} for review (document {})",
+                            script.document_number, script.version, script.document_id
+                        ))
+                        .field("script", &script);
+                }
+                ScriptsCommand::MakeEffective { document_id, user } => {
+                    let script = repo.make_effective(document_id, user)?;
+                    output
+                        .line(format!("{} v{} is in force", script.document_number, script.version))
+                        .field("script", &script);
+                }
+            }
+        }
+        Command::Users { command } => {
+            let database = Database::new(config.database.clone())?;
+            let transfer = OwnershipTransfer::new(&database);
+            match command {
+                UsersCommand::OpenItems { user_id } => {
+                    let items = transfer.open_items(user_id)?;
+                    if items.is_empty() {
+                        output.line(format!("Nothing open for {}", user_id));
+                    }
+                    for item in &items {
+                        output.line(format!("{:<50} {}", item.title, item.record));
+                    }
+                    output.field("items", &items);
+                }
+                UsersCommand::Deactivate { user_id, to, assign, user, reason }
+                | UsersCommand::Transfer { user_id, to, assign, user, reason } => {
+                    let mut plan = TransferPlan::new();
+                    if let Some(owner) = to {
+                        plan = plan.with_default_owner(owner);
+                    }
+                    for assignment in assign {
+                        let (record, owner) = TransferPlan::parse_assignment(assignment)?;
+                        plan = plan.with_owner(record, &owner);
+                    }
+                    let reason = ChangeReason::new(reason.as_str())?;
+                    let report = match command {
+                        UsersCommand::Deactivate { .. } => transfer.deactivate(user_id, &plan, user, &reason)?,
+                        _ => transfer.transfer(user_id, &plan, user, &reason)?,
+                    };
+                    for item in &report.items {
+                        output.line(format!("{} -> {}: {}", item.record, item.new_owner, item.title));
+                    }
+                    output
+                        .line(match report.deactivated {
+                            true => format!("Deactivated {}; {} item(s) transferred", user_id, report.items.len()),
+                            false => format!("Transferred {} item(s) from {}", report.items.len(), user_id),
+                        })
+                        .field("report", &report);
+                }
+            }
+        }
+        Command::BatchRecords { command } => {
+            let database = Database::new(config.database.clone())?;
+            let repo = BatchRecordRepo::new(&database);
+            let print_review = |output: &mut CommandOutput, report: &ReviewReport| {
+                output.line(format!(
+                    "Lot {} ({}) is {}: {} of {} entries verified automatically",
+                    report.batch.lot_number,
+                    report.batch.product,
+                    report.batch.status.as_str(),
+                    report.verified_automatically,
+                    report.entries
+                ));
+                for exception in &report.exceptions {
+                    let disposition = exception.disposition.as_ref();
+                    output.line(format!(
+                        "{} {} / {} {}: {}{}",
+                        exception.entry_id,
+                        exception.step,
+                        exception.parameter,
+                        exception.kind.as_str(),
+                        exception.detail,
+                        disposition.map(|d| format!(" [{}: {}]", d.reviewed_by, d.comment)).unwrap_or_default()
+                    ));
+                }
+                output.field("review", report);
+            };
+            match command {
+                BatchRecordsCommand::Review { lot } => {
+                    let report = repo.review(repo.by_lot(lot)?.id)?;
+                    print_review(&mut output, &report);
+                }
+                BatchRecordsCommand::Entries { lot } => {
+                    let entries = repo.entries(repo.by_lot(lot)?.id)?;
+                    for entry in &entries {
+                        output.line(format!(
+                            "{} / {} = {} ({})",
+                            entry.spec.step,
+                            entry.spec.parameter,
+                            entry.value.as_deref().unwrap_or("-"),
+                            entry.recorded_by.as_deref().unwrap_or("not recorded")
+                        ));
+                    }
+                    output.field("entries", &entries);
+                }
+                BatchRecordsCommand::Disposition { lot, entry, kind, comment, user } => {
+                    let kind = ExceptionKind::parse(kind).ok_or_else(|| qmsrs::QmsError::Validation {
+                        field: "kind".to_string(),
+                        message: format!("Unknown exception {}; expected out_of_limits, corrected or deviation", kind),
+                    })?;
+                    let report = repo.disposition(repo.by_lot(lot)?.id, *entry, kind, comment, user)?;
+                    print_review(&mut output, &report);
+                }
+                BatchRecordsCommand::Release { lot, comment, user } => {
+                    let report = repo.release(repo.by_lot(lot)?.id, user, comment.as_deref())?;
+                    print_review(&mut output, &report);
+                }
+                BatchRecordsCommand::Reject { lot, reason, user } => {
+                    let reason = ChangeReason::new(reason.as_str())?;
+                    let batch = repo.reject(repo.by_lot(lot)?.id, user, &reason)?;
+                    output.line(format!("Lot {} rejected", batch.lot_number)).field("batch", &batch);
+                }
+            }
+        }
+        Command::Delegations { command } => {
+            let database = Database::new(config.database.clone())?;
+            let repo = DelegationRepo::new(&database).with_approval_required(config.compliance.cfr_part_11_mode);
+            let describe = |delegation: &Delegation| {
+                format!(
+                    "{} {} -> {} {} to {} [{}]",
+                    delegation.id,
+                    delegation.delegator,
+                    delegation.delegate,
+                    delegation.starts_on,
+                    delegation.ends_on,
+                    delegation.status.as_str()
+                )
+            };
+            if let DelegationsCommand::List = command {
+                let delegations = repo.list()?;
+                for delegation in &delegations {
+                    output.line(describe(delegation));
+                }
+                output.field("delegations", &delegations);
+            } else {
+                let delegation = match command {
+                    DelegationsCommand::Request { delegator, to, from, until, reason, user } => {
+                        repo.request(delegator, to, *from, *until, reason, user)?
+                    }
+                    DelegationsCommand::Approve { id, user } => repo.approve(*id, user)?,
+                    DelegationsCommand::Reject { id, user } => repo.reject(*id, user)?,
+                    DelegationsCommand::Revoke { id, user } => repo.revoke(*id, user)?,
+                    DelegationsCommand::List => unreachable!(),
+                };
+                output.line(describe(&delegation)).field("delegation", &delegation);
+            }
+        }
+        Command::Config { .. } => unreachable!("config commands run before the configuration is loaded"),
+        Command::Analytics { command: AnalyticsCommand::Export { output: directory } } => {
+            let database = Database::new(config.database.clone())?;
+            let snapshot = analytics_export::export_snapshot(&database, directory, "cli_user", chrono::Utc::now())?;
+            for file in &snapshot.manifest.files {
+                output.line(format!("{}: {} rows", file.file, file.rows));
+            }
+            output
+                .line(format!("Wrote analytics snapshot to {}", snapshot.directory.display()))
+                .field("directory", &snapshot.directory)
+                .field("files", &snapshot.manifest.files);
+        }
+        Command::ExportAudit { output: path, user, timezone } => {
+            let database = Database::new(config.database.clone())?;
+            let tz = match timezone {
+                Some(tz) => tz.parse()?,
+                None => config.application.display_timezone,
+            };
+            let file = std::fs::File::create(path)?;
+            let auditor = AccessAuditor::new(database, &config.compliance);
+            let exported = auditor.write_audit_trail_csv("cli_user", user.as_deref(), tz, file, &mut |done, total| {
+                // Progress is for people; JSON output stays a single document
+                if format == OutputFormat::Text {
+                    let percent = if total == 0 { 100 } else { done * 100 / total };
+                    eprint!("\rExported {}/{} entries ({}%)", done, total, percent);
+                }
+            })?;
+            if format == OutputFormat::Text {
+                eprintln!();
+            }
+            output
+                .line(format!("Wrote {} audit entries to {}", exported, path.display()))
+                .field("entries", exported)
+                .field("path", path);
+        }
+    }
+    Ok(output)
+}
 
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+/// Start the TUI application
+async fn start_tui(mut app: TuiApp) -> Result<()> {
+    // Setup terminal; restored when the guard drops, also on error
+    let _guard = TerminalGuard::enter()?;
+    let backend = CrosstermBackend::new(io::stdout());
+    let mut terminal = Terminal::new(backend)?;
 
-    result
+    // Run the main TUI loop
+    run_tui_loop(&mut terminal, &mut app).await
 }
 
 /// Main TUI event loop
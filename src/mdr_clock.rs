@@ -0,0 +1,511 @@
+//! # MDR Clock - Regulatory Reporting Deadlines for Adverse Events
+//!
+//! Once a manufacturer becomes aware of a reportable event, 21 CFR 803 sets
+//! the clock: a 30-day report (803.50), a 5-work-day report for events that
+//! need remedial action to prevent serious harm (803.53) and a supplemental
+//! report within 30 days of receiving new information (803.56). An
+//! [`MdrClock`] tracks one such deadline for an adverse event from its start
+//! date until the report is submitted. [`MdrClockMonitor`] warns the quality
+//! managers once when a clock is about to run out and once more when it has
+//! been breached; the overdue scan job runs it and the TUI dashboard shows the
+//! countdowns.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::notification::{EmailMessage, Notifier};
+
+/// User recorded for clock warnings.
+pub const MDR_CLOCK_USER: &str = "system:mdr_clock";
+
+/// Regulatory deadline being tracked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MdrClockKind {
+    /// 5 work days from awareness (21 CFR 803.53)
+    FiveDay,
+    /// 30 calendar days from awareness (21 CFR 803.50)
+    ThirtyDay,
+    /// 30 calendar days from receipt of new information (21 CFR 803.56)
+    Supplemental,
+}
+
+impl MdrClockKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MdrClockKind::FiveDay => "five_day",
+            MdrClockKind::ThirtyDay => "thirty_day",
+            MdrClockKind::Supplemental => "supplemental",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "five_day" => Some(MdrClockKind::FiveDay),
+            "thirty_day" => Some(MdrClockKind::ThirtyDay),
+            "supplemental" => Some(MdrClockKind::Supplemental),
+            _ => None,
+        }
+    }
+
+    /// Human-readable report name.
+    pub fn label(&self) -> &'static str {
+        match self {
+            MdrClockKind::FiveDay => "5-day report",
+            MdrClockKind::ThirtyDay => "30-day report",
+            MdrClockKind::Supplemental => "Supplemental report",
+        }
+    }
+
+    /// Date the report is due for a clock started on `started_on`. Work days
+    /// skip weekends only; public holidays are not known here.
+    pub fn due_on(&self, started_on: NaiveDate) -> NaiveDate {
+        match self {
+            MdrClockKind::FiveDay => {
+                let mut due = started_on;
+                let mut work_days = 0;
+                while work_days < 5 {
+                    due += Duration::days(1);
+                    if !matches!(due.weekday(), Weekday::Sat | Weekday::Sun) {
+                        work_days += 1;
+                    }
+                }
+                due
+            }
+            MdrClockKind::ThirtyDay | MdrClockKind::Supplemental => started_on + Duration::days(30),
+        }
+    }
+}
+
+/// Where a clock stands on a given day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClockStatus {
+    Submitted,
+    OnTrack,
+    DueSoon,
+    Breached,
+}
+
+/// A reporting deadline for one adverse event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MdrClock {
+    pub id: Uuid,
+    pub adverse_event_id: Uuid,
+    pub kind: MdrClockKind,
+    /// Awareness date, or the date new information arrived for supplemental reports
+    pub started_on: NaiveDate,
+    pub due_on: NaiveDate,
+    pub started_by: String,
+    pub started_at: DateTime<Utc>,
+    pub submitted_by: Option<String>,
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub warned_at: Option<DateTime<Utc>>,
+    pub breach_notified_at: Option<DateTime<Utc>>,
+}
+
+impl MdrClock {
+    /// Days left until the due date; negative once it has passed.
+    pub fn days_remaining(&self, today: NaiveDate) -> i64 {
+        (self.due_on - today).num_days()
+    }
+
+    /// Status on `today`, counting clocks with at most `warning_days` left as
+    /// due soon.
+    pub fn status(&self, today: NaiveDate, warning_days: u32) -> ClockStatus {
+        let remaining = self.days_remaining(today);
+        if self.submitted_at.is_some() {
+            ClockStatus::Submitted
+        } else if remaining < 0 {
+            ClockStatus::Breached
+        } else if remaining <= i64::from(warning_days) {
+            ClockStatus::DueSoon
+        } else {
+            ClockStatus::OnTrack
+        }
+    }
+}
+
+/// Starts, submits and lists clocks.
+pub struct MdrClockRepo<'a> {
+    db: &'a Database,
+}
+
+impl<'a> MdrClockRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Start a clock for `adverse_event_id`. An event has at most one open
+    /// 5-day and one open 30-day clock; supplemental clocks may overlap.
+    pub fn start(
+        &self,
+        adverse_event_id: Uuid,
+        kind: MdrClockKind,
+        started_on: NaiveDate,
+        user_id: &str,
+    ) -> Result<MdrClock> {
+        let now = Utc::now();
+        if started_on > now.date_naive() {
+            return Err(QmsError::Validation {
+                field: "started_on".to_string(),
+                message: "A regulatory clock cannot start in the future".to_string(),
+            });
+        }
+        let clock = MdrClock {
+            id: Uuid::new_v4(),
+            adverse_event_id,
+            kind,
+            started_on,
+            due_on: kind.due_on(started_on),
+            started_by: user_id.to_string(),
+            started_at: now,
+            submitted_by: None,
+            submitted_at: None,
+            warned_at: None,
+            breach_notified_at: None,
+        };
+        let audit = AuditManager::new(self.db.clone());
+        self.db.unit_of_work(|uow| {
+            let conn = uow.connection();
+            let exists: bool = conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM adverse_events WHERE id = ?1 AND deleted_at IS NULL)",
+                params![adverse_event_id.to_string()],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                return Err(QmsError::NotFound {
+                    resource: "adverse_event".to_string(),
+                    id: adverse_event_id.to_string(),
+                });
+            }
+            if kind != MdrClockKind::Supplemental {
+                let open: Option<String> = conn
+                    .query_row(
+                        "SELECT id FROM mdr_clocks
+                         WHERE adverse_event_id = ?1 AND kind = ?2 AND submitted_at IS NULL",
+                        params![adverse_event_id.to_string(), kind.as_str()],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+                if let Some(open) = open {
+                    return Err(QmsError::Validation {
+                        field: "kind".to_string(),
+                        message: format!("A {} clock ({}) is already running for this event", kind.label(), open),
+                    });
+                }
+            }
+            conn.execute(
+                "INSERT INTO mdr_clocks (id, adverse_event_id, kind, started_on, due_on, started_by, started_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    clock.id.to_string(),
+                    adverse_event_id.to_string(),
+                    kind.as_str(),
+                    started_on.to_string(),
+                    clock.due_on.to_string(),
+                    user_id,
+                    now.to_rfc3339()
+                ],
+            )?;
+            audit.log_action_in(
+                uow,
+                user_id,
+                "mdr_clock_started",
+                &resource(adverse_event_id),
+                "Success",
+                Some(format!("{} started {}, due {}", kind.label(), started_on, clock.due_on)),
+            )
+        })?;
+        Ok(clock)
+    }
+
+    /// Record that the report for `clock_id` was submitted.
+    pub fn mark_submitted(&self, clock_id: Uuid, user_id: &str) -> Result<MdrClock> {
+        let audit = AuditManager::new(self.db.clone());
+        self.db.unit_of_work(|uow| {
+            let mut clock = fetch(uow.connection(), clock_id)?
+                .ok_or_else(|| QmsError::NotFound { resource: "mdr_clock".to_string(), id: clock_id.to_string() })?;
+            if clock.submitted_at.is_some() {
+                return Err(QmsError::Validation {
+                    field: "clock_id".to_string(),
+                    message: format!("The {} for this clock is already submitted", clock.kind.label()),
+                });
+            }
+            let now = Utc::now();
+            uow.connection().execute(
+                "UPDATE mdr_clocks SET submitted_by = ?2, submitted_at = ?3 WHERE id = ?1",
+                params![clock_id.to_string(), user_id, now.to_rfc3339()],
+            )?;
+            let late = now.date_naive() > clock.due_on;
+            audit.log_action_in(
+                uow,
+                user_id,
+                "mdr_clock_submitted",
+                &resource(clock.adverse_event_id),
+                if late { "Warning" } else { "Success" },
+                Some(format!(
+                    "{} submitted{} (due {})",
+                    clock.kind.label(),
+                    if late { " late" } else { "" },
+                    clock.due_on
+                )),
+            )?;
+            clock.submitted_by = Some(user_id.to_string());
+            clock.submitted_at = Some(now);
+            Ok(clock)
+        })
+    }
+
+    /// Clocks whose report is not yet submitted, soonest due first.
+    pub fn open(&self) -> Result<Vec<MdrClock>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM mdr_clocks WHERE submitted_at IS NULL ORDER BY due_on, started_at",
+                COLUMNS
+            ))?;
+            let clocks = stmt.query_map([], row_to_clock)?.collect::<std::result::Result<Vec<_>, _>>()?;
+            Ok(clocks)
+        })
+    }
+
+    /// Every clock of an adverse event, oldest first.
+    pub fn for_event(&self, adverse_event_id: Uuid) -> Result<Vec<MdrClock>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM mdr_clocks WHERE adverse_event_id = ?1 ORDER BY started_at, id",
+                COLUMNS
+            ))?;
+            let clocks = stmt
+                .query_map(params![adverse_event_id.to_string()], row_to_clock)?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            Ok(clocks)
+        })
+    }
+}
+
+/// A warning raised for a clock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MdrClockAlert {
+    pub clock: MdrClock,
+    /// `DueSoon` or `Breached`
+    pub status: ClockStatus,
+    pub message_id: Option<String>,
+}
+
+/// Warns about clocks that are due soon or breached. Each clock is warned
+/// at most once per status.
+pub struct MdrClockMonitor<'a> {
+    db: &'a Database,
+    warning_days: u32,
+    notifier: Option<(&'a dyn Notifier, Vec<String>)>,
+}
+
+impl<'a> MdrClockMonitor<'a> {
+    pub fn new(db: &'a Database, warning_days: u32) -> Self {
+        Self { db, warning_days, notifier: None }
+    }
+
+    /// Email warnings to `recipients`.
+    pub fn with_notifier(mut self, notifier: &'a dyn Notifier, recipients: Vec<String>) -> Self {
+        self.notifier = Some((notifier, recipients));
+        self
+    }
+
+    /// Raise the warnings newly due at `now`.
+    pub fn check(&self, now: DateTime<Utc>) -> Result<Vec<MdrClockAlert>> {
+        let today = now.date_naive();
+        let mut alerts = Vec::new();
+        for clock in MdrClockRepo::new(self.db).open()? {
+            let status = clock.status(today, self.warning_days);
+            let column = match status {
+                ClockStatus::Breached if clock.breach_notified_at.is_none() => "breach_notified_at",
+                ClockStatus::DueSoon if clock.warned_at.is_none() => "warned_at",
+                _ => continue,
+            };
+            let message_id = match &self.notifier {
+                Some((notifier, recipients)) if !recipients.is_empty() => {
+                    Some(notifier.send(&warning_email(&clock, status, recipients.clone(), today))?)
+                }
+                _ => None,
+            };
+            self.db.unit_of_work(|uow| {
+                uow.connection().execute(
+                    &format!("UPDATE mdr_clocks SET {} = ?2 WHERE id = ?1", column),
+                    params![clock.id.to_string(), now.to_rfc3339()],
+                )?;
+                let (action, outcome) = match status {
+                    ClockStatus::Breached => ("mdr_clock_breached", "Failure"),
+                    _ => ("mdr_clock_due_soon", "Warning"),
+                };
+                AuditManager::new(self.db.clone()).log_action_in(
+                    uow,
+                    MDR_CLOCK_USER,
+                    action,
+                    &resource(clock.adverse_event_id),
+                    outcome,
+                    Some(format!("{} due {}", clock.kind.label(), clock.due_on)),
+                )
+            })?;
+            alerts.push(MdrClockAlert { clock, status, message_id });
+        }
+        Ok(alerts)
+    }
+}
+
+fn warning_email(clock: &MdrClock, status: ClockStatus, recipients: Vec<String>, today: NaiveDate) -> EmailMessage {
+    let headline = match status {
+        ClockStatus::Breached => format!("{} is overdue since {}", clock.kind.label(), clock.due_on),
+        _ => format!("{} is due in {} day(s)", clock.kind.label(), clock.days_remaining(today)),
+    };
+    let body = format!(
+        "{} for adverse event {}.\n\n\
+         Clock started: {}\nDue: {}\n\n\
+         This message was sent automatically by QMSrs.",
+        headline, clock.adverse_event_id, clock.started_on, clock.due_on,
+    );
+    EmailMessage::new(recipients, format!("MDR deadline: {}", headline), body)
+}
+
+fn resource(adverse_event_id: Uuid) -> String {
+    format!("adverse_event:{}", adverse_event_id)
+}
+
+const COLUMNS: &str = "id, adverse_event_id, kind, started_on, due_on, started_by, started_at, submitted_by,
+     submitted_at, warned_at, breach_notified_at";
+
+fn fetch(conn: &rusqlite::Connection, clock_id: Uuid) -> Result<Option<MdrClock>> {
+    Ok(conn
+        .query_row(
+            &format!("SELECT {} FROM mdr_clocks WHERE id = ?1", COLUMNS),
+            params![clock_id.to_string()],
+            row_to_clock,
+        )
+        .optional()?)
+}
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn parse_uuid(row: &Row, index: usize) -> rusqlite::Result<Uuid> {
+    let value: String = row.get(index)?;
+    Uuid::parse_str(&value).map_err(|e| conversion_error(index, e.to_string()))
+}
+
+fn parse_date(row: &Row, index: usize) -> rusqlite::Result<NaiveDate> {
+    let value: String = row.get(index)?;
+    NaiveDate::parse_from_str(&value, "%Y-%m-%d").map_err(|e| conversion_error(index, e.to_string()))
+}
+
+fn parse_time(row: &Row, index: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| conversion_error(index, e.to_string()))
+        })
+        .transpose()
+}
+
+fn row_to_clock(row: &Row) -> rusqlite::Result<MdrClock> {
+    let kind: String = row.get(2)?;
+    Ok(MdrClock {
+        id: parse_uuid(row, 0)?,
+        adverse_event_id: parse_uuid(row, 1)?,
+        kind: MdrClockKind::parse(&kind).ok_or_else(|| conversion_error(2, format!("unknown clock kind {}", kind)))?,
+        started_on: parse_date(row, 3)?,
+        due_on: parse_date(row, 4)?,
+        started_by: row.get(5)?,
+        started_at: parse_time(row, 6)?.ok_or_else(|| conversion_error(6, "started_at is missing".to_string()))?,
+        submitted_by: row.get(7)?,
+        submitted_at: parse_time(row, 8)?,
+        warned_at: parse_time(row, 9)?,
+        breach_notified_at: parse_time(row, 10)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::post_market::{AdverseEvent, AdverseEventRepo, Severity};
+    use chrono::TimeZone;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingNotifier {
+        sent: Mutex<Vec<EmailMessage>>,
+    }
+
+    impl Notifier for RecordingNotifier {
+        fn send(&self, message: &EmailMessage) -> Result<String> {
+            let mut sent = self.sent.lock().unwrap();
+            sent.push(message.clone());
+            Ok(format!("msg-{}", sent.len()))
+        }
+    }
+
+    #[test]
+    fn test_clocks_count_down_and_warn_once_before_and_after_breach() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let event = AdverseEvent::new("field_service", "Pump occlusion alarm failed", Severity::Critical);
+        AdverseEventRepo::new(&db).insert(&event).unwrap();
+
+        // Friday awareness: five work days skip the weekend
+        let friday = NaiveDate::from_ymd_opt(2026, 10, 9).unwrap();
+        assert_eq!(MdrClockKind::FiveDay.due_on(friday), NaiveDate::from_ymd_opt(2026, 10, 16).unwrap());
+        assert_eq!(MdrClockKind::ThirtyDay.due_on(friday), NaiveDate::from_ymd_opt(2026, 11, 8).unwrap());
+
+        let repo = MdrClockRepo::new(&db);
+        let five_day = repo.start(event.id, MdrClockKind::FiveDay, friday, "qa_manager").unwrap();
+        let thirty_day = repo.start(event.id, MdrClockKind::ThirtyDay, friday, "qa_manager").unwrap();
+        assert!(repo.start(event.id, MdrClockKind::FiveDay, friday, "qa_manager").is_err());
+        assert!(matches!(
+            repo.start(Uuid::new_v4(), MdrClockKind::ThirtyDay, friday, "qa_manager"),
+            Err(QmsError::NotFound { .. })
+        ));
+        assert_eq!(repo.open().unwrap(), vec![five_day.clone(), thirty_day.clone()]);
+
+        let notifier = RecordingNotifier::default();
+        let monitor = MdrClockMonitor::new(&db, 2).with_notifier(&notifier, vec!["qm@example.com".to_string()]);
+        let wednesday = Utc.with_ymd_and_hms(2026, 10, 14, 8, 0, 0).unwrap();
+        assert_eq!(five_day.days_remaining(wednesday.date_naive()), 2);
+        let alerts = monitor.check(wednesday).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!((alerts[0].clock.id, alerts[0].status), (five_day.id, ClockStatus::DueSoon));
+        assert!(monitor.check(wednesday).unwrap().is_empty(), "warned once");
+
+        let saturday = Utc.with_ymd_and_hms(2026, 10, 17, 8, 0, 0).unwrap();
+        let alerts = monitor.check(saturday).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].status, ClockStatus::Breached);
+        assert_eq!(alerts[0].message_id.as_deref(), Some("msg-2"));
+        assert!(notifier.sent.lock().unwrap()[1].subject.contains("5-day report is overdue"));
+        assert!(monitor.check(saturday).unwrap().is_empty());
+
+        let submitted = repo.mark_submitted(five_day.id, "qa_manager").unwrap();
+        assert_eq!(submitted.status(saturday.date_naive(), 2), ClockStatus::Submitted);
+        assert!(repo.mark_submitted(five_day.id, "qa_manager").is_err());
+        assert_eq!(repo.open().unwrap().len(), 1);
+        assert_eq!(repo.for_event(event.id).unwrap().len(), 2);
+
+        let audit = db.get_audit_entries_for_resource(&format!("adverse_event:{}", event.id)).unwrap();
+        let actions: Vec<&str> = audit.iter().map(|e| e.action.as_str()).collect();
+        assert!(actions.contains(&"mdr_clock_due_soon"));
+        assert!(actions.contains(&"mdr_clock_breached"));
+        assert_eq!(actions.last(), Some(&"mdr_clock_submitted"));
+    }
+}
@@ -0,0 +1,1636 @@
+//! # Schema Migrations - Versioned Database Evolution
+//!
+//! The baseline schema is created idempotently by `Database::initialize_schema`.
+//! Every change after that baseline is expressed as a numbered, forward-only
+//! migration so that existing production databases and fresh installations end
+//! up with an identical schema.
+//!
+//! Each migration runs inside its own transaction together with the insert into
+//! `schema_migrations`, so a failure leaves the database at the previous version
+//! (FDA 21 CFR Part 11 §11.10(a) system validation evidence).
+
+use crate::error::{QmsError, Result};
+use chrono::Utc;
+use rusqlite::{params, Connection};
+
+/// A single forward-only schema migration.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    /// Monotonically increasing version number (starting at 1)
+    pub version: u32,
+    /// Short human-readable description recorded in `schema_migrations`
+    pub description: &'static str,
+    /// SQL batch executed in a single transaction
+    pub sql: &'static str,
+}
+
+/// Ordered list of all schema migrations.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "training records, suppliers and adverse events",
+        sql: "
+            -- TASK-025: Training Records schema
+            CREATE TABLE IF NOT EXISTS training_records (
+                id TEXT PRIMARY KEY,
+                employee_id TEXT NOT NULL,
+                training_item TEXT NOT NULL,
+                mandatory BOOLEAN NOT NULL,
+                assigned_by TEXT NOT NULL,
+                due_date TEXT NOT NULL,
+                completion_date TEXT,
+                status TEXT NOT NULL CHECK (status IN ('Pending', 'InProgress', 'Completed', 'Overdue')),
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (employee_id) REFERENCES users(id),
+                FOREIGN KEY (assigned_by) REFERENCES users(id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_training_records_status ON training_records(status);
+            CREATE INDEX IF NOT EXISTS idx_training_records_employee ON training_records(employee_id);
+            CREATE INDEX IF NOT EXISTS idx_training_records_due_date ON training_records(due_date);
+
+            -- TASK-027: Supplier Management schema
+            CREATE TABLE IF NOT EXISTS suppliers (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                contact_info TEXT,
+                qualification_status TEXT NOT NULL CHECK (qualification_status IN ('Pending','Qualified','Disqualified')),
+                qualification_date TEXT,
+                qualification_expiry_date TEXT,
+                approved_by TEXT,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (approved_by) REFERENCES users(id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_suppliers_status ON suppliers(qualification_status);
+            CREATE INDEX IF NOT EXISTS idx_suppliers_name ON suppliers(name);
+
+            -- Phase 5: Post-market surveillance (severity stored as `Severity as i32`)
+            CREATE TABLE IF NOT EXISTS adverse_events (
+                id TEXT PRIMARY KEY,
+                reported_on TEXT NOT NULL,
+                reporter TEXT NOT NULL,
+                description TEXT NOT NULL,
+                severity INTEGER NOT NULL CHECK (severity IN (0, 1, 2)),
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            );
+            CREATE INDEX IF NOT EXISTS idx_adverse_events_reported_on ON adverse_events(reported_on);
+            CREATE INDEX IF NOT EXISTS idx_adverse_events_severity ON adverse_events(severity);
+        ",
+    },
+    Migration {
+        version: 2,
+        description: "signed report registry",
+        sql: "
+            CREATE TABLE IF NOT EXISTS report_signatures (
+                id TEXT PRIMARY KEY,
+                report_title TEXT NOT NULL,
+                file_name TEXT NOT NULL,
+                file_sha256 TEXT NOT NULL UNIQUE,
+                payload_sha256 TEXT NOT NULL,
+                signature TEXT NOT NULL,
+                public_key TEXT NOT NULL,
+                key_id TEXT NOT NULL,
+                algorithm TEXT NOT NULL,
+                signed_by TEXT NOT NULL,
+                signed_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_report_signatures_payload ON report_signatures(payload_sha256);
+        ",
+    },
+    Migration {
+        version: 3,
+        description: "scheduled report distribution",
+        sql: "
+            CREATE TABLE IF NOT EXISTS report_schedules (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                kind TEXT NOT NULL CHECK (kind IN ('ComplianceSummary', 'CapaAging')),
+                recurrence TEXT NOT NULL,
+                recipients TEXT NOT NULL,
+                enabled BOOLEAN NOT NULL DEFAULT 1,
+                next_run_at TEXT NOT NULL,
+                last_run_at TEXT,
+                created_by TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_report_schedules_next_run ON report_schedules(enabled, next_run_at);
+
+            CREATE TABLE IF NOT EXISTS report_runs (
+                id TEXT PRIMARY KEY,
+                schedule_id TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                completed_at TEXT NOT NULL,
+                status TEXT NOT NULL CHECK (status IN ('Succeeded', 'Failed')),
+                stored_path TEXT,
+                file_sha256 TEXT,
+                message_id TEXT,
+                recipients TEXT NOT NULL,
+                error TEXT,
+                FOREIGN KEY (schedule_id) REFERENCES report_schedules(id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_report_runs_schedule ON report_runs(schedule_id, started_at);
+        ",
+    },
+    Migration {
+        version: 4,
+        description: "field-level change history with reason for change",
+        sql: "
+            CREATE TABLE IF NOT EXISTS change_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                change_id TEXT NOT NULL,
+                record_type TEXT NOT NULL,
+                record_id TEXT NOT NULL,
+                field_name TEXT NOT NULL,
+                old_value TEXT,
+                new_value TEXT,
+                reason TEXT NOT NULL CHECK (length(trim(reason)) > 0),
+                changed_by TEXT NOT NULL,
+                changed_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_change_history_record ON change_history(record_type, record_id, changed_at);
+            CREATE INDEX IF NOT EXISTS idx_change_history_change ON change_history(change_id);
+        ",
+    },
+    Migration {
+        version: 5,
+        description: "record version snapshots",
+        sql: "
+            CREATE TABLE IF NOT EXISTS record_versions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                record_type TEXT NOT NULL,
+                record_id TEXT NOT NULL,
+                version INTEGER NOT NULL,
+                snapshot TEXT NOT NULL,
+                snapshot_sha256 TEXT NOT NULL,
+                captured_by TEXT NOT NULL,
+                captured_at TEXT NOT NULL,
+                UNIQUE(record_type, record_id, version)
+            );
+            CREATE INDEX IF NOT EXISTS idx_record_versions_as_of ON record_versions(record_type, record_id, captured_at);
+        ",
+    },
+    Migration {
+        version: 6,
+        description: "soft-delete columns on record tables",
+        sql: "
+            -- Append-only evidence tables (audit_trail, change_history, record_versions,
+            -- report_signatures, report_runs) are never deleted and get no columns.
+            ALTER TABLE users ADD COLUMN deleted_at TEXT;
+            ALTER TABLE users ADD COLUMN deleted_by TEXT;
+            ALTER TABLE users ADD COLUMN deletion_reason TEXT;
+
+            ALTER TABLE documents ADD COLUMN deleted_at TEXT;
+            ALTER TABLE documents ADD COLUMN deleted_by TEXT;
+            ALTER TABLE documents ADD COLUMN deletion_reason TEXT;
+
+            ALTER TABLE capa_records ADD COLUMN deleted_at TEXT;
+            ALTER TABLE capa_records ADD COLUMN deleted_by TEXT;
+            ALTER TABLE capa_records ADD COLUMN deletion_reason TEXT;
+
+            ALTER TABLE capa_actions ADD COLUMN deleted_at TEXT;
+            ALTER TABLE capa_actions ADD COLUMN deleted_by TEXT;
+            ALTER TABLE capa_actions ADD COLUMN deletion_reason TEXT;
+
+            ALTER TABLE risk_assessments ADD COLUMN deleted_at TEXT;
+            ALTER TABLE risk_assessments ADD COLUMN deleted_by TEXT;
+            ALTER TABLE risk_assessments ADD COLUMN deletion_reason TEXT;
+
+            ALTER TABLE control_measures ADD COLUMN deleted_at TEXT;
+            ALTER TABLE control_measures ADD COLUMN deleted_by TEXT;
+            ALTER TABLE control_measures ADD COLUMN deletion_reason TEXT;
+
+            ALTER TABLE training_records ADD COLUMN deleted_at TEXT;
+            ALTER TABLE training_records ADD COLUMN deleted_by TEXT;
+            ALTER TABLE training_records ADD COLUMN deletion_reason TEXT;
+
+            ALTER TABLE suppliers ADD COLUMN deleted_at TEXT;
+            ALTER TABLE suppliers ADD COLUMN deleted_by TEXT;
+            ALTER TABLE suppliers ADD COLUMN deletion_reason TEXT;
+
+            ALTER TABLE adverse_events ADD COLUMN deleted_at TEXT;
+            ALTER TABLE adverse_events ADD COLUMN deleted_by TEXT;
+            ALTER TABLE adverse_events ADD COLUMN deletion_reason TEXT;
+
+            ALTER TABLE report_schedules ADD COLUMN deleted_at TEXT;
+            ALTER TABLE report_schedules ADD COLUMN deleted_by TEXT;
+            ALTER TABLE report_schedules ADD COLUMN deletion_reason TEXT;
+        ",
+    },
+    Migration {
+        version: 7,
+        description: "multi-site partitioning",
+        sql: "
+            CREATE TABLE IF NOT EXISTS sites (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+            );
+            -- Existing single-site data belongs to the default site
+            INSERT OR IGNORE INTO sites (id, name) VALUES ('default', 'Default Site');
+
+            -- site_id '*' grants corporate (all-site) access
+            CREATE TABLE IF NOT EXISTS site_roles (
+                user_id TEXT NOT NULL,
+                site_id TEXT NOT NULL,
+                role TEXT NOT NULL CHECK (role IN ('Viewer', 'QualityEngineer', 'QualityManager', 'CorporateQa')),
+                granted_by TEXT NOT NULL,
+                granted_at TEXT NOT NULL,
+                PRIMARY KEY (user_id, site_id, role),
+                FOREIGN KEY (user_id) REFERENCES users(id)
+            );
+
+            ALTER TABLE audit_trail ADD COLUMN site_id TEXT NOT NULL DEFAULT 'default';
+            ALTER TABLE documents ADD COLUMN site_id TEXT NOT NULL DEFAULT 'default';
+            ALTER TABLE capa_records ADD COLUMN site_id TEXT NOT NULL DEFAULT 'default';
+            ALTER TABLE risk_assessments ADD COLUMN site_id TEXT NOT NULL DEFAULT 'default';
+            ALTER TABLE training_records ADD COLUMN site_id TEXT NOT NULL DEFAULT 'default';
+            ALTER TABLE suppliers ADD COLUMN site_id TEXT NOT NULL DEFAULT 'default';
+            ALTER TABLE adverse_events ADD COLUMN site_id TEXT NOT NULL DEFAULT 'default';
+            ALTER TABLE report_schedules ADD COLUMN site_id TEXT NOT NULL DEFAULT 'default';
+
+            CREATE INDEX IF NOT EXISTS idx_audit_trail_site ON audit_trail(site_id, timestamp);
+            CREATE INDEX IF NOT EXISTS idx_capa_records_site ON capa_records(site_id);
+            CREATE INDEX IF NOT EXISTS idx_training_records_site ON training_records(site_id);
+            CREATE INDEX IF NOT EXISTS idx_suppliers_site ON suppliers(site_id);
+            CREATE INDEX IF NOT EXISTS idx_adverse_events_site ON adverse_events(site_id);
+        ",
+    },
+    Migration {
+        version: 8,
+        description: "per-user display timezone",
+        sql: "
+            -- Presentation only; stored timestamps stay RFC 3339 UTC
+            ALTER TABLE users ADD COLUMN display_timezone TEXT;
+        ",
+    },
+    Migration {
+        version: 9,
+        description: "materialized reporting summaries",
+        sql: "
+            -- Per-site aggregates rebuilt by reporting_views; stale = 1 forces a rebuild
+            CREATE TABLE IF NOT EXISTS capa_summary (
+                site_id TEXT NOT NULL,
+                status TEXT NOT NULL,
+                priority TEXT NOT NULL,
+                record_count INTEGER NOT NULL,
+                overdue_count INTEGER NOT NULL,
+                PRIMARY KEY (site_id, status, priority)
+            );
+            CREATE TABLE IF NOT EXISTS supplier_summary (
+                site_id TEXT NOT NULL,
+                qualification_status TEXT NOT NULL,
+                supplier_count INTEGER NOT NULL,
+                PRIMARY KEY (site_id, qualification_status)
+            );
+            CREATE TABLE IF NOT EXISTS training_summary (
+                site_id TEXT NOT NULL,
+                status TEXT NOT NULL,
+                record_count INTEGER NOT NULL,
+                PRIMARY KEY (site_id, status)
+            );
+            CREATE TABLE IF NOT EXISTS summary_refreshes (
+                summary TEXT PRIMARY KEY,
+                refreshed_at TEXT,
+                stale INTEGER NOT NULL DEFAULT 1
+            );
+            INSERT OR IGNORE INTO summary_refreshes (summary)
+                VALUES ('capa_summary'), ('supplier_summary'), ('training_summary');
+        ",
+    },
+    Migration {
+        version: 10,
+        description: "electronic signatures",
+        sql: "
+            -- Written in the same unit of work as the signed record and its audit entry
+            CREATE TABLE IF NOT EXISTS electronic_signatures (
+                id TEXT PRIMARY KEY,
+                record_type TEXT NOT NULL,
+                record_id TEXT NOT NULL,
+                signer_id TEXT NOT NULL,
+                meaning TEXT NOT NULL,
+                record_sha256 TEXT NOT NULL,
+                signed_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_electronic_signatures_record
+                ON electronic_signatures(record_type, record_id);
+        ",
+    },
+    Migration {
+        version: 11,
+        description: "quality events",
+        sql: "
+            -- Critical errors escalated for FDA notification assessment
+            CREATE TABLE IF NOT EXISTS quality_events (
+                id TEXT PRIMARY KEY,
+                error_code TEXT NOT NULL,
+                severity TEXT NOT NULL,
+                message TEXT NOT NULL,
+                context TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                notification_id TEXT,
+                acknowledged_by TEXT,
+                acknowledged_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_quality_events_open
+                ON quality_events(acknowledged_at, created_at);
+        ",
+    },
+    Migration {
+        version: 12,
+        description: "quality issue intake",
+        sql: "
+            -- Complaints, NCRs, audit findings and observations awaiting or after triage
+            CREATE TABLE IF NOT EXISTS quality_intake (
+                id TEXT PRIMARY KEY,
+                source TEXT NOT NULL CHECK (source IN ('complaint', 'nonconformance', 'audit_finding', 'internal_observation')),
+                title TEXT NOT NULL,
+                description TEXT NOT NULL,
+                reported_by TEXT NOT NULL,
+                reported_at TEXT NOT NULL,
+                routed_to TEXT CHECK (routed_to IN ('capa', 'adverse_event', 'change_control', 'no_action')),
+                routed_record_id TEXT,
+                triaged_by TEXT,
+                triaged_at TEXT,
+                triage_rationale TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_quality_intake_triage
+                ON quality_intake(triaged_at, reported_at);
+        ",
+    },
+    Migration {
+        version: 13,
+        description: "process and software validation",
+        sql: "
+            CREATE TABLE IF NOT EXISTS validation_protocols (
+                id TEXT PRIMARY KEY,
+                subject TEXT NOT NULL,
+                subject_kind TEXT NOT NULL CHECK (subject_kind IN ('process', 'software_tool')),
+                stage TEXT NOT NULL CHECK (stage IN ('IQ', 'OQ', 'PQ')),
+                title TEXT NOT NULL,
+                status TEXT NOT NULL CHECK (status IN ('draft', 'approved', 'in_execution', 'completed')),
+                created_by TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                approved_by TEXT,
+                approved_at TEXT,
+                completed_by TEXT,
+                completed_at TEXT,
+                revalidation_due TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_validation_protocols_revalidation
+                ON validation_protocols(status, revalidation_due);
+            CREATE TABLE IF NOT EXISTS validation_evidence (
+                id TEXT PRIMARY KEY,
+                protocol_id TEXT NOT NULL REFERENCES validation_protocols(id),
+                step TEXT NOT NULL,
+                passed INTEGER NOT NULL,
+                evidence_ref TEXT NOT NULL,
+                recorded_by TEXT NOT NULL,
+                recorded_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS validation_deviations (
+                id TEXT PRIMARY KEY,
+                protocol_id TEXT NOT NULL REFERENCES validation_protocols(id),
+                description TEXT NOT NULL,
+                raised_by TEXT NOT NULL,
+                raised_at TEXT NOT NULL,
+                resolution TEXT,
+                resolved_at TEXT
+            );
+        ",
+    },
+    Migration {
+        version: 14,
+        description: "environmental monitoring",
+        sql: "
+            CREATE TABLE IF NOT EXISTS environmental_limits (
+                area TEXT NOT NULL,
+                parameter TEXT NOT NULL,
+                min_value REAL,
+                max_value REAL,
+                updated_by TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (area, parameter)
+            );
+            CREATE TABLE IF NOT EXISTS environmental_readings (
+                id TEXT PRIMARY KEY,
+                area TEXT NOT NULL,
+                parameter TEXT NOT NULL,
+                value REAL NOT NULL,
+                recorded_by TEXT NOT NULL,
+                recorded_at TEXT NOT NULL,
+                excursion_id TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_environmental_readings_trend
+                ON environmental_readings(area, parameter, recorded_at);
+            CREATE TABLE IF NOT EXISTS environmental_excursions (
+                id TEXT PRIMARY KEY,
+                reading_id TEXT NOT NULL REFERENCES environmental_readings(id),
+                area TEXT NOT NULL,
+                parameter TEXT NOT NULL,
+                value REAL NOT NULL,
+                limit_text TEXT NOT NULL,
+                status TEXT NOT NULL CHECK (status IN ('open', 'escalated', 'closed')),
+                ncr_id TEXT,
+                capa_id TEXT,
+                opened_at TEXT NOT NULL,
+                closed_by TEXT,
+                closed_at TEXT,
+                disposition TEXT
+            );
+        ",
+    },
+    Migration {
+        version: 15,
+        description: "audit finding commitments",
+        sql: "
+            CREATE TABLE IF NOT EXISTS audit_findings (
+                id TEXT PRIMARY KEY,
+                audit_kind TEXT NOT NULL CHECK (audit_kind IN ('internal', 'supplier')),
+                audit_reference TEXT NOT NULL,
+                description TEXT NOT NULL,
+                severity TEXT NOT NULL CHECK (severity IN ('major', 'minor', 'observation')),
+                owner TEXT NOT NULL,
+                committed_date TEXT NOT NULL,
+                raised_by TEXT NOT NULL,
+                raised_at TEXT NOT NULL,
+                closure_evidence TEXT,
+                closed_by TEXT,
+                closed_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_audit_findings_open
+                ON audit_findings(closed_at, committed_date);
+        ",
+    },
+    Migration {
+        version: 16,
+        description: "record numbering rules and sequences",
+        sql: "
+            CREATE TABLE IF NOT EXISTS numbering_rules (
+                scope TEXT PRIMARY KEY,
+                prefix TEXT NOT NULL,
+                padding INTEGER NOT NULL CHECK (padding BETWEEN 1 AND 10),
+                include_site BOOLEAN NOT NULL,
+                include_year BOOLEAN NOT NULL,
+                updated_by TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            -- Last allocated value per scope and partition (site and/or year)
+            CREATE TABLE IF NOT EXISTS numbering_sequences (
+                scope TEXT NOT NULL,
+                partition TEXT NOT NULL,
+                last_value INTEGER NOT NULL,
+                PRIMARY KEY (scope, partition)
+            );
+        ",
+    },
+    Migration {
+        version: 17,
+        description: "human-readable quality intake numbers",
+        sql: "
+            ALTER TABLE quality_intake ADD COLUMN record_number TEXT;
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_quality_intake_number ON quality_intake(record_number);
+        ",
+    },
+    Migration {
+        version: 18,
+        description: "record links",
+        sql: "
+            -- Typed traceability links between records of any module; retired, never deleted
+            CREATE TABLE IF NOT EXISTS record_links (
+                id TEXT PRIMARY KEY,
+                source_type TEXT NOT NULL,
+                source_id TEXT NOT NULL,
+                target_type TEXT NOT NULL,
+                target_id TEXT NOT NULL,
+                link_type TEXT NOT NULL
+                    CHECK (link_type IN ('addresses', 'derived_from', 'controlled_by', 'affects', 'relates_to')),
+                created_by TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                removed_by TEXT,
+                removed_at TEXT,
+                removal_reason TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_record_links_source ON record_links(source_type, source_id);
+            CREATE INDEX IF NOT EXISTS idx_record_links_target ON record_links(target_type, target_id);
+        ",
+    },
+    Migration {
+        version: 19,
+        description: "escalation rules",
+        sql: "
+            -- Reminder and escalation rules evaluated by the report scheduler
+            CREATE TABLE IF NOT EXISTS escalation_rules (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                subject TEXT NOT NULL CHECK (subject IN ('capa', 'document')),
+                trigger_kind TEXT NOT NULL CHECK (trigger_kind IN ('open_longer_than', 'overdue_by')),
+                threshold_days INTEGER NOT NULL CHECK (threshold_days >= 0),
+                priority TEXT,
+                escalate_to TEXT NOT NULL,
+                recipients TEXT NOT NULL,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                created_by TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+            -- One firing per rule and record, so escalations are not repeated every run
+            CREATE TABLE IF NOT EXISTS escalation_firings (
+                rule_id TEXT NOT NULL REFERENCES escalation_rules(id),
+                record_id TEXT NOT NULL,
+                subject TEXT NOT NULL,
+                fired_at TEXT NOT NULL,
+                message_id TEXT,
+                PRIMARY KEY (rule_id, record_id)
+            );
+        ",
+    },
+    Migration {
+        version: 20,
+        description: "kpi targets and monthly snapshots",
+        sql: "
+            -- Per-metric overrides of the KPI targets in the configuration
+            CREATE TABLE IF NOT EXISTS kpi_targets (
+                metric TEXT PRIMARY KEY,
+                target REAL NOT NULL CHECK (target BETWEEN 0 AND 100),
+                amber_margin REAL NOT NULL CHECK (amber_margin BETWEEN 0 AND 100),
+                updated_by TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            -- Latest value of each KPI per calendar month (YYYY-MM) for trends
+            CREATE TABLE IF NOT EXISTS kpi_snapshots (
+                metric TEXT NOT NULL,
+                month TEXT NOT NULL,
+                value REAL NOT NULL,
+                recorded_at TEXT NOT NULL,
+                PRIMARY KEY (metric, month)
+            );
+        ",
+    },
+    Migration {
+        version: 21,
+        description: "csrf tokens for browser sessions",
+        sql: "
+            -- Synchronizer token a cookie session must echo on state-changing requests
+            ALTER TABLE sessions ADD COLUMN csrf_token TEXT;
+            CREATE INDEX IF NOT EXISTS idx_sessions_user ON sessions(user_id, is_active);
+        ",
+    },
+    Migration {
+        version: 22,
+        description: "row versions for optimistic concurrency",
+        sql: "
+            -- Incremented on every update; writers must present the version they read
+            ALTER TABLE suppliers ADD COLUMN row_version INTEGER NOT NULL DEFAULT 1;
+            ALTER TABLE training_records ADD COLUMN row_version INTEGER NOT NULL DEFAULT 1;
+            ALTER TABLE documents ADD COLUMN row_version INTEGER NOT NULL DEFAULT 1;
+        ",
+    },
+    Migration {
+        version: 23,
+        description: "background job run history",
+        sql: "
+            CREATE TABLE IF NOT EXISTS job_runs (
+                id TEXT PRIMARY KEY,
+                job TEXT NOT NULL CHECK (job IN ('backup', 'overdue_scan', 'report_generation')),
+                run_trigger TEXT NOT NULL CHECK (run_trigger IN ('scheduled', 'manual')),
+                triggered_by TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                completed_at TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                outcome TEXT NOT NULL CHECK (outcome IN ('succeeded', 'failed')),
+                detail TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_job_runs_job ON job_runs(job, started_at);
+        ",
+    },
+    Migration {
+        version: 24,
+        description: "database maintenance job",
+        sql: "
+            -- Rebuilt to admit the new job kind in the CHECK constraint
+            CREATE TABLE job_runs_new (
+                id TEXT PRIMARY KEY,
+                job TEXT NOT NULL
+                    CHECK (job IN ('backup', 'overdue_scan', 'report_generation', 'database_maintenance')),
+                run_trigger TEXT NOT NULL CHECK (run_trigger IN ('scheduled', 'manual')),
+                triggered_by TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                completed_at TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                outcome TEXT NOT NULL CHECK (outcome IN ('succeeded', 'failed')),
+                detail TEXT
+            );
+            INSERT INTO job_runs_new SELECT * FROM job_runs;
+            DROP TABLE job_runs;
+            ALTER TABLE job_runs_new RENAME TO job_runs;
+            CREATE INDEX IF NOT EXISTS idx_job_runs_job ON job_runs(job, started_at);
+        ",
+    },
+    Migration {
+        version: 25,
+        description: "analytics export job",
+        sql: "
+            CREATE TABLE job_runs_new (
+                id TEXT PRIMARY KEY,
+                job TEXT NOT NULL CHECK (job IN ('backup', 'overdue_scan', 'report_generation',
+                                                 'database_maintenance', 'analytics_export')),
+                run_trigger TEXT NOT NULL CHECK (run_trigger IN ('scheduled', 'manual')),
+                triggered_by TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                completed_at TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                outcome TEXT NOT NULL CHECK (outcome IN ('succeeded', 'failed')),
+                detail TEXT
+            );
+            INSERT INTO job_runs_new SELECT * FROM job_runs;
+            DROP TABLE job_runs;
+            ALTER TABLE job_runs_new RENAME TO job_runs;
+            CREATE INDEX IF NOT EXISTS idx_job_runs_job ON job_runs(job, started_at);
+        ",
+    },
+    Migration {
+        version: 26,
+        description: "audit WORM sync job",
+        sql: "
+            CREATE TABLE job_runs_new (
+                id TEXT PRIMARY KEY,
+                job TEXT NOT NULL CHECK (job IN ('backup', 'overdue_scan', 'report_generation',
+                                                 'database_maintenance', 'analytics_export', 'audit_worm_sync')),
+                run_trigger TEXT NOT NULL CHECK (run_trigger IN ('scheduled', 'manual')),
+                triggered_by TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                completed_at TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                outcome TEXT NOT NULL CHECK (outcome IN ('succeeded', 'failed')),
+                detail TEXT
+            );
+            INSERT INTO job_runs_new SELECT * FROM job_runs;
+            DROP TABLE job_runs;
+            ALTER TABLE job_runs_new RENAME TO job_runs;
+            CREATE INDEX IF NOT EXISTS idx_job_runs_job ON job_runs(job, started_at);
+        ",
+    },
+    Migration {
+        version: 27,
+        description: "legal holds",
+        sql: "
+            -- record_id NULL holds every record of record_table
+            CREATE TABLE IF NOT EXISTS legal_holds (
+                id TEXT PRIMARY KEY,
+                record_table TEXT NOT NULL,
+                record_id TEXT,
+                reason TEXT NOT NULL,
+                placed_by TEXT NOT NULL,
+                placed_at TEXT NOT NULL,
+                released_by TEXT,
+                released_at TEXT,
+                release_reason TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_legal_holds_active
+                ON legal_holds(record_table, record_id) WHERE released_at IS NULL;
+        ",
+    },
+    Migration {
+        version: 28,
+        description: "mdr reporting clocks",
+        sql: "
+            CREATE TABLE IF NOT EXISTS mdr_clocks (
+                id TEXT PRIMARY KEY,
+                adverse_event_id TEXT NOT NULL REFERENCES adverse_events(id),
+                kind TEXT NOT NULL CHECK (kind IN ('five_day', 'thirty_day', 'supplemental')),
+                started_on TEXT NOT NULL,
+                due_on TEXT NOT NULL,
+                started_by TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                submitted_by TEXT,
+                submitted_at TEXT,
+                warned_at TEXT,
+                breach_notified_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_mdr_clocks_open ON mdr_clocks(due_on) WHERE submitted_at IS NULL;
+            CREATE INDEX IF NOT EXISTS idx_mdr_clocks_event ON mdr_clocks(adverse_event_id);
+        ",
+    },
+    Migration {
+        version: 29,
+        description: "clinical evaluations and literature review",
+        sql: "
+            CREATE TABLE IF NOT EXISTS clinical_evaluations (
+                id TEXT PRIMARY KEY,
+                device TEXT NOT NULL,
+                plan_reference TEXT NOT NULL,
+                report_reference TEXT,
+                revision INTEGER NOT NULL DEFAULT 0,
+                owner TEXT NOT NULL,
+                review_interval_months INTEGER NOT NULL CHECK (review_interval_months BETWEEN 1 AND 60),
+                last_approved_on TEXT,
+                next_update_due TEXT,
+                created_by TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS literature_searches (
+                id TEXT PRIMARY KEY,
+                evaluation_id TEXT NOT NULL REFERENCES clinical_evaluations(id),
+                databases TEXT NOT NULL,
+                search_terms TEXT NOT NULL,
+                inclusion_criteria TEXT NOT NULL,
+                exclusion_criteria TEXT NOT NULL,
+                executed_on TEXT NOT NULL,
+                executed_by TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_literature_searches_evaluation ON literature_searches(evaluation_id);
+            -- decision NULL until the reference is screened
+            CREATE TABLE IF NOT EXISTS literature_references (
+                id TEXT PRIMARY KEY,
+                search_id TEXT NOT NULL REFERENCES literature_searches(id),
+                citation TEXT NOT NULL,
+                decision TEXT CHECK (decision IN ('included', 'excluded')),
+                screening_note TEXT,
+                screened_by TEXT,
+                screened_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_literature_references_search ON literature_references(search_id);
+        ",
+    },
+    Migration {
+        version: 30,
+        description: "standards and regulation register",
+        sql: "
+            CREATE TABLE IF NOT EXISTS standards (
+                id TEXT PRIMARY KEY,
+                designation TEXT NOT NULL,
+                edition TEXT NOT NULL,
+                title TEXT NOT NULL,
+                kind TEXT NOT NULL CHECK (kind IN ('standard', 'regulation')),
+                created_by TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                UNIQUE (designation, edition)
+            );
+            CREATE TABLE IF NOT EXISTS standard_clauses (
+                id TEXT PRIMARY KEY,
+                standard_id TEXT NOT NULL REFERENCES standards(id),
+                clause TEXT NOT NULL,
+                title TEXT NOT NULL,
+                UNIQUE (standard_id, clause)
+            );
+            -- Append-only; the latest assessment per standard and product is current
+            CREATE TABLE IF NOT EXISTS standard_applicability (
+                id TEXT PRIMARY KEY,
+                standard_id TEXT NOT NULL REFERENCES standards(id),
+                product TEXT NOT NULL,
+                applicable INTEGER NOT NULL,
+                rationale TEXT NOT NULL,
+                assessed_by TEXT NOT NULL,
+                assessed_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_standard_applicability_product
+                ON standard_applicability(standard_id, product, assessed_at);
+            CREATE TABLE IF NOT EXISTS standard_gaps (
+                id TEXT PRIMARY KEY,
+                standard_id TEXT NOT NULL REFERENCES standards(id),
+                clause_id TEXT REFERENCES standard_clauses(id),
+                product TEXT NOT NULL,
+                description TEXT NOT NULL,
+                action_reference TEXT,
+                raised_by TEXT NOT NULL,
+                raised_at TEXT NOT NULL,
+                resolution TEXT,
+                closed_by TEXT,
+                closed_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_standard_gaps_open ON standard_gaps(raised_at) WHERE closed_at IS NULL;
+            -- Rebuilt to admit the 'satisfies' link type in the CHECK constraint
+            CREATE TABLE record_links_new (
+                id TEXT PRIMARY KEY,
+                source_type TEXT NOT NULL,
+                source_id TEXT NOT NULL,
+                target_type TEXT NOT NULL,
+                target_id TEXT NOT NULL,
+                link_type TEXT NOT NULL CHECK (
+                    link_type IN ('addresses', 'derived_from', 'controlled_by', 'affects', 'relates_to', 'satisfies')
+                ),
+                created_by TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                removed_by TEXT,
+                removed_at TEXT,
+                removal_reason TEXT
+            );
+            INSERT INTO record_links_new SELECT * FROM record_links;
+            DROP TABLE record_links;
+            ALTER TABLE record_links_new RENAME TO record_links;
+            CREATE INDEX IF NOT EXISTS idx_record_links_source ON record_links(source_type, source_id);
+            CREATE INDEX IF NOT EXISTS idx_record_links_target ON record_links(target_type, target_id);
+        ",
+    },
+    Migration {
+        version: 31,
+        description: "document section to clause mappings",
+        sql: "
+            CREATE TABLE IF NOT EXISTS clause_mappings (
+                id TEXT PRIMARY KEY,
+                document_id TEXT NOT NULL REFERENCES documents(id),
+                section TEXT NOT NULL,
+                clause_id TEXT NOT NULL REFERENCES standard_clauses(id),
+                mapped_by TEXT NOT NULL,
+                mapped_at TEXT NOT NULL,
+                UNIQUE (document_id, section, clause_id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_clause_mappings_clause ON clause_mappings(clause_id);
+        ",
+    },
+    Migration {
+        version: 32,
+        description: "sampled audit trail reviews",
+        sql: "
+            CREATE TABLE IF NOT EXISTS audit_trail_reviews (
+                id TEXT PRIMARY KEY,
+                period_start TEXT NOT NULL,
+                period_end TEXT NOT NULL,
+                strategy TEXT NOT NULL CHECK (strategy IN ('random', 'failures_first', 'per_user')),
+                sample_size INTEGER NOT NULL,
+                population INTEGER NOT NULL,
+                reviewer TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                completed_at TEXT,
+                conclusion TEXT,
+                UNIQUE (period_start, period_end)
+            );
+            -- disposition NULL until the reviewer has looked at the entry
+            CREATE TABLE IF NOT EXISTS audit_review_samples (
+                review_id TEXT NOT NULL REFERENCES audit_trail_reviews(id),
+                audit_entry_id TEXT NOT NULL REFERENCES audit_trail(id),
+                disposition TEXT CHECK (disposition IN ('acceptable', 'investigate')),
+                comment TEXT,
+                dispositioned_at TEXT,
+                PRIMARY KEY (review_id, audit_entry_id)
+            );
+        ",
+    },
+    Migration {
+        version: 33,
+        description: "access review campaigns",
+        sql: "
+            CREATE TABLE IF NOT EXISTS access_review_campaigns (
+                id TEXT PRIMARY KEY,
+                quarter TEXT NOT NULL UNIQUE,
+                opened_by TEXT NOT NULL,
+                opened_at TEXT NOT NULL,
+                due_date TEXT NOT NULL,
+                closed_by TEXT,
+                closed_at TEXT
+            );
+            -- site_id NULL is the organisation-wide role in users.role
+            CREATE TABLE IF NOT EXISTS access_review_items (
+                id TEXT PRIMARY KEY,
+                campaign_id TEXT NOT NULL REFERENCES access_review_campaigns(id),
+                user_id TEXT NOT NULL REFERENCES users(id),
+                username TEXT NOT NULL,
+                site_id TEXT,
+                role TEXT NOT NULL,
+                decision TEXT CHECK (decision IN ('attest', 'revoke')),
+                decided_by TEXT,
+                decided_at TEXT,
+                comment TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_access_review_items_campaign ON access_review_items(campaign_id);
+
+            CREATE TABLE job_runs_new (
+                id TEXT PRIMARY KEY,
+                job TEXT NOT NULL CHECK (job IN ('backup', 'overdue_scan', 'report_generation',
+                                                 'database_maintenance', 'analytics_export', 'audit_worm_sync',
+                                                 'access_review')),
+                run_trigger TEXT NOT NULL CHECK (run_trigger IN ('scheduled', 'manual')),
+                triggered_by TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                completed_at TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                outcome TEXT NOT NULL CHECK (outcome IN ('succeeded', 'failed')),
+                detail TEXT
+            );
+            INSERT INTO job_runs_new SELECT * FROM job_runs;
+            DROP TABLE job_runs;
+            ALTER TABLE job_runs_new RENAME TO job_runs;
+            CREATE INDEX IF NOT EXISTS idx_job_runs_job ON job_runs(job, started_at);
+        ",
+    },
+    Migration {
+        version: 34,
+        description: "storage samples",
+        sql: "
+            CREATE TABLE IF NOT EXISTS storage_samples (
+                id TEXT PRIMARY KEY,
+                sampled_at TEXT NOT NULL,
+                database_bytes INTEGER NOT NULL,
+                log_bytes INTEGER NOT NULL,
+                free_bytes INTEGER NOT NULL,
+                growth_bytes_per_day REAL,
+                days_until_full REAL,
+                level TEXT NOT NULL CHECK (level IN ('ok', 'warning', 'critical'))
+            );
+            CREATE INDEX IF NOT EXISTS idx_storage_samples_sampled_at ON storage_samples(sampled_at);
+
+            CREATE TABLE job_runs_new (
+                id TEXT PRIMARY KEY,
+                job TEXT NOT NULL CHECK (job IN ('backup', 'overdue_scan', 'report_generation',
+                                                 'database_maintenance', 'analytics_export', 'audit_worm_sync',
+                                                 'access_review', 'storage_monitor')),
+                run_trigger TEXT NOT NULL CHECK (run_trigger IN ('scheduled', 'manual')),
+                triggered_by TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                completed_at TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                outcome TEXT NOT NULL CHECK (outcome IN ('succeeded', 'failed')),
+                detail TEXT
+            );
+            INSERT INTO job_runs_new SELECT * FROM job_runs;
+            DROP TABLE job_runs;
+            ALTER TABLE job_runs_new RENAME TO job_runs;
+            CREATE INDEX IF NOT EXISTS idx_job_runs_job ON job_runs(job, started_at);
+        ",
+    },
+    Migration {
+        version: 35,
+        description: "issue tracker sync",
+        sql: "
+            -- Statuses as of the last sync tell which side changed since
+            CREATE TABLE IF NOT EXISTS issue_links (
+                action_id TEXT PRIMARY KEY REFERENCES capa_actions(id),
+                capa_id TEXT NOT NULL,
+                issue_key TEXT NOT NULL UNIQUE,
+                local_status TEXT NOT NULL,
+                remote_status TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                synced_at TEXT NOT NULL,
+                conflict TEXT
+            );
+            CREATE TABLE IF NOT EXISTS issue_comments (
+                id TEXT PRIMARY KEY,
+                action_id TEXT NOT NULL REFERENCES capa_actions(id),
+                issue_key TEXT NOT NULL,
+                remote_id TEXT NOT NULL,
+                author TEXT NOT NULL,
+                body TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                imported_at TEXT NOT NULL,
+                UNIQUE (issue_key, remote_id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_issue_comments_action ON issue_comments(action_id);
+
+            CREATE TABLE job_runs_new (
+                id TEXT PRIMARY KEY,
+                job TEXT NOT NULL CHECK (job IN ('backup', 'overdue_scan', 'report_generation',
+                                                 'database_maintenance', 'analytics_export', 'audit_worm_sync',
+                                                 'access_review', 'storage_monitor', 'issue_sync')),
+                run_trigger TEXT NOT NULL CHECK (run_trigger IN ('scheduled', 'manual')),
+                triggered_by TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                completed_at TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                outcome TEXT NOT NULL CHECK (outcome IN ('succeeded', 'failed')),
+                detail TEXT
+            );
+            INSERT INTO job_runs_new SELECT * FROM job_runs;
+            DROP TABLE job_runs;
+            ALTER TABLE job_runs_new RENAME TO job_runs;
+            CREATE INDEX IF NOT EXISTS idx_job_runs_job ON job_runs(job, started_at);
+        ",
+    },
+    Migration {
+        version: 36,
+        description: "LIMS test result batches",
+        sql: "
+            CREATE TABLE IF NOT EXISTS test_result_batches (
+                id TEXT PRIMARY KEY,
+                source_system TEXT NOT NULL,
+                format TEXT NOT NULL CHECK (format IN ('csv', 'json')),
+                target TEXT NOT NULL CHECK (target IN ('capa_verification', 'validation', 'incoming_inspection')),
+                record_id TEXT NOT NULL,
+                sha256 TEXT NOT NULL,
+                result_count INTEGER NOT NULL,
+                failed_count INTEGER NOT NULL,
+                ingested_by TEXT NOT NULL,
+                ingested_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_test_result_batches_record ON test_result_batches(target, record_id);
+            CREATE TABLE IF NOT EXISTS test_results (
+                id TEXT PRIMARY KEY,
+                batch_id TEXT NOT NULL REFERENCES test_result_batches(id),
+                sample_id TEXT NOT NULL,
+                test TEXT NOT NULL,
+                value TEXT,
+                unit TEXT,
+                lower_limit REAL,
+                upper_limit REAL,
+                expected TEXT,
+                reported_result TEXT,
+                passed INTEGER NOT NULL,
+                tested_at TEXT,
+                instrument TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_test_results_batch ON test_results(batch_id);
+        ",
+    },
+    Migration {
+        version: 37,
+        description: "email complaint intake",
+        sql: "
+            CREATE TABLE IF NOT EXISTS email_intake_messages (
+                id TEXT PRIMARY KEY,
+                message_id TEXT NOT NULL UNIQUE,
+                sender TEXT NOT NULL,
+                subject TEXT NOT NULL,
+                -- Subject without reply and forward prefixes, for duplicate detection
+                normalized_subject TEXT NOT NULL,
+                body_sha256 TEXT NOT NULL,
+                sent_at TEXT,
+                intake_id TEXT NOT NULL REFERENCES quality_intake(id),
+                duplicate INTEGER NOT NULL,
+                processed_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_email_intake_messages_thread
+                ON email_intake_messages(sender, normalized_subject, processed_at);
+            CREATE TABLE IF NOT EXISTS intake_attachments (
+                id TEXT PRIMARY KEY,
+                intake_id TEXT NOT NULL REFERENCES quality_intake(id),
+                message_id TEXT NOT NULL REFERENCES email_intake_messages(id),
+                file_name TEXT NOT NULL,
+                content_type TEXT NOT NULL,
+                relative_path TEXT NOT NULL,
+                sha256 TEXT NOT NULL,
+                size_bytes INTEGER NOT NULL,
+                stored_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_intake_attachments_intake ON intake_attachments(intake_id);
+
+            CREATE TABLE job_runs_new (
+                id TEXT PRIMARY KEY,
+                job TEXT NOT NULL CHECK (job IN ('backup', 'overdue_scan', 'report_generation',
+                                                 'database_maintenance', 'analytics_export', 'audit_worm_sync',
+                                                 'access_review', 'storage_monitor', 'issue_sync', 'email_intake')),
+                run_trigger TEXT NOT NULL CHECK (run_trigger IN ('scheduled', 'manual')),
+                triggered_by TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                completed_at TEXT NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                outcome TEXT NOT NULL CHECK (outcome IN ('succeeded', 'failed')),
+                detail TEXT
+            );
+            INSERT INTO job_runs_new SELECT * FROM job_runs;
+            DROP TABLE job_runs;
+            ALTER TABLE job_runs_new RENAME TO job_runs;
+            CREATE INDEX IF NOT EXISTS idx_job_runs_job ON job_runs(job, started_at);
+        ",
+    },
+    Migration {
+        version: 38,
+        description: "SPC rule violation alerts",
+        sql: "
+            CREATE TABLE IF NOT EXISTS spc_alerts (
+                id TEXT PRIMARY KEY,
+                chart TEXT NOT NULL,
+                kind TEXT NOT NULL CHECK (kind IN ('x_bar', 'range', 'p')),
+                point_label TEXT NOT NULL,
+                rule TEXT NOT NULL,
+                value REAL NOT NULL,
+                center REAL NOT NULL,
+                lcl REAL NOT NULL,
+                ucl REAL NOT NULL,
+                created_at TEXT NOT NULL,
+                UNIQUE (chart, kind, point_label, rule)
+            );
+        ",
+    },
+    Migration {
+        version: 39,
+        description: "quality issue cause categories",
+        sql: "
+            -- Defect or cause category counted in Pareto analyses
+            ALTER TABLE quality_intake ADD COLUMN cause_category TEXT;
+            CREATE INDEX IF NOT EXISTS idx_quality_intake_cause
+                ON quality_intake(reported_at, source, cause_category);
+        ",
+    },
+    Migration {
+        version: 40,
+        description: "cost of quality",
+        sql: "
+            -- Costs recorded against CAPAs, NCRs, recalls and scrap dispositions
+            CREATE TABLE IF NOT EXISTS quality_costs (
+                id TEXT PRIMARY KEY,
+                source TEXT NOT NULL CHECK (source IN ('capa', 'nonconformance', 'recall', 'scrap_disposition')),
+                record_id TEXT NOT NULL,
+                category TEXT NOT NULL
+                    CHECK (category IN ('prevention', 'appraisal', 'internal_failure', 'external_failure')),
+                amount_cents INTEGER NOT NULL CHECK (amount_cents > 0),
+                incurred_on TEXT NOT NULL,
+                description TEXT NOT NULL,
+                recorded_by TEXT NOT NULL,
+                recorded_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_quality_costs_record ON quality_costs(source, record_id);
+            CREATE INDEX IF NOT EXISTS idx_quality_costs_incurred ON quality_costs(incurred_on, category);
+        ",
+    },
+    Migration {
+        version: 41,
+        description: "unit shipments",
+        sql: "
+            -- Units shipped per calendar month (YYYY-MM), the denominator of complaint rates
+            CREATE TABLE IF NOT EXISTS unit_shipments (
+                month TEXT PRIMARY KEY CHECK (length(month) = 7),
+                units INTEGER NOT NULL CHECK (units >= 0),
+                recorded_by TEXT NOT NULL,
+                recorded_at TEXT NOT NULL
+            );
+        ",
+    },
+    Migration {
+        version: 42,
+        description: "supplier documents",
+        sql: "
+            -- Versions of supplier-provided certificates and agreements; files live in the file store
+            CREATE TABLE IF NOT EXISTS supplier_documents (
+                id TEXT PRIMARY KEY,
+                supplier_id TEXT NOT NULL REFERENCES suppliers(id),
+                kind TEXT NOT NULL
+                    CHECK (kind IN ('iso_certificate', 'quality_agreement', 'data_processing_agreement', 'other')),
+                title TEXT NOT NULL,
+                version INTEGER NOT NULL,
+                file_name TEXT NOT NULL,
+                relative_path TEXT NOT NULL,
+                sha256 TEXT NOT NULL,
+                size_bytes INTEGER NOT NULL,
+                expires_on TEXT,
+                uploaded_by TEXT NOT NULL,
+                uploaded_at TEXT NOT NULL,
+                superseded_at TEXT,
+                warned_at TEXT,
+                expiry_notified_at TEXT,
+                UNIQUE (supplier_id, kind, title, version)
+            );
+            CREATE INDEX IF NOT EXISTS idx_supplier_documents_expiry
+                ON supplier_documents(expires_on) WHERE superseded_at IS NULL;
+        ",
+    },
+    Migration {
+        version: 43,
+        description: "document effectivity by site and product",
+        sql: "
+            -- site_id '*' applies to every site, product '' to every product
+            CREATE TABLE IF NOT EXISTS document_effectivity (
+                document_id TEXT NOT NULL REFERENCES documents(id),
+                version TEXT NOT NULL,
+                site_id TEXT NOT NULL,
+                product TEXT NOT NULL DEFAULT '',
+                effective_from TEXT NOT NULL,
+                set_by TEXT NOT NULL,
+                set_at TEXT NOT NULL,
+                PRIMARY KEY (document_id, version, site_id, product)
+            );
+            CREATE INDEX IF NOT EXISTS idx_document_effectivity_site
+                ON document_effectivity(site_id, effective_from);
+        ",
+    },
+    Migration {
+        version: 44,
+        description: "controlled prints",
+        sql: "
+            -- Numbered paper copies of a document revision; open until returned or destroyed
+            CREATE TABLE IF NOT EXISTS controlled_prints (
+                id TEXT PRIMARY KEY,
+                document_id TEXT NOT NULL REFERENCES documents(id),
+                version TEXT NOT NULL,
+                copy_number INTEGER NOT NULL,
+                recipient TEXT NOT NULL,
+                issued_on TEXT NOT NULL,
+                issued_by TEXT NOT NULL,
+                issued_at TEXT NOT NULL,
+                disposition TEXT CHECK (disposition IN ('returned', 'destroyed')),
+                reconciled_on TEXT,
+                reconciled_by TEXT,
+                UNIQUE (document_id, version, copy_number)
+            );
+            CREATE INDEX IF NOT EXISTS idx_controlled_prints_open
+                ON controlled_prints(document_id) WHERE disposition IS NULL;
+        ",
+    },
+    Migration {
+        version: 45,
+        description: "ad hoc quality tasks",
+        sql: "
+            -- linked_record is a `type:id` reference to the record the work concerns
+            CREATE TABLE IF NOT EXISTS tasks (
+                id TEXT PRIMARY KEY,
+                title TEXT NOT NULL,
+                kind TEXT NOT NULL
+                    CHECK (kind IN ('investigation', 'review', 'verification', 'follow_up', 'other')),
+                due_date TEXT NOT NULL,
+                linked_record TEXT,
+                assignee TEXT NOT NULL REFERENCES users(id),
+                status TEXT NOT NULL CHECK (status IN ('open', 'completed')),
+                created_by TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                completed_by TEXT,
+                completed_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_tasks_assignee ON tasks(assignee, status, due_date);
+            CREATE INDEX IF NOT EXISTS idx_tasks_linked_record ON tasks(linked_record);
+        ",
+    },
+    Migration {
+        version: 46,
+        description: "persisted domain event log",
+        sql: "
+            -- payload is the serialized QmsEvent; sequence orders replays
+            CREATE TABLE IF NOT EXISTS events (
+                sequence INTEGER PRIMARY KEY AUTOINCREMENT,
+                id TEXT NOT NULL UNIQUE,
+                name TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                recorded_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_events_name ON events(name, sequence);
+        ",
+    },
+    Migration {
+        version: 47,
+        description: "crash recovery journal for multi-step workflows",
+        sql: "
+            CREATE TABLE IF NOT EXISTS workflow_journal (
+                id TEXT PRIMARY KEY,
+                kind TEXT NOT NULL,
+                subject TEXT NOT NULL,
+                started_by TEXT NOT NULL,
+                started_at TEXT NOT NULL,
+                status TEXT NOT NULL
+                    CHECK (status IN ('running', 'completed', 'rolled_back', 'resumed', 'abandoned')),
+                finished_at TEXT,
+                resolution TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_workflow_journal_status ON workflow_journal(status, started_at);
+            -- payload is the JSON a recovery handler needs to undo or continue the step
+            CREATE TABLE IF NOT EXISTS workflow_journal_steps (
+                sequence INTEGER PRIMARY KEY AUTOINCREMENT,
+                workflow_id TEXT NOT NULL REFERENCES workflow_journal(id),
+                name TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                recorded_at TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_workflow_journal_steps ON workflow_journal_steps(workflow_id, sequence);
+        ",
+    },
+    Migration {
+        version: 48,
+        description: "feature flag overrides",
+        sql: "
+            CREATE TABLE IF NOT EXISTS feature_flags (
+                feature TEXT PRIMARY KEY,
+                enabled INTEGER NOT NULL,
+                changed_by TEXT NOT NULL,
+                changed_at TEXT NOT NULL,
+                reason TEXT NOT NULL
+            );
+        ",
+    },
+    Migration {
+        version: 49,
+        description: "records of plugin-defined types",
+        sql: "
+            CREATE TABLE IF NOT EXISTS custom_records (
+                record_type TEXT NOT NULL,
+                id TEXT NOT NULL,
+                reference TEXT NOT NULL,
+                title TEXT NOT NULL,
+                status TEXT NOT NULL,
+                is_open INTEGER NOT NULL,
+                assignee TEXT,
+                due_date TEXT,
+                fields TEXT NOT NULL DEFAULT '{}',
+                created_by TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (record_type, id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_custom_records_assignee ON custom_records(assignee, is_open);
+        ",
+    },
+    Migration {
+        version: 50,
+        description: "validation script versions",
+        sql: "
+            CREATE TABLE IF NOT EXISTS validation_scripts (
+                document_id TEXT NOT NULL REFERENCES documents(id),
+                version INTEGER NOT NULL,
+                hook TEXT NOT NULL,
+                source TEXT NOT NULL,
+                content_hash TEXT NOT NULL,
+                created_by TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                effective_at TEXT,
+                effective_by TEXT,
+                PRIMARY KEY (document_id, version)
+            );
+            CREATE INDEX IF NOT EXISTS idx_validation_scripts_hook ON validation_scripts(hook, effective_at);
+        ",
+    },
+    Migration {
+        version: 51,
+        description: "document owners for periodic review",
+        sql: "
+            -- NULL means the author still owns the document
+            ALTER TABLE documents ADD COLUMN owner TEXT;
+        ",
+    },
+    Migration {
+        version: 52,
+        description: "approval delegations",
+        sql: "
+            CREATE TABLE IF NOT EXISTS approval_delegations (
+                id TEXT PRIMARY KEY,
+                delegator TEXT NOT NULL,
+                delegate TEXT NOT NULL,
+                starts_on TEXT NOT NULL,
+                ends_on TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                status TEXT NOT NULL CHECK (status IN ('pending', 'active', 'rejected', 'revoked')),
+                requested_by TEXT NOT NULL,
+                requested_at TEXT NOT NULL,
+                decided_by TEXT,
+                decided_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_approval_delegations_delegate
+                ON approval_delegations(delegate, status, starts_on);
+            -- Set when the approver signed as a delegate
+            ALTER TABLE documents ADD COLUMN approved_on_behalf_of TEXT;
+        ",
+    },
+    Migration {
+        version: 53,
+        description: "electronic batch records",
+        sql: "
+            CREATE TABLE IF NOT EXISTS batch_records (
+                id TEXT PRIMARY KEY,
+                lot_number TEXT NOT NULL UNIQUE,
+                product TEXT NOT NULL,
+                status TEXT NOT NULL CHECK (status IN ('open', 'released', 'rejected')),
+                opened_by TEXT NOT NULL,
+                opened_at TEXT NOT NULL,
+                dispositioned_by TEXT,
+                dispositioned_at TEXT,
+                disposition_note TEXT
+            );
+            -- One row per entry the master record requires; the value columns fill in as the lot is built
+            CREATE TABLE IF NOT EXISTS batch_record_entries (
+                id TEXT PRIMARY KEY,
+                batch_id TEXT NOT NULL REFERENCES batch_records(id),
+                position INTEGER NOT NULL,
+                step TEXT NOT NULL,
+                parameter TEXT NOT NULL,
+                unit TEXT,
+                lower_limit REAL,
+                upper_limit REAL,
+                expected TEXT,
+                required INTEGER NOT NULL,
+                second_person INTEGER NOT NULL,
+                value TEXT,
+                recorded_by TEXT,
+                recorded_at TEXT,
+                corrections INTEGER NOT NULL DEFAULT 0,
+                verified_by TEXT,
+                verified_at TEXT,
+                deviation TEXT,
+                UNIQUE (batch_id, step, parameter)
+            );
+            -- Reviewer dispositions of exceptions, cleared when the entry changes
+            CREATE TABLE IF NOT EXISTS batch_record_dispositions (
+                entry_id TEXT NOT NULL REFERENCES batch_record_entries(id),
+                kind TEXT NOT NULL,
+                comment TEXT NOT NULL,
+                reviewed_by TEXT NOT NULL,
+                reviewed_at TEXT NOT NULL,
+                PRIMARY KEY (entry_id, kind)
+            );
+        ",
+    },
+    Migration {
+        version: 54,
+        description: "row versions for CAPAs",
+        sql: "
+            ALTER TABLE capa_records ADD COLUMN row_version INTEGER NOT NULL DEFAULT 1;
+            ALTER TABLE capa_actions ADD COLUMN row_version INTEGER NOT NULL DEFAULT 1;
+        ",
+    },
+];
+
+/// Create the bookkeeping table that records applied migrations.
+fn ensure_migrations_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Highest migration version recorded in the database (0 when none applied).
+pub fn current_version(conn: &Connection) -> Result<u32> {
+    ensure_migrations_table(conn)?;
+    let version: Option<u32> = conn.query_row(
+        "SELECT MAX(version) FROM schema_migrations",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(version.unwrap_or(0))
+}
+
+/// Apply all pending migrations in version order.
+///
+/// Returns the versions applied by this call (empty when already up to date).
+pub fn apply_migrations(conn: &mut Connection) -> Result<Vec<u32>> {
+    let current = current_version(conn)?;
+    if let Some(latest) = MIGRATIONS.last() {
+        if current > latest.version {
+            return Err(QmsError::Database {
+                message: format!(
+                    "Database schema version {} is newer than supported version {}",
+                    current, latest.version
+                ),
+            });
+        }
+    }
+
+    let mut applied = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration.sql).map_err(|e| QmsError::Database {
+            message: format!("Migration {} ({}) failed: {}", migration.version, migration.description, e),
+        })?;
+        tx.execute(
+            "INSERT INTO schema_migrations (version, description, applied_at) VALUES (?1, ?2, ?3)",
+            params![migration.version, migration.description, Utc::now().to_rfc3339()],
+        )?;
+        tx.commit()?;
+
+        tracing::info!(
+            component = "database",
+            action = "schema_migration_applied",
+            version = migration.version,
+            description = migration.description,
+            "Applied schema migration"
+        );
+        applied.push(migration.version);
+    }
+
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::database::Database;
+
+    fn fresh_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    fn object_exists(db: &Database, kind: &str, name: &str) -> bool {
+        db.with_connection(|conn| {
+            let mut stmt = conn.prepare("SELECT 1 FROM sqlite_master WHERE type = ?1 AND name = ?2")?;
+            Ok(stmt.exists(params![kind, name])?)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_migration_versions_are_sequential() {
+        for (idx, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version as usize, idx + 1);
+            assert!(!migration.description.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_fresh_database_has_all_tables_and_indexes() {
+        let db = fresh_db();
+        for table in [
+            "training_records",
+            "suppliers",
+            "adverse_events",
+            "report_signatures",
+            "report_schedules",
+            "report_runs",
+            "change_history",
+            "record_versions",
+            "sites",
+            "site_roles",
+            "capa_summary",
+            "supplier_summary",
+            "training_summary",
+            "summary_refreshes",
+            "electronic_signatures",
+            "quality_events",
+            "quality_intake",
+            "validation_protocols",
+            "validation_evidence",
+            "validation_deviations",
+            "environmental_limits",
+            "environmental_readings",
+            "environmental_excursions",
+            "audit_findings",
+            "numbering_rules",
+            "numbering_sequences",
+            "record_links",
+            "escalation_rules",
+            "escalation_firings",
+            "kpi_targets",
+            "kpi_snapshots",
+            "schema_migrations",
+        ] {
+            assert!(object_exists(&db, "table", table), "{} table should exist", table);
+        }
+        for index in [
+            "idx_training_records_status",
+            "idx_training_records_employee",
+            "idx_suppliers_status",
+            "idx_adverse_events_reported_on",
+        ] {
+            assert!(object_exists(&db, "index", index), "{} index should exist", index);
+        }
+        assert_eq!(db.schema_version().unwrap(), MIGRATIONS.last().unwrap().version);
+    }
+
+    #[test]
+    fn test_apply_migrations_is_idempotent() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        Database::create_baseline_schema(&conn).unwrap();
+        let first = apply_migrations(&mut conn).unwrap();
+        let second = apply_migrations(&mut conn).unwrap();
+
+        assert_eq!(first.len(), MIGRATIONS.len());
+        assert!(second.is_empty());
+        assert_eq!(current_version(&conn).unwrap(), MIGRATIONS.len() as u32);
+    }
+
+    #[test]
+    fn test_training_foreign_key_enforced() {
+        let db = fresh_db();
+        let result = db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO training_records (id, employee_id, training_item, mandatory, assigned_by, due_date, status)
+                 VALUES ('t1', 'ghost', 'SOP-001', 1, 'ghost', '2025-01-01', 'Pending')",
+                [],
+            )?;
+            Ok(())
+        });
+        assert!(result.is_err(), "unknown employee must violate the foreign key");
+
+        db.seed_test_users(&["emp1", "mgr1"]);
+        let result = db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO training_records (id, employee_id, training_item, mandatory, assigned_by, due_date, status)
+                 VALUES ('t2', 'emp1', 'SOP-001', 1, 'mgr1', '2025-01-01', 'Pending')",
+                [],
+            )?;
+            Ok(())
+        });
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_adverse_event_severity_check() {
+        let db = fresh_db();
+        let result = db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO adverse_events (id, reported_on, reporter, description, severity)
+                 VALUES ('ae1', '2025-01-01T00:00:00Z', 'tester', 'bad', 9)",
+                [],
+            )?;
+            Ok(())
+        });
+        assert!(result.is_err(), "severity outside 0..=2 must be rejected");
+    }
+
+    #[test]
+    fn test_newer_schema_is_rejected() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        Database::create_baseline_schema(&conn).unwrap();
+        apply_migrations(&mut conn).unwrap();
+        conn.execute(
+            "INSERT INTO schema_migrations (version, description, applied_at) VALUES (999, 'future', 'now')",
+            [],
+        )
+        .unwrap();
+        assert!(apply_migrations(&mut conn).is_err());
+    }
+}
@@ -0,0 +1,439 @@
+//! # MIR Export - EU MDR Manufacturer Incident Reports
+//!
+//! Serious incidents with devices placed on the EU market are reported to
+//! the competent authorities on the Manufacturer Incident Report (MIR) form
+//! (MDR 2017/745 Art. 87, MDCG 2020-10). [`MirExporter`] fills the form from
+//! an adverse event and the incident details only the reporter knows (device
+//! identification, incident classification, country), validates the mandatory
+//! fields and renders either the form's XML structure or an Excel
+//! SpreadsheetML workbook with labels and dates in the configured locale.
+//! The awareness date is taken from the event's earliest MDR clock when one
+//! was started. Every export is recorded in the audit trail.
+
+use chrono::{Duration, NaiveDate, Utc};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::config::ApplicationConfig;
+use crate::database::Database;
+use crate::error::{Result, ValidationErrors};
+use crate::html_report::escape;
+use crate::i18n::{tr, Locale};
+use crate::post_market::AdverseEventRepo;
+
+/// MIR form version the export is structured after.
+pub const MIR_FORM_VERSION: &str = "7.3.1";
+
+/// Device risk classes under MDR Annex VIII.
+pub const RISK_CLASSES: [&str; 4] = ["I", "IIa", "IIb", "III"];
+
+/// Position of the report in the incident's reporting sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MirReportType {
+    Initial,
+    FollowUp,
+    Final,
+    InitialAndFinal,
+}
+
+impl MirReportType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MirReportType::Initial => "initial",
+            MirReportType::FollowUp => "follow_up",
+            MirReportType::Final => "final",
+            MirReportType::InitialAndFinal => "initial_and_final",
+        }
+    }
+}
+
+/// Incident classification, which sets the reporting deadline (Art. 87(3)-(5)).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MirIncidentType {
+    SeriousPublicHealthThreat,
+    Death,
+    SeriousDeterioration,
+    OtherSeriousIncident,
+}
+
+impl MirIncidentType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MirIncidentType::SeriousPublicHealthThreat => "serious_public_health_threat",
+            MirIncidentType::Death => "death",
+            MirIncidentType::SeriousDeterioration => "serious_deterioration",
+            MirIncidentType::OtherSeriousIncident => "other_serious_incident",
+        }
+    }
+
+    /// Calendar days from awareness to the report deadline.
+    pub fn reporting_days(&self) -> i64 {
+        match self {
+            MirIncidentType::SeriousPublicHealthThreat => 2,
+            MirIncidentType::Death | MirIncidentType::SeriousDeterioration => 10,
+            MirIncidentType::OtherSeriousIncident => 15,
+        }
+    }
+}
+
+/// Device involved in the incident.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirDevice {
+    pub name: String,
+    pub model: String,
+    #[serde(default)]
+    pub basic_udi_di: Option<String>,
+    #[serde(default)]
+    pub udi_di: Option<String>,
+    /// One of [`RISK_CLASSES`]
+    pub risk_class: String,
+    #[serde(default)]
+    pub notified_body_certificate: Option<String>,
+}
+
+/// Incident details supplied by the reporter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirDetails {
+    pub report_type: MirReportType,
+    pub incident_type: MirIncidentType,
+    /// ISO 3166-1 alpha-2 code of the country where the incident occurred
+    pub incident_country: String,
+    #[serde(default)]
+    pub incident_date: Option<NaiveDate>,
+    pub device: MirDevice,
+}
+
+/// A filled-in MIR form.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirReport {
+    pub adverse_event_id: Uuid,
+    pub manufacturer_name: String,
+    /// EUDAMED single registration number, e.g. `DE-MF-000012345`
+    pub srn: String,
+    pub report_date: NaiveDate,
+    pub awareness_date: NaiveDate,
+    pub severity: String,
+    pub description: String,
+    pub details: MirDetails,
+}
+
+impl MirReport {
+    pub fn reporting_deadline(&self) -> NaiveDate {
+        self.awareness_date + Duration::days(self.details.incident_type.reporting_days())
+    }
+
+    /// Check the mandatory fields, collecting every problem.
+    pub fn validate(&self) -> Result<()> {
+        let mut errors = ValidationErrors::new();
+        if self.manufacturer_name.trim().is_empty() {
+            errors.add("manufacturer_name", "Manufacturer name is required");
+        }
+        if !is_srn(&self.srn) {
+            errors.add("srn", "Single registration number must look like XX-MF-000000000");
+        }
+        let country = &self.details.incident_country;
+        if country.len() != 2 || !country.chars().all(|c| c.is_ascii_uppercase()) {
+            errors.add("incident_country", "Incident country must be an ISO 3166-1 alpha-2 code");
+        }
+        if self.description.trim().is_empty() {
+            errors.add("description", "Incident description is required");
+        }
+        if let Some(incident_date) = self.details.incident_date {
+            if incident_date > self.awareness_date {
+                errors.add("incident_date", "Incident date cannot be after the awareness date");
+            }
+        }
+        if self.awareness_date > self.report_date {
+            errors.add("awareness_date", "Awareness date cannot be after the report date");
+        }
+        let device = &self.details.device;
+        if device.name.trim().is_empty() {
+            errors.add("device.name", "Device name is required");
+        }
+        if device.model.trim().is_empty() {
+            errors.add("device.model", "Device model or catalogue number is required");
+        }
+        if device.basic_udi_di.as_deref().map_or(true, |v| v.trim().is_empty())
+            && device.udi_di.as_deref().map_or(true, |v| v.trim().is_empty())
+        {
+            errors.add("device.udi_di", "A UDI-DI or Basic UDI-DI is required");
+        }
+        if !RISK_CLASSES.contains(&device.risk_class.as_str()) {
+            errors.add("device.risk_class", format!("Risk class must be one of {}", RISK_CLASSES.join(", ")));
+        }
+        errors.into_result()
+    }
+
+    /// The form's XML structure; values use ISO 8601 dates and enum codes.
+    pub fn to_xml(&self, locale: Locale) -> String {
+        let device = &self.details.device;
+        let optional = |value: &Option<String>| value.as_deref().map(escape).unwrap_or_default();
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!("<mir formVersion=\"{}\" language=\"{}\">\n", MIR_FORM_VERSION, locale.code()));
+        xml.push_str("  <adminInfo>\n");
+        xml.push_str(&element(4, "reportType", self.details.report_type.as_str()));
+        xml.push_str(&element(4, "incidentType", self.details.incident_type.as_str()));
+        xml.push_str(&element(4, "reportDate", &self.report_date.to_string()));
+        xml.push_str(&element(4, "awarenessDate", &self.awareness_date.to_string()));
+        xml.push_str(&element(4, "reportingDeadline", &self.reporting_deadline().to_string()));
+        xml.push_str(&element(4, "manufacturerReference", &self.adverse_event_id.to_string()));
+        xml.push_str("  </adminInfo>\n  <manufacturer>\n");
+        xml.push_str(&element(4, "name", &escape(&self.manufacturer_name)));
+        xml.push_str(&element(4, "srn", &escape(&self.srn)));
+        xml.push_str("  </manufacturer>\n  <device>\n");
+        xml.push_str(&element(4, "name", &escape(&device.name)));
+        xml.push_str(&element(4, "model", &escape(&device.model)));
+        xml.push_str(&element(4, "basicUdiDi", &optional(&device.basic_udi_di)));
+        xml.push_str(&element(4, "udiDi", &optional(&device.udi_di)));
+        xml.push_str(&element(4, "riskClass", &escape(&device.risk_class)));
+        xml.push_str(&element(4, "nbCertificate", &optional(&device.notified_body_certificate)));
+        xml.push_str("  </device>\n  <incident>\n");
+        xml.push_str(&element(4, "country", &escape(&self.details.incident_country)));
+        xml.push_str(&element(
+            4,
+            "incidentDate",
+            &self.details.incident_date.map(|d| d.to_string()).unwrap_or_default(),
+        ));
+        xml.push_str(&element(4, "severity", &escape(&self.severity)));
+        xml.push_str(&element(4, "description", &escape(&self.description)));
+        xml.push_str("  </incident>\n</mir>\n");
+        xml
+    }
+
+    /// Excel 2003 SpreadsheetML workbook with one labelled row per field;
+    /// labels, classifications and dates follow `locale`.
+    pub fn to_spreadsheet_xml(&self, locale: Locale) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<?mso-application progid=\"Excel.Sheet\"?>\n\
+             <Workbook xmlns=\"urn:schemas-microsoft-com:office:spreadsheet\" \
+             xmlns:ss=\"urn:schemas-microsoft-com:office:spreadsheet\">\n",
+        );
+        xml.push_str(&format!("  <Worksheet ss:Name=\"MIR {}\">\n    <Table>\n", MIR_FORM_VERSION));
+        for (label, value) in self.rows(locale) {
+            xml.push_str(&format!(
+                "      <Row><Cell><Data ss:Type=\"String\">{}</Data></Cell>\
+                 <Cell><Data ss:Type=\"String\">{}</Data></Cell></Row>\n",
+                escape(label),
+                escape(&value)
+            ));
+        }
+        xml.push_str("    </Table>\n  </Worksheet>\n</Workbook>\n");
+        xml
+    }
+
+    fn rows(&self, locale: Locale) -> Vec<(&'static str, String)> {
+        let date = |d: NaiveDate| d.format(locale.date_format()).to_string();
+        let device = &self.details.device;
+        let report_type = format!("mir.report_type.{}", self.details.report_type.as_str());
+        let incident_type = format!("mir.incident_type.{}", self.details.incident_type.as_str());
+        vec![
+            (tr(locale, "mir.field.report_type"), tr(locale, &report_type).to_string()),
+            (tr(locale, "mir.field.incident_type"), tr(locale, &incident_type).to_string()),
+            (tr(locale, "mir.field.report_date"), date(self.report_date)),
+            (tr(locale, "mir.field.awareness_date"), date(self.awareness_date)),
+            (tr(locale, "mir.field.reporting_deadline"), date(self.reporting_deadline())),
+            (tr(locale, "mir.field.manufacturer_reference"), self.adverse_event_id.to_string()),
+            (tr(locale, "mir.field.manufacturer_name"), self.manufacturer_name.clone()),
+            (tr(locale, "mir.field.srn"), self.srn.clone()),
+            (tr(locale, "mir.field.device_name"), device.name.clone()),
+            (tr(locale, "mir.field.device_model"), device.model.clone()),
+            (tr(locale, "mir.field.basic_udi_di"), device.basic_udi_di.clone().unwrap_or_default()),
+            (tr(locale, "mir.field.udi_di"), device.udi_di.clone().unwrap_or_default()),
+            (tr(locale, "mir.field.risk_class"), device.risk_class.clone()),
+            (tr(locale, "mir.field.nb_certificate"), device.notified_body_certificate.clone().unwrap_or_default()),
+            (tr(locale, "mir.field.incident_country"), self.details.incident_country.clone()),
+            (tr(locale, "mir.field.incident_date"), self.details.incident_date.map(date).unwrap_or_default()),
+            (tr(locale, "mir.field.severity"), self.severity.clone()),
+            (tr(locale, "mir.field.description"), self.description.clone()),
+        ]
+    }
+}
+
+/// Output format of an export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MirFormat {
+    Xml,
+    Spreadsheet,
+}
+
+impl MirFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MirFormat::Xml => "xml",
+            MirFormat::Spreadsheet => "spreadsheet",
+        }
+    }
+}
+
+/// Fills MIR forms from adverse events.
+pub struct MirExporter<'a> {
+    db: &'a Database,
+}
+
+impl<'a> MirExporter<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// The form for `adverse_event_id` as of `report_date`, unvalidated.
+    pub fn build(
+        &self,
+        adverse_event_id: Uuid,
+        details: &MirDetails,
+        application: &ApplicationConfig,
+        report_date: NaiveDate,
+    ) -> Result<MirReport> {
+        let event = AdverseEventRepo::new(self.db).get(adverse_event_id)?;
+        let clock_start: Option<String> = self.db.with_connection(|conn| {
+            Ok(conn.query_row(
+                "SELECT MIN(started_on) FROM mdr_clocks WHERE adverse_event_id = ?1",
+                params![adverse_event_id.to_string()],
+                |row| row.get(0),
+            )?)
+        })?;
+        let awareness_date = clock_start
+            .and_then(|value| NaiveDate::parse_from_str(&value, "%Y-%m-%d").ok())
+            .unwrap_or_else(|| event.reported_on.date_naive());
+        Ok(MirReport {
+            adverse_event_id,
+            manufacturer_name: application.organization_name.clone(),
+            srn: application.eu_srn.clone().unwrap_or_default(),
+            report_date,
+            awareness_date,
+            severity: format!("{:?}", event.severity),
+            description: event.description,
+            details: details.clone(),
+        })
+    }
+
+    /// Validated form in `format`, exported on behalf of `user_id` and
+    /// recorded in the audit trail.
+    pub fn export(
+        &self,
+        adverse_event_id: Uuid,
+        details: &MirDetails,
+        application: &ApplicationConfig,
+        format: MirFormat,
+        user_id: &str,
+    ) -> Result<String> {
+        let report = self.build(adverse_event_id, details, application, Utc::now().date_naive())?;
+        report.validate()?;
+        let output = match format {
+            MirFormat::Xml => report.to_xml(application.locale),
+            MirFormat::Spreadsheet => report.to_spreadsheet_xml(application.locale),
+        };
+        let metadata = serde_json::json!({
+            "format": format.as_str(),
+            "report_type": details.report_type.as_str(),
+            "incident_type": details.incident_type.as_str(),
+            "reporting_deadline": report.reporting_deadline().to_string(),
+        });
+        AuditManager::new(self.db.clone()).log_action(
+            user_id,
+            "mir_exported",
+            &format!("adverse_event:{}", adverse_event_id),
+            "Success",
+            Some(metadata.to_string()),
+        )?;
+        Ok(output)
+    }
+}
+
+fn element(indent: usize, name: &str, value: &str) -> String {
+    format!("{:indent$}<{name}>{value}</{name}>\n", "", indent = indent, name = name, value = value)
+}
+
+/// `XX-MF-000000000`: country code, actor role and a nine-digit number.
+fn is_srn(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes.len() == 15
+        && bytes[..2].iter().all(u8::is_ascii_uppercase)
+        && &bytes[2..6] == b"-MF-"
+        && bytes[6..].iter().all(u8::is_ascii_digit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::error::QmsError;
+    use crate::mdr_clock::{MdrClockKind, MdrClockRepo};
+    use crate::post_market::{AdverseEvent, Severity};
+
+    fn details() -> MirDetails {
+        MirDetails {
+            report_type: MirReportType::Initial,
+            incident_type: MirIncidentType::SeriousDeterioration,
+            incident_country: "DE".to_string(),
+            incident_date: NaiveDate::from_ymd_opt(2026, 10, 1),
+            device: MirDevice {
+                name: "InfuMax Volumetric Pump".to_string(),
+                model: "IMX-200".to_string(),
+                basic_udi_di: Some("4260123456IMX2QX".to_string()),
+                udi_di: None,
+                risk_class: "IIb".to_string(),
+                notified_body_certificate: Some("NB 0123 MDR 4711".to_string()),
+            },
+        }
+    }
+
+    #[test]
+    fn test_mir_export_validates_and_formats_per_locale() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let event = AdverseEvent::new("clinic", "Over-infusion <10 mL> & alarm silent", Severity::Critical);
+        AdverseEventRepo::new(&db).insert(&event).unwrap();
+        let aware = NaiveDate::from_ymd_opt(2026, 10, 2).unwrap();
+        MdrClockRepo::new(&db).start(event.id, MdrClockKind::ThirtyDay, aware, "qa_manager").unwrap();
+
+        let mut application = ApplicationConfig::default();
+        let exporter = MirExporter::new(&db);
+        let report = exporter.build(event.id, &details(), &application, Utc::now().date_naive()).unwrap();
+        assert_eq!(report.awareness_date, aware, "awareness date comes from the MDR clock");
+        assert_eq!(report.reporting_deadline(), NaiveDate::from_ymd_opt(2026, 10, 12).unwrap());
+        let missing_srn = exporter.export(event.id, &details(), &application, MirFormat::Xml, "qa_manager");
+        assert!(matches!(missing_srn, Err(QmsError::ValidationErrors { errors }) if errors.get("srn").is_some()));
+
+        let mut invalid = report.clone();
+        invalid.srn = "DE-MF-000012345".to_string();
+        invalid.details.incident_country = "Germany".to_string();
+        invalid.details.device.basic_udi_di = None;
+        invalid.details.device.risk_class = "IV".to_string();
+        match invalid.validate() {
+            Err(QmsError::ValidationErrors { errors }) => {
+                let fields: Vec<&str> = errors.iter().map(|(field, _)| field).collect();
+                assert_eq!(fields, vec!["device.risk_class", "device.udi_di", "incident_country"]);
+            }
+            other => panic!("expected field errors, got {:?}", other),
+        }
+
+        application.eu_srn = Some("DE-MF-000012345".to_string());
+        let xml = exporter.export(event.id, &details(), &application, MirFormat::Xml, "qa_manager").unwrap();
+        assert!(xml.contains("<mir formVersion=\"7.3.1\" language=\"en\">"));
+        assert!(xml.contains("<awarenessDate>2026-10-02</awarenessDate>"));
+        assert!(xml.contains("<incidentType>serious_deterioration</incidentType>"));
+        assert!(xml.contains("<description>Over-infusion &lt;10 mL&gt; &amp; alarm silent</description>"));
+
+        application.locale = Locale::De;
+        let sheet = exporter.export(event.id, &details(), &application, MirFormat::Spreadsheet, "qa_manager").unwrap();
+        assert!(sheet.contains("<?mso-application progid=\"Excel.Sheet\"?>"));
+        assert!(sheet.contains(
+            "<Data ss:Type=\"String\">Datum der Kenntnisnahme</Data></Cell><Cell><Data ss:Type=\"String\">02.10.2026</Data>"
+        ));
+        assert!(sheet.contains("Unerwartete schwerwiegende Verschlechterung"));
+
+        let audit = db.get_audit_entries_for_resource(&format!("adverse_event:{}", event.id)).unwrap();
+        assert_eq!(audit.iter().filter(|e| e.action == "mir_exported").count(), 2);
+    }
+}
@@ -0,0 +1,257 @@
+//! # Network Policy - API Address Filtering & Mutual TLS
+//!
+//! The API can be limited to known networks with `[api.network]`: an
+//! allowlist of addresses or CIDR ranges the API answers (empty allows every
+//! address) and a denylist that is refused even when allowlisted. With
+//! `[api.tls]` the API is served over TLS and can require clients to present
+//! a certificate issued by a configured CA (mutual TLS). Refused requests
+//! and failed handshakes are written to the audit trail as security events.
+
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::net::IpAddr;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use tokio_rustls::rustls::server::{AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient};
+use tokio_rustls::rustls::{Certificate, PrivateKey, RootCertStore, ServerConfig};
+use tokio_rustls::TlsAcceptor;
+
+use crate::config::{ApiTlsConfig, NetworkPolicyConfig};
+use crate::error::{QmsError, Result};
+
+/// An address range in CIDR notation; a bare address is a single host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNetwork {
+    address: IpAddr,
+    prefix: u8,
+}
+
+impl IpNetwork {
+    /// Whether `ip` is inside the range. IPv4-mapped IPv6 addresses match
+    /// IPv4 ranges.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+            v4 => v4,
+        };
+        match (self.address, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                prefix_matches(u128::from(u32::from(net)), u128::from(u32::from(ip)), self.prefix, 32)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => prefix_matches(u128::from(net), u128::from(ip), self.prefix, 128),
+            _ => false,
+        }
+    }
+}
+
+fn prefix_matches(network: u128, ip: u128, prefix: u8, bits: u8) -> bool {
+    if prefix == 0 {
+        return true;
+    }
+    let shift = u32::from(bits - prefix);
+    network >> shift == ip >> shift
+}
+
+impl FromStr for IpNetwork {
+    type Err = QmsError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || QmsError::Validation {
+            field: "network".to_string(),
+            message: format!("'{}' is not an IP address or CIDR range", s),
+        };
+        let (address, prefix) = match s.trim().split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (s.trim(), None),
+        };
+        let address: IpAddr = address.parse().map_err(|_| invalid())?;
+        let max = if address.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse::<u8>().ok().filter(|p| *p <= max).ok_or_else(invalid)?,
+            None => max,
+        };
+        Ok(Self { address, prefix })
+    }
+}
+
+impl fmt::Display for IpNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.address, self.prefix)
+    }
+}
+
+/// Parsed `[api.network]` rules.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetworkPolicy {
+    allow: Vec<IpNetwork>,
+    deny: Vec<IpNetwork>,
+}
+
+impl NetworkPolicy {
+    pub fn from_config(config: &NetworkPolicyConfig) -> Result<Self> {
+        let parse = |field: &str, entries: &[String]| {
+            entries
+                .iter()
+                .map(|entry| {
+                    entry.parse::<IpNetwork>().map_err(|e| match e {
+                        QmsError::Validation { message, .. } => {
+                            QmsError::Validation { field: field.to_string(), message }
+                        }
+                        other => other,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(Self {
+            allow: parse("api.network.allowlist", &config.allowlist)?,
+            deny: parse("api.network.denylist", &config.denylist)?,
+        })
+    }
+
+    /// Whether any address is refused.
+    pub fn is_restricted(&self) -> bool {
+        !self.allow.is_empty() || !self.deny.is_empty()
+    }
+
+    /// Check a peer address; the error is the reason for refusing it. An
+    /// unknown peer is refused whenever the policy restricts addresses.
+    pub fn check(&self, peer: Option<IpAddr>) -> std::result::Result<(), String> {
+        if !self.is_restricted() {
+            return Ok(());
+        }
+        let Some(ip) = peer else {
+            return Err("peer address unknown".to_string());
+        };
+        if let Some(network) = self.deny.iter().find(|network| network.contains(ip)) {
+            return Err(format!("{} is in denied range {}", ip, network));
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(|network| network.contains(ip)) {
+            return Err(format!("{} is not in an allowed range", ip));
+        }
+        Ok(())
+    }
+}
+
+/// TLS acceptor for `[api.tls]`; client certificates are verified against
+/// `client_ca_path` when it is set, and required with `require_client_cert`.
+pub fn tls_acceptor(config: &ApiTlsConfig) -> Result<TlsAcceptor> {
+    let certs = load_certificates(&config.cert_path)?;
+    let key = load_private_key(&config.key_path)?;
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let builder = match &config.client_ca_path {
+        Some(ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certificates(ca_path)? {
+                roots.add(&cert).map_err(|e| tls_error(ca_path, e.to_string()))?;
+            }
+            if config.require_client_cert {
+                builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+            } else {
+                builder.with_client_cert_verifier(AllowAnyAnonymousOrAuthenticatedClient::new(roots).boxed())
+            }
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let server_config =
+        builder.with_single_cert(certs, key).map_err(|e| tls_error(&config.cert_path, e.to_string()))?;
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+fn load_certificates(path: &str) -> Result<Vec<Certificate>> {
+    let mut reader = BufReader::new(open(path)?);
+    let certs = rustls_pemfile::certs(&mut reader).map_err(|e| tls_error(path, e.to_string()))?;
+    if certs.is_empty() {
+        return Err(tls_error(path, "no PEM certificates found".to_string()));
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+fn load_private_key(path: &str) -> Result<PrivateKey> {
+    let mut reader = BufReader::new(open(path)?);
+    for item in rustls_pemfile::read_all(&mut reader).map_err(|e| tls_error(path, e.to_string()))? {
+        if let rustls_pemfile::Item::PKCS8Key(key)
+        | rustls_pemfile::Item::RSAKey(key)
+        | rustls_pemfile::Item::ECKey(key) = item
+        {
+            return Ok(PrivateKey(key));
+        }
+    }
+    Err(tls_error(path, "no PEM private key found".to_string()))
+}
+
+fn open(path: &str) -> Result<File> {
+    File::open(Path::new(path)).map_err(|e| QmsError::FileSystem { path: path.to_string(), message: e.to_string() })
+}
+
+fn tls_error(path: &str, message: String) -> QmsError {
+    QmsError::Configuration { message: format!("TLS material {}: {}", path, message) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(allowlist: &[&str], denylist: &[&str]) -> NetworkPolicy {
+        NetworkPolicy::from_config(&NetworkPolicyConfig {
+            allowlist: allowlist.iter().map(|s| s.to_string()).collect(),
+            denylist: denylist.iter().map(|s| s.to_string()).collect(),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_network_ranges() {
+        let lan: IpNetwork = "10.20.0.0/16".parse().unwrap();
+        assert!(lan.contains("10.20.255.1".parse().unwrap()));
+        assert!(!lan.contains("10.21.0.1".parse().unwrap()));
+        assert!(lan.contains("::ffff:10.20.0.9".parse().unwrap()), "IPv4-mapped IPv6");
+        let host: IpNetwork = "2001:db8::1".parse().unwrap();
+        assert_eq!(host.to_string(), "2001:db8::1/128");
+        assert!(host.contains("2001:db8::1".parse().unwrap()));
+        assert!(!host.contains("2001:db8::2".parse().unwrap()));
+        assert!("0.0.0.0/0".parse::<IpNetwork>().unwrap().contains("203.0.113.7".parse().unwrap()));
+        assert!("10.0.0.0/33".parse::<IpNetwork>().is_err());
+        assert!("intranet".parse::<IpNetwork>().is_err());
+    }
+
+    #[test]
+    fn test_allowlist_and_denylist() {
+        let open = policy(&[], &[]);
+        assert!(open.check(None).is_ok(), "no rules, no restriction");
+
+        let restricted = policy(&["10.0.0.0/8", "127.0.0.1"], &["10.66.0.0/16"]);
+        assert!(restricted.check(Some("10.1.2.3".parse().unwrap())).is_ok());
+        assert!(restricted.check(Some("127.0.0.1".parse().unwrap())).is_ok());
+        assert!(restricted.check(Some("192.168.1.5".parse().unwrap())).unwrap_err().contains("not in an allowed"));
+        assert!(restricted.check(Some("10.66.1.1".parse().unwrap())).unwrap_err().contains("denied range"));
+        assert!(restricted.check(None).is_err(), "unknown peers are refused");
+
+        let deny_only = policy(&[], &["198.51.100.0/24"]);
+        assert!(deny_only.check(Some("203.0.113.1".parse().unwrap())).is_ok());
+        assert!(deny_only.check(Some("198.51.100.20".parse().unwrap())).is_err());
+
+        let err = NetworkPolicy::from_config(&NetworkPolicyConfig {
+            allowlist: vec![],
+            denylist: vec!["10.0.0.0/40".to_string()],
+        })
+        .unwrap_err();
+        assert!(matches!(err, QmsError::Validation { field, .. } if field == "api.network.denylist"));
+    }
+
+    #[test]
+    fn test_missing_tls_material_is_reported() {
+        let config = ApiTlsConfig {
+            enabled: true,
+            cert_path: "/nonexistent/api.crt".to_string(),
+            key_path: "/nonexistent/api.key".to_string(),
+            client_ca_path: None,
+            require_client_cert: false,
+        };
+        assert!(
+            matches!(tls_acceptor(&config), Err(QmsError::FileSystem { path, .. }) if path == "/nonexistent/api.crt")
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! # Notifications - Email Distribution
+//!
+//! Outgoing messages (e.g. scheduled report distribution) go through the
+//! [`Notifier`] trait. [`OutboxNotifier`] writes RFC 5322 / MIME messages into
+//! a spool directory that the site mail transfer agent delivers, so the QMS
+//! host needs no SMTP credentials and every sent message is retained on disk.
+
+use base64::{engine::general_purpose, Engine as _};
+use chrono::Utc;
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::config::NotificationConfig;
+use crate::error::{QmsError, Result};
+
+/// Email to be delivered to a distribution list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmailMessage {
+    pub to: Vec<String>,
+    pub subject: String,
+    pub body: String,
+    /// Files attached as `application/octet-stream` (PDF for `.pdf` files)
+    pub attachments: Vec<PathBuf>,
+}
+
+impl EmailMessage {
+    /// Create a plain-text message without attachments.
+    pub fn new<S: Into<String>>(to: Vec<String>, subject: S, body: S) -> Self {
+        Self { to, subject: subject.into(), body: body.into(), attachments: Vec::new() }
+    }
+
+    /// Attach a file.
+    pub fn with_attachment<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.attachments.push(path.into());
+        self
+    }
+
+    /// Ensure the message has at least one plausible recipient.
+    pub fn validate(&self) -> Result<()> {
+        if self.to.is_empty() {
+            return Err(QmsError::Validation {
+                field: "to".to_string(),
+                message: "At least one recipient is required".to_string(),
+            });
+        }
+        if let Some(bad) = self.to.iter().find(|addr| !is_valid_address(addr)) {
+            return Err(QmsError::Validation {
+                field: "to".to_string(),
+                message: format!("Invalid email address: {}", bad),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Minimal syntactic check: `local@domain` without whitespace or header breaks.
+pub fn is_valid_address(address: &str) -> bool {
+    match address.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !address.chars().any(|c| c.is_whitespace() || matches!(c, ',' | '<' | '>'))
+        }
+        None => false,
+    }
+}
+
+/// Delivery channel for outgoing messages.
+pub trait Notifier: Send + Sync {
+    /// Queue `message` for delivery and return its message identifier.
+    fn send(&self, message: &EmailMessage) -> Result<String>;
+}
+
+/// Notifier that spools MIME messages as `.eml` files.
+#[derive(Debug, Clone)]
+pub struct OutboxNotifier {
+    outbox: PathBuf,
+    from_address: String,
+}
+
+impl OutboxNotifier {
+    pub fn new<P: Into<PathBuf>, S: Into<String>>(outbox: P, from_address: S) -> Self {
+        Self { outbox: outbox.into(), from_address: from_address.into() }
+    }
+
+    /// Notifier from configuration; `None` when notifications are disabled.
+    pub fn from_config(config: &NotificationConfig) -> Option<Self> {
+        config
+            .enabled
+            .then(|| Self::new(&config.outbox_directory, config.from_address.clone()))
+    }
+
+    /// Spool directory.
+    pub fn outbox(&self) -> &Path {
+        &self.outbox
+    }
+
+    /// Render `message` as a MIME document.
+    pub fn render(&self, message: &EmailMessage, message_id: &str) -> Result<String> {
+        let boundary = format!("qmsrs-{}", Uuid::new_v4().simple());
+        let mut out = String::new();
+        out.push_str(&format!("From: {}\r\n", header_value(&self.from_address)));
+        out.push_str(&format!("To: {}\r\n", message.to.iter().map(|t| header_value(t)).collect::<Vec<_>>().join(", ")));
+        out.push_str(&format!("Subject: {}\r\n", header_value(&message.subject)));
+        out.push_str(&format!("Date: {}\r\n", Utc::now().to_rfc2822()));
+        out.push_str(&format!("Message-ID: <{}>\r\n", message_id));
+        out.push_str("MIME-Version: 1.0\r\n");
+        out.push_str(&format!("Content-Type: multipart/mixed; boundary=\"{}\"\r\n\r\n", boundary));
+
+        out.push_str(&format!("--{}\r\n", boundary));
+        out.push_str("Content-Type: text/plain; charset=utf-8\r\n");
+        out.push_str("Content-Transfer-Encoding: 8bit\r\n\r\n");
+        out.push_str(&message.body.replace('\n', "\r\n"));
+        out.push_str("\r\n");
+
+        for path in &message.attachments {
+            let bytes = fs::read(path).map_err(|e| QmsError::FileSystem {
+                path: path.display().to_string(),
+                message: e.to_string(),
+            })?;
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("attachment");
+            let content_type = if name.ends_with(".pdf") { "application/pdf" } else { "application/octet-stream" };
+            out.push_str(&format!("--{}\r\n", boundary));
+            out.push_str(&format!("Content-Type: {}; name=\"{}\"\r\n", content_type, header_value(name)));
+            out.push_str("Content-Transfer-Encoding: base64\r\n");
+            out.push_str(&format!("Content-Disposition: attachment; filename=\"{}\"\r\n\r\n", header_value(name)));
+            let encoded = general_purpose::STANDARD.encode(&bytes);
+            for chunk in encoded.as_bytes().chunks(76) {
+                out.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+                out.push_str("\r\n");
+            }
+        }
+        out.push_str(&format!("--{}--\r\n", boundary));
+        Ok(out)
+    }
+}
+
+impl Notifier for OutboxNotifier {
+    fn send(&self, message: &EmailMessage) -> Result<String> {
+        message.validate()?;
+        let id = Uuid::new_v4();
+        let domain = self.from_address.split_once('@').map(|(_, d)| d).unwrap_or("localhost");
+        let message_id = format!("{}@{}", id, domain);
+        let rendered = self.render(message, &message_id)?;
+
+        fs::create_dir_all(&self.outbox).map_err(|e| QmsError::FileSystem {
+            path: self.outbox.display().to_string(),
+            message: e.to_string(),
+        })?;
+        // Write under a temporary name so the MTA never picks up a partial file.
+        let tmp_path = self.outbox.join(format!("{}.tmp", id));
+        let path = self.outbox.join(format!("{}.eml", id));
+        fs::write(&tmp_path, rendered)
+            .and_then(|_| fs::rename(&tmp_path, &path))
+            .map_err(|e| QmsError::FileSystem {
+                path: path.display().to_string(),
+                message: e.to_string(),
+            })?;
+
+        tracing::info!(
+            component = "notification",
+            action = "email_queued",
+            message_id = %message_id,
+            recipients = message.to.len(),
+            "Queued email notification"
+        );
+        Ok(message_id)
+    }
+}
+
+/// Strip CR/LF so values cannot inject additional headers.
+fn header_value(value: &str) -> String {
+    value.chars().filter(|c| !matches!(c, '\r' | '\n')).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_address_validation() {
+        assert!(is_valid_address("qa@example.com"));
+        assert!(!is_valid_address("qa.example.com"));
+        assert!(!is_valid_address("qa@localhost"));
+        assert!(!is_valid_address("qa@example.com\r\nBcc: x@y.z"));
+
+        let message = EmailMessage::new(vec!["bad".to_string()], "Subject", "Body");
+        assert!(matches!(message.validate(), Err(QmsError::Validation { .. })));
+    }
+
+    #[test]
+    fn test_outbox_writes_mime_with_attachment() {
+        let dir = tempdir().unwrap();
+        let attachment = dir.path().join("summary.pdf");
+        fs::write(&attachment, b"%PDF-1.4 test").unwrap();
+        let notifier = OutboxNotifier::new(dir.path().join("outbox"), "qms@example.com");
+
+        let message = EmailMessage::new(
+            vec!["qa@example.com".to_string(), "ceo@example.com".to_string()],
+            "Monthly Compliance Summary\r\nBcc: evil@example.com",
+            "Attached.",
+        )
+        .with_attachment(&attachment);
+        let message_id = notifier.send(&message).unwrap();
+        assert!(message_id.ends_with("@example.com"));
+
+        let files: Vec<_> = fs::read_dir(notifier.outbox()).unwrap().map(|e| e.unwrap().path()).collect();
+        assert_eq!(files.len(), 1);
+        let eml = fs::read_to_string(&files[0]).unwrap();
+        assert!(eml.contains("To: qa@example.com, ceo@example.com\r\n"));
+        assert!(eml.contains("Subject: Monthly Compliance SummaryBcc: evil@example.com\r\n"));
+        assert!(eml.contains("Content-Type: application/pdf; name=\"summary.pdf\""));
+        assert!(eml.contains(&general_purpose::STANDARD.encode(b"%PDF-1.4 test")));
+    }
+
+    #[test]
+    fn test_disabled_config_has_no_notifier() {
+        assert!(OutboxNotifier::from_config(&NotificationConfig::default()).is_none());
+    }
+}
@@ -0,0 +1,287 @@
+//! # Numbering - Controlled Record Number Allocation
+//!
+//! Record numbers such as `SOP-BER-007` or `CAPA-2025-0012` are generated
+//! from a [`NumberingRule`] per scope (one scope per [`DocumentType`], CAPAs
+//! and each quality intake source) instead of being typed by hand, so numbers
+//! stay unique and consistently formatted (21 CFR 820.40, ISO 13485 §4.2.4).
+//! UUIDs remain the internal keys; numbers are what people read. Sequences live
+//! in `numbering_sequences` and are advanced by a single upsert, which SQLite
+//! executes atomically even with several writers.
+
+use chrono::{DateTime, Datelike, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::document::DocumentType;
+use crate::error::{QmsError, Result};
+
+/// Scope of CAPA numbers.
+pub const CAPA_SCOPE: &str = "capa";
+/// Scope of nonconformance report numbers.
+pub const NCR_SCOPE: &str = "ncr";
+
+/// Built-in `(scope, prefix, padding)` of yearly record numbers.
+const YEARLY_DEFAULTS: [(&str, &str, u8); 5] = [
+    (CAPA_SCOPE, "CAPA", 4),
+    (NCR_SCOPE, "NCR", 3),
+    ("complaint", "CMP", 3),
+    ("audit_finding", "AF", 3),
+    ("internal_observation", "OBS", 3),
+];
+
+/// Format of the numbers allocated in one scope.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NumberingRule {
+    /// Record family, e.g. `document:SOP`
+    pub scope: String,
+    pub prefix: String,
+    /// Minimum digits of the zero-padded sequence
+    pub padding: u8,
+    /// Insert the site code and keep a separate sequence per site
+    pub include_site: bool,
+    /// Insert the year and restart the sequence every year
+    pub include_year: bool,
+}
+
+impl NumberingRule {
+    pub fn document_scope(document_type: &DocumentType) -> String {
+        format!("document:{}", document_type.prefix())
+    }
+
+    /// Rule used for a scope nobody configured: documents number per site,
+    /// CAPAs and intake records per year.
+    pub fn default_for(scope: &str) -> Self {
+        if let Some((_, prefix, padding)) = YEARLY_DEFAULTS.iter().find(|(s, _, _)| *s == scope) {
+            return Self {
+                scope: scope.to_string(),
+                prefix: prefix.to_string(),
+                padding: *padding,
+                include_site: false,
+                include_year: true,
+            };
+        }
+        let prefix = scope.rsplit(':').next().unwrap_or(scope).to_uppercase();
+        Self { scope: scope.to_string(), prefix, padding: 3, include_site: scope.starts_with("document:"), include_year: false }
+    }
+
+    /// Sequence partition: numbering restarts per site and/or year.
+    fn partition(&self, site_id: &str, year: i32) -> String {
+        let mut parts = Vec::new();
+        if self.include_site {
+            parts.push(site_code(site_id));
+        }
+        if self.include_year {
+            parts.push(year.to_string());
+        }
+        parts.join("-")
+    }
+
+    /// Number `sequence` formatted for `site_id` and `year`.
+    pub fn format(&self, site_id: &str, year: i32, sequence: u64) -> String {
+        let partition = self.partition(site_id, year);
+        let sequence = format!("{:0width$}", sequence, width = self.padding as usize);
+        if partition.is_empty() {
+            format!("{}-{}", self.prefix, sequence)
+        } else {
+            format!("{}-{}-{}", self.prefix, partition, sequence)
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let valid_prefix = !self.prefix.is_empty() && self.prefix.chars().all(|c| c.is_ascii_alphanumeric());
+        if !valid_prefix {
+            return Err(QmsError::Validation {
+                field: "prefix".to_string(),
+                message: format!("Prefix must be non-empty and alphanumeric, got '{}'", self.prefix),
+            });
+        }
+        if !(1..=10).contains(&self.padding) {
+            return Err(QmsError::Validation {
+                field: "padding".to_string(),
+                message: format!("Padding must be between 1 and 10 digits, got {}", self.padding),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Site code printed in numbers: the site id in upper case.
+fn site_code(site_id: &str) -> String {
+    site_id.to_uppercase()
+}
+
+/// Allocate the next number of `rule` on `conn`, e.g. inside a unit of work
+/// that also inserts the numbered record.
+pub fn allocate_with(conn: &Connection, rule: &NumberingRule, site_id: &str, now: DateTime<Utc>) -> Result<String> {
+    let year = now.year();
+    let sequence: u64 = conn.query_row(
+        "INSERT INTO numbering_sequences (scope, partition, last_value) VALUES (?1, ?2, 1)
+         ON CONFLICT(scope, partition) DO UPDATE SET last_value = last_value + 1
+         RETURNING last_value",
+        params![rule.scope, rule.partition(site_id, year)],
+        |row| row.get(0),
+    )?;
+    Ok(rule.format(site_id, year, sequence))
+}
+
+/// Numbering rules and allocation.
+pub struct NumberingRepo<'a> {
+    db: &'a Database,
+}
+
+impl<'a> NumberingRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Configure the numbering rule of a scope. Existing numbers are kept;
+    /// sequences continue from their last value.
+    pub fn set_rule(&self, rule: &NumberingRule, updated_by: &str) -> Result<()> {
+        rule.validate()?;
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "INSERT INTO numbering_rules (scope, prefix, padding, include_site, include_year, updated_by, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                 ON CONFLICT(scope) DO UPDATE SET
+                     prefix = excluded.prefix, padding = excluded.padding, include_site = excluded.include_site,
+                     include_year = excluded.include_year, updated_by = excluded.updated_by,
+                     updated_at = excluded.updated_at",
+                params![
+                    rule.scope,
+                    rule.prefix,
+                    rule.padding,
+                    rule.include_site,
+                    rule.include_year,
+                    updated_by,
+                    Utc::now().to_rfc3339(),
+                ],
+            )?;
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                updated_by,
+                "numbering_rule_set",
+                &format!("numbering_rule:{}", rule.scope),
+                "Success",
+                Some(serde_json::to_string(rule)?),
+            )
+        })
+    }
+
+    /// Configured rule of `scope`, or its default.
+    pub fn rule(&self, scope: &str) -> Result<NumberingRule> {
+        let configured = self.db.with_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT scope, prefix, padding, include_site, include_year FROM numbering_rules WHERE scope = ?1",
+                    params![scope],
+                    |row| {
+                        Ok(NumberingRule {
+                            scope: row.get(0)?,
+                            prefix: row.get(1)?,
+                            padding: row.get(2)?,
+                            include_site: row.get(3)?,
+                            include_year: row.get(4)?,
+                        })
+                    },
+                )
+                .optional()?)
+        })?;
+        Ok(configured.unwrap_or_else(|| NumberingRule::default_for(scope)))
+    }
+
+    /// Allocate the next number of `scope` at `site_id`.
+    pub fn next_number(&self, scope: &str, site_id: &str) -> Result<String> {
+        let rule = self.rule(scope)?;
+        self.db.unit_of_work(|uow| allocate_with(uow.connection(), &rule, site_id, Utc::now()))
+    }
+
+    /// Allocate the next document number for `document_type` at `site_id`.
+    pub fn next_document_number(&self, document_type: &DocumentType, site_id: &str) -> Result<String> {
+        self.next_number(&NumberingRule::document_scope(document_type), site_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::site::DEFAULT_SITE_ID;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_document_numbers_per_type_and_site() {
+        let db = test_db();
+        let repo = NumberingRepo::new(&db);
+        assert_eq!(repo.next_document_number(&DocumentType::SOP, "ber").unwrap(), "SOP-BER-001");
+        assert_eq!(repo.next_document_number(&DocumentType::SOP, "ber").unwrap(), "SOP-BER-002");
+        assert_eq!(repo.next_document_number(&DocumentType::SOP, "bos").unwrap(), "SOP-BOS-001");
+        assert_eq!(repo.next_document_number(&DocumentType::WorkInstruction, "ber").unwrap(), "WI-BER-001");
+    }
+
+    #[test]
+    fn test_configured_rule_formats_numbers() {
+        let db = test_db();
+        let repo = NumberingRepo::new(&db);
+        let scope = NumberingRule::document_scope(&DocumentType::Form);
+        let rule = NumberingRule { scope: scope.clone(), prefix: "F".to_string(), padding: 5, include_site: false, include_year: true };
+        assert!(repo.set_rule(&NumberingRule { prefix: "F-1".to_string(), ..rule.clone() }, "admin").is_err());
+        assert!(repo.set_rule(&NumberingRule { padding: 0, ..rule.clone() }, "admin").is_err());
+        repo.set_rule(&rule, "admin").unwrap();
+
+        let year = Utc::now().year();
+        assert_eq!(repo.next_number(&scope, DEFAULT_SITE_ID).unwrap(), format!("F-{}-00001", year));
+        assert_eq!(repo.rule(&scope).unwrap(), rule);
+        assert_eq!(rule.format("ber", 2025, 123_456), "F-2025-123456");
+    }
+
+    #[test]
+    fn test_yearly_record_numbers() {
+        let db = test_db();
+        let repo = NumberingRepo::new(&db);
+        let year = Utc::now().year();
+        assert_eq!(repo.next_number(CAPA_SCOPE, "ber").unwrap(), format!("CAPA-{}-0001", year));
+        assert_eq!(repo.next_number(CAPA_SCOPE, "bos").unwrap(), format!("CAPA-{}-0002", year));
+        assert_eq!(repo.next_number(NCR_SCOPE, "ber").unwrap(), format!("NCR-{}-001", year));
+        assert_eq!(NumberingRule::default_for(CAPA_SCOPE).format(DEFAULT_SITE_ID, 2025, 12), "CAPA-2025-0012");
+    }
+
+    #[test]
+    fn test_concurrent_allocation_is_unique() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(DatabaseConfig {
+            url: dir.path().join("numbering.db").display().to_string(),
+            max_connections: 8,
+            wal_mode: true,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let db = db.clone();
+                std::thread::spawn(move || {
+                    (0..10)
+                        .map(|_| NumberingRepo::new(&db).next_document_number(&DocumentType::SOP, "ber").unwrap())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut numbers: Vec<String> = handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+        numbers.sort();
+        numbers.dedup();
+        assert_eq!(numbers.len(), 40);
+        assert_eq!(numbers.last().unwrap(), "SOP-BER-040");
+    }
+}
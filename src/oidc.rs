@@ -0,0 +1,464 @@
+//! # OIDC - Corporate Single Sign-On
+//!
+//! OpenID Connect authorization-code flow with PKCE for Azure AD, Okta and
+//! other providers. [`OidcClient::authorization_url`] starts a login and
+//! [`OidcClient::complete`] exchanges the returned code, verifies the RS256
+//! ID token against the provider's signing keys (issuer, audience, expiry,
+//! nonce) and yields the [`SsoIdentity`]. [`provision_user`] then matches the
+//! identity to a local user, creating one when JIT provisioning is enabled,
+//! and keeps the user's role in step with the mapped groups. Provisioning,
+//! role changes and refusals are recorded in the audit trail.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Duration, Utc};
+use ring::{digest, signature};
+use rusqlite::{params, OptionalExtension};
+use serde::Deserialize;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::authorization::UserRole;
+use crate::config::OidcConfig;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+
+/// Time allowed between starting a login and the provider's callback.
+const PENDING_LOGIN_MINUTES: i64 = 10;
+
+/// Clock skew tolerated when checking ID token expiry.
+const CLOCK_SKEW_SECONDS: i64 = 60;
+
+/// Endpoints from the provider's discovery document.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+/// A provider signing key (JWK); only RSA keys are used.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Jwk {
+    pub kty: String,
+    #[serde(default)]
+    pub kid: Option<String>,
+    /// Base64url modulus
+    #[serde(default)]
+    pub n: Option<String>,
+    /// Base64url exponent
+    #[serde(default)]
+    pub e: Option<String>,
+}
+
+/// The provider's published signing keys.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
+
+/// Identity asserted by a verified ID token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SsoIdentity {
+    /// Provider's stable subject id
+    pub subject: String,
+    /// Value of the configured username claim
+    pub username: String,
+    pub email: Option<String>,
+    /// Values of the configured roles claim
+    pub groups: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JwtHeader {
+    alg: String,
+    #[serde(default)]
+    kid: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+struct PendingLogin {
+    nonce: String,
+    code_verifier: String,
+    started_at: DateTime<Utc>,
+}
+
+/// OIDC relying party for one provider.
+pub struct OidcClient {
+    config: OidcConfig,
+    metadata: ProviderMetadata,
+    keys: JwkSet,
+    /// Logins started but not yet completed, by `state`
+    pending: Mutex<HashMap<String, PendingLogin>>,
+}
+
+impl OidcClient {
+    pub fn new(config: OidcConfig, metadata: ProviderMetadata, keys: JwkSet) -> Self {
+        Self { config, metadata, keys, pending: Mutex::new(HashMap::new()) }
+    }
+
+    /// Fetch the discovery document and signing keys of the configured issuer.
+    pub async fn discover(config: OidcConfig) -> Result<Self> {
+        let http = reqwest::Client::new();
+        let issuer = config.issuer.trim_end_matches('/').to_string();
+        let metadata: ProviderMetadata =
+            fetch_json(&http, &format!("{}/.well-known/openid-configuration", issuer)).await?;
+        if metadata.issuer.trim_end_matches('/') != issuer {
+            return Err(QmsError::Configuration {
+                message: format!("OIDC discovery returned issuer '{}', expected '{}'", metadata.issuer, issuer),
+            });
+        }
+        let keys = fetch_json(&http, &metadata.jwks_uri).await?;
+        Ok(Self::new(config, metadata, keys))
+    }
+
+    pub fn config(&self) -> &OidcConfig {
+        &self.config
+    }
+
+    /// Start a login: the provider URL the browser is redirected to. The
+    /// request carries a fresh `state`, `nonce` and PKCE challenge.
+    pub fn authorization_url(&self, now: DateTime<Utc>) -> Result<String> {
+        let state = random_token();
+        let nonce = random_token();
+        let code_verifier = format!("{}{}", random_token(), random_token());
+        let challenge = URL_SAFE_NO_PAD.encode(digest::digest(&digest::SHA256, code_verifier.as_bytes()));
+        let url = reqwest::Url::parse_with_params(
+            &self.metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", self.config.client_id.as_str()),
+                ("redirect_uri", self.config.redirect_uri.as_str()),
+                ("scope", self.config.scopes.join(" ").as_str()),
+                ("state", state.as_str()),
+                ("nonce", nonce.as_str()),
+                ("code_challenge", challenge.as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| QmsError::Configuration { message: format!("Invalid OIDC authorization endpoint: {}", e) })?;
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, login| now - login.started_at < Duration::minutes(PENDING_LOGIN_MINUTES));
+        pending.insert(state, PendingLogin { nonce, code_verifier, started_at: now });
+        Ok(url.into())
+    }
+
+    /// Finish a login from the provider's callback: exchange `code` and
+    /// verify the ID token.
+    pub async fn complete(&self, code: &str, state: &str) -> Result<SsoIdentity> {
+        let login = self.take_pending(state, Utc::now())?;
+        let secret = self.config.client_secret.clone().or_else(|| std::env::var("QMS_OIDC_CLIENT_SECRET").ok());
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.config.redirect_uri.as_str()),
+            ("client_id", self.config.client_id.as_str()),
+            ("code_verifier", login.code_verifier.as_str()),
+        ];
+        if let Some(secret) = &secret {
+            form.push(("client_secret", secret.as_str()));
+        }
+        let response =
+            reqwest::Client::new().post(&self.metadata.token_endpoint).form(&form).send().await.map_err(http_error)?;
+        if !response.status().is_success() {
+            return Err(QmsError::Security { message: format!("OIDC token endpoint returned {}", response.status()) });
+        }
+        let tokens: TokenResponse = response.json().await.map_err(http_error)?;
+        self.verify_id_token(&tokens.id_token, &login.nonce, Utc::now())
+    }
+
+    /// The pending login for `state`; each state can be used once.
+    fn take_pending(&self, state: &str, now: DateTime<Utc>) -> Result<PendingLogin> {
+        self.pending
+            .lock()
+            .unwrap()
+            .remove(state)
+            .filter(|login| now - login.started_at < Duration::minutes(PENDING_LOGIN_MINUTES))
+            .ok_or_else(|| QmsError::Security { message: "Unknown or expired OIDC login state".to_string() })
+    }
+
+    /// Verify an ID token's RS256 signature, then its claims.
+    pub fn verify_id_token(&self, id_token: &str, nonce: &str, now: DateTime<Utc>) -> Result<SsoIdentity> {
+        let parts: Vec<&str> = id_token.split('.').collect();
+        let &[encoded_header, payload, sig] = parts.as_slice() else {
+            return Err(invalid_token("malformed token"));
+        };
+        let header: JwtHeader = decode_json(encoded_header)?;
+        if header.alg != "RS256" {
+            return Err(invalid_token(&format!("unsupported algorithm '{}'", header.alg)));
+        }
+        let key = self
+            .keys
+            .keys
+            .iter()
+            .filter(|key| key.kty == "RSA")
+            .find(|key| header.kid.is_none() || key.kid == header.kid)
+            .ok_or_else(|| invalid_token("no matching signing key"))?;
+        let (Some(n), Some(e)) = (&key.n, &key.e) else {
+            return Err(invalid_token("incomplete signing key"));
+        };
+        let components = signature::RsaPublicKeyComponents { n: decode(n)?, e: decode(e)? };
+        components
+            .verify(
+                &signature::RSA_PKCS1_2048_8192_SHA256,
+                format!("{}.{}", encoded_header, payload).as_bytes(),
+                &decode(sig)?,
+            )
+            .map_err(|_| invalid_token("bad signature"))?;
+        self.identity_from_claims(&decode_json(payload)?, nonce, now)
+    }
+
+    /// Check issuer, audience, expiry and nonce, then read the identity.
+    pub fn identity_from_claims(&self, claims: &Value, nonce: &str, now: DateTime<Utc>) -> Result<SsoIdentity> {
+        let text = |name: &str| claims.get(name).and_then(Value::as_str).map(str::trim).filter(|v| !v.is_empty());
+        if text("iss").map(|iss| iss.trim_end_matches('/')) != Some(self.metadata.issuer.trim_end_matches('/')) {
+            return Err(invalid_token("wrong issuer"));
+        }
+        let client_id = self.config.client_id.as_str();
+        let audience_ok = match claims.get("aud") {
+            Some(Value::String(aud)) => aud == client_id,
+            Some(Value::Array(auds)) => auds.iter().any(|aud| aud.as_str() == Some(client_id)),
+            _ => false,
+        };
+        if !audience_ok {
+            return Err(invalid_token("wrong audience"));
+        }
+        match claims.get("exp").and_then(Value::as_i64) {
+            Some(exp) if exp + CLOCK_SKEW_SECONDS > now.timestamp() => {}
+            _ => return Err(invalid_token("expired")),
+        }
+        if text("nonce") != Some(nonce) {
+            return Err(invalid_token("nonce mismatch"));
+        }
+        let subject = text("sub").ok_or_else(|| invalid_token("missing subject"))?.to_string();
+        let username = text(&self.config.username_claim)
+            .ok_or_else(|| invalid_token(&format!("missing '{}' claim", self.config.username_claim)))?
+            .to_string();
+        let groups = match claims.get(&self.config.roles_claim) {
+            Some(Value::Array(values)) => values.iter().filter_map(Value::as_str).map(str::to_string).collect(),
+            Some(Value::String(value)) => vec![value.clone()],
+            _ => Vec::new(),
+        };
+        Ok(SsoIdentity { subject, username, email: text("email").map(str::to_string), groups })
+    }
+}
+
+/// Highest local role mapped from the identity's groups.
+pub fn mapped_role(config: &OidcConfig, identity: &SsoIdentity) -> Option<UserRole> {
+    identity.groups.iter().filter_map(|group| config.role_mapping.get(group).copied()).max()
+}
+
+/// Local user id for a verified SSO identity, matched on `users.username`.
+/// Existing users take the role mapped from their groups, if any; unknown
+/// users are created with the mapped role (or the default role) when JIT
+/// provisioning is enabled, and refused otherwise.
+pub fn provision_user(db: &Database, config: &OidcConfig, identity: &SsoIdentity) -> Result<String> {
+    let audit = AuditManager::new(db.clone());
+    let refuse = |reason: &str| -> Result<String> {
+        audit.log_action(&identity.username, "sso_login", "authentication", "Failure", Some(reason.to_string()))?;
+        Err(QmsError::Security { message: format!("SSO login refused: {}", reason) })
+    };
+    let existing: Option<(String, String, bool)> = db.with_connection(|conn| {
+        Ok(conn
+            .query_row("SELECT id, role, is_active FROM users WHERE username = ?1", params![identity.username], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .optional()?)
+    })?;
+    let mapped = mapped_role(config, identity);
+    match existing {
+        Some((_, _, false)) => refuse("user is deactivated"),
+        Some((user_id, current, true)) => {
+            if let Some(role) = mapped.filter(|role| role.as_str() != current) {
+                db.unit_of_work(|uow| {
+                    uow.connection().execute(
+                        "UPDATE users SET role = ?2, updated_at = ?3 WHERE id = ?1",
+                        params![user_id, role.as_str(), Utc::now().to_rfc3339()],
+                    )?;
+                    audit.log_action_in(
+                        uow,
+                        &user_id,
+                        "user_role_synced",
+                        &format!("user:{}", user_id),
+                        "Success",
+                        Some(format!("{} -> {} from SSO groups", current, role.as_str())),
+                    )
+                })?;
+            }
+            Ok(user_id)
+        }
+        None if !config.jit_provisioning => refuse("unknown user and JIT provisioning is disabled"),
+        None => {
+            let Some(role) = mapped.or(config.default_role) else {
+                return refuse("no QMS role is mapped to the user's groups");
+            };
+            let user_id = Uuid::new_v4().to_string();
+            // SSO users have no local password; the placeholder never verifies
+            let email = identity.email.clone().unwrap_or_else(|| format!("{}@sso.invalid", identity.subject));
+            db.unit_of_work(|uow| {
+                uow.connection().execute(
+                    "INSERT INTO users (id, username, email, password_hash, salt, role) VALUES (?1, ?2, ?3, '!', ?4, ?5)",
+                    params![user_id, identity.username, email, random_token(), role.as_str()],
+                )?;
+                audit.log_action_in(
+                    uow,
+                    &user_id,
+                    "user_provisioned",
+                    &format!("user:{}", user_id),
+                    "Success",
+                    Some(format!("role {} via SSO subject {}", role.as_str(), identity.subject)),
+                )
+            })?;
+            Ok(user_id)
+        }
+    }
+}
+
+fn random_token() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+fn invalid_token(reason: &str) -> QmsError {
+    QmsError::Security { message: format!("Invalid ID token: {}", reason) }
+}
+
+fn decode(part: &str) -> Result<Vec<u8>> {
+    URL_SAFE_NO_PAD.decode(part.trim_end_matches('=')).map_err(|_| invalid_token("bad base64url encoding"))
+}
+
+fn decode_json<T: serde::de::DeserializeOwned>(part: &str) -> Result<T> {
+    serde_json::from_slice(&decode(part)?).map_err(|_| invalid_token("bad JSON"))
+}
+
+fn http_error(e: reqwest::Error) -> QmsError {
+    QmsError::Security { message: format!("OIDC provider request failed: {}", e) }
+}
+
+async fn fetch_json<T: serde::de::DeserializeOwned>(http: &reqwest::Client, url: &str) -> Result<T> {
+    let response =
+        http.get(url).send().await.map_err(|e| QmsError::Configuration {
+            message: format!("OIDC discovery request to {} failed: {}", url, e),
+        })?;
+    response.error_for_status().map_err(http_error)?.json().await.map_err(|e| QmsError::Configuration {
+        message: format!("Invalid OIDC discovery response from {}: {}", url, e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use serde_json::json;
+
+    fn client(jit_provisioning: bool) -> OidcClient {
+        let mut config = OidcConfig {
+            enabled: true,
+            issuer: "https://login.example.com".to_string(),
+            client_id: "qms".to_string(),
+            redirect_uri: "https://qms.example.com/auth/oidc/callback".to_string(),
+            jit_provisioning,
+            ..OidcConfig::default()
+        };
+        config.role_mapping.insert("qms-engineers".to_string(), UserRole::QualityEngineer);
+        config.role_mapping.insert("qms-approvers".to_string(), UserRole::QualityManager);
+        let metadata = ProviderMetadata {
+            issuer: "https://login.example.com/".to_string(),
+            authorization_endpoint: "https://login.example.com/authorize".to_string(),
+            token_endpoint: "https://login.example.com/token".to_string(),
+            jwks_uri: "https://login.example.com/keys".to_string(),
+        };
+        OidcClient::new(config, metadata, JwkSet::default())
+    }
+
+    fn identity(username: &str, groups: &[&str]) -> SsoIdentity {
+        SsoIdentity {
+            subject: format!("sub-{}", username),
+            username: username.to_string(),
+            email: None,
+            groups: groups.iter().map(|g| g.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_claims_are_checked() {
+        let client = client(false);
+        let now = Utc::now();
+        let claims = json!({
+            "iss": "https://login.example.com",
+            "aud": ["other", "qms"],
+            "exp": now.timestamp() + 300,
+            "nonce": "n1",
+            "sub": "abc",
+            "preferred_username": "jdoe",
+            "roles": ["qms-approvers", "staff"],
+        });
+        let identity = client.identity_from_claims(&claims, "n1", now).unwrap();
+        assert_eq!(identity.username, "jdoe");
+        assert_eq!(mapped_role(client.config(), &identity), Some(UserRole::QualityManager));
+
+        assert!(client.identity_from_claims(&claims, "n2", now).is_err(), "nonce");
+        assert!(client.identity_from_claims(&claims, "n1", now + Duration::minutes(10)).is_err(), "expiry");
+        let mut foreign = claims.clone();
+        foreign["aud"] = json!("someone-else");
+        assert!(client.identity_from_claims(&foreign, "n1", now).is_err(), "audience");
+
+        let unsigned =
+            format!("{}.{}.", URL_SAFE_NO_PAD.encode(br#"{"alg":"none"}"#), URL_SAFE_NO_PAD.encode(claims.to_string()));
+        assert!(matches!(client.verify_id_token(&unsigned, "n1", now), Err(QmsError::Security { .. })));
+    }
+
+    #[test]
+    fn test_authorization_url_uses_pkce_and_single_use_state() {
+        let client = client(false);
+        let now = Utc::now();
+        let url = reqwest::Url::parse(&client.authorization_url(now).unwrap()).unwrap();
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        assert_eq!(params["code_challenge_method"], "S256");
+        assert_eq!(params["scope"], "openid profile email");
+        let state = &params["state"];
+        let login = client.take_pending(state, now).unwrap();
+        let challenge = URL_SAFE_NO_PAD.encode(digest::digest(&digest::SHA256, login.code_verifier.as_bytes()));
+        assert_eq!(params["code_challenge"], challenge);
+        assert_eq!(params["nonce"], login.nonce);
+        assert!(client.take_pending(state, now).is_err(), "state is single use");
+    }
+
+    #[test]
+    fn test_provisioning_and_role_sync() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let closed = client(false);
+        assert!(provision_user(&db, closed.config(), &identity("newbie", &["qms-engineers"])).is_err());
+
+        let open = client(true);
+        assert!(provision_user(&db, open.config(), &identity("outsider", &["staff"])).is_err(), "no mapped role");
+        let user_id = provision_user(&db, open.config(), &identity("jdoe", &["qms-engineers"])).unwrap();
+        assert_eq!(UserRole::of_user(&db, &user_id).unwrap(), Some(UserRole::QualityEngineer));
+
+        let again = provision_user(&db, closed.config(), &identity("jdoe", &["qms-approvers"])).unwrap();
+        assert_eq!(again, user_id);
+        assert_eq!(UserRole::of_user(&db, &user_id).unwrap(), Some(UserRole::QualityManager));
+        let actions: Vec<String> = db
+            .get_audit_entries_for_resource(&format!("user:{}", user_id))
+            .unwrap()
+            .into_iter()
+            .map(|e| e.action)
+            .collect();
+        assert_eq!(actions, vec!["user_provisioned", "user_role_synced"]);
+        assert_eq!(db.get_audit_entries_for_resource("authentication").unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,380 @@
+//! # Ownership Transfer - Reassigning Work When a User Leaves
+//!
+//! A deactivated account cannot complete the CAPAs, actions, document
+//! reviews, tasks, audit findings or plugin records still assigned to it, so
+//! that work would silently stall. [`OwnershipTransfer::deactivate`] refuses
+//! to deactivate a user until a [`TransferPlan`] names an active new owner
+//! for every open item, then reassigns the items and deactivates the account
+//! in one unit of work. Each item gets an `ownership_transferred` audit entry
+//! and the [`TransferReport`] is recorded on the user.
+//!
+//! Accounts deactivated elsewhere, e.g. by an access review revocation, are
+//! handed over afterwards with [`OwnershipTransfer::transfer`].
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::AuditManager;
+use crate::authorization::{Permission, UserRole};
+use crate::change_history::ChangeReason;
+use crate::database::Database;
+use crate::error::{QmsError, Result, ValidationErrors};
+use crate::links::RecordRef;
+
+/// Open items by owner, as `(record_type, id, title)`.
+const OWNED_ITEMS: &[&str] = &[
+    "SELECT 'capa', id, title FROM capa_records
+     WHERE assigned_to = ?1 AND status NOT IN ('Closed', 'Cancelled') AND deleted_at IS NULL",
+    "SELECT 'capa_action', id, description FROM capa_actions
+     WHERE assigned_to = ?1 AND status IN ('Planned', 'InProgress', 'Overdue') AND deleted_at IS NULL",
+    "SELECT 'document', id, document_number || ' ' || title FROM documents
+     WHERE COALESCE(owner, created_by) = ?1 AND status NOT IN ('Obsolete', 'Retired') AND deleted_at IS NULL",
+    "SELECT 'task', id, title FROM tasks WHERE assignee = ?1 AND status = 'open'",
+    "SELECT 'audit_finding', id, description FROM audit_findings WHERE owner = ?1 AND closed_at IS NULL",
+    "SELECT record_type, id, title FROM custom_records WHERE assignee = ?1 AND is_open = 1",
+];
+
+/// An open item a user is responsible for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnedItem {
+    pub record: RecordRef,
+    pub title: String,
+}
+
+/// New owners for a departing user's open items.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransferPlan {
+    /// Owner of every item without an entry in `owners`
+    pub default_owner: Option<String>,
+    pub owners: BTreeMap<RecordRef, String>,
+}
+
+impl TransferPlan {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_default_owner(mut self, owner: &str) -> Self {
+        self.default_owner = Some(owner.to_string());
+        self
+    }
+
+    pub fn with_owner(mut self, record: RecordRef, owner: &str) -> Self {
+        self.owners.insert(record, owner.to_string());
+        self
+    }
+
+    /// Parse a `<record>=<owner>` assignment, e.g. `capa:c-17=alice`.
+    pub fn parse_assignment(value: &str) -> Result<(RecordRef, String)> {
+        match value.split_once('=') {
+            Some((record, owner)) if !owner.trim().is_empty() => {
+                Ok((RecordRef::parse(record.trim())?, owner.trim().to_string()))
+            }
+            _ => Err(QmsError::Validation {
+                field: "assign".to_string(),
+                message: format!("Expected <record>=<owner>, got '{}'", value),
+            }),
+        }
+    }
+
+    fn owner_of(&self, record: &RecordRef) -> Option<&str> {
+        self.owners.get(record).or(self.default_owner.as_ref()).map(String::as_str)
+    }
+}
+
+/// An item handed to a new owner.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferredItem {
+    pub record: RecordRef,
+    pub title: String,
+    pub new_owner: String,
+}
+
+/// What a transfer did, as recorded in the audit trail.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransferReport {
+    pub user_id: String,
+    pub performed_by: String,
+    pub reason: String,
+    /// Whether the account was deactivated with the transfer
+    pub deactivated: bool,
+    pub transferred_at: DateTime<Utc>,
+    pub items: Vec<TransferredItem>,
+}
+
+/// Guided hand-over of a user's open items.
+pub struct OwnershipTransfer<'a> {
+    db: &'a Database,
+}
+
+impl<'a> OwnershipTransfer<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Open items `user_id` is responsible for, by record type.
+    pub fn open_items(&self, user_id: &str) -> Result<Vec<OwnedItem>> {
+        self.db.with_connection(|conn| owned_items(conn, user_id))
+    }
+
+    /// Reassign the open items of `user_id` as `plan` says and deactivate the
+    /// account. Requires [`Permission::ManageUsers`].
+    pub fn deactivate(
+        &self,
+        user_id: &str,
+        plan: &TransferPlan,
+        performed_by: &str,
+        reason: &ChangeReason,
+    ) -> Result<TransferReport> {
+        self.run(user_id, plan, performed_by, reason, true)
+    }
+
+    /// Reassign the open items of an already deactivated `user_id`.
+    /// Requires [`Permission::ManageUsers`].
+    pub fn transfer(
+        &self,
+        user_id: &str,
+        plan: &TransferPlan,
+        performed_by: &str,
+        reason: &ChangeReason,
+    ) -> Result<TransferReport> {
+        self.run(user_id, plan, performed_by, reason, false)
+    }
+
+    fn run(
+        &self,
+        user_id: &str,
+        plan: &TransferPlan,
+        performed_by: &str,
+        reason: &ChangeReason,
+        deactivate: bool,
+    ) -> Result<TransferReport> {
+        match UserRole::of_user(self.db, performed_by)? {
+            Some(role) if role.has_permission(Permission::ManageUsers) => {}
+            _ => {
+                return Err(QmsError::Security {
+                    message: format!("User {} is not permitted to manage users", performed_by),
+                });
+            }
+        }
+        let audit = AuditManager::new(self.db.clone());
+        let now = Utc::now();
+        self.db.unit_of_work(|uow| {
+            let conn = uow.connection();
+            let active: bool = conn
+                .query_row(
+                    "SELECT is_active FROM users WHERE id = ?1 AND deleted_at IS NULL",
+                    params![user_id],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .ok_or_else(|| QmsError::NotFound { resource: "user".to_string(), id: user_id.to_string() })?;
+            if active != deactivate {
+                return Err(QmsError::Validation {
+                    field: "user_id".to_string(),
+                    message: match active {
+                        true => format!("User {} is active; deactivate the account to transfer its work", user_id),
+                        false => format!("User {} is already deactivated", user_id),
+                    },
+                });
+            }
+
+            let items = owned_items(conn, user_id)?;
+            let mut errors = ValidationErrors::new();
+            for item in &items {
+                match plan.owner_of(&item.record) {
+                    None => errors.add(item.record.to_string(), "No new owner given"),
+                    Some(owner) if owner == user_id => {
+                        errors.add(item.record.to_string(), "New owner is the departing user")
+                    }
+                    Some(owner) if !is_active_user(conn, owner)? => {
+                        errors.add(item.record.to_string(), format!("'{}' is not an active user", owner))
+                    }
+                    Some(_) => {}
+                }
+            }
+            errors.into_result()?;
+
+            let mut transferred = Vec::with_capacity(items.len());
+            for item in items {
+                let new_owner = plan.owner_of(&item.record).unwrap_or_default().to_string();
+                reassign(conn, &item.record, &new_owner, now)?;
+                audit.log_action_in(
+                    uow,
+                    performed_by,
+                    "ownership_transferred",
+                    &item.record.to_string(),
+                    "Success",
+                    Some(format!("From {} to {}: {}", user_id, new_owner, reason.as_str())),
+                )?;
+                transferred.push(TransferredItem { record: item.record, title: item.title, new_owner });
+            }
+            if deactivate {
+                conn.execute(
+                    "UPDATE users SET is_active = 0, updated_at = ?2 WHERE id = ?1",
+                    params![user_id, now.to_rfc3339()],
+                )?;
+                conn.execute("UPDATE sessions SET is_active = 0 WHERE user_id = ?1", params![user_id])?;
+                conn.execute("DELETE FROM site_roles WHERE user_id = ?1", params![user_id])?;
+            }
+            let report = TransferReport {
+                user_id: user_id.to_string(),
+                performed_by: performed_by.to_string(),
+                reason: reason.as_str().to_string(),
+                deactivated: deactivate,
+                transferred_at: now,
+                items: transferred,
+            };
+            audit.log_action_in(
+                uow,
+                performed_by,
+                if deactivate { "user_deactivated" } else { "user_work_transferred" },
+                &format!("user:{}", user_id),
+                "Success",
+                Some(serde_json::to_string(&report)?),
+            )?;
+            Ok(report)
+        })
+    }
+}
+
+fn owned_items(conn: &Connection, user_id: &str) -> Result<Vec<OwnedItem>> {
+    let mut items = Vec::new();
+    for sql in OWNED_ITEMS {
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(params![user_id], |row| {
+            let record = RecordRef::new(row.get::<_, String>(0)?, row.get::<_, String>(1)?);
+            Ok(OwnedItem { record, title: row.get(2)? })
+        })?;
+        items.extend(rows.collect::<rusqlite::Result<Vec<_>>>()?);
+    }
+    Ok(items)
+}
+
+fn is_active_user(conn: &Connection, user_id: &str) -> Result<bool> {
+    Ok(conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM users WHERE id = ?1 AND is_active = 1 AND deleted_at IS NULL)",
+        params![user_id],
+        |row| row.get(0),
+    )?)
+}
+
+fn reassign(conn: &Connection, record: &RecordRef, owner: &str, now: DateTime<Utc>) -> Result<()> {
+    let (id, now) = (record.record_id.as_str(), now.to_rfc3339());
+    match record.record_type.as_str() {
+        "capa" => conn.execute(
+            "UPDATE capa_records SET assigned_to = ?2, updated_at = ?3, row_version = row_version + 1 WHERE id = ?1",
+            params![id, owner, now],
+        )?,
+        "capa_action" => conn.execute(
+            "UPDATE capa_actions SET assigned_to = ?2, updated_at = ?3, row_version = row_version + 1 WHERE id = ?1",
+            params![id, owner, now],
+        )?,
+        "document" => conn.execute(
+            "UPDATE documents SET owner = ?2, updated_at = ?3, row_version = row_version + 1 WHERE id = ?1",
+            params![id, owner, now],
+        )?,
+        "task" => conn.execute("UPDATE tasks SET assignee = ?2 WHERE id = ?1", params![id, owner])?,
+        "audit_finding" => conn.execute("UPDATE audit_findings SET owner = ?2 WHERE id = ?1", params![id, owner])?,
+        record_type => conn.execute(
+            "UPDATE custom_records SET assignee = ?3, updated_at = ?4 WHERE record_type = ?1 AND id = ?2",
+            params![record_type, id, owner, now],
+        )?,
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::tasks::{TaskInbox, TaskKind, TaskRepo};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_deactivation_requires_and_records_transfer() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["admin", "leaver", "alice", "bob"]);
+        db.with_connection(|conn| {
+            conn.execute("UPDATE users SET role = 'Administrator' WHERE id = 'admin'", [])?;
+            conn.execute(
+                "INSERT INTO capa_records (id, title, description, capa_type, priority, status, initiator_id,
+                                           assigned_to, created_at, updated_at)
+                 VALUES ('capa-1', 'Seal leak', 'Leak at station 4', 'Corrective', 'High', 'RootCauseAnalysis',
+                         'admin', 'leaver', '2026-01-05', '2026-01-05')",
+                [],
+            )?;
+            conn.execute(
+                "INSERT INTO documents (id, document_number, title, version, status, document_type, content_hash,
+                                        created_by)
+                 VALUES ('d1', 'SOP-001', 'Cleaning', '1.0', 'Effective', 'SOP', 'h', 'leaver'),
+                        ('d2', 'SOP-002', 'Gowning', '1.0', 'Retired', 'SOP', 'h', 'leaver')",
+                [],
+            )?;
+            conn.execute("UPDATE documents SET review_date = '2026-12-01' WHERE id = 'd1'", [])?;
+            Ok(())
+        })
+        .unwrap();
+        let due = NaiveDate::from_ymd_opt(2026, 11, 1).unwrap();
+        let task =
+            TaskRepo::new(&db).create("Review label proof", TaskKind::Review, due, None, "leaver", "admin").unwrap();
+
+        let transfer = OwnershipTransfer::new(&db);
+        let items = transfer.open_items("leaver").unwrap();
+        let records: Vec<String> = items.iter().map(|item| item.record.to_string()).collect();
+        assert_eq!(records, vec!["capa:capa-1".to_string(), "document:d1".to_string(), format!("task:{}", task.id)]);
+
+        let reason = ChangeReason::new("Left the company").unwrap();
+        let partial = TransferPlan::new().with_owner(RecordRef::new("capa", "capa-1"), "alice");
+        let refused = transfer.deactivate("leaver", &partial, "admin", &reason);
+        let Err(QmsError::ValidationErrors { errors }) = refused else {
+            panic!("items without a new owner are refused");
+        };
+        assert_eq!(errors.len(), 2);
+        assert!(UserRole::of_user(&db, "leaver").unwrap().is_some(), "nothing changed");
+
+        let plan = partial.with_default_owner("bob");
+        assert!(transfer.deactivate("leaver", &plan, "alice", &reason).is_err(), "needs manage_users");
+        let report = transfer.deactivate("leaver", &plan, "admin", &reason).unwrap();
+        assert!(report.deactivated && report.items.len() == 3);
+        assert_eq!(report.items[0].new_owner, "alice");
+        assert!(transfer.open_items("leaver").unwrap().is_empty());
+        let assignment = TransferPlan::parse_assignment("task:t-1=bob").unwrap();
+        assert_eq!(assignment, (RecordRef::new("task", "t-1"), "bob".to_string()));
+        assert!(TransferPlan::parse_assignment("task:t-1").is_err());
+        assert_eq!(UserRole::of_user(&db, "leaver").unwrap(), None);
+        let inbox: Vec<String> =
+            TaskInbox::new(&db).for_user("bob", due).unwrap().iter().map(|item| item.record.to_string()).collect();
+        assert_eq!(inbox, vec![format!("task:{}", task.id), "document:d1".to_string()], "the review moves too");
+        let row_versions: (i64, i64) = db
+            .with_connection(|conn| {
+                Ok(conn.query_row(
+                    "SELECT (SELECT row_version FROM documents WHERE id = 'd1'),
+                            (SELECT row_version FROM capa_records WHERE id = 'capa-1')",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )?)
+            })
+            .unwrap();
+        assert_eq!(row_versions, (2, 2), "cached ETags of the document and the CAPA are invalidated");
+        assert_eq!(transfer.open_items("bob").unwrap().len(), 2);
+
+        let audit = db.get_audit_entries_for_resource("user:leaver").unwrap();
+        let entry = audit.iter().find(|entry| entry.action == "user_deactivated").unwrap();
+        let recorded: TransferReport = serde_json::from_str(entry.metadata.as_deref().unwrap()).unwrap();
+        assert_eq!(recorded, report);
+        assert_eq!(db.get_audit_entries_for_resource("capa:capa-1").unwrap()[0].action, "ownership_transferred");
+        assert!(transfer.deactivate("leaver", &plan, "admin", &reason).is_err(), "already deactivated");
+        transfer.transfer("leaver", &TransferPlan::new(), "admin", &reason).unwrap();
+    }
+}
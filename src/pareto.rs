@@ -0,0 +1,254 @@
+//! # Pareto - Dominant Complaint and Nonconformance Causes
+//!
+//! Ranks the defect or cause categories assigned to quality issues at intake
+//! (see [`QualityIntake::categorize`](crate::quality_intake::QualityIntake::categorize))
+//! over a chosen period, most frequent first, with each category's share and
+//! the cumulative share. The "vital few" categories that together account for
+//! 80 % of the issues are where preventive action pays off most (ISO 13485
+//! §8.4, §8.5.3). Rankings are served at `GET /analytics/pareto` and included
+//! in the compliance summary as a management review input.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::quality_intake::IssueSource;
+use crate::report::{Chart, ChartDatum, ChartKind, ReportSection, ReportTable};
+
+/// Category of issues not categorized yet.
+pub const UNCATEGORIZED: &str = "Uncategorized";
+
+/// Cumulative share covered by the vital few categories.
+pub const VITAL_FEW_PERCENT: f64 = 80.0;
+
+/// Period analysed when none is selected, and for management review.
+pub const DEFAULT_PERIOD_DAYS: i64 = 90;
+
+/// Sources analysed when none are selected.
+pub const DEFAULT_SOURCES: [IssueSource; 2] = [IssueSource::Complaint, IssueSource::Nonconformance];
+
+/// One category of a [`ParetoRanking`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParetoEntry {
+    pub category: String,
+    pub count: usize,
+    /// Share of all issues in the period
+    pub percent: f64,
+    /// Share of this and all more frequent categories
+    pub cumulative_percent: f64,
+    /// Among the categories making up the first 80 % of issues
+    pub vital_few: bool,
+}
+
+/// Cause categories of the issues reported in `[from, to)`, most frequent
+/// first; ties are ordered by name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParetoRanking {
+    pub sources: Vec<IssueSource>,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub total: usize,
+    pub entries: Vec<ParetoEntry>,
+}
+
+impl ParetoRanking {
+    /// Rank `counts` per category.
+    pub fn from_counts(
+        sources: Vec<IssueSource>,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        counts: BTreeMap<String, usize>,
+    ) -> Self {
+        let total: usize = counts.values().sum();
+        let mut ranked: Vec<(String, usize)> = counts.into_iter().filter(|(_, count)| *count > 0).collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let mut covered = 0;
+        let entries = ranked
+            .into_iter()
+            .map(|(category, count)| {
+                let share = |n: usize| n as f64 * 100.0 / total as f64;
+                let vital_few = share(covered) < VITAL_FEW_PERCENT;
+                covered += count;
+                ParetoEntry { category, count, percent: share(count), cumulative_percent: share(covered), vital_few }
+            })
+            .collect();
+        Self { sources, from, to, total, entries }
+    }
+
+    pub fn vital_few(&self) -> Vec<&ParetoEntry> {
+        self.entries.iter().filter(|entry| entry.vital_few).collect()
+    }
+}
+
+/// Pareto analyses over the quality intake.
+pub struct ParetoAnalysis<'a> {
+    db: &'a Database,
+}
+
+impl<'a> ParetoAnalysis<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Rank the cause categories of issues from `sources` reported in
+    /// `[from, to)`.
+    pub fn rank(&self, sources: &[IssueSource], from: DateTime<Utc>, to: DateTime<Utc>) -> Result<ParetoRanking> {
+        if sources.is_empty() {
+            return Err(QmsError::Validation {
+                field: "sources".to_string(),
+                message: "At least one issue source is required".to_string(),
+            });
+        }
+        if from >= to {
+            return Err(QmsError::Validation {
+                field: "to".to_string(),
+                message: format!("Period end {} is not after its start {}", to, from),
+            });
+        }
+        let rows = self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT source, COALESCE(NULLIF(TRIM(cause_category), ''), ?3), COUNT(*)
+                 FROM quality_intake
+                 WHERE reported_at >= ?1 AND reported_at < ?2
+                 GROUP BY 1, 2",
+            )?;
+            let rows = stmt
+                .query_map(params![from.to_rfc3339(), to.to_rfc3339(), UNCATEGORIZED], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rows)
+        })?;
+
+        let mut counts = BTreeMap::new();
+        for (source, category, count) in rows {
+            if IssueSource::parse(&source).map_or(false, |source| sources.contains(&source)) {
+                *counts.entry(category).or_insert(0) += count as usize;
+            }
+        }
+        Ok(ParetoRanking::from_counts(sources.to_vec(), from, to, counts))
+    }
+
+    /// Ranking of complaint and NCR causes over the last
+    /// [`DEFAULT_PERIOD_DAYS`], as reviewed by management.
+    pub fn management_review(&self, now: DateTime<Utc>) -> Result<ParetoRanking> {
+        self.rank(&DEFAULT_SOURCES, now - Duration::days(DEFAULT_PERIOD_DAYS), now)
+    }
+}
+
+/// Management review input: issue counts per cause category with the vital
+/// few called out.
+pub fn pareto_section(ranking: &ParetoRanking) -> ReportSection {
+    let sources: Vec<&str> = ranking.sources.iter().map(|source| source.as_str()).collect();
+    let vital_few: Vec<&str> = ranking.vital_few().iter().map(|entry| entry.category.as_str()).collect();
+    let section = ReportSection::new("Complaint and Nonconformance Causes").key_values(vec![
+        ("Period", format!("{} to {}", ranking.from.date_naive(), ranking.to.date_naive())),
+        ("Sources", sources.join(", ")),
+        ("Issues", ranking.total.to_string()),
+        ("Vital Few", if vital_few.is_empty() { "-".to_string() } else { vital_few.join(", ") }),
+    ]);
+    if ranking.entries.is_empty() {
+        return section;
+    }
+    let chart = Chart {
+        title: "Issues by Cause (Pareto)".to_string(),
+        kind: ChartKind::Bar,
+        data: ranking
+            .entries
+            .iter()
+            .map(|entry| ChartDatum { label: entry.category.clone(), value: entry.count as f32 })
+            .collect(),
+    };
+    section.chart(chart).table(ranking.entries.iter().fold(
+        ReportTable::new(vec!["Cause", "Issues", "Share", "Cumulative", "Vital Few"])
+            .with_column_weights(vec![2.4, 0.8, 0.8, 1.0, 0.8]),
+        |table, entry| {
+            table.with_row(vec![
+                entry.category.clone(),
+                entry.count.to_string(),
+                format!("{:.1} %", entry.percent),
+                format!("{:.1} %", entry.cumulative_percent),
+                if entry.vital_few { "Yes" } else { "No" }.to_string(),
+            ])
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::quality_intake::QualityIntake;
+    use crate::report::ReportBlock;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_ranks_causes_of_selected_sources_and_period() {
+        let db = test_db();
+        let intake = QualityIntake::new(&db);
+        let issues = [
+            (IssueSource::Complaint, Some("Seal leak")),
+            (IssueSource::Complaint, Some("Seal leak")),
+            (IssueSource::Nonconformance, Some(" Seal leak ")),
+            (IssueSource::Nonconformance, Some("Seal leak")),
+            (IssueSource::Nonconformance, Some("Label misprint")),
+            (IssueSource::Complaint, Some("Label misprint")),
+            (IssueSource::Complaint, None),
+            (IssueSource::Nonconformance, Some("Cracked housing")),
+            (IssueSource::InternalObservation, Some("Seal leak")),
+        ];
+        let mut ids = Vec::new();
+        for (source, category) in issues {
+            let item = intake.submit(source, "Issue", "Description", "qe1").unwrap();
+            if let Some(category) = category {
+                intake.categorize(item.id, category, "qe1").unwrap();
+            }
+            ids.push(item.id);
+        }
+        assert!(intake.categorize(ids[0], " ", "qe1").is_err());
+        assert_eq!(intake.get(ids[2]).unwrap().cause_category.as_deref(), Some("Seal leak"));
+        db.with_connection(|conn| {
+            let old = (Utc::now() - Duration::days(200)).to_rfc3339();
+            conn.execute("UPDATE quality_intake SET reported_at = ?1 WHERE id = ?2", params![old, ids[7].to_string()])?;
+            Ok(())
+        })
+        .unwrap();
+
+        let now = Utc::now() + Duration::seconds(1);
+        let ranking = ParetoAnalysis::new(&db).management_review(now).unwrap();
+        assert_eq!(ranking.total, 7);
+        let ranked: Vec<(&str, usize, bool)> =
+            ranking.entries.iter().map(|e| (e.category.as_str(), e.count, e.vital_few)).collect();
+        assert_eq!(ranked, [("Seal leak", 4, true), ("Label misprint", 2, true), (UNCATEGORIZED, 1, false)]);
+        assert!((ranking.entries[1].cumulative_percent - 600.0 / 7.0).abs() < 1e-9);
+        assert!((ranking.entries[2].cumulative_percent - 100.0).abs() < 1e-9);
+
+        let year_ago = now - Duration::days(365);
+        let complaints = ParetoAnalysis::new(&db).rank(&[IssueSource::Complaint], year_ago, now).unwrap();
+        assert_eq!(complaints.total, 4);
+        assert_eq!(complaints.entries[0].category, "Seal leak");
+        assert_eq!(complaints.vital_few().len(), 3);
+        assert!(ParetoAnalysis::new(&db).rank(&[], now - Duration::days(1), now).is_err());
+        assert!(ParetoAnalysis::new(&db).rank(&DEFAULT_SOURCES, now, now).is_err());
+
+        let section = pareto_section(&ranking);
+        assert!(matches!(&section.blocks[0], ReportBlock::KeyValue(pairs)
+            if pairs[3] == ("Vital Few".to_string(), "Seal leak, Label misprint".to_string())));
+        assert!(matches!(&section.blocks[2], ReportBlock::Table(table) if table.rows.len() == 3));
+    }
+}
@@ -1,11 +1,49 @@
+//! # PDF Report Engine - Paginated Compliance Documents
+//!
+//! Renders a renderer-independent [`Report`] into a multi-page PDF with an
+//! optional cover page and table of contents, automatic pagination of
+//! paragraphs and tables (header rows repeat on every page), running headers
+//! and "Page X of Y" footers. Feature modules (compliance summaries, CAPA
+//! detail exports, audit extracts) build a `Report` and call [`render_report`].
+
 use chrono::{DateTime, Utc};
-use pdf_canvas::{BuiltinFont, Canvas, Pdf};
-use std::fs::File;
+use pdf_canvas::graphicsstate::Color;
+use pdf_canvas::{BuiltinFont, Canvas, FontSource, Pdf};
+use std::f32::consts::{FRAC_PI_2, PI};
+use std::io;
 use std::path::Path;
 
+use crate::api::MetricsResponse;
 use crate::error::QmsError;
+use crate::i18n::{tr, tr_args, Locale};
+use crate::kpi::KpiStatus;
+use crate::report::{HeatLevel, HeatMap, ReportBlock, ReportBuilder, ReportSection, ReportTable, CHART_PALETTE};
 use crate::Result;
 
+pub use crate::report::{Chart, ChartDatum, ChartKind, Report};
+
+/// A4 portrait page size in points.
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN_LEFT: f32 = 50.0;
+const MARGIN_RIGHT: f32 = 545.0;
+const CONTENT_WIDTH: f32 = MARGIN_RIGHT - MARGIN_LEFT;
+/// Content area between running header and footer.
+const CONTENT_TOP: f32 = 770.0;
+const CONTENT_BOTTOM: f32 = 115.0;
+
+const BODY_SIZE: f32 = 10.0;
+const BODY_LEADING: f32 = 14.0;
+const TABLE_SIZE: f32 = 9.0;
+const TABLE_LEADING: f32 = 12.0;
+const TABLE_PADDING: f32 = 3.0;
+const KEY_VALUE_OFFSET: f32 = 200.0;
+const TOC_LEADING: f32 = 18.0;
+const BAR_CHART_HEIGHT: f32 = 190.0;
+const PIE_CHART_HEIGHT: f32 = 160.0;
+const HEAT_CELL_HEIGHT: f32 = 26.0;
+const HEAT_LABEL_WIDTH: f32 = 110.0;
+
 /// Core compliance metrics aggregated for reporting.
 #[derive(Debug, Clone)]
 pub struct ComplianceMetrics {
@@ -34,100 +72,879 @@ pub struct ComplianceReportConfig<'a> {
     pub title: Option<&'a str>,
 }
 
+/// Configuration for a metrics report rendered from the API `MetricsResponse`.
+#[derive(Debug, Clone)]
+pub struct MetricsReportConfig<'a> {
+    /// Destination path for the generated PDF file.
+    pub output_path: &'a Path,
+    /// System version string for footer.
+    pub application_version: &'a str,
+    /// Aggregated CAPA, risk and supplier metrics as served by `GET /metrics`.
+    pub metrics: &'a MetricsResponse,
+    /// UTC timestamp of report generation.
+    pub generated_on: DateTime<Utc>,
+    /// Optional custom title; defaults to standard title if `None`.
+    pub title: Option<&'a str>,
+}
+
+/// Summary of a rendered PDF.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedReport {
+    /// Total number of pages including cover and table of contents
+    pub page_count: usize,
+    /// Section titles with their 1-based starting page
+    pub section_pages: Vec<(String, usize)>,
+}
+
 /// Generate a compliance PDF report adhering to FDA documentation requirements.
 ///
-/// The document follows a simple single-page template containing:
-/// 1. Header with title and generation timestamp.
-/// 2. Body with compliance metrics table.
-/// 3. Footer with software version and immutable checksum placeholder.
-///
-/// The function is ACiD-safe (atomic file creation using a temporary file which is
-/// renamed on success) and idempotent (identical input → identical output).
+/// Produces a single "Compliance Metrics" section with running header and
+/// footer via [`render_report`].
 pub fn generate_compliance_report(cfg: &ComplianceReportConfig) -> Result<()> {
-    let tmp_path = cfg.output_path.with_extension("tmp");
+    let metrics = &cfg.metrics;
+    let report = ReportBuilder::new(cfg.title.unwrap_or("FDA Compliance Summary Report"))
+        .with_generated_on(cfg.generated_on)
+        .with_application_version(cfg.application_version)
+        .with_section(ReportSection::new("Compliance Metrics").key_values(vec![
+            ("Open CAPA Records", metrics.open_capa.to_string()),
+            ("Open High-Severity Risks", metrics.open_risks.to_string()),
+            ("Qualified Supplier %", format!("{:.1}%", metrics.qualified_supplier_pct)),
+            ("Training Completion %", format!("{:.1}%", metrics.training_completion_pct)),
+        ]))
+        .build();
+
+    render_report(&report, cfg.output_path).map(|_| ())
+}
+
+/// Build the standard chart set for a metrics snapshot:
+/// CAPA status distribution (bar), risk acceptability (pie) and supplier
+/// qualification mix (pie).
+pub fn metrics_charts(metrics: &MetricsResponse) -> Vec<Chart> {
+    let supplier = &metrics.supplier_metrics;
+    vec![
+        Chart::from_counts(
+            "CAPA Status Distribution",
+            ChartKind::Bar,
+            &metrics.capa_metrics.status_counts,
+        ),
+        Chart::from_counts(
+            "Risk Acceptability Distribution",
+            ChartKind::Pie,
+            &metrics.risk_report.acceptability_distribution,
+        ),
+        Chart {
+            title: "Supplier Qualification Mix".to_string(),
+            kind: ChartKind::Pie,
+            data: vec![
+                ChartDatum { label: "Qualified".to_string(), value: supplier.qualified_count as f32 },
+                ChartDatum { label: "Pending".to_string(), value: supplier.pending_count as f32 },
+                ChartDatum { label: "Disqualified".to_string(), value: supplier.disqualified_count as f32 },
+            ],
+        },
+    ]
+}
+
+/// Generate a compliance PDF report with embedded charts from a `MetricsResponse`.
+///
+/// Layout: a summary section followed by a charts section containing the CAPA
+/// status bar chart and the risk acceptability / supplier qualification pies.
+pub fn generate_metrics_report(cfg: &MetricsReportConfig) -> Result<()> {
+    render_report(&build_metrics_report(cfg), cfg.output_path).map(|_| ())
+}
+
+/// Report model behind [`generate_metrics_report`] (for signed or scheduled output).
+pub fn build_metrics_report(cfg: &MetricsReportConfig) -> Report {
+    let capa = &cfg.metrics.capa_metrics;
+    let risk = &cfg.metrics.risk_report;
+    let supplier = &cfg.metrics.supplier_metrics;
+
+    let summary = ReportSection::new("Summary").key_values(vec![
+        ("Total CAPA Records", capa.total_count.to_string()),
+        ("Open CAPA Records", capa.total_count.saturating_sub(capa.closed_count).to_string()),
+        ("Overdue CAPA Records", capa.overdue_count.to_string()),
+        ("Risk Assessments", risk.total_assessments.to_string()),
+        ("Pending Control Measures", risk.pending_control_measures.to_string()),
+        ("Risk Compliance Status", format!("{:?}", risk.compliance_status)),
+        ("Qualified Supplier %", format!("{:.1}%", supplier.qualified_percentage)),
+    ]);
+    let charts = metrics_charts(cfg.metrics)
+        .into_iter()
+        .fold(ReportSection::new("Distributions"), |section, chart| section.chart(chart));
+
+    let mut builder = ReportBuilder::new(cfg.title.unwrap_or("FDA Compliance Metrics Report"))
+        .with_generated_on(cfg.generated_on)
+        .with_application_version(cfg.application_version)
+        .with_section(summary);
+    if !cfg.metrics.kpis.is_empty() {
+        builder = builder.with_section(kpi_section(&cfg.metrics.kpis));
+    }
+    builder.with_section(charts).build()
+}
+
+/// KPI status against targets followed by the month-over-month trend, one
+/// column per month on record.
+pub fn kpi_section(kpis: &[KpiStatus]) -> ReportSection {
+    let percent = |value: f64| format!("{:.1}%", value);
+    let status = kpis.iter().fold(
+        ReportTable::new(vec!["KPI", "Target", "Actual", "Status", "Prior Month", "Change"])
+            .with_column_weights(vec![3.0, 1.5, 1.2, 1.2, 1.5, 1.5]),
+        |table, kpi| {
+            table.with_row(vec![
+                kpi.label.clone(),
+                kpi.target.describe(),
+                percent(kpi.actual),
+                kpi.status.as_str().to_uppercase(),
+                kpi.previous().map(percent).unwrap_or_else(|| "-".to_string()),
+                kpi.change().map(|change| format!("{:+.1} pts", change)).unwrap_or_else(|| "-".to_string()),
+            ])
+        },
+    );
+
+    let mut months: Vec<&str> =
+        kpis.iter().flat_map(|kpi| kpi.trend.iter().map(|point| point.month.as_str())).collect();
+    months.sort_unstable();
+    months.dedup();
+    let mut headers = vec!["KPI"];
+    headers.extend(months.iter().copied());
+    let trend = kpis.iter().fold(ReportTable::new(headers), |table, kpi| {
+        let mut row = vec![kpi.label.clone()];
+        row.extend(months.iter().map(|month| {
+            let point = kpi.trend.iter().find(|point| point.month == *month);
+            point.map(|point| percent(point.value)).unwrap_or_else(|| "-".to_string())
+        }));
+        table.with_row(row)
+    });
+
+    ReportSection::new("KPI Status").table(status).heading("Month-over-Month Trend").table(trend)
+}
+
+/// Render `report` to `output_path`.
+///
+/// The file is written to a temporary sibling and renamed on success so a
+/// partially written report never replaces a good one.
+pub fn render_report(report: &Report, output_path: &Path) -> Result<RenderedReport> {
+    let (pages, section_pages) = layout_report(report);
+    let page_count = pages.len();
+    let tmp_path = output_path.with_extension("tmp");
 
     // Create PDF; built-in fonts avoid external font dependencies.
-    let mut document = Pdf::create(&tmp_path).map_err(|e| QmsError::Application {
+    let mut document = Pdf::create(&tmp_path.to_string_lossy()).map_err(|e| QmsError::Application {
         message: format!("Failed to create PDF: {e}"),
     })?;
+    document.set_title(&metadata_text(&report.title));
+    document.set_creator("QMSrs");
+    document.set_producer(&format!("QMSrs {}", metadata_text(&report.application_version)));
+    if let Some(org) = &report.organization {
+        document.set_author(&metadata_text(org));
+    }
 
-    let title_text = cfg
-        .title
-        .unwrap_or("FDA Compliance Summary Report");
-
-    document.render_page(595.0, 842.0, |canvas| {
-        render_header(canvas, title_text, cfg.generated_on)?;
-        render_metrics_table(canvas, &cfg.metrics)?;
-        render_footer(canvas, cfg.application_version)?;
-        Ok(())
-    })?;
+    for (idx, page) in pages.iter().enumerate() {
+        document
+            .render_page(PAGE_WIDTH, PAGE_HEIGHT, |canvas| {
+                for title in &page.outline {
+                    canvas.add_outline(title);
+                }
+                for op in &page.ops {
+                    draw(canvas, op, report.locale)?;
+                }
+                if page.decorated {
+                    render_running_header(canvas, report)?;
+                    render_footer(canvas, report, idx + 1, page_count)?;
+                }
+                Ok(())
+            })
+            .map_err(|e| QmsError::Application {
+                message: format!("Failed to render PDF page {}: {e}", idx + 1),
+            })?;
+    }
 
     document.finish().map_err(|e| QmsError::Application {
         message: format!("Failed to finish PDF: {e}"),
     })?;
 
     // Atomic replace to ensure durability.
-    std::fs::rename(&tmp_path, cfg.output_path).map_err(|e| QmsError::FileSystem {
-        path: cfg.output_path.display().to_string(),
+    std::fs::rename(&tmp_path, output_path).map_err(|e| QmsError::FileSystem {
+        path: output_path.display().to_string(),
         message: e.to_string(),
     })?;
 
+    Ok(RenderedReport { page_count, section_pages })
+}
+
+/// PDF info dictionary strings are written unescaped by `pdf_canvas`.
+fn metadata_text(text: &str) -> String {
+    text.chars().filter(|c| !matches!(c, '(' | ')' | '\\')).collect()
+}
+
+// ---------------------------------------------------------------------------
+// Layout
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+/// Positioned drawing instruction produced by the layout pass.
+#[derive(Debug, Clone)]
+enum DrawOp {
+    Text { x: f32, y: f32, font: BuiltinFont, size: f32, align: Align, text: String },
+    Line { x1: f32, y1: f32, x2: f32, y2: f32 },
+    FillRect { x: f32, y: f32, width: f32, height: f32, gray: u8 },
+    Chart { chart: Chart, x: f32, y: f32, width: f32, height: f32 },
+    HeatMap { map: HeatMap, x: f32, y: f32, width: f32, height: f32 },
+}
+
+#[derive(Debug, Clone, Default)]
+struct PageLayout {
+    ops: Vec<DrawOp>,
+    /// PDF outline entries pointing at this page
+    outline: Vec<String>,
+    /// Whether running header and footer are drawn (false for the cover page)
+    decorated: bool,
+}
+
+/// Lay out every page up front so page numbers (TOC, "Page X of Y") are known
+/// before anything is written.
+fn layout_report(report: &Report) -> (Vec<PageLayout>, Vec<(String, usize)>) {
+    let toc_pages = if report.table_of_contents && !report.sections.is_empty() {
+        report.sections.len().div_ceil(toc_entries_per_page())
+    } else {
+        0
+    };
+    let front_matter = usize::from(report.cover_page) + toc_pages;
+
+    let mut flow = Flow::new(front_matter);
+    let mut section_pages = Vec::with_capacity(report.sections.len());
+    for section in &report.sections {
+        let page = flow.begin_section(section);
+        section_pages.push((section.title.clone(), page));
+        for block in &section.blocks {
+            flow.block(block);
+        }
+        flow.gap(12.0);
+    }
+
+    let mut pages = Vec::with_capacity(front_matter + flow.pages.len());
+    if report.cover_page {
+        pages.push(layout_cover(report));
+    }
+    if toc_pages > 0 {
+        pages.extend(layout_toc(&section_pages, report.locale));
+    }
+    pages.extend(flow.pages);
+    (pages, section_pages)
+}
+
+fn toc_entries_per_page() -> usize {
+    // First line is reserved for the "Table of Contents" heading.
+    (((CONTENT_TOP - CONTENT_BOTTOM) / TOC_LEADING) as usize).saturating_sub(2).max(1)
+}
+
+fn layout_cover(report: &Report) -> PageLayout {
+    let center = PAGE_WIDTH / 2.0;
+    let mut ops = Vec::new();
+    let mut y = 560.0;
+    for line in wrap_text(&report.title, BuiltinFont::Helvetica_Bold, 26.0, CONTENT_WIDTH) {
+        ops.push(text(center, y, BuiltinFont::Helvetica_Bold, 26.0, Align::Center, line));
+        y -= 32.0;
+    }
+    if let Some(subtitle) = &report.subtitle {
+        for line in wrap_text(subtitle, BuiltinFont::Helvetica, 16.0, CONTENT_WIDTH) {
+            ops.push(text(center, y, BuiltinFont::Helvetica, 16.0, Align::Center, line));
+            y -= 22.0;
+        }
+    }
+    ops.push(DrawOp::Line { x1: MARGIN_LEFT + 80.0, y1: y, x2: MARGIN_RIGHT - 80.0, y2: y });
+
+    let locale = report.locale;
+    let mut details = Vec::new();
+    if let Some(org) = &report.organization {
+        details.push(tr_args(locale, "report.organization", &[("name", org)]));
+    }
+    if let Some(prepared_by) = &report.prepared_by {
+        details.push(tr_args(locale, "report.prepared_by", &[("name", prepared_by)]));
+    }
+    details.push(generated_label(report));
+    details.push(tr_args(locale, "report.version", &[("version", &report.application_version)]));
+
+    let mut y = 220.0;
+    for line in details {
+        ops.push(text(center, y, BuiltinFont::Helvetica, 12.0, Align::Center, line));
+        y -= 18.0;
+    }
+    PageLayout { ops, outline: vec![tr(locale, "report.cover").to_string()], decorated: false }
+}
+
+/// "Generated: <timestamp>" in the report's locale and display time zone.
+fn generated_label(report: &Report) -> String {
+    let timestamp = report.display_timezone.format(report.generated_on);
+    tr_args(report.locale, "report.generated", &[("timestamp", &timestamp)])
+}
+
+fn layout_toc(section_pages: &[(String, usize)], locale: Locale) -> Vec<PageLayout> {
+    let title_width = CONTENT_WIDTH - 40.0;
+    section_pages
+        .chunks(toc_entries_per_page())
+        .enumerate()
+        .map(|(chunk_idx, entries)| {
+            let mut ops = vec![text(
+                MARGIN_LEFT,
+                CONTENT_TOP - 16.0,
+                BuiltinFont::Helvetica_Bold,
+                16.0,
+                Align::Left,
+                tr(locale, "report.table_of_contents").to_string(),
+            )];
+            let mut y = CONTENT_TOP - 16.0 - 2.0 * TOC_LEADING;
+            for (title, page) in entries {
+                let label = truncate_to_width(title, BuiltinFont::Helvetica, 11.0, title_width);
+                ops.push(text(MARGIN_LEFT, y, BuiltinFont::Helvetica, 11.0, Align::Left, label));
+                ops.push(text(MARGIN_RIGHT, y, BuiltinFont::Helvetica, 11.0, Align::Right, page.to_string()));
+                y -= TOC_LEADING;
+            }
+            let outline = if chunk_idx == 0 { vec![tr(locale, "report.table_of_contents").to_string()] } else { Vec::new() };
+            PageLayout { ops, outline, decorated: true }
+        })
+        .collect()
+}
+
+/// Top-to-bottom flow of content pages with automatic page breaks.
+struct Flow {
+    pages: Vec<PageLayout>,
+    /// Pages preceding the first content page (cover + TOC)
+    page_offset: usize,
+    /// Baseline cursor on the current page
+    y: f32,
+}
+
+impl Flow {
+    fn new(page_offset: usize) -> Self {
+        Self {
+            pages: vec![PageLayout { decorated: true, ..Default::default() }],
+            page_offset,
+            y: CONTENT_TOP,
+        }
+    }
+
+    /// Absolute 1-based number of the current page.
+    fn page_number(&self) -> usize {
+        self.page_offset + self.pages.len()
+    }
+
+    fn current(&mut self) -> &mut PageLayout {
+        self.pages.last_mut().expect("flow always has a page")
+    }
+
+    fn new_page(&mut self) {
+        self.pages.push(PageLayout { decorated: true, ..Default::default() });
+        self.y = CONTENT_TOP;
+    }
+
+    /// Break the page unless `height` points still fit above the bottom margin.
+    fn ensure(&mut self, height: f32) {
+        if self.y - height < CONTENT_BOTTOM && self.y < CONTENT_TOP {
+            self.new_page();
+        }
+    }
+
+    fn push(&mut self, op: DrawOp) {
+        self.current().ops.push(op);
+    }
+
+    fn gap(&mut self, points: f32) {
+        if self.y - points < CONTENT_BOTTOM {
+            self.new_page();
+        } else {
+            self.y -= points;
+        }
+    }
+
+    fn begin_section(&mut self, section: &ReportSection) -> usize {
+        let page_has_content = !self.current().ops.is_empty();
+        if section.page_break_before && page_has_content {
+            self.new_page();
+        } else {
+            // Keep the heading together with at least a few lines of content.
+            self.ensure(24.0 + 4.0 * BODY_LEADING);
+        }
+        self.y -= 16.0;
+        let y = self.y;
+        self.push(text(MARGIN_LEFT, y, BuiltinFont::Helvetica_Bold, 16.0, Align::Left, section.title.clone()));
+        self.push(DrawOp::Line { x1: MARGIN_LEFT, y1: y - 5.0, x2: MARGIN_RIGHT, y2: y - 5.0 });
+        self.current().outline.push(section.title.clone());
+        self.y -= 24.0;
+        self.page_number()
+    }
+
+    fn block(&mut self, block: &ReportBlock) {
+        match block {
+            ReportBlock::Heading(title) => {
+                self.ensure(18.0 + 2.0 * BODY_LEADING);
+                self.y -= 6.0;
+                let y = self.y;
+                self.push(text(MARGIN_LEFT, y, BuiltinFont::Helvetica_Bold, 12.0, Align::Left, title.clone()));
+                self.y -= 18.0;
+            }
+            ReportBlock::Paragraph(body) => {
+                for line in wrap_text(body, BuiltinFont::Helvetica, BODY_SIZE, CONTENT_WIDTH) {
+                    self.ensure(BODY_LEADING);
+                    let y = self.y;
+                    self.push(text(MARGIN_LEFT, y, BuiltinFont::Helvetica, BODY_SIZE, Align::Left, line));
+                    self.y -= BODY_LEADING;
+                }
+                self.y -= 4.0;
+            }
+            ReportBlock::KeyValue(pairs) => {
+                let value_width = CONTENT_WIDTH - KEY_VALUE_OFFSET;
+                for (key, value) in pairs {
+                    let lines = wrap_text(value, BuiltinFont::Helvetica, BODY_SIZE, value_width);
+                    let height = lines.len() as f32 * BODY_LEADING + 4.0;
+                    self.ensure(height);
+                    let y = self.y;
+                    let label = truncate_to_width(key, BuiltinFont::Helvetica_Bold, BODY_SIZE, KEY_VALUE_OFFSET - 10.0);
+                    self.push(text(MARGIN_LEFT, y, BuiltinFont::Helvetica_Bold, BODY_SIZE, Align::Left, label));
+                    for (idx, line) in lines.into_iter().enumerate() {
+                        let line_y = y - idx as f32 * BODY_LEADING;
+                        self.push(text(MARGIN_LEFT + KEY_VALUE_OFFSET, line_y, BuiltinFont::Helvetica, BODY_SIZE, Align::Left, line));
+                    }
+                    self.y = y - height;
+                }
+                self.y -= 4.0;
+            }
+            ReportBlock::Table(table) => self.table(table),
+            ReportBlock::Chart(chart) => {
+                let height = match chart.kind {
+                    ChartKind::Bar => BAR_CHART_HEIGHT,
+                    ChartKind::Pie => PIE_CHART_HEIGHT,
+                };
+                self.ensure(height + 10.0);
+                let bottom = self.y - height;
+                self.push(DrawOp::Chart { chart: chart.clone(), x: MARGIN_LEFT, y: bottom, width: CONTENT_WIDTH, height });
+                self.y = bottom - 14.0;
+            }
+            ReportBlock::HeatMap(map) => {
+                let height = heat_map_height(map);
+                self.ensure(height + 10.0);
+                let bottom = self.y - height;
+                self.push(DrawOp::HeatMap { map: map.clone(), x: MARGIN_LEFT, y: bottom, width: CONTENT_WIDTH, height });
+                self.y = bottom - 14.0;
+            }
+            ReportBlock::Spacer(points) => self.gap(*points),
+        }
+    }
+
+    fn table(&mut self, table: &ReportTable) {
+        let weights = table.normalized_weights();
+        let mut columns = Vec::with_capacity(weights.len());
+        let mut x = MARGIN_LEFT;
+        for weight in &weights {
+            let width = weight * CONTENT_WIDTH;
+            columns.push((x, width));
+            x += width;
+        }
+
+        let header = self.table_cells(&table.headers, &columns, BuiltinFont::Helvetica_Bold);
+        let header_height = row_height(&header);
+
+        self.ensure(header_height + TABLE_LEADING + 2.0 * TABLE_PADDING);
+        self.table_row(&header, &columns, header_height, true);
+
+        for row in &table.rows {
+            let cells = self.table_cells(row, &columns, BuiltinFont::Helvetica);
+            let height = row_height(&cells);
+            if self.y - height < CONTENT_BOTTOM {
+                self.new_page();
+                self.table_row(&header, &columns, header_height, true);
+            }
+            self.table_row(&cells, &columns, height, false);
+        }
+        self.y -= 8.0;
+    }
+
+    fn table_cells(&self, values: &[String], columns: &[(f32, f32)], font: BuiltinFont) -> Vec<Vec<String>> {
+        columns
+            .iter()
+            .enumerate()
+            .map(|(idx, (_, width))| {
+                let value = values.get(idx).map(String::as_str).unwrap_or("");
+                wrap_text(value, font, TABLE_SIZE, width - 2.0 * TABLE_PADDING)
+            })
+            .collect()
+    }
+
+    fn table_row(&mut self, cells: &[Vec<String>], columns: &[(f32, f32)], height: f32, header: bool) {
+        let top = self.y;
+        let font = if header { BuiltinFont::Helvetica_Bold } else { BuiltinFont::Helvetica };
+        if header {
+            self.push(DrawOp::FillRect { x: MARGIN_LEFT, y: top - height, width: CONTENT_WIDTH, height, gray: 225 });
+        }
+        for ((x, _), lines) in columns.iter().zip(cells) {
+            for (idx, line) in lines.iter().enumerate() {
+                let baseline = top - TABLE_PADDING - TABLE_SIZE - idx as f32 * TABLE_LEADING;
+                self.push(text(x + TABLE_PADDING, baseline, font, TABLE_SIZE, Align::Left, line.clone()));
+            }
+        }
+        self.push(DrawOp::Line { x1: MARGIN_LEFT, y1: top - height, x2: MARGIN_RIGHT, y2: top - height });
+        self.y = top - height;
+    }
+}
+
+/// Title and row-axis band + grid + column labels and axis caption.
+fn heat_map_height(map: &HeatMap) -> f32 {
+    32.0 + map.row_labels.len().max(1) as f32 * HEAT_CELL_HEIGHT + 30.0
+}
+
+fn row_height(cells: &[Vec<String>]) -> f32 {
+    let lines = cells.iter().map(Vec::len).max().unwrap_or(1).max(1);
+    lines as f32 * TABLE_LEADING + 2.0 * TABLE_PADDING
+}
+
+fn text(x: f32, y: f32, font: BuiltinFont, size: f32, align: Align, text: String) -> DrawOp {
+    DrawOp::Text { x, y, font, size, align, text }
+}
+
+/// Greedy word wrap using the built-in font metrics. Explicit newlines start
+/// new lines; words wider than `max_width` are split by character. Always
+/// returns at least one (possibly empty) line.
+fn wrap_text(text: &str, font: BuiltinFont, size: f32, max_width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.split('\n') {
+        let mut current = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if current.is_empty() { word.to_string() } else { format!("{} {}", current, word) };
+            if font.get_width(size, &candidate) <= max_width {
+                current = candidate;
+                continue;
+            }
+            if !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+            if font.get_width(size, word) <= max_width {
+                current = word.to_string();
+            } else {
+                for ch in word.chars() {
+                    current.push(ch);
+                    if font.get_width(size, &current) > max_width && current.chars().count() > 1 {
+                        current.pop();
+                        lines.push(std::mem::take(&mut current));
+                        current.push(ch);
+                    }
+                }
+            }
+        }
+        lines.push(current);
+    }
+    lines
+}
+
+/// Shorten `text` with an ellipsis so it fits in `max_width`.
+fn truncate_to_width(text: &str, font: BuiltinFont, size: f32, max_width: f32) -> String {
+    if font.get_width(size, text) <= max_width {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    for ch in text.chars() {
+        out.push(ch);
+        if font.get_width(size, &format!("{}...", out)) > max_width {
+            out.pop();
+            break;
+        }
+    }
+    format!("{}...", out)
+}
+
+// ---------------------------------------------------------------------------
+// Drawing
+// ---------------------------------------------------------------------------
+
+fn draw(canvas: &mut Canvas, op: &DrawOp, locale: Locale) -> io::Result<()> {
+    match op {
+        DrawOp::Text { x, y, font, size, align, text } => match align {
+            Align::Left => canvas.left_text(*x, *y, *font, *size, text),
+            Align::Right => canvas.right_text(*x, *y, *font, *size, text),
+            Align::Center => canvas.center_text(*x, *y, *font, *size, text),
+        },
+        DrawOp::Line { x1, y1, x2, y2 } => {
+            canvas.set_stroke_color(Color::gray(0))?;
+            canvas.line(*x1, *y1, *x2, *y2)
+        }
+        DrawOp::FillRect { x, y, width, height, gray } => {
+            canvas.set_fill_color(Color::gray(*gray))?;
+            canvas.rectangle(*x, *y, *width, *height)?;
+            canvas.fill()?;
+            canvas.set_fill_color(Color::gray(0))
+        }
+        DrawOp::Chart { chart, x, y, width, height } => {
+            canvas.gsave()?;
+            render_chart(canvas, chart, *x, *y, *width, *height, locale)?;
+            canvas.grestore()
+        }
+        DrawOp::HeatMap { map, x, y, width, height } => {
+            canvas.gsave()?;
+            render_heat_map(canvas, map, *x, *y, *width, *height)?;
+            canvas.grestore()
+        }
+    }
+}
+
+fn render_running_header(canvas: &mut Canvas, report: &Report) -> io::Result<()> {
+    let title = truncate_to_width(&report.title, BuiltinFont::Helvetica_Bold, 10.0, CONTENT_WIDTH - 140.0);
+    canvas.left_text(MARGIN_LEFT, 805.0, BuiltinFont::Helvetica_Bold, 10.0, &title)?;
+    canvas.right_text(MARGIN_RIGHT, 805.0, BuiltinFont::Helvetica, 9.0, &generated_label(report))?;
+    canvas.line(MARGIN_LEFT, 798.0, MARGIN_RIGHT, 798.0)?;
+    Ok(())
+}
+
+fn render_footer(canvas: &mut Canvas, report: &Report, page: usize, page_count: usize) -> io::Result<()> {
+    canvas.line(MARGIN_LEFT, 100.0, MARGIN_RIGHT, 100.0)?;
+    let footer_text = format!("QMSrs version {} | © 2025 QMS Development Team", report.application_version);
+    canvas.center_text(PAGE_WIDTH / 2.0, 85.0, BuiltinFont::Helvetica, 10.0, &footer_text)?;
+    if let Some(note) = &report.footer_note {
+        let note = truncate_to_width(note, BuiltinFont::Helvetica, 8.0, CONTENT_WIDTH - 80.0);
+        canvas.left_text(MARGIN_LEFT, 70.0, BuiltinFont::Helvetica, 8.0, &note)?;
+    }
+    let page_label = tr_args(report.locale, "report.page_of", &[("page", &page), ("count", &page_count)]);
+    canvas.right_text(MARGIN_RIGHT, 70.0, BuiltinFont::Helvetica, 9.0, &page_label)?;
     Ok(())
 }
 
-fn render_header(canvas: &mut Canvas, title: &str, ts: DateTime<Utc>) -> pdf_canvas::Result<()> {
-    let font = BuiltinFont::Helvetica_Bold;
-    canvas.left_text(50.0, 800.0, font, 24.0, title)?;
+fn palette_color(idx: usize) -> Color {
+    let (r, g, b) = CHART_PALETTE[idx % CHART_PALETTE.len()];
+    Color::rgb(r, g, b)
+}
+
+/// Render `chart` inside the box whose lower-left corner is (`x`, `y`).
+fn render_chart(
+    canvas: &mut Canvas,
+    chart: &Chart,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    locale: Locale,
+) -> io::Result<()> {
+    canvas.left_text(x, y + height, BuiltinFont::Helvetica_Bold, 12.0, &chart.title)?;
+    let body_height = height - 18.0;
+
+    if chart.total() <= 0.0 {
+        canvas.left_text(x, y + body_height / 2.0, BuiltinFont::Helvetica_Oblique, 10.0, tr(locale, "report.no_data"))?;
+        return Ok(());
+    }
+
+    match chart.kind {
+        ChartKind::Bar => render_bar_chart(canvas, chart, x, y, width, body_height),
+        ChartKind::Pie => render_pie_chart(canvas, chart, x, y, width, body_height),
+    }
+}
+
+fn render_bar_chart(canvas: &mut Canvas, chart: &Chart, x: f32, y: f32, width: f32, height: f32) -> io::Result<()> {
+    let label_band = 14.0;
+    let plot_bottom = y + label_band;
+    let plot_height = height - label_band - 12.0;
+    let max_value = chart.data.iter().map(|d| d.value).fold(0.0_f32, f32::max);
+    let slot = width / chart.data.len() as f32;
+    let bar_width = slot * 0.6;
+
+    // Axis
+    canvas.set_stroke_color(Color::gray(0))?;
+    canvas.line(x, plot_bottom, x + width, plot_bottom)?;
+
+    for (idx, datum) in chart.data.iter().enumerate() {
+        let bar_height = if max_value > 0.0 { datum.value / max_value * plot_height } else { 0.0 };
+        let bar_x = x + idx as f32 * slot + (slot - bar_width) / 2.0;
+        let center = bar_x + bar_width / 2.0;
+
+        canvas.set_fill_color(palette_color(idx))?;
+        canvas.rectangle(bar_x, plot_bottom, bar_width, bar_height)?;
+        canvas.fill()?;
+
+        canvas.set_fill_color(Color::gray(0))?;
+        canvas.center_text(center, plot_bottom + bar_height + 3.0, BuiltinFont::Helvetica, 9.0, &format!("{}", datum.value))?;
+        canvas.center_text(center, y, BuiltinFont::Helvetica, 9.0, &datum.label)?;
+    }
 
-    let subtitle = format!("Generated: {}", ts.format("%Y-%m-%d %H:%M UTC"));
-    canvas.left_text(50.0, 780.0, BuiltinFont::Helvetica, 12.0, &subtitle)?;
-    canvas.line(50.0, 775.0, 545.0, 775.0)?;
     Ok(())
 }
 
-fn render_metrics_table(canvas: &mut Canvas, metrics: &ComplianceMetrics) -> pdf_canvas::Result<()> {
-    let font_label = BuiltinFont::Helvetica_Bold;
-    let font_value = BuiltinFont::Helvetica;
+fn render_pie_chart(canvas: &mut Canvas, chart: &Chart, x: f32, y: f32, width: f32, height: f32) -> io::Result<()> {
+    let total = chart.total();
+    let radius = (height / 2.0).min(width / 4.0) - 4.0;
+    let cx = x + radius + 4.0;
+    let cy = y + height / 2.0;
+    let legend_x = cx + radius + 12.0;
 
-    let start_y = 740.0;
-    let line_height = 22.0;
+    let mut start = FRAC_PI_2;
+    for (idx, datum) in chart.data.iter().enumerate() {
+        let share = datum.value.max(0.0) / total;
+        let legend_y = y + height - 12.0 - idx as f32 * 14.0;
 
-    let rows = vec![
-        ("Open CAPA Records", metrics.open_capa.to_string()),
-        (
-            "Open High-Severity Risks",
-            metrics.open_risks.to_string(),
-        ),
-        (
-            "Qualified Supplier %",
-            format!("{:.1}%", metrics.qualified_supplier_pct),
-        ),
-        (
-            "Training Completion %",
-            format!("{:.1}%", metrics.training_completion_pct),
-        ),
-    ];
+        canvas.set_fill_color(palette_color(idx))?;
+        if share > 0.0 {
+            // Slices run clockwise from 12 o'clock.
+            let end = start - share * 2.0 * PI;
+            canvas.move_to(cx, cy)?;
+            canvas.line_to(cx + radius * start.cos(), cy + radius * start.sin())?;
+            for (a0, a1) in arc_segments(start, end) {
+                let (p1, p2, p3) = arc_bezier(cx, cy, radius, a0, a1);
+                canvas.curve_to(p1.0, p1.1, p2.0, p2.1, p3.0, p3.1)?;
+            }
+            canvas.line_to(cx, cy)?;
+            canvas.fill()?;
+            start = end;
+        }
 
-    for (idx, (label, value)) in rows.into_iter().enumerate() {
-        let y = start_y - (idx as f64 * line_height);
-        canvas.left_text(50.0, y, font_label, 12.0, label)?;
-        canvas.right_text(545.0, y, font_value, 12.0, &value)?;
+        canvas.rectangle(legend_x, legend_y, 8.0, 8.0)?;
+        canvas.fill()?;
+        canvas.set_fill_color(Color::gray(0))?;
+        let legend = format!("{} ({}, {:.0}%)", datum.label, datum.value, share * 100.0);
+        canvas.left_text(legend_x + 12.0, legend_y, BuiltinFont::Helvetica, 9.0, &legend)?;
     }
 
     Ok(())
 }
 
-fn render_footer(canvas: &mut Canvas, version: &str) -> pdf_canvas::Result<()> {
-    canvas.line(50.0, 100.0, 545.0, 100.0)?;
-    let footer_text = format!("QMSrs version {} | © 2025 QMS Development Team", version);
-    canvas.center_text(297.5, 85.0, BuiltinFont::Helvetica, 10.0, &footer_text)?;
+fn heat_color(level: HeatLevel) -> Color {
+    let (r, g, b) = level.rgb();
+    Color::rgb(r, g, b)
+}
+
+/// Render `map` inside the box whose lower-left corner is (`x`, `y`).
+fn render_heat_map(canvas: &mut Canvas, map: &HeatMap, x: f32, y: f32, width: f32, height: f32) -> io::Result<()> {
+    canvas.left_text(x, y + height - 12.0, BuiltinFont::Helvetica_Bold, 12.0, &map.title)?;
+    let columns = map.column_labels.len().max(1);
+    let cell_width = (width - HEAT_LABEL_WIDTH) / columns as f32;
+    let grid_left = x + HEAT_LABEL_WIDTH;
+    let grid_top = y + height - 32.0;
+    let grid_bottom = grid_top - map.row_labels.len() as f32 * HEAT_CELL_HEIGHT;
+
+    canvas.set_stroke_color(Color::gray(255))?;
+    for (row_idx, label) in map.row_labels.iter().enumerate() {
+        let cell_y = grid_top - (row_idx + 1) as f32 * HEAT_CELL_HEIGHT;
+        let text_y = cell_y + HEAT_CELL_HEIGHT / 2.0 - 3.0;
+        canvas.set_fill_color(Color::gray(0))?;
+        let label = truncate_to_width(label, BuiltinFont::Helvetica, 9.0, HEAT_LABEL_WIDTH - 8.0);
+        canvas.right_text(grid_left - 6.0, text_y, BuiltinFont::Helvetica, 9.0, &label)?;
+
+        for col_idx in 0..map.column_labels.len() {
+            let Some(cell) = map.cells.get(row_idx).and_then(|row| row.get(col_idx)) else {
+                continue;
+            };
+            let cell_x = grid_left + col_idx as f32 * cell_width;
+            canvas.set_fill_color(heat_color(cell.level))?;
+            canvas.rectangle(cell_x, cell_y, cell_width, HEAT_CELL_HEIGHT)?;
+            canvas.fill()?;
+            canvas.set_fill_color(Color::gray(0))?;
+            if cell.value > 0 {
+                let center = cell_x + cell_width / 2.0;
+                canvas.center_text(center, text_y, BuiltinFont::Helvetica_Bold, 10.0, &cell.value.to_string())?;
+            }
+        }
+    }
+
+    canvas.set_fill_color(Color::gray(0))?;
+    for (col_idx, label) in map.column_labels.iter().enumerate() {
+        let center = grid_left + (col_idx as f32 + 0.5) * cell_width;
+        let label = truncate_to_width(label, BuiltinFont::Helvetica, 9.0, cell_width - 4.0);
+        canvas.center_text(center, grid_bottom - 12.0, BuiltinFont::Helvetica, 9.0, &label)?;
+    }
+    canvas.center_text(grid_left + (width - HEAT_LABEL_WIDTH) / 2.0, y, BuiltinFont::Helvetica_Oblique, 9.0, &map.column_axis)?;
+    canvas.right_text(grid_left - 6.0, grid_top + 4.0, BuiltinFont::Helvetica_Oblique, 9.0, &map.row_axis)?;
     Ok(())
 }
 
+/// Split the arc from `start` to `end` (radians) into pieces of at most 90°,
+/// the largest span a single cubic Bézier approximates accurately.
+fn arc_segments(start: f32, end: f32) -> Vec<(f32, f32)> {
+    let sweep = end - start;
+    let count = (sweep.abs() / FRAC_PI_2).ceil().max(1.0) as usize;
+    let step = sweep / count as f32;
+    (0..count)
+        .map(|i| (start + step * i as f32, start + step * (i + 1) as f32))
+        .collect()
+}
+
+/// Control points and end point of the cubic Bézier approximating a circular arc.
+fn arc_bezier(cx: f32, cy: f32, r: f32, a0: f32, a1: f32) -> ((f32, f32), (f32, f32), (f32, f32)) {
+    let k = 4.0 / 3.0 * ((a1 - a0) / 4.0).tan();
+    let (s0, c0) = a0.sin_cos();
+    let (s1, c1) = a1.sin_cos();
+    (
+        (cx + r * (c0 - k * s0), cy + r * (s0 + k * c0)),
+        (cx + r * (c1 + k * s1), cy + r * (s1 - k * c1)),
+        (cx + r * c1, cy + r * s1),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::capa::CapaMetrics;
+    use std::collections::HashMap;
+    use crate::risk::{ComplianceStatus, RiskManagementReport};
+    use crate::supplier::SupplierMetrics;
+    use crate::kpi::{KpiMetric, KpiTarget, KpiTrendPoint, RagStatus};
+    use std::fs::File;
+    use std::io::Read;
     use tempfile::tempdir;
+    use uuid::Uuid;
+
+    fn assert_pdf_header(path: &Path) {
+        let mut f = File::open(path).unwrap();
+        let mut header = [0u8; 5];
+        f.read_exact(&mut header).unwrap();
+        assert_eq!(&header, b"%PDF-");
+    }
+
+    fn sample_metrics() -> MetricsResponse {
+        let mut status_counts = HashMap::new();
+        status_counts.insert("Open".to_string(), 3);
+        status_counts.insert("Closed".to_string(), 2);
+        status_counts.insert("Implementation".to_string(), 1);
+        let mut acceptability = HashMap::new();
+        acceptability.insert("Acceptable".to_string(), 4);
+        acceptability.insert("Unacceptable".to_string(), 1);
+
+        MetricsResponse {
+            capa_metrics: CapaMetrics {
+                total_count: 6,
+                status_counts,
+                priority_counts: HashMap::new(),
+                overdue_count: 1,
+                closed_count: 2,
+            },
+            risk_report: RiskManagementReport {
+                id: Uuid::new_v4(),
+                generated_at: Utc::now(),
+                generated_by: "tester".to_string(),
+                total_assessments: 5,
+                risk_level_distribution: HashMap::new(),
+                acceptability_distribution: acceptability,
+                pending_control_measures: 2,
+                compliance_status: ComplianceStatus::RequiresAttention,
+            },
+            supplier_metrics: SupplierMetrics {
+                total_count: 4,
+                qualified_count: 3,
+                pending_count: 1,
+                disqualified_count: 0,
+                qualified_percentage: 75.0,
+            },
+            kpis: vec![KpiStatus {
+                metric: KpiMetric::TrainingCompletion,
+                label: KpiMetric::TrainingCompletion.label().to_string(),
+                actual: 93.0,
+                target: KpiTarget::new(KpiMetric::TrainingCompletion, 95.0),
+                status: RagStatus::Amber,
+                trend: vec![
+                    KpiTrendPoint { month: "2025-02".to_string(), value: 90.5 },
+                    KpiTrendPoint { month: "2025-03".to_string(), value: 93.0 },
+                ],
+            }],
+        }
+    }
 
     #[test]
     fn test_generate_compliance_report() {
@@ -149,10 +966,189 @@ mod tests {
 
         generate_compliance_report(&cfg).expect("PDF generation should succeed");
         // Validate file exists and starts with %PDF- header
-        let mut f = File::open(&path).unwrap();
-        let mut header = [0u8; 5];
-        use std::io::Read;
-        f.read_exact(&mut header).unwrap();
-        assert_eq!(&header, b"%PDF-");
+        assert_pdf_header(&path);
+    }
+
+    #[test]
+    fn test_generate_metrics_report_with_charts() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("metrics_report.pdf");
+        let metrics = sample_metrics();
+
+        let cfg = MetricsReportConfig {
+            output_path: &path,
+            application_version: crate::APPLICATION_VERSION,
+            metrics: &metrics,
+            generated_on: Utc::now(),
+            title: None,
+        };
+
+        generate_metrics_report(&cfg).expect("PDF generation should succeed");
+        assert_pdf_header(&path);
+        assert!(!path.with_extension("tmp").exists());
+
+        // Vector chart operators: curves for pie slices, rectangles for bars.
+        let bytes = std::fs::read(&path).unwrap();
+        let content = String::from_utf8_lossy(&bytes);
+        assert!(content.contains(" c\n"), "pie slices should be drawn with Bezier curves");
+        assert!(content.contains(" re\n"), "bars should be drawn as rectangles");
+    }
+
+    #[test]
+    fn test_generate_metrics_report_without_data() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("empty_metrics.pdf");
+        let mut metrics = sample_metrics();
+        metrics.capa_metrics.status_counts.clear();
+        metrics.risk_report.acceptability_distribution.clear();
+        metrics.supplier_metrics = SupplierMetrics::default();
+
+        let cfg = MetricsReportConfig {
+            output_path: &path,
+            application_version: crate::APPLICATION_VERSION,
+            metrics: &metrics,
+            generated_on: Utc::now(),
+            title: Some("Empty Metrics"),
+        };
+        generate_metrics_report(&cfg).expect("empty charts should still render");
+        assert_pdf_header(&path);
+    }
+
+    #[test]
+    fn test_kpi_section_shows_status_and_trend() {
+        let section = kpi_section(&sample_metrics().kpis);
+        let tables: Vec<&ReportTable> = section
+            .blocks
+            .iter()
+            .filter_map(|block| match block {
+                ReportBlock::Table(table) => Some(table),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(tables.len(), 2);
+        assert_eq!(tables[0].rows[0], vec!["Training Completion", ">= 95.0%", "93.0%", "AMBER", "90.5%", "+2.5 pts"]);
+        assert_eq!(tables[1].headers, vec!["KPI", "2025-02", "2025-03"]);
+        assert_eq!(tables[1].rows[0], vec!["Training Completion", "90.5%", "93.0%"]);
+    }
+
+    #[test]
+    fn test_metrics_charts_are_sorted_and_complete() {
+        let charts = metrics_charts(&sample_metrics());
+        assert_eq!(charts.len(), 3);
+
+        assert_eq!(charts[0].kind, ChartKind::Bar);
+        let labels: Vec<&str> = charts[0].data.iter().map(|d| d.label.as_str()).collect();
+        assert_eq!(labels, vec!["Closed", "Implementation", "Open"]);
+        assert_eq!(charts[0].total(), 6.0);
+
+        assert_eq!(charts[1].kind, ChartKind::Pie);
+        assert_eq!(charts[1].total(), 5.0);
+        assert_eq!(charts[2].total(), 4.0);
+    }
+
+    fn page_objects(path: &Path) -> usize {
+        let bytes = std::fs::read(path).unwrap();
+        String::from_utf8_lossy(&bytes).matches("/Type /Page\n").count()
+    }
+
+    #[test]
+    fn test_render_report_paginates_long_table() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("audit_extract.pdf");
+
+        let table = (0..150).fold(
+            ReportTable::new(vec!["Timestamp", "User", "Action", "Details"])
+                .with_column_weights(vec![2.0, 1.0, 1.0, 3.0]),
+            |table, i| {
+                table.with_row(vec![
+                    format!("2025-01-01T00:{:02}:00Z", i % 60),
+                    "qa_user".to_string(),
+                    "UPDATE".to_string(),
+                    format!("Changed record {} with a reasonably long explanation that wraps", i),
+                ])
+            },
+        );
+        let report = ReportBuilder::new("Audit Trail Extract")
+            .with_section(ReportSection::new("Entries").table(table))
+            .build();
+
+        let rendered = render_report(&report, &path).unwrap();
+        assert!(rendered.page_count > 1, "150 rows must span several pages");
+        assert_eq!(page_objects(&path), rendered.page_count);
+        assert_eq!(rendered.section_pages, vec![("Entries".to_string(), 1)]);
+
+        let content = String::from_utf8_lossy(&std::fs::read(&path).unwrap()).to_string();
+        let last = format!("Page {} of {}", rendered.page_count, rendered.page_count);
+        assert!(content.contains(&last));
+    }
+
+    #[test]
+    fn test_cover_and_table_of_contents_numbering() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("capa_detail.pdf");
+
+        let report = ReportBuilder::new("CAPA Detail Report")
+            .with_subtitle("CAPA-2025-001")
+            .with_organization("Medical Device Company")
+            .with_prepared_by("qa_manager")
+            .with_cover_page()
+            .with_table_of_contents()
+            .with_section(ReportSection::new("Description").paragraph("Nonconforming seal strength."))
+            .with_section(ReportSection::new("Investigation").with_page_break().paragraph("Root cause analysis."))
+            .with_section(ReportSection::new("Actions").with_page_break().heading("Corrective").paragraph("Retrain."))
+            .build();
+
+        let rendered = render_report(&report, &path).unwrap();
+        // cover (1) + TOC (2) + one page per forced section
+        assert_eq!(rendered.page_count, 5);
+        assert_eq!(
+            rendered.section_pages,
+            vec![
+                ("Description".to_string(), 3),
+                ("Investigation".to_string(), 4),
+                ("Actions".to_string(), 5),
+            ]
+        );
+        assert_eq!(page_objects(&path), 5);
+    }
+
+    #[test]
+    fn test_sections_share_page_without_break() {
+        let report = ReportBuilder::new("Short")
+            .with_section(ReportSection::new("One").paragraph("a"))
+            .with_section(ReportSection::new("Two").paragraph("b"))
+            .build();
+        let (pages, section_pages) = layout_report(&report);
+        assert_eq!(pages.len(), 1);
+        assert_eq!(section_pages[1].1, 1);
+        assert_eq!(pages[0].outline, vec!["One".to_string(), "Two".to_string()]);
+    }
+
+    #[test]
+    fn test_wrap_text_respects_width() {
+        let text = "Corrective action plan approved by the quality review board after investigation";
+        let lines = wrap_text(text, BuiltinFont::Helvetica, 10.0, 120.0);
+        assert!(lines.len() > 1);
+        for line in &lines {
+            assert!(BuiltinFont::Helvetica.get_width(10.0, line) <= 120.0);
+        }
+        assert_eq!(lines.join(" "), text);
+
+        assert_eq!(wrap_text("", BuiltinFont::Helvetica, 10.0, 100.0), vec![String::new()]);
+        assert_eq!(wrap_text("a\nb", BuiltinFont::Helvetica, 10.0, 100.0), vec!["a", "b"]);
+
+        let long_word = "X".repeat(200);
+        assert!(wrap_text(&long_word, BuiltinFont::Helvetica, 10.0, 100.0).len() > 1);
+    }
+
+    #[test]
+    fn test_arc_segments_cover_sweep() {
+        let segments = arc_segments(FRAC_PI_2, FRAC_PI_2 - 2.0 * PI);
+        assert_eq!(segments.len(), 4);
+        assert!((segments[0].0 - FRAC_PI_2).abs() < 1e-6);
+        assert!((segments[3].1 - (FRAC_PI_2 - 2.0 * PI)).abs() < 1e-5);
+
+        let (_, _, end) = arc_bezier(0.0, 0.0, 10.0, 0.0, FRAC_PI_2);
+        assert!(end.0.abs() < 1e-4 && (end.1 - 10.0).abs() < 1e-4);
     }
 }
\ No newline at end of file
@@ -1,11 +1,14 @@
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::database::Database;
 use crate::error::{QmsError, Result};
+use crate::events::{EventBus, QmsEvent};
+use crate::site::DEFAULT_SITE_ID;
 
 /// Adverse event severity levels per FDA guidance.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Severity {
     Critical,
     Major,
@@ -38,79 +41,110 @@ impl AdverseEvent {
 /// Repository handling persistence of adverse events.
 pub struct AdverseEventRepo<'a> {
     db: &'a Database,
+    site_id: String,
+    events: Option<EventBus>,
 }
 
 impl<'a> AdverseEventRepo<'a> {
     pub fn new(db: &'a Database) -> Self {
-        Self { db }
+        Self { db, site_id: DEFAULT_SITE_ID.to_string(), events: None }
+    }
+
+    /// Publish `AdverseEventReported` on `events` after each insert.
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Scope the repository to events reported at `site_id`.
+    pub fn with_site<S: Into<String>>(mut self, site_id: S) -> Self {
+        self.site_id = site_id.into();
+        self
     }
 
     /// Persist a new adverse event entry.
     pub fn insert(&self, event: &AdverseEvent) -> Result<()> {
-        let conn = self.db.get_conn()?;
-        conn.execute(
-            "INSERT INTO adverse_events (id, reported_on, reporter, description, severity)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            (
-                event.id.to_string(),
-                event.reported_on.to_rfc3339(),
-                &event.reporter,
-                &event.description,
-                event.severity as i32,
-            ),
-        )?;
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO adverse_events (id, reported_on, reporter, description, severity, site_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                (
+                    event.id.to_string(),
+                    event.reported_on.to_rfc3339(),
+                    &event.reporter,
+                    &event.description,
+                    event.severity as i32,
+                    &self.site_id,
+                ),
+            )?;
+            Ok(())
+        })?;
+        if let Some(events) = &self.events {
+            events.publish(&QmsEvent::AdverseEventReported {
+                event_id: event.id,
+                reporter: event.reporter.clone(),
+                severity: event.severity,
+                reported_on: event.reported_on,
+            });
+        }
         Ok(())
     }
 
     /// Fetch an event by UUID.
     pub fn get(&self, id: Uuid) -> Result<AdverseEvent> {
-        let conn = self.db.get_conn()?;
-        let mut stmt = conn.prepare(
-            "SELECT id, reported_on, reporter, description, severity FROM adverse_events WHERE id = ?1",
-        )?;
-        let row = stmt.query_row((id.to_string(),), |row| {
-            Ok(AdverseEvent {
-                id: Uuid::parse_str(row.get::<_, String>(0)?.as_str()).map_err(|e| QmsError::Application { message: format!("Invalid UUID in DB: {e}") })?,
-                reported_on: DateTime::parse_from_rfc3339(row.get::<_, String>(1)?.as_str())
-                    .map_err(|e| QmsError::Application { message: format!("Invalid timestamp in DB: {e}") })?
-                    .with_timezone(&Utc),
-                reporter: row.get(2)?,
-                description: row.get(3)?,
-                severity: match row.get::<_, i32>(4)? {
-                    0 => Severity::Critical,
-                    1 => Severity::Major,
-                    _ => Severity::Minor,
-                },
-            })
-        })?;
-        Ok(row)
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, reported_on, reporter, description, severity FROM adverse_events
+                 WHERE id = ?1 AND site_id = ?2 AND deleted_at IS NULL",
+            )?;
+            match stmt.query_row((id.to_string(), &self.site_id), row_to_event) {
+                Ok(event) => Ok(event),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Err(QmsError::NotFound {
+                    resource: "adverse_event".to_string(),
+                    id: id.to_string(),
+                }),
+                Err(e) => Err(e.into()),
+            }
+        })
     }
 }
 
+fn row_to_event(row: &rusqlite::Row) -> rusqlite::Result<AdverseEvent> {
+    let id: String = row.get(0)?;
+    let reported_on: String = row.get(1)?;
+    Ok(AdverseEvent {
+        id: Uuid::parse_str(&id).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+        reported_on: DateTime::parse_from_rfc3339(&reported_on)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(e)))?
+            .with_timezone(&Utc),
+        reporter: row.get(2)?,
+        description: row.get(3)?,
+        severity: match row.get::<_, i32>(4)? {
+            0 => Severity::Critical,
+            1 => Severity::Major,
+            _ => Severity::Minor,
+        },
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::database::Database;
+    use crate::config::DatabaseConfig;
 
     #[test]
     fn test_insert_and_get_event() {
-        let db = Database::in_memory().unwrap();
-        db.initialize_schema().unwrap();
-        // add adverse_events table for tests
-        {
-            let conn = db.get_conn().unwrap();
-            conn.execute(
-                "CREATE TABLE IF NOT EXISTS adverse_events (
-                    id TEXT PRIMARY KEY,
-                    reported_on TEXT NOT NULL,
-                    reporter TEXT NOT NULL,
-                    description TEXT NOT NULL,
-                    severity INTEGER NOT NULL
-                )",
-                (),
-            )
-            .unwrap();
-        }
+        // adverse_events is created by schema migration 1
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
         let repo = AdverseEventRepo::new(&db);
         let event = AdverseEvent::new("tester", "failure mode detected", Severity::Major);
         repo.insert(&event).unwrap();
@@ -118,5 +152,33 @@ mod tests {
         let fetched = repo.get(event.id).unwrap();
         assert_eq!(fetched.description, "failure mode detected");
         assert_eq!(fetched.severity, Severity::Major);
+        assert!(repo.get(Uuid::new_v4()).is_err());
+    }
+
+    #[test]
+    fn test_insert_publishes_reported_event() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let events = EventBus::new();
+        let flagged = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = flagged.clone();
+        events.subscribe("risk_flagging", move |event: &QmsEvent| -> Result<()> {
+            if let QmsEvent::AdverseEventReported { event_id, severity: Severity::Critical, .. } = event {
+                sink.lock().unwrap().push(*event_id);
+            }
+            Ok(())
+        });
+        let repo = AdverseEventRepo::new(&db).with_events(events);
+        let critical = AdverseEvent::new("tester", "burn injury", Severity::Critical);
+        repo.insert(&critical).unwrap();
+        repo.insert(&AdverseEvent::new("tester", "cosmetic scratch", Severity::Minor)).unwrap();
+
+        assert_eq!(*flagged.lock().unwrap(), vec![critical.id]);
     }
-}
\ No newline at end of file
+}
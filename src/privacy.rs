@@ -0,0 +1,359 @@
+//! # Privacy - Data Subject Requests
+//!
+//! Supports GDPR access (Art. 15) and erasure (Art. 17) requests for people
+//! named in quality records, such as complaint reporters and employees.
+//! [`PrivacyService::locate`] finds every record naming the person,
+//! [`PrivacyService::access_report`] turns the result into a report for the
+//! data subject, and [`PrivacyService::redact`] overwrites the name where
+//! this is permitted. Quality evidence such as training records, audit trail
+//! entries and change history must be kept (21 CFR 820.180, Part 11 §11.10(e))
+//! and is reported but never redacted. Other records are redacted only once
+//! their retention period has elapsed and while no legal hold covers them. The audit trail identifies the data
+//! subject by a hash, never by name, so the request log does not itself
+//! re-identify the person.
+
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::audit::AuditManager;
+use crate::change_history::ChangeReason;
+use crate::config::ComplianceConfig;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::keystore::sha256_hex;
+use crate::legal_hold::LegalHoldService;
+use crate::report::{Report, ReportBuilder, ReportSection, ReportTable};
+
+/// Value written over a redacted name.
+pub const REDACTED: &str = "[redacted]";
+
+/// A column that can name a person.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PersonalDataSource {
+    pub table: &'static str,
+    pub column: &'static str,
+    id_column: &'static str,
+    /// Column the retention period is measured from
+    anchor: &'static str,
+    /// The person's role in the record
+    pub role: &'static str,
+    /// Why the column is never redacted, if it is not
+    pub retained_because: Option<&'static str>,
+}
+
+/// Columns searched for a data subject. Table and column names are
+/// interpolated into SQL, so only these are ever used.
+pub const PERSONAL_DATA_SOURCES: &[PersonalDataSource] = &[
+    PersonalDataSource {
+        table: "adverse_events",
+        column: "reporter",
+        id_column: "id",
+        anchor: "created_at",
+        role: "reporter",
+        retained_because: None,
+    },
+    PersonalDataSource {
+        table: "quality_intake",
+        column: "reported_by",
+        id_column: "id",
+        anchor: "reported_at",
+        role: "reporter",
+        retained_because: None,
+    },
+    PersonalDataSource {
+        table: "training_records",
+        column: "employee_id",
+        id_column: "id",
+        anchor: "created_at",
+        role: "employee",
+        retained_because: Some("training evidence (21 CFR 820.25)"),
+    },
+    PersonalDataSource {
+        table: "audit_findings",
+        column: "owner",
+        id_column: "id",
+        anchor: "raised_at",
+        role: "finding owner",
+        retained_because: Some("audit evidence (21 CFR 820.22)"),
+    },
+    PersonalDataSource {
+        table: "audit_trail",
+        column: "user_id",
+        id_column: "id",
+        anchor: "timestamp",
+        role: "actor",
+        retained_because: Some("audit trail (21 CFR 11.10(e))"),
+    },
+    PersonalDataSource {
+        table: "change_history",
+        column: "changed_by",
+        id_column: "change_id",
+        anchor: "changed_at",
+        role: "actor",
+        retained_because: Some("change history (21 CFR 11.10(e))"),
+    },
+];
+
+/// A record naming the data subject.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PersonalDataHit {
+    pub table: String,
+    pub column: String,
+    pub record_id: String,
+    pub role: String,
+    /// Retention anchor of the record as stored
+    pub recorded_at: String,
+    /// Days since `recorded_at`
+    pub age_days: i64,
+    /// Why the hit cannot be redacted now; `None` when it can
+    pub retention_block: Option<String>,
+}
+
+impl PersonalDataHit {
+    pub fn redactable(&self) -> bool {
+        self.retention_block.is_none()
+    }
+}
+
+/// Result of a redaction request.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct RedactionOutcome {
+    pub redacted: Vec<PersonalDataHit>,
+    pub retained: Vec<PersonalDataHit>,
+}
+
+/// Audit reference of a data subject: a hash of the normalized identifier.
+pub fn subject_reference(subject: &str) -> String {
+    format!("data_subject:{}", &sha256_hex(subject.trim().to_lowercase().as_bytes())[..16])
+}
+
+/// Locates, reports and redacts personal data.
+pub struct PrivacyService {
+    database: Database,
+    audit: AuditManager,
+    retention_days: u32,
+}
+
+impl PrivacyService {
+    pub fn new(database: Database, config: &ComplianceConfig) -> Self {
+        Self { audit: AuditManager::new(database.clone()), database, retention_days: config.audit_retention_days }
+    }
+
+    /// Every record naming `subject` (exact, case-insensitive match).
+    pub fn locate(&self, subject: &str, user_id: &str, now: DateTime<Utc>) -> Result<Vec<PersonalDataHit>> {
+        let hits = self.find(subject, now)?;
+        self.audit.log_action(
+            user_id,
+            "privacy_subject_located",
+            &subject_reference(subject),
+            "Success",
+            Some(format!("{} record(s) found", hits.len())),
+        )?;
+        Ok(hits)
+    }
+
+    /// Report listing the records that name `subject`, for an access request.
+    pub fn access_report(&self, subject: &str, user_id: &str, now: DateTime<Utc>) -> Result<Report> {
+        let hits = self.find(subject, now)?;
+        let table = hits.iter().fold(
+            ReportTable::new(vec!["Record", "Field", "Role", "Recorded", "Retention"])
+                .with_column_weights(vec![3.0, 1.5, 1.5, 2.0, 2.5]),
+            |table, hit| {
+                table.with_row(vec![
+                    format!("{}:{}", hit.table, hit.record_id),
+                    hit.column.clone(),
+                    hit.role.clone(),
+                    hit.recorded_at.clone(),
+                    hit.retention_block.clone().unwrap_or_else(|| "may be erased on request".to_string()),
+                ])
+            },
+        );
+        let report = ReportBuilder::new("Personal Data Access Report")
+            .with_subtitle(subject.trim())
+            .with_generated_on(now)
+            .with_prepared_by(user_id)
+            .with_section(ReportSection::new("Request").key_values(vec![
+                ("Data subject", subject.trim().to_string()),
+                ("Records found", hits.len().to_string()),
+                ("Retention period", format!("{} days", self.retention_days)),
+            ]))
+            .with_section(ReportSection::new("Records").table(table))
+            .build();
+        self.audit.log_action(
+            user_id,
+            "privacy_access_report",
+            &subject_reference(subject),
+            "Success",
+            Some(format!("{} record(s) reported", hits.len())),
+        )?;
+        Ok(report)
+    }
+
+    /// Overwrite `subject` with [`REDACTED`] wherever retention rules allow.
+    /// Records that must be kept are returned as retained.
+    pub fn redact(
+        &self,
+        subject: &str,
+        user_id: &str,
+        reason: &ChangeReason,
+        now: DateTime<Utc>,
+    ) -> Result<RedactionOutcome> {
+        let (redacted, retained): (Vec<_>, Vec<_>) =
+            self.find(subject, now)?.into_iter().partition(PersonalDataHit::redactable);
+        let reference = subject_reference(subject);
+        self.database.unit_of_work(|uow| {
+            for hit in &redacted {
+                let source = source(&hit.table, &hit.column)?;
+                uow.connection().execute(
+                    &format!("UPDATE {} SET {} = ?2 WHERE {} = ?1", source.table, source.column, source.id_column),
+                    params![hit.record_id, REDACTED],
+                )?;
+                self.audit.log_action_in(
+                    uow,
+                    user_id,
+                    "personal_data_redacted",
+                    &format!("{}:{}", hit.table, hit.record_id),
+                    "Success",
+                    Some(format!("{} redacted for {}; reason: {}", hit.column, reference, reason)),
+                )?;
+            }
+            self.audit.log_action_in(
+                uow,
+                user_id,
+                "privacy_redaction_request",
+                &reference,
+                if retained.is_empty() { "Success" } else { "Warning" },
+                Some(format!("{} record(s) redacted, {} retained; reason: {}", redacted.len(), retained.len(), reason)),
+            )
+        })?;
+        Ok(RedactionOutcome { redacted, retained })
+    }
+
+    fn find(&self, subject: &str, now: DateTime<Utc>) -> Result<Vec<PersonalDataHit>> {
+        let subject = subject.trim();
+        if subject.is_empty() {
+            return Err(QmsError::Validation {
+                field: "subject".to_string(),
+                message: "Data subject identifier is required".to_string(),
+            });
+        }
+        let holds = LegalHoldService::new(&self.database);
+        self.database.with_connection(|conn| {
+            let mut hits = Vec::new();
+            for source in PERSONAL_DATA_SOURCES {
+                let mut stmt = conn.prepare(&format!(
+                    "SELECT DISTINCT {id}, {anchor}, CAST(julianday(?2) - julianday({anchor}) AS INTEGER) FROM {table}
+                     WHERE lower(trim({column})) = lower(?1) ORDER BY {anchor}",
+                    id = source.id_column,
+                    anchor = source.anchor,
+                    table = source.table,
+                    column = source.column,
+                ))?;
+                let rows = stmt.query_map(params![subject, now.to_rfc3339()], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?))
+                })?;
+                for row in rows {
+                    let (record_id, recorded_at, age_days) = row?;
+                    let retention_block = match source.retained_because {
+                        Some(reason) => Some(format!("retained: {}", reason)),
+                        None => match holds.holds_on(source.table, &record_id)?.first() {
+                            Some(hold) => Some(format!("under legal hold: {}", hold.reason)),
+                            None if age_days < self.retention_days as i64 => {
+                                Some(format!("inside {}-day retention period", self.retention_days))
+                            }
+                            None => None,
+                        },
+                    };
+                    hits.push(PersonalDataHit {
+                        table: source.table.to_string(),
+                        column: source.column.to_string(),
+                        record_id,
+                        role: source.role.to_string(),
+                        recorded_at,
+                        age_days,
+                        retention_block,
+                    });
+                }
+            }
+            Ok(hits)
+        })
+    }
+}
+
+fn source(table: &str, column: &str) -> Result<&'static PersonalDataSource> {
+    PERSONAL_DATA_SOURCES.iter().find(|s| s.table == table && s.column == column).ok_or_else(|| QmsError::Validation {
+        field: "table".to_string(),
+        message: format!("{}.{} is not a personal data source", table, column),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::post_market::{AdverseEvent, AdverseEventRepo, Severity};
+    use crate::quality_intake::{IssueSource, QualityIntake};
+    use chrono::Duration;
+
+    fn setup() -> (Database, PrivacyService) {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let service = PrivacyService::new(db.clone(), &ComplianceConfig::default());
+        (db, service)
+    }
+
+    #[test]
+    fn test_locate_and_access_report() {
+        let (db, service) = setup();
+        AdverseEventRepo::new(&db).insert(&AdverseEvent::new("Jane Doe", "Rash", Severity::Minor)).unwrap();
+        AdverseEventRepo::new(&db).insert(&AdverseEvent::new("John Roe", "Burn", Severity::Major)).unwrap();
+        AuditManager::new(db.clone()).log_action("jane doe", "login", "session", "Success", None).unwrap();
+
+        let hits = service.locate(" JANE DOE ", "dpo", Utc::now()).unwrap();
+        let tables: Vec<&str> = hits.iter().map(|h| h.table.as_str()).collect();
+        assert_eq!(tables, vec!["adverse_events", "audit_trail"]);
+        assert!(hits.iter().all(|h| !h.redactable()), "new records are inside retention");
+
+        let report = service.access_report("Jane Doe", "dpo", Utc::now()).unwrap();
+        assert_eq!(report.sections.len(), 2);
+        let audit = db.get_audit_entries_for_resource(&subject_reference("jane doe")).unwrap();
+        assert_eq!(audit.len(), 2);
+        assert!(audit.iter().all(|e| !e.resource.contains("Jane")));
+        assert!(service.locate("  ", "dpo", Utc::now()).is_err());
+    }
+
+    #[test]
+    fn test_redaction_respects_retention() {
+        let (db, service) = setup();
+        let event = AdverseEvent::new("Jane Doe", "Rash", Severity::Minor);
+        AdverseEventRepo::new(&db).insert(&event).unwrap();
+        QualityIntake::new(&db).submit(IssueSource::Complaint, "Leaking seal", "Seal failed", "Jane Doe").unwrap();
+        let reason = ChangeReason::new("Erasure request DSR-2026-004").unwrap();
+
+        let early = service.redact("Jane Doe", "dpo", &reason, Utc::now()).unwrap();
+        assert!(early.redacted.is_empty());
+        assert_eq!(early.retained.len(), 2);
+
+        let later = Utc::now() + Duration::days(2556);
+        let holds = LegalHoldService::new(&db);
+        let hold = holds.place("adverse_events", None, "admin", &ChangeReason::new("Litigation hold").unwrap()).unwrap();
+        let held = service.redact("Jane Doe", "dpo", &reason, later).unwrap();
+        assert_eq!(held.redacted.len(), 1);
+        assert_eq!(held.retained[0].retention_block.as_deref(), Some("under legal hold: Litigation hold"));
+        holds.release(hold.id, "admin", &ChangeReason::new("Hold lifted").unwrap()).unwrap();
+
+        let outcome = service.redact("Jane Doe", "dpo", &reason, later).unwrap();
+        assert_eq!(outcome.redacted.len(), 1);
+        assert_eq!(AdverseEventRepo::new(&db).get(event.id).unwrap().reporter, REDACTED);
+        assert!(service.locate("Jane Doe", "dpo", later).unwrap().iter().all(|h| h.table == "audit_trail"));
+        let audit = db.get_audit_entries_for_resource(&format!("adverse_events:{}", event.id)).unwrap();
+        assert_eq!(audit[0].action, "personal_data_redacted");
+    }
+}
@@ -0,0 +1,305 @@
+//! # Quality Events - Critical Error Escalation
+//!
+//! [`QmsError::requires_fda_notification`] marks errors (audit trail and
+//! security failures) that may have to be assessed for reporting to the FDA.
+//! [`CriticalErrorHandler`] turns each such error into a persisted quality
+//! event, emails the quality manager and tracks who acknowledged the event
+//! and when, so no critical failure is left unassessed.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::notification::{EmailMessage, Notifier};
+
+/// A critical error awaiting (or having received) quality assessment.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityEvent {
+    pub id: Uuid,
+    pub error_code: String,
+    pub severity: String,
+    pub message: String,
+    /// Where the error surfaced, e.g. `api:/audit_trail/export`
+    pub context: String,
+    pub created_at: DateTime<Utc>,
+    /// Message id of the quality manager notification, once sent
+    pub notification_id: Option<String>,
+    pub acknowledged_by: Option<String>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+}
+
+impl QualityEvent {
+    pub fn from_error(error: &QmsError, context: &str) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            error_code: error.error_code().to_string(),
+            severity: error.severity().as_str().to_string(),
+            message: error.to_string(),
+            context: context.to_string(),
+            created_at: Utc::now(),
+            notification_id: None,
+            acknowledged_by: None,
+            acknowledged_at: None,
+        }
+    }
+
+    pub fn is_acknowledged(&self) -> bool {
+        self.acknowledged_at.is_some()
+    }
+}
+
+/// Persistence for quality events.
+pub struct QualityEventRepo<'a> {
+    db: &'a Database,
+}
+
+impl<'a> QualityEventRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    pub fn insert(&self, event: &QualityEvent) -> Result<()> {
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO quality_events
+                     (id, error_code, severity, message, context, created_at, notification_id)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    event.id.to_string(),
+                    event.error_code,
+                    event.severity,
+                    event.message,
+                    event.context,
+                    event.created_at.to_rfc3339(),
+                    event.notification_id,
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn get(&self, id: Uuid) -> Result<QualityEvent> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM quality_events WHERE id = ?1", COLUMNS),
+                        params![id.to_string()],
+                        row_to_event,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "quality_event".to_string(), id: id.to_string() })
+    }
+
+    /// Events nobody has acknowledged yet, oldest first.
+    pub fn unacknowledged(&self) -> Result<Vec<QualityEvent>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM quality_events WHERE acknowledged_at IS NULL ORDER BY created_at",
+                COLUMNS
+            ))?;
+            let events = stmt.query_map([], row_to_event)?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(events)
+        })
+    }
+
+    fn set_notification(&self, id: Uuid, notification_id: &str) -> Result<()> {
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "UPDATE quality_events SET notification_id = ?2 WHERE id = ?1",
+                params![id.to_string(), notification_id],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Record that `user_id` has assessed the event; audited in the same
+    /// transaction. Fails if the event was already acknowledged.
+    pub fn acknowledge(&self, id: Uuid, user_id: &str) -> Result<QualityEvent> {
+        let mut event = self.get(id)?;
+        if event.is_acknowledged() {
+            return Err(QmsError::Validation {
+                field: "acknowledged_at".to_string(),
+                message: format!("Quality event {} was already acknowledged", id),
+            });
+        }
+        let now = Utc::now();
+        let audit = AuditManager::new(self.db.clone());
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE quality_events SET acknowledged_by = ?2, acknowledged_at = ?3 WHERE id = ?1",
+                params![id.to_string(), user_id, now.to_rfc3339()],
+            )?;
+            audit.log_action_in(
+                uow,
+                user_id,
+                "quality_event_acknowledged",
+                &format!("quality_event:{}", id),
+                "Success",
+                Some(serde_json::json!({ "error_code": event.error_code }).to_string()),
+            )
+        })?;
+        event.acknowledged_by = Some(user_id.to_string());
+        event.acknowledged_at = Some(now);
+        Ok(event)
+    }
+}
+
+const COLUMNS: &str =
+    "id, error_code, severity, message, context, created_at, notification_id, acknowledged_by, acknowledged_at";
+
+fn parse_time(row: &Row, index: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+        })
+        .transpose()
+}
+
+fn row_to_event(row: &Row) -> rusqlite::Result<QualityEvent> {
+    let id: String = row.get(0)?;
+    Ok(QualityEvent {
+        id: Uuid::parse_str(&id)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))?,
+        error_code: row.get(1)?,
+        severity: row.get(2)?,
+        message: row.get(3)?,
+        context: row.get(4)?,
+        created_at: parse_time(row, 5)?.unwrap_or_default(),
+        notification_id: row.get(6)?,
+        acknowledged_by: row.get(7)?,
+        acknowledged_at: parse_time(row, 8)?,
+    })
+}
+
+/// Escalates errors that require FDA notification assessment.
+pub struct CriticalErrorHandler<'a> {
+    db: &'a Database,
+    notifier: Option<&'a dyn Notifier>,
+    quality_managers: Vec<String>,
+}
+
+impl<'a> CriticalErrorHandler<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, notifier: None, quality_managers: Vec::new() }
+    }
+
+    /// Email new quality events to `quality_managers` through `notifier`.
+    pub fn with_notifier(mut self, notifier: &'a dyn Notifier, quality_managers: Vec<String>) -> Self {
+        self.notifier = Some(notifier);
+        self.quality_managers = quality_managers;
+        self
+    }
+
+    /// Record a quality event for `error` if it requires FDA notification
+    /// and notify the quality manager. Returns `None` for other errors. A
+    /// failed notification is logged; the event stays unacknowledged either
+    /// way.
+    pub fn handle(&self, error: &QmsError, context: &str) -> Result<Option<QualityEvent>> {
+        if !error.requires_fda_notification() {
+            return Ok(None);
+        }
+        let mut event = QualityEvent::from_error(error, context);
+        let repo = QualityEventRepo::new(self.db);
+        repo.insert(&event)?;
+        tracing::error!(quality_event = %event.id, code = %event.error_code, "critical error recorded as quality event");
+
+        if let Some(notifier) = self.notifier.filter(|_| !self.quality_managers.is_empty()) {
+            let message = EmailMessage::new(
+                self.quality_managers.clone(),
+                format!("[QMS] Critical error {} requires assessment", event.error_code),
+                format!(
+                    "A critical error was recorded as quality event {}.\n\nContext: {}\nError: {}\nRecorded: {}\n\n\
+                     Assess whether FDA notification is required and acknowledge the event.",
+                    event.id,
+                    event.context,
+                    event.message,
+                    event.created_at.to_rfc3339()
+                ),
+            );
+            match notifier.send(&message) {
+                Ok(message_id) => {
+                    repo.set_notification(event.id, &message_id)?;
+                    event.notification_id = Some(message_id);
+                }
+                Err(e) => tracing::warn!(quality_event = %event.id, "quality manager notification failed: {e}"),
+            }
+        }
+        Ok(Some(event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use std::sync::Mutex;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    struct Capture(Mutex<Vec<EmailMessage>>);
+
+    impl Notifier for Capture {
+        fn send(&self, message: &EmailMessage) -> Result<String> {
+            self.0.lock().unwrap().push(message.clone());
+            Ok("msg-1".to_string())
+        }
+    }
+
+    #[test]
+    fn test_critical_error_creates_event_and_notifies() {
+        let db = test_db();
+        let capture = Capture(Mutex::new(Vec::new()));
+        let handler = CriticalErrorHandler::new(&db).with_notifier(&capture, vec!["qm@example.com".to_string()]);
+
+        let ignored = QmsError::Validation { field: "title".to_string(), message: "empty".to_string() };
+        assert!(handler.handle(&ignored, "capa form").unwrap().is_none());
+
+        let error = QmsError::AuditTrail { message: "hash chain broken".to_string() };
+        let event = handler.handle(&error, "integrity check").unwrap().unwrap();
+        assert_eq!(event.error_code, "AUDIT_ERROR");
+        assert_eq!(event.severity, "CRITICAL");
+        assert_eq!(event.notification_id.as_deref(), Some("msg-1"));
+
+        let sent = capture.0.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, vec!["qm@example.com".to_string()]);
+        assert!(sent[0].body.contains("hash chain broken"));
+        assert_eq!(QualityEventRepo::new(&db).unacknowledged().unwrap(), vec![event]);
+    }
+
+    #[test]
+    fn test_acknowledgement_is_tracked_and_audited() {
+        let db = test_db();
+        let error = QmsError::Security { message: "signature key missing".to_string() };
+        let event = CriticalErrorHandler::new(&db).handle(&error, "startup").unwrap().unwrap();
+        assert!(event.notification_id.is_none());
+
+        let repo = QualityEventRepo::new(&db);
+        let acknowledged = repo.acknowledge(event.id, "qa_manager").unwrap();
+        assert_eq!(acknowledged.acknowledged_by.as_deref(), Some("qa_manager"));
+        assert_eq!(repo.get(event.id).unwrap(), acknowledged);
+        assert!(repo.unacknowledged().unwrap().is_empty());
+        assert!(repo.acknowledge(event.id, "qa_manager").is_err());
+
+        let audit = db.get_audit_entries_for_resource(&format!("quality_event:{}", event.id)).unwrap();
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0].action, "quality_event_acknowledged");
+    }
+}
@@ -0,0 +1,407 @@
+//! # Quality Intake - Single Entry Point for Quality Issues
+//!
+//! Complaints, nonconformances (NCRs), audit findings and internal
+//! observations are all submitted as an [`IntakeItem`]. Triage then routes
+//! each item into the downstream process that handles it – a CAPA, an
+//! adverse event (post-market surveillance) or change control – or closes it
+//! with a documented no-action rationale (ISO 13485 §8.2.2, §8.3).
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::capa::{CapaPriority, CapaService, CapaType};
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::links::{link_with, LinkType, RecordRef};
+use crate::numbering::{NumberingRepo, NCR_SCOPE};
+use crate::post_market::{AdverseEvent, AdverseEventRepo, Severity};
+use crate::site::DEFAULT_SITE_ID;
+
+/// Where an issue was raised.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueSource {
+    Complaint,
+    Nonconformance,
+    AuditFinding,
+    InternalObservation,
+}
+
+impl IssueSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IssueSource::Complaint => "complaint",
+            IssueSource::Nonconformance => "nonconformance",
+            IssueSource::AuditFinding => "audit_finding",
+            IssueSource::InternalObservation => "internal_observation",
+        }
+    }
+
+    /// Numbering scope of intake records from this source
+    pub fn numbering_scope(&self) -> &'static str {
+        match self {
+            IssueSource::Nonconformance => NCR_SCOPE,
+            other => other.as_str(),
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [
+            IssueSource::Complaint,
+            IssueSource::Nonconformance,
+            IssueSource::AuditFinding,
+            IssueSource::InternalObservation,
+        ]
+        .into_iter()
+        .find(|source| source.as_str() == value)
+    }
+}
+
+/// Downstream process chosen at triage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "process", rename_all = "snake_case")]
+pub enum Route {
+    /// Open a CAPA assigned to `assigned_to`
+    Capa { capa_type: CapaType, priority: CapaPriority, assigned_to: String },
+    /// Record an adverse event for post-market surveillance
+    AdverseEvent { severity: Severity },
+    /// Hand over to change control (tracked by the intake item only)
+    ChangeControl,
+    /// Close without further action; the rationale is mandatory
+    NoAction,
+}
+
+impl Route {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Route::Capa { .. } => "capa",
+            Route::AdverseEvent { .. } => "adverse_event",
+            Route::ChangeControl => "change_control",
+            Route::NoAction => "no_action",
+        }
+    }
+}
+
+/// An issue submitted for triage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IntakeItem {
+    pub id: Uuid,
+    /// Human-readable number, e.g. `NCR-2025-003`
+    pub record_number: String,
+    pub source: IssueSource,
+    pub title: String,
+    pub description: String,
+    pub reported_by: String,
+    pub reported_at: DateTime<Utc>,
+    /// Process the item was routed to (`capa`, `adverse_event`, ...); `None` while awaiting triage
+    pub routed_to: Option<String>,
+    /// Id of the CAPA or adverse event created by triage
+    pub routed_record_id: Option<String>,
+    pub triaged_by: Option<String>,
+    pub triaged_at: Option<DateTime<Utc>>,
+    pub triage_rationale: Option<String>,
+    /// Defect or cause category counted in Pareto analyses
+    pub cause_category: Option<String>,
+}
+
+impl IntakeItem {
+    pub fn is_triaged(&self) -> bool {
+        self.triaged_at.is_some()
+    }
+}
+
+/// Intake and triage of quality issues.
+pub struct QualityIntake<'a> {
+    db: &'a Database,
+    capa_service: Option<&'a CapaService>,
+}
+
+impl<'a> QualityIntake<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, capa_service: None }
+    }
+
+    /// Service used to open CAPAs for items routed to [`Route::Capa`].
+    pub fn with_capa_service(mut self, capa_service: &'a CapaService) -> Self {
+        self.capa_service = Some(capa_service);
+        self
+    }
+
+    /// Submit an issue for triage.
+    pub fn submit(&self, source: IssueSource, title: &str, description: &str, reported_by: &str) -> Result<IntakeItem> {
+        if title.trim().is_empty() {
+            return Err(QmsError::Validation { field: "title".to_string(), message: "Title is required".to_string() });
+        }
+        let record_number = NumberingRepo::new(self.db).next_number(source.numbering_scope(), DEFAULT_SITE_ID)?;
+        let item = IntakeItem {
+            id: Uuid::new_v4(),
+            record_number,
+            source,
+            title: title.to_string(),
+            description: description.to_string(),
+            reported_by: reported_by.to_string(),
+            reported_at: Utc::now(),
+            routed_to: None,
+            routed_record_id: None,
+            triaged_by: None,
+            triaged_at: None,
+            triage_rationale: None,
+            cause_category: None,
+        };
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO quality_intake (id, record_number, source, title, description, reported_by, reported_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    item.id.to_string(),
+                    item.record_number,
+                    item.source.as_str(),
+                    item.title,
+                    item.description,
+                    item.reported_by,
+                    item.reported_at.to_rfc3339(),
+                ],
+            )?;
+            Ok(())
+        })?;
+        AuditManager::new(self.db.clone()).log_action(
+            reported_by,
+            "quality_issue_submitted",
+            &format!("quality_intake:{}", item.id),
+            "Success",
+            Some(format!("{} {}: {}", source.as_str(), item.record_number, title)),
+        )?;
+        Ok(item)
+    }
+
+    pub fn get(&self, id: Uuid) -> Result<IntakeItem> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM quality_intake WHERE id = ?1", COLUMNS),
+                        params![id.to_string()],
+                        row_to_item,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "quality_intake".to_string(), id: id.to_string() })
+    }
+
+    /// Items awaiting triage, oldest first.
+    pub fn awaiting_triage(&self) -> Result<Vec<IntakeItem>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM quality_intake WHERE triaged_at IS NULL ORDER BY reported_at",
+                COLUMNS
+            ))?;
+            let items = stmt.query_map([], row_to_item)?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(items)
+        })
+    }
+
+    /// Route an item into its downstream process, creating the CAPA or
+    /// adverse event it leads to. Each item is triaged once.
+    pub fn triage(&self, id: Uuid, route: Route, triaged_by: &str, rationale: &str) -> Result<IntakeItem> {
+        let mut item = self.get(id)?;
+        if item.is_triaged() {
+            return Err(QmsError::Validation {
+                field: "triaged_at".to_string(),
+                message: format!("Quality issue {} was already triaged", id),
+            });
+        }
+        if route == Route::NoAction && rationale.trim().is_empty() {
+            return Err(QmsError::Validation {
+                field: "triage_rationale".to_string(),
+                message: "Closing an issue without action requires a rationale".to_string(),
+            });
+        }
+
+        let routed_record_id = match &route {
+            Route::Capa { capa_type, priority, assigned_to } => {
+                let service = self.capa_service.ok_or_else(|| QmsError::Configuration {
+                    message: "Quality intake has no CAPA service to route to".to_string(),
+                })?;
+                let capa = service.create_capa(
+                    item.title.clone(),
+                    format!("{}\n\nRaised from {} {}", item.description, item.source.as_str(), item.record_number),
+                    capa_type.clone(),
+                    priority.clone(),
+                    triaged_by.to_string(),
+                    assigned_to.clone(),
+                    None,
+                )?;
+                Some(capa.id)
+            }
+            Route::AdverseEvent { severity } => {
+                let event = AdverseEvent::new(item.reported_by.clone(), item.description.clone(), *severity);
+                AdverseEventRepo::new(self.db).insert(&event)?;
+                Some(event.id.to_string())
+            }
+            Route::ChangeControl | Route::NoAction => None,
+        };
+
+        let now = Utc::now();
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "UPDATE quality_intake
+                 SET routed_to = ?2, routed_record_id = ?3, triaged_by = ?4, triaged_at = ?5, triage_rationale = ?6
+                 WHERE id = ?1",
+                params![id.to_string(), route.as_str(), routed_record_id, triaged_by, now.to_rfc3339(), rationale],
+            )?;
+            if let Some(record_id) = &routed_record_id {
+                let routed = RecordRef::new(route.as_str(), record_id.as_str());
+                link_with(conn, &routed, &RecordRef::new("quality_intake", id.to_string()), LinkType::DerivedFrom, triaged_by, now)?;
+            }
+            Ok(())
+        })?;
+        AuditManager::new(self.db.clone()).log_action(
+            triaged_by,
+            "quality_issue_triaged",
+            &format!("quality_intake:{}", id),
+            "Success",
+            Some(serde_json::json!({ "route": route, "record": routed_record_id, "rationale": rationale }).to_string()),
+        )?;
+
+        item.routed_to = Some(route.as_str().to_string());
+        item.routed_record_id = routed_record_id;
+        item.triaged_by = Some(triaged_by.to_string());
+        item.triaged_at = Some(now);
+        item.triage_rationale = Some(rationale.to_string());
+        Ok(item)
+    }
+
+    /// Assign the defect or cause category the item is counted under in
+    /// Pareto analyses. It may be revised as the investigation progresses.
+    pub fn categorize(&self, id: Uuid, category: &str, categorized_by: &str) -> Result<IntakeItem> {
+        let category = category.trim();
+        if category.is_empty() {
+            return Err(QmsError::Validation {
+                field: "cause_category".to_string(),
+                message: "Cause category is required".to_string(),
+            });
+        }
+        let mut item = self.get(id)?;
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "UPDATE quality_intake SET cause_category = ?2 WHERE id = ?1",
+                params![id.to_string(), category],
+            )?;
+            Ok(())
+        })?;
+        AuditManager::new(self.db.clone()).log_action(
+            categorized_by,
+            "quality_issue_categorized",
+            &format!("quality_intake:{}", id),
+            "Success",
+            Some(format!("{} -> {}", item.cause_category.as_deref().unwrap_or("-"), category)),
+        )?;
+        item.cause_category = Some(category.to_string());
+        Ok(item)
+    }
+}
+
+const COLUMNS: &str = "id, source, title, description, reported_by, reported_at, routed_to, routed_record_id,
+     triaged_by, triaged_at, triage_rationale, record_number, cause_category";
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn parse_time(row: &Row, index: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| conversion_error(index, e.to_string()))
+        })
+        .transpose()
+}
+
+fn row_to_item(row: &Row) -> rusqlite::Result<IntakeItem> {
+    let id: String = row.get(0)?;
+    let source: String = row.get(1)?;
+    Ok(IntakeItem {
+        id: Uuid::parse_str(&id).map_err(|e| conversion_error(0, e.to_string()))?,
+        source: IssueSource::parse(&source).ok_or_else(|| conversion_error(1, format!("unknown source {}", source)))?,
+        title: row.get(2)?,
+        description: row.get(3)?,
+        reported_by: row.get(4)?,
+        reported_at: parse_time(row, 5)?.unwrap_or_default(),
+        routed_to: row.get(6)?,
+        routed_record_id: row.get(7)?,
+        triaged_by: row.get(8)?,
+        triaged_at: parse_time(row, 9)?,
+        triage_rationale: row.get(10)?,
+        record_number: row.get::<_, Option<String>>(11)?.unwrap_or_default(),
+        cause_category: row.get(12)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_triage_routes_into_capa_and_adverse_event() {
+        let db = test_db();
+        let capa_service = CapaService::new(AuditManager::new(db.clone()));
+        let intake = QualityIntake::new(&db).with_capa_service(&capa_service);
+
+        let ncr = intake.submit(IssueSource::Nonconformance, "Seal failure", "Lot 42 failed peel test", "qe1").unwrap();
+        let complaint = intake.submit(IssueSource::Complaint, "Burn", "Patient reported a burn", "support").unwrap();
+        let year = Utc::now().format("%Y");
+        assert_eq!(ncr.record_number, format!("NCR-{}-001", year));
+        assert_eq!(complaint.record_number, format!("CMP-{}-001", year));
+        assert_eq!(intake.awaiting_triage().unwrap().len(), 2);
+
+        let route = Route::Capa { capa_type: CapaType::Corrective, priority: CapaPriority::High, assigned_to: "eng1".to_string() };
+        let routed = intake.triage(ncr.id, route, "qa_manager", "Systemic seal issue").unwrap();
+        assert_eq!(routed.routed_to.as_deref(), Some("capa"));
+        let capa = RecordRef::new("capa", routed.routed_record_id.clone().unwrap());
+        let links = crate::links::LinkRepo::new(&db).links_of(&capa).unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].link_type, LinkType::DerivedFrom);
+        assert_eq!(links[0].target, RecordRef::new("quality_intake", ncr.id.to_string()));
+
+        let routed = intake
+            .triage(complaint.id, Route::AdverseEvent { severity: Severity::Major }, "qa_manager", "Injury")
+            .unwrap();
+        let event_id = Uuid::parse_str(routed.routed_record_id.as_deref().unwrap()).unwrap();
+        assert_eq!(AdverseEventRepo::new(&db).get(event_id).unwrap().severity, Severity::Major);
+
+        assert!(intake.awaiting_triage().unwrap().is_empty());
+        assert_eq!(intake.get(complaint.id).unwrap(), routed);
+        assert!(intake.triage(ncr.id, Route::ChangeControl, "qa_manager", "again").is_err());
+        assert_eq!(db.get_audit_entries_for_resource(&format!("quality_intake:{}", ncr.id)).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_no_action_requires_rationale() {
+        let db = test_db();
+        let intake = QualityIntake::new(&db);
+        let item = intake.submit(IssueSource::InternalObservation, "Label smudge", "Cosmetic only", "op1").unwrap();
+
+        assert!(intake.triage(item.id, Route::NoAction, "qa_manager", " ").is_err());
+        let route = Route::Capa { capa_type: CapaType::Preventive, priority: CapaPriority::Low, assigned_to: "eng1".to_string() };
+        assert!(matches!(intake.triage(item.id, route, "qa_manager", "x"), Err(QmsError::Configuration { .. })));
+        let closed = intake.triage(item.id, Route::NoAction, "qa_manager", "Cosmetic, within spec").unwrap();
+        assert_eq!(closed.routed_to.as_deref(), Some("no_action"));
+        assert!(closed.routed_record_id.is_none());
+    }
+}
@@ -0,0 +1,234 @@
+//! # Read Cache - In-Process Cache for Hot Read Paths
+//!
+//! Every API request resolves the caller's role, and document pages list the
+//! revisions in effect at a site. Both are read far more often than they
+//! change, so [`ReadCache`] keeps recent results in memory. Every pooled
+//! connection is [watched](ReadCache::watch): its update hook notes the
+//! tables a transaction inserts into, updates or deletes from, and its commit
+//! hook drops the entries derived from them, whichever module made the
+//! write. Invalidating only at commit keeps a read of the old rows, made
+//! while the write was still uncommitted, from being cached afterwards.
+//! Entries also expire after [`ENTRY_TTL`]. That bounds how long a read
+//! racing a commit can keep a stale result.
+
+use std::collections::BTreeSet;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use moka::sync::Cache;
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::authorization::UserRole;
+use crate::document::EffectiveDocument;
+use crate::error::Result;
+
+/// Longest time an entry is served before it is read again
+pub const ENTRY_TTL: Duration = Duration::from_secs(60);
+/// Users whose role is kept
+const ROLE_CAPACITY: u64 = 10_000;
+/// (site, product, day) lists of effective documents kept
+const EFFECTIVE_DOCUMENTS_CAPACITY: u64 = 1_000;
+
+type EffectiveDocumentsKey = (String, Option<String>, NaiveDate);
+
+/// Hit and miss counts since the database was opened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ReadCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Cached results of frequent reads, shared by every clone of a
+/// [`Database`](crate::database::Database).
+pub struct ReadCache {
+    roles: Cache<String, Option<UserRole>>,
+    effective_documents: Cache<EffectiveDocumentsKey, Arc<Vec<EffectiveDocument>>>,
+    /// Bumped on every invalidation; a load that overlaps one is not stored
+    generation: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Default for ReadCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReadCache {
+    pub fn new() -> Self {
+        Self {
+            roles: Cache::builder().max_capacity(ROLE_CAPACITY).time_to_live(ENTRY_TTL).build(),
+            effective_documents: Cache::builder()
+                .max_capacity(EFFECTIVE_DOCUMENTS_CAPACITY)
+                .time_to_live(ENTRY_TTL)
+                .build(),
+            generation: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Role of `user_id`, loaded with `load` on a miss.
+    pub fn role(&self, user_id: &str, load: impl FnOnce() -> Result<Option<UserRole>>) -> Result<Option<UserRole>> {
+        self.get_or_load(&self.roles, user_id.to_string(), load)
+    }
+
+    /// Effective documents at a site, loaded with `load` on a miss.
+    pub fn effective_documents(
+        &self,
+        site_id: &str,
+        product: Option<&str>,
+        on: NaiveDate,
+        load: impl FnOnce() -> Result<Vec<EffectiveDocument>>,
+    ) -> Result<Vec<EffectiveDocument>> {
+        let key = (site_id.to_string(), product.map(str::to_string), on);
+        let documents = self.get_or_load(&self.effective_documents, key, || load().map(Arc::new))?;
+        Ok(documents.as_ref().clone())
+    }
+
+    /// Invalidate the entries derived from the tables `conn` writes, when
+    /// its transaction commits. Writes rolled back invalidate nothing.
+    pub fn watch(self: &Arc<Self>, conn: &Connection) {
+        let written = Arc::new(Mutex::new(BTreeSet::new()));
+        let noted = written.clone();
+        conn.update_hook(Some(move |_action, _db: &str, table: &str, _row| {
+            noted.lock().unwrap().insert(table.to_string());
+        }));
+        let (cache, committed) = (self.clone(), written.clone());
+        conn.commit_hook(Some(move || {
+            let tables = std::mem::take(&mut *committed.lock().unwrap());
+            for table in tables {
+                cache.invalidate_table(&table);
+            }
+            false
+        }));
+        conn.rollback_hook(Some(move || written.lock().unwrap().clear()));
+    }
+
+    /// Drop every entry derived from `table`. Called when a transaction
+    /// writing it commits.
+    pub fn invalidate_table(&self, table: &str) {
+        match table {
+            "users" => self.roles.invalidate_all(),
+            "documents" | "document_effectivity" => self.effective_documents.invalidate_all(),
+            _ => return,
+        }
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Drop every entry.
+    pub fn clear(&self) {
+        self.roles.invalidate_all();
+        self.effective_documents.invalidate_all();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn stats(&self) -> ReadCacheStats {
+        ReadCacheStats { hits: self.hits.load(Ordering::Relaxed), misses: self.misses.load(Ordering::Relaxed) }
+    }
+
+    fn get_or_load<K, V>(&self, cache: &Cache<K, V>, key: K, load: impl FnOnce() -> Result<V>) -> Result<V>
+    where
+        K: Hash + Eq + Send + Sync + 'static,
+        V: Clone + Send + Sync + 'static,
+    {
+        if let Some(value) = cache.get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(value);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let generation = self.generation.load(Ordering::SeqCst);
+        let value = load()?;
+        if self.generation.load(Ordering::SeqCst) == generation {
+            cache.insert(key, value.clone());
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::database::Database;
+    use crate::document::DocumentEffectivityRepo;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_writes_from_any_module_invalidate_cached_reads() {
+        let db = test_db();
+        db.seed_test_users(&["eng"]);
+        let cache = db.read_cache();
+        let before = cache.stats();
+
+        assert_eq!(UserRole::of_user(&db, "eng").unwrap(), Some(UserRole::QualityEngineer));
+        assert_eq!(UserRole::of_user(&db, "eng").unwrap(), Some(UserRole::QualityEngineer));
+        assert_eq!(cache.stats().hits, before.hits + 1);
+        db.with_connection(|conn| {
+            conn.execute("UPDATE users SET role = 'QualityManager' WHERE id = 'eng'", [])?;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(UserRole::of_user(&db, "eng").unwrap(), Some(UserRole::QualityManager));
+
+        let repo = DocumentEffectivityRepo::new(&db);
+        let today = NaiveDate::from_ymd_opt(2026, 5, 4).unwrap();
+        assert!(repo.effective_at("default", None, today).unwrap().is_empty());
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO documents (id, document_number, title, version, status, document_type, content_hash, created_by)
+                 VALUES ('d1', 'SOP-001', 'Gowning', '1.0', 'Effective', 'SOP', 'h', 'eng')",
+                [],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        let hits = cache.stats().hits;
+        let documents = repo.effective_at("default", None, today).unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(cache.stats().hits, hits, "reloaded after the insert");
+        assert_eq!(repo.effective_at("default", None, today).unwrap(), documents);
+        assert_eq!(cache.stats().hits, hits + 1);
+    }
+
+    #[test]
+    fn test_reads_during_an_uncommitted_write_are_invalidated_at_commit() {
+        let db = test_db();
+        db.seed_test_users(&["eng"]);
+        let cache = db.read_cache();
+
+        db.unit_of_work(|uow| {
+            uow.connection().execute("UPDATE users SET role = 'QualityManager' WHERE id = 'eng'", [])?;
+            // A concurrent reader still sees, and caches, the committed role
+            let seen = cache.role("eng", || Ok(Some(UserRole::QualityEngineer)))?;
+            assert_eq!(seen, Some(UserRole::QualityEngineer));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(UserRole::of_user(&db, "eng").unwrap(), Some(UserRole::QualityManager));
+
+        let rolled_back: Result<()> = db.unit_of_work(|uow| {
+            uow.connection().execute("UPDATE users SET role = 'Administrator' WHERE id = 'eng'", [])?;
+            Err(crate::error::QmsError::Application { message: "abandoned".to_string() })
+        });
+        assert!(rolled_back.is_err());
+        let hits = cache.stats().hits;
+        assert_eq!(UserRole::of_user(&db, "eng").unwrap(), Some(UserRole::QualityManager));
+        assert_eq!(cache.stats().hits, hits + 1, "a rolled back write invalidates nothing");
+    }
+}
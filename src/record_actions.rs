@@ -0,0 +1,260 @@
+//! # Record Actions - Operations on the Selected Record
+//!
+//! The TUI's action menu lists what can be done with the record under the
+//! cursor and runs it through the same services the API uses. A CAPA can be
+//! moved to a next status, reassigned, annotated and exported to PDF; any
+//! other record can be annotated. Status changes and reassignments ask for a
+//! reason, which is kept in the record's change history. Notes are kept in
+//! the record's audit trail, so they carry author and time like every other
+//! change.
+
+use std::path::PathBuf;
+
+use chrono::Utc;
+
+use crate::app_context::AppContext;
+use crate::authorization::Permission;
+use crate::capa::{CapaRecord, CapaStatus};
+use crate::capa_report::{capa_history, generate_capa_report, CapaReportConfig};
+use crate::change_history::ChangeReason;
+use crate::error::{QmsError, Result};
+use crate::i18n::{tr, tr_args, Locale};
+use crate::record_history::RecordHistoryRepo;
+use crate::search::{SearchKind, SearchResult};
+
+/// Directory below the data directory that receives record exports.
+pub const EXPORT_DIR: &str = "exports";
+
+/// One entry of the action menu.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordAction {
+    /// Move a CAPA to this status; closing needs a signature and is not offered
+    ChangeStatus(CapaStatus),
+    /// Hand a CAPA to another owner; asks for the user id, then the reason
+    Reassign,
+    /// Add a free-text note to the record's audit trail; asks for the text
+    AddNote,
+    /// Write the CAPA record PDF below `<data_directory>/exports`
+    ExportPdf,
+}
+
+impl RecordAction {
+    /// Whether the action asks for a line of text before it runs.
+    pub fn needs_input(&self) -> bool {
+        matches!(self, RecordAction::Reassign | RecordAction::AddNote)
+    }
+
+    /// Whether the action changes the record and so asks for a reason for
+    /// the change (after the text of [`Self::needs_input`], if any).
+    pub fn needs_reason(&self) -> bool {
+        matches!(self, RecordAction::ChangeStatus(_) | RecordAction::Reassign)
+    }
+
+    pub fn required_permission(&self) -> Permission {
+        match self {
+            RecordAction::ExportPdf => Permission::ReadRecords,
+            _ => Permission::WriteRecords,
+        }
+    }
+
+    pub fn label(&self, locale: Locale) -> String {
+        match self {
+            RecordAction::ChangeStatus(status) => {
+                tr_args(locale, "tui.actions.change_status", &[("status", &status.as_str())])
+            }
+            RecordAction::Reassign => tr(locale, "tui.actions.reassign").to_string(),
+            RecordAction::AddNote => tr(locale, "tui.actions.add_note").to_string(),
+            RecordAction::ExportPdf => tr(locale, "tui.actions.export_pdf").to_string(),
+        }
+    }
+}
+
+/// Runs menu actions against the shared services.
+pub struct RecordActions {
+    context: AppContext,
+}
+
+impl RecordActions {
+    pub fn new(context: &AppContext) -> Self {
+        Self { context: context.clone() }
+    }
+
+    /// Actions offered for `record`, in menu order.
+    pub fn available(&self, record: &SearchResult) -> Result<Vec<RecordAction>> {
+        if record.kind != SearchKind::Capa {
+            return Ok(vec![RecordAction::AddNote]);
+        }
+        let capa = self.load_capa(&record.id)?;
+        let mut actions: Vec<RecordAction> = [
+            CapaStatus::InvestigationInProgress,
+            CapaStatus::RootCauseAnalysis,
+            CapaStatus::CorrectiveActionInProgress,
+            CapaStatus::PreventiveActionInProgress,
+            CapaStatus::EffectivenessVerification,
+            CapaStatus::Cancelled,
+        ]
+        .into_iter()
+        .filter(|status| *status != capa.status && capa.status.can_transition_to(status))
+        .map(RecordAction::ChangeStatus)
+        .collect();
+        actions.extend([RecordAction::Reassign, RecordAction::AddNote, RecordAction::ExportPdf]);
+        Ok(actions)
+    }
+
+    /// Run `action` on `record` as `user`. `input` is the assignee or note
+    /// for actions that ask for one, `reason` the reason for the change for
+    /// actions that change the record. Returns a line describing the result.
+    pub fn perform(
+        &self,
+        record: &SearchResult,
+        action: &RecordAction,
+        input: &str,
+        reason: &str,
+        user: &str,
+    ) -> Result<String> {
+        let input = input.trim();
+        if action.needs_input() && input.is_empty() {
+            let field = if *action == RecordAction::Reassign { "assigned_to" } else { "note" };
+            return Err(QmsError::Validation { field: field.to_string(), message: "A value is required".to_string() });
+        }
+        match action {
+            RecordAction::ChangeStatus(status) => {
+                let reason = ChangeReason::new(reason)?;
+                let mut capa = self.load_capa(&record.id)?;
+                self.context.capa_service.update_status(&mut capa, status.clone(), user, &reason, None)?;
+                Ok(format!("{} moved to {}", capa.display_id(), status.as_str()))
+            }
+            RecordAction::Reassign => {
+                let reason = ChangeReason::new(reason)?;
+                let mut capa = self.load_capa(&record.id)?;
+                self.context.capa_service.reassign(&mut capa, input, user, &reason)?;
+                Ok(format!("{} reassigned to {}", capa.display_id(), input))
+            }
+            RecordAction::AddNote => {
+                self.context.audit_manager.log_action(
+                    user,
+                    "note_added",
+                    &format!("{}:{}", record.record_type(), record.id),
+                    "Success",
+                    Some(input.to_string()),
+                )?;
+                Ok(format!("Note added to {}", record.reference))
+            }
+            RecordAction::ExportPdf => {
+                let capa = self.load_capa(&record.id)?;
+                let path = self.export_pdf(&capa, user)?;
+                Ok(format!("{} exported to {}", capa.display_id(), path.display()))
+            }
+        }
+    }
+
+    /// The CAPA as of its latest version snapshot.
+    fn load_capa(&self, capa_id: &str) -> Result<CapaRecord> {
+        RecordHistoryRepo::new(&self.context.database)
+            .record_as_of::<CapaRecord>(capa_id, Utc::now())?
+            .ok_or_else(|| QmsError::NotFound { resource: "CAPA".to_string(), id: capa_id.to_string() })
+    }
+
+    fn export_pdf(&self, capa: &CapaRecord, user: &str) -> Result<PathBuf> {
+        let directory = PathBuf::from(&self.context.config.application.data_directory).join(EXPORT_DIR);
+        std::fs::create_dir_all(&directory)
+            .map_err(|e| QmsError::FileSystem { path: directory.display().to_string(), message: e.to_string() })?;
+        let path = directory.join(format!("{}.pdf", capa.display_id()));
+        let history = capa_history(&self.context.database, &capa.id)?;
+        let rendered = generate_capa_report(&CapaReportConfig {
+            output_path: &path,
+            capa,
+            history: &history,
+            prepared_by: user,
+            organization: Some(&self.context.config.application.organization_name),
+            generated_on: Utc::now(),
+        })?;
+        self.context.audit_manager.log_action(
+            user,
+            "capa_exported",
+            &format!("capa:{}", capa.id),
+            "Success",
+            Some(format!("Exported {} ({} pages)", path.display(), rendered.page_count)),
+        )?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capa::{CapaPriority, CapaType};
+    use crate::config::{Config, DatabaseConfig};
+    use crate::database::Database;
+
+    #[test]
+    fn test_capa_actions_run_through_services() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["qa"]);
+        let context = AppContext::with_database(Config::default(), db.clone());
+        let capa = context
+            .capa_service
+            .create_capa(
+                "Seal failure".to_string(),
+                "Lot 42 failed peel test".to_string(),
+                CapaType::Corrective,
+                CapaPriority::High,
+                "qa".to_string(),
+                "qa".to_string(),
+                None,
+            )
+            .unwrap();
+        let record = SearchResult {
+            kind: SearchKind::Capa,
+            id: capa.id.clone(),
+            reference: capa.capa_number.clone(),
+            title: capa.title.clone(),
+            status: capa.status.as_str().to_string(),
+            score: 100,
+            custom_type: None,
+        };
+        let actions = RecordActions::new(&context);
+        assert_eq!(
+            actions.available(&record).unwrap(),
+            vec![
+                RecordAction::ChangeStatus(CapaStatus::InvestigationInProgress),
+                RecordAction::ChangeStatus(CapaStatus::Cancelled),
+                RecordAction::Reassign,
+                RecordAction::AddNote,
+                RecordAction::ExportPdf,
+            ]
+        );
+
+        let moved = RecordAction::ChangeStatus(CapaStatus::InvestigationInProgress);
+        assert!(actions.perform(&record, &moved, "", " ", "qa").is_err(), "no reason given");
+        actions.perform(&record, &moved, "", "Complaint trend confirmed", "qa").unwrap();
+        assert!(actions.perform(&record, &RecordAction::Reassign, " ", "Workload", "qa").is_err());
+        assert_eq!(
+            actions.perform(&record, &RecordAction::Reassign, "lead", "Workload", "qa").unwrap(),
+            format!("{} reassigned to lead", capa.capa_number)
+        );
+        actions.perform(&record, &RecordAction::AddNote, "Supplier contacted", "", "qa").unwrap();
+
+        let latest = actions.load_capa(&capa.id).unwrap();
+        assert_eq!((latest.status, latest.assigned_to.as_str()), (CapaStatus::InvestigationInProgress, "lead"));
+        assert!(!actions.available(&record).unwrap().contains(&moved));
+        let trail: Vec<(String, Option<String>)> =
+            capa_history(&db, &capa.id).unwrap().into_iter().map(|e| (e.action, e.metadata)).collect();
+        assert_eq!(trail.last().unwrap(), &("note_added".to_string(), Some("Supplier contacted".to_string())));
+        assert!(trail.iter().any(|(action, _)| action == "capa_reassigned"));
+        let reasons: Vec<String> = crate::change_history::ChangeHistoryRepo::new(&db)
+            .history("capa", &capa.id)
+            .unwrap()
+            .into_iter()
+            .map(|change| change.reason.as_str().to_string())
+            .collect();
+        assert_eq!(reasons, vec!["Complaint trend confirmed", "Workload"]);
+    }
+}
@@ -0,0 +1,310 @@
+//! # Record History - Version Snapshots & "As Of" Views
+//!
+//! Generalizes `document_versions` to other quality records: each create or
+//! update of a CAPA, risk assessment or supplier stores a full JSON snapshot
+//! under an incrementing version number. Snapshots are never modified, so an
+//! inspector can diff any two versions or reconstruct a record as it stood on
+//! a given date (FDA 21 CFR Part 11 §11.10(b)/(e)).
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::capa::CapaRecord;
+use crate::change_history::{diff_values, FieldChange};
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::keystore::sha256_hex;
+use crate::risk::RiskAssessment;
+use crate::supplier::Supplier;
+
+/// A record type whose versions are kept in `record_versions`.
+pub trait VersionedRecord: Serialize + DeserializeOwned {
+    /// Stable type name stored with each snapshot, e.g. `"capa"`.
+    const RECORD_TYPE: &'static str;
+
+    fn record_id(&self) -> String;
+}
+
+impl VersionedRecord for CapaRecord {
+    const RECORD_TYPE: &'static str = "capa";
+
+    fn record_id(&self) -> String {
+        self.id.clone()
+    }
+}
+
+impl VersionedRecord for RiskAssessment {
+    const RECORD_TYPE: &'static str = "risk_assessment";
+
+    fn record_id(&self) -> String {
+        self.id.to_string()
+    }
+}
+
+impl VersionedRecord for Supplier {
+    const RECORD_TYPE: &'static str = "supplier";
+
+    fn record_id(&self) -> String {
+        self.id.to_string()
+    }
+}
+
+/// Stored snapshot of one version of a record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordVersion {
+    pub record_type: String,
+    pub record_id: String,
+    pub version: u32,
+    pub snapshot: serde_json::Value,
+    pub snapshot_sha256: String,
+    pub captured_by: String,
+    pub captured_at: DateTime<Utc>,
+}
+
+impl RecordVersion {
+    /// Deserialize the snapshot back into its record type.
+    pub fn restore<T: VersionedRecord>(&self) -> Result<T> {
+        if self.record_type != T::RECORD_TYPE {
+            return Err(QmsError::Validation {
+                field: "record_type".to_string(),
+                message: format!("Snapshot is a {} record, not {}", self.record_type, T::RECORD_TYPE),
+            });
+        }
+        Ok(serde_json::from_value(self.snapshot.clone())?)
+    }
+}
+
+/// Store a snapshot of `record` on an open connection (normally inside the
+/// write's transaction). Returns the new version, or `None` when the record
+/// is identical to its latest snapshot.
+pub fn snapshot_with<T: VersionedRecord>(
+    conn: &Connection,
+    record: &T,
+    captured_by: &str,
+    captured_at: DateTime<Utc>,
+) -> Result<Option<u32>> {
+    let record_id = record.record_id();
+    let snapshot = serde_json::to_string(record)?;
+    let sha256 = sha256_hex(snapshot.as_bytes());
+
+    let latest: Option<(u32, String)> = conn
+        .query_row(
+            "SELECT version, snapshot_sha256 FROM record_versions
+             WHERE record_type = ?1 AND record_id = ?2
+             ORDER BY version DESC LIMIT 1",
+            params![T::RECORD_TYPE, record_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    if latest.as_ref().is_some_and(|(_, latest_sha)| *latest_sha == sha256) {
+        return Ok(None);
+    }
+
+    let version = latest.map(|(v, _)| v + 1).unwrap_or(1);
+    conn.execute(
+        "INSERT INTO record_versions (
+            record_type, record_id, version, snapshot, snapshot_sha256, captured_by, captured_at
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            T::RECORD_TYPE,
+            record_id,
+            version,
+            snapshot,
+            sha256,
+            captured_by,
+            timestamp(captured_at),
+        ],
+    )?;
+    Ok(Some(version))
+}
+
+/// Fixed-width UTC timestamps so stored values order correctly as text.
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// Repository over `record_versions`.
+pub struct RecordHistoryRepo<'a> {
+    db: &'a Database,
+}
+
+impl<'a> RecordHistoryRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Snapshot the current state of `record`.
+    pub fn snapshot<T: VersionedRecord>(&self, record: &T, captured_by: &str) -> Result<Option<u32>> {
+        self.db.with_connection(|conn| snapshot_with(conn, record, captured_by, Utc::now()))
+    }
+
+    /// All versions of a record, oldest first.
+    pub fn versions(&self, record_type: &str, record_id: &str) -> Result<Vec<RecordVersion>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT record_type, record_id, version, snapshot, snapshot_sha256, captured_by, captured_at
+                 FROM record_versions WHERE record_type = ?1 AND record_id = ?2
+                 ORDER BY version",
+            )?;
+            let rows = stmt.query_map(params![record_type, record_id], row_to_version)?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        })
+    }
+
+    /// A specific version of a record.
+    pub fn version(&self, record_type: &str, record_id: &str, version: u32) -> Result<RecordVersion> {
+        self.db.with_connection(|conn| {
+            conn.query_row(
+                "SELECT record_type, record_id, version, snapshot, snapshot_sha256, captured_by, captured_at
+                 FROM record_versions WHERE record_type = ?1 AND record_id = ?2 AND version = ?3",
+                params![record_type, record_id, version],
+                row_to_version,
+            )
+            .optional()?
+            .ok_or_else(|| QmsError::NotFound {
+                resource: format!("{} version", record_type),
+                id: format!("{}@{}", record_id, version),
+            })
+        })
+    }
+
+    /// Latest snapshot captured at or before `at`; `None` if the record did
+    /// not exist yet.
+    pub fn as_of(&self, record_type: &str, record_id: &str, at: DateTime<Utc>) -> Result<Option<RecordVersion>> {
+        self.db.with_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT record_type, record_id, version, snapshot, snapshot_sha256, captured_by, captured_at
+                     FROM record_versions
+                     WHERE record_type = ?1 AND record_id = ?2 AND captured_at <= ?3
+                     ORDER BY version DESC LIMIT 1",
+                    params![record_type, record_id, timestamp(at)],
+                    row_to_version,
+                )
+                .optional()?)
+        })
+    }
+
+    /// Typed reconstruction of a record as it stood at `at`.
+    pub fn record_as_of<T: VersionedRecord>(&self, record_id: &str, at: DateTime<Utc>) -> Result<Option<T>> {
+        self.as_of(T::RECORD_TYPE, record_id, at)?
+            .map(|version| version.restore())
+            .transpose()
+    }
+
+    /// Field-level differences between two versions of a record.
+    pub fn diff(&self, record_type: &str, record_id: &str, from: u32, to: u32) -> Result<Vec<FieldChange>> {
+        let before = self.version(record_type, record_id, from)?;
+        let after = self.version(record_type, record_id, to)?;
+        Ok(diff_values(&before.snapshot, &after.snapshot, &["row_version"]))
+    }
+}
+
+fn row_to_version(row: &rusqlite::Row) -> rusqlite::Result<RecordVersion> {
+    let snapshot: String = row.get(3)?;
+    let captured_at: String = row.get(6)?;
+    Ok(RecordVersion {
+        record_type: row.get(0)?,
+        record_id: row.get(1)?,
+        version: row.get(2)?,
+        snapshot: serde_json::from_str(&snapshot).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+        snapshot_sha256: row.get(4)?,
+        captured_by: row.get(5)?,
+        captured_at: DateTime::parse_from_rfc3339(&captured_at)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(6, rusqlite::types::Type::Text, Box::new(e)))?
+            .with_timezone(&Utc),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::supplier::SupplierStatus;
+    use chrono::TimeZone;
+    use uuid::Uuid;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    fn supplier(status: SupplierStatus) -> Supplier {
+        let created = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        Supplier {
+            id: Uuid::nil(),
+            name: "Acme Components".to_string(),
+            contact_info: None,
+            status,
+            qualification_date: None,
+            qualification_expiry_date: None,
+            approved_by: None,
+            created_at: created,
+            updated_at: created,
+            row_version: 1,
+        }
+    }
+
+    #[test]
+    fn test_snapshots_are_versioned_and_deduplicated() {
+        let db = test_db();
+        let at = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let pending = supplier(SupplierStatus::Pending);
+        db.with_connection(|conn| {
+            assert_eq!(snapshot_with(conn, &pending, "qa", at)?, Some(1));
+            assert_eq!(snapshot_with(conn, &pending, "qa", at)?, None);
+            assert_eq!(snapshot_with(conn, &supplier(SupplierStatus::Qualified), "qa", at)?, Some(2));
+            Ok(())
+        })
+        .unwrap();
+
+        let repo = RecordHistoryRepo::new(&db);
+        let versions = repo.versions("supplier", &Uuid::nil().to_string()).unwrap();
+        assert_eq!(versions.iter().map(|v| v.version).collect::<Vec<_>>(), vec![1, 2]);
+        let restored: Supplier = versions[1].restore().unwrap();
+        assert_eq!(restored.status, SupplierStatus::Qualified);
+        assert!(versions[0].restore::<CapaRecord>().is_err());
+
+        let diff = repo.diff("supplier", &Uuid::nil().to_string(), 1, 2).unwrap();
+        assert_eq!(
+            diff,
+            vec![FieldChange {
+                field: "status".to_string(),
+                old_value: Some("Pending".to_string()),
+                new_value: Some("Qualified".to_string()),
+            }]
+        );
+        assert!(matches!(repo.version("supplier", "missing", 1), Err(QmsError::NotFound { .. })));
+    }
+
+    #[test]
+    fn test_as_of_reconstruction() {
+        let db = test_db();
+        let jan = Utc.with_ymd_and_hms(2025, 1, 10, 9, 0, 0).unwrap();
+        let mar = Utc.with_ymd_and_hms(2025, 3, 10, 9, 0, 0).unwrap();
+        db.with_connection(|conn| {
+            snapshot_with(conn, &supplier(SupplierStatus::Pending), "qa", jan)?;
+            snapshot_with(conn, &supplier(SupplierStatus::Disqualified), "qa", mar)?;
+            Ok(())
+        })
+        .unwrap();
+
+        let repo = RecordHistoryRepo::new(&db);
+        let id = Uuid::nil().to_string();
+        let before = Utc.with_ymd_and_hms(2024, 12, 31, 0, 0, 0).unwrap();
+        assert!(repo.record_as_of::<Supplier>(&id, before).unwrap().is_none());
+        let feb = Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap();
+        assert_eq!(repo.record_as_of::<Supplier>(&id, feb).unwrap().unwrap().status, SupplierStatus::Pending);
+        assert_eq!(repo.record_as_of::<Supplier>(&id, mar).unwrap().unwrap().status, SupplierStatus::Disqualified);
+    }
+}
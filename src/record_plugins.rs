@@ -0,0 +1,397 @@
+//! # Record Plugins - Integrator-Defined Record Types
+//!
+//! A deployment may need records QMSrs does not model, such as lot release
+//! checklists or equipment logbooks. An integrator implements
+//! [`RecordPlugin`] for each such type, registers it at compile time with
+//! [`RecordPluginRegistry::with_plugin`] and hands the registry to
+//! [`AppContext::with_plugins`](crate::app_context::AppContext::with_plugins).
+//! Records of every plugin type share the `custom_records` table, so without
+//! changes to the core modules they are:
+//!
+//! - audited on every save, as `<record_type>:<id>`;
+//! - checked on every save by the validation scripts for custom records;
+//! - found by the search, as [`SearchKind::Custom`](crate::search::SearchKind::Custom) hits;
+//! - listed in the assignee's task inbox while the plugin reports them open;
+//! - listed on the TUI's Records tab, with the plugin's fields in the detail pane.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use chrono::{NaiveDate, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::links::RecordRef;
+use crate::validation_scripts::{RuleHook, ScriptedRules};
+
+/// Record types of the core modules, which a plugin cannot take over.
+const CORE_RECORD_TYPES: &[&str] = &[
+    "task",
+    "capa",
+    "capa_action",
+    "audit_finding",
+    "training",
+    "document",
+    "supplier",
+    "complaint",
+    "risk",
+    "risk_assessment",
+];
+
+/// A field a plugin stores on its records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldSpec {
+    /// Key in [`CustomRecord::fields`]
+    pub key: &'static str,
+    /// Label in the TUI detail pane
+    pub label: &'static str,
+    pub required: bool,
+}
+
+/// A record type contributed by an integrator.
+pub trait RecordPlugin: Send + Sync {
+    /// Type name, e.g. `lot_release`: lowercase letters, digits and `_`
+    fn record_type(&self) -> &'static str;
+
+    /// Name shown in the TUI
+    fn display_name(&self) -> &'static str;
+
+    /// Fields of the record, in detail pane order
+    fn fields(&self) -> &'static [FieldSpec] {
+        &[]
+    }
+
+    /// Whether a record in `status` still needs work from its assignee
+    fn is_open(&self, status: &str) -> bool;
+
+    /// Reject a record before it is saved. By default, checks that every
+    /// required field has a value.
+    fn validate(&self, record: &CustomRecord) -> Result<()> {
+        validate_required_fields(self.fields(), record)
+    }
+}
+
+/// The registered plugins. Clones share the same plugins.
+#[derive(Clone, Default)]
+pub struct RecordPluginRegistry {
+    plugins: Vec<Arc<dyn RecordPlugin>>,
+}
+
+impl RecordPluginRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `plugin`. Fails when its type name is malformed, belongs to
+    /// a core module or is already registered.
+    pub fn with_plugin(mut self, plugin: impl RecordPlugin + 'static) -> Result<Self> {
+        let record_type = plugin.record_type();
+        let well_formed = !record_type.is_empty()
+            && record_type.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_');
+        let message = if !well_formed {
+            "Record types use lowercase letters, digits and '_'"
+        } else if CORE_RECORD_TYPES.contains(&record_type) {
+            "Record type belongs to a core module"
+        } else if self.get(record_type).is_some() {
+            "Record type is already registered"
+        } else {
+            self.plugins.push(Arc::new(plugin));
+            return Ok(self);
+        };
+        Err(QmsError::Validation { field: "record_type".to_string(), message: format!("{}: {}", message, record_type) })
+    }
+
+    pub fn get(&self, record_type: &str) -> Option<&dyn RecordPlugin> {
+        self.plugins.iter().find(|plugin| plugin.record_type() == record_type).map(|plugin| plugin.as_ref())
+    }
+
+    /// Plugins in registration order.
+    pub fn plugins(&self) -> impl Iterator<Item = &dyn RecordPlugin> {
+        self.plugins.iter().map(|plugin| plugin.as_ref())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Display name of `record_type`; the type itself when no plugin is
+    /// registered for it any more.
+    pub fn display_name<'a>(&self, record_type: &'a str) -> &'a str {
+        self.get(record_type).map_or(record_type, |plugin| plugin.display_name())
+    }
+}
+
+/// A record of a plugin type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomRecord {
+    pub record_type: String,
+    /// Internal key (UUID)
+    pub id: String,
+    /// Identifier people use, e.g. `LR-2026-014`
+    pub reference: String,
+    pub title: String,
+    pub status: String,
+    pub assignee: Option<String>,
+    pub due_date: Option<NaiveDate>,
+    /// Values of the plugin's [`FieldSpec`]s by key
+    pub fields: BTreeMap<String, String>,
+}
+
+impl CustomRecord {
+    pub fn new(record_type: &str, reference: &str, title: &str, status: &str) -> Self {
+        Self {
+            record_type: record_type.to_string(),
+            id: Uuid::new_v4().to_string(),
+            reference: reference.to_string(),
+            title: title.to_string(),
+            status: status.to_string(),
+            assignee: None,
+            due_date: None,
+            fields: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_assignee(mut self, assignee: &str, due_date: Option<NaiveDate>) -> Self {
+        self.assignee = Some(assignee.to_string());
+        self.due_date = due_date;
+        self
+    }
+
+    pub fn with_field(mut self, key: &str, value: &str) -> Self {
+        self.fields.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn record_ref(&self) -> RecordRef {
+        RecordRef::new(&self.record_type, &self.id)
+    }
+}
+
+/// Check that every required field of `fields` has a non-blank value.
+pub fn validate_required_fields(fields: &[FieldSpec], record: &CustomRecord) -> Result<()> {
+    let missing = |spec: &&FieldSpec| record.fields.get(spec.key).map_or(true, |value| value.trim().is_empty());
+    match fields.iter().filter(|spec| spec.required).find(missing) {
+        Some(spec) => Err(QmsError::Validation {
+            field: spec.key.to_string(),
+            message: format!("{} is required", spec.label),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Records of the registered plugin types.
+pub struct CustomRecordRepo<'a> {
+    db: &'a Database,
+    plugins: &'a RecordPluginRegistry,
+}
+
+impl<'a> CustomRecordRepo<'a> {
+    pub fn new(db: &'a Database, plugins: &'a RecordPluginRegistry) -> Self {
+        Self { db, plugins }
+    }
+
+    /// Create or update `record` after its plugin validated it; the save is
+    /// audited as `custom_record_created` or `custom_record_updated`.
+    pub fn save(&self, record: &CustomRecord, user: &str) -> Result<CustomRecord> {
+        let plugin = self.plugins.get(&record.record_type).ok_or_else(|| QmsError::Validation {
+            field: "record_type".to_string(),
+            message: format!("No plugin is registered for record type '{}'", record.record_type),
+        })?;
+        for (field, value) in [("reference", &record.reference), ("title", &record.title), ("status", &record.status)] {
+            if value.trim().is_empty() {
+                let message = format!("{} is required", field);
+                return Err(QmsError::Validation { field: field.to_string(), message });
+            }
+        }
+        plugin.validate(record)?;
+        ScriptedRules::new(self.db.clone()).check(RuleHook::CustomRecord, record)?;
+
+        let resource = record.record_ref().to_string();
+        let fields = serde_json::to_string(&record.fields)?;
+        let now = Utc::now().to_rfc3339();
+        self.db.unit_of_work(|uow| {
+            let conn = uow.connection();
+            let previous: Option<String> = conn
+                .query_row(
+                    "SELECT status FROM custom_records WHERE record_type = ?1 AND id = ?2",
+                    params![record.record_type, record.id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let (action, details) = match &previous {
+                Some(status) if *status != record.status => {
+                    ("custom_record_updated", format!("Status {} -> {}", status, record.status))
+                }
+                Some(_) => ("custom_record_updated", format!("'{}' updated", record.title)),
+                None => ("custom_record_created", format!("'{}' ({}) created", record.title, record.reference)),
+            };
+            conn.execute(
+                "INSERT INTO custom_records (record_type, id, reference, title, status, is_open, assignee, due_date,
+                                             fields, created_by, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?11)
+                 ON CONFLICT(record_type, id) DO UPDATE SET
+                     reference = excluded.reference, title = excluded.title, status = excluded.status,
+                     is_open = excluded.is_open, assignee = excluded.assignee, due_date = excluded.due_date,
+                     fields = excluded.fields, updated_at = excluded.updated_at",
+                params![
+                    record.record_type,
+                    record.id,
+                    record.reference.trim(),
+                    record.title.trim(),
+                    record.status,
+                    plugin.is_open(&record.status),
+                    record.assignee,
+                    record.due_date.map(|due| due.to_string()),
+                    fields,
+                    user,
+                    now,
+                ],
+            )?;
+            AuditManager::new(self.db.clone()).log_action_in(uow, user, action, &resource, "Success", Some(details))
+        })?;
+        self.get(&record.record_type, &record.id)
+    }
+
+    pub fn get(&self, record_type: &str, id: &str) -> Result<CustomRecord> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM custom_records WHERE record_type = ?1 AND id = ?2", RECORD_COLUMNS),
+                        params![record_type, id],
+                        row_to_record,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: record_type.to_string(), id: id.to_string() })
+    }
+
+    /// Records of `record_type`, or of every type, by type and reference.
+    pub fn list(&self, record_type: Option<&str>) -> Result<Vec<CustomRecord>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM custom_records WHERE ?1 IS NULL OR record_type = ?1 ORDER BY record_type, reference",
+                RECORD_COLUMNS
+            ))?;
+            let records = stmt.query_map(params![record_type], row_to_record)?.collect::<rusqlite::Result<_>>()?;
+            Ok(records)
+        })
+    }
+
+}
+
+const RECORD_COLUMNS: &str = "record_type, id, reference, title, status, assignee, due_date, fields";
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn row_to_record(row: &Row) -> rusqlite::Result<CustomRecord> {
+    let due_date = row
+        .get::<_, Option<String>>(6)?
+        .map(|value| NaiveDate::parse_from_str(&value, "%Y-%m-%d").map_err(|e| conversion_error(6, e.to_string())))
+        .transpose()?;
+    let fields: String = row.get(7)?;
+    Ok(CustomRecord {
+        record_type: row.get(0)?,
+        id: row.get(1)?,
+        reference: row.get(2)?,
+        title: row.get(3)?,
+        status: row.get(4)?,
+        assignee: row.get(5)?,
+        due_date,
+        fields: serde_json::from_str(&fields).map_err(|e| conversion_error(7, e.to_string()))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::search::{SearchKind, SearchService};
+    use crate::tasks::TaskInbox;
+
+    struct LotRelease;
+
+    impl RecordPlugin for LotRelease {
+        fn record_type(&self) -> &'static str {
+            "lot_release"
+        }
+
+        fn display_name(&self) -> &'static str {
+            "Lot Release"
+        }
+
+        fn fields(&self) -> &'static [FieldSpec] {
+            &[
+                FieldSpec { key: "lot", label: "Lot number", required: true },
+                FieldSpec { key: "disposition", label: "Disposition", required: false },
+            ]
+        }
+
+        fn is_open(&self, status: &str) -> bool {
+            status != "Released" && status != "Rejected"
+        }
+    }
+
+    #[test]
+    fn test_plugin_records_are_audited_searched_and_in_inbox() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["qa"]);
+        let plugins = RecordPluginRegistry::new().with_plugin(LotRelease).unwrap();
+        assert!(plugins.clone().with_plugin(LotRelease).is_err(), "registered twice");
+        assert!(RecordPluginRegistry::new().with_plugin(Named("capa")).is_err(), "core type");
+        assert!(RecordPluginRegistry::new().with_plugin(Named("Lot:x")).is_err(), "malformed type");
+        assert_eq!(plugins.display_name("lot_release"), "Lot Release");
+
+        let repo = CustomRecordRepo::new(&db, &plugins);
+        let today = Utc::now().date_naive();
+        let record = CustomRecord::new("lot_release", "LR-2026-014", "Release of sterile kit lot", "Pending")
+            .with_assignee("qa", Some(today));
+        assert!(repo.save(&record, "qa").is_err(), "lot number is required");
+        assert!(repo.save(&CustomRecord::new("equipment_log", "EQ-1", "Autoclave", "Open"), "qa").is_err());
+        let record = repo.save(&record.with_field("lot", "L-2026-0042"), "qa").unwrap();
+        assert_eq!(repo.list(Some("lot_release")).unwrap(), vec![record.clone()]);
+
+        let hits = SearchService::new(&db).search("L-2026-0042", 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!((hits[0].kind, hits[0].record_type()), (SearchKind::Custom, "lot_release"));
+        let inbox = TaskInbox::new(&db).for_user("qa", today).unwrap();
+        assert_eq!(inbox.iter().map(|item| &item.record).collect::<Vec<_>>(), vec![&record.record_ref()]);
+
+        let released = CustomRecord { status: "Released".to_string(), ..record.clone() };
+        repo.save(&released, "qa").unwrap();
+        assert!(TaskInbox::new(&db).for_user("qa", today).unwrap().is_empty());
+        let audit = db.get_audit_entries_for_resource(&record.record_ref().to_string()).unwrap();
+        let actions: Vec<&str> = audit.iter().map(|entry| entry.action.as_str()).collect();
+        assert_eq!(actions.len(), 2);
+        assert!(actions.contains(&"custom_record_created") && actions.contains(&"custom_record_updated"));
+    }
+
+    struct Named(&'static str);
+
+    impl RecordPlugin for Named {
+        fn record_type(&self) -> &'static str {
+            self.0
+        }
+
+        fn display_name(&self) -> &'static str {
+            self.0
+        }
+
+        fn is_open(&self, _status: &str) -> bool {
+            true
+        }
+    }
+}
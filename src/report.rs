@@ -0,0 +1,490 @@
+//! # Report Model - Renderer-Independent Report Structure
+//!
+//! Reports (compliance summaries, CAPA detail exports, audit extracts) are
+//! assembled as a [`Report`] made of titled [`ReportSection`]s, each holding a
+//! sequence of [`ReportBlock`]s. Renderers such as `pdf_report` take care of
+//! layout, pagination, headers/footers and the table of contents, so feature
+//! modules only describe *what* goes into a report.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::clock::Clock;
+use crate::display_time::DisplayTimezone;
+use crate::i18n::Locale;
+
+/// Fill colours cycled across chart categories (colour-blind friendly palette),
+/// shared by all renderers so PDF and HTML output match.
+pub const CHART_PALETTE: [(u8, u8, u8); 6] = [
+    (0, 114, 178),
+    (230, 159, 0),
+    (0, 158, 115),
+    (213, 94, 0),
+    (204, 121, 167),
+    (86, 180, 233),
+];
+
+/// Chart rendering style for a metrics distribution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ChartKind {
+    /// Vertical bars scaled to the largest value.
+    Bar,
+    /// Filled pie slices proportional to each value's share of the total.
+    Pie,
+}
+
+/// Single labelled value rendered as a bar or pie slice.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChartDatum {
+    /// Category label shown in the legend / under the bar.
+    pub label: String,
+    /// Non-negative magnitude for the category.
+    pub value: f32,
+}
+
+/// Chart definition drawn with vector primitives (no raster images).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Chart {
+    /// Caption rendered above the chart.
+    pub title: String,
+    /// Bar or pie rendering.
+    pub kind: ChartKind,
+    /// Data points in display order.
+    pub data: Vec<ChartDatum>,
+}
+
+impl Chart {
+    /// Build a chart from a count distribution (e.g., `CapaMetrics::status_counts`).
+    ///
+    /// Entries are sorted by label so output is deterministic regardless of
+    /// `HashMap` iteration order.
+    pub fn from_counts(title: &str, kind: ChartKind, counts: &HashMap<String, usize>) -> Self {
+        let mut data: Vec<ChartDatum> = counts
+            .iter()
+            .map(|(label, count)| ChartDatum { label: label.clone(), value: *count as f32 })
+            .collect();
+        data.sort_by(|a, b| a.label.cmp(&b.label));
+        Self { title: title.to_string(), kind, data }
+    }
+
+    /// Sum of all data values.
+    pub fn total(&self) -> f32 {
+        self.data.iter().map(|d| d.value.max(0.0)).sum()
+    }
+}
+
+/// Colour band of a heat map cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum HeatLevel {
+    /// Broadly acceptable (green)
+    Low,
+    /// Tolerable / ALARP region (amber)
+    Medium,
+    /// Unacceptable (red)
+    High,
+}
+
+impl HeatLevel {
+    /// Cell fill colour; light tints keep black cell counts readable.
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        match self {
+            HeatLevel::Low => (198, 239, 206),
+            HeatLevel::Medium => (255, 235, 156),
+            HeatLevel::High => (255, 199, 206),
+        }
+    }
+}
+
+/// Single heat map cell: a count shaded by its band.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct HeatCell {
+    pub value: usize,
+    pub level: HeatLevel,
+}
+
+/// Labelled grid of counts, e.g. an ISO 14971 severity × probability matrix.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HeatMap {
+    /// Caption rendered above the grid.
+    pub title: String,
+    /// Axis caption for the rows (left side).
+    pub row_axis: String,
+    /// Axis caption for the columns (bottom).
+    pub column_axis: String,
+    /// Row labels, top to bottom.
+    pub row_labels: Vec<String>,
+    /// Column labels, left to right.
+    pub column_labels: Vec<String>,
+    /// `cells[row][column]`, same shape as the labels.
+    pub cells: Vec<Vec<HeatCell>>,
+}
+
+impl HeatMap {
+    /// Sum of all cell values.
+    pub fn total(&self) -> usize {
+        self.cells.iter().flatten().map(|c| c.value).sum()
+    }
+}
+
+/// Tabular data with a header row; renderers repeat the header on page breaks.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReportTable {
+    /// Column captions
+    pub headers: Vec<String>,
+    /// Data rows (each row should have `headers.len()` cells)
+    pub rows: Vec<Vec<String>>,
+    /// Relative column widths; equal widths when `None`
+    pub column_weights: Option<Vec<f32>>,
+}
+
+impl ReportTable {
+    /// Create a table with the given column captions and no rows.
+    pub fn new<S: Into<String>>(headers: Vec<S>) -> Self {
+        Self {
+            headers: headers.into_iter().map(Into::into).collect(),
+            rows: Vec::new(),
+            column_weights: None,
+        }
+    }
+
+    /// Append a data row.
+    pub fn with_row<S: Into<String>>(mut self, row: Vec<S>) -> Self {
+        self.rows.push(row.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Set relative column widths (e.g., `[1.0, 3.0]` for a narrow/wide pair).
+    pub fn with_column_weights(mut self, weights: Vec<f32>) -> Self {
+        self.column_weights = Some(weights);
+        self
+    }
+
+    /// Normalised column weights summing to 1.0.
+    pub fn normalized_weights(&self) -> Vec<f32> {
+        let columns = self.headers.len().max(1);
+        match &self.column_weights {
+            Some(weights) if weights.len() == columns && weights.iter().all(|w| *w > 0.0) => {
+                let sum: f32 = weights.iter().sum();
+                weights.iter().map(|w| w / sum).collect()
+            }
+            _ => vec![1.0 / columns as f32; columns],
+        }
+    }
+}
+
+/// Content element inside a report section.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum ReportBlock {
+    /// Sub-heading inside a section
+    Heading(String),
+    /// Word-wrapped body text
+    Paragraph(String),
+    /// Label/value pairs (metadata, summary figures)
+    KeyValue(Vec<(String, String)>),
+    /// Multi-row table with repeating header
+    Table(ReportTable),
+    /// Vector chart
+    Chart(Chart),
+    /// Shaded count grid
+    HeatMap(HeatMap),
+    /// Vertical whitespace in points
+    Spacer(f32),
+}
+
+/// Titled group of blocks; each section gets an entry in the table of contents.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReportSection {
+    /// Section title (also used for the TOC and PDF outline)
+    pub title: String,
+    /// Start the section on a fresh page even if space remains
+    pub page_break_before: bool,
+    /// Ordered content
+    pub blocks: Vec<ReportBlock>,
+}
+
+impl ReportSection {
+    /// Create an empty section.
+    pub fn new<S: Into<String>>(title: S) -> Self {
+        Self { title: title.into(), page_break_before: false, blocks: Vec::new() }
+    }
+
+    /// Force this section onto a new page.
+    pub fn with_page_break(mut self) -> Self {
+        self.page_break_before = true;
+        self
+    }
+
+    /// Append a sub-heading.
+    pub fn heading<S: Into<String>>(mut self, text: S) -> Self {
+        self.blocks.push(ReportBlock::Heading(text.into()));
+        self
+    }
+
+    /// Append a paragraph.
+    pub fn paragraph<S: Into<String>>(mut self, text: S) -> Self {
+        self.blocks.push(ReportBlock::Paragraph(text.into()));
+        self
+    }
+
+    /// Append label/value pairs.
+    pub fn key_values<K: Into<String>, V: Into<String>>(mut self, pairs: Vec<(K, V)>) -> Self {
+        self.blocks.push(ReportBlock::KeyValue(
+            pairs.into_iter().map(|(k, v)| (k.into(), v.into())).collect(),
+        ));
+        self
+    }
+
+    /// Append a table.
+    pub fn table(mut self, table: ReportTable) -> Self {
+        self.blocks.push(ReportBlock::Table(table));
+        self
+    }
+
+    /// Append a chart.
+    pub fn chart(mut self, chart: Chart) -> Self {
+        self.blocks.push(ReportBlock::Chart(chart));
+        self
+    }
+
+    /// Append a heat map.
+    pub fn heat_map(mut self, heat_map: HeatMap) -> Self {
+        self.blocks.push(ReportBlock::HeatMap(heat_map));
+        self
+    }
+
+    /// Append vertical whitespace.
+    pub fn spacer(mut self, points: f32) -> Self {
+        self.blocks.push(ReportBlock::Spacer(points));
+        self
+    }
+}
+
+/// Complete report ready for rendering.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Report {
+    /// Document title (cover page, running header, PDF metadata)
+    pub title: String,
+    /// Optional subtitle on the cover page
+    pub subtitle: Option<String>,
+    /// Organization name (FDA establishment)
+    pub organization: Option<String>,
+    /// Person or system that prepared the report
+    pub prepared_by: Option<String>,
+    /// UTC timestamp of report generation
+    pub generated_on: DateTime<Utc>,
+    /// Software version printed in the footer
+    pub application_version: String,
+    /// Extra footer text (e.g., document number or checksum)
+    pub footer_note: Option<String>,
+    /// Render a cover page
+    pub cover_page: bool,
+    /// Render a table of contents after the cover page
+    pub table_of_contents: bool,
+    /// Ordered report sections
+    pub sections: Vec<ReportSection>,
+    /// Language of renderer-generated labels (cover, TOC, page numbers)
+    pub locale: Locale,
+    /// Time zone in which `generated_on` is displayed
+    pub display_timezone: DisplayTimezone,
+}
+
+impl Report {
+    /// Canonical JSON of the report content used for checksums and signatures.
+    ///
+    /// `footer_note` is excluded because signing stamps the checksum into it.
+    pub fn canonical_payload(&self) -> Vec<u8> {
+        let mut unstamped = self.clone();
+        unstamped.footer_note = None;
+        serde_json::to_vec(&unstamped).unwrap_or_default()
+    }
+
+    /// Lowercase hex SHA-256 of [`Report::canonical_payload`].
+    pub fn payload_sha256(&self) -> String {
+        crate::keystore::sha256_hex(&self.canonical_payload())
+    }
+}
+
+/// Builder for [`Report`] following the `with_*` convention used by `AuditLogEntry`.
+#[derive(Debug, Clone)]
+pub struct ReportBuilder {
+    report: Report,
+}
+
+impl ReportBuilder {
+    /// Start a report with the given title; cover page and TOC are disabled by default.
+    pub fn new<S: Into<String>>(title: S) -> Self {
+        Self {
+            report: Report {
+                title: title.into(),
+                subtitle: None,
+                organization: None,
+                prepared_by: None,
+                generated_on: Utc::now(),
+                application_version: crate::APPLICATION_VERSION.to_string(),
+                footer_note: None,
+                cover_page: false,
+                table_of_contents: false,
+                sections: Vec::new(),
+                locale: Locale::default(),
+                display_timezone: DisplayTimezone::default(),
+            },
+        }
+    }
+
+    /// Set the cover page subtitle
+    pub fn with_subtitle<S: Into<String>>(mut self, subtitle: S) -> Self {
+        self.report.subtitle = Some(subtitle.into());
+        self
+    }
+
+    /// Set the organization name
+    pub fn with_organization<S: Into<String>>(mut self, organization: S) -> Self {
+        self.report.organization = Some(organization.into());
+        self
+    }
+
+    /// Set the preparer shown on the cover page
+    pub fn with_prepared_by<S: Into<String>>(mut self, prepared_by: S) -> Self {
+        self.report.prepared_by = Some(prepared_by.into());
+        self
+    }
+
+    /// Override the generation timestamp (for reproducible output)
+    pub fn with_generated_on(mut self, generated_on: DateTime<Utc>) -> Self {
+        self.report.generated_on = generated_on;
+        self
+    }
+
+    /// Stamp the report with the current time of `clock`
+    pub fn with_clock(self, clock: &dyn Clock) -> Self {
+        self.with_generated_on(clock.now())
+    }
+
+    /// Override the software version printed in the footer
+    pub fn with_application_version<S: Into<String>>(mut self, version: S) -> Self {
+        self.report.application_version = version.into();
+        self
+    }
+
+    /// Add text printed in every page footer
+    pub fn with_footer_note<S: Into<String>>(mut self, note: S) -> Self {
+        self.report.footer_note = Some(note.into());
+        self
+    }
+
+    /// Set the language of renderer-generated labels.
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.report.locale = locale;
+        self
+    }
+
+    /// Set the time zone in which the generation timestamp is displayed.
+    pub fn with_display_timezone(mut self, tz: DisplayTimezone) -> Self {
+        self.report.display_timezone = tz;
+        self
+    }
+
+    /// Enable the cover page.
+    pub fn with_cover_page(mut self) -> Self {
+        self.report.cover_page = true;
+        self
+    }
+
+    /// Enable the table of contents.
+    pub fn with_table_of_contents(mut self) -> Self {
+        self.report.table_of_contents = true;
+        self
+    }
+
+    /// Append a section.
+    pub fn with_section(mut self, section: ReportSection) -> Self {
+        self.report.sections.push(section);
+        self
+    }
+
+    /// Finish building.
+    pub fn build(self) -> Report {
+        self.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_defaults_and_sections() {
+        let report = ReportBuilder::new("CAPA Detail")
+            .with_organization("Acme Medical")
+            .with_cover_page()
+            .with_table_of_contents()
+            .with_section(ReportSection::new("Summary").paragraph("All good"))
+            .with_section(
+                ReportSection::new("Actions")
+                    .with_page_break()
+                    .table(ReportTable::new(vec!["ID", "Description"]).with_row(vec!["1", "Fix"])),
+            )
+            .build();
+
+        assert_eq!(report.title, "CAPA Detail");
+        assert_eq!(report.application_version, crate::APPLICATION_VERSION);
+        assert!(report.cover_page && report.table_of_contents);
+        assert_eq!(report.sections.len(), 2);
+        assert!(report.sections[1].page_break_before);
+        assert!(matches!(report.sections[1].blocks[0], ReportBlock::Table(_)));
+    }
+
+    #[test]
+    fn test_generated_on_from_clock() {
+        use crate::clock::MockClock;
+        use chrono::TimeZone;
+        let clock = MockClock::at(Utc.with_ymd_and_hms(2026, 6, 30, 17, 0, 0).unwrap());
+        let report = ReportBuilder::new("Quarterly Review").with_clock(&clock).build();
+        assert_eq!(report.generated_on, clock.now());
+    }
+
+    #[test]
+    fn test_table_weights_normalized() {
+        let equal = ReportTable::new(vec!["A", "B", "C", "D"]);
+        assert_eq!(equal.normalized_weights(), vec![0.25; 4]);
+
+        let weighted = ReportTable::new(vec!["A", "B"]).with_column_weights(vec![1.0, 3.0]);
+        assert_eq!(weighted.normalized_weights(), vec![0.25, 0.75]);
+
+        // Mismatched weights fall back to equal widths
+        let invalid = ReportTable::new(vec!["A", "B"]).with_column_weights(vec![1.0]);
+        assert_eq!(invalid.normalized_weights(), vec![0.5, 0.5]);
+    }
+
+    #[test]
+    fn test_payload_checksum_ignores_footer_note() {
+        let generated_on = Utc::now();
+        let base = ReportBuilder::new("Report")
+            .with_generated_on(generated_on)
+            .with_section(ReportSection::new("S").paragraph("text"))
+            .build();
+        let stamped = ReportBuilder::new("Report")
+            .with_generated_on(generated_on)
+            .with_footer_note("Payload SHA-256: abc")
+            .with_section(ReportSection::new("S").paragraph("text"))
+            .build();
+        assert_eq!(base.payload_sha256(), stamped.payload_sha256());
+
+        let changed = ReportBuilder::new("Report")
+            .with_generated_on(generated_on)
+            .with_section(ReportSection::new("S").paragraph("edited"))
+            .build();
+        assert_ne!(base.payload_sha256(), changed.payload_sha256());
+    }
+
+    #[test]
+    fn test_chart_from_counts_sorted() {
+        let mut counts = HashMap::new();
+        counts.insert("b".to_string(), 2);
+        counts.insert("a".to_string(), 1);
+        let chart = Chart::from_counts("Dist", ChartKind::Bar, &counts);
+        assert_eq!(chart.data[0].label, "a");
+        assert_eq!(chart.total(), 3.0);
+    }
+}
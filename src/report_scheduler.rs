@@ -0,0 +1,1031 @@
+//! # Report Scheduler - Recurring Report Generation and Distribution
+//!
+//! Recurring reports (monthly compliance summary, weekly CAPA aging) are
+//! configured as [`ReportSchedule`]s. [`ReportScheduler::run_due`] generates
+//! every due report, files it in the EDMS [`FileStore`], emails it to the
+//! schedule's distribution list through a [`Notifier`] and records a
+//! [`ReportRun`] plus an audit trail entry for each run, successful or not, as
+//! evidence of management review inputs (ISO 13485 §5.6, 21 CFR 820.20(c)).
+//! Each run is journaled in the [`WorkflowJournal`]; [`ReportRunRecovery`]
+//! finishes or discards a run a crash interrupted.
+//! [`ReportScheduler::run_escalations`] evaluates the reminder and escalation
+//! rules of the `escalation` module on the same data.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc, Weekday};
+use rusqlite::{params, OptionalExtension};
+use serde_json::json;
+use std::fmt;
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::api::MetricsResponse;
+use crate::audit::AuditManager;
+use crate::audit_findings::{finding_aging_section, AuditFinding};
+use crate::clinical::{clinical_evaluation_section, ClinicalEvaluationSummary};
+use crate::cost_of_quality::{cost_of_quality_section, CostOfQualityReport};
+use crate::capa::CapaRecord;
+use crate::capa_report::build_capa_aging_report;
+use crate::change_history::{record_changes, ChangeReason, FieldChange};
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::escalation::{document_review_subjects, Escalation, EscalationEngine, EscalationSubject};
+use crate::file_store::{FileStore, StoredFile};
+use crate::keystore::Keystore;
+use crate::notification::{is_valid_address, EmailMessage, Notifier};
+use crate::pareto::{pareto_section, ParetoRanking};
+use crate::pdf_report::{build_metrics_report, render_report, MetricsReportConfig};
+use crate::report::Report;
+use crate::report_signature::render_signed_report;
+use crate::workflow_journal::{JournaledWorkflow, RecoveryAction, WorkflowJournal, WorkflowRecovery};
+
+/// File store category for scheduled report outputs.
+pub const SCHEDULED_REPORTS_CATEGORY: &str = "scheduled-reports";
+
+/// User recorded in the audit trail for scheduler actions.
+const SCHEDULER_USER: &str = "system:report_scheduler";
+
+/// Workflow journal kind of a scheduled run.
+pub const REPORT_RUN_WORKFLOW: &str = "scheduled_report_run";
+
+/// Report produced by a schedule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduledReportKind {
+    /// CAPA, risk and supplier metrics with charts
+    ComplianceSummary,
+    /// Open CAPAs bucketed by age
+    CapaAging,
+}
+
+impl ScheduledReportKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScheduledReportKind::ComplianceSummary => "ComplianceSummary",
+            ScheduledReportKind::CapaAging => "CapaAging",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "ComplianceSummary" => Ok(ScheduledReportKind::ComplianceSummary),
+            "CapaAging" => Ok(ScheduledReportKind::CapaAging),
+            _ => Err(QmsError::Validation {
+                field: "kind".to_string(),
+                message: format!("Unknown scheduled report kind: {}", value),
+            }),
+        }
+    }
+
+    /// Prefix of generated file names.
+    fn file_prefix(&self) -> &'static str {
+        match self {
+            ScheduledReportKind::ComplianceSummary => "compliance-summary",
+            ScheduledReportKind::CapaAging => "capa-aging",
+        }
+    }
+}
+
+/// When a schedule fires; runs are due at 00:00 UTC on the given day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recurrence {
+    /// Every week on the given weekday
+    Weekly(Weekday),
+    /// Every month on the given day (1-28 so every month has it)
+    Monthly(u32),
+}
+
+impl Recurrence {
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Recurrence::Monthly(day) if !(1..=28).contains(day) => Err(QmsError::Validation {
+                field: "recurrence".to_string(),
+                message: format!("Monthly day must be between 1 and 28, got {}", day),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// First run time strictly after `after`.
+    pub fn next_after(&self, after: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Recurrence::Weekly(weekday) => {
+                let mut date = after.date_naive() + Duration::days(1);
+                while date.weekday() != *weekday {
+                    date += Duration::days(1);
+                }
+                midnight(date)
+            }
+            Recurrence::Monthly(day) => {
+                let (year, month) = (after.year(), after.month());
+                let this_month = NaiveDate::from_ymd_opt(year, month, *day).map(midnight);
+                match this_month {
+                    Some(candidate) if candidate > after => candidate,
+                    _ => {
+                        let (year, month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+                        midnight(NaiveDate::from_ymd_opt(year, month, (*day).clamp(1, 28)).unwrap_or_default())
+                    }
+                }
+            }
+        }
+    }
+
+    /// Storage form: `weekly:Mon` or `monthly:1`.
+    pub fn parse(value: &str) -> Result<Self> {
+        let invalid = || QmsError::Validation {
+            field: "recurrence".to_string(),
+            message: format!("Invalid recurrence: {}", value),
+        };
+        let recurrence = match value.split_once(':') {
+            Some(("weekly", day)) => Recurrence::Weekly(day.parse::<Weekday>().map_err(|_| invalid())?),
+            Some(("monthly", day)) => Recurrence::Monthly(day.parse::<u32>().map_err(|_| invalid())?),
+            _ => return Err(invalid()),
+        };
+        recurrence.validate()?;
+        Ok(recurrence)
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recurrence::Weekly(day) => write!(f, "weekly:{}", day),
+            Recurrence::Monthly(day) => write!(f, "monthly:{}", day),
+        }
+    }
+}
+
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap_or_default())
+}
+
+/// Recurring report configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportSchedule {
+    pub id: Uuid,
+    pub name: String,
+    pub kind: ScheduledReportKind,
+    pub recurrence: Recurrence,
+    /// Distribution list
+    pub recipients: Vec<String>,
+    pub enabled: bool,
+    pub next_run_at: DateTime<Utc>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ReportSchedule {
+    /// Create an enabled schedule whose first run is the next occurrence after `now`.
+    pub fn new(
+        name: String,
+        kind: ScheduledReportKind,
+        recurrence: Recurrence,
+        recipients: Vec<String>,
+        created_by: String,
+        now: DateTime<Utc>,
+    ) -> Result<Self> {
+        if name.trim().is_empty() {
+            return Err(QmsError::Validation {
+                field: "name".to_string(),
+                message: "Schedule name is required".to_string(),
+            });
+        }
+        recurrence.validate()?;
+        if recipients.is_empty() {
+            return Err(QmsError::Validation {
+                field: "recipients".to_string(),
+                message: "At least one recipient is required".to_string(),
+            });
+        }
+        if let Some(bad) = recipients.iter().find(|r| !is_valid_address(r)) {
+            return Err(QmsError::Validation {
+                field: "recipients".to_string(),
+                message: format!("Invalid email address: {}", bad),
+            });
+        }
+
+        Ok(Self {
+            id: Uuid::new_v4(),
+            name,
+            kind,
+            recurrence,
+            recipients,
+            enabled: true,
+            next_run_at: recurrence.next_after(now),
+            last_run_at: None,
+            created_by,
+            created_at: now,
+        })
+    }
+
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        self.enabled && self.next_run_at <= now
+    }
+}
+
+/// Outcome of a scheduled run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunStatus {
+    Succeeded,
+    Failed,
+}
+
+impl RunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunStatus::Succeeded => "Succeeded",
+            RunStatus::Failed => "Failed",
+        }
+    }
+}
+
+/// Compliance record of one scheduled run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportRun {
+    pub id: Uuid,
+    pub schedule_id: Uuid,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+    pub status: RunStatus,
+    /// File store path relative to the store root
+    pub stored_path: Option<String>,
+    pub file_sha256: Option<String>,
+    /// Message identifier of the distribution email
+    pub message_id: Option<String>,
+    pub recipients: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// Repository for `report_schedules` and `report_runs`.
+pub struct ReportScheduleRepo<'a> {
+    db: &'a Database,
+}
+
+impl<'a> ReportScheduleRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    pub fn insert(&self, schedule: &ReportSchedule) -> Result<()> {
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO report_schedules (
+                    id, name, kind, recurrence, recipients, enabled, next_run_at, last_run_at,
+                    created_by, created_at
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    schedule.id.to_string(),
+                    schedule.name,
+                    schedule.kind.as_str(),
+                    schedule.recurrence.to_string(),
+                    serde_json::to_string(&schedule.recipients)?,
+                    schedule.enabled,
+                    schedule.next_run_at.to_rfc3339(),
+                    schedule.last_run_at.map(|t| t.to_rfc3339()),
+                    schedule.created_by,
+                    schedule.created_at.to_rfc3339(),
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn get(&self, id: Uuid) -> Result<ReportSchedule> {
+        self.db.with_connection(|conn| {
+            conn.query_row(
+                "SELECT id, name, kind, recurrence, recipients, enabled, next_run_at, last_run_at,
+                        created_by, created_at
+                 FROM report_schedules WHERE id = ?1 AND deleted_at IS NULL",
+                params![id.to_string()],
+                row_to_schedule,
+            )
+            .optional()?
+            .ok_or_else(|| QmsError::NotFound {
+                resource: "report_schedule".to_string(),
+                id: id.to_string(),
+            })
+        })
+    }
+
+    pub fn list(&self) -> Result<Vec<ReportSchedule>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, name, kind, recurrence, recipients, enabled, next_run_at, last_run_at,
+                        created_by, created_at
+                 FROM report_schedules WHERE deleted_at IS NULL ORDER BY name",
+            )?;
+            let rows = stmt.query_map([], row_to_schedule)?;
+            Ok(rows.collect::<std::result::Result<Vec<_>, _>>()?)
+        })
+    }
+
+    /// Enable or disable a schedule, recording who changed it and why.
+    pub fn set_enabled(&self, id: Uuid, enabled: bool, changed_by: &str, reason: &ChangeReason) -> Result<()> {
+        self.db.with_connection(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            let previous: bool = tx
+                .query_row(
+                    "SELECT enabled FROM report_schedules WHERE id = ?1 AND deleted_at IS NULL",
+                    params![id.to_string()],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .ok_or_else(|| QmsError::NotFound {
+                    resource: "report_schedule".to_string(),
+                    id: id.to_string(),
+                })?;
+            tx.execute(
+                "UPDATE report_schedules SET enabled = ?2 WHERE id = ?1",
+                params![id.to_string(), enabled],
+            )?;
+            if previous != enabled {
+                let change = FieldChange {
+                    field: "enabled".to_string(),
+                    old_value: Some(previous.to_string()),
+                    new_value: Some(enabled.to_string()),
+                };
+                record_changes(&tx, "report_schedule", &id.to_string(), changed_by, reason, &[change])?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// Record `run` and advance the schedule in one transaction.
+    pub fn record_run(&self, schedule: &ReportSchedule, run: &ReportRun) -> Result<()> {
+        self.db.with_connection(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute(
+                "INSERT INTO report_runs (
+                    id, schedule_id, started_at, completed_at, status, stored_path, file_sha256,
+                    message_id, recipients, error
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    run.id.to_string(),
+                    run.schedule_id.to_string(),
+                    run.started_at.to_rfc3339(),
+                    run.completed_at.to_rfc3339(),
+                    run.status.as_str(),
+                    run.stored_path,
+                    run.file_sha256,
+                    run.message_id,
+                    serde_json::to_string(&run.recipients)?,
+                    run.error,
+                ],
+            )?;
+            tx.execute(
+                "UPDATE report_schedules SET next_run_at = ?2, last_run_at = ?3 WHERE id = ?1",
+                params![
+                    schedule.id.to_string(),
+                    schedule.next_run_at.to_rfc3339(),
+                    schedule.last_run_at.map(|t| t.to_rfc3339()),
+                ],
+            )?;
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// Runs of a schedule, newest first.
+    pub fn runs_for(&self, schedule_id: Uuid) -> Result<Vec<ReportRun>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, schedule_id, started_at, completed_at, status, stored_path, file_sha256,
+                        message_id, recipients, error
+                 FROM report_runs WHERE schedule_id = ?1 ORDER BY started_at DESC",
+            )?;
+            let rows = stmt.query_map(params![schedule_id.to_string()], row_to_run)?;
+            Ok(rows.collect::<std::result::Result<Vec<_>, _>>()?)
+        })
+    }
+}
+
+fn conversion_error<E: std::error::Error + Send + Sync + 'static>(idx: usize, e: E) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e))
+}
+
+fn parse_uuid(row: &rusqlite::Row, idx: usize) -> rusqlite::Result<Uuid> {
+    let value: String = row.get(idx)?;
+    Uuid::parse_str(&value).map_err(|e| conversion_error(idx, e))
+}
+
+fn parse_time(row: &rusqlite::Row, idx: usize) -> rusqlite::Result<DateTime<Utc>> {
+    let value: String = row.get(idx)?;
+    DateTime::parse_from_rfc3339(&value)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| conversion_error(idx, e))
+}
+
+fn parse_recipients(row: &rusqlite::Row, idx: usize) -> rusqlite::Result<Vec<String>> {
+    let value: String = row.get(idx)?;
+    serde_json::from_str(&value).map_err(|e| conversion_error(idx, e))
+}
+
+fn row_to_schedule(row: &rusqlite::Row) -> rusqlite::Result<ReportSchedule> {
+    let kind: String = row.get(2)?;
+    let recurrence: String = row.get(3)?;
+    let last_run_at: Option<String> = row.get(7)?;
+    Ok(ReportSchedule {
+        id: parse_uuid(row, 0)?,
+        name: row.get(1)?,
+        kind: ScheduledReportKind::parse(&kind).map_err(|e| conversion_error(2, e))?,
+        recurrence: Recurrence::parse(&recurrence).map_err(|e| conversion_error(3, e))?,
+        recipients: parse_recipients(row, 4)?,
+        enabled: row.get(5)?,
+        next_run_at: parse_time(row, 6)?,
+        last_run_at: match last_run_at {
+            Some(_) => Some(parse_time(row, 7)?),
+            None => None,
+        },
+        created_by: row.get(8)?,
+        created_at: parse_time(row, 9)?,
+    })
+}
+
+fn row_to_run(row: &rusqlite::Row) -> rusqlite::Result<ReportRun> {
+    let status: String = row.get(4)?;
+    Ok(ReportRun {
+        id: parse_uuid(row, 0)?,
+        schedule_id: parse_uuid(row, 1)?,
+        started_at: parse_time(row, 2)?,
+        completed_at: parse_time(row, 3)?,
+        status: if status == "Succeeded" { RunStatus::Succeeded } else { RunStatus::Failed },
+        stored_path: row.get(5)?,
+        file_sha256: row.get(6)?,
+        message_id: row.get(7)?,
+        recipients: parse_recipients(row, 8)?,
+        error: row.get(9)?,
+    })
+}
+
+/// Data snapshot the scheduled reports are generated from.
+#[derive(Debug, Clone, Copy)]
+pub struct ReportData<'a> {
+    pub capa_records: &'a [CapaRecord],
+    pub metrics: &'a MetricsResponse,
+    /// Audit findings summarized as a management review input
+    pub audit_findings: &'a [AuditFinding],
+    /// Clinical evaluation status, also a management review input
+    pub clinical_evaluations: &'a [ClinicalEvaluationSummary],
+    /// Complaint and NCR causes ranked for the review period
+    pub cause_pareto: Option<&'a ParetoRanking>,
+    /// Quality costs per quarter, the financial input
+    pub cost_of_quality: Option<&'a CostOfQualityReport>,
+}
+
+/// Generates, files and distributes due scheduled reports.
+pub struct ReportScheduler<'a> {
+    db: &'a Database,
+    store: &'a FileStore,
+    notifier: Option<&'a dyn Notifier>,
+    keystore: Option<&'a Keystore>,
+}
+
+impl<'a> ReportScheduler<'a> {
+    pub fn new(db: &'a Database, store: &'a FileStore) -> Self {
+        Self { db, store, notifier: None, keystore: None }
+    }
+
+    /// Email reports to each schedule's distribution list.
+    pub fn with_notifier(mut self, notifier: &'a dyn Notifier) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Sign reports with the system key (see `report_signature`).
+    pub fn with_keystore(mut self, keystore: &'a Keystore) -> Self {
+        self.keystore = Some(keystore);
+        self
+    }
+
+    /// Run every enabled schedule due at `now`.
+    ///
+    /// A failing schedule is recorded as a failed run and does not prevent the
+    /// remaining schedules from running.
+    pub fn run_due(&self, now: DateTime<Utc>, data: &ReportData) -> Result<Vec<ReportRun>> {
+        let repo = ReportScheduleRepo::new(self.db);
+        let audit = AuditManager::new(self.db.clone());
+        let journal = WorkflowJournal::new(self.db.clone());
+        let mut runs = Vec::new();
+
+        for mut schedule in repo.list()?.into_iter().filter(|s| s.is_due(now)) {
+            let workflow = journal.begin(REPORT_RUN_WORKFLOW, &schedule_subject(schedule.id), SCHEDULER_USER, now)?;
+            let run = self.run_schedule(&schedule, now, data, &journal, &workflow);
+            schedule.last_run_at = Some(now);
+            schedule.next_run_at = schedule.recurrence.next_after(now);
+            repo.record_run(&schedule, &run)?;
+
+            let details = match &run.error {
+                Some(error) => format!("Scheduled report '{}' failed: {}", schedule.name, error),
+                None => format!(
+                    "Scheduled report '{}' stored as {} and sent to {} recipient(s)",
+                    schedule.name,
+                    run.stored_path.as_deref().unwrap_or("-"),
+                    if run.message_id.is_some() { run.recipients.len() } else { 0 }
+                ),
+            };
+            audit.log_action(
+                SCHEDULER_USER,
+                "scheduled_report_run",
+                &format!("report_schedule:{}", schedule.id),
+                if run.status == RunStatus::Succeeded { "Success" } else { "Failure" },
+                Some(details),
+            )?;
+            journal.complete(&workflow, run.completed_at)?;
+            runs.push(run);
+        }
+        Ok(runs)
+    }
+
+    /// Evaluate the escalation rules against the open CAPAs in `data` and the
+    /// documents awaiting periodic review, notifying through this scheduler's
+    /// notifier.
+    pub fn run_escalations(&self, now: DateTime<Utc>, data: &ReportData) -> Result<Vec<Escalation>> {
+        let mut subjects: Vec<EscalationSubject> =
+            data.capa_records.iter().filter_map(EscalationSubject::from_capa).collect();
+        subjects.extend(document_review_subjects(self.db)?);
+        let engine = EscalationEngine::new(self.db);
+        match self.notifier {
+            Some(notifier) => engine.with_notifier(notifier).evaluate(&subjects, now),
+            None => engine.evaluate(&subjects, now),
+        }
+    }
+
+    /// Generate, file and distribute one report, journaling each step so an
+    /// interrupted run can be recovered (see [`ReportRunRecovery`]).
+    fn run_schedule(
+        &self,
+        schedule: &ReportSchedule,
+        now: DateTime<Utc>,
+        data: &ReportData,
+        journal: &WorkflowJournal,
+        workflow: &str,
+    ) -> ReportRun {
+        let mut run = ReportRun {
+            id: Uuid::new_v4(),
+            schedule_id: schedule.id,
+            started_at: now,
+            completed_at: now,
+            status: RunStatus::Succeeded,
+            stored_path: None,
+            file_sha256: None,
+            message_id: None,
+            recipients: schedule.recipients.clone(),
+            error: None,
+        };
+
+        let result = self.generate(schedule, now, data).and_then(|stored| {
+            run.stored_path = Some(stored.relative_path.clone());
+            run.file_sha256 = Some(stored.sha256.clone());
+            let filed = json!({ "stored_path": stored.relative_path, "sha256": stored.sha256 });
+            journal.record_step(workflow, FILED_STEP, &filed, now)?;
+            let message_id = match self.notifier {
+                Some(notifier) => Some(notifier.send(&distribution_email(schedule, &stored, now))?),
+                None => None,
+            };
+            journal.record_step(workflow, DISTRIBUTED_STEP, &json!({ "message_id": message_id }), now)?;
+            Ok(message_id)
+        });
+        match result {
+            Ok(message_id) => run.message_id = message_id,
+            Err(e) => {
+                tracing::error!(schedule = %schedule.name, "scheduled report failed: {e}");
+                run.status = RunStatus::Failed;
+                run.error = Some(e.to_string());
+            }
+        }
+        run.completed_at = Utc::now().max(now);
+        run
+    }
+
+    /// Render the schedule's report and file it in the store.
+    fn generate(&self, schedule: &ReportSchedule, now: DateTime<Utc>, data: &ReportData) -> Result<StoredFile> {
+        let file_name = format!("{}-{}.pdf", schedule.kind.file_prefix(), now.format("%Y%m%d-%H%M%S"));
+        let staging = self.store.staging_dir()?.join(format!("{}.pdf", Uuid::new_v4()));
+        let report = build_scheduled_report(schedule, now, data, &staging);
+
+        let rendered = match self.keystore {
+            Some(keystore) => render_signed_report(&report, &staging, keystore, self.db, SCHEDULER_USER).map(|_| ()),
+            None => render_report(&report, &staging).map(|_| ()),
+        };
+        let stored = rendered.and_then(|_| {
+            let contents = std::fs::read(&staging).map_err(|e| QmsError::FileSystem {
+                path: staging.display().to_string(),
+                message: e.to_string(),
+            })?;
+            self.store.store(SCHEDULED_REPORTS_CATEGORY, &file_name, &contents, now)
+        });
+        let _ = std::fs::remove_file(&staging);
+        stored
+    }
+}
+
+const FILED_STEP: &str = "filed";
+const DISTRIBUTED_STEP: &str = "distributed";
+
+fn schedule_subject(schedule_id: Uuid) -> String {
+    format!("report_schedule:{}", schedule_id)
+}
+
+/// Recovers scheduled runs interrupted by a crash.
+///
+/// A run that was distributed is resumed: the run is recorded and the
+/// schedule advanced, so recipients do not get the report twice. Any other
+/// run is rolled back: nothing is recorded and the schedule stays due, so the
+/// next scheduler pass produces it again. A report already filed is kept, as
+/// the file store is write-once, and named in the resolution.
+pub struct ReportRunRecovery {
+    db: Database,
+}
+
+impl ReportRunRecovery {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+}
+
+impl WorkflowRecovery for ReportRunRecovery {
+    fn kind(&self) -> &str {
+        REPORT_RUN_WORKFLOW
+    }
+
+    fn recover(&self, workflow: &JournaledWorkflow, now: DateTime<Utc>) -> Result<RecoveryAction> {
+        let schedule_id = workflow
+            .subject
+            .strip_prefix("report_schedule:")
+            .and_then(|id| Uuid::parse_str(id).ok())
+            .ok_or_else(|| QmsError::Validation {
+                field: "subject".to_string(),
+                message: format!("'{}' is not a report schedule", workflow.subject),
+            })?;
+        let filed = workflow.step(FILED_STEP);
+        let stored_path = filed.and_then(|step| step["stored_path"].as_str()).map(str::to_string);
+        let Some(distributed) = workflow.step(DISTRIBUTED_STEP) else {
+            return Ok(RecoveryAction::RolledBack(match stored_path {
+                Some(path) => format!(
+                    "Run discarded before distribution; the schedule stays due. Filed report {} is retained",
+                    path
+                ),
+                None => "Run discarded before a report was filed; the schedule stays due".to_string(),
+            }));
+        };
+
+        let repo = ReportScheduleRepo::new(&self.db);
+        if repo.runs_for(schedule_id)?.iter().any(|run| run.started_at == workflow.started_at) {
+            return Ok(RecoveryAction::Resumed("Run was already recorded".to_string()));
+        }
+        let mut schedule = repo.get(schedule_id)?;
+        let run = ReportRun {
+            id: Uuid::new_v4(),
+            schedule_id,
+            started_at: workflow.started_at,
+            completed_at: now,
+            status: RunStatus::Succeeded,
+            stored_path,
+            file_sha256: filed.and_then(|step| step["sha256"].as_str()).map(str::to_string),
+            message_id: distributed["message_id"].as_str().map(str::to_string),
+            recipients: schedule.recipients.clone(),
+            error: None,
+        };
+        schedule.last_run_at = Some(workflow.started_at);
+        schedule.next_run_at = schedule.recurrence.next_after(workflow.started_at);
+        repo.record_run(&schedule, &run)?;
+        Ok(RecoveryAction::Resumed(format!(
+            "Report {} was already distributed; run {} recorded and the schedule advanced",
+            run.stored_path.as_deref().unwrap_or("-"),
+            run.id
+        )))
+    }
+}
+
+fn build_scheduled_report(schedule: &ReportSchedule, now: DateTime<Utc>, data: &ReportData, output_path: &Path) -> Report {
+    match schedule.kind {
+        ScheduledReportKind::ComplianceSummary => {
+            let mut report = build_metrics_report(&MetricsReportConfig {
+                output_path,
+                application_version: crate::APPLICATION_VERSION,
+                metrics: data.metrics,
+                generated_on: now,
+                title: Some(schedule.name.as_str()),
+            });
+            report.sections.push(finding_aging_section(data.audit_findings, now));
+            report.sections.push(clinical_evaluation_section(data.clinical_evaluations, now));
+            report.sections.extend(data.cause_pareto.map(pareto_section));
+            report.sections.extend(data.cost_of_quality.map(cost_of_quality_section));
+            report
+        }
+        ScheduledReportKind::CapaAging => {
+            let mut report = build_capa_aging_report(data.capa_records, now);
+            report.title = schedule.name.clone();
+            report
+        }
+    }
+}
+
+fn distribution_email(schedule: &ReportSchedule, stored: &StoredFile, now: DateTime<Utc>) -> EmailMessage {
+    let body = format!(
+        "The scheduled report \"{}\" was generated on {}.\n\n\
+         Stored as: {}\nSHA-256: {}\n\n\
+         This message was sent automatically by QMSrs.",
+        schedule.name,
+        now.format("%Y-%m-%d %H:%M UTC"),
+        stored.relative_path,
+        stored.sha256,
+    );
+    EmailMessage::new(
+        schedule.recipients.clone(),
+        format!("{} - {}", schedule.name, now.format("%Y-%m-%d")),
+        body,
+    )
+    .with_attachment(&stored.path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capa::CapaMetrics;
+    use crate::config::DatabaseConfig;
+    use crate::notification::OutboxNotifier;
+    use crate::risk::{ComplianceStatus, RiskManagementReport};
+    use crate::workflow_journal::WorkflowStatus;
+    use std::collections::HashMap;
+    use tempfile::tempdir;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    fn empty_metrics() -> MetricsResponse {
+        MetricsResponse {
+            capa_metrics: CapaMetrics {
+                total_count: 0,
+                status_counts: HashMap::new(),
+                priority_counts: HashMap::new(),
+                overdue_count: 0,
+                closed_count: 0,
+            },
+            risk_report: RiskManagementReport {
+                id: Uuid::new_v4(),
+                generated_at: Utc::now(),
+                generated_by: "tester".to_string(),
+                total_assessments: 0,
+                risk_level_distribution: HashMap::new(),
+                acceptability_distribution: HashMap::new(),
+                pending_control_measures: 0,
+                compliance_status: ComplianceStatus::Compliant,
+            },
+            supplier_metrics: Default::default(),
+            kpis: Vec::new(),
+        }
+    }
+
+    fn at(y: i32, m: u32, d: u32, h: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap()
+    }
+
+    struct FailingNotifier;
+
+    impl Notifier for FailingNotifier {
+        fn send(&self, _message: &EmailMessage) -> Result<String> {
+            Err(QmsError::Network { message: "relay unavailable".to_string() })
+        }
+    }
+
+    #[test]
+    fn test_recurrence_next_after() {
+        // 2025-03-14 is a Friday.
+        let friday = at(2025, 3, 14, 9);
+        assert_eq!(Recurrence::Weekly(Weekday::Mon).next_after(friday), at(2025, 3, 17, 0));
+        assert_eq!(Recurrence::Weekly(Weekday::Fri).next_after(friday), at(2025, 3, 21, 0));
+        assert_eq!(Recurrence::Monthly(20).next_after(friday), at(2025, 3, 20, 0));
+        assert_eq!(Recurrence::Monthly(1).next_after(friday), at(2025, 4, 1, 0));
+        assert_eq!(Recurrence::Monthly(1).next_after(at(2025, 12, 5, 0)), at(2026, 1, 1, 0));
+    }
+
+    #[test]
+    fn test_recurrence_round_trip_and_validation() {
+        for recurrence in [Recurrence::Weekly(Weekday::Wed), Recurrence::Monthly(28)] {
+            assert_eq!(Recurrence::parse(&recurrence.to_string()).unwrap(), recurrence);
+        }
+        assert!(Recurrence::parse("monthly:31").is_err());
+        assert!(Recurrence::parse("daily").is_err());
+    }
+
+    #[test]
+    fn test_schedule_requires_valid_recipients() {
+        let result = ReportSchedule::new(
+            "CAPA Aging".to_string(),
+            ScheduledReportKind::CapaAging,
+            Recurrence::Weekly(Weekday::Mon),
+            vec!["not-an-address".to_string()],
+            "qa1".to_string(),
+            Utc::now(),
+        );
+        assert!(matches!(result, Err(QmsError::Validation { .. })));
+    }
+
+    #[test]
+    fn test_run_due_generates_stores_and_distributes() {
+        let db = test_db();
+        let dir = tempdir().unwrap();
+        let store = FileStore::for_data_dir(dir.path());
+        let notifier = OutboxNotifier::new(dir.path().join("outbox"), "qms@example.com");
+        let repo = ReportScheduleRepo::new(&db);
+
+        let created = at(2025, 3, 14, 9);
+        let monthly = ReportSchedule::new(
+            "Monthly Compliance Summary".to_string(),
+            ScheduledReportKind::ComplianceSummary,
+            Recurrence::Monthly(1),
+            vec!["qa@example.com".to_string()],
+            "qa1".to_string(),
+            created,
+        )
+        .unwrap();
+        let weekly = ReportSchedule::new(
+            "Weekly CAPA Aging".to_string(),
+            ScheduledReportKind::CapaAging,
+            Recurrence::Weekly(Weekday::Mon),
+            vec!["qa@example.com".to_string(), "ops@example.com".to_string()],
+            "qa1".to_string(),
+            created,
+        )
+        .unwrap();
+        repo.insert(&monthly).unwrap();
+        repo.insert(&weekly).unwrap();
+
+        let metrics = empty_metrics();
+        let data = ReportData {
+            capa_records: &[],
+            metrics: &metrics,
+            audit_findings: &[],
+            clinical_evaluations: &[],
+            cause_pareto: None,
+            cost_of_quality: None,
+        };
+        let scheduler = ReportScheduler::new(&db, &store).with_notifier(&notifier);
+
+        // Monday 2025-03-17: only the weekly schedule is due.
+        let monday = at(2025, 3, 17, 1);
+        let runs = scheduler.run_due(monday, &data).unwrap();
+        assert_eq!(runs.len(), 1);
+        let run = &runs[0];
+        assert_eq!(run.status, RunStatus::Succeeded, "{:?}", run.error);
+        assert_eq!(run.stored_path.as_deref(), Some("scheduled-reports/2025/03/capa-aging-20250317-010000.pdf"));
+        assert!(run.message_id.is_some());
+        assert!(store.root().join(run.stored_path.as_ref().unwrap()).exists());
+        assert_eq!(std::fs::read_dir(notifier.outbox()).unwrap().count(), 1);
+
+        let advanced = repo.get(weekly.id).unwrap();
+        assert_eq!(advanced.last_run_at, Some(monday));
+        assert_eq!(advanced.next_run_at, at(2025, 3, 24, 0));
+        assert_eq!(repo.runs_for(weekly.id).unwrap(), runs);
+        assert!(scheduler.run_due(monday, &data).unwrap().is_empty(), "run must not repeat");
+
+        let audit = db.get_audit_entries_for_resource(&format!("report_schedule:{}", weekly.id)).unwrap();
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0].action, "scheduled_report_run");
+        assert!(WorkflowJournal::new(db.clone()).incomplete().unwrap().is_empty(), "run journaled as completed");
+    }
+
+    #[test]
+    fn test_failed_distribution_is_recorded() {
+        let db = test_db();
+        let dir = tempdir().unwrap();
+        let store = FileStore::new(dir.path());
+        let repo = ReportScheduleRepo::new(&db);
+        let schedule = ReportSchedule::new(
+            "Weekly CAPA Aging".to_string(),
+            ScheduledReportKind::CapaAging,
+            Recurrence::Weekly(Weekday::Mon),
+            vec!["qa@example.com".to_string()],
+            "qa1".to_string(),
+            at(2025, 3, 14, 9),
+        )
+        .unwrap();
+        repo.insert(&schedule).unwrap();
+
+        let metrics = empty_metrics();
+        let data = ReportData {
+            capa_records: &[],
+            metrics: &metrics,
+            audit_findings: &[],
+            clinical_evaluations: &[],
+            cause_pareto: None,
+            cost_of_quality: None,
+        };
+        let notifier = FailingNotifier;
+        let runs = ReportScheduler::new(&db, &store)
+            .with_notifier(&notifier)
+            .run_due(at(2025, 3, 17, 0), &data)
+            .unwrap();
+
+        assert_eq!(runs[0].status, RunStatus::Failed);
+        assert!(runs[0].stored_path.is_some(), "report is filed even when email fails");
+        assert!(runs[0].error.as_deref().unwrap().contains("relay unavailable"));
+        let audit = db.get_audit_entries_for_resource(&format!("report_schedule:{}", schedule.id)).unwrap();
+        assert_eq!(audit[0].outcome, "FAILURE");
+    }
+
+    #[test]
+    fn test_interrupted_runs_are_resumed_or_rolled_back() {
+        let db = test_db();
+        let repo = ReportScheduleRepo::new(&db);
+        let journal = WorkflowJournal::new(db.clone());
+        let schedule = |name: &str| {
+            let schedule = ReportSchedule::new(
+                name.to_string(),
+                ScheduledReportKind::CapaAging,
+                Recurrence::Weekly(Weekday::Mon),
+                vec!["qa@example.com".to_string()],
+                "qa1".to_string(),
+                at(2025, 3, 14, 9),
+            )
+            .unwrap();
+            repo.insert(&schedule).unwrap();
+            schedule
+        };
+        let monday = at(2025, 3, 17, 1);
+        let sent = schedule("Sent");
+        let unsent = schedule("Unsent");
+
+        // Crash after emailing but before the run was recorded
+        let begin = |id| journal.begin(REPORT_RUN_WORKFLOW, &schedule_subject(id), SCHEDULER_USER, monday).unwrap();
+        let workflow = begin(sent.id);
+        let filed = json!({ "stored_path": "scheduled-reports/2025/03/a.pdf", "sha256": "ab" });
+        journal.record_step(&workflow, FILED_STEP, &filed, monday).unwrap();
+        journal.record_step(&workflow, DISTRIBUTED_STEP, &json!({ "message_id": "m1" }), monday).unwrap();
+        // Crash after filing but before emailing
+        let workflow = begin(unsent.id);
+        journal.record_step(&workflow, FILED_STEP, &filed, monday).unwrap();
+
+        let recovery = ReportRunRecovery::new(db.clone());
+        let outcomes = journal.recover(&[&recovery], at(2025, 3, 17, 8)).unwrap();
+        let statuses: Vec<_> = outcomes.iter().map(|outcome| outcome.status).collect();
+        assert_eq!(statuses, vec![WorkflowStatus::Resumed, WorkflowStatus::RolledBack]);
+
+        let runs = repo.runs_for(sent.id).unwrap();
+        assert_eq!((runs[0].message_id.as_deref(), runs[0].started_at), (Some("m1"), monday));
+        assert!(!repo.get(sent.id).unwrap().is_due(monday), "not emailed twice");
+        assert!(repo.runs_for(unsent.id).unwrap().is_empty());
+        assert!(repo.get(unsent.id).unwrap().is_due(monday), "runs again");
+        assert!(outcomes[1].resolution.contains("scheduled-reports/2025/03/a.pdf"));
+    }
+
+    #[test]
+    fn test_run_escalations_notifies_overdue_document_review() {
+        use crate::escalation::{EscalationRule, EscalationRuleRepo, SubjectKind, Trigger};
+
+        let db = test_db();
+        db.seed_test_users(&["qa1"]);
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO documents (id, document_number, title, version, status, document_type, content_hash, created_by, review_date)
+                 VALUES ('d1', 'SOP-BER-001', 'Cleaning', '1.0', 'Effective', 'SOP', 'h', 'qa1', '2025-03-01')",
+                [],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        let rule = EscalationRule::new(
+            "Review overdue",
+            SubjectKind::Document,
+            Trigger::OverdueBy,
+            14,
+            None,
+            "Department head",
+            vec!["head@example.com".to_string()],
+            "qa1",
+        )
+        .unwrap();
+        EscalationRuleRepo::new(&db).insert(&rule).unwrap();
+
+        let dir = tempdir().unwrap();
+        let store = FileStore::new(dir.path());
+        let notifier = OutboxNotifier::new(dir.path().join("outbox"), "qms@example.com");
+        let metrics = empty_metrics();
+        let data = ReportData {
+            capa_records: &[],
+            metrics: &metrics,
+            audit_findings: &[],
+            clinical_evaluations: &[],
+            cause_pareto: None,
+            cost_of_quality: None,
+        };
+        let scheduler = ReportScheduler::new(&db, &store).with_notifier(&notifier);
+
+        assert!(scheduler.run_escalations(at(2025, 3, 10, 0), &data).unwrap().is_empty());
+        let fired = scheduler.run_escalations(at(2025, 3, 17, 0), &data).unwrap();
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].reference, "SOP-BER-001");
+        assert_eq!(std::fs::read_dir(notifier.outbox()).unwrap().count(), 1);
+        let audit = db.get_audit_entries_for_resource("document:d1").unwrap();
+        assert_eq!(audit[0].action, "escalation_fired");
+    }
+}
@@ -0,0 +1,379 @@
+//! # Report Signatures - Checksummed and Signed PDF Outputs
+//!
+//! Every signed report carries the SHA-256 of its canonical payload in the page
+//! footer. After rendering, the SHA-256 of the finished PDF file and the payload
+//! checksum are signed with the system key from the [`Keystore`] and recorded in
+//! `report_signatures`. `qmsrs verify-report <file>` recomputes the file hash,
+//! looks up the registry entry and checks the signature, so any modification of
+//! a distributed report is detected (FDA 21 CFR Part 11 §11.10(c), §11.70).
+
+use base64::{engine::general_purpose, Engine as _};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, OptionalExtension};
+use std::fmt;
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::keystore::{self, Keystore, SIGNATURE_ALGORITHM};
+use crate::pdf_report::{render_report, RenderedReport};
+use crate::report::Report;
+
+/// Footer prefix preceding the embedded payload checksum.
+pub const PAYLOAD_MARKER: &str = "Payload SHA-256: ";
+
+/// Registry entry for a signed report file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportSignatureRecord {
+    pub id: Uuid,
+    pub report_title: String,
+    pub file_name: String,
+    /// Hex SHA-256 of the PDF file as written
+    pub file_sha256: String,
+    /// Hex SHA-256 of the report payload (also printed in the footer)
+    pub payload_sha256: String,
+    /// Base64 Ed25519 signature over [`signing_message`]
+    pub signature: String,
+    /// Base64 raw public key used for signing
+    pub public_key: String,
+    pub key_id: String,
+    pub algorithm: String,
+    pub signed_by: String,
+    pub signed_at: DateTime<Utc>,
+}
+
+/// Result of rendering and signing a report.
+#[derive(Debug, Clone)]
+pub struct SignedReport {
+    pub rendered: RenderedReport,
+    pub record: ReportSignatureRecord,
+}
+
+/// Message covered by the signature; binds file bytes and payload together.
+pub fn signing_message(file_sha256: &str, payload_sha256: &str) -> Vec<u8> {
+    format!("qmsrs-report-v1:{}:{}", file_sha256, payload_sha256).into_bytes()
+}
+
+/// Repository for the `report_signatures` table.
+pub struct ReportSignatureRepo<'a> {
+    db: &'a Database,
+}
+
+impl<'a> ReportSignatureRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Persist a new signature record.
+    pub fn insert(&self, record: &ReportSignatureRecord) -> Result<()> {
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO report_signatures (
+                    id, report_title, file_name, file_sha256, payload_sha256, signature,
+                    public_key, key_id, algorithm, signed_by, signed_at
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    record.id.to_string(),
+                    record.report_title,
+                    record.file_name,
+                    record.file_sha256,
+                    record.payload_sha256,
+                    record.signature,
+                    record.public_key,
+                    record.key_id,
+                    record.algorithm,
+                    record.signed_by,
+                    record.signed_at.to_rfc3339(),
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Look up the record for a PDF by its file hash.
+    pub fn find_by_file_hash(&self, file_sha256: &str) -> Result<Option<ReportSignatureRecord>> {
+        self.db.with_connection(|conn| {
+            let record = conn
+                .query_row(
+                    "SELECT id, report_title, file_name, file_sha256, payload_sha256, signature,
+                            public_key, key_id, algorithm, signed_by, signed_at
+                     FROM report_signatures WHERE file_sha256 = ?1",
+                    params![file_sha256],
+                    row_to_record,
+                )
+                .optional()?;
+            Ok(record)
+        })
+    }
+}
+
+fn row_to_record(row: &rusqlite::Row) -> rusqlite::Result<ReportSignatureRecord> {
+    let id: String = row.get(0)?;
+    let signed_at: String = row.get(10)?;
+    Ok(ReportSignatureRecord {
+        id: Uuid::parse_str(&id).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+        report_title: row.get(1)?,
+        file_name: row.get(2)?,
+        file_sha256: row.get(3)?,
+        payload_sha256: row.get(4)?,
+        signature: row.get(5)?,
+        public_key: row.get(6)?,
+        key_id: row.get(7)?,
+        algorithm: row.get(8)?,
+        signed_by: row.get(9)?,
+        signed_at: DateTime::parse_from_rfc3339(&signed_at)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(10, rusqlite::types::Type::Text, Box::new(e)))?
+            .with_timezone(&Utc),
+    })
+}
+
+/// Render `report` with its payload checksum in the footer, sign the output
+/// with the system key and record the signature.
+pub fn render_signed_report(
+    report: &Report,
+    output_path: &Path,
+    keystore: &Keystore,
+    db: &Database,
+    signed_by: &str,
+) -> Result<SignedReport> {
+    let payload_sha256 = report.payload_sha256();
+    let mut stamped = report.clone();
+    let marker = format!("{}{}", PAYLOAD_MARKER, payload_sha256);
+    stamped.footer_note = Some(match &report.footer_note {
+        Some(note) => format!("{} | {}", marker, note),
+        None => marker,
+    });
+
+    let rendered = render_report(&stamped, output_path)?;
+    let file_sha256 = file_sha256(output_path)?;
+    let signature = keystore.sign(&signing_message(&file_sha256, &payload_sha256));
+
+    let record = ReportSignatureRecord {
+        id: Uuid::new_v4(),
+        report_title: report.title.clone(),
+        file_name: output_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        file_sha256,
+        payload_sha256,
+        signature: general_purpose::STANDARD.encode(signature),
+        public_key: general_purpose::STANDARD.encode(keystore.public_key()),
+        key_id: keystore.key_id(),
+        algorithm: SIGNATURE_ALGORITHM.to_string(),
+        signed_by: signed_by.to_string(),
+        signed_at: Utc::now(),
+    };
+    ReportSignatureRepo::new(db).insert(&record)?;
+
+    tracing::info!(
+        component = "report_signature",
+        action = "report_signed",
+        report = %record.report_title,
+        file_sha256 = %record.file_sha256,
+        key_id = %record.key_id,
+        user_id = %signed_by,
+        "Signed report registered"
+    );
+
+    Ok(SignedReport { rendered, record })
+}
+
+/// Outcome of `verify-report`.
+#[derive(Debug, Clone)]
+pub struct ReportVerification {
+    /// Hex SHA-256 of the file on disk
+    pub file_sha256: String,
+    /// Payload checksum found in the PDF footer, if any
+    pub embedded_payload_sha256: Option<String>,
+    /// Registry entry matching the file hash
+    pub record: Option<ReportSignatureRecord>,
+    /// Signature verifies against the recorded public key
+    pub signature_valid: bool,
+    /// Embedded checksum equals the recorded payload checksum
+    pub payload_matches: bool,
+    /// Recorded key equals the current system key (`None` when no key is available)
+    pub signed_with_system_key: Option<bool>,
+}
+
+impl ReportVerification {
+    /// The file is registered, untampered, and its signature verifies.
+    pub fn is_valid(&self) -> bool {
+        self.record.is_some()
+            && self.signature_valid
+            && self.payload_matches
+            && self.signed_with_system_key != Some(false)
+    }
+}
+
+impl fmt::Display for ReportVerification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "File SHA-256:    {}", self.file_sha256)?;
+        writeln!(
+            f,
+            "Payload SHA-256: {}",
+            self.embedded_payload_sha256.as_deref().unwrap_or("(not embedded)")
+        )?;
+        match &self.record {
+            Some(record) => {
+                writeln!(f, "Report:          {}", record.report_title)?;
+                writeln!(f, "Signed by:       {} at {}", record.signed_by, record.signed_at.to_rfc3339())?;
+                writeln!(f, "Key:             {} ({})", record.key_id, record.algorithm)?;
+                writeln!(f, "Signature:       {}", if self.signature_valid { "valid" } else { "INVALID" })?;
+                writeln!(f, "Payload match:   {}", if self.payload_matches { "yes" } else { "NO" })?;
+                if let Some(system) = self.signed_with_system_key {
+                    writeln!(f, "System key:      {}", if system { "yes" } else { "NO" })?;
+                }
+            }
+            None => writeln!(f, "Registry:        no signature record for this file (modified or unknown)")?,
+        }
+        write!(f, "Result:          {}", if self.is_valid() { "VERIFIED" } else { "VERIFICATION FAILED" })
+    }
+}
+
+/// Verify a report file against the signature registry.
+pub fn verify_report_file(path: &Path, db: &Database, keystore: Option<&Keystore>) -> Result<ReportVerification> {
+    let bytes = std::fs::read(path).map_err(|e| QmsError::FileSystem {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    })?;
+    let file_sha256 = keystore::sha256_hex(&bytes);
+    let embedded_payload_sha256 = extract_payload_checksum(&bytes);
+    let record = ReportSignatureRepo::new(db).find_by_file_hash(&file_sha256)?;
+
+    let (signature_valid, payload_matches, signed_with_system_key) = match &record {
+        Some(record) => {
+            let public_key = general_purpose::STANDARD.decode(&record.public_key).unwrap_or_default();
+            let signature = general_purpose::STANDARD.decode(&record.signature).unwrap_or_default();
+            let valid = keystore::verify_signature(
+                &public_key,
+                &signing_message(&file_sha256, &record.payload_sha256),
+                &signature,
+            );
+            let matches = embedded_payload_sha256.as_deref() == Some(record.payload_sha256.as_str());
+            let system = keystore.map(|k| k.public_key() == public_key.as_slice());
+            (valid, matches, system)
+        }
+        None => (false, false, None),
+    };
+
+    Ok(ReportVerification {
+        file_sha256,
+        embedded_payload_sha256,
+        record,
+        signature_valid,
+        payload_matches,
+        signed_with_system_key,
+    })
+}
+
+fn file_sha256(path: &Path) -> Result<String> {
+    let bytes = std::fs::read(path).map_err(|e| QmsError::FileSystem {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    })?;
+    Ok(keystore::sha256_hex(&bytes))
+}
+
+/// Find the footer checksum in the (uncompressed) PDF content stream.
+fn extract_payload_checksum(bytes: &[u8]) -> Option<String> {
+    let marker = PAYLOAD_MARKER.as_bytes();
+    let start = bytes.windows(marker.len()).position(|w| w == marker)? + marker.len();
+    let candidate = bytes.get(start..start + 64)?;
+    if candidate.iter().all(|b| b.is_ascii_hexdigit()) {
+        Some(String::from_utf8_lossy(candidate).to_string())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::report::{ReportBuilder, ReportSection};
+    use tempfile::tempdir;
+
+    fn setup() -> (tempfile::TempDir, Database, Keystore) {
+        let dir = tempdir().unwrap();
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let keystore = Keystore::open_or_create(&Keystore::keys_dir(dir.path())).unwrap();
+        (dir, db, keystore)
+    }
+
+    fn sample_report() -> Report {
+        ReportBuilder::new("Monthly Compliance Summary")
+            .with_section(ReportSection::new("Summary").paragraph("All CAPAs on schedule."))
+            .build()
+    }
+
+    #[test]
+    fn test_signed_report_verifies() {
+        let (dir, db, keystore) = setup();
+        let path = dir.path().join("summary.pdf");
+
+        let report = sample_report();
+        let signed = render_signed_report(&report, &path, &keystore, &db, "qa_manager").unwrap();
+        assert_eq!(signed.record.payload_sha256, report.payload_sha256());
+
+        let verification = verify_report_file(&path, &db, Some(&keystore)).unwrap();
+        assert_eq!(verification.embedded_payload_sha256.as_deref(), Some(signed.record.payload_sha256.as_str()));
+        assert!(verification.signature_valid);
+        assert!(verification.is_valid(), "{}", verification);
+    }
+
+    #[test]
+    fn test_tampered_report_fails_verification() {
+        let (dir, db, keystore) = setup();
+        let path = dir.path().join("summary.pdf");
+        render_signed_report(&sample_report(), &path, &keystore, &db, "qa_manager").unwrap();
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        let pos = bytes.windows(3).position(|w| w == b"All").unwrap();
+        bytes[pos] = b'N';
+        std::fs::write(&path, bytes).unwrap();
+
+        let verification = verify_report_file(&path, &db, Some(&keystore)).unwrap();
+        assert!(verification.record.is_none());
+        assert!(!verification.is_valid());
+        assert!(verification.to_string().contains("VERIFICATION FAILED"));
+    }
+
+    #[test]
+    fn test_forged_registry_signature_rejected() {
+        let (dir, db, keystore) = setup();
+        let path = dir.path().join("summary.pdf");
+        let signed = render_signed_report(&sample_report(), &path, &keystore, &db, "qa_manager").unwrap();
+
+        db.with_connection(|conn| {
+            conn.execute(
+                "UPDATE report_signatures SET payload_sha256 = ?1 WHERE id = ?2",
+                params!["0".repeat(64), signed.record.id.to_string()],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+
+        let verification = verify_report_file(&path, &db, Some(&keystore)).unwrap();
+        assert!(!verification.signature_valid);
+        assert!(!verification.is_valid());
+    }
+
+    #[test]
+    fn test_extract_payload_checksum() {
+        let hash = "a".repeat(64);
+        let content = format!("BT ({}{}) Tj ET", PAYLOAD_MARKER, hash);
+        assert_eq!(extract_payload_checksum(content.as_bytes()), Some(hash));
+        assert_eq!(extract_payload_checksum(b"no marker here"), None);
+    }
+}
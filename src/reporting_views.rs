@@ -0,0 +1,320 @@
+//! # Reporting Views - Materialized Dashboard Summaries
+//!
+//! `capa_summary`, `supplier_summary` and `training_summary` hold per-site
+//! aggregate counts so dashboards and metrics endpoints read a handful of
+//! rows instead of scanning the record tables on every request. Repositories
+//! mark a summary stale in the same transaction as the write that changes it
+//! ([`invalidate_with`]); readers rebuild a summary when it is stale or older
+//! than [`SUMMARY_MAX_AGE_MINUTES`], since overdue counts move with the clock.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::capa::CapaMetrics;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::supplier::SupplierMetrics;
+use crate::training::TrainingMetrics;
+
+/// Age after which a summary is rebuilt even without an invalidation.
+pub const SUMMARY_MAX_AGE_MINUTES: i64 = 15;
+
+/// A materialized summary table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Summary {
+    Capa,
+    Supplier,
+    Training,
+}
+
+impl Summary {
+    pub const ALL: [Summary; 3] = [Summary::Capa, Summary::Supplier, Summary::Training];
+
+    pub fn table(&self) -> &'static str {
+        match self {
+            Summary::Capa => "capa_summary",
+            Summary::Supplier => "supplier_summary",
+            Summary::Training => "training_summary",
+        }
+    }
+
+    /// Summary fed by a record table, if any.
+    pub fn for_source_table(table: &str) -> Option<Summary> {
+        match table {
+            "capa_records" => Some(Summary::Capa),
+            "suppliers" => Some(Summary::Supplier),
+            "training_records" => Some(Summary::Training),
+            _ => None,
+        }
+    }
+
+    /// Statement rebuilding the summary from live (non-deleted) records.
+    fn rebuild_sql(&self) -> &'static str {
+        match self {
+            Summary::Capa => {
+                "INSERT INTO capa_summary (site_id, status, priority, record_count, overdue_count)
+                 SELECT site_id, status, priority, COUNT(*),
+                        SUM(CASE WHEN due_date IS NOT NULL AND julianday(due_date) < julianday(?1)
+                                  AND status <> 'Closed' THEN 1 ELSE 0 END)
+                 FROM capa_records WHERE deleted_at IS NULL
+                 GROUP BY site_id, status, priority"
+            }
+            Summary::Supplier => {
+                "INSERT INTO supplier_summary (site_id, qualification_status, supplier_count)
+                 SELECT site_id, qualification_status, COUNT(*)
+                 FROM suppliers WHERE deleted_at IS NULL
+                 GROUP BY site_id, qualification_status"
+            }
+            Summary::Training => {
+                "INSERT INTO training_summary (site_id, status, record_count)
+                 SELECT site_id, status, COUNT(*)
+                 FROM training_records WHERE deleted_at IS NULL
+                 GROUP BY site_id, status"
+            }
+        }
+    }
+}
+
+/// Mark `summary` stale on `conn`, typically inside the writer's transaction.
+pub fn invalidate_with(conn: &Connection, summary: Summary) -> Result<()> {
+    conn.execute(
+        "UPDATE summary_refreshes SET stale = 1 WHERE summary = ?1",
+        params![summary.table()],
+    )?;
+    Ok(())
+}
+
+/// Reads (and lazily rebuilds) the summary tables.
+pub struct SummaryRepo<'a> {
+    db: &'a Database,
+}
+
+impl<'a> SummaryRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Mark `summary` stale.
+    pub fn invalidate(&self, summary: Summary) -> Result<()> {
+        self.db.with_connection(|conn| invalidate_with(conn, summary))
+    }
+
+    /// Rebuild `summary` from the record tables as of `now`.
+    pub fn refresh(&self, summary: Summary, now: DateTime<Utc>) -> Result<()> {
+        self.db.with_connection(|conn| {
+            let tx = conn.unchecked_transaction()?;
+            tx.execute(&format!("DELETE FROM {}", summary.table()), [])?;
+            if summary == Summary::Capa {
+                tx.execute(summary.rebuild_sql(), params![now.to_rfc3339()])?;
+            } else {
+                tx.execute(summary.rebuild_sql(), [])?;
+            }
+            tx.execute(
+                "UPDATE summary_refreshes SET stale = 0, refreshed_at = ?2 WHERE summary = ?1",
+                params![summary.table(), now.to_rfc3339()],
+            )?;
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    /// Whether `summary` was invalidated or is older than `max_age` at `now`.
+    pub fn needs_refresh(&self, summary: Summary, now: DateTime<Utc>, max_age: Duration) -> Result<bool> {
+        let state: Option<(bool, Option<String>)> = self.db.with_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT stale, refreshed_at FROM summary_refreshes WHERE summary = ?1",
+                    params![summary.table()],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?)
+        })?;
+        let (stale, refreshed_at) = state.ok_or_else(|| QmsError::NotFound {
+            resource: "summary".to_string(),
+            id: summary.table().to_string(),
+        })?;
+        let refreshed_at = refreshed_at
+            .map(|value| crate::display_time::parse_stored_utc(&value))
+            .transpose()?;
+        Ok(stale || refreshed_at.is_none_or(|at| now - at > max_age))
+    }
+
+    /// Rebuild every summary that needs it; returns the ones rebuilt.
+    pub fn refresh_stale(&self, now: DateTime<Utc>, max_age: Duration) -> Result<Vec<Summary>> {
+        let mut refreshed = Vec::new();
+        for summary in Summary::ALL {
+            if self.needs_refresh(summary, now, max_age)? {
+                self.refresh(summary, now)?;
+                refreshed.push(summary);
+            }
+        }
+        Ok(refreshed)
+    }
+
+    fn ensure_fresh(&self, summary: Summary, now: DateTime<Utc>) -> Result<()> {
+        if self.needs_refresh(summary, now, Duration::minutes(SUMMARY_MAX_AGE_MINUTES))? {
+            self.refresh(summary, now)?;
+        }
+        Ok(())
+    }
+
+    /// CAPA counts for `site_id` as of `now`.
+    pub fn capa_metrics(&self, site_id: &str, now: DateTime<Utc>) -> Result<CapaMetrics> {
+        self.ensure_fresh(Summary::Capa, now)?;
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT status, priority, record_count, overdue_count FROM capa_summary WHERE site_id = ?1",
+            )?;
+            let rows = stmt.query_map(params![site_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)?, row.get::<_, i64>(3)?))
+            })?;
+            let mut metrics = CapaMetrics {
+                total_count: 0,
+                status_counts: HashMap::new(),
+                priority_counts: HashMap::new(),
+                overdue_count: 0,
+                closed_count: 0,
+            };
+            for row in rows {
+                let (status, priority, count, overdue) = row?;
+                let count = count as usize;
+                metrics.total_count += count;
+                metrics.overdue_count += overdue as usize;
+                if status == "Closed" {
+                    metrics.closed_count += count;
+                }
+                *metrics.status_counts.entry(status).or_insert(0) += count;
+                *metrics.priority_counts.entry(priority).or_insert(0) += count;
+            }
+            Ok(metrics)
+        })
+    }
+
+    /// Supplier qualification mix for `site_id`.
+    pub fn supplier_metrics(&self, site_id: &str, now: DateTime<Utc>) -> Result<SupplierMetrics> {
+        self.ensure_fresh(Summary::Supplier, now)?;
+        let counts = self.status_counts(
+            "SELECT qualification_status, supplier_count FROM supplier_summary WHERE site_id = ?1",
+            site_id,
+        )?;
+        let count = |status: &str| counts.get(status).copied().unwrap_or(0);
+        Ok(SupplierMetrics::from_counts(count("Qualified"), count("Pending"), count("Disqualified")))
+    }
+
+    /// Training completion mix for `site_id` (same buckets as
+    /// [`TrainingService::calculate_metrics`](crate::training::TrainingService::calculate_metrics)).
+    pub fn training_metrics(&self, site_id: &str, now: DateTime<Utc>) -> Result<TrainingMetrics> {
+        self.ensure_fresh(Summary::Training, now)?;
+        let counts = self.status_counts(
+            "SELECT status, record_count FROM training_summary WHERE site_id = ?1",
+            site_id,
+        )?;
+        let mut metrics = TrainingMetrics::default();
+        for (status, count) in counts {
+            metrics.total_count += count;
+            match status.as_str() {
+                "Completed" => metrics.completed += count,
+                "Overdue" => metrics.overdue += count,
+                _ => metrics.pending += count,
+            }
+        }
+        Ok(metrics)
+    }
+
+    fn status_counts(&self, sql: &str, site_id: &str) -> Result<HashMap<String, usize>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(sql)?;
+            let rows = stmt.query_map(params![site_id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+            })?;
+            let mut counts = HashMap::new();
+            for row in rows {
+                let (status, count) = row?;
+                counts.insert(status, count);
+            }
+            Ok(counts)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditLogger;
+    use crate::change_history::ChangeReason;
+    use crate::config::DatabaseConfig;
+    use crate::site::DEFAULT_SITE_ID;
+    use crate::supplier::SupplierService;
+    use crate::supplier_repo::SupplierRepository;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_service_writes_invalidate_supplier_summary() {
+        let db = test_db();
+        db.seed_test_users(&["qa"]);
+        let repo = SummaryRepo::new(&db);
+        let now = Utc::now();
+        assert_eq!(repo.supplier_metrics(DEFAULT_SITE_ID, now).unwrap().total_count, 0);
+        assert!(!repo.needs_refresh(Summary::Supplier, now, Duration::minutes(5)).unwrap());
+
+        let service = SupplierService::new(AuditLogger::new_test(), SupplierRepository::new(db.clone()));
+        let mut supplier = service.register_supplier("Acme".into(), None).unwrap();
+        service.register_supplier("Globex".into(), None).unwrap();
+        assert!(repo.needs_refresh(Summary::Supplier, now, Duration::minutes(5)).unwrap());
+
+        let reason = ChangeReason::new("audit passed").unwrap();
+        service.qualify_supplier(&mut supplier, "qa".into(), None, &reason).unwrap();
+        let metrics = repo.supplier_metrics(DEFAULT_SITE_ID, now).unwrap();
+        assert_eq!((metrics.total_count, metrics.qualified_count, metrics.pending_count), (2, 1, 1));
+        assert_eq!(metrics.qualified_percentage, 50.0);
+        assert!(repo.supplier_metrics("other-site", now).unwrap().total_count == 0);
+    }
+
+    #[test]
+    fn test_capa_summary_counts_overdue_and_ages_out() {
+        let db = test_db();
+        db.seed_test_users(&["qa"]);
+        db.with_connection(|conn| {
+            for (id, status, due) in [
+                ("c1", "Identified", Some("2025-01-01T00:00:00+00:00")),
+                ("c2", "Closed", Some("2025-01-01T00:00:00+00:00")),
+                ("c3", "Identified", None),
+            ] {
+                conn.execute(
+                    "INSERT INTO capa_records (id, title, description, capa_type, priority, status,
+                        initiator_id, assigned_to, created_at, updated_at, due_date)
+                     VALUES (?1, 't', 'd', 'Corrective', 'High', ?2, 'qa', 'qa', ?3, ?3, ?4)",
+                    params![id, status, "2024-12-01T00:00:00+00:00", due],
+                )?;
+            }
+            Ok(())
+        })
+        .unwrap();
+
+        let repo = SummaryRepo::new(&db);
+        let now = "2025-02-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let metrics = repo.capa_metrics(DEFAULT_SITE_ID, now).unwrap();
+        assert_eq!(metrics.total_count, 3);
+        assert_eq!(metrics.overdue_count, 1);
+        assert_eq!(metrics.closed_count, 1);
+        assert_eq!(metrics.priority_counts.get("High"), Some(&3));
+
+        assert!(repo.refresh_stale(now, Duration::minutes(5)).unwrap().contains(&Summary::Supplier));
+        assert!(repo.refresh_stale(now, Duration::minutes(5)).unwrap().is_empty());
+        let later = now + Duration::minutes(SUMMARY_MAX_AGE_MINUTES + 1);
+        assert_eq!(repo.refresh_stale(later, Duration::minutes(SUMMARY_MAX_AGE_MINUTES)).unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,137 @@
+//! # Request Audit - API Mutation Logging
+//!
+//! Every state-changing API request (POST, PUT, PATCH, DELETE) is written
+//! to the audit trail with its method, route, actor, outcome, latency and a
+//! summary of the payload. Secrets such as passwords and tokens are
+//! redacted before the summary is stored, and long payloads are truncated.
+//! Logging can be switched per [`RouteClass`] in `[api.request_audit]`.
+
+use axum::http::Method;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Marker stored instead of a redacted value.
+pub const REDACTED: &str = "[redacted]";
+
+/// Largest request body captured for the payload summary; bigger or
+/// unsized bodies are passed through and noted as not captured.
+pub const MAX_CAPTURED_BODY_BYTES: u64 = 64 * 1024;
+
+/// Group of API routes that share an audit setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteClass {
+    /// Login, logout and single sign-on
+    Authentication,
+    /// Quality records and everything else
+    Records,
+}
+
+impl RouteClass {
+    pub const ALL: [RouteClass; 2] = [RouteClass::Authentication, RouteClass::Records];
+
+    pub fn all() -> Vec<RouteClass> {
+        Self::ALL.to_vec()
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RouteClass::Authentication => "authentication",
+            RouteClass::Records => "records",
+        }
+    }
+
+    /// Class of a request path.
+    pub fn of(path: &str) -> Self {
+        if path == "/login" || path == "/logout" || path.starts_with("/auth/") {
+            RouteClass::Authentication
+        } else {
+            RouteClass::Records
+        }
+    }
+}
+
+/// Field names redacted when no list is configured.
+pub fn default_redacted_fields() -> Vec<String> {
+    ["password", "secret", "client_secret", "token", "access_token", "id_token", "refresh_token", "csrf_token"]
+        .iter()
+        .map(|f| f.to_string())
+        .collect()
+}
+
+/// Routes that take POST but only read; GraphQL queries are not mutations.
+pub const READ_ONLY_ROUTES: &[&str] = &["/graphql"];
+
+/// Whether `method` changes state and is therefore audited.
+pub fn is_mutation(method: &Method) -> bool {
+    matches!(*method, Method::POST | Method::PUT | Method::PATCH | Method::DELETE)
+}
+
+/// Replace the values of `fields` (matched case-insensitively, at any depth).
+pub fn redact(value: &mut Value, fields: &[String]) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                if fields.iter().any(|f| f.eq_ignore_ascii_case(key)) {
+                    *field = Value::String(REDACTED.to_string());
+                } else {
+                    redact(field, fields);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| redact(item, fields)),
+        _ => {}
+    }
+}
+
+/// Redacted, length-limited summary of a request body. JSON bodies are
+/// redacted field by field; other bodies are only described, as they cannot
+/// be redacted reliably.
+pub fn summarize_payload(body: &[u8], content_type: Option<&str>, fields: &[String], max_chars: usize) -> String {
+    if body.is_empty() {
+        return String::new();
+    }
+    let summary = match serde_json::from_slice::<Value>(body) {
+        Ok(mut json) => {
+            redact(&mut json, fields);
+            json.to_string()
+        }
+        Err(_) => format!("<{} bytes {}>", body.len(), content_type.unwrap_or("application/octet-stream")),
+    };
+    truncate(summary, max_chars)
+}
+
+fn truncate(text: String, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload_summary_redacts_and_truncates() {
+        let fields = default_redacted_fields();
+        let body = br#"{"username":"qa","Password":"hunter2","nested":[{"refresh_token":"r1","note":"ok"}]}"#;
+        let summary = summarize_payload(body, Some("application/json"), &fields, 500);
+        assert!(!summary.contains("hunter2") && !summary.contains("r1"));
+        assert!(summary.contains(r#""Password":"[redacted]""#));
+        assert!(summary.contains(r#""note":"ok""#));
+
+        assert_eq!(summarize_payload(b"a=1&b=2", Some("text/plain"), &fields, 500), "<7 bytes text/plain>");
+        assert_eq!(summarize_payload(br#"{"title":"abcdef"}"#, None, &fields, 5), "{\"tit…");
+        assert_eq!(summarize_payload(b"", None, &fields, 5), "");
+    }
+
+    #[test]
+    fn test_route_classes_and_methods() {
+        assert_eq!(RouteClass::of("/login"), RouteClass::Authentication);
+        assert_eq!(RouteClass::of("/auth/oidc/callback"), RouteClass::Authentication);
+        assert_eq!(RouteClass::of("/documents/d1/approve"), RouteClass::Records);
+        assert!(is_mutation(&Method::DELETE));
+        assert!(!is_mutation(&Method::GET));
+    }
+}
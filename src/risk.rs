@@ -13,6 +13,8 @@
 
 use crate::error::{QmsError, Result};
 use crate::audit::AuditLogger;
+use crate::database::Database;
+use crate::record_history::RecordHistoryRepo;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -120,14 +122,29 @@ pub enum VerificationStatus {
 }
 
 /// Risk Management Service implementing ISO 14971
+#[derive(Clone)]
 pub struct RiskManagementService {
     audit_logger: AuditLogger,
+    record_history: Option<Database>,
 }
 
 impl RiskManagementService {
     /// Create new Risk Management Service
     pub fn new(audit_logger: AuditLogger) -> Self {
-        Self { audit_logger }
+        Self { audit_logger, record_history: None }
+    }
+
+    /// Store a version snapshot of every risk assessment change in `database`.
+    pub fn with_record_history(mut self, database: Database) -> Self {
+        self.record_history = Some(database);
+        self
+    }
+
+    fn snapshot(&self, assessment: &RiskAssessment, user: &str) -> Result<()> {
+        if let Some(db) = &self.record_history {
+            RecordHistoryRepo::new(db).snapshot(assessment, user)?;
+        }
+        Ok(())
     }
 
     /// Create new risk assessment (ISO 14971 compliant)
@@ -179,6 +196,7 @@ impl RiskManagementService {
             "SUCCESS",
             Some(format!("Created risk assessment for device: {}", device_name)),
         ).await?;
+        self.snapshot(&assessment, &created_by)?;
 
         Ok(assessment)
     }
@@ -247,6 +265,7 @@ impl RiskManagementService {
             "SUCCESS",
             Some(format!("Calculated residual risk level: {}", residual_risk_level)),
         ).await?;
+        self.snapshot(risk_assessment, &calculated_by)?;
 
         Ok(())
     }
@@ -287,6 +306,7 @@ impl RiskManagementService {
             "SUCCESS",
             Some("Risk assessment approved".to_string()),
         ).await?;
+        self.snapshot(risk_assessment, &reviewed_by)?;
 
         Ok(())
     }
@@ -327,12 +347,7 @@ impl RiskManagementService {
 
     /// Determine risk acceptability based on risk level
     fn determine_acceptability(&self, risk_level: u8) -> RiskAcceptability {
-        match risk_level {
-            1..=5 => RiskAcceptability::Acceptable,
-            6..=15 => RiskAcceptability::Tolerable,
-            16..=25 => RiskAcceptability::Unacceptable,
-            _ => RiskAcceptability::Unacceptable,
-        }
+        RiskAcceptability::from_risk_level(risk_level)
     }
 
     /// Generate risk management report
@@ -421,7 +436,28 @@ pub enum ComplianceStatus {
     NonCompliant,
 }
 
+impl RiskAcceptability {
+    /// Acceptability region of a risk level on the 5×5 matrix
+    pub fn from_risk_level(risk_level: u8) -> Self {
+        match risk_level {
+            1..=5 => RiskAcceptability::Acceptable,
+            6..=15 => RiskAcceptability::Tolerable,
+            16..=25 => RiskAcceptability::Unacceptable,
+            _ => RiskAcceptability::Unacceptable,
+        }
+    }
+}
+
 impl RiskSeverity {
+    /// All severities, least severe first
+    pub const ALL: [RiskSeverity; 5] = [
+        RiskSeverity::Negligible,
+        RiskSeverity::Minor,
+        RiskSeverity::Serious,
+        RiskSeverity::Critical,
+        RiskSeverity::Catastrophic,
+    ];
+
     pub fn from_u8(value: u8) -> Result<Self> {
         match value {
             1 => Ok(RiskSeverity::Negligible),
@@ -448,6 +484,15 @@ impl RiskSeverity {
 }
 
 impl RiskProbability {
+    /// All probabilities, least likely first
+    pub const ALL: [RiskProbability; 5] = [
+        RiskProbability::Remote,
+        RiskProbability::Unlikely,
+        RiskProbability::Possible,
+        RiskProbability::Probable,
+        RiskProbability::Frequent,
+    ];
+
     pub fn from_u8(value: u8) -> Result<Self> {
         match value {
             1 => Ok(RiskProbability::Remote),
@@ -473,6 +518,91 @@ impl RiskProbability {
     }
 }
 
+/// Assessment placed on a cell of a [`RiskHeatMap`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RiskHeatMapEntry {
+    pub id: Uuid,
+    pub device_name: String,
+    pub hazard_description: String,
+    pub status: RiskAssessmentStatus,
+}
+
+/// One severity × probability cell of a [`RiskHeatMap`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RiskHeatMapCell {
+    pub severity: RiskSeverity,
+    pub probability: RiskProbability,
+    pub risk_level: u8,
+    pub acceptability: RiskAcceptability,
+    pub assessments: Vec<RiskHeatMapEntry>,
+}
+
+/// Occupancy of the 5×5 severity × probability matrix by the current
+/// (not archived) assessments, using their initial or residual estimate.
+/// Rows run from Catastrophic down to Negligible and columns from Remote to
+/// Frequent, the way the matrix is drawn.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RiskHeatMap {
+    pub residual: bool,
+    pub rows: Vec<Vec<RiskHeatMapCell>>,
+}
+
+impl RiskHeatMap {
+    /// Place `assessments` on the matrix. For the residual matrix those
+    /// without a residual estimate yet are left out.
+    pub fn from_assessments(assessments: &[RiskAssessment], residual: bool) -> Self {
+        let rows = RiskSeverity::ALL
+            .iter()
+            .rev()
+            .map(|&severity| {
+                RiskProbability::ALL
+                    .iter()
+                    .map(|&probability| {
+                        let risk_level = severity as u8 * probability as u8;
+                        RiskHeatMapCell {
+                            severity,
+                            probability,
+                            risk_level,
+                            acceptability: RiskAcceptability::from_risk_level(risk_level),
+                            assessments: Vec::new(),
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let mut map = Self { residual, rows };
+        for assessment in assessments.iter().filter(|a| a.status != RiskAssessmentStatus::Archived) {
+            let estimate = if residual {
+                assessment.residual_severity.zip(assessment.residual_probability)
+            } else {
+                Some((assessment.initial_severity, assessment.initial_probability))
+            };
+            let Some((severity, probability)) = estimate else {
+                continue;
+            };
+            let row = RiskSeverity::ALL.len() - severity as usize;
+            let column = probability as usize - 1;
+            map.rows[row][column].assessments.push(RiskHeatMapEntry {
+                id: assessment.id,
+                device_name: assessment.device_name.clone(),
+                hazard_description: assessment.hazard_description.clone(),
+                status: assessment.status.clone(),
+            });
+        }
+        map
+    }
+
+    /// Cell at `row` (0 = Catastrophic) and `column` (0 = Remote)
+    pub fn cell(&self, row: usize, column: usize) -> Option<&RiskHeatMapCell> {
+        self.rows.get(row).and_then(|cells| cells.get(column))
+    }
+
+    /// Number of assessments on the matrix
+    pub fn total(&self) -> usize {
+        self.rows.iter().flatten().map(|cell| cell.assessments.len()).sum()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -588,4 +718,92 @@ mod tests {
         let non_compliant_assessments = vec![non_compliant_assessment];
         assert_eq!(service.assess_compliance_status(&non_compliant_assessments), ComplianceStatus::NonCompliant);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_record_history_as_of() {
+        let database = Database::new(crate::config::DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let service = RiskManagementService::new(AuditLogger::new_test()).with_record_history(database.clone());
+
+        let mut assessment = service.create_risk_assessment(
+            "Infusion Pump".to_string(),
+            "Over-infusion".to_string(),
+            "Free flow during setup".to_string(),
+            "Clamp open → free flow → overdose".to_string(),
+            "Drug overdose".to_string(),
+            RiskSeverity::Critical,
+            RiskProbability::Possible,
+            "risk_engineer".to_string(),
+        ).await.unwrap();
+        let before_residual = Utc::now();
+        service
+            .calculate_residual_risk(&mut assessment, RiskSeverity::Critical, RiskProbability::Remote, "risk_engineer".to_string())
+            .await
+            .unwrap();
+
+        let history = RecordHistoryRepo::new(&database);
+        let id = assessment.id.to_string();
+        assert_eq!(history.versions("risk_assessment", &id).unwrap().len(), 2);
+        let earlier: RiskAssessment = history.record_as_of(&id, before_residual).unwrap().unwrap();
+        assert!(earlier.residual_risk_level.is_none());
+        let current: RiskAssessment = history.record_as_of(&id, Utc::now()).unwrap().unwrap();
+        assert_eq!(current.residual_risk_level, Some(4));
+    }
+
+    #[tokio::test]
+    async fn test_heat_map_places_current_assessments() {
+        let service = RiskManagementService::new(AuditLogger::new_test());
+        let mut assessments = Vec::new();
+        for (hazard, severity, probability) in [
+            ("Over-infusion", RiskSeverity::Critical, RiskProbability::Possible),
+            ("Air embolism", RiskSeverity::Critical, RiskProbability::Possible),
+            ("Alarm fatigue", RiskSeverity::Minor, RiskProbability::Frequent),
+        ] {
+            let assessment = service
+                .create_risk_assessment(
+                    "Infusion Pump".to_string(),
+                    hazard.to_string(),
+                    "Situation".to_string(),
+                    "Sequence".to_string(),
+                    "Harm".to_string(),
+                    severity,
+                    probability,
+                    "risk_engineer".to_string(),
+                )
+                .await
+                .unwrap();
+            assessments.push(assessment);
+        }
+        assessments[2].status = RiskAssessmentStatus::Archived;
+        service
+            .calculate_residual_risk(
+                &mut assessments[0],
+                RiskSeverity::Critical,
+                RiskProbability::Remote,
+                "risk_engineer".to_string(),
+            )
+            .await
+            .unwrap();
+
+        let initial = RiskHeatMap::from_assessments(&assessments, false);
+        assert_eq!(initial.total(), 2);
+        let cell = initial.cell(1, 2).unwrap();
+        assert_eq!((cell.severity, cell.probability), (RiskSeverity::Critical, RiskProbability::Possible));
+        assert_eq!(cell.risk_level, 12);
+        assert_eq!(cell.acceptability, RiskAcceptability::Tolerable);
+        let hazards: Vec<&str> = cell.assessments.iter().map(|a| a.hazard_description.as_str()).collect();
+        assert_eq!(hazards, ["Over-infusion", "Air embolism"]);
+        assert_eq!(initial.cell(0, 4).unwrap().acceptability, RiskAcceptability::Unacceptable);
+        assert!(initial.cell(5, 0).is_none());
+
+        let residual = RiskHeatMap::from_assessments(&assessments, true);
+        assert_eq!(residual.total(), 1);
+        assert_eq!(residual.cell(1, 0).unwrap().assessments[0].id, assessments[0].id);
+    }
+}
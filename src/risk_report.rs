@@ -0,0 +1,371 @@
+//! # Risk Report - Per-Device Risk Management PDF
+//!
+//! Renders a [`RiskManagementReport`] together with the underlying
+//! [`RiskAssessment`]s of one device: summary figures, initial and residual
+//! severity × probability heat maps, the per-hazard analysis table and the
+//! verification status of every control measure. Forms the risk management
+//! report required by ISO 14971 §8 for the risk management file.
+
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use crate::pdf_report::{render_report, RenderedReport};
+use crate::report::{
+    Chart, ChartKind, HeatCell, HeatLevel, HeatMap, Report, ReportBuilder, ReportSection, ReportTable,
+};
+use crate::risk::{
+    ControlMeasureType, RiskAcceptability, RiskAssessment, RiskManagementReport, RiskProbability,
+    RiskSeverity, VerificationStatus,
+};
+use crate::Result;
+
+/// Configuration for a per-device risk management report.
+#[derive(Debug, Clone)]
+pub struct RiskReportConfig<'a> {
+    /// Destination path for the generated PDF file.
+    pub output_path: &'a Path,
+    /// Device whose risk management file is reported.
+    pub device_name: &'a str,
+    /// Aggregate report from `RiskManagementService::generate_risk_report`.
+    pub report: &'a RiskManagementReport,
+    /// Assessments backing the report; entries for other devices are ignored.
+    pub assessments: &'a [RiskAssessment],
+    /// Optional organisation name for the cover page.
+    pub organization: Option<&'a str>,
+    /// UTC timestamp of report generation.
+    pub generated_on: DateTime<Utc>,
+}
+
+/// Distinct device names in `assessments`, sorted.
+pub fn devices(assessments: &[RiskAssessment]) -> Vec<String> {
+    assessments
+        .iter()
+        .map(|a| a.device_name.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Severity × probability matrix of initial (or residual) risk estimates.
+///
+/// Rows run from Catastrophic (top) to Negligible; assessments without a
+/// residual estimate are left out of the residual matrix.
+pub fn risk_matrix(assessments: &[RiskAssessment], residual: bool) -> HeatMap {
+    let mut counts: HashMap<(u8, u8), usize> = HashMap::new();
+    for assessment in assessments {
+        let estimate = if residual {
+            assessment.residual_severity.zip(assessment.residual_probability)
+        } else {
+            Some((assessment.initial_severity, assessment.initial_probability))
+        };
+        if let Some((severity, probability)) = estimate {
+            *counts.entry((severity as u8, probability as u8)).or_insert(0) += 1;
+        }
+    }
+
+    let cells = RiskSeverity::ALL
+        .iter()
+        .rev()
+        .map(|severity| {
+            RiskProbability::ALL
+                .iter()
+                .map(|probability| {
+                    let level = *severity as u8 * *probability as u8;
+                    HeatCell {
+                        value: counts.get(&(*severity as u8, *probability as u8)).copied().unwrap_or(0),
+                        level: heat_level(RiskAcceptability::from_risk_level(level)),
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    HeatMap {
+        title: if residual { "Residual Risk Matrix" } else { "Initial Risk Matrix" }.to_string(),
+        row_axis: "Severity".to_string(),
+        column_axis: "Probability".to_string(),
+        row_labels: RiskSeverity::ALL.iter().rev().map(|s| format!("{:?} ({})", s, *s as u8)).collect(),
+        column_labels: RiskProbability::ALL.iter().map(|p| format!("{:?} ({})", p, *p as u8)).collect(),
+        cells,
+    }
+}
+
+/// Build the per-device risk report model.
+pub fn build_risk_report(cfg: &RiskReportConfig) -> Report {
+    let device: Vec<RiskAssessment> =
+        cfg.assessments.iter().filter(|a| a.device_name == cfg.device_name).cloned().collect();
+
+    let mut matrix = ReportSection::new("Risk Matrix").heat_map(risk_matrix(&device, false));
+    if device.iter().any(|a| a.residual_risk_level.is_some()) {
+        matrix = matrix.heat_map(risk_matrix(&device, true));
+    }
+    matrix = matrix.paragraph(
+        "Green: broadly acceptable (level 1-5). Amber: tolerable, reduce as far as possible (6-15). \
+         Red: unacceptable (16-25).",
+    );
+
+    let mut builder = ReportBuilder::new(format!("Risk Management Report: {}", cfg.device_name))
+        .with_subtitle("ISO 14971 Risk Management File Summary")
+        .with_prepared_by(cfg.report.generated_by.clone())
+        .with_generated_on(cfg.generated_on)
+        .with_cover_page()
+        .with_table_of_contents()
+        .with_section(summary_section(cfg, &device))
+        .with_section(matrix)
+        .with_section(hazard_section(&device))
+        .with_section(control_measure_section(&device));
+    if let Some(org) = cfg.organization {
+        builder = builder.with_organization(org);
+    }
+    builder.build()
+}
+
+/// Render the per-device risk report to `cfg.output_path`.
+pub fn generate_risk_report(cfg: &RiskReportConfig) -> Result<RenderedReport> {
+    render_report(&build_risk_report(cfg), cfg.output_path)
+}
+
+fn summary_section(cfg: &RiskReportConfig, assessments: &[RiskAssessment]) -> ReportSection {
+    let measures: Vec<_> = assessments.iter().flat_map(|a| &a.control_measures).collect();
+    let verified = measures
+        .iter()
+        .filter(|m| m.verification_status == VerificationStatus::Verified)
+        .count();
+    let unacceptable_residual = assessments
+        .iter()
+        .filter(|a| a.residual_acceptability == Some(RiskAcceptability::Unacceptable))
+        .count();
+
+    let mut acceptability: HashMap<String, usize> = HashMap::new();
+    for assessment in assessments {
+        *acceptability.entry(format!("{:?}", assessment.acceptability)).or_insert(0) += 1;
+    }
+
+    ReportSection::new("Summary")
+        .key_values(vec![
+            ("Device", cfg.device_name.to_string()),
+            ("Report ID", cfg.report.id.to_string()),
+            ("Risk Data As Of", cfg.report.generated_at.format("%Y-%m-%d %H:%M UTC").to_string()),
+            ("Compliance Status", format!("{:?}", cfg.report.compliance_status)),
+            ("Hazards Assessed", assessments.len().to_string()),
+            ("Control Measures", measures.len().to_string()),
+            ("Verified Control Measures", format!("{} of {}", verified, measures.len())),
+            ("Pending Control Measures", cfg.report.pending_control_measures.to_string()),
+            ("Unacceptable Residual Risks", unacceptable_residual.to_string()),
+        ])
+        .chart(Chart::from_counts("Initial Risk Acceptability", ChartKind::Pie, &acceptability))
+}
+
+fn hazard_section(assessments: &[RiskAssessment]) -> ReportSection {
+    let section = ReportSection::new("Hazard Analysis").with_page_break();
+    if assessments.is_empty() {
+        return section.paragraph("No risk assessments recorded for this device.");
+    }
+
+    let table = assessments.iter().enumerate().fold(
+        ReportTable::new(vec!["#", "Hazard", "Hazardous Situation", "Harm", "Initial", "Residual", "Status"])
+            .with_column_weights(vec![0.4, 1.8, 1.8, 1.5, 1.2, 1.2, 1.0]),
+        |table, (idx, a)| {
+            let residual = match (a.residual_severity, a.residual_probability, a.residual_risk_level) {
+                (Some(s), Some(p), Some(level)) => estimate_label(s, p, level, a.residual_acceptability),
+                _ => "Not evaluated".to_string(),
+            };
+            table.with_row(vec![
+                (idx + 1).to_string(),
+                a.hazard_description.clone(),
+                a.hazardous_situation.clone(),
+                a.harm_description.clone(),
+                estimate_label(a.initial_severity, a.initial_probability, a.initial_risk_level, Some(a.acceptability)),
+                residual,
+                format!("{:?}", a.status),
+            ])
+        },
+    );
+    section.table(table)
+}
+
+fn control_measure_section(assessments: &[RiskAssessment]) -> ReportSection {
+    let section = ReportSection::new("Control Measure Verification");
+    let rows: Vec<Vec<String>> = assessments
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, a)| {
+            a.control_measures.iter().map(move |m| {
+                let verified = match (&m.verified_by, m.verified_at) {
+                    (Some(by), Some(at)) => format!("{} ({})", by, at.format("%Y-%m-%d")),
+                    (Some(by), None) => by.clone(),
+                    _ => "-".to_string(),
+                };
+                vec![
+                    (idx + 1).to_string(),
+                    measure_type_label(&m.measure_type).to_string(),
+                    m.description.clone(),
+                    m.effectiveness_verification.clone(),
+                    format!("{:?}", m.verification_status),
+                    verified,
+                ]
+            })
+        })
+        .collect();
+    if rows.is_empty() {
+        return section.paragraph("No control measures recorded for this device.");
+    }
+
+    let table = rows.into_iter().fold(
+        ReportTable::new(vec!["Hazard #", "Type", "Measure", "Verification Method", "Status", "Verified By"])
+            .with_column_weights(vec![0.7, 1.2, 2.2, 1.8, 1.0, 1.5]),
+        ReportTable::with_row,
+    );
+    section
+        .paragraph("Hazard numbers refer to the Hazard Analysis table. Control options follow the ISO 14971 §7.1 priority order.")
+        .table(table)
+}
+
+fn heat_level(acceptability: RiskAcceptability) -> HeatLevel {
+    match acceptability {
+        RiskAcceptability::Acceptable => HeatLevel::Low,
+        RiskAcceptability::Tolerable => HeatLevel::Medium,
+        RiskAcceptability::Unacceptable => HeatLevel::High,
+    }
+}
+
+fn estimate_label(
+    severity: RiskSeverity,
+    probability: RiskProbability,
+    level: u8,
+    acceptability: Option<RiskAcceptability>,
+) -> String {
+    let estimate = format!("S{} × P{} = {}", severity as u8, probability as u8, level);
+    match acceptability {
+        Some(a) => format!("{} ({:?})", estimate, a),
+        None => estimate,
+    }
+}
+
+fn measure_type_label(measure_type: &ControlMeasureType) -> &'static str {
+    match measure_type {
+        ControlMeasureType::InherentSafety => "Inherent safety by design",
+        ControlMeasureType::ProtectiveMeasures => "Protective measure",
+        ControlMeasureType::Information => "Information for safety",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditLogger;
+    use crate::risk::RiskManagementService;
+    use tempfile::tempdir;
+
+    async fn sample_assessments(service: &RiskManagementService) -> Vec<RiskAssessment> {
+        let mut shock = service
+            .create_risk_assessment(
+                "Infusion Pump".to_string(),
+                "Electrical shock".to_string(),
+                "User contact with live parts".to_string(),
+                "Enclosure crack exposes mains wiring".to_string(),
+                "Electric shock injury".to_string(),
+                RiskSeverity::Catastrophic,
+                RiskProbability::Probable,
+                "risk_eng".to_string(),
+            )
+            .await
+            .unwrap();
+        let mut measure = service
+            .add_control_measure(
+                shock.id,
+                ControlMeasureType::InherentSafety,
+                "Double insulation".to_string(),
+                "IEC 60601-1 class II design".to_string(),
+                "Dielectric strength test".to_string(),
+                "eng1".to_string(),
+            )
+            .await
+            .unwrap();
+        service.verify_control_measure(&mut measure, "qa1".to_string(), true).await.unwrap();
+        shock.control_measures.push(measure);
+        service
+            .calculate_residual_risk(&mut shock, RiskSeverity::Catastrophic, RiskProbability::Remote, "risk_eng".to_string())
+            .await
+            .unwrap();
+
+        let occlusion = service
+            .create_risk_assessment(
+                "Infusion Pump".to_string(),
+                "Occlusion".to_string(),
+                "Line blocked during infusion".to_string(),
+                "Kinked tubing".to_string(),
+                "Delayed therapy".to_string(),
+                RiskSeverity::Serious,
+                RiskProbability::Possible,
+                "risk_eng".to_string(),
+            )
+            .await
+            .unwrap();
+        let other_device = service
+            .create_risk_assessment(
+                "Glucose Meter".to_string(),
+                "Wrong reading".to_string(),
+                "Miscalibrated strip".to_string(),
+                "Expired strip lot".to_string(),
+                "Incorrect dosing".to_string(),
+                RiskSeverity::Critical,
+                RiskProbability::Unlikely,
+                "risk_eng".to_string(),
+            )
+            .await
+            .unwrap();
+        vec![shock, occlusion, other_device]
+    }
+
+    #[tokio::test]
+    async fn test_risk_matrix_counts_and_bands() {
+        let service = RiskManagementService::new(AuditLogger::new_test());
+        let assessments = sample_assessments(&service).await;
+
+        let initial = risk_matrix(&assessments, false);
+        assert_eq!(initial.total(), 3);
+        // Row 0 is Catastrophic, column 3 is Probable.
+        assert_eq!(initial.cells[0][3].value, 1);
+        assert_eq!(initial.cells[0][3].level, HeatLevel::High);
+        assert_eq!(initial.cells[4][0].level, HeatLevel::Low);
+        assert_eq!(initial.cells[2][2], HeatCell { value: 1, level: HeatLevel::Medium });
+
+        let residual = risk_matrix(&assessments, true);
+        assert_eq!(residual.total(), 1);
+        assert_eq!(residual.cells[0][0], HeatCell { value: 1, level: HeatLevel::Low });
+    }
+
+    #[tokio::test]
+    async fn test_build_risk_report_filters_device() {
+        let service = RiskManagementService::new(AuditLogger::new_test());
+        let assessments = sample_assessments(&service).await;
+        let summary = service.generate_risk_report(&assessments[..2], "qa1".to_string()).await.unwrap();
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("risk.pdf");
+
+        assert_eq!(devices(&assessments), vec!["Glucose Meter".to_string(), "Infusion Pump".to_string()]);
+
+        let cfg = RiskReportConfig {
+            output_path: &path,
+            device_name: "Infusion Pump",
+            report: &summary,
+            assessments: &assessments,
+            organization: Some("Acme Medical"),
+            generated_on: Utc::now(),
+        };
+        let report = build_risk_report(&cfg);
+        let titles: Vec<&str> = report.sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["Summary", "Risk Matrix", "Hazard Analysis", "Control Measure Verification"]);
+
+        let payload = String::from_utf8(report.canonical_payload()).unwrap();
+        assert!(payload.contains("Occlusion"));
+        assert!(!payload.contains("Wrong reading"), "other devices must be excluded");
+        assert!(payload.contains("Residual Risk Matrix"));
+
+        let rendered = generate_risk_report(&cfg).unwrap();
+        assert!(std::fs::read(&path).unwrap().starts_with(b"%PDF-"));
+        assert_eq!(rendered.section_pages.len(), 4);
+    }
+}
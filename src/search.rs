@@ -0,0 +1,305 @@
+//! # Search - Cross-Module Record Lookup
+//!
+//! One keyword or identifier query runs over documents, CAPAs, suppliers,
+//! training records, complaints and risk assessments. Each module contributes
+//! a SELECT yielding `(id, reference, title, detail, status)`; matches are
+//! ranked in Rust so an exact identifier beats a title hit, which beats a hit
+//! in the description. CAPAs and risk assessments are read from their latest
+//! version snapshot, the record their services maintain. Records of plugin
+//! types are searched together, their field values serving as the detail.
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+
+/// Results returned when the caller does not ask for a limit.
+pub const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+/// Record family of a search hit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchKind {
+    Document,
+    Capa,
+    Supplier,
+    Training,
+    Complaint,
+    Risk,
+    /// A record of a plugin type, see [`crate::record_plugins`]
+    Custom,
+}
+
+impl SearchKind {
+    pub const ALL: [SearchKind; 7] = [
+        SearchKind::Document,
+        SearchKind::Capa,
+        SearchKind::Supplier,
+        SearchKind::Training,
+        SearchKind::Complaint,
+        SearchKind::Risk,
+        SearchKind::Custom,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchKind::Document => "document",
+            SearchKind::Capa => "capa",
+            SearchKind::Supplier => "supplier",
+            SearchKind::Training => "training",
+            SearchKind::Complaint => "complaint",
+            SearchKind::Risk => "risk",
+            SearchKind::Custom => "custom",
+        }
+    }
+
+    /// Column holding the record type of each row, for plugin records.
+    fn record_type_column(&self) -> &'static str {
+        match self {
+            SearchKind::Custom => "record_type",
+            _ => "NULL",
+        }
+    }
+
+    /// Candidate rows with columns `id, reference, title, detail, status`.
+    fn source_sql(&self) -> &'static str {
+        match self {
+            SearchKind::Document => {
+                "SELECT id, document_number AS reference, title, document_type AS detail, status
+                 FROM documents WHERE deleted_at IS NULL"
+            }
+            SearchKind::Capa => {
+                "SELECT v.record_id AS id, COALESCE(json_extract(v.snapshot, '$.capa_number'), v.record_id) AS reference,
+                        json_extract(v.snapshot, '$.title') AS title,
+                        json_extract(v.snapshot, '$.description') AS detail,
+                        json_extract(v.snapshot, '$.status') AS status
+                 FROM record_versions v
+                 WHERE v.record_type = 'capa' AND v.version = (
+                     SELECT MAX(w.version) FROM record_versions w
+                     WHERE w.record_type = v.record_type AND w.record_id = v.record_id)"
+            }
+            SearchKind::Supplier => {
+                "SELECT id, id AS reference, name AS title, contact_info AS detail, qualification_status AS status
+                 FROM suppliers WHERE deleted_at IS NULL"
+            }
+            SearchKind::Training => {
+                "SELECT id, id AS reference, training_item AS title, employee_id AS detail, status
+                 FROM training_records WHERE deleted_at IS NULL"
+            }
+            SearchKind::Complaint => {
+                "SELECT id, COALESCE(record_number, id) AS reference, title, description AS detail,
+                        COALESCE(routed_to, 'untriaged') AS status
+                 FROM quality_intake WHERE source = 'complaint'"
+            }
+            SearchKind::Risk => {
+                "SELECT v.record_id AS id, v.record_id AS reference, json_extract(v.snapshot, '$.device_name') AS title,
+                        json_extract(v.snapshot, '$.hazard_description') AS detail,
+                        json_extract(v.snapshot, '$.status') AS status
+                 FROM record_versions v
+                 WHERE v.record_type = 'risk_assessment' AND v.version = (
+                     SELECT MAX(w.version) FROM record_versions w
+                     WHERE w.record_type = v.record_type AND w.record_id = v.record_id)"
+            }
+            SearchKind::Custom => {
+                "SELECT id, reference, title, fields AS detail, status, record_type FROM custom_records"
+            }
+        }
+    }
+}
+
+/// One ranked search hit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub kind: SearchKind,
+    /// Internal key (UUID)
+    pub id: String,
+    /// Identifier people use: the record number where there is one
+    pub reference: String,
+    pub title: String,
+    pub status: String,
+    /// Higher is more relevant
+    pub score: u32,
+    /// Plugin record type of a [`SearchKind::Custom`] hit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_type: Option<String>,
+}
+
+impl SearchResult {
+    /// Record type of the hit, e.g. `capa` or a plugin's type.
+    pub fn record_type(&self) -> &str {
+        self.custom_type.as_deref().unwrap_or(self.kind.as_str())
+    }
+}
+
+/// Relevance of a candidate row; matching is case-insensitive.
+fn relevance(query: &str, id: &str, reference: &str, title: &str) -> u32 {
+    let title = title.to_lowercase();
+    if id.eq_ignore_ascii_case(query) || reference.eq_ignore_ascii_case(query) {
+        100
+    } else if title.starts_with(query) {
+        60
+    } else if title.contains(query) {
+        40
+    } else if reference.to_lowercase().contains(query) {
+        30
+    } else {
+        10
+    }
+}
+
+/// `%query%` with LIKE wildcards in the query taken literally.
+fn like_pattern(query: &str) -> String {
+    let escaped = query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// Keyword and identifier search across quality records.
+pub struct SearchService<'a> {
+    db: &'a Database,
+    kinds: Vec<SearchKind>,
+}
+
+impl<'a> SearchService<'a> {
+    /// Search over every record family.
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, kinds: SearchKind::ALL.to_vec() }
+    }
+
+    /// Search only `kinds`, e.g. those of the enabled modules.
+    pub fn with_kinds(mut self, kinds: Vec<SearchKind>) -> Self {
+        self.kinds = kinds;
+        self
+    }
+
+    /// Up to `limit` records matching `query`, most relevant first.
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Err(QmsError::Validation { field: "q".to_string(), message: "Search query is required".to_string() });
+        }
+        let pattern = like_pattern(&query);
+        let mut results = Vec::new();
+        self.db.with_connection(|conn| {
+            for &kind in &self.kinds {
+                let sql = format!(
+                    "SELECT id, reference, title, status, {} FROM ({})
+                     WHERE id LIKE ?1 ESCAPE '\\' OR reference LIKE ?1 ESCAPE '\\'
+                        OR title LIKE ?1 ESCAPE '\\' OR detail LIKE ?1 ESCAPE '\\'",
+                    kind.record_type_column(),
+                    kind.source_sql()
+                );
+                let mut stmt = conn.prepare(&sql)?;
+                let rows = stmt.query_map(params![pattern], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                        row.get::<_, Option<String>>(3)?.unwrap_or_default(),
+                        row.get::<_, Option<String>>(4)?,
+                    ))
+                })?;
+                for row in rows {
+                    let (id, reference, title, status, custom_type) = row?;
+                    let score = relevance(&query, &id, &reference, &title);
+                    results.push(SearchResult { kind, id, reference, title, status, score, custom_type });
+                }
+            }
+            Ok(())
+        })?;
+        results.sort_by(|a, b| b.score.cmp(&a.score).then(a.kind.cmp(&b.kind)).then_with(|| a.reference.cmp(&b.reference)));
+        results.truncate(limit);
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{AuditLogger, AuditManager};
+    use crate::capa::{CapaPriority, CapaService, CapaType};
+    use crate::config::DatabaseConfig;
+    use crate::quality_intake::{IssueSource, QualityIntake};
+    use crate::risk::{RiskManagementService, RiskProbability, RiskSeverity};
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_search_spans_modules_and_ranks_identifiers_first() {
+        let db = test_db();
+        db.seed_test_users(&["qa"]);
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO documents (id, document_number, title, version, status, document_type, content_hash, created_by)
+                 VALUES ('d1', 'SOP-BER-001', 'Seal integrity testing', '1.0', 'Effective', 'SOP', 'h', 'qa')",
+                [],
+            )?;
+            conn.execute(
+                "INSERT INTO suppliers (id, name, qualification_status) VALUES ('s1', 'Acme Seals GmbH', 'Qualified')",
+                [],
+            )?;
+            conn.execute(
+                "INSERT INTO training_records (id, employee_id, training_item, mandatory, assigned_by, due_date, status)
+                 VALUES ('t1', 'qa', 'Seal inspection', 1, 'qa', '2025-01-31', 'Pending')",
+                [],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        let capa_service = CapaService::new(AuditManager::new(db.clone()));
+        let capa = capa_service
+            .create_capa(
+                "Seal failure".to_string(),
+                "Lot 42 failed peel test".to_string(),
+                CapaType::Corrective,
+                CapaPriority::High,
+                "qa".to_string(),
+                "qa".to_string(),
+                None,
+            )
+            .unwrap();
+        let complaint = QualityIntake::new(&db)
+            .submit(IssueSource::Complaint, "Leaking seal", "Pouch seal leaked in transit", "support")
+            .unwrap();
+        RiskManagementService::new(AuditLogger::new_test())
+            .with_record_history(db.clone())
+            .create_risk_assessment(
+                "Pouch".to_string(),
+                "Broken sterile seal".to_string(),
+                "Situation".to_string(),
+                "Sequence".to_string(),
+                "Infection".to_string(),
+                RiskSeverity::Serious,
+                RiskProbability::Remote,
+                "qa".to_string(),
+            )
+            .await
+            .unwrap();
+
+        let service = SearchService::new(&db);
+        let results = service.search("seal", DEFAULT_SEARCH_LIMIT).unwrap();
+        let mut kinds: Vec<SearchKind> = results.iter().map(|r| r.kind).collect();
+        kinds.sort();
+        assert_eq!(kinds, SearchKind::ALL.to_vec());
+        assert_eq!(results[0].score, 60);
+        assert_eq!(results.last().unwrap().kind, SearchKind::Risk);
+
+        let by_number = service.search(&capa.capa_number.to_lowercase(), DEFAULT_SEARCH_LIMIT).unwrap();
+        assert_eq!(by_number[0].id, capa.id);
+        assert_eq!(by_number[0].score, 100);
+        assert_eq!(by_number[0].status, "Identified");
+        assert_eq!(service.search(&complaint.record_number, 1).unwrap()[0].kind, SearchKind::Complaint);
+
+        assert!(service.search("50%_off", DEFAULT_SEARCH_LIMIT).unwrap().is_empty());
+        assert!(service.search("   ", DEFAULT_SEARCH_LIMIT).is_err());
+    }
+}
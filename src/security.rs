@@ -1,18 +1,63 @@
 use crate::{Result, QmsError, config::SecurityConfig};
+use crate::audit::AuditManager;
+use crate::clock::{system_clock, SharedClock};
+use crate::database::Database;
+use crate::time_integrity::SignatureGuard;
 use ring::{
+    pbkdf2,
     rand::SecureRandom,
     signature::{self, KeyPair, RsaKeyPair, RSA_PKCS1_SHA256},
 };
 use base64::{engine::general_purpose, Engine as _};
 use std::collections::HashMap;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use chrono::{DateTime, Utc, Duration};
+use rusqlite::{params, OptionalExtension};
+use std::num::NonZeroU32;
 use uuid::Uuid;
 
-/// Security manager for FDA-compliant operations
+/// PBKDF2-HMAC-SHA256 rounds used for `users.password_hash`
+const PASSWORD_HASH_ITERATIONS: u32 = 100_000;
+
+/// Hash `password` with `salt` for storage in `users.password_hash`.
+pub fn hash_password(password: &str, salt: &str) -> String {
+    let mut hash = [0u8; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PASSWORD_HASH_ITERATIONS).expect("non-zero iterations"),
+        salt.as_bytes(),
+        password.as_bytes(),
+        &mut hash,
+    );
+    general_purpose::STANDARD.encode(hash)
+}
+
+/// Constant-time check of `password` against a stored hash.
+pub fn verify_password(password: &str, salt: &str, stored_hash: &str) -> bool {
+    let Ok(expected) = general_purpose::STANDARD.decode(stored_hash) else {
+        return false;
+    };
+    pbkdf2::verify(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PASSWORD_HASH_ITERATIONS).expect("non-zero iterations"),
+        salt.as_bytes(),
+        password.as_bytes(),
+        &expected,
+    )
+    .is_ok()
+}
+
+/// Security manager for FDA-compliant operations.
+///
+/// Clones share one session store, so a single manager built at startup can
+/// be used concurrently by the API, the TUI and background jobs.
+#[derive(Clone)]
 pub struct SecurityManager {
     config: SecurityConfig,
-    pub active_sessions: HashMap<String, Session>,
-    signature_manager: DigitalSignatureManager,
+    active_sessions: Arc<RwLock<HashMap<String, Session>>>,
+    signature_manager: Arc<DigitalSignatureManager>,
+    time_guard: Option<SignatureGuard>,
+    clock: SharedClock,
 }
 
 impl SecurityManager {
@@ -22,48 +67,233 @@ impl SecurityManager {
         
         Ok(Self {
             config,
-            active_sessions: HashMap::new(),
-            signature_manager,
+            active_sessions: Arc::new(RwLock::new(HashMap::new())),
+            signature_manager: Arc::new(signature_manager),
+            time_guard: None,
+            clock: system_clock(),
         })
     }
 
+    /// Read the current time from `clock` for session expiry and lockouts
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Refuse signatures while the clock monitor reports unresolved drift
+    pub fn with_time_guard(mut self, guard: SignatureGuard) -> Self {
+        self.time_guard = Some(guard);
+        self
+    }
+
     /// Get reference to digital signature manager
     pub fn signature_manager(&self) -> &DigitalSignatureManager {
         &self.signature_manager
     }
 
+    // A panic while a session lock was held cannot leave an entry half
+    // written, so a poisoned store is still used.
+    fn sessions(&self) -> RwLockReadGuard<'_, HashMap<String, Session>> {
+        self.active_sessions.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn sessions_mut(&self) -> RwLockWriteGuard<'_, HashMap<String, Session>> {
+        self.active_sessions.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// In-memory session by id, whether or not it is still valid
+    pub fn session(&self, session_id: &str) -> Option<Session> {
+        self.sessions().get(session_id).cloned()
+    }
+
+    /// Number of sessions held in memory
+    pub fn active_session_count(&self) -> usize {
+        self.sessions().len()
+    }
+
     /// Simple session-based authentication for demo purposes
-    pub fn authenticate_user(&mut self, username: &str, _password: &str) -> Result<String> {
+    pub fn authenticate_user(&self, username: &str, _password: &str) -> Result<String> {
         // Simplified authentication - in production this would verify against database
         let session_id = self.create_session(username.to_string(), None)?;
         Ok(session_id)
     }
 
     /// Create new session
-    pub fn create_session(&mut self, user_id: String, ip_address: Option<String>) -> Result<String> {
-        let session_id = uuid::Uuid::new_v4().to_string();
-        let expires_at = Utc::now() + Duration::minutes(self.config.session_timeout_minutes as i64);
+    pub fn create_session(&self, user_id: String, ip_address: Option<String>) -> Result<String> {
+        Ok(self.start_session(user_id, ip_address).id)
+    }
+
+    fn start_session(&self, user_id: String, ip_address: Option<String>) -> Session {
+        let now = self.clock.now();
+        let expires_at = now + Duration::minutes(self.config.session_timeout_minutes as i64);
 
         let session = Session {
-            id: session_id.clone(),
+            id: uuid::Uuid::new_v4().to_string(),
             user_id,
             ip_address,
-            created_at: Utc::now(),
-            last_activity: Utc::now(),
+            created_at: now,
+            last_activity: now,
             expires_at,
             is_active: true,
+            csrf_token: Uuid::new_v4().simple().to_string(),
         };
 
-        self.active_sessions.insert(session_id.clone(), session);
-        Ok(session_id)
+        self.sessions_mut().insert(session.id.clone(), session.clone());
+        session
+    }
+
+    /// Verify credentials against the `users` table and open a session that
+    /// is persisted in `sessions`, so it survives restarts and can back a
+    /// browser cookie. Failed attempts count towards the configured lockout;
+    /// every attempt is audited.
+    pub fn login(&self, db: &Database, username: &str, password: &str, ip_address: Option<String>) -> Result<Session> {
+        let audit = AuditManager::new(db.clone());
+        let now = self.clock.now();
+        let user: Option<(String, String, String, u32, Option<String>)> = db.with_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT id, password_hash, salt, failed_login_attempts, locked_until
+                     FROM users WHERE username = ?1 AND is_active = 1",
+                    params![username],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+                )
+                .optional()?)
+        })?;
+        // One message for every failure so callers cannot probe for accounts
+        let rejected = || QmsError::Security { message: "Invalid username or password".to_string() };
+        let Some((user_id, password_hash, salt, failed_attempts, locked_until)) = user else {
+            audit.log_action(username, "login", "authentication", "Failure", Some("unknown or inactive user".to_string()))?;
+            return Err(rejected());
+        };
+        let resource = format!("user:{}", user_id);
+        let locked = locked_until
+            .and_then(|until| DateTime::parse_from_rfc3339(&until).ok())
+            .is_some_and(|until| until > now);
+        if locked {
+            audit.log_action(&user_id, "login", &resource, "Failure", Some("account locked".to_string()))?;
+            return Err(rejected());
+        }
+        if !verify_password(password, &salt, &password_hash) {
+            let attempts = failed_attempts + 1;
+            let lock = (attempts >= self.config.max_failed_login_attempts)
+                .then(|| (now + Duration::minutes(self.config.lockout_duration_minutes as i64)).to_rfc3339());
+            db.with_connection(|conn| {
+                conn.execute(
+                    "UPDATE users SET failed_login_attempts = ?2, locked_until = ?3 WHERE id = ?1",
+                    params![user_id, if lock.is_some() { 0 } else { attempts }, lock],
+                )?;
+                Ok(())
+            })?;
+            let detail = if lock.is_some() { "wrong password; account locked" } else { "wrong password" };
+            audit.log_action(&user_id, "login", &resource, "Failure", Some(detail.to_string()))?;
+            return Err(rejected());
+        }
+
+        self.open_session(db, &user_id, ip_address, "password")
+    }
+
+    /// Open a persisted session for a user whose identity has already been
+    /// established by `method` (`password`, `oidc`), clearing any failed
+    /// login count. The login is audited.
+    pub fn open_session(
+        &self,
+        db: &Database,
+        user_id: &str,
+        ip_address: Option<String>,
+        method: &str,
+    ) -> Result<Session> {
+        let now = self.clock.now();
+        let session = self.start_session(user_id.to_string(), ip_address);
+        db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE users SET failed_login_attempts = 0, locked_until = NULL, last_login = ?2 WHERE id = ?1",
+                params![user_id, now.to_rfc3339()],
+            )?;
+            uow.connection().execute(
+                "INSERT INTO sessions (id, user_id, ip_address, created_at, last_activity, expires_at, is_active, csrf_token)
+                 VALUES (?1, ?2, ?3, ?4, ?4, ?5, 1, ?6)",
+                params![
+                    session.id,
+                    session.user_id,
+                    session.ip_address,
+                    session.created_at.to_rfc3339(),
+                    session.expires_at.to_rfc3339(),
+                    session.csrf_token
+                ],
+            )?;
+            AuditManager::new(db.clone()).log_action_in(
+                uow,
+                user_id,
+                "login",
+                &format!("user:{}", user_id),
+                "Success",
+                Some(format!("method: {}", method)),
+            )
+        })?;
+        Ok(session)
+    }
+
+    /// Look up an active persisted session and extend it by the session
+    /// timeout (sliding expiry).
+    pub fn resume_session(&self, db: &Database, session_id: &str) -> Result<Option<Session>> {
+        let now = self.clock.now();
+        let expires_at = now + Duration::minutes(self.config.session_timeout_minutes as i64);
+        let session = db.with_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    "UPDATE sessions SET last_activity = ?2, expires_at = ?3
+                     WHERE id = ?1 AND is_active = 1 AND expires_at > ?2 AND csrf_token IS NOT NULL
+                     RETURNING id, user_id, ip_address, created_at, csrf_token",
+                    params![session_id, now.to_rfc3339(), expires_at.to_rfc3339()],
+                    |row| {
+                        let created_at: String = row.get(3)?;
+                        Ok(Session {
+                            id: row.get(0)?,
+                            user_id: row.get(1)?,
+                            ip_address: row.get(2)?,
+                            created_at: DateTime::parse_from_rfc3339(&created_at)
+                                .map(|t| t.with_timezone(&Utc))
+                                .unwrap_or(now),
+                            last_activity: now,
+                            expires_at,
+                            is_active: true,
+                            csrf_token: row.get(4)?,
+                        })
+                    },
+                )
+                .optional()?)
+        })?;
+        if let Some(session) = &session {
+            self.sessions_mut().insert(session.id.clone(), session.clone());
+        }
+        Ok(session)
+    }
+
+    /// End a persisted session.
+    pub fn logout(&self, db: &Database, session_id: &str) -> Result<()> {
+        self.revoke_session(session_id)?;
+        let user_id: Option<String> = db.with_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    "UPDATE sessions SET is_active = 0 WHERE id = ?1 AND is_active = 1 RETURNING user_id",
+                    params![session_id],
+                    |row| row.get(0),
+                )
+                .optional()?)
+        })?;
+        if let Some(user_id) = user_id {
+            AuditManager::new(db.clone()).log_action(&user_id, "logout", &format!("user:{}", user_id), "Success", None)?;
+        }
+        Ok(())
     }
 
     /// Validate session
-    pub fn validate_session(&mut self, session_id: &str) -> Result<Option<&Session>> {
-        if let Some(session) = self.active_sessions.get_mut(session_id) {
-            if session.is_active && Utc::now() < session.expires_at {
-                session.last_activity = Utc::now();
-                return Ok(Some(session));
+    pub fn validate_session(&self, session_id: &str) -> Result<Option<Session>> {
+        let now = self.clock.now();
+        if let Some(session) = self.sessions_mut().get_mut(session_id) {
+            if session.is_active && now < session.expires_at {
+                session.last_activity = now;
+                return Ok(Some(session.clone()));
             } else {
                 session.is_active = false;
             }
@@ -72,17 +302,32 @@ impl SecurityManager {
     }
 
     /// Revoke session
-    pub fn revoke_session(&mut self, session_id: &str) -> Result<()> {
-        if let Some(session) = self.active_sessions.get_mut(session_id) {
+    pub fn revoke_session(&self, session_id: &str) -> Result<()> {
+        if let Some(session) = self.sessions_mut().get_mut(session_id) {
             session.is_active = false;
         }
         Ok(())
     }
 
+    /// Close every session, in memory and persisted, e.g. at shutdown.
+    /// Returns how many were still open.
+    pub fn close_all_sessions(&self, db: &Database) -> Result<usize> {
+        let mut closed: std::collections::HashSet<String> =
+            self.sessions_mut().drain().filter(|(_, session)| session.is_active).map(|(id, _)| id).collect();
+        db.with_connection(|conn| {
+            let mut stmt = conn.prepare("UPDATE sessions SET is_active = 0 WHERE is_active = 1 RETURNING id")?;
+            for id in stmt.query_map([], |row| row.get::<_, String>(0))? {
+                closed.insert(id?);
+            }
+            Ok(())
+        })?;
+        Ok(closed.len())
+    }
+
     /// Clean expired sessions
-    pub fn cleanup_expired_sessions(&mut self) {
-        let now = Utc::now();
-        self.active_sessions.retain(|_, session| {
+    pub fn cleanup_expired_sessions(&self) {
+        let now = self.clock.now();
+        self.sessions_mut().retain(|_, session| {
             session.is_active && session.expires_at > now
         });
     }
@@ -96,6 +341,9 @@ impl SecurityManager {
         timestamp: &DateTime<Utc>,
         additional_data: Option<&str>,
     ) -> Result<FDASignature> {
+        if let Some(guard) = &self.time_guard {
+            guard.ensure_signing_allowed()?;
+        }
         self.signature_manager.create_audit_signature(
             user_id, action, resource, timestamp, additional_data
         )
@@ -118,6 +366,8 @@ pub struct Session {
     pub last_activity: DateTime<Utc>,
     pub expires_at: DateTime<Utc>,
     pub is_active: bool,
+    /// Token a browser must echo in `x-csrf-token` on state-changing requests
+    pub csrf_token: String,
 }
 
 /// Digital signature manager for FDA 21 CFR Part 11 compliance
@@ -339,7 +589,7 @@ mod tests {
 
     #[test]
     fn test_session_management() {
-        let mut security = SecurityManager::new(test_security_config()).unwrap();
+        let security = SecurityManager::new(test_security_config()).unwrap();
         let user_id = "user123".to_string();
         let ip_address = Some("192.168.1.1".to_string());
 
@@ -357,6 +607,44 @@ mod tests {
         assert!(session.is_none());
     }
 
+    #[test]
+    fn test_sessions_expire_on_injected_clock() {
+        use crate::clock::MockClock;
+
+        let config = test_security_config();
+        let timeout = Duration::minutes(config.session_timeout_minutes as i64);
+        let clock = MockClock::at(Utc::now());
+        let security = SecurityManager::new(config).unwrap().with_clock(clock.shared());
+        let session_id = security.create_session("user123".to_string(), None).unwrap();
+
+        clock.advance(timeout - Duration::seconds(1));
+        assert!(security.validate_session(&session_id).unwrap().is_some());
+        clock.advance(Duration::seconds(1));
+        assert!(security.validate_session(&session_id).unwrap().is_none());
+        security.cleanup_expired_sessions();
+        assert_eq!(security.active_session_count(), 0);
+    }
+
+    #[test]
+    fn test_clones_share_sessions_across_threads() {
+        let security = SecurityManager::new(test_security_config()).unwrap();
+        let handles: Vec<_> = (0..8)
+            .map(|worker| {
+                let security = security.clone();
+                std::thread::spawn(move || {
+                    (0..25)
+                        .map(|_| security.create_session(format!("user{}", worker), None).unwrap())
+                        .filter(|id| security.validate_session(id).unwrap().is_some())
+                        .count()
+                })
+            })
+            .collect();
+        let validated: usize = handles.into_iter().map(|handle| handle.join().unwrap()).sum();
+
+        assert_eq!(validated, 200);
+        assert_eq!(security.active_session_count(), 200);
+    }
+
     #[test]
     fn test_signature_validation_failures() {
         let mut fda_sig = FDASignature {
@@ -389,4 +677,69 @@ mod tests {
         assert!(fda_sig.validate().is_err()); // Should fail due to age
         assert!(!fda_sig.is_current(24)); // Should not be current
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_clock_drift_blocks_audit_signatures() {
+        use crate::config::{DatabaseConfig, TimeIntegrityConfig};
+        use crate::database::Database;
+        use crate::time_integrity::TimeIntegrityMonitor;
+
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        }).unwrap();
+        let monitor = TimeIntegrityMonitor::new(TimeIntegrityConfig::default(), db);
+        let manager = SecurityManager::new(test_security_config()).unwrap().with_time_guard(monitor.guard());
+        let now = Utc::now();
+        assert!(manager.generate_audit_signature("qa", "APPROVE", "SOP-001", &now, None).is_ok());
+
+        monitor.evaluate(&[Duration::seconds(90)], now).unwrap();
+        let result = manager.generate_audit_signature("qa", "APPROVE", "SOP-001", &now, None);
+        assert!(matches!(result, Err(QmsError::Security { .. })));
+    }
+
+    #[test]
+    fn test_login_persists_session_and_locks_out() {
+        use crate::config::DatabaseConfig;
+
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        }).unwrap();
+        db.seed_test_users(&["alice", "bob"]);
+        db.with_connection(|conn| {
+            conn.execute(
+                "UPDATE users SET password_hash = ?1, salt = 's1' WHERE id IN ('alice', 'bob')",
+                params![hash_password("correct horse", "s1")],
+            )?;
+            Ok(())
+        }).unwrap();
+        let security = SecurityManager::new(test_security_config()).unwrap();
+
+        let session = security.login(&db, "alice", "correct horse", None).unwrap();
+        assert_eq!(session.user_id, "alice");
+        let restarted = SecurityManager::new(test_security_config()).unwrap();
+        let resumed = restarted.resume_session(&db, &session.id).unwrap().unwrap();
+        assert_eq!(resumed.csrf_token, session.csrf_token);
+        restarted.logout(&db, &session.id).unwrap();
+        assert!(security.resume_session(&db, &session.id).unwrap().is_none());
+
+        for _ in 0..3 {
+            assert!(matches!(security.login(&db, "bob", "guess", None), Err(QmsError::Security { .. })));
+        }
+        assert!(security.login(&db, "bob", "correct horse", None).is_err(), "locked after 3 failures");
+        assert!(security.login(&db, "mallory", "x", None).is_err());
+        let audit = db.get_audit_entries_for_resource("user:bob").unwrap();
+        assert_eq!(audit.len(), 4);
+        assert!(audit.iter().all(|e| e.action == "login" && e.outcome == "FAILURE"));
+        let audit = db.get_audit_entries_for_resource("user:alice").unwrap();
+        let actions: Vec<&str> = audit.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(actions, vec!["login", "logout"]);
+    }
+}
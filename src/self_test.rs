@@ -0,0 +1,304 @@
+//! # Self Test - Startup Compliance Checklist
+//!
+//! Before users work in the system, and on demand at `/health/compliance`,
+//! the installation checks the conditions its compliance depends on: the
+//! schema is current with every migration applied, encryption is on, the
+//! audit trail is intact, the clock is sane, there is room to keep writing
+//! audit entries and a recent backup exists. Each check passes or fails
+//! with a detail line. Critical failures stop startup; the others are
+//! logged as warnings so an operator can act before they become incidents.
+
+use std::path::PathBuf;
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::database::Database;
+use crate::error::Result;
+use crate::jobs::{JobKind, JobOutcome};
+use crate::migrations::MIGRATIONS;
+use crate::storage_monitor::available_space;
+use crate::time_integrity::ClockCheck;
+
+/// Audit entries this far in the future mean the clock went backwards.
+const MAX_AUDIT_CLOCK_SKEW_MINUTES: i64 = 5;
+
+/// Outcome of one checklist item.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComplianceCheck {
+    pub name: String,
+    pub passed: bool,
+    /// A failed critical check stops startup
+    pub critical: bool,
+    pub detail: String,
+}
+
+/// Detail of a passed check, or the reason a check failed.
+type CheckOutcome = std::result::Result<String, String>;
+
+impl ComplianceCheck {
+    /// An error while running a check counts as a failure.
+    fn new(name: &str, critical: bool, outcome: Result<CheckOutcome>) -> Self {
+        let (passed, detail) = match outcome {
+            Ok(Ok(detail)) => (true, detail),
+            Ok(Err(reason)) => (false, reason),
+            Err(e) => (false, e.to_string()),
+        };
+        Self { name: name.to_string(), passed, critical, detail }
+    }
+}
+
+/// Result of a full self-test run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComplianceChecklist {
+    pub checked_at: DateTime<Utc>,
+    pub checks: Vec<ComplianceCheck>,
+}
+
+impl ComplianceChecklist {
+    pub fn all_passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &ComplianceCheck> {
+        self.checks.iter().filter(|check| !check.passed)
+    }
+
+    /// Failed checks that must stop startup.
+    pub fn critical_failures(&self) -> impl Iterator<Item = &ComplianceCheck> {
+        self.failures().filter(|check| check.critical)
+    }
+
+    /// One line per check, e.g. `[PASS] schema_version: version 33 (current)`.
+    pub fn summary(&self) -> String {
+        self.checks
+            .iter()
+            .map(|check| format!("[{}] {}: {}", if check.passed { "PASS" } else { "FAIL" }, check.name, check.detail))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Settings the checklist needs from the configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfTestSettings {
+    pub data_directory: PathBuf,
+    pub encryption_enabled: bool,
+    pub encrypt_logs: bool,
+    pub min_free_disk_mb: u64,
+    /// Two backup intervals: one missed run is tolerated
+    pub max_backup_age: Duration,
+}
+
+impl SelfTestSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            data_directory: PathBuf::from(&config.application.data_directory),
+            encryption_enabled: config.security.encryption_enabled,
+            encrypt_logs: config.logging.encrypt_logs,
+            min_free_disk_mb: config.compliance.min_free_disk_mb,
+            max_backup_age: Duration::hours(2 * i64::from(config.database.backup_interval_hours.max(1))),
+        }
+    }
+}
+
+/// Run every check against `db`. `clock` is the latest NTP comparison when
+/// the time integrity monitor is running.
+pub fn run_self_test(
+    db: &Database,
+    settings: &SelfTestSettings,
+    clock: Option<&ClockCheck>,
+    now: DateTime<Utc>,
+) -> ComplianceChecklist {
+    let expected = MIGRATIONS.last().map(|m| m.version).unwrap_or_default();
+    let checks = vec![
+        ComplianceCheck::new(
+            "schema_version",
+            true,
+            db.schema_version().map(|version| match version == expected {
+                true => Ok(format!("version {} (current)", version)),
+                false => Err(format!("version {}, expected {}", version, expected)),
+            }),
+        ),
+        ComplianceCheck::new("migrations_applied", true, check_migrations(db)),
+        ComplianceCheck::new(
+            "encryption_enabled",
+            true,
+            Ok(match (settings.encryption_enabled, settings.encrypt_logs) {
+                (true, true) => Ok("encryption at rest and log encryption enabled".to_string()),
+                (false, _) => Err("encryption at rest is disabled".to_string()),
+                (true, false) => Err("log encryption is disabled".to_string()),
+            }),
+        ),
+        ComplianceCheck::new(
+            "audit_trail_integrity",
+            true,
+            db.verify_audit_integrity().map(|report| match report.integrity_verified {
+                true => Ok(format!("{} entries verified", report.total_entries)),
+                false => Err(report.details),
+            }),
+        ),
+        ComplianceCheck::new("clock_sanity", false, check_clock(db, clock, now)),
+        ComplianceCheck::new("disk_space", false, check_disk_space(settings)),
+        ComplianceCheck::new("backup_recency", false, check_backup(db, settings.max_backup_age, now)),
+    ];
+    ComplianceChecklist { checked_at: now, checks }
+}
+
+fn check_migrations(db: &Database) -> Result<CheckOutcome> {
+    let applied: Vec<u32> = db.with_connection(|conn| {
+        let mut stmt = conn.prepare("SELECT version FROM schema_migrations ORDER BY version")?;
+        let versions = stmt.query_map([], |row| row.get(0))?.collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(versions)
+    })?;
+    let missing: Vec<String> =
+        MIGRATIONS.iter().filter(|m| !applied.contains(&m.version)).map(|m| m.version.to_string()).collect();
+    Ok(match missing.is_empty() {
+        true => Ok(format!("{} of {} migrations applied", applied.len(), MIGRATIONS.len())),
+        false => Err(format!("migration(s) {} not applied", missing.join(", "))),
+    })
+}
+
+fn check_clock(db: &Database, clock: Option<&ClockCheck>, now: DateTime<Utc>) -> Result<CheckOutcome> {
+    let latest: Option<String> =
+        db.with_connection(|conn| Ok(conn.query_row("SELECT MAX(timestamp) FROM audit_trail", [], |row| row.get(0))?))?;
+    let latest = latest.and_then(|value| DateTime::parse_from_rfc3339(&value).ok()).map(|t| t.with_timezone(&Utc));
+    if let Some(latest) = latest {
+        if latest > now + Duration::minutes(MAX_AUDIT_CLOCK_SKEW_MINUTES) {
+            return Ok(Err(format!("system time {} is before the latest audit entry at {}", now, latest)));
+        }
+    }
+    Ok(match clock {
+        Some(check) if !check.within_tolerance => {
+            Err(format!("NTP offset of {} ms exceeds tolerance", check.offset_ms.unwrap_or_default()))
+        }
+        Some(check) => Ok(match check.offset_ms {
+            Some(offset) => format!("NTP offset {} ms; audit timestamps monotonic", offset),
+            None => "no NTP server answered; audit timestamps monotonic".to_string(),
+        }),
+        None => Ok("audit timestamps monotonic; NTP monitoring off".to_string()),
+    })
+}
+
+fn check_disk_space(settings: &SelfTestSettings) -> Result<CheckOutcome> {
+    let available_mb = available_space(&settings.data_directory)? / (1024 * 1024);
+    Ok(match available_mb < settings.min_free_disk_mb {
+        true => Err(format!("{} MB free, at least {} MB required", available_mb, settings.min_free_disk_mb)),
+        false => Ok(format!("{} MB free", available_mb)),
+    })
+}
+
+fn check_backup(db: &Database, max_age: Duration, now: DateTime<Utc>) -> Result<CheckOutcome> {
+    let latest: Option<String> = db.with_connection(|conn| {
+        Ok(conn.query_row(
+            "SELECT MAX(completed_at) FROM job_runs WHERE job = ?1 AND outcome = ?2",
+            params![JobKind::Backup.as_str(), JobOutcome::Succeeded.as_str()],
+            |row| row.get(0),
+        )?)
+    })?;
+    let latest = latest.and_then(|value| DateTime::parse_from_rfc3339(&value).ok()).map(|t| t.with_timezone(&Utc));
+    Ok(match latest {
+        None => Err("no successful backup recorded".to_string()),
+        Some(at) if now - at > max_age => {
+            Err(format!("last backup {} is older than {} hours", at, max_age.num_hours()))
+        }
+        Some(at) => Ok(format!("last backup {}", at)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use std::path::Path;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    fn settings(dir: &Path) -> SelfTestSettings {
+        SelfTestSettings {
+            data_directory: dir.join("not-created-yet"),
+            encryption_enabled: true,
+            encrypt_logs: true,
+            min_free_disk_mb: 0,
+            max_backup_age: Duration::hours(48),
+        }
+    }
+
+    fn record_backup(db: &Database, completed_at: DateTime<Utc>) {
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO job_runs
+                     (id, job, run_trigger, triggered_by, started_at, completed_at, duration_ms, outcome)
+                 VALUES (?1, 'backup', 'scheduled', 'system', ?2, ?2, 5, 'succeeded')",
+                params![uuid::Uuid::new_v4().to_string(), completed_at.to_rfc3339()],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_checklist_on_healthy_installation() {
+        let db = test_db();
+        let dir = tempfile::TempDir::new().unwrap();
+        let now = Utc::now();
+        record_backup(&db, now - Duration::hours(3));
+
+        let checklist = run_self_test(&db, &settings(dir.path()), None, now);
+        let names: Vec<&str> = checklist.checks.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "schema_version",
+                "migrations_applied",
+                "encryption_enabled",
+                "audit_trail_integrity",
+                "clock_sanity",
+                "disk_space",
+                "backup_recency"
+            ]
+        );
+        assert!(checklist.all_passed(), "{}", checklist.summary());
+        assert!(checklist.summary().starts_with("[PASS] schema_version"));
+    }
+
+    #[test]
+    fn test_failures_are_reported() {
+        let db = test_db();
+        let dir = tempfile::TempDir::new().unwrap();
+        let now = Utc::now();
+        let mut settings = settings(dir.path());
+        settings.encrypt_logs = false;
+        settings.min_free_disk_mb = u64::MAX;
+        let drift = ClockCheck {
+            checked_at: now,
+            offset_ms: Some(9_000),
+            servers_queried: 2,
+            servers_responding: 2,
+            within_tolerance: false,
+        };
+
+        let checklist = run_self_test(&db, &settings, Some(&drift), now);
+        let failed: Vec<&str> = checklist.failures().map(|c| c.name.as_str()).collect();
+        assert_eq!(failed, vec!["encryption_enabled", "clock_sanity", "disk_space", "backup_recency"]);
+        let critical: Vec<&str> = checklist.critical_failures().map(|c| c.name.as_str()).collect();
+        assert_eq!(critical, vec!["encryption_enabled"]);
+
+        record_backup(&db, now - Duration::hours(72));
+        let checklist = run_self_test(&db, &settings, None, now);
+        let backup = checklist.checks.iter().find(|c| c.name == "backup_recency").unwrap();
+        assert!(!backup.passed && backup.detail.contains("older than 48 hours"));
+        assert!(checklist.checks.iter().find(|c| c.name == "clock_sanity").unwrap().passed);
+    }
+}
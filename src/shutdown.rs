@@ -0,0 +1,233 @@
+//! # Shutdown - Graceful Shutdown Coordinator
+//!
+//! On SIGINT, SIGTERM, SIGHUP or a normal exit from the TUI, the
+//! [`ShutdownCoordinator`] takes the process down in a fixed order:
+//!
+//! 1. stop the tracked background tasks (API servers, job ticker, monitors);
+//! 2. wait for running jobs and for every checked-out database connection,
+//!    so in-flight transactions commit or roll back rather than being cut off;
+//! 3. close all sessions, in memory and in the `sessions` table;
+//! 4. record an `APPLICATION_SHUTDOWN` audit entry;
+//! 5. flush the non-blocking log writer.
+//!
+//! Only then does the signal handler exit. Waits are bounded by a drain
+//! timeout; when it expires the shutdown proceeds and the audit entry
+//! records what did not finish.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing_appender::non_blocking::WorkerGuard;
+
+use crate::app_context::AppContext;
+use crate::audit::AuditManager;
+use crate::crash_guard::restore_terminal;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::jobs::JobScheduler;
+use crate::security::SecurityManager;
+
+/// User recorded for the shutdown audit entry.
+pub const SHUTDOWN_USER: &str = "system:shutdown";
+
+/// Longest wait for running jobs and open transactions, each.
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+const DRAIN_POLL: Duration = Duration::from_millis(50);
+const RESOURCE: &str = "qms_system";
+
+/// What the shutdown completed, as recorded in the audit trail.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShutdownReport {
+    pub reason: String,
+    pub tasks_stopped: usize,
+    /// Whether running jobs finished within the drain timeout
+    pub jobs_finished: bool,
+    /// Whether every database connection was returned within the drain timeout
+    pub transactions_finished: bool,
+    pub sessions_closed: usize,
+}
+
+/// Runs the shutdown sequence once, from a signal or a normal exit.
+pub struct ShutdownCoordinator {
+    db: Database,
+    jobs: JobScheduler,
+    security: SecurityManager,
+    drain_timeout: Duration,
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+    log_guard: Mutex<Option<WorkerGuard>>,
+    started: AtomicBool,
+}
+
+impl ShutdownCoordinator {
+    pub fn new(context: &AppContext) -> Self {
+        Self {
+            db: context.database.clone(),
+            jobs: context.jobs.clone(),
+            security: context.security.clone(),
+            drain_timeout: DEFAULT_DRAIN_TIMEOUT,
+            tasks: Mutex::new(Vec::new()),
+            log_guard: Mutex::new(None),
+            started: AtomicBool::new(false),
+        }
+    }
+
+    pub fn with_drain_timeout(mut self, timeout: Duration) -> Self {
+        self.drain_timeout = timeout;
+        self
+    }
+
+    /// Flush the non-blocking log writer of `guard` as the last step.
+    pub fn with_log_guard(self, guard: WorkerGuard) -> Self {
+        *self.log_guard.lock().unwrap_or_else(PoisonError::into_inner) = Some(guard);
+        self
+    }
+
+    /// Stop `task` at the start of the shutdown.
+    pub fn track(&self, task: JoinHandle<()>) {
+        self.tasks.lock().unwrap_or_else(PoisonError::into_inner).push(task);
+    }
+
+    /// Run the shutdown sequence. Fails when a shutdown has already started.
+    pub async fn shutdown(&self, reason: &str) -> Result<ShutdownReport> {
+        if self.started.swap(true, Ordering::SeqCst) {
+            return Err(QmsError::Validation {
+                field: "shutdown".to_string(),
+                message: "Shutdown is already in progress".to_string(),
+            });
+        }
+
+        let tasks = std::mem::take(&mut *self.tasks.lock().unwrap_or_else(PoisonError::into_inner));
+        for task in &tasks {
+            task.abort();
+        }
+        let jobs_finished = self.drain(|| self.jobs.running_jobs() == 0).await;
+        let transactions_finished = self.drain(|| self.db.pool_stats().active_connections == 0).await;
+        let sessions_closed = self.security.close_all_sessions(&self.db)?;
+        let report = ShutdownReport {
+            reason: reason.to_string(),
+            tasks_stopped: tasks.len(),
+            jobs_finished,
+            transactions_finished,
+            sessions_closed,
+        };
+
+        let outcome = if jobs_finished && transactions_finished { "Success" } else { "Warning" };
+        AuditManager::new(self.db.clone()).log_action(
+            SHUTDOWN_USER,
+            "APPLICATION_SHUTDOWN",
+            RESOURCE,
+            outcome,
+            Some(serde_json::to_string(&report)?),
+        )?;
+
+        // Dropping the guard flushes buffered log lines
+        drop(self.log_guard.lock().unwrap_or_else(PoisonError::into_inner).take());
+        Ok(report)
+    }
+
+    /// Poll `done` until it holds or the drain timeout passes.
+    async fn drain(&self, done: impl Fn() -> bool) -> bool {
+        let deadline = Instant::now() + self.drain_timeout;
+        while !done() {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(DRAIN_POLL).await;
+        }
+        true
+    }
+
+    /// Restore the terminal and shut down gracefully when the process is
+    /// interrupted, terminated or its terminal hangs up, then exit with the
+    /// conventional `128 + signal` status.
+    pub fn spawn_signal_handler(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let (signal, status) = match wait_for_signal().await {
+                Ok(received) => received,
+                Err(e) => {
+                    tracing::error!("signal handlers could not be installed: {e}");
+                    return;
+                }
+            };
+            restore_terminal();
+            if let Err(e) = self.shutdown(&format!("Received {}", signal)).await {
+                eprintln!("shutdown after {} did not complete: {}", signal, e);
+            }
+            std::process::exit(status);
+        })
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() -> io::Result<(&'static str, i32)> {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut hangup = signal(SignalKind::hangup())?;
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    Ok(tokio::select! {
+        _ = interrupt.recv() => ("SIGINT", 130),
+        _ = terminate.recv() => ("SIGTERM", 143),
+        _ = hangup.recv() => ("SIGHUP", 129),
+    })
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> io::Result<(&'static str, i32)> {
+    tokio::signal::ctrl_c().await?;
+    Ok(("Ctrl+C", 130))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, DatabaseConfig};
+    use std::io::Write;
+
+    #[tokio::test]
+    async fn test_shutdown_drains_closes_sessions_and_audits() {
+        let config = Config {
+            database: DatabaseConfig {
+                url: ":memory:".to_string(),
+                max_connections: 10,
+                wal_mode: false,
+                backup_interval_hours: 24,
+                backup_retention_days: 90,
+            },
+            ..Config::default()
+        };
+        let context = AppContext::new(config).unwrap();
+        context.database.seed_test_users(&["qa"]);
+        let session = context.security.open_session(&context.database, "qa", None, "password").unwrap();
+
+        let log_dir = tempfile::TempDir::new().unwrap();
+        let log_path = log_dir.path().join("qms.log");
+        let (mut writer, guard) = tracing_appender::non_blocking(std::fs::File::create(&log_path).unwrap());
+        writer.write_all(b"last line before shutdown\n").unwrap();
+        let coordinator = ShutdownCoordinator::new(&context).with_log_guard(guard);
+        let (alive, stopped) = tokio::sync::oneshot::channel::<()>();
+        coordinator.track(tokio::spawn(async move {
+            let _alive = alive;
+            std::future::pending::<()>().await
+        }));
+
+        let report = coordinator.shutdown("Received SIGTERM").await.unwrap();
+        assert!(stopped.await.is_err(), "tracked task was stopped");
+        assert_eq!((report.tasks_stopped, report.sessions_closed), (1, 1));
+        assert!(report.jobs_finished && report.transactions_finished);
+        assert!(context.security.validate_session(&session.id).unwrap().is_none());
+        assert!(context.security.resume_session(&context.database, &session.id).unwrap().is_none());
+        assert_eq!(std::fs::read_to_string(&log_path).unwrap(), "last line before shutdown\n");
+
+        let entries = context.database.get_audit_entries_for_resource(RESOURCE).unwrap();
+        let entry = entries.iter().find(|e| e.action == "APPLICATION_SHUTDOWN").unwrap();
+        assert_eq!((entry.user_id.as_str(), entry.outcome.as_str()), (SHUTDOWN_USER, "SUCCESS"));
+        let recorded: ShutdownReport = serde_json::from_str(entry.metadata.as_deref().unwrap()).unwrap();
+        assert_eq!(recorded, report);
+        assert!(coordinator.shutdown("again").await.is_err());
+    }
+}
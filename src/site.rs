@@ -0,0 +1,403 @@
+//! # Sites - Multi-Site Data Partitioning
+//!
+//! A single deployment can host several manufacturing sites. Core records and
+//! audit entries carry a `site_id`; repositories scoped with `with_site` only
+//! see their own site's rows. Users hold roles per site, and corporate QA
+//! holds a role on [`ALL_SITES`] that grants read access everywhere plus the
+//! cross-site [`SiteRepo::rollup`].
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+
+/// Site owning all records created before multi-site partitioning.
+pub const DEFAULT_SITE_ID: &str = "default";
+
+/// Pseudo site id under which corporate (all-site) roles are granted.
+pub const ALL_SITES: &str = "*";
+
+/// A manufacturing site hosted by this deployment.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Site {
+    pub id: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Site {
+    pub fn new<S1: Into<String>, S2: Into<String>>(id: S1, name: S2) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Site-scoped role, ordered from least to most privileged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SiteRole {
+    /// Read-only access to the site's records
+    Viewer,
+    /// Create and update the site's records
+    QualityEngineer,
+    /// Approve and close the site's records
+    QualityManager,
+    /// Read access to every site and cross-site rollups
+    CorporateQa,
+}
+
+impl SiteRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SiteRole::Viewer => "Viewer",
+            SiteRole::QualityEngineer => "QualityEngineer",
+            SiteRole::QualityManager => "QualityManager",
+            SiteRole::CorporateQa => "CorporateQa",
+        }
+    }
+
+    /// Whether the role may modify site records.
+    pub fn can_write(&self) -> bool {
+        matches!(self, SiteRole::QualityEngineer | SiteRole::QualityManager)
+    }
+}
+
+impl std::str::FromStr for SiteRole {
+    type Err = QmsError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "Viewer" => Ok(SiteRole::Viewer),
+            "QualityEngineer" => Ok(SiteRole::QualityEngineer),
+            "QualityManager" => Ok(SiteRole::QualityManager),
+            "CorporateQa" => Ok(SiteRole::CorporateQa),
+            other => Err(QmsError::Validation {
+                field: "role".to_string(),
+                message: format!("Unknown site role: {}", other),
+            }),
+        }
+    }
+}
+
+/// A role granted to a user at a site (or at [`ALL_SITES`]).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SiteRoleAssignment {
+    pub user_id: String,
+    pub site_id: String,
+    pub role: SiteRole,
+    pub granted_by: String,
+    pub granted_at: DateTime<Utc>,
+}
+
+/// Per-site quality indicators for corporate QA.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SiteRollup {
+    pub site_id: String,
+    pub site_name: String,
+    pub open_capas: u64,
+    pub overdue_training: u64,
+    pub qualified_suppliers: u64,
+    pub adverse_events: u64,
+    pub audit_entries: u64,
+}
+
+/// Repository for `sites` and `site_roles`.
+pub struct SiteRepo<'a> {
+    db: &'a Database,
+}
+
+impl<'a> SiteRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    pub fn create_site(&self, site: &Site) -> Result<()> {
+        if site.id.trim().is_empty() || site.id == ALL_SITES {
+            return Err(QmsError::Validation {
+                field: "id".to_string(),
+                message: format!("Invalid site id: '{}'", site.id),
+            });
+        }
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO sites (id, name, created_at) VALUES (?1, ?2, ?3)",
+                params![site.id, site.name, site.created_at.to_rfc3339()],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn get_site(&self, id: &str) -> Result<Site> {
+        self.db.with_connection(|conn| {
+            conn.query_row("SELECT id, name, created_at FROM sites WHERE id = ?1", params![id], row_to_site)
+                .optional()?
+                .ok_or_else(|| QmsError::NotFound {
+                    resource: "site".to_string(),
+                    id: id.to_string(),
+                })
+        })
+    }
+
+    pub fn list_sites(&self) -> Result<Vec<Site>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare("SELECT id, name, created_at FROM sites ORDER BY id")?;
+            let rows = stmt.query_map([], row_to_site)?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        })
+    }
+
+    /// Grant `role` to a user. Corporate QA is granted on [`ALL_SITES`];
+    /// every other role on an existing site.
+    pub fn grant_role(&self, user_id: &str, site_id: &str, role: SiteRole, granted_by: &str) -> Result<()> {
+        match (role, site_id == ALL_SITES) {
+            (SiteRole::CorporateQa, true) => {}
+            (SiteRole::CorporateQa, false) | (_, true) => {
+                return Err(QmsError::Validation {
+                    field: "site_id".to_string(),
+                    message: format!("{} cannot be granted on site '{}'", role.as_str(), site_id),
+                });
+            }
+            (_, false) => {
+                self.get_site(site_id)?;
+            }
+        }
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "INSERT OR IGNORE INTO site_roles (user_id, site_id, role, granted_by, granted_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![user_id, site_id, role.as_str(), granted_by, Utc::now().to_rfc3339()],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn revoke_role(&self, user_id: &str, site_id: &str, role: SiteRole) -> Result<()> {
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "DELETE FROM site_roles WHERE user_id = ?1 AND site_id = ?2 AND role = ?3",
+                params![user_id, site_id, role.as_str()],
+            )?;
+            Ok(())
+        })
+    }
+
+    pub fn roles_for(&self, user_id: &str) -> Result<Vec<SiteRoleAssignment>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT user_id, site_id, role, granted_by, granted_at FROM site_roles
+                 WHERE user_id = ?1 ORDER BY site_id, role",
+            )?;
+            let rows = stmt.query_map(params![user_id], row_to_assignment)?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        })
+    }
+
+    /// Most privileged role the user holds at `site_id`, including corporate roles.
+    pub fn role_at(&self, user_id: &str, site_id: &str) -> Result<Option<SiteRole>> {
+        Ok(self
+            .roles_for(user_id)?
+            .into_iter()
+            .filter(|a| a.site_id == site_id || a.site_id == ALL_SITES)
+            .map(|a| a.role)
+            .max())
+    }
+
+    /// Sites whose data the user may see.
+    pub fn accessible_sites(&self, user_id: &str) -> Result<Vec<String>> {
+        let roles = self.roles_for(user_id)?;
+        if roles.iter().any(|a| a.site_id == ALL_SITES) {
+            return Ok(self.list_sites()?.into_iter().map(|s| s.id).collect());
+        }
+        let mut sites: Vec<String> = roles.into_iter().map(|a| a.site_id).collect();
+        sites.dedup();
+        Ok(sites)
+    }
+
+    /// Check that the user may read (or, with `write`, modify) data at `site_id`.
+    pub fn authorize(&self, user_id: &str, site_id: &str, write: bool) -> Result<SiteRole> {
+        let role = self.role_at(user_id, site_id)?.ok_or_else(|| QmsError::Security {
+            message: format!("User {} has no role at site {}", user_id, site_id),
+        })?;
+        if write && !role.can_write() {
+            return Err(QmsError::Security {
+                message: format!("Role {} at site {} is read-only", role.as_str(), site_id),
+            });
+        }
+        Ok(role)
+    }
+
+    /// Cross-site quality indicators; restricted to corporate QA. Training is
+    /// overdue when incomplete past its due date as of `today`.
+    pub fn rollup(&self, user_id: &str, today: NaiveDate) -> Result<Vec<SiteRollup>> {
+        if self.role_at(user_id, ALL_SITES)? != Some(SiteRole::CorporateQa) {
+            return Err(QmsError::Security {
+                message: format!("Cross-site rollups require the CorporateQa role ({})", user_id),
+            });
+        }
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT s.id, s.name,
+                    (SELECT COUNT(*) FROM capa_records c WHERE c.site_id = s.id AND c.deleted_at IS NULL
+                        AND c.status NOT IN ('Closed', 'Cancelled')),
+                    (SELECT COUNT(*) FROM training_records t WHERE t.site_id = s.id AND t.deleted_at IS NULL
+                        AND t.status != 'Completed' AND t.due_date < ?1),
+                    (SELECT COUNT(*) FROM suppliers p WHERE p.site_id = s.id AND p.deleted_at IS NULL
+                        AND p.qualification_status = 'Qualified'),
+                    (SELECT COUNT(*) FROM adverse_events e WHERE e.site_id = s.id AND e.deleted_at IS NULL),
+                    (SELECT COUNT(*) FROM audit_trail a WHERE a.site_id = s.id)
+                 FROM sites s ORDER BY s.id",
+            )?;
+            let rows = stmt.query_map(params![today.to_string()], |row| {
+                Ok(SiteRollup {
+                    site_id: row.get(0)?,
+                    site_name: row.get(1)?,
+                    open_capas: row.get::<_, i64>(2)? as u64,
+                    overdue_training: row.get::<_, i64>(3)? as u64,
+                    qualified_suppliers: row.get::<_, i64>(4)? as u64,
+                    adverse_events: row.get::<_, i64>(5)? as u64,
+                    audit_entries: row.get::<_, i64>(6)? as u64,
+                })
+            })?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        })
+    }
+}
+
+/// Parse stored timestamps, which are RFC 3339 or SQLite `CURRENT_TIMESTAMP`.
+fn parse_timestamp(idx: usize, value: &str) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .or_else(|_| {
+            chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").map(|t| t.and_utc())
+        })
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e)))
+}
+
+fn row_to_site(row: &rusqlite::Row) -> rusqlite::Result<Site> {
+    let created_at: String = row.get(2)?;
+    Ok(Site {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        created_at: parse_timestamp(2, &created_at)?,
+    })
+}
+
+fn row_to_assignment(row: &rusqlite::Row) -> rusqlite::Result<SiteRoleAssignment> {
+    let role: String = row.get(2)?;
+    let granted_at: String = row.get(4)?;
+    Ok(SiteRoleAssignment {
+        user_id: row.get(0)?,
+        site_id: row.get(1)?,
+        role: role.parse().map_err(|e: QmsError| {
+            rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e))
+        })?,
+        granted_by: row.get(3)?,
+        granted_at: parse_timestamp(4, &granted_at)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditManager;
+    use crate::config::DatabaseConfig;
+    use crate::supplier::{Supplier, SupplierStatus};
+    use crate::supplier_repo::SupplierRepository;
+    use uuid::Uuid;
+
+    fn setup() -> Database {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["alice", "bob", "carol"]);
+        let repo = SiteRepo::new(&db);
+        repo.create_site(&Site::new("plant-a", "Plant A")).unwrap();
+        repo.create_site(&Site::new("plant-b", "Plant B")).unwrap();
+        db
+    }
+
+    fn supplier(name: &str, status: SupplierStatus) -> Supplier {
+        Supplier {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            contact_info: None,
+            status,
+            qualification_date: None,
+            qualification_expiry_date: None,
+            approved_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            row_version: 1,
+        }
+    }
+
+    #[test]
+    fn test_site_scoped_roles() {
+        let db = setup();
+        let repo = SiteRepo::new(&db);
+        assert_eq!(repo.list_sites().unwrap().len(), 3, "default site is created by migration");
+
+        repo.grant_role("alice", "plant-a", SiteRole::QualityEngineer, "admin").unwrap();
+        repo.grant_role("alice", "plant-b", SiteRole::Viewer, "admin").unwrap();
+        repo.grant_role("carol", ALL_SITES, SiteRole::CorporateQa, "admin").unwrap();
+        assert!(repo.grant_role("bob", ALL_SITES, SiteRole::Viewer, "admin").is_err());
+        assert!(repo.grant_role("bob", "plant-a", SiteRole::CorporateQa, "admin").is_err());
+        assert!(matches!(
+            repo.grant_role("bob", "plant-z", SiteRole::Viewer, "admin"),
+            Err(QmsError::NotFound { .. })
+        ));
+
+        assert_eq!(repo.authorize("alice", "plant-a", true).unwrap(), SiteRole::QualityEngineer);
+        assert!(repo.authorize("alice", "plant-b", false).is_ok());
+        assert!(matches!(repo.authorize("alice", "plant-b", true), Err(QmsError::Security { .. })));
+        assert!(repo.authorize("bob", "plant-a", false).is_err());
+        assert!(repo.authorize("carol", "plant-b", false).is_ok());
+        assert!(repo.authorize("carol", "plant-b", true).is_err());
+
+        assert_eq!(repo.accessible_sites("alice").unwrap(), vec!["plant-a", "plant-b"]);
+        assert_eq!(repo.accessible_sites("carol").unwrap(), vec!["default", "plant-a", "plant-b"]);
+        repo.revoke_role("alice", "plant-b", SiteRole::Viewer).unwrap();
+        assert_eq!(repo.accessible_sites("alice").unwrap(), vec!["plant-a"]);
+    }
+
+    #[test]
+    fn test_site_isolation_and_rollup() {
+        let db = setup();
+        let plant_a = SupplierRepository::new(db.clone()).with_site("plant-a");
+        let plant_b = SupplierRepository::new(db.clone()).with_site("plant-b");
+        let acme = supplier("Acme", SupplierStatus::Qualified);
+        plant_a.insert(&acme).unwrap();
+        plant_a.insert(&supplier("Bolt Co", SupplierStatus::Pending)).unwrap();
+        plant_b.insert(&supplier("Cast Inc", SupplierStatus::Qualified)).unwrap();
+        assert!(plant_a.fetch_by_id(&acme.id).unwrap().is_some());
+        assert!(plant_b.fetch_by_id(&acme.id).unwrap().is_none(), "sites must not see each other's records");
+
+        AuditManager::new(db.clone())
+            .with_site("plant-b")
+            .log_action("alice", "supplier_qualified", "supplier:cast", "Success", None)
+            .unwrap();
+        let entries = db.get_audit_entries_for_resource("supplier:cast").unwrap();
+        assert_eq!(entries[0].site_id, "plant-b");
+
+        let repo = SiteRepo::new(&db);
+        repo.grant_role("carol", ALL_SITES, SiteRole::CorporateQa, "admin").unwrap();
+        repo.grant_role("alice", "plant-a", SiteRole::QualityManager, "admin").unwrap();
+        assert!(matches!(repo.rollup("alice", Utc::now().date_naive()), Err(QmsError::Security { .. })));
+
+        let rollup = repo.rollup("carol", Utc::now().date_naive()).unwrap();
+        let by_site = |id: &str| rollup.iter().find(|r| r.site_id == id).unwrap().clone();
+        assert_eq!(by_site("plant-a").qualified_suppliers, 1);
+        assert_eq!(by_site("plant-b").qualified_suppliers, 1);
+        assert_eq!(by_site("plant-b").audit_entries, 1);
+        assert_eq!(by_site("default").qualified_suppliers, 0);
+    }
+}
@@ -0,0 +1,338 @@
+//! # Soft Delete - Record Deletion & Retention Enforcement
+//!
+//! Quality records are never removed outright. Deleting a record stamps
+//! `deleted_at`, `deleted_by` and `deletion_reason`; repository queries skip
+//! such rows by default. A hard delete is only possible for a record that is
+//! already soft-deleted and older than the configured retention period.
+//! Records under legal hold can be neither deleted nor purged. Every
+//! attempt, allowed or refused, is written to the audit trail
+//! (FDA 21 CFR Part 11 §11.10(c), 21 CFR 820.180).
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::AuditManager;
+use crate::change_history::ChangeReason;
+use crate::config::ComplianceConfig;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::legal_hold::LegalHoldService;
+use crate::reporting_views::{invalidate_with, Summary};
+
+/// Tables supporting soft delete, with the column the retention period is
+/// measured from.
+pub const SOFT_DELETE_TABLES: &[(&str, &str)] = &[
+    ("users", "created_at"),
+    ("documents", "created_at"),
+    ("capa_records", "created_at"),
+    ("capa_actions", "created_at"),
+    ("risk_assessments", "created_at"),
+    ("control_measures", "implemented_at"),
+    ("training_records", "created_at"),
+    ("suppliers", "created_at"),
+    ("adverse_events", "created_at"),
+    ("report_schedules", "created_at"),
+];
+
+/// Deletion details of a soft-deleted record.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeletionInfo {
+    pub deleted_at: DateTime<Utc>,
+    pub deleted_by: String,
+    pub reason: String,
+}
+
+/// Soft delete, restore and retention-checked hard delete for record tables.
+pub struct SoftDeleteService {
+    database: Database,
+    audit: AuditManager,
+    retention_days: u32,
+}
+
+impl SoftDeleteService {
+    pub fn new(database: Database, config: &ComplianceConfig) -> Self {
+        Self {
+            audit: AuditManager::new(database.clone()),
+            database,
+            retention_days: config.audit_retention_days,
+        }
+    }
+
+    /// Mark a record deleted.
+    pub fn soft_delete(&self, table: &str, id: &str, user_id: &str, reason: &ChangeReason) -> Result<()> {
+        let result = self.mark_deleted(table, id, user_id, reason);
+        self.audit_attempt(user_id, "record_soft_deleted", table, id, reason, &result)?;
+        result
+    }
+
+    /// Undo a soft delete.
+    pub fn restore(&self, table: &str, id: &str, user_id: &str, reason: &ChangeReason) -> Result<()> {
+        let result = self.clear_deleted(table, id);
+        self.audit_attempt(user_id, "record_restored", table, id, reason, &result)?;
+        result
+    }
+
+    /// Permanently remove a soft-deleted record whose retention period has
+    /// elapsed by `now`.
+    pub fn hard_delete(
+        &self,
+        table: &str,
+        id: &str,
+        user_id: &str,
+        reason: &ChangeReason,
+        now: DateTime<Utc>,
+    ) -> Result<()> {
+        let result = self.purge(table, id, now);
+        self.audit_attempt(user_id, "record_hard_deleted", table, id, reason, &result)?;
+        result
+    }
+
+    /// Deletion details, or `None` when the record is live.
+    pub fn deletion(&self, table: &str, id: &str) -> Result<Option<DeletionInfo>> {
+        let (table, _) = lookup(table)?;
+        self.database.with_connection(|conn| {
+            let row: Option<(Option<String>, Option<String>, Option<String>)> = conn
+                .query_row(
+                    &format!("SELECT deleted_at, deleted_by, deletion_reason FROM {} WHERE id = ?1", table),
+                    params![id],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                )
+                .optional()?;
+            let (deleted_at, deleted_by, reason) = row.ok_or_else(|| not_found(table, id))?;
+            match deleted_at {
+                None => Ok(None),
+                Some(deleted_at) => Ok(Some(DeletionInfo {
+                    deleted_at: DateTime::parse_from_rfc3339(&deleted_at)
+                        .map_err(|e| QmsError::Database { message: format!("Invalid deleted_at: {}", e) })?
+                        .with_timezone(&Utc),
+                    deleted_by: deleted_by.unwrap_or_default(),
+                    reason: reason.unwrap_or_default(),
+                })),
+            }
+        })
+    }
+
+    fn mark_deleted(&self, table: &str, id: &str, user_id: &str, reason: &ChangeReason) -> Result<()> {
+        let (table, _) = lookup(table)?;
+        if self.deletion(table, id)?.is_some() {
+            return Err(QmsError::Validation {
+                field: "id".to_string(),
+                message: format!("{} {} is already deleted", table, id),
+            });
+        }
+        LegalHoldService::new(&self.database).ensure_not_held(table, id, "deletion")?;
+        self.database.with_connection(|conn| {
+            conn.execute(
+                &format!(
+                    "UPDATE {} SET deleted_at = ?2, deleted_by = ?3, deletion_reason = ?4 WHERE id = ?1",
+                    table
+                ),
+                params![id, Utc::now().to_rfc3339(), user_id, reason.as_str()],
+            )?;
+            invalidate_summary(conn, table)
+        })
+    }
+
+    fn clear_deleted(&self, table: &str, id: &str) -> Result<()> {
+        let (table, _) = lookup(table)?;
+        if self.deletion(table, id)?.is_none() {
+            return Err(QmsError::Validation {
+                field: "id".to_string(),
+                message: format!("{} {} is not deleted", table, id),
+            });
+        }
+        self.database.with_connection(|conn| {
+            conn.execute(
+                &format!(
+                    "UPDATE {} SET deleted_at = NULL, deleted_by = NULL, deletion_reason = NULL WHERE id = ?1",
+                    table
+                ),
+                params![id],
+            )?;
+            invalidate_summary(conn, table)
+        })
+    }
+
+    fn purge(&self, table: &str, id: &str, now: DateTime<Utc>) -> Result<()> {
+        let (table, anchor) = lookup(table)?;
+        if self.deletion(table, id)?.is_none() {
+            return Err(QmsError::Validation {
+                field: "deleted_at".to_string(),
+                message: format!("{} {} must be soft-deleted before it can be purged", table, id),
+            });
+        }
+        LegalHoldService::new(&self.database).ensure_not_held(table, id, "purge")?;
+        self.database.with_connection(|conn| {
+            let age_days: f64 = conn.query_row(
+                &format!("SELECT julianday(?2) - julianday({}) FROM {} WHERE id = ?1", anchor, table),
+                params![id, now.to_rfc3339()],
+                |row| row.get(0),
+            )?;
+            if age_days < self.retention_days as f64 {
+                return Err(QmsError::Validation {
+                    field: "retention_period".to_string(),
+                    message: format!(
+                        "{} {} is inside its {}-day retention period ({} days old)",
+                        table, id, self.retention_days, age_days.floor()
+                    ),
+                });
+            }
+            conn.execute(&format!("DELETE FROM {} WHERE id = ?1", table), params![id])?;
+            invalidate_summary(conn, table)
+        })
+    }
+
+    fn audit_attempt(
+        &self,
+        user_id: &str,
+        action: &str,
+        table: &str,
+        id: &str,
+        reason: &ChangeReason,
+        result: &Result<()>,
+    ) -> Result<()> {
+        let (outcome, details) = match result {
+            Ok(()) => ("Success", format!("Reason: {}", reason)),
+            Err(e) => ("Failure", format!("Reason: {}; refused: {}", reason, e)),
+        };
+        self.audit
+            .log_action(user_id, action, &format!("{}:{}", table, id), outcome, Some(details))
+    }
+}
+
+/// Resolve a table name against [`SOFT_DELETE_TABLES`] (table names cannot be
+/// bound as SQL parameters, so only known names are ever interpolated).
+fn lookup(table: &str) -> Result<(&'static str, &'static str)> {
+    SOFT_DELETE_TABLES
+        .iter()
+        .find(|(name, _)| *name == table)
+        .copied()
+        .ok_or_else(|| QmsError::Validation {
+            field: "table".to_string(),
+            message: format!("{} does not support deletion", table),
+        })
+}
+
+/// Deleting or restoring a record changes the summary built from its table.
+fn invalidate_summary(conn: &Connection, table: &str) -> Result<()> {
+    match Summary::for_source_table(table) {
+        Some(summary) => invalidate_with(conn, summary),
+        None => Ok(()),
+    }
+}
+
+fn not_found(table: &str, id: &str) -> QmsError {
+    QmsError::NotFound {
+        resource: table.to_string(),
+        id: id.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::post_market::{AdverseEvent, AdverseEventRepo, Severity};
+    use chrono::Duration;
+
+    fn setup() -> (Database, SoftDeleteService, AdverseEvent) {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let event = AdverseEvent::new("reporter", "entered against wrong device", Severity::Minor);
+        AdverseEventRepo::new(&db).insert(&event).unwrap();
+        let service = SoftDeleteService::new(db.clone(), &ComplianceConfig::default());
+        (db, service, event)
+    }
+
+    #[test]
+    fn test_soft_delete_hides_record_and_restore_brings_it_back() {
+        let (db, service, event) = setup();
+        let id = event.id.to_string();
+        let reason = ChangeReason::new("Duplicate entry").unwrap();
+
+        service.soft_delete("adverse_events", &id, "qa", &reason).unwrap();
+        assert!(matches!(AdverseEventRepo::new(&db).get(event.id), Err(QmsError::NotFound { .. })));
+        let info = service.deletion("adverse_events", &id).unwrap().unwrap();
+        assert_eq!(info.deleted_by, "qa");
+        assert_eq!(info.reason, "Duplicate entry");
+        assert!(service.soft_delete("adverse_events", &id, "qa", &reason).is_err());
+
+        service.restore("adverse_events", &id, "qa", &reason).unwrap();
+        assert!(AdverseEventRepo::new(&db).get(event.id).is_ok());
+    }
+
+    #[test]
+    fn test_hard_delete_blocked_inside_retention_and_always_audited() {
+        let (db, service, event) = setup();
+        let id = event.id.to_string();
+        let reason = ChangeReason::new("Retention expired").unwrap();
+
+        // Live records cannot be purged at all
+        assert!(matches!(
+            service.hard_delete("adverse_events", &id, "admin", &reason, Utc::now()),
+            Err(QmsError::Validation { field, .. }) if field == "deleted_at"
+        ));
+        service.soft_delete("adverse_events", &id, "admin", &reason).unwrap();
+        assert!(matches!(
+            service.hard_delete("adverse_events", &id, "admin", &reason, Utc::now()),
+            Err(QmsError::Validation { field, .. }) if field == "retention_period"
+        ));
+
+        let later = Utc::now() + Duration::days(2556);
+        service.hard_delete("adverse_events", &id, "admin", &reason, later).unwrap();
+        assert!(matches!(service.deletion("adverse_events", &id), Err(QmsError::NotFound { .. })));
+
+        let audit = db.get_audit_entries_for_resource(&format!("adverse_events:{}", id)).unwrap();
+        let outcomes: Vec<(&str, &str)> = audit.iter().map(|e| (e.action.as_str(), e.outcome.as_str())).collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                ("record_hard_deleted", "FAILURE"),
+                ("record_soft_deleted", "SUCCESS"),
+                ("record_hard_deleted", "FAILURE"),
+                ("record_hard_deleted", "SUCCESS"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_legal_hold_blocks_deletion_and_purge() {
+        let (db, service, event) = setup();
+        let id = event.id.to_string();
+        let reason = ChangeReason::new("Retention expired").unwrap();
+        let holds = LegalHoldService::new(&db);
+
+        let hold = holds.place("adverse_events", None, "admin", &ChangeReason::new("Product liability claim").unwrap()).unwrap();
+        assert!(matches!(
+            service.soft_delete("adverse_events", &id, "admin", &reason),
+            Err(QmsError::Validation { field, .. }) if field == "legal_hold"
+        ));
+        holds.release(hold.id, "admin", &ChangeReason::new("Claim withdrawn").unwrap()).unwrap();
+        service.soft_delete("adverse_events", &id, "admin", &reason).unwrap();
+
+        holds.place("adverse_events", Some(id.as_str()), "admin", &ChangeReason::new("Regulator inquiry").unwrap()).unwrap();
+        let later = Utc::now() + Duration::days(2556);
+        assert!(matches!(
+            service.hard_delete("adverse_events", &id, "admin", &reason, later),
+            Err(QmsError::Validation { field, .. }) if field == "legal_hold"
+        ));
+        assert!(service.deletion("adverse_events", &id).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_unknown_tables_are_rejected() {
+        let (_db, service, _event) = setup();
+        let reason = ChangeReason::new("Cleanup").unwrap();
+        assert!(matches!(
+            service.soft_delete("audit_trail", "1", "admin", &reason),
+            Err(QmsError::Validation { .. })
+        ));
+    }
+}
@@ -0,0 +1,629 @@
+//! # SPC - Statistical Process Control Charts
+//!
+//! Control charts for the trend review meeting (ISO 13485 §8.4): X-bar/R
+//! charts over measurements ingested from lab systems, and p-charts over the
+//! fraction of failed inspection results per batch and the monthly complaint
+//! rate. Limits are the usual three-sigma limits estimated from the data.
+//!
+//! Every point is checked against the Western Electric rules. A violation is
+//! stored once as an [`SpcAlert`], audited and, with a notifier, emailed to
+//! the quality managers. Charts export as CSV for the trend data and as SVG
+//! for the meeting slides.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::html_report::escape;
+use crate::notification::{EmailMessage, Notifier};
+use crate::vigilance_export::csv_field;
+
+/// User recorded for alerts raised by chart evaluation.
+pub const SPC_USER: &str = "system:spc";
+
+/// A2, D3 and D4 for subgroup sizes 2 to 10.
+const XBAR_R_CONSTANTS: [(f64, f64, f64); 9] = [
+    (1.880, 0.0, 3.267),
+    (1.023, 0.0, 2.574),
+    (0.729, 0.0, 2.282),
+    (0.577, 0.0, 2.114),
+    (0.483, 0.0, 2.004),
+    (0.419, 0.076, 1.924),
+    (0.373, 0.136, 1.864),
+    (0.337, 0.184, 1.816),
+    (0.308, 0.223, 1.777),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartKind {
+    /// Subgroup means
+    XBar,
+    /// Subgroup ranges
+    Range,
+    /// Fraction nonconforming
+    P,
+}
+
+impl ChartKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChartKind::XBar => "x_bar",
+            ChartKind::Range => "range",
+            ChartKind::P => "p",
+        }
+    }
+}
+
+/// Western Electric rules for an out-of-control process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WesternElectricRule {
+    /// Rule 1: one point beyond the three-sigma limits
+    BeyondThreeSigma,
+    /// Rule 2: two of three consecutive points beyond two sigma, same side
+    TwoOfThreeBeyondTwoSigma,
+    /// Rule 3: four of five consecutive points beyond one sigma, same side
+    FourOfFiveBeyondOneSigma,
+    /// Rule 4: eight consecutive points on the same side of the center line
+    EightOnOneSide,
+}
+
+impl WesternElectricRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WesternElectricRule::BeyondThreeSigma => "beyond_three_sigma",
+            WesternElectricRule::TwoOfThreeBeyondTwoSigma => "two_of_three_beyond_two_sigma",
+            WesternElectricRule::FourOfFiveBeyondOneSigma => "four_of_five_beyond_one_sigma",
+            WesternElectricRule::EightOnOneSide => "eight_on_one_side",
+        }
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            WesternElectricRule::BeyondThreeSigma => "point beyond a control limit",
+            WesternElectricRule::TwoOfThreeBeyondTwoSigma => "2 of 3 points beyond 2 sigma",
+            WesternElectricRule::FourOfFiveBeyondOneSigma => "4 of 5 points beyond 1 sigma",
+            WesternElectricRule::EightOnOneSide => "8 points on one side of the center line",
+        }
+    }
+}
+
+/// One plotted point; p-chart limits vary with the sample size.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChartPoint {
+    pub label: String,
+    pub value: f64,
+    pub center: f64,
+    pub lcl: f64,
+    pub ucl: f64,
+    /// Rules this point completes a violation of
+    pub violations: Vec<WesternElectricRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ControlChart {
+    pub kind: ChartKind,
+    pub title: String,
+    pub points: Vec<ChartPoint>,
+}
+
+/// Measurements taken together, e.g. consecutive parts of one lot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subgroup {
+    pub label: String,
+    pub values: Vec<f64>,
+}
+
+/// Count of nonconforming units in a sample, e.g. failed results of a
+/// batch or complaints per units shipped in a month.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sample {
+    pub label: String,
+    pub nonconforming: u64,
+    pub size: u64,
+}
+
+/// Point before rule evaluation: value, center line and sigma.
+struct Plot {
+    label: String,
+    value: f64,
+    center: f64,
+    sigma: f64,
+}
+
+impl ControlChart {
+    /// Chart with limits at three sigma, bounded by `range`, and the rules
+    /// evaluated on each point's distance from its center line.
+    fn new(kind: ChartKind, title: &str, plots: Vec<Plot>, range: (f64, f64)) -> Self {
+        let zones: Vec<f64> = plots
+            .iter()
+            .map(|plot| match plot.value - plot.center {
+                delta if plot.sigma > 0.0 => delta / plot.sigma,
+                delta if delta == 0.0 => 0.0,
+                delta => delta.signum() * f64::INFINITY,
+            })
+            .collect();
+        let points = plots
+            .into_iter()
+            .enumerate()
+            .map(|(index, plot)| ChartPoint {
+                violations: violations_at(&zones, index),
+                lcl: (plot.center - 3.0 * plot.sigma).max(range.0),
+                ucl: (plot.center + 3.0 * plot.sigma).min(range.1),
+                label: plot.label,
+                value: plot.value,
+                center: plot.center,
+            })
+            .collect();
+        Self { kind, title: title.to_string(), points }
+    }
+
+    pub fn in_control(&self) -> bool {
+        self.points.iter().all(|point| point.violations.is_empty())
+    }
+
+    /// Every violation, as (point, rule).
+    pub fn violations(&self) -> impl Iterator<Item = (&ChartPoint, WesternElectricRule)> {
+        self.points.iter().flat_map(|point| point.violations.iter().map(move |rule| (point, *rule)))
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("label,value,center,lcl,ucl,violations\n");
+        for point in &self.points {
+            let rules = point.violations.iter().map(|rule| rule.as_str()).collect::<Vec<_>>().join(";");
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{},{}",
+                csv_field(&point.label),
+                point.value,
+                point.center,
+                point.lcl,
+                point.ucl,
+                rules
+            );
+        }
+        out
+    }
+
+    /// Line chart with center line, control limits and violations in red.
+    pub fn to_svg(&self) -> String {
+        let (width, height, pad) = (720.0_f64, 260.0_f64, 24.0_f64);
+        let values = self.points.iter().flat_map(|p| [p.value, p.lcl, p.ucl]);
+        let (low, high) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| (lo.min(v), hi.max(v)));
+        let span = if high > low { high - low } else { 1.0 };
+        let x =
+            |index: usize| pad + index as f64 * (width - 2.0 * pad) / self.points.len().saturating_sub(1).max(1) as f64;
+        let y = |value: f64| pad + (high - value) / span * (height - 2.0 * pad);
+
+        let mut out = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {width} {height}\" role=\"img\" aria-label=\"{label}\" \
+             font-family=\"Helvetica, Arial, sans-serif\" font-size=\"9\">\n<text x=\"{pad}\" y=\"14\">{label}</text>\n",
+            label = escape(&self.title),
+        );
+        let lines: [(&str, fn(&ChartPoint) -> f64, &str); 3] =
+            [("UCL", |p| p.ucl, "4 3"), ("CL", |p| p.center, "none"), ("LCL", |p| p.lcl, "4 3")];
+        for (name, line, dash) in lines {
+            let path = self
+                .points
+                .iter()
+                .enumerate()
+                .map(|(index, point)| format!("{:.1},{:.1}", x(index), y(line(point))))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let _ = writeln!(
+                out,
+                "<polyline points=\"{}\" fill=\"none\" stroke=\"#555\" stroke-dasharray=\"{}\"><title>{}</title></polyline>",
+                path, dash, name
+            );
+        }
+        let path = self
+            .points
+            .iter()
+            .enumerate()
+            .map(|(index, point)| format!("{:.1},{:.1}", x(index), y(point.value)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let _ = writeln!(out, "<polyline points=\"{}\" fill=\"none\" stroke=\"#1f4e79\"/>", path);
+        for (index, point) in self.points.iter().enumerate() {
+            let fill = if point.violations.is_empty() { "#1f4e79" } else { "#c00000" };
+            let _ = writeln!(
+                out,
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"{}\"><title>{}: {}</title></circle>",
+                x(index),
+                y(point.value),
+                fill,
+                escape(&point.label),
+                point.value
+            );
+        }
+        out.push_str("</svg>\n");
+        out
+    }
+
+    /// Write `<name>.csv` and `<name>.svg` into `directory`.
+    pub fn export(&self, directory: &Path) -> Result<Vec<PathBuf>> {
+        fs::create_dir_all(directory)
+            .map_err(|e| QmsError::FileSystem { path: directory.display().to_string(), message: e.to_string() })?;
+        let name: String = format!("{}_{}", self.title, self.kind.as_str())
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+            .collect();
+        [("csv", self.to_csv()), ("svg", self.to_svg())]
+            .into_iter()
+            .map(|(extension, content)| {
+                let path = directory.join(format!("{}.{}", name, extension));
+                fs::write(&path, content)
+                    .map_err(|e| QmsError::FileSystem { path: path.display().to_string(), message: e.to_string() })?;
+                Ok(path)
+            })
+            .collect()
+    }
+}
+
+/// Rules violated by the run of points ending at `index`.
+fn violations_at(zones: &[f64], index: usize) -> Vec<WesternElectricRule> {
+    let window = |size: usize| &zones[(index + 1).saturating_sub(size)..=index];
+    // `count` of the last `size` points, including the last, beyond `limit` on its side
+    let beyond = |size: usize, count: usize, limit: f64| {
+        let last = zones[index];
+        last.abs() > limit
+            && window(size).len() == size
+            && window(size).iter().filter(|z| z.abs() > limit && z.signum() == last.signum()).count() >= count
+    };
+    let mut violations = Vec::new();
+    if zones[index].abs() > 3.0 {
+        violations.push(WesternElectricRule::BeyondThreeSigma);
+    }
+    if beyond(3, 2, 2.0) {
+        violations.push(WesternElectricRule::TwoOfThreeBeyondTwoSigma);
+    }
+    if beyond(5, 4, 1.0) {
+        violations.push(WesternElectricRule::FourOfFiveBeyondOneSigma);
+    }
+    let run = window(8);
+    if run.len() == 8 && (run.iter().all(|z| *z > 0.0) || run.iter().all(|z| *z < 0.0)) {
+        violations.push(WesternElectricRule::EightOnOneSide);
+    }
+    violations
+}
+
+/// X-bar and R charts of equally sized subgroups of 2 to 10 measurements.
+pub fn xbar_r_charts(title: &str, subgroups: &[Subgroup]) -> Result<(ControlChart, ControlChart)> {
+    let size = subgroups.first().map_or(0, |subgroup| subgroup.values.len());
+    if subgroups.len() < 2 || subgroups.iter().any(|subgroup| subgroup.values.len() != size) {
+        return Err(QmsError::Validation {
+            field: "subgroups".to_string(),
+            message: "At least two subgroups of equal size are required".to_string(),
+        });
+    }
+    let Some(&(a2, _, d4)) = size.checked_sub(2).and_then(|index| XBAR_R_CONSTANTS.get(index)) else {
+        return Err(QmsError::Validation {
+            field: "subgroup_size".to_string(),
+            message: format!("Subgroup size {} is outside 2 to 10", size),
+        });
+    };
+    let means: Vec<f64> = subgroups.iter().map(|s| s.values.iter().sum::<f64>() / size as f64).collect();
+    let ranges: Vec<f64> = subgroups
+        .iter()
+        .map(|s| {
+            s.values.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+                - s.values.iter().copied().fold(f64::INFINITY, f64::min)
+        })
+        .collect();
+    let grand_mean = means.iter().sum::<f64>() / means.len() as f64;
+    let mean_range = ranges.iter().sum::<f64>() / ranges.len() as f64;
+
+    let plots = |values: &[f64], center: f64, sigma: f64| {
+        subgroups
+            .iter()
+            .zip(values)
+            .map(|(subgroup, value)| Plot { label: subgroup.label.clone(), value: *value, center, sigma })
+            .collect::<Vec<_>>()
+    };
+    let x_bar = ControlChart::new(
+        ChartKind::XBar,
+        title,
+        plots(&means, grand_mean, a2 * mean_range / 3.0),
+        (f64::NEG_INFINITY, f64::INFINITY),
+    );
+    // D3 is the lower limit clipped at zero, so one sigma is (D4 - 1) R-bar / 3
+    let range = ControlChart::new(
+        ChartKind::Range,
+        title,
+        plots(&ranges, mean_range, (d4 - 1.0) * mean_range / 3.0),
+        (0.0, f64::INFINITY),
+    );
+    Ok((x_bar, range))
+}
+
+/// p-chart of the fraction nonconforming, with limits per sample size.
+pub fn p_chart(title: &str, samples: &[Sample]) -> Result<ControlChart> {
+    if samples.len() < 2 || samples.iter().any(|sample| sample.size == 0 || sample.nonconforming > sample.size) {
+        return Err(QmsError::Validation {
+            field: "samples".to_string(),
+            message: "At least two samples, each with a size of at least its nonconforming count, are required"
+                .to_string(),
+        });
+    }
+    let total: u64 = samples.iter().map(|sample| sample.size).sum();
+    let center = samples.iter().map(|sample| sample.nonconforming).sum::<u64>() as f64 / total as f64;
+    let plots = samples
+        .iter()
+        .map(|sample| Plot {
+            label: sample.label.clone(),
+            value: sample.nonconforming as f64 / sample.size as f64,
+            center,
+            sigma: (center * (1.0 - center) / sample.size as f64).sqrt(),
+        })
+        .collect();
+    Ok(ControlChart::new(ChartKind::P, title, plots, (0.0, 1.0)))
+}
+
+/// A rule violation raised once per chart, point and rule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpcAlert {
+    pub id: String,
+    pub chart: String,
+    pub kind: ChartKind,
+    pub point_label: String,
+    pub rule: WesternElectricRule,
+    pub value: f64,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Chart data from the QMS records and alerting on violations.
+pub struct SpcService<'a> {
+    db: &'a Database,
+    notifier: Option<(&'a dyn Notifier, Vec<String>)>,
+}
+
+impl<'a> SpcService<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, notifier: None }
+    }
+
+    /// Email new alerts to `recipients`.
+    pub fn with_notifier(mut self, notifier: &'a dyn Notifier, recipients: Vec<String>) -> Self {
+        self.notifier = Some((notifier, recipients));
+        self
+    }
+
+    /// Numeric results of `test` ingested since `since`, in consecutive
+    /// subgroups of `subgroup_size`; an incomplete last subgroup is left out.
+    pub fn inspection_subgroups(
+        &self,
+        test: &str,
+        subgroup_size: usize,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Subgroup>> {
+        let measurements: Vec<(String, f64)> = self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT b.ingested_at, r.value FROM test_results r JOIN test_result_batches b ON b.id = r.batch_id
+                 WHERE r.test = ?1 AND b.ingested_at >= ?2 ORDER BY b.ingested_at, r.rowid",
+            )?;
+            let rows = stmt
+                .query_map(params![test, since.to_rfc3339()], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rows
+                .into_iter()
+                .filter_map(|(at, value)| Some((at.chars().take(10).collect(), value?.trim().parse().ok()?)))
+                .collect())
+        })?;
+        Ok(measurements
+            .chunks_exact(subgroup_size.max(1))
+            .enumerate()
+            .map(|(index, chunk)| Subgroup {
+                label: format!("#{} {}", index + 1, chunk[0].0),
+                values: chunk.iter().map(|(_, value)| *value).collect(),
+            })
+            .collect())
+    }
+
+    /// Failed results of `test` per ingested batch since `since`.
+    pub fn inspection_samples(&self, test: &str, since: DateTime<Utc>) -> Result<Vec<Sample>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT b.id, b.ingested_at, SUM(CASE WHEN r.passed THEN 0 ELSE 1 END), COUNT(*)
+                 FROM test_results r JOIN test_result_batches b ON b.id = r.batch_id
+                 WHERE r.test = ?1 AND b.ingested_at >= ?2
+                 GROUP BY b.id ORDER BY b.ingested_at, b.id",
+            )?;
+            let samples = stmt
+                .query_map(params![test, since.to_rfc3339()], |row| {
+                    let id: String = row.get(0)?;
+                    let at: String = row.get(1)?;
+                    Ok(Sample {
+                        label: format!("{} {}", &at[..at.len().min(10)], &id[..id.len().min(8)]),
+                        nonconforming: row.get::<_, i64>(2)? as u64,
+                        size: row.get::<_, i64>(3)? as u64,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(samples)
+        })
+    }
+
+    /// Complaints received per month against the units distributed that
+    /// month, keyed `YYYY-MM`.
+    pub fn complaint_samples(&self, units_by_month: &BTreeMap<String, u64>) -> Result<Vec<Sample>> {
+        units_by_month
+            .iter()
+            .map(|(month, units)| {
+                if chrono::NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").is_err() {
+                    return Err(QmsError::Validation {
+                        field: "month".to_string(),
+                        message: format!("'{}' is not a YYYY-MM month", month),
+                    });
+                }
+                let complaints: i64 = self.db.with_connection(|conn| {
+                    Ok(conn.query_row(
+                        "SELECT COUNT(*) FROM quality_intake
+                         WHERE source = 'complaint' AND substr(reported_at, 1, 7) = ?1",
+                        params![month],
+                        |row| row.get(0),
+                    )?)
+                })?;
+                Ok(Sample { label: month.clone(), nonconforming: complaints as u64, size: *units })
+            })
+            .collect()
+    }
+
+    /// Store the chart's violations not raised before, audit them and
+    /// email them to the quality managers; returns the new alerts.
+    pub fn raise_alerts(&self, chart: &ControlChart, now: DateTime<Utc>) -> Result<Vec<SpcAlert>> {
+        let audit = AuditManager::new(self.db.clone());
+        let alerts = self.db.unit_of_work(|uow| {
+            let mut alerts = Vec::new();
+            for (point, rule) in chart.violations() {
+                let alert = SpcAlert {
+                    id: Uuid::new_v4().to_string(),
+                    chart: chart.title.clone(),
+                    kind: chart.kind,
+                    point_label: point.label.clone(),
+                    rule,
+                    value: point.value,
+                    created_at: now,
+                };
+                let inserted = uow.connection().execute(
+                    "INSERT OR IGNORE INTO spc_alerts (id, chart, kind, point_label, rule, value, center, lcl, ucl,
+                                                       created_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    params![
+                        alert.id,
+                        alert.chart,
+                        chart.kind.as_str(),
+                        alert.point_label,
+                        rule.as_str(),
+                        point.value,
+                        point.center,
+                        point.lcl,
+                        point.ucl,
+                        now.to_rfc3339(),
+                    ],
+                )?;
+                if inserted == 0 {
+                    continue;
+                }
+                audit.log_action_in(
+                    uow,
+                    SPC_USER,
+                    "spc_rule_violated",
+                    &format!("spc:{}:{}", chart.title, chart.kind.as_str()),
+                    "Failure",
+                    Some(format!("{}: {} ({})", point.label, rule.describe(), point.value)),
+                )?;
+                alerts.push(alert);
+            }
+            Ok(alerts)
+        })?;
+        if let Some((notifier, recipients)) = &self.notifier {
+            if !alerts.is_empty() && !recipients.is_empty() {
+                notifier.send(&alert_email(chart, &alerts, recipients.clone()))?;
+            }
+        }
+        Ok(alerts)
+    }
+}
+
+fn alert_email(chart: &ControlChart, alerts: &[SpcAlert], recipients: Vec<String>) -> EmailMessage {
+    let lines: Vec<String> = alerts
+        .iter()
+        .map(|alert| format!("- {}: {} (value {})", alert.point_label, alert.rule.describe(), alert.value))
+        .collect();
+    let body = format!(
+        "The {} chart of {} shows {} new out-of-control signal(s):\n\n{}\n\n\
+         Review the trend and decide whether a CAPA is needed.\n\n\
+         This message was sent automatically by QMSrs.",
+        chart.kind.as_str(),
+        chart.title,
+        alerts.len(),
+        lines.join("\n"),
+    );
+    EmailMessage::new(recipients, format!("QMS SPC: {} out of control", chart.title), body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::quality_intake::{IssueSource, QualityIntake};
+
+    fn subgroup(index: usize, values: &[f64]) -> Subgroup {
+        Subgroup { label: format!("#{}", index), values: values.to_vec() }
+    }
+
+    #[test]
+    fn test_xbar_r_limits_and_western_electric_rules() {
+        // Means alternate around 10.0 with a range of 0.4, then the process shifts
+        let shift = |i: usize| if i % 2 == 0 { 0.05 } else { -0.05 };
+        let mut subgroups: Vec<Subgroup> =
+            (0..20).map(|i| subgroup(i, &[10.0, 10.2, 9.8, 10.1, 9.9].map(|v| v + shift(i)))).collect();
+        subgroups.push(subgroup(20, &[10.4, 10.6, 10.2, 10.5, 10.3]));
+        let (x_bar, range) = xbar_r_charts("Seal strength", &subgroups).unwrap();
+        let point = &x_bar.points[0];
+        assert!((point.ucl - (point.center + 0.577 * 0.4)).abs() < 1e-9);
+        assert_eq!(range.points[0].lcl, 0.0);
+        assert_eq!(x_bar.points[20].violations, vec![WesternElectricRule::BeyondThreeSigma]);
+        assert!(x_bar.points[..20].iter().all(|point| point.violations.is_empty()));
+
+        let zones = [0.5, 2.5, 0.1, 2.2, -0.3, 1.5, 1.2, 1.1, 1.3];
+        assert_eq!(violations_at(&zones, 3), vec![WesternElectricRule::TwoOfThreeBeyondTwoSigma]);
+        assert_eq!(violations_at(&zones, 8), vec![WesternElectricRule::FourOfFiveBeyondOneSigma]);
+        assert_eq!(violations_at(&[0.2; 8], 7), vec![WesternElectricRule::EightOnOneSide]);
+        assert!(xbar_r_charts("x", &[subgroup(0, &[1.0, 2.0]), subgroup(1, &[1.0])]).is_err());
+
+        let csv = x_bar.to_csv();
+        assert!(csv.lines().nth(21).unwrap().ends_with(",beyond_three_sigma"));
+        assert_eq!(x_bar.to_svg().matches("fill=\"#c00000\"").count(), 1);
+    }
+
+    #[test]
+    fn test_complaint_rate_p_chart_raises_alerts_once() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let intake = QualityIntake::new(&db);
+        for _ in 0..9 {
+            intake.submit(IssueSource::Complaint, "Leaking bag", "Bag leaked at the seam", "support").unwrap();
+        }
+        let month = Utc::now().format("%Y-%m").to_string();
+        let units =
+            BTreeMap::from([("2020-01".to_string(), 1000), ("2020-02".to_string(), 1000), (month.clone(), 1000)]);
+        let service = SpcService::new(&db);
+        let samples = service.complaint_samples(&units).unwrap();
+        assert_eq!(samples.iter().map(|s| s.nonconforming).collect::<Vec<_>>(), vec![0, 0, 9]);
+
+        let chart = p_chart("Complaint rate", &samples).unwrap();
+        assert_eq!((chart.points[0].center, chart.points[0].lcl), (0.003, 0.0));
+        assert!(!chart.in_control());
+        let alerts = service.raise_alerts(&chart, Utc::now()).unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(
+            (alerts[0].point_label.as_str(), alerts[0].rule),
+            (month.as_str(), WesternElectricRule::BeyondThreeSigma)
+        );
+        assert!(service.raise_alerts(&chart, Utc::now()).unwrap().is_empty(), "raised once");
+        assert_eq!(db.get_audit_entries_for_resource("spc:Complaint rate:p").unwrap().len(), 1);
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let files = chart.export(dir.path()).unwrap();
+        assert!(files[0].ends_with("complaint_rate_p.csv") && files[1].ends_with("complaint_rate_p.svg"));
+    }
+}
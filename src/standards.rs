@@ -0,0 +1,703 @@
+//! # Standards - Register of Applicable Standards & Regulations
+//!
+//! Catalogs the standards and regulations the QMS and its products answer to
+//! (ISO 13485:2016, ISO 14971:2019, IEC 62304, 21 CFR 820, ...) down to clause
+//! level. Applicability is assessed per product with a documented rationale;
+//! a reassessment supersedes the previous one but both stay on file. Gaps
+//! found against an applicable standard are recorded and closed with their
+//! resolution. QMS procedures are linked to the clauses they satisfy through
+//! the record link table, so clause coverage appears in the traceability
+//! graph and a clause no procedure covers shows up as uncovered.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::links::{LinkRepo, LinkType, RecordLink, RecordRef};
+
+/// Record type of clauses in record links.
+pub const CLAUSE_RECORD_TYPE: &str = "standard_clause";
+
+/// Record type of QMS procedure documents in record links.
+pub const DOCUMENT_RECORD_TYPE: &str = "document";
+
+/// Whether an entry is a consensus standard or a regulation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StandardKind {
+    Standard,
+    Regulation,
+}
+
+impl StandardKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StandardKind::Standard => "standard",
+            StandardKind::Regulation => "regulation",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [StandardKind::Standard, StandardKind::Regulation].into_iter().find(|item| item.as_str() == value)
+    }
+}
+
+/// A standard or regulation in a specific edition.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Standard {
+    pub id: String,
+    /// e.g. "ISO 14971" or "21 CFR 820"
+    pub designation: String,
+    /// e.g. "2019"; empty for regulations cited without an edition
+    pub edition: String,
+    pub title: String,
+    pub kind: StandardKind,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Standard {
+    /// Citation such as `ISO 14971:2019`.
+    pub fn reference(&self) -> String {
+        if self.edition.is_empty() {
+            self.designation.clone()
+        } else {
+            format!("{}:{}", self.designation, self.edition)
+        }
+    }
+}
+
+/// A clause of a standard.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StandardClause {
+    pub id: String,
+    pub standard_id: String,
+    /// Clause number, e.g. "7.1"
+    pub clause: String,
+    pub title: String,
+}
+
+impl StandardClause {
+    pub fn record_ref(&self) -> RecordRef {
+        RecordRef::new(CLAUSE_RECORD_TYPE, self.id.clone())
+    }
+}
+
+/// Assessment of whether a standard applies to a product.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Applicability {
+    pub id: String,
+    pub standard_id: String,
+    pub product: String,
+    pub applicable: bool,
+    pub rationale: String,
+    pub assessed_by: String,
+    pub assessed_at: DateTime<Utc>,
+}
+
+/// A gap between the QMS or a product and an applicable standard.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GapAssessment {
+    pub id: String,
+    pub standard_id: String,
+    /// Clause the gap is against; `None` for gaps against the whole standard
+    pub clause_id: Option<String>,
+    pub product: String,
+    pub description: String,
+    /// CAPA or change record addressing the gap
+    pub action_reference: Option<String>,
+    pub raised_by: String,
+    pub raised_at: DateTime<Utc>,
+    pub resolution: Option<String>,
+    pub closed_by: Option<String>,
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+impl GapAssessment {
+    pub fn is_open(&self) -> bool {
+        self.closed_at.is_none()
+    }
+}
+
+/// A clause with the procedures linked to it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClauseCoverage {
+    pub clause: StandardClause,
+    /// Ids of the documents that satisfy the clause
+    pub procedures: Vec<String>,
+}
+
+/// Persistence for the standards register.
+pub struct StandardsRepo<'a> {
+    db: &'a Database,
+    audit: AuditManager,
+}
+
+impl<'a> StandardsRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, audit: AuditManager::new(db.clone()) }
+    }
+
+    /// Catalog a standard or regulation; a designation is listed once per edition.
+    pub fn add_standard(
+        &self,
+        designation: &str,
+        edition: &str,
+        title: &str,
+        kind: StandardKind,
+        created_by: &str,
+    ) -> Result<Standard> {
+        require(&[("designation", designation), ("title", title)])?;
+        let standard = Standard {
+            id: Uuid::new_v4().to_string(),
+            designation: designation.trim().to_string(),
+            edition: edition.trim().to_string(),
+            title: title.trim().to_string(),
+            kind,
+            created_by: created_by.to_string(),
+            created_at: Utc::now(),
+        };
+        self.db.unit_of_work(|uow| {
+            let existing: Option<String> = uow
+                .connection()
+                .query_row(
+                    "SELECT id FROM standards WHERE designation = ?1 AND edition = ?2",
+                    params![standard.designation, standard.edition],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if existing.is_some() {
+                return Err(QmsError::Validation {
+                    field: "edition".to_string(),
+                    message: format!("{} is already in the register", standard.reference()),
+                });
+            }
+            uow.connection().execute(
+                "INSERT INTO standards (id, designation, edition, title, kind, created_by, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    standard.id,
+                    standard.designation,
+                    standard.edition,
+                    standard.title,
+                    kind.as_str(),
+                    created_by,
+                    standard.created_at.to_rfc3339(),
+                ],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                created_by,
+                "standard_registered",
+                &resource(&standard.id),
+                "Success",
+                Some(format!("{} {} ({})", standard.reference(), standard.title, kind.as_str())),
+            )
+        })?;
+        Ok(standard)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Standard> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM standards WHERE id = ?1", STANDARD_COLUMNS),
+                        params![id],
+                        row_to_standard,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "standard".to_string(), id: id.to_string() })
+    }
+
+    /// Look a standard up by its citation, e.g. `ISO 13485:2016`.
+    pub fn find_by_reference(&self, reference: &str) -> Result<Standard> {
+        self.list()?
+            .into_iter()
+            .find(|standard| standard.reference() == reference.trim())
+            .ok_or_else(|| QmsError::NotFound { resource: "standard".to_string(), id: reference.to_string() })
+    }
+
+    /// Every standard in the register, by designation and edition.
+    pub fn list(&self) -> Result<Vec<Standard>> {
+        self.db.with_connection(|conn| {
+            let mut stmt =
+                conn.prepare(&format!("SELECT {} FROM standards ORDER BY designation, edition", STANDARD_COLUMNS))?;
+            let standards = stmt.query_map([], row_to_standard)?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(standards)
+        })
+    }
+
+    /// Add a clause to a standard.
+    pub fn add_clause(&self, standard_id: &str, clause: &str, title: &str, created_by: &str) -> Result<StandardClause> {
+        require(&[("clause", clause), ("title", title)])?;
+        let standard = self.get(standard_id)?;
+        let clause = StandardClause {
+            id: Uuid::new_v4().to_string(),
+            standard_id: standard_id.to_string(),
+            clause: clause.trim().to_string(),
+            title: title.trim().to_string(),
+        };
+        self.db.unit_of_work(|uow| {
+            let inserted = uow.connection().execute(
+                "INSERT OR IGNORE INTO standard_clauses (id, standard_id, clause, title) VALUES (?1, ?2, ?3, ?4)",
+                params![clause.id, standard_id, clause.clause, clause.title],
+            )?;
+            if inserted == 0 {
+                return Err(QmsError::Validation {
+                    field: "clause".to_string(),
+                    message: format!("{} clause {} is already registered", standard.reference(), clause.clause),
+                });
+            }
+            self.audit.log_action_in(
+                uow,
+                created_by,
+                "standard_clause_added",
+                &resource(standard_id),
+                "Success",
+                Some(format!("{} {} {}", standard.reference(), clause.clause, clause.title)),
+            )
+        })?;
+        Ok(clause)
+    }
+
+    /// Clauses of a standard, ordered by clause number.
+    pub fn clauses(&self, standard_id: &str) -> Result<Vec<StandardClause>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, standard_id, clause, title FROM standard_clauses WHERE standard_id = ?1 ORDER BY clause",
+            )?;
+            let clauses = stmt.query_map(params![standard_id], row_to_clause)?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(clauses)
+        })
+    }
+
+    /// Record whether a standard applies to `product`, superseding any earlier assessment.
+    pub fn assess_applicability(
+        &self,
+        standard_id: &str,
+        product: &str,
+        applicable: bool,
+        rationale: &str,
+        assessed_by: &str,
+    ) -> Result<Applicability> {
+        require(&[("product", product), ("rationale", rationale)])?;
+        let standard = self.get(standard_id)?;
+        let assessment = Applicability {
+            id: Uuid::new_v4().to_string(),
+            standard_id: standard_id.to_string(),
+            product: product.trim().to_string(),
+            applicable,
+            rationale: rationale.to_string(),
+            assessed_by: assessed_by.to_string(),
+            assessed_at: Utc::now(),
+        };
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "INSERT INTO standard_applicability
+                     (id, standard_id, product, applicable, rationale, assessed_by, assessed_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    assessment.id,
+                    standard_id,
+                    assessment.product,
+                    applicable,
+                    rationale,
+                    assessed_by,
+                    assessment.assessed_at.to_rfc3339(),
+                ],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                assessed_by,
+                "standard_applicability_assessed",
+                &resource(standard_id),
+                "Success",
+                Some(format!(
+                    "{} {} to {}: {}",
+                    standard.reference(),
+                    if applicable { "applies" } else { "does not apply" },
+                    assessment.product,
+                    rationale
+                )),
+            )
+        })?;
+        Ok(assessment)
+    }
+
+    /// Current (latest) assessment of a standard for `product`.
+    pub fn applicability(&self, standard_id: &str, product: &str) -> Result<Option<Applicability>> {
+        self.db.with_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    &format!(
+                        "SELECT {} FROM standard_applicability WHERE standard_id = ?1 AND product = ?2
+                         ORDER BY assessed_at DESC, rowid DESC LIMIT 1",
+                        APPLICABILITY_COLUMNS
+                    ),
+                    params![standard_id, product],
+                    row_to_applicability,
+                )
+                .optional()?)
+        })
+    }
+
+    /// Standards currently assessed as applicable to `product`.
+    pub fn applicable_to(&self, product: &str) -> Result<Vec<Standard>> {
+        let mut standards = Vec::new();
+        for standard in self.list()? {
+            if self.applicability(&standard.id, product)?.map_or(false, |a| a.applicable) {
+                standards.push(standard);
+            }
+        }
+        Ok(standards)
+    }
+
+    /// Record a gap against a standard that applies to `product`.
+    pub fn record_gap(
+        &self,
+        standard_id: &str,
+        clause_id: Option<&str>,
+        product: &str,
+        description: &str,
+        action_reference: Option<&str>,
+        raised_by: &str,
+    ) -> Result<GapAssessment> {
+        require(&[("description", description)])?;
+        let standard = self.get(standard_id)?;
+        if !self.applicability(standard_id, product)?.map_or(false, |a| a.applicable) {
+            return Err(QmsError::Validation {
+                field: "product".to_string(),
+                message: format!("{} is not assessed as applicable to {}", standard.reference(), product),
+            });
+        }
+        if let Some(clause_id) = clause_id {
+            if !self.clauses(standard_id)?.iter().any(|c| c.id == clause_id) {
+                return Err(QmsError::NotFound { resource: "standard_clause".to_string(), id: clause_id.to_string() });
+            }
+        }
+        let gap = GapAssessment {
+            id: Uuid::new_v4().to_string(),
+            standard_id: standard_id.to_string(),
+            clause_id: clause_id.map(str::to_string),
+            product: product.to_string(),
+            description: description.to_string(),
+            action_reference: action_reference.map(str::to_string),
+            raised_by: raised_by.to_string(),
+            raised_at: Utc::now(),
+            resolution: None,
+            closed_by: None,
+            closed_at: None,
+        };
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "INSERT INTO standard_gaps
+                     (id, standard_id, clause_id, product, description, action_reference, raised_by, raised_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    gap.id,
+                    standard_id,
+                    gap.clause_id,
+                    gap.product,
+                    description,
+                    gap.action_reference,
+                    raised_by,
+                    gap.raised_at.to_rfc3339(),
+                ],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                raised_by,
+                "standard_gap_recorded",
+                &resource(standard_id),
+                "Warning",
+                Some(format!("Gap {} for {}: {}", gap.id, product, description)),
+            )
+        })?;
+        Ok(gap)
+    }
+
+    /// Close a gap with its resolution.
+    pub fn close_gap(&self, gap_id: &str, resolution: &str, closed_by: &str) -> Result<GapAssessment> {
+        require(&[("resolution", resolution)])?;
+        let mut gap = self.gap(gap_id)?;
+        if !gap.is_open() {
+            return Err(QmsError::Validation {
+                field: "gap_id".to_string(),
+                message: format!("Gap {} is already closed", gap_id),
+            });
+        }
+        let now = Utc::now();
+        gap.resolution = Some(resolution.to_string());
+        gap.closed_by = Some(closed_by.to_string());
+        gap.closed_at = Some(now);
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE standard_gaps SET resolution = ?2, closed_by = ?3, closed_at = ?4 WHERE id = ?1",
+                params![gap_id, resolution, closed_by, now.to_rfc3339()],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                closed_by,
+                "standard_gap_closed",
+                &resource(&gap.standard_id),
+                "Success",
+                Some(format!("Gap {}: {}", gap_id, resolution)),
+            )
+        })?;
+        Ok(gap)
+    }
+
+    /// Open gaps, oldest first.
+    pub fn open_gaps(&self) -> Result<Vec<GapAssessment>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM standard_gaps WHERE closed_at IS NULL ORDER BY raised_at, id",
+                GAP_COLUMNS
+            ))?;
+            let gaps = stmt.query_map([], row_to_gap)?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(gaps)
+        })
+    }
+
+    /// Link a QMS procedure document to a clause it satisfies.
+    pub fn link_procedure(&self, document_id: &str, clause_id: &str, linked_by: &str) -> Result<RecordLink> {
+        let document_exists: bool = self.db.with_connection(|conn| {
+            Ok(conn.query_row(
+                "SELECT EXISTS (SELECT 1 FROM documents WHERE id = ?1 AND deleted_at IS NULL)",
+                params![document_id],
+                |row| row.get(0),
+            )?)
+        })?;
+        if !document_exists {
+            return Err(QmsError::NotFound { resource: "document".to_string(), id: document_id.to_string() });
+        }
+        let clause = self.clause(clause_id)?;
+        LinkRepo::new(self.db).link(
+            &RecordRef::new(DOCUMENT_RECORD_TYPE, document_id),
+            &clause.record_ref(),
+            LinkType::Satisfies,
+            linked_by,
+        )
+    }
+
+    /// Clauses of a standard with the procedures satisfying each; clauses
+    /// with no procedure are coverage gaps.
+    pub fn coverage(&self, standard_id: &str) -> Result<Vec<ClauseCoverage>> {
+        let links = LinkRepo::new(self.db);
+        self.clauses(standard_id)?
+            .into_iter()
+            .map(|clause| {
+                let procedures = links
+                    .links_of(&clause.record_ref())?
+                    .into_iter()
+                    .filter(|link| {
+                        link.link_type == LinkType::Satisfies && link.source.record_type == DOCUMENT_RECORD_TYPE
+                    })
+                    .map(|link| link.source.record_id)
+                    .collect();
+                Ok(ClauseCoverage { clause, procedures })
+            })
+            .collect()
+    }
+
+    fn clause(&self, id: &str) -> Result<StandardClause> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        "SELECT id, standard_id, clause, title FROM standard_clauses WHERE id = ?1",
+                        params![id],
+                        row_to_clause,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "standard_clause".to_string(), id: id.to_string() })
+    }
+
+    fn gap(&self, id: &str) -> Result<GapAssessment> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM standard_gaps WHERE id = ?1", GAP_COLUMNS),
+                        params![id],
+                        row_to_gap,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "standard_gap".to_string(), id: id.to_string() })
+    }
+}
+
+fn require(fields: &[(&str, &str)]) -> Result<()> {
+    match fields.iter().find(|(_, value)| value.trim().is_empty()) {
+        Some((field, _)) => {
+            Err(QmsError::Validation { field: field.to_string(), message: format!("{} is required", field) })
+        }
+        None => Ok(()),
+    }
+}
+
+fn resource(standard_id: &str) -> String {
+    format!("standard:{}", standard_id)
+}
+
+const STANDARD_COLUMNS: &str = "id, designation, edition, title, kind, created_by, created_at";
+
+const APPLICABILITY_COLUMNS: &str = "id, standard_id, product, applicable, rationale, assessed_by, assessed_at";
+
+const GAP_COLUMNS: &str = "id, standard_id, clause_id, product, description, action_reference, raised_by, raised_at,
+     resolution, closed_by, closed_at";
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn parse_time(row: &Row, index: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| conversion_error(index, e.to_string()))
+        })
+        .transpose()
+}
+
+fn required_time(row: &Row, index: usize) -> rusqlite::Result<DateTime<Utc>> {
+    parse_time(row, index)?.ok_or_else(|| conversion_error(index, "timestamp is missing".to_string()))
+}
+
+fn row_to_standard(row: &Row) -> rusqlite::Result<Standard> {
+    let kind: String = row.get(4)?;
+    Ok(Standard {
+        id: row.get(0)?,
+        designation: row.get(1)?,
+        edition: row.get(2)?,
+        title: row.get(3)?,
+        kind: StandardKind::parse(&kind).ok_or_else(|| conversion_error(4, format!("unknown kind {}", kind)))?,
+        created_by: row.get(5)?,
+        created_at: required_time(row, 6)?,
+    })
+}
+
+fn row_to_clause(row: &Row) -> rusqlite::Result<StandardClause> {
+    Ok(StandardClause { id: row.get(0)?, standard_id: row.get(1)?, clause: row.get(2)?, title: row.get(3)? })
+}
+
+fn row_to_applicability(row: &Row) -> rusqlite::Result<Applicability> {
+    Ok(Applicability {
+        id: row.get(0)?,
+        standard_id: row.get(1)?,
+        product: row.get(2)?,
+        applicable: row.get(3)?,
+        rationale: row.get(4)?,
+        assessed_by: row.get(5)?,
+        assessed_at: required_time(row, 6)?,
+    })
+}
+
+fn row_to_gap(row: &Row) -> rusqlite::Result<GapAssessment> {
+    Ok(GapAssessment {
+        id: row.get(0)?,
+        standard_id: row.get(1)?,
+        clause_id: row.get(2)?,
+        product: row.get(3)?,
+        description: row.get(4)?,
+        action_reference: row.get(5)?,
+        raised_by: row.get(6)?,
+        raised_at: required_time(row, 7)?,
+        resolution: row.get(8)?,
+        closed_by: row.get(9)?,
+        closed_at: parse_time(row, 10)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+
+    #[test]
+    fn test_register_assess_gaps_and_clause_coverage() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["qa"]);
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO documents
+                     (id, document_number, title, version, status, document_type, content_hash, created_by)
+                 VALUES ('sop-7', 'SOP-007', 'Risk Management Procedure', '2.0', 'Effective', 'SOP', 'h', 'qa')",
+                [],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        let repo = StandardsRepo::new(&db);
+
+        let iso14971 = repo
+            .add_standard(
+                "ISO 14971",
+                "2019",
+                "Application of risk management to medical devices",
+                StandardKind::Standard,
+                "qa",
+            )
+            .unwrap();
+        assert_eq!(iso14971.reference(), "ISO 14971:2019");
+        assert!(repo.add_standard("ISO 14971", "2019", "Duplicate", StandardKind::Standard, "qa").is_err());
+        let iec62304 = repo
+            .add_standard("IEC 62304", "2006+A1:2015", "Software life cycle processes", StandardKind::Standard, "qa")
+            .unwrap();
+        let plan = repo.add_clause(&iso14971.id, "4.4", "Risk management plan", "qa").unwrap();
+        let review = repo.add_clause(&iso14971.id, "9", "Risk management review", "qa").unwrap();
+        assert!(repo.add_clause(&iso14971.id, "4.4", "Again", "qa").is_err());
+
+        repo.assess_applicability(&iso14971.id, "InfuMax IMX-200", true, "Class IIb active device", "qa").unwrap();
+        repo.assess_applicability(&iec62304.id, "InfuMax IMX-200", true, "Pump firmware", "qa").unwrap();
+        repo.assess_applicability(&iec62304.id, "InfuMax IMX-200", false, "Firmware supplied as OTS component", "qa")
+            .unwrap();
+        let applicable: Vec<String> =
+            repo.applicable_to("InfuMax IMX-200").unwrap().into_iter().map(|s| s.reference()).collect();
+        assert_eq!(applicable, vec!["ISO 14971:2019"], "latest assessment wins");
+
+        assert!(repo.record_gap(&iec62304.id, None, "InfuMax IMX-200", "No SOUP list", None, "qa").is_err());
+        let gap = repo
+            .record_gap(
+                &iso14971.id,
+                Some(&review.id),
+                "InfuMax IMX-200",
+                "No review before release",
+                Some("CAPA-0031"),
+                "qa",
+            )
+            .unwrap();
+        assert_eq!(repo.open_gaps().unwrap(), vec![gap.clone()]);
+        let closed = repo.close_gap(&gap.id, "Review added to release checklist", "qa").unwrap();
+        assert!(!closed.is_open());
+        assert!(repo.close_gap(&gap.id, "Again", "qa").is_err());
+        assert!(repo.open_gaps().unwrap().is_empty());
+
+        assert!(matches!(repo.link_procedure("missing", &plan.id, "qa"), Err(QmsError::NotFound { .. })));
+        let link = repo.link_procedure("sop-7", &plan.id, "qa").unwrap();
+        assert_eq!(link.link_type, LinkType::Satisfies);
+        let coverage = repo.coverage(&iso14971.id).unwrap();
+        assert_eq!(coverage[0].clause.clause, "4.4");
+        assert_eq!(coverage[0].procedures, vec!["sop-7".to_string()]);
+        assert!(coverage[1].procedures.is_empty(), "clause 9 is not yet covered");
+
+        let audit = db.get_audit_entries_for_resource(&format!("standard:{}", iso14971.id)).unwrap();
+        assert!(audit.iter().any(|e| e.action == "standard_gap_closed"));
+    }
+}
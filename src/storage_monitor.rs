@@ -0,0 +1,479 @@
+//! # Storage Monitor - Disk Space & Database Growth
+//!
+//! An audit entry that cannot be written because the disk is full is a
+//! compliance incident, so storage is watched before it runs out. Every run
+//! of the storage monitor job measures the database (including its WAL), the
+//! log directory and the free space of the data directory, projects growth
+//! over the last `projection_window_days` and rates the result against the
+//! `[storage]` thresholds. Samples are kept in `storage_samples`; a level
+//! change is audited, a worsening level is emailed to the quality managers
+//! and the latest sample is shown on the dashboard.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::config::{Config, StorageConfig};
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::notification::{EmailMessage, Notifier};
+
+/// User recorded for storage level changes.
+pub const STORAGE_MONITOR_USER: &str = "system:storage_monitor";
+
+const RESOURCE: &str = "storage";
+const MB: u64 = 1024 * 1024;
+
+/// Rating of a sample against the `[storage]` thresholds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageLevel {
+    Ok,
+    /// Free space below `warning_free_mb` or projected to run out soon
+    Warning,
+    /// Free space below `critical_free_mb`
+    Critical,
+}
+
+impl StorageLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StorageLevel::Ok => "ok",
+            StorageLevel::Warning => "warning",
+            StorageLevel::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for StorageLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for StorageLevel {
+    type Err = QmsError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        [StorageLevel::Ok, StorageLevel::Warning, StorageLevel::Critical]
+            .into_iter()
+            .find(|level| level.as_str() == s)
+            .ok_or_else(|| QmsError::Validation {
+                field: "level".to_string(),
+                message: format!("Unknown storage level: {}", s),
+            })
+    }
+}
+
+/// Sizes measured at one point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageUsage {
+    pub database_bytes: u64,
+    pub log_bytes: u64,
+    pub free_bytes: u64,
+}
+
+/// A recorded measurement with its growth projection and rating.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageSample {
+    pub id: Uuid,
+    pub sampled_at: DateTime<Utc>,
+    pub database_bytes: u64,
+    pub log_bytes: u64,
+    pub free_bytes: u64,
+    /// Combined database and log growth over the projection window
+    pub growth_bytes_per_day: Option<f64>,
+    /// Days until the free space is used up at the projected growth
+    pub days_until_full: Option<f64>,
+    pub level: StorageLevel,
+}
+
+impl StorageSample {
+    pub fn free_mb(&self) -> u64 {
+        self.free_bytes / MB
+    }
+
+    /// One-line description, e.g. `812 MB free; database 3.2 GB, logs 120 MB; full in ~9 days`.
+    pub fn describe(&self) -> String {
+        let mut text = format!(
+            "{} free; database {}, logs {}",
+            format_bytes(self.free_bytes),
+            format_bytes(self.database_bytes),
+            format_bytes(self.log_bytes)
+        );
+        if let Some(days) = self.days_until_full {
+            text.push_str(&format!("; full in ~{:.0} days", days));
+        }
+        text
+    }
+}
+
+/// Locations measured by the monitor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoragePaths {
+    /// Database file; `None` for an in-memory database
+    pub database: Option<PathBuf>,
+    pub log_directory: PathBuf,
+    pub data_directory: PathBuf,
+}
+
+impl StoragePaths {
+    pub fn from_config(config: &Config) -> Self {
+        let database = (config.database.url != ":memory:").then(|| PathBuf::from(&config.database.url));
+        let log_directory = match Path::new(&config.logging.file).parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        Self { database, log_directory, data_directory: PathBuf::from(&config.application.data_directory) }
+    }
+
+    /// Measure the current sizes and free space.
+    pub fn measure(&self) -> Result<StorageUsage> {
+        let database_bytes = match &self.database {
+            Some(path) => {
+                let wal = PathBuf::from(format!("{}-wal", path.display()));
+                file_size(path)? + file_size(&wal)?
+            }
+            None => 0,
+        };
+        Ok(StorageUsage {
+            database_bytes,
+            log_bytes: directory_size(&self.log_directory)?,
+            free_bytes: available_space(&self.data_directory)?,
+        })
+    }
+}
+
+/// Free space on the file system holding `path`, or its nearest existing
+/// ancestor when `path` is not created yet.
+pub fn available_space(path: &Path) -> Result<u64> {
+    let existing = path.ancestors().find(|p| !p.as_os_str().is_empty() && p.exists()).unwrap_or(Path::new("."));
+    fs2::available_space(existing).map_err(|e| fs_error(existing, e))
+}
+
+fn file_size(path: &Path) -> Result<u64> {
+    match std::fs::metadata(path) {
+        Ok(metadata) => Ok(metadata.len()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(e) => Err(fs_error(path, e)),
+    }
+}
+
+fn directory_size(path: &Path) -> Result<u64> {
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(fs_error(path, e)),
+    };
+    let mut total = 0;
+    for entry in entries.flatten() {
+        let metadata = entry.metadata().map_err(|e| fs_error(&entry.path(), e))?;
+        total += if metadata.is_dir() { directory_size(&entry.path())? } else { metadata.len() };
+    }
+    Ok(total)
+}
+
+fn fs_error(path: &Path, e: std::io::Error) -> QmsError {
+    QmsError::FileSystem { path: path.display().to_string(), message: e.to_string() }
+}
+
+/// Records samples and raises alerts when the storage level worsens.
+pub struct StorageMonitor<'a> {
+    db: &'a Database,
+    config: &'a StorageConfig,
+    notifier: Option<(&'a dyn Notifier, Vec<String>)>,
+}
+
+impl<'a> StorageMonitor<'a> {
+    pub fn new(db: &'a Database, config: &'a StorageConfig) -> Self {
+        Self { db, config, notifier: None }
+    }
+
+    /// Email worsening levels to `recipients`.
+    pub fn with_notifier(mut self, notifier: &'a dyn Notifier, recipients: Vec<String>) -> Self {
+        self.notifier = Some((notifier, recipients));
+        self
+    }
+
+    /// Record `usage` measured at `now`, project growth and rate it. A level
+    /// different from the previous sample's is audited; a worse one is also
+    /// emailed.
+    pub fn record(&self, usage: StorageUsage, now: DateTime<Utc>) -> Result<StorageSample> {
+        let window_start = now - Duration::days(i64::from(self.config.projection_window_days));
+        let mut points: Vec<(DateTime<Utc>, u64)> = samples_since(self.db, window_start)?
+            .iter()
+            .map(|s| (s.sampled_at, s.database_bytes + s.log_bytes))
+            .collect();
+        points.push((now, usage.database_bytes + usage.log_bytes));
+        let growth_bytes_per_day = growth_per_day(&points);
+        let days_until_full =
+            growth_bytes_per_day.filter(|growth| *growth > 0.0).map(|growth| usage.free_bytes as f64 / growth);
+
+        let free_mb = usage.free_bytes / MB;
+        let level = if free_mb < self.config.critical_free_mb {
+            StorageLevel::Critical
+        } else if free_mb < self.config.warning_free_mb
+            || days_until_full.map_or(false, |days| days < f64::from(self.config.warning_days_until_full))
+        {
+            StorageLevel::Warning
+        } else {
+            StorageLevel::Ok
+        };
+        let sample = StorageSample {
+            id: Uuid::new_v4(),
+            sampled_at: now,
+            database_bytes: usage.database_bytes,
+            log_bytes: usage.log_bytes,
+            free_bytes: usage.free_bytes,
+            growth_bytes_per_day,
+            days_until_full,
+            level,
+        };
+
+        let previous = latest_sample(self.db)?.map_or(StorageLevel::Ok, |s| s.level);
+        if level > previous {
+            if let Some((notifier, recipients)) = &self.notifier {
+                if !recipients.is_empty() {
+                    notifier.send(&alert_email(&sample, recipients.clone()))?;
+                }
+            }
+        }
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "INSERT INTO storage_samples (id, sampled_at, database_bytes, log_bytes, free_bytes,
+                     growth_bytes_per_day, days_until_full, level)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    sample.id.to_string(),
+                    now.to_rfc3339(),
+                    sample.database_bytes as i64,
+                    sample.log_bytes as i64,
+                    sample.free_bytes as i64,
+                    sample.growth_bytes_per_day,
+                    sample.days_until_full,
+                    level.as_str(),
+                ],
+            )?;
+            if level == previous {
+                return Ok(());
+            }
+            let (action, outcome) = match level {
+                StorageLevel::Critical => ("storage_critical", "Failure"),
+                StorageLevel::Warning => ("storage_warning", "Warning"),
+                StorageLevel::Ok => ("storage_recovered", "Success"),
+            };
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                STORAGE_MONITOR_USER,
+                action,
+                RESOURCE,
+                outcome,
+                Some(sample.describe()),
+            )
+        })?;
+        Ok(sample)
+    }
+}
+
+/// Least-squares slope of `(time, bytes)` in bytes per day; `None` without
+/// two samples at different times.
+fn growth_per_day(points: &[(DateTime<Utc>, u64)]) -> Option<f64> {
+    let origin = points.first()?.0;
+    let xs: Vec<f64> = points.iter().map(|(at, _)| (*at - origin).num_seconds() as f64 / 86_400.0).collect();
+    let ys: Vec<f64> = points.iter().map(|(_, bytes)| *bytes as f64).collect();
+    let n = xs.len() as f64;
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+    let variance: f64 = xs.iter().map(|x| (x - mean_x).powi(2)).sum();
+    if variance == 0.0 {
+        return None;
+    }
+    let covariance: f64 = xs.iter().zip(&ys).map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    Some(covariance / variance)
+}
+
+fn alert_email(sample: &StorageSample, recipients: Vec<String>) -> EmailMessage {
+    let headline = match sample.level {
+        StorageLevel::Critical => format!("critically low disk space ({} MB free)", sample.free_mb()),
+        _ => format!("disk space running low ({} MB free)", sample.free_mb()),
+    };
+    let body = format!(
+        "QMS storage is {}.\n\n{}\n\n\
+         Audit entries cannot be recorded once the disk is full. Free space or \
+         extend the volume holding the data directory.\n\n\
+         This message was sent automatically by QMSrs.",
+        headline,
+        sample.describe(),
+    );
+    EmailMessage::new(recipients, format!("QMS storage: {}", headline), body)
+}
+
+/// `1536` as `1.5 KB`, binary units.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+const COLUMNS: &str =
+    "id, sampled_at, database_bytes, log_bytes, free_bytes, growth_bytes_per_day, days_until_full, level";
+
+/// Most recent sample, if any.
+pub fn latest_sample(db: &Database) -> Result<Option<StorageSample>> {
+    db.with_connection(|conn| {
+        Ok(conn
+            .query_row(
+                &format!("SELECT {} FROM storage_samples ORDER BY sampled_at DESC LIMIT 1", COLUMNS),
+                [],
+                map_sample,
+            )
+            .optional()?)
+    })
+}
+
+/// Samples taken at or after `since`, oldest first.
+pub fn samples_since(db: &Database, since: DateTime<Utc>) -> Result<Vec<StorageSample>> {
+    db.with_connection(|conn| {
+        let mut stmt = conn
+            .prepare(&format!("SELECT {} FROM storage_samples WHERE sampled_at >= ?1 ORDER BY sampled_at", COLUMNS))?;
+        let samples = stmt.query_map(params![since.to_rfc3339()], map_sample)?.collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(samples)
+    })
+}
+
+fn map_sample(row: &Row) -> rusqlite::Result<StorageSample> {
+    let id: String = row.get(0)?;
+    let sampled_at: String = row.get(1)?;
+    let level: String = row.get(7)?;
+    Ok(StorageSample {
+        id: Uuid::parse_str(&id).map_err(|e| conversion_error(0, e.to_string()))?,
+        sampled_at: DateTime::parse_from_rfc3339(&sampled_at)
+            .map(|t| t.with_timezone(&Utc))
+            .map_err(|e| conversion_error(1, e.to_string()))?,
+        database_bytes: row.get::<_, i64>(2)? as u64,
+        log_bytes: row.get::<_, i64>(3)? as u64,
+        free_bytes: row.get::<_, i64>(4)? as u64,
+        growth_bytes_per_day: row.get(5)?,
+        days_until_full: row.get(6)?,
+        level: level.parse().map_err(|e: QmsError| conversion_error(7, e.to_string()))?,
+    })
+}
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use chrono::TimeZone;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct RecordingNotifier {
+        sent: Mutex<Vec<EmailMessage>>,
+    }
+
+    impl Notifier for RecordingNotifier {
+        fn send(&self, message: &EmailMessage) -> Result<String> {
+            let mut sent = self.sent.lock().unwrap();
+            sent.push(message.clone());
+            Ok(format!("msg-{}", sent.len()))
+        }
+    }
+
+    fn usage(database_mb: u64, free_mb: u64) -> StorageUsage {
+        StorageUsage { database_bytes: database_mb * MB, log_bytes: 10 * MB, free_bytes: free_mb * MB }
+    }
+
+    #[test]
+    fn test_growth_projection_and_alerts() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        let config = StorageConfig {
+            warning_free_mb: 2048,
+            critical_free_mb: 512,
+            warning_days_until_full: 30,
+            projection_window_days: 30,
+        };
+        let notifier = RecordingNotifier::default();
+        let monitor = StorageMonitor::new(&db, &config).with_notifier(&notifier, vec!["qa@example.com".to_string()]);
+        let day = |d: u32| Utc.with_ymd_and_hms(2026, 10, d, 6, 0, 0).unwrap();
+
+        let first = monitor.record(usage(1000, 50_000), day(1)).unwrap();
+        assert_eq!((first.level, first.growth_bytes_per_day), (StorageLevel::Ok, None));
+
+        // 100 MB/day with 50 GB free: no warning
+        let steady = monitor.record(usage(1100, 49_900), day(2)).unwrap();
+        assert_eq!(steady.growth_bytes_per_day, Some((100 * MB) as f64));
+        assert_eq!(steady.level, StorageLevel::Ok);
+
+        // 2900 MB free at 100 MB/day runs out within 30 days
+        let soon = monitor.record(usage(1200, 2900), day(3)).unwrap();
+        assert_eq!(soon.days_until_full, Some(29.0));
+        assert_eq!(soon.level, StorageLevel::Warning);
+
+        let critical = monitor.record(usage(1300, 400), day(4)).unwrap();
+        assert_eq!(critical.level, StorageLevel::Critical);
+        assert!(critical.describe().starts_with("400.0 MB free; database 1.3 GB, logs 10.0 MB"));
+        let again = monitor.record(usage(1300, 390), day(5)).unwrap();
+        assert_eq!(again.level, StorageLevel::Critical);
+
+        let sent = notifier.sent.lock().unwrap();
+        assert_eq!(sent.len(), 2, "one email per worsening level");
+        assert!(sent[1].subject.contains("critically low disk space (400 MB free)"));
+        drop(sent);
+
+        let recovered = monitor.record(usage(1300, 80_000), day(6)).unwrap();
+        assert_eq!(recovered.level, StorageLevel::Ok);
+        let actions: Vec<String> =
+            db.get_audit_entries_for_resource(RESOURCE).unwrap().into_iter().map(|e| e.action).collect();
+        assert_eq!(actions, vec!["storage_warning", "storage_critical", "storage_recovered"]);
+
+        assert_eq!(latest_sample(&db).unwrap().unwrap(), recovered);
+        assert_eq!(samples_since(&db, day(4)).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_measure_sizes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("qms.db"), vec![0u8; 4096]).unwrap();
+        std::fs::write(dir.path().join("qms.db-wal"), vec![0u8; 1024]).unwrap();
+        let logs = dir.path().join("logs");
+        std::fs::create_dir_all(logs.join("archive")).unwrap();
+        std::fs::write(logs.join("qms.log"), vec![0u8; 100]).unwrap();
+        std::fs::write(logs.join("archive").join("qms.log.1"), vec![0u8; 50]).unwrap();
+
+        let paths = StoragePaths {
+            database: Some(dir.path().join("qms.db")),
+            log_directory: logs,
+            data_directory: dir.path().join("data"),
+        };
+        let usage = paths.measure().unwrap();
+        assert_eq!((usage.database_bytes, usage.log_bytes), (5120, 150));
+        assert!(usage.free_bytes > 0);
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(12), "12 B");
+    }
+}
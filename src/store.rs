@@ -0,0 +1,205 @@
+//! # Store - Repository Abstractions
+//!
+//! Services talk to persistence through the [`SupplierStore`] and
+//! [`TrainingStore`] traits rather than the rusqlite repositories directly.
+//! The repositories remain the default implementation; the in-memory stores
+//! below back fast unit tests and mark the seam for alternative backends.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use uuid::Uuid;
+
+use crate::change_history::ChangeReason;
+use crate::concurrency::check_version;
+use crate::error::{QmsError, Result};
+use crate::supplier::Supplier;
+use crate::supplier_repo::SupplierRepository;
+use crate::training::TrainingRecord;
+use crate::training_repo::TrainingRepository;
+
+/// Persistence for supplier records.
+pub trait SupplierStore: Send + Sync {
+    fn insert(&self, supplier: &Supplier) -> Result<()>;
+    /// Replace a stored supplier and return its new version; `NotFound` when
+    /// it does not exist, `Conflict` when `supplier.row_version` is stale.
+    fn update(&self, supplier: &Supplier, changed_by: &str, reason: &ChangeReason) -> Result<i64>;
+    fn fetch_by_id(&self, id: &Uuid) -> Result<Option<Supplier>>;
+}
+
+/// Persistence for training records.
+pub trait TrainingStore: Send + Sync {
+    fn insert(&self, record: &TrainingRecord) -> Result<()>;
+    /// Replace a stored record and return its new version; `NotFound` when
+    /// it does not exist, `Conflict` when `record.row_version` is stale.
+    fn update(&self, record: &TrainingRecord, changed_by: &str, reason: &ChangeReason) -> Result<i64>;
+    fn fetch_by_id(&self, id: &Uuid) -> Result<Option<TrainingRecord>>;
+    fn fetch_by_employee(&self, employee_id: &str) -> Result<Vec<TrainingRecord>>;
+}
+
+impl SupplierStore for SupplierRepository {
+    fn insert(&self, supplier: &Supplier) -> Result<()> {
+        SupplierRepository::insert(self, supplier)
+    }
+
+    fn update(&self, supplier: &Supplier, changed_by: &str, reason: &ChangeReason) -> Result<i64> {
+        SupplierRepository::update(self, supplier, changed_by, reason)
+    }
+
+    fn fetch_by_id(&self, id: &Uuid) -> Result<Option<Supplier>> {
+        SupplierRepository::fetch_by_id(self, id)
+    }
+}
+
+impl TrainingStore for TrainingRepository {
+    fn insert(&self, record: &TrainingRecord) -> Result<()> {
+        TrainingRepository::insert(self, record)
+    }
+
+    fn update(&self, record: &TrainingRecord, changed_by: &str, reason: &ChangeReason) -> Result<i64> {
+        TrainingRepository::update(self, record, changed_by, reason)
+    }
+
+    fn fetch_by_id(&self, id: &Uuid) -> Result<Option<TrainingRecord>> {
+        TrainingRepository::fetch_by_id(self, id)
+    }
+
+    fn fetch_by_employee(&self, employee_id: &str) -> Result<Vec<TrainingRecord>> {
+        TrainingRepository::fetch_by_employee(self, employee_id)
+    }
+}
+
+fn lock_error<T>(_: T) -> QmsError {
+    QmsError::Database { message: "in-memory store lock poisoned".to_string() }
+}
+
+/// Supplier store held in memory; clones share the same records.
+#[derive(Clone, Default)]
+pub struct InMemorySupplierStore {
+    suppliers: Arc<RwLock<BTreeMap<Uuid, Supplier>>>,
+}
+
+impl InMemorySupplierStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SupplierStore for InMemorySupplierStore {
+    fn insert(&self, supplier: &Supplier) -> Result<()> {
+        self.suppliers.write().map_err(lock_error)?.insert(supplier.id, supplier.clone());
+        Ok(())
+    }
+
+    fn update(&self, supplier: &Supplier, _changed_by: &str, _reason: &ChangeReason) -> Result<i64> {
+        match self.suppliers.write().map_err(lock_error)?.get_mut(&supplier.id) {
+            Some(stored) => {
+                check_version("supplier", &supplier.id.to_string(), supplier.row_version, stored.row_version)?;
+                *stored = Supplier { row_version: stored.row_version + 1, ..supplier.clone() };
+                Ok(stored.row_version)
+            }
+            None => Err(QmsError::NotFound { resource: "supplier".to_string(), id: supplier.id.to_string() }),
+        }
+    }
+
+    fn fetch_by_id(&self, id: &Uuid) -> Result<Option<Supplier>> {
+        Ok(self.suppliers.read().map_err(lock_error)?.get(id).cloned())
+    }
+}
+
+/// Training store held in memory; clones share the same records.
+#[derive(Clone, Default)]
+pub struct InMemoryTrainingStore {
+    records: Arc<RwLock<BTreeMap<Uuid, TrainingRecord>>>,
+}
+
+impl InMemoryTrainingStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TrainingStore for InMemoryTrainingStore {
+    fn insert(&self, record: &TrainingRecord) -> Result<()> {
+        self.records.write().map_err(lock_error)?.insert(record.id, record.clone());
+        Ok(())
+    }
+
+    fn update(&self, record: &TrainingRecord, _changed_by: &str, _reason: &ChangeReason) -> Result<i64> {
+        match self.records.write().map_err(lock_error)?.get_mut(&record.id) {
+            Some(stored) => {
+                check_version("training_record", &record.id.to_string(), record.row_version, stored.row_version)?;
+                *stored = TrainingRecord { row_version: stored.row_version + 1, ..record.clone() };
+                Ok(stored.row_version)
+            }
+            None => Err(QmsError::NotFound { resource: "training_record".to_string(), id: record.id.to_string() }),
+        }
+    }
+
+    fn fetch_by_id(&self, id: &Uuid) -> Result<Option<TrainingRecord>> {
+        Ok(self.records.read().map_err(lock_error)?.get(id).cloned())
+    }
+
+    fn fetch_by_employee(&self, employee_id: &str) -> Result<Vec<TrainingRecord>> {
+        let records = self.records.read().map_err(lock_error)?;
+        let mut matching: Vec<_> = records.values().filter(|r| r.employee_id == employee_id).cloned().collect();
+        matching.sort_by_key(|r| r.due_date);
+        Ok(matching)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditLogger;
+    use crate::supplier::{SupplierService, SupplierStatus};
+    use crate::training::{TrainingService, TrainingStatus};
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_supplier_service_on_in_memory_store() {
+        let store = InMemorySupplierStore::new();
+        let service = SupplierService::new(AuditLogger::new_test(), store.clone());
+        let mut supplier = service.register_supplier("Acme".to_string(), None).unwrap();
+        service
+            .qualify_supplier(&mut supplier, "qa".to_string(), None, &ChangeReason::new("Audit passed").unwrap())
+            .unwrap();
+
+        let stored = store.fetch_by_id(&supplier.id).unwrap().unwrap();
+        assert_eq!(stored.status, SupplierStatus::Qualified);
+        assert_eq!((stored.row_version, supplier.row_version), (2, 2));
+
+        let mut stale = stored.clone();
+        stale.row_version = 1;
+        let reason = ChangeReason::new("Late edit").unwrap();
+        assert!(matches!(
+            service.disqualify_supplier(&mut stale, "qa".to_string(), &reason),
+            Err(QmsError::Conflict { expected: 1, actual: 2, .. })
+        ));
+
+        let mut missing = supplier.clone();
+        missing.id = Uuid::new_v4();
+        let reason = ChangeReason::new("n/a").unwrap();
+        assert!(matches!(store.update(&missing, "qa", &reason), Err(QmsError::NotFound { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_training_service_on_in_memory_store() {
+        let store = InMemoryTrainingStore::new();
+        let service = TrainingService::new(AuditLogger::new_test(), store.clone());
+        let due = NaiveDate::from_ymd_opt(2030, 1, 1).unwrap();
+        let mut record = service
+            .create_training_record("emp1".to_string(), "GMP".to_string(), true, due, "manager".to_string())
+            .await
+            .unwrap();
+        service
+            .mark_completed(&mut record, "manager".to_string(), true, &ChangeReason::new("Quiz passed").unwrap())
+            .await
+            .unwrap();
+
+        let records = store.fetch_by_employee("emp1").unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].status, TrainingStatus::Completed);
+        assert!(store.fetch_by_employee("emp2").unwrap().is_empty());
+    }
+}
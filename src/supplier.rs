@@ -13,11 +13,13 @@
 //! * Qualify or disqualify suppliers with audit logging.
 //! * Generate supplier compliance metrics.
 
-use crate::{audit::AuditLogger, error::Result};
+use crate::{audit::AuditLogger, change_history::ChangeReason, error::Result};
+use crate::concurrency::{initial_row_version, INITIAL_ROW_VERSION};
 use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use crate::supplier_repo::SupplierRepository;
+use crate::store::SupplierStore;
 
 /// Supplier qualification status
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -39,11 +41,14 @@ pub struct Supplier {
     pub approved_by: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Optimistic concurrency version; updates must present the current one
+    #[serde(default = "initial_row_version")]
+    pub row_version: i64,
 }
 
 /// Supplier compliance metrics structure
 /// Provides aggregated counts for dashboard & API usage.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
 pub struct SupplierMetrics {
     /// Total number of suppliers in system
     pub total_count: usize,
@@ -60,19 +65,17 @@ pub struct SupplierMetrics {
 impl SupplierMetrics {
     /// Compute metrics from slice of suppliers – FAST/ISOLATED helper.
     pub fn from_suppliers(suppliers: &[Supplier]) -> Self {
-        let total_count = suppliers.len();
-        let qualified_count = suppliers
-            .iter()
-            .filter(|s| s.status == SupplierStatus::Qualified)
-            .count();
-        let pending_count = suppliers
-            .iter()
-            .filter(|s| s.status == SupplierStatus::Pending)
-            .count();
-        let disqualified_count = suppliers
-            .iter()
-            .filter(|s| s.status == SupplierStatus::Disqualified)
-            .count();
+        let count = |status: SupplierStatus| suppliers.iter().filter(|s| s.status == status).count();
+        Self::from_counts(
+            count(SupplierStatus::Qualified),
+            count(SupplierStatus::Pending),
+            count(SupplierStatus::Disqualified),
+        )
+    }
+
+    /// Build metrics from per-status counts (e.g. a summary table).
+    pub fn from_counts(qualified_count: usize, pending_count: usize, disqualified_count: usize) -> Self {
+        let total_count = qualified_count + pending_count + disqualified_count;
         let qualified_percentage = if total_count == 0 {
             0.0
         } else {
@@ -90,13 +93,16 @@ impl SupplierMetrics {
 }
 
 /// Service layer encapsulating supplier lifecycle operations
-pub struct SupplierService {
+///
+/// Generic over its [`SupplierStore`]; defaults to the rusqlite repository.
+#[derive(Clone)]
+pub struct SupplierService<S: SupplierStore = SupplierRepository> {
     audit_logger: AuditLogger,
-    repository: SupplierRepository,
+    repository: S,
 }
 
-impl SupplierService {
-    pub fn new(audit_logger: AuditLogger, repository: SupplierRepository) -> Self {
+impl<S: SupplierStore> SupplierService<S> {
+    pub fn new(audit_logger: AuditLogger, repository: S) -> Self {
         Self {
             audit_logger,
             repository,
@@ -115,6 +121,7 @@ impl SupplierService {
             approved_by: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            row_version: INITIAL_ROW_VERSION,
         };
         // Persist
         self.repository.insert(&supplier)?;
@@ -129,12 +136,19 @@ impl SupplierService {
         Ok(supplier)
     }
 
-    /// Qualify a supplier (update status & dates)
+    /// Fetch a supplier with its current version
+    pub fn get_supplier(&self, id: &Uuid) -> Result<Option<Supplier>> {
+        self.repository.fetch_by_id(id)
+    }
+
+    /// Qualify a supplier (update status & dates). Fails with `Conflict`
+    /// when `supplier` is stale; on success it carries the new version.
     pub fn qualify_supplier(
         &self,
         supplier: &mut Supplier,
         approved_by: String,
         expiry: Option<NaiveDate>,
+        reason: &ChangeReason,
     ) -> Result<()> {
         supplier.status = SupplierStatus::Qualified;
         supplier.qualification_date = Some(Utc::now().date_naive());
@@ -142,28 +156,28 @@ impl SupplierService {
         supplier.approved_by = Some(approved_by.clone());
         supplier.updated_at = Utc::now();
 
-        self.repository.update(supplier)?;
+        supplier.row_version = self.repository.update(supplier, &approved_by, reason)?;
         self.audit_logger.log_event(
             &approved_by,
             "QUALIFY_SUPPLIER",
             &format!("supplier:{}", supplier.id),
             "SUCCESS",
-            None,
+            Some(reason.to_string()),
         );
         Ok(())
     }
 
-    /// Disqualify supplier
-    pub fn disqualify_supplier(&self, supplier: &mut Supplier, by: String, reason: String) -> Result<()> {
+    /// Disqualify supplier; stale copies are rejected like in `qualify_supplier`
+    pub fn disqualify_supplier(&self, supplier: &mut Supplier, by: String, reason: &ChangeReason) -> Result<()> {
         supplier.status = SupplierStatus::Disqualified;
         supplier.updated_at = Utc::now();
-        self.repository.update(supplier)?;
+        supplier.row_version = self.repository.update(supplier, &by, reason)?;
         self.audit_logger.log_event(
             &by,
             "DISQUALIFY_SUPPLIER",
             &format!("supplier:{}", supplier.id),
             "SUCCESS",
-            Some(reason),
+            Some(reason.to_string()),
         );
         Ok(())
     }
@@ -176,7 +190,15 @@ mod tests {
     use crate::supplier_repo::SupplierRepository;
 
     fn setup_service() -> SupplierService {
-        let db = Database::new(DatabaseConfig::default()).unwrap();
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 1,
+        })
+        .unwrap();
+        db.seed_test_users(&["qa_manager"]);
         let repo = SupplierRepository::new(db);
         SupplierService::new(AuditLogger::new_test(), repo)
     }
@@ -187,7 +209,7 @@ mod tests {
         let mut supplier = service.register_supplier("Test Vendor".to_string(), None).unwrap();
         assert_eq!(supplier.status, SupplierStatus::Pending);
         service
-            .qualify_supplier(&mut supplier, "qa_manager".to_string(), None)
+            .qualify_supplier(&mut supplier, "qa_manager".to_string(), None, &ChangeReason::new("Audit passed").unwrap())
             .unwrap();
         assert_eq!(supplier.status, SupplierStatus::Qualified);
         assert!(supplier.qualification_date.is_some());
@@ -198,7 +220,7 @@ mod tests {
         let service = setup_service();
         let mut supplier = service.register_supplier("Bad Vendor".to_string(), None).unwrap();
         service
-            .disqualify_supplier(&mut supplier, "qa_manager".to_string(), "Quality issues".to_string())
+            .disqualify_supplier(&mut supplier, "qa_manager".to_string(), &ChangeReason::new("Quality issues").unwrap())
             .unwrap();
         assert_eq!(supplier.status, SupplierStatus::Disqualified);
     }
@@ -217,6 +239,7 @@ mod tests {
             approved_by: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
+            row_version: 1,
         });
         // Qualified supplier
         suppliers.push(Supplier {
@@ -229,6 +252,7 @@ mod tests {
             approved_by: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
+            row_version: 1,
         });
         // Disqualified supplier
         suppliers.push(Supplier {
@@ -241,6 +265,7 @@ mod tests {
             approved_by: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
+            row_version: 1,
         });
 
         let metrics = SupplierMetrics::from_suppliers(&suppliers);
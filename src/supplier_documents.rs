@@ -0,0 +1,579 @@
+//! # Supplier Documents - Certificates and Agreements from Suppliers
+//!
+//! Suppliers provide ISO certificates, quality agreements and data processing
+//! agreements (DPAs), uploaded through the API or the CLI. Each upload is kept
+//! write-once in the file store and becomes the next version of the supplier's
+//! document with the same kind and title; earlier versions remain as history.
+//!
+//! [`SupplierDocumentMonitor`] runs with the overdue scan. It warns the
+//! quality managers once when a current document is about to expire and once
+//! when it has expired. An expired ISO certificate or quality agreement also
+//! moves a qualified supplier back to `Pending`. The supplier then needs a valid
+//! document and requalification (21 CFR 820.50, ISO 13485 §7.4.1).
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::change_history::ChangeReason;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::file_store::{safe_file_name, FileStore};
+use crate::notification::{EmailMessage, Notifier};
+use crate::supplier::SupplierStatus;
+use crate::supplier_repo::SupplierRepository;
+
+/// User recorded for expiry warnings and qualification changes.
+pub const SUPPLIER_DOCUMENTS_USER: &str = "system:supplier_documents";
+
+/// File store category of supplier documents.
+pub const DOCUMENT_CATEGORY: &str = "supplier_documents";
+
+/// Days before expiry on which the quality managers are warned.
+pub const EXPIRY_WARNING_DAYS: i64 = 30;
+
+/// Kind of document exchanged with a supplier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SupplierDocumentKind {
+    IsoCertificate,
+    QualityAgreement,
+    DataProcessingAgreement,
+    Other,
+}
+
+impl SupplierDocumentKind {
+    pub const ALL: [SupplierDocumentKind; 4] = [
+        SupplierDocumentKind::IsoCertificate,
+        SupplierDocumentKind::QualityAgreement,
+        SupplierDocumentKind::DataProcessingAgreement,
+        SupplierDocumentKind::Other,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SupplierDocumentKind::IsoCertificate => "iso_certificate",
+            SupplierDocumentKind::QualityAgreement => "quality_agreement",
+            SupplierDocumentKind::DataProcessingAgreement => "data_processing_agreement",
+            SupplierDocumentKind::Other => "other",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == value)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SupplierDocumentKind::IsoCertificate => "ISO certificate",
+            SupplierDocumentKind::QualityAgreement => "Quality agreement",
+            SupplierDocumentKind::DataProcessingAgreement => "Data processing agreement",
+            SupplierDocumentKind::Other => "Document",
+        }
+    }
+
+    /// Whether the supplier's qualification depends on a valid document.
+    pub fn affects_qualification(&self) -> bool {
+        matches!(self, SupplierDocumentKind::IsoCertificate | SupplierDocumentKind::QualityAgreement)
+    }
+}
+
+/// Validity of a document on a given day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExpiryStatus {
+    Valid,
+    /// Expires within [`EXPIRY_WARNING_DAYS`]
+    ExpiringSoon,
+    Expired,
+}
+
+impl ExpiryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExpiryStatus::Valid => "valid",
+            ExpiryStatus::ExpiringSoon => "expiring_soon",
+            ExpiryStatus::Expired => "expired",
+        }
+    }
+}
+
+/// One version of a supplier document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SupplierDocument {
+    pub id: Uuid,
+    pub supplier_id: Uuid,
+    pub kind: SupplierDocumentKind,
+    pub title: String,
+    /// 1 for the first upload of this kind and title
+    pub version: i64,
+    /// Name of the file as provided
+    pub file_name: String,
+    /// Location in the file store
+    pub relative_path: String,
+    pub sha256: String,
+    pub size_bytes: u64,
+    /// Last day the document is valid; open-ended when absent
+    pub expires_on: Option<NaiveDate>,
+    pub uploaded_by: String,
+    pub uploaded_at: DateTime<Utc>,
+    /// When a newer version replaced this one
+    pub superseded_at: Option<DateTime<Utc>>,
+    pub warned_at: Option<DateTime<Utc>>,
+    pub expiry_notified_at: Option<DateTime<Utc>>,
+}
+
+impl SupplierDocument {
+    pub fn status(&self, today: NaiveDate) -> ExpiryStatus {
+        match self.expires_on {
+            Some(expires_on) if expires_on < today => ExpiryStatus::Expired,
+            Some(expires_on) if expires_on - today <= Duration::days(EXPIRY_WARNING_DAYS) => {
+                ExpiryStatus::ExpiringSoon
+            }
+            _ => ExpiryStatus::Valid,
+        }
+    }
+
+    pub fn is_current(&self) -> bool {
+        self.superseded_at.is_none()
+    }
+}
+
+/// Supplier documents and their version history.
+pub struct SupplierDocumentRepo<'a> {
+    db: &'a Database,
+    store: &'a FileStore,
+}
+
+impl<'a> SupplierDocumentRepo<'a> {
+    pub fn new(db: &'a Database, store: &'a FileStore) -> Self {
+        Self { db, store }
+    }
+
+    /// Store `contents` as the next version of the supplier's `kind` document
+    /// titled `title`, superseding the current one.
+    #[allow(clippy::too_many_arguments)]
+    pub fn upload(
+        &self,
+        supplier_id: Uuid,
+        kind: SupplierDocumentKind,
+        title: &str,
+        file_name: &str,
+        contents: &[u8],
+        expires_on: Option<NaiveDate>,
+        uploaded_by: &str,
+        now: DateTime<Utc>,
+    ) -> Result<SupplierDocument> {
+        let title = title.trim();
+        if title.is_empty() {
+            return Err(QmsError::Validation {
+                field: "title".to_string(),
+                message: "A document title is required".to_string(),
+            });
+        }
+        if contents.is_empty() {
+            return Err(QmsError::Validation {
+                field: "contents".to_string(),
+                message: "The uploaded document is empty".to_string(),
+            });
+        }
+        let version = self.db.with_connection(|conn| {
+            let known: Option<String> = conn
+                .query_row(
+                    "SELECT id FROM suppliers WHERE id = ?1 AND deleted_at IS NULL",
+                    params![supplier_id.to_string()],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if known.is_none() {
+                return Err(QmsError::NotFound { resource: "supplier".to_string(), id: supplier_id.to_string() });
+            }
+            let latest: Option<i64> = conn.query_row(
+                "SELECT MAX(version) FROM supplier_documents WHERE supplier_id = ?1 AND kind = ?2 AND title = ?3",
+                params![supplier_id.to_string(), kind.as_str(), title],
+                |row| row.get(0),
+            )?;
+            Ok(latest.unwrap_or(0) + 1)
+        })?;
+
+        let id = Uuid::new_v4();
+        let stored_name = format!("{}-{}", id, safe_file_name(file_name));
+        let file = self.store.store(DOCUMENT_CATEGORY, &stored_name, contents, now)?;
+        let document = SupplierDocument {
+            id,
+            supplier_id,
+            kind,
+            title: title.to_string(),
+            version,
+            file_name: file_name.to_string(),
+            relative_path: file.relative_path,
+            sha256: file.sha256,
+            size_bytes: file.size_bytes,
+            expires_on,
+            uploaded_by: uploaded_by.to_string(),
+            uploaded_at: now,
+            superseded_at: None,
+            warned_at: None,
+            expiry_notified_at: None,
+        };
+        self.db.unit_of_work(|uow| {
+            let conn = uow.connection();
+            conn.execute(
+                "UPDATE supplier_documents SET superseded_at = ?4
+                 WHERE supplier_id = ?1 AND kind = ?2 AND title = ?3 AND superseded_at IS NULL",
+                params![supplier_id.to_string(), kind.as_str(), document.title, now.to_rfc3339()],
+            )?;
+            conn.execute(
+                "INSERT INTO supplier_documents (id, supplier_id, kind, title, version, file_name, relative_path,
+                     sha256, size_bytes, expires_on, uploaded_by, uploaded_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                params![
+                    document.id.to_string(),
+                    supplier_id.to_string(),
+                    kind.as_str(),
+                    document.title,
+                    version,
+                    document.file_name,
+                    document.relative_path,
+                    document.sha256,
+                    document.size_bytes as i64,
+                    expires_on.map(|date| date.to_string()),
+                    document.uploaded_by,
+                    now.to_rfc3339(),
+                ],
+            )?;
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                uploaded_by,
+                "supplier_document_uploaded",
+                &format!("supplier:{}", supplier_id),
+                "Success",
+                Some(format!(
+                    "{} '{}' v{} ({}), expires {}",
+                    kind.label(),
+                    document.title,
+                    version,
+                    document.sha256,
+                    expires_on.map_or_else(|| "never".to_string(), |date| date.to_string())
+                )),
+            )
+        })?;
+        Ok(document)
+    }
+
+    /// Current version of each of the supplier's documents.
+    pub fn documents(&self, supplier_id: Uuid) -> Result<Vec<SupplierDocument>> {
+        query_documents(
+            self.db,
+            &format!(
+                "SELECT {} FROM supplier_documents WHERE supplier_id = ?1 AND superseded_at IS NULL
+                 ORDER BY kind, title",
+                COLUMNS
+            ),
+            params![supplier_id.to_string()],
+        )
+    }
+
+    /// Every version of the supplier's `kind` document titled `title`,
+    /// oldest first.
+    pub fn history(&self, supplier_id: Uuid, kind: SupplierDocumentKind, title: &str) -> Result<Vec<SupplierDocument>> {
+        query_documents(
+            self.db,
+            &format!(
+                "SELECT {} FROM supplier_documents WHERE supplier_id = ?1 AND kind = ?2 AND title = ?3
+                 ORDER BY version",
+                COLUMNS
+            ),
+            params![supplier_id.to_string(), kind.as_str(), title.trim()],
+        )
+    }
+}
+
+fn query_documents(db: &Database, sql: &str, params: impl rusqlite::Params) -> Result<Vec<SupplierDocument>> {
+    db.with_connection(|conn| {
+        let mut stmt = conn.prepare(sql)?;
+        let documents = stmt.query_map(params, row_to_document)?.collect::<rusqlite::Result<_>>()?;
+        Ok(documents)
+    })
+}
+
+/// A warning raised for a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SupplierDocumentAlert {
+    pub document: SupplierDocument,
+    pub supplier_name: String,
+    /// `ExpiringSoon` or `Expired`
+    pub status: ExpiryStatus,
+    /// The supplier was moved from `Qualified` back to `Pending`
+    pub qualification_suspended: bool,
+    pub message_id: Option<String>,
+}
+
+/// Warns about current documents that expire soon or have expired, each at
+/// most once per status.
+pub struct SupplierDocumentMonitor<'a> {
+    db: &'a Database,
+    notifier: Option<(&'a dyn Notifier, Vec<String>)>,
+}
+
+impl<'a> SupplierDocumentMonitor<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, notifier: None }
+    }
+
+    /// Email warnings to `recipients`.
+    pub fn with_notifier(mut self, notifier: &'a dyn Notifier, recipients: Vec<String>) -> Self {
+        self.notifier = Some((notifier, recipients));
+        self
+    }
+
+    /// Raise the warnings newly due at `now`.
+    pub fn check(&self, now: DateTime<Utc>) -> Result<Vec<SupplierDocumentAlert>> {
+        let today = now.date_naive();
+        let documents = query_documents(
+            self.db,
+            &format!(
+                "SELECT {} FROM supplier_documents WHERE superseded_at IS NULL AND expires_on IS NOT NULL
+                 ORDER BY expires_on, id",
+                COLUMNS
+            ),
+            [],
+        )?;
+        let suppliers = SupplierRepository::new(self.db.clone());
+        let mut alerts = Vec::new();
+        for document in documents {
+            let status = document.status(today);
+            let column = match status {
+                ExpiryStatus::Expired if document.expiry_notified_at.is_none() => "expiry_notified_at",
+                ExpiryStatus::ExpiringSoon if document.warned_at.is_none() => "warned_at",
+                _ => continue,
+            };
+            let Some(mut supplier) = suppliers.fetch_by_id(&document.supplier_id)? else {
+                continue;
+            };
+
+            let qualification_suspended = status == ExpiryStatus::Expired
+                && document.kind.affects_qualification()
+                && supplier.status == SupplierStatus::Qualified;
+            if qualification_suspended {
+                let reason = ChangeReason::new(format!(
+                    "{} '{}' expired on {}",
+                    document.kind.label(),
+                    document.title,
+                    document.expires_on.unwrap_or(today)
+                ))?;
+                supplier.status = SupplierStatus::Pending;
+                supplier.updated_at = now;
+                supplier.row_version = suppliers.update(&supplier, SUPPLIER_DOCUMENTS_USER, &reason)?;
+            }
+            let message_id = match &self.notifier {
+                Some((notifier, recipients)) if !recipients.is_empty() => Some(notifier.send(&expiry_email(
+                    &document,
+                    &supplier.name,
+                    status,
+                    qualification_suspended,
+                    recipients.clone(),
+                ))?),
+                _ => None,
+            };
+            self.db.unit_of_work(|uow| {
+                uow.connection().execute(
+                    &format!("UPDATE supplier_documents SET {} = ?2 WHERE id = ?1", column),
+                    params![document.id.to_string(), now.to_rfc3339()],
+                )?;
+                let (action, outcome) = match status {
+                    ExpiryStatus::Expired => ("supplier_document_expired", "Failure"),
+                    _ => ("supplier_document_expiring", "Warning"),
+                };
+                AuditManager::new(self.db.clone()).log_action_in(
+                    uow,
+                    SUPPLIER_DOCUMENTS_USER,
+                    action,
+                    &format!("supplier:{}", document.supplier_id),
+                    outcome,
+                    Some(format!(
+                        "{} '{}' v{} expires {}{}",
+                        document.kind.label(),
+                        document.title,
+                        document.version,
+                        document.expires_on.map_or_else(String::new, |date| date.to_string()),
+                        if qualification_suspended { "; qualification suspended" } else { "" }
+                    )),
+                )
+            })?;
+            alerts.push(SupplierDocumentAlert {
+                document,
+                supplier_name: supplier.name,
+                status,
+                qualification_suspended,
+                message_id,
+            });
+        }
+        Ok(alerts)
+    }
+}
+
+fn expiry_email(
+    document: &SupplierDocument,
+    supplier_name: &str,
+    status: ExpiryStatus,
+    qualification_suspended: bool,
+    recipients: Vec<String>,
+) -> EmailMessage {
+    let expires_on = document.expires_on.map_or_else(String::new, |date| date.to_string());
+    let headline = match status {
+        ExpiryStatus::Expired => format!("{} of {} expired on {}", document.kind.label(), supplier_name, expires_on),
+        _ => format!("{} of {} expires on {}", document.kind.label(), supplier_name, expires_on),
+    };
+    let consequence = if qualification_suspended {
+        "The supplier has been set back to Pending and needs requalification once a valid document is provided.\n\n"
+    } else {
+        ""
+    };
+    let body = format!(
+        "{}.\n\nDocument: {} (version {})\nFile: {}\n\n{}\
+         This message was sent automatically by QMSrs.",
+        headline, document.title, document.version, document.file_name, consequence,
+    );
+    EmailMessage::new(recipients, format!("Supplier document: {}", headline), body)
+}
+
+const COLUMNS: &str = "id, supplier_id, kind, title, version, file_name, relative_path, sha256, size_bytes, expires_on,
+     uploaded_by, uploaded_at, superseded_at, warned_at, expiry_notified_at";
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn parse_uuid(row: &Row, index: usize) -> rusqlite::Result<Uuid> {
+    let value: String = row.get(index)?;
+    Uuid::parse_str(&value).map_err(|e| conversion_error(index, e.to_string()))
+}
+
+fn parse_timestamp(row: &Row, index: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|timestamp| timestamp.with_timezone(&Utc))
+                .map_err(|e| conversion_error(index, e.to_string()))
+        })
+        .transpose()
+}
+
+fn row_to_document(row: &Row) -> rusqlite::Result<SupplierDocument> {
+    let kind: String = row.get(2)?;
+    let expires_on = row
+        .get::<_, Option<String>>(9)?
+        .map(|value| NaiveDate::parse_from_str(&value, "%Y-%m-%d").map_err(|e| conversion_error(9, e.to_string())))
+        .transpose()?;
+    Ok(SupplierDocument {
+        id: parse_uuid(row, 0)?,
+        supplier_id: parse_uuid(row, 1)?,
+        kind: SupplierDocumentKind::parse(&kind).ok_or_else(|| conversion_error(2, format!("unknown kind {}", kind)))?,
+        title: row.get(3)?,
+        version: row.get(4)?,
+        file_name: row.get(5)?,
+        relative_path: row.get(6)?,
+        sha256: row.get(7)?,
+        size_bytes: row.get::<_, i64>(8)? as u64,
+        expires_on,
+        uploaded_by: row.get(10)?,
+        uploaded_at: parse_timestamp(row, 11)?.ok_or_else(|| conversion_error(11, "missing upload time".to_string()))?,
+        superseded_at: parse_timestamp(row, 12)?,
+        warned_at: parse_timestamp(row, 13)?,
+        expiry_notified_at: parse_timestamp(row, 14)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditLogger;
+    use crate::config::DatabaseConfig;
+    use crate::supplier::SupplierService;
+    use std::sync::Mutex;
+    use tempfile::tempdir;
+
+    #[derive(Default)]
+    struct RecordingNotifier {
+        sent: Mutex<Vec<EmailMessage>>,
+    }
+
+    impl Notifier for RecordingNotifier {
+        fn send(&self, message: &EmailMessage) -> Result<String> {
+            let mut sent = self.sent.lock().unwrap();
+            sent.push(message.clone());
+            Ok(format!("msg-{}", sent.len()))
+        }
+    }
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_versions_expiry_alerts_and_requalification() {
+        let db = test_db();
+        db.seed_test_users(&["qa_manager"]);
+        let dir = tempdir().unwrap();
+        let store = FileStore::for_data_dir(dir.path());
+        let service = SupplierService::new(AuditLogger::new_test(), SupplierRepository::new(db.clone()));
+        let mut supplier = service.register_supplier("Moulding Co".to_string(), None).unwrap();
+        let reason = ChangeReason::new("Audit passed").unwrap();
+        service.qualify_supplier(&mut supplier, "qa_manager".to_string(), None, &reason).unwrap();
+
+        let now = Utc::now();
+        let today = now.date_naive();
+        let repo = SupplierDocumentRepo::new(&db, &store);
+        let upload = |kind, title, expires_in: i64| {
+            repo.upload(
+                supplier.id,
+                kind,
+                title,
+                "certificate.pdf",
+                b"%PDF-1.4",
+                Some(today + Duration::days(expires_in)),
+                "supplier_portal",
+                now,
+            )
+        };
+        let first = upload(SupplierDocumentKind::IsoCertificate, "ISO 13485", 400).unwrap();
+        let second = upload(SupplierDocumentKind::IsoCertificate, "ISO 13485", -1).unwrap();
+        upload(SupplierDocumentKind::DataProcessingAgreement, "DPA", 10).unwrap();
+        assert_eq!((first.version, second.version), (1, 2));
+        assert_ne!(first.relative_path, second.relative_path);
+        assert!(upload(SupplierDocumentKind::QualityAgreement, " ", 10).is_err());
+        let unknown = repo.upload(Uuid::new_v4(), SupplierDocumentKind::Other, "NDA", "nda.pdf", b"x", None, "qa", now);
+        assert!(matches!(unknown, Err(QmsError::NotFound { .. })));
+
+        let history = repo.history(supplier.id, SupplierDocumentKind::IsoCertificate, "ISO 13485").unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(!history[0].is_current() && history[1].is_current());
+        let current = repo.documents(supplier.id).unwrap();
+        assert_eq!(current.len(), 2);
+        assert_eq!(current[1].status(today), ExpiryStatus::Expired);
+        assert_eq!(current[0].status(today), ExpiryStatus::ExpiringSoon);
+
+        let notifier = RecordingNotifier::default();
+        let monitor = SupplierDocumentMonitor::new(&db).with_notifier(&notifier, vec!["qm@example.com".to_string()]);
+        let alerts = monitor.check(now).unwrap();
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(alerts[0].status, ExpiryStatus::Expired);
+        assert!(alerts[0].qualification_suspended);
+        assert!(!alerts[1].qualification_suspended);
+        assert_eq!(notifier.sent.lock().unwrap().len(), 2);
+        assert!(monitor.check(now).unwrap().is_empty(), "each status is raised once");
+        assert_eq!(service.get_supplier(&supplier.id).unwrap().unwrap().status, SupplierStatus::Pending);
+        let changes = db.get_audit_entries_for_resource(&format!("supplier:{}", supplier.id)).unwrap();
+        assert!(changes.iter().any(|entry| entry.action == "supplier_document_expired"));
+    }
+}
@@ -1,25 +1,40 @@
-use crate::{database::Database, error::Result, supplier::{Supplier, SupplierStatus}};
+use crate::{database::Database, error::{QmsError, Result}, supplier::{Supplier, SupplierStatus}};
+use crate::change_history::{diff_fields, record_changes, ChangeReason};
+use crate::concurrency::check_version;
+use crate::record_history::snapshot_with;
+use crate::reporting_views::{invalidate_with, Summary};
+use crate::site::DEFAULT_SITE_ID;
 use chrono::NaiveDate;
-use rusqlite::params;
+use rusqlite::{params, Connection};
 use uuid::Uuid;
 
 /// Repository for `suppliers` table
+#[derive(Clone)]
 pub struct SupplierRepository {
     db: Database,
+    site_id: String,
 }
 
 impl SupplierRepository {
     pub fn new(db: Database) -> Self {
-        Self { db }
+        Self { db, site_id: DEFAULT_SITE_ID.to_string() }
     }
 
+    /// Scope the repository to suppliers of `site_id`.
+    pub fn with_site<S: Into<String>>(mut self, site_id: S) -> Self {
+        self.site_id = site_id.into();
+        self
+    }
+
+    /// Insert a supplier and store its first version snapshot.
     pub fn insert(&self, supplier: &Supplier) -> Result<()> {
         self.db.with_connection(|conn| {
-            conn.execute(
+            let tx = conn.unchecked_transaction()?;
+            tx.execute(
                 "INSERT INTO suppliers (
                     id, name, contact_info, qualification_status, qualification_date,
-                    qualification_expiry_date, approved_by, created_at, updated_at
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    qualification_expiry_date, approved_by, created_at, updated_at, site_id, row_version
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
                 params![
                     supplier.id.to_string(),
                     supplier.name,
@@ -30,15 +45,29 @@ impl SupplierRepository {
                     supplier.approved_by,
                     supplier.created_at.to_rfc3339(),
                     supplier.updated_at.to_rfc3339(),
+                    self.site_id,
+                    supplier.row_version,
                 ],
             )?;
+            snapshot_with(&tx, supplier, supplier.approved_by.as_deref().unwrap_or("system"), chrono::Utc::now())?;
+            invalidate_with(&tx, Summary::Supplier)?;
+            tx.commit()?;
             Ok(())
         })
     }
 
-    pub fn update(&self, supplier: &Supplier) -> Result<()> {
+    /// Update a supplier, recording field-level changes with `reason`.
+    /// `supplier.row_version` must be the stored version (else `Conflict`);
+    /// returns the incremented version.
+    pub fn update(&self, supplier: &Supplier, changed_by: &str, reason: &ChangeReason) -> Result<i64> {
         self.db.with_connection(|conn| {
-            conn.execute(
+            let tx = conn.unchecked_transaction()?;
+            let previous = self.fetch_with(&tx, &supplier.id)?.ok_or_else(|| QmsError::NotFound {
+                resource: "supplier".to_string(),
+                id: supplier.id.to_string(),
+            })?;
+            check_version("supplier", &supplier.id.to_string(), supplier.row_version, previous.row_version)?;
+            let updated = tx.execute(
                 "UPDATE suppliers SET
                     name = ?2,
                     contact_info = ?3,
@@ -46,8 +75,9 @@ impl SupplierRepository {
                     qualification_date = ?5,
                     qualification_expiry_date = ?6,
                     approved_by = ?7,
-                    updated_at = ?8
-                 WHERE id = ?1",
+                    updated_at = ?8,
+                    row_version = row_version + 1
+                 WHERE id = ?1 AND site_id = ?9 AND row_version = ?10",
                 params![
                     supplier.id.to_string(),
                     supplier.name,
@@ -57,26 +87,46 @@ impl SupplierRepository {
                     supplier.qualification_expiry_date.map(|d| d.to_string()),
                     supplier.approved_by,
                     supplier.updated_at.to_rfc3339(),
+                    self.site_id,
+                    supplier.row_version,
                 ],
             )?;
-            Ok(())
+            if updated == 0 {
+                // Another connection committed between our read and write
+                let actual = self.fetch_with(&tx, &supplier.id)?.map_or(supplier.row_version, |s| s.row_version);
+                return Err(QmsError::Conflict {
+                    resource: "supplier".to_string(),
+                    id: supplier.id.to_string(),
+                    expected: supplier.row_version,
+                    actual,
+                });
+            }
+            let current = Supplier { row_version: supplier.row_version + 1, ..supplier.clone() };
+            let changes = diff_fields(&previous, &current, &["created_at", "updated_at", "row_version"])?;
+            record_changes(&tx, "supplier", &supplier.id.to_string(), changed_by, reason, &changes)?;
+            snapshot_with(&tx, &current, changed_by, chrono::Utc::now())?;
+            invalidate_with(&tx, Summary::Supplier)?;
+            tx.commit()?;
+            Ok(current.row_version)
         })
     }
 
     pub fn fetch_by_id(&self, id: &Uuid) -> Result<Option<Supplier>> {
-        self.db.with_connection(|conn| {
-            let mut stmt = conn.prepare(
-                "SELECT id, name, contact_info, qualification_status, qualification_date,
-                        qualification_expiry_date, approved_by, created_at, updated_at
-                 FROM suppliers WHERE id = ?1",
-            )?;
-            let mut rows = stmt.query(params![id.to_string()])?;
-            if let Some(row) = rows.next()? {
-                Ok(Some(self.row_to_supplier(row)?))
-            } else {
-                Ok(None)
-            }
-        })
+        self.db.with_connection(|conn| self.fetch_with(conn, id))
+    }
+
+    fn fetch_with(&self, conn: &Connection, id: &Uuid) -> Result<Option<Supplier>> {
+        let mut stmt = conn.prepare(
+            "SELECT id, name, contact_info, qualification_status, qualification_date,
+                    qualification_expiry_date, approved_by, created_at, updated_at, row_version
+             FROM suppliers WHERE id = ?1 AND site_id = ?2 AND deleted_at IS NULL",
+        )?;
+        let mut rows = stmt.query(params![id.to_string(), self.site_id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(self.row_to_supplier(row)?))
+        } else {
+            Ok(None)
+        }
     }
 
     fn row_to_supplier(&self, row: &rusqlite::Row) -> rusqlite::Result<Supplier> {
@@ -107,6 +157,7 @@ impl SupplierRepository {
             updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(8)?)
                 .unwrap()
                 .with_timezone(&chrono::Utc),
+            row_version: row.get(9)?,
         })
     }
 }
@@ -117,7 +168,14 @@ mod tests {
     use crate::config::DatabaseConfig;
 
     fn setup_repo() -> SupplierRepository {
-        let db = Database::new(DatabaseConfig::default()).unwrap();
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 1,
+        })
+        .unwrap();
         SupplierRepository::new(db)
     }
 
@@ -134,10 +192,64 @@ mod tests {
             approved_by: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
+            row_version: 1,
         };
         repo.insert(&supplier).unwrap();
         let fetched = repo.fetch_by_id(&supplier.id).unwrap();
         assert!(fetched.is_some());
         assert_eq!(fetched.unwrap().name, supplier.name);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_update_records_change_history() {
+        let repo = setup_repo();
+        repo.db.seed_test_users(&["qa_manager"]);
+        let mut supplier = Supplier {
+            id: Uuid::new_v4(),
+            name: "VendorY".to_string(),
+            contact_info: None,
+            status: SupplierStatus::Pending,
+            qualification_date: None,
+            qualification_expiry_date: None,
+            approved_by: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            row_version: 1,
+        };
+        repo.insert(&supplier).unwrap();
+
+        supplier.status = SupplierStatus::Qualified;
+        supplier.approved_by = Some("qa_manager".to_string());
+        supplier.updated_at = chrono::Utc::now();
+        let reason = ChangeReason::new("On-site audit passed").unwrap();
+        assert_eq!(repo.update(&supplier, "qa_manager", &reason).unwrap(), 2);
+        assert_eq!(repo.fetch_by_id(&supplier.id).unwrap().unwrap().row_version, 2);
+
+        let history = crate::change_history::ChangeHistoryRepo::new(&repo.db)
+            .history("supplier", &supplier.id.to_string())
+            .unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].reason, reason);
+        let fields: Vec<&str> = history[0].changes.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, vec!["approved_by", "status"]);
+        assert_eq!(history[0].changes[1].old_value.as_deref(), Some("Pending"));
+
+        let mut missing = supplier.clone();
+        missing.id = Uuid::new_v4();
+        assert!(matches!(repo.update(&missing, "qa_manager", &reason), Err(QmsError::NotFound { .. })));
+
+        // A second editor still holding version 1 must not overwrite the change
+        supplier.status = SupplierStatus::Disqualified;
+        assert!(matches!(
+            repo.update(&supplier, "qa_manager", &reason),
+            Err(QmsError::Conflict { expected: 1, actual: 2, .. })
+        ));
+        assert_eq!(repo.fetch_by_id(&supplier.id).unwrap().unwrap().status, SupplierStatus::Qualified);
+
+        let versions = crate::record_history::RecordHistoryRepo::new(&repo.db)
+            .versions("supplier", &supplier.id.to_string())
+            .unwrap();
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[1].captured_by, "qa_manager");
+    }
+}
@@ -0,0 +1,431 @@
+//! # Tasks - Ad Hoc Quality Work Assignments
+//!
+//! Not all quality work fits a CAPA or an NCR: reviewing a supplier response,
+//! checking a label proof, following up on an audit observation. A [`Task`]
+//! gives such work a title, a type, a due date and an assignee, optionally
+//! linked to the record it concerns, so the assignment stays traceable. Any
+//! module can create one through [`TaskRepo::create`]; creation, reassignment
+//! and completion are audited.
+//!
+//! [`TaskInbox`] aggregates everything open for one user: their tasks, the
+//! CAPAs and CAPA actions assigned to them, the audit findings they own,
+//! their pending training and the open records of plugin types assigned to
+//! them, ordered by due date.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::links::RecordRef;
+
+/// Kind of work a task asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskKind {
+    Investigation,
+    Review,
+    Verification,
+    FollowUp,
+    Other,
+}
+
+impl TaskKind {
+    pub const ALL: [TaskKind; 5] =
+        [TaskKind::Investigation, TaskKind::Review, TaskKind::Verification, TaskKind::FollowUp, TaskKind::Other];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskKind::Investigation => "investigation",
+            TaskKind::Review => "review",
+            TaskKind::Verification => "verification",
+            TaskKind::FollowUp => "follow_up",
+            TaskKind::Other => "other",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_ascii_lowercase().replace('-', "_");
+        Self::ALL.into_iter().find(|kind| kind.as_str() == value)
+    }
+}
+
+/// Lifecycle of a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Open,
+    Completed,
+}
+
+impl TaskStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Open => "open",
+            TaskStatus::Completed => "completed",
+        }
+    }
+}
+
+/// A unit of ad hoc quality work assigned to one user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Task {
+    pub id: Uuid,
+    pub title: String,
+    pub kind: TaskKind,
+    pub due_date: NaiveDate,
+    /// Record the work concerns, e.g. `supplier:…` or `lot:L-2025-042`
+    pub linked_record: Option<RecordRef>,
+    pub assignee: String,
+    pub status: TaskStatus,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub completed_by: Option<String>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl Task {
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        self.status == TaskStatus::Open && self.due_date < today
+    }
+}
+
+/// Task records.
+pub struct TaskRepo<'a> {
+    db: &'a Database,
+}
+
+impl<'a> TaskRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Assign a new task to `assignee`, optionally linked to the record it
+    /// concerns.
+    pub fn create(
+        &self,
+        title: &str,
+        kind: TaskKind,
+        due_date: NaiveDate,
+        linked_record: Option<&RecordRef>,
+        assignee: &str,
+        created_by: &str,
+    ) -> Result<Task> {
+        let title = title.trim();
+        if title.is_empty() {
+            return Err(QmsError::Validation { field: "title".to_string(), message: "Title is required".to_string() });
+        }
+        let id = Uuid::new_v4();
+        self.db.unit_of_work(|uow| {
+            ensure_user(uow.connection(), assignee)?;
+            uow.connection().execute(
+                "INSERT INTO tasks (id, title, kind, due_date, linked_record, assignee, status, created_by, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, 'open', ?7, ?8)",
+                params![
+                    id.to_string(),
+                    title,
+                    kind.as_str(),
+                    due_date.to_string(),
+                    linked_record.map(RecordRef::to_string),
+                    assignee,
+                    created_by,
+                    Utc::now().to_rfc3339(),
+                ],
+            )?;
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                created_by,
+                "task_created",
+                &format!("task:{}", id),
+                "Success",
+                Some(format!("{} '{}' assigned to {}, due {}", kind.as_str(), title, assignee, due_date)),
+            )
+        })?;
+        self.get(id)
+    }
+
+    /// Hand an open task to another user.
+    pub fn reassign(&self, task_id: Uuid, assignee: &str, user: &str) -> Result<Task> {
+        let task = self.open_task(task_id)?;
+        self.db.unit_of_work(|uow| {
+            ensure_user(uow.connection(), assignee)?;
+            uow.connection().execute(
+                "UPDATE tasks SET assignee = ?2 WHERE id = ?1",
+                params![task_id.to_string(), assignee],
+            )?;
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                user,
+                "task_reassigned",
+                &format!("task:{}", task_id),
+                "Success",
+                Some(format!("From {} to {}", task.assignee, assignee)),
+            )
+        })?;
+        self.get(task_id)
+    }
+
+    /// Mark an open task as done.
+    pub fn complete(&self, task_id: Uuid, user: &str) -> Result<Task> {
+        self.open_task(task_id)?;
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE tasks SET status = 'completed', completed_by = ?2, completed_at = ?3 WHERE id = ?1",
+                params![task_id.to_string(), user, Utc::now().to_rfc3339()],
+            )?;
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                user,
+                "task_completed",
+                &format!("task:{}", task_id),
+                "Success",
+                None,
+            )
+        })?;
+        self.get(task_id)
+    }
+
+    pub fn get(&self, task_id: Uuid) -> Result<Task> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM tasks WHERE id = ?1", TASK_COLUMNS),
+                        params![task_id.to_string()],
+                        row_to_task,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "task".to_string(), id: task_id.to_string() })
+    }
+
+    /// Tasks linked to a record, oldest first.
+    pub fn for_record(&self, record: &RecordRef) -> Result<Vec<Task>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM tasks WHERE linked_record = ?1 ORDER BY created_at",
+                TASK_COLUMNS
+            ))?;
+            let tasks = stmt.query_map(params![record.to_string()], row_to_task)?.collect::<rusqlite::Result<_>>()?;
+            Ok(tasks)
+        })
+    }
+
+    fn open_task(&self, task_id: Uuid) -> Result<Task> {
+        let task = self.get(task_id)?;
+        if task.status != TaskStatus::Open {
+            return Err(QmsError::Validation {
+                field: "status".to_string(),
+                message: format!("Task {} is already {}", task_id, task.status.as_str()),
+            });
+        }
+        Ok(task)
+    }
+}
+
+/// One open item in a user's inbox.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InboxItem {
+    /// The task, CAPA, CAPA action, audit finding, training, document due for
+    /// periodic review or plugin record
+    pub record: RecordRef,
+    pub title: String,
+    pub due_date: Option<NaiveDate>,
+    pub overdue: bool,
+}
+
+/// Open work assigned to a user across modules.
+pub struct TaskInbox<'a> {
+    db: &'a Database,
+}
+
+impl<'a> TaskInbox<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Everything open for `user`, earliest due first; items without a due
+    /// date come last.
+    pub fn for_user(&self, user: &str, today: NaiveDate) -> Result<Vec<InboxItem>> {
+        let sources = [
+            ("task", "SELECT id, title, due_date FROM tasks WHERE assignee = ?1 AND status = 'open'"),
+            (
+                "capa",
+                "SELECT id, title, due_date FROM capa_records
+                 WHERE assigned_to = ?1 AND status NOT IN ('Closed', 'Cancelled') AND deleted_at IS NULL",
+            ),
+            (
+                "capa_action",
+                "SELECT id, description, due_date FROM capa_actions
+                 WHERE assigned_to = ?1 AND status IN ('Planned', 'InProgress', 'Overdue') AND deleted_at IS NULL",
+            ),
+            (
+                "audit_finding",
+                "SELECT id, description, committed_date FROM audit_findings WHERE owner = ?1 AND closed_at IS NULL",
+            ),
+            (
+                "training",
+                "SELECT id, training_item, due_date FROM training_records
+                 WHERE employee_id = ?1 AND status != 'Completed' AND deleted_at IS NULL",
+            ),
+            // Periodic reviews go to the document's owner, its author until one is assigned
+            (
+                "document",
+                "SELECT id, 'Periodic review of ' || document_number || ' ' || title, review_date FROM documents
+                 WHERE COALESCE(owner, created_by) = ?1 AND review_date IS NOT NULL
+                   AND status NOT IN ('Obsolete', 'Retired') AND deleted_at IS NULL",
+            ),
+        ];
+        let mut items = Vec::new();
+        self.db.with_connection(|conn| {
+            let mut rows: Vec<rusqlite::Result<(String, String, String, Option<String>)>> = Vec::new();
+            for (record_type, sql) in sources {
+                let mut stmt = conn.prepare(sql)?;
+                let source = stmt.query_map(params![user], |row| {
+                    Ok((record_type.to_string(), row.get(0)?, row.get(1)?, row.get(2)?))
+                })?;
+                rows.extend(source);
+            }
+            // Plugin records carry their own type
+            let mut stmt = conn.prepare(
+                "SELECT record_type, id, title, due_date FROM custom_records WHERE assignee = ?1 AND is_open = 1",
+            )?;
+            rows.extend(stmt.query_map(params![user], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?);
+            for row in rows {
+                let (record_type, id, title, due_date) = row?;
+                // Due dates are stored as dates or RFC 3339 timestamps
+                let due_date = due_date.and_then(|value| value.get(..10).and_then(|day| day.parse().ok()));
+                items.push(InboxItem {
+                    record: RecordRef::new(record_type, id),
+                    title,
+                    due_date,
+                    overdue: due_date.is_some_and(|due| due < today),
+                });
+            }
+            Ok(())
+        })?;
+        items.sort_by(|a, b| match (a.due_date, b.due_date) {
+            (Some(a_due), Some(b_due)) => a_due.cmp(&b_due),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        });
+        Ok(items)
+    }
+}
+
+const TASK_COLUMNS: &str =
+    "id, title, kind, due_date, linked_record, assignee, status, created_by, created_at, completed_by, completed_at";
+
+fn ensure_user(conn: &rusqlite::Connection, user: &str) -> Result<()> {
+    let active: bool = conn.query_row(
+        "SELECT EXISTS (SELECT 1 FROM users WHERE id = ?1 AND is_active = 1 AND deleted_at IS NULL)",
+        params![user],
+        |row| row.get(0),
+    )?;
+    if !active {
+        return Err(QmsError::Validation {
+            field: "assignee".to_string(),
+            message: format!("'{}' is not an active user", user),
+        });
+    }
+    Ok(())
+}
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn parse_timestamp(row: &Row, index: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|timestamp| timestamp.with_timezone(&Utc))
+                .map_err(|e| conversion_error(index, e.to_string()))
+        })
+        .transpose()
+}
+
+fn row_to_task(row: &Row) -> rusqlite::Result<Task> {
+    let id: String = row.get(0)?;
+    let kind: String = row.get(2)?;
+    let due_date: String = row.get(3)?;
+    let linked_record = row
+        .get::<_, Option<String>>(4)?
+        .map(|value| RecordRef::parse(&value).map_err(|e| conversion_error(4, e.to_string())))
+        .transpose()?;
+    let status: String = row.get(6)?;
+    Ok(Task {
+        id: Uuid::parse_str(&id).map_err(|e| conversion_error(0, e.to_string()))?,
+        title: row.get(1)?,
+        kind: TaskKind::parse(&kind).ok_or_else(|| conversion_error(2, format!("unknown task kind {}", kind)))?,
+        due_date: NaiveDate::parse_from_str(&due_date, "%Y-%m-%d").map_err(|e| conversion_error(3, e.to_string()))?,
+        linked_record,
+        assignee: row.get(5)?,
+        status: if status == TaskStatus::Completed.as_str() { TaskStatus::Completed } else { TaskStatus::Open },
+        created_by: row.get(7)?,
+        created_at: parse_timestamp(row, 8)?.ok_or_else(|| conversion_error(8, "missing creation time".to_string()))?,
+        completed_by: row.get(9)?,
+        completed_at: parse_timestamp(row, 10)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use chrono::Duration;
+
+    #[test]
+    fn test_tasks_and_assigned_work_in_inbox() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["qe1", "qe2", "qm"]);
+        let today = Utc::now().date_naive();
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO training_records (id, employee_id, training_item, mandatory, assigned_by, due_date, status)
+                 VALUES ('tr1', 'qe1', 'SOP-001', 1, 'qm', ?1, 'Pending')",
+                params![(today + Duration::days(30)).to_string()],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        let repo = TaskRepo::new(&db);
+        let supplier = RecordRef::new("supplier", "acme");
+
+        let due = |days: i64| today + Duration::days(days);
+        let review =
+            repo.create("Review supplier 8D response", TaskKind::Review, due(5), Some(&supplier), "qe1", "qm").unwrap();
+        let label = repo.create("Check label proof", TaskKind::Verification, due(-1), None, "qe2", "qm").unwrap();
+        assert!(repo.create(" ", TaskKind::Other, today, None, "qe1", "qm").is_err());
+        assert!(repo.create("Follow up", TaskKind::FollowUp, today, None, "nobody", "qm").is_err());
+        assert_eq!(repo.for_record(&supplier).unwrap(), vec![review.clone()]);
+        assert_eq!(TaskKind::parse("follow-up"), Some(TaskKind::FollowUp));
+
+        let label = repo.reassign(label.id, "qe1", "qm").unwrap();
+        assert_eq!(label.assignee, "qe1");
+        assert!(label.is_overdue(today));
+        let inbox = TaskInbox::new(&db).for_user("qe1", today).unwrap();
+        let records: Vec<String> = inbox.iter().map(|item| item.record.to_string()).collect();
+        assert_eq!(records, [format!("task:{}", label.id), format!("task:{}", review.id), "training:tr1".to_string()]);
+        assert!(inbox[0].overdue && !inbox[1].overdue);
+
+        let done = repo.complete(label.id, "qe1").unwrap();
+        assert_eq!((done.status, done.completed_by.as_deref()), (TaskStatus::Completed, Some("qe1")));
+        assert!(repo.complete(label.id, "qe1").is_err());
+        assert_eq!(TaskInbox::new(&db).for_user("qe1", today).unwrap().len(), 2);
+        assert!(TaskInbox::new(&db).for_user("qe2", today).unwrap().is_empty());
+        let audit = db.get_audit_entries_for_resource(&format!("task:{}", label.id)).unwrap();
+        assert_eq!(audit.len(), 3);
+    }
+}
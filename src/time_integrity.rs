@@ -0,0 +1,332 @@
+//! # Time Integrity - NTP Drift Detection
+//!
+//! Audit trail timestamps are only as trustworthy as the system clock. The
+//! [`TimeIntegrityMonitor`] compares the clock against the configured NTP
+//! servers (SNTP, RFC 4330) at startup and every `check_interval_minutes`.
+//! Drift beyond `max_drift_ms` is recorded as a critical audit event and,
+//! when `block_signatures_on_drift` is set, new electronic signatures are
+//! refused through the shared [`SignatureGuard`] until a later check is back
+//! within tolerance.
+
+use std::net::UdpSocket;
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::audit::AuditManager;
+use crate::config::TimeIntegrityConfig;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+
+/// Seconds between the NTP epoch (1900-01-01) and the Unix epoch.
+const NTP_UNIX_EPOCH_DELTA: i64 = 2_208_988_800;
+const NTP_PACKET_LEN: usize = 48;
+
+/// Outcome of one comparison against the NTP servers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockCheck {
+    pub checked_at: DateTime<Utc>,
+    /// Median offset of the responding servers (positive: local clock is behind)
+    pub offset_ms: Option<i64>,
+    pub servers_queried: usize,
+    pub servers_responding: usize,
+    /// False only when a measured offset exceeds the configured tolerance
+    pub within_tolerance: bool,
+}
+
+/// Cloneable handle consulted before an electronic signature is applied.
+#[derive(Debug, Clone)]
+pub struct SignatureGuard {
+    block_on_drift: bool,
+    max_drift_ms: i64,
+    last_check: Arc<RwLock<Option<ClockCheck>>>,
+}
+
+impl SignatureGuard {
+    /// Latest completed clock check, if any.
+    pub fn last_check(&self) -> Option<ClockCheck> {
+        self.last_check.read().ok().and_then(|check| check.clone())
+    }
+
+    /// Refuse signing while the last check found unresolved drift.
+    pub fn ensure_signing_allowed(&self) -> Result<()> {
+        if !self.block_on_drift {
+            return Ok(());
+        }
+        match self.last_check() {
+            Some(check) if !check.within_tolerance => Err(QmsError::Security {
+                message: format!(
+                    "Electronic signatures are blocked: system clock drift of {} ms exceeds {} ms",
+                    check.offset_ms.unwrap_or_default(),
+                    self.max_drift_ms
+                ),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Periodically verifies the system clock and records drift in the audit trail.
+pub struct TimeIntegrityMonitor {
+    config: TimeIntegrityConfig,
+    audit: AuditManager,
+    guard: SignatureGuard,
+}
+
+impl TimeIntegrityMonitor {
+    pub fn new(config: TimeIntegrityConfig, database: Database) -> Self {
+        let guard = SignatureGuard {
+            block_on_drift: config.block_signatures_on_drift,
+            max_drift_ms: config.max_drift_ms as i64,
+            last_check: Arc::new(RwLock::new(None)),
+        };
+        Self {
+            config,
+            audit: AuditManager::new(database),
+            guard,
+        }
+    }
+
+    /// Guard to hand to signing components.
+    pub fn guard(&self) -> SignatureGuard {
+        self.guard.clone()
+    }
+
+    /// Query every configured server and evaluate the result.
+    pub fn check_now(&self) -> Result<ClockCheck> {
+        let timeout = std::time::Duration::from_millis(self.config.timeout_ms);
+        let offsets: Vec<Duration> = self
+            .config
+            .ntp_servers
+            .iter()
+            .filter_map(|server| match query_offset(server, timeout) {
+                Ok(offset) => Some(offset),
+                Err(e) => {
+                    tracing::warn!(server = %server, error = %e, "NTP query failed");
+                    None
+                }
+            })
+            .collect();
+        self.evaluate(&offsets, Utc::now())
+    }
+
+    /// Record a check from measured server offsets. A check where no server
+    /// answered is logged as a warning but leaves any existing block in place.
+    pub fn evaluate(&self, offsets: &[Duration], now: DateTime<Utc>) -> Result<ClockCheck> {
+        let was_within = self.guard.last_check().is_none_or(|check| check.within_tolerance);
+        let offset_ms = median_ms(offsets);
+        let within_tolerance = match offset_ms {
+            Some(offset) => offset.abs() <= self.config.max_drift_ms as i64,
+            None => was_within,
+        };
+        let check = ClockCheck {
+            checked_at: now,
+            offset_ms,
+            servers_queried: self.config.ntp_servers.len(),
+            servers_responding: offsets.len(),
+            within_tolerance,
+        };
+
+        let details = serde_json::json!({
+            "offset_ms": offset_ms,
+            "max_drift_ms": self.config.max_drift_ms,
+            "servers_responding": check.servers_responding,
+            "servers_queried": check.servers_queried,
+            "signatures_blocked": !within_tolerance && self.config.block_signatures_on_drift,
+        });
+        if offset_ms.is_none() {
+            let mut details = details;
+            details["severity"] = "warning".into();
+            self.audit
+                .log_action("system", "clock_check_unavailable", "system_clock", "Warning", Some(details.to_string()))?;
+        } else if !within_tolerance {
+            let mut details = details;
+            details["severity"] = "critical".into();
+            self.audit
+                .log_action("system", "clock_drift_detected", "system_clock", "Failure", Some(details.to_string()))?;
+        } else if !was_within {
+            self.audit
+                .log_action("system", "clock_drift_resolved", "system_clock", "Success", Some(details.to_string()))?;
+        }
+
+        if let Ok(mut last) = self.guard.last_check.write() {
+            *last = Some(check.clone());
+        }
+        Ok(check)
+    }
+
+    /// Run `check_now` immediately and then every `check_interval_minutes`.
+    pub fn spawn_periodic(self) -> tokio::task::JoinHandle<()> {
+        let monitor = Arc::new(self);
+        tokio::spawn(async move {
+            let period = std::time::Duration::from_secs(u64::from(monitor.config.check_interval_minutes.max(1)) * 60);
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                let monitor = monitor.clone();
+                match tokio::task::spawn_blocking(move || monitor.check_now()).await {
+                    Ok(Err(e)) => tracing::error!(error = %e, "Clock integrity check failed"),
+                    Err(e) => tracing::error!(error = %e, "Clock integrity task panicked"),
+                    Ok(Ok(_)) => {}
+                }
+            }
+        })
+    }
+}
+
+/// Offset of the local clock from `server` (`host` or `host:port`) via SNTP.
+pub fn query_offset(server: &str, timeout: std::time::Duration) -> Result<Duration> {
+    let address = if server.contains(':') { server.to_string() } else { format!("{}:123", server) };
+    let network_error = |e: std::io::Error| QmsError::Network {
+        message: format!("NTP query to {} failed: {}", address, e),
+    };
+
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(network_error)?;
+    socket.set_read_timeout(Some(timeout)).map_err(network_error)?;
+    socket.connect(&address).map_err(network_error)?;
+
+    let mut request = [0u8; NTP_PACKET_LEN];
+    request[0] = 0x23; // LI 0, version 4, mode 3 (client)
+    let originate = to_ntp_timestamp(Utc::now());
+    request[40..48].copy_from_slice(&originate.to_be_bytes());
+    socket.send(&request).map_err(network_error)?;
+
+    let mut response = [0u8; NTP_PACKET_LEN];
+    let received = socket.recv(&mut response).map_err(network_error)?;
+    let destination = Utc::now();
+    if received < NTP_PACKET_LEN {
+        return Err(QmsError::Network {
+            message: format!("NTP response from {} is truncated ({} bytes)", address, received),
+        });
+    }
+    parse_offset(&response, originate, destination).ok_or_else(|| QmsError::Network {
+        message: format!("Invalid NTP response from {}", address),
+    })
+}
+
+/// Clock offset `((t2 - t1) + (t3 - t4)) / 2` from a server reply, checking it
+/// answers our request and is not a kiss-of-death packet.
+fn parse_offset(response: &[u8; NTP_PACKET_LEN], originate: u64, destination: DateTime<Utc>) -> Option<Duration> {
+    let mode = response[0] & 0x07;
+    let stratum = response[1];
+    let read = |at: usize| u64::from_be_bytes(response[at..at + 8].try_into().expect("8-byte slice"));
+    if mode != 4 || stratum == 0 || read(24) != originate {
+        return None;
+    }
+    let t1 = from_ntp_timestamp(originate)?;
+    let t2 = from_ntp_timestamp(read(32))?;
+    let t3 = from_ntp_timestamp(read(40))?;
+    Some(((t2 - t1) + (t3 - destination)) / 2)
+}
+
+fn to_ntp_timestamp(at: DateTime<Utc>) -> u64 {
+    let seconds = (at.timestamp() + NTP_UNIX_EPOCH_DELTA) as u64;
+    let fraction = (u64::from(at.timestamp_subsec_nanos()) << 32) / 1_000_000_000;
+    (seconds << 32) | fraction
+}
+
+fn from_ntp_timestamp(value: u64) -> Option<DateTime<Utc>> {
+    let seconds = (value >> 32) as i64 - NTP_UNIX_EPOCH_DELTA;
+    let nanos = (((value & 0xFFFF_FFFF) * 1_000_000_000) >> 32) as u32;
+    Utc.timestamp_opt(seconds, nanos).single()
+}
+
+fn median_ms(offsets: &[Duration]) -> Option<i64> {
+    let mut millis: Vec<i64> = offsets.iter().map(Duration::num_milliseconds).collect();
+    millis.sort_unstable();
+    match millis.len() {
+        0 => None,
+        n if n % 2 == 1 => Some(millis[n / 2]),
+        n => Some((millis[n / 2 - 1] + millis[n / 2]) / 2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    /// Answer one SNTP request as a server whose clock is `skew` ahead.
+    fn fake_ntp_server(skew: Duration) -> String {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = socket.local_addr().unwrap().to_string();
+        std::thread::spawn(move || {
+            let mut request = [0u8; NTP_PACKET_LEN];
+            let (_, peer) = socket.recv_from(&mut request).unwrap();
+            let server_now = to_ntp_timestamp(Utc::now() + skew);
+            let mut response = [0u8; NTP_PACKET_LEN];
+            response[0] = 0x24; // version 4, mode 4 (server)
+            response[1] = 2;
+            response[24..32].copy_from_slice(&request[40..48]);
+            response[32..40].copy_from_slice(&server_now.to_be_bytes());
+            response[40..48].copy_from_slice(&server_now.to_be_bytes());
+            socket.send_to(&response, peer).unwrap();
+        });
+        address
+    }
+
+    #[test]
+    fn test_sntp_offset_measurement() {
+        let server = fake_ntp_server(Duration::seconds(30));
+        let offset = query_offset(&server, std::time::Duration::from_secs(2)).unwrap();
+        assert!((offset - Duration::seconds(30)).num_milliseconds().abs() < 500, "offset {:?}", offset);
+
+        let at = Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap() + Duration::milliseconds(250);
+        let back = from_ntp_timestamp(to_ntp_timestamp(at)).unwrap();
+        assert!((back - at).num_microseconds().unwrap().abs() <= 1);
+    }
+
+    #[test]
+    fn test_drift_blocks_signatures_until_resolved() {
+        let db = test_db();
+        let monitor = TimeIntegrityMonitor::new(TimeIntegrityConfig::default(), db.clone());
+        let guard = monitor.guard();
+        let now = Utc::now();
+
+        monitor.evaluate(&[Duration::milliseconds(5_000), Duration::milliseconds(4_000)], now).unwrap();
+        assert!(matches!(guard.ensure_signing_allowed(), Err(QmsError::Security { .. })));
+
+        // An unreachable pool does not lift the block.
+        let check = monitor.evaluate(&[], now).unwrap();
+        assert!(!check.within_tolerance);
+        assert!(guard.ensure_signing_allowed().is_err());
+
+        monitor.evaluate(&[Duration::milliseconds(20)], now).unwrap();
+        assert!(guard.ensure_signing_allowed().is_ok());
+
+        let actions: Vec<String> = db
+            .get_audit_entries_for_resource("system_clock")
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.action)
+            .collect();
+        assert!(actions.contains(&"clock_drift_detected".to_string()));
+        assert!(actions.contains(&"clock_check_unavailable".to_string()));
+        assert!(actions.contains(&"clock_drift_resolved".to_string()));
+    }
+
+    #[test]
+    fn test_blocking_is_configurable() {
+        let config = TimeIntegrityConfig {
+            block_signatures_on_drift: false,
+            ..TimeIntegrityConfig::default()
+        };
+        let monitor = TimeIntegrityMonitor::new(config, test_db());
+        let check = monitor.evaluate(&[Duration::seconds(-10)], Utc::now()).unwrap();
+        assert_eq!(check.offset_ms, Some(-10_000));
+        assert!(!check.within_tolerance);
+        assert!(monitor.guard().ensure_signing_allowed().is_ok());
+    }
+}
@@ -13,11 +13,14 @@
 //! * Mark trainings complete with competency verification.
 //! * Generate training metrics for dashboards & audits.
 
-use crate::{audit::AuditLogger, error::Result};
+use crate::{audit::AuditLogger, change_history::ChangeReason, error::Result};
+use crate::clock::{system_clock, SharedClock};
+use crate::concurrency::{initial_row_version, INITIAL_ROW_VERSION};
 use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use crate::training_repo::TrainingRepository;
+use crate::store::TrainingStore;
 
 /// Training status lifecycle
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -41,19 +44,32 @@ pub struct TrainingRecord {
     pub status: TrainingStatus,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Optimistic concurrency version; updates must present the current one
+    #[serde(default = "initial_row_version")]
+    pub row_version: i64,
 }
 
 impl TrainingRecord {
-    /// Check and update status based on dates.
-    fn refresh_status(&mut self) {
-        if self.status == TrainingStatus::Completed {
-            return;
-        }
-        let today = Utc::now().date_naive();
-        if today > self.due_date {
-            self.status = TrainingStatus::Overdue;
+    /// Status as it stood on `today`: a completion recorded later does not
+    /// count yet, and an open training past its due date is overdue.
+    pub fn status_on(&self, today: NaiveDate) -> TrainingStatus {
+        let completed = self.status == TrainingStatus::Completed
+            && self.completion_date.map_or(true, |completed_on| completed_on <= today);
+        if completed {
+            TrainingStatus::Completed
+        } else if today > self.due_date {
+            TrainingStatus::Overdue
+        } else if matches!(self.status, TrainingStatus::Completed | TrainingStatus::Overdue) {
+            TrainingStatus::Pending
+        } else {
+            self.status
         }
     }
+
+    /// Check and update status based on dates.
+    pub fn refresh_status(&mut self, today: NaiveDate) {
+        self.status = self.status_on(today);
+    }
 }
 
 /// Aggregated metrics for dashboard/reporting
@@ -66,19 +82,30 @@ pub struct TrainingMetrics {
 }
 
 /// Service layer for training management
-pub struct TrainingService {
+///
+/// Generic over its [`TrainingStore`]; defaults to the rusqlite repository.
+#[derive(Clone)]
+pub struct TrainingService<S: TrainingStore = TrainingRepository> {
     audit_logger: AuditLogger,
-    repository: TrainingRepository,
+    repository: S,
+    clock: SharedClock,
 }
 
-impl TrainingService {
-    pub fn new(audit_logger: AuditLogger, repository: TrainingRepository) -> Self {
+impl<S: TrainingStore> TrainingService<S> {
+    pub fn new(audit_logger: AuditLogger, repository: S) -> Self {
         Self {
             audit_logger,
             repository,
+            clock: system_clock(),
         }
     }
 
+    /// Read the current time from `clock` for timestamps and overdue status.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Assign a new training to employee
     pub async fn create_training_record(
         &self,
@@ -88,6 +115,7 @@ impl TrainingService {
         due_date: NaiveDate,
         assigned_by: String,
     ) -> Result<TrainingRecord> {
+        let now = self.clock.now();
         let record = TrainingRecord {
             id: Uuid::new_v4(),
             employee_id: employee_id.clone(),
@@ -97,8 +125,9 @@ impl TrainingService {
             due_date,
             completion_date: None,
             status: TrainingStatus::Pending,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
+            created_at: now,
+            updated_at: now,
+            row_version: INITIAL_ROW_VERSION,
         };
 
         // Persist to database
@@ -121,19 +150,23 @@ impl TrainingService {
         Ok(record)
     }
 
-    /// Mark training as completed with competency verification flag
+    /// Mark training as completed with competency verification flag.
+    /// Fails with `Conflict` when `record` is stale; on success it carries
+    /// the new version.
     pub async fn mark_completed(
         &self,
         record: &mut TrainingRecord,
         completed_by: String,
         competency_verified: bool,
+        reason: &ChangeReason,
     ) -> Result<()> {
-        record.completion_date = Some(Utc::now().date_naive());
+        let now = self.clock.now();
+        record.completion_date = Some(now.date_naive());
         record.status = TrainingStatus::Completed;
-        record.updated_at = Utc::now();
+        record.updated_at = now;
 
         // Persist update first
-        self.repository.update(record)?;
+        record.row_version = self.repository.update(record, &completed_by, reason)?;
 
         // Audit
         self.audit_logger
@@ -142,18 +175,20 @@ impl TrainingService {
                 "COMPLETE_TRAINING",
                 &format!("training:{}", record.id),
                 "SUCCESS",
-                Some(format!("competency_verified={}", competency_verified)),
+                Some(format!("competency_verified={}; reason={}", competency_verified, reason)),
             )
             .await?;
         Ok(())
     }
 
-    /// Compute high-level metrics from records slice
+    /// Compute high-level metrics from records slice, with each status
+    /// refreshed to the service clock's current date.
     pub fn calculate_metrics(&self, records: &[TrainingRecord]) -> TrainingMetrics {
         let mut metrics = TrainingMetrics::default();
         metrics.total_count = records.len();
+        let today = self.clock.today();
         for rec in records {
-            match rec.status {
+            match rec.status_on(today) {
                 TrainingStatus::Completed => metrics.completed += 1,
                 TrainingStatus::Overdue => metrics.overdue += 1,
                 _ => metrics.pending += 1,
@@ -183,6 +218,7 @@ mod tests {
             backup_retention_days: 1,
         })
         .unwrap();
+        db.seed_test_users(&["emp1", "emp2", "emp3", "manager1", "manager"]);
         let repo = TrainingRepository::new(db);
         TrainingService::new(test_logger(), repo)
     }
@@ -217,7 +253,7 @@ mod tests {
             .await
             .unwrap();
         service
-            .mark_completed(&mut rec, "emp1".to_string(), true)
+            .mark_completed(&mut rec, "emp1".to_string(), true, &ChangeReason::new("Course completed").unwrap())
             .await
             .unwrap();
         assert_eq!(rec.status, TrainingStatus::Completed);
@@ -240,7 +276,7 @@ mod tests {
             .await
             .unwrap();
         service
-            .mark_completed(&mut rec1, "emp1".to_string(), true)
+            .mark_completed(&mut rec1, "emp1".to_string(), true, &ChangeReason::new("Course completed").unwrap())
             .await
             .unwrap();
         records.push(rec1);
@@ -270,6 +306,7 @@ mod tests {
             status: TrainingStatus::Overdue,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            row_version: 1,
         };
         records.push(rec3);
 
@@ -279,4 +316,32 @@ mod tests {
         assert_eq!(metrics.pending, 1);
         assert_eq!(metrics.overdue, 1);
     }
+
+    #[tokio::test]
+    async fn test_metrics_refresh_status_from_clock() {
+        use crate::clock::MockClock;
+        use chrono::TimeZone;
+        let clock = MockClock::at(Utc.with_ymd_and_hms(2026, 2, 1, 12, 0, 0).unwrap());
+        let service = setup_service().with_clock(clock.shared());
+        let due = NaiveDate::from_ymd_opt(2026, 2, 10).unwrap();
+        let mut record = service
+            .create_training_record("emp1".into(), "Line Clearance".into(), true, due, "manager".into())
+            .await
+            .unwrap();
+        assert_eq!(service.calculate_metrics(std::slice::from_ref(&record)).pending, 1);
+
+        clock.advance(chrono::Duration::days(14));
+        assert_eq!(service.calculate_metrics(std::slice::from_ref(&record)).overdue, 1);
+        service
+            .mark_completed(&mut record, "emp1".into(), true, &ChangeReason::new("Late completion").unwrap())
+            .await
+            .unwrap();
+        assert_eq!(record.completion_date, NaiveDate::from_ymd_opt(2026, 2, 15));
+        assert_eq!(service.calculate_metrics(std::slice::from_ref(&record)).completed, 1);
+
+        // Reconstructed as of the due date, before the completion
+        assert_eq!(record.status_on(due), TrainingStatus::Pending);
+        record.refresh_status(NaiveDate::from_ymd_opt(2026, 2, 12).unwrap());
+        assert_eq!(record.status, TrainingStatus::Overdue);
+    }
 }
\ No newline at end of file
@@ -1,6 +1,10 @@
-use crate::{database::Database, error::Result, training::{TrainingRecord, TrainingStatus}};
+use crate::{database::Database, error::{QmsError, Result}, training::{TrainingRecord, TrainingStatus}};
+use crate::change_history::{diff_fields, record_changes, ChangeReason};
+use crate::concurrency::check_version;
+use crate::reporting_views::{invalidate_with, Summary};
+use crate::site::DEFAULT_SITE_ID;
 use chrono::NaiveDate;
-use rusqlite::params;
+use rusqlite::{params, Connection};
 use uuid::Uuid;
 
 /// Repository layer for `training_records` persistence.
@@ -9,24 +13,33 @@ use uuid::Uuid;
 /// isolated from domain services. All operations are transactional and
 /// leverage the central `Database` abstraction to maintain ACiD
 /// properties required by FDA 21 CFR Part 11.
+#[derive(Clone)]
 pub struct TrainingRepository {
     db: Database,
+    site_id: String,
 }
 
 impl TrainingRepository {
     /// Create a new repository instance.
     pub fn new(db: Database) -> Self {
-        Self { db }
+        Self { db, site_id: DEFAULT_SITE_ID.to_string() }
+    }
+
+    /// Scope the repository to training records of `site_id`.
+    pub fn with_site<S: Into<String>>(mut self, site_id: S) -> Self {
+        self.site_id = site_id.into();
+        self
     }
 
     /// Insert a new training record.
     pub fn insert(&self, record: &TrainingRecord) -> Result<()> {
         self.db.with_connection(|conn| {
-            conn.execute(
+            let tx = conn.unchecked_transaction()?;
+            tx.execute(
                 "INSERT INTO training_records (
                     id, employee_id, training_item, mandatory, assigned_by,
-                    due_date, completion_date, status, created_at, updated_at
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    due_date, completion_date, status, created_at, updated_at, site_id, row_version
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
                 params![
                     record.id.to_string(),
                     record.employee_id,
@@ -38,16 +51,28 @@ impl TrainingRepository {
                     format!("{:?}", record.status),
                     record.created_at.to_rfc3339(),
                     record.updated_at.to_rfc3339(),
+                    self.site_id,
+                    record.row_version,
                 ],
             )?;
+            invalidate_with(&tx, Summary::Training)?;
+            tx.commit()?;
             Ok(())
         })
     }
 
-    /// Update an existing training record.
-    pub fn update(&self, record: &TrainingRecord) -> Result<()> {
+    /// Update an existing training record, recording field-level changes
+    /// with `reason` in the same transaction. `record.row_version` must be
+    /// the stored version (else `Conflict`); returns the incremented version.
+    pub fn update(&self, record: &TrainingRecord, changed_by: &str, reason: &ChangeReason) -> Result<i64> {
         self.db.with_connection(|conn| {
-            conn.execute(
+            let tx = conn.unchecked_transaction()?;
+            let previous = self.fetch_with(&tx, &record.id)?.ok_or_else(|| QmsError::NotFound {
+                resource: "training_record".to_string(),
+                id: record.id.to_string(),
+            })?;
+            check_version("training_record", &record.id.to_string(), record.row_version, previous.row_version)?;
+            let updated = tx.execute(
                 "UPDATE training_records SET
                     employee_id = ?2,
                     training_item = ?3,
@@ -56,8 +81,9 @@ impl TrainingRepository {
                     due_date = ?6,
                     completion_date = ?7,
                     status = ?8,
-                    updated_at = ?9
-                 WHERE id = ?1",
+                    updated_at = ?9,
+                    row_version = row_version + 1
+                 WHERE id = ?1 AND site_id = ?10 AND row_version = ?11",
                 params![
                     record.id.to_string(),
                     record.employee_id,
@@ -68,28 +94,46 @@ impl TrainingRepository {
                     record.completion_date.map(|d| d.to_string()),
                     format!("{:?}", record.status),
                     record.updated_at.to_rfc3339(),
+                    self.site_id,
+                    record.row_version,
                 ],
             )?;
-            Ok(())
+            if updated == 0 {
+                // Another connection committed between our read and write
+                let actual = self.fetch_with(&tx, &record.id)?.map_or(record.row_version, |r| r.row_version);
+                return Err(QmsError::Conflict {
+                    resource: "training_record".to_string(),
+                    id: record.id.to_string(),
+                    expected: record.row_version,
+                    actual,
+                });
+            }
+            let changes = diff_fields(&previous, record, &["created_at", "updated_at", "row_version"])?;
+            record_changes(&tx, "training_record", &record.id.to_string(), changed_by, reason, &changes)?;
+            invalidate_with(&tx, Summary::Training)?;
+            tx.commit()?;
+            Ok(record.row_version + 1)
         })
     }
 
     /// Fetch a single training record by ID.
     pub fn fetch_by_id(&self, id: &Uuid) -> Result<Option<TrainingRecord>> {
-        self.db.with_connection(|conn| {
-            let mut stmt = conn.prepare(
-                "SELECT id, employee_id, training_item, mandatory, assigned_by,
-                        due_date, completion_date, status, created_at, updated_at
-                 FROM training_records WHERE id = ?1",
-            )?;
+        self.db.with_connection(|conn| self.fetch_with(conn, id))
+    }
 
-            let mut rows = stmt.query(params![id.to_string()])?;
-            if let Some(row) = rows.next()? {
-                Ok(Some(self.row_to_record(row)?))
-            } else {
-                Ok(None)
-            }
-        })
+    fn fetch_with(&self, conn: &Connection, id: &Uuid) -> Result<Option<TrainingRecord>> {
+        let mut stmt = conn.prepare(
+            "SELECT id, employee_id, training_item, mandatory, assigned_by,
+                    due_date, completion_date, status, created_at, updated_at, row_version
+             FROM training_records WHERE id = ?1 AND site_id = ?2 AND deleted_at IS NULL",
+        )?;
+
+        let mut rows = stmt.query(params![id.to_string(), self.site_id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(self.row_to_record(row)?))
+        } else {
+            Ok(None)
+        }
     }
 
     /// Fetch all training records for an employee.
@@ -97,11 +141,11 @@ impl TrainingRepository {
         self.db.with_connection(|conn| {
             let mut stmt = conn.prepare(
                 "SELECT id, employee_id, training_item, mandatory, assigned_by,
-                        due_date, completion_date, status, created_at, updated_at
-                 FROM training_records WHERE employee_id = ?1",
+                        due_date, completion_date, status, created_at, updated_at, row_version
+                 FROM training_records WHERE employee_id = ?1 AND site_id = ?2 AND deleted_at IS NULL",
             )?;
 
-            let record_iter = stmt.query_map(params![employee_id], |row| self.row_to_record(row))?;
+            let record_iter = stmt.query_map(params![employee_id, self.site_id], |row| self.row_to_record(row))?;
             let mut records = Vec::new();
             for rec in record_iter {
                 records.push(rec?);
@@ -139,6 +183,7 @@ impl TrainingRepository {
             updated_at: chrono::DateTime::parse_from_rfc3339(&row.get::<_, String>(9)?)
                 .unwrap()
                 .with_timezone(&chrono::Utc),
+            row_version: row.get(10)?,
         })
     }
 }
@@ -157,6 +202,7 @@ mod tests {
             backup_retention_days: 1,
         })
         .unwrap();
+        db.seed_test_users(&["emp_test", "manager"]);
         TrainingRepository::new(db)
     }
 
@@ -174,6 +220,7 @@ mod tests {
             status: TrainingStatus::Pending,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
+            row_version: 1,
         };
 
         repo.insert(&record).unwrap();
@@ -197,16 +244,26 @@ mod tests {
             status: TrainingStatus::Pending,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
+            row_version: 1,
         };
 
         repo.insert(&record).unwrap();
         record.status = TrainingStatus::Completed;
         record.completion_date = Some(chrono::Utc::now().date_naive());
         record.updated_at = chrono::Utc::now();
-        repo.update(&record).unwrap();
+        let reason = ChangeReason::new("Training session attended").unwrap();
+        repo.update(&record, "manager", &reason).unwrap();
 
         let rec_db = repo.fetch_by_id(&record.id).unwrap().unwrap();
         assert_eq!(rec_db.status, TrainingStatus::Completed);
         assert!(rec_db.completion_date.is_some());
+
+        let history = crate::change_history::ChangeHistoryRepo::new(&repo.db)
+            .history("training_record", &record.id.to_string())
+            .unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].changed_by, "manager");
+        let fields: Vec<&str> = history[0].changes.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(fields, vec!["completion_date", "status"]);
     }
 }
\ No newline at end of file
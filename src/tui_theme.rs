@@ -0,0 +1,123 @@
+//! # TUI Theme - Standard and Accessible Rendering
+//!
+//! The TUI marks list lines with emoji and status glyphs and tells states
+//! apart by color. Screen readers announce the glyphs by name, many
+//! terminals draw them as boxes or at the wrong width, and color alone is
+//! lost on monochrome or high-contrast setups. In accessible mode
+//! [`TuiTheme`] replaces status glyphs with ASCII markers, drops decorative
+//! pictographs, removes colors and shows the selection in reverse video.
+
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::ListItem;
+
+/// Status glyphs and the ASCII marker shown for them in accessible mode.
+/// Sequences with a variation selector come before their bare glyph.
+const ASCII_MARKERS: &[(&str, &str)] = &[
+    ("✔️", "[OK]"),
+    ("⚠️", "[!]"),
+    ("✓", "[OK]"),
+    ("✔", "[OK]"),
+    ("✅", "[OK]"),
+    ("✗", "[X]"),
+    ("❌", "[X]"),
+    ("⚠", "[!]"),
+    ("⏳", "[..]"),
+    ("●", "*"),
+    ("⟳", "~"),
+    ("▶", ">"),
+    ("→", "->"),
+    ("←", "<-"),
+    ("│", "|"),
+    ("▏", "_"),
+];
+
+/// Emoji, dingbats and technical symbols without an ASCII marker, plus the
+/// joiners and variation selectors that combine them.
+fn is_pictograph(c: char) -> bool {
+    matches!(c as u32, 0x1F000..=0x1FAFF | 0x2300..=0x23FF | 0x2600..=0x27BF | 0xFE0F | 0x200D)
+}
+
+/// How the TUI draws text and emphasis.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TuiTheme {
+    accessible: bool,
+}
+
+impl TuiTheme {
+    pub fn new(accessible: bool) -> Self {
+        Self { accessible }
+    }
+
+    pub fn is_accessible(&self) -> bool {
+        self.accessible
+    }
+
+    /// `text` as displayed: unchanged, or in accessible mode with ASCII
+    /// markers in place of glyphs and without pictographs.
+    pub fn text(&self, text: impl Into<String>) -> String {
+        let text = text.into();
+        if !self.accessible {
+            return text;
+        }
+        let mut plain = ASCII_MARKERS.iter().fold(text, |text, (glyph, marker)| text.replace(glyph, marker));
+        let leading_pictograph = plain.starts_with(is_pictograph);
+        plain.retain(|c| !is_pictograph(c));
+        if leading_pictograph {
+            plain = plain.trim_start().to_string();
+        }
+        plain
+    }
+
+    /// A list line showing [`TuiTheme::text`].
+    pub fn item(&self, text: impl Into<String>) -> ListItem<'static> {
+        ListItem::new(self.text(text))
+    }
+
+    /// `style` as displayed; accessible mode keeps only bold, underline and
+    /// the like, leaving colors to the terminal.
+    pub fn style(&self, style: Style) -> Style {
+        match self.accessible {
+            true => Style { fg: None, bg: None, ..style },
+            false => style,
+        }
+    }
+
+    /// Style of the selected entry; reverse video in accessible mode.
+    pub fn highlight(&self, style: Style) -> Style {
+        match self.accessible {
+            true => Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            false => style,
+        }
+    }
+
+    /// Marker in front of the selected list entry.
+    pub fn highlight_symbol(&self) -> &'static str {
+        if self.accessible {
+            "> "
+        } else {
+            "▶ "
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    #[test]
+    fn test_accessible_theme_uses_ascii_and_no_colors() {
+        let standard = TuiTheme::default();
+        assert_eq!(standard.text("📈 Data fresh ✔️"), "📈 Data fresh ✔️");
+        assert_eq!(standard.style(Style::default().fg(Color::Red)).fg, Some(Color::Red));
+
+        let theme = TuiTheme::new(true);
+        assert_eq!(theme.text("📈 Data fresh ✔️"), "Data fresh [OK]");
+        assert_eq!(theme.text("⚠️  Overdue: 2"), "[!]  Overdue: 2");
+        assert_eq!(theme.text("✏️  qa: status 'Open' → 'Closed'"), "qa: status 'Open' -> 'Closed'");
+        assert_eq!(theme.text("Überfällig: 3  │  Offen: 1"), "Überfällig: 3  |  Offen: 1");
+        let style = theme.style(Style::default().fg(Color::Red).bg(Color::Yellow).add_modifier(Modifier::BOLD));
+        assert_eq!((style.fg, style.bg, style.add_modifier), (None, None, Modifier::BOLD));
+        assert!(theme.highlight(Style::default().bg(Color::Blue)).add_modifier.contains(Modifier::REVERSED));
+    }
+}
@@ -1,25 +1,99 @@
 use crate::Result;
 use ratatui::{
     backend::Backend,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Tabs},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Sparkline, Tabs},
     Frame,
 };
-use crossterm::event::{self, Event, KeyCode};
+use crossterm::event::{self, Event, KeyCode, MouseButton, MouseEvent, MouseEventKind};
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
 use crate::api::MetricsResponse;
+use crate::app_context::AppContext;
+use crate::audit_findings::{FindingAging, FindingRepo, AGING_BUCKETS};
+use crate::audit_tail::{AuditTail, AuditTailFilter, DEFAULT_TAIL_CAPACITY};
+use crate::authorization::{Permission, UserRole};
+use crate::config::{save_tui_layout, ComplianceConfig, TuiLayout};
+use crate::capa::CapaMetrics;
+use crate::change_history::ChangeRecord;
+use crate::database::Database;
+use crate::display_time::DisplayTimezone;
+use crate::environmental::{EnvironmentalRepo, EnvironmentalTrend};
+use crate::error::{QmsError, ValidationErrors};
+use crate::feature_flags::Feature;
+use crate::i18n::{tr, tr_args, Locale};
+use crate::jobs::{JobKind, JobOutcome, JobRun, JobStatus, JobTrigger};
+use crate::kpi::RagStatus;
+use crate::mdr_clock::{ClockStatus, MdrClock, MdrClockRepo};
+use crate::record_actions::{RecordAction, RecordActions};
+use crate::record_plugins::{CustomRecord, CustomRecordRepo};
+use crate::reporting_views::SummaryRepo;
+use crate::risk::{RiskAcceptability, RiskHeatMap, RiskHeatMapCell, RiskProbability, RiskSeverity};
+use crate::search::{SearchResult, SearchService, DEFAULT_SEARCH_LIMIT};
+use crate::site::DEFAULT_SITE_ID;
+use crate::storage_monitor::{self, StorageLevel, StorageSample};
 use crate::supplier::SupplierMetrics;
 use crate::training::TrainingMetrics;
+use crate::tui_theme::TuiTheme;
 use tokio::sync::mpsc::{UnboundedSender, UnboundedReceiver, unbounded_channel};
 
+/// API address used when none is configured
+const DEFAULT_API_BASE: &str = "http://127.0.0.1:3000";
+
+/// Open MDR clocks listed on the dashboard, soonest due first
+const DASHBOARD_MDR_CLOCKS: usize = 5;
+
+/// Percent the list/detail split moves per Ctrl+←/→
+const SPLIT_STEP_PERCENT: i16 = 5;
+
+/// How often the live audit tail looks for new entries
+const AUDIT_TAIL_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Rows and columns of the risk heat map
+const RISK_MATRIX_SIZE: usize = RiskSeverity::ALL.len();
+
+/// Width of the severity labels left of the risk heat map
+const RISK_LABEL_WIDTH: u16 = 17;
+
 /// Messages returned from async API fetch tasks
 #[derive(Debug)]
 enum MetricsMessage {
     CapaRisk(MetricsResponse),
     Supplier(SupplierMetrics),
     Training(TrainingMetrics),
+    ChangeHistory(String, Vec<ChangeRecord>),
+    Search(Vec<SearchResult>),
+    RiskHeatMap(RiskHeatMap),
+    /// A manually triggered job finished (or could not be started)
+    JobFinished(JobKind, Result<JobRun>),
+    /// A fetch failed (connection refused, error status or bad payload)
+    Unavailable(&'static str),
+}
+
+/// Action menu open on a record
+#[derive(Debug, Clone)]
+pub struct ActionMenu {
+    pub record: SearchResult,
+    pub actions: Vec<RecordAction>,
+    pub list_state: ratatui::widgets::ListState,
+    // Text typed for the selected action while it asks for some
+    pub input: Option<String>,
+    // Assignee or note already entered while the action asks for its reason
+    pub value: Option<String>,
+}
+
+impl ActionMenu {
+    fn selected_action(&self) -> Option<&RecordAction> {
+        self.list_state.selected().and_then(|i| self.actions.get(i))
+    }
+
+    /// Whether the text being typed is the reason for the change.
+    fn asks_for_reason(&self) -> bool {
+        self.selected_action()
+            .is_some_and(|action| action.needs_reason() && (self.value.is_some() || !action.needs_input()))
+    }
 }
 
 /// Main TUI application state
@@ -36,6 +110,9 @@ pub struct TuiApp {
     pub reports_list_state: ratatui::widgets::ListState,
     pub supplier_list_state: ratatui::widgets::ListState,
     pub training_list_state: ratatui::widgets::ListState,
+    pub search_list_state: ratatui::widgets::ListState,
+    pub jobs_list_state: ratatui::widgets::ListState,
+    pub records_list_state: ratatui::widgets::ListState,
     // Latest metrics fetched from API
     pub metrics: Option<MetricsResponse>,
     // Time of last metrics refresh
@@ -43,6 +120,66 @@ pub struct TuiApp {
     // ADD
     pub supplier_metrics: Option<SupplierMetrics>,
     pub training_metrics: Option<TrainingMetrics>,
+    // Change history of the record being inspected, with its label
+    pub change_history: Option<(String, Vec<ChangeRecord>)>,
+    // Display language for labels
+    pub locale: Locale,
+    // Time zone for displayed timestamps
+    pub display_timezone: DisplayTimezone,
+    // Set while API fetches fail; cleared by the next successful fetch
+    pub offline_since: Option<Instant>,
+    // Time of the last successful API fetch
+    pub last_live_update: Option<Instant>,
+    // CAPA counts read from the local database while offline
+    pub local_capa_metrics: Option<CapaMetrics>,
+    // Field errors of the last rejected form submission
+    pub form_errors: Option<ValidationErrors>,
+    // Environmental monitoring trends shown on the dashboard
+    pub environmental_trends: Vec<EnvironmentalTrend>,
+    // Open audit findings by age
+    pub audit_finding_aging: Option<FindingAging>,
+    // Open MDR reporting clocks, soonest due first
+    pub mdr_clocks: Vec<MdrClock>,
+    // Latest disk space sample; warned about unless its level is ok
+    pub storage_sample: Option<StorageSample>,
+    // Query typed on the Search tab and the hits of the last run
+    pub search_query: String,
+    pub search_results: Option<Vec<SearchResult>>,
+    // Set after a search until the query is edited again: keys act on the hits
+    pub search_results_focused: bool,
+    // Action menu on the selected hit and the outcome of its last action
+    pub action_menu: Option<ActionMenu>,
+    pub action_notice: Option<String>,
+    // Live audit trail tail, its filter line while being edited, and the last poll
+    pub audit_tail: Option<AuditTail>,
+    pub audit_tail_filter_input: Option<String>,
+    last_tail_poll: Instant,
+    // List/detail split, and the configuration file it is saved to per user
+    pub layout: TuiLayout,
+    layout_file: Option<PathBuf>,
+    // Glyphs and colors, or ASCII markers and reverse video in accessible mode
+    pub theme: TuiTheme,
+    // Background job statuses and the outcome of the last manual trigger
+    pub job_statuses: Vec<JobStatus>,
+    pub job_notice: Option<String>,
+    // Records of plugin types, listed on the Records tab
+    pub custom_records: Vec<CustomRecord>,
+    // Risk heat map, the selected cell (row 0 = Catastrophic, column 0 =
+    // Remote) and whether its assessments are being browsed
+    pub risk_heat_map: Option<RiskHeatMap>,
+    pub risk_residual: bool,
+    pub risk_cell: (usize, usize),
+    pub risk_drill_down: bool,
+    pub risk_list_state: ratatui::widgets::ListState,
+    // Screen area of the heat map cells when last drawn, for mouse clicks
+    risk_grid: Option<Rect>,
+    // Shared context: job scheduler and the signed-in user
+    context: Option<AppContext>,
+    // Same-process database used when the API is unreachable
+    fallback_db: Option<Database>,
+    // API base URL and bearer token for metric fetches
+    api_base: String,
+    api_token: Option<String>,
     // Channel for receiving async metrics updates
     api_rx: UnboundedReceiver<MetricsMessage>,
     api_tx: UnboundedSender<MetricsMessage>,
@@ -87,22 +224,131 @@ impl TuiApp {
             reports_list_state: reports_state,
             supplier_list_state: supplier_state,
             training_list_state: training_state,
+            search_list_state: ratatui::widgets::ListState::default(),
+            jobs_list_state: ratatui::widgets::ListState::default(),
+            records_list_state: ratatui::widgets::ListState::default(),
             metrics: None,
             last_metrics_fetch: Instant::now() - Duration::from_secs(10),
             supplier_metrics: None,
             training_metrics: None,
+            change_history: None,
+            locale: Locale::default(),
+            display_timezone: DisplayTimezone::default(),
+            offline_since: None,
+            last_live_update: None,
+            local_capa_metrics: None,
+            form_errors: None,
+            environmental_trends: Vec::new(),
+            audit_finding_aging: None,
+            mdr_clocks: Vec::new(),
+            storage_sample: None,
+            search_query: String::new(),
+            search_results: None,
+            search_results_focused: false,
+            action_menu: None,
+            action_notice: None,
+            audit_tail: None,
+            audit_tail_filter_input: None,
+            last_tail_poll: Instant::now(),
+            layout: TuiLayout::default(),
+            layout_file: None,
+            theme: TuiTheme::default(),
+            job_statuses: Vec::new(),
+            job_notice: None,
+            custom_records: Vec::new(),
+            risk_heat_map: None,
+            risk_residual: false,
+            risk_cell: (0, 0),
+            risk_drill_down: false,
+            risk_list_state: ratatui::widgets::ListState::default(),
+            risk_grid: None,
+            context: None,
+            fallback_db: None,
+            api_base: DEFAULT_API_BASE.to_string(),
+            api_token: None,
             api_rx: rx,
             api_tx: tx,
         }
     }
 
+    /// Display labels in `locale`
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Display timestamps in `tz`
+    pub fn with_display_timezone(mut self, tz: DisplayTimezone) -> Self {
+        self.display_timezone = tz;
+        self
+    }
+
+    /// Take locale, display zone, offline database and background jobs from
+    /// the shared context
+    pub fn with_context(mut self, context: &AppContext) -> Self {
+        self.context = Some(context.clone());
+        self.layout = context.config.tui.layout_for(&context.current_user());
+        self.theme = TuiTheme::new(context.config.tui.accessible);
+        self.with_locale(context.config.application.locale)
+            .with_display_timezone(context.config.application.display_timezone)
+            .with_offline_fallback(context.database.clone())
+    }
+
+    /// Read metrics straight from `database` when the API is unreachable
+    pub fn with_offline_fallback(mut self, database: Database) -> Self {
+        self.fallback_db = Some(database);
+        self
+    }
+
+    /// Save layout changes to the configuration file at `path`
+    pub fn with_layout_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.layout_file = Some(path.into());
+        self
+    }
+
+    /// Fetch metrics from the API at `base_url`, authenticating with `token`
+    pub fn with_api<S: Into<String>>(mut self, base_url: S, token: &str) -> Self {
+        self.api_base = base_url.into();
+        self.api_token = Some(token.to_string());
+        self
+    }
+
     /// Handle input events
     pub fn handle_input(&mut self) -> Result<()> {
-        use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+        use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
 
         if event::poll(Duration::from_millis(10))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
+            let event = event::read()?;
+            if let Event::Mouse(mouse) = event {
+                self.handle_mouse(mouse);
+            }
+            if let Event::Key(key) = event {
+                // An open action menu takes every key until it closes
+                let in_menu = key.kind == KeyEventKind::Press && self.action_menu.is_some();
+                if in_menu {
+                    self.handle_action_menu_key(key.code);
+                }
+                // Layout keys work on every tab: Ctrl+←/→ move the split, F2 toggles the detail pane
+                let relayout = key.kind == KeyEventKind::Press
+                    && !in_menu
+                    && match (key.code, key.modifiers.contains(KeyModifiers::CONTROL)) {
+                        (KeyCode::Left, true) => self.resize_split(-SPLIT_STEP_PERCENT),
+                        (KeyCode::Right, true) => self.resize_split(SPLIT_STEP_PERCENT),
+                        (KeyCode::F(2), _) => self.toggle_detail_pane(),
+                        _ => false,
+                    };
+                // On the Search tab typed characters go to the query, on
+                // the Audit Trail tab the live tail claims its keys
+                let editing = key.kind == KeyEventKind::Press
+                    && !in_menu
+                    && !relayout
+                    && match self.current_tab {
+                        TabState::Search => self.edit_search_query(key.code),
+                        TabState::AuditTrail => self.handle_audit_tail_key(key.code),
+                        TabState::Risk => self.handle_risk_key(key.code),
+                        _ => false,
+                    };
+                if key.kind == KeyEventKind::Press && !in_menu && !relayout && !editing {
                     match key.code {
                         KeyCode::Char('q') | KeyCode::Esc => self.should_quit = true,
                         KeyCode::Tab | KeyCode::Right => self.next_tab(),
@@ -114,6 +360,10 @@ impl TuiApp {
                         KeyCode::F(1) => self.show_help(),
                         KeyCode::Home => self.move_to_first(),
                         KeyCode::End => self.move_to_last(),
+                        KeyCode::Char('/') if self.tab_enabled(TabState::Search) => {
+                            self.current_tab = TabState::Search
+                        }
+                        KeyCode::Char('a') => self.open_action_menu(),
                         _ => {}
                     }
                 }
@@ -122,33 +372,425 @@ impl TuiApp {
 
         // Periodically refresh metrics (every 5 seconds)
         self.refresh_metrics();
+        self.poll_audit_tail();
         Ok(())
     }
 
+    /// Apply a key to the Search tab's query: characters and Backspace edit
+    /// it, Enter runs it. Once hits are shown, `a` or Enter opens the action
+    /// menu on the selected one. Returns `false` for keys left to navigation.
+    fn edit_search_query(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::Char('a') | KeyCode::Enter if self.search_results_focused => self.open_action_menu(),
+            KeyCode::Char(c) => {
+                self.search_results_focused = false;
+                self.search_query.push(c);
+            }
+            KeyCode::Backspace => {
+                self.search_results_focused = false;
+                self.search_query.pop();
+            }
+            KeyCode::Enter => self.run_search(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Move the list/detail split by `delta` percent. Returns `true`, the
+    /// key being handled either way.
+    pub fn resize_split(&mut self, delta: i16) -> bool {
+        self.layout = self.layout.resized(delta);
+        self.save_layout();
+        true
+    }
+
+    /// Show or hide the detail pane. Returns `true` like [`Self::resize_split`].
+    pub fn toggle_detail_pane(&mut self) -> bool {
+        self.layout.detail_pane = !self.layout.detail_pane;
+        self.save_layout();
+        true
+    }
+
+    /// Write the layout to the configuration file as the signed-in user's.
+    fn save_layout(&self) {
+        let (Some(path), Some(context)) = (&self.layout_file, &self.context) else {
+            return;
+        };
+        if let Err(e) = save_tui_layout(path, &context.current_user(), &self.layout) {
+            tracing::warn!("layout could not be saved: {e}");
+        }
+    }
+
+    /// Split `area` into the list pane and, when shown, the detail pane.
+    fn split_panes(&self, area: Rect) -> (Rect, Option<Rect>) {
+        if !self.layout.detail_pane {
+            return (area, None);
+        }
+        let list_percent = self.layout.list_percent;
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(list_percent), Constraint::Percentage(100 - list_percent)].as_ref())
+            .split(area);
+        (panes[0], Some(panes[1]))
+    }
+
+    /// Fields of the item selected on the current tab, for the detail pane.
+    fn detail_lines(&self) -> Vec<(&'static str, String)> {
+        match self.current_tab {
+            TabState::Search => self
+                .selected_record()
+                .map(|hit| {
+                    vec![
+                        ("tui.detail.kind", hit.record_type().to_string()),
+                        ("tui.detail.reference", hit.reference),
+                        ("tui.detail.title", hit.title),
+                        ("tui.detail.status", hit.status),
+                        ("tui.detail.id", hit.id),
+                    ]
+                })
+                .unwrap_or_default(),
+            TabState::Jobs => {
+                let Some(status) = self.jobs_list_state.selected().and_then(|i| self.job_statuses.get(i)) else {
+                    return Vec::new();
+                };
+                let tz = self.display_timezone;
+                let mut lines = vec![
+                    ("tui.detail.job", status.job.to_string()),
+                    ("tui.detail.interval", format!("{} min", status.interval_minutes)),
+                    ("tui.detail.next_run", tz.format(status.next_run_at)),
+                ];
+                if let Some(run) = &status.last_run {
+                    lines.extend([
+                        ("tui.detail.last_run", tz.format(run.started_at)),
+                        ("tui.detail.triggered_by", format!("{} ({})", run.triggered_by, run.trigger.as_str())),
+                        ("tui.detail.outcome", format!("{} in {} ms", run.outcome.as_str(), run.duration_ms)),
+                        ("tui.detail.result", run.detail.clone().unwrap_or_default()),
+                    ]);
+                }
+                lines
+            }
+            TabState::Records => {
+                let (Some(record), Some(context)) =
+                    (self.records_list_state.selected().and_then(|i| self.custom_records.get(i)), &self.context)
+                else {
+                    return Vec::new();
+                };
+                let mut lines = vec![
+                    ("tui.detail.kind", context.plugins.display_name(&record.record_type).to_string()),
+                    ("tui.detail.reference", record.reference.clone()),
+                    ("tui.detail.title", record.title.clone()),
+                    ("tui.detail.status", record.status.clone()),
+                    ("tui.detail.assignee", record.assignee.clone().unwrap_or_default()),
+                    ("tui.detail.due_date", record.due_date.map(|due| due.to_string()).unwrap_or_default()),
+                ];
+                // Plugin labels are not in the catalog, so they are shown as written
+                if let Some(plugin) = context.plugins.get(&record.record_type) {
+                    lines.extend(
+                        plugin
+                            .fields()
+                            .iter()
+                            .map(|spec| (spec.label, record.fields.get(spec.key).cloned().unwrap_or_default())),
+                    );
+                }
+                lines
+            }
+            TabState::Risk => {
+                let Some(cell) = self.selected_risk_cell() else {
+                    return Vec::new();
+                };
+                match self.risk_list_state.selected().and_then(|i| cell.assessments.get(i)) {
+                    Some(entry) => vec![
+                        ("tui.detail.device", entry.device_name.clone()),
+                        ("tui.detail.hazard", entry.hazard_description.clone()),
+                        ("tui.detail.status", format!("{:?}", entry.status)),
+                        ("tui.detail.id", entry.id.to_string()),
+                    ],
+                    None => vec![
+                        ("tui.detail.severity", format!("{:?} ({})", cell.severity, cell.severity as u8)),
+                        ("tui.detail.probability", format!("{:?} ({})", cell.probability, cell.probability as u8)),
+                        ("tui.detail.risk_level", cell.risk_level.to_string()),
+                        ("tui.detail.acceptability", format!("{:?}", cell.acceptability)),
+                        ("tui.detail.assessments", cell.assessments.len().to_string()),
+                    ],
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn render_detail_pane<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
+        let lines: Vec<Line> = self
+            .detail_lines()
+            .into_iter()
+            .map(|(label, value)| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{}: ", tr(self.locale, label)),
+                        Style::default().add_modifier(Modifier::BOLD),
+                    ),
+                    Span::raw(value),
+                ])
+            })
+            .collect();
+        let details = Paragraph::new(lines)
+            .wrap(ratatui::widgets::Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title(self.theme.text(tr(self.locale, "tui.block.details"))));
+        f.render_widget(details, area);
+    }
+
+    /// Keys of the Audit Trail tab's live tail: `t` starts and stops it, `p`
+    /// or Space pauses, ↑/↓ and PgUp/PgDn scroll back, `f` edits the
+    /// user/action filter. Returns `false` for keys left to navigation.
+    fn handle_audit_tail_key(&mut self, code: KeyCode) -> bool {
+        if let Some(input) = self.audit_tail_filter_input.as_mut() {
+            match code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => {
+                    let filter = AuditTailFilter::parse(input);
+                    if let Some(tail) = self.audit_tail.as_mut() {
+                        tail.filter = filter;
+                    }
+                    self.audit_tail_filter_input = None;
+                }
+                KeyCode::Esc => self.audit_tail_filter_input = None,
+                _ => {}
+            }
+            return true;
+        }
+        if code == KeyCode::Char('t') {
+            self.toggle_audit_tail();
+            return true;
+        }
+        let Some(tail) = self.audit_tail.as_mut() else {
+            return false;
+        };
+        match code {
+            KeyCode::Char('p') | KeyCode::Char(' ') => tail.toggle_pause(),
+            KeyCode::Up | KeyCode::Char('k') => tail.scroll_up(1),
+            KeyCode::Down | KeyCode::Char('j') => tail.scroll_down(1),
+            KeyCode::PageUp => tail.scroll_up(10),
+            KeyCode::PageDown => tail.scroll_down(10),
+            KeyCode::Char('f') => self.audit_tail_filter_input = Some(tail.filter.to_string()),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Start following new audit entries in the same-process database, or
+    /// stop when already following.
+    pub fn toggle_audit_tail(&mut self) {
+        if self.audit_tail.take().is_some() {
+            self.audit_tail_filter_input = None;
+            return;
+        }
+        let Some(database) = &self.fallback_db else {
+            return;
+        };
+        match AuditTail::start(database.clone(), DEFAULT_TAIL_CAPACITY) {
+            Ok(tail) => self.audit_tail = Some(tail),
+            Err(e) => tracing::warn!("audit tail could not start: {e}"),
+        }
+    }
+
+    fn poll_audit_tail(&mut self) {
+        if self.last_tail_poll.elapsed() < AUDIT_TAIL_POLL_INTERVAL {
+            return;
+        }
+        self.last_tail_poll = Instant::now();
+        if let Some(tail) = self.audit_tail.as_mut() {
+            if let Err(e) = tail.poll() {
+                tracing::warn!("audit tail poll failed: {e}");
+            }
+        }
+    }
+
+    /// Search for the typed query in the same-process database, or through
+    /// the API when there is none.
+    pub fn run_search(&mut self) {
+        if self.search_query.trim().is_empty() {
+            self.search_results = None;
+            return;
+        }
+        if let Some(database) = &self.fallback_db {
+            match SearchService::new(database).search(&self.search_query, DEFAULT_SEARCH_LIMIT) {
+                Ok(results) => self.show_search_results(results),
+                Err(e) => tracing::warn!("search failed: {e}"),
+            }
+            return;
+        }
+        let request = self.api_request("/search").query(&[("q", self.search_query.as_str())]);
+        spawn_fetch(self.api_tx.clone(), request, "/search", MetricsMessage::Search);
+    }
+
+    fn show_search_results(&mut self, results: Vec<SearchResult>) {
+        self.search_list_state.select(if results.is_empty() { None } else { Some(0) });
+        self.search_results_focused = !results.is_empty();
+        self.search_results = Some(results);
+    }
+
+    /// The record under the cursor, for tabs that list stored records.
+    fn selected_record(&self) -> Option<SearchResult> {
+        match self.current_tab {
+            TabState::Search => self.search_results.as_ref()?.get(self.search_list_state.selected()?).cloned(),
+            _ => None,
+        }
+    }
+
+    /// Open the action menu on the selected record. Actions run through the
+    /// shared context's services, so there is no menu without one.
+    pub fn open_action_menu(&mut self) {
+        let Some(record) = self.selected_record() else {
+            return;
+        };
+        let Some(context) = &self.context else {
+            self.action_notice = Some(tr(self.locale, "tui.actions.unavailable").to_string());
+            return;
+        };
+        match RecordActions::new(context).available(&record) {
+            Ok(actions) => {
+                let mut list_state = ratatui::widgets::ListState::default();
+                list_state.select(Some(0));
+                self.action_menu = Some(ActionMenu { record, actions, list_state, input: None, value: None });
+            }
+            Err(e) => {
+                let label = record.reference.clone();
+                self.action_notice =
+                    Some(tr_args(self.locale, "tui.actions.failed", &[("action", &label), ("message", &e)]));
+            }
+        }
+    }
+
+    /// Apply a key to the open action menu: move, choose, or type the text
+    /// the chosen action asks for, then the reason for a change. Esc steps
+    /// back.
+    pub fn handle_action_menu_key(&mut self, code: KeyCode) {
+        let Some(menu) = self.action_menu.as_mut() else {
+            return;
+        };
+        let reason_next = !menu.asks_for_reason() && menu.selected_action().is_some_and(RecordAction::needs_reason);
+        if let Some(input) = menu.input.as_mut() {
+            match code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter if reason_next => menu.value = menu.input.replace(String::new()),
+                KeyCode::Enter => self.run_selected_action(),
+                KeyCode::Esc => {
+                    menu.input = None;
+                    menu.value = None;
+                }
+                _ => {}
+            }
+            return;
+        }
+        let len = menu.actions.len();
+        let selected = menu.list_state.selected().unwrap_or(0);
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => menu.list_state.select(Some((selected + len - 1) % len)),
+            KeyCode::Down | KeyCode::Char('j') => menu.list_state.select(Some((selected + 1) % len)),
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                if menu.actions[selected].needs_input() || menu.actions[selected].needs_reason() {
+                    menu.input = Some(String::new());
+                } else {
+                    self.run_selected_action();
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') => self.action_menu = None,
+            _ => {}
+        }
+    }
+
+    /// Run the chosen action if the signed-in user's role permits it, then
+    /// close the menu and search again so the hit shows the new state.
+    fn run_selected_action(&mut self) {
+        let (Some(menu), Some(context)) = (self.action_menu.take(), self.context.clone()) else {
+            return;
+        };
+        let Some(action) = menu.selected_action() else {
+            return;
+        };
+        let label = action.label(self.locale);
+        let user = context.current_user();
+        let permitted = matches!(
+            UserRole::of_user(&context.database, &user),
+            Ok(Some(role)) if role.has_permission(action.required_permission())
+        );
+        if !permitted {
+            self.action_notice = Some(tr_args(self.locale, "tui.actions.not_permitted", &[("action", &label)]));
+            return;
+        }
+        let typed = menu.input.as_deref().unwrap_or_default();
+        let (input, reason) = match (&menu.value, menu.asks_for_reason()) {
+            (Some(value), _) => (value.as_str(), typed),
+            (None, true) => ("", typed),
+            (None, false) => (typed, ""),
+        };
+        let performed = RecordActions::new(&context).perform(&menu.record, action, input, reason, &user);
+        self.action_notice = Some(match performed {
+            Ok(detail) => tr_args(self.locale, "tui.actions.done", &[("detail", &detail)]),
+            Err(e) => tr_args(self.locale, "tui.actions.failed", &[("action", &label), ("message", &e)]),
+        });
+        self.run_search();
+    }
+
+    /// Show the field errors of a rejected form submission. Returns `false`
+    /// (and shows nothing) when `error` is not a validation error.
+    pub fn show_form_errors(&mut self, error: QmsError) -> bool {
+        let mut errors = ValidationErrors::new();
+        if errors.absorb(error).is_err() {
+            return false;
+        }
+        self.form_errors = Some(errors);
+        true
+    }
+
+    /// One `field: message` line per invalid field
+    fn form_error_lines(&self) -> Vec<String> {
+        self.form_errors
+            .iter()
+            .flat_map(|errors| errors.iter())
+            .map(|(field, message)| format!("✗ {}: {}", field, message))
+            .collect()
+    }
+
     /// Move to next tab
     pub fn next_tab(&mut self) {
-        self.current_tab = match self.current_tab {
-            TabState::Dashboard => TabState::Documents,
-            TabState::Documents => TabState::AuditTrail,
-            TabState::AuditTrail => TabState::Capa,
-            TabState::Capa => TabState::Suppliers,
-            TabState::Suppliers => TabState::Training,
-            TabState::Training => TabState::Reports,
-            TabState::Reports => TabState::Dashboard,
-        };
+        self.form_errors = None;
+        self.current_tab = self.neighbour_tab(1);
     }
 
     /// Move to previous tab
     pub fn previous_tab(&mut self) {
-        self.current_tab = match self.current_tab {
-            TabState::Dashboard => TabState::Reports,
-            TabState::Documents => TabState::Dashboard,
-            TabState::AuditTrail => TabState::Documents,
-            TabState::Capa => TabState::AuditTrail,
-            TabState::Suppliers => TabState::Capa,
-            TabState::Training => TabState::Suppliers,
-            TabState::Reports => TabState::Training,
-        };
+        self.current_tab = self.neighbour_tab(-1);
+    }
+
+    /// Tabs shown in the tab bar: every tab whose module is enabled
+    pub fn visible_tabs(&self) -> Vec<TabState> {
+        TabState::ALL.into_iter().filter(|tab| self.tab_enabled(*tab)).collect()
+    }
+
+    fn tab_enabled(&self, tab: TabState) -> bool {
+        if tab == TabState::Records {
+            return self.context.as_ref().is_some_and(|context| !context.plugins.is_empty());
+        }
+        tab.feature().map_or(true, |feature| self.feature_enabled(feature))
+    }
+
+    /// Whether the shared context's feature flags enable `feature`; without
+    /// a context every module is shown
+    fn feature_enabled(&self, feature: Feature) -> bool {
+        self.context.as_ref().map_or(true, |context| context.features.is_enabled(feature))
+    }
+
+    /// The visible tab `step` places after the current one, wrapping around
+    fn neighbour_tab(&self, step: isize) -> TabState {
+        let tabs = self.visible_tabs();
+        let current = tabs.iter().position(|tab| *tab == self.current_tab).unwrap_or(0);
+        tabs[(current as isize + step).rem_euclid(tabs.len() as isize) as usize]
     }
 
     /// Move selection up
@@ -208,6 +850,54 @@ let i = match self.supplier_list_state.selected() {
                 };
                 self.reports_list_state.select(Some(i));
             }
+            TabState::Search => {
+                let len = self.search_result_count();
+                if len == 0 {
+                    return;
+                }
+                let i = match self.search_list_state.selected() {
+                    Some(i) => if i == 0 { len - 1 } else { i - 1 },
+                    None => 0,
+                };
+                self.search_list_state.select(Some(i));
+            }
+            TabState::Jobs => {
+                let len = self.job_statuses.len();
+                if len == 0 {
+                    return;
+                }
+                let i = match self.jobs_list_state.selected() {
+                    Some(i) => if i == 0 { len - 1 } else { i - 1 },
+                    None => 0,
+                };
+                self.jobs_list_state.select(Some(i));
+            }
+            TabState::Records => {
+                let len = self.custom_records.len();
+                if len == 0 {
+                    return;
+                }
+                let i = match self.records_list_state.selected() {
+                    Some(i) => if i == 0 { len - 1 } else { i - 1 },
+                    None => 0,
+                };
+                self.records_list_state.select(Some(i));
+            }
+            TabState::Risk if self.risk_drill_down => {
+                let len = self.selected_risk_cell().map_or(0, |cell| cell.assessments.len());
+                if len == 0 {
+                    return;
+                }
+                let i = match self.risk_list_state.selected() {
+                    Some(i) => if i == 0 { len - 1 } else { i - 1 },
+                    None => 0,
+                };
+                self.risk_list_state.select(Some(i));
+            }
+            TabState::Risk => {
+                let (row, column) = self.risk_cell;
+                self.select_risk_cell(((row + RISK_MATRIX_SIZE - 1) % RISK_MATRIX_SIZE, column));
+            }
         }
     }
 
@@ -268,6 +958,54 @@ let i = match self.supplier_list_state.selected() {
                 };
                 self.reports_list_state.select(Some(i));
             }
+            TabState::Search => {
+                let len = self.search_result_count();
+                if len == 0 {
+                    return;
+                }
+                let i = match self.search_list_state.selected() {
+                    Some(i) => (i + 1) % len,
+                    None => 0,
+                };
+                self.search_list_state.select(Some(i));
+            }
+            TabState::Jobs => {
+                let len = self.job_statuses.len();
+                if len == 0 {
+                    return;
+                }
+                let i = match self.jobs_list_state.selected() {
+                    Some(i) => (i + 1) % len,
+                    None => 0,
+                };
+                self.jobs_list_state.select(Some(i));
+            }
+            TabState::Records => {
+                let len = self.custom_records.len();
+                if len == 0 {
+                    return;
+                }
+                let i = match self.records_list_state.selected() {
+                    Some(i) => (i + 1) % len,
+                    None => 0,
+                };
+                self.records_list_state.select(Some(i));
+            }
+            TabState::Risk if self.risk_drill_down => {
+                let len = self.selected_risk_cell().map_or(0, |cell| cell.assessments.len());
+                if len == 0 {
+                    return;
+                }
+                let i = match self.risk_list_state.selected() {
+                    Some(i) => (i + 1) % len,
+                    None => 0,
+                };
+                self.risk_list_state.select(Some(i));
+            }
+            TabState::Risk => {
+                let (row, column) = self.risk_cell;
+                self.select_risk_cell(((row + 1) % RISK_MATRIX_SIZE, column));
+            }
         }
     }
 
@@ -281,6 +1019,11 @@ let i = match self.supplier_list_state.selected() {
             TabState::Suppliers => self.supplier_list_state.select(Some(0)),
             TabState::Training => self.training_list_state.select(Some(0)),
             TabState::Reports => self.reports_list_state.select(Some(0)),
+            TabState::Search => self.search_list_state.select(Some(0).filter(|_| self.search_result_count() > 0)),
+            TabState::Jobs => self.jobs_list_state.select(Some(0).filter(|_| !self.job_statuses.is_empty())),
+            TabState::Records => self.records_list_state.select(Some(0).filter(|_| !self.custom_records.is_empty())),
+            TabState::Risk if self.risk_drill_down => self.risk_list_state.select(Some(0)),
+            TabState::Risk => self.select_risk_cell((0, self.risk_cell.1)),
         }
     }
 
@@ -294,6 +1037,14 @@ let i = match self.supplier_list_state.selected() {
 TabState::Suppliers => self.supplier_list_state.select(Some(self.get_supplier_list_items().len() - 1)),
             TabState::Training => self.training_list_state.select(Some(3)), // 4 items index 3
             TabState::Reports => self.reports_list_state.select(Some(2)), // 3 items, index 2
+            TabState::Search => self.search_list_state.select(self.search_result_count().checked_sub(1)),
+            TabState::Jobs => self.jobs_list_state.select(self.job_statuses.len().checked_sub(1)),
+            TabState::Records => self.records_list_state.select(self.custom_records.len().checked_sub(1)),
+            TabState::Risk if self.risk_drill_down => {
+                let len = self.selected_risk_cell().map_or(0, |cell| cell.assessments.len());
+                self.risk_list_state.select(len.checked_sub(1));
+            }
+            TabState::Risk => self.select_risk_cell((RISK_MATRIX_SIZE - 1, self.risk_cell.1)),
         }
     }
 
@@ -307,6 +1058,13 @@ TabState::Suppliers => self.supplier_list_state.select(Some(self.get_supplier_li
         println!("Enter/Space: Select item");
         println!("Home      : First item");
         println!("End       : Last item");
+        println!("/         : Search all records");
+        println!("a         : Actions on the selected record");
+        println!("t         : Follow new audit entries (Audit Trail tab)");
+        println!("r         : Initial/residual risk (Risk tab)");
+        println!("Click     : Assessments of a heat map cell (Risk tab)");
+        println!("Ctrl+←/→  : Resize the list and detail panes");
+        println!("F2        : Show or hide the detail pane");
         println!("h/F1      : Show this help");
         println!("q/Esc     : Quit application");
         println!("=============================\n");
@@ -350,9 +1108,9 @@ TabState::Suppliers => self.supplier_list_state.select(Some(self.get_supplier_li
             TabState::Capa => {
                 if let Some(selected) = self.capa_list_state.selected() {
                     match selected {
-                        0 => println!("🔧 CAPA-001: Non-conforming Product Investigation [OPEN] - Opening investigation details..."),
-                        1 => println!("🔧 CAPA-002: Audit Finding Remediation [IN PROGRESS] - Viewing action plan..."),
-                        2 => println!("🔧 CAPA-003: Process Improvement Initiative [CLOSED] - Showing effectiveness verification..."),
+                        0 => println!("🔧 CAPA-2025-0001: Non-conforming Product Investigation [OPEN] - Opening investigation details..."),
+                        1 => println!("🔧 CAPA-2025-0002: Audit Finding Remediation [IN PROGRESS] - Viewing action plan..."),
+                        2 => println!("🔧 CAPA-2025-0003: Process Improvement Initiative [CLOSED] - Showing effectiveness verification..."),
                         _ => println!("CAPA item {} selected", selected),
                     }
                 }
@@ -384,89 +1142,359 @@ TabState::Suppliers => self.supplier_list_state.select(Some(self.get_supplier_li
                     }
                 }
             }
+            TabState::Search => self.run_search(),
+            TabState::Jobs => self.trigger_selected_job(),
+            // The list and detail pane show everything there is
+            TabState::Records => {}
+            TabState::Risk => self.toggle_risk_drill_down(),
+        }
+    }
+
+    /// Run the selected background job now, if the signed-in user's role
+    /// permits it. The run happens off the UI thread; its outcome is shown
+    /// once it arrives.
+    pub fn trigger_selected_job(&mut self) {
+        let Some(context) = self.context.clone() else {
+            return;
+        };
+        let Some(kind) = self.jobs_list_state.selected().and_then(|i| self.job_statuses.get(i)).map(|s| s.job) else {
+            return;
+        };
+        let user = context.current_user();
+        let permitted = matches!(
+            UserRole::of_user(&context.database, &user),
+            Ok(Some(role)) if role.has_permission(Permission::RunJobs)
+        );
+        if !permitted {
+            self.job_notice = Some(tr_args(self.locale, "tui.jobs.not_permitted", &[("job", &kind)]));
+            return;
+        }
+        self.job_notice = Some(tr_args(self.locale, "tui.jobs.started", &[("job", &kind)]));
+        let tx = self.api_tx.clone();
+        let run = move || {
+            let _ = tx.send(MetricsMessage::JobFinished(kind, context.jobs.run(kind, JobTrigger::Manual, &user)));
+        };
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => drop(runtime.spawn_blocking(run)),
+            Err(_) => run(),
+        }
+        self.process_api_messages();
+    }
+
+    /// Keys of the Risk tab: ←/→ move between probability columns (past the
+    /// outer columns they switch tabs), `r` swaps initial and residual
+    /// estimates, Backspace returns from a cell's assessments to the grid.
+    /// Returns `false` for keys left to navigation.
+    fn handle_risk_key(&mut self, code: KeyCode) -> bool {
+        let (row, column) = self.risk_cell;
+        match code {
+            KeyCode::Left if !self.risk_drill_down && column > 0 => self.select_risk_cell((row, column - 1)),
+            KeyCode::Right if !self.risk_drill_down && column + 1 < RISK_MATRIX_SIZE => {
+                self.select_risk_cell((row, column + 1))
+            }
+            KeyCode::Backspace if self.risk_drill_down => self.select_risk_cell(self.risk_cell),
+            KeyCode::Char('r') => {
+                self.risk_residual = !self.risk_residual;
+                self.risk_heat_map = None;
+                self.select_risk_cell(self.risk_cell);
+                self.fetch_risk_heat_map();
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// A left click on a heat map cell selects it and lists its assessments.
+    pub fn handle_mouse(&mut self, mouse: MouseEvent) {
+        if self.current_tab != TabState::Risk || mouse.kind != MouseEventKind::Down(MouseButton::Left) {
+            return;
+        }
+        if let Some(cell) = self.risk_cell_at(mouse.column, mouse.row) {
+            self.select_risk_cell(cell);
+            self.toggle_risk_drill_down();
+        }
+    }
+
+    /// Heat map cell drawn at screen position (`x`, `y`), as (row, column).
+    fn risk_cell_at(&self, x: u16, y: u16) -> Option<(usize, usize)> {
+        let grid = self.risk_grid?;
+        if x < grid.x || y < grid.y || x >= grid.right() || y >= grid.bottom() {
+            return None;
+        }
+        let size = RISK_MATRIX_SIZE as u16;
+        Some((((y - grid.y) / (grid.height / size)) as usize, ((x - grid.x) / (grid.width / size)) as usize))
+    }
+
+    /// Move the heat map selection to `cell`, back on the grid.
+    fn select_risk_cell(&mut self, cell: (usize, usize)) {
+        self.risk_cell = cell;
+        self.risk_drill_down = false;
+        self.risk_list_state.select(None);
+    }
+
+    /// Enter the assessment list of the selected cell, or return to the grid.
+    fn toggle_risk_drill_down(&mut self) {
+        if self.risk_drill_down {
+            self.select_risk_cell(self.risk_cell);
+        } else if self.selected_risk_cell().map_or(false, |cell| !cell.assessments.is_empty()) {
+            self.risk_drill_down = true;
+            self.risk_list_state.select(Some(0));
         }
     }
 
+    fn selected_risk_cell(&self) -> Option<&RiskHeatMapCell> {
+        let (row, column) = self.risk_cell;
+        self.risk_heat_map.as_ref()?.cell(row, column)
+    }
+
     /// Main render function
     pub fn render<B: Backend>(&mut self, f: &mut Frame<B>) {
+        let banner = self.offline_banner();
+        let banner_height = if banner.is_some() { 1 } else { 0 };
         let chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+            .constraints([Constraint::Length(3), Constraint::Length(banner_height), Constraint::Min(0)].as_ref())
             .split(f.size());
 
         self.render_tabs(f, chunks[0]);
-        
+        if let Some(banner) = banner {
+            let warning = Paragraph::new(self.theme.text(banner))
+                .style(self.theme.style(Style::default().fg(Color::Black).bg(Color::Yellow)));
+            f.render_widget(warning, chunks[1]);
+        }
+
+        let field_errors = self.form_error_lines();
+        let content = if field_errors.is_empty() {
+            chunks[2]
+        } else {
+            let split = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(field_errors.len() as u16 + 2)].as_ref())
+                .split(chunks[2]);
+            let items: Vec<ListItem> = field_errors.into_iter().map(|line| self.theme.item(line)).collect();
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(tr(self.locale, "tui.block.invalid_fields")))
+                .style(self.theme.style(Style::default().fg(Color::Red)));
+            f.render_widget(list, split[1]);
+            split[0]
+        };
+
         match self.current_tab {
-            TabState::Dashboard => self.render_dashboard(f, chunks[1]),
-            TabState::Documents => self.render_documents(f, chunks[1]),
-            TabState::AuditTrail => self.render_audit_trail(f, chunks[1]),
-            TabState::Capa => self.render_capa(f, chunks[1]),
-            TabState::Suppliers => self.render_suppliers(f, chunks[1]),
-            TabState::Training => self.render_training(f, chunks[1]),
-            TabState::Reports => self.render_reports(f, chunks[1]),
+            TabState::Dashboard => self.render_dashboard(f, content),
+            TabState::Documents => self.render_documents(f, content),
+            TabState::AuditTrail => self.render_audit_trail(f, content),
+            TabState::Capa => self.render_capa(f, content),
+            TabState::Suppliers => self.render_suppliers(f, content),
+            TabState::Training => self.render_training(f, content),
+            TabState::Reports => self.render_reports(f, content),
+            TabState::Search => self.render_search(f, content),
+            TabState::Jobs => self.render_jobs(f, content),
+            TabState::Records => self.render_records(f, content),
+            TabState::Risk => self.render_risk(f, content),
         }
     }
 
+    /// Staleness warning shown while the API is unreachable.
+    fn offline_banner(&self) -> Option<String> {
+        self.offline_since?;
+        let source = if self.fallback_db.is_some() { "tui.offline.local" } else { "tui.offline.cached" };
+        let age = match self.last_live_update {
+            Some(at) => tr_args(self.locale, "tui.offline.last_update", &[("seconds", &at.elapsed().as_secs())]),
+            None => tr(self.locale, "tui.offline.never_updated").to_string(),
+        };
+        Some(format!("⚠ {} ({})", tr(self.locale, source), age))
+    }
+
     /// Render tab bar
     fn render_tabs<B: Backend>(&self, f: &mut Frame<B>, area: Rect) {
-        let tab_titles = vec!["Dashboard", "Documents", "Audit Trail", "CAPA", "Suppliers", "Training", "Reports"];
+        let visible = self.visible_tabs();
+        let tab_titles: Vec<&str> = visible.iter().map(|tab| tr(self.locale, tab.title_id())).collect();
         let tabs = Tabs::new(tab_titles)
-            .block(Block::default().borders(Borders::ALL).title("QMS - FDA Compliant"))
-            .style(Style::default().fg(Color::White))
-            .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
-            .select(self.current_tab as usize);
+            .block(Block::default().borders(Borders::ALL).title(tr(self.locale, "tui.title")))
+            .style(self.theme.style(Style::default().fg(Color::White)))
+            .highlight_style(self.theme.highlight(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)))
+            .select(visible.iter().position(|tab| *tab == self.current_tab).unwrap_or(0));
         
         f.render_widget(tabs, area);
     }
 
     /// Render dashboard tab
     fn render_dashboard<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
-        let dashboard_items = vec![
-            ListItem::new("✓ FDA CFR Part 820 Compliance: ACTIVE"),
-            ListItem::new("✓ Audit Trail System: OPERATIONAL"),
-            ListItem::new("✓ Document Control: READY"),
-            ListItem::new("✓ User Authentication: ENABLED"),
-            ListItem::new("✓ Encryption Status: AES-256 ACTIVE"),
+        let mut dashboard_items = vec![
+            self.theme.item("✓ FDA CFR Part 820 Compliance: ACTIVE"),
+            self.theme.item("✓ Audit Trail System: OPERATIONAL"),
+            self.theme.item("✓ Document Control: READY"),
+            self.theme.item("✓ User Authentication: ENABLED"),
+            self.theme.item("✓ Encryption Status: AES-256 ACTIVE"),
         ];
+        let storage_warning = self.get_storage_warning_item();
+        let status_height = dashboard_items.len() as u16 + 2 + u16::from(storage_warning.is_some());
+        dashboard_items.extend(storage_warning);
 
         let dashboard_list = List::new(dashboard_items)
-            .block(Block::default().borders(Borders::ALL).title("System Status"))
-            .highlight_style(Style::default().bg(Color::Blue).fg(Color::White))
-            .highlight_symbol("▶ ");
+            .block(Block::default().borders(Borders::ALL).title(tr(self.locale, "tui.block.system_status")))
+            .highlight_style(self.theme.highlight(Style::default().bg(Color::Blue).fg(Color::White)))
+            .highlight_symbol(self.theme.highlight_symbol());
+
+        let kpi_items = self.get_kpi_list_items();
+        if self.environmental_trends.is_empty()
+            && self.audit_finding_aging.is_none()
+            && self.mdr_clocks.is_empty()
+            && kpi_items.is_empty()
+        {
+            f.render_stateful_widget(dashboard_list, area, &mut self.dashboard_list_state);
+            return;
+        }
+        let kpi_height = if kpi_items.is_empty() { 0 } else { kpi_items.len() as u16 + 2 };
+        let findings_height = if self.audit_finding_aging.is_some() { 3 } else { 0 };
+        let clock_items = self.get_mdr_clock_list_items();
+        let clocks_height = if clock_items.is_empty() { 0 } else { clock_items.len() as u16 + 2 };
+        let mut constraints = vec![
+            Constraint::Length(status_height),
+            Constraint::Length(kpi_height),
+            Constraint::Length(findings_height),
+            Constraint::Length(clocks_height),
+        ];
+        constraints.extend(self.environmental_trends.iter().map(|_| Constraint::Length(4)));
+        constraints.push(Constraint::Min(0));
+        let chunks = Layout::default().direction(Direction::Vertical).constraints(constraints).split(area);
+        f.render_stateful_widget(dashboard_list, chunks[0], &mut self.dashboard_list_state);
 
-        f.render_stateful_widget(dashboard_list, area, &mut self.dashboard_list_state);
+        if !kpi_items.is_empty() {
+            let kpis = List::new(kpi_items)
+                .block(Block::default().borders(Borders::ALL).title(tr(self.locale, "tui.block.kpis")));
+            f.render_widget(kpis, chunks[1]);
+        }
+
+        if let Some(aging) = &self.audit_finding_aging {
+            let mut text: Vec<String> =
+                AGING_BUCKETS.iter().zip(aging.buckets).map(|((_, label), count)| format!("{}: {}", label, count)).collect();
+            text.push(tr_args(self.locale, "tui.findings.overdue", &[("count", &aging.overdue)]));
+            let color = if aging.overdue > 0 { Color::Red } else { Color::White };
+            let findings = Paragraph::new(self.theme.text(text.join("  │  ")))
+                .block(Block::default().borders(Borders::ALL).title(tr(self.locale, "tui.block.audit_findings")))
+                .style(self.theme.style(Style::default().fg(color)));
+            f.render_widget(findings, chunks[2]);
+        }
+
+        if !clock_items.is_empty() {
+            let clocks = List::new(clock_items)
+                .block(Block::default().borders(Borders::ALL).title(tr(self.locale, "tui.block.mdr_clocks")));
+            f.render_widget(clocks, chunks[3]);
+        }
+
+        for (trend, chunk) in self.environmental_trends.iter().zip(chunks.iter().skip(4)) {
+            let title = tr_args(
+                self.locale,
+                "tui.block.environmental_trend",
+                &[("area", &trend.area), ("parameter", &trend.parameter.as_str()), ("excursions", &trend.excursions())],
+            );
+            let color = if trend.excursions() > 0 { Color::Red } else { Color::Green };
+            let data = trend.sparkline();
+            let sparkline = Sparkline::default()
+                .block(Block::default().borders(Borders::ALL).title(title))
+                .data(&data)
+                .max(100)
+                .style(self.theme.style(Style::default().fg(color)));
+            f.render_widget(sparkline, *chunk);
+        }
     }
 
     /// Render documents tab
     fn render_documents<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
         let document_items = vec![
-            ListItem::new("📄 SOP-001: Quality System Procedures [APPROVED]"),
-            ListItem::new("📄 WI-002: Calibration Work Instructions [DRAFT]"),
-            ListItem::new("📄 FORM-003: Device Master Record [EFFECTIVE]"),
+            self.theme.item("📄 SOP-001: Quality System Procedures [APPROVED]"),
+            self.theme.item("📄 WI-002: Calibration Work Instructions [DRAFT]"),
+            self.theme.item("📄 FORM-003: Device Master Record [EFFECTIVE]"),
         ];
 
         let document_list = List::new(document_items)
-            .block(Block::default().borders(Borders::ALL).title("Document Control"))
-            .highlight_style(Style::default().bg(Color::Green).fg(Color::White))
-            .highlight_symbol("▶ ");
+            .block(Block::default().borders(Borders::ALL).title(tr(self.locale, "tui.block.document_control")))
+            .highlight_style(self.theme.highlight(Style::default().bg(Color::Green).fg(Color::White)))
+            .highlight_symbol(self.theme.highlight_symbol());
 
         f.render_stateful_widget(document_list, area, &mut self.documents_list_state);
     }
 
     /// Render audit trail tab
     fn render_audit_trail<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        if self.audit_tail.is_some() {
+            self.render_audit_tail(f, area);
+            return;
+        }
         let audit_items = vec![
-            ListItem::new("🔍 2024-01-15 10:30:25 - User login: admin [SUCCESS]"),
-            ListItem::new("🔍 2024-01-15 10:31:12 - Document accessed: SOP-001 [SUCCESS]"),
-            ListItem::new("🔍 2024-01-15 10:32:45 - Configuration changed [SUCCESS]"),
+            self.theme.item("🔍 2024-01-15 10:30:25 - User login: admin [SUCCESS]"),
+            self.theme.item("🔍 2024-01-15 10:31:12 - Document accessed: SOP-001 [SUCCESS]"),
+            self.theme.item("🔍 2024-01-15 10:32:45 - Configuration changed [SUCCESS]"),
         ];
 
         let audit_list = List::new(audit_items)
-            .block(Block::default().borders(Borders::ALL).title("Audit Trail"))
-            .highlight_style(Style::default().bg(Color::Red).fg(Color::White))
-            .highlight_symbol("▶ ");
+            .block(Block::default().borders(Borders::ALL).title(tr(self.locale, "tui.block.audit_trail")))
+            .highlight_style(self.theme.highlight(Style::default().bg(Color::Red).fg(Color::White)))
+            .highlight_symbol(self.theme.highlight_symbol());
+
+        if self.change_history.is_some() {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+                .split(area);
+            f.render_stateful_widget(audit_list, chunks[0], &mut self.audit_list_state);
+
+            let label = self.change_history.as_ref().map(|(label, _)| label.clone()).unwrap_or_default();
+            let title = tr_args(self.locale, "tui.block.change_history", &[("record", &label)]);
+            let history_list = List::new(self.get_change_history_list_items())
+                .block(Block::default().borders(Borders::ALL).title(title));
+            f.render_widget(history_list, chunks[1]);
+        } else {
+            f.render_stateful_widget(audit_list, area, &mut self.audit_list_state);
+        }
+    }
+
+    /// Render the live audit tail, newest entry at the bottom, with the
+    /// filter line below while it is being edited
+    fn render_audit_tail<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let filter_height = if self.audit_tail_filter_input.is_some() { 3 } else { 0 };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(filter_height)].as_ref())
+            .split(area);
+        let Some(tail) = &self.audit_tail else {
+            return;
+        };
+        let tz = self.display_timezone;
+        let items: Vec<ListItem> = tail
+            .view(chunks[0].height.saturating_sub(2) as usize)
+            .into_iter()
+            .map(|entry| {
+                let time = chrono::DateTime::parse_from_rfc3339(&entry.timestamp)
+                    .map(|t| tz.to_local(t.with_timezone(&chrono::Utc)).format("%H:%M:%S").to_string())
+                    .unwrap_or_else(|_| entry.timestamp.clone());
+                let color = match entry.outcome.as_str() {
+                    "FAILURE" => Color::Red,
+                    "WARNING" => Color::Yellow,
+                    _ => Color::White,
+                };
+                self.theme.item(format!(
+                    "{} {} {} {} [{}]",
+                    time, entry.user_id, entry.action, entry.resource, entry.outcome
+                ))
+                .style(self.theme.style(Style::default().fg(color)))
+            })
+            .collect();
+        let mut title = match tail.is_paused() {
+            true => tr_args(self.locale, "tui.block.audit_tail_paused", &[("pending", &tail.pending())]),
+            false => tr(self.locale, "tui.block.audit_tail_live").to_string(),
+        };
+        if !tail.filter.is_empty() {
+            title = format!("{} - {}", title, tail.filter);
+        }
+        f.render_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(title)), chunks[0]);
 
-        f.render_stateful_widget(audit_list, area, &mut self.audit_list_state);
+        if let Some(input) = &self.audit_tail_filter_input {
+            let line = Paragraph::new(self.theme.text(format!("{}▏", input)))
+                .block(Block::default().borders(Borders::ALL).title(tr(self.locale, "tui.audit_tail.filter")));
+            f.render_widget(line, chunks[1]);
+        }
     }
 
     /// Render reports tab
@@ -474,9 +1502,9 @@ TabState::Suppliers => self.supplier_list_state.select(Some(self.get_supplier_li
         let report_items = self.get_reports_list_items();
 
         let report_list = List::new(report_items)
-            .block(Block::default().borders(Borders::ALL).title("Reports"))
-            .highlight_style(Style::default().bg(Color::Magenta).fg(Color::White))
-            .highlight_symbol("▶ ");
+            .block(Block::default().borders(Borders::ALL).title(tr(self.locale, "tui.block.reports")))
+            .highlight_style(self.theme.highlight(Style::default().bg(Color::Magenta).fg(Color::White)))
+            .highlight_symbol(self.theme.highlight_symbol());
 
         f.render_stateful_widget(report_list, area, &mut self.reports_list_state);
     }
@@ -484,15 +1512,15 @@ TabState::Suppliers => self.supplier_list_state.select(Some(self.get_supplier_li
     /// Render CAPA tab
     fn render_capa<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
         let capa_items = vec![
-            ListItem::new("🔧 CAPA-001: Non-conforming Product Investigation [OPEN]"),
-            ListItem::new("🔧 CAPA-002: Audit Finding Remediation [IN PROGRESS]"),
-            ListItem::new("🔧 CAPA-003: Process Improvement Initiative [CLOSED]"),
+            self.theme.item("🔧 CAPA-2025-0001: Non-conforming Product Investigation [OPEN]"),
+            self.theme.item("🔧 CAPA-2025-0002: Audit Finding Remediation [IN PROGRESS]"),
+            self.theme.item("🔧 CAPA-2025-0003: Process Improvement Initiative [CLOSED]"),
         ];
 
         let capa_list = List::new(capa_items)
-            .block(Block::default().borders(Borders::ALL).title("CAPA Management"))
-            .highlight_style(Style::default().bg(Color::Yellow).fg(Color::Black))
-            .highlight_symbol("▶ ");
+            .block(Block::default().borders(Borders::ALL).title(tr(self.locale, "tui.block.capa")))
+            .highlight_style(self.theme.highlight(Style::default().bg(Color::Yellow).fg(Color::Black)))
+            .highlight_symbol(self.theme.highlight_symbol());
 
         f.render_stateful_widget(capa_list, area, &mut self.capa_list_state);
     }
@@ -502,9 +1530,9 @@ TabState::Suppliers => self.supplier_list_state.select(Some(self.get_supplier_li
         let supplier_items = self.get_supplier_list_items();
 
         let supplier_list = List::new(supplier_items)
-            .block(Block::default().borders(Borders::ALL).title("Supplier Management"))
-            .highlight_style(Style::default().bg(Color::Cyan).fg(Color::Black))
-            .highlight_symbol("▶ ");
+            .block(Block::default().borders(Borders::ALL).title(tr(self.locale, "tui.block.suppliers")))
+            .highlight_style(self.theme.highlight(Style::default().bg(Color::Cyan).fg(Color::Black)))
+            .highlight_symbol(self.theme.highlight_symbol());
 
         f.render_stateful_widget(supplier_list, area, &mut self.supplier_list_state);
     }
@@ -513,65 +1541,305 @@ TabState::Suppliers => self.supplier_list_state.select(Some(self.get_supplier_li
     fn render_training<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
         let items = self.get_training_list_items();
         let list = List::new(items)
-            .block(Block::default().borders(Borders::ALL).title("Training Records"))
-            .highlight_style(Style::default().bg(Color::LightGreen).fg(Color::Black))
-            .highlight_symbol("▶ ");
+            .block(Block::default().borders(Borders::ALL).title(tr(self.locale, "tui.block.training")))
+            .highlight_style(self.theme.highlight(Style::default().bg(Color::LightGreen).fg(Color::Black)))
+            .highlight_symbol(self.theme.highlight_symbol());
         f.render_stateful_widget(list, area, &mut self.training_list_state);
     }
 
-    /// Refresh metrics from the API if the refresh interval has elapsed.
-    fn refresh_metrics(&mut self) {
-        if self.last_metrics_fetch.elapsed() < Duration::from_secs(5) {
-            // Still process any queued messages even if we do not request new data
-        } else {
-            // Spawn non-blocking tasks for each endpoint
-            let tx = self.api_tx.clone();
-            tokio::spawn(async move {
-                if let Ok(resp) = reqwest::get("http://127.0.0.1:3000/metrics").await {
-                    if resp.status().is_success() {
-                        if let Ok(data) = resp.json::<MetricsResponse>().await {
-                            let _ = tx.send(MetricsMessage::CapaRisk(data));
-                        }
-                    }
-                }
-            });
+    /// Render Search tab: the query line above the ranked hits
+    fn render_search<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
+            .split(area);
+        let input = Paragraph::new(self.theme.text(format!("{}▏", self.search_query)))
+            .block(Block::default().borders(Borders::ALL).title(tr(self.locale, "tui.block.search")));
+        f.render_widget(input, chunks[0]);
 
-            let tx_sup = self.api_tx.clone();
-            tokio::spawn(async move {
-                if let Ok(resp) = reqwest::get("http://127.0.0.1:3000/supplier_metrics").await {
-                    if resp.status().is_success() {
-                        if let Ok(data) = resp.json::<SupplierMetrics>().await {
-                            let _ = tx_sup.send(MetricsMessage::Supplier(data));
-                        }
-                    }
-                }
-            });
+        let notice_height = if self.action_notice.is_some() { 3 } else { 0 };
+        let results = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(notice_height)].as_ref())
+            .split(chunks[1]);
+        let (list_area, detail_area) = self.split_panes(results[0]);
+        let title = tr_args(self.locale, "tui.block.search_results", &[("count", &self.search_result_count())]);
+        let list = List::new(self.get_search_list_items())
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(self.theme.highlight(Style::default().bg(Color::Blue).fg(Color::White)))
+            .highlight_symbol(self.theme.highlight_symbol());
+        f.render_stateful_widget(list, list_area, &mut self.search_list_state);
+        if let Some(detail_area) = detail_area {
+            self.render_detail_pane(f, detail_area);
+        }
+        if let Some(notice) = &self.action_notice {
+            f.render_widget(Paragraph::new(notice.clone()).block(Block::default().borders(Borders::ALL)), results[1]);
+        }
+        self.render_action_menu(f, area);
+    }
 
-            let tx_train = self.api_tx.clone();
-            tokio::spawn(async move {
-                if let Ok(resp) = reqwest::get("http://127.0.0.1:3000/training_metrics").await {
-                    if resp.status().is_success() {
-                        if let Ok(data) = resp.json::<TrainingMetrics>().await {
-                            let _ = tx_train.send(MetricsMessage::Training(data));
-                        }
+    /// Render the open action menu over `area`, with the input line while
+    /// the chosen action asks for text
+    fn render_action_menu<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let locale = self.locale;
+        let Some(menu) = self.action_menu.as_mut() else {
+            return;
+        };
+        let input_height = if menu.input.is_some() { 3 } else { 0 };
+        let height = (menu.actions.len() as u16 + 2 + input_height).min(area.height);
+        let width = (area.width * 3 / 5).max(40).min(area.width);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(input_height)].as_ref())
+            .split(popup);
+        f.render_widget(Clear, popup);
+
+        let title = tr_args(locale, "tui.block.actions", &[("record", &menu.record.reference)]);
+        let items: Vec<ListItem> = menu.actions.iter().map(|action| self.theme.item(action.label(locale))).collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(self.theme.highlight(Style::default().bg(Color::Yellow).fg(Color::Black)))
+            .highlight_symbol(self.theme.highlight_symbol());
+        f.render_stateful_widget(list, chunks[0], &mut menu.list_state);
+
+        if let Some(input) = &menu.input {
+            let prompt = match menu.selected_action() {
+                _ if menu.asks_for_reason() => "tui.actions.prompt.reason",
+                Some(RecordAction::Reassign) => "tui.actions.prompt.assignee",
+                _ => "tui.actions.prompt.note",
+            };
+            let line = Paragraph::new(self.theme.text(format!("{}▏", input)))
+                .block(Block::default().borders(Borders::ALL).title(tr(locale, prompt)));
+            f.render_widget(line, chunks[1]);
+        }
+    }
+
+    /// Render Jobs tab: one line per job, with the last manual run's outcome below
+    fn render_jobs<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let notice_height = if self.job_notice.is_some() { 3 } else { 0 };
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(notice_height)].as_ref())
+            .split(area);
+        let (list_area, detail_area) = self.split_panes(chunks[0]);
+        let list = List::new(self.get_job_list_items())
+            .block(Block::default().borders(Borders::ALL).title(tr(self.locale, "tui.block.jobs")))
+            .highlight_style(self.theme.highlight(Style::default().bg(Color::Blue).fg(Color::White)))
+            .highlight_symbol(self.theme.highlight_symbol());
+        f.render_stateful_widget(list, list_area, &mut self.jobs_list_state);
+        if let Some(detail_area) = detail_area {
+            self.render_detail_pane(f, detail_area);
+        }
+        if let Some(notice) = &self.job_notice {
+            f.render_widget(Paragraph::new(notice.clone()).block(Block::default().borders(Borders::ALL)), chunks[1]);
+        }
+    }
+
+    /// Render Records tab: records of the plugin types
+    fn render_records<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let (list_area, detail_area) = self.split_panes(area);
+        let list = List::new(self.get_record_list_items())
+            .block(Block::default().borders(Borders::ALL).title(tr(self.locale, "tui.block.records")))
+            .highlight_style(self.theme.highlight(Style::default().bg(Color::Blue).fg(Color::White)))
+            .highlight_symbol(self.theme.highlight_symbol());
+        f.render_stateful_widget(list, list_area, &mut self.records_list_state);
+        if let Some(detail_area) = detail_area {
+            self.render_detail_pane(f, detail_area);
+        }
+    }
+
+    /// Render Risk tab: the severity × probability heat map above the
+    /// assessments on the selected cell
+    fn render_risk<B: Backend>(&mut self, f: &mut Frame<B>, area: Rect) {
+        let (main_area, detail_area) = self.split_panes(area);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(60), Constraint::Min(0)].as_ref())
+            .split(main_area);
+        let title = if self.risk_residual { "tui.block.risk_residual" } else { "tui.block.risk_initial" };
+        let block = Block::default().borders(Borders::ALL).title(tr(self.locale, title));
+        let inner = block.inner(chunks[0]);
+        f.render_widget(block, chunks[0]);
+        if let Some(detail_area) = detail_area {
+            self.render_detail_pane(f, detail_area);
+        }
+
+        let size = RISK_MATRIX_SIZE as u16;
+        let cell_width = inner.width.saturating_sub(RISK_LABEL_WIDTH) / size;
+        let cell_height = inner.height.saturating_sub(1) / size;
+        self.risk_grid = None;
+        let Some(heat_map) = &self.risk_heat_map else {
+            f.render_widget(List::new(vec![self.pending_metrics_item("⏳ Fetching risk heat map...")]), inner);
+            return;
+        };
+        if cell_width == 0 || cell_height == 0 {
+            return;
+        }
+        let grid = Rect::new(inner.x + RISK_LABEL_WIDTH, inner.y, cell_width * size, cell_height * size);
+        for (r, cells) in heat_map.rows.iter().enumerate() {
+            let y = grid.y + r as u16 * cell_height;
+            if let Some(first) = cells.first() {
+                let label = format!("{:?} ({})", first.severity, first.severity as u8);
+                f.render_widget(Paragraph::new(label), Rect::new(inner.x, y + cell_height / 2, RISK_LABEL_WIDTH, 1));
+            }
+            for (c, cell) in cells.iter().enumerate() {
+                let (marker, color) = match cell.acceptability {
+                    RiskAcceptability::Acceptable => ("✓", Color::Green),
+                    RiskAcceptability::Tolerable => ("⚠", Color::Yellow),
+                    RiskAcceptability::Unacceptable => ("✗", Color::Red),
+                };
+                let style = Style::default().fg(Color::Black).bg(color);
+                let style = if (r, c) == self.risk_cell {
+                    self.theme.highlight(style.add_modifier(Modifier::REVERSED | Modifier::BOLD))
+                } else {
+                    self.theme.style(style)
+                };
+                let mut lines = vec![Line::from(""); usize::from((cell_height - 1) / 2)];
+                lines.push(Line::from(self.theme.text(format!("{} {}", marker, cell.assessments.len()))));
+                let area = Rect::new(grid.x + c as u16 * cell_width, y, cell_width, cell_height);
+                f.render_widget(Paragraph::new(lines).alignment(Alignment::Center).style(style), area);
+            }
+        }
+        for (c, probability) in RiskProbability::ALL.iter().enumerate() {
+            let label = Paragraph::new(format!("{:?}", probability)).alignment(Alignment::Center);
+            f.render_widget(label, Rect::new(grid.x + c as u16 * cell_width, grid.bottom(), cell_width, 1));
+        }
+        self.risk_grid = Some(grid);
+
+        let Some(cell) = self.selected_risk_cell() else {
+            return;
+        };
+        let title = tr_args(
+            self.locale,
+            "tui.block.risk_cell",
+            &[("severity", &format!("{:?}", cell.severity)), ("probability", &format!("{:?}", cell.probability))],
+        );
+        let items: Vec<ListItem> = cell
+            .assessments
+            .iter()
+            .map(|entry| {
+                self.theme.item(format!("{}: {} ({:?})", entry.device_name, entry.hazard_description, entry.status))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .highlight_style(self.theme.highlight(Style::default().bg(Color::Blue).fg(Color::White)))
+            .highlight_symbol(self.theme.highlight_symbol());
+        f.render_stateful_widget(list, chunks[1], &mut self.risk_list_state);
+    }
+
+    /// Fetch the change history of a record from the API for display in the
+    /// Audit Trail tab.
+    pub fn load_change_history(&mut self, record_type: &str, record_id: &str) {
+        let label = format!("{}:{}", record_type, record_id);
+        let request = self.api_request(&format!("/records/{}/{}/history", record_type, record_id));
+        let tx = self.api_tx.clone();
+        tokio::spawn(async move {
+            if let Ok(resp) = request.send().await {
+                if resp.status().is_success() {
+                    if let Ok(data) = resp.json::<Vec<ChangeRecord>>().await {
+                        let _ = tx.send(MetricsMessage::ChangeHistory(label, data));
                     }
                 }
-            });
+            }
+        });
+    }
 
+    /// Refresh metrics from the API if the refresh interval has elapsed.
+    fn refresh_metrics(&mut self) {
+        if self.last_metrics_fetch.elapsed() >= Duration::from_secs(5) {
+            // Spawn non-blocking tasks for each endpoint
+            spawn_fetch(self.api_tx.clone(), self.api_request("/metrics"), "/metrics", MetricsMessage::CapaRisk);
+            spawn_fetch(
+                self.api_tx.clone(),
+                self.api_request("/supplier_metrics"),
+                "/supplier_metrics",
+                MetricsMessage::Supplier,
+            );
+            spawn_fetch(
+                self.api_tx.clone(),
+                self.api_request("/training_metrics"),
+                "/training_metrics",
+                MetricsMessage::Training,
+            );
+            self.fetch_risk_heat_map();
+            self.load_dashboard_data();
+            self.load_job_statuses();
+            self.load_custom_records();
             self.last_metrics_fetch = Instant::now();
         }
+        // Still process any queued messages even if we do not request new data
+        self.process_api_messages();
+    }
+
+    /// Fetch the heat map for the initial or residual estimates, as shown.
+    fn fetch_risk_heat_map(&self) {
+        let path = if self.risk_residual { "/risk_heat_map?residual=true" } else { "/risk_heat_map" };
+        spawn_fetch(self.api_tx.clone(), self.api_request(path), path, MetricsMessage::RiskHeatMap);
+    }
+
+    /// GET request for `path` on the configured API.
+    fn api_request(&self, path: &str) -> reqwest::RequestBuilder {
+        let request = reqwest::Client::new().get(format!("{}{}", self.api_base, path));
+        match &self.api_token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
 
-        // Non-blocking processing of incoming messages
+    /// Non-blocking processing of incoming fetch results.
+    fn process_api_messages(&mut self) {
         loop {
             match self.api_rx.try_recv() {
                 Ok(MetricsMessage::CapaRisk(m)) => {
                     self.metrics = Some(m);
+                    self.mark_live();
                 }
                 Ok(MetricsMessage::Supplier(s)) => {
                     self.supplier_metrics = Some(s);
+                    self.mark_live();
                 }
                 Ok(MetricsMessage::Training(t)) => {
                     self.training_metrics = Some(t);
+                    self.mark_live();
+                }
+                Ok(MetricsMessage::ChangeHistory(label, history)) => {
+                    self.change_history = Some((label, history));
+                }
+                Ok(MetricsMessage::Search(results)) => {
+                    self.show_search_results(results);
+                    self.mark_live();
+                }
+                Ok(MetricsMessage::RiskHeatMap(heat_map)) => {
+                    self.show_risk_heat_map(heat_map);
+                    self.mark_live();
+                }
+                Ok(MetricsMessage::JobFinished(kind, result)) => {
+                    self.job_notice = Some(match result {
+                        Ok(run) => tr_args(
+                            self.locale,
+                            "tui.jobs.finished",
+                            &[
+                                ("job", &kind),
+                                ("outcome", &run.outcome.as_str()),
+                                ("duration", &run.duration_ms),
+                                ("detail", &run.detail.unwrap_or_default()),
+                            ],
+                        ),
+                        Err(e) => tr_args(self.locale, "tui.jobs.failed", &[("job", &kind), ("message", &e)]),
+                    });
+                    self.load_job_statuses();
+                }
+                Ok(MetricsMessage::Unavailable(endpoint)) => {
+                    tracing::debug!("API fetch of {} failed; using offline data", endpoint);
+                    self.offline_since.get_or_insert_with(Instant::now);
+                    self.load_local_metrics();
                 }
                 Err(tokio::sync::mpsc::error::TryRecvError::Empty) => break,
                 Err(tokio::sync::mpsc::error::TryRecvError::Disconnected) => break,
@@ -579,52 +1847,359 @@ TabState::Suppliers => self.supplier_list_state.select(Some(self.get_supplier_li
         }
     }
 
+    /// Replace the heat map unless it is for the estimates no longer shown;
+    /// the assessment list is left when its cell has emptied.
+    fn show_risk_heat_map(&mut self, heat_map: RiskHeatMap) {
+        if heat_map.residual != self.risk_residual {
+            return;
+        }
+        self.risk_heat_map = Some(heat_map);
+        let len = self.selected_risk_cell().map_or(0, |cell| cell.assessments.len());
+        if len == 0 {
+            self.select_risk_cell(self.risk_cell);
+        } else if self.risk_list_state.selected().map_or(false, |i| i >= len) {
+            self.risk_list_state.select(Some(len - 1));
+        }
+    }
+
+    fn mark_live(&mut self) {
+        self.offline_since = None;
+        self.last_live_update = Some(Instant::now());
+    }
+
+    /// Replace metrics with direct reads of the fallback database. Without one
+    /// (or if the read fails) the last fetched values stay on screen.
+    fn load_local_metrics(&mut self) {
+        let Some(database) = &self.fallback_db else {
+            return;
+        };
+        let repo = SummaryRepo::new(database);
+        let now = chrono::Utc::now();
+        match repo.capa_metrics(DEFAULT_SITE_ID, now) {
+            Ok(capa) => self.local_capa_metrics = Some(capa),
+            Err(e) => tracing::warn!("offline CAPA metrics unavailable: {e}"),
+        }
+        match repo.supplier_metrics(DEFAULT_SITE_ID, now) {
+            Ok(suppliers) => self.supplier_metrics = Some(suppliers),
+            Err(e) => tracing::warn!("offline supplier metrics unavailable: {e}"),
+        }
+        match repo.training_metrics(DEFAULT_SITE_ID, now) {
+            Ok(training) => self.training_metrics = Some(training),
+            Err(e) => tracing::warn!("offline training metrics unavailable: {e}"),
+        }
+    }
+
+    /// Reload the dashboard's audit finding aging, MDR clocks and 14-day
+    /// environmental trends from the same-process database, when there is one.
+    fn load_dashboard_data(&mut self) {
+        let Some(database) = &self.fallback_db else {
+            return;
+        };
+        let now = chrono::Utc::now();
+        match FindingRepo::new(database).aging(now) {
+            Ok(aging) => self.audit_finding_aging = Some(aging),
+            Err(e) => tracing::warn!("audit finding aging unavailable: {e}"),
+        }
+        match EnvironmentalRepo::new(database).dashboard_trends(now - chrono::Duration::days(14)) {
+            Ok(trends) => self.environmental_trends = trends,
+            Err(e) => tracing::warn!("environmental trends unavailable: {e}"),
+        }
+        if self.feature_enabled(Feature::PostMarket) {
+            match MdrClockRepo::new(database).open() {
+                Ok(clocks) => self.mdr_clocks = clocks,
+                Err(e) => tracing::warn!("MDR clocks unavailable: {e}"),
+            }
+        }
+        match storage_monitor::latest_sample(database) {
+            Ok(sample) => self.storage_sample = sample,
+            Err(e) => tracing::warn!("storage sample unavailable: {e}"),
+        }
+    }
+
+    /// Reload the status of the background jobs, when running in-process.
+    fn load_job_statuses(&mut self) {
+        let Some(context) = &self.context else {
+            return;
+        };
+        match context.jobs.statuses(chrono::Utc::now()) {
+            Ok(statuses) => {
+                if self.jobs_list_state.selected().map_or(true, |i| i >= statuses.len()) {
+                    self.jobs_list_state.select(if statuses.is_empty() { None } else { Some(0) });
+                }
+                self.job_statuses = statuses;
+            }
+            Err(e) => tracing::warn!("job statuses unavailable: {e}"),
+        }
+    }
+
+    /// Reload the records of the plugin types, when any are registered.
+    fn load_custom_records(&mut self) {
+        let Some(context) = self.context.as_ref().filter(|context| !context.plugins.is_empty()) else {
+            return;
+        };
+        match CustomRecordRepo::new(&context.database, &context.plugins).list(None) {
+            Ok(records) => {
+                if self.records_list_state.selected().map_or(true, |i| i >= records.len()) {
+                    self.records_list_state.select(if records.is_empty() { None } else { Some(0) });
+                }
+                self.custom_records = records;
+            }
+            Err(e) => tracing::warn!("plugin records unavailable: {e}"),
+        }
+    }
+
+    /// Placeholder for metrics not received yet; says so once the API is known
+    /// to be down instead of waiting forever.
+    fn pending_metrics_item(&self, fetching: &'static str) -> ratatui::widgets::ListItem<'static> {
+        if self.offline_since.is_some() {
+            self.theme.item("⚠ Metrics unavailable (API offline)")
+        } else {
+            self.theme.item(fetching)
+        }
+    }
+
     /// Construct list items for the Reports tab based on current metrics.
     fn get_reports_list_items(&self) -> Vec<ratatui::widgets::ListItem<'static>> {
-        use ratatui::widgets::ListItem;
-        if let Some(metrics) = &self.metrics {
+        let capa_total = match (&self.local_capa_metrics, &self.metrics) {
+            (Some(local), _) if self.offline_since.is_some() => Some(local.total_count),
+            (_, Some(metrics)) => Some(metrics.capa_metrics.total_count),
+            _ => None,
+        };
+        if let Some(capa_total) = capa_total {
+            let risk = match &self.metrics {
+                Some(metrics) => format!("🛡️  Risk Assessments: {}", metrics.risk_report.total_assessments),
+                None => "🛡️  Risk Assessments: unavailable offline".to_string(),
+            };
+            let freshness = if self.offline_since.is_some() { "📉 Data stale ⚠" } else { "📈 Data fresh ✔️" };
             vec![
-                ListItem::new(format!("🚀 CAPA Total: {}", metrics.capa_metrics.total_count)),
-                ListItem::new(format!("🛡️  Risk Assessments: {}", metrics.risk_report.total_assessments)),
-                ListItem::new("📈 Data fresh ✔️"),
+                self.theme.item(format!("🚀 CAPA Total: {}", capa_total)),
+                self.theme.item(risk),
+                self.theme.item(freshness),
             ]
         } else {
-            vec![ListItem::new("⏳ Fetching metrics...")]
+            vec![self.pending_metrics_item("⏳ Fetching metrics...")]
         }
     }
 
+    /// One line per KPI from the latest metrics, coloured by its RAG status.
+    fn get_kpi_list_items(&self) -> Vec<ratatui::widgets::ListItem<'static>> {
+        let Some(metrics) = &self.metrics else {
+            return Vec::new();
+        };
+        metrics
+            .kpis
+            .iter()
+            .map(|kpi| {
+                let change = kpi.change().map(|change| format!("{:+.1}", change)).unwrap_or_default();
+                let line = tr_args(
+                    self.locale,
+                    "tui.kpi.line",
+                    &[
+                        ("label", &kpi.label),
+                        ("actual", &format!("{:.1}%", kpi.actual)),
+                        ("target", &kpi.target.describe()),
+                        ("change", &change),
+                    ],
+                );
+                let color = match kpi.status {
+                    RagStatus::Green => Color::Green,
+                    RagStatus::Amber => Color::Yellow,
+                    RagStatus::Red => Color::Red,
+                };
+                // Without colors the RAG status is spelled out
+                let marker = match self.theme.is_accessible() {
+                    true => format!("[{}]", kpi.status.as_str()),
+                    false => "●".to_string(),
+                };
+                self.theme
+                    .item(format!("{} {}", marker, line.trim_end()))
+                    .style(self.theme.style(Style::default().fg(color)))
+            })
+            .collect()
+    }
+
+    /// Warning line for low disk space, coloured by level.
+    fn get_storage_warning_item(&self) -> Option<ratatui::widgets::ListItem<'static>> {
+        let sample = self.storage_sample.as_ref()?;
+        let (key, color) = match sample.level {
+            StorageLevel::Ok => return None,
+            StorageLevel::Warning => ("tui.storage.warning", Color::Yellow),
+            StorageLevel::Critical => ("tui.storage.critical", Color::Red),
+        };
+        let line = tr_args(self.locale, key, &[("detail", &sample.describe())]);
+        Some(self.theme.item(format!("⚠ {}", line)).style(self.theme.style(Style::default().fg(color))))
+    }
+
+    /// Countdown lines for the open MDR clocks shown on the dashboard.
+    fn get_mdr_clock_list_items(&self) -> Vec<ratatui::widgets::ListItem<'static>> {
+        let today = chrono::Utc::now().date_naive();
+        let warning_days = self
+            .context
+            .as_ref()
+            .map_or(ComplianceConfig::default().mdr_warning_days, |c| c.config.compliance.mdr_warning_days);
+        self.mdr_clocks
+            .iter()
+            .take(DASHBOARD_MDR_CLOCKS)
+            .map(|clock| {
+                let event = clock.adverse_event_id.to_string();
+                let days = clock.days_remaining(today);
+                let args: [(&str, &dyn std::fmt::Display); 4] =
+                    [("report", &clock.kind.label()), ("event", &&event[..8]), ("due", &clock.due_on), ("days", &days)];
+                let (key, color) = match clock.status(today, warning_days) {
+                    ClockStatus::Breached => ("tui.mdr.overdue", Color::Red),
+                    ClockStatus::DueSoon => ("tui.mdr.due_in", Color::Yellow),
+                    _ => ("tui.mdr.due_in", Color::Green),
+                };
+                self.theme
+                    .item(format!("⏱ {}", tr_args(self.locale, key, &args)))
+                    .style(self.theme.style(Style::default().fg(color)))
+            })
+            .collect()
+    }
+
     /// Construct list items for the Suppliers tab based on current metrics.
     fn get_supplier_list_items(&self) -> Vec<ratatui::widgets::ListItem<'static>> {
-        use ratatui::widgets::ListItem;
         if let Some(metrics) = &self.supplier_metrics {
             vec![
-                ListItem::new(format!("🏢 Total Suppliers: {}", metrics.total_count)),
-                ListItem::new(format!("✅ Qualified: {}", metrics.qualified_count)),
-                ListItem::new(format!("⏳ Pending: {}", metrics.pending_count)),
-                ListItem::new(format!("❌ Disqualified: {}", metrics.disqualified_count)),
-                ListItem::new(format!("📊 Qualified %: {:.1}%", metrics.qualified_percentage)),
+                self.theme.item(format!("🏢 Total Suppliers: {}", metrics.total_count)),
+                self.theme.item(format!("✅ Qualified: {}", metrics.qualified_count)),
+                self.theme.item(format!("⏳ Pending: {}", metrics.pending_count)),
+                self.theme.item(format!("❌ Disqualified: {}", metrics.disqualified_count)),
+                self.theme.item(format!("📊 Qualified %: {:.1}%", metrics.qualified_percentage)),
             ]
         } else {
-            vec![ListItem::new("⏳ Fetching supplier metrics...")]
+            vec![self.pending_metrics_item("⏳ Fetching supplier metrics...")]
+        }
+    }
+
+    /// Construct one list item per changed field of the loaded change history.
+    fn get_change_history_list_items(&self) -> Vec<ratatui::widgets::ListItem<'static>> {
+        let tz = self.display_timezone;
+        match &self.change_history {
+            Some((_, history)) if !history.is_empty() => history
+                .iter()
+                .flat_map(|record| {
+                    record.changes.iter().map(move |change| {
+                        self.theme.item(format!(
+                            "✏️  {} {}: {} '{}' → '{}' ({})",
+                            tz.format(record.changed_at),
+                            record.changed_by,
+                            change.field,
+                            change.old_value.as_deref().unwrap_or("-"),
+                            change.new_value.as_deref().unwrap_or("-"),
+                            record.reason
+                        ))
+                    })
+                })
+                .collect(),
+            Some(_) => vec![self.theme.item("No recorded changes")],
+            None => vec![self.theme.item("⏳ Fetching change history...")],
+        }
+    }
+
+    fn search_result_count(&self) -> usize {
+        self.search_results.as_ref().map_or(0, Vec::len)
+    }
+
+    /// Construct one list item per search hit.
+    fn get_search_list_items(&self) -> Vec<ratatui::widgets::ListItem<'static>> {
+        match &self.search_results {
+            Some(results) if !results.is_empty() => results
+                .iter()
+                .map(|hit| {
+                    let line = format!("[{}] {} - {} ({})", hit.record_type(), hit.reference, hit.title, hit.status);
+                    self.theme.item(line)
+                })
+                .collect(),
+            Some(_) => vec![self.theme.item(tr(self.locale, "tui.search.no_results").to_string())],
+            None => vec![self.theme.item(tr(self.locale, "tui.search.hint").to_string())],
+        }
+    }
+
+    /// One line per plugin record: its type, reference, title and status.
+    fn get_record_list_items(&self) -> Vec<ratatui::widgets::ListItem<'static>> {
+        let Some(context) = self.context.as_ref().filter(|_| !self.custom_records.is_empty()) else {
+            return vec![self.theme.item(tr(self.locale, "tui.records.none").to_string())];
+        };
+        self.custom_records
+            .iter()
+            .map(|record| {
+                let kind = context.plugins.display_name(&record.record_type);
+                self.theme.item(format!("[{}] {} - {} ({})", kind, record.reference, record.title, record.status))
+            })
+            .collect()
+    }
+
+    /// One line per background job: last run, duration and result, next run.
+    fn get_job_list_items(&self) -> Vec<ratatui::widgets::ListItem<'static>> {
+        if self.job_statuses.is_empty() {
+            return vec![self.theme.item(tr(self.locale, "tui.jobs.none").to_string())];
         }
+        let tz = self.display_timezone;
+        self.job_statuses
+            .iter()
+            .map(|status| {
+                let next_run = tz.format(status.next_run_at);
+                let (line, color) = match &status.last_run {
+                    Some(run) => (
+                        tr_args(
+                            self.locale,
+                            "tui.jobs.line",
+                            &[
+                                ("job", &status.job),
+                                ("last_run", &tz.format(run.started_at)),
+                                ("duration", &run.duration_ms),
+                                ("outcome", &run.outcome.as_str()),
+                                ("next_run", &next_run),
+                            ],
+                        ),
+                        if run.outcome == JobOutcome::Succeeded { Color::Green } else { Color::Red },
+                    ),
+                    None => (
+                        tr_args(self.locale, "tui.jobs.never_run", &[("job", &status.job), ("next_run", &next_run)]),
+                        Color::White,
+                    ),
+                };
+                let marker = if status.running { "⟳" } else { "●" };
+                self.theme.item(format!("{} {}", marker, line)).style(self.theme.style(Style::default().fg(color)))
+            })
+            .collect()
     }
 
     /// Construct list items for the Training tab based on current metrics.
     fn get_training_list_items(&self) -> Vec<ratatui::widgets::ListItem<'static>> {
-        use ratatui::widgets::ListItem;
         if let Some(metrics) = &self.training_metrics {
             vec![
-                ListItem::new(format!("👥 Total Trainings: {}", metrics.total_count)),
-                ListItem::new(format!("✅ Completed: {}", metrics.completed)),
-                ListItem::new(format!("⏳ Pending: {}", metrics.pending)),
-                ListItem::new(format!("⚠️  Overdue: {}", metrics.overdue)),
+                self.theme.item(format!("👥 Total Trainings: {}", metrics.total_count)),
+                self.theme.item(format!("✅ Completed: {}", metrics.completed)),
+                self.theme.item(format!("⏳ Pending: {}", metrics.pending)),
+                self.theme.item(format!("⚠️  Overdue: {}", metrics.overdue)),
             ]
         } else {
-            vec![ListItem::new("⏳ Fetching training metrics...")]
+            vec![self.pending_metrics_item("⏳ Fetching training metrics...")]
         }
     }
 }
 
+/// Send `request` and forward the decoded payload, or report `path` as
+/// unavailable.
+fn spawn_fetch<T>(
+    tx: UnboundedSender<MetricsMessage>,
+    request: reqwest::RequestBuilder,
+    path: &'static str,
+    wrap: fn(T) -> MetricsMessage,
+) where
+    T: serde::de::DeserializeOwned + Send + 'static,
+{
+    tokio::spawn(async move {
+        let fetched = match request.send().await {
+            Ok(resp) if resp.status().is_success() => resp.json::<T>().await.ok(),
+            _ => None,
+        };
+        let _ = tx.send(fetched.map_or(MetricsMessage::Unavailable(path), wrap));
+    });
+}
+
 /// Tab states for navigation
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TabState {
@@ -635,13 +2210,68 @@ pub enum TabState {
     Suppliers = 4,
     Training = 5,
     Reports = 6,
+    Search = 7,
+    Jobs = 8,
+    Risk = 9,
+    Records = 10,
+}
+
+impl TabState {
+    /// Tabs in tab bar order
+    pub const ALL: [TabState; 11] = [
+        TabState::Dashboard,
+        TabState::Documents,
+        TabState::AuditTrail,
+        TabState::Capa,
+        TabState::Suppliers,
+        TabState::Training,
+        TabState::Reports,
+        TabState::Search,
+        TabState::Jobs,
+        TabState::Risk,
+        TabState::Records,
+    ];
+
+    /// Module whose feature flag hides the tab; the dashboard and the audit
+    /// trail are always shown, records whenever a plugin is registered
+    pub fn feature(self) -> Option<Feature> {
+        match self {
+            TabState::Dashboard | TabState::AuditTrail | TabState::Records => None,
+            TabState::Documents => Some(Feature::Documents),
+            TabState::Capa => Some(Feature::Capa),
+            TabState::Suppliers => Some(Feature::Suppliers),
+            TabState::Training => Some(Feature::Training),
+            TabState::Reports => Some(Feature::Reports),
+            TabState::Search => Some(Feature::Search),
+            TabState::Jobs => Some(Feature::Jobs),
+            TabState::Risk => Some(Feature::Risk),
+        }
+    }
+
+    fn title_id(self) -> &'static str {
+        match self {
+            TabState::Dashboard => "tui.tab.dashboard",
+            TabState::Documents => "tui.tab.documents",
+            TabState::AuditTrail => "tui.tab.audit_trail",
+            TabState::Capa => "tui.tab.capa",
+            TabState::Suppliers => "tui.tab.suppliers",
+            TabState::Training => "tui.tab.training",
+            TabState::Reports => "tui.tab.reports",
+            TabState::Search => "tui.tab.search",
+            TabState::Jobs => "tui.tab.jobs",
+            TabState::Risk => "tui.tab.risk",
+            TabState::Records => "tui.tab.records",
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::capa::CapaService;
     use crate::supplier::SupplierMetrics;
     use crate::training::TrainingMetrics;
+use crate::tui_theme::TuiTheme;
 
     #[test]
     fn test_tui_app_creation() {
@@ -651,6 +2281,481 @@ mod tests {
         assert_eq!(app.selected_menu_item, 0);
     }
 
+    #[test]
+    fn test_labels_follow_locale() {
+        let mut app = TuiApp::new().with_locale(Locale::De);
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(120, 12)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol.as_str()).collect();
+        assert!(screen.contains("Lieferanten"));
+        assert!(screen.contains("Systemstatus"));
+        assert!(!screen.contains("Suppliers"));
+    }
+
+    fn test_db() -> Database {
+        Database::new(crate::config::DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_offline_falls_back_to_local_database() {
+        let db = test_db();
+        let now = chrono::Utc::now();
+        crate::supplier_repo::SupplierRepository::new(db.clone())
+            .insert(&crate::supplier::Supplier {
+                id: uuid::Uuid::new_v4(),
+                name: "Acme".to_string(),
+                contact_info: None,
+                status: crate::supplier::SupplierStatus::Pending,
+                qualification_date: None,
+                qualification_expiry_date: None,
+                approved_by: None,
+                created_at: now,
+                updated_at: now,
+                row_version: 1,
+            })
+            .unwrap();
+        let mut app = TuiApp::new().with_offline_fallback(db);
+        app.api_tx.send(MetricsMessage::Unavailable("/supplier_metrics")).unwrap();
+        app.process_api_messages();
+
+        assert!(app.offline_since.is_some());
+        assert_eq!(app.supplier_metrics.as_ref().unwrap().total_count, 1);
+        assert!(app.offline_banner().unwrap().contains("local database"));
+
+        app.api_tx.send(MetricsMessage::Supplier(SupplierMetrics::default())).unwrap();
+        app.process_api_messages();
+        assert!(app.offline_since.is_none());
+        assert!(app.offline_banner().is_none());
+    }
+
+    #[test]
+    fn test_offline_without_fallback_keeps_cached_values() {
+        let mut app = TuiApp::new();
+        app.api_tx.send(MetricsMessage::Unavailable("/training_metrics")).unwrap();
+        app.process_api_messages();
+        assert!(app.training_metrics.is_none());
+        assert!(app.offline_banner().unwrap().contains("cached values (no live data yet)"));
+
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(120, 12)).unwrap();
+        app.current_tab = TabState::Training;
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol.as_str()).collect();
+        assert!(screen.contains("API offline"));
+        assert!(!screen.contains("Fetching"));
+    }
+
+    #[test]
+    fn test_dashboard_shows_findings_and_environmental_trends() {
+        let db = test_db();
+        let repo = EnvironmentalRepo::new(&db);
+        let limit = crate::environmental::MonitoringLimit {
+            area: "ISO7-A".to_string(),
+            parameter: crate::environmental::Parameter::Temperature,
+            min: Some(18.0),
+            max: Some(24.0),
+        };
+        repo.set_limit(&limit, "qa_manager").unwrap();
+        repo.record_reading("ISO7-A", limit.parameter, 26.0, "op1").unwrap();
+
+        let mut app = TuiApp::new().with_offline_fallback(db);
+        app.load_dashboard_data();
+        assert_eq!(app.environmental_trends.len(), 1);
+        assert_eq!(app.audit_finding_aging, Some(FindingAging::default()));
+
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol.as_str()).collect();
+        assert!(screen.contains("ISO7-A temperature (1 excursions)"));
+        assert!(screen.contains("0-30 days: 0"));
+    }
+
+    #[test]
+    fn test_dashboard_counts_down_mdr_clocks() {
+        use crate::mdr_clock::MdrClockKind;
+        use crate::post_market::{AdverseEvent, AdverseEventRepo, Severity};
+
+        let db = test_db();
+        let event = AdverseEvent::new("field_service", "Infusion pump over-delivery", Severity::Critical);
+        AdverseEventRepo::new(&db).insert(&event).unwrap();
+        let today = chrono::Utc::now().date_naive();
+        let repo = MdrClockRepo::new(&db);
+        repo.start(event.id, MdrClockKind::FiveDay, today - chrono::Duration::days(10), "qa_manager").unwrap();
+        repo.start(event.id, MdrClockKind::ThirtyDay, today, "qa_manager").unwrap();
+
+        let mut app = TuiApp::new().with_offline_fallback(db);
+        app.load_dashboard_data();
+        assert_eq!(app.mdr_clocks.len(), 2);
+
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol.as_str()).collect();
+        let short_id = &event.id.to_string()[..8];
+        assert!(screen.contains("MDR Reporting Deadlines"));
+        assert!(screen.contains(&format!("5-day report for event {}: overdue since", short_id)));
+        assert!(screen.contains("(30 day(s) left)"));
+    }
+
+    #[test]
+    fn test_dashboard_warns_about_low_disk_space() {
+        use crate::config::StorageConfig;
+        use crate::storage_monitor::{StorageMonitor, StorageUsage};
+
+        let db = test_db();
+        let config = StorageConfig::default();
+        let usage = StorageUsage { database_bytes: 1000 << 20, log_bytes: 10 << 20, free_bytes: 400 << 20 };
+        StorageMonitor::new(&db, &config).record(usage, chrono::Utc::now()).unwrap();
+
+        let mut app = TuiApp::new().with_offline_fallback(db);
+        app.load_dashboard_data();
+        assert_eq!(app.storage_sample.as_ref().map(|s| s.level), Some(StorageLevel::Critical));
+
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol.as_str()).collect();
+        assert!(screen.contains("Disk space critical: 400.0 MB free; database 1000.0 MB, logs 10.0 MB"));
+    }
+
+    #[test]
+    fn test_accessible_mode_renders_ascii_without_colors() {
+        let mut config = crate::config::Config::default();
+        config.tui.accessible = true;
+        let context = AppContext::with_database(config, test_db());
+        let mut app = TuiApp::new().with_context(&context);
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(100, 20)).unwrap();
+
+        let mut screens = Vec::new();
+        for tab in [TabState::Dashboard, TabState::Documents, TabState::Suppliers] {
+            app.current_tab = tab;
+            terminal.draw(|f| app.render(f)).unwrap();
+            let cells = terminal.backend().buffer().content();
+            let screen: String = cells.iter().map(|cell| cell.symbol.as_str()).collect();
+            // Only ASCII and the box drawing of the borders
+            assert!(screen.chars().all(|c| c.is_ascii() || ('\u{2500}'..='\u{257F}').contains(&c)), "{}", screen);
+            assert!(cells.iter().all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset));
+            screens.push(screen);
+        }
+        assert!(screens[0].contains("> [OK] FDA CFR Part 820 Compliance: ACTIVE"));
+        assert!(screens[1].contains("SOP-001: Quality System Procedures [APPROVED]"));
+        assert!(screens[2].contains("[..] Fetching supplier metrics..."));
+    }
+
+    #[test]
+    fn test_search_screen_lists_local_results() {
+        let db = test_db();
+        db.with_connection(|conn| {
+            conn.execute("INSERT INTO suppliers (id, name, qualification_status) VALUES ('s-1', 'Acme Seals', 'Qualified')", [])?;
+            Ok(())
+        })
+        .unwrap();
+        let mut app = TuiApp::new().with_offline_fallback(db);
+        app.current_tab = TabState::Search;
+        for c in "acmx".chars() {
+            assert!(app.edit_search_query(KeyCode::Char(c)));
+        }
+        app.edit_search_query(KeyCode::Backspace);
+        app.edit_search_query(KeyCode::Char('e'));
+        assert!(app.edit_search_query(KeyCode::Enter));
+        assert!(!app.edit_search_query(KeyCode::Down));
+        assert_eq!(app.search_query, "acme");
+        assert_eq!(app.search_result_count(), 1);
+        assert_eq!(app.search_list_state.selected(), Some(0));
+
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 12)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol.as_str()).collect();
+        assert!(screen.contains("[supplier] s-1 - Acme Seals (Qualified)"));
+        assert!(screen.contains("Results (1)"));
+    }
+
+    #[test]
+    fn test_jobs_tab_runs_selected_job_for_permitted_roles() {
+        let db = test_db();
+        db.seed_test_users(&["manager"]);
+        db.with_connection(|conn| {
+            conn.execute("UPDATE users SET role = 'QualityManager' WHERE id = 'manager'", [])?;
+            Ok(())
+        })
+        .unwrap();
+        let context = AppContext::with_database(crate::config::Config::default(), db);
+        let mut app = TuiApp::new().with_context(&context);
+        app.current_tab = TabState::Jobs;
+        app.load_job_statuses();
+        assert_eq!(app.job_statuses.len(), 4);
+        app.move_down();
+        assert_eq!(app.job_statuses[app.jobs_list_state.selected().unwrap()].job, JobKind::OverdueScan);
+
+        app.handle_enter();
+        assert_eq!(app.job_notice.as_deref(), Some("Your role does not permit running overdue_scan"));
+        assert!(app.job_statuses[1].last_run.is_none());
+
+        context.set_session(Some(crate::app_context::SessionContext {
+            user_id: "manager".to_string(),
+            session_id: "s-1".to_string(),
+        }));
+        app.handle_enter();
+        assert!(app.job_notice.as_deref().unwrap().starts_with("overdue_scan succeeded in"));
+        let run = app.job_statuses[1].last_run.clone().unwrap();
+        assert_eq!((run.trigger, run.triggered_by.as_str()), (JobTrigger::Manual, "manager"));
+
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(120, 12)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol.as_str()).collect();
+        assert!(screen.contains("backup: never run"));
+        assert!(screen.contains("overdue_scan: last run"));
+    }
+
+    #[test]
+    fn test_action_menu_changes_status_of_selected_capa() {
+        let db = test_db();
+        db.seed_test_users(&["engineer"]);
+        let context = AppContext::with_database(crate::config::Config::default(), db);
+        let capa = context
+            .capa_service
+            .create_capa(
+                "Seal failure".to_string(),
+                "Lot 42 failed peel test".to_string(),
+                crate::capa::CapaType::Corrective,
+                crate::capa::CapaPriority::High,
+                "engineer".to_string(),
+                "engineer".to_string(),
+                None,
+            )
+            .unwrap();
+        let mut app = TuiApp::new().with_context(&context);
+        app.current_tab = TabState::Search;
+        app.search_query = "seal".to_string();
+        app.run_search();
+        assert!(app.edit_search_query(KeyCode::Char('a')));
+        let menu = app.action_menu.as_ref().unwrap();
+        assert_eq!(menu.record.id, capa.id);
+        assert_eq!(menu.actions[0], RecordAction::ChangeStatus(crate::capa::CapaStatus::InvestigationInProgress));
+
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(100, 16)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol.as_str()).collect();
+        assert!(screen.contains(&format!("Actions - {}", capa.capa_number)));
+        assert!(screen.contains("Change status to Investigation In Progress"));
+
+        // Status changes ask for the reason; the system user (no session) has no role
+        let give_reason = |app: &mut TuiApp| {
+            app.handle_action_menu_key(KeyCode::Enter);
+            assert!(app.action_menu.as_ref().unwrap().asks_for_reason());
+            for c in "Complaint trend".chars() {
+                app.handle_action_menu_key(KeyCode::Char(c));
+            }
+            app.handle_action_menu_key(KeyCode::Enter);
+        };
+        give_reason(&mut app);
+        let denied = "Your role does not permit: Change status to Investigation In Progress";
+        assert_eq!(app.action_notice.as_deref(), Some(denied));
+        assert!(app.action_menu.is_none());
+
+        context.set_session(Some(crate::app_context::SessionContext {
+            user_id: "engineer".to_string(),
+            session_id: "s-1".to_string(),
+        }));
+        app.edit_search_query(KeyCode::Enter);
+        give_reason(&mut app);
+        let expected = format!("Done: {} moved to Investigation In Progress", capa.capa_number);
+        assert_eq!(app.action_notice.as_deref(), Some(expected.as_str()));
+        assert_eq!(app.search_results.as_ref().unwrap()[0].status, "InvestigationInProgress");
+
+        // Text actions ask for input; Esc steps back, then closes the menu
+        app.edit_search_query(KeyCode::Enter);
+        let note = app.action_menu.as_ref().unwrap().actions.iter().position(|a| *a == RecordAction::AddNote).unwrap();
+        app.action_menu.as_mut().unwrap().list_state.select(Some(note));
+        app.handle_action_menu_key(KeyCode::Enter);
+        for c in "Called supplier".chars() {
+            app.handle_action_menu_key(KeyCode::Char(c));
+        }
+        app.handle_action_menu_key(KeyCode::Esc);
+        assert_eq!(app.action_menu.as_ref().unwrap().input, None);
+        app.handle_action_menu_key(KeyCode::Esc);
+        assert!(app.action_menu.is_none());
+    }
+
+    #[test]
+    fn test_audit_trail_live_tail() {
+        let db = test_db();
+        let audit = crate::audit::AuditManager::new(db.clone());
+        audit.log_action("alice", "login", "session", "Success", None).unwrap();
+        let mut app = TuiApp::new().with_offline_fallback(db);
+        app.current_tab = TabState::AuditTrail;
+        assert!(!app.handle_audit_tail_key(KeyCode::Char('p')), "no tail yet");
+        assert!(app.handle_audit_tail_key(KeyCode::Char('t')));
+
+        audit.log_action("bob", "document_approved", "document:d1", "Failure", None).unwrap();
+        app.last_tail_poll = Instant::now() - AUDIT_TAIL_POLL_INTERVAL;
+        app.poll_audit_tail();
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(100, 12)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol.as_str()).collect();
+        assert!(screen.contains("Audit Trail - live"));
+        assert!(screen.contains("alice login session [SUCCESS]"));
+        assert!(screen.contains("bob document_approved document:d1 [FAILURE]"));
+
+        app.handle_audit_tail_key(KeyCode::Char('f'));
+        for c in "user:bob".chars() {
+            assert!(app.handle_audit_tail_key(KeyCode::Char(c)));
+        }
+        app.handle_audit_tail_key(KeyCode::Enter);
+        app.handle_audit_tail_key(KeyCode::Char('p'));
+        audit.log_action("bob", "logout", "session", "Success", None).unwrap();
+        app.last_tail_poll = Instant::now() - AUDIT_TAIL_POLL_INTERVAL;
+        app.poll_audit_tail();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol.as_str()).collect();
+        assert!(screen.contains("Audit Trail - paused, 1 new (p resumes) - user:bob"));
+        assert!(!screen.contains("alice login"));
+
+        assert!(app.handle_audit_tail_key(KeyCode::Char('t')));
+        assert!(app.audit_tail.is_none());
+    }
+
+    #[test]
+    fn test_detail_pane_resizes_and_saves_layout_per_user() {
+        let db = test_db();
+        db.with_connection(|conn| {
+            conn.execute("INSERT INTO suppliers (id, name, qualification_status) VALUES ('s-1', 'Acme Seals', 'Qualified')", [])?;
+            Ok(())
+        })
+        .unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        let config_file = dir.path().join("qms.toml");
+        std::fs::write(&config_file, "").unwrap();
+        let context = AppContext::with_database(crate::config::Config::default(), db);
+        let mut app = TuiApp::new().with_context(&context).with_layout_file(&config_file);
+        app.current_tab = TabState::Search;
+        app.search_query = "acme".to_string();
+        app.run_search();
+
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(100, 14)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let buffer = terminal.backend().buffer().clone();
+        let row = |y: u16| (0..100).map(|x| buffer.get(x, y).symbol.as_str()).collect::<String>();
+        let screen: String = (0..14).map(row).collect();
+        assert!(screen.contains("Title: Acme Seals") && screen.contains("Status: Qualified"));
+        let detail_column = |row: String| row.chars().position(|c| c == 'D');
+        assert_eq!(detail_column(row(6)), Some(61), "list pane takes 60% by default");
+
+        app.resize_split(-SPLIT_STEP_PERCENT);
+        app.resize_split(-SPLIT_STEP_PERCENT);
+        assert_eq!(app.layout.list_percent, 50);
+        let saved: crate::config::TuiConfig =
+            toml::from_str::<toml::Table>(&std::fs::read_to_string(&config_file).unwrap()).unwrap()["tui"]
+                .clone()
+                .try_into()
+                .unwrap();
+        assert_eq!(saved.layout_for("system"), app.layout);
+        assert_eq!(saved.layout_for("someone_else"), TuiLayout::default());
+
+        app.toggle_detail_pane();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol.as_str()).collect();
+        assert!(!screen.contains("Title: Acme Seals"));
+    }
+
+    #[test]
+    fn test_risk_tab_drills_into_clicked_heat_map_cell() {
+        let mut heat_map = RiskHeatMap::from_assessments(&[], false);
+        heat_map.rows[1][2].assessments.push(crate::risk::RiskHeatMapEntry {
+            id: uuid::Uuid::new_v4(),
+            device_name: "Infusion Pump".to_string(),
+            hazard_description: "Over-infusion".to_string(),
+            status: crate::risk::RiskAssessmentStatus::Draft,
+        });
+        let mut app = TuiApp::new();
+        app.current_tab = TabState::Risk;
+        app.show_risk_heat_map(heat_map);
+
+        app.move_down();
+        assert!(!app.handle_risk_key(KeyCode::Left));
+        assert!(app.handle_risk_key(KeyCode::Right));
+        assert_eq!(app.risk_cell, (1, 1));
+        app.handle_enter();
+        assert!(!app.risk_drill_down, "empty cells have nothing to list");
+
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(120, 30)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol.as_str()).collect();
+        assert!(screen.contains("Catastrophic (5)"));
+        assert!(screen.contains("Frequent"));
+        assert!(screen.contains("⚠ 1"));
+
+        let grid = app.risk_grid.unwrap();
+        let (width, height) = (grid.width / 5, grid.height / 5);
+        app.handle_mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: grid.x + 2 * width + width / 2,
+            row: grid.y + height + height / 2,
+            modifiers: crossterm::event::KeyModifiers::NONE,
+        });
+        assert_eq!(app.risk_cell, (1, 2));
+        assert!(app.risk_drill_down);
+        assert_eq!(app.detail_lines()[1], ("tui.detail.hazard", "Over-infusion".to_string()));
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol.as_str()).collect();
+        assert!(screen.contains("Infusion Pump: Over-infusion (Draft)"));
+        assert!(screen.contains("Assessments - Critical / Possible"));
+
+        assert!(app.handle_risk_key(KeyCode::Backspace));
+        assert!(!app.risk_drill_down);
+        assert_eq!(app.detail_lines()[2], ("tui.detail.risk_level", "12".to_string()));
+    }
+
+    #[test]
+    fn test_form_errors_render_per_field() {
+        let mut app = TuiApp::new();
+        assert!(!app.show_form_errors(QmsError::Database { message: "locked".to_string() }));
+        let error = CapaService::validate_new_capa("", "", "qa", "").unwrap_err();
+        assert!(app.show_form_errors(error));
+        assert_eq!(app.form_error_lines(), vec![
+            "✗ assigned_to: Assignee is required".to_string(),
+            "✗ description: Description is required".to_string(),
+            "✗ title: Title is required".to_string(),
+        ]);
+
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(80, 16)).unwrap();
+        app.current_tab = TabState::Capa;
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol.as_str()).collect();
+        assert!(screen.contains("Invalid Fields"));
+        assert!(screen.contains("title: Title is required"));
+
+        app.next_tab();
+        assert!(app.form_errors.is_none());
+    }
+
+    #[test]
+    fn test_disabled_modules_are_hidden_from_tabs() {
+        let mut config = crate::config::Config::default();
+        config.features.disabled = vec![Feature::Suppliers, Feature::Risk];
+        let context = AppContext::with_database(config, test_db());
+        let mut app = TuiApp::new().with_context(&context);
+        // Records is hidden as well, there being no plugins
+        assert_eq!(app.visible_tabs().len(), TabState::ALL.len() - 3);
+
+        app.current_tab = TabState::Capa;
+        app.next_tab();
+        assert_eq!(app.current_tab, TabState::Training);
+        app.current_tab = TabState::Dashboard;
+        app.previous_tab();
+        assert_eq!(app.current_tab, TabState::Jobs);
+
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(160, 12)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let tab_bar: String =
+            terminal.backend().buffer().content()[..160 * 3].iter().map(|cell| cell.symbol.as_str()).collect();
+        assert!(tab_bar.contains("Training") && !tab_bar.contains("Suppliers") && !tab_bar.contains("Risk"));
+    }
+
     #[test]
     fn test_tab_navigation() {
         let mut app = TuiApp::new();
@@ -674,8 +2779,20 @@ mod tests {
         app.next_tab();
         assert_eq!(app.current_tab, TabState::Reports);
         
+        app.next_tab();
+        assert_eq!(app.current_tab, TabState::Search);
+
+        app.next_tab();
+        assert_eq!(app.current_tab, TabState::Jobs);
+
+        app.next_tab();
+        assert_eq!(app.current_tab, TabState::Risk);
+        
         app.next_tab();
         assert_eq!(app.current_tab, TabState::Dashboard);
+
+        app.previous_tab();
+        assert_eq!(app.current_tab, TabState::Risk);
     }
 
     #[test]
@@ -802,10 +2919,58 @@ mod tests {
                 pending_control_measures: 0,
                 compliance_status: ComplianceStatus::Compliant,
             },
+            supplier_metrics: Default::default(),
+            kpis: Vec::new(),
         });
 
         let items = app.get_reports_list_items();
         assert!(items.len() >= 2);
+        assert!(app.get_kpi_list_items().is_empty());
+    }
+
+    #[test]
+    fn test_dashboard_lists_kpis_with_rag_status() {
+        use crate::kpi::{KpiMetric, KpiStatus, KpiTarget};
+        use crate::risk::{ComplianceStatus, RiskManagementReport};
+        use chrono::Utc;
+        use std::collections::HashMap;
+        use uuid::Uuid;
+
+        let mut app = TuiApp::new();
+        app.metrics = Some(MetricsResponse {
+            capa_metrics: CapaMetrics {
+                total_count: 0,
+                status_counts: HashMap::new(),
+                priority_counts: HashMap::new(),
+                overdue_count: 0,
+                closed_count: 0,
+            },
+            risk_report: RiskManagementReport {
+                id: Uuid::new_v4(),
+                generated_at: Utc::now(),
+                generated_by: "tester".to_string(),
+                total_assessments: 0,
+                risk_level_distribution: HashMap::new(),
+                acceptability_distribution: HashMap::new(),
+                pending_control_measures: 0,
+                compliance_status: ComplianceStatus::Compliant,
+            },
+            supplier_metrics: Default::default(),
+            kpis: vec![KpiStatus {
+                metric: KpiMetric::CapaOnTimeClosure,
+                label: KpiMetric::CapaOnTimeClosure.label().to_string(),
+                actual: 72.0,
+                target: KpiTarget::new(KpiMetric::CapaOnTimeClosure, 90.0),
+                status: RagStatus::Red,
+                trend: Vec::new(),
+            }],
+        });
+
+        assert_eq!(app.get_kpi_list_items().len(), 1);
+        let mut terminal = ratatui::Terminal::new(ratatui::backend::TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol.as_str()).collect();
+        assert!(screen.contains("CAPA On-Time Closure: 72.0% (target >= 90.0%)"));
     }
 
     #[test]
@@ -843,4 +3008,25 @@ mod tests {
         let items = app.get_training_list_items();
         assert_eq!(items.len(), 4);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_get_change_history_list_items() {
+        use crate::change_history::{ChangeReason, FieldChange};
+        let mut app = TuiApp::new();
+        app.change_history = Some(("supplier:s-1".to_string(), Vec::new()));
+        assert_eq!(app.get_change_history_list_items().len(), 1);
+
+        let field = |name: &str| FieldChange { field: name.to_string(), old_value: None, new_value: Some("x".to_string()) };
+        let record = ChangeRecord {
+            change_id: uuid::Uuid::new_v4(),
+            record_type: "supplier".to_string(),
+            record_id: "s-1".to_string(),
+            changed_by: "qa_manager".to_string(),
+            changed_at: chrono::Utc::now(),
+            reason: ChangeReason::new("Audit passed").unwrap(),
+            changes: vec![field("status"), field("approved_by")],
+        };
+        app.change_history = Some(("supplier:s-1".to_string(), vec![record.clone(), record]));
+        assert_eq!(app.get_change_history_list_items().len(), 4);
+    }
+}
@@ -0,0 +1,210 @@
+//! # Unit of Work - Atomic Multi-Step Operations
+//!
+//! Groups the writes of one business operation — e.g. closing a CAPA, the
+//! electronic signature applied to it and the audit entry describing it —
+//! into a single transaction via [`Database::unit_of_work`], so either all of
+//! them are stored or none is. Signatures carry the SHA-256 of the signed
+//! record so they stay linked to exactly that content (21 CFR Part 11 §11.70).
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::keystore::sha256_hex;
+use crate::logging::AuditLogEntry;
+
+/// Open transaction handed to a unit-of-work closure.
+pub struct UnitOfWork<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> UnitOfWork<'a> {
+    pub(crate) fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Connection inside the transaction, for `*_with(conn, ..)` helpers.
+    pub fn connection(&self) -> &Connection {
+        self.conn
+    }
+
+    /// Validate and store an audit entry as part of the transaction.
+    pub fn audit(&self, entry: &AuditLogEntry) -> Result<()> {
+        entry.validate()?;
+        Database::write_audit_entry(self.conn, entry)
+    }
+
+    /// Store an electronic signature as part of the transaction.
+    pub fn record_signature(&self, signature: &ElectronicSignature) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO electronic_signatures
+                 (id, record_type, record_id, signer_id, meaning, record_sha256, signed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                signature.id.to_string(),
+                signature.record_type,
+                signature.record_id,
+                signature.signer_id,
+                signature.meaning,
+                signature.record_sha256,
+                signature.signed_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+/// An electronic signature applied to a record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElectronicSignature {
+    pub id: Uuid,
+    pub record_type: String,
+    pub record_id: String,
+    pub signer_id: String,
+    /// Meaning of the signature, e.g. "approval" or "closure" (§11.50)
+    pub meaning: String,
+    /// SHA-256 of the record's JSON as signed
+    pub record_sha256: String,
+    pub signed_at: DateTime<Utc>,
+}
+
+impl ElectronicSignature {
+    /// Sign `record` (serialized to JSON) as `signer_id` with `meaning`.
+    pub fn sign<T: Serialize>(
+        record_type: &str,
+        record_id: &str,
+        record: &T,
+        signer_id: &str,
+        meaning: &str,
+    ) -> Result<Self> {
+        if meaning.trim().is_empty() {
+            return Err(QmsError::Validation {
+                field: "meaning".to_string(),
+                message: "Electronic signatures must state their meaning".to_string(),
+            });
+        }
+        Ok(Self {
+            id: Uuid::new_v4(),
+            record_type: record_type.to_string(),
+            record_id: record_id.to_string(),
+            signer_id: signer_id.to_string(),
+            meaning: meaning.to_string(),
+            record_sha256: sha256_hex(serde_json::to_string(record)?.as_bytes()),
+            signed_at: Utc::now(),
+        })
+    }
+
+    /// Whether the signature was made on `record` as it is now.
+    pub fn covers<T: Serialize>(&self, record: &T) -> Result<bool> {
+        Ok(self.record_sha256 == sha256_hex(serde_json::to_string(record)?.as_bytes()))
+    }
+}
+
+/// Read access to stored electronic signatures.
+pub struct SignatureRepo<'a> {
+    db: &'a Database,
+}
+
+impl<'a> SignatureRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Signatures applied to a record, oldest first.
+    pub fn for_record(&self, record_type: &str, record_id: &str) -> Result<Vec<ElectronicSignature>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, record_type, record_id, signer_id, meaning, record_sha256, signed_at
+                 FROM electronic_signatures WHERE record_type = ?1 AND record_id = ?2
+                 ORDER BY signed_at",
+            )?;
+            let rows = stmt.query_map(params![record_type, record_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, String>(6)?,
+                ))
+            })?;
+            rows.map(|row| {
+                let (id, record_type, record_id, signer_id, meaning, record_sha256, signed_at) = row?;
+                Ok(ElectronicSignature {
+                    id: Uuid::parse_str(&id).map_err(|e| QmsError::Database { message: e.to_string() })?,
+                    record_type,
+                    record_id,
+                    signer_id,
+                    meaning,
+                    record_sha256,
+                    signed_at: DateTime::parse_from_rfc3339(&signed_at)
+                        .map_err(|e| QmsError::Database { message: e.to_string() })?
+                        .with_timezone(&Utc),
+                })
+            })
+            .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::logging::AuditOutcome;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    fn entry(user: &str) -> AuditLogEntry {
+        AuditLogEntry::new(
+            user.to_string(),
+            "document_approved".to_string(),
+            "document:1".to_string(),
+            AuditOutcome::Success,
+            "uow-session".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_unit_of_work_commits_all_writes() {
+        let db = test_db();
+        let signature = ElectronicSignature::sign("document", "1", &"v1 content", "qa", "approval").unwrap();
+        db.unit_of_work(|uow| {
+            uow.record_signature(&signature)?;
+            uow.audit(&entry("qa"))
+        })
+        .unwrap();
+
+        assert_eq!(SignatureRepo::new(&db).for_record("document", "1").unwrap(), vec![signature]);
+        assert_eq!(db.get_audit_entries_for_resource("document:1").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_failed_step_rolls_back_earlier_writes() {
+        let db = test_db();
+        let signature = ElectronicSignature::sign("document", "1", &"v1 content", "qa", "approval").unwrap();
+        let result = db.unit_of_work(|uow| {
+            uow.audit(&entry("qa"))?;
+            uow.record_signature(&signature)?;
+            uow.audit(&entry("")) // invalid: no user
+        });
+
+        assert!(result.is_err());
+        assert!(SignatureRepo::new(&db).for_record("document", "1").unwrap().is_empty());
+        assert!(db.get_audit_entries_for_resource("document:1").unwrap().is_empty());
+        assert!(ElectronicSignature::sign("document", "1", &"x", "qa", " ").is_err());
+    }
+}
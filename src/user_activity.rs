@@ -0,0 +1,371 @@
+//! # User Activity - Per-Employee Activity Report
+//!
+//! Summarizes what one user did over a period: logins (and failed attempts),
+//! records created, modified and approved, and electronic signatures
+//! applied. Periodic access reviews use it to judge whether an account is
+//! still needed at its role; on termination it documents the account's last
+//! activity for the closeout record. Activity is read from the audit trail,
+//! classified by action name, and signatures from the signature registers.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use chrono::{NaiveDate, Utc};
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+
+use crate::audit::AuditManager;
+use crate::database::{row_to_audit_entry, AuditTrailEntry, Database};
+use crate::error::{QmsError, Result};
+use crate::keystore::Keystore;
+use crate::report::{Report, ReportBuilder, ReportSection, ReportTable};
+use crate::report_signature::{render_signed_report, SignedReport};
+
+/// Login actions written by password and SSO authentication.
+const LOGIN_ACTIONS: [&str; 2] = ["login", "sso_login"];
+
+/// Activity category of an audit entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    Login,
+    FailedLogin,
+    Logout,
+    Created,
+    Modified,
+    Approved,
+    Signed,
+    Other,
+}
+
+impl ActivityKind {
+    /// Classify an audit entry by its action name (and outcome for logins).
+    pub fn classify(action: &str, outcome: &str) -> Self {
+        if LOGIN_ACTIONS.contains(&action) {
+            if outcome == "SUCCESS" {
+                ActivityKind::Login
+            } else {
+                ActivityKind::FailedLogin
+            }
+        } else if action == "logout" {
+            ActivityKind::Logout
+        } else if mentions(action, &["approved"]) {
+            ActivityKind::Approved
+        } else if mentions(action, &["signed", "signature"]) {
+            ActivityKind::Signed
+        } else if mentions(action, &["created", "added", "registered", "recorded", "raised", "started"]) {
+            ActivityKind::Created
+        } else if mentions(
+            action,
+            &["updated", "changed", "modified", "edited", "closed", "deleted", "linked", "redacted"],
+        ) {
+            ActivityKind::Modified
+        } else {
+            ActivityKind::Other
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ActivityKind::Login => "Logins",
+            ActivityKind::FailedLogin => "Failed logins",
+            ActivityKind::Logout => "Logouts",
+            ActivityKind::Created => "Records created",
+            ActivityKind::Modified => "Records modified",
+            ActivityKind::Approved => "Records approved",
+            ActivityKind::Signed => "Signing actions",
+            ActivityKind::Other => "Other actions",
+        }
+    }
+}
+
+fn mentions(action: &str, words: &[&str]) -> bool {
+    words.iter().any(|word| action.contains(word))
+}
+
+/// The user's account as it stands now.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AccountInfo {
+    pub user_id: String,
+    pub username: String,
+    pub role: String,
+    pub active: bool,
+    pub last_login: Option<String>,
+    pub archived: bool,
+}
+
+/// A signature applied by the user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppliedSignature {
+    /// Signed record, e.g. `capa:<id>` or the title of a signed report
+    pub record: String,
+    pub meaning: String,
+    pub signed_at: String,
+}
+
+/// One user's activity over a period.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UserActivity {
+    pub account: AccountInfo,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub counts: BTreeMap<ActivityKind, usize>,
+    /// Entry count per audit action
+    pub by_action: BTreeMap<String, usize>,
+    pub signatures: Vec<AppliedSignature>,
+    /// Audit trail entries by the user in the period, oldest first
+    pub entries: Vec<AuditTrailEntry>,
+}
+
+impl UserActivity {
+    pub fn count(&self, kind: ActivityKind) -> usize {
+        self.counts.get(&kind).copied().unwrap_or(0)
+    }
+
+    /// Collect the activity of `user_id` between `from` and `to` (inclusive).
+    pub fn collect(db: &Database, user_id: &str, from: NaiveDate, to: NaiveDate) -> Result<Self> {
+        if from > to {
+            return Err(QmsError::Validation {
+                field: "from".to_string(),
+                message: format!("Period start {} is after its end {}", from, to),
+            });
+        }
+        let (from_text, to_text) = (from.to_string(), to.to_string());
+        db.with_connection(|conn| {
+            let account = conn
+                .query_row(
+                    "SELECT id, username, role, is_active, last_login, deleted_at IS NOT NULL FROM users WHERE id = ?1",
+                    params![user_id],
+                    |row| {
+                        Ok(AccountInfo {
+                            user_id: row.get(0)?,
+                            username: row.get(1)?,
+                            role: row.get(2)?,
+                            active: row.get(3)?,
+                            last_login: row.get(4)?,
+                            archived: row.get(5)?,
+                        })
+                    },
+                )
+                .optional()?
+                .ok_or_else(|| QmsError::NotFound { resource: "user".to_string(), id: user_id.to_string() })?;
+
+            let mut stmt = conn.prepare(
+                "SELECT id, timestamp, user_id, action, resource, outcome, ip_address, session_id,
+                        metadata, compliance_version, signature_hash, created_at, site_id
+                 FROM audit_trail
+                 WHERE user_id = ?1 AND substr(timestamp, 1, 10) BETWEEN ?2 AND ?3
+                 ORDER BY timestamp, id",
+            )?;
+            let entries = stmt
+                .query_map(params![user_id, from_text, to_text], row_to_audit_entry)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            let mut stmt = conn.prepare(
+                "SELECT record_type || ':' || record_id, meaning, signed_at FROM electronic_signatures
+                 WHERE signer_id = ?1 AND substr(signed_at, 1, 10) BETWEEN ?2 AND ?3
+                 UNION ALL
+                 SELECT 'report: ' || report_title, 'report authorship', signed_at FROM report_signatures
+                 WHERE signed_by = ?1 AND substr(signed_at, 1, 10) BETWEEN ?2 AND ?3
+                 ORDER BY 3",
+            )?;
+            let signatures = stmt
+                .query_map(params![user_id, from_text, to_text], |row| {
+                    Ok(AppliedSignature { record: row.get(0)?, meaning: row.get(1)?, signed_at: row.get(2)? })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+
+            let mut counts = BTreeMap::new();
+            let mut by_action = BTreeMap::new();
+            for entry in &entries {
+                *counts.entry(ActivityKind::classify(&entry.action, &entry.outcome)).or_insert(0) += 1;
+                *by_action.entry(entry.action.clone()).or_insert(0) += 1;
+            }
+            Ok(UserActivity { account, from, to, counts, by_action, signatures, entries })
+        })
+    }
+}
+
+/// Build the user activity report model.
+pub fn build_activity_report(activity: &UserActivity, prepared_by: &str) -> Report {
+    let account = &activity.account;
+    let status = match (account.active, account.archived) {
+        (_, true) => "Archived",
+        (true, false) => "Active",
+        (false, false) => "Deactivated",
+    };
+    let account_section = ReportSection::new("Account").key_values(vec![
+        ("User ID", account.user_id.clone()),
+        ("Username", account.username.clone()),
+        ("Role", account.role.clone()),
+        ("Account Status", status.to_string()),
+        ("Last Login", account.last_login.clone().unwrap_or_else(|| "Never".to_string())),
+        ("Period", format!("{} to {}", activity.from, activity.to)),
+    ]);
+
+    let mut summary: Vec<(String, String)> = [
+        ActivityKind::Login,
+        ActivityKind::FailedLogin,
+        ActivityKind::Created,
+        ActivityKind::Modified,
+        ActivityKind::Approved,
+        ActivityKind::Signed,
+        ActivityKind::Other,
+    ]
+    .iter()
+    .map(|kind| (kind.label().to_string(), activity.count(*kind).to_string()))
+    .collect();
+    summary.push(("Signatures applied".to_string(), activity.signatures.len().to_string()));
+    summary.push(("Audit trail entries".to_string(), activity.entries.len().to_string()));
+    let summary_section = ReportSection::new("Summary").key_values(summary);
+
+    let signatures = ReportSection::new("Signatures Applied");
+    let signatures = if activity.signatures.is_empty() {
+        signatures.paragraph("No signatures applied in the period.")
+    } else {
+        signatures.table(activity.signatures.iter().fold(
+            ReportTable::new(vec!["Signed At", "Record", "Meaning"]).with_column_weights(vec![1.6, 3.0, 1.4]),
+            |table, s| table.with_row(vec![s.signed_at.clone(), s.record.clone(), s.meaning.clone()]),
+        ))
+    };
+
+    let actions = ReportSection::new("Activity by Action");
+    let actions = if activity.by_action.is_empty() {
+        actions.paragraph("No activity recorded in the period.")
+    } else {
+        actions.table(activity.by_action.iter().fold(
+            ReportTable::new(vec!["Action", "Category", "Entries"]).with_column_weights(vec![2.5, 1.5, 0.8]),
+            |table, (action, count)| {
+                let outcome = if LOGIN_ACTIONS.contains(&action.as_str()) { "SUCCESS" } else { "" };
+                table.with_row(vec![
+                    action.clone(),
+                    ActivityKind::classify(action, outcome).label().to_string(),
+                    count.to_string(),
+                ])
+            },
+        ))
+    };
+
+    let log = ReportSection::new("Activity Log").with_page_break();
+    let log = if activity.entries.is_empty() {
+        log.paragraph("No audit trail entries in the period.")
+    } else {
+        log.table(
+            activity.entries.iter().fold(
+                ReportTable::new(vec!["Timestamp", "Action", "Resource", "Outcome"])
+                    .with_column_weights(vec![1.6, 1.6, 2.4, 0.8]),
+                |table, e| {
+                    table.with_row(vec![e.timestamp.clone(), e.action.clone(), e.resource.clone(), e.outcome.clone()])
+                },
+            ),
+        )
+    };
+
+    ReportBuilder::new(format!("User Activity Report: {}", account.username))
+        .with_subtitle(format!("{} to {}", activity.from, activity.to))
+        .with_prepared_by(prepared_by)
+        .with_generated_on(Utc::now())
+        .with_section(account_section)
+        .with_section(summary_section)
+        .with_section(signatures)
+        .with_section(actions)
+        .with_section(log)
+        .build()
+}
+
+/// Render the activity report as a signed PDF and audit its generation.
+pub fn generate_activity_report(
+    db: &Database,
+    keystore: &Keystore,
+    activity: &UserActivity,
+    output_path: &Path,
+    generated_by: &str,
+) -> Result<SignedReport> {
+    let signed =
+        render_signed_report(&build_activity_report(activity, generated_by), output_path, keystore, db, generated_by)?;
+    AuditManager::new(db.clone()).log_action(
+        generated_by,
+        "user_activity_report_generated",
+        &format!("user:{}", activity.account.user_id),
+        "Success",
+        Some(format!("{} to {}, {} entries", activity.from, activity.to, activity.entries.len())),
+    )?;
+    Ok(signed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+
+    #[test]
+    fn test_classify_actions() {
+        assert_eq!(ActivityKind::classify("login", "SUCCESS"), ActivityKind::Login);
+        assert_eq!(ActivityKind::classify("sso_login", "FAILURE"), ActivityKind::FailedLogin);
+        assert_eq!(ActivityKind::classify("capa_created", "SUCCESS"), ActivityKind::Created);
+        assert_eq!(ActivityKind::classify("corrective_action_added", "SUCCESS"), ActivityKind::Created);
+        assert_eq!(ActivityKind::classify("capa_status_updated", "SUCCESS"), ActivityKind::Modified);
+        assert_eq!(ActivityKind::classify("document_approved", "SUCCESS"), ActivityKind::Approved);
+        assert_eq!(ActivityKind::classify("capa_closed_signed", "SUCCESS"), ActivityKind::Signed);
+        assert_eq!(ActivityKind::classify("access.view_complaint", "SUCCESS"), ActivityKind::Other);
+    }
+
+    #[test]
+    fn test_collect_user_activity() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["jdoe", "qa"]);
+        let audit = AuditManager::new(db.clone());
+        for (user, action, outcome) in [
+            ("jdoe", "login", "Failure"),
+            ("jdoe", "login", "Success"),
+            ("jdoe", "capa_created", "Success"),
+            ("jdoe", "capa_updated", "Success"),
+            ("jdoe", "document_approved", "Success"),
+            ("qa", "capa_created", "Success"),
+        ] {
+            audit.log_action(user, action, "capa:c1", outcome, None).unwrap();
+        }
+        db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO electronic_signatures
+                     (id, record_type, record_id, signer_id, meaning, record_sha256, signed_at)
+                 VALUES ('s1', 'capa', 'c1', 'jdoe', 'closure', 'h', ?1)",
+                params![Utc::now().to_rfc3339()],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        let today = Utc::now().date_naive();
+
+        let activity = UserActivity::collect(&db, "jdoe", today, today).unwrap();
+        assert_eq!(activity.account.username, "jdoe");
+        assert_eq!(activity.entries.len(), 5, "other users' entries are excluded");
+        assert_eq!(activity.count(ActivityKind::Login), 1);
+        assert_eq!(activity.count(ActivityKind::FailedLogin), 1);
+        assert_eq!(activity.count(ActivityKind::Created), 1);
+        assert_eq!(activity.count(ActivityKind::Modified), 1);
+        assert_eq!(activity.count(ActivityKind::Approved), 1);
+        assert_eq!(
+            activity.signatures,
+            vec![AppliedSignature {
+                record: "capa:c1".to_string(),
+                meaning: "closure".to_string(),
+                signed_at: activity.signatures[0].signed_at.clone(),
+            }]
+        );
+        let yesterday = today.pred_opt().unwrap();
+        assert!(UserActivity::collect(&db, "jdoe", yesterday, yesterday).unwrap().entries.is_empty());
+        assert!(matches!(UserActivity::collect(&db, "ghost", today, today), Err(QmsError::NotFound { .. })));
+
+        let report = build_activity_report(&activity, "qa");
+        let titles: Vec<&str> = report.sections.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["Account", "Summary", "Signatures Applied", "Activity by Action", "Activity Log"]);
+    }
+}
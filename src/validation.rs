@@ -0,0 +1,629 @@
+//! # Validation - Process & Software Tool Validation (IQ/OQ/PQ)
+//!
+//! Tracks validation protocols for manufacturing processes (21 CFR 820.75)
+//! and software tools used in the quality system (ISO 13485 §4.1.6) through
+//! their lifecycle: protocol approval, execution evidence, deviations and
+//! final report sign-off. Approval and sign-off are electronic signatures
+//! written in one unit of work with their audit entries. A signed-off
+//! protocol carries a revalidation due date, which change control can pull
+//! forward with [`ValidationRepo::require_revalidation`].
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::unit_of_work::ElectronicSignature;
+
+/// Qualification stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ValidationStage {
+    /// Installation qualification
+    Iq,
+    /// Operational qualification
+    Oq,
+    /// Performance qualification
+    Pq,
+}
+
+/// What is being validated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SubjectKind {
+    Process,
+    SoftwareTool,
+}
+
+/// Protocol lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProtocolStatus {
+    Draft,
+    Approved,
+    /// At least one execution step recorded
+    InExecution,
+    /// Final report signed off
+    Completed,
+}
+
+impl ValidationStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ValidationStage::Iq => "IQ",
+            ValidationStage::Oq => "OQ",
+            ValidationStage::Pq => "PQ",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [ValidationStage::Iq, ValidationStage::Oq, ValidationStage::Pq]
+            .into_iter()
+            .find(|item| item.as_str() == value)
+    }
+}
+
+impl SubjectKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubjectKind::Process => "process",
+            SubjectKind::SoftwareTool => "software_tool",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [SubjectKind::Process, SubjectKind::SoftwareTool]
+            .into_iter()
+            .find(|item| item.as_str() == value)
+    }
+}
+
+impl ProtocolStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProtocolStatus::Draft => "draft",
+            ProtocolStatus::Approved => "approved",
+            ProtocolStatus::InExecution => "in_execution",
+            ProtocolStatus::Completed => "completed",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        [ProtocolStatus::Draft, ProtocolStatus::Approved, ProtocolStatus::InExecution, ProtocolStatus::Completed]
+            .into_iter()
+            .find(|item| item.as_str() == value)
+    }
+}
+
+/// A validation protocol and, once signed off, its final report.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationProtocol {
+    pub id: String,
+    /// Process or tool name, e.g. "Pouch sealer PS-2"
+    pub subject: String,
+    pub subject_kind: SubjectKind,
+    pub stage: ValidationStage,
+    pub title: String,
+    pub status: ProtocolStatus,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub approved_by: Option<String>,
+    pub approved_at: Option<DateTime<Utc>>,
+    pub completed_by: Option<String>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub revalidation_due: Option<NaiveDate>,
+}
+
+/// Result of one executed protocol step.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionEvidence {
+    pub id: String,
+    pub protocol_id: String,
+    pub step: String,
+    pub passed: bool,
+    /// Reference to the raw evidence (file, screenshot, record id)
+    pub evidence_ref: String,
+    pub recorded_by: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Departure from the approved protocol found during execution.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Deviation {
+    pub id: String,
+    pub protocol_id: String,
+    pub description: String,
+    pub raised_by: String,
+    pub raised_at: DateTime<Utc>,
+    pub resolution: Option<String>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// Persistence and lifecycle rules for validation protocols.
+pub struct ValidationRepo<'a> {
+    db: &'a Database,
+    audit: AuditManager,
+}
+
+impl<'a> ValidationRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, audit: AuditManager::new(db.clone()) }
+    }
+
+    /// Draft a new protocol.
+    pub fn create_protocol(
+        &self,
+        subject: &str,
+        subject_kind: SubjectKind,
+        stage: ValidationStage,
+        title: &str,
+        created_by: &str,
+    ) -> Result<ValidationProtocol> {
+        let protocol = ValidationProtocol {
+            id: Uuid::new_v4().to_string(),
+            subject: subject.to_string(),
+            subject_kind,
+            stage,
+            title: title.to_string(),
+            status: ProtocolStatus::Draft,
+            created_by: created_by.to_string(),
+            created_at: Utc::now(),
+            approved_by: None,
+            approved_at: None,
+            completed_by: None,
+            completed_at: None,
+            revalidation_due: None,
+        };
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO validation_protocols
+                     (id, subject, subject_kind, stage, title, status, created_by, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    protocol.id,
+                    protocol.subject,
+                    protocol.subject_kind.as_str(),
+                    protocol.stage.as_str(),
+                    protocol.title,
+                    protocol.status.as_str(),
+                    protocol.created_by,
+                    protocol.created_at.to_rfc3339(),
+                ],
+            )?;
+            Ok(())
+        })?;
+        self.audit.log_action(
+            created_by,
+            "validation_protocol_created",
+            &resource(&protocol.id),
+            "Success",
+            Some(format!("{} {} for {}", protocol.stage.as_str(), protocol.title, protocol.subject)),
+        )?;
+        Ok(protocol)
+    }
+
+    pub fn get(&self, id: &str) -> Result<ValidationProtocol> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!("SELECT {} FROM validation_protocols WHERE id = ?1", PROTOCOL_COLUMNS),
+                        params![id],
+                        row_to_protocol,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "validation_protocol".to_string(), id: id.to_string() })
+    }
+
+    /// Approve a draft protocol for execution (signed).
+    pub fn approve(&self, id: &str, approver: &str) -> Result<ElectronicSignature> {
+        let mut protocol = self.get(id)?;
+        expect_status(&protocol, &[ProtocolStatus::Draft], "approve")?;
+        let now = Utc::now();
+        protocol.status = ProtocolStatus::Approved;
+        protocol.approved_by = Some(approver.to_string());
+        protocol.approved_at = Some(now);
+        let signature = ElectronicSignature::sign("validation_protocol", id, &protocol, approver, "protocol approval")?;
+
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE validation_protocols SET status = ?2, approved_by = ?3, approved_at = ?4 WHERE id = ?1",
+                params![id, protocol.status.as_str(), approver, now.to_rfc3339()],
+            )?;
+            uow.record_signature(&signature)?;
+            self.audit.log_action_in(
+                uow,
+                approver,
+                "validation_protocol_approved",
+                &resource(id),
+                "Success",
+                Some(format!("signature {}", signature.id)),
+            )
+        })?;
+        Ok(signature)
+    }
+
+    /// Record the outcome of an executed step of an approved protocol.
+    pub fn record_evidence(
+        &self,
+        id: &str,
+        step: &str,
+        passed: bool,
+        evidence_ref: &str,
+        recorded_by: &str,
+    ) -> Result<ExecutionEvidence> {
+        let protocol = self.get(id)?;
+        expect_status(&protocol, &[ProtocolStatus::Approved, ProtocolStatus::InExecution], "execute")?;
+        let evidence = ExecutionEvidence {
+            id: Uuid::new_v4().to_string(),
+            protocol_id: id.to_string(),
+            step: step.to_string(),
+            passed,
+            evidence_ref: evidence_ref.to_string(),
+            recorded_by: recorded_by.to_string(),
+            recorded_at: Utc::now(),
+        };
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "INSERT INTO validation_evidence (id, protocol_id, step, passed, evidence_ref, recorded_by, recorded_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    evidence.id,
+                    evidence.protocol_id,
+                    evidence.step,
+                    evidence.passed,
+                    evidence.evidence_ref,
+                    evidence.recorded_by,
+                    evidence.recorded_at.to_rfc3339(),
+                ],
+            )?;
+            uow.connection().execute(
+                "UPDATE validation_protocols SET status = ?2 WHERE id = ?1",
+                params![id, ProtocolStatus::InExecution.as_str()],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                recorded_by,
+                "validation_evidence_recorded",
+                &resource(id),
+                if passed { "Success" } else { "Failure" },
+                Some(format!("step {}: {}", step, evidence_ref)),
+            )
+        })?;
+        Ok(evidence)
+    }
+
+    /// Execution evidence of a protocol, in recording order.
+    pub fn evidence(&self, id: &str) -> Result<Vec<ExecutionEvidence>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, protocol_id, step, passed, evidence_ref, recorded_by, recorded_at
+                 FROM validation_evidence WHERE protocol_id = ?1 ORDER BY recorded_at",
+            )?;
+            let evidence = stmt
+                .query_map(params![id], |row| {
+                    Ok(ExecutionEvidence {
+                        id: row.get(0)?,
+                        protocol_id: row.get(1)?,
+                        step: row.get(2)?,
+                        passed: row.get(3)?,
+                        evidence_ref: row.get(4)?,
+                        recorded_by: row.get(5)?,
+                        recorded_at: parse_time(row, 6)?.unwrap_or_default(),
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(evidence)
+        })
+    }
+
+    /// Raise a deviation against a protocol in execution.
+    pub fn raise_deviation(&self, id: &str, description: &str, raised_by: &str) -> Result<Deviation> {
+        let protocol = self.get(id)?;
+        expect_status(&protocol, &[ProtocolStatus::Approved, ProtocolStatus::InExecution], "raise a deviation on")?;
+        let deviation = Deviation {
+            id: Uuid::new_v4().to_string(),
+            protocol_id: id.to_string(),
+            description: description.to_string(),
+            raised_by: raised_by.to_string(),
+            raised_at: Utc::now(),
+            resolution: None,
+            resolved_at: None,
+        };
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO validation_deviations (id, protocol_id, description, raised_by, raised_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    deviation.id,
+                    deviation.protocol_id,
+                    deviation.description,
+                    deviation.raised_by,
+                    deviation.raised_at.to_rfc3339(),
+                ],
+            )?;
+            Ok(())
+        })?;
+        self.audit
+            .log_action(raised_by, "validation_deviation_raised", &resource(id), "Warning", Some(description.to_string()))?;
+        Ok(deviation)
+    }
+
+    /// Document how a deviation was resolved.
+    pub fn resolve_deviation(&self, deviation_id: &str, resolution: &str, resolved_by: &str) -> Result<()> {
+        if resolution.trim().is_empty() {
+            return Err(QmsError::Validation {
+                field: "resolution".to_string(),
+                message: "A deviation resolution must be documented".to_string(),
+            });
+        }
+        let protocol_id: String = self
+            .db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        "SELECT protocol_id FROM validation_deviations WHERE id = ?1 AND resolved_at IS NULL",
+                        params![deviation_id],
+                        |row| row.get(0),
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound { resource: "open validation deviation".to_string(), id: deviation_id.to_string() })?;
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE validation_deviations SET resolution = ?2, resolved_at = ?3 WHERE id = ?1",
+                params![deviation_id, resolution, Utc::now().to_rfc3339()],
+            )?;
+            self.audit.log_action_in(
+                uow,
+                resolved_by,
+                "validation_deviation_resolved",
+                &resource(&protocol_id),
+                "Success",
+                Some(format!("deviation {}: {}", deviation_id, resolution)),
+            )
+        })
+    }
+
+    /// Deviations raised against a protocol.
+    pub fn deviations(&self, id: &str) -> Result<Vec<Deviation>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, protocol_id, description, raised_by, raised_at, resolution, resolved_at
+                 FROM validation_deviations WHERE protocol_id = ?1 ORDER BY raised_at",
+            )?;
+            let deviations = stmt
+                .query_map(params![id], |row| {
+                    Ok(Deviation {
+                        id: row.get(0)?,
+                        protocol_id: row.get(1)?,
+                        description: row.get(2)?,
+                        raised_by: row.get(3)?,
+                        raised_at: parse_time(row, 4)?.unwrap_or_default(),
+                        resolution: row.get(5)?,
+                        resolved_at: parse_time(row, 6)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(deviations)
+        })
+    }
+
+    /// Sign off the final report. Requires recorded evidence and every
+    /// deviation resolved; sets revalidation due `revalidation_interval_days`
+    /// from today.
+    pub fn sign_off_report(
+        &self,
+        id: &str,
+        signer: &str,
+        revalidation_interval_days: i64,
+    ) -> Result<ElectronicSignature> {
+        let mut protocol = self.get(id)?;
+        expect_status(&protocol, &[ProtocolStatus::InExecution], "sign off")?;
+        let evidence = self.evidence(id)?;
+        let open_deviations = self.deviations(id)?.into_iter().filter(|d| d.resolved_at.is_none()).count();
+        if open_deviations > 0 {
+            return Err(QmsError::Validation {
+                field: "deviations".to_string(),
+                message: format!("{} deviation(s) must be resolved before sign-off", open_deviations),
+            });
+        }
+        let failed: Vec<_> = evidence.iter().filter(|e| !e.passed).map(|e| e.step.as_str()).collect();
+        if !failed.is_empty() && self.deviations(id)?.is_empty() {
+            return Err(QmsError::Validation {
+                field: "evidence".to_string(),
+                message: format!("Failed steps without a deviation: {}", failed.join(", ")),
+            });
+        }
+
+        let now = Utc::now();
+        let due = (now + Duration::days(revalidation_interval_days)).date_naive();
+        protocol.status = ProtocolStatus::Completed;
+        protocol.completed_by = Some(signer.to_string());
+        protocol.completed_at = Some(now);
+        protocol.revalidation_due = Some(due);
+        let signature = ElectronicSignature::sign("validation_protocol", id, &(&protocol, &evidence), signer, "final report approval")?;
+
+        self.db.unit_of_work(|uow| {
+            uow.connection().execute(
+                "UPDATE validation_protocols
+                 SET status = ?2, completed_by = ?3, completed_at = ?4, revalidation_due = ?5 WHERE id = ?1",
+                params![id, protocol.status.as_str(), signer, now.to_rfc3339(), due.to_string()],
+            )?;
+            uow.record_signature(&signature)?;
+            self.audit.log_action_in(
+                uow,
+                signer,
+                "validation_report_signed",
+                &resource(id),
+                "Success",
+                Some(format!("signature {}, revalidation due {}", signature.id, due)),
+            )
+        })?;
+        Ok(signature)
+    }
+
+    /// Change control hook: a change affecting `subject` brings the
+    /// revalidation of its completed protocols forward to `due` (never
+    /// later than already scheduled). Returns how many protocols changed.
+    pub fn require_revalidation(&self, subject: &str, change_reference: &str, due: NaiveDate, by: &str) -> Result<usize> {
+        let updated = self.db.unit_of_work(|uow| {
+            let updated = uow.connection().execute(
+                "UPDATE validation_protocols SET revalidation_due = ?2
+                 WHERE subject = ?1 AND status = 'completed'
+                   AND (revalidation_due IS NULL OR revalidation_due > ?2)",
+                params![subject, due.to_string()],
+            )?;
+            if updated > 0 {
+                self.audit.log_action_in(
+                    uow,
+                    by,
+                    "revalidation_required",
+                    &format!("validation_subject:{}", subject),
+                    "Success",
+                    Some(format!("{} protocol(s) due {} after change {}", updated, due, change_reference)),
+                )?;
+            }
+            Ok(updated)
+        })?;
+        Ok(updated)
+    }
+
+    /// Completed protocols whose revalidation is due on or before `date`.
+    pub fn revalidation_due_by(&self, date: NaiveDate) -> Result<Vec<ValidationProtocol>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM validation_protocols
+                 WHERE status = 'completed' AND revalidation_due <= ?1 ORDER BY revalidation_due",
+                PROTOCOL_COLUMNS
+            ))?;
+            let protocols = stmt.query_map(params![date.to_string()], row_to_protocol)?.collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(protocols)
+        })
+    }
+}
+
+fn resource(id: &str) -> String {
+    format!("validation_protocol:{}", id)
+}
+
+fn expect_status(protocol: &ValidationProtocol, allowed: &[ProtocolStatus], action: &str) -> Result<()> {
+    if allowed.contains(&protocol.status) {
+        Ok(())
+    } else {
+        Err(QmsError::Validation {
+            field: "status".to_string(),
+            message: format!("Cannot {} a protocol in status {}", action, protocol.status.as_str()),
+        })
+    }
+}
+
+const PROTOCOL_COLUMNS: &str = "id, subject, subject_kind, stage, title, status, created_by, created_at,
+     approved_by, approved_at, completed_by, completed_at, revalidation_due";
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn parse_time(row: &Row, index: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| conversion_error(index, e.to_string()))
+        })
+        .transpose()
+}
+
+fn parse_text<T>(row: &Row, index: usize, parse: fn(&str) -> Option<T>) -> rusqlite::Result<T> {
+    let value: String = row.get(index)?;
+    parse(&value).ok_or_else(|| conversion_error(index, format!("unexpected value {}", value)))
+}
+
+fn row_to_protocol(row: &Row) -> rusqlite::Result<ValidationProtocol> {
+    let revalidation_due: Option<String> = row.get(12)?;
+    Ok(ValidationProtocol {
+        id: row.get(0)?,
+        subject: row.get(1)?,
+        subject_kind: parse_text(row, 2, SubjectKind::parse)?,
+        stage: parse_text(row, 3, ValidationStage::parse)?,
+        title: row.get(4)?,
+        status: parse_text(row, 5, ProtocolStatus::parse)?,
+        created_by: row.get(6)?,
+        created_at: parse_time(row, 7)?.unwrap_or_default(),
+        approved_by: row.get(8)?,
+        approved_at: parse_time(row, 9)?,
+        completed_by: row.get(10)?,
+        completed_at: parse_time(row, 11)?,
+        revalidation_due: revalidation_due
+            .map(|d| d.parse::<NaiveDate>().map_err(|e| conversion_error(12, e.to_string())))
+            .transpose()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::unit_of_work::SignatureRepo;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_protocol_lifecycle_with_deviation() {
+        let db = test_db();
+        let repo = ValidationRepo::new(&db);
+        let protocol = repo
+            .create_protocol("Pouch sealer PS-2", SubjectKind::Process, ValidationStage::Oq, "Seal strength OQ", "ve1")
+            .unwrap();
+        assert!(repo.record_evidence(&protocol.id, "1", true, "run-1.csv", "ve1").is_err());
+
+        repo.approve(&protocol.id, "qa_manager").unwrap();
+        repo.record_evidence(&protocol.id, "1", true, "run-1.csv", "ve1").unwrap();
+        repo.record_evidence(&protocol.id, "2", false, "run-2.csv", "ve1").unwrap();
+        assert!(repo.sign_off_report(&protocol.id, "qa_manager", 365).is_err());
+
+        let deviation = repo.raise_deviation(&protocol.id, "Step 2 seal below 1.2 N", "ve1").unwrap();
+        assert!(repo.sign_off_report(&protocol.id, "qa_manager", 365).is_err());
+        repo.resolve_deviation(&deviation.id, "Re-run after heater calibration passed", "qa_manager").unwrap();
+        repo.sign_off_report(&protocol.id, "qa_manager", 365).unwrap();
+
+        let completed = repo.get(&protocol.id).unwrap();
+        assert_eq!(completed.status, ProtocolStatus::Completed);
+        assert_eq!(completed.revalidation_due, Some((Utc::now() + Duration::days(365)).date_naive()));
+        let signatures = SignatureRepo::new(&db).for_record("validation_protocol", &protocol.id).unwrap();
+        assert_eq!(signatures.iter().map(|s| s.meaning.as_str()).collect::<Vec<_>>(), ["protocol approval", "final report approval"]);
+    }
+
+    #[test]
+    fn test_change_control_pulls_revalidation_forward() {
+        let db = test_db();
+        let repo = ValidationRepo::new(&db);
+        let protocol = repo
+            .create_protocol("Label printer tool", SubjectKind::SoftwareTool, ValidationStage::Iq, "Install check", "ve1")
+            .unwrap();
+        repo.approve(&protocol.id, "qa_manager").unwrap();
+        repo.record_evidence(&protocol.id, "1", true, "install.log", "ve1").unwrap();
+        repo.sign_off_report(&protocol.id, "qa_manager", 730).unwrap();
+
+        let soon = Utc::now().date_naive() + Duration::days(30);
+        assert!(repo.revalidation_due_by(soon).unwrap().is_empty());
+        assert_eq!(repo.require_revalidation("Label printer tool", "CR-17", soon, "qa_manager").unwrap(), 1);
+        // A later date never postpones an earlier one
+        assert_eq!(repo.require_revalidation("Label printer tool", "CR-18", soon + Duration::days(10), "qa_manager").unwrap(), 0);
+
+        let due = repo.revalidation_due_by(soon).unwrap();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].revalidation_due, Some(soon));
+    }
+}
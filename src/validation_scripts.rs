@@ -0,0 +1,536 @@
+//! # Validation Scripts - Customer-Specific Rules
+//!
+//! Rules that differ between customers, such as "a Critical CAPA must be due
+//! within 30 days", are written as Rhai scripts rather than code. Each script
+//! is a controlled document of type `ValidationScript`: a version is submitted
+//! for review, approved through the document approval workflow and then made
+//! effective. Its author cannot be its approver. The effective version of
+//! every script for a [`RuleHook`] runs at that service boundary, e.g. when a
+//! CAPA is created or changes status, with the record bound to `record`. A
+//! script passes by evaluating to `true` or `()`; `false` or a message string
+//! rejects the change as a validation error of the script's document number.
+//!
+//! Scripts run sandboxed: Rhai has no file, network or process access, the
+//! engine bounds operations, call depth and data sizes, and `print`/`debug`
+//! output is discarded. A script that cannot be run rejects the change, so a
+//! broken rule never lets a record through unchecked.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rhai::{Dynamic, Engine, EvalAltResult, Scope, AST};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::document::{document_workflow, DocumentStatus};
+use crate::error::{QmsError, Result, ValidationErrors};
+use crate::keystore::sha256_hex;
+
+/// `document_type` of validation scripts in the `documents` table
+pub const SCRIPT_DOCUMENT_TYPE: &str = "ValidationScript";
+
+/// Operations one script run may take before it is stopped
+const MAX_OPERATIONS: u64 = 100_000;
+const MAX_CALL_LEVELS: usize = 16;
+const MAX_EXPR_DEPTH: usize = 64;
+const MAX_STRING_SIZE: usize = 10_000;
+const MAX_COLLECTION_SIZE: usize = 1_000;
+
+/// Service boundary a script runs at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleHook {
+    /// A CAPA is created or changes status
+    Capa,
+    /// A record of a plugin type is saved, see [`crate::record_plugins`]
+    CustomRecord,
+}
+
+impl RuleHook {
+    pub const ALL: [RuleHook; 2] = [RuleHook::Capa, RuleHook::CustomRecord];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RuleHook::Capa => "capa",
+            RuleHook::CustomRecord => "custom_record",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self> {
+        Self::ALL.into_iter().find(|hook| hook.as_str() == value).ok_or_else(|| QmsError::Validation {
+            field: "hook".to_string(),
+            message: format!("Unknown rule hook '{}'; expected capa or custom_record", value),
+        })
+    }
+}
+
+/// One version of a validation script.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationScript {
+    pub document_id: String,
+    /// Controlled document number, e.g. `VS-001`
+    pub document_number: String,
+    pub title: String,
+    pub version: u32,
+    pub hook: RuleHook,
+    pub source: String,
+    /// SHA-256 of `source`, also the document's content hash
+    pub content_hash: String,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    /// When the version was made effective; `None` until then
+    pub effective_at: Option<DateTime<Utc>>,
+}
+
+/// Validation scripts and their controlled document versions.
+pub struct ValidationScriptRepo<'a> {
+    db: &'a Database,
+}
+
+impl<'a> ValidationScriptRepo<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db }
+    }
+
+    /// Submit a script for review: the first version of `document_number`,
+    /// or its next version. The source must compile. The version in force
+    /// stays in force until the new one is made effective.
+    pub fn submit(
+        &self,
+        document_number: &str,
+        title: &str,
+        hook: RuleHook,
+        source: &str,
+        change_description: &str,
+        user: &str,
+    ) -> Result<ValidationScript> {
+        let (document_number, title) = (document_number.trim(), title.trim());
+        let mut errors = ValidationErrors::new();
+        if document_number.is_empty() {
+            errors.add("document_number", "Document number is required");
+        }
+        if title.is_empty() {
+            errors.add("title", "Title is required");
+        }
+        if change_description.trim().is_empty() {
+            errors.add("change_description", "Describe the change");
+        }
+        if let Err(e) = sandboxed_engine().compile(source) {
+            errors.add("source", format!("Script does not compile: {}", e));
+        }
+        errors.into_result()?;
+
+        let content_hash = sha256_hex(source.as_bytes());
+        let now = Utc::now().to_rfc3339();
+        let (document_id, version) = self.db.unit_of_work(|uow| {
+            let conn = uow.connection();
+            let existing: Option<(String, String, String, String)> = conn
+                .query_row(
+                    "SELECT id, document_type, status, version FROM documents
+                     WHERE document_number = ?1 AND deleted_at IS NULL",
+                    params![document_number],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                )
+                .optional()?;
+            let (document_id, version) = match existing {
+                None => {
+                    let document_id = Uuid::new_v4().to_string();
+                    conn.execute(
+                        "INSERT INTO documents (id, document_number, title, version, status, document_type,
+                                                content_hash, created_by, created_at, updated_at)
+                         VALUES (?1, ?2, ?3, '1', 'UnderReview', ?4, ?5, ?6, ?7, ?7)",
+                        params![document_id, document_number, title, SCRIPT_DOCUMENT_TYPE, content_hash, user, now],
+                    )?;
+                    (document_id, 1)
+                }
+                Some((_, document_type, _, _)) if document_type != SCRIPT_DOCUMENT_TYPE => {
+                    return Err(QmsError::Validation {
+                        field: "document_number".to_string(),
+                        message: format!("{} is a {} document, not a script", document_number, document_type),
+                    });
+                }
+                Some((_, _, status, _)) if status == "UnderReview" || status == "Approved" => {
+                    return Err(QmsError::Validation {
+                        field: "document_number".to_string(),
+                        message: format!("{} already has a version awaiting effectivity ({})", document_number, status),
+                    });
+                }
+                Some((document_id, _, _, version)) => {
+                    let version = version.parse::<u32>().unwrap_or(0) + 1;
+                    conn.execute(
+                        "UPDATE documents SET title = ?2, version = ?3, status = 'UnderReview', content_hash = ?4,
+                                approved_by = NULL, updated_at = ?5, row_version = row_version + 1
+                         WHERE id = ?1",
+                        params![document_id, title, version.to_string(), content_hash, now],
+                    )?;
+                    (document_id, version)
+                }
+            };
+            conn.execute(
+                "INSERT INTO document_versions (id, document_id, version, change_description, content_hash, created_by)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    Uuid::new_v4().to_string(),
+                    document_id,
+                    version.to_string(),
+                    change_description.trim(),
+                    content_hash,
+                    user
+                ],
+            )?;
+            conn.execute(
+                "INSERT INTO validation_scripts
+                     (document_id, version, hook, source, content_hash, created_by, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![document_id, version, hook.as_str(), source, content_hash, user, now],
+            )?;
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                user,
+                "validation_script_submitted",
+                &format!("document:{}", document_id),
+                "Success",
+                Some(format!("{} v{} ({}): {}", document_number, version, hook.as_str(), change_description.trim())),
+            )?;
+            Ok((document_id, version))
+        })?;
+        self.get(&document_id, version)
+    }
+
+    /// Put the approved version of a script in force. Fails unless the
+    /// document was approved, and by someone other than the script's author.
+    pub fn make_effective(&self, document_id: &str, user: &str) -> Result<ValidationScript> {
+        let now = Utc::now().to_rfc3339();
+        let version = self.db.unit_of_work(|uow| {
+            let conn = uow.connection();
+            let (document_type, status, version, approved_by): (String, String, String, Option<String>) = conn
+                .query_row(
+                    "SELECT document_type, status, version, approved_by FROM documents
+                     WHERE id = ?1 AND deleted_at IS NULL",
+                    params![document_id],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                )
+                .optional()?
+                .filter(|(document_type, ..)| document_type == SCRIPT_DOCUMENT_TYPE)
+                .ok_or_else(|| QmsError::NotFound {
+                    resource: "validation_script".to_string(),
+                    id: document_id.to_string(),
+                })?;
+            let version: u32 = version.parse().map_err(|_| QmsError::Validation {
+                field: "version".to_string(),
+                message: format!("Document {} has no numeric version", document_id),
+            })?;
+            let effective = DocumentStatus::Effective.as_str();
+            document_workflow().check(document_id, &(), &status, effective, user, None)?;
+            let author: String = conn.query_row(
+                "SELECT created_by FROM validation_scripts WHERE document_id = ?1 AND version = ?2",
+                params![document_id, version],
+                |row| row.get(0),
+            )?;
+            if approved_by.as_deref() == Some(author.as_str()) {
+                return Err(QmsError::Security {
+                    message: format!("Validation script {} v{} was approved by its author", document_id, version),
+                });
+            }
+            conn.execute(
+                "UPDATE documents SET status = 'Effective', effective_date = ?2, updated_at = ?2,
+                        row_version = row_version + 1
+                 WHERE id = ?1",
+                params![document_id, now],
+            )?;
+            conn.execute(
+                "UPDATE validation_scripts SET effective_at = ?3, effective_by = ?4
+                 WHERE document_id = ?1 AND version = ?2",
+                params![document_id, version, now, user],
+            )?;
+            AuditManager::new(self.db.clone()).log_action_in(
+                uow,
+                user,
+                "validation_script_effective",
+                &format!("document:{}", document_id),
+                "Success",
+                Some(format!("Version {} in force", version)),
+            )?;
+            Ok(version)
+        })?;
+        self.get(document_id, version)
+    }
+
+    pub fn get(&self, document_id: &str, version: u32) -> Result<ValidationScript> {
+        self.db
+            .with_connection(|conn| {
+                Ok(conn
+                    .query_row(
+                        &format!(
+                            "SELECT {} FROM validation_scripts s JOIN documents d ON d.id = s.document_id
+                             WHERE s.document_id = ?1 AND s.version = ?2",
+                            SCRIPT_COLUMNS
+                        ),
+                        params![document_id, version],
+                        row_to_script,
+                    )
+                    .optional()?)
+            })?
+            .ok_or_else(|| QmsError::NotFound {
+                resource: "validation_script".to_string(),
+                id: format!("{} v{}", document_id, version),
+            })
+    }
+
+    /// Every version of every script, by document number and version.
+    pub fn list(&self) -> Result<Vec<ValidationScript>> {
+        self.query(
+            &format!(
+                "SELECT {} FROM validation_scripts s JOIN documents d ON d.id = s.document_id
+                 WHERE d.deleted_at IS NULL ORDER BY d.document_number, s.version",
+                SCRIPT_COLUMNS
+            ),
+            params![],
+        )
+    }
+
+    /// The latest effective version of each script for `hook`, skipping
+    /// scripts whose document was made obsolete or retired.
+    pub fn in_force(&self, hook: RuleHook) -> Result<Vec<ValidationScript>> {
+        self.query(
+            &format!(
+                "SELECT {} FROM validation_scripts s JOIN documents d ON d.id = s.document_id
+                 WHERE s.hook = ?1 AND s.effective_at IS NOT NULL
+                   AND d.deleted_at IS NULL AND d.status NOT IN ('Obsolete', 'Retired')
+                   AND s.version = (SELECT MAX(t.version) FROM validation_scripts t
+                                    WHERE t.document_id = s.document_id AND t.effective_at IS NOT NULL)
+                 ORDER BY d.document_number",
+                SCRIPT_COLUMNS
+            ),
+            params![hook.as_str()],
+        )
+    }
+
+    fn query(&self, sql: &str, params: impl rusqlite::Params) -> Result<Vec<ValidationScript>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(sql)?;
+            let scripts = stmt.query_map(params, row_to_script)?.collect::<rusqlite::Result<_>>()?;
+            Ok(scripts)
+        })
+    }
+}
+
+/// Runs the scripts in force at a service boundary. Compiled scripts are
+/// kept by content hash; clones share them.
+#[derive(Clone)]
+pub struct ScriptedRules {
+    db: Database,
+    engine: Arc<OnceLock<Engine>>,
+    compiled: Arc<Mutex<HashMap<String, Arc<AST>>>>,
+}
+
+impl ScriptedRules {
+    pub fn new(db: Database) -> Self {
+        Self { db, engine: Arc::new(OnceLock::new()), compiled: Arc::new(Mutex::new(HashMap::new())) }
+    }
+
+    /// Run every script in force for `hook` against `record`, reporting the
+    /// violations of all of them at once.
+    pub fn check<T: Serialize>(&self, hook: RuleHook, record: &T) -> Result<()> {
+        let scripts = ValidationScriptRepo::new(&self.db).in_force(hook)?;
+        if scripts.is_empty() {
+            return Ok(());
+        }
+        let record = rhai::serde::to_dynamic(record).map_err(|e| QmsError::Application {
+            message: format!("Record cannot be passed to validation scripts: {}", e),
+        })?;
+        let mut violations = ValidationErrors::new();
+        for script in &scripts {
+            if let Some(message) = self.evaluate(script, record.clone())? {
+                violations.add(script.document_number.clone(), message);
+            }
+        }
+        violations.into_result()
+    }
+
+    /// The violation message of `script` for `record`, if it fails.
+    fn evaluate(&self, script: &ValidationScript, record: Dynamic) -> Result<Option<String>> {
+        let failed = |message: String| QmsError::Application {
+            message: format!("Validation script {} v{} failed: {}", script.document_number, script.version, message),
+        };
+        let engine = self.engine.get_or_init(sandboxed_engine);
+        let ast = {
+            let mut compiled = self.compiled.lock().unwrap_or_else(PoisonError::into_inner);
+            match compiled.get(&script.content_hash) {
+                Some(ast) => ast.clone(),
+                None => {
+                    let ast = Arc::new(engine.compile(&script.source).map_err(|e| failed(e.to_string()))?);
+                    compiled.insert(script.content_hash.clone(), ast.clone());
+                    ast
+                }
+            }
+        };
+        let mut scope = Scope::new();
+        scope.push_constant("record", record);
+        let outcome = engine.eval_ast_with_scope::<Dynamic>(&mut scope, &ast).map_err(|e| failed(e.to_string()))?;
+        if outcome.is_unit() {
+            Ok(None)
+        } else if let Ok(passed) = outcome.as_bool() {
+            Ok((!passed).then(|| format!("{} is not satisfied", script.title)))
+        } else if outcome.is_string() {
+            Ok(Some(outcome.into_string().unwrap_or_default()))
+        } else {
+            Err(failed(format!("evaluated to {} instead of a bool or message", outcome.type_name())))
+        }
+    }
+}
+
+/// A Rhai engine with resource limits, no output and no `eval`, plus
+/// `days_between(from, to)` for dates and RFC 3339 timestamps.
+fn sandboxed_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_expr_depths(MAX_EXPR_DEPTH, MAX_EXPR_DEPTH)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_COLLECTION_SIZE)
+        .set_max_map_size(MAX_COLLECTION_SIZE)
+        .disable_symbol("eval");
+    engine.on_print(|_| {});
+    engine.on_debug(|_, _, _| {});
+    engine.register_fn("days_between", |from: &str, to: &str| -> std::result::Result<i64, Box<EvalAltResult>> {
+        Ok((script_date(to)? - script_date(from)?).num_days())
+    });
+    engine
+}
+
+fn script_date(value: &str) -> std::result::Result<NaiveDate, Box<EvalAltResult>> {
+    value.get(..10).and_then(|day| day.parse().ok()).ok_or_else(|| format!("'{}' is not a date", value).into())
+}
+
+const SCRIPT_COLUMNS: &str = "s.document_id, d.document_number, d.title, s.version, s.hook, s.source, s.content_hash,
+                              s.created_by, s.created_at, s.effective_at";
+
+fn conversion_error(index: usize, message: String) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, message.into())
+}
+
+fn parse_timestamp(row: &Row, index: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|timestamp| timestamp.with_timezone(&Utc))
+                .map_err(|e| conversion_error(index, e.to_string()))
+        })
+        .transpose()
+}
+
+fn row_to_script(row: &Row) -> rusqlite::Result<ValidationScript> {
+    let hook: String = row.get(4)?;
+    Ok(ValidationScript {
+        document_id: row.get(0)?,
+        document_number: row.get(1)?,
+        title: row.get(2)?,
+        version: row.get(3)?,
+        hook: RuleHook::parse(&hook).map_err(|e| conversion_error(4, e.to_string()))?,
+        source: row.get(5)?,
+        content_hash: row.get(6)?,
+        created_by: row.get(7)?,
+        created_at: parse_timestamp(row, 8)?.ok_or_else(|| conversion_error(8, "missing creation time".to_string()))?,
+        effective_at: parse_timestamp(row, 9)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capa::{CapaPriority, CapaService, CapaType};
+    use crate::change_history::ChangeReason;
+    use crate::config::DatabaseConfig;
+    use crate::document::DocumentApprovals;
+
+    const CRITICAL_DUE_IN_30_DAYS: &str = r#"
+        if record.priority == "Critical" {
+            if record.due_date == () {
+                return "Critical CAPAs need a due date";
+            }
+            if days_between(record.created_at, record.due_date) > 30 {
+                return "Critical CAPAs must be due within 30 days";
+            }
+        }
+        true
+    "#;
+
+    #[test]
+    fn test_effective_scripts_validate_capas() {
+        let db = Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap();
+        db.seed_test_users(&["qe", "qm"]);
+        let repo = ValidationScriptRepo::new(&db);
+        let title = "Critical CAPA due date";
+        assert!(repo.submit("VS-001", title, RuleHook::Capa, "if record.priority ==", "Initial", "qe").is_err());
+        let script = repo.submit("VS-001", title, RuleHook::Capa, CRITICAL_DUE_IN_30_DAYS, "Initial", "qe").unwrap();
+        assert_eq!((script.version, script.effective_at), (1, None));
+        assert!(repo.make_effective(&script.document_id, "qm").is_err(), "not approved yet");
+
+        let service =
+            CapaService::new(AuditManager::new(db.clone())).with_validation_scripts(ScriptedRules::new(db.clone()));
+        let create = |priority: CapaPriority, due_in_days: Option<i64>| {
+            service.create_capa(
+                "Sterile barrier breach".to_string(),
+                "Pouch seal failures in lot 42".to_string(),
+                CapaType::Corrective,
+                priority,
+                "qe".to_string(),
+                "qe".to_string(),
+                due_in_days.map(|days| Utc::now() + chrono::Duration::days(days)),
+            )
+        };
+        assert!(create(CapaPriority::Critical, Some(60)).is_ok(), "the script is not in force yet");
+
+        let approvals = DocumentApprovals::new(&db);
+        let reason = ChangeReason::new("Rule reviewed").unwrap();
+        let row_version = approvals.fetch(&script.document_id).unwrap().unwrap().row_version;
+        approvals.approve(&script.document_id, "qm", row_version, &reason).unwrap();
+        let script = repo.make_effective(&script.document_id, "qm").unwrap();
+        assert!(script.effective_at.is_some());
+        assert_eq!(repo.in_force(RuleHook::Capa).unwrap(), vec![script.clone()]);
+        assert!(repo.in_force(RuleHook::CustomRecord).unwrap().is_empty());
+
+        match create(CapaPriority::Critical, Some(60)) {
+            Err(QmsError::ValidationErrors { errors }) => {
+                assert_eq!(errors.get("VS-001"), Some("Critical CAPAs must be due within 30 days"));
+            }
+            other => panic!("expected a rule violation, got {:?}", other.map(|capa| capa.id)),
+        }
+        assert!(create(CapaPriority::Critical, None).is_err());
+        assert!(create(CapaPriority::Critical, Some(20)).is_ok());
+        assert!(create(CapaPriority::Low, Some(90)).is_ok());
+
+        // A revision under review leaves version 1 in force; one approved by
+        // its own author never takes effect
+        let runaway = "loop { }";
+        let revision = repo.submit("VS-001", title, RuleHook::Capa, runaway, "Tighten", "qm").unwrap();
+        assert_eq!(revision.version, 2);
+        assert_eq!(repo.in_force(RuleHook::Capa).unwrap(), vec![script.clone()]);
+        let row_version = approvals.fetch(&script.document_id).unwrap().unwrap().row_version;
+        approvals.approve(&script.document_id, "qm", row_version, &reason).unwrap();
+        assert!(matches!(repo.make_effective(&script.document_id, "qm"), Err(QmsError::Security { .. })));
+
+        let rules = ScriptedRules::new(db.clone());
+        let runaway_script = ValidationScript { source: runaway.to_string(), content_hash: "x".to_string(), ..script };
+        let outcome = rules.evaluate(&runaway_script, Dynamic::UNIT);
+        assert!(matches!(outcome, Err(QmsError::Application { .. })), "stopped by the operation limit");
+
+        let audit = db.get_audit_entries_for_resource(&format!("document:{}", revision.document_id)).unwrap();
+        let actions: Vec<&str> = audit.iter().map(|entry| entry.action.as_str()).collect();
+        assert_eq!(actions.iter().filter(|action| **action == "validation_script_submitted").count(), 2);
+        assert!(actions.contains(&"validation_script_effective"));
+    }
+}
@@ -0,0 +1,270 @@
+//! # Vigilance Export - Complaint and Adverse Event Data Sharing
+//!
+//! Exports adverse events and complaints as CSV for vigilance partners
+//! (distributors, notified bodies, authorized representatives). In
+//! [`ExportMode::Pseudonymized`] the configured identifying fields are
+//! replaced by [`Keystore::pseudonym`] tokens: the same reporter always maps
+//! to the same token, so partners can still correlate reports, but names and
+//! contact details never leave the system (GDPR Art. 4(5), Art. 89). Every
+//! export is recorded in the audit trail with its mode and field list.
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+use crate::keystore::Keystore;
+
+/// Columns of the export, in order.
+pub const EXPORT_FIELDS: [&str; 8] =
+    ["record_type", "id", "reference", "reported_at", "reporter", "severity", "title", "description"];
+
+/// Fields pseudonymized when the configuration does not list any.
+pub fn default_pseudonymized_fields() -> Vec<String> {
+    vec!["reporter".to_string()]
+}
+
+/// Reject field lists naming columns the export does not have; the record
+/// type is structural and cannot be pseudonymized.
+pub fn validate_fields(fields: &[String]) -> Result<()> {
+    match fields.iter().find(|f| f.as_str() == "record_type" || !EXPORT_FIELDS.contains(&f.as_str())) {
+        Some(field) => Err(QmsError::Validation {
+            field: "pseudonymized_fields".to_string(),
+            message: format!("'{}' cannot be pseudonymized; expected one of {}", field, EXPORT_FIELDS[1..].join(", ")),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Whether identifying fields are exported as recorded or tokenized.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportMode {
+    /// Full data, for internal use
+    Identified,
+    /// The listed fields replaced by deterministic tokens
+    Pseudonymized(Vec<String>),
+}
+
+impl ExportMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportMode::Identified => "identified",
+            ExportMode::Pseudonymized(_) => "pseudonymized",
+        }
+    }
+}
+
+/// One exported complaint or adverse event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VigilanceRecord {
+    /// `adverse_event` or `complaint`
+    pub record_type: String,
+    pub id: String,
+    /// Record number where there is one, otherwise the id
+    pub reference: String,
+    pub reported_at: String,
+    pub reporter: String,
+    pub severity: String,
+    pub title: String,
+    pub description: String,
+}
+
+impl VigilanceRecord {
+    /// Values in [`EXPORT_FIELDS`] order.
+    fn values_mut(&mut self) -> [(&'static str, &mut String); 8] {
+        [
+            ("record_type", &mut self.record_type),
+            ("id", &mut self.id),
+            ("reference", &mut self.reference),
+            ("reported_at", &mut self.reported_at),
+            ("reporter", &mut self.reporter),
+            ("severity", &mut self.severity),
+            ("title", &mut self.title),
+            ("description", &mut self.description),
+        ]
+    }
+
+    fn csv_row(&self) -> String {
+        let values = [
+            &self.record_type,
+            &self.id,
+            &self.reference,
+            &self.reported_at,
+            &self.reporter,
+            &self.severity,
+            &self.title,
+            &self.description,
+        ];
+        let mut row = values.iter().map(|value| csv_field(value)).collect::<Vec<_>>().join(",");
+        row.push('\n');
+        row
+    }
+}
+
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Builds vigilance exports from the adverse event and complaint records.
+pub struct VigilanceExporter<'a> {
+    db: &'a Database,
+    keystore: Option<&'a Keystore>,
+}
+
+impl<'a> VigilanceExporter<'a> {
+    pub fn new(db: &'a Database) -> Self {
+        Self { db, keystore: None }
+    }
+
+    /// Key used for pseudonym tokens; required by [`ExportMode::Pseudonymized`].
+    pub fn with_keystore(mut self, keystore: &'a Keystore) -> Self {
+        self.keystore = Some(keystore);
+        self
+    }
+
+    /// Live adverse events and complaints, oldest first.
+    pub fn records(&self) -> Result<Vec<VigilanceRecord>> {
+        self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT 'adverse_event', id, id, reported_on, reporter,
+                        CASE severity WHEN 0 THEN 'Critical' WHEN 1 THEN 'Major' ELSE 'Minor' END, '', description
+                 FROM adverse_events WHERE deleted_at IS NULL
+                 UNION ALL
+                 SELECT 'complaint', id, COALESCE(record_number, id), reported_at, reported_by, '', title, description
+                 FROM quality_intake WHERE source = ?1
+                 ORDER BY 4, 2",
+            )?;
+            let records = stmt
+                .query_map(params!["complaint"], |row| {
+                    Ok(VigilanceRecord {
+                        record_type: row.get(0)?,
+                        id: row.get(1)?,
+                        reference: row.get(2)?,
+                        reported_at: row.get(3)?,
+                        reporter: row.get(4)?,
+                        severity: row.get(5)?,
+                        title: row.get(6)?,
+                        description: row.get(7)?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(records)
+        })
+    }
+
+    /// Records with `mode` applied.
+    pub fn export(&self, mode: &ExportMode) -> Result<Vec<VigilanceRecord>> {
+        let mut records = self.records()?;
+        if let ExportMode::Pseudonymized(fields) = mode {
+            validate_fields(fields)?;
+            let keystore = self.keystore.ok_or_else(|| QmsError::Configuration {
+                message: "Pseudonymized exports require the system keystore".to_string(),
+            })?;
+            for record in &mut records {
+                for (name, value) in record.values_mut() {
+                    if fields.iter().any(|f| f == name) {
+                        *value = keystore.pseudonym(value);
+                    }
+                }
+            }
+        }
+        Ok(records)
+    }
+
+    /// CSV export (header first) on behalf of `user_id`, recorded in the audit trail.
+    pub fn export_csv(&self, user_id: &str, mode: &ExportMode) -> Result<String> {
+        let records = self.export(mode)?;
+        let mut csv = EXPORT_FIELDS.join(",");
+        csv.push('\n');
+        for record in &records {
+            csv.push_str(&record.csv_row());
+        }
+        let fields = match mode {
+            ExportMode::Identified => Vec::new(),
+            ExportMode::Pseudonymized(fields) => fields.clone(),
+        };
+        let metadata = serde_json::json!({
+            "mode": mode.as_str(),
+            "pseudonymized_fields": fields,
+            "records": records.len(),
+        });
+        AuditManager::new(self.db.clone()).log_action(
+            user_id,
+            "vigilance_exported",
+            "vigilance_export",
+            "Success",
+            Some(metadata.to_string()),
+        )?;
+        Ok(csv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use crate::post_market::{AdverseEvent, AdverseEventRepo, Severity};
+    use crate::quality_intake::{IssueSource, QualityIntake};
+    use tempfile::tempdir;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_pseudonymized_export_tokenizes_configured_fields() {
+        let db = test_db();
+        let dir = tempdir().unwrap();
+        let keystore = Keystore::open_or_create(dir.path()).unwrap();
+        let repo = AdverseEventRepo::new(&db);
+        repo.insert(&AdverseEvent::new("Jane Doe", "Rash after use", Severity::Minor)).unwrap();
+        repo.insert(&AdverseEvent::new("Jane Doe", "Second reaction", Severity::Major)).unwrap();
+        QualityIntake::new(&db)
+            .submit(IssueSource::Complaint, "Leaking seal", "Reported by patient J.D.", "support")
+            .unwrap();
+
+        let exporter = VigilanceExporter::new(&db).with_keystore(&keystore);
+        let identified = exporter.export(&ExportMode::Identified).unwrap();
+        assert_eq!(identified.len(), 3);
+        assert!(identified.iter().any(|r| r.reporter == "Jane Doe"));
+
+        let mode = ExportMode::Pseudonymized(vec!["reporter".to_string(), "description".to_string()]);
+        let shared = exporter.export(&mode).unwrap();
+        let events: Vec<&VigilanceRecord> = shared.iter().filter(|r| r.record_type == "adverse_event").collect();
+        assert_eq!(events[0].reporter, keystore.pseudonym("Jane Doe"));
+        assert_eq!(events[0].reporter, events[1].reporter, "same reporter, same token");
+        assert!(shared.iter().all(|r| r.description.starts_with("PSN-")));
+        let complaint = shared.iter().find(|r| r.record_type == "complaint").unwrap();
+        assert_eq!(complaint.title, "Leaking seal");
+        assert!(complaint.reference.starts_with("CMP-"));
+
+        let csv = exporter.export_csv("qa", &mode).unwrap();
+        assert!(csv.starts_with("record_type,id,reference,reported_at,reporter,severity,title,description\n"));
+        assert!(!csv.contains("Jane Doe") && !csv.contains("J.D."));
+        let audit = db.get_audit_entries_for_resource("vigilance_export").unwrap();
+        assert_eq!(audit[0].action, "vigilance_exported");
+    }
+
+    #[test]
+    fn test_pseudonymization_requires_known_fields_and_key() {
+        let db = test_db();
+        let mode = ExportMode::Pseudonymized(default_pseudonymized_fields());
+        assert!(matches!(VigilanceExporter::new(&db).export(&mode), Err(QmsError::Configuration { .. })));
+        assert!(validate_fields(&["patient_name".to_string()]).is_err());
+        assert!(validate_fields(&["record_type".to_string()]).is_err());
+        assert!(validate_fields(&default_pseudonymized_fields()).is_ok());
+    }
+}
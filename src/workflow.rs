@@ -0,0 +1,267 @@
+//! # Workflow - Declarative Status Machines
+//!
+//! CAPAs, documents and change requests move through their statuses under
+//! the same kind of rules: a transition must be listed for the record type,
+//! its guards must hold for the record, it may need an electronic signature
+//! of a given meaning, and it may have to notify people. A
+//! [`WorkflowDefinition`] states those rules as data, so a new record type
+//! such as a deviation or a SCAR declares its states and transitions instead
+//! of writing another status match.
+//!
+//! A signature only satisfies a transition when the acting user made it, on
+//! the record as it stands; one taken before the record was edited is stale.
+//!
+//! Transitions marked with [`Transition::with_notification`] publish
+//! [`QmsEvent::WorkflowTransitioned`]. CAPA closure and document effectivity
+//! keep their own events, which subscribers already route on.
+
+use chrono::Utc;
+use serde::Serialize;
+
+use crate::error::{QmsError, Result};
+use crate::events::{EventBus, QmsEvent};
+use crate::unit_of_work::ElectronicSignature;
+
+/// Source state matching every state of a workflow.
+pub const ANY_STATE: &str = "*";
+
+/// Precondition on the record; `Err` carries the reason the transition is
+/// refused.
+pub type Guard<T> = fn(&T) -> std::result::Result<(), String>;
+
+/// A permitted move between two states.
+pub struct Transition<T = ()> {
+    pub from: &'static str,
+    pub to: &'static str,
+    guards: Vec<Guard<T>>,
+    /// Meaning of the electronic signature the transition requires
+    pub signature: Option<&'static str>,
+    /// Whether taking the transition publishes `WorkflowTransitioned`
+    pub notify: bool,
+}
+
+impl<T> Transition<T> {
+    /// Move from `from`, or from every state with [`ANY_STATE`], to `to`.
+    pub fn new(from: &'static str, to: &'static str) -> Self {
+        Self { from, to, guards: Vec::new(), signature: None, notify: false }
+    }
+
+    pub fn with_guard(mut self, guard: Guard<T>) -> Self {
+        self.guards.push(guard);
+        self
+    }
+
+    /// Require the signer's electronic signature with `meaning` (§11.50).
+    pub fn with_signature(mut self, meaning: &'static str) -> Self {
+        self.signature = Some(meaning);
+        self
+    }
+
+    pub fn with_notification(mut self) -> Self {
+        self.notify = true;
+        self
+    }
+}
+
+/// States and transitions of one record type.
+pub struct WorkflowDefinition<T = ()> {
+    name: &'static str,
+    states: Vec<&'static str>,
+    transitions: Vec<Transition<T>>,
+}
+
+impl<T> WorkflowDefinition<T> {
+    /// Workflow of the records of type `name`, e.g. `capa`; signatures for
+    /// its transitions must be made on that record type.
+    pub fn new(name: &'static str, states: &[&'static str]) -> Self {
+        Self { name, states: states.to_vec(), transitions: Vec::new() }
+    }
+
+    /// Add `transition`. Panics when it names a state the workflow does not
+    /// have, since definitions are fixed in code.
+    pub fn with_transition(mut self, transition: Transition<T>) -> Self {
+        assert!(
+            transition.from == ANY_STATE || self.states.contains(&transition.from),
+            "workflow {} has no state {}",
+            self.name,
+            transition.from
+        );
+        assert!(self.states.contains(&transition.to), "workflow {} has no state {}", self.name, transition.to);
+        self.transitions.push(transition);
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn states(&self) -> &[&'static str] {
+        &self.states
+    }
+
+    /// Transition from `from` to `to`; one listed for `from` takes precedence
+    /// over one from [`ANY_STATE`].
+    pub fn transition(&self, from: &str, to: &str) -> Option<&Transition<T>> {
+        let listed = |source: &str| self.transitions.iter().find(|t| t.from == source && t.to == to);
+        listed(from).or_else(|| listed(ANY_STATE))
+    }
+
+    pub fn can_transition(&self, from: &str, to: &str) -> bool {
+        self.transition(from, to).is_some()
+    }
+
+    /// States reachable from `from` in one step, in definition order.
+    pub fn next_states(&self, from: &str) -> Vec<&'static str> {
+        let mut next: Vec<&'static str> = Vec::new();
+        for state in &self.states {
+            if *state != from && self.can_transition(from, state) && !next.contains(state) {
+                next.push(*state);
+            }
+        }
+        next
+    }
+
+    /// Check that `record`, identified by `record_id`, may move from `from`
+    /// to `to` as `user_id` with `signature`, returning the transition that
+    /// applies.
+    pub fn check(
+        &self,
+        record_id: &str,
+        record: &T,
+        from: &str,
+        to: &str,
+        user_id: &str,
+        signature: Option<&ElectronicSignature>,
+    ) -> Result<&Transition<T>>
+    where
+        T: Serialize,
+    {
+        let transition = self.transition(from, to).ok_or_else(|| QmsError::Validation {
+            field: "status".to_string(),
+            message: format!("Invalid status transition from {} to {}", from, to),
+        })?;
+        for guard in &transition.guards {
+            guard(record).map_err(|reason| QmsError::Validation {
+                field: "status".to_string(),
+                message: format!("Cannot move from {} to {}: {}", from, to, reason),
+            })?;
+        }
+        if let Some(meaning) = transition.signature {
+            let signature = match signature {
+                Some(signature)
+                    if signature.record_type == self.name
+                        && signature.record_id == record_id
+                        && signature.meaning == meaning =>
+                {
+                    signature
+                }
+                _ => {
+                    return Err(QmsError::Security {
+                        message: format!(
+                            "Moving {} {} to {} requires an electronic signature meaning '{}'",
+                            self.name, record_id, to, meaning
+                        ),
+                    });
+                }
+            };
+            if signature.signer_id != user_id {
+                return Err(QmsError::Security {
+                    message: format!(
+                        "Signature {} was made by {}, not by {}",
+                        signature.id, signature.signer_id, user_id
+                    ),
+                });
+            }
+            if !signature.covers(record)? {
+                return Err(QmsError::Security {
+                    message: format!(
+                        "Signature {} was made on another version of {} {}; sign it again",
+                        signature.id, self.name, record_id
+                    ),
+                });
+            }
+        }
+        Ok(transition)
+    }
+
+    /// Publish `WorkflowTransitioned` for `transition` if it notifies.
+    pub fn notify(&self, events: &EventBus, transition: &Transition<T>, record_id: &str, from: &str, user_id: &str) {
+        if transition.notify {
+            events.publish(&QmsEvent::WorkflowTransitioned {
+                workflow: self.name.to_string(),
+                record_id: record_id.to_string(),
+                from: from.to_string(),
+                to: transition.to.to_string(),
+                by: user_id.to_string(),
+                at: Utc::now(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Serialize)]
+    struct Deviation {
+        id: String,
+        root_cause: Option<String>,
+    }
+
+    fn root_cause_recorded(deviation: &Deviation) -> std::result::Result<(), String> {
+        match deviation.root_cause {
+            Some(_) => Ok(()),
+            None => Err("no root cause recorded".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_definition_enforces_guards_signatures_and_notifications() {
+        let workflow = WorkflowDefinition::new("deviation", &["Open", "Investigated", "Closed", "Voided"])
+            .with_transition(Transition::new("Open", "Investigated").with_guard(root_cause_recorded))
+            .with_transition(Transition::new("Investigated", "Closed").with_signature("closure").with_notification())
+            .with_transition(Transition::new(ANY_STATE, "Voided"));
+        let mut deviation = Deviation { id: "dev-1".to_string(), root_cause: None };
+
+        assert_eq!(workflow.next_states("Open"), vec!["Investigated", "Voided"]);
+        assert!(!workflow.can_transition("Open", "Closed"));
+        assert!(workflow.check(&deviation.id, &deviation, "Open", "Closed", "qa", None).is_err());
+        let refused = workflow.check(&deviation.id, &deviation, "Open", "Investigated", "qa", None).unwrap_err();
+        assert!(refused.to_string().contains("no root cause recorded"));
+        deviation.root_cause = Some("Worn gasket".to_string());
+        workflow.check(&deviation.id, &deviation, "Open", "Investigated", "qa", None).unwrap();
+
+        assert!(matches!(
+            workflow.check(&deviation.id, &deviation, "Investigated", "Closed", "qa", None),
+            Err(QmsError::Security { .. })
+        ));
+        let approval = ElectronicSignature::sign("deviation", "dev-1", &deviation, "qa", "approval").unwrap();
+        assert!(workflow.check(&deviation.id, &deviation, "Investigated", "Closed", "qa", Some(&approval)).is_err());
+        let closure = ElectronicSignature::sign("deviation", "dev-1", &deviation, "qa", "closure").unwrap();
+        let by_other = workflow.check(&deviation.id, &deviation, "Investigated", "Closed", "eng", Some(&closure));
+        assert!(matches!(by_other, Err(QmsError::Security { .. })), "signed by someone else");
+        deviation.root_cause = Some("Worn gasket and misaligned clamp".to_string());
+        let stale = workflow.check(&deviation.id, &deviation, "Investigated", "Closed", "qa", Some(&closure));
+        assert!(matches!(stale, Err(QmsError::Security { .. })), "signed before the record changed");
+        let closure = ElectronicSignature::sign("deviation", "dev-1", &deviation, "qa", "closure").unwrap();
+        let transition =
+            workflow.check(&deviation.id, &deviation, "Investigated", "Closed", "qa", Some(&closure)).unwrap();
+
+        let events = EventBus::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        events.subscribe("test", move |event: &QmsEvent| -> Result<()> {
+            sink.lock().unwrap().push(event.clone());
+            Ok(())
+        });
+        workflow.notify(&events, transition, &deviation.id, "Investigated", "qa");
+        let voided = workflow.check(&deviation.id, &deviation, "Closed", "Voided", "qa", None).unwrap();
+        workflow.notify(&events, voided, &deviation.id, "Closed", "qa");
+        assert!(matches!(
+            &seen.lock().unwrap()[..],
+            [QmsEvent::WorkflowTransitioned { workflow, to, .. }] if workflow == "deviation" && to == "Closed"
+        ));
+    }
+}
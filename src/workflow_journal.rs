@@ -0,0 +1,379 @@
+//! # Workflow Journal - Crash Recovery for Multi-Step Workflows
+//!
+//! Workflows that span more than one transaction (scheduled report runs that
+//! file a PDF, email it and then record the run; imports and bulk updates
+//! applied in batches) write a journal as they go: [`WorkflowJournal::begin`]
+//! before the first step, [`WorkflowJournal::record_step`] after each step
+//! commits, [`WorkflowJournal::complete`] at the end. A crash leaves the
+//! workflow `Running`. At startup [`WorkflowJournal::recover`] hands every
+//! such workflow to the [`WorkflowRecovery`] registered for its kind, which
+//! rolls it back or resumes it from its recorded steps. Each recovery is
+//! recorded in the audit trail; a workflow no handler can resolve is marked
+//! `Abandoned` for manual review (21 CFR 820.70(i), ISO 13485 §4.1.6).
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::audit::AuditManager;
+use crate::database::Database;
+use crate::error::{QmsError, Result};
+
+/// User recorded for recovery audit entries.
+pub const RECOVERY_USER: &str = "system:recovery";
+
+/// State of a journaled workflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WorkflowStatus {
+    /// Started and not finished; after a restart, interrupted
+    Running,
+    Completed,
+    /// Interrupted and undone at startup
+    RolledBack,
+    /// Interrupted and finished at startup
+    Resumed,
+    /// Interrupted and left for manual review
+    Abandoned,
+}
+
+impl WorkflowStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkflowStatus::Running => "running",
+            WorkflowStatus::Completed => "completed",
+            WorkflowStatus::RolledBack => "rolled_back",
+            WorkflowStatus::Resumed => "resumed",
+            WorkflowStatus::Abandoned => "abandoned",
+        }
+    }
+
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "running" => Ok(WorkflowStatus::Running),
+            "completed" => Ok(WorkflowStatus::Completed),
+            "rolled_back" => Ok(WorkflowStatus::RolledBack),
+            "resumed" => Ok(WorkflowStatus::Resumed),
+            "abandoned" => Ok(WorkflowStatus::Abandoned),
+            other => Err(QmsError::Validation {
+                field: "status".to_string(),
+                message: format!("Unknown workflow status: {}", other),
+            }),
+        }
+    }
+}
+
+/// A committed step and the data needed to undo or continue from it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalStep {
+    pub sequence: i64,
+    pub name: String,
+    pub payload: serde_json::Value,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// A journaled workflow with its steps in order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournaledWorkflow {
+    pub id: String,
+    /// Selects the [`WorkflowRecovery`] handler
+    pub kind: String,
+    /// Record the workflow acts on, e.g. `report_schedule:<id>`
+    pub subject: String,
+    pub started_by: String,
+    pub started_at: DateTime<Utc>,
+    pub status: WorkflowStatus,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// What recovery did, for interrupted workflows
+    pub resolution: Option<String>,
+    pub steps: Vec<JournalStep>,
+}
+
+impl JournaledWorkflow {
+    /// Payload of the step named `name`, if it was recorded.
+    pub fn step(&self, name: &str) -> Option<&serde_json::Value> {
+        self.steps.iter().find(|step| step.name == name).map(|step| &step.payload)
+    }
+}
+
+/// What a handler did with an interrupted workflow, with a description for
+/// the audit trail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecoveryAction {
+    RolledBack(String),
+    Resumed(String),
+}
+
+/// Rolls back or resumes interrupted workflows of one kind.
+pub trait WorkflowRecovery {
+    fn kind(&self) -> &str;
+
+    fn recover(&self, workflow: &JournaledWorkflow, now: DateTime<Utc>) -> Result<RecoveryAction>;
+}
+
+/// Result of recovering one interrupted workflow.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RecoveryOutcome {
+    pub workflow_id: String,
+    pub kind: String,
+    pub subject: String,
+    pub status: WorkflowStatus,
+    pub resolution: String,
+}
+
+/// Journal of multi-step workflows in the `workflow_journal` tables.
+#[derive(Clone)]
+pub struct WorkflowJournal {
+    db: Database,
+}
+
+impl WorkflowJournal {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Start journaling a workflow; returns its id.
+    pub fn begin(&self, kind: &str, subject: &str, started_by: &str, now: DateTime<Utc>) -> Result<String> {
+        let id = Uuid::new_v4().to_string();
+        self.db.with_connection(|conn| {
+            conn.execute(
+                "INSERT INTO workflow_journal (id, kind, subject, started_by, started_at, status)
+                 VALUES (?1, ?2, ?3, ?4, ?5, 'running')",
+                params![id, kind, subject, started_by, now.to_rfc3339()],
+            )?;
+            Ok(())
+        })?;
+        Ok(id)
+    }
+
+    /// Record that step `name` of workflow `id` committed. `payload` holds
+    /// what recovery needs to undo or continue from it.
+    pub fn record_step<T: Serialize>(&self, id: &str, name: &str, payload: &T, now: DateTime<Utc>) -> Result<()> {
+        let payload = serde_json::to_string(payload)?;
+        self.db.with_connection(|conn| {
+            let updated = conn.execute(
+                "INSERT INTO workflow_journal_steps (workflow_id, name, payload, recorded_at)
+                 SELECT id, ?2, ?3, ?4 FROM workflow_journal WHERE id = ?1 AND status = 'running'",
+                params![id, name, payload, now.to_rfc3339()],
+            )?;
+            if updated == 0 {
+                return Err(not_running(id));
+            }
+            Ok(())
+        })
+    }
+
+    /// Mark workflow `id` as finished normally.
+    pub fn complete(&self, id: &str, now: DateTime<Utc>) -> Result<()> {
+        self.finish(id, WorkflowStatus::Completed, None, now)
+    }
+
+    pub fn get(&self, id: &str) -> Result<JournaledWorkflow> {
+        let workflow = self.db.with_connection(|conn| {
+            conn.query_row(
+                "SELECT id, kind, subject, started_by, started_at, status, finished_at, resolution
+                 FROM workflow_journal WHERE id = ?1",
+                params![id],
+                workflow_from_row,
+            )
+            .optional()
+            .map_err(Into::into)
+        })?;
+        let workflow = workflow.ok_or_else(|| QmsError::NotFound {
+            resource: "workflow".to_string(),
+            id: id.to_string(),
+        })?;
+        self.with_steps(workflow)
+    }
+
+    /// Workflows still running, oldest first. Called at startup, before any
+    /// new workflow begins, these are the ones a crash interrupted.
+    pub fn incomplete(&self) -> Result<Vec<JournaledWorkflow>> {
+        let workflows = self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, kind, subject, started_by, started_at, status, finished_at, resolution
+                 FROM workflow_journal WHERE status = 'running' ORDER BY started_at, rowid",
+            )?;
+            let rows = stmt.query_map([], workflow_from_row)?;
+            rows.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
+        })?;
+        workflows.into_iter().map(|workflow| self.with_steps(workflow)).collect()
+    }
+
+    /// Roll back or resume every interrupted workflow with the handler for
+    /// its kind, recording each outcome in the audit trail. A workflow with
+    /// no handler, or whose handler fails, is marked `Abandoned`.
+    pub fn recover(&self, handlers: &[&dyn WorkflowRecovery], now: DateTime<Utc>) -> Result<Vec<RecoveryOutcome>> {
+        let audit = AuditManager::new(self.db.clone());
+        let mut outcomes = Vec::new();
+        for workflow in self.incomplete()? {
+            let handler = handlers.iter().find(|handler| handler.kind() == workflow.kind);
+            let (status, resolution) = match handler.map(|handler| handler.recover(&workflow, now)) {
+                Some(Ok(RecoveryAction::RolledBack(note))) => (WorkflowStatus::RolledBack, note),
+                Some(Ok(RecoveryAction::Resumed(note))) => (WorkflowStatus::Resumed, note),
+                Some(Err(e)) => (WorkflowStatus::Abandoned, format!("Recovery failed, manual review required: {}", e)),
+                None => (WorkflowStatus::Abandoned, "No recovery handler, manual review required".to_string()),
+            };
+            self.finish(&workflow.id, status, Some(&resolution), now)?;
+
+            let completed_steps: Vec<&str> = workflow.steps.iter().map(|step| step.name.as_str()).collect();
+            audit.log_action(
+                RECOVERY_USER,
+                "workflow_recovered",
+                &format!("workflow:{}", workflow.id),
+                if status == WorkflowStatus::Abandoned { "Failure" } else { "Success" },
+                Some(format!(
+                    "Interrupted {} workflow on {} started by {} at {} ({} step(s) completed: {}) was {}: {}",
+                    workflow.kind,
+                    workflow.subject,
+                    workflow.started_by,
+                    workflow.started_at.to_rfc3339(),
+                    completed_steps.len(),
+                    if completed_steps.is_empty() { "none".to_string() } else { completed_steps.join(", ") },
+                    status.as_str(),
+                    resolution
+                )),
+            )?;
+            tracing::warn!(workflow = %workflow.id, kind = %workflow.kind, "interrupted workflow {}", status.as_str());
+            outcomes.push(RecoveryOutcome {
+                workflow_id: workflow.id,
+                kind: workflow.kind,
+                subject: workflow.subject,
+                status,
+                resolution,
+            });
+        }
+        Ok(outcomes)
+    }
+
+    fn finish(&self, id: &str, status: WorkflowStatus, resolution: Option<&str>, now: DateTime<Utc>) -> Result<()> {
+        self.db.with_connection(|conn| {
+            let updated = conn.execute(
+                "UPDATE workflow_journal SET status = ?2, resolution = ?3, finished_at = ?4
+                 WHERE id = ?1 AND status = 'running'",
+                params![id, status.as_str(), resolution, now.to_rfc3339()],
+            )?;
+            if updated == 0 {
+                return Err(not_running(id));
+            }
+            Ok(())
+        })
+    }
+
+    fn with_steps(&self, mut workflow: JournaledWorkflow) -> Result<JournaledWorkflow> {
+        workflow.steps = self.db.with_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT sequence, name, payload, recorded_at FROM workflow_journal_steps
+                 WHERE workflow_id = ?1 ORDER BY sequence",
+            )?;
+            let rows = stmt.query_map(params![workflow.id], |row| {
+                let payload: String = row.get(2)?;
+                Ok(JournalStep {
+                    sequence: row.get(0)?,
+                    name: row.get(1)?,
+                    payload: serde_json::from_str(&payload).map_err(|e| conversion_error(2, e))?,
+                    recorded_at: parse_time(row, 3)?,
+                })
+            })?;
+            rows.collect::<rusqlite::Result<Vec<_>>>().map_err(Into::into)
+        })?;
+        Ok(workflow)
+    }
+}
+
+fn not_running(id: &str) -> QmsError {
+    QmsError::Validation {
+        field: "workflow".to_string(),
+        message: format!("Workflow {} is not running", id),
+    }
+}
+
+fn workflow_from_row(row: &Row) -> rusqlite::Result<JournaledWorkflow> {
+    let status: String = row.get(5)?;
+    let finished_at: Option<String> = row.get(6)?;
+    Ok(JournaledWorkflow {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        subject: row.get(2)?,
+        started_by: row.get(3)?,
+        started_at: parse_time(row, 4)?,
+        status: WorkflowStatus::parse(&status).map_err(|e| conversion_error(5, e))?,
+        finished_at: finished_at
+            .map(|value| DateTime::parse_from_rfc3339(&value).map(|t| t.with_timezone(&Utc)))
+            .transpose()
+            .map_err(|e| conversion_error(6, e))?,
+        resolution: row.get(7)?,
+        steps: Vec::new(),
+    })
+}
+
+fn parse_time(row: &Row, index: usize) -> rusqlite::Result<DateTime<Utc>> {
+    let value: String = row.get(index)?;
+    DateTime::parse_from_rfc3339(&value).map(|t| t.with_timezone(&Utc)).map_err(|e| conversion_error(index, e))
+}
+
+fn conversion_error<E: std::error::Error + Send + Sync + 'static>(index: usize, e: E) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DatabaseConfig;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn test_db() -> Database {
+        Database::new(DatabaseConfig {
+            url: ":memory:".to_string(),
+            max_connections: 10,
+            wal_mode: false,
+            backup_interval_hours: 24,
+            backup_retention_days: 90,
+        })
+        .unwrap()
+    }
+
+    struct UndoImport;
+
+    impl WorkflowRecovery for UndoImport {
+        fn kind(&self) -> &str {
+            "import"
+        }
+
+        fn recover(&self, workflow: &JournaledWorkflow, _now: DateTime<Utc>) -> Result<RecoveryAction> {
+            let rows = workflow.step("batch_1").and_then(|payload| payload["rows"].as_u64()).unwrap_or(0);
+            Ok(RecoveryAction::RolledBack(format!("removed {} imported row(s)", rows)))
+        }
+    }
+
+    #[test]
+    fn test_interrupted_workflows_are_recovered_and_audited() {
+        let db = test_db();
+        let journal = WorkflowJournal::new(db.clone());
+        let now = Utc.with_ymd_and_hms(2026, 6, 1, 8, 0, 0).unwrap();
+
+        let finished = journal.begin("import", "supplier_list", "qa", now).unwrap();
+        journal.record_step(&finished, "batch_1", &json!({ "rows": 10 }), now).unwrap();
+        journal.complete(&finished, now).unwrap();
+        let interrupted = journal.begin("import", "supplier_list", "qa", now).unwrap();
+        journal.record_step(&interrupted, "batch_1", &json!({ "rows": 25 }), now).unwrap();
+        let unknown = journal.begin("bulk_update", "capa", "qa", now).unwrap();
+
+        let pending: Vec<String> = journal.incomplete().unwrap().into_iter().map(|w| w.id).collect();
+        assert_eq!(pending, vec![interrupted.clone(), unknown.clone()]);
+
+        let outcomes = journal.recover(&[&UndoImport], now).unwrap();
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(outcomes[0].status, WorkflowStatus::RolledBack);
+        assert_eq!(outcomes[0].resolution, "removed 25 imported row(s)");
+        assert_eq!(outcomes[1].status, WorkflowStatus::Abandoned);
+        assert!(journal.incomplete().unwrap().is_empty());
+        assert_eq!(journal.get(&finished).unwrap().status, WorkflowStatus::Completed);
+        assert!(journal.record_step(&interrupted, "batch_2", &json!({}), now).is_err());
+
+        let entries = db.get_audit_entries_for_resource(&format!("workflow:{}", unknown)).unwrap();
+        assert_eq!((entries[0].action.as_str(), entries[0].outcome.as_str()), ("workflow_recovered", "FAILURE"));
+        assert!(journal.recover(&[&UndoImport], now).unwrap().is_empty(), "recovered once");
+    }
+}